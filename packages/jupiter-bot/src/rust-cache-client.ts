@@ -36,7 +36,8 @@ export interface RustArbitrageOpportunity {
  * Health response from Rust cache
  */
 export interface RustHealthResponse {
-  status: string;
+  /** ok / degraded still serve prices; down is returned with HTTP 503 */
+  status: 'ok' | 'degraded' | 'down';
   cached_pools: number;
   cached_pairs: string[];
 }
@@ -81,7 +82,7 @@ export class RustPoolCacheClient {
 
     try {
       const response = await this.axios.get<RustHealthResponse>('/health');
-      this.available = response.data.status === 'ok';
+      this.available = response.data.status !== 'down';
       this.lastHealthCheck = now;
       
       if (this.available) {
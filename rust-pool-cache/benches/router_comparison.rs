@@ -9,6 +9,9 @@ use solana_pool_cache::price_cache::{PoolPrice, PriceCache};
 use solana_pool_cache::router::Router;
 use solana_pool_cache::router_bfs::BfsScanner;
 use solana_pool_cache::router_bellman_ford::BellmanFordScanner;
+use solana_pool_cache::execution_cost::ExecutionCostModel;
+use solana_pool_cache::quote::{geometric_ladder, QuoteEngine, DEFAULT_LADDER_STEPS};
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -33,6 +36,39 @@ fn create_realistic_pool_set(num_pools: usize) -> Vec<PoolPrice> {
             price: base_price,
             last_update: Instant::now(),
             slot: 1000,
+            liquidity_usd: None,
+            commitment: None,
+        });
+    }
+    
+    pools
+}
+
+/// 多代币合成图：`num_tokens` 个代币，每个池子连接两个不同代币，
+/// 价格 = 两侧"公允价值"之比 ± 0.3% 的确定性扰动（会产生少量有利可图的环路）
+fn create_synthetic_graph(num_pools: usize, num_tokens: usize) -> Vec<PoolPrice> {
+    let value = |t: usize| 1.0 + t as f64 * 0.37;
+    
+    let mut pools = Vec::new();
+    for i in 0..num_pools {
+        let base = i % num_tokens;
+        let quote = (base + 1 + (i * 7) % (num_tokens - 1)) % num_tokens;
+        let skew = 1.0 + ((i * 13) % 7) as f64 * 0.001 - 0.003;
+        let price = value(base) / value(quote) * skew;
+        
+        pools.push(PoolPrice {
+            pool_id: format!("syn_{:04}", i),
+            dex_name: "Raydium AMM V4".to_string(),
+            pair: format!("TK{:02}/TK{:02}", base, quote),
+            base_reserve: 1_000_000_000_000,
+            quote_reserve: (price * 1_000_000_000_000.0) as u64,
+            base_decimals: 6,
+            quote_decimals: 6,
+            price,
+            last_update: Instant::now(),
+            slot: 1000,
+            liquidity_usd: None,
+            commitment: None,
         });
     }
     
@@ -58,7 +94,7 @@ fn bench_quick_scanner(c: &mut Criterion) {
 
 fn bench_bfs_scanner(c: &mut Criterion) {
     let pools = create_realistic_pool_set(32);
-    let scanner = BfsScanner::new(3, 0.1);
+    let scanner = BfsScanner::new(3, 0.1, ExecutionCostModel::shared_default());
     
     c.bench_function("bfs_scanner_32_pools", |b| {
         b.iter(|| {
@@ -69,7 +105,7 @@ fn bench_bfs_scanner(c: &mut Criterion) {
 
 fn bench_bellman_ford_scanner(c: &mut Criterion) {
     let pools = create_realistic_pool_set(32);
-    let scanner = BellmanFordScanner::new(6, 0.1);
+    let scanner = BellmanFordScanner::new(6, 0.1, ExecutionCostModel::shared_default());
     
     c.bench_function("bellman_ford_32_pools", |b| {
         b.iter(|| {
@@ -83,7 +119,7 @@ fn bench_scaling(c: &mut Criterion) {
     
     for pool_count in [10, 20, 32, 50, 100].iter() {
         let pools = create_realistic_pool_set(*pool_count);
-        let scanner = BfsScanner::new(3, 0.1);
+        let scanner = BfsScanner::new(3, 0.1, ExecutionCostModel::shared_default());
         
         group.bench_with_input(
             BenchmarkId::new("bfs", pool_count),
@@ -99,12 +135,262 @@ fn bench_scaling(c: &mut Criterion) {
     group.finish();
 }
 
+/// 🔢 驻留前后的 BFS 扩展核心（同一张图、同样的剪枝规则、同样的 AMM 公式）
+///
+/// - `string_keyed`：驻留前的做法 —— 边克隆 `PoolPrice` 和代币 `String`，
+///   路径签名用 `join` / `format!` 拼成 "A->B::p1|p2"
+/// - `interned`：`TokenId` / `PoolId` 的 Copy 边，签名是 `path_signature` 的 u64 哈希
+///
+/// 两者返回闭合环路数，基准开始前先确认结果一致。
+mod keying {
+    use solana_pool_cache::dex_interface::amm_calculator::calculate_amm_output_f64;
+    use solana_pool_cache::interning::{path_signature, PoolId, TokenId, TokenRegistry};
+    use solana_pool_cache::price_cache::PoolPrice;
+    use std::collections::{HashMap, HashSet, VecDeque};
+
+    const FEE: f64 = 0.0025;
+
+    fn ui_reserves(pool: &PoolPrice) -> (f64, f64) {
+        (
+            pool.base_reserve as f64 / 10f64.powi(pool.base_decimals as i32),
+            pool.quote_reserve as f64 / 10f64.powi(pool.quote_decimals as i32),
+        )
+    }
+
+    fn sorted_tokens(pools: &[PoolPrice]) -> Vec<String> {
+        let mut tokens: Vec<String> = pools.iter()
+            .flat_map(|p| p.pair.split('/').map(str::to_string))
+            .collect();
+        tokens.sort();
+        tokens.dedup();
+        tokens
+    }
+
+    pub mod string_keyed {
+        use super::*;
+
+        #[derive(Clone)]
+        struct Edge {
+            pool: PoolPrice,
+            from_token: String,
+            to_token: String,
+            reserve_in: f64,
+            reserve_out: f64,
+        }
+
+        #[derive(Clone)]
+        struct Node {
+            tokens: Vec<String>,
+            amount: f64,
+            edges: Vec<Edge>,
+        }
+
+        fn signature(node: &Node) -> String {
+            let pool_ids: Vec<String> = node.edges.iter().map(|e| e.pool.pool_id.clone()).collect();
+            format!("{}::{}", node.tokens.join("->"), pool_ids.join("|"))
+        }
+
+        pub fn count_cycles(pools: &[PoolPrice], amount: f64, max_depth: usize) -> usize {
+            let mut adjacency: HashMap<String, Vec<Edge>> = HashMap::new();
+            for pool in pools {
+                let Some((base, quote)) = pool.pair.split_once('/') else { continue };
+                let (base_reserve, quote_reserve) = ui_reserves(pool);
+                adjacency.entry(quote.to_string()).or_default().push(Edge {
+                    pool: pool.clone(),
+                    from_token: quote.to_string(),
+                    to_token: base.to_string(),
+                    reserve_in: quote_reserve,
+                    reserve_out: base_reserve,
+                });
+                adjacency.entry(base.to_string()).or_default().push(Edge {
+                    pool: pool.clone(),
+                    from_token: base.to_string(),
+                    to_token: quote.to_string(),
+                    reserve_in: base_reserve,
+                    reserve_out: quote_reserve,
+                });
+            }
+
+            let mut cycles = 0;
+            for start in sorted_tokens(pools) {
+                let mut queue = VecDeque::from([Node { tokens: vec![start.clone()], amount, edges: Vec::new() }]);
+                let mut visited = HashSet::new();
+                while let Some(node) = queue.pop_front() {
+                    let depth = node.tokens.len() - 1;
+                    let current = node.tokens.last().unwrap().clone();
+                    if depth >= 2 && current == start {
+                        if node.amount > amount {
+                            cycles += 1;
+                        }
+                        continue;
+                    }
+                    if depth >= max_depth {
+                        continue;
+                    }
+                    for edge in adjacency.get(&current).into_iter().flatten() {
+                        debug_assert_eq!(edge.from_token, current);
+                        let next = edge.to_token.clone();
+                        if depth < 2 && next == start && depth >= 1 {
+                            continue;
+                        }
+                        if next != start && node.tokens.contains(&next) {
+                            continue;
+                        }
+                        let mut expanded = node.clone();
+                        expanded.amount = calculate_amm_output_f64(node.amount, edge.reserve_in, edge.reserve_out, FEE);
+                        expanded.tokens.push(next);
+                        expanded.edges.push(edge.clone());
+                        if visited.insert(signature(&expanded)) {
+                            queue.push_back(expanded);
+                        }
+                    }
+                }
+            }
+            cycles
+        }
+    }
+
+    pub mod interned {
+        use super::*;
+
+        #[derive(Clone, Copy)]
+        struct Edge {
+            pool: PoolId,
+            to_token: TokenId,
+            reserve_in: f64,
+            reserve_out: f64,
+        }
+
+        #[derive(Clone)]
+        struct Node {
+            tokens: Vec<TokenId>,
+            amount: f64,
+            edges: Vec<Edge>,
+        }
+
+        pub fn count_cycles(pools: &[PoolPrice], amount: f64, max_depth: usize) -> usize {
+            let registry = TokenRegistry::from_symbols(&sorted_tokens(pools));
+            let mut adjacency = vec![Vec::new(); registry.len()];
+            for (i, pool) in pools.iter().enumerate() {
+                let Some((base, quote)) = pool.pair.split_once('/') else { continue };
+                let (Some(base), Some(quote)) = (registry.get(base), registry.get(quote)) else { continue };
+                let (base_reserve, quote_reserve) = ui_reserves(pool);
+                let pool = PoolId::new(i);
+                adjacency[quote.index()].push(Edge { pool, to_token: base, reserve_in: quote_reserve, reserve_out: base_reserve });
+                adjacency[base.index()].push(Edge { pool, to_token: quote, reserve_in: base_reserve, reserve_out: quote_reserve });
+            }
+
+            let mut cycles = 0;
+            for start in registry.sorted_ids() {
+                let mut queue = VecDeque::from([Node { tokens: vec![start], amount, edges: Vec::new() }]);
+                let mut visited = HashSet::new();
+                while let Some(node) = queue.pop_front() {
+                    let depth = node.tokens.len() - 1;
+                    let current = *node.tokens.last().unwrap();
+                    if depth >= 2 && current == start {
+                        if node.amount > amount {
+                            cycles += 1;
+                        }
+                        continue;
+                    }
+                    if depth >= max_depth {
+                        continue;
+                    }
+                    for edge in &adjacency[current.index()] {
+                        let next = edge.to_token;
+                        if depth < 2 && next == start && depth >= 1 {
+                            continue;
+                        }
+                        if next != start && node.tokens.contains(&next) {
+                            continue;
+                        }
+                        let mut expanded = node.clone();
+                        expanded.amount = calculate_amm_output_f64(node.amount, edge.reserve_in, edge.reserve_out, FEE);
+                        expanded.tokens.push(next);
+                        expanded.edges.push(*edge);
+                        if visited.insert(path_signature(&expanded.tokens, expanded.edges.iter().map(|e| e.pool))) {
+                            queue.push_back(expanded);
+                        }
+                    }
+                }
+            }
+            cycles
+        }
+    }
+}
+
+fn bench_interned_routers(c: &mut Criterion) {
+    // 🔢 TokenId / PoolId 驻留前后对比：300 个池子、60 个代币
+    let pools = create_synthetic_graph(300, 60);
+    let (amount, max_depth) = (1000.0, 3);
+    assert_eq!(
+        keying::string_keyed::count_cycles(&pools, amount, max_depth),
+        keying::interned::count_cycles(&pools, amount, max_depth),
+        "string-keyed and interned BFS must find the same cycles"
+    );
+
+    let mut group = c.benchmark_group("bfs_keying_300_pools");
+    group.bench_function(BenchmarkId::new("string_keyed", max_depth), |b| {
+        b.iter(|| keying::string_keyed::count_cycles(black_box(&pools), black_box(amount), max_depth))
+    });
+    group.bench_function(BenchmarkId::new("interned", max_depth), |b| {
+        b.iter(|| keying::interned::count_cycles(black_box(&pools), black_box(amount), max_depth))
+    });
+    group.finish();
+
+    // 路径签名：拼接字符串（驻留前）vs `PathSignature` 键（不分配），对全部闭合环路（不按 ROI 过滤）
+    let paths = BfsScanner::new(3, f64::MIN, ExecutionCostModel::shared_default()).find_all_opportunities(&pools, amount);
+    assert!(!paths.is_empty());
+    let mut group = c.benchmark_group("path_signature");
+    group.bench_function("joined_string", |b| {
+        b.iter(|| {
+            black_box(&paths).iter()
+                .map(|p| p.steps.iter().map(|s| s.pool_id.as_str()).collect::<Vec<_>>().join("->"))
+                .collect::<HashSet<String>>()
+        })
+    });
+    group.bench_function("signature_key", |b| {
+        b.iter(|| black_box(&paths).iter().map(|p| p.signature().key()).collect::<HashSet<u64>>())
+    });
+    group.finish();
+
+    // 完整扫描器（驻留后的热路径）
+    let mut group = c.benchmark_group("router_300_pools");
+    
+    let bfs = BfsScanner::new(3, 0.1, ExecutionCostModel::shared_default());
+    group.bench_function("bfs_depth_3", |b| {
+        b.iter(|| bfs.find_all_opportunities(black_box(&pools), black_box(1000.0)))
+    });
+    
+    let bellman_ford = BellmanFordScanner::new(6, 0.1, ExecutionCostModel::shared_default());
+    group.bench_function("bellman_ford_6_hops", |b| {
+        b.iter(|| bellman_ford.find_all_cycles(black_box(&pools), black_box(1000.0)))
+    });
+    
+    group.finish();
+}
+
+fn bench_quote_ladder(c: &mut Criterion) {
+    let pools = create_realistic_pool_set(300);
+    let amounts = geometric_ladder(10.0, DEFAULT_LADDER_STEPS);
+    
+    // 目标：300个池子、8档 < 5ms（含快照构建）
+    c.bench_function("quote_ladder_300_pools_8_sizes", |b| {
+        b.iter(|| {
+            let engine = QuoteEngine::from_snapshot(black_box(&pools));
+            engine.depth_curve("SOL", "USDC", black_box(&amounts))
+        })
+    });
+}
+
 criterion_group!(
     benches,
     bench_quick_scanner,
     bench_bfs_scanner,
     bench_bellman_ford_scanner,
-    bench_scaling
+    bench_scaling,
+    bench_interned_routers,
+    bench_quote_ladder
 );
 
 criterion_main!(benches);
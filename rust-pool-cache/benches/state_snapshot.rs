@@ -0,0 +1,67 @@
+/*!
+ * 状态层同步拉取基准测试
+ *
+ * 对比 `snapshot_into`（复用缓冲区、先过滤后克隆）与 `get_all_prices`（克隆全部）
+ */
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use solana_pool_cache::dashmap_state::DashMapStateLayer;
+use solana_pool_cache::price_cache::PoolPrice;
+use solana_pool_cache::state_layer::{SnapshotFilter, StateLayer};
+use std::time::Instant;
+
+fn populated_state(num_pools: usize) -> DashMapStateLayer {
+    let pairs = ["SOL/USDC", "SOL/USDT", "USDC/USDT", "SOL/RAY", "RAY/USDC"];
+    let dexes = ["Raydium", "Orca", "Meteora", "Phoenix"];
+
+    let state = DashMapStateLayer::new();
+    for i in 0..num_pools {
+        state.update_price(PoolPrice {
+            pool_id: format!("pool_{}", i),
+            dex_name: dexes[i % dexes.len()].to_string(),
+            pair: pairs[i % pairs.len()].to_string(),
+            base_reserve: 1_000_000_000_000,
+            quote_reserve: 185_000_000_000,
+            base_decimals: 9,
+            quote_decimals: 6,
+            price: 185.0 + i as f64 * 0.1,
+            last_update: Instant::now(),
+            // 每个 slot 10 个池子：增量拉取最近 1 个 slot 约返回 10 个
+            slot: 1000 + (i / 10) as u64,
+            liquidity_usd: None,
+            commitment: None,
+        });
+    }
+    state
+}
+
+fn bench_snapshot_pull(c: &mut Criterion) {
+    let state = populated_state(2000);
+    let latest_slot = state.get_latest_slot();
+    let mut group = c.benchmark_group("state_snapshot_2000_pools");
+
+    group.bench_function("get_all_prices", |b| {
+        b.iter(|| black_box(state.get_all_prices()))
+    });
+
+    let mut buf = Vec::new();
+    let all = SnapshotFilter::new();
+    group.bench_function("snapshot_into_all", |b| {
+        b.iter(|| state.snapshot_into(black_box(&mut buf), black_box(&all)))
+    });
+
+    let by_pair = SnapshotFilter::new().pair("SOL/USDC");
+    group.bench_function("snapshot_into_pair", |b| {
+        b.iter(|| state.snapshot_into(black_box(&mut buf), black_box(&by_pair)))
+    });
+
+    let incremental = SnapshotFilter::new().changed_since(latest_slot - 1);
+    group.bench_function("snapshot_into_changed_since", |b| {
+        b.iter(|| state.snapshot_into(black_box(&mut buf), black_box(&incremental)))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_snapshot_pull);
+criterion_main!(benches);
@@ -6,6 +6,7 @@
 
 use solana_pool_cache::price_cache::{PoolPrice, PriceCache};
 use solana_pool_cache::router_bellman_ford::BellmanFordScanner;
+use solana_pool_cache::execution_cost::ExecutionCostModel;
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -61,6 +62,8 @@ fn test_case_1() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "lifinity_sol_usdc".to_string(),
@@ -72,11 +75,13 @@ fn test_case_1() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
     ];
     
     // 运行Bellman-Ford
-    let scanner = BellmanFordScanner::new(6, 0.0001);  // 极低阈值确保找到
+    let scanner = BellmanFordScanner::new(6, 0.0001, ExecutionCostModel::shared_default());  // 极低阈值确保找到
     let cycles = scanner.find_all_cycles(&pools, 1000.0);
     
     println!("Bellman-Ford结果:");
@@ -133,6 +138,8 @@ fn test_case_2() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "orca_sol_usdt".to_string(),
@@ -144,6 +151,8 @@ fn test_case_2() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "solfi_usdc_usdt".to_string(),
@@ -155,10 +164,12 @@ fn test_case_2() {
             base_decimals: 6,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
     ];
     
-    let scanner = BellmanFordScanner::new(6, 0.0001);
+    let scanner = BellmanFordScanner::new(6, 0.0001, ExecutionCostModel::shared_default());
     let cycles = scanner.find_all_cycles(&pools, 1000.0);
     
     println!("Bellman-Ford结果:");
@@ -210,6 +221,8 @@ fn test_case_3() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "p2".to_string(),
@@ -221,6 +234,8 @@ fn test_case_3() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "p3".to_string(),
@@ -232,6 +247,8 @@ fn test_case_3() {
             base_decimals: 6,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "p4".to_string(),
@@ -243,10 +260,12 @@ fn test_case_3() {
             base_decimals: 6,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
     ];
     
-    let scanner = BellmanFordScanner::new(6, 0.0001);
+    let scanner = BellmanFordScanner::new(6, 0.0001, ExecutionCostModel::shared_default());
     let cycles = scanner.find_all_cycles(&pools, 1000.0);
     
     println!("Bellman-Ford结果:");
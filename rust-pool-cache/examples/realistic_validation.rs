@@ -6,6 +6,7 @@
 
 use solana_pool_cache::price_cache::PoolPrice;
 use solana_pool_cache::router_bellman_ford::BellmanFordScanner;
+use solana_pool_cache::execution_cost::ExecutionCostModel;
 use std::time::Instant;
 
 fn main() {
@@ -53,6 +54,8 @@ fn test_case_2hop() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "lifinity_sol_usdc".to_string(),
@@ -64,10 +67,12 @@ fn test_case_2hop() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
     ];
     
-    let scanner = BellmanFordScanner::new(6, 0.0001);
+    let scanner = BellmanFordScanner::new(6, 0.0001, ExecutionCostModel::shared_default());
     let cycles = scanner.find_all_cycles(&pools, 1000.0);
     
     print_results(&cycles, "USDC→SOL→USDC", 0.3, 0.5);
@@ -105,6 +110,8 @@ fn test_case_3hop_profitable() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "orca_sol_usdt".to_string(),
@@ -116,6 +123,8 @@ fn test_case_3hop_profitable() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "alphaq_usdc_usdt".to_string(),
@@ -127,10 +136,12 @@ fn test_case_3hop_profitable() {
             base_decimals: 6,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
     ];
     
-    let scanner = BellmanFordScanner::new(6, 0.0001);
+    let scanner = BellmanFordScanner::new(6, 0.0001, ExecutionCostModel::shared_default());
     let cycles = scanner.find_all_cycles(&pools, 1000.0);
     
     print_results(&cycles, "USDC→SOL→USDT→USDC", 0.2, 0.35);
@@ -169,6 +180,8 @@ fn test_case_4hop_profitable() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "raydium_sol_ray".to_string(),
@@ -180,6 +193,8 @@ fn test_case_4hop_profitable() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "meteora_ray_jup".to_string(),
@@ -191,6 +206,8 @@ fn test_case_4hop_profitable() {
             base_decimals: 6,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "orca_jup_usdc".to_string(),
@@ -202,10 +219,12 @@ fn test_case_4hop_profitable() {
             base_decimals: 6,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
     ];
     
-    let scanner = BellmanFordScanner::new(6, 0.0001);
+    let scanner = BellmanFordScanner::new(6, 0.0001, ExecutionCostModel::shared_default());
     let cycles = scanner.find_all_cycles(&pools, 1000.0);
     
     print_results(&cycles, "USDC→SOL→RAY→JUP→USDC", 0.1, 0.25);
@@ -243,6 +262,8 @@ fn test_case_5hop() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "p2".to_string(),
@@ -254,6 +275,8 @@ fn test_case_5hop() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "p3".to_string(),
@@ -265,6 +288,8 @@ fn test_case_5hop() {
             base_decimals: 6,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "p4".to_string(),
@@ -276,6 +301,8 @@ fn test_case_5hop() {
             base_decimals: 6,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "p5".to_string(),
@@ -287,10 +314,12 @@ fn test_case_5hop() {
             base_decimals: 6,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
     ];
     
-    let scanner = BellmanFordScanner::new(6, 0.0001);
+    let scanner = BellmanFordScanner::new(6, 0.0001, ExecutionCostModel::shared_default());
     let cycles = scanner.find_all_cycles(&pools, 1000.0);
     
     print_results(&cycles, "任意5跳路径", 0.0, 10.0);
@@ -320,6 +349,8 @@ fn test_case_unprofitable() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "p2".to_string(),
@@ -331,6 +362,8 @@ fn test_case_unprofitable() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "p3".to_string(),
@@ -342,10 +375,12 @@ fn test_case_unprofitable() {
             base_decimals: 6,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
     ];
     
-    let scanner = BellmanFordScanner::new(6, 0.0001);
+    let scanner = BellmanFordScanner::new(6, 0.0001, ExecutionCostModel::shared_default());
     let cycles = scanner.find_all_cycles(&pools, 1000.0);
     
     if cycles.is_empty() {
@@ -411,6 +446,7 @@ fn print_results(cycles: &[solana_pool_cache::router::ArbitragePath], expected_p
 
 use solana_pool_cache::price_cache::PoolPrice;
 use solana_pool_cache::router_bellman_ford::BellmanFordScanner;
+use solana_pool_cache::execution_cost::ExecutionCostModel;
 use std::time::Instant;
 
 fn main() {
@@ -458,6 +494,8 @@ fn test_case_2hop() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "lifinity_sol_usdc".to_string(),
@@ -469,10 +507,12 @@ fn test_case_2hop() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
     ];
     
-    let scanner = BellmanFordScanner::new(6, 0.0001);
+    let scanner = BellmanFordScanner::new(6, 0.0001, ExecutionCostModel::shared_default());
     let cycles = scanner.find_all_cycles(&pools, 1000.0);
     
     print_results(&cycles, "USDC→SOL→USDC", 0.3, 0.5);
@@ -510,6 +550,8 @@ fn test_case_3hop_profitable() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "orca_sol_usdt".to_string(),
@@ -521,6 +563,8 @@ fn test_case_3hop_profitable() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "alphaq_usdc_usdt".to_string(),
@@ -532,10 +576,12 @@ fn test_case_3hop_profitable() {
             base_decimals: 6,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
     ];
     
-    let scanner = BellmanFordScanner::new(6, 0.0001);
+    let scanner = BellmanFordScanner::new(6, 0.0001, ExecutionCostModel::shared_default());
     let cycles = scanner.find_all_cycles(&pools, 1000.0);
     
     print_results(&cycles, "USDC→SOL→USDT→USDC", 0.2, 0.35);
@@ -574,6 +620,8 @@ fn test_case_4hop_profitable() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "raydium_sol_ray".to_string(),
@@ -585,6 +633,8 @@ fn test_case_4hop_profitable() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "meteora_ray_jup".to_string(),
@@ -596,6 +646,8 @@ fn test_case_4hop_profitable() {
             base_decimals: 6,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "orca_jup_usdc".to_string(),
@@ -607,10 +659,12 @@ fn test_case_4hop_profitable() {
             base_decimals: 6,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
     ];
     
-    let scanner = BellmanFordScanner::new(6, 0.0001);
+    let scanner = BellmanFordScanner::new(6, 0.0001, ExecutionCostModel::shared_default());
     let cycles = scanner.find_all_cycles(&pools, 1000.0);
     
     print_results(&cycles, "USDC→SOL→RAY→JUP→USDC", 0.1, 0.25);
@@ -648,6 +702,8 @@ fn test_case_5hop() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "p2".to_string(),
@@ -659,6 +715,8 @@ fn test_case_5hop() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "p3".to_string(),
@@ -670,6 +728,8 @@ fn test_case_5hop() {
             base_decimals: 6,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "p4".to_string(),
@@ -681,6 +741,8 @@ fn test_case_5hop() {
             base_decimals: 6,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "p5".to_string(),
@@ -692,10 +754,12 @@ fn test_case_5hop() {
             base_decimals: 6,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
     ];
     
-    let scanner = BellmanFordScanner::new(6, 0.0001);
+    let scanner = BellmanFordScanner::new(6, 0.0001, ExecutionCostModel::shared_default());
     let cycles = scanner.find_all_cycles(&pools, 1000.0);
     
     print_results(&cycles, "任意5跳路径", 0.0, 10.0);
@@ -725,6 +789,8 @@ fn test_case_unprofitable() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "p2".to_string(),
@@ -736,6 +802,8 @@ fn test_case_unprofitable() {
             base_decimals: 9,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
         PoolPrice {
             pool_id: "p3".to_string(),
@@ -747,10 +815,12 @@ fn test_case_unprofitable() {
             base_decimals: 6,
             quote_decimals: 6,
             last_update: Instant::now(),
+            liquidity_usd: None,
+            commitment: None,
         },
     ];
     
-    let scanner = BellmanFordScanner::new(6, 0.0001);
+    let scanner = BellmanFordScanner::new(6, 0.0001, ExecutionCostModel::shared_default());
     let cycles = scanner.find_all_cycles(&pools, 1000.0);
     
     if cycles.is_empty() {
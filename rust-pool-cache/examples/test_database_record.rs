@@ -22,6 +22,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         record_opportunities: true,
         record_pool_updates: false,
         record_performance: true,
+        opportunity_lifecycle_ttl_secs: 30,
     }).await?;
     
     db.set_subscription_start();
@@ -42,6 +43,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 liquidity_quote: 150_000_000_000_000,
                 expected_input: 1000.0,
                 expected_output: 6.65,
+                price_impact_percent: 0.05,
+                effective_fee_bps: 25.0,
+                raw_input_token: None,
+                raw_output_token: None,
             },
             RouteStep {
                 pool_id: "7XawhbbxtsRcQA8KTkHT9f9nc6d69UwqCDh6U5EEbEmX".to_string(),
@@ -53,6 +58,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 liquidity_quote: 150_800_000_000_000,
                 expected_input: 6.65,
                 expected_output: 1002.32,
+                price_impact_percent: 0.02,
+                effective_fee_bps: 30.0,
+                raw_input_token: None,
+                raw_output_token: None,
             },
             RouteStep {
                 pool_id: "Pi9nzTjPxD8DsRfRBGfKYzmefJoJM8TcXu2jyaQjSHm".to_string(),
@@ -64,6 +73,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 liquidity_quote: 10_000_000_000_000,
                 expected_input: 1002.32,
                 expected_output: 1002.62,
+                price_impact_percent: 0.01,
+                effective_fee_bps: 1.0,
+                raw_input_token: None,
+                raw_output_token: None,
             },
         ],
         start_token: "USDC".to_string(),
@@ -71,6 +84,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         input_amount: 1000.0,
         output_amount: 1002.62,
         gross_profit: 2.62,
+        dex_fees: 0.27,
+        execution_fees: 0.0,
         estimated_fees: 0.27,
         net_profit: 2.35,
         roi_percent: 0.235,
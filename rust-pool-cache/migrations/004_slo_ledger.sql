@@ -0,0 +1,12 @@
+-- 可用性SLO账本（状态切换 + 周期检查点）
+-- 注意：不删除旧数据，重启后用于恢复可用率统计
+
+CREATE TABLE IF NOT EXISTS slo_ledger (
+    id BIGSERIAL PRIMARY KEY,
+    component VARCHAR(40) NOT NULL,
+    is_up BOOLEAN NOT NULL,
+    recorded_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_slo_ledger_time ON slo_ledger(recorded_at DESC);
+CREATE INDEX IF NOT EXISTS idx_slo_ledger_component ON slo_ledger(component);
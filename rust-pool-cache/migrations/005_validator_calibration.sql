@@ -0,0 +1,13 @@
+-- 验证器置信度校准表（每个十分位桶一行，定期重新拟合后覆盖）
+-- 注意：不删除旧数据，重启后直接恢复校准映射
+
+CREATE TABLE IF NOT EXISTS validator_calibration (
+    bucket INTEGER PRIMARY KEY,
+    lower_bound DOUBLE PRECISION NOT NULL,
+    upper_bound DOUBLE PRECISION NOT NULL,
+    samples BIGINT NOT NULL,
+    persisted BIGINT NOT NULL,
+    empirical_rate DOUBLE PRECISION,
+    calibrated DOUBLE PRECISION NOT NULL,
+    fitted_at TIMESTAMP NOT NULL
+);
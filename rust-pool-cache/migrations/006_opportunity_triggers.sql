@@ -0,0 +1,11 @@
+-- 机会触发来源与扫描延迟
+-- Calculator 记录每个机会是由哪个池子（或时钟）触发的，以及扫描耗时。
+-- 003 每次启动都会重建 arbitrage_opportunities，因此这里用 IF NOT EXISTS 幂等追加列。
+
+ALTER TABLE arbitrage_opportunities ADD COLUMN IF NOT EXISTS trigger_type VARCHAR(20);
+ALTER TABLE arbitrage_opportunities ADD COLUMN IF NOT EXISTS trigger_source VARCHAR(100);
+ALTER TABLE arbitrage_opportunities ADD COLUMN IF NOT EXISTS trigger_price_change_percent DOUBLE PRECISION;
+ALTER TABLE arbitrage_opportunities ADD COLUMN IF NOT EXISTS scan_latency_ms DOUBLE PRECISION;
+ALTER TABLE arbitrage_opportunities ADD COLUMN IF NOT EXISTS confidence_score DOUBLE PRECISION;
+
+CREATE INDEX IF NOT EXISTS idx_opportunities_trigger_source ON arbitrage_opportunities(trigger_source);
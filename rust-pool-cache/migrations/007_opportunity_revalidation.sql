@@ -0,0 +1,6 @@
+-- 上报前按最新缓存重新定价的结果
+-- roi_percent 保持发现时的 ROI，这里追加重新定价后的 ROI 和分类（confirmed / degraded）。
+-- 003 每次启动都会重建 arbitrage_opportunities，因此这里用 IF NOT EXISTS 幂等追加列。
+
+ALTER TABLE arbitrage_opportunities ADD COLUMN IF NOT EXISTS revalidated_roi_percent DOUBLE PRECISION;
+ALTER TABLE arbitrage_opportunities ADD COLUMN IF NOT EXISTS revalidation_status VARCHAR(20);
@@ -0,0 +1,20 @@
+-- 交易级模拟结果（每次扫描的最佳机会构建真实 swap 交易并 simulateTransaction）
+-- 注意：不删除旧数据；status = simulated | reverted | unsupported
+
+CREATE TABLE IF NOT EXISTS transaction_simulations (
+    id SERIAL PRIMARY KEY,
+    simulated_at TIMESTAMP NOT NULL,
+    path_signature TEXT NOT NULL,
+    path_summary TEXT NOT NULL,
+    roi_percent DOUBLE PRECISION NOT NULL,
+    status VARCHAR(20) NOT NULL,
+    beat_input BOOLEAN NOT NULL DEFAULT FALSE,
+    compute_units BIGINT,
+    input_amount BIGINT,
+    net_delta BIGINT,
+    hop_deltas TEXT,
+    error TEXT,
+    simulation_latency_ms BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS idx_transaction_simulations_time ON transaction_simulations(simulated_at DESC);
@@ -0,0 +1,32 @@
+-- 机会生命周期（每个指纹 = 路径签名 + 方向）
+-- 首次发现插入一行，TTL 内再次发现只更新 last_seen_at / 峰值 ROI；
+-- 重新定价失败时写入终态（status = invalidated），模拟结果写入同一行。
+-- 注意：不删除旧数据；003 只重建 arbitrage_opportunities，这张表跨重启保留。
+
+CREATE TABLE IF NOT EXISTS opportunity_lifecycle (
+    id BIGSERIAL PRIMARY KEY,
+    fingerprint VARCHAR(16) NOT NULL,
+    path_signature TEXT NOT NULL,
+    path_summary TEXT NOT NULL,
+    hop_count INTEGER NOT NULL,
+    first_seen_at TIMESTAMP NOT NULL,
+    last_seen_at TIMESTAMP NOT NULL,
+    detection_count INTEGER NOT NULL DEFAULT 1,
+    first_roi_percent DOUBLE PRECISION NOT NULL,
+    peak_roi_percent DOUBLE PRECISION NOT NULL,
+    last_roi_percent DOUBLE PRECISION NOT NULL
+);
+
+-- 终态字段（逐列追加，已有数据库原地升级）
+ALTER TABLE opportunity_lifecycle ADD COLUMN IF NOT EXISTS status VARCHAR(20) NOT NULL DEFAULT 'active';
+ALTER TABLE opportunity_lifecycle ADD COLUMN IF NOT EXISTS closed_at TIMESTAMP;
+ALTER TABLE opportunity_lifecycle ADD COLUMN IF NOT EXISTS revalidated_roi_percent DOUBLE PRECISION;
+ALTER TABLE opportunity_lifecycle ADD COLUMN IF NOT EXISTS revalidation_status VARCHAR(20);
+ALTER TABLE opportunity_lifecycle ADD COLUMN IF NOT EXISTS invalidation_reason TEXT;
+ALTER TABLE opportunity_lifecycle ADD COLUMN IF NOT EXISTS simulated_at TIMESTAMP;
+ALTER TABLE opportunity_lifecycle ADD COLUMN IF NOT EXISTS simulation_status VARCHAR(20);
+ALTER TABLE opportunity_lifecycle ADD COLUMN IF NOT EXISTS simulation_success BOOLEAN;
+ALTER TABLE opportunity_lifecycle ADD COLUMN IF NOT EXISTS simulated_profit BIGINT;
+
+CREATE INDEX IF NOT EXISTS idx_opportunity_lifecycle_fingerprint
+    ON opportunity_lifecycle(fingerprint, last_seen_at DESC);
@@ -0,0 +1,6 @@
+-- 每跳滑点假设：价格冲击（%）与实际手续费（bps），以及整条路径的最大单跳冲击
+-- 003 每次启动都会重建 arbitrage_opportunities / arbitrage_steps，因此这里用 IF NOT EXISTS 幂等追加列。
+
+ALTER TABLE arbitrage_steps ADD COLUMN IF NOT EXISTS price_impact_percent DOUBLE PRECISION;
+ALTER TABLE arbitrage_steps ADD COLUMN IF NOT EXISTS effective_fee_bps DOUBLE PRECISION;
+ALTER TABLE arbitrage_opportunities ADD COLUMN IF NOT EXISTS worst_hop_impact_percent DOUBLE PRECISION;
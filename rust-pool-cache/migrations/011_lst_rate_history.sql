@@ -0,0 +1,10 @@
+-- LST 每个 epoch 的赎回比率（推算下一 epoch 比率用）
+-- 注意：不删除旧数据，重启后恢复最近几个 epoch 的历史
+
+CREATE TABLE IF NOT EXISTS lst_rate_history (
+    mint VARCHAR(64) NOT NULL,
+    epoch BIGINT NOT NULL,
+    rate DOUBLE PRECISION NOT NULL,
+    recorded_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (mint, epoch)
+);
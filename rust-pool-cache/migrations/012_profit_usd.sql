@@ -0,0 +1,4 @@
+-- 净利润的美元价值：按 PriceOracle（最深稳定币池子 + 锚定代币三角换算）换算，没有新鲜价格时为 NULL
+-- 003 每次启动都会重建 arbitrage_opportunities，因此这里用 IF NOT EXISTS 幂等追加列。
+
+ALTER TABLE arbitrage_opportunities ADD COLUMN IF NOT EXISTS profit_usd DOUBLE PRECISION;
@@ -0,0 +1,40 @@
+-- 池子更新历史（回放用）：WebSocket 路径写入 PriceCache 的每次更新，含 slot 与精度
+-- 注意：不随 003 重建，回放需要跨重启保留的历史
+
+CREATE TABLE IF NOT EXISTS pool_update_history (
+    id BIGSERIAL PRIMARY KEY,
+    recorded_at TIMESTAMP NOT NULL,
+    pool_address VARCHAR(100) NOT NULL,
+    pair VARCHAR(100) NOT NULL,
+    dex_name VARCHAR(50) NOT NULL,
+    price DOUBLE PRECISION NOT NULL,
+    base_reserve BIGINT NOT NULL,
+    quote_reserve BIGINT NOT NULL,
+    base_decimals SMALLINT NOT NULL,
+    quote_decimals SMALLINT NOT NULL,
+    slot BIGINT NOT NULL
+);
+
+-- 回放按 (recorded_at, id) 顺序分页读取
+CREATE INDEX IF NOT EXISTS idx_pool_update_history_time ON pool_update_history(recorded_at, id);
+
+-- 回放发现的机会（与实时 arbitrage_opportunities 分开，按 replay_run 区分每次回放）
+CREATE TABLE IF NOT EXISTS replayed_opportunities (
+    id BIGSERIAL PRIMARY KEY,
+    replay_run VARCHAR(100) NOT NULL,
+    -- 触发扫描时回放到的市场时间（最近一次应用的更新的 recorded_at）
+    market_time TIMESTAMP NOT NULL,
+    replayed_at TIMESTAMP NOT NULL,
+    arbitrage_type VARCHAR(20) NOT NULL,
+    start_token VARCHAR(20) NOT NULL,
+    path_summary TEXT NOT NULL,
+    path_signature TEXT NOT NULL,
+    hop_count INTEGER NOT NULL,
+    input_amount DOUBLE PRECISION NOT NULL,
+    net_profit DOUBLE PRECISION NOT NULL,
+    roi_percent DOUBLE PRECISION NOT NULL,
+    trigger_source TEXT,
+    confidence_score DOUBLE PRECISION
+);
+
+CREATE INDEX IF NOT EXISTS idx_replayed_opportunities_run ON replayed_opportunities(replay_run, market_time);
@@ -0,0 +1,26 @@
+-- 路由扫描摘要：每次扫描一行（耗时、涉及池子数、按状态计数），用于长期分析机会闪烁率
+-- 注意：不随 003 重建，保留跨重启的历史；scan_id 为进程内编号，重启后从 1 开始
+
+CREATE TABLE IF NOT EXISTS scan_summaries (
+    id BIGSERIAL PRIMARY KEY,
+    scan_id BIGINT NOT NULL,
+    scanned_at TIMESTAMP NOT NULL,
+    duration_ms DOUBLE PRECISION NOT NULL,
+    trigger_type VARCHAR(20) NOT NULL,
+    trigger_source VARCHAR(200) NOT NULL,
+    scoped BOOLEAN NOT NULL,
+    pools_used INTEGER NOT NULL,
+    opportunity_count INTEGER NOT NULL,
+    new_count INTEGER NOT NULL,
+    persisting_count INTEGER NOT NULL,
+    improved_count INTEGER NOT NULL,
+    worsened_count INTEGER NOT NULL,
+    gone_count INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_scan_summaries_time ON scan_summaries(scanned_at);
+
+-- 上报的机会相对上一次扫描的状态（new / persisting / improved / worsened）
+-- 003 每次启动都会重建 arbitrage_opportunities，因此这里用 IF NOT EXISTS 幂等追加列。
+ALTER TABLE arbitrage_opportunities ADD COLUMN IF NOT EXISTS scan_id BIGINT;
+ALTER TABLE arbitrage_opportunities ADD COLUMN IF NOT EXISTS scan_status VARCHAR(20);
@@ -0,0 +1,303 @@
+/*!
+ * 📡 accountSubscribe 参数（commitment / encoding）与通知数据解码
+ *
+ * - commitment：默认 confirmed；processed 比 confirmed 早 400-800ms 推送，但数据可能被回滚，
+ *   只给指定的热门池子使用（[websocket] 全局设置，PoolConfig.commitment 按池子覆盖）。
+ *   写入缓存的 `PoolPrice::commitment` 记录产生该更新的 commitment，
+ *   验证器对大额机会要求 confirmed 及以上（`validation_rules::ConfirmedRequiredAboveUsd`）。
+ * - encoding：默认 base64；大账户（例如 Phoenix 市场）可以用 base64+zstd 减少带宽，
+ *   解码按通知里 `value.data[1]` 声明的编码进行。
+ * - 通知解析是全函数：畸形通知（坏 JSON、缺字段、坏 base64、不支持的编码）归入 `MalformedKind`，
+ *   由调用方计数并记入 ErrorTracker，不会中断读取循环；只有传输错误才触发重连。
+ */
+
+use std::fmt;
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// 订阅 commitment（顺序即确认程度：processed < confirmed < finalized）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Commitment {
+    Processed,
+    #[default]
+    Confirmed,
+    Finalized,
+}
+
+impl Commitment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Commitment::Processed => "processed",
+            Commitment::Confirmed => "confirmed",
+            Commitment::Finalized => "finalized",
+        }
+    }
+
+    /// confirmed 或 finalized（不会被回滚的数据）
+    pub fn is_confirmed(&self) -> bool {
+        *self >= Commitment::Confirmed
+    }
+}
+
+impl fmt::Display for Commitment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 账户数据编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AccountEncoding {
+    #[default]
+    #[serde(rename = "base64")]
+    Base64,
+    #[serde(rename = "base64+zstd")]
+    Base64Zstd,
+}
+
+impl AccountEncoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountEncoding::Base64 => "base64",
+            AccountEncoding::Base64Zstd => "base64+zstd",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "base64" => Some(AccountEncoding::Base64),
+            "base64+zstd" => Some(AccountEncoding::Base64Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// accountSubscribe 的第二个参数
+pub fn subscribe_options(commitment: Commitment, encoding: AccountEncoding) -> Value {
+    json!({
+        "encoding": encoding.as_str(),
+        "commitment": commitment.as_str()
+    })
+}
+
+/// 畸形消息的类别（ErrorTracker 的 error_type 和指标标签）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MalformedKind {
+    /// 不是合法 JSON（包括被截断的帧）
+    BadJson,
+    /// 缺少字段或字段类型不对（空 data 数组、非数字的 subscription id ...）
+    MissingField,
+    /// base64 解码失败，或 base64+zstd 解压失败
+    BadBase64,
+    /// 不支持的编码（jsonParsed、base58 等）
+    UnexpectedEncoding,
+}
+
+impl MalformedKind {
+    pub const ALL: [MalformedKind; 4] = [
+        MalformedKind::BadJson,
+        MalformedKind::MissingField,
+        MalformedKind::BadBase64,
+        MalformedKind::UnexpectedEncoding,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MalformedKind::BadJson => "bad_json",
+            MalformedKind::MissingField => "missing_field",
+            MalformedKind::BadBase64 => "bad_base64",
+            MalformedKind::UnexpectedEncoding => "unexpected_encoding",
+        }
+    }
+
+    /// ErrorTracker 的 error_type
+    pub fn error_type(&self) -> &'static str {
+        match self {
+            MalformedKind::BadJson => "malformed_message_bad_json",
+            MalformedKind::MissingField => "malformed_message_missing_field",
+            MalformedKind::BadBase64 => "malformed_message_bad_base64",
+            MalformedKind::UnexpectedEncoding => "malformed_message_unexpected_encoding",
+        }
+    }
+}
+
+/// 一条畸形消息（内容错误，不是传输错误）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MalformedMessage {
+    pub kind: MalformedKind,
+    pub detail: String,
+}
+
+impl MalformedMessage {
+    pub fn new(kind: MalformedKind, detail: impl Into<String>) -> Self {
+        Self { kind, detail: detail.into() }
+    }
+}
+
+impl fmt::Display for MalformedMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "malformed message ({}): {}", self.kind.as_str(), self.detail)
+    }
+}
+
+impl std::error::Error for MalformedMessage {}
+
+/// 解析后的 accountNotification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountNotification {
+    pub subscription_id: u64,
+    /// 缺少 context.slot 时为 0（调用方告警）
+    pub slot: u64,
+    /// 账户 owner（部分节点不带）
+    pub owner: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// subscription id / request id：数字，或者数字字符串（部分节点这样返回）
+pub fn subscription_id(value: &Value) -> Option<u64> {
+    value.as_u64().or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
+/// 解析一条 accountNotification（已经确认 method 是 accountNotification）
+pub fn parse_account_notification(msg: &Value) -> Result<AccountNotification, MalformedMessage> {
+    let subscription_id = msg.pointer("/params/subscription")
+        .and_then(subscription_id)
+        .ok_or_else(|| MalformedMessage::new(MalformedKind::MissingField, "params.subscription is missing or not an integer"))?;
+    let data = msg.pointer("/params/result/value/data")
+        .ok_or_else(|| MalformedMessage::new(MalformedKind::MissingField, "params.result.value.data is missing"))?;
+    Ok(AccountNotification {
+        subscription_id,
+        slot: msg.pointer("/params/result/context/slot").and_then(|s| s.as_u64()).unwrap_or(0),
+        owner: msg.pointer("/params/result/value/owner").and_then(|o| o.as_str()).map(str::to_string),
+        data: decode_account_data(data)?,
+    })
+}
+
+/// 解码通知中的 `value.data`（`[数据, 编码]`，缺少编码时按 base64）
+///
+/// 按通知声明的编码解码（即使与订阅时请求的不同）；base64 中的空白（部分节点折行）会被忽略。
+pub fn decode_account_data(data: &Value) -> Result<Vec<u8>, MalformedMessage> {
+    let data_array = match data {
+        Value::Array(array) => array,
+        // 旧版 binary（base58）编码直接给字符串
+        Value::String(_) => {
+            return Err(MalformedMessage::new(MalformedKind::UnexpectedEncoding, "data is a bare string (base58), expected [data, encoding]"));
+        }
+        _ => return Err(MalformedMessage::new(MalformedKind::MissingField, "data is not an array")),
+    };
+    let encoded = data_array
+        .first()
+        .and_then(|d| d.as_str())
+        .ok_or_else(|| MalformedMessage::new(MalformedKind::MissingField, "data[0] is missing or not a string"))?;
+    let encoding = match data_array.get(1) {
+        None => "base64",
+        Some(encoding) => encoding.as_str()
+            .ok_or_else(|| MalformedMessage::new(MalformedKind::MissingField, "data[1] (encoding) is not a string"))?,
+    };
+    let encoding = AccountEncoding::from_str(encoding).ok_or_else(|| {
+        MalformedMessage::new(MalformedKind::UnexpectedEncoding, format!("unsupported account data encoding '{}'", encoding))
+    })?;
+
+    let decoded = if encoded.bytes().any(|b| b.is_ascii_whitespace()) {
+        let compact: String = encoded.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+        base64::engine::general_purpose::STANDARD.decode(compact)
+    } else {
+        base64::engine::general_purpose::STANDARD.decode(encoded)
+    }
+    .map_err(|e| MalformedMessage::new(MalformedKind::BadBase64, format!("invalid base64 ({} chars): {}", encoded.len(), e)))?;
+    match encoding {
+        AccountEncoding::Base64 => Ok(decoded),
+        AccountEncoding::Base64Zstd => zstd::stream::decode_all(decoded.as_slice())
+            .map_err(|e| MalformedMessage::new(MalformedKind::BadBase64, format!("invalid zstd payload: {}", e))),
+    }
+}
+
+/// 解析失败的文本帧（不是 JSON）
+pub fn parse_message(text: &str) -> Result<Value, MalformedMessage> {
+    serde_json::from_str(text).map_err(|e| MalformedMessage::new(MalformedKind::BadJson, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commitment_ordering_and_options() {
+        assert!(!Commitment::Processed.is_confirmed());
+        assert!(Commitment::Confirmed.is_confirmed() && Commitment::Finalized.is_confirmed());
+        assert_eq!(Commitment::default(), Commitment::Confirmed);
+
+        let options = subscribe_options(Commitment::Processed, AccountEncoding::Base64Zstd);
+        assert_eq!(options, json!({"encoding": "base64+zstd", "commitment": "processed"}));
+        assert!(decode_account_data(&json!(["AAEC", "jsonParsed"])).is_err());
+        assert_eq!(decode_account_data(&json!(["AAEC"])).unwrap(), vec![0, 1, 2]);
+    }
+
+    fn notification(subscription: Value, data: Value) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "method": "accountNotification",
+            "params": {
+                "subscription": subscription,
+                "result": { "context": { "slot": 42 }, "value": { "data": data, "owner": "Owner111" } }
+            }
+        })
+    }
+
+    fn kind_of(msg: &Value) -> Option<MalformedKind> {
+        parse_account_notification(msg).err().map(|e| e.kind)
+    }
+
+    #[test]
+    fn test_parse_account_notification() {
+        let parsed = parse_account_notification(&notification(json!(7), json!(["AAEC", "base64"]))).unwrap();
+        assert_eq!(parsed, AccountNotification {
+            subscription_id: 7,
+            slot: 42,
+            owner: Some("Owner111".to_string()),
+            data: vec![0, 1, 2],
+        });
+
+        // 字符串形式的 subscription id、折行的 base64 都能解析
+        let parsed = parse_account_notification(&notification(json!("7"), json!(["AA\nEC ", "base64"]))).unwrap();
+        assert_eq!((parsed.subscription_id, parsed.data), (7, vec![0, 1, 2]));
+
+        // 未请求 base64+zstd 但节点按它返回：按声明的编码解码
+        let compressed = zstd::stream::encode_all(&[9u8, 8, 7][..], 0).unwrap();
+        let encoded = base64::engine::general_purpose::STANDARD.encode(compressed);
+        let parsed = parse_account_notification(&notification(json!(7), json!([encoded, "base64+zstd"]))).unwrap();
+        assert_eq!(parsed.data, vec![9, 8, 7]);
+    }
+
+    #[test]
+    fn test_broken_frames_are_classified() {
+        let corpus: Vec<(Value, MalformedKind)> = vec![
+            (notification(json!(7), json!([])), MalformedKind::MissingField),
+            (notification(json!(7), json!([null, "base64"])), MalformedKind::MissingField),
+            (notification(json!(7), json!(["AAEC", 64])), MalformedKind::MissingField),
+            (notification(json!(7), json!({"parsed": {}})), MalformedKind::MissingField),
+            (notification(json!("seven"), json!(["AAEC", "base64"])), MalformedKind::MissingField),
+            (notification(json!(-1), json!(["AAEC", "base64"])), MalformedKind::MissingField),
+            (notification(json!(null), json!(["AAEC", "base64"])), MalformedKind::MissingField),
+            (json!({"method": "accountNotification", "params": {"subscription": 7}}), MalformedKind::MissingField),
+            (json!({"method": "accountNotification"}), MalformedKind::MissingField),
+            // 截断的 base64
+            (notification(json!(7), json!(["AAE", "base64"])), MalformedKind::BadBase64),
+            (notification(json!(7), json!(["%%% not base64 %%%", "base64"])), MalformedKind::BadBase64),
+            // 声明 base64+zstd 但数据没有压缩
+            (notification(json!(7), json!(["AAEC", "base64+zstd"])), MalformedKind::BadBase64),
+            (notification(json!(7), json!(["AAEC", "jsonParsed"])), MalformedKind::UnexpectedEncoding),
+            (notification(json!(7), json!("1111")), MalformedKind::UnexpectedEncoding),
+        ];
+        for (msg, expected) in &corpus {
+            assert_eq!(kind_of(msg), Some(*expected), "frame: {}", msg);
+        }
+
+        for text in ["", "not json", "{\"jsonrpc\": \"2.0\", \"method\": \"accountNotif", "[1, 2"] {
+            assert_eq!(parse_message(text).unwrap_err().kind, MalformedKind::BadJson, "frame: {:?}", text);
+        }
+    }
+}
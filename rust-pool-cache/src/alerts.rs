@@ -0,0 +1,591 @@
+/*!
+ * 套利机会告警分发
+ *
+ * 一条共享的机会流 -> 多个独立评估的告警 sink：
+ * - production：ROI阈值 + 同一路径冷却（生产频道保持安静）
+ * - firehose：每个机会都推送（忽略ROI阈值和冷却），按每分钟条数限流，
+ *   超出部分在窗口结束时汇总为 "suppressed N additional opportunities"
+ *
+ * 每个 sink 可独立选择模板：
+ * - compact：单行摘要
+ * - detailed：包含逐跳明细和数据年龄
+ *
+ * 同一机会在多个 sink 使用同一模板时只渲染一次。
+ *
+ * 扫描差异事件（New / Improved / Gone 等）通过 dispatch_event 分发：
+ * production 只接收状态变化（New / Improved，以及已告警机会的 Gone），
+ * firehose 接收全部事件。
+ */
+
+use crate::price_cache::PriceCache;
+use crate::scan_diff::{EventKind, OpportunityEvent};
+use crate::router_split_optimizer::OptimizedPath;
+use serde::{Deserialize, Serialize};
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Sink 模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SinkMode {
+    /// 阈值 + 冷却
+    Production,
+    /// 全量推送，仅按速率限流
+    Firehose,
+}
+
+/// 消息模板
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertTemplate {
+    /// 单行摘要
+    Compact,
+    /// 逐跳明细 + 数据年龄
+    Detailed,
+}
+
+/// 单个 sink 的配置
+#[derive(Debug, Clone)]
+pub struct SinkConfig {
+    /// Sink 名称（日志 / 传输层标识）
+    pub name: String,
+    pub mode: SinkMode,
+    pub template: AlertTemplate,
+    /// 最小ROI（仅 production 生效）
+    pub min_roi_percent: f64,
+    /// 同一路径冷却时间（仅 production 生效）
+    pub cooldown: Duration,
+    /// 每分钟最多消息数（仅 firehose 生效，0 = 不限）
+    pub max_per_minute: usize,
+}
+
+/// 告警中的单跳信息
+#[derive(Debug, Clone)]
+pub struct AlertStep {
+    pub pool_id: String,
+    pub dex_name: String,
+    pub input_token: String,
+    pub output_token: String,
+    pub expected_input: f64,
+    pub expected_output: f64,
+    /// 该池子数据年龄（毫秒），缓存中不存在时为 None
+    pub data_age_ms: Option<u64>,
+}
+
+/// 告警用的机会快照
+#[derive(Debug, Clone)]
+pub struct AlertOpportunity {
+    pub signature: String,
+    pub start_token: String,
+    pub input_amount: f64,
+    pub net_profit: f64,
+    pub roi_percent: f64,
+    pub steps: Vec<AlertStep>,
+}
+
+impl AlertOpportunity {
+    /// 从路由结果构建，数据年龄取自价格缓存
+    pub fn from_path(path: &OptimizedPath, price_cache: &PriceCache) -> Self {
+        let base = &path.base_path;
+        Self {
+            signature: base.signature().to_string(),
+            start_token: base.start_token.clone(),
+            input_amount: base.input_amount,
+            net_profit: path.optimized_net_profit,
+            roi_percent: path.optimized_roi,
+            steps: base.steps.iter()
+                .map(|step| AlertStep {
+                    pool_id: step.pool_id.clone(),
+                    dex_name: step.dex_name.clone(),
+                    input_token: step.input_token.clone(),
+                    output_token: step.output_token.clone(),
+                    expected_input: step.expected_input,
+                    expected_output: step.expected_output,
+                    data_age_ms: price_cache.get_price(&step.pool_id)
+                        .map(|p| p.last_update.elapsed().as_millis() as u64),
+                })
+                .collect(),
+        }
+    }
+
+    fn render(&self, template: AlertTemplate) -> String {
+        match template {
+            AlertTemplate::Compact => format!(
+                "💰 {:.4}% ROI | +{:.4} {} | {} hops | {}",
+                self.roi_percent, self.net_profit, self.start_token, self.steps.len(), self.signature
+            ),
+            AlertTemplate::Detailed => {
+                let mut msg = format!(
+                    "💰 Arbitrage opportunity: {:.4}% ROI\n   Path: {}\n   Input: {:.4} {}\n   Net profit: {:.4} {}\n",
+                    self.roi_percent, self.signature, self.input_amount, self.start_token, self.net_profit, self.start_token
+                );
+                for (idx, step) in self.steps.iter().enumerate() {
+                    let age = step.data_age_ms
+                        .map(|ms| format!("{}ms", ms))
+                        .unwrap_or_else(|| "n/a".to_string());
+                    msg.push_str(&format!(
+                        "   {}. [{}] {:.4} {} -> {:.4} {} (pool {}, data age {})\n",
+                        idx + 1, step.dex_name,
+                        step.expected_input, step.input_token,
+                        step.expected_output, step.output_token,
+                        step.pool_id, age
+                    ));
+                }
+                msg
+            }
+        }
+    }
+}
+
+/// 待发送的消息
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertMessage {
+    pub sink: String,
+    pub template: Option<AlertTemplate>,
+    /// 同一渲染结果在多个 sink 间共享
+    pub body: Arc<str>,
+}
+
+/// 传输层（日志 / webhook / Telegram 等）
+pub trait AlertTransport: Send + Sync {
+    fn send(&self, message: &AlertMessage);
+}
+
+/// 写入日志的传输层
+pub struct LogTransport;
+
+impl AlertTransport for LogTransport {
+    fn send(&self, message: &AlertMessage) {
+        info!(target: "alerts", sink = %message.sink, "{}", message.body);
+    }
+}
+
+/// 单个 sink 的运行状态
+struct SinkState {
+    config: SinkConfig,
+    /// production：路径签名 -> 上次发送时间
+    last_sent: HashMap<String, Instant>,
+    /// firehose：当前限流窗口
+    window_start: Option<Instant>,
+    sent_in_window: usize,
+    suppressed_in_window: usize,
+}
+
+impl SinkState {
+    fn new(config: SinkConfig) -> Self {
+        Self {
+            config,
+            last_sent: HashMap::new(),
+            window_start: None,
+            sent_in_window: 0,
+            suppressed_in_window: 0,
+        }
+    }
+
+    /// 窗口结束时生成溢出汇总并开启新窗口
+    fn roll_window(&mut self, now: Instant) -> Option<AlertMessage> {
+        let expired = self.window_start
+            .map(|start| now.duration_since(start) >= Duration::from_secs(60))
+            .unwrap_or(true);
+        if !expired {
+            return None;
+        }
+
+        let summary = (self.suppressed_in_window > 0).then(|| AlertMessage {
+            sink: self.config.name.clone(),
+            template: None,
+            body: Arc::from(format!(
+                "suppressed {} additional opportunities",
+                self.suppressed_in_window
+            )),
+        });
+
+        self.window_start = Some(now);
+        self.sent_in_window = 0;
+        self.suppressed_in_window = 0;
+        summary
+    }
+
+    /// firehose 限流：占用当前窗口的一个名额
+    fn take_rate_slot(&mut self) -> bool {
+        if self.config.max_per_minute > 0 && self.sent_in_window >= self.config.max_per_minute {
+            self.suppressed_in_window += 1;
+            return false;
+        }
+        self.sent_in_window += 1;
+        true
+    }
+
+    /// 该 sink 是否接受此机会（会更新冷却 / 限流状态）
+    fn accept(&mut self, opportunity: &AlertOpportunity, now: Instant) -> bool {
+        match self.config.mode {
+            SinkMode::Production => {
+                if opportunity.roi_percent < self.config.min_roi_percent {
+                    return false;
+                }
+                if let Some(last) = self.last_sent.get(&opportunity.signature) {
+                    if now.duration_since(*last) < self.config.cooldown {
+                        return false;
+                    }
+                }
+                self.last_sent.insert(opportunity.signature.clone(), now);
+                true
+            }
+            SinkMode::Firehose => self.take_rate_slot(),
+        }
+    }
+
+    /// Gone 事件：production 仅通知曾经告警过的机会
+    fn accept_gone(&mut self, signature: &str) -> bool {
+        match self.config.mode {
+            SinkMode::Production => self.last_sent.remove(signature).is_some(),
+            SinkMode::Firehose => self.take_rate_slot(),
+        }
+    }
+}
+
+/// 告警分发器：每个 sink 独立评估同一条机会流
+pub struct AlertDispatcher {
+    sinks: Vec<SinkState>,
+    transport: Arc<dyn AlertTransport>,
+}
+
+impl AlertDispatcher {
+    pub fn new(sinks: Vec<SinkConfig>, transport: Arc<dyn AlertTransport>) -> Self {
+        Self {
+            sinks: sinks.into_iter().map(SinkState::new).collect(),
+            transport,
+        }
+    }
+
+    pub fn sink_count(&self) -> usize {
+        self.sinks.len()
+    }
+
+    /// 分发一个机会，返回实际发送的消息（含窗口溢出汇总）
+    pub fn dispatch(&mut self, opportunity: &AlertOpportunity, now: Instant) -> Vec<AlertMessage> {
+        self.dispatch_with_header(None, opportunity, now, true)
+    }
+
+    /// 分发扫描差异事件；非 Gone 事件需要附带当前机会快照
+    pub fn dispatch_event(
+        &mut self,
+        event: &OpportunityEvent,
+        opportunity: Option<&AlertOpportunity>,
+        now: Instant,
+    ) -> Vec<AlertMessage> {
+        match (event.kind, opportunity) {
+            (EventKind::Gone(_), _) => self.dispatch_gone(event, now),
+            (kind, Some(opportunity)) => {
+                let state_change = matches!(kind, EventKind::New | EventKind::Improved);
+                self.dispatch_with_header(Some(event.headline()), opportunity, now, state_change)
+            }
+            (_, None) => Vec::new(),
+        }
+    }
+
+    fn dispatch_with_header(
+        &mut self,
+        header: Option<String>,
+        opportunity: &AlertOpportunity,
+        now: Instant,
+        production_eligible: bool,
+    ) -> Vec<AlertMessage> {
+        // 每个模板最多渲染一次
+        let compact: OnceCell<Arc<str>> = OnceCell::new();
+        let detailed: OnceCell<Arc<str>> = OnceCell::new();
+        let mut sent = Vec::new();
+
+        for sink in &mut self.sinks {
+            if sink.config.mode == SinkMode::Firehose {
+                sent.extend(sink.roll_window(now));
+            } else if !production_eligible {
+                continue;
+            }
+
+            if !sink.accept(opportunity, now) {
+                continue;
+            }
+
+            let template = sink.config.template;
+            let cell = match template {
+                AlertTemplate::Compact => &compact,
+                AlertTemplate::Detailed => &detailed,
+            };
+            let body = cell.get_or_init(|| {
+                let rendered = opportunity.render(template);
+                match &header {
+                    Some(header) => Arc::from(format!("{}\n{}", header, rendered)),
+                    None => Arc::from(rendered),
+                }
+            }).clone();
+            sent.push(AlertMessage {
+                sink: sink.config.name.clone(),
+                template: Some(template),
+                body,
+            });
+        }
+
+        for message in &sent {
+            self.transport.send(message);
+        }
+        sent
+    }
+
+    fn dispatch_gone(&mut self, event: &OpportunityEvent, now: Instant) -> Vec<AlertMessage> {
+        let body: Arc<str> = Arc::from(event.headline());
+        let mut sent = Vec::new();
+
+        for sink in &mut self.sinks {
+            if sink.config.mode == SinkMode::Firehose {
+                sent.extend(sink.roll_window(now));
+            }
+
+            if sink.accept_gone(&event.signature) {
+                sent.push(AlertMessage {
+                    sink: sink.config.name.clone(),
+                    template: Some(sink.config.template),
+                    body: body.clone(),
+                });
+            }
+        }
+
+        for message in &sent {
+            self.transport.send(message);
+        }
+        sent
+    }
+
+    /// 定期调用：没有新机会时也能发出已结束窗口的溢出汇总
+    pub fn flush(&mut self, now: Instant) -> Vec<AlertMessage> {
+        let sent: Vec<AlertMessage> = self.sinks.iter_mut()
+            .filter(|sink| sink.config.mode == SinkMode::Firehose)
+            .filter_map(|sink| sink.roll_window(now))
+            .collect();
+
+        for message in &sent {
+            self.transport.send(message);
+        }
+        sent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingTransport {
+        messages: Mutex<Vec<AlertMessage>>,
+    }
+
+    impl AlertTransport for RecordingTransport {
+        fn send(&self, message: &AlertMessage) {
+            self.messages.lock().unwrap().push(message.clone());
+        }
+    }
+
+    impl RecordingTransport {
+        fn for_sink(&self, sink: &str) -> Vec<AlertMessage> {
+            self.messages.lock().unwrap().iter()
+                .filter(|m| m.sink == sink)
+                .cloned()
+                .collect()
+        }
+    }
+
+    fn opportunity(signature: &str, roi: f64) -> AlertOpportunity {
+        AlertOpportunity {
+            signature: signature.to_string(),
+            start_token: "SOL".to_string(),
+            input_amount: 10.0,
+            net_profit: 10.0 * roi / 100.0,
+            roi_percent: roi,
+            steps: vec![
+                AlertStep {
+                    pool_id: "poolA".to_string(),
+                    dex_name: "Raydium".to_string(),
+                    input_token: "SOL".to_string(),
+                    output_token: "USDC".to_string(),
+                    expected_input: 10.0,
+                    expected_output: 1400.0,
+                    data_age_ms: Some(120),
+                },
+                AlertStep {
+                    pool_id: "poolB".to_string(),
+                    dex_name: "Orca".to_string(),
+                    input_token: "USDC".to_string(),
+                    output_token: "SOL".to_string(),
+                    expected_input: 1400.0,
+                    expected_output: 10.0 + 10.0 * roi / 100.0,
+                    data_age_ms: None,
+                },
+            ],
+        }
+    }
+
+    fn dispatcher(transport: Arc<RecordingTransport>) -> AlertDispatcher {
+        AlertDispatcher::new(
+            vec![
+                SinkConfig {
+                    name: "prod".to_string(),
+                    mode: SinkMode::Production,
+                    template: AlertTemplate::Compact,
+                    min_roi_percent: 0.5,
+                    cooldown: Duration::from_secs(30),
+                    max_per_minute: 0,
+                },
+                SinkConfig {
+                    name: "staging".to_string(),
+                    mode: SinkMode::Firehose,
+                    template: AlertTemplate::Detailed,
+                    min_roi_percent: 0.5,
+                    cooldown: Duration::from_secs(30),
+                    max_per_minute: 3,
+                },
+            ],
+            transport,
+        )
+    }
+
+    #[test]
+    fn test_each_sink_receives_its_subset() {
+        let transport = Arc::new(RecordingTransport::default());
+        let mut dispatcher = dispatcher(transport.clone());
+        let t0 = Instant::now();
+
+        dispatcher.dispatch(&opportunity("a->b", 0.8), t0);                           // prod + firehose
+        dispatcher.dispatch(&opportunity("c->d", 0.1), t0);                           // 低于阈值：仅 firehose
+        dispatcher.dispatch(&opportunity("a->b", 0.9), t0 + Duration::from_secs(5));  // 冷却中：仅 firehose
+
+        let prod = transport.for_sink("prod");
+        assert_eq!(prod.len(), 1);
+        assert_eq!(prod[0].template, Some(AlertTemplate::Compact));
+        assert!(prod[0].body.contains("a->b"));
+        assert!(!prod[0].body.contains('\n'));
+
+        let staging = transport.for_sink("staging");
+        assert_eq!(staging.len(), 3);
+        assert!(staging.iter().all(|m| m.template == Some(AlertTemplate::Detailed)));
+        assert!(staging[0].body.contains("Path: a->b"));
+        assert!(staging[0].body.contains("[Raydium]"));
+        assert!(staging[0].body.contains("data age 120ms"));
+        assert!(staging[0].body.contains("data age n/a"));
+
+        // 冷却结束后 production 再次发送
+        dispatcher.dispatch(&opportunity("a->b", 0.9), t0 + Duration::from_secs(31));
+        assert_eq!(transport.for_sink("prod").len(), 2);
+    }
+
+    #[test]
+    fn test_firehose_overflow_summary() {
+        let transport = Arc::new(RecordingTransport::default());
+        let mut dispatcher = dispatcher(transport.clone());
+        let t0 = Instant::now();
+
+        // 10 个机会，限流 3/分钟 -> 7 个被压制
+        for i in 0..10 {
+            dispatcher.dispatch(&opportunity(&format!("p{}", i), 0.2), t0 + Duration::from_secs(i));
+        }
+        assert_eq!(transport.for_sink("staging").len(), 3);
+        assert!(dispatcher.flush(t0 + Duration::from_secs(30)).is_empty());
+
+        // 窗口结束：汇总 + 新窗口
+        let summary = dispatcher.flush(t0 + Duration::from_secs(60));
+        assert_eq!(summary.len(), 1);
+        assert_eq!(&*summary[0].body, "suppressed 7 additional opportunities");
+        assert_eq!(summary[0].template, None);
+
+        // 汇总只发一次
+        assert!(dispatcher.flush(t0 + Duration::from_secs(125)).is_empty());
+    }
+
+    #[test]
+    fn test_summary_emitted_before_next_window_message() {
+        let transport = Arc::new(RecordingTransport::default());
+        let mut dispatcher = dispatcher(transport.clone());
+        let t0 = Instant::now();
+
+        for i in 0..5 {
+            dispatcher.dispatch(&opportunity(&format!("p{}", i), 0.2), t0);
+        }
+        let sent = dispatcher.dispatch(&opportunity("late", 0.2), t0 + Duration::from_secs(61));
+        let staging: Vec<_> = sent.iter().filter(|m| m.sink == "staging").collect();
+        assert_eq!(staging.len(), 2);
+        assert_eq!(&*staging[0].body, "suppressed 2 additional opportunities");
+        assert!(staging[1].body.contains("late"));
+    }
+
+    #[test]
+    fn test_shared_rendering_across_sinks() {
+        let transport = Arc::new(RecordingTransport::default());
+        let mut dispatcher = AlertDispatcher::new(
+            vec!["one", "two"].into_iter()
+                .map(|name| SinkConfig {
+                    name: name.to_string(),
+                    mode: SinkMode::Firehose,
+                    template: AlertTemplate::Compact,
+                    min_roi_percent: 0.0,
+                    cooldown: Duration::ZERO,
+                    max_per_minute: 0,
+                })
+                .collect(),
+            transport,
+        );
+
+        let sent = dispatcher.dispatch(&opportunity("a->b", 1.0), Instant::now());
+        assert_eq!(sent.len(), 2);
+        assert!(Arc::ptr_eq(&sent[0].body, &sent[1].body));
+    }
+
+    #[test]
+    fn test_dispatch_scan_events() {
+        use crate::scan_diff::{ScanDiffer, ScanMeta, ScanObservation};
+
+        let transport = Arc::new(RecordingTransport::default());
+        let mut dispatcher = dispatcher(transport.clone());
+        let mut differ = ScanDiffer::new(0.1, 10);
+        let t0 = Instant::now();
+        let meta = || ScanMeta {
+            scanned_at: chrono::Utc::now(),
+            duration_ms: 1.0,
+            trigger_type: "clock".to_string(),
+            trigger_source: "timer".to_string(),
+            scoped: false,
+        };
+        let observe = |roi: f64| vec![ScanObservation {
+            fingerprint: "a->b|fwd".to_string(),
+            signature: "a->b".to_string(),
+            roi_percent: roi,
+            pool_ids: vec!["a".to_string(), "b".to_string()],
+            roi_by_amount: Vec::new(),
+            hops: Vec::new(),
+        }];
+
+        for event in differ.observe(meta(), observe(0.8), |_| false).events {
+            dispatcher.dispatch_event(&event, Some(&opportunity("a->b", 0.8)), t0);
+        }
+        for event in differ.observe(meta(), observe(0.82), |_| false).events {
+            dispatcher.dispatch_event(&event, Some(&opportunity("a->b", 0.82)), t0);
+        }
+        for event in differ.observe(meta(), Vec::new(), |_| false).events {
+            dispatcher.dispatch_event(&event, None, t0);
+        }
+
+        // production：New + Gone（Persisting 不推送）
+        let prod = transport.for_sink("prod");
+        assert_eq!(prod.len(), 2);
+        assert!(prod[0].body.starts_with("🆕 NEW a->b"));
+        assert!(prod[1].body.starts_with("👋 GONE a->b (filtered)"));
+
+        // firehose：全部三个事件
+        let staging = transport.for_sink("staging");
+        assert_eq!(staging.len(), 3);
+        assert!(staging[1].body.starts_with("🔁 PERSISTING"));
+        assert!(staging[1].body.contains("[Raydium]"));
+    }
+}
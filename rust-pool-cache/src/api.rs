@@ -1,20 +1,60 @@
 use axum::{
     extract::State,
-    response::Json,
+    http::{header, StatusCode},
+    response::{IntoResponse, Json},
     routing::{get, post},
     Router,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
 
 use crate::arbitrage::{scan_for_arbitrage, ArbitrageOpportunity};
-use crate::error_tracker::ErrorTracker;
+use crate::error_tracker::{DegradedPoolType, ErrorSummary, ErrorTracker};
 use crate::price_cache::PriceCache;
+use crate::state_layer::{ExclusionCounts, ExclusionReason};
 use crate::opportunity_validator::{OpportunityValidator, ValidationResult};
 use crate::lst_arbitrage::LstArbitrageDetector;  // 🔥 LST套利
 
 use crate::onchain_simulator::OnChainSimulator;
+use crate::backpressure::{BackpressureMonitor, BackpressureStatus};
+use crate::config::PoolConfig;
+use crate::dex_interface::ActivityStatus;
+use crate::pool_factory::{OwnerCheck, PoolFactory, KNOWN_POOL_TYPES};
+use crate::pair_naming::{AutoPairName, PairNamer};
+use crate::pool_reload::{PoolReloader, ReloadSummary};
+use dashmap::DashMap;
+use crate::slo::{SloRow, SloTracker};
+use crate::sharding::ShardStatus;
+use crate::scan_diff::{LifecycleSnapshot, ScanDiffer, ScanRecord};
+use crate::synthetic::WhatIfReport;
+use crate::calibration::{CalibrationTable, Calibrator};
+use crate::quote::{geometric_ladder, DepthCurve, QuoteEngine, DEFAULT_LADDER_STEPS};
+use crate::token_graph::{pool_tokens, ExcludedPool, TokenGraph};
+use crate::discovery::DiscoveredPool;
+use crate::database::{DatabaseManager, OpportunityLifecycleRecord};
+use crate::metrics::MetricsCollector;
+use crate::pool_stats::{DegradedPool, DexWindowStats, HeadLag, PoolLifecycle, PoolStatsCollector, PoolWindowStats};
+use crate::coordinator::CoordinatorStats;
+use crate::prometheus::{MetricKind, PrometheusWriter};
+use crate::health::{self, ComponentHealth, HealthStatus, Heartbeats};
+use crate::circuit_breaker::Quarantine;
+use crate::stake_pool_reader::StakePoolReader;
+use crate::rpc_manager::RpcManager;
+use crate::notifications::NotificationMetrics;
+use crate::websocket::{WsCacheSizes, WsPoolAccounts};
+use crate::vault_reader::{VaultReader, VaultSnapshot};
+use crate::vault_audit::{self, PoolConsistency};
+use crate::pool_history::{HistoryResponse, PoolHistory};
+use crate::reference_price::{ReferencePriceChecker, ReferenceReport};
+use crate::focus::{focus_key, FocusStats};
+use crate::spread_heatmap::{PairSpreadMatrix, SpreadHeatmap};
+use crate::validation_rules::{RuleStats, RuleStatsSnapshot};
+use crate::scan_capture::{CaptureControl, CaptureStatus};
+use crate::liquidity::LiquidityUsd;
+use crate::account_subscription::Commitment;
+use crate::supervisor::Supervisor;
 
 /// API State shared across handlers
 #[derive(Clone)]
@@ -22,14 +62,53 @@ pub struct ApiState {
     pub price_cache: Arc<PriceCache>,
     pub error_tracker: Arc<ErrorTracker>,
     pub simulator: Option<Arc<OnChainSimulator>>,  // 🎯 链上模拟器（可选）
+    pub backpressure: Option<Arc<BackpressureMonitor>>,  // 🔥 反压监视器（可选）
+    pub pools: Arc<std::sync::RwLock<Vec<PoolConfig>>>,  // 配置的池子列表（热重载时更新）
+    pub reloader: Option<Arc<PoolReloader>>,  // ♻️ 池子列表热重载（可选）
+    pub pair_namer: Arc<PairNamer>,  // 🏷️ pair 为空的池子的自动命名结果（/pools 展示）
+    pub owner_checks: Arc<DashMap<String, OwnerCheck>>,  // 🔒 owner校验结果
+    pub slo: Option<Arc<std::sync::Mutex<SloTracker>>>,  // 📈 可用性SLO（可选）
+    pub sharding: Option<ShardStatus>,  // 🧩 分片分配（多实例部署）
+    pub opportunity_lifecycle: Arc<std::sync::Mutex<ScanDiffer>>,  // 🔄 机会生命周期 + 🗃️ 最近 N 次扫描结果与差异（/scans）
+    pub whatif: Option<Arc<std::sync::Mutex<WhatIfReport>>>,  // 🧪 what-if 扫描报告（可选）
+    pub calibration: Option<Arc<Calibrator>>,  // 🎯 验证器置信度校准（可选）
+    pub base_token: String,  // 💵 扫描计价代币（/graph 可达性检查的起点）
+    pub min_pool_liquidity_usd: f64,  // 💧 路由的最低池子流动性（/graph 按同一下限建图）
+    pub discovered_pools: Arc<HashMap<String, DiscoveredPool>>,  // 🔭 自动发现的池子（按地址）
+    pub database: Option<Arc<tokio::sync::Mutex<DatabaseManager>>>,  // 🗂️ 数据库（机会生命周期查询，可选）
+    pub metrics: Arc<MetricsCollector>,  // 📈 延迟 / 消息 / 扫描耗时（/metrics）
+    pub pool_stats: Arc<PoolStatsCollector>,  // 📈 池子级更新计数（/metrics）
+    pub coordinator_stats: Arc<tokio::sync::Mutex<CoordinatorStats>>,  // 📈 Coordinator 触发计数 / 生效阈值（/stats/coordinator、/metrics）
+    pub heartbeats: Heartbeats,  // 🩺 WebSocket / Coordinator / Calculator 心跳（/health）
+    pub stake_pool_reader: Option<Arc<StakePoolReader>>,  // 🩺 LST 检测开启时报告 stake pool 缓存年龄
+    pub rpc_manager: Arc<RpcManager>,  // 🛰️ 共享 RPC 的按调用方请求计数（/metrics）
+    pub notification_metrics: Arc<NotificationMetrics>,  // 📣 机会通知投递计数（/metrics）
+    pub ws_cache_sizes: WsCacheSizes,  // 📏 WebSocket 内部缓存大小（/metrics）
+    pub supervisor: Supervisor,  // 🛟 受监督任务的重启次数（/health、/metrics）
+    pub focus: Option<Arc<FocusStats>>,  // 🎯 重点交易对的评估 / 漏评估计数（/metrics，可选）
+    pub validation: Arc<RuleStats>,  // 🧱 验证规则的拒绝计数（/stats/validation、/metrics）
+    pub capture: Arc<CaptureControl>,  // 🧊 扫描输入抓取开关（POST /debug/capture）
+    pub vault_reader: Arc<VaultReader>,  // 🔍 vault 余额与所属池子（/vaults、一致性检查）
+    pub pool_accounts: WsPoolAccounts,  // 🔍 最近一次的池子账户数据（一致性检查）
+    pub history: Option<Arc<PoolHistory>>,  // 🕰️ 逐池价格历史（可选）
+    pub reference_prices: Option<Arc<ReferencePriceChecker>>,  // 🧭 外部参考价格校验（/reference_prices、/metrics，可选）
+    pub spread_heatmap: Option<Arc<SpreadHeatmap>>,  // 🌡️ 有序池子组合的滚动价差统计（/stats/spreads，可选）
 }
 
 /// Response for health check
 #[derive(Serialize)]
 pub struct HealthResponse {
-    status: String,
+    /// ok / degraded / down（取最差的组件）
+    status: HealthStatus,
+    components: Vec<ComponentHealth>,
     cached_pools: usize,
     cached_pairs: Vec<String>,
+    /// 🚨 反序列化失败速率超过阈值的 pool_type
+    degraded_pool_types: Vec<DegradedPoolType>,
+    /// 🧯 被熔断隔离的池子
+    quarantined_pools: Vec<Quarantine>,
+    /// 🔒 owner 不匹配被拒绝激活的池子
+    degraded_pools: Vec<DegradedPool>,
 }
 
 /// Response for price query
@@ -42,6 +121,12 @@ pub struct PriceResponse {
     base_reserve: u64,
     quote_reserve: u64,
     age_ms: u128,
+    /// 💾 仍是快照恢复的数据（尚未收到实时更新）
+    restored: bool,
+    /// 💧 估算的美元流动性（approximate = CLMM / CLOB 的尽力估计；没有美元价格时为 null）
+    liquidity_usd: Option<LiquidityUsd>,
+    /// 📡 产生该更新的订阅 commitment（非订阅来源为 null）
+    commitment: Option<Commitment>,
 }
 
 /// Response for arbitrage scan
@@ -82,6 +167,19 @@ impl From<ArbitrageOpportunity> for ArbitrageOpportunityDto {
     }
 }
 
+/// Response for GET /dexes
+#[derive(Serialize)]
+pub struct DexStatus {
+    dex: String,
+    enabled: bool,
+    /// 配置中映射到该 DEX 的池子数
+    configured_pools: usize,
+    /// 其中当前在价格缓存中的池子数
+    active_pools: usize,
+    /// 因 DEX 被禁用而跳过的次数
+    skipped_disabled: u64,
+}
+
 /// Request for arbitrage scan
 #[derive(Deserialize)]
 pub struct ScanRequest {
@@ -93,14 +191,480 @@ fn default_threshold() -> f64 {
     0.5 // Default 0.5% threshold
 }
 
-/// GET /health - Health check endpoint
-async fn health(State(state): State<ApiState>) -> Json<HealthResponse> {
+/// GET /health - 🩺 Readiness: overall status + per-component breakdown (503 when down)
+async fn health(State(state): State<ApiState>) -> (StatusCode, Json<HealthResponse>) {
+    let now_ms = health::now_ms();
     let (cached_pools, cached_pairs) = state.price_cache.get_stats();
+    let degraded_pool_types = state.error_tracker.degraded_pool_types().await;
+    
+    let mut components = state.heartbeats.evaluate(now_ms);
+    
+    // 配置的池子中价格新鲜（非快照恢复）的数量
+    let (configured, fresh) = {
+        let pools = state.pools.read().unwrap();
+        let fresh = pools
+            .iter()
+            .filter(|pool| {
+                !state.price_cache.is_restored(&pool.address)
+                    && state.price_cache.get_price(&pool.address).is_some_and(|price| {
+                        price.last_update.elapsed().as_millis() as u64 <= health::FRESH_PRICE_MS
+                    })
+            })
+            .count();
+        (pools.len(), fresh)
+    };
+    components.push(health::pools_health(configured, fresh));
+    
+    if let Some(db) = &state.database {
+        let reachable = tokio::time::timeout(std::time::Duration::from_secs(1), async {
+            db.lock().await.ping().await
+        })
+        .await
+        .unwrap_or(false);
+        components.push(if reachable {
+            ComponentHealth::new("database", HealthStatus::Ok, None, "reachable")
+        } else {
+            ComponentHealth::new("database", HealthStatus::Degraded, None, "unreachable")
+        });
+    }
+    
+    if let Some(reader) = &state.stake_pool_reader {
+        let (_, age) = reader.get_cache_info();
+        components.push(health::stake_pool_health(age, reader.cache_ttl().max(std::time::Duration::from_secs(1))));
+    }
+    
+    components.push(if degraded_pool_types.is_empty() {
+        ComponentHealth::new("deserializers", HealthStatus::Ok, None, "no degraded pool types")
+    } else {
+        let names: Vec<&str> = degraded_pool_types.iter().map(|d| d.pool_type.as_str()).collect();
+        ComponentHealth::new("deserializers", HealthStatus::Degraded, None, format!("degraded: {}", names.join(", ")))
+    });
     
-    Json(HealthResponse {
-        status: "ok".to_string(),
+    let quarantined_pools = state.price_cache.circuit_breaker().quarantined();
+    components.push(health::quarantine_health(&quarantined_pools));
+    let degraded_pools = state.pool_stats.degraded_pools();
+    components.push(health::degraded_pools_health(&degraded_pools));
+    components.extend(state.supervisor.health(std::time::Instant::now()));
+    
+    let status = health::overall_status(&components);
+    let code = if status == HealthStatus::Down { StatusCode::SERVICE_UNAVAILABLE } else { StatusCode::OK };
+    
+    (code, Json(HealthResponse {
+        status,
+        components,
         cached_pools,
         cached_pairs,
+        degraded_pool_types,
+        quarantined_pools,
+        degraded_pools,
+    }))
+}
+
+/// Response for status endpoint
+#[derive(Serialize)]
+pub struct StatusResponse {
+    cached_pools: usize,
+    backpressure: Option<BackpressureStatus>,
+    sharding: Option<ShardStatus>,
+}
+
+/// GET /status - Pipeline status (backpressure load, last scan threshold)
+async fn status(State(state): State<ApiState>) -> Json<StatusResponse> {
+    let (cached_pools, _) = state.price_cache.get_stats();
+    
+    Json(StatusResponse {
+        cached_pools,
+        backpressure: state.backpressure.as_ref().map(|m| m.status()),
+        sharding: state.sharding.clone(),
+    })
+}
+
+/// Response for pool debug endpoint
+#[derive(Serialize)]
+pub struct PoolDebugResponse {
+    name: String,
+    address: String,
+    pair: String,
+    pool_type: String,
+    status: String,
+    owner: Option<String>,
+    expected_owner: Option<String>,
+    reason: Option<String>,
+    suggested_type: Option<String>,
+    /// "static"（config.toml）或 "auto"（启动时自动发现）
+    source: String,
+    /// 自动发现时估算的流动性（美元）
+    liquidity_usd: Option<f64>,
+    /// 🪦 active / retired（账户已关闭或迁移，可从配置中删除）/ degraded（owner 与 pool_type 不符）
+    lifecycle: PoolLifecycle,
+    /// 🚦 最近一次解析出的活跃状态（尚未收到账户数据时为 None）
+    activity: Option<ActivityStatus>,
+    /// 🏷️ pair 留空时按链上 mint 生成的 pair / 名称 / mint（可抄回配置固定）
+    auto_named: Option<AutoPairName>,
+}
+
+/// Query for /pools
+#[derive(Deserialize)]
+pub struct PoolsQuery {
+    /// 只返回某个来源的池子（static / auto）
+    source: Option<String>,
+    /// 🪦 只返回某个生命周期状态的池子（active / retired / degraded）
+    lifecycle: Option<String>,
+}
+
+/// GET /pools - 🔒 Configured pools with owner verification status and 🪦 lifecycle state
+async fn get_pools(
+    State(state): State<ApiState>,
+    axum::extract::Query(query): axum::extract::Query<PoolsQuery>,
+) -> Json<Vec<PoolDebugResponse>> {
+    let response: Vec<PoolDebugResponse> = state.pools
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|pool| {
+            let source = if state.discovered_pools.contains_key(&pool.address) { "auto" } else { "static" };
+            query.source.as_deref().is_none_or(|wanted| wanted == source)
+        })
+        .filter(|pool| {
+            let lifecycle = state.pool_stats.lifecycle(&pool.name);
+            query.lifecycle.as_deref().is_none_or(|wanted| wanted == lifecycle.label())
+        })
+        .map(|pool| {
+            let discovered = state.discovered_pools.get(&pool.address);
+            let check = state.owner_checks.get(&pool.address).map(|c| c.value().clone());
+            let status = match &check {
+                Some(c) if c.verified => "verified",
+                Some(_) => "owner_mismatch",
+                None => "unchecked",
+            };
+            
+            PoolDebugResponse {
+                name: pool.name.clone(),
+                address: pool.address.clone(),
+                pair: pool.pair.clone(),
+                pool_type: pool.pool_type.clone(),
+                status: status.to_string(),
+                owner: check.as_ref().map(|c| c.owner.clone()),
+                expected_owner: PoolFactory::expected_owner(&pool.pool_type).map(|s| s.to_string()),
+                reason: check.as_ref().and_then(|c| c.reason()),
+                suggested_type: check.and_then(|c| c.suggested_type),
+                source: if discovered.is_some() { "auto" } else { "static" }.to_string(),
+                liquidity_usd: discovered.map(|d| d.liquidity_usd),
+                lifecycle: state.pool_stats.lifecycle(&pool.name),
+                activity: state.price_cache.activity_status(&pool.address),
+                auto_named: state.pair_namer.get(&pool.address),
+            }
+        })
+        .collect();
+    
+    Json(response)
+}
+
+/// GET /dexes - 🔌 Known deserializers with enable switch and pool counts
+async fn get_dexes(State(state): State<ApiState>) -> Json<Vec<DexStatus>> {
+    let pools = state.pools.read().unwrap();
+    let response = KNOWN_POOL_TYPES
+        .iter()
+        .map(|dex| {
+            let configured: Vec<&PoolConfig> = pools
+                .iter()
+                .filter(|pool| PoolFactory::canonical_pool_type(&pool.pool_type) == Some(*dex))
+                .collect();
+            DexStatus {
+                dex: dex.to_string(),
+                enabled: PoolFactory::is_dex_enabled(dex),
+                configured_pools: configured.len(),
+                active_pools: configured
+                    .iter()
+                    .filter(|pool| state.price_cache.get_price(&pool.address).is_some())
+                    .count(),
+                skipped_disabled: state.pool_stats.skipped_disabled(dex),
+            }
+        })
+        .collect();
+    
+    Json(response)
+}
+
+/// POST /reload - ♻️ Re-read config.toml and apply pool additions / removals / edits
+async fn reload_pools(
+    State(state): State<ApiState>,
+) -> Result<Json<ReloadSummary>, (StatusCode, String)> {
+    let reloader = state.reloader
+        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "Pool reload not enabled".to_string()))?;
+    reloader.reload()
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("{:#}", e)))
+}
+
+/// GET /slo - 📈 Per-component availability table (1h / 24h / 7d)
+async fn get_slo(State(state): State<ApiState>) -> Json<Vec<SloRow>> {
+    let rows = match &state.slo {
+        Some(tracker) => tracker.lock().unwrap().table(chrono::Utc::now()),
+        None => Vec::new(),
+    };
+    
+    Json(rows)
+}
+
+/// GET /opportunities - 🔄 Active opportunities and recently ended ones with lifecycle metadata
+async fn get_opportunities(State(state): State<ApiState>) -> Json<LifecycleSnapshot> {
+    Json(state.opportunity_lifecycle.lock().unwrap().snapshot())
+}
+
+/// GET /opportunities/:fingerprint - 🗂️ Persisted lifecycle history of one opportunity (path + direction)
+async fn get_opportunity_history(
+    axum::extract::Path(fingerprint): axum::extract::Path<String>,
+    State(state): State<ApiState>,
+) -> Result<Json<Vec<OpportunityLifecycleRecord>>, (StatusCode, String)> {
+    let db = state.database
+        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "Database not enabled".to_string()))?;
+    let history = db.lock().await
+        .get_opportunity_history(&fingerprint)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if history.is_empty() {
+        return Err((StatusCode::NOT_FOUND, format!("No lifecycle recorded for {}", fingerprint)));
+    }
+    Ok(Json(history))
+}
+
+/// GET /scans/latest - 🗃️ Most recent router scan with per-opportunity diff against the previous scan
+async fn get_latest_scan(State(state): State<ApiState>) -> Result<Json<ScanRecord>, (StatusCode, String)> {
+    state.opportunity_lifecycle.lock().unwrap()
+        .latest_scan()
+        .cloned()
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "No scans recorded yet".to_string()))
+}
+
+/// GET /scans/:id - 🗃️ One router scan from the in-memory history
+async fn get_scan(
+    axum::extract::Path(id): axum::extract::Path<u64>,
+    State(state): State<ApiState>,
+) -> Result<Json<ScanRecord>, (StatusCode, String)> {
+    state.opportunity_lifecycle.lock().unwrap()
+        .scan(id)
+        .cloned()
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, format!("Scan {} is not in the history", id)))
+}
+
+/// GET /whatif/opportunities - 🧪 Latest what-if scan (synthetic pools, never alerted)
+async fn get_whatif_opportunities(State(state): State<ApiState>) -> Json<WhatIfReport> {
+    let report = match &state.whatif {
+        Some(report) => report.lock().unwrap().clone(),
+        None => WhatIfReport::default(),
+    };
+    
+    Json(report)
+}
+
+/// Response for validator calibration
+#[derive(Serialize)]
+pub struct CalibrationResponse {
+    enabled: bool,
+    /// 缓冲区中的历史结果数（下次刷新时参与拟合）
+    history_samples: usize,
+    table: Option<CalibrationTable>,
+}
+
+/// GET /validator/calibration - 🎯 Confidence decile -> calibrated probability table
+async fn get_validator_calibration(State(state): State<ApiState>) -> Json<CalibrationResponse> {
+    let response = match &state.calibration {
+        Some(calibrator) => CalibrationResponse {
+            enabled: true,
+            history_samples: calibrator.history_len(),
+            table: Some(calibrator.table()),
+        },
+        None => CalibrationResponse {
+            enabled: false,
+            history_samples: 0,
+            table: None,
+        },
+    };
+    
+    Json(response)
+}
+
+/// Query for /quote
+#[derive(Deserialize)]
+pub struct QuoteQuery {
+    /// 输入代币（如 SOL）
+    from: String,
+    /// 输出代币（如 USDC）
+    to: String,
+    /// 请求金额（UI单位，curve=true 时作为阶梯中心）
+    amount: Option<f64>,
+    /// 逗号分隔的金额列表，如 "1,10,100"
+    amounts: Option<String>,
+    /// 按请求金额的 0.1× ~ 10× 几何阶梯报价
+    #[serde(default)]
+    curve: bool,
+}
+
+/// Response for /quote
+#[derive(Serialize)]
+pub struct QuoteResponse {
+    #[serde(flatten)]
+    curve: DepthCurve,
+    elapsed_us: u128,
+}
+
+/// GET /quote - 📐 Size-tiered quote with depth curve (one snapshot for all sizes)
+async fn get_quote(
+    State(state): State<ApiState>,
+    axum::extract::Query(query): axum::extract::Query<QuoteQuery>,
+) -> Json<QuoteResponse> {
+    let start = std::time::Instant::now();
+    let amount = query.amount.unwrap_or(1.0);
+    
+    let amounts: Vec<f64> = match &query.amounts {
+        Some(list) => list.split(',').filter_map(|a| a.trim().parse().ok()).collect(),
+        None if query.curve => geometric_ladder(amount, DEFAULT_LADDER_STEPS),
+        None => vec![amount],
+    };
+    
+    let snapshot = state.price_cache.get_all_prices();
+    let engine = QuoteEngine::from_snapshot(&snapshot);
+    let curve = engine.depth_curve(&query.from, &query.to, &amounts);
+    
+    Json(QuoteResponse {
+        curve,
+        elapsed_us: start.elapsed().as_micros(),
+    })
+}
+
+/// Query for /graph（与 Complete 扫描的 get_consistent_snapshot 参数一致）
+#[derive(Deserialize)]
+pub struct GraphQuery {
+    #[serde(default = "default_graph_max_age_ms")]
+    max_age_ms: u64,
+    #[serde(default = "default_graph_max_slot_spread")]
+    max_slot_spread: u64,
+    /// 可达性检查的起点（默认 [calculator] base_token）
+    base_token: Option<String>,
+}
+
+fn default_graph_max_age_ms() -> u64 {
+    2000
+}
+
+fn default_graph_max_slot_spread() -> u64 {
+    10
+}
+
+/// 图中的一条边（一个池子）
+#[derive(Serialize)]
+pub struct GraphEdgeDto {
+    pool_id: String,
+    dex_name: String,
+    pair: String,
+    base: String,
+    quote: String,
+    price: f64,
+    fee_rate: f64,
+    base_reserve: u64,
+    quote_reserve: u64,
+    age_ms: u128,
+    slot: u64,
+    /// 💧 估算的美元流动性（见 /prices）
+    liquidity_usd: Option<LiquidityUsd>,
+}
+
+/// Response for /graph
+#[derive(Serialize)]
+pub struct GraphResponse {
+    max_age_ms: u64,
+    max_slot_spread: u64,
+    latest_slot: u64,
+    base_token: String,
+    nodes: Vec<String>,
+    edges: Vec<GraphEdgeDto>,
+    connected_components: usize,
+    /// 各连通分量的代币（按大小降序）
+    components: Vec<Vec<String>>,
+    /// 从 base_token 出发不可达的代币
+    unreachable_from_base: Vec<String>,
+    /// 💧 [router] min_pool_liquidity_usd（0 = 不过滤）
+    min_pool_liquidity_usd: f64,
+    /// 未进图的池子：quarantined / stale / slot_spread / 非 Active 的活跃状态（快照过滤）或 zero_price / invalid_pair / same_token /
+    /// low_liquidity（建图过滤）
+    excluded_pools: Vec<ExcludedPool>,
+}
+
+/// GET /graph - 🕸️ Token graph the routers see (same snapshot filter and graph builder)
+async fn get_graph(
+    State(state): State<ApiState>,
+    axum::extract::Query(query): axum::extract::Query<GraphQuery>,
+) -> Json<GraphResponse> {
+    let latest_slot = state.price_cache.get_latest_slot();
+    let detailed = state.price_cache.get_consistent_snapshot_detailed(query.max_age_ms, query.max_slot_spread);
+    
+    // 快照过滤掉的池子
+    let mut excluded_pools: Vec<ExcludedPool> = detailed.excluded
+        .iter()
+        .map(|(pool_id, reason)| ExcludedPool {
+            pool_id: pool_id.clone(),
+            pair: state.price_cache.get_price(pool_id).map(|p| p.pair).unwrap_or_default(),
+            reason: match reason {
+                ExclusionReason::StaleByTime { .. } => "stale",
+                ExclusionReason::StaleBySlot { .. } => "slot_spread",
+                ExclusionReason::ZeroPrice => "zero_price",
+                ExclusionReason::Quarantined => "quarantined",
+                ExclusionReason::ReferenceDeviation => "reference_deviation",
+                ExclusionReason::Inactive { status } => status,
+            }
+            .to_string(),
+        })
+        .collect();
+    excluded_pools.sort_by(|a, b| a.pool_id.cmp(&b.pool_id));
+    
+    let snapshot = detailed.included;
+    let graph = TokenGraph::build_with_min_liquidity(&snapshot, state.min_pool_liquidity_usd);
+    excluded_pools.extend(graph.excluded.iter().cloned());
+    
+    let edges: Vec<GraphEdgeDto> = graph.pools.iter()
+        .map(|p| {
+            let (base, quote) = pool_tokens(p).unwrap_or_default();
+            GraphEdgeDto {
+                pool_id: p.pool_id.clone(),
+                dex_name: p.dex_name.clone(),
+                pair: p.pair.clone(),
+                base,
+                quote,
+                price: p.price,
+                fee_rate: crate::fee_registry::fee_rate(&p.pool_id, &p.dex_name),
+                base_reserve: p.base_reserve,
+                quote_reserve: p.quote_reserve,
+                age_ms: p.last_update.elapsed().as_millis(),
+                slot: p.slot,
+                liquidity_usd: p.liquidity_usd,
+            }
+        })
+        .collect();
+    
+    let base_token = query.base_token.unwrap_or_else(|| state.base_token.clone());
+    let reachable = graph.reachable_from(&base_token);
+    let unreachable_from_base = graph.tokens.iter()
+        .filter(|t| !reachable.contains(*t))
+        .cloned()
+        .collect();
+    let components = graph.connected_components();
+    
+    Json(GraphResponse {
+        max_age_ms: query.max_age_ms,
+        max_slot_spread: query.max_slot_spread,
+        latest_slot,
+        base_token,
+        nodes: graph.tokens.clone(),
+        edges,
+        connected_components: components.len(),
+        components,
+        unreachable_from_base,
+        min_pool_liquidity_usd: state.min_pool_liquidity_usd,
+        excluded_pools,
     })
 }
 
@@ -118,6 +682,9 @@ async fn get_all_prices(State(state): State<ApiState>) -> Json<Vec<PriceResponse
             base_reserve: p.base_reserve,
             quote_reserve: p.quote_reserve,
             age_ms: p.last_update.elapsed().as_millis(),
+            restored: state.price_cache.is_restored(&p.pool_id),
+            liquidity_usd: p.liquidity_usd,
+            commitment: p.commitment,
         })
         .collect();
     
@@ -141,12 +708,76 @@ async fn get_pair_prices(
             base_reserve: p.base_reserve,
             quote_reserve: p.quote_reserve,
             age_ms: p.last_update.elapsed().as_millis(),
+            restored: state.price_cache.is_restored(&p.pool_id),
+            liquidity_usd: p.liquidity_usd,
+            commitment: p.commitment,
         })
         .collect();
     
     Json(response)
 }
 
+/// GET /reference_prices - 🧭 Latest external reference prices and pools deviating from them
+async fn get_reference_prices(
+    State(state): State<ApiState>,
+) -> Result<Json<ReferenceReport>, (StatusCode, String)> {
+    let checker = state.reference_prices
+        .as_ref()
+        .ok_or((StatusCode::NOT_FOUND, "Reference price checks are disabled".to_string()))?;
+    Ok(Json(checker.report()))
+}
+
+/// GET /vaults - 🔍 Every registered vault with its balance, last update and owning pool(s)
+async fn get_vaults(State(state): State<ApiState>) -> Json<Vec<VaultSnapshot>> {
+    Json(state.vault_reader.snapshot())
+}
+
+/// GET /pools/:address/consistency - 🔍 Cached reserves vs vault balances vs last raw pool-account parse
+async fn get_pool_consistency(
+    axum::extract::Path(address): axum::extract::Path<String>,
+    State(state): State<ApiState>,
+) -> Result<Json<PoolConsistency>, (StatusCode, String)> {
+    let pool = state.price_cache.get_price(&address)
+        .ok_or((StatusCode::NOT_FOUND, format!("Pool {} is not in the cache", address)))?;
+    let raw_reserves = state.pool_accounts.raw_reserves(&address);
+    Ok(Json(vault_audit::check_pool(&pool, &state.vault_reader, raw_reserves)))
+}
+
+/// Query for /pools/:address/history
+#[derive(Deserialize)]
+pub struct HistoryQuery {
+    #[serde(default = "default_history_since_secs")]
+    since_secs: u64,
+    /// 样本数超过该值时降采样为 OHLC
+    #[serde(default = "default_history_max_points")]
+    max_points: usize,
+    /// 指定 K 线桶宽（秒），总是返回 OHLC
+    bucket_secs: Option<u64>,
+}
+
+fn default_history_since_secs() -> u64 {
+    3600
+}
+
+fn default_history_max_points() -> usize {
+    500
+}
+
+/// GET /pools/:address/history - 🕰️ In-memory price history of one pool (raw samples or OHLC buckets)
+async fn get_pool_history(
+    axum::extract::Path(address): axum::extract::Path<String>,
+    axum::extract::Query(query): axum::extract::Query<HistoryQuery>,
+    State(state): State<ApiState>,
+) -> Result<Json<HistoryResponse>, (StatusCode, String)> {
+    let history = state.history
+        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "History not enabled".to_string()))?;
+    let since_ms = chrono::Utc::now().timestamp_millis() - (query.since_secs * 1000) as i64;
+    let bucket_ms = query.bucket_secs.map(|secs| (secs * 1000) as i64);
+    history.query(&address, since_ms, query.max_points, bucket_ms)
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, format!("No history recorded for {}", address)))
+}
+
 /// POST /scan-arbitrage - Scan for arbitrage opportunities
 async fn scan_arbitrage(
     State(state): State<ApiState>,
@@ -166,10 +797,272 @@ async fn scan_arbitrage(
     })
 }
 
+/// Query for /snapshot（不带参数 = Complete 扫描使用的按池子类型策略快照）
+#[derive(Deserialize)]
+pub struct SnapshotQuery {
+    max_age_ms: Option<u64>,
+    #[serde(default = "default_graph_max_slot_spread")]
+    max_slot_spread: u64,
+}
+
+/// 被排除的池子及原因
+#[derive(Serialize)]
+pub struct SnapshotExclusionDto {
+    pool_id: String,
+    pair: String,
+    dex_name: String,
+    #[serde(flatten)]
+    reason: ExclusionReason,
+}
+
+/// Response for /snapshot
+#[derive(Serialize)]
+pub struct SnapshotResponse {
+    /// "policy"（按池子类型预算）或 "fixed"（使用查询参数）
+    mode: &'static str,
+    max_age_ms: Option<u64>,
+    max_slot_spread: Option<u64>,
+    latest_slot: u64,
+    included: usize,
+    excluded_counts: ExclusionCounts,
+    excluded: Vec<SnapshotExclusionDto>,
+}
+
+/// GET /snapshot - 🔍 Which pools the consistent snapshot dropped, and why
+async fn get_snapshot(
+    State(state): State<ApiState>,
+    axum::extract::Query(query): axum::extract::Query<SnapshotQuery>,
+) -> Json<SnapshotResponse> {
+    let (mode, max_slot_spread, result) = match query.max_age_ms {
+        Some(max_age_ms) => (
+            "fixed",
+            Some(query.max_slot_spread),
+            state.price_cache.get_consistent_snapshot_detailed(max_age_ms, query.max_slot_spread),
+        ),
+        None => ("policy", None, state.price_cache.get_policy_snapshot()),
+    };
+    
+    let mut excluded: Vec<SnapshotExclusionDto> = result.excluded
+        .iter()
+        .map(|(pool_id, reason)| {
+            let pool = state.price_cache.get_price(pool_id);
+            SnapshotExclusionDto {
+                pool_id: pool_id.clone(),
+                pair: pool.as_ref().map(|p| p.pair.clone()).unwrap_or_default(),
+                dex_name: pool.map(|p| p.dex_name).unwrap_or_default(),
+                reason: *reason,
+            }
+        })
+        .collect();
+    excluded.sort_by(|a, b| a.pool_id.cmp(&b.pool_id));
+    
+    Json(SnapshotResponse {
+        mode,
+        max_age_ms: query.max_age_ms,
+        max_slot_spread,
+        latest_slot: state.price_cache.get_latest_slot(),
+        included: result.included.len(),
+        excluded_counts: result.counts(),
+        excluded,
+    })
+}
+
+/// Query for /stats/pools 和 /stats/dex
+#[derive(Deserialize)]
+pub struct PoolActivityQuery {
+    #[serde(default = "default_stats_window_secs")]
+    window_secs: u64,
+    /// 只返回窗口内订阅最多的前 N 个池子（/stats/pools）
+    top: Option<usize>,
+}
+
+fn default_stats_window_secs() -> u64 {
+    3600
+}
+
+/// GET /stats/pools - 🔥 Per-pool activity within the window (sorted by subscriptions)
+async fn get_pool_activity(
+    State(state): State<ApiState>,
+    axum::extract::Query(query): axum::extract::Query<PoolActivityQuery>,
+) -> Json<Vec<PoolWindowStats>> {
+    let mut pools = state.pool_stats.window_stats(query.window_secs);
+    if let Some(top) = query.top {
+        pools.truncate(top);
+    }
+    Json(pools)
+}
+
+/// GET /stats/dex - 📊 Activity grouped by DEX within the window
+async fn get_dex_activity(
+    State(state): State<ApiState>,
+    axum::extract::Query(query): axum::extract::Query<PoolActivityQuery>,
+) -> Json<Vec<DexWindowStats>> {
+    Json(state.pool_stats.dex_stats(query.window_secs))
+}
+
+/// Query for /stats/slot_lag
+#[derive(Deserialize)]
+pub struct SlotLagQuery {
+    /// 只返回 p95 延迟超过该 slot 数的池子（默认全部）
+    #[serde(default)]
+    min_p95_slots: u64,
+}
+
+/// Response for /stats/slot_lag
+#[derive(Serialize)]
+pub struct SlotLagResponse {
+    /// 当前链头 slot（未启用 [chain_head] 或尚未获取时为 null）
+    head_slot: Option<u64>,
+    /// 价格缓存中的最大 slot
+    latest_cached_slot: u64,
+    pools: Vec<PoolSlotLag>,
+}
+
+#[derive(Serialize)]
+pub struct PoolSlotLag {
+    pool_name: String,
+    #[serde(flatten)]
+    lag: HeadLag,
+}
+
+/// GET /stats/slot_lag - ⛓️ Per-pool notification lag versus the chain head (sorted by p95)
+async fn get_slot_lag(
+    State(state): State<ApiState>,
+    axum::extract::Query(query): axum::extract::Query<SlotLagQuery>,
+) -> Json<SlotLagResponse> {
+    let pools = state.pool_stats
+        .head_lags()
+        .into_iter()
+        .filter(|(_, lag)| lag.p95 >= query.min_p95_slots)
+        .map(|(pool_name, lag)| PoolSlotLag { pool_name, lag })
+        .collect();
+    Json(SlotLagResponse {
+        head_slot: crate::chain_head::global().head_slot(),
+        latest_cached_slot: state.price_cache.get_latest_slot(),
+        pools,
+    })
+}
+
+/// GET /stats/validation - 🧱 Paths checked by the validation pipeline and rejections per rule
+async fn get_validation_stats(State(state): State<ApiState>) -> Json<RuleStatsSnapshot> {
+    Json(state.validation.snapshot())
+}
+
+/// GET /stats/coordinator - 🎯 Coordinator trigger counts and the currently effective event threshold
+async fn get_coordinator_stats(State(state): State<ApiState>) -> Json<CoordinatorStats> {
+    Json(state.coordinator_stats.lock().await.clone())
+}
+
+/// Query for /stats/spreads
+#[derive(Deserialize)]
+pub struct SpreadsQuery {
+    /// 只返回该交易对（如 SOL/USDC，按代币别名归一）；省略时返回全部
+    pair: Option<String>,
+}
+
+/// GET /stats/spreads - 🌡️ Rolling spread statistics for every ordered pool combination of a pair
+async fn get_spread_heatmap(
+    State(state): State<ApiState>,
+    axum::extract::Query(query): axum::extract::Query<SpreadsQuery>,
+) -> Result<Json<Vec<PairSpreadMatrix>>, (StatusCode, String)> {
+    let heatmap = state.spread_heatmap
+        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "Spread heatmap not enabled".to_string()))?;
+    let now = std::time::Instant::now();
+    match query.pair {
+        Some(pair) => {
+            let key = focus_key(&pair);
+            heatmap.matrix(&key, now)
+                .map(|matrix| Json(vec![matrix]))
+                .ok_or((StatusCode::NOT_FOUND, format!("No spread samples for {}", pair)))
+        }
+        None => Ok(Json(heatmap.pairs().iter().filter_map(|pair| heatmap.matrix(pair, now)).collect())),
+    }
+}
+
+/// Query for /debug/capture
+#[derive(Deserialize)]
+pub struct CaptureQuery {
+    /// 抓取接下来的扫描次数（默认 1）
+    #[serde(default = "default_capture_scans")]
+    scans: u64,
+}
+
+fn default_capture_scans() -> u64 {
+    1
+}
+
+/// POST /debug/capture - 🧊 Dump the inputs of the next N scans to JSON files for offline replay
+async fn capture_scans(
+    State(state): State<ApiState>,
+    axum::extract::Query(query): axum::extract::Query<CaptureQuery>,
+) -> Json<CaptureStatus> {
+    state.capture.arm(query.scans);
+    Json(state.capture.status())
+}
+
+/// GET /metrics - 📈 Prometheus text exposition (pool_cache_* metrics)
+async fn get_metrics(State(state): State<ApiState>) -> impl IntoResponse {
+    let mut writer = PrometheusWriter::new();
+    state.metrics.write_prometheus(&mut writer);
+    state.coordinator_stats.lock().await.write_prometheus(&mut writer);
+    state.pool_stats.write_prometheus(&mut writer);
+    state.rpc_manager.write_prometheus(&mut writer);
+    state.notification_metrics.write_prometheus(&mut writer);
+    
+    // 📏 有上限的内部缓存（长时间运行应保持平稳）
+    let mut cache_sizes = state.ws_cache_sizes.sizes();
+    cache_sizes.push(state.error_tracker.cache_size().await);
+    writer.cache_sizes(&cache_sizes);
+    state.supervisor.write_prometheus(&mut writer);
+    if let Some(focus) = &state.focus {
+        focus.write_prometheus(&mut writer);
+    }
+    state.validation.write_prometheus(&mut writer);
+    if let Some(reference_prices) = &state.reference_prices {
+        reference_prices.write_prometheus(&mut writer);
+    }
+    
+    // 新鲜度按池子类型策略判断（与 Complete 扫描一致）
+    let snapshot = state.price_cache.get_policy_snapshot();
+    let excluded = snapshot.counts();
+    let (total, _) = state.price_cache.get_stats();
+    writer.family("pool_cache_pools_total", "Pools in the price cache", MetricKind::Gauge);
+    writer.sample("pool_cache_pools_total", &[], total as f64);
+    writer.family(
+        "pool_cache_pools_fresh",
+        "Pools within their per-type staleness budget",
+        MetricKind::Gauge,
+    );
+    writer.sample("pool_cache_pools_fresh", &[], snapshot.included.len() as f64);
+    writer.family("pool_cache_pools_stale", "Pools excluded as stale, by reason", MetricKind::Gauge);
+    writer.sample("pool_cache_pools_stale", &[("reason", "time")], excluded.stale_by_time as f64);
+    writer.sample("pool_cache_pools_stale", &[("reason", "slot")], excluded.stale_by_slot as f64);
+    writer.sample("pool_cache_pools_stale", &[("reason", "zero_price")], excluded.zero_price as f64);
+    writer.family(
+        "pool_cache_pools_quarantined",
+        "Pools excluded by the circuit breaker",
+        MetricKind::Gauge,
+    );
+    writer.sample("pool_cache_pools_quarantined", &[], excluded.quarantined as f64);
+    writer.family(
+        "pool_cache_pools_inactive",
+        "Pools excluded because their activity status is not active",
+        MetricKind::Gauge,
+    );
+    writer.sample("pool_cache_pools_inactive", &[], excluded.inactive as f64);
+    writer.family(
+        "pool_cache_circuit_breaker_trips_total",
+        "Circuit breaker trips since startup",
+        MetricKind::Counter,
+    );
+    writer.sample("pool_cache_circuit_breaker_trips_total", &[], state.price_cache.circuit_breaker().total_trips() as f64);
+    
+    ([(header::CONTENT_TYPE, crate::prometheus::CONTENT_TYPE)], writer.finish())
+}
+
 /// GET /errors - Get error statistics
-async fn get_errors(State(state): State<ApiState>) -> Json<serde_json::Value> {
-    let report = state.error_tracker.get_error_report().await;
-    Json(serde_json::to_value(report).unwrap_or_default())
+async fn get_errors(State(state): State<ApiState>) -> Json<Vec<ErrorSummary>> {
+    Json(state.error_tracker.summaries().await)
 }
 
 /// 🎯 数据质量统计端点 - 用于监控数据一致性
@@ -233,6 +1126,8 @@ pub struct ValidatedOpportunityDto {
     #[serde(flatten)]
     opportunity: ArbitrageOpportunityDto,
     confidence_score: f64,
+    calibrated_probability: f64,
+    expected_value_pct: f64,
     average_age_ms: u64,
     slot_spread: u64,
 }
@@ -258,7 +1153,11 @@ async fn scan_validated(
     let opportunities = scan_for_arbitrage(&state.price_cache, threshold_pct);
     
     // 🎯 阶段2：轻量级验证（数据质量检查）
-    let validator = OpportunityValidator::with_defaults(state.price_cache.clone());
+    let validator = match &state.calibration {
+        Some(calibrator) => OpportunityValidator::with_defaults(state.price_cache.clone())
+            .with_calibrator(calibrator.clone()),
+        None => OpportunityValidator::with_defaults(state.price_cache.clone()),
+    };
     let (valid_opps, _invalid_opps, stats) = validator.validate_batch(opportunities, amount);
     
     // 🎯 阶段3：链上模拟验证（可选，仅高置信度机会）
@@ -289,15 +1188,18 @@ async fn scan_validated(
         .into_iter()
         .map(|(opp, confidence)| {
             // 获取数据质量详情
-            let (age, slot_spread) = if let ValidationResult::Valid { data_quality, .. } = validator.validate(&opp, amount) {
-                (data_quality.average_age_ms, data_quality.slot_spread)
+            let (age, slot_spread, probability) = if let ValidationResult::Valid { data_quality, calibrated_probability, .. } = validator.validate(&opp, amount) {
+                (data_quality.average_age_ms, data_quality.slot_spread, calibrated_probability)
             } else {
-                (0, 0)
+                (0, 0, validator.calibrated_probability(confidence))
             };
+            let expected_value_pct = probability * opp.estimated_profit_pct;
             
             ValidatedOpportunityDto {
                 opportunity: opp.into(),
                 confidence_score: confidence,
+                calibrated_probability: probability,
+                expected_value_pct,
                 average_age_ms: age,
                 slot_spread,
             }
@@ -381,17 +1283,7 @@ struct LstOpportunityDto {
 }
 
 /// Create the API router
-pub fn create_router(
-    price_cache: Arc<PriceCache>, 
-    error_tracker: Arc<ErrorTracker>,
-    simulator: Option<Arc<OnChainSimulator>>,
-) -> Router {
-    let state = ApiState { 
-        price_cache,
-        error_tracker,
-        simulator,
-    };
-    
+pub fn create_router(state: ApiState) -> Router {
     // Configure CORS
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -400,6 +1292,24 @@ pub fn create_router(
     
     Router::new()
         .route("/health", get(health))
+        .route("/status", get(status))
+        .route("/pools", get(get_pools))
+        .route("/pools/:address/consistency", get(get_pool_consistency))
+        .route("/pools/:address/history", get(get_pool_history))
+        .route("/vaults", get(get_vaults))
+        .route("/reference_prices", get(get_reference_prices))
+        .route("/dexes", get(get_dexes))
+        .route("/reload", post(reload_pools))
+        .route("/slo", get(get_slo))
+        .route("/opportunities", get(get_opportunities))
+        .route("/opportunities/:fingerprint", get(get_opportunity_history))
+        .route("/scans/latest", get(get_latest_scan))
+        .route("/scans/:id", get(get_scan))
+        .route("/whatif/opportunities", get(get_whatif_opportunities))
+        .route("/validator/calibration", get(get_validator_calibration))
+        .route("/quote", get(get_quote))
+        .route("/graph", get(get_graph))
+        .route("/snapshot", get(get_snapshot))
         .route("/prices", get(get_all_prices))
         .route("/prices/:pair", get(get_pair_prices))
         .route("/scan-arbitrage", post(scan_arbitrage))
@@ -407,30 +1317,51 @@ pub fn create_router(
         .route("/lst-opportunities", get(scan_lst_opportunities))  // 🔥 LST折价机会
         .route("/errors", get(get_errors))
         .route("/data-quality", get(get_data_quality))
+        .route("/stats/pools", get(get_pool_activity))
+        .route("/stats/dex", get(get_dex_activity))
+        .route("/stats/slot_lag", get(get_slot_lag))
+        .route("/stats/validation", get(get_validation_stats))
+        .route("/stats/coordinator", get(get_coordinator_stats))
+        .route("/stats/spreads", get(get_spread_heatmap))
+        .route("/debug/capture", post(capture_scans))
+        .route("/metrics", get(get_metrics))
         .layer(cors)
         .with_state(state)
 }
 
 /// Start the API server
-pub async fn start_api_server(
-    price_cache: Arc<PriceCache>,
-    error_tracker: Arc<ErrorTracker>,
-    simulator: Option<Arc<OnChainSimulator>>,
-    port: u16,
-) -> anyhow::Result<()> {
-    let app = create_router(price_cache, error_tracker, simulator);
+pub async fn start_api_server(state: ApiState, port: u16) -> anyhow::Result<()> {
+    let app = create_router(state);
     
     let addr = std::net::SocketAddr::from(([0, 0, 0, 0], port));
     println!("🌐 HTTP API server listening on http://0.0.0.0:{}", port);
     println!("   Endpoints:");
     println!("     GET  /health");
+    println!("     GET  /status               🔥 Backpressure / scan status");
+    println!("     GET  /pools                🔒 Pool owner verification (?source=static|auto&lifecycle=active|retired|degraded)");
+    println!("     POST /reload               ♻️  Hot-reload pool list from config");
+    println!("     GET  /slo                  📈 Availability SLO table");
+    println!("     GET  /opportunities        🔄 Opportunity lifecycle");
+    println!("     GET  /opportunities/:fingerprint 🗂️  Persisted lifecycle history");
+    println!("     GET  /whatif/opportunities 🧪 What-if scan (synthetic pools)");
+    println!("     GET  /validator/calibration 🎯 Confidence calibration table");
+    println!("     GET  /quote                📐 Size-tiered quote (?from=&to=&amount=&curve=true)");
+    println!("     GET  /graph                🕸️  Token graph (?max_age_ms=&max_slot_spread=&base_token=)");
+    println!("     GET  /snapshot             🔍 Snapshot exclusions by reason (?max_age_ms=&max_slot_spread=)");
     println!("     GET  /prices");
     println!("     GET  /prices/:pair");
+    println!("     GET  /reference_prices     🧭 External reference prices and deviating pools");
     println!("     POST /scan-arbitrage       (Legacy)");
     println!("     POST /scan-validated       🎯 Recommended: With validation");
     println!("     GET  /lst-opportunities    🔥 LST discount arbitrage");
     println!("     GET  /errors");
     println!("     GET  /data-quality         📊 Data consistency stats");
+    println!("     GET  /stats/pools          🔥 Pool activity (?window_secs=3600&top=20)");
+    println!("     GET  /stats/dex            📊 Activity by DEX (?window_secs=3600)");
+    println!("     GET  /stats/slot_lag       ⛓️  Notification lag vs chain head (?min_p95_slots=)");
+    println!("     GET  /stats/validation     🧱 Validation rejections per rule");
+    println!("     POST /debug/capture        🧊 Capture next scan inputs for replay (?scans=1)");
+    println!("     GET  /metrics              📈 Prometheus metrics (pool_cache_*)");
     
     let listener = tokio::net::TcpListener::bind(addr).await?;
     axum::serve(listener, app).await?;
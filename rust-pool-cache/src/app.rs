@@ -0,0 +1,1890 @@
+/*!
+ * 🧩 Application：进程装配（main.rs 与嵌入方共用）
+ *
+ * `Application::build` 按配置完成全部接线并启动后台任务：池子发现 / 分片 / RPC 初始化 / 数据库 /
+ * WebSocket 连接 / Coordinator -> Calculator 管线 / HTTP API / Phoenix 刷新 / 指标输出。
+ * `Application::run` 监督这些任务，`shutdown` 被取消或任一关键任务退出后有序关闭：
+ * 退订 -> 管线 -> 快照 -> 数据库 flush，其余周期任务直接中止。
+ *
+ * 启动与关闭摘要不直接打印，收集到 `StartupReport` / `ShutdownReport`（实现 Display），由调用方渲染。
+ *
+ * ```no_run
+ * # async fn example() -> anyhow::Result<()> {
+ * use solana_pool_cache::{config::Config, Application};
+ * use tokio_util::sync::CancellationToken;
+ *
+ * let app = Application::build(Config::load_from_file("config.toml")?).await?;
+ * println!("{}", app.startup_report());
+ * let report = app.run(CancellationToken::new()).await?;
+ * println!("{}", report);
+ * # Ok(())
+ * # }
+ * ```
+ */
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::Result;
+use dashmap::DashMap;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::{self, JoinHandle};
+use tokio::time::{interval, sleep, Duration};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::circuit_breaker::CircuitBreaker;
+use crate::config::{priority_order, Config, PoolConfig, SimulationConfig};
+use crate::database::{DatabaseConfig, DatabaseManager};
+use crate::error_tracker::ErrorTracker;
+use crate::lst_registry::LstRegistry;
+use crate::metrics::MetricsCollector;
+use crate::mint_decimals_cache::{self, init_global_mint_cache};
+use crate::opportunity_merger::OpportunityMerger;
+use crate::pool_factory::{DexFilter, OwnerCheck, PoolFactory};
+use crate::pool_stats::PoolStatsCollector;
+use crate::price_cache::{self, PoolPrice, PriceCache};
+use crate::router_advanced::{AdvancedRouter, AdvancedRouterConfig};
+use crate::stake_pool_reader::StakePoolReader;
+use crate::websocket::WebSocketClient;
+use crate::{
+    alerts, api, backpressure, calculator_loop, calibration, chain_head, coordinator, discovery, endpoint_pool, execution_cost,
+    fee_registry, focus, health, inventory, liquidity, notifications, onchain_simulator, opportunity_output,
+    opportunity_validator, orderbook_cache, pair_naming, pipeline, pool_history, pool_initializer, pool_mints, pool_reload, pool_update_log,
+    price_oracle, price_snapshot, proxy, reconnect_backoff, reference_price, router_direct, rpc_manager,
+    scan_capture, scan_diff, sharding, slo, spread_heatmap, spread_monitor, supervisor, synthetic, token_alias, vault_audit,
+};
+
+/// 默认 HTTP API 端口
+pub const DEFAULT_API_PORT: u16 = 3001;
+/// 关闭时等待在途工作（退订 / 最后一次扫描 / 快照 / 数据库 flush）的上限
+const SHUTDOWN_DEADLINE: Duration = Duration::from_secs(5);
+
+/// 构建选项（配置之外、由调用方决定的部分）
+#[derive(Debug, Clone)]
+pub struct ApplicationOptions {
+    /// POST /reload 重新读取的配置文件（None 时不启用热重载）
+    pub config_path: Option<String>,
+    /// HTTP API 端口（0 = 由系统分配）
+    pub api_port: u16,
+}
+
+impl Default for ApplicationOptions {
+    fn default() -> Self {
+        Self {
+            config_path: None,
+            api_port: DEFAULT_API_PORT,
+        }
+    }
+}
+
+/// RPC 批量初始化结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InitializationReport {
+    NotConfigured,
+    Disabled,
+    NoRpcUrls,
+    /// 批量查询失败，只靠 WebSocket 激活池子
+    Failed(String),
+    Completed {
+        activated: usize,
+        queried: usize,
+        /// 等 WebSocket 连接后再订阅 vault 的池子数
+        pending_vaults: usize,
+    },
+}
+
+/// 数据库连接结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DatabaseReport {
+    NotConfigured,
+    Disabled,
+    Connected,
+    /// 连接失败，继续运行但不记录
+    Failed(String),
+}
+
+/// LST 检测器的 stake pool 读取器状态
+#[derive(Debug, Clone)]
+pub struct LstReport {
+    pub rpc_url: String,
+    pub cache_ttl_secs: u64,
+    /// (符号, 理论兑换比率)，初始化拉取失败时为 None
+    pub rates: Vec<(String, Option<f64>)>,
+    pub epoch_ends_in: Option<Duration>,
+}
+
+/// 启动摘要（原 main.rs 中直接打印的内容）
+#[derive(Debug, Clone)]
+pub struct StartupReport {
+    pub monitored_pools: usize,
+    pub discovered_pools: usize,
+    pub initialization: InitializationReport,
+    pub database: DatabaseReport,
+    pub phoenix_pools: usize,
+    /// None = LST 检测器未启用或读取器创建失败
+    pub lst: Option<LstReport>,
+    pub websocket_url: String,
+    pub api_port: u16,
+}
+
+impl fmt::Display for StartupReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "📡 Monitoring {} pools ({} discovered)", self.monitored_pools, self.discovered_pools)?;
+        match &self.initialization {
+            InitializationReport::NotConfigured => writeln!(f, "ℹ️  Pool initialization not configured")?,
+            InitializationReport::Disabled => writeln!(f, "ℹ️  Pool initialization disabled in config")?,
+            InitializationReport::NoRpcUrls => writeln!(f, "⚠️  Pool initialization enabled but no RPC URLs configured")?,
+            InitializationReport::Failed(e) => writeln!(f, "⚠️  Pool initialization failed: {}", e)?,
+            InitializationReport::Completed { activated, queried, pending_vaults } => {
+                writeln!(f, "✅ Initialized {}/{} pools successfully", activated, queried)?;
+                if *pending_vaults > 0 {
+                    writeln!(f, "📌 {} pools need vault subscription", pending_vaults)?;
+                }
+            }
+        }
+        match &self.database {
+            DatabaseReport::NotConfigured => writeln!(f, "🗄️  Database: not configured")?,
+            DatabaseReport::Disabled => writeln!(f, "🗄️  Database: disabled")?,
+            DatabaseReport::Connected => writeln!(f, "🗄️  Database: connected")?,
+            DatabaseReport::Failed(e) => writeln!(f, "⚠️  Database initialization failed: {} (continuing without recording)", e)?,
+        }
+        if self.phoenix_pools > 0 {
+            writeln!(f, "🛰️  Phoenix price refresher: {} pools", self.phoenix_pools)?;
+        }
+        match &self.lst {
+            Some(lst) => {
+                writeln!(f, "🔥 Stake pool reader: {} (cache TTL {}s)", lst.rpc_url, lst.cache_ttl_secs)?;
+                for (symbol, rate) in &lst.rates {
+                    match rate {
+                        Some(rate) => writeln!(f, "      {} rate: {:.6}", symbol, rate)?,
+                        None => writeln!(f, "      {} rate: unavailable", symbol)?,
+                    }
+                }
+                if let Some(time_to_end) = lst.epoch_ends_in {
+                    writeln!(f, "   Epoch ends in: {}s", time_to_end.as_secs())?;
+                }
+            }
+            None => writeln!(f, "ℹ️  LST detector off")?,
+        }
+        writeln!(f, "🔌 WebSocket: {}", self.websocket_url)?;
+        writeln!(f, "🌐 HTTP API: http://0.0.0.0:{}", self.api_port)?;
+        write!(f, "✅ All tasks started successfully!")
+    }
+}
+
+/// 触发关闭的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownTrigger {
+    /// 调用方取消了 shutdown token（Ctrl+C 等）
+    Requested,
+    WebSocketExited,
+    MetricsExited,
+    ArbitrageExited,
+    ApiExited,
+    PhoenixRefresherExited,
+}
+
+impl ShutdownTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShutdownTrigger::Requested => "shutdown requested",
+            ShutdownTrigger::WebSocketExited => "WebSocket task terminated",
+            ShutdownTrigger::MetricsExited => "Metrics task terminated",
+            ShutdownTrigger::ArbitrageExited => "Arbitrage scanner terminated",
+            ShutdownTrigger::ApiExited => "API server terminated",
+            ShutdownTrigger::PhoenixRefresherExited => "Phoenix refresher terminated",
+        }
+    }
+}
+
+/// 关闭摘要
+#[derive(Debug, Clone)]
+pub struct ShutdownReport {
+    pub trigger: ShutdownTrigger,
+    pub unsubscribed_accounts: usize,
+    pub flushed_records: u64,
+    pub cached_pools: usize,
+    pub unique_pairs: Vec<String>,
+}
+
+impl fmt::Display for ShutdownReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "🛑 Shutdown ({})", self.trigger.as_str())?;
+        writeln!(f, "🛑 Unsubscribed {} accounts, flushed {} records", self.unsubscribed_accounts, self.flushed_records)?;
+        writeln!(f, "💾 Cache Statistics:")?;
+        writeln!(f, "   Cached pools: {}", self.cached_pools)?;
+        write!(f, "   Unique pairs: {:?}", self.unique_pairs)
+    }
+}
+
+/// Application 持有的全部任务句柄
+struct AppTasks {
+    websocket: JoinHandle<usize>,
+    pipeline: pipeline::PipelineHandles,
+    api: JoinHandle<()>,
+    metrics: JoinHandle<()>,
+    arbitrage: JoinHandle<()>,
+    phoenix_refresher: Option<JoinHandle<()>>,
+    snapshot: Option<JoinHandle<()>>,
+    spread_monitor: Option<JoinHandle<()>>,
+    /// 写数据库的后台任务（关闭时等待其完成最后一次写入）
+    db_tasks: Vec<JoinHandle<()>>,
+    /// 无需等待的周期任务（epoch 监视 / LST 刷新 / what-if），关闭时中止
+    background: Vec<JoinHandle<()>>,
+}
+
+/// 装配好的进程（全部任务已启动）
+pub struct Application {
+    report: StartupReport,
+    price_cache: Arc<PriceCache>,
+    metrics: Arc<MetricsCollector>,
+    pool_stats: Arc<PoolStatsCollector>,
+    db_manager: Option<Arc<tokio::sync::Mutex<DatabaseManager>>>,
+    shutdown_tx: broadcast::Sender<()>,
+    tasks: AppTasks,
+}
+
+// Epoch watcher: Token-2022 转账手续费按 epoch 生效，epoch 切换后重新拉取带手续费的 mint
+async fn mint_epoch_worker(mint_cache: Arc<mint_decimals_cache::MintInfoCache>) {
+    const EPOCH_POLL_INTERVAL_SECS: u64 = 60;
+
+    loop {
+        match mint_cache.rpc().call(|client| client.get_epoch_info().map_err(Box::new)).await {
+            Ok(epoch_info) => {
+                let stale_mints = mint_cache.observe_epoch(epoch_info.epoch);
+                if !stale_mints.is_empty() {
+                    info!("🪙 Epoch {}: refreshing {} transfer-fee mints", epoch_info.epoch, stale_mints.len());
+                }
+                for mint in stale_mints {
+                    let cache = mint_cache.clone();
+                    match task::spawn_blocking(move || cache.refresh(&mint)).await {
+                        Ok(Ok(_)) => {}
+                        Ok(Err(e)) => warn!("Failed to refresh mint {}: {}", mint, e),
+                        Err(e) => warn!("Mint refresh task join error: {}", e),
+                    }
+                }
+            }
+            Err(e) => warn!("Failed to fetch epoch info: {}", e),
+        }
+
+        sleep(Duration::from_secs(EPOCH_POLL_INTERVAL_SECS)).await;
+    }
+}
+
+/// 模拟器的 RPC：`[simulation] rpc_url` 单独配置时使用独立端点（仍共用令牌桶），否则使用共享端点
+fn simulation_rpc(shared: &Arc<rpc_manager::RpcManager>, sim_config: &SimulationConfig) -> rpc_manager::RpcHandle {
+    match &sim_config.rpc_url {
+        Some(url) => Arc::new(shared.derive(vec![url.clone()], rpc_manager::DEFAULT_TIMEOUT)).handle("simulator"),
+        None => shared.handle("simulator"),
+    }
+}
+
+// Phoenix pool refresh worker (moved outside main function)
+async fn phoenix_refresh_worker(
+    pools: Vec<PoolConfig>,
+    rpc: rpc_manager::RpcHandle,
+    price_cache: Arc<PriceCache>,
+) {
+    const STALE_THRESHOLD_MS: u64 = 3000;
+    const MIN_REFRESH_INTERVAL_SECS: u64 = 5;
+    const FULL_REFRESH_TICKS: u64 = 6; // 6 * 5s ≈ 30s (legacy cadence)
+
+    let mut tick_counter: u64 = 0;
+
+    loop {
+        tick_counter = tick_counter.wrapping_add(1);
+        let force_refresh = tick_counter % FULL_REFRESH_TICKS == 0;
+
+        for pool in &pools {
+            if !pool.pool_type.to_lowercase().contains("phoenix") {
+                continue;
+            }
+
+            let is_stale = price_cache.is_price_stale(&pool.address, STALE_THRESHOLD_MS);
+            if !is_stale && !force_refresh {
+                continue;
+            }
+
+            let pubkey = match Pubkey::from_str(&pool.address) {
+                Ok(key) => key,
+                Err(e) => {
+                    warn!("Invalid Phoenix pubkey {}: {}", pool.address, e);
+                    continue;
+                }
+            };
+
+            // 🛰️ 共享 RPC：端点故障转移和限速由 RpcManager 负责
+            match rpc.call(move |client| client.get_account_with_commitment(&pubkey, CommitmentConfig::confirmed()).map_err(Box::new)).await {
+                Ok(response) => {
+                    if let Some(account) = response.value {
+                        let owner = account.owner.to_string();
+                        match PoolFactory::create_pool_for(&pool.pool_type, Some(&owner), &account.data) {
+                            Ok(pool_state) => {
+                                let price = pool_state.calculate_price();
+                                if price == 0.0 {
+                                    continue;
+                                }
+                                let (base_reserve, quote_reserve) = pool_state.get_reserves();
+                                let (base_decimals, quote_decimals) = pool_state.get_decimals();
+                                let pool_price = PoolPrice {
+                                    pool_id: pool.address.clone(),
+                                    dex_name: pool_state.dex_name().to_string(),
+                                    pair: pool.pair.clone(),  // 🔥 FIX: 使用 pair 而不是 name
+                                    price,
+                                    base_reserve: base_reserve as u64,
+                                    quote_reserve: quote_reserve as u64,
+                                    base_decimals,
+                                    quote_decimals,
+                                    last_update: std::time::Instant::now(),
+                                    slot: response.context.slot,
+                                    liquidity_usd: None,
+                                    commitment: None,
+                                };
+                                price_cache.update_price(pool_price);
+                                orderbook_cache::register(&pool.address, pool_state);
+                            }
+                            Err(e) => {
+                                warn!("Failed to parse Phoenix pool {}: {}", pool.address, e);
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("RPC error for Phoenix pool {}: {}", pool.address, e);
+                }
+            }
+        }
+
+        sleep(Duration::from_secs(MIN_REFRESH_INTERVAL_SECS)).await;
+    }
+}
+
+
+impl Application {
+    /// 按默认选项构建（API 端口 3001，不启用配置热重载）
+    pub async fn build(config: Config) -> Result<Self> {
+        Self::build_with_options(config, ApplicationOptions::default()).await
+    }
+
+    /// 完成接线并启动全部后台任务；WebSocket 所有端点都连接失败时返回错误
+    pub async fn build_with_options(mut config: Config, options: ApplicationOptions) -> Result<Self> {
+        // 🔌 DEX 启用开关：禁用的 DEX 在 PoolFactory 中直接拒绝（DexDisabled）
+        let dex_filter = DexFilter::from_config(&config.dexes.clone().unwrap_or_default());
+        let disabled_dexes = dex_filter.disabled_dexes();
+        if !disabled_dexes.is_empty() {
+            info!("🔌 Disabled DEXes: {}", disabled_dexes.join(", "));
+        }
+        PoolFactory::set_dex_filter(dex_filter);
+        
+        // 🔀 多端点故障转移：WebSocket 订阅、vault 主动查询、Phoenix 刷新共用活跃端点
+        let ws_endpoints = Arc::new(endpoint_pool::EndpointPool::new(config.websocket_urls().to_vec()));
+        if ws_endpoints.endpoint_count() > 1 {
+            info!("🔀 {} WebSocket endpoints configured for failover", ws_endpoints.endpoint_count());
+        }
+        
+        // 🛰️ 共享 HTTP RPC：池子初始化、vault 预取、Phoenix 刷新、Stake Pool、mint 元数据和模拟
+        // 共用端点故障转移和同一个令牌桶（避免 429）
+        let rpc_manager = Arc::new(rpc_manager::RpcManager::from_config(&config));
+        
+        // 🔭 池子自动发现：与静态配置合并后走正常的分片 / 初始化 / 订阅流程
+        let discovered_pools: Vec<discovery::DiscoveredPool> = match config.discovery.clone().filter(|d| d.enabled) {
+            Some(discovery_config) => {
+                // getProgramAccounts 较慢：独立的 30s 超时，仍共用令牌桶
+                let urls = discovery_config.rpc_url.clone()
+                    .map(|url| vec![url])
+                    .unwrap_or_else(|| rpc_manager.urls());
+                info!("🔭 Discovering pools via {}...", urls[0]);
+                let discovery_rpc = Arc::new(rpc_manager.derive(urls, Duration::from_secs(30)));
+                let pools = discovery::discover_pools(&discovery_config, config.pools(), &discovery_rpc.handle("discovery")).await;
+                for pool in &pools {
+                    info!("  🔭 {} ({}) liquidity ${:.0}", pool.name, pool.address, pool.liquidity_usd);
+                }
+                let discovered: Vec<PoolConfig> = pools.iter().map(|p| p.to_pool_config()).collect();
+                pool_reload::merge_discovered(&mut config.pools, &discovered);
+                pools
+            }
+            None => Vec::new(),
+        };
+        if config.pools().is_empty() {
+            anyhow::bail!("No pools configured and discovery found none");
+        }
+        
+        // 🧭 已知代币符号（发现目标、LST）：自动命名和池子方向共用
+        let mint_registry = pool_mints::global();
+        if let Some(discovery_config) = &config.discovery {
+            for target in &discovery_config.target_mints {
+                mint_registry.register_symbol(&target.mint, &target.symbol);
+            }
+        }
+        if let Some(lst_config) = &config.lst_detector {
+            for token in &lst_config.tokens {
+                mint_registry.register_symbol(&token.mint, &token.symbol);
+            }
+        }
+        
+        // 🏷️ pair 留空的池子按链上 mint 自动命名（在分片和订阅之前，后续组件都使用生成的名称）
+        let pair_namer = Arc::new(pair_naming::PairNamer::new(
+            rpc_manager.handle("pair_naming"),
+            config.pool_naming.clone().unwrap_or_default().fetch_metadata,
+        ));
+        let auto_named = pair_namer.resolve(&mut config.pools).await;
+        if auto_named > 0 {
+            info!("🏷️  Auto-named {} pools from on-chain mints", auto_named);
+        }
+        
+        // 🧩 多实例分片：只订阅本实例负责的池子 + 锚定池
+        let shard_assignment = config.sharding.as_ref()
+            .filter(|s| s.enabled)
+            .map(|s| {
+                let ring = sharding::ShardRing::new(s.shard_count, s.virtual_nodes);
+                let assignment = sharding::ShardAssignment::assign(&ring, s.shard_index, config.pools(), &s.anchor_pools);
+                let status = assignment.status();
+                info!(
+                    "🧩 Shard {}/{}: {} owned, {} anchor, {} mirrored pools",
+                    status.shard_index, status.shard_count,
+                    status.owned_pools, status.anchor_pools, status.mirrored_pools
+                );
+                assignment
+            });
+        let monitored_pools: Vec<PoolConfig> = match &shard_assignment {
+            Some(assignment) => assignment.subscribed_pools(config.pools()),
+            None => config.pools().to_vec(),
+        };
+        
+        info!("Pools to monitor: {}", monitored_pools.len());
+        for pool in &monitored_pools {
+            info!("  - {} ({})", pool.name, pool.address);
+        }
+
+        // 💸 池子级手续费覆盖（fee_bps）
+        let fee_overrides = fee_registry::global().load_from_pools(config.pools());
+        if fee_overrides > 0 {
+            info!("💸 Loaded {} per-pool fee overrides (fee_bps)", fee_overrides);
+        }
+
+        // 🧭 池子方向（base_mint / quote_mint），未配置的池子按 pair 名称猜测
+        let oriented_pools = mint_registry.load_from_pools(config.pools());
+        info!(
+            "🧭 {}/{} pools have base_mint/quote_mint configured",
+            oriented_pools, config.pools().len()
+        );
+
+        // 🪞 代币别名（默认 wSOL → SOL；[router] token_aliases = {} 关闭）
+        if let Some(router) = &config.router {
+            let aliases = token_alias::TokenAliases::from_map(&router.token_aliases);
+            let active = aliases.len();
+            if token_alias::install(aliases) {
+                info!("🪞 {} token aliases active", active);
+            } else {
+                warn!("🪞 Token aliases already installed, [router] token_aliases ignored");
+            }
+        }
+
+        // Display proxy configuration
+        if let Some(proxy) = &config.proxy {
+            if proxy.enabled {
+                info!("Proxy: {}:{} (enabled)", proxy.host, proxy.port);
+            } else {
+                info!("Proxy: disabled");
+            }
+        } else {
+            info!("Proxy: not configured");
+        }
+        
+        // Initialize error tracker
+        let error_tracker = Arc::new(ErrorTracker::from_config(&config.error_tracking.clone().unwrap_or_default()));
+        
+        // Initialize metrics collector
+        let metrics = Arc::new(MetricsCollector::new(1000));
+        
+        // Initialize price cache
+        // 🧯 池子级熔断：未配置 [circuit_breaker] 时按默认阈值启用
+        let breaker_config = config.circuit_breaker.clone().unwrap_or_default();
+        let price_cache = PriceCache::new().with_circuit_breaker(CircuitBreaker::new(&breaker_config));
+        // ⛓️ 可选：一致性快照以链头为最新 slot（整个数据源落后时也能排除）
+        let chain_head_config = config.chain_head.clone().unwrap_or_default();
+        let price_cache = Arc::new(if chain_head_config.enabled && chain_head_config.anchor_snapshots {
+            info!("⛓️  Consistent snapshots anchored to the chain head slot");
+            price_cache.with_chain_head_anchor(chain_head::global())
+        } else {
+            price_cache
+        });
+        
+        // ⏱️ 池子级新鲜度预算覆盖（max_age_ms），其余按池子类型默认值
+        let staleness_overrides = price_cache.staleness().load_from_pools(config.pools());
+        if staleness_overrides > 0 {
+            info!("⏱️  Loaded {} per-pool staleness overrides (max_age_ms)", staleness_overrides);
+        }
+        if breaker_config.enabled {
+            let breaker_overrides = price_cache.circuit_breaker().load_from_pools(config.pools());
+            info!(
+                "🧯 Circuit breaker: {:.0}% price jump / {:.0}% reserve change per slot, {}s cooldown ({} per-pool overrides)",
+                breaker_config.max_price_jump_percent,
+                breaker_config.max_reserve_change_percent,
+                breaker_config.cooldown_secs,
+                breaker_overrides
+            );
+        }
+        
+        // 💾 用上次的快照预热缓存（恢复的条目标记为过期，等实时更新或主动 RPC 刷新）
+        let snapshot_config = config.snapshot.clone().filter(|s| s.enabled);
+        if let Some(snapshot_cfg) = &snapshot_config {
+            let pool_ids: std::collections::HashSet<String> = monitored_pools.iter()
+                .map(|p| p.address.clone())
+                .collect();
+            price_snapshot::restore(snapshot_cfg, &price_cache, &pool_ids);
+        }
+        
+        // 🔒 池子 owner 校验结果（RPC初始化与WebSocket首次通知共享）
+        let owner_checks: Arc<DashMap<String, OwnerCheck>> = Arc::new(DashMap::new());
+        
+        // Initialize global mint decimals cache (used by WhirlpoolState price calculation)
+        init_global_mint_cache(rpc_manager.handle("mint_cache"));
+        
+        // 🔌 被禁用 DEX 的池子不参与 RPC 初始化（计入 skipped_disabled 而不是错误）
+        let mut init_skipped_disabled: Vec<&'static str> = Vec::new();
+        
+        // 🚀 Initialize pools proactively (if enabled)
+        let mut initialization = InitializationReport::NotConfigured;
+        if let Some(init_config) = &config.initialization {
+            if init_config.enabled && !init_config.rpc_urls.is_empty() {
+                info!("🚀 Initializing pools via RPC batch query ({} endpoints)...", init_config.rpc_urls.len());
+                // 🚀 与订阅顺序一致：priority 高的池子先激活
+                let init_pools: Vec<&PoolConfig> = priority_order(&monitored_pools)
+                    .into_iter()
+                    .map(|idx| &monitored_pools[idx])
+                    .filter(|p| {
+                        let enabled = PoolFactory::is_dex_enabled(&p.pool_type);
+                        if !enabled {
+                            info!("   🔌 Skipping {} ({} disabled)", p.name, p.pool_type);
+                            init_skipped_disabled.push(
+                                PoolFactory::canonical_pool_type(&p.pool_type).unwrap_or("unknown"),
+                            );
+                        }
+                        enabled
+                    })
+                    .collect();
+                info!(
+                    "   Pools to query: {}, batch size: {}, max retries: {}",
+                    init_pools.len(), init_config.batch_size, init_config.max_retries
+                );
+                
+                let initializer = pool_initializer::PoolInitializer::new(rpc_manager.handle("pool_initializer"));
+                
+                let pool_addresses: Vec<String> = init_pools
+                    .iter()
+                    .map(|p| p.address.clone())
+                    .collect();
+                
+                match initializer
+                    .fetch_pool_accounts(&pool_addresses, init_config.max_retries)
+                    .await
+                {
+                    Ok(accounts_data) => {
+                        let mut activated = 0;
+                        let mut pools_needing_vaults: Vec<(String, String, String, String)> = Vec::new(); // (pool_name, pool_addr, vault_a, vault_b)
+                        
+                        for (idx, account_data) in accounts_data.iter().enumerate() {
+                            if let Some(account) = account_data {
+                                let pool_config = init_pools[idx];
+                                let data = &account.data;
+                                
+                                // 🔒 owner 校验：pool_type 与账户所属 program 不一致则拒绝激活
+                                let owner_check = PoolFactory::check_owner(
+                                    &pool_config.pool_type,
+                                    &account.owner.to_string(),
+                                    data,
+                                );
+                                let owner_verified = owner_check.verified;
+                                if let Some(reason) = owner_check.reason() {
+                                    warn!(
+                                        "   ⛔ Refusing {}: {} (configured type: {}, suggested: {})",
+                                        pool_config.name,
+                                        reason,
+                                        pool_config.pool_type,
+                                        owner_check.suggested_type.as_deref().unwrap_or("unknown")
+                                    );
+                                    error_tracker.record_error("owner_mismatch", format!("{}: {}", pool_config.name, reason)).await;
+                                }
+                                owner_checks.insert(pool_config.address.clone(), owner_check);
+                                if !owner_verified {
+                                    continue;
+                                }
+                                
+                                // 尝试解析并激活池子
+                                match account.create_pool(&pool_config.pool_type) {
+                                    Ok(pool) => {
+                                        // 🚦 等待 vault 数据的池子也激活（vault 余额到达后才进路由）
+                                        let activity = pool.activity_status();
+                                        price_cache.set_activity_status(&pool_config.address, activity.clone());
+                                        if activity.is_trackable() {
+                                            // 添加到价格缓存
+                                            let (base_reserve, quote_reserve) = pool.get_reserves();
+                                            let price = pool.calculate_price();
+                                            let (base_decimals, quote_decimals) = pool.get_decimals();
+                                            
+                                            price_cache.update_price(price_cache::PoolPrice {
+                                                pool_id: pool_config.address.clone(),
+                                                dex_name: pool.dex_name().to_string(),
+                                                pair: pool_config.pair.clone(),  // 🔥 FIX: 使用 pair 而不是 name
+                                                base_reserve,
+                                                quote_reserve,
+                                                base_decimals,
+                                                quote_decimals,
+                                                price,
+                                                last_update: std::time::Instant::now(),
+                                                slot: 0, // 初始化时slot为0
+                                                liquidity_usd: None,
+                                                commitment: None,
+                                            });
+                                            
+                                            activated += 1;
+                                            info!("   ✅ Activated: {} ({}, {})", pool_config.name, pool.dex_name(), activity);
+                                            
+                                            // 🔥 关键修复：在RPC初始化时就记录需要vault的池子
+                                            if let Some((vault_a, vault_b)) = pool.get_vault_addresses() {
+                                                let vault_a_str = vault_a.to_string();
+                                                let vault_b_str = vault_b.to_string();
+                                                info!("   📌 Pre-registering vaults for {}: {}, {}", 
+                                                      pool_config.name, 
+                                                      &vault_a_str[0..8],
+                                                      &vault_b_str[0..8]);
+                                                pools_needing_vaults.push((
+                                                    pool_config.name.clone(),
+                                                    pool_config.address.clone(),
+                                                    vault_a_str,
+                                                    vault_b_str
+                                                ));
+                                            }
+                                        } else {
+                                            info!("   ⚠️  Inactive: {} ({})", pool_config.name, activity);
+                                        }
+                                    }
+                                    Err(e) => {
+                                        info!("   ⚠️  Failed to parse: {} - {}", pool_config.name, e);
+                                    }
+                                }
+                            } else {
+                                let pool_config = init_pools[idx];
+                                info!("   ❌ Not found: {}", pool_config.name);
+                            }
+                        }
+                        
+                        // 📌 需要 vault 的池子等 WebSocket 连接后再订阅
+                        initialization = InitializationReport::Completed {
+                            activated,
+                            queried: pool_addresses.len(),
+                            pending_vaults: pools_needing_vaults.len(),
+                        };
+                    }
+                    Err(e) => {
+                        warn!(
+                            "⚠️  Pool initialization failed: {}, continuing with WebSocket only",
+                            e
+                        );
+                        initialization = InitializationReport::Failed(e.to_string());
+                    }
+                }
+            } else if !init_config.enabled {
+                initialization = InitializationReport::Disabled;
+            } else {
+                initialization = InitializationReport::NoRpcUrls;
+            }
+        }
+        
+        // Initialize database (if enabled)
+        let (db_manager, database_report) = if let Some(db_config) = &config.database {
+            if db_config.enabled {
+                info!("🗄️  Initializing database...");
+                match DatabaseManager::new(DatabaseConfig {
+                    enabled: db_config.enabled,
+                    url: db_config.url.clone(),
+                    record_opportunities: db_config.record_opportunities,
+                    record_pool_updates: db_config.record_pool_updates,
+                    record_performance: db_config.record_performance,
+                    opportunity_lifecycle_ttl_secs: db_config.opportunity_lifecycle_ttl_secs,
+                }).await {
+                    Ok(mut db) => {
+                        db.set_subscription_start();
+                        (Some(Arc::new(tokio::sync::Mutex::new(db))), DatabaseReport::Connected)
+                    }
+                    Err(e) => {
+                        warn!("⚠️  Database initialization failed: {}, continuing without database recording", e);
+                        (None, DatabaseReport::Failed(e.to_string()))
+                    }
+                }
+            } else {
+                (None, DatabaseReport::Disabled)
+            }
+        } else {
+            (None, DatabaseReport::NotConfigured)
+        };
+
+        // 🛟 长时间运行任务的监督：panic 后退避重启，超过每小时上限时 /health 报 down
+        let supervisor = supervisor::Supervisor::new(supervisor::SupervisorPolicy::from_config(
+            &config.supervisor.clone().unwrap_or_default(),
+        ));
+
+        // 🚨 Phoenix价格刷新 - 防止WebSocket长时间无更新导致价格陈旧
+        let phoenix_pools: Vec<PoolConfig> = config
+            .pools()
+            .iter()
+            .filter(|p| p.pool_type.to_lowercase().contains("phoenix"))
+            .cloned()
+            .collect();
+        let phoenix_pool_count = phoenix_pools.len();
+
+        let phoenix_refresh_handle = if !phoenix_pools.is_empty() {
+            info!("🛰️  Starting Phoenix price refresher ({} pools)...", phoenix_pools.len());
+            let price_cache_clone = price_cache.clone();
+            let rpc = rpc_manager.handle("phoenix_refresh");
+            Some(supervisor::spawn_supervised("phoenix_refresher", &supervisor, move || {
+                phoenix_refresh_worker(phoenix_pools.clone(), rpc.clone(), price_cache_clone.clone())
+            }))
+        } else {
+            None
+        };
+
+        // 🛑 无需等待的周期任务，关闭时中止
+        let mut background_handles: Vec<JoinHandle<()>> = Vec::new();
+
+        // 🪙 epoch 切换时刷新 Token-2022 转账手续费参数
+        if let Some(mint_cache) = mint_decimals_cache::get_global_mint_cache() {
+            background_handles.push(supervisor::spawn_supervised("mint_epoch_watcher", &supervisor, move || {
+                mint_epoch_worker(mint_cache.clone())
+            }));
+        }
+        
+        // 🔥 Initialize StakePoolReader for LST Enhanced Detector
+        let (stake_pool_reader, lst_report) = match config.lst_detector.as_ref().filter(|l| l.enabled) {
+            Some(lst_config) => {
+                info!("🔥 Initializing Stake Pool Reader for LST detection...");
+                
+                let registry = match LstRegistry::from_config(lst_config) {
+                    Ok(registry) => registry,
+                    Err(e) => {
+                        warn!("⚠️  Invalid [lst_detector] tokens: {:#}", e);
+                        warn!("   Falling back to built-in LSTs (mSOL, jitoSOL)");
+                        LstRegistry::default()
+                    }
+                };
+                info!("   LSTs: {}", registry.entries().iter()
+                    .map(|e| e.symbol.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "));
+                
+                match StakePoolReader::new(rpc_manager.handle("stake_pool"), lst_config.stake_pool_update_interval, Arc::new(registry)) {
+                    Ok(reader) => {
+                        let reader: Arc<StakePoolReader> = Arc::new(reader.with_default_apy(lst_config.default_apy_percent));
+                        
+                        // 🪙 恢复最近几个 epoch 的比率历史（推算下一 epoch 比率）
+                        if let Some(db) = &db_manager {
+                            match db.lock().await.load_lst_rates(8).await {
+                                Ok(samples) => reader.seed_history(&samples),
+                                Err(e) => warn!("⚠️  Failed to load LST rate history: {}", e),
+                            }
+                        }
+                        
+                        // Initial update to fetch theoretical rates（阻塞 RPC，放到 blocking 线程）
+                        let initial_reader = reader.clone();
+                        let initial_update = task::spawn_blocking(move || initial_reader.update_cache())
+                            .await
+                            .unwrap_or_else(|e| Err(anyhow::anyhow!("stake pool task failed: {}", e)));
+                        let rates = match initial_update {
+                            Ok(_) => reader.get_cache_info().0,
+                            Err(e) => {
+                                warn!("⚠️  Failed to initialize stake pool cache: {}", e);
+                                warn!("   Using default theoretical rates (mSOL: 1.05, jitoSOL: 1.04)");
+                                Default::default()
+                            }
+                        };
+                        let lst_report = LstReport {
+                            rpc_url: rpc_manager.active_url().to_string(),
+                            cache_ttl_secs: lst_config.stake_pool_update_interval,
+                            rates: reader.registry().entries().iter()
+                                .map(|entry| (entry.symbol.clone(), rates.get(&entry.mint).copied()))
+                                .collect(),
+                            epoch_ends_in: reader.time_to_epoch_end(),
+                        };
+                        
+                        // 🪙 定期刷新比率，新 epoch 的比率写入数据库
+                        let refresh_reader = reader.clone();
+                        let refresh_db = db_manager.clone();
+                        let refresh_interval = Duration::from_secs(lst_config.stake_pool_update_interval.max(1));
+                        background_handles.push(tokio::spawn(async move {
+                            loop {
+                                if let Some(db) = &refresh_db {
+                                    let samples = refresh_reader.take_new_samples();
+                                    if !samples.is_empty() {
+                                        if let Err(e) = db.lock().await.record_lst_rates(&samples).await {
+                                            warn!("⚠️  Failed to record LST rates: {}", e);
+                                        }
+                                    }
+                                }
+                                tokio::time::sleep(refresh_interval).await;
+                                let reader = refresh_reader.clone();
+                                if let Ok(Err(e)) = task::spawn_blocking(move || reader.update_cache()).await {
+                                    warn!("⚠️  Stake pool refresh failed: {}", e);
+                                }
+                            }
+                        }));
+                        
+                        (Some(reader), Some(lst_report))
+                    }
+                    Err(e) => {
+                        warn!("⚠️  Failed to create StakePoolReader: {}", e);
+                        warn!("   LST Enhanced Detector will be disabled");
+                        (None, None)
+                    }
+                }
+            }
+            None => (None, None),
+        };
+        
+        // ✅ FIX: Connect WebSocket in the building task (avoids spawn scheduling issue)
+        info!("🔌 Establishing WebSocket connection...");
+        
+        // 🔀 依次尝试各端点，全部失败才退出
+        let mut connect_attempts = 0;
+        let ws_stream = loop {
+            let url = ws_endpoints.active_url();
+            match proxy::connect_direct(&url).await {
+                Ok(stream) => {
+                    info!("✅ WebSocket connected to {}", url);
+                    break stream;
+                }
+                Err(e) => {
+                    error!("❌ Failed to connect to WebSocket {}: {}", url, e);
+                    connect_attempts += 1;
+                    ws_endpoints.record_failure(ws_endpoints.active_index(), config.websocket.reconnect_alert_after_failures);
+                    if connect_attempts >= ws_endpoints.endpoint_count() {
+                        error!("   Please check your network connection and RPC endpoint");
+                        return Err(e);
+                    }
+                    ws_endpoints.fail_over();
+                }
+            }
+        };
+        
+        // Get price change threshold from config
+        let price_change_threshold = config.logging
+            .as_ref()
+            .map(|l| l.price_change_threshold_percent)
+            .unwrap_or(1.0);
+        
+        // 🛑 关闭信号：run() 收到 shutdown 后广播给 WebSocket / Coordinator / Calculator / 数据库任务
+        let (shutdown_tx, _) = broadcast::channel::<()>(4);
+        
+        // 💾 定期写价格快照（关闭时再写最后一次）
+        let snapshot_handle = snapshot_config.map(|snapshot_cfg| {
+            info!("💾 Price snapshot enabled: {} (every {}s)", snapshot_cfg.path, snapshot_cfg.interval_secs);
+            price_snapshot::spawn_snapshot_task(snapshot_cfg, price_cache.clone(), shutdown_tx.subscribe())
+        });
+        
+        // 📏 交易对价差持续超阈值告警
+        let spread_monitor_handle = config.spread_monitor.clone()
+            .filter(|spread_cfg| spread_cfg.enabled)
+            .map(|spread_cfg| {
+                info!("📏 Spread monitor enabled: alert above {:.1} bps sustained for {}s{}",
+                    spread_cfg.threshold_bps, spread_cfg.sustain_secs,
+                    if spread_cfg.webhook_url.is_some() { " (webhook)" } else { "" });
+                spread_monitor::spawn_spread_monitor(spread_cfg, price_cache.clone(), shutdown_tx.subscribe())
+            });
+        
+        // 🌡️ 有序池子组合的滚动价差统计（/stats/spreads，指标日志输出超阈值最久的组合）
+        let spread_heatmap = config.spread_heatmap.clone()
+            .filter(|heatmap_cfg| heatmap_cfg.enabled)
+            .map(|heatmap_cfg| {
+                info!("🌡️ Spread heatmap enabled: {}s window, {:.1} bps threshold, {} samples per cell",
+                    heatmap_cfg.window_secs, heatmap_cfg.threshold_bps, heatmap_cfg.max_samples_per_cell);
+                let heatmap = Arc::new(spread_heatmap::SpreadHeatmap::new(&heatmap_cfg));
+                background_handles.push(spread_heatmap::spawn_spread_heatmap(
+                    heatmap.clone(),
+                    price_cache.clone(),
+                    shutdown_tx.subscribe(),
+                ));
+                (heatmap, heatmap_cfg.top_n)
+            });
+        
+        // 🎞️ 记录池子更新（回放数据源，replay 二进制按时间顺序读回）
+        let pool_update_recorder = match (&db_manager, &config.database) {
+            (Some(db), Some(db_config)) if db_config.record_pool_updates => {
+                info!("🎞️ Recording pool updates to pool_update_history");
+                Some(pool_update_log::spawn_recorder(db.clone(), shutdown_tx.subscribe()))
+            }
+            _ => None,
+        };
+        
+        // 🔥 Initialize WebSocket client (with Coordinator event sender)
+        info!("Initializing WebSocket client...");
+
+        let ws_client = WebSocketClient::new(
+            config.websocket_url().to_string(),
+            metrics.clone(),
+            config.proxy.clone(),
+            price_cache.clone(),
+            error_tracker.clone(),
+            price_change_threshold,
+            true, // 🚀 主动触发vault订阅（经共享 RPC）
+        )
+        .with_endpoints(ws_endpoints.clone())
+        .with_owner_checks(owner_checks.clone())
+        .with_backoff(reconnect_backoff::BackoffPolicy::from_config(&config.websocket))
+        .with_max_subscriptions_per_connection(config.websocket.max_subscriptions_per_connection)
+        .with_pool_data_cache_capacity(config.websocket.max_cached_pool_accounts)
+        .with_subscription_options(config.websocket.commitment, config.websocket.encoding)
+        .with_subscription_pacing(config.websocket.priority_batch_size, config.websocket.subscribe_rate_per_sec)
+        .with_subscription_cleanup(
+            config.websocket.unsubscribe_unknown_after,
+            config.websocket.resubscribe_silent_after(),
+        )
+        .with_rpc(rpc_manager.handle("vault_prefetch"))
+        .with_supervisor(supervisor.clone())
+        .with_shutdown(shutdown_tx.clone());
+        let (ws_client, pool_update_recorder_handle) = match pool_update_recorder {
+            Some((recorder, handle)) => (ws_client.with_update_recorder(recorder), Some(handle)),
+            None => (ws_client, None),
+        };
+        let ws_client = Arc::new(ws_client);
+
+        // 🔥 Get pool stats collector before moving ws_client
+        let pool_stats = ws_client.pool_stats();
+        for dex in &init_skipped_disabled {
+            pool_stats.record_skipped_disabled(dex);
+        }
+        let ws_heartbeat = ws_client.heartbeat(); // 🩺 /health
+        let vault_reader = ws_client.vault_reader(); // 🔁 路径池子重复检查
+        let ws_cache_sizes = ws_client.cache_sizes(); // 📏 /metrics
+        let ws_pool_accounts = ws_client.pool_accounts(); // 🔍 一致性检查
+        
+        // Spawn WebSocket processing task with the already-connected stream
+        info!("Starting WebSocket message processing task...");
+        let pools = monitored_pools.clone();
+        let ws_client_for_reload = ws_client.clone();
+        let ws_client_for_pipeline = ws_client.clone();
+        // 🛟 panic 后重新连接订阅（已连接的 stream 只在第一次运行时使用）
+        let mut ws_stream = Some(ws_stream);
+        let ws_handle = supervisor::spawn_supervised("websocket", &supervisor, move || {
+            let ws_client = ws_client.clone();
+            let pools = pools.clone();
+            let stream = ws_stream.take();
+            async move {
+                let result = match stream {
+                    Some(stream) => ws_client.run_with_stream(stream, pools).await,
+                    None => ws_client.run(pools).await,
+                };
+                if let Err(e) = result {
+                    error!("Fatal WebSocket error: {}", e);
+                }
+                ws_client.unsubscribed_count()
+            }
+        });
+        
+        // Spawn metrics reporting task
+        info!("📊 Starting metrics reporting task...");
+        let metrics_clone = metrics.clone();
+        let endpoints_for_metrics = ws_endpoints.clone();
+        let heatmap_for_metrics = spread_heatmap.clone();
+        let metrics_handle = supervisor::spawn_supervised("metrics_reporter", &supervisor, move || {
+            let metrics_clone = metrics_clone.clone();
+            let endpoints_for_metrics = endpoints_for_metrics.clone();
+            let heatmap_for_metrics = heatmap_for_metrics.clone();
+            async move {
+                let mut ticker = interval(Duration::from_secs(60));
+
+                loop {
+                    ticker.tick().await;
+                    metrics_clone.print_stats(60);
+
+                    // 🌡️ 超阈值时间最长的价差组合
+                    if let Some((heatmap, top_n)) = &heatmap_for_metrics {
+                        heatmap.log_top_persistent(*top_n);
+                    }
+
+                    // 🔀 多端点时输出各端点健康状态
+                    if endpoints_for_metrics.endpoint_count() > 1 {
+                        for endpoint in endpoints_for_metrics.health() {
+                            info!(
+                                "🔀 {} {}: {} consecutive failures, last message {}, avg latency {}μs",
+                                if endpoint.active { "[active]" } else { "[standby]" },
+                                endpoint.url,
+                                endpoint.consecutive_failures,
+                                endpoint.last_message_ms_ago
+                                    .map(|ms| format!("{}ms ago", ms))
+                                    .unwrap_or_else(|| "never".to_string()),
+                                endpoint.avg_latency_micros
+                            );
+                        }
+                    }
+                }
+            }
+        });
+        
+        // Spawn advanced arbitrage router task
+        info!("⚡ Starting advanced arbitrage router with Bellman-Ford + DP optimization...");
+        let db_manager_clone = db_manager.clone();
+        let router_config = AdvancedRouterConfig::from_config(config.router.as_ref());
+
+        // 🔥 反压监视器：模拟器上报负载，Calculator扫描前采样
+        let backpressure_monitor = config.router.as_ref()
+            .and_then(|r| r.backpressure.as_ref())
+            .map(|bp| {
+                info!("🔥 Backpressure enabled: elevated={:.2}, high={:.2}", bp.elevated_load, bp.high_load);
+                Arc::new(backpressure::BackpressureMonitor::new(backpressure::BackpressurePolicy {
+                    enabled: bp.enabled,
+                    elevated_load: bp.elevated_load,
+                    high_load: bp.high_load,
+                    elevated_roi_multiplier: bp.elevated_roi_multiplier,
+                    high_roi_multiplier: bp.high_roi_multiplier,
+                    downgrade_hybrid_to_fast: bp.downgrade_hybrid_to_fast,
+                    queue_capacity: bp.queue_capacity,
+                }))
+            });
+
+        // 💲 USD 定价（扫描档位换算与期望值排序共用）
+        let price_oracle = Arc::new(price_oracle::PriceOracle::new(
+            price_cache.clone(),
+            &config.price_oracle.clone().unwrap_or_default(),
+        ));
+
+        // 💧 池子美元流动性：代币美元价格定期从 USD 定价刷新，PriceCache 写入时按储备估算
+        if router_config.min_pool_liquidity_usd > 0.0 {
+            info!("💧 Routing excludes pools below ${:.0} liquidity", router_config.min_pool_liquidity_usd);
+        }
+        background_handles.push(liquidity::spawn_refresher(
+            price_cache.clone(),
+            price_oracle.clone(),
+            liquidity::DEFAULT_REFRESH_INTERVAL,
+            &supervisor,
+        ));
+
+        // ⛽ 执行成本：优先费率定期刷新，起始代币的 SOL 汇率由 USD 定价换算
+        let execution_cost_model = Arc::new(execution_cost::ExecutionCostModel::new(
+            config.execution_cost.clone().unwrap_or_default(),
+        ));
+        let mut cost_tokens: Vec<String> = config.router.as_ref()
+            .map(|r| r.start_tokens.clone())
+            .unwrap_or_default();
+        cost_tokens.push(config.calculator.clone().unwrap_or_default().base_token);
+        cost_tokens.extend(["USDC".to_string(), "USDT".to_string()]);
+        cost_tokens.sort();
+        cost_tokens.dedup();
+        background_handles.push(execution_cost::spawn_refresher(
+            execution_cost_model.clone(),
+            rpc_manager.handle("priority_fees"),
+            price_oracle.clone(),
+            cost_tokens,
+            &supervisor,
+        ));
+
+        // 🔍 vault 一致性巡检：缓存储备偏离 vault 余额时告警（未配置时按默认值开启）
+        let vault_audit_config = config.vault_audit.clone().unwrap_or_default();
+        if vault_audit_config.enabled {
+            background_handles.push(vault_audit::spawn_auditor(
+                price_cache.clone(),
+                vault_reader.clone(),
+                ws_pool_accounts.clone(),
+                Duration::from_secs(vault_audit_config.interval_minutes * 60),
+                vault_audit_config.max_divergence_percent,
+                &supervisor,
+            ));
+        }
+
+        // 🧭 外部参考价格交叉校验：偏离超过硬上限的池子排除出路由（未配置时不启用）
+        let reference_prices = config.reference_prices.clone()
+            .filter(|r| r.enabled && !r.feeds.is_empty())
+            .map(|reference_config| {
+                info!(
+                    "🧭 Checking pools against {} reference price feeds every {}s (warn {:.1}%, exclude {:.1}%)",
+                    reference_config.feeds.len(), reference_config.poll_interval_secs,
+                    reference_config.warn_deviation_percent, reference_config.exclude_deviation_percent,
+                );
+                let checker = Arc::new(reference_price::ReferencePriceChecker::new(reference_config));
+                background_handles.push(reference_price::spawn_checker(checker.clone(), price_cache.clone(), &supervisor));
+                checker
+            });
+
+        // 🕰️ 逐池价格历史：订阅价格更新广播，在独立任务中写入内存环形缓冲（与数据库无关）
+        let history_config = config.history.clone().unwrap_or_default();
+        let price_history = history_config.enabled
+            .then(|| {
+                let history = Arc::new(pool_history::PoolHistory::new(&history_config));
+                let heartbeat = Duration::from_secs(history_config.heartbeat_secs);
+                let (task_history, price_cache, shutdown_tx) = (history.clone(), price_cache.clone(), shutdown_tx.clone());
+                background_handles.push(supervisor::spawn_supervised("pool_history", &supervisor, move || {
+                    pool_history::run_history_recorder(
+                        task_history.clone(),
+                        price_cache.clone(),
+                        heartbeat,
+                        shutdown_tx.subscribe(),
+                    )
+                }));
+                history
+            });
+
+        // ⛓️ 链头跟踪：getSlot 轮询，池子 p95 head_lag 超阈值时定期告警
+        if chain_head_config.enabled {
+            background_handles.push(chain_head::spawn_poller(
+                chain_head::global(),
+                rpc_manager.handle("chain_head"),
+                Duration::from_millis(chain_head_config.poll_interval_ms),
+                &supervisor,
+            ));
+            let max_p95_slots = chain_head_config.lag_warn_p95_slots;
+            if max_p95_slots > 0 {
+                let pool_stats = pool_stats.clone();
+                background_handles.push(tokio::spawn(async move {
+                    let mut ticker = interval(Duration::from_secs(60));
+                    loop {
+                        ticker.tick().await;
+                        for (pool_name, lag) in pool_stats.lagging_pools(max_p95_slots) {
+                            warn!(
+                                "⛓️  {} lags the chain head: p95 {} slots (p50 {}, last {}, threshold {})",
+                                pool_name, lag.p95, lag.p50, lag.last, max_p95_slots
+                            );
+                        }
+                    }
+                }));
+            }
+        }
+
+        // 🧊 扫描输入抓取（[debug_capture] 每次扫描都抓取；POST /debug/capture 按次抓取）
+        let scan_capture = Arc::new(scan_capture::CaptureControl::new(&config.debug_capture.clone().unwrap_or_default()));
+
+        // 🔥 Calculator 依赖（扫描任务由 Coordinator 经 pipeline 派发）
+        let calculator_router = {
+            let router = AdvancedRouter::new(price_cache.clone(), router_config.clone())
+                .with_price_oracle(price_oracle.clone())
+                .with_vault_reader(vault_reader.clone())
+                .with_execution_cost(execution_cost_model.clone())
+                .with_capture(scan_capture.clone());
+            let router = match &backpressure_monitor {
+                Some(monitor) => router.with_backpressure(monitor.clone()),
+                None => router,
+            };
+            if router_config.path_cache.enabled {
+                router.spawn_path_cache_invalidation();
+            }
+            Arc::new(router)
+        };
+        // 🔔 告警分发：每个 sink 独立评估同一条机会流
+        let alert_dispatcher = config.alerts.as_ref()
+            .filter(|a| a.enabled && !a.sinks.is_empty())
+            .map(|a| {
+                let sinks = a.sinks.iter()
+                    .map(|s| alerts::SinkConfig {
+                        name: s.name.clone(),
+                        mode: s.mode,
+                        template: s.template,
+                        min_roi_percent: s.min_roi_percent,
+                        cooldown: Duration::from_secs(s.cooldown_secs),
+                        max_per_minute: s.max_per_minute,
+                    })
+                    .collect();
+                info!("🔔 Alert dispatcher enabled with {} sink(s)", a.sinks.len());
+                alerts::AlertDispatcher::new(sinks, Arc::new(alerts::LogTransport))
+            });
+        // 📣 机会通知：验证通过的机会经有界通道交给后台分发任务（webhook / Telegram）
+        let notification_metrics = Arc::new(notifications::NotificationMetrics::default());
+        let notification_sender = match config.notifications.as_ref().filter(|n| n.enabled && !n.sinks.is_empty()) {
+            Some(n) => {
+                let dispatcher = notifications::NotificationDispatcher::from_config(n, notification_metrics.clone())?;
+                info!("📣 Opportunity notifications enabled: {}", dispatcher.sink_names().join(", "));
+                let (sender, handle) = dispatcher.spawn(n.queue_capacity);
+                background_handles.push(handle);
+                Some(sender)
+            }
+            None => None,
+        };
+        
+        // 🧾 结构化机会输出（[output]）：控制台输出不变，另外每条验证通过的机会写一行 JSON
+        let opportunity_output = config.output.as_ref()
+            .map(opportunity_output::OpportunityOutput::from_config)
+            .transpose()?
+            .flatten();
+        if let Some(output) = &opportunity_output {
+            info!("🧾 Structured opportunity output enabled: {}", output.sink_names().join(", "));
+        }
+
+        // 💼 库存感知（[inventory]）：按持有余额过滤 / 截断投入 / 前置兑换
+        let inventory = config.inventory.as_ref()
+            .filter(|inventory_cfg| inventory_cfg.enabled)
+            .map(|inventory_cfg| {
+                info!(
+                    "💼 Inventory-aware opportunities enabled: {:?} (unheld: {:?}, convert from held: {})",
+                    inventory_cfg.balances, inventory_cfg.unheld, inventory_cfg.convert_from_held
+                );
+                inventory::Inventory::from_config(inventory_cfg)
+            });
+        
+        // 🔄 扫描间差异（全量 + 定向，按指纹）：New / Persisting / Improved / Worsened / Gone，
+        // 🗃️ 同时保留最近 N 次扫描记录（/scans）
+        let material_roi_delta = config.router.as_ref()
+            .map(|r| r.material_roi_delta_percent)
+            .unwrap_or(0.1);
+        let scan_history_size = config.router.as_ref()
+            .map(|r| r.scan_history_size)
+            .unwrap_or(scan_diff::DEFAULT_HISTORY_CAPACITY);
+        let scan_differ = Arc::new(std::sync::Mutex::new(
+            scan_diff::ScanDiffer::new(material_roi_delta, 200).with_history_capacity(scan_history_size),
+        ));
+        let scan_differ_task = scan_differ.clone();
+        // 🧪 What-if 扫描（低优先级通道）：实时池子 + 合成池子叠加层，结果只进 /whatif/opportunities
+        let whatif_every_n = config.whatif.as_ref()
+            .filter(|w| w.enabled && !config.synthetic_pools.is_empty())
+            .map(|w| w.every_n_scans.max(1));
+        let (whatif_tx, whatif_report) = match whatif_every_n {
+            Some(_) => {
+                let specs = config.synthetic_pools.iter()
+                    .map(|p| synthetic::SyntheticPoolSpec {
+                        name: p.name.clone(),
+                        pair: p.pair.clone(),
+                        dex: p.dex.clone(),
+                        base_reserve: p.base_reserve,
+                        quote_reserve: p.quote_reserve,
+                        anchor_pair: p.anchor_pair.clone(),
+                        price_offset_percent: p.price_offset_percent,
+                    })
+                    .collect();
+                let scanner = synthetic::WhatIfScanner::new(price_cache.clone(), specs, router_config.clone())
+                    .with_execution_cost(execution_cost_model.clone());
+                let report = scanner.report();
+                let (tx, mut rx) = mpsc::channel::<f64>(1);
+                let synthetic_count = config.synthetic_pools.len();
+                
+                background_handles.push(tokio::spawn(async move {
+                    info!("🧪 What-if scanner started with {} synthetic pool(s)", synthetic_count);
+                    while let Some(amount) = rx.recv().await {
+                        let opportunities = scanner.scan(amount).await;
+                        for opp in &opportunities {
+                            info!(
+                                target: "whatif",
+                                "🧪 [SYNTHETIC] {:.4}% ROI via {} (synthetic: {})",
+                                opp.roi_percent, opp.signature, opp.synthetic_pools.join(", ")
+                            );
+                        }
+                    }
+                }));
+                
+                (Some(tx), Some(report))
+            }
+            None => (None, None),
+        };
+
+        // 🔀 跨扫描去重 + 逐跳验证，通过的机会写入数据库
+        let dedup_ttl_secs = config.router.as_ref()
+            .map(|r| r.opportunity_dedup_ttl_secs)
+            .unwrap_or(30);
+        let opportunity_merger = OpportunityMerger::new()
+            .with_ttl(Duration::from_secs(dedup_ttl_secs));
+        // 🧱 验证规则流水线（[validation]；单跳冲击上限未设置时沿用 [router]）
+        let validation_config = config.validation.clone().unwrap_or_default();
+        let path_validator = opportunity_validator::OpportunityValidator::new(
+            price_cache.clone(),
+            opportunity_validator::ValidatorConfig {
+                max_hop_impact_percent: validation_config.max_single_hop_impact_percent
+                    .or(config.router.as_ref().map(|r| r.max_hop_impact_percent))
+                    .unwrap_or(2.0),
+                allow_pool_reuse: config.router.as_ref().is_some_and(|r| r.allow_pool_reuse),
+                ..opportunity_validator::ValidatorConfig::from(&validation_config)
+            },
+        )
+        .with_vault_reader(vault_reader.clone());
+        info!("🧱 Validation rules: {:?}", path_validator.active_rules());
+        let validation_stats = path_validator.rule_stats();
+        
+        // 🧪 交易级模拟：配置了 payer 时，每次扫描对最佳机会构建真实 swap 交易并 simulateTransaction
+        let tx_simulator = match config.simulation.as_ref().filter(|s| s.enabled) {
+            Some(sim_config) => match sim_config.payer_pubkey.as_deref().map(Pubkey::from_str) {
+                Some(Ok(payer)) => {
+                    info!("🧪 Transaction simulation enabled (payer {})", payer);
+                    let simulator = onchain_simulator::OnChainSimulator::new(
+                        simulation_rpc(&rpc_manager, sim_config),
+                        sim_config.into(),
+                    ).with_transaction_payer(payer);
+                    Some(Arc::new(simulator))
+                }
+                Some(Err(e)) => {
+                    warn!("Invalid simulation.payer_pubkey, transaction simulation disabled: {}", e);
+                    None
+                }
+                None => None,
+            },
+            None => None,
+        };
+        let path_validator = path_validator.with_simulation(tx_simulator.is_some());
+        let db_router_mode = format!("{:?}", router_config.mode).to_lowercase();
+        let db_min_roi = router_config.min_roi_percent;
+
+        // 🎯 重点交易对：每次更新都重算价差矩阵，不经过 Coordinator 冷却
+        let focus_stats = config.focus.clone()
+            .filter(|focus_cfg| focus_cfg.enabled && !focus_cfg.pairs.is_empty())
+            .map(|focus_cfg| {
+                info!("🎯 Focus watcher enabled: {:?} (size {}, min ROI {:.3}%)",
+                    focus_cfg.pairs, focus_cfg.trade_size, focus_cfg.min_roi_percent);
+                let stats = Arc::new(focus::FocusStats::new(&focus_cfg.pairs));
+                let recorder = db_manager.clone().map(|db| focus::FocusRecorder {
+                    db,
+                    router_mode: db_router_mode.clone(),
+                    min_roi_threshold: db_min_roi,
+                });
+                let (price_cache, task_stats, shutdown_tx) = (price_cache.clone(), stats.clone(), shutdown_tx.clone());
+                let execution_cost = execution_cost_model.clone();
+                background_handles.push(supervisor::spawn_supervised("focus_watcher", &supervisor, move || {
+                    focus::run_focus_watcher(
+                        focus_cfg.clone(),
+                        price_cache.clone(),
+                        task_stats.clone(),
+                        recorder.clone(),
+                        execution_cost.clone(),
+                        shutdown_tx.subscribe(),
+                    )
+                }));
+                stats
+            });
+        info!("🔀 Opportunity dedup TTL: {}s", dedup_ttl_secs);
+
+        // 💵 扫描金额档位
+        let calculator_config = config.calculator.clone().unwrap_or_default();
+        let price_cache_calculator = price_cache.clone();
+        info!(
+            "💵 Scan amounts: {:?} USD in {}",
+            calculator_config.amounts_usd, calculator_config.base_token
+        );
+
+        let calculator_scan_heartbeat = Arc::new(std::sync::atomic::AtomicU64::new(0));  // 📈 SLO: 最近完成扫描时间
+        let calculator_scan_heartbeat_task = calculator_scan_heartbeat.clone();
+        let metrics_calculator = metrics.clone();  // 📈 扫描耗时直方图
+
+        // ⚡ 两跳直接套利快速通道：Coordinator 按事件检查受影响的交易对，Calculator 合并进跨扫描去重
+        let direct_table = config.router.as_ref()
+            .and_then(|r| r.direct.as_ref())
+            .filter(|d| d.enabled)
+            .map(|d| {
+                info!("⚡ Direct arbitrage fast path enabled (min spread {:.3}%)", d.min_spread_percent);
+                Arc::new(router_direct::DirectArbTable::new(price_cache.clone(), d.min_spread_percent)
+                    .with_min_liquidity_usd(router_config.min_pool_liquidity_usd)
+                    .with_execution_cost(execution_cost_model.clone()))
+            });
+        let direct_table_calculator = direct_table.clone();
+
+        // 🎯 Coordinator (混合触发 + 计算风暴防护) -> Calculator，整条管线只创建一次
+        info!("🎯 Starting Coordinator -> Calculator pipeline...");
+        // [coordinator] 未配置时：100ms 时钟兜底、0.2% 触发、全局冷却关闭、同一池子 20ms 防抖动
+        let trigger = config.coordinator.clone().unwrap_or_default();
+        let coordinator_config = coordinator::CoordinatorConfig {
+            tick_interval_ms: trigger.tick_interval_ms,
+            high_threshold_percent: trigger.high_threshold_percent,
+            cooldown_ms: trigger.cooldown_ms,
+            per_pool_cooldown_ms: trigger.per_pool_cooldown_ms,
+            event_channel_capacity: 1024,    // 事件channel（高容量）
+            calc_channel_capacity: 1,        // 计算任务channel（容量1，防止堆积）
+            adaptive: config.adaptive_threshold.clone(),  // 🎚️ 阈值随波动 / Calculator 负载自适应（可选）
+        };
+        // 🛟 Calculator panic 后用同一份状态重启（去重窗口、what-if 计数、告警冷却都保留）
+        let calculator_state = calculator_loop::CalculatorState {
+            config: calculator_config,
+            price_oracle,
+            router: calculator_router,
+            metrics: metrics_calculator,
+            scan_heartbeat: calculator_scan_heartbeat_task,
+            direct_table: direct_table_calculator,
+            price_cache: price_cache_calculator,
+            min_roi_percent: db_min_roi,
+            opportunity_merger,
+            path_validator,
+            db_manager: db_manager_clone,
+            tx_simulator,
+            router_mode: db_router_mode,
+            opportunity_output,
+            notification_sender,
+            whatif_tx,
+            whatif_every_n,
+            scans_since_whatif: 0,
+            scan_differ: scan_differ_task,
+            alert_dispatcher,
+            inventory,
+        };
+        let pipeline = pipeline::spawn(coordinator_config, &shutdown_tx, direct_table, &supervisor, calculator_state, calculator_loop::run);
+        let coordinator_tick_heartbeat = pipeline.coordinator_tick_heartbeat.clone();  // 📈 SLO: 漏tick检测
+        let coordinator_stats = pipeline.coordinator_stats.clone();  // 📈 /metrics: 触发计数
+
+        // 🔥 Register Coordinator sender with WebSocket client
+        ws_client_for_pipeline.set_coordinator_sender(pipeline.event_tx.clone());
+        info!("✅ WebSocket client configured with Coordinator");
+
+        let arbitrage_handle = if config.router.as_ref()
+            .and_then(|r| r.event_driven.as_ref())
+            .map(|e| e.enabled)
+            .unwrap_or(false)
+        {
+            // 🎯 Event-driven mode
+            tokio::spawn(async move {
+                // 🔥 DEPRECATED: Old event-driven logic disabled - Coordinator and Calculator now handle all triggers
+                info!("⚠️  Legacy event-driven router is DISABLED - Coordinator and Calculator are now handling all triggers");
+
+                // Keep this task alive but do nothing
+                loop {
+                    tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+                    info!("⚠️  Legacy event-driven router task is idle (Coordinator is active)");
+                }
+            })
+        } else {
+            // 🎯 Non-event-driven mode - return a dummy handle for compatibility
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(Duration::from_secs(3600)).await;
+                }
+            })
+        };
+
+        // 🛑 写数据库的后台任务（关闭时等待其完成最后一次写入）
+        let mut db_task_handles: Vec<tokio::task::JoinHandle<()>> = Vec::new();
+        db_task_handles.extend(pool_update_recorder_handle);
+        
+        // 🎯 置信度校准器（模拟启用且开启校准时）
+        let calibrator = match config.simulation.as_ref().filter(|s| s.enabled && s.calibration_enabled) {
+            Some(sim_config) => {
+                let refresh_hours = sim_config.calibration_refresh_hours.max(1) as i64;
+                let calibrator = Arc::new(
+                    calibration::Calibrator::new(50_000, chrono::Duration::hours(refresh_hours))
+                        .with_min_samples(sim_config.calibration_min_samples, sim_config.calibration_min_bucket_samples)
+                        .with_exploration(sim_config.calibration_explore_every),
+                );
+                
+                // 从数据库恢复校准表（重启后沿用上次拟合结果）
+                if let Some(db) = &db_manager {
+                    match db.lock().await.load_calibration().await {
+                        Ok(Some(table)) => {
+                            info!("🎯 Restored calibration table ({} samples)", table.total_samples);
+                            calibrator.restore(table);
+                        }
+                        Ok(None) => info!("🎯 No stored calibration table, using identity mapping"),
+                        Err(e) => warn!("Failed to restore calibration table: {}", e),
+                    }
+                }
+                
+                // 定期检查是否到期，重新拟合后持久化
+                let calibrator_task = calibrator.clone();
+                let db_calibration = db_manager.clone();
+                let mut calibration_shutdown = shutdown_tx.subscribe();
+                db_task_handles.push(tokio::spawn(async move {
+                    let mut ticker = interval(Duration::from_secs(3600));
+                    loop {
+                        let table = tokio::select! {
+                            _ = ticker.tick() => calibrator_task.refresh_if_due(chrono::Utc::now()),
+                            _ = calibration_shutdown.recv() => {
+                                // 🛑 关闭前持久化当前校准表
+                                if let Some(db) = &db_calibration {
+                                    let table = calibrator_task.table();
+                                    if table.fitted_at.is_some() {
+                                        if let Err(e) = db.lock().await.save_calibration(&table).await {
+                                            warn!("Failed to persist calibration table on shutdown: {}", e);
+                                        }
+                                    }
+                                }
+                                break;
+                            }
+                        };
+                        if let Some(table) = table {
+                            info!("🎯 Calibration table refitted from {} outcomes", table.total_samples);
+                            if let Some(db) = &db_calibration {
+                                if let Err(e) = db.lock().await.save_calibration(&table).await {
+                                    warn!("Failed to persist calibration table: {}", e);
+                                }
+                            }
+                        }
+                    }
+                }));
+                
+                info!("🎯 Confidence calibration enabled (refit every {}h, EV floor {:.3}%, min {} outcomes / {} per bucket)",
+                      refresh_hours, sim_config.min_expected_value_pct,
+                      sim_config.calibration_min_samples, sim_config.calibration_min_bucket_samples);
+                Some(calibrator)
+            }
+            None => None,
+        };
+        
+        // 🎯 创建链上模拟器（如果配置启用）
+        let simulator = if let Some(sim_config) = &config.simulation {
+            if sim_config.enabled {
+                let rpc = simulation_rpc(&rpc_manager, sim_config);
+                
+                info!("🎯 Initializing on-chain simulator...");
+                info!("   RPC URL: {}", rpc.manager().active_url().chars().take(50).collect::<String>());
+                info!("   Min confidence: {:.1}%", sim_config.min_confidence_for_simulation);
+                info!("   Max concurrent: {}", sim_config.max_concurrent_simulations);
+                
+                let simulator = onchain_simulator::OnChainSimulator::new(rpc, sim_config.into());
+                let simulator = match &backpressure_monitor {
+                    Some(monitor) => simulator.with_backpressure(monitor.clone()),
+                    None => simulator,
+                };
+                let simulator = match &calibrator {
+                    Some(calibrator) => simulator.with_calibrator(calibrator.clone()),
+                    None => simulator,
+                };
+                Some(Arc::new(simulator))
+            } else {
+                info!("ℹ️  On-chain simulation disabled");
+                None
+            }
+        } else {
+            info!("ℹ️  On-chain simulation not configured");
+            None
+        };
+        
+        // 📈 可用性 SLO 追踪（可选）
+        let slo_tracker = if let Some(slo_cfg) = config.slo.as_ref().filter(|s| s.enabled) {
+            let targets = slo_cfg.targets
+                .iter()
+                .filter_map(|(name, target)| slo::SloComponent::parse(name).map(|c| (c, *target)))
+                .collect();
+            let mut tracker = slo::SloTracker::new(targets, slo_cfg.default_target_percent);
+            
+            // 从数据库恢复账本（停机间隔计为不可用）
+            if let Some(db) = &db_manager {
+                match db.lock().await.load_slo_entries().await {
+                    Ok(entries) => tracker.restore(entries),
+                    Err(e) => warn!("Failed to restore SLO ledger: {}", e),
+                }
+            }
+            
+            let tracker = Arc::new(std::sync::Mutex::new(tracker));
+            info!("📈 SLO tracking enabled (sample every {}s)", slo_cfg.sample_interval_secs);
+            
+            let tracker_task = tracker.clone();
+            let slo_cfg = slo_cfg.clone();
+            let price_cache_slo = price_cache.clone();
+            let error_tracker_slo = error_tracker.clone();
+            let db_slo = db_manager.clone();
+            let coordinator_tick_heartbeat = coordinator_tick_heartbeat.clone();
+            let calculator_scan_heartbeat = calculator_scan_heartbeat.clone();
+            let mut slo_shutdown = shutdown_tx.subscribe();
+            let api_port = options.api_port;
+            db_task_handles.push(tokio::spawn(async move {
+                use std::sync::atomic::Ordering;
+                let mut ticker = interval(Duration::from_secs(slo_cfg.sample_interval_secs.max(1)));
+                
+                loop {
+                    tokio::select! {
+                        _ = ticker.tick() => {}
+                        _ = slo_shutdown.recv() => {
+                            // 🛑 关闭前写入最后一个检查点，重启后停机间隔从此处开始计算
+                            if let Some(db) = &db_slo {
+                                let checkpoint = tracker_task.lock().unwrap().checkpoint(chrono::Utc::now());
+                                if let Err(e) = db.lock().await.record_slo_entries(&checkpoint).await {
+                                    warn!("Failed to write final SLO checkpoint: {}", e);
+                                }
+                            }
+                            break;
+                        }
+                    }
+                    let now = chrono::Utc::now();
+                    let now_ms = now.timestamp_millis() as u64;
+                    
+                    // 数据源：任一池子在阈值内有更新
+                    let freshest_age_ms = price_cache_slo
+                        .get_all_prices()
+                        .iter()
+                        .map(|p| p.last_update.elapsed().as_millis() as u64)
+                        .min();
+                    let feed_up = freshest_age_ms
+                        .map(|age| age <= slo_cfg.feed_stale_secs * 1000)
+                        .unwrap_or(false);
+                    
+                    // 缓存：至少一半池子数据新鲜
+                    let (total, fresh, _, _, _) = price_cache_slo.get_data_quality_stats();
+                    let cache_up = total > 0 && fresh * 2 >= total;
+                    
+                    // Coordinator：1秒内有tick（100ms周期）
+                    let last_tick = coordinator_tick_heartbeat.load(Ordering::Relaxed);
+                    let coordinator_up = last_tick > 0 && now_ms.saturating_sub(last_tick) <= 1000;
+                    
+                    // Calculator：10秒内完成过扫描
+                    let last_scan = calculator_scan_heartbeat.load(Ordering::Relaxed);
+                    let calculator_up = last_scan > 0 && now_ms.saturating_sub(last_scan) <= 10_000;
+                    
+                    // API：自探测端口
+                    let api_up = matches!(
+                        tokio::time::timeout(
+                            Duration::from_secs(1),
+                            tokio::net::TcpStream::connect(("127.0.0.1", api_port)),
+                        ).await,
+                        Ok(Ok(_))
+                    );
+                    
+                    let checkpoint = {
+                        let mut tracker = tracker_task.lock().unwrap();
+                        tracker.record(slo::SloComponent::FeedConnection, feed_up, now);
+                        tracker.record(slo::SloComponent::CacheHealth, cache_up, now);
+                        tracker.record(slo::SloComponent::CoordinatorTick, coordinator_up, now);
+                        tracker.record(slo::SloComponent::CalculatorScan, calculator_up, now);
+                        tracker.record(slo::SloComponent::ApiResponsiveness, api_up, now);
+                        tracker.checkpoint(now)
+                    };
+                    
+                    // 数据库：写入检查点即为可写性探测
+                    if let Some(db) = &db_slo {
+                        let writable = db.lock().await.record_slo_entries(&checkpoint).await.is_ok();
+                        tracker_task.lock().unwrap().record(slo::SloComponent::DatabaseWritability, writable, now);
+                    }
+                    
+                    let breaches = {
+                        let mut tracker = tracker_task.lock().unwrap();
+                        tracker.prune(now);
+                        tracker.evaluate_breaches(now)
+                    };
+                    for breach in breaches {
+                        error_tracker_slo.record_error(
+                            "slo_breach",
+                            format!(
+                                "{} 24h availability {:.3}% < target {:.3}%",
+                                breach.component.as_str(), breach.uptime_24h, breach.target
+                            ),
+                        ).await;
+                    }
+                }
+            }));
+            
+            Some(tracker)
+        } else {
+            None
+        };
+        
+        // Spawn HTTP API server LAST (starts in background)
+        info!("Starting HTTP API server on port {}...", options.api_port);
+        let api_handle = {
+            // ♻️ POST /reload：重新读取配置，增删池子不重启进程（需要知道配置文件路径）
+            let configured_pools = Arc::new(std::sync::RwLock::new(config.pools().to_vec()));
+            let reloader = options.config_path.clone().map(|config_path| {
+                Arc::new(pool_reload::PoolReloader::new(
+                    config_path,
+                    ws_client_for_reload,
+                    configured_pools.clone(),
+                ).with_discovered_pools(discovered_pools.iter().map(|p| p.to_pool_config()).collect())
+                    .with_pair_namer(pair_namer.clone()))
+            });
+            let api_state = api::ApiState {
+                price_cache: price_cache.clone(),
+                error_tracker: error_tracker.clone(),
+                simulator: simulator.clone(),
+                backpressure: backpressure_monitor.clone(),
+                pools: configured_pools,
+                reloader,
+                pair_namer: pair_namer.clone(),
+                owner_checks: owner_checks.clone(),
+                slo: slo_tracker.clone(),
+                sharding: shard_assignment.as_ref().map(|a| a.status()),
+                opportunity_lifecycle: scan_differ.clone(),
+                whatif: whatif_report.clone(),
+                calibration: calibrator.clone(),
+                base_token: config.calculator.clone().unwrap_or_default().base_token,
+                min_pool_liquidity_usd: router_config.min_pool_liquidity_usd,
+                discovered_pools: Arc::new(discovered_pools.iter().map(|p| (p.address.clone(), p.clone())).collect()),
+                database: db_manager.clone(),
+                metrics: metrics.clone(),
+                pool_stats: pool_stats.clone(),
+                coordinator_stats: coordinator_stats.clone(),
+                heartbeats: health::Heartbeats {
+                    websocket: ws_heartbeat,
+                    coordinator_tick: coordinator_tick_heartbeat.clone(),
+                    calculator_scan: calculator_scan_heartbeat.clone(),
+                },
+                stake_pool_reader: stake_pool_reader.clone(),
+                rpc_manager: rpc_manager.clone(),
+                notification_metrics: notification_metrics.clone(),
+                ws_cache_sizes,
+                supervisor: supervisor.clone(),
+                focus: focus_stats.clone(),
+                validation: validation_stats.clone(),
+                capture: scan_capture.clone(),
+                vault_reader: vault_reader.clone(),
+                pool_accounts: ws_pool_accounts.clone(),
+                history: price_history.clone(),
+                reference_prices: reference_prices.clone(),
+                spread_heatmap: spread_heatmap.as_ref().map(|(heatmap, _)| heatmap.clone()),
+            };
+            let api_port = options.api_port;
+            supervisor::spawn_supervised("api_server", &supervisor, move || {
+                let api_state = api_state.clone();
+                async move {
+                    if let Err(e) = api::start_api_server(api_state, api_port).await {
+                        error!("API server error: {}", e);
+                    }
+                }
+            })
+        };
+
+        let report = StartupReport {
+            monitored_pools: monitored_pools.len(),
+            discovered_pools: discovered_pools.len(),
+            initialization,
+            database: database_report,
+            phoenix_pools: phoenix_pool_count,
+            lst: lst_report,
+            websocket_url: ws_endpoints.active_url(),
+            api_port: options.api_port,
+        };
+
+        Ok(Self {
+            report,
+            price_cache,
+            metrics,
+            pool_stats,
+            db_manager,
+            shutdown_tx,
+            tasks: AppTasks {
+                websocket: ws_handle,
+                pipeline,
+                api: api_handle,
+                metrics: metrics_handle,
+                arbitrage: arbitrage_handle,
+                phoenix_refresher: phoenix_refresh_handle,
+                snapshot: snapshot_handle,
+                spread_monitor: spread_monitor_handle,
+                db_tasks: db_task_handles,
+                background: background_handles,
+            },
+        })
+    }
+
+    pub fn startup_report(&self) -> &StartupReport {
+        &self.report
+    }
+
+    pub fn price_cache(&self) -> Arc<PriceCache> {
+        self.price_cache.clone()
+    }
+
+    pub fn metrics(&self) -> Arc<MetricsCollector> {
+        self.metrics.clone()
+    }
+
+    pub fn pool_stats(&self) -> Arc<PoolStatsCollector> {
+        self.pool_stats.clone()
+    }
+
+    /// 运行直到 `shutdown` 被取消或任一关键任务退出，然后在有限时间内有序关闭
+    ///
+    /// 仍阻塞在同步 RPC 调用中的任务不等待（spawn_blocking 无法中止），进程需要有界退出时由调用方决定。
+    pub async fn run(self, shutdown: CancellationToken) -> Result<ShutdownReport> {
+        let Self { price_cache, db_manager, shutdown_tx, tasks, .. } = self;
+        let AppTasks {
+            websocket: mut ws_handle,
+            pipeline,
+            api: mut api_handle,
+            metrics: mut metrics_handle,
+            arbitrage: mut arbitrage_handle,
+            phoenix_refresher: mut phoenix_refresh_handle,
+            snapshot: snapshot_handle,
+            spread_monitor: spread_monitor_handle,
+            db_tasks: db_task_handles,
+            background: background_handles,
+        } = tasks;
+
+        let trigger = tokio::select! {
+            _ = &mut ws_handle => ShutdownTrigger::WebSocketExited,
+            _ = &mut metrics_handle => ShutdownTrigger::MetricsExited,
+            _ = &mut arbitrage_handle => ShutdownTrigger::ArbitrageExited,
+            _ = &mut api_handle => ShutdownTrigger::ApiExited,
+            _ = async {
+                match phoenix_refresh_handle.as_mut() {
+                    Some(handle) => {
+                        let _ = handle.await;
+                    }
+                    None => std::future::pending::<()>().await,
+                }
+            } => ShutdownTrigger::PhoenixRefresherExited,
+            _ = shutdown.cancelled() => ShutdownTrigger::Requested,
+        };
+        if trigger == ShutdownTrigger::Requested {
+            info!("🛑 Shutdown requested, stopping tasks...");
+        } else {
+            error!("🛑 {}, shutting down", trigger.as_str());
+        }
+
+        // 🛑 广播关闭信号，在有限时间内等待各任务完成在途工作
+        let _ = shutdown_tx.send(());
+        let deadline = tokio::time::Instant::now() + SHUTDOWN_DEADLINE;
+
+        // WebSocket：退订所有账户并关闭连接（RPC无响应时超时放弃；已退出的任务不再等待）
+        let unsubscribed = if trigger == ShutdownTrigger::WebSocketExited {
+            0
+        } else {
+            let result = tokio::time::timeout_at(deadline, &mut ws_handle).await;
+            match result {
+                Ok(Ok(count)) => count,
+                Ok(Err(e)) => {
+                    warn!("WebSocket task failed during shutdown: {}", e);
+                    0
+                }
+                Err(_) => {
+                    warn!("WebSocket unsubscribe timed out");
+                    ws_handle.abort();
+                    0
+                }
+            }
+        };
+
+        // Coordinator 停止派发 -> Calculator 完成当前扫描后退出
+        if tokio::time::timeout_at(deadline, async {
+            let _ = pipeline.coordinator.await;
+            let _ = pipeline.calculator.await;
+        }).await.is_err() {
+            warn!("Coordinator/Calculator did not stop before the shutdown deadline");
+        }
+
+        // 💾 等待最后一次快照写入
+        if let Some(handle) = snapshot_handle {
+            if tokio::time::timeout_at(deadline, handle).await.is_err() {
+                warn!("Price snapshot was not written before the shutdown deadline");
+            }
+        }
+        if let Some(handle) = spread_monitor_handle {
+            let _ = tokio::time::timeout_at(deadline, handle).await;
+        }
+
+        // 数据库：等待后台任务最后一次写入，然后flush并关闭连接池
+        let flushed = match &db_manager {
+            Some(db) => {
+                let _ = tokio::time::timeout_at(deadline, futures_util::future::join_all(db_task_handles)).await;
+                match tokio::time::timeout_at(deadline, db.lock()).await {
+                    Ok(db) => {
+                        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+                        let flushed = db.flush(remaining).await;
+                        db.close();
+                        flushed
+                    }
+                    Err(_) => {
+                        warn!("Database still busy at shutdown deadline, skipping flush");
+                        0
+                    }
+                }
+            }
+            None => 0,
+        };
+
+        // 其余任务没有需要落盘的状态，直接中止
+        for handle in [api_handle, metrics_handle, arbitrage_handle].into_iter()
+            .chain(phoenix_refresh_handle)
+            .chain(background_handles)
+        {
+            handle.abort();
+        }
+
+        info!("🛑 Shutdown complete: unsubscribed {} accounts, flushed {} records", unsubscribed, flushed);
+        let (cached_pools, unique_pairs) = price_cache.get_stats();
+        Ok(ShutdownReport {
+            trigger,
+            unsubscribed_accounts: unsubscribed,
+            flushed_records: flushed,
+            cached_pools,
+            unique_pairs,
+        })
+    }
+}
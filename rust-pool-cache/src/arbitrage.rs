@@ -111,34 +111,23 @@ pub fn format_opportunity(opp: &ArbitrageOpportunity) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::time::Instant;
     
     #[test]
     fn test_detect_arbitrage() {
         let pool_a = PoolPrice {
-            pool_id: "raydium_sol_usdc".to_string(),
             dex_name: "Raydium".to_string(),
-            pair: "SOL/USDC".to_string(),
             base_reserve: 0,
             quote_reserve: 0,
-            base_decimals: 9,
-            quote_decimals: 6,
-            price: 100.0,
-            last_update: Instant::now(),
             slot: 1000,
+            ..PoolPrice::for_test("raydium_sol_usdc", "SOL/USDC", 100.0)
         };
         
         let pool_b = PoolPrice {
-            pool_id: "orca_sol_usdc".to_string(),
             dex_name: "Orca".to_string(),
-            pair: "SOL/USDC".to_string(),
             base_reserve: 0,
             quote_reserve: 0,
-            base_decimals: 9,
-            quote_decimals: 6,
-            price: 101.0,
-            last_update: Instant::now(),
             slot: 1000,
+            ..PoolPrice::for_test("orca_sol_usdc", "SOL/USDC", 101.0)
         };
         
         let opp = detect_arbitrage(&pool_a, &pool_b, 0.5);
@@ -151,29 +140,19 @@ mod tests {
     #[test]
     fn test_no_arbitrage_below_threshold() {
         let pool_a = PoolPrice {
-            pool_id: "raydium_sol_usdc".to_string(),
             dex_name: "Raydium".to_string(),
-            pair: "SOL/USDC".to_string(),
             base_reserve: 0,
             quote_reserve: 0,
-            base_decimals: 9,
-            quote_decimals: 6,
-            price: 100.0,
-            last_update: Instant::now(),
             slot: 1000,
+            ..PoolPrice::for_test("raydium_sol_usdc", "SOL/USDC", 100.0)
         };
         
         let pool_b = PoolPrice {
-            pool_id: "orca_sol_usdc".to_string(),
             dex_name: "Orca".to_string(),
-            pair: "SOL/USDC".to_string(),
             base_reserve: 0,
             quote_reserve: 0,
-            base_decimals: 9,
-            quote_decimals: 6,
-            price: 100.1,
-            last_update: Instant::now(),
             slot: 1000,
+            ..PoolPrice::for_test("orca_sol_usdc", "SOL/USDC", 100.1)
         };
         
         // 0.1% difference is below 0.5% threshold
@@ -0,0 +1,420 @@
+/*!
+ * 反压信号（Backpressure）
+ *
+ * 下游（链上模拟器 / 验证器）向上游 Calculator 暴露负载因子：
+ * - 排队深度 / 队列容量
+ * - 在途模拟数 / 并发上限
+ *
+ * Calculator 每次扫描前采样负载因子，负载高时：
+ * - 提高本次扫描的 min_roi 阈值（按配置倍数）
+ * - Hybrid 模式降级为 Fast
+ * - 跳过已在等待模拟的路径签名：模拟器入队时标记、模拟结束时清除，
+ *   扫描器产出路径后立即查询（拆分优化和 ROI 过滤之前）
+ */
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::router::ArbitragePath;
+
+/// 负载等级
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum LoadLevel {
+    /// 正常：使用基础阈值
+    Normal,
+    /// 偏高：适度提高阈值
+    Elevated,
+    /// 过载：大幅提高阈值，Hybrid降级为Fast
+    High,
+}
+
+/// 反压策略（负载因子 -> 扫描参数的映射）
+#[derive(Debug, Clone)]
+pub struct BackpressurePolicy {
+    /// 是否启用反压
+    pub enabled: bool,
+    /// 进入 Elevated 的负载因子
+    pub elevated_load: f64,
+    /// 进入 High 的负载因子
+    pub high_load: f64,
+    /// Elevated 时 min_roi 倍数
+    pub elevated_roi_multiplier: f64,
+    /// High 时 min_roi 倍数
+    pub high_roi_multiplier: f64,
+    /// High 时是否将 Hybrid 降级为 Fast
+    pub downgrade_hybrid_to_fast: bool,
+    /// 模拟排队容量（用于计算队列负载）
+    pub queue_capacity: usize,
+}
+
+impl Default for BackpressurePolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            elevated_load: 0.5,
+            high_load: 0.8,
+            elevated_roi_multiplier: 1.5,
+            high_roi_multiplier: 3.0,
+            downgrade_hybrid_to_fast: true,
+            queue_capacity: 50,
+        }
+    }
+}
+
+/// 单次扫描的负载记录
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanMetrics {
+    /// 扫描前采样的负载因子（0.0 - 1.0）
+    pub load_factor: f64,
+    /// 负载等级
+    pub load_level: LoadLevel,
+    /// 基础 min_roi
+    pub base_min_roi_percent: f64,
+    /// 本次扫描实际使用的 min_roi
+    pub effective_min_roi_percent: f64,
+    /// 实际使用的路由模式
+    pub mode: String,
+    /// 是否发生 Hybrid -> Fast 降级
+    pub mode_downgraded: bool,
+    /// 因等待模拟被跳过的路径数
+    pub skipped_pending: usize,
+    /// 最终输出的路径数
+    pub paths_found: usize,
+    /// 记录时间
+    pub timestamp: String,
+}
+
+/// 反压监视器（下游更新，上游采样）
+pub struct BackpressureMonitor {
+    policy: BackpressurePolicy,
+    /// 排队中的模拟请求数
+    queue_depth: AtomicUsize,
+    /// 在途模拟数
+    in_flight: AtomicUsize,
+    /// 并发上限（由模拟器注册）
+    in_flight_limit: AtomicUsize,
+    /// 累计扫描数 / 被提高阈值的扫描数
+    total_scans: AtomicU64,
+    throttled_scans: AtomicU64,
+    /// 最近一次扫描记录
+    last_scan: Mutex<Option<ScanMetrics>>,
+    /// 等待模拟的路径签名键（`PathSignature::key`）-> 持有标记的模拟数
+    pending: Mutex<HashMap<u64, usize>>,
+}
+
+impl BackpressureMonitor {
+    /// 创建新的监视器
+    pub fn new(policy: BackpressurePolicy) -> Self {
+        Self {
+            policy,
+            queue_depth: AtomicUsize::new(0),
+            in_flight: AtomicUsize::new(0),
+            in_flight_limit: AtomicUsize::new(0),
+            total_scans: AtomicU64::new(0),
+            throttled_scans: AtomicU64::new(0),
+            last_scan: Mutex::new(None),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 获取策略
+    pub fn policy(&self) -> &BackpressurePolicy {
+        &self.policy
+    }
+
+    /// 注册并发上限（模拟器 max_concurrent）
+    pub fn set_in_flight_limit(&self, limit: usize) {
+        self.in_flight_limit.store(limit, Ordering::Relaxed);
+    }
+
+    /// 新增排队请求
+    pub fn enqueue(&self, count: usize) {
+        self.queue_depth.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// 请求出队（饱和减法，避免下溢）
+    pub fn dequeue(&self, count: usize) {
+        let _ = self.queue_depth.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |depth| {
+            Some(depth.saturating_sub(count))
+        });
+    }
+
+    /// 开始一次模拟，返回的 guard 在 drop 时自动减少在途计数
+    pub fn begin_simulation(self: &Arc<Self>) -> InFlightGuard {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard {
+            monitor: Arc::clone(self),
+        }
+    }
+
+    /// 标记路径签名为"等待模拟"，返回的 guard 在模拟结束（drop）时清除标记
+    pub fn mark_pending(self: &Arc<Self>, keys: impl IntoIterator<Item = u64>) -> PendingGuard {
+        let keys: Vec<u64> = keys.into_iter().collect();
+        let mut pending = self.pending.lock().unwrap();
+        for key in &keys {
+            *pending.entry(*key).or_insert(0) += 1;
+        }
+        PendingGuard {
+            monitor: Arc::clone(self),
+            keys,
+        }
+    }
+
+    /// 路径签名是否仍在等待模拟
+    pub fn is_pending(&self, key: u64) -> bool {
+        self.pending.lock().unwrap().contains_key(&key)
+    }
+
+    /// 当前等待模拟的路径签名数
+    pub fn pending_count(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    pub fn queue_depth(&self) -> usize {
+        self.queue_depth.load(Ordering::Relaxed)
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// 负载因子：max(排队深度/容量, 在途/上限)，限制在 [0, 1]
+    pub fn load_factor(&self) -> f64 {
+        let queue_load = if self.policy.queue_capacity > 0 {
+            self.queue_depth() as f64 / self.policy.queue_capacity as f64
+        } else {
+            0.0
+        };
+
+        let limit = self.in_flight_limit.load(Ordering::Relaxed);
+        let in_flight_load = if limit > 0 {
+            self.in_flight() as f64 / limit as f64
+        } else {
+            0.0
+        };
+
+        queue_load.max(in_flight_load).clamp(0.0, 1.0)
+    }
+
+    /// 负载因子 -> 负载等级
+    pub fn level_for(&self, load_factor: f64) -> LoadLevel {
+        if !self.policy.enabled {
+            LoadLevel::Normal
+        } else if load_factor >= self.policy.high_load {
+            LoadLevel::High
+        } else if load_factor >= self.policy.elevated_load {
+            LoadLevel::Elevated
+        } else {
+            LoadLevel::Normal
+        }
+    }
+
+    /// 根据负载等级计算本次扫描的 min_roi
+    pub fn adaptive_min_roi(&self, base_min_roi: f64, level: LoadLevel) -> f64 {
+        match level {
+            LoadLevel::Normal => base_min_roi,
+            LoadLevel::Elevated => base_min_roi * self.policy.elevated_roi_multiplier,
+            LoadLevel::High => base_min_roi * self.policy.high_roi_multiplier,
+        }
+    }
+
+    /// 是否应将 Hybrid 降级为 Fast
+    pub fn should_downgrade_hybrid(&self, level: LoadLevel) -> bool {
+        self.policy.downgrade_hybrid_to_fast && level == LoadLevel::High
+    }
+
+    /// 记录扫描结果
+    pub fn record_scan(&self, metrics: ScanMetrics) {
+        self.total_scans.fetch_add(1, Ordering::Relaxed);
+        if metrics.load_level != LoadLevel::Normal {
+            self.throttled_scans.fetch_add(1, Ordering::Relaxed);
+        }
+        *self.last_scan.lock().unwrap() = Some(metrics);
+    }
+
+    /// 最近一次扫描记录
+    pub fn last_scan(&self) -> Option<ScanMetrics> {
+        self.last_scan.lock().unwrap().clone()
+    }
+
+    /// 状态快照（用于 /status）
+    pub fn status(&self) -> BackpressureStatus {
+        let load_factor = self.load_factor();
+        BackpressureStatus {
+            enabled: self.policy.enabled,
+            load_factor,
+            load_level: self.level_for(load_factor),
+            queue_depth: self.queue_depth(),
+            queue_capacity: self.policy.queue_capacity,
+            in_flight: self.in_flight(),
+            in_flight_limit: self.in_flight_limit.load(Ordering::Relaxed),
+            total_scans: self.total_scans.load(Ordering::Relaxed),
+            throttled_scans: self.throttled_scans.load(Ordering::Relaxed),
+            pending_paths: self.pending_count(),
+            last_scan: self.last_scan(),
+        }
+    }
+}
+
+/// 在途模拟 guard
+pub struct InFlightGuard {
+    monitor: Arc<BackpressureMonitor>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        let _ = self.monitor.in_flight.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+            Some(n.saturating_sub(1))
+        });
+    }
+}
+
+/// 等待模拟标记 guard
+pub struct PendingGuard {
+    monitor: Arc<BackpressureMonitor>,
+    keys: Vec<u64>,
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        let mut pending = self.monitor.pending.lock().unwrap();
+        for key in &self.keys {
+            if let Some(count) = pending.get_mut(key) {
+                *count -= 1;
+                if *count == 0 {
+                    pending.remove(key);
+                }
+            }
+        }
+    }
+}
+
+/// 单次扫描的等待标记过滤（负载非 Normal 时由路由器创建并传给各扫描阶段）
+pub struct PendingFilter<'a> {
+    monitor: &'a BackpressureMonitor,
+    /// 被跳过的签名键（Hybrid 两个阶段跳过同一路径只计一次）
+    skipped: Mutex<HashSet<u64>>,
+}
+
+impl<'a> PendingFilter<'a> {
+    pub fn new(monitor: &'a BackpressureMonitor) -> Self {
+        Self {
+            monitor,
+            skipped: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// 路径是否仍在等待模拟（是则计入跳过数）
+    pub fn skip(&self, path: &ArbitragePath) -> bool {
+        let key = path.signature().key();
+        let pending = self.monitor.is_pending(key);
+        if pending {
+            self.skipped.lock().unwrap().insert(key);
+        }
+        pending
+    }
+
+    /// 移除仍在等待模拟的路径
+    pub fn retain(&self, paths: &mut Vec<ArbitragePath>) {
+        paths.retain(|path| !self.skip(path));
+    }
+
+    /// 本次扫描跳过的路径数
+    pub fn skipped(&self) -> usize {
+        self.skipped.lock().unwrap().len()
+    }
+}
+
+/// 反压状态（序列化输出）
+#[derive(Debug, Clone, Serialize)]
+pub struct BackpressureStatus {
+    pub enabled: bool,
+    pub load_factor: f64,
+    pub load_level: LoadLevel,
+    pub queue_depth: usize,
+    pub queue_capacity: usize,
+    pub in_flight: usize,
+    pub in_flight_limit: usize,
+    pub total_scans: u64,
+    pub throttled_scans: u64,
+    /// 等待模拟的路径签名数
+    pub pending_paths: usize,
+    pub last_scan: Option<ScanMetrics>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saturated_monitor() -> Arc<BackpressureMonitor> {
+        let monitor = Arc::new(BackpressureMonitor::new(BackpressurePolicy::default()));
+        monitor.set_in_flight_limit(10);
+        monitor
+    }
+
+    #[test]
+    fn test_load_factor_from_queue_and_in_flight() {
+        let monitor = saturated_monitor();
+        assert_eq!(monitor.load_factor(), 0.0);
+
+        // 排队 25/50 = 0.5
+        monitor.enqueue(25);
+        assert!((monitor.load_factor() - 0.5).abs() < 1e-9);
+
+        // 在途 9/10 = 0.9（取较大值）
+        let guards: Vec<_> = (0..9).map(|_| monitor.begin_simulation()).collect();
+        assert!((monitor.load_factor() - 0.9).abs() < 1e-9);
+
+        drop(guards);
+        monitor.dequeue(100);
+        assert_eq!(monitor.queue_depth(), 0);
+        assert_eq!(monitor.in_flight(), 0);
+        assert_eq!(monitor.load_factor(), 0.0);
+    }
+
+    #[test]
+    fn test_adaptive_threshold_mapping() {
+        let monitor = saturated_monitor();
+
+        assert_eq!(monitor.level_for(0.2), LoadLevel::Normal);
+        assert_eq!(monitor.level_for(0.6), LoadLevel::Elevated);
+        assert_eq!(monitor.level_for(0.95), LoadLevel::High);
+
+        assert_eq!(monitor.adaptive_min_roi(0.3, LoadLevel::Normal), 0.3);
+        assert!((monitor.adaptive_min_roi(0.3, LoadLevel::Elevated) - 0.45).abs() < 1e-9);
+        assert!((monitor.adaptive_min_roi(0.3, LoadLevel::High) - 0.9).abs() < 1e-9);
+
+        assert!(monitor.should_downgrade_hybrid(LoadLevel::High));
+        assert!(!monitor.should_downgrade_hybrid(LoadLevel::Elevated));
+    }
+
+    #[test]
+    fn test_pending_markers_live_until_simulation_ends() {
+        let monitor = saturated_monitor();
+
+        let first = monitor.mark_pending([1, 2]);
+        let second = monitor.mark_pending([2]);
+        assert!(monitor.is_pending(1) && monitor.is_pending(2));
+        assert!(!monitor.is_pending(3));
+        assert_eq!(monitor.pending_count(), 2);
+
+        // 同一签名被两次模拟标记：两次都结束后才清除
+        drop(first);
+        assert!(!monitor.is_pending(1));
+        assert!(monitor.is_pending(2));
+        drop(second);
+        assert_eq!(monitor.pending_count(), 0);
+    }
+
+    #[test]
+    fn test_disabled_policy_stays_normal() {
+        let monitor = BackpressureMonitor::new(BackpressurePolicy {
+            enabled: false,
+            ..Default::default()
+        });
+        monitor.enqueue(1000);
+        assert_eq!(monitor.level_for(monitor.load_factor()), LoadLevel::Normal);
+    }
+}
@@ -0,0 +1,91 @@
+/*!
+ * 抓取池子账户写成 golden fixture
+ *
+ * 用法：cargo run --bin capture_fixture -- <address> <pool_type> [out_dir]
+ * RPC 地址取 RPC_URL 环境变量（默认主网公共节点），输出 `<out_dir>/<pool_type>.json`（默认 ./fixtures）。
+ */
+
+use std::env;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+
+use solana_pool_cache::pool_factory::PoolFactory;
+use solana_pool_cache::pool_fixture::PoolFixture;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() < 2 {
+        eprintln!("Usage: capture_fixture <address> <pool_type> [out_dir]");
+        std::process::exit(2);
+    }
+    let address = &args[0];
+    let out_dir = PathBuf::from(args.get(2).map(|s| s.as_str()).unwrap_or("fixtures"));
+
+    // 1. 规范化 pool_type（fixture 文件按规范名称命名）
+    let pool_type = match PoolFactory::canonical_pool_type(&args[1]) {
+        Some(t) => t,
+        None => {
+            eprintln!("❌ Unknown pool_type '{}'", args[1]);
+            std::process::exit(2);
+        }
+    };
+    let pubkey = match Pubkey::from_str(address) {
+        Ok(pk) => pk,
+        Err(e) => {
+            eprintln!("❌ Invalid address {}: {}", address, e);
+            std::process::exit(2);
+        }
+    };
+
+    // 2. 拉取账户（记录 slot）
+    let rpc_url = env::var("RPC_URL").unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string());
+    println!("🔌 Using RPC URL: {}", rpc_url);
+    let rpc_client = RpcClient::new(rpc_url);
+
+    let response = match rpc_client.get_account_with_commitment(&pubkey, CommitmentConfig::confirmed()) {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!("❌ RPC error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let account = match response.value {
+        Some(account) => account,
+        None => {
+            eprintln!("❌ Account {} not found", address);
+            std::process::exit(1);
+        }
+    };
+    let owner = account.owner.to_string();
+    println!("✅ Account fetched at slot {}: {} bytes, owner {}", response.context.slot, account.data.len(), owner);
+
+    // 3. 抓取前先确认能解析、owner 正确（不阻止写入，便于为失败的类型留样本）
+    if let Err(e) = PoolFactory::verify_owner(pool_type, &owner) {
+        eprintln!("⚠️  {}", e);
+    }
+    match PoolFactory::create_pool(pool_type, &account.data) {
+        Ok(pool) => {
+            println!("   Price: {:.6}  Reserves: {:?}  Decimals: {:?}", pool.calculate_price(), pool.get_reserves(), pool.get_decimals());
+        }
+        Err(e) => eprintln!("⚠️  Deserialization failed: {}", e),
+    }
+
+    // 4. 写入 fixture
+    let mut fixture = PoolFixture::new(pool_type, &account.data);
+    fixture.address = Some(address.clone());
+    fixture.owner = Some(owner);
+    fixture.slot = Some(response.context.slot);
+    fixture.captured_at = Some(chrono::Utc::now().to_rfc3339());
+
+    match fixture.save(&out_dir) {
+        Ok(path) => println!("💾 Fixture written to {}", path.display()),
+        Err(e) => {
+            eprintln!("❌ {:#}", e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+/*!
+ * 识别未知池子账户
+ *
+ * 用法：cargo run --bin pool-probe -- <address>
+ * RPC 地址取 RPC_URL 环境变量（默认主网公共节点）。拉取账户后用每个已知反序列化器尝试解析，
+ * 打印解析结果与账户大小检查，最后给出最佳匹配的 config.toml 片段。
+ */
+
+use std::env;
+use std::str::FromStr;
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+
+use solana_pool_cache::utils::PoolProbeReport;
+
+fn main() {
+    let address = match env::args().nth(1) {
+        Some(address) => address,
+        None => {
+            eprintln!("Usage: pool-probe <address>");
+            std::process::exit(2);
+        }
+    };
+    let pubkey = match Pubkey::from_str(&address) {
+        Ok(pk) => pk,
+        Err(e) => {
+            eprintln!("❌ Invalid address {}: {}", address, e);
+            std::process::exit(2);
+        }
+    };
+
+    // 1. 拉取账户
+    let rpc_url = env::var("RPC_URL").unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string());
+    println!("🔌 Using RPC URL: {}", rpc_url);
+    let rpc_client = RpcClient::new(rpc_url);
+
+    let response = match rpc_client.get_account_with_commitment(&pubkey, CommitmentConfig::confirmed()) {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!("❌ RPC error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let account = match response.value {
+        Some(account) => account,
+        None => {
+            eprintln!("❌ Account {} not found", address);
+            std::process::exit(1);
+        }
+    };
+    println!("✅ Account fetched at slot {}\n", response.context.slot);
+
+    // 2. 逐个反序列化器探测
+    let report = PoolProbeReport::probe(&address, &account.owner.to_string(), &account.data);
+    print!("{}", report.render());
+
+    if report.best_match().is_none() {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,291 @@
+/*!
+ * 回放记录的池子更新，评估路由器 / 配置改动
+ *
+ * 用法：cargo run --bin replay -- <config.toml> <from> <to> [--speed <N|max>] [--run <name>]
+ *
+ * 从 pool_update_history 按时间顺序读取 [from, to) 的更新（`[database] record_pool_updates = true`
+ * 时由实时进程记录），写回 PriceCache 并驱动 Coordinator -> Calculator。发现的机会写入
+ * replayed_opportunities（按 --run 标记），不碰实时表。WebSocket / RPC / 模拟器全部不启动。
+ * 时间为 UTC（RFC 3339 或 YYYY-MM-DD[ HH:MM:SS]），--speed 默认 1（实时），10 为 10 倍速，max 不等待。
+ * 注意：加速回放时数据年龄按回放墙钟计算，验证器的新鲜度检查比实时宽松。
+ */
+
+use std::env;
+use std::sync::atomic::AtomicI64;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+use tracing_subscriber::EnvFilter;
+
+use solana_pool_cache::circuit_breaker::CircuitBreaker;
+use solana_pool_cache::config::{CalculatorConfig, Config};
+use solana_pool_cache::coordinator::CoordinatorConfig;
+use solana_pool_cache::database::{DatabaseConfig, DatabaseManager, OpportunityContext};
+use solana_pool_cache::execution_cost::{self, ExecutionCostModel};
+use solana_pool_cache::opportunity_merger::OpportunityMerger;
+use solana_pool_cache::opportunity_validator::{OpportunityValidator, ValidationResult, ValidatorConfig};
+use solana_pool_cache::pipeline;
+use solana_pool_cache::price_cache::PriceCache;
+use solana_pool_cache::price_oracle::PriceOracle;
+use solana_pool_cache::replay::{parse_timestamp, DatabaseUpdateSource, ReplaySpeed, Replayer};
+use solana_pool_cache::router_advanced::{AdvancedRouter, AdvancedRouterConfig};
+use solana_pool_cache::scan_tiers;
+use solana_pool_cache::supervisor::Supervisor;
+use solana_pool_cache::{fee_registry, pool_mints};
+
+struct ReplayArgs {
+    config_path: String,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    speed: ReplaySpeed,
+    run: String,
+}
+
+fn parse_args() -> Result<ReplayArgs> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() < 3 {
+        bail!("Usage: replay <config.toml> <from> <to> [--speed <N|max>] [--run <name>]");
+    }
+    let from = parse_timestamp(&args[1])?;
+    let to = parse_timestamp(&args[2])?;
+    if to <= from {
+        bail!("Replay range is empty: {} >= {}", from, to);
+    }
+
+    let mut speed = ReplaySpeed::Scaled(1.0);
+    let mut run = None;
+    let mut rest = args[3..].iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().with_context(|| format!("{} requires a value", flag))?;
+        match flag.as_str() {
+            "--speed" => speed = ReplaySpeed::parse(value)?,
+            "--run" => run = Some(value.clone()),
+            _ => bail!("Unknown option {}", flag),
+        }
+    }
+
+    Ok(ReplayArgs {
+        config_path: args[0].clone(),
+        from,
+        to,
+        speed,
+        run: run.unwrap_or_else(|| format!(
+            "replay-{}-{}@{}",
+            from.format("%Y%m%dT%H%M%S"),
+            to.format("%Y%m%dT%H%M%S"),
+            Utc::now().format("%Y%m%dT%H%M%S")
+        )),
+    })
+}
+
+/// 回放用的 Calculator：按档位扫描 → 验证 → 去重 → 写 replayed_opportunities
+struct ReplayCalculator {
+    router: AdvancedRouter,
+    oracle: PriceOracle,
+    /// ⛽ 执行成本：优先费用配置的静态值，起始代币汇率每次扫描按回放价格换算
+    execution_cost: Arc<ExecutionCostModel>,
+    cost_tokens: Vec<String>,
+    calculator_config: CalculatorConfig,
+    merger: OpportunityMerger,
+    validator: OpportunityValidator,
+    db: Arc<DatabaseManager>,
+    run: String,
+    market_clock: Arc<AtomicI64>,
+    scans: u64,
+    recorded: u64,
+}
+
+impl ReplayCalculator {
+    /// `scope` 与生产 Calculator 一致：事件触发只扫描经过触发交易对代币的路径
+    async fn scan(&mut self, trigger_source: &str, scope: Option<&[String]>) {
+        let tiers = scan_tiers::resolve_tiers(&self.calculator_config, &self.oracle);
+        if tiers.is_empty() {
+            debug!("💵 No USD price for {} yet, skipping scan", self.calculator_config.base_token);
+            return;
+        }
+        let Some(market_time) = Replayer::market_time(&self.market_clock) else {
+            return;
+        };
+        self.scans += 1;
+        execution_cost::refresh_sol_rates(&self.execution_cost, &self.oracle, &self.cost_tokens);
+
+        let mut tier_results = Vec::with_capacity(tiers.len());
+        for tier in &tiers {
+            let tier_paths = match scope {
+                Some(tokens) => self.router.find_scoped_routes(tier.amount, tokens).await,
+                None => self.router.find_optimal_routes(tier.amount).await,
+            };
+            tier_results.push((*tier, tier_paths));
+        }
+        let paths = self.router.ranker().rank(scan_tiers::merge_tiers(tier_results), |p| &p.path);
+        // 先验证再去重：被拒绝的路径不进入去重表（与生产 calculator_loop 一致）
+        let validated: Vec<_> = paths.iter()
+            .map(|p| p.path.base_path.clone())
+            .filter_map(|path| match self.validator.validate_path(&path) {
+                ValidationResult::Valid { confidence_score, .. } => Some((path, confidence_score)),
+                rejected => {
+                    debug!("🔀 Opportunity {} rejected: {:?}", path.signature(), rejected);
+                    None
+                }
+            })
+            .collect();
+        let new_paths = self.merger.filter_new(validated, Instant::now(), |(path, _)| path);
+
+        for (path, confidence_score) in new_paths {
+            info!(
+                "🎞️ [{}] {:.4}% ROI via {} (triggered by {})",
+                market_time.format("%Y-%m-%d %H:%M:%S%.3f"), path.roi_percent, path.signature(), trigger_source
+            );
+            let context = OpportunityContext {
+                trigger_source: trigger_source.to_string(),
+                confidence_score: Some(confidence_score),
+                ..Default::default()
+            };
+            match self.db.record_replayed_opportunity(&self.run, market_time, &path, &context).await {
+                Ok(()) => self.recorded += 1,
+                Err(e) => warn!("Failed to record replayed opportunity {}: {}", path.signature(), e),
+            }
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .init();
+
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("❌ {:#}", e);
+            std::process::exit(2);
+        }
+    };
+    let config = Config::load_from_file(&args.config_path)?;
+    let db_config = config.database.as_ref()
+        .filter(|db| db.enabled && !db.url.is_empty())
+        .context("Replay reads recorded updates from [database]; enable it with a url")?;
+
+    // 只建回放用的表，不重建实时表
+    let db = DatabaseManager::new_for_replay(DatabaseConfig {
+        enabled: true,
+        url: db_config.url.clone(),
+        record_opportunities: false,
+        record_pool_updates: false,
+        record_performance: false,
+        opportunity_lifecycle_ttl_secs: db_config.opportunity_lifecycle_ttl_secs,
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("Failed to connect to database: {}", e))?;
+    let db = Arc::new(db);
+
+    // 池子级配置（手续费覆盖 / mint 方向 / 新鲜度预算 / 熔断阈值），与实时进程一致
+    fee_registry::global().load_from_pools(config.pools());
+    let mint_registry = pool_mints::global();
+    if let Some(lst_config) = &config.lst_detector {
+        for token in &lst_config.tokens {
+            mint_registry.register_symbol(&token.mint, &token.symbol);
+        }
+    }
+    mint_registry.load_from_pools(config.pools());
+    let breaker_config = config.circuit_breaker.clone().unwrap_or_default();
+    let price_cache = Arc::new(PriceCache::new().with_circuit_breaker(CircuitBreaker::new(&breaker_config)));
+    price_cache.staleness().load_from_pools(config.pools());
+    price_cache.circuit_breaker().load_from_pools(config.pools());
+
+    let router_config = AdvancedRouterConfig::from_config(config.router.as_ref());
+    let dedup_ttl = config.router.as_ref().map(|r| r.opportunity_dedup_ttl_secs).unwrap_or(30);
+    let (shutdown_tx, _) = broadcast::channel::<()>(4);
+    let mut replayer_shutdown = shutdown_tx.subscribe();
+
+    info!(
+        "🎞️ Replay {}: {} -> {} at {:?} (router {:?}, min ROI {:.3}%)",
+        args.run, args.from, args.to, args.speed, router_config.mode, router_config.min_roi_percent
+    );
+
+    let price_change_threshold = config.logging.as_ref()
+        .map(|l| l.price_change_threshold_percent)
+        .unwrap_or(1.0);
+    let validation_config = config.validation.clone().unwrap_or_default();
+    let (pipeline, mut replayer) = {
+        // Replayer 需要管线的 event_tx，Calculator 需要 Replayer 的市场时钟：先建时钟再接线
+        let market_clock = Arc::new(AtomicI64::new(0));
+        let execution_cost = Arc::new(ExecutionCostModel::new(config.execution_cost.clone().unwrap_or_default()));
+        let calculator_config = config.calculator.clone().unwrap_or_default();
+        let mut cost_tokens = router_config.token_filter.start_tokens.clone();
+        cost_tokens.push(calculator_config.base_token.clone());
+        cost_tokens.sort();
+        cost_tokens.dedup();
+        let calculator = ReplayCalculator {
+            router: AdvancedRouter::new(price_cache.clone(), router_config.clone())
+                .with_execution_cost(execution_cost.clone())
+                .with_price_oracle(Arc::new(
+                    PriceOracle::new(price_cache.clone(), &config.price_oracle.clone().unwrap_or_default()),
+                )),
+            oracle: PriceOracle::new(price_cache.clone(), &config.price_oracle.clone().unwrap_or_default()),
+            execution_cost,
+            cost_tokens,
+            calculator_config,
+            merger: OpportunityMerger::new().with_ttl(Duration::from_secs(dedup_ttl)),
+            validator: OpportunityValidator::new(price_cache.clone(), ValidatorConfig {
+                max_hop_impact_percent: validation_config.max_single_hop_impact_percent
+                    .or(config.router.as_ref().map(|r| r.max_hop_impact_percent))
+                    .unwrap_or(2.0),
+                allow_pool_reuse: config.router.as_ref().is_some_and(|r| r.allow_pool_reuse),
+                ..ValidatorConfig::from(&validation_config)
+            }),
+            db: db.clone(),
+            run: args.run.clone(),
+            market_clock: market_clock.clone(),
+            scans: 0,
+            recorded: 0,
+        };
+        let supervisor = Supervisor::default();
+        let pipeline = pipeline::spawn(CoordinatorConfig::default(), &shutdown_tx, None, &supervisor, calculator, |mut tasks, mut calculator| async move {
+            while let Some(task) = tasks.next().await {
+                calculator.scan(&task.trigger_source, task.scope.as_deref()).await;
+            }
+            // 回放结束后按最终状态再扫描一次
+            calculator.scan("replay-end", None).await;
+            info!("🧮 Replay calculator: {} scans, {} opportunities recorded", calculator.scans, calculator.recorded);
+        });
+        let replayer = Replayer::new(price_cache.clone(), pipeline.event_tx.clone(), args.speed, price_change_threshold)
+            .with_market_clock(market_clock);
+        (pipeline, replayer)
+    };
+
+    let ctrl_c_tx = shutdown_tx.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            info!("🛑 Ctrl+C received, stopping replay");
+            let _ = ctrl_c_tx.send(());
+        }
+    });
+
+    let mut source = DatabaseUpdateSource::new(db.clone(), args.from, args.to);
+    let summary = replayer.run(&mut source, &mut replayer_shutdown).await?;
+    info!(
+        "🎞️ Replayed {} updates across {} pools ({} -> {}) in {:.1}s{}",
+        summary.updates,
+        summary.pools,
+        summary.first_update.map(|t| t.to_string()).unwrap_or_else(|| "-".to_string()),
+        summary.last_update.map(|t| t.to_string()).unwrap_or_else(|| "-".to_string()),
+        summary.elapsed.as_secs_f64(),
+        if summary.interrupted { " (interrupted)" } else { "" }
+    );
+
+    // 停止管线：Calculator 完成当前扫描和最终扫描后退出
+    let _ = shutdown_tx.send(());
+    let _ = pipeline.coordinator.await;
+    let _ = pipeline.calculator.await;
+    let written = db.flush(Duration::from_secs(10)).await;
+    info!("🎞️ Replay {} finished, {} rows written", args.run, written);
+    db.close();
+
+    Ok(())
+}
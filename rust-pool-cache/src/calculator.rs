@@ -5,7 +5,7 @@
 /// 职责：
 /// 1. 接收 CalculationTask（来自 Coordinator）
 /// 2. 获取状态快照
-/// 3. 运行 Bellman-Ford 和 BFS 算法
+/// 3. 运行 Bellman-Ford 和 BFS 算法（带定向范围时只从范围内代币发起 BFS）
 /// 4. 返回套利路径
 ///
 /// 设计原则：
@@ -15,6 +15,7 @@
 /// ======================================================================
 
 use crate::state_layer::StateLayer;
+use crate::execution_cost::ExecutionCostModel;
 use crate::router_bellman_ford::BellmanFordScanner;
 use crate::router_bfs::BfsScanner;
 use crate::router::{ArbitragePath};
@@ -68,8 +69,9 @@ pub struct Calculator {
 impl Calculator {
     /// 创建新的计算器
     pub fn new(worldview: Arc<dyn StateLayer>, config: CalculatorConfig) -> Self {
-        let bf_scanner = BellmanFordScanner::new(config.bf_max_hops, config.min_roi_percent);
-        let bfs_scanner = BfsScanner::new(config.bfs_max_hops, config.min_roi_percent);
+        let execution_cost = ExecutionCostModel::shared_default();
+        let bf_scanner = BellmanFordScanner::new(config.bf_max_hops, config.min_roi_percent, execution_cost.clone());
+        let bfs_scanner = BfsScanner::new(config.bfs_max_hops, config.min_roi_percent, execution_cost);
 
         Self {
             worldview,
@@ -79,6 +81,13 @@ impl Calculator {
         }
     }
 
+    /// ⛽ 两个扫描器共用的执行成本模型
+    pub fn with_execution_cost(mut self, execution_cost: Arc<ExecutionCostModel>) -> Self {
+        self.bf_scanner = self.bf_scanner.with_execution_cost(execution_cost.clone());
+        self.bfs_scanner = self.bfs_scanner.with_execution_cost(execution_cost);
+        self
+    }
+
     /// 执行计算任务
     ///
     /// 注意：此方法应在 spawn_blocking 中调用
@@ -113,10 +122,13 @@ impl Calculator {
         // 2. 运行算法
         let mut all_paths = Vec::new();
 
-        // 2.1 BFS（快速 2-3 跳）
+        // 2.1 BFS（快速 2-3 跳；定向任务只从触发交易对的代币发起）
         if self.config.enable_bfs {
             let bfs_start = Instant::now();
-            let bfs_paths = self.run_bfs(&snapshot);
+            let bfs_paths = match &task.scope {
+                Some(tokens) => self.bfs_scanner.find_opportunities_from(&snapshot, 10.0, tokens),
+                None => self.run_bfs(&snapshot),
+            };
             let bfs_time = bfs_start.elapsed();
 
             debug!(
@@ -127,8 +139,8 @@ impl Calculator {
             all_paths.extend(bfs_paths);
         }
 
-        // 2.2 Bellman-Ford（深度 4-6 跳）
-        if self.config.enable_bf {
+        // 2.2 Bellman-Ford（深度 4-6 跳；无法按代币播种，定向任务跳过，留给时钟全量扫描）
+        if self.config.enable_bf && task.scope.is_none() {
             let bf_start = Instant::now();
             let bf_paths = self.run_bellman_ford(&snapshot);
             let bf_time = bf_start.elapsed();
@@ -202,18 +214,21 @@ mod tests {
     use crate::price_cache::{PoolPrice, PriceCache};
     use crate::state_layer::StateLayer;
 
+    /// 测试代币 A–D 没有 USD 价格，给执行成本模型一个汇率才能定价上报
+    fn priced_test_tokens() -> Arc<ExecutionCostModel> {
+        crate::router_fixture::priced_execution_cost(&[("A", 150.0), ("B", 150.0), ("C", 150.0), ("D", 150.0)])
+    }
+
+    /// 深池子（价格冲击可忽略），储备比例与价格一致
     fn create_test_pool_price(pool_id: &str, pair: &str, price: f64) -> PoolPrice {
+        let base_reserve = 1_000_000_000_000u64;
         PoolPrice {
-            pool_id: pool_id.to_string(),
             dex_name: "Test".to_string(),
-            pair: pair.to_string(),
-            base_reserve: 1000,
-            quote_reserve: 1000,
+            base_reserve,
+            quote_reserve: (base_reserve as f64 * price) as u64,
             base_decimals: 6,
-            quote_decimals: 6,
-            price,
-            last_update: Instant::now(),
             slot: 1000,
+            ..PoolPrice::for_test(pool_id, pair, price)
         }
     }
 
@@ -224,18 +239,21 @@ mod tests {
         // 添加测试数据
         worldview.update_price(create_test_pool_price("pool1", "A/B", 1.0));
         worldview.update_price(create_test_pool_price("pool2", "B/C", 1.0));
-        worldview.update_price(create_test_pool_price("pool3", "C/A", 1.01)); // 套利机会
+        worldview.update_price(create_test_pool_price("pool3", "C/A", 1.02)); // 套利机会（扣除 3 × 0.25% 手续费后仍高于 0.3%）
 
         let calculator = Calculator::new(
             worldview,
             CalculatorConfig::default()
-        );
+        ).with_execution_cost(priced_test_tokens());
 
         let task = CalculationTask {
             trigger_type: crate::coordinator::TriggerType::Clock,
             trigger_source: "test".to_string(),
             price_change_percent: None,
             created_at: Instant::now(),
+            scope: None,
+            event_timestamp: None,
+            ingest: None,
         };
 
         let paths = calculator.calculate(&task);
@@ -258,13 +276,17 @@ mod tests {
             ..Default::default()
         };
 
-        let calculator = Calculator::new(worldview, config);
+        let calculator = Calculator::new(worldview, config)
+            .with_execution_cost(priced_test_tokens());
 
         let task = CalculationTask {
             trigger_type: crate::coordinator::TriggerType::Clock,
             trigger_source: "test".to_string(),
             price_change_percent: None,
             created_at: Instant::now(),
+            scope: None,
+            event_timestamp: None,
+            ingest: None,
         };
 
         let paths = calculator.calculate(&task);
@@ -289,13 +311,17 @@ mod tests {
             ..Default::default()
         };
 
-        let calculator = Calculator::new(worldview, config);
+        let calculator = Calculator::new(worldview, config)
+            .with_execution_cost(priced_test_tokens());
 
         let task = CalculationTask {
             trigger_type: crate::coordinator::TriggerType::Clock,
             trigger_source: "test".to_string(),
             price_change_percent: None,
             created_at: Instant::now(),
+            scope: None,
+            event_timestamp: None,
+            ingest: None,
         };
 
         let paths = calculator.calculate(&task);
@@ -304,6 +330,39 @@ mod tests {
         assert!(!paths.is_empty(), "Bellman-Ford should find 4-hop arbitrage");
     }
 
+    #[test]
+    fn test_calculator_scoped_task() {
+        let worldview = Arc::new(PriceCache::new());
+        for pool in crate::router_fixture::triangle(1.015).build() {
+            // 一致快照忽略 slot 0
+            worldview.update_price(PoolPrice { slot: 1000, ..pool });
+        }
+
+        let config = CalculatorConfig {
+            min_roi_percent: 0.1,
+            ..Default::default()
+        };
+        let calculator = Calculator::new(worldview, config)
+            .with_execution_cost(crate::router_fixture::priced_fixture_tokens());
+        let task = |scope: &[&str]| CalculationTask {
+            trigger_type: crate::coordinator::TriggerType::Event,
+            trigger_source: "test".to_string(),
+            price_change_percent: Some(0.01),
+            created_at: Instant::now(),
+            scope: Some(scope.iter().map(|t| t.to_string()).collect()),
+            event_timestamp: None,
+            ingest: None,
+        };
+
+        // 环路经过 FXB：只从 FXB 发起，BF 不参与
+        let paths = calculator.calculate(&task(&["FXB", "FXZ"]));
+        assert!(!paths.is_empty());
+        assert!(paths.iter().all(|p| p.start_token == "FXB"));
+
+        // 范围内代币不在图中：没有种子
+        assert!(calculator.calculate(&task(&["FXY", "FXZ"])).is_empty());
+    }
+
     #[test]
     fn test_calculator_deduplication() {
         let worldview = Arc::new(PriceCache::new());
@@ -323,6 +382,9 @@ mod tests {
             trigger_source: "test".to_string(),
             price_change_percent: None,
             created_at: Instant::now(),
+            scope: None,
+            event_timestamp: None,
+            ingest: None,
         };
 
         let paths = calculator.calculate(&task);
@@ -0,0 +1,379 @@
+/*!
+ * 🧮 Calculator 主循环：Coordinator 派发的计算任务 → 扫描 → 差异 / 去重 / 验证 → 上报
+ *
+ * 每个任务按美元档位扫描（事件触发时只扫经过触发交易对代币的路径），结果依次经过：
+ * 扫描间差异（`ScanDiffer`）→ 跨扫描去重 → 库存规划 → 逐跳验证 → 上报前重新定价，
+ * 通过的机会写库、结构化输出、通知；差异事件交给告警分发。
+ *
+ * 🛟 依赖与跨扫描状态放在 `CalculatorState` 里，由管线持有：panic 重启后新的主循环
+ * 拿到同一份状态（去重窗口、what-if 计数、告警冷却都保留）。
+ */
+
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::{mpsc, Mutex, OwnedMutexGuard};
+use tracing::{debug, info, warn};
+
+use crate::alerts::AlertDispatcher;
+use crate::config::CalculatorConfig;
+use crate::database::DatabaseManager;
+use crate::inventory::Inventory;
+use crate::metrics::MetricsCollector;
+use crate::notifications::NotificationSender;
+use crate::onchain_simulator::OnChainSimulator;
+use crate::opportunity_merger::OpportunityMerger;
+use crate::opportunity_output::OpportunityOutput;
+use crate::opportunity_validator::OpportunityValidator;
+use crate::pipeline::CalculationTasks;
+use crate::price_cache::PriceCache;
+use crate::price_oracle::PriceOracle;
+use crate::router::{ArbitragePath, PathSignature};
+use crate::router_advanced::AdvancedRouter;
+use crate::router_direct::DirectArbTable;
+use crate::scan_diff::ScanDiffer;
+use crate::{
+    alerts, coordinator, database, latency_budget, notifications, opportunity_output, opportunity_validator,
+    router_split_optimizer, scan_diff, scan_tiers,
+};
+
+/// Calculator 的依赖与跨扫描状态
+pub struct CalculatorState {
+    /// 扫描金额档位 / base_token
+    pub config: CalculatorConfig,
+    pub price_oracle: Arc<PriceOracle>,
+    pub router: Arc<AdvancedRouter>,
+    /// 📈 扫描耗时 / 延迟预算
+    pub metrics: Arc<MetricsCollector>,
+    /// 📈 SLO：最近完成扫描时间（unix 毫秒）
+    pub scan_heartbeat: Arc<AtomicU64>,
+    /// ⚡ 两跳直接套利快速通道（两次扫描之间发现的机会合并进去重）
+    pub direct_table: Option<Arc<DirectArbTable>>,
+    pub price_cache: Arc<PriceCache>,
+    /// 写库与快速通道机会的 ROI 下限
+    pub min_roi_percent: f64,
+    pub opportunity_merger: OpportunityMerger,
+    pub path_validator: OpportunityValidator,
+    pub db_manager: Option<Arc<Mutex<DatabaseManager>>>,
+    /// 🧪 交易级模拟（配置了 payer 时）
+    pub tx_simulator: Option<Arc<OnChainSimulator>>,
+    pub router_mode: String,
+    pub opportunity_output: Option<OpportunityOutput>,
+    pub notification_sender: Option<NotificationSender>,
+    /// 🧪 What-if 扫描通道（低优先级，满时跳过）
+    pub whatif_tx: Option<mpsc::Sender<f64>>,
+    pub whatif_every_n: Option<u64>,
+    pub scans_since_whatif: u64,
+    /// 🔄 扫描间差异 + 最近 N 次扫描记录（与 /scans、/opportunities/lifecycle 共享）
+    pub scan_differ: Arc<std::sync::Mutex<ScanDiffer>>,
+    pub alert_dispatcher: Option<AlertDispatcher>,
+    pub inventory: Option<Inventory>,
+}
+
+/// Calculator 主循环（`pipeline::spawn` 的 calculator），任务流结束时返回
+pub async fn run(mut tasks: CalculationTasks, mut state: OwnedMutexGuard<CalculatorState>) {
+    let CalculatorState {
+        config, price_oracle, router, metrics, scan_heartbeat, direct_table, price_cache, min_roi_percent,
+        opportunity_merger, path_validator, db_manager, tx_simulator, router_mode, opportunity_output,
+        notification_sender, whatif_tx, whatif_every_n, scans_since_whatif, scan_differ, alert_dispatcher, inventory,
+    } = &mut *state;
+    let (min_roi_percent, whatif_every_n) = (*min_roi_percent, *whatif_every_n);
+    info!("🧮 Calculator task started, waiting for tasks from Coordinator...");
+
+    // 只在两次扫描之间响应关闭信号，进行中的扫描总是完整结束
+    while let Some(task) = tasks.next().await {
+        debug!("🧮 Received calculation task: {:?} from {}", task.trigger_type, task.trigger_source);
+        // ⏱️ 延迟预算：Calculator 之前的阶段由任务携带的时间戳算出
+        let mut latency = latency_budget::LatencyBreakdown::from_task(&task, Instant::now());
+
+        // 💵 按当前价格把美元档位换算成 base_token 数量
+        let tiers = scan_tiers::resolve_tiers(config, price_oracle);
+        if tiers.is_empty() {
+            warn!("💵 No fresh USD price for base token {} and no fallback_amounts, skipping scan", config.base_token);
+            continue;
+        }
+
+        // 🎯 事件触发只扫描经过触发交易对代币的路径，时钟兜底做全量扫描
+        let scope = task.scope.as_deref();
+        match scope {
+            Some(tokens) => info!("🔍 Starting scoped arbitrage scan over {} (triggered by: {})", tokens.join(", "), task.trigger_source),
+            None => info!("🔍 Starting arbitrage scan (triggered by: {})", task.trigger_source),
+        }
+
+        // Run router scan once per amount tier
+        let scan_started = Instant::now();
+        let mut tier_results = Vec::with_capacity(tiers.len());
+        for tier in &tiers {
+            let tier_paths = match scope {
+                Some(tokens) => router.find_scoped_routes(tier.amount, tokens).await,
+                None => router.find_optimal_routes(tier.amount).await,
+            };
+            let best_roi = tier_paths.iter().map(|p| p.optimized_roi).fold(f64::NAN, f64::max);
+            info!(
+                "💵 {} tier ({:.4} {}): {} opportunities, best ROI {:.4}%",
+                tier.label(&config.base_token), tier.amount, config.base_token, tier_paths.len(),
+                if best_roi.is_nan() { 0.0 } else { best_roi }
+            );
+            tier_results.push((*tier, tier_paths));
+        }
+        // 📐 按美元期望值（而不是 ROI）排序，后续去重 / 验证 / 输出都沿用这个顺序
+        let paths = router.ranker().rank(scan_tiers::merge_tiers(tier_results), |p| &p.path);
+        let scan_duration = scan_started.elapsed();
+        latency.set_scan(scan_duration, router.take_snapshot_time());
+        metrics.record_scan_duration(scan_duration, scope.is_some());
+        let scan_latency_ms = scan_duration.as_secs_f64() * 1000.0;
+
+        let total_paths = paths.len();
+        scan_heartbeat.store(
+            chrono::Utc::now().timestamp_millis() as u64,
+            std::sync::atomic::Ordering::Relaxed,
+        );
+        info!(
+            "⏱️  {} scan completed in {:.1}ms ({} tiers), found {} opportunities",
+            if scope.is_some() { "Scoped" } else { "Full" }, scan_latency_ms, tiers.len(), total_paths
+        );
+
+        // 🔄 与上次扫描对比并记入扫描历史，状态附到上报的机会上
+        // 消失的机会按路径数据是否过期区分原因；定向扫描只覆盖部分路径，不判断消失
+        let trigger_type = format!("{:?}", task.trigger_type).to_lowercase();
+        let scan_diff::ScanOutcome { record: scan_record, events: scan_events } = scan_differ.lock().unwrap().observe(
+            scan_diff::ScanMeta {
+                scanned_at: chrono::Utc::now(),
+                duration_ms: scan_latency_ms,
+                trigger_type: trigger_type.clone(),
+                trigger_source: task.trigger_source.clone(),
+                scoped: scope.is_some(),
+            },
+            paths.iter()
+                .map(|p| scan_diff::ScanObservation::from_path(&p.path.base_path, p.path.optimized_roi, p.roi_by_amount.clone()))
+                .collect(),
+            |lifecycle| lifecycle.pool_ids.iter().any(|pool_id| {
+                price_cache.get_price(pool_id)
+                    .map(|p| p.last_update.elapsed() > Duration::from_secs(5))
+                    .unwrap_or(true)
+            }),
+        );
+        debug!(
+            "🗃️ Scan #{}: {} new, {} persisting, {} improved, {} worsened, {} gone",
+            scan_record.id, scan_record.counts.new, scan_record.counts.persisting,
+            scan_record.counts.improved, scan_record.counts.worsened, scan_record.counts.gone
+        );
+        let scan_statuses: HashMap<&str, scan_diff::EventKind> = scan_record.entries.iter()
+            .map(|entry| (entry.fingerprint.as_str(), entry.status))
+            .collect();
+
+        // ⚡ 快速通道在两次扫描之间发现的直接套利（第一个档位的美元金额换算成 quote 代币数量）
+        let direct_paths: Vec<ArbitragePath> = direct_table.as_ref()
+            .map(|table| table.take_pending())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|opportunity| {
+                let (_, quote_token) = opportunity.pair.split_once('/')?;
+                let quote_usd = price_oracle.get_usd_price(quote_token)?;
+                opportunity.to_path(price_cache, tiers[0].amount_usd? / quote_usd)
+            })
+            .filter(|path| path.roi_percent >= min_roi_percent)
+            .collect();
+        if !direct_paths.is_empty() {
+            debug!("⚡ Merging {} direct arbitrage paths from the fast path", direct_paths.len());
+        }
+
+        // 🔀 逐跳验证 → 去重（只对通过验证的路径，TTL内已记录过的跳过）→ 持久化
+        //    先去重会把被拒绝的路径也记入去重表，TTL 内即使变为有效也不再上报
+        let validation_started = Instant::now();
+        let candidates: Vec<ArbitragePath> = paths.iter().map(|p| p.path.base_path.clone()).chain(direct_paths).collect();
+        // 💼 按持有余额处理（验证在实际投入金额上进行）
+        let (candidates, capital) = match inventory.as_ref() {
+            Some(inventory) => {
+                let plan = inventory.plan(candidates, price_cache, min_roi_percent);
+                if plan.dropped > 0 {
+                    debug!("💼 {} opportunities dropped by inventory", plan.dropped);
+                }
+                (plan.paths, plan.capital)
+            }
+            None => (candidates, HashMap::new()),
+        };
+        let mut invalidated: Vec<(ArbitragePath, opportunity_validator::Revalidation)> = Vec::new();
+        let validated: Vec<(ArbitragePath, f64, opportunity_validator::Revalidation)> = candidates.into_iter()
+            .filter_map(|path| match path_validator.validate_path(&path) {
+                opportunity_validator::ValidationResult::Valid { confidence_score, .. } => {
+                    Some((path, confidence_score))
+                }
+                rejected => {
+                    debug!(
+                        "🔀 Opportunity {} rejected by {}: {:?}",
+                        path.signature(), rejected.rejected_by().unwrap_or("unknown"), rejected
+                    );
+                    None
+                }
+            })
+            // 🔁 上报前按最新缓存重新定价（发现到上报之间价格可能已经变化）
+            .filter_map(|(path, confidence_score)| {
+                let revalidation = opportunity_validator::revalidate(&path, price_cache);
+                if revalidation.is_invalidated() {
+                    debug!("🔁 Opportunity {} invalidated before reporting: {:?}", path.signature(), revalidation);
+                    invalidated.push((path, revalidation));
+                    return None;
+                }
+                Some((path, confidence_score, revalidation))
+            })
+            .collect();
+        let validated_count = validated.len();
+        let accepted = opportunity_merger.filter_new(validated, Instant::now(), |(path, _, _)| path);
+        latency.validation_us = validation_started.elapsed().as_micros() as u64;
+        metrics.record_latency_breakdown(&latency);
+
+        // 🗃️ 扫描摘要写库（按状态计数，分析长期闪烁率）
+        if let Some(db) = db_manager.clone() {
+            let record = scan_record.clone();
+            tokio::spawn(async move {
+                if let Err(e) = db.lock().await.record_scan_summary(&record).await {
+                    warn!("Failed to record scan summary #{}: {}", record.id, e);
+                }
+            });
+        }
+
+        // 🗂️ 机会生命周期：每次发现都记录（不受去重 TTL 影响），再写入重新定价结果
+        if let Some(db) = db_manager.clone().filter(|_| !paths.is_empty()) {
+            let observed: Vec<ArbitragePath> = paths.iter()
+                .map(|p| p.path.base_path.clone())
+                .collect();
+            let mut revalidations = std::mem::take(&mut invalidated);
+            revalidations.extend(accepted.iter().map(|(path, _, r)| (path.clone(), r.clone())));
+            tokio::spawn(async move {
+                let db = db.lock().await;
+                for path in &observed {
+                    if let Err(e) = db.observe_opportunity(path).await {
+                        warn!("Failed to record opportunity lifecycle {}: {}", path.fingerprint(), e);
+                    }
+                }
+                for (path, revalidation) in &revalidations {
+                    if let Err(e) = db.record_opportunity_revalidation(path, revalidation).await {
+                        warn!("Failed to record revalidation for {}: {}", path.fingerprint(), e);
+                    }
+                }
+            });
+        }
+
+        if !accepted.is_empty() {
+            let degraded = accepted.iter()
+                .filter(|(_, _, r)| matches!(r, opportunity_validator::Revalidation::Degraded { .. }))
+                .count();
+            // ⏱️ 端到端：收到触发通知（时钟触发：任务创建）→ 上报
+            let latency = latency.complete(task.origin(), Instant::now());
+            metrics.record_detection_latency(
+                Duration::from_micros(latency.end_to_end_us),
+                task.trigger_type == coordinator::TriggerType::Event,
+            );
+            info!(
+                "🔥 {} opportunities: {} validated, {} new, {} degraded on revalidation (triggered by: {}, detected in {:.2}ms)",
+                total_paths, validated_count, accepted.len(), degraded, task.trigger_source,
+                latency.end_to_end_us as f64 / 1000.0
+            );
+
+            // 🧪 本次扫描重新定价后最好的机会做交易级模拟（不阻塞下一次扫描）
+            let top = accepted.iter().max_by(|a, b| {
+                let roi = |r: &opportunity_validator::Revalidation| r.revalidated_roi().unwrap_or(f64::MIN);
+                roi(&a.2).total_cmp(&roi(&b.2))
+            });
+            if let (Some(simulator), Some((path, _, _))) = (&tx_simulator, top) {
+                let simulator = simulator.clone();
+                let path = path.clone();
+                let cache = price_cache.clone();
+                let db = db_manager.clone();
+                tokio::spawn(async move {
+                    let outcome = simulator.simulate_path(path.clone(), cache).await;
+                    if let Some(db) = db {
+                        if let Err(e) = db.lock().await.record_transaction_simulation(&path, &outcome).await {
+                            warn!("Failed to record transaction simulation {}: {}", path.signature(), e);
+                        }
+                    }
+                });
+            }
+
+            // 发现上下文（写库与结构化输出共用）
+            let contexts: Vec<database::OpportunityContext> = accepted.iter()
+                .map(|(path, confidence_score, revalidation)| database::OpportunityContext {
+                    trigger_type: trigger_type.clone(),
+                    trigger_source: task.trigger_source.clone(),
+                    trigger_price_change_percent: task.price_change_percent,
+                    scan_latency_ms,
+                    confidence_score: Some(*confidence_score),
+                    revalidated_roi_percent: revalidation.revalidated_roi(),
+                    revalidation_status: Some(revalidation.status().to_string()),
+                    // 💲 净利润按当前美元价格换算（没有新鲜价格时只记录原生代币利润）
+                    profit_usd: price_oracle.get_usd_price(&path.start_token).map(|price| path.net_profit * price),
+                    latency: Some(latency.clone()),
+                    capital: capital.get(&path.signature().key()).cloned(),
+                    scan_id: Some(scan_record.id),
+                    scan_status: scan_statuses.get(path.fingerprint().as_str()).copied(),
+                })
+                .collect();
+
+            // 🧾 结构化输出：每条机会一行 JSON（带拆分方案，快速通道的直接套利没有）
+            if let Some(output) = opportunity_output.as_mut() {
+                let optimized: HashMap<PathSignature<'_>, &router_split_optimizer::OptimizedPath> = paths.iter()
+                    .map(|p| (p.path.base_path.signature(), &p.path))
+                    .collect();
+                for ((path, _, _), context) in accepted.iter().zip(&contexts) {
+                    let record = opportunity_output::OpportunityRecord::new(
+                        path,
+                        optimized.get(&path.signature()).copied(),
+                        context,
+                        price_cache,
+                    );
+                    output.emit(&record);
+                }
+            }
+
+            // 📣 机会通知：try_send，队列满时丢弃并计数，不阻塞扫描
+            if let Some(sender) = &notification_sender {
+                for ((path, _, _), context) in accepted.iter().zip(&contexts) {
+                    sender.notify(notifications::Notification::new(path, context));
+                }
+            }
+
+            if let Some(db) = db_manager.clone() {
+                let router_mode = router_mode.clone();
+
+                // 写库放到独立任务，不阻塞下一次扫描
+                tokio::spawn(async move {
+                    let db = db.lock().await;
+                    for ((path, _, _), context) in accepted.iter().zip(&contexts) {
+                        if let Err(e) = db.record_opportunity_with_context(path, &router_mode, min_roi_percent, Some(context)).await {
+                            warn!("Failed to record opportunity {}: {}", path.signature(), e);
+                        }
+                    }
+                });
+            }
+        } else if validated_count > 0 {
+            debug!("🔀 {} validated opportunities, all reported within the dedup TTL", validated_count);
+        }
+
+        // 🧪 低优先级 what-if 扫描：通道满时直接跳过，不阻塞生产扫描
+        if let (Some(tx), Some(every_n)) = (&whatif_tx, whatif_every_n) {
+            *scans_since_whatif += 1;
+            if *scans_since_whatif >= every_n && tx.try_send(tiers[0].amount).is_ok() {
+                *scans_since_whatif = 0;
+            }
+        }
+
+        // 🔔 扫描差异事件交给告警分发
+        if let Some(dispatcher) = alert_dispatcher.as_mut() {
+            let now = Instant::now();
+            let current: HashMap<u64, &router_split_optimizer::OptimizedPath> = paths.iter()
+                .map(|p| (p.path.base_path.signature().key(), &p.path))
+                .collect();
+            for event in &scan_events {
+                let opportunity = current.get(&PathSignature::key_of(&event.signature))
+                    .map(|p| alerts::AlertOpportunity::from_path(p, price_cache));
+                dispatcher.dispatch_event(event, opportunity.as_ref(), now);
+            }
+            dispatcher.flush(now);
+        }
+    }
+
+    info!("🧮 Calculator task shutdown");
+}
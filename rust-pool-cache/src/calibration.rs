@@ -0,0 +1,448 @@
+/*!
+ * 置信度校准
+ *
+ * 验证器的 confidence_score（0-100）是启发式评分，不是概率。
+ * 利用历史结果（链上复核后机会是否仍然存在）进行校准：
+ * 1. 按置信度十分位分桶，统计每桶的经验持续率
+ * 2. 保序回归（Pool Adjacent Violators）保证映射单调不减
+ * 3. 桶中点之间分段线性插值 -> calibrated_probability
+ *
+ * 模拟门控：calibrated_probability × 理论利润 ≥ 期望值下限 才触发链上模拟。
+ * 校准表定期（默认每天）重新拟合并持久化到数据库。
+ *
+ * 样本不足时不信任拟合结果：历史少于 `min_fit_samples` 不拟合，
+ * 样本少于 `min_bucket_samples` 的桶不参与保序回归，落在这些桶里的置信度
+ * 仍按原始置信度阈值门控。被门控拒绝的机会每 `explore_every` 个放行一个，
+ * 低置信度区间也能持续积累复核结果（否则一次失败就可能永久锁死）。
+ */
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+
+/// 分桶数（十分位）
+pub const BUCKET_COUNT: usize = 10;
+
+/// 默认：至少 200 条复核结果才拟合
+pub const DEFAULT_MIN_FIT_SAMPLES: usize = 200;
+
+/// 默认：桶内至少 30 条复核结果才使用该桶的校准概率
+pub const DEFAULT_MIN_BUCKET_SAMPLES: u64 = 30;
+
+/// 默认：门控拒绝的机会每 20 个放行一个（约 5% 探索）
+pub const DEFAULT_EXPLORE_EVERY: u64 = 20;
+
+/// 一条历史结果
+#[derive(Debug, Clone, Copy)]
+pub struct OutcomeSample {
+    /// 验证器置信度（0-100）
+    pub confidence: f64,
+    /// 复核时机会是否仍然存在
+    pub persisted: bool,
+}
+
+/// 单个置信度桶
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CalibrationBucket {
+    pub bucket: usize,
+    pub lower: f64,
+    pub upper: f64,
+    pub samples: u64,
+    pub persisted: u64,
+    /// 经验持续率（无样本时为 None）
+    pub empirical_rate: Option<f64>,
+    /// 保序回归后的校准概率
+    pub calibrated: f64,
+}
+
+/// 校准表
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CalibrationTable {
+    pub buckets: Vec<CalibrationBucket>,
+    pub total_samples: u64,
+    pub fitted_at: Option<DateTime<Utc>>,
+}
+
+fn bucket_index(confidence: f64) -> usize {
+    let idx = (confidence.clamp(0.0, 100.0) / (100.0 / BUCKET_COUNT as f64)).floor() as usize;
+    idx.min(BUCKET_COUNT - 1)
+}
+
+impl CalibrationTable {
+    /// 未拟合的恒等映射（probability = confidence / 100）
+    pub fn identity() -> Self {
+        let width = 100.0 / BUCKET_COUNT as f64;
+        Self {
+            buckets: (0..BUCKET_COUNT)
+                .map(|i| CalibrationBucket {
+                    bucket: i,
+                    lower: i as f64 * width,
+                    upper: (i + 1) as f64 * width,
+                    samples: 0,
+                    persisted: 0,
+                    empirical_rate: None,
+                    calibrated: (i as f64 + 0.5) * width / 100.0,
+                })
+                .collect(),
+            total_samples: 0,
+            fitted_at: None,
+        }
+    }
+
+    /// 从历史结果拟合（每个有样本的桶都参与）
+    pub fn fit(samples: &[OutcomeSample], now: DateTime<Utc>) -> Self {
+        Self::fit_with_min_samples(samples, 1, now)
+    }
+
+    /// 从历史结果拟合，样本少于 `min_bucket_samples` 的桶按空桶处理（相邻桶插值）
+    pub fn fit_with_min_samples(samples: &[OutcomeSample], min_bucket_samples: u64, now: DateTime<Utc>) -> Self {
+        let min_bucket_samples = min_bucket_samples.max(1);
+        let mut table = Self::identity();
+        if samples.is_empty() {
+            return table;
+        }
+
+        for sample in samples {
+            let bucket = &mut table.buckets[bucket_index(sample.confidence)];
+            bucket.samples += 1;
+            if sample.persisted {
+                bucket.persisted += 1;
+            }
+        }
+
+        // 保序回归（PAV），按样本数加权；块 = (加权均值, 权重, 覆盖的桶)
+        let mut blocks: Vec<(f64, f64, Vec<usize>)> = Vec::new();
+        for bucket in table.buckets.iter_mut().filter(|b| b.samples > 0) {
+            let rate = bucket.persisted as f64 / bucket.samples as f64;
+            bucket.empirical_rate = Some(rate);
+            if bucket.samples < min_bucket_samples {
+                continue;
+            }
+            blocks.push((rate, bucket.samples as f64, vec![bucket.bucket]));
+
+            while blocks.len() >= 2 && blocks[blocks.len() - 2].0 > blocks[blocks.len() - 1].0 {
+                let (v2, w2, idx2) = blocks.pop().unwrap();
+                let (v1, w1, mut idx1) = blocks.pop().unwrap();
+                idx1.extend(idx2);
+                blocks.push(((v1 * w1 + v2 * w2) / (w1 + w2), w1 + w2, idx1));
+            }
+        }
+
+        for (value, _, indices) in &blocks {
+            for &i in indices {
+                table.buckets[i].calibrated = *value;
+            }
+        }
+
+        // 空桶 / 样本不足的桶：在相邻已拟合的桶之间线性插值（两端取最近值）
+        let fitted: Vec<usize> = table.buckets.iter()
+            .filter(|b| b.samples >= min_bucket_samples)
+            .map(|b| b.bucket)
+            .collect();
+        for i in 0..BUCKET_COUNT {
+            if table.buckets[i].samples >= min_bucket_samples {
+                continue;
+            }
+            let prev = fitted.iter().rev().find(|&&j| j < i).copied();
+            let next = fitted.iter().find(|&&j| j > i).copied();
+            table.buckets[i].calibrated = match (prev, next) {
+                (Some(p), Some(n)) => {
+                    let (vp, vn) = (table.buckets[p].calibrated, table.buckets[n].calibrated);
+                    vp + (vn - vp) * (i - p) as f64 / (n - p) as f64
+                }
+                (Some(p), None) => table.buckets[p].calibrated,
+                (None, Some(n)) => table.buckets[n].calibrated,
+                (None, None) => table.buckets[i].calibrated,
+            };
+        }
+
+        table.total_samples = samples.len() as u64;
+        table.fitted_at = Some(now);
+        table
+    }
+
+    /// 从持久化的桶恢复（桶数不符时返回 None，回退为重新拟合）
+    pub fn from_buckets(mut buckets: Vec<CalibrationBucket>, fitted_at: Option<DateTime<Utc>>) -> Option<Self> {
+        buckets.sort_by_key(|b| b.bucket);
+        if buckets.len() != BUCKET_COUNT || buckets.iter().enumerate().any(|(i, b)| b.bucket != i) {
+            return None;
+        }
+
+        Some(Self {
+            total_samples: buckets.iter().map(|b| b.samples).sum(),
+            buckets,
+            fitted_at,
+        })
+    }
+
+    /// 置信度所在的桶
+    pub fn bucket(&self, confidence: f64) -> &CalibrationBucket {
+        &self.buckets[bucket_index(confidence)]
+    }
+
+    /// 置信度 -> 校准概率（桶中点间分段线性插值）
+    pub fn probability(&self, confidence: f64) -> f64 {
+        let width = 100.0 / BUCKET_COUNT as f64;
+        let position = confidence.clamp(0.0, 100.0) / width - 0.5;
+
+        if position <= 0.0 {
+            return self.buckets[0].calibrated;
+        }
+        let lower = position.floor() as usize;
+        if lower >= BUCKET_COUNT - 1 {
+            return self.buckets[BUCKET_COUNT - 1].calibrated;
+        }
+
+        let t = position - lower as f64;
+        let (a, b) = (self.buckets[lower].calibrated, self.buckets[lower + 1].calibrated);
+        (a + (b - a) * t).clamp(0.0, 1.0)
+    }
+}
+
+/// 校准器：累积历史结果，定期重新拟合
+pub struct Calibrator {
+    table: RwLock<CalibrationTable>,
+    history: Mutex<VecDeque<OutcomeSample>>,
+    max_history: usize,
+    refresh_interval: chrono::Duration,
+    /// 历史少于该数量时不拟合
+    min_fit_samples: usize,
+    /// 桶内样本少于该数量时，该桶按原始置信度阈值门控
+    min_bucket_samples: u64,
+    /// 门控拒绝的机会每 N 个放行一个（0 = 不探索）
+    explore_every: u64,
+    /// 门控拒绝计数
+    rejected: AtomicU64,
+}
+
+impl Calibrator {
+    pub fn new(max_history: usize, refresh_interval: chrono::Duration) -> Self {
+        Self {
+            table: RwLock::new(CalibrationTable::identity()),
+            history: Mutex::new(VecDeque::new()),
+            max_history,
+            refresh_interval,
+            min_fit_samples: DEFAULT_MIN_FIT_SAMPLES,
+            min_bucket_samples: DEFAULT_MIN_BUCKET_SAMPLES,
+            explore_every: DEFAULT_EXPLORE_EVERY,
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    /// 设置拟合所需的最少样本数（整体 / 每桶）
+    pub fn with_min_samples(mut self, min_fit_samples: usize, min_bucket_samples: u64) -> Self {
+        self.min_fit_samples = min_fit_samples.max(1);
+        self.min_bucket_samples = min_bucket_samples.max(1);
+        self
+    }
+
+    /// 设置探索频率：门控拒绝的机会每 `explore_every` 个放行一个（0 = 关闭）
+    pub fn with_exploration(mut self, explore_every: u64) -> Self {
+        self.explore_every = explore_every;
+        self
+    }
+
+    /// 从持久化的表恢复（重启后）
+    pub fn restore(&self, table: CalibrationTable) {
+        *self.table.write().unwrap() = table;
+    }
+
+    /// 记录一次复核结果
+    pub fn record_outcome(&self, confidence: f64, persisted: bool) {
+        let mut history = self.history.lock().unwrap();
+        if history.len() >= self.max_history {
+            history.pop_front();
+        }
+        history.push_back(OutcomeSample { confidence, persisted });
+    }
+
+    pub fn history_len(&self) -> usize {
+        self.history.lock().unwrap().len()
+    }
+
+    /// 到期则重新拟合，返回新表（用于持久化）
+    pub fn refresh_if_due(&self, now: DateTime<Utc>) -> Option<CalibrationTable> {
+        let due = match self.table.read().unwrap().fitted_at {
+            Some(fitted_at) => now - fitted_at >= self.refresh_interval,
+            None => true,
+        };
+        if !due {
+            return None;
+        }
+
+        let samples: Vec<OutcomeSample> = self.history.lock().unwrap().iter().copied().collect();
+        if samples.len() < self.min_fit_samples {
+            return None;
+        }
+
+        let table = CalibrationTable::fit_with_min_samples(&samples, self.min_bucket_samples, now);
+        *self.table.write().unwrap() = table.clone();
+        Some(table)
+    }
+
+    pub fn table(&self) -> CalibrationTable {
+        self.table.read().unwrap().clone()
+    }
+
+    pub fn probability(&self, confidence: f64) -> f64 {
+        self.table.read().unwrap().probability(confidence)
+    }
+
+    /// 置信度所在的桶是否已有足够样本（表已拟合且桶内样本 ≥ `min_bucket_samples`）
+    pub fn is_calibrated(&self, confidence: f64) -> bool {
+        let table = self.table.read().unwrap();
+        table.fitted_at.is_some() && table.bucket(confidence).samples >= self.min_bucket_samples
+    }
+
+    /// 期望值（与理论利润同单位）
+    pub fn expected_value(&self, confidence: f64, theoretical_profit: f64) -> f64 {
+        self.probability(confidence) * theoretical_profit
+    }
+
+    /// 模拟门控
+    ///
+    /// 已校准的桶：期望值 ≥ `ev_floor`；样本不足时回退为原始置信度 ≥ `min_confidence`。
+    /// 未通过的机会每 `explore_every` 个放行一个，保证门控以下也有复核样本。
+    pub fn should_simulate(&self, confidence: f64, theoretical_profit: f64, ev_floor: f64, min_confidence: f64) -> bool {
+        let passed = if self.is_calibrated(confidence) {
+            self.expected_value(confidence, theoretical_profit) >= ev_floor
+        } else {
+            confidence >= min_confidence
+        };
+        passed || self.explore()
+    }
+
+    /// 门控拒绝时是否作为探索样本放行
+    fn explore(&self) -> bool {
+        if self.explore_every == 0 {
+            return false;
+        }
+        (self.rejected.fetch_add(1, Ordering::Relaxed) + 1) % self.explore_every == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 每个桶 100 个样本，持续率按给定值
+    fn synthetic_history(rates: &[f64; BUCKET_COUNT]) -> Vec<OutcomeSample> {
+        let mut samples = Vec::new();
+        for (bucket, rate) in rates.iter().enumerate() {
+            let persisted = (rate * 100.0).round() as usize;
+            for i in 0..100 {
+                samples.push(OutcomeSample {
+                    confidence: bucket as f64 * 10.0 + 5.0,
+                    persisted: i < persisted,
+                });
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn test_fit_recovers_monotone_rates() {
+        let rates = [0.05, 0.1, 0.15, 0.2, 0.3, 0.4, 0.5, 0.6, 0.75, 0.9];
+        let table = CalibrationTable::fit(&synthetic_history(&rates), Utc::now());
+
+        for (bucket, rate) in table.buckets.iter().zip(rates.iter()) {
+            assert!((bucket.calibrated - rate).abs() < 1e-9);
+            assert_eq!(bucket.samples, 100);
+        }
+        // 桶中点精确命中，中点之间线性插值
+        assert!((table.probability(95.0) - 0.9).abs() < 1e-9);
+        assert!((table.probability(90.0) - 0.825).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fit_pools_adjacent_violators() {
+        // 第 5、6 桶倒挂（0.5 > 0.3），保序回归合并为 0.4
+        let rates = [0.05, 0.1, 0.15, 0.2, 0.5, 0.3, 0.5, 0.6, 0.75, 0.9];
+        let table = CalibrationTable::fit(&synthetic_history(&rates), Utc::now());
+
+        assert!((table.buckets[4].calibrated - 0.4).abs() < 1e-9);
+        assert!((table.buckets[5].calibrated - 0.4).abs() < 1e-9);
+        assert_eq!(table.buckets[4].empirical_rate, Some(0.5));
+        for pair in table.buckets.windows(2) {
+            assert!(pair[0].calibrated <= pair[1].calibrated);
+        }
+    }
+
+    #[test]
+    fn test_ev_gating_and_restart_persistence() {
+        let calibrator = Calibrator::new(10_000, chrono::Duration::hours(24)).with_exploration(0);
+        let rates = [0.0, 0.0, 0.0, 0.0, 0.1, 0.1, 0.2, 0.2, 0.5, 0.8];
+        for sample in synthetic_history(&rates) {
+            calibrator.record_outcome(sample.confidence, sample.persisted);
+        }
+        let now = Utc::now();
+        let table = calibrator.refresh_if_due(now).expect("first fit is always due");
+        assert!(calibrator.refresh_if_due(now + chrono::Duration::hours(1)).is_none());
+
+        // 期望值下限 0.2%：高置信 + 小利润 vs 低置信 + 大利润（每桶 100 个样本，均已校准）
+        assert!(calibrator.should_simulate(95.0, 0.3, 0.2, 80.0));    // 0.8 × 0.3 = 0.24
+        assert!(!calibrator.should_simulate(85.0, 0.3, 0.2, 80.0));   // 0.5 × 0.3 = 0.15
+        assert!(calibrator.should_simulate(55.0, 2.5, 0.2, 80.0));    // 0.1 × 2.5 = 0.25
+        assert!(!calibrator.should_simulate(25.0, 5.0, 0.2, 80.0));   // 0.0
+
+        // 模拟重启：按数据库行（桶）恢复，映射一致
+        let rows = table.buckets.iter().rev().cloned().collect();
+        let restored = CalibrationTable::from_buckets(rows, table.fitted_at).unwrap();
+        let restarted = Calibrator::new(10_000, chrono::Duration::hours(24));
+        restarted.restore(restored);
+        assert_eq!(restarted.table(), table);
+        for confidence in [5.0, 37.0, 64.0, 95.0] {
+            assert_eq!(restarted.probability(confidence), calibrator.probability(confidence));
+        }
+    }
+
+    #[test]
+    fn test_single_failure_does_not_lock_out_simulation() {
+        let calibrator = Calibrator::new(10_000, chrono::Duration::hours(1))
+            .with_min_samples(50, 10)
+            .with_exploration(0);
+        let now = Utc::now();
+
+        // 一次失败的复核：样本不足，不拟合，仍按原始阈值门控
+        calibrator.record_outcome(95.0, false);
+        assert!(calibrator.refresh_if_due(now).is_none());
+        assert!(!calibrator.is_calibrated(95.0));
+        assert!(calibrator.should_simulate(95.0, 0.05, 0.2, 80.0));
+        assert!(!calibrator.should_simulate(50.0, 5.0, 0.2, 80.0));
+
+        // 只有 90-100 桶攒够样本：该桶按 EV 门控，其余桶继续用原始阈值
+        for i in 0..49 {
+            calibrator.record_outcome(95.0, i % 2 == 0);
+        }
+        let table = calibrator.refresh_if_due(now).expect("enough samples to fit");
+        assert_eq!(table.buckets[9].samples, 50);
+        assert!(calibrator.is_calibrated(95.0));
+        assert!(!calibrator.is_calibrated(85.0));
+        assert!((calibrator.probability(95.0) - 0.5).abs() < 1e-9);
+        assert!(!calibrator.should_simulate(95.0, 0.3, 0.2, 80.0));   // 0.5 × 0.3 = 0.15
+        assert!(calibrator.should_simulate(85.0, 0.05, 0.2, 80.0));
+
+        // 样本不足的桶不参与保序回归
+        calibrator.record_outcome(15.0, true);
+        let table = calibrator.refresh_if_due(now + chrono::Duration::hours(1)).unwrap();
+        assert_eq!(table.buckets[1].empirical_rate, Some(1.0));
+        assert!((table.buckets[1].calibrated - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_exploration_simulates_some_rejected_opportunities() {
+        let calibrator = Calibrator::new(10_000, chrono::Duration::hours(1))
+            .with_min_samples(1, 1)
+            .with_exploration(4);
+        for _ in 0..100 {
+            calibrator.record_outcome(55.0, false);
+        }
+        calibrator.refresh_if_due(Utc::now()).unwrap();
+
+        // 校准概率为 0：EV 永远低于下限，但每 4 个被拒绝的机会放行 1 个
+        let simulated = (0..20).filter(|_| calibrator.should_simulate(55.0, 10.0, 0.1, 80.0)).count();
+        assert_eq!(simulated, 5);
+    }
+}
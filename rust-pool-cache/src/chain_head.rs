@@ -0,0 +1,96 @@
+/*!
+ * ⛓️ 链头 slot 跟踪
+ *
+ * 通过共享 RPC 管理器定期调用 getSlot（confirmed，与账户订阅的 commitment 一致）维护
+ * current_head_slot。池子统计用它计算每次更新的 head_lag = 链头 − 更新 slot，
+ * 从而发现某个 RPC 提供商的通知整体落后；一致性快照也可以选择以链头为"最新 slot"。
+ *
+ * 链头只增不减：轮询结果偶尔落后（负载均衡到慢节点）时不会把链头拉回去。
+ */
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use solana_sdk::commitment_config::CommitmentConfig;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::rpc_manager::RpcHandle;
+use crate::supervisor::{spawn_supervised, Supervisor};
+
+/// 当前链头 slot（0 = 尚未获取）
+#[derive(Debug, Default)]
+pub struct ChainHead {
+    head_slot: AtomicU64,
+}
+
+static GLOBAL: OnceLock<ChainHead> = OnceLock::new();
+
+/// 进程级链头（池子统计和价格缓存共用）
+pub fn global() -> &'static ChainHead {
+    GLOBAL.get_or_init(ChainHead::new)
+}
+
+impl ChainHead {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录观测到的链头 slot（取最大值）
+    pub fn observe(&self, slot: u64) {
+        self.head_slot.fetch_max(slot, Ordering::Relaxed);
+    }
+
+    /// 当前链头（尚未获取时为 None）
+    pub fn head_slot(&self) -> Option<u64> {
+        match self.head_slot.load(Ordering::Relaxed) {
+            0 => None,
+            slot => Some(slot),
+        }
+    }
+
+    /// `slot` 落后链头多少个 slot（超前按 0 计；链头未知时为 None）
+    pub fn lag(&self, slot: u64) -> Option<u64> {
+        self.head_slot().map(|head| head.saturating_sub(slot))
+    }
+}
+
+/// 后台轮询 getSlot（🛟 受监督，panic 后重启）
+pub fn spawn_poller(head: &'static ChainHead, rpc: RpcHandle, interval: Duration, supervisor: &Supervisor) -> JoinHandle<()> {
+    spawn_supervised("chain_head_poller", supervisor, move || {
+        let rpc = rpc.clone();
+        async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                ticker.tick().await;
+                match rpc.call(|client| client.get_slot_with_commitment(CommitmentConfig::confirmed()).map_err(Box::new)).await {
+                    Ok(slot) => {
+                        head.observe(slot);
+                        debug!("⛓️ Chain head slot: {}", slot);
+                    }
+                    Err(e) => warn!("⛓️ Failed to poll chain head slot: {}", e),
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_head_only_moves_forward() {
+        let head = ChainHead::new();
+        assert_eq!(head.head_slot(), None);
+        assert_eq!(head.lag(100), None);
+
+        head.observe(1_000);
+        head.observe(990); // 慢节点返回的旧 slot 不回退
+        assert_eq!(head.head_slot(), Some(1_000));
+        assert_eq!(head.lag(994), Some(6));
+        assert_eq!(head.lag(1_002), Some(0));
+    }
+}
@@ -0,0 +1,325 @@
+/*!
+ * 池子级熔断（可疑数据隔离）
+ *
+ * 反序列化器解析错位或 vault 读到错误余额时，池子价格可能一次跳 90%，在有人发现之前
+ * 触发一串假机会。PriceCache 写入每次更新前先和上一次更新比较：
+ *
+ * - 价格变化超过 `max_price_jump_percent`（默认 30%，`[[pools]] max_price_jump_percent` 覆盖）
+ * - 任一侧储备量变化超过 `max_reserve_change_percent` × slot 间隔（相邻 slot 内储备翻倍不正常）
+ *
+ * 触发后池子进入隔离：缓存和统计照常更新，但新鲜度快照和路由图排除它。冷却期过后连续
+ * `clear_after_sane_updates` 次正常更新才解除；隔离期间再次触发则重新计时。
+ * 判断总是对比相邻两次更新，所以一次性的错误读数在恢复时会再触发一次（错误值 → 正确值
+ * 同样是大跳变），真实的价格跳变则在冷却期后按新价位解除。
+ */
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::config::{CircuitBreakerConfig, PoolConfig};
+use crate::price_cache::PoolPrice;
+
+/// 触发隔离的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TripReason {
+    /// 单次更新价格跳变
+    PriceJump,
+    /// 储备量变化与 slot 间隔不符
+    ReserveJump,
+}
+
+impl TripReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TripReason::PriceJump => "price_jump",
+            TripReason::ReserveJump => "reserve_jump",
+        }
+    }
+}
+
+/// 熔断状态变化（PriceCache::update_price 返回，供统计记录）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BreakerEvent {
+    /// 进入隔离或隔离中再次触发
+    Tripped(TripReason),
+    /// 解除隔离
+    Cleared,
+}
+
+/// 一个被隔离的池子
+#[derive(Debug, Clone, Serialize)]
+pub struct Quarantine {
+    pub pool_id: String,
+    pub pair: String,
+    /// 最近一次触发的原因
+    pub reason: TripReason,
+    /// 最近一次触发时的变化幅度（%）
+    pub change_percent: f64,
+    /// 本次隔离期间的触发次数
+    pub trips: u64,
+    /// 最近一次触发后的连续正常更新次数
+    pub sane_updates: u32,
+    #[serde(skip)]
+    pub since: Instant,
+    #[serde(skip)]
+    pub until: Instant,
+}
+
+/// 池子级熔断器：全局阈值 + 池子级价格阈值覆盖 + 隔离表
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    enabled: bool,
+    max_price_jump_percent: f64,
+    max_reserve_change_percent: f64,
+    cooldown: Duration,
+    clear_after: u32,
+    /// pool_id -> max_price_jump_percent
+    pool_overrides: DashMap<String, f64>,
+    quarantined: DashMap<String, Quarantine>,
+    total_trips: AtomicU64,
+}
+
+/// 默认不启用（测试和工具直接用 PriceCache::new()），主程序按 [circuit_breaker] 配置启用
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(&CircuitBreakerConfig {
+            enabled: false,
+            ..Default::default()
+        })
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(config: &CircuitBreakerConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            max_price_jump_percent: config.max_price_jump_percent,
+            max_reserve_change_percent: config.max_reserve_change_percent,
+            cooldown: Duration::from_secs(config.cooldown_secs),
+            clear_after: config.clear_after_sane_updates.max(1),
+            pool_overrides: DashMap::new(),
+            quarantined: DashMap::new(),
+            total_trips: AtomicU64::new(0),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// 从池子配置加载价格阈值覆盖，返回加载数量
+    pub fn load_from_pools(&self, pools: &[PoolConfig]) -> usize {
+        let mut loaded = 0;
+        for pool in pools {
+            if let Some(threshold) = pool.max_price_jump_percent {
+                self.pool_overrides.insert(pool.address.clone(), threshold);
+                loaded += 1;
+            }
+        }
+        loaded
+    }
+
+    /// 按新的池子配置重新登记覆盖（热重载时调用）
+    pub fn reload_pool(&self, pool: &PoolConfig) {
+        match pool.max_price_jump_percent {
+            Some(threshold) => {
+                self.pool_overrides.insert(pool.address.clone(), threshold);
+            }
+            None => {
+                self.pool_overrides.remove(&pool.address);
+            }
+        }
+    }
+
+    /// 清除池子覆盖和隔离状态（池子被删除时调用）
+    pub fn clear_pool(&self, pool_id: &str) {
+        self.pool_overrides.remove(pool_id);
+        self.quarantined.remove(pool_id);
+    }
+
+    /// 池子的价格阈值：池子覆盖 > 全局值
+    pub fn price_threshold(&self, pool_id: &str) -> f64 {
+        self.pool_overrides
+            .get(pool_id)
+            .map(|threshold| *threshold)
+            .unwrap_or(self.max_price_jump_percent)
+    }
+
+    /// 对比相邻两次更新，可疑时返回 (原因, 变化幅度 %)
+    ///
+    /// 价格为 0 的一侧不比较（vault 型 / CLMM 池子允许以 0 价格激活）。
+    pub fn check(&self, previous: &PoolPrice, next: &PoolPrice) -> Option<(TripReason, f64)> {
+        if previous.price > 0.0 && next.price > 0.0 {
+            let change = ((next.price - previous.price) / previous.price * 100.0).abs();
+            if change > self.price_threshold(&next.pool_id) {
+                return Some((TripReason::PriceJump, change));
+            }
+        }
+
+        let slot_gap = next.slot.saturating_sub(previous.slot).max(1);
+        let allowed = self.max_reserve_change_percent * slot_gap as f64;
+        let sides = [
+            (previous.base_reserve, next.base_reserve),
+            (previous.quote_reserve, next.quote_reserve),
+        ];
+        sides.iter()
+            .filter(|(before, _)| *before > 0)
+            .map(|(before, after)| (*after as f64 - *before as f64).abs() / *before as f64 * 100.0)
+            .find(|change| *change > allowed)
+            .map(|change| (TripReason::ReserveJump, change))
+    }
+
+    /// 记录一次更新（`previous` 为缓存中的上一次更新，快照恢复的数据传 None）
+    pub fn observe(&self, previous: Option<&PoolPrice>, next: &PoolPrice, now: Instant) -> Option<BreakerEvent> {
+        if !self.enabled {
+            return None;
+        }
+        let tripped = previous.and_then(|previous| self.check(previous, next));
+
+        if let Some((reason, change_percent)) = tripped {
+            self.total_trips.fetch_add(1, Ordering::Relaxed);
+            let mut entry = self.quarantined.entry(next.pool_id.clone()).or_insert_with(|| Quarantine {
+                pool_id: next.pool_id.clone(),
+                pair: next.pair.clone(),
+                reason,
+                change_percent,
+                trips: 0,
+                sane_updates: 0,
+                since: now,
+                until: now,
+            });
+            entry.reason = reason;
+            entry.change_percent = change_percent;
+            entry.trips += 1;
+            entry.sane_updates = 0;
+            entry.until = now + self.cooldown;
+            warn!(
+                "🧯 Pool {} ({}) quarantined: {:?} {:.1}% (trip #{}, cooldown {}s)",
+                next.pair, next.pool_id, reason, change_percent, entry.trips, self.cooldown.as_secs()
+            );
+            return Some(BreakerEvent::Tripped(reason));
+        }
+
+        let cleared = match self.quarantined.get_mut(&next.pool_id) {
+            Some(mut entry) => {
+                entry.sane_updates += 1;
+                entry.sane_updates >= self.clear_after && now >= entry.until
+            }
+            None => return None,
+        };
+        if !cleared {
+            return None;
+        }
+        let (_, entry) = self.quarantined.remove(&next.pool_id)?;
+        info!(
+            "🧯 Pool {} ({}) released from quarantine after {:.0}s ({} trips)",
+            entry.pair, entry.pool_id, now.duration_since(entry.since).as_secs_f64(), entry.trips
+        );
+        Some(BreakerEvent::Cleared)
+    }
+
+    pub fn is_quarantined(&self, pool_id: &str) -> bool {
+        self.quarantined.contains_key(pool_id)
+    }
+
+    /// 当前被隔离的池子（按 pool_id 排序）
+    pub fn quarantined(&self) -> Vec<Quarantine> {
+        let mut pools: Vec<Quarantine> = self.quarantined.iter().map(|entry| entry.value().clone()).collect();
+        pools.sort_by(|a, b| a.pool_id.cmp(&b.pool_id));
+        pools
+    }
+
+    /// 启动以来的触发总次数
+    pub fn total_trips(&self) -> u64 {
+        self.total_trips.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(price: f64, base_reserve: u64, slot: u64) -> PoolPrice {
+        PoolPrice {
+            base_reserve,
+            quote_reserve: (base_reserve as f64 * price / 1000.0) as u64,
+            slot,
+            ..PoolPrice::for_test("pool", "SOL/USDC", price)
+        }
+    }
+
+    fn breaker() -> CircuitBreaker {
+        CircuitBreaker::new(&CircuitBreakerConfig { cooldown_secs: 10, ..Default::default() })
+    }
+
+    #[test]
+    fn test_glitch_quarantines_until_cooldown_and_two_sane_updates() {
+        let breaker = breaker();
+        let start = Instant::now();
+        let normal = price(185.0, 1_000_000_000_000, 100);
+        let glitch = price(18.5, 1_000_000_000_000, 101);
+
+        assert_eq!(breaker.observe(None, &normal, start), None);
+        assert!(matches!(
+            breaker.observe(Some(&normal), &glitch, start),
+            Some(BreakerEvent::Tripped(TripReason::PriceJump))
+        ));
+        assert!(breaker.is_quarantined("pool"));
+
+        // 错误值 → 正确值同样是大跳变：重新计时
+        let recovered = price(185.0, 1_000_000_000_000, 102);
+        assert!(breaker.observe(Some(&glitch), &recovered, start + Duration::from_secs(1)).is_some());
+        assert_eq!(breaker.quarantined()[0].trips, 2);
+
+        // 两次正常更新，但冷却期还没过
+        let later = start + Duration::from_secs(5);
+        assert_eq!(breaker.observe(Some(&recovered), &recovered, later), None);
+        assert_eq!(breaker.observe(Some(&recovered), &recovered, later), None);
+        assert!(breaker.is_quarantined("pool"));
+
+        let after_cooldown = start + Duration::from_secs(12);
+        assert_eq!(breaker.observe(Some(&recovered), &recovered, after_cooldown), Some(BreakerEvent::Cleared));
+        assert!(!breaker.is_quarantined("pool"));
+        assert_eq!(breaker.total_trips(), 2);
+    }
+
+    #[test]
+    fn test_reserve_jump_scales_with_slot_gap_and_pool_override() {
+        let breaker = breaker();
+        let before = price(185.0, 1_000_000_000_000, 100);
+
+        // 储备翻倍（+100%）：相邻 slot 可疑，隔了 3 个 slot 允许 150%
+        let doubled = PoolPrice { base_reserve: 2_000_000_000_000, slot: 101, ..before.clone() };
+        assert_eq!(breaker.check(&before, &doubled).map(|(r, _)| r), Some(TripReason::ReserveJump));
+        let doubled_later = PoolPrice { slot: 103, ..doubled };
+        assert_eq!(breaker.check(&before, &doubled_later), None);
+
+        // 波动大的池子调高价格阈值
+        let jumped = PoolPrice { price: 250.0, slot: 101, ..before.clone() };
+        assert!(breaker.check(&before, &jumped).is_some());
+        breaker.reload_pool(&PoolConfig {
+            address: "pool".to_string(),
+            name: "SOL/USDC".to_string(),
+            pair: "SOL/USDC".to_string(),
+            pool_type: "amm_v4".to_string(),
+            fee_bps: None,
+            max_age_ms: None,
+            base_mint: None,
+            quote_mint: None,
+            max_price_jump_percent: Some(50.0),
+            commitment: None,
+            encoding: None,
+            priority: None,
+        });
+        assert_eq!(breaker.check(&before, &jumped), None);
+
+        // 未启用时不隔离
+        let disabled = CircuitBreaker::default();
+        assert_eq!(disabled.observe(Some(&before), &PoolPrice { price: 1.0, ..before.clone() }, Instant::now()), None);
+    }
+}
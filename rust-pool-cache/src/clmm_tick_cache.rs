@@ -0,0 +1,188 @@
+/*!
+ * Raydium CLMM tick 注册表
+ *
+ * 池子账户只有当前 tick，跨 tick 报价需要相邻的 TickArray 账户。WebSocket 收到池子更新时
+ * 在这里维护当前 tick 两侧的 TickArray 窗口（复用 vault 订阅通道订阅）；当前 tick 移到
+ * 另一个数组时，新进入窗口的数组订阅、移出窗口的退订。TickArray 更新后重建报价器并
+ * 注册到 orderbook_cache，路由器按 tick 报价。
+ */
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use dashmap::DashMap;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::deserializers::clmm_quoter::{
+    tick_array_address, tick_array_starts_around, ClmmQuoter, TickArray,
+};
+use crate::deserializers::raydium_clmm::{RaydiumClmmPoolState, FEE_RATE_DENOMINATOR};
+
+/// 当前 tick 所在数组两侧各订阅的 TickArray 数（共 3 个数组）
+pub const TICK_ARRAY_RADIUS: i32 = 1;
+
+struct ClmmPoolTicks {
+    pool: RaydiumClmmPoolState,
+    /// TickArray 起始 tick -> 最新数据（尚未收到数据的为 None）
+    arrays: HashMap<i32, Option<TickArray>>,
+}
+
+/// 池子更新后 TickArray 窗口的变化
+#[derive(Debug, Default, PartialEq)]
+pub struct TickArrayWindow {
+    /// 新进入窗口、需要订阅的地址
+    pub subscribe: Vec<String>,
+    /// 移出窗口、需要退订的地址
+    pub unsubscribe: Vec<String>,
+}
+
+static CLMM_POOLS: OnceLock<DashMap<String, ClmmPoolTicks>> = OnceLock::new();
+/// TickArray 地址 -> (pool_id, 起始 tick)
+static TICK_ARRAYS: OnceLock<DashMap<String, (String, i32)>> = OnceLock::new();
+
+fn pools() -> &'static DashMap<String, ClmmPoolTicks> {
+    CLMM_POOLS.get_or_init(DashMap::new)
+}
+
+fn tick_arrays() -> &'static DashMap<String, (String, i32)> {
+    TICK_ARRAYS.get_or_init(DashMap::new)
+}
+
+/// 处理 CLMM 池子更新，返回 TickArray 窗口的变化
+pub fn observe_pool(pool_id: &str, data: &[u8]) -> TickArrayWindow {
+    let mut window = TickArrayWindow::default();
+    let pool = match RaydiumClmmPoolState::from_account_data_manual(data) {
+        Ok(pool) if pool.tick_spacing > 0 => pool,
+        _ => return window,
+    };
+    let pool_pubkey = match Pubkey::from_str(pool_id) {
+        Ok(pubkey) => pubkey,
+        Err(_) => return window,
+    };
+
+    let mut entry = pools().entry(pool_id.to_string()).or_insert_with(|| ClmmPoolTicks {
+        pool: pool.clone(),
+        arrays: HashMap::new(),
+    });
+    entry.pool = pool;
+
+    let wanted = tick_array_starts_around(entry.pool.tick_current, entry.pool.tick_spacing, TICK_ARRAY_RADIUS);
+    let stale: Vec<i32> = entry.arrays.keys().copied().filter(|start| !wanted.contains(start)).collect();
+    for start in stale {
+        entry.arrays.remove(&start);
+        let address = tick_array_address(&pool_pubkey, start).to_string();
+        tick_arrays().remove(&address);
+        window.unsubscribe.push(address);
+    }
+    for start in wanted {
+        if entry.arrays.contains_key(&start) {
+            continue;
+        }
+        entry.arrays.insert(start, None);
+        let address = tick_array_address(&pool_pubkey, start).to_string();
+        tick_arrays().insert(address.clone(), (pool_id.to_string(), start));
+        window.subscribe.push(address);
+    }
+    publish(pool_id, &entry);
+    window
+}
+
+/// 是否为已登记的 TickArray 账户
+pub fn is_tick_array(address: &str) -> bool {
+    tick_arrays().contains_key(address)
+}
+
+/// 处理 TickArray 更新，返回是否解析成功
+pub fn update_tick_array(address: &str, data: &[u8]) -> bool {
+    let (pool_id, start) = match tick_arrays().get(address) {
+        Some(entry) => entry.value().clone(),
+        None => return false,
+    };
+    let array = match TickArray::from_account_data(data) {
+        Ok(array) if array.start_tick_index == start => array,
+        _ => return false,
+    };
+
+    match pools().get_mut(&pool_id) {
+        Some(mut entry) => {
+            entry.arrays.insert(start, Some(array));
+            publish(&pool_id, &entry);
+            true
+        }
+        None => false,
+    }
+}
+
+/// 已登记的 (TickArray 地址, pool_id)，重连后重放订阅
+pub fn subscriptions() -> Vec<(String, String)> {
+    tick_arrays().iter()
+        .map(|entry| (entry.key().clone(), entry.value().0.clone()))
+        .collect()
+}
+
+/// 移除池子（热重载删除时调用），返回需要退订的 TickArray 地址
+pub fn remove_pool(pool_id: &str) -> Vec<String> {
+    pools().remove(pool_id);
+    let addresses: Vec<String> = tick_arrays().iter()
+        .filter(|entry| entry.value().0 == pool_id)
+        .map(|entry| entry.key().clone())
+        .collect();
+    for address in &addresses {
+        tick_arrays().remove(address);
+    }
+    addresses
+}
+
+/// 用最新池子状态和已收到的 TickArray 重建报价器
+fn publish(pool_id: &str, entry: &ClmmPoolTicks) {
+    // 费率：池子级覆盖 > 链上 AmmConfig（更新缓存时写入）> DEX 默认值
+    let fee_rate = crate::fee_registry::fee_rate(pool_id, "Raydium CLMM");
+    let mut quoter = ClmmQuoter::new(entry.pool.clone(), (fee_rate * FEE_RATE_DENOMINATOR).round() as u32);
+    for array in entry.arrays.values().flatten() {
+        quoter.load_tick_array(array);
+    }
+    // 当前 tick 所在数组未加载的报价器 has_orderbook() = false，orderbook_cache 会忽略
+    crate::orderbook_cache::register(pool_id, Box::new(quoter));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_follows_current_tick() {
+        // 捕获的 RAY/USDC 池子：tick 6170，tick_spacing 60 → 数组 0 / 3600 / 7200
+        let mut data = std::fs::read("analysis-results/raydium_clmm-SOL-USDC-(Raydium-CLMM).bin").unwrap();
+        let pool_id = Pubkey::new_unique();
+        let pool_key = pool_id.to_string();
+
+        let first = observe_pool(&pool_key, &data);
+        assert_eq!(first.subscribe.len(), 3);
+        assert!(first.unsubscribe.is_empty());
+        assert!(first.subscribe.iter().all(|address| is_tick_array(address)));
+        assert_eq!(first.subscribe[0], tick_array_address(&pool_id, 0).to_string());
+
+        // 同一数组内的更新不改变窗口
+        assert_eq!(observe_pool(&pool_key, &data), TickArrayWindow::default());
+
+        // tick 移到 7300（数组 7200）：10800 进入窗口，0 移出
+        const TICK_CURRENT_OFFSET: usize = 8 + 261;
+        data[TICK_CURRENT_OFFSET..TICK_CURRENT_OFFSET + 4].copy_from_slice(&7300i32.to_le_bytes());
+        let moved = observe_pool(&pool_key, &data);
+        assert_eq!(moved.subscribe, vec![tick_array_address(&pool_id, 10800).to_string()]);
+        assert_eq!(moved.unsubscribe, first.subscribe[..1].to_vec());
+        assert!(!is_tick_array(&first.subscribe[0]));
+
+        // 数据属于其他数组的更新被拒绝
+        let address = tick_array_address(&pool_id, 7200).to_string();
+        let mut array = vec![0u8; crate::deserializers::clmm_quoter::TICK_ARRAY_LEN];
+        array[40..44].copy_from_slice(&3600i32.to_le_bytes());
+        assert!(!update_tick_array(&address, &array));
+        array[40..44].copy_from_slice(&7200i32.to_le_bytes());
+        assert!(update_tick_array(&address, &array));
+
+        let removed = remove_pool(&pool_key);
+        assert_eq!(removed.len(), 3);
+        assert!(!is_tick_array(&address));
+    }
+}
@@ -1,6 +1,11 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
+use tracing::warn;
+
+use crate::account_subscription::{AccountEncoding, Commitment};
+use crate::pool_factory::{PoolFactory, KNOWN_POOL_TYPES};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -21,11 +26,235 @@ pub struct Config {
     pub lst_detector: Option<LstDetectorConfig>,  // 🔥 LST检测器配置
     #[serde(default)]
     pub state_layer: Option<StateLayerConfig>,  // 🔥 状态层配置
+    #[serde(default)]
+    pub slo: Option<SloConfig>,  // 📈 可用性SLO追踪
+    #[serde(default)]
+    pub alerts: Option<AlertsConfig>,  // 🔔 告警分发
+    #[serde(default)]
+    pub sharding: Option<ShardingConfig>,  // 🧩 多实例池子分片
+    #[serde(default)]
+    pub synthetic_pools: Vec<SyntheticPoolConfig>,  // 🧪 合成池子（what-if）
+    #[serde(default)]
+    pub whatif: Option<WhatIfConfig>,  // 🧪 what-if 扫描（需显式启用）
+    #[serde(default)]
+    pub calculator: Option<CalculatorConfig>,  // 💵 扫描金额档位
+    #[serde(default)]
+    pub discovery: Option<DiscoveryConfig>,  // 🔭 启动时链上发现池子
+    #[serde(default)]
+    pub snapshot: Option<SnapshotConfig>,  // 💾 价格缓存快照（重启预热）
+    #[serde(default)]
+    pub price_oracle: Option<PriceOracleConfig>,  // 💲 USD 定价（稳定币池子 + 锚定代币三角换算）
+    #[serde(default)]
+    pub dexes: Option<DexesConfig>,  // 🔌 按 DEX 启用 / 禁用反序列化器
+    #[serde(default)]
+    pub error_tracking: Option<ErrorTrackingConfig>,  // 🚨 错误速率窗口与 pool_type 降级阈值
+    #[serde(default)]
+    pub output: Option<OutputConfig>,  // 🧾 机会的结构化 JSON 输出（jsonl 文件 / stdout）
+    #[serde(default)]
+    pub spread_monitor: Option<SpreadMonitorConfig>,  // 📏 交易对价差持续超阈值告警
+    #[serde(default)]
+    pub spread_heatmap: Option<SpreadHeatmapConfig>,  // 🌡️ 有序池子组合的滚动价差统计（/stats/spreads）
+    #[serde(default)]
+    pub circuit_breaker: Option<CircuitBreakerConfig>,  // 🧯 池子级熔断（可疑数据隔离）
+    #[serde(default)]
+    pub rpc: Option<RpcConfig>,  // 🛰️ 共享 HTTP RPC（端点故障转移 + 令牌桶限速）
+    #[serde(default)]
+    pub notifications: Option<NotificationsConfig>,  // 📣 验证通过的机会推送（webhook / Telegram）
+    #[serde(default)]
+    pub execution_cost: Option<ExecutionCostConfig>,  // ⛽ 执行成本（签名费 + 优先费 + Jito 小费）
+    #[serde(default)]
+    pub chain_head: Option<ChainHeadConfig>,  // ⛓️ 链头 slot 跟踪（池子通知延迟）
+    #[serde(default)]
+    pub supervisor: Option<SupervisorConfig>,  // 🛟 长时间运行任务 panic 后自动重启
+    #[serde(default)]
+    pub focus: Option<FocusConfig>,  // 🎯 重点交易对：每次更新都重算价差矩阵（不受冷却限制）
+    #[serde(default)]
+    pub validation: Option<ValidationConfig>,  // 🧱 机会验证规则流水线（规则集合 / 顺序 / 参数）
+    #[serde(default)]
+    pub debug_capture: Option<DebugCaptureConfig>,  // 🧊 扫描输入抓取（快照 + 路由配置写 JSON，离线重放）
+    #[serde(default)]
+    pub vault_audit: Option<VaultAuditConfig>,  // 🔍 缓存储备与 vault 余额的一致性巡检
+    #[serde(default)]
+    pub history: Option<HistoryConfig>,  // 🕰️ 逐池价格历史（内存环形缓冲，GET /pools/{address}/history）
+    #[serde(default)]
+    pub reference_prices: Option<ReferencePriceConfig>,  // 🧭 外部参考价格交叉校验（偏离标记 / 排除路由）
+    #[serde(default)]
+    pub inventory: Option<InventoryConfig>,  // 💼 持有余额（按库存过滤 / 截断投入 / 前置兑换）
+    #[serde(default)]
+    pub coordinator: Option<CoordinatorTriggerConfig>,  // 🎯 Coordinator 时钟周期 / 触发阈值 / 冷却
+    #[serde(default)]
+    pub adaptive_threshold: Option<AdaptiveThresholdConfig>,  // 🎚️ Coordinator 触发阈值随波动 / 负载自适应
+    #[serde(default)]
+    pub pool_naming: Option<PoolNamingConfig>,  // 🏷️ pair 为空的池子按链上 mint 自动命名
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebSocketConfig {
-    pub url: String,
+    /// 🔀 单个地址或地址列表（第一个为主端点，其余按顺序作为故障转移备用）
+    ///
+    /// ```toml
+    /// [websocket]
+    /// url = ["wss://primary.example", "wss://secondary.example"]
+    /// ```
+    #[serde(rename = "url", deserialize_with = "deserialize_url_list")]
+    pub urls: Vec<String>,
+    /// 🔄 重连退避：首次等待（毫秒）
+    #[serde(default = "default_reconnect_initial_delay_ms")]
+    pub reconnect_initial_delay_ms: u64,
+    /// 🔄 重连退避：每次失败的倍数
+    #[serde(default = "default_reconnect_multiplier")]
+    pub reconnect_multiplier: f64,
+    /// 🔄 重连退避：等待上限（毫秒）
+    #[serde(default = "default_reconnect_max_delay_ms")]
+    pub reconnect_max_delay_ms: u64,
+    /// 🔄 连接存活超过该时长（秒）视为稳定，退避重置
+    #[serde(default = "default_reconnect_stable_secs")]
+    pub reconnect_stable_secs: u64,
+    /// 🔄 同一端点连续失败超过该次数时上报 ErrorTracker
+    #[serde(default = "default_reconnect_alert_after_failures")]
+    pub reconnect_alert_after_failures: u32,
+    /// 🪣 HTTP RPC 的请求预算（次/秒，所有 RPC 调用方共用，0 = 不限；`[rpc] requests_per_second` 优先）
+    #[serde(default = "default_rpc_requests_per_second")]
+    pub rpc_requests_per_second: f64,
+    /// 🔀 单条连接最多订阅的池子数，超过时池子按地址分到多条连接（同一 URL，各自重连）
+    #[serde(default = "default_max_subscriptions_per_connection")]
+    pub max_subscriptions_per_connection: usize,
+    /// 🧹 未知 subscription_id 收到超过该条数的通知后自动退订
+    #[serde(default = "default_unsubscribe_unknown_after")]
+    pub unsubscribe_unknown_after: u32,
+    /// 🔁 池子订阅超过该秒数没有通知（同一连接上其他池子仍在更新）时重新订阅，0 = 不检查
+    #[serde(default = "default_resubscribe_silent_after_secs")]
+    pub resubscribe_silent_after_secs: u64,
+    /// 🌐 最多缓存多少个池子的原始账户数据（vault 更新时重新解析用），超过时淘汰最久未更新的池子
+    #[serde(default = "default_max_cached_pool_accounts")]
+    pub max_cached_pool_accounts: usize,
+    /// 📡 池子订阅的默认 commitment（processed / confirmed / finalized）；热门池子用 PoolConfig.commitment 单独设为 processed
+    ///
+    /// ```toml
+    /// [websocket]
+    /// commitment = "confirmed"
+    /// encoding = "base64"   # 或 "base64+zstd"（大账户，例如 Phoenix 市场）
+    ///
+    /// [[pools]]
+    /// name = "SOL/USDC (Raydium V4)"
+    /// commitment = "processed"
+    /// ```
+    #[serde(default)]
+    pub commitment: Commitment,
+    /// 📡 池子订阅的默认账户数据编码（PoolConfig.encoding 按池子覆盖）
+    #[serde(default)]
+    pub encoding: AccountEncoding,
+    /// 🚀 连接建立后立即订阅的高优先级池子数（按 PoolConfig.priority 排序），
+    /// 这些池子全部确认并收到数据时打印就绪日志
+    ///
+    /// ```toml
+    /// [websocket]
+    /// priority_batch_size = 20
+    /// subscribe_rate_per_sec = 50   # 其余池子的订阅速率，0 = 一次全部发送
+    ///
+    /// [[pools]]
+    /// name = "SOL/USDC (Raydium V4)"
+    /// priority = 100
+    /// ```
+    #[serde(default = "default_priority_batch_size")]
+    pub priority_batch_size: usize,
+    /// 🚀 高优先级批次之后的池子订阅速率（条/秒，0 = 不限）
+    #[serde(default = "default_subscribe_rate_per_sec")]
+    pub subscribe_rate_per_sec: u32,
+}
+
+impl WebSocketConfig {
+    pub fn new(url: String) -> Self {
+        Self {
+            urls: vec![url],
+            reconnect_initial_delay_ms: default_reconnect_initial_delay_ms(),
+            reconnect_multiplier: default_reconnect_multiplier(),
+            reconnect_max_delay_ms: default_reconnect_max_delay_ms(),
+            reconnect_stable_secs: default_reconnect_stable_secs(),
+            reconnect_alert_after_failures: default_reconnect_alert_after_failures(),
+            rpc_requests_per_second: default_rpc_requests_per_second(),
+            max_subscriptions_per_connection: default_max_subscriptions_per_connection(),
+            unsubscribe_unknown_after: default_unsubscribe_unknown_after(),
+            resubscribe_silent_after_secs: default_resubscribe_silent_after_secs(),
+            max_cached_pool_accounts: default_max_cached_pool_accounts(),
+            commitment: Commitment::default(),
+            encoding: AccountEncoding::default(),
+            priority_batch_size: default_priority_batch_size(),
+            subscribe_rate_per_sec: default_subscribe_rate_per_sec(),
+        }
+    }
+
+    /// 🔁 重新订阅的安静时长（0 = 不检查）
+    pub fn resubscribe_silent_after(&self) -> Option<std::time::Duration> {
+        (self.resubscribe_silent_after_secs > 0)
+            .then(|| std::time::Duration::from_secs(self.resubscribe_silent_after_secs))
+    }
+}
+
+/// `url = "wss://..."` 或 `url = ["wss://...", ...]`
+fn deserialize_url_list<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(url) => vec![url],
+        OneOrMany::Many(urls) => urls,
+    })
+}
+
+fn default_reconnect_initial_delay_ms() -> u64 {
+    1_000
+}
+
+fn default_reconnect_multiplier() -> f64 {
+    2.0
+}
+
+fn default_reconnect_max_delay_ms() -> u64 {
+    60_000
+}
+
+fn default_reconnect_stable_secs() -> u64 {
+    30
+}
+
+fn default_rpc_requests_per_second() -> f64 {
+    10.0
+}
+
+fn default_reconnect_alert_after_failures() -> u32 {
+    10
+}
+
+fn default_max_subscriptions_per_connection() -> usize {
+    100
+}
+
+fn default_unsubscribe_unknown_after() -> u32 {
+    5
+}
+
+fn default_resubscribe_silent_after_secs() -> u64 {
+    600
+}
+
+fn default_max_cached_pool_accounts() -> usize {
+    crate::pool_data_cache::DEFAULT_CAPACITY
+}
+
+fn default_priority_batch_size() -> usize {
+    20
+}
+
+fn default_subscribe_rate_per_sec() -> u32 {
+    50
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,28 +276,95 @@ pub struct DatabaseConfig {
     pub record_pool_updates: bool,
     #[serde(default = "default_true")]
     pub record_performance: bool,
+    /// 🗂️ 机会生命周期 TTL：同一机会在该时长内再次发现只更新原记录（秒）
+    #[serde(default = "default_opportunity_lifecycle_ttl_secs")]
+    pub opportunity_lifecycle_ttl_secs: u64,
+}
+
+fn default_opportunity_lifecycle_ttl_secs() -> u64 {
+    30
 }
 
 fn default_true() -> bool {
     true
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PoolConfig {
     pub address: String,
+    /// 🏷️ pair 留空时可省略，启动时生成 "BASE/QUOTE (DEX)"（见 `pair_naming`）
+    #[serde(default)]
     pub name: String,
+    /// 🏷️ 留空或省略时按链上 mint 自动生成（见 `pair_naming`）
     #[serde(default = "default_pair")]
     pub pair: String,
     #[serde(default = "default_pool_type")]
     pub pool_type: String,
+    /// 池子级手续费覆盖（bps），例如 CLMM 的 1/5/25/100 bps 费率档
+    #[serde(default)]
+    pub fee_bps: Option<u32>,
+    /// 池子级新鲜度预算覆盖（毫秒），未配置时按池子类型默认值
+    #[serde(default)]
+    pub max_age_ms: Option<u64>,
+    /// base 代币 mint（base_reserve 对应的一侧）；配置后按 mint 判断价格方向，不再依赖 pair 字符串
+    #[serde(default)]
+    pub base_mint: Option<String>,
+    /// quote 代币 mint
+    #[serde(default)]
+    pub quote_mint: Option<String>,
+    /// 池子级熔断阈值覆盖（单次更新价格变化 %），波动本来就大的交易对调高
+    #[serde(default)]
+    pub max_price_jump_percent: Option<f64>,
+    /// 📡 池子级订阅 commitment 覆盖（未配置时使用 [websocket] commitment）
+    #[serde(default)]
+    pub commitment: Option<Commitment>,
+    /// 📡 池子级账户数据编码覆盖（未配置时使用 [websocket] encoding）
+    #[serde(default)]
+    pub encoding: Option<AccountEncoding>,
+    /// 🚀 订阅 / 初始化优先级（越大越先订阅，未配置为 0；相同时按配置顺序）
+    #[serde(default)]
+    pub priority: Option<u32>,
+}
+
+/// 🚀 池子的订阅 / 初始化顺序（返回下标）：priority 高的在前，相同时保持配置顺序
+pub fn priority_order(pools: &[PoolConfig]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..pools.len()).collect();
+    order.sort_by_key(|&idx| std::cmp::Reverse(pools[idx].priority.unwrap_or(0)));
+    order
 }
 
 fn default_pool_type() -> String {
     "amm_v4".to_string()
 }
 
+/// 未配置 pair 时的占位值（与空字符串一样触发自动命名）
+pub const UNKNOWN_PAIR: &str = "UNKNOWN/UNKNOWN";
+
 fn default_pair() -> String {
-    "UNKNOWN/UNKNOWN".to_string()
+    UNKNOWN_PAIR.to_string()
+}
+
+/// 🏷️ 池子自动命名配置
+///
+/// `[[pools]]` 的 pair 留空（或省略）时，启动和热重载时查询池子账户，按 base / quote mint
+/// 生成 pair 和名称：先查内置符号表（含 [discovery] 目标和 LST），没有时查询 Metaplex
+/// 元数据账户的 symbol（`fetch_metadata`），仍然没有时用 mint 缩写（"7xKX…"）。
+///
+/// ```toml
+/// [pool_naming]
+/// fetch_metadata = false
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolNamingConfig {
+    /// 内置符号表没有的 mint 查询链上元数据（共用 [rpc] 令牌桶）
+    #[serde(default = "default_true")]
+    pub fetch_metadata: bool,
+}
+
+impl Default for PoolNamingConfig {
+    fn default() -> Self {
+        Self { fetch_metadata: true }
+    }
 }
 
 /// Logging configuration
@@ -107,6 +403,69 @@ pub struct RouterConfig {
     pub split_optimizer: Option<SplitOptimizerConfig>,
     #[serde(default)]
     pub event_driven: Option<EventDrivenConfig>,
+    #[serde(default)]
+    pub backpressure: Option<BackpressureConfig>,  // 🔥 下游反压
+    #[serde(default)]
+    pub direct: Option<DirectArbConfig>,  // ⚡ 两跳直接套利快速通道
+    #[serde(default)]
+    pub path_cache: Option<PathCacheConfig>,  // 🔥 路径骨架缓存
+    #[serde(default)]
+    pub ranking: Option<RankingConfig>,  // 📐 机会按期望值排序的权重
+    /// 相邻扫描间ROI变化超过该值（百分点）视为 Improved / Worsened
+    #[serde(default = "default_material_roi_delta")]
+    pub material_roi_delta_percent: f64,
+    /// 同一路径在该时间内重复发现只记录一次（秒）
+    #[serde(default = "default_opportunity_dedup_ttl")]
+    pub opportunity_dedup_ttl_secs: u64,
+    /// 🗃️ 内存中保留的最近扫描数（GET /scans/latest、GET /scans/:id）
+    #[serde(default = "default_scan_history_size")]
+    pub scan_history_size: usize,
+    /// 任意一跳价格冲击超过该值（%）的路径在验证阶段被拒绝
+    #[serde(default = "default_max_hop_impact")]
+    pub max_hop_impact_percent: f64,
+    /// 🔁 允许路径重复使用同一池子（默认拒绝：A→B 与 B→A 走同一池子，或包装池共用 vault）
+    #[serde(default)]
+    pub allow_pool_reuse: bool,
+    /// 💧 美元流动性低于该值的池子不参与路由（BFS / Bellman-Ford 建图和两跳快速通道；0 = 不过滤）
+    ///
+    /// 流动性按 储备 × 代币美元价格 估算（见 GET /prices 的 liquidity_usd），
+    /// 没有美元价格、无法估算的池子不受影响。
+    #[serde(default)]
+    pub min_pool_liquidity_usd: f64,
+    /// 只保留以这些代币为起点的循环（空 = 所有代币，例如 ["SOL", "USDC"]）
+    #[serde(default)]
+    pub start_tokens: Vec<String>,
+    /// 设置后每一跳的代币都必须在其中（起点代币自动允许）
+    #[serde(default)]
+    pub intermediate_whitelist: Option<Vec<String>>,
+    /// 涉及这些代币的池子不参与路由（例如价格被操纵的骗局代币）
+    #[serde(default)]
+    pub token_blacklist: Vec<String>,
+    /// 代币别名 -> 规范符号，建图时视为同一节点（默认 wSOL → SOL；设为空表关闭）
+    ///
+    /// ```toml
+    /// [router.token_aliases]
+    /// wSOL = "SOL"
+    /// USDCet = "USDC"  # 桥接 USDC 默认不合并，需要显式配置
+    /// ```
+    #[serde(default = "crate::token_alias::default_aliases")]
+    pub token_aliases: HashMap<String, String>,
+}
+
+fn default_material_roi_delta() -> f64 {
+    0.1
+}
+
+fn default_opportunity_dedup_ttl() -> u64 {
+    30
+}
+
+fn default_scan_history_size() -> usize {
+    100
+}
+
+fn default_max_hop_impact() -> f64 {
+    2.0
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -198,6 +557,154 @@ fn default_max_concurrent_scans() -> usize {
     3
 }
 
+/// ⚡ 两跳直接套利快速通道（[router.direct]）
+///
+/// Coordinator 收到价格事件时按交易对检查最优买卖价，不等完整扫描。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectArbConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 扣除两边手续费后的最小价差（百分比）
+    #[serde(default = "default_direct_min_spread")]
+    pub min_spread_percent: f64,
+}
+
+fn default_direct_min_spread() -> f64 {
+    0.3
+}
+
+/// 🔥 路径缓存（[router.path_cache]）
+///
+/// complete_scan 先按当前价格重算缓存的路径骨架，达标就跳过完整扫描。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathCacheConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 骨架的生存时间；距上次完整扫描超过该值时强制完整扫描（秒）
+    #[serde(default = "default_path_cache_ttl_secs")]
+    pub ttl_secs: u64,
+    /// 最多缓存的骨架数
+    #[serde(default = "default_path_cache_max_entries")]
+    pub max_entries: usize,
+    /// 任一池子价格相对缓存时变动超过该值（%）时，经过它的骨架失效
+    #[serde(default = "default_path_cache_invalidation_percent")]
+    pub invalidation_percent: f64,
+}
+
+fn default_path_cache_ttl_secs() -> u64 {
+    30
+}
+
+fn default_path_cache_max_entries() -> usize {
+    1000
+}
+
+fn default_path_cache_invalidation_percent() -> f64 {
+    0.5
+}
+
+impl Default for PathCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ttl_secs: default_path_cache_ttl_secs(),
+            max_entries: default_path_cache_max_entries(),
+            invalidation_percent: default_path_cache_invalidation_percent(),
+        }
+    }
+}
+
+/// 📐 机会排序（[router.ranking]）
+///
+/// 期望值 = 美元净利润 × 执行概率，执行概率由三个因子相乘：
+/// `hop_success_rate ^ 跳数` × `exp(-impact_weight × 总价格冲击%)` × `0.5 ^ (最旧池子数据年龄 / age_half_life_ms)`
+///
+/// ```toml
+/// [router.ranking]
+/// hop_success_rate = 0.95
+/// impact_weight = 0.5
+/// age_half_life_ms = 2000
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankingConfig {
+    /// 单跳成功执行的概率（跳数越多，越可能有一跳在落地前被抢）
+    #[serde(default = "default_ranking_hop_success_rate")]
+    pub hop_success_rate: f64,
+    /// 每 1% 总价格冲击的衰减系数
+    #[serde(default = "default_ranking_impact_weight")]
+    pub impact_weight: f64,
+    /// 路径中最旧池子的数据年龄达到该值时执行概率减半（毫秒）
+    #[serde(default = "default_ranking_age_half_life_ms")]
+    pub age_half_life_ms: u64,
+}
+
+fn default_ranking_hop_success_rate() -> f64 {
+    0.95
+}
+
+fn default_ranking_impact_weight() -> f64 {
+    0.5
+}
+
+fn default_ranking_age_half_life_ms() -> u64 {
+    2000
+}
+
+impl Default for RankingConfig {
+    fn default() -> Self {
+        Self {
+            hop_success_rate: default_ranking_hop_success_rate(),
+            impact_weight: default_ranking_impact_weight(),
+            age_half_life_ms: default_ranking_age_half_life_ms(),
+        }
+    }
+}
+
+/// 🔥 反压配置：模拟器负载过高时 Calculator 自适应收紧扫描
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackpressureConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 负载因子达到此值进入 Elevated
+    #[serde(default = "default_elevated_load")]
+    pub elevated_load: f64,
+    /// 负载因子达到此值进入 High
+    #[serde(default = "default_high_load")]
+    pub high_load: f64,
+    /// Elevated 时 min_roi 倍数
+    #[serde(default = "default_elevated_roi_multiplier")]
+    pub elevated_roi_multiplier: f64,
+    /// High 时 min_roi 倍数
+    #[serde(default = "default_high_roi_multiplier")]
+    pub high_roi_multiplier: f64,
+    /// High 时 Hybrid 降级为 Fast
+    #[serde(default = "default_true")]
+    pub downgrade_hybrid_to_fast: bool,
+    /// 模拟排队容量
+    #[serde(default = "default_backpressure_queue_capacity")]
+    pub queue_capacity: usize,
+}
+
+fn default_elevated_load() -> f64 {
+    0.5
+}
+
+fn default_high_load() -> f64 {
+    0.8
+}
+
+fn default_elevated_roi_multiplier() -> f64 {
+    1.5
+}
+
+fn default_high_roi_multiplier() -> f64 {
+    3.0
+}
+
+fn default_backpressure_queue_capacity() -> usize {
+    50
+}
+
 /// 🎯 链上模拟配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SimulationConfig {
@@ -216,6 +723,36 @@ pub struct SimulationConfig {
     /// 模拟超时（毫秒）
     #[serde(default = "default_simulation_timeout")]
     pub simulation_timeout_ms: u64,
+    /// 启用置信度校准（期望值门控替代置信度阈值）
+    #[serde(default)]
+    pub calibration_enabled: bool,
+    /// 期望值下限（%）：校准概率 × 理论利润
+    #[serde(default = "default_min_expected_value")]
+    pub min_expected_value_pct: f64,
+    /// 校准表重新拟合间隔（小时）
+    #[serde(default = "default_calibration_refresh_hours")]
+    pub calibration_refresh_hours: u64,
+    /// 至少积累这么多复核结果才拟合校准表
+    #[serde(default = "default_calibration_min_samples")]
+    pub calibration_min_samples: usize,
+    /// 置信度桶内至少这么多复核结果才按期望值门控（否则沿用 min_confidence_for_simulation）
+    #[serde(default = "default_calibration_min_bucket_samples")]
+    pub calibration_min_bucket_samples: u64,
+    /// 门控拒绝的机会每 N 个仍模拟一个，持续积累低置信度区间的复核结果（0 = 关闭）
+    #[serde(default = "default_calibration_explore_every")]
+    pub calibration_explore_every: u64,
+    /// 交易级模拟的 payer（配置后每次扫描对最佳机会构建真实 swap 交易并 simulateTransaction）
+    #[serde(default)]
+    pub payer_pubkey: Option<String>,
+}
+
+impl SimulationConfig {
+    /// 模拟使用的 RPC URL（未配置时从 WebSocket URL 转换为 HTTP URL）
+    pub fn resolve_rpc_url(&self, websocket_url: &str) -> String {
+        self.rpc_url.clone().unwrap_or_else(|| {
+            websocket_url.replace("wss://", "https://").replace("ws://", "http://")
+        })
+    }
 }
 
 fn default_min_confidence() -> f64 {
@@ -230,6 +767,26 @@ fn default_simulation_timeout() -> u64 {
     500
 }
 
+fn default_min_expected_value() -> f64 {
+    0.1
+}
+
+fn default_calibration_refresh_hours() -> u64 {
+    24
+}
+
+fn default_calibration_min_samples() -> usize {
+    crate::calibration::DEFAULT_MIN_FIT_SAMPLES
+}
+
+fn default_calibration_min_bucket_samples() -> u64 {
+    crate::calibration::DEFAULT_MIN_BUCKET_SAMPLES
+}
+
+fn default_calibration_explore_every() -> u64 {
+    crate::calibration::DEFAULT_EXPLORE_EVERY
+}
+
 /// 🚀 池子初始化配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InitializationConfig {
@@ -319,6 +876,46 @@ pub struct LstDetectorConfig {
     pub marinade: Option<MarinadeConfig>,
     #[serde(default)]
     pub jito: Option<JitoConfig>,
+    /// 额外的 LST（bSOL / jucySOL 等）；同一 mint 出现在这里时覆盖 marinade / jito 段
+    #[serde(default)]
+    pub tokens: Vec<LstTokenConfig>,
+    /// 公允价值随 epoch 进度向推算的下一 epoch 比率插值（关闭时直接使用链上当前比率）
+    #[serde(default = "default_true")]
+    pub epoch_interpolation: bool,
+    /// 推算下一 epoch 比率时的默认年化收益（百分比）
+    #[serde(default = "default_lst_apy")]
+    pub default_apy_percent: f64,
+}
+
+/// Stake pool 账户布局
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LstPoolType {
+    /// Marinade State 账户
+    Marinade,
+    /// 标准 SPL stake pool 程序
+    SplStakePool,
+    /// Sanctum 部署的 SPL stake pool（账户布局相同）
+    Sanctum,
+}
+
+/// 一个 LST 的注册信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LstTokenConfig {
+    /// 池子交易对中使用的代币符号，例如 "bSOL"
+    pub symbol: String,
+    pub mint: String,
+    pub stake_pool_address: String,
+    #[serde(default = "default_lst_pool_type")]
+    pub pool_type: LstPoolType,
+    #[serde(default = "default_lst_unstake_fee")]
+    pub unstake_fee: f64,
+    /// 赎回等待时间（秒）
+    #[serde(default = "default_lst_unstake_delay")]
+    pub unstake_delay_secs: u64,
+    /// 年化收益（百分比），历史比率不足时用于推算下一 epoch 比率；未设置时用 default_apy_percent
+    #[serde(default)]
+    pub apy_percent: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -367,77 +964,2021 @@ fn default_jito_unstake_fee() -> f64 {
     0.001
 }
 
-/// 状态层配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct StateLayerConfig {
-    /// 状态层类型
-    ///
-    /// 可选值：
-    /// - "rwlock": RwLock<HashMap>，适合低频更新（<100次/秒）
-    /// - "dashmap": DashMap，适合高频更新（>100次/秒）
-    ///
-    /// 默认值："rwlock"
-    #[serde(default = "default_state_layer_type")]
-    pub state_type: String,
+fn default_lst_pool_type() -> LstPoolType {
+    LstPoolType::SplStakePool
+}
 
-    /// 是否自动选择最优状态层类型
-    ///
-    /// 如果启用，根据预期负载自动选择 RwLock 或 DashMap
-    ///
-    /// 默认值：false
-    #[serde(default)]
-    pub auto_select: bool,
+fn default_lst_unstake_fee() -> f64 {
+    0.001
+}
 
-    /// 预期更新频率（次/秒）
-    ///
-    /// 用于自动选择时的参考值
-    ///
-    /// 默认值：50.0
-    #[serde(default = "default_expected_update_rate")]
-    pub expected_update_rate: f64,
+fn default_lst_unstake_delay() -> u64 {
+    2 * 24 * 3600 // 约一个 epoch
 }
 
-fn default_state_layer_type() -> String {
-    "rwlock".to_string()
+fn default_lst_apy() -> f64 {
+    7.0
 }
 
-fn default_expected_update_rate() -> f64 {
-    50.0
+/// 📈 可用性 SLO 配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SloConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 采样间隔（秒）
+    #[serde(default = "default_slo_sample_interval")]
+    pub sample_interval_secs: u64,
+    /// 默认可用率目标（百分比）
+    #[serde(default = "default_slo_target")]
+    pub default_target_percent: f64,
+    /// 按组件覆盖目标，如 feed_connection = 99.5
+    #[serde(default)]
+    pub targets: std::collections::HashMap<String, f64>,
+    /// 数据源无更新超过此时长视为断开（秒）
+    #[serde(default = "default_slo_feed_stale")]
+    pub feed_stale_secs: u64,
 }
 
-impl Config {
-    /// Load configuration from a TOML file
-    pub fn load_from_file(path: &str) -> Result<Self> {
-        let content = fs::read_to_string(path)
-            .with_context(|| format!("Failed to read config file: {}", path))?;
+fn default_slo_sample_interval() -> u64 {
+    10
+}
 
-        let config: Config = toml::from_str(&content)
-            .with_context(|| "Failed to parse config TOML")?;
+fn default_slo_target() -> f64 {
+    99.0
+}
 
-        // Validate configuration
-        if config.websocket.url.is_empty() {
-            anyhow::bail!("WebSocket URL cannot be empty");
-        }
+fn default_slo_feed_stale() -> u64 {
+    30
+}
 
-        if config.pools.is_empty() {
-            anyhow::bail!("At least one pool must be configured");
-        }
+/// 🔔 告警分发配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertsConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub sinks: Vec<AlertSinkConfig>,
+}
 
-        for pool in &config.pools {
-            if pool.address.is_empty() {
-                anyhow::bail!("Pool address cannot be empty");
-            }
-            if pool.name.is_empty() {
-                anyhow::bail!("Pool name cannot be empty");
-            }
+/// 单个告警 sink 配置
+///
+/// ```toml
+/// [[alerts.sinks]]
+/// name = "staging"
+/// mode = "firehose"        # production | firehose
+/// template = "detailed"    # compact | detailed
+/// max_per_minute = 30
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertSinkConfig {
+    pub name: String,
+    #[serde(default = "default_alert_mode")]
+    pub mode: crate::alerts::SinkMode,
+    #[serde(default = "default_alert_template")]
+    pub template: crate::alerts::AlertTemplate,
+    /// 最小ROI（production）
+    #[serde(default = "default_alert_min_roi")]
+    pub min_roi_percent: f64,
+    /// 同一路径冷却（production，秒）
+    #[serde(default = "default_alert_cooldown")]
+    pub cooldown_secs: u64,
+    /// 每分钟消息上限（firehose，0 = 不限）
+    #[serde(default = "default_alert_max_per_minute")]
+    pub max_per_minute: usize,
+}
+
+fn default_alert_mode() -> crate::alerts::SinkMode {
+    crate::alerts::SinkMode::Production
+}
+
+fn default_alert_template() -> crate::alerts::AlertTemplate {
+    crate::alerts::AlertTemplate::Compact
+}
+
+fn default_alert_min_roi() -> f64 {
+    0.5
+}
+
+fn default_alert_cooldown() -> u64 {
+    60
+}
+
+fn default_alert_max_per_minute() -> usize {
+    30
+}
+
+/// 🧩 多实例分片配置
+///
+/// ```toml
+/// [sharding]
+/// shard_index = 0
+/// shard_count = 2
+/// anchor_pools = ["SOL/USDC (Raydium V4)"]   # 地址或名称，所有实例都订阅
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardingConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub shard_index: usize,
+    pub shard_count: usize,
+    /// 每个分片在哈希环上的虚拟节点数
+    #[serde(default = "default_shard_virtual_nodes")]
+    pub virtual_nodes: usize,
+    #[serde(default)]
+    pub anchor_pools: Vec<String>,
+}
+
+fn default_shard_virtual_nodes() -> usize {
+    128
+}
+
+/// 🧪 合成池子配置
+///
+/// ```toml
+/// [[synthetic_pools]]
+/// name = "sol_usdt_bridge"
+/// pair = "SOL/USDT"
+/// dex = "Orca"
+/// quote_reserve = 2000000.0
+/// anchor_pair = "SOL/USDC"
+/// price_offset_percent = 0.5
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyntheticPoolConfig {
+    pub name: String,
+    pub pair: String,
+    pub dex: String,
+    #[serde(default)]
+    pub base_reserve: f64,
+    pub quote_reserve: f64,
+    #[serde(default)]
+    pub anchor_pair: Option<String>,
+    #[serde(default)]
+    pub price_offset_percent: f64,
+}
+
+/// 🧪 what-if 扫描配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhatIfConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 每 N 次生产扫描触发一次 what-if 扫描
+    #[serde(default = "default_whatif_every_n")]
+    pub every_n_scans: u64,
+}
+
+fn default_whatif_every_n() -> u64 {
+    10
+}
+
+/// 💲 USD 定价配置
+///
+/// 美元价格从 PriceCache 中最深的稳定币池子推导，其余代币经锚定代币换算
+/// （mSOL = mSOL/SOL × SOL/USDC）。超过 max_age_ms 的池子不参与定价。
+///
+/// ```toml
+/// [price_oracle]
+/// anchor_tokens = ["SOL"]
+/// max_age_ms = 30000
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceOracleConfig {
+    /// 三角换算使用的锚定代币（按顺序尝试，本身需与 USDC / USDT 直接配对）
+    #[serde(default = "default_price_oracle_anchor_tokens")]
+    pub anchor_tokens: Vec<String>,
+    /// 池子价格的新鲜度上限（毫秒）
+    #[serde(default = "default_price_oracle_max_age_ms")]
+    pub max_age_ms: u64,
+}
+
+impl Default for PriceOracleConfig {
+    fn default() -> Self {
+        Self {
+            anchor_tokens: default_price_oracle_anchor_tokens(),
+            max_age_ms: default_price_oracle_max_age_ms(),
+        }
+    }
+}
+
+/// 🔌 DEX 开关（[dexes]）
+///
+/// 某个 DEX 程序升级后反序列化器出问题时，不用逐个删池子配置：
+///
+/// ```toml
+/// [dexes]
+/// goonfi = false                          # 按 pool_type 名称（别名也可以）禁用
+/// allowlist = ["amm_v4", "whirlpool"]     # 可选：只启用这些 DEX
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DexesConfig {
+    /// 设置后只有列出的 DEX 启用（开关表中的 false 仍然生效）
+    #[serde(default)]
+    pub allowlist: Option<Vec<String>>,
+    /// DEX 名称 -> 是否启用（未列出的默认启用）
+    #[serde(flatten)]
+    pub switches: HashMap<String, bool>,
+}
+
+fn default_price_oracle_anchor_tokens() -> Vec<String> {
+    vec!["SOL".to_string()]
+}
+
+fn default_price_oracle_max_age_ms() -> u64 {
+    30_000
+}
+
+/// 🚨 错误追踪配置
+///
+/// 每个 error_key 保留最近的发生时间（环形缓冲，上限 max_occurrences_per_key），
+/// 用于按窗口统计错误速率。error_key 总数超过 max_error_keys 时淘汰最久未发生的 key。某个 pool_type 在窗口内的反序列化失败超过阈值时，
+/// 标记为降级并在 /health 中报告。
+///
+/// ```toml
+/// [error_tracking]
+/// rate_window_secs = 300
+/// deserialize_failure_threshold = 50
+/// max_error_keys = 500
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorTrackingConfig {
+    /// 错误速率统计窗口（秒）
+    #[serde(default = "default_error_rate_window_secs")]
+    pub rate_window_secs: u64,
+    /// 每个 error_key 最多保留的发生时间数
+    #[serde(default = "default_error_max_occurrences_per_key")]
+    pub max_occurrences_per_key: usize,
+    /// 窗口内某 pool_type 反序列化失败超过该次数时标记为降级
+    #[serde(default = "default_deserialize_failure_threshold")]
+    pub deserialize_failure_threshold: usize,
+    /// 最多保留的 error_key 数
+    #[serde(default = "default_error_max_keys")]
+    pub max_error_keys: usize,
+}
+
+impl Default for ErrorTrackingConfig {
+    fn default() -> Self {
+        Self {
+            rate_window_secs: default_error_rate_window_secs(),
+            max_occurrences_per_key: default_error_max_occurrences_per_key(),
+            deserialize_failure_threshold: default_deserialize_failure_threshold(),
+            max_error_keys: default_error_max_keys(),
+        }
+    }
+}
+
+fn default_error_rate_window_secs() -> u64 {
+    300
+}
+
+fn default_error_max_occurrences_per_key() -> usize {
+    1000
+}
+
+fn default_deserialize_failure_threshold() -> usize {
+    50
+}
+
+fn default_error_max_keys() -> usize {
+    500
+}
+
+/// 🧾 结构化机会输出配置
+///
+/// 每个通过验证的机会序列化为一行 JSON（路径每一跳、金额、ROI、所用池子的 slot / 更新时间、
+/// 触发来源与验证状态），供下游工具消费。控制台的人类可读输出不受影响。
+///
+/// ```toml
+/// [[output.sinks]]
+/// type = "jsonl_file"
+/// path = "logs/opportunities.jsonl"
+/// max_bytes = 104857600    # 超过后轮转为 .1、.2 ...
+/// max_files = 5
+///
+/// [[output.sinks]]
+/// type = "stdout_json"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub sinks: Vec<OutputSinkConfig>,
+}
+
+/// 单个输出 sink
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputSinkConfig {
+    #[serde(rename = "type")]
+    pub kind: OutputSinkKind,
+    /// jsonl_file 的文件路径
+    #[serde(default)]
+    pub path: Option<String>,
+    /// jsonl_file 单个文件的大小上限（字节），超过后轮转
+    #[serde(default = "default_output_max_bytes")]
+    pub max_bytes: u64,
+    /// jsonl_file 保留的轮转文件数（不含当前文件）
+    #[serde(default = "default_output_max_files")]
+    pub max_files: usize,
+}
+
+/// 输出 sink 类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputSinkKind {
+    /// 追加写入 jsonl 文件，按大小轮转
+    JsonlFile,
+    /// 每行一个 JSON 对象写到 stdout
+    StdoutJson,
+}
+
+fn default_output_max_bytes() -> u64 {
+    100 * 1024 * 1024
+}
+
+fn default_output_max_files() -> usize {
+    5
+}
+
+/// 📏 交易对价差监控配置
+///
+/// 对每个至少有 2 个池子的交易对计算各场所间的最大价差，超过 `threshold_bps` 并持续
+/// `sustain_secs` 秒后告警一次（warn 日志；配置了 `webhook_url` 时同时 POST JSON）。
+/// 价差回落到阈值以下后重新计时。
+///
+/// ```toml
+/// [spread_monitor]
+/// threshold_bps = 50
+/// sustain_secs = 30
+/// webhook_url = "https://hooks.example.com/spread"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpreadMonitorConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 告警阈值（基点）
+    #[serde(default = "default_spread_threshold_bps")]
+    pub threshold_bps: f64,
+    /// 价差需要持续超过阈值的秒数
+    #[serde(default = "default_spread_sustain_secs")]
+    pub sustain_secs: u64,
+    /// 可选：告警 POST 的目标地址（http / https）
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+fn default_spread_threshold_bps() -> f64 {
+    50.0
+}
+
+fn default_spread_sustain_secs() -> u64 {
+    30
+}
+
+/// 🌡️ 价差热力图配置
+///
+/// 对同一交易对上每个有序池子组合（buy -> sell，手续费已计入）统计 `window_secs` 窗口内
+/// 的价差：时间加权的 mean / p95、max、超过 `threshold_bps` 的累计时长。每个组合最多保留
+/// `max_samples_per_cell` 个样本；定期指标日志输出超阈值时间最长的 `top_n` 个组合。
+///
+/// ```toml
+/// [spread_heatmap]
+/// window_secs = 3600
+/// threshold_bps = 10
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpreadHeatmapConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 滚动窗口（秒）
+    #[serde(default = "default_heatmap_window_secs")]
+    pub window_secs: u64,
+    /// 统计超阈值时长的阈值（基点，净价差）
+    #[serde(default = "default_heatmap_threshold_bps")]
+    pub threshold_bps: f64,
+    /// 每个组合最多保留的样本数（超出时丢弃最旧的）
+    #[serde(default = "default_heatmap_max_samples_per_cell")]
+    pub max_samples_per_cell: usize,
+    /// 指标日志输出的组合数
+    #[serde(default = "default_heatmap_top_n")]
+    pub top_n: usize,
+}
+
+fn default_heatmap_window_secs() -> u64 {
+    3600
+}
+
+fn default_heatmap_threshold_bps() -> f64 {
+    10.0
+}
+
+fn default_heatmap_max_samples_per_cell() -> usize {
+    2048
+}
+
+fn default_heatmap_top_n() -> usize {
+    5
+}
+
+/// 🧯 池子级熔断配置
+///
+/// 单次更新价格变化超过 `max_price_jump_percent`，或储备量变化超过
+/// `max_reserve_change_percent` × slot 间隔时隔离池子：缓存和统计照常更新，但新鲜度快照
+/// 和路由图排除它。`cooldown_secs` 之后连续 `clear_after_sane_updates` 次正常更新才解除。
+/// 未配置时按默认值启用；`[[pools]] max_price_jump_percent` 可覆盖单个池子的价格阈值。
+///
+/// ```toml
+/// [circuit_breaker]
+/// max_price_jump_percent = 30
+/// max_reserve_change_percent = 50
+/// cooldown_secs = 60
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 单次更新允许的最大价格变化（%）
+    #[serde(default = "default_breaker_price_jump_percent")]
+    pub max_price_jump_percent: f64,
+    /// 每个 slot 间隔允许的最大储备量变化（%）
+    #[serde(default = "default_breaker_reserve_change_percent")]
+    pub max_reserve_change_percent: f64,
+    /// 隔离后至少保持的秒数（再次触发时重新计时）
+    #[serde(default = "default_breaker_cooldown_secs")]
+    pub cooldown_secs: u64,
+    /// 解除隔离需要的连续正常更新次数
+    #[serde(default = "default_breaker_clear_after")]
+    pub clear_after_sane_updates: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_price_jump_percent: default_breaker_price_jump_percent(),
+            max_reserve_change_percent: default_breaker_reserve_change_percent(),
+            cooldown_secs: default_breaker_cooldown_secs(),
+            clear_after_sane_updates: default_breaker_clear_after(),
+        }
+    }
+}
+
+fn default_breaker_price_jump_percent() -> f64 {
+    30.0
+}
+
+fn default_breaker_reserve_change_percent() -> f64 {
+    50.0
+}
+
+fn default_breaker_cooldown_secs() -> u64 {
+    60
+}
+
+fn default_breaker_clear_after() -> u32 {
+    2
+}
+
+/// 🛰️ 共享 HTTP RPC 配置
+///
+/// 池子初始化、vault 预取、Phoenix 刷新、Stake Pool 读取、mint 元数据和链上模拟共用
+/// 同一组端点和同一个令牌桶。`urls` 为空时依次使用 `[initialization] rpc_urls` 和
+/// WebSocket 地址对应的 HTTP 地址；`requests_per_second` 未配置时沿用
+/// `[websocket] rpc_requests_per_second`。
+///
+/// ```toml
+/// [rpc]
+/// urls = ["https://primary.example", "https://backup.example"]
+/// requests_per_second = 10
+/// burst = 5
+/// failover_after_failures = 3
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcConfig {
+    /// 端点列表（按故障转移顺序）
+    #[serde(default)]
+    pub urls: Vec<String>,
+    /// 长期请求速率（次/秒，0 = 不限）
+    #[serde(default)]
+    pub requests_per_second: Option<f64>,
+    /// 令牌桶容量：空闲后允许连续发出的请求数
+    #[serde(default = "default_rpc_burst")]
+    pub burst: u32,
+    /// 单个请求超时（毫秒）
+    #[serde(default = "default_rpc_timeout_ms")]
+    pub timeout_ms: u64,
+    /// 同一端点连续连接失败 / 超时该次数后切换（429 立即切换）
+    #[serde(default = "default_rpc_failover_after")]
+    pub failover_after_failures: u32,
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self {
+            urls: Vec::new(),
+            requests_per_second: None,
+            burst: default_rpc_burst(),
+            timeout_ms: default_rpc_timeout_ms(),
+            failover_after_failures: default_rpc_failover_after(),
+        }
+    }
+}
+
+fn default_rpc_burst() -> u32 {
+    5
+}
+
+fn default_rpc_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_rpc_failover_after() -> u32 {
+    3
+}
+
+/// 📣 机会通知配置
+///
+/// Calculator 把验证通过的机会经通道交给后台分发任务，每个 sink 独立过滤、独立限流，
+/// 投递失败按指数退避重试有限次数。同一机会（指纹）在 `cooldown_secs` 内每个 sink 只推送一次。
+///
+/// ```toml
+/// [notifications]
+/// max_retries = 3
+/// retry_backoff_ms = 1000
+///
+/// [[notifications.sinks]]
+/// name = "ops"
+/// type = "webhook"               # webhook | telegram
+/// url = "https://hooks.example.com/arb"
+/// min_roi_percent = 0.5
+/// min_profit_usd = 5.0
+/// max_hops = 4
+/// cooldown_secs = 600
+///
+/// [[notifications.sinks]]
+/// name = "team-chat"
+/// type = "telegram"
+/// bot_token = "123456:ABC..."
+/// chat_id = "-1001234567890"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationsConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Calculator -> 分发任务的通道容量（满了直接丢弃并计数，不阻塞扫描）
+    #[serde(default = "default_notification_queue_capacity")]
+    pub queue_capacity: usize,
+    /// 投递失败后的最大重试次数
+    #[serde(default = "default_notification_max_retries")]
+    pub max_retries: u32,
+    /// 首次重试前的等待（毫秒），之后每次翻倍
+    #[serde(default = "default_notification_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+    #[serde(default)]
+    pub sinks: Vec<NotificationSinkConfig>,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            queue_capacity: default_notification_queue_capacity(),
+            max_retries: default_notification_max_retries(),
+            retry_backoff_ms: default_notification_retry_backoff_ms(),
+            sinks: Vec::new(),
+        }
+    }
+}
+
+/// 单个通知 sink
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationSinkConfig {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: NotificationSinkKind,
+    /// webhook 的 POST 地址
+    #[serde(default)]
+    pub url: Option<String>,
+    /// telegram 机器人 token
+    #[serde(default)]
+    pub bot_token: Option<String>,
+    /// telegram 会话 ID（群组为负数）
+    #[serde(default)]
+    pub chat_id: Option<String>,
+    /// telegram Bot API 地址（自建代理时覆盖）
+    #[serde(default = "default_telegram_api_url")]
+    pub telegram_api_url: String,
+    /// 最小 ROI（百分比）
+    #[serde(default)]
+    pub min_roi_percent: f64,
+    /// 最小美元利润（没有美元价格的机会不推送）
+    #[serde(default)]
+    pub min_profit_usd: Option<f64>,
+    /// 最多跳数
+    #[serde(default)]
+    pub max_hops: Option<usize>,
+    /// 同一机会（指纹）的推送间隔（秒）
+    #[serde(default = "default_notification_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+/// 通知 sink 类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationSinkKind {
+    /// POST JSON 负载
+    Webhook,
+    /// Telegram Bot API sendMessage
+    Telegram,
+}
+
+fn default_notification_queue_capacity() -> usize {
+    256
+}
+
+fn default_notification_max_retries() -> u32 {
+    3
+}
+
+fn default_notification_retry_backoff_ms() -> u64 {
+    1000
+}
+
+fn default_telegram_api_url() -> String {
+    "https://api.telegram.org".to_string()
+}
+
+fn default_notification_cooldown_secs() -> u64 {
+    600
+}
+
+/// ⛽ 执行成本模型配置
+///
+/// 路由器按 lamports 估算一笔套利交易的执行成本，再经 USD/SOL 价格换算成起始代币：
+/// 签名费 + Σ 每跳 compute units × 优先费率（micro-lamports/CU）+ 可选 Jito 小费。
+/// 优先费率由 getRecentPrioritizationFees 定期刷新，取不到时用 priority_fee_micro_lamports。
+///
+/// ```toml
+/// [execution_cost]
+/// base_fee_lamports = 5000
+/// priority_fee_micro_lamports = 10000   # 静态兜底
+/// priority_fee_percentile = 75
+/// refresh_secs = 10
+/// jito_tip_lamports = 100000            # 0 = 不走 Jito
+///
+/// [execution_cost.compute_units]        # 按 DEX 覆盖每跳 CU（名称模糊匹配）
+/// whirlpool = 120000
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionCostConfig {
+    /// 每个签名的基础费（lamports）
+    #[serde(default = "default_execution_base_fee_lamports")]
+    pub base_fee_lamports: u64,
+    #[serde(default = "default_execution_signatures")]
+    pub signatures: u64,
+    /// 交易固定开销（compute budget 指令、ATA 检查等）的 CU
+    #[serde(default = "default_execution_overhead_compute_units")]
+    pub overhead_compute_units: u64,
+    /// 每跳 CU 覆盖：DEX 名称（小写子串匹配）-> CU
+    #[serde(default)]
+    pub compute_units: HashMap<String, u64>,
+    /// 静态优先费率（micro-lamports/CU），实时费率取不到时使用
+    #[serde(default = "default_execution_priority_fee")]
+    pub priority_fee_micro_lamports: u64,
+    /// 取 getRecentPrioritizationFees 结果的百分位
+    #[serde(default = "default_execution_priority_fee_percentile")]
+    pub priority_fee_percentile: u8,
+    /// 优先费率刷新间隔（秒），0 = 不刷新，只用静态值
+    #[serde(default = "default_execution_refresh_secs")]
+    pub refresh_secs: u64,
+    /// 固定 Jito 小费（lamports），0 = 不加
+    #[serde(default)]
+    pub jito_tip_lamports: u64,
+}
+
+impl Default for ExecutionCostConfig {
+    fn default() -> Self {
+        Self {
+            base_fee_lamports: default_execution_base_fee_lamports(),
+            signatures: default_execution_signatures(),
+            overhead_compute_units: default_execution_overhead_compute_units(),
+            compute_units: HashMap::new(),
+            priority_fee_micro_lamports: default_execution_priority_fee(),
+            priority_fee_percentile: default_execution_priority_fee_percentile(),
+            refresh_secs: default_execution_refresh_secs(),
+            jito_tip_lamports: 0,
+        }
+    }
+}
+
+fn default_execution_base_fee_lamports() -> u64 {
+    5_000
+}
+
+fn default_execution_signatures() -> u64 {
+    1
+}
+
+fn default_execution_overhead_compute_units() -> u64 {
+    20_000
+}
+
+fn default_execution_priority_fee() -> u64 {
+    10_000
+}
+
+fn default_execution_priority_fee_percentile() -> u8 {
+    75
+}
+
+fn default_execution_refresh_secs() -> u64 {
+    10
+}
+
+/// ⛓️ 链头 slot 跟踪配置
+///
+/// 每隔 `poll_interval_ms` 通过共享 RPC 管理器调用 getSlot（confirmed，与订阅一致）维护链头，
+/// 每次池子更新记录 head_lag = 链头 − 更新 slot（每池滚动 p50 / p95，见 GET /stats/slot_lag）。
+///
+/// ```toml
+/// [chain_head]
+/// enabled = true
+/// poll_interval_ms = 1000
+/// lag_warn_p95_slots = 8       # 池子 p95 延迟超过该 slot 数时告警，0 = 不告警
+/// anchor_snapshots = false     # true：一致性快照以链头（而非缓存中最大 slot）为最新 slot
+/// ```
+///
+/// ⚠️ `anchor_snapshots = true` 时整个数据源一起落后也会被排除出快照（默认只看池子之间的相对 slot）。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainHeadConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_chain_head_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    #[serde(default = "default_chain_head_lag_warn_p95_slots")]
+    pub lag_warn_p95_slots: u64,
+    #[serde(default)]
+    pub anchor_snapshots: bool,
+}
+
+impl Default for ChainHeadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            poll_interval_ms: default_chain_head_poll_interval_ms(),
+            lag_warn_p95_slots: default_chain_head_lag_warn_p95_slots(),
+            anchor_snapshots: false,
+        }
+    }
+}
+
+fn default_chain_head_poll_interval_ms() -> u64 {
+    1_000
+}
+
+fn default_chain_head_lag_warn_p95_slots() -> u64 {
+    8
+}
+
+/// 🛟 任务监督配置
+///
+/// WebSocket、Coordinator、Calculator、API 服务和各类刷新任务 panic 后按退避自动重启；
+/// 一小时内重启超过 `max_restarts_per_hour` 次后放弃，/health 中该组件报 down。
+///
+/// ```toml
+/// [supervisor]
+/// max_restarts_per_hour = 10
+/// restart_backoff_ms = 1000      # 首次重启前的退避，之后指数增长（full jitter）
+/// max_restart_backoff_ms = 30000
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupervisorConfig {
+    #[serde(default = "default_supervisor_max_restarts_per_hour")]
+    pub max_restarts_per_hour: u32,
+    #[serde(default = "default_supervisor_restart_backoff_ms")]
+    pub restart_backoff_ms: u64,
+    #[serde(default = "default_supervisor_max_restart_backoff_ms")]
+    pub max_restart_backoff_ms: u64,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            max_restarts_per_hour: default_supervisor_max_restarts_per_hour(),
+            restart_backoff_ms: default_supervisor_restart_backoff_ms(),
+            max_restart_backoff_ms: default_supervisor_max_restart_backoff_ms(),
+        }
+    }
+}
+
+fn default_supervisor_max_restarts_per_hour() -> u32 {
+    10
+}
+
+fn default_supervisor_restart_backoff_ms() -> u64 {
+    1_000
+}
+
+fn default_supervisor_max_restart_backoff_ms() -> u64 {
+    30_000
+}
+
+/// 🎯 重点交易对盯盘配置
+///
+/// 列出的交易对由独立的轻量任务盯盘：状态层每次更新其中任一池子，都立即重算该交易对
+/// 所有池子两两之间的价差矩阵（含两边手续费，按 `trade_size` 计算两跳路径），净 ROI
+/// 达到 `min_roi_percent` 时立即上报并写库。不经过 Coordinator，也就不受冷却和去抖限制。
+///
+/// ```toml
+/// [focus]
+/// pairs = ["SOL/USDC", "JUP/USDC"]
+/// trade_size = 1000.0        # quote 代币数量
+/// min_roi_percent = 0.05
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 交易对名称（"BASE/QUOTE"，按代币别名归一，wSOL/USDC 与 SOL/USDC 相同）
+    #[serde(default)]
+    pub pairs: Vec<String>,
+    /// 计算两跳路径的输入金额（quote 代币数量）
+    #[serde(default = "default_focus_trade_size")]
+    pub trade_size: f64,
+    /// 上报阈值：扣除手续费和执行成本后的净 ROI（百分比）
+    #[serde(default = "default_focus_min_roi_percent")]
+    pub min_roi_percent: f64,
+}
+
+impl Default for FocusConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            pairs: Vec::new(),
+            trade_size: default_focus_trade_size(),
+            min_roi_percent: default_focus_min_roi_percent(),
+        }
+    }
+}
+
+fn default_focus_trade_size() -> f64 {
+    1_000.0
+}
+
+fn default_focus_min_roi_percent() -> f64 {
+    0.05
+}
+
+/// 🧱 机会验证规则流水线
+///
+/// 路由器输出的每条路径按 `rules` 的顺序逐条检查，第一条拒绝的规则记入
+/// `pool_cache_validation_rejections_total{rule}` 和 GET /stats/validation。
+/// 从 `rules` 中删掉某条规则即关闭该检查。
+///
+/// ```toml
+/// [validation]
+/// rules = ["no_pool_reuse", "max_data_age", "slot_alignment", "price_deviation",
+///          "min_liquidity", "max_single_hop_impact", "max_total_roi_sanity",
+///          "simulation_required_above_usd", "confirmed_required_above_usd"]
+/// max_data_age_ms = 2000
+/// max_slot_spread = 5
+/// max_price_deviation_percent = 5.0
+/// min_liquidity_multiplier = 10.0         # 输入侧储备 ≥ 该跳输入 × 10
+/// max_single_hop_impact_percent = 2.0     # 未设置时沿用 [router] max_hop_impact_percent
+/// max_total_roi_percent = 15.0            # 超过即拒绝（计算 / 数据错误）
+/// suspicious_roi_percent = 8.0            # 超过记录警告
+/// simulation_required_above_usd = 5000.0  # 0 = 不检查
+/// confirmed_required_above_usd = 1000.0   # 超过该金额不接受 processed 订阅的池子数据，0 = 不检查
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationConfig {
+    /// 执行的规则及顺序（见 `validation_rules::DEFAULT_RULES`）
+    #[serde(default = "default_validation_rules")]
+    pub rules: Vec<String>,
+    #[serde(default = "default_validation_max_data_age_ms")]
+    pub max_data_age_ms: u64,
+    #[serde(default = "default_validation_max_slot_spread")]
+    pub max_slot_spread: u64,
+    /// 池子当前价格相对发现时的最大偏离（%）
+    #[serde(default = "default_validation_max_price_deviation")]
+    pub max_price_deviation_percent: f64,
+    #[serde(default = "default_validation_min_liquidity_multiplier")]
+    pub min_liquidity_multiplier: f64,
+    /// 单跳最大价格冲击（%）；未设置时使用 [router] max_hop_impact_percent
+    #[serde(default)]
+    pub max_single_hop_impact_percent: Option<f64>,
+    #[serde(default = "default_validation_max_total_roi")]
+    pub max_total_roi_percent: f64,
+    #[serde(default = "default_validation_suspicious_roi")]
+    pub suspicious_roi_percent: f64,
+    /// 名义金额超过该值（美元）的路径必须配置交易级模拟（[simulation] payer_pubkey）
+    #[serde(default)]
+    pub simulation_required_above_usd: f64,
+    /// 📡 名义金额超过该值（美元）的路径，池子数据必须来自 confirmed 及以上的订阅
+    #[serde(default = "default_validation_confirmed_required_above_usd")]
+    pub confirmed_required_above_usd: f64,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self {
+            rules: default_validation_rules(),
+            max_data_age_ms: default_validation_max_data_age_ms(),
+            max_slot_spread: default_validation_max_slot_spread(),
+            max_price_deviation_percent: default_validation_max_price_deviation(),
+            min_liquidity_multiplier: default_validation_min_liquidity_multiplier(),
+            max_single_hop_impact_percent: None,
+            max_total_roi_percent: default_validation_max_total_roi(),
+            suspicious_roi_percent: default_validation_suspicious_roi(),
+            simulation_required_above_usd: 0.0,
+            confirmed_required_above_usd: default_validation_confirmed_required_above_usd(),
+        }
+    }
+}
+
+fn default_validation_rules() -> Vec<String> {
+    crate::validation_rules::DEFAULT_RULES.iter().map(|rule| rule.to_string()).collect()
+}
+
+fn default_validation_max_data_age_ms() -> u64 {
+    2000
+}
+
+fn default_validation_max_slot_spread() -> u64 {
+    5
+}
+
+fn default_validation_max_price_deviation() -> f64 {
+    5.0
+}
+
+fn default_validation_min_liquidity_multiplier() -> f64 {
+    10.0
+}
+
+fn default_validation_max_total_roi() -> f64 {
+    15.0
+}
+
+fn default_validation_suspicious_roi() -> f64 {
+    8.0
+}
+
+fn default_validation_confirmed_required_above_usd() -> f64 {
+    1_000.0
+}
+
+/// 🧊 扫描输入抓取配置
+///
+/// `enabled = true` 时每次扫描前都把路由快照和路由配置写入 `dir` 下带时间戳的 JSON 文件
+/// （文件较大，只用于排查问题）。未开启时仍可用 `POST /debug/capture?scans=N` 抓取接下来的
+/// N 次扫描。文件可交给 `AdvancedRouter::scan_from_snapshot` 离线重放。
+///
+/// ```toml
+/// [debug_capture]
+/// enabled = false
+/// dir = "captures"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugCaptureConfig {
+    /// 抓取每一次扫描
+    #[serde(default)]
+    pub enabled: bool,
+    /// 抓取文件目录
+    #[serde(default = "default_debug_capture_dir")]
+    pub dir: String,
+}
+
+impl Default for DebugCaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: default_debug_capture_dir(),
+        }
+    }
+}
+
+fn default_debug_capture_dir() -> String {
+    "captures".to_string()
+}
+
+/// 🔍 vault 一致性巡检配置
+///
+/// 每隔 `interval_minutes` 比较缓存中 vault 型池子（SolFi V2、GoonFi 等）的储备与
+/// VaultReader 最近一次看到的 vault 余额，相对差超过 `max_divergence_percent` 的池子打警告日志
+/// （缓存储备与 vault 余额不一致正是 SolFi 幻影价格的成因）。未配置该段时按默认值开启。
+/// 单个池子可随时用 `GET /pools/{address}/consistency` 检查。
+///
+/// ```toml
+/// [vault_audit]
+/// enabled = true
+/// interval_minutes = 5
+/// max_divergence_percent = 1.0
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultAuditConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_vault_audit_interval_minutes")]
+    pub interval_minutes: u64,
+    /// 缓存储备与 vault 余额的最大相对差（%）
+    #[serde(default = "default_vault_audit_max_divergence_percent")]
+    pub max_divergence_percent: f64,
+}
+
+impl Default for VaultAuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_minutes: default_vault_audit_interval_minutes(),
+            max_divergence_percent: default_vault_audit_max_divergence_percent(),
+        }
+    }
+}
+
+fn default_vault_audit_interval_minutes() -> u64 {
+    5
+}
+
+fn default_vault_audit_max_divergence_percent() -> f64 {
+    1.0
+}
+
+/// 🕰️ 逐池价格历史配置
+///
+/// 每个池子在内存中保留最多 `max_entries_per_pool` 条 (时间, slot, 价格, 储备) 样本：
+/// 价格相对上一条样本变化达到 `min_change_percent` 时记录，`heartbeat_secs` 内没有样本时补一条当前值。
+/// 内存上限约为 池子数 × max_entries_per_pool × 40 字节，与数据库是否开启无关。未配置该段时按默认值开启。
+///
+/// ```toml
+/// [history]
+/// enabled = true
+/// max_entries_per_pool = 2000
+/// min_change_percent = 0.01
+/// heartbeat_secs = 30
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_history_max_entries_per_pool")]
+    pub max_entries_per_pool: usize,
+    #[serde(default = "default_history_min_change_percent")]
+    pub min_change_percent: f64,
+    #[serde(default = "default_history_heartbeat_secs")]
+    pub heartbeat_secs: u64,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_entries_per_pool: default_history_max_entries_per_pool(),
+            min_change_percent: default_history_min_change_percent(),
+            heartbeat_secs: default_history_heartbeat_secs(),
+        }
+    }
+}
+
+fn default_history_max_entries_per_pool() -> usize {
+    2_000
+}
+
+fn default_history_min_change_percent() -> f64 {
+    0.01
+}
+
+fn default_history_heartbeat_secs() -> u64 {
+    30
+}
+
+/// 🧭 外部参考价格交叉校验配置
+///
+/// 每隔 `poll_interval_secs` 从外部 REST 接口（Pyth Hermes 或任何返回 JSON 的价格接口）拉取主要交易对的
+/// 参考价格，和缓存中对应池子的价格比较：偏离超过 `warn_deviation_percent` 标记并告警，超过
+/// `exclude_deviation_percent` 时排除出路由和快照。用来在几秒内发现新反序列化器的小数位错误和 base/quote 颠倒。
+/// 多个 feed 共用同一个 `url` 时每轮只请求一次；参考价格超过 `max_age_secs` 未更新时不再参与比较
+/// （外部接口故障只打警告，已排除的池子随之恢复）。不配置该段时不启用。
+///
+/// ```toml
+/// [reference_prices]
+/// enabled = true
+/// poll_interval_secs = 10
+/// warn_deviation_percent = 2.0
+/// exclude_deviation_percent = 10.0
+///
+/// [[reference_prices.feeds]]
+/// pair = "SOL/USD"  # USD 匹配 USDC / USDT 池子
+/// url = "https://hermes.pyth.network/v2/updates/price/latest?ids[]=0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d"
+/// price_pointer = "/parsed/0/price/price"
+/// exponent_pointer = "/parsed/0/price/expo"  # 可选：价格 = 值 × 10^expo
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferencePriceConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 拉取间隔（秒），也是对外部接口的请求频率上限
+    #[serde(default = "default_reference_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    /// 偏离超过该值（%）时标记并告警
+    #[serde(default = "default_reference_warn_deviation_percent")]
+    pub warn_deviation_percent: f64,
+    /// 偏离超过该值（%）时排除出路由
+    #[serde(default = "default_reference_exclude_deviation_percent")]
+    pub exclude_deviation_percent: f64,
+    /// 参考价格的最长有效期（秒）
+    #[serde(default = "default_reference_max_age_secs")]
+    pub max_age_secs: u64,
+    #[serde(default)]
+    pub feeds: Vec<ReferenceFeedConfig>,
+}
+
+impl Default for ReferencePriceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            poll_interval_secs: default_reference_poll_interval_secs(),
+            warn_deviation_percent: default_reference_warn_deviation_percent(),
+            exclude_deviation_percent: default_reference_exclude_deviation_percent(),
+            max_age_secs: default_reference_max_age_secs(),
+            feeds: Vec::new(),
+        }
+    }
+}
+
+/// 一个参考价格来源：从 `url` 的 JSON 响应中按 JSON Pointer 取出 `pair` 的价格
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferenceFeedConfig {
+    /// "BASE/QUOTE"，1 BASE 值多少 QUOTE
+    pub pair: String,
+    pub url: String,
+    /// 价格字段的 JSON Pointer（数字或数字字符串）
+    pub price_pointer: String,
+    /// 十进制指数字段的 JSON Pointer（Pyth 的 expo）
+    #[serde(default)]
+    pub exponent_pointer: Option<String>,
+}
+
+fn default_reference_poll_interval_secs() -> u64 {
+    10
+}
+
+fn default_reference_warn_deviation_percent() -> f64 {
+    2.0
+}
+
+fn default_reference_exclude_deviation_percent() -> f64 {
+    10.0
+}
+
+fn default_reference_max_age_secs() -> u64 {
+    120
+}
+
+/// 💼 库存感知配置
+///
+/// 扫描默认假设起始代币无限供应。配置实际持有的余额后，Calculator 在验证前对路径做后处理：
+/// 起始代币没有持有的路径按 `unheld` 过滤或排到最后；投入超过持有量的按持有量重新定价，
+/// 低于 router.min_roi_percent 的丢弃；`convert_from_held` 时尝试从持有的代币经最深的池子
+/// 前置兑换成起始代币（兑换损耗计入 ROI）。机会输出的 `capital` 字段给出实际需要的资金和来源库存。
+/// 不配置该段时不启用。
+///
+/// ```toml
+/// [inventory]
+/// balances = { SOL = 12.5, USDC = 2000.0 }
+/// unheld = "down_rank"  # filter（默认）| down_rank
+/// convert_from_held = true
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InventoryConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 代币符号 -> 可用数量（按代币别名归一化，wSOL 计入 SOL）
+    #[serde(default)]
+    pub balances: HashMap<String, f64>,
+    /// 起始代币没有持有（且无法前置兑换）的路径如何处理
+    #[serde(default)]
+    pub unheld: UnheldPathPolicy,
+    /// 起始代币没有持有时，尝试从持有的代币前置一跳兑换
+    #[serde(default)]
+    pub convert_from_held: bool,
+}
+
+/// 起始代币没有持有的路径的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnheldPathPolicy {
+    /// 丢弃
+    #[default]
+    Filter,
+    /// 保留，排在所有可执行路径之后
+    DownRank,
+}
+
+/// 🎚️ Coordinator 自适应触发阈值（[adaptive_threshold]）
+///
+/// 固定的 0.2% 阈值在剧烈波动时让 Calculator 饱和（skipped_triggers / failed_sends 攀升），
+/// 在平静时段又几乎只剩时钟扫描。启用后 Coordinator 维护事件价格变化和触发被拒率的 EWMA：
+/// 被拒率超过 `max_rejection_rate` 时每个 tick 按 `raise_factor` 抬高阈值（至少到典型变化的
+/// `volatility_multiplier` 倍），Calculator 连续 `idle_ticks` 个 tick 空闲时按 `decay_factor` 降低，
+/// 始终限制在 [min, max] 内。当前生效的阈值见 GET /stats/coordinator 和 /metrics。
+///
+/// ```toml
+/// [adaptive_threshold]
+/// min_threshold_percent = 0.05
+/// max_threshold_percent = 1.0
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdaptiveThresholdConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 阈值下限（百分比）
+    #[serde(default = "default_adaptive_min_threshold")]
+    pub min_threshold_percent: f64,
+    /// 阈值上限（百分比）
+    #[serde(default = "default_adaptive_max_threshold")]
+    pub max_threshold_percent: f64,
+    /// EWMA 平滑系数（0, 1]，越大越跟随最近的样本
+    #[serde(default = "default_adaptive_ewma_alpha")]
+    pub ewma_alpha: f64,
+    /// 触发被拒率（Calculator 繁忙 + cooldown 跳过）超过此值时抬高阈值
+    #[serde(default = "default_adaptive_max_rejection_rate")]
+    pub max_rejection_rate: f64,
+    /// 每个饱和 tick 的抬高倍数
+    #[serde(default = "default_adaptive_raise_factor")]
+    pub raise_factor: f64,
+    /// 饱和时阈值至少为事件价格变化 EWMA 的多少倍
+    #[serde(default = "default_adaptive_volatility_multiplier")]
+    pub volatility_multiplier: f64,
+    /// Calculator 连续空闲多少个 tick 后降低一次阈值
+    #[serde(default = "default_adaptive_idle_ticks")]
+    pub idle_ticks: u32,
+    /// 每次降低的倍数
+    #[serde(default = "default_adaptive_decay_factor")]
+    pub decay_factor: f64,
+    /// 阈值相对上次记录变化超过此值（百分点）时打日志
+    #[serde(default = "default_adaptive_log_step")]
+    pub log_step_percent: f64,
+}
+
+impl Default for AdaptiveThresholdConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_threshold_percent: default_adaptive_min_threshold(),
+            max_threshold_percent: default_adaptive_max_threshold(),
+            ewma_alpha: default_adaptive_ewma_alpha(),
+            max_rejection_rate: default_adaptive_max_rejection_rate(),
+            raise_factor: default_adaptive_raise_factor(),
+            volatility_multiplier: default_adaptive_volatility_multiplier(),
+            idle_ticks: default_adaptive_idle_ticks(),
+            decay_factor: default_adaptive_decay_factor(),
+            log_step_percent: default_adaptive_log_step(),
+        }
+    }
+}
+
+fn default_adaptive_min_threshold() -> f64 {
+    0.05
+}
+
+fn default_adaptive_max_threshold() -> f64 {
+    1.0
+}
+
+fn default_adaptive_ewma_alpha() -> f64 {
+    0.2
+}
+
+fn default_adaptive_max_rejection_rate() -> f64 {
+    0.3
+}
+
+fn default_adaptive_raise_factor() -> f64 {
+    1.2
+}
+
+fn default_adaptive_volatility_multiplier() -> f64 {
+    1.5
+}
+
+fn default_adaptive_idle_ticks() -> u32 {
+    10
+}
+
+fn default_adaptive_decay_factor() -> f64 {
+    0.9
+}
+
+fn default_adaptive_log_step() -> f64 {
+    0.05
+}
+
+/// 🎯 Coordinator 触发配置（[coordinator]）
+///
+/// 时钟每 `tick_interval_ms` 兜底扫描一次；价格变化超过 `high_threshold_percent` 的事件立即触发，
+/// 同一池子在 `per_pool_cooldown_ms` 内只触发一次，`cooldown_ms > 0` 时再叠加全局冷却。
+/// 冷却超过时钟周期时事件触发不会比时钟更快，校验时报错。
+///
+/// ```toml
+/// [coordinator]
+/// tick_interval_ms = 100
+/// cooldown_ms = 0
+/// per_pool_cooldown_ms = 20
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoordinatorTriggerConfig {
+    /// 时钟周期（兜底扫描间隔）
+    #[serde(default = "default_coordinator_tick_interval_ms")]
+    pub tick_interval_ms: u64,
+    /// 价格变化超过此值（百分比）触发事件扫描（启用 [adaptive_threshold] 时为初始值）
+    #[serde(default = "default_coordinator_high_threshold_percent")]
+    pub high_threshold_percent: f64,
+    /// 全局冷却（任意池子触发后所有池子都等待；0 = 关闭）
+    #[serde(default)]
+    pub cooldown_ms: u64,
+    /// 按池冷却（同一池子的连续更新防抖动）
+    #[serde(default = "default_coordinator_per_pool_cooldown_ms")]
+    pub per_pool_cooldown_ms: u64,
+}
+
+impl Default for CoordinatorTriggerConfig {
+    fn default() -> Self {
+        Self {
+            tick_interval_ms: default_coordinator_tick_interval_ms(),
+            high_threshold_percent: default_coordinator_high_threshold_percent(),
+            cooldown_ms: 0,
+            per_pool_cooldown_ms: default_coordinator_per_pool_cooldown_ms(),
+        }
+    }
+}
+
+fn default_coordinator_tick_interval_ms() -> u64 {
+    100
+}
+
+fn default_coordinator_high_threshold_percent() -> f64 {
+    0.2
+}
+
+fn default_coordinator_per_pool_cooldown_ms() -> u64 {
+    20
+}
+
+/// 💵 Calculator 扫描金额配置
+///
+/// 路由器按每个金额档位各扫描一次，同一路径在小额时盈利、大额时常因滑点亏损。
+///
+/// ```toml
+/// [calculator]
+/// base_token = "USDC"
+/// amounts_usd = [100, 1000, 10000]
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalculatorConfig {
+    /// 扫描金额的计价代币（USDC / USDT / SOL 或任何 PriceOracle 能定价的代币）
+    #[serde(default = "default_calculator_base_token")]
+    pub base_token: String,
+    /// 金额档位（美元），按 PriceOracle 的实时美元价格换算成 base_token 数量
+    #[serde(default = "default_calculator_amounts_usd")]
+    pub amounts_usd: Vec<f64>,
+    /// 没有新鲜 USD 价格时改用的原生 base_token 数量（为空则跳过扫描）
+    #[serde(default)]
+    pub fallback_amounts: Vec<f64>,
+}
+
+impl Default for CalculatorConfig {
+    fn default() -> Self {
+        Self {
+            base_token: default_calculator_base_token(),
+            amounts_usd: default_calculator_amounts_usd(),
+            fallback_amounts: Vec::new(),
         }
+    }
+}
+
+fn default_calculator_base_token() -> String {
+    "USDC".to_string()
+}
+
+fn default_calculator_amounts_usd() -> Vec<f64> {
+    vec![100.0, 1_000.0, 10_000.0]
+}
+
+/// 🔭 池子自动发现配置
+///
+/// 启动时对已启用 DEX 的 program 做 getProgramAccounts（dataSize + mint memcmp 过滤），
+/// 找出目标代币两两之间的池子，按流动性筛选后与静态配置合并。
+///
+/// ```toml
+/// [discovery]
+/// enabled = true
+/// dexes = ["amm_v4", "clmm", "whirlpool", "meteora_dlmm"]
+/// max_pools_per_dex = 20
+/// per_dex_caps = { whirlpool = 40 }
+/// min_liquidity_usd = 50000
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveryConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 目标代币（只发现两边都是目标代币的池子）
+    #[serde(default = "default_discovery_target_mints")]
+    pub target_mints: Vec<DiscoveryTarget>,
+    /// 启用的 DEX（pool_type 名称，program id 取自 PoolFactory 的 owner 表）
+    #[serde(default = "default_discovery_dexes")]
+    pub dexes: Vec<String>,
+    /// 每个 DEX 最多保留的池子数（按流动性降序）
+    #[serde(default = "default_discovery_max_pools_per_dex")]
+    pub max_pools_per_dex: usize,
+    /// 单个 DEX 的上限覆盖（pool_type -> 数量）
+    #[serde(default)]
+    pub per_dex_caps: HashMap<String, usize>,
+    /// 最低流动性（美元）
+    #[serde(default = "default_discovery_min_liquidity_usd")]
+    pub min_liquidity_usd: f64,
+    /// getProgramAccounts 使用的 RPC（默认活跃 WebSocket 端点对应的 HTTP 地址）
+    #[serde(default)]
+    pub rpc_url: Option<String>,
+}
+
+/// 发现目标代币
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveryTarget {
+    pub symbol: String,
+    pub mint: String,
+}
+
+impl DiscoveryConfig {
+    /// 某个 DEX 的池子上限
+    pub fn cap_for(&self, pool_type: &str) -> usize {
+        self.per_dex_caps.get(pool_type).copied().unwrap_or(self.max_pools_per_dex)
+    }
+}
+
+/// 💾 价格缓存快照配置
+///
+/// 定期把 PriceCache 写入本地文件，启动时先用快照预热（恢复的条目标记为过期，
+/// 收到实时更新前不参与路由），不依赖数据库。
+///
+/// ```toml
+/// [snapshot]
+/// enabled = true
+/// path = "data/price_cache.snapshot"
+/// interval_secs = 30
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 快照文件路径
+    #[serde(default = "default_snapshot_path")]
+    pub path: String,
+    /// 写入间隔（秒）
+    #[serde(default = "default_snapshot_interval_secs")]
+    pub interval_secs: u64,
+}
+
+fn default_snapshot_path() -> String {
+    "data/price_cache.snapshot".to_string()
+}
+
+fn default_snapshot_interval_secs() -> u64 {
+    30
+}
+
+fn default_discovery_target_mints() -> Vec<DiscoveryTarget> {
+    [
+        ("SOL", "So11111111111111111111111111111111111111112"),
+        ("USDC", "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"),
+        ("USDT", "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB"),
+        ("mSOL", "mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So"),
+        ("jitoSOL", "J1toso1uCk3RLmjorhTtrVwY9HJ7X8V9yYac6Y7kGCPn"),
+    ]
+    .iter()
+    .map(|(symbol, mint)| DiscoveryTarget { symbol: symbol.to_string(), mint: mint.to_string() })
+    .collect()
+}
+
+fn default_discovery_dexes() -> Vec<String> {
+    vec!["amm_v4".to_string(), "clmm".to_string(), "whirlpool".to_string(), "meteora_dlmm".to_string()]
+}
+
+fn default_discovery_max_pools_per_dex() -> usize {
+    20
+}
+
+fn default_discovery_min_liquidity_usd() -> f64 {
+    50_000.0
+}
+
+/// 状态层配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateLayerConfig {
+    /// 状态层类型
+    ///
+    /// 可选值：
+    /// - "rwlock": RwLock<HashMap>，适合低频更新（<100次/秒）
+    /// - "dashmap": DashMap，适合高频更新（>100次/秒）
+    ///
+    /// 默认值："rwlock"
+    #[serde(default = "default_state_layer_type")]
+    pub state_type: String,
+
+    /// 是否自动选择最优状态层类型
+    ///
+    /// 如果启用，根据预期负载自动选择 RwLock 或 DashMap
+    ///
+    /// 默认值：false
+    #[serde(default)]
+    pub auto_select: bool,
+
+    /// 预期更新频率（次/秒）
+    ///
+    /// 用于自动选择时的参考值
+    ///
+    /// 默认值：50.0
+    #[serde(default = "default_expected_update_rate")]
+    pub expected_update_rate: f64,
+}
+
+fn default_state_layer_type() -> String {
+    "rwlock".to_string()
+}
+
+fn default_expected_update_rate() -> f64 {
+    50.0
+}
+
+/// 🩺 配置校验结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigIssues {
+    /// 致命问题（启动失败）
+    pub errors: Vec<String>,
+    /// 可疑但可运行的配置（只记录日志）
+    pub warnings: Vec<String>,
+}
 
+impl ConfigIssues {
+    fn error(&mut self, message: impl Into<String>) {
+        self.errors.push(message.into());
+    }
+
+    fn warning(&mut self, message: impl Into<String>) {
+        self.warnings.push(message.into());
+    }
+}
+
+/// pool_type 为 "unknown" / 空时按 owner 或数据大小探测，也是合法值
+fn is_known_pool_type(pool_type: &str) -> bool {
+    pool_type.is_empty() || pool_type == "unknown" || PoolFactory::canonical_pool_type(pool_type).is_some()
+}
+
+/// "BASE/QUOTE" -> (BASE, QUOTE)，两边都不能为空且只能有一个 '/'
+fn split_pair(pair: &str) -> Option<(&str, &str)> {
+    let (base, quote) = pair.split_once('/')?;
+    let (base, quote) = (base.trim(), quote.trim());
+    (!base.is_empty() && !quote.is_empty() && !quote.contains('/')).then_some((base, quote))
+}
+
+/// 每个 `[[pools]]` 表头所在行（1 起）；与池子数不一致（例如内联数组写法）时不提供行号
+fn pool_header_lines(source: &str, pool_count: usize) -> Option<Vec<usize>> {
+    let lines: Vec<usize> = source.lines()
+        .enumerate()
+        .filter(|(_, line)| line.trim() == "[[pools]]")
+        .map(|(index, _)| index + 1)
+        .collect();
+    (lines.len() == pool_count).then_some(lines)
+}
+
+impl Config {
+    /// Load configuration from a TOML file (parsed, then checked by [`Config::validate`])
+    pub fn load_from_file(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {}", path))?;
+
+        let config: Config = toml::from_str(&content)
+            .with_context(|| "Failed to parse config TOML")?;
+
+        config.validate_source(Some(&content))?;
         Ok(config)
     }
 
-    /// Get the WebSocket URL
+    /// 🩺 启动前校验：一次收集全部错误，返回列出所有问题的单个错误；警告只记录日志
+    pub fn validate(&self) -> Result<()> {
+        self.validate_source(None)
+    }
+
+    /// 同 `validate`；提供原始 TOML 时错误信息附带池子所在行号
+    fn validate_source(&self, source: Option<&str>) -> Result<()> {
+        let issues = self.collect_issues(source);
+        for warning in &issues.warnings {
+            warn!("⚠️  Config: {}", warning);
+        }
+        if issues.errors.is_empty() {
+            return Ok(());
+        }
+        anyhow::bail!(
+            "Invalid configuration ({} problem{}):\n  - {}",
+            issues.errors.len(),
+            if issues.errors.len() == 1 { "" } else { "s" },
+            issues.errors.join("\n  - ")
+        )
+    }
+
+    /// 全部校验问题（不记录日志，不提前返回）
+    pub fn collect_issues(&self, source: Option<&str>) -> ConfigIssues {
+        let mut issues = ConfigIssues::default();
+
+        if self.websocket.urls.is_empty() || self.websocket.urls.iter().any(|u| u.is_empty()) {
+            issues.error("WebSocket URL cannot be empty");
+        }
+        if self.websocket.max_subscriptions_per_connection == 0 {
+            issues.error("websocket.max_subscriptions_per_connection must be at least 1");
+        }
+        if self.websocket.max_cached_pool_accounts == 0 {
+            issues.error("websocket.max_cached_pool_accounts must be at least 1");
+        }
+        if self.websocket.commitment == Commitment::Processed {
+            issues.warning("websocket.commitment = \"processed\" applies to every pool; prefer per-pool commitment overrides for hot pairs");
+        }
+
+        let discovery_enabled = self.discovery.as_ref().is_some_and(|d| d.enabled);
+        if self.pools.is_empty() && !discovery_enabled {
+            issues.error("At least one pool must be configured");
+        }
+
+        // 池子：地址 / 名称 / pool_type / pair（错误信息带 [[pools]] 所在行号）
+        let header_lines = source.and_then(|source| pool_header_lines(source, self.pools.len()));
+        let pool_label = |index: usize| {
+            let pool = &self.pools[index];
+            let name = if pool.name.is_empty() { &pool.address } else { &pool.name };
+            match header_lines.as_ref().map(|lines| lines[index]) {
+                Some(line) => format!("\"{}\" (line {})", name, line),
+                None => format!("\"{}\"", name),
+            }
+        };
+        let mut by_address: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, pool) in self.pools.iter().enumerate() {
+            let label = pool_label(index);
+            if pool.address.is_empty() {
+                issues.error(format!("Pool {}: address cannot be empty", label));
+            } else {
+                by_address.entry(pool.address.as_str()).or_default().push(index);
+            }
+            // 🏷️ pair 留空的池子启动时自动命名，名称也可以留空
+            let auto_named = crate::pair_naming::needs_naming(pool);
+            if pool.name.is_empty() && !auto_named {
+                issues.error(format!("Pool {}: name cannot be empty (or leave pair empty to name it from on-chain mints)", label));
+            }
+            if !is_known_pool_type(&pool.pool_type) {
+                issues.error(format!(
+                    "Pool {}: unknown pool_type \"{}\" (known: {})",
+                    label, pool.pool_type, KNOWN_POOL_TYPES.join(", ")
+                ));
+            }
+            if !auto_named && split_pair(&pool.pair).is_none() {
+                issues.error(format!("Pool {}: pair \"{}\" must be two tokens separated by '/'", label, pool.pair));
+            }
+            if pool.max_price_jump_percent.is_some_and(|t| t.is_nan() || t <= 0.0) {
+                issues.error(format!("Pool {}: max_price_jump_percent must be positive", label));
+            }
+        }
+        // 按配置顺序报告重复地址
+        for pool in &self.pools {
+            let Some(duplicates) = by_address.remove(pool.address.as_str()) else { continue };
+            if duplicates.len() > 1 {
+                issues.error(format!(
+                    "Duplicate pool address {}: {}",
+                    pool.address,
+                    duplicates.iter().map(|index| pool_label(*index)).collect::<Vec<_>>().join(", ")
+                ));
+            }
+        }
+
+        if let Some(router) = &self.router {
+            if !(2..=6).contains(&router.max_hops) {
+                issues.error(format!("router.max_hops must be between 2 and 6 (got {})", router.max_hops));
+            }
+            if router.min_roi_percent.is_nan() || router.min_roi_percent < 0.0 {
+                issues.error(format!("router.min_roi_percent must not be negative (got {})", router.min_roi_percent));
+            }
+            if router.min_pool_liquidity_usd.is_nan() || router.min_pool_liquidity_usd < 0.0 {
+                issues.error(format!("router.min_pool_liquidity_usd must not be negative (got {})", router.min_pool_liquidity_usd));
+            }
+        }
+
+        if let Some(init) = &self.initialization {
+            if init.batch_size == 0 || init.batch_size > 100 {
+                issues.error(format!("initialization.batch_size must be between 1 and 100 (got {})", init.batch_size));
+            }
+        }
+
+        for sink in self.output.iter().flat_map(|output| &output.sinks) {
+            if sink.kind == OutputSinkKind::JsonlFile && sink.path.as_deref().is_none_or(str::is_empty) {
+                issues.error("[[output.sinks]] of type jsonl_file requires a path");
+            }
+        }
+
+        for sink in self.notifications.iter().flat_map(|n| &n.sinks) {
+            let missing = |value: &Option<String>| value.as_deref().is_none_or(str::is_empty);
+            match sink.kind {
+                NotificationSinkKind::Webhook if missing(&sink.url) => {
+                    issues.error(format!("[[notifications.sinks]] '{}' of type webhook requires a url", sink.name));
+                }
+                NotificationSinkKind::Telegram if missing(&sink.bot_token) || missing(&sink.chat_id) => {
+                    issues.error(format!(
+                        "[[notifications.sinks]] '{}' of type telegram requires bot_token and chat_id",
+                        sink.name
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(cost) = &self.execution_cost {
+            if cost.priority_fee_percentile > 100 {
+                issues.error("execution_cost.priority_fee_percentile must be between 0 and 100");
+            }
+        }
+
+        if let Some(head) = self.chain_head.as_ref().filter(|h| h.enabled) {
+            if head.poll_interval_ms == 0 {
+                issues.error("chain_head.poll_interval_ms must be greater than 0");
+            }
+        }
+
+        if let Some(supervisor) = &self.supervisor {
+            if supervisor.restart_backoff_ms == 0 {
+                issues.error("supervisor.restart_backoff_ms must be greater than 0");
+            }
+            if supervisor.max_restarts_per_hour == 0 {
+                issues.warning("supervisor.max_restarts_per_hour = 0: panicked tasks are never restarted");
+            }
+        }
+
+        if let Some(focus) = self.focus.as_ref().filter(|f| f.enabled) {
+            if focus.pairs.is_empty() {
+                issues.warning("focus.pairs is empty: focus watcher has nothing to watch");
+            }
+            if let Some(pair) = focus.pairs.iter().find(|p| p.split('/').filter(|t| !t.trim().is_empty()).count() != 2) {
+                issues.error(format!("focus.pairs entry '{}' is not a BASE/QUOTE pair", pair));
+            }
+            if focus.trade_size.is_nan() || focus.trade_size <= 0.0 {
+                issues.error("focus.trade_size must be positive");
+            }
+        }
+
+        if let Some(validation) = &self.validation {
+            for rule in &validation.rules {
+                if !crate::validation_rules::is_known_rule(rule) {
+                    issues.error(format!(
+                        "validation.rules: unknown rule '{}' (expected one of {:?})",
+                        rule, crate::validation_rules::DEFAULT_RULES
+                    ));
+                }
+            }
+            if validation.max_data_age_ms == 0 || validation.max_slot_spread == 0 {
+                issues.error("validation.max_data_age_ms and validation.max_slot_spread must be greater than 0");
+            }
+            let limits = [
+                Some(validation.max_price_deviation_percent),
+                Some(validation.max_total_roi_percent),
+                validation.max_single_hop_impact_percent,
+            ];
+            if limits.into_iter().flatten().any(|limit| limit.is_nan() || limit <= 0.0) {
+                issues.error("validation percentage limits must be positive");
+            }
+            if validation.min_liquidity_multiplier.is_nan() || validation.min_liquidity_multiplier < 0.0 {
+                issues.error("validation.min_liquidity_multiplier must not be negative");
+            }
+            if validation.simulation_required_above_usd.is_nan() || validation.simulation_required_above_usd < 0.0 {
+                issues.error("validation.simulation_required_above_usd must not be negative");
+            }
+            if validation.confirmed_required_above_usd.is_nan() || validation.confirmed_required_above_usd < 0.0 {
+                issues.error("validation.confirmed_required_above_usd must not be negative");
+            }
+            if validation.suspicious_roi_percent > validation.max_total_roi_percent {
+                issues.warning("validation.suspicious_roi_percent is above max_total_roi_percent: suspicious ROIs are never reported");
+            }
+        }
+
+        if let Some(capture) = &self.debug_capture {
+            if capture.dir.trim().is_empty() {
+                issues.error("debug_capture.dir must not be empty");
+            }
+            if capture.enabled {
+                issues.warning("debug_capture.enabled writes every scan input to disk; use POST /debug/capture for one-off captures");
+            }
+        }
+
+        if let Some(audit) = self.vault_audit.as_ref().filter(|a| a.enabled) {
+            if audit.interval_minutes == 0 {
+                issues.error("vault_audit.interval_minutes must be greater than 0");
+            }
+            if audit.max_divergence_percent.is_nan() || audit.max_divergence_percent <= 0.0 {
+                issues.error("vault_audit.max_divergence_percent must be positive");
+            }
+        }
+
+        if let Some(history) = self.history.as_ref().filter(|h| h.enabled) {
+            if history.max_entries_per_pool == 0 || history.heartbeat_secs == 0 {
+                issues.error("history.max_entries_per_pool and history.heartbeat_secs must be greater than 0");
+            }
+            if history.min_change_percent.is_nan() || history.min_change_percent < 0.0 {
+                issues.error("history.min_change_percent must not be negative");
+            }
+        }
+
+        if let Some(reference) = self.reference_prices.as_ref().filter(|r| r.enabled) {
+            if reference.poll_interval_secs == 0 || reference.max_age_secs == 0 {
+                issues.error("reference_prices.poll_interval_secs and reference_prices.max_age_secs must be greater than 0");
+            }
+            if !(reference.warn_deviation_percent > 0.0
+                && reference.exclude_deviation_percent >= reference.warn_deviation_percent)
+            {
+                issues.error("reference_prices requires 0 < warn_deviation_percent <= exclude_deviation_percent");
+            }
+            if reference.feeds.is_empty() {
+                issues.warning("reference_prices is enabled but has no feeds");
+            }
+            for feed in &reference.feeds {
+                if feed.pair.split('/').filter(|token| !token.is_empty()).count() != 2 {
+                    issues.error(format!("reference_prices feed pair '{}' must be BASE/QUOTE", feed.pair));
+                }
+                if !feed.price_pointer.starts_with('/') {
+                    issues.error(format!("reference_prices feed {}: price_pointer must be a JSON Pointer starting with '/'", feed.pair));
+                }
+            }
+        }
+
+        if let Some(inventory) = self.inventory.as_ref().filter(|i| i.enabled) {
+            for (token, amount) in &inventory.balances {
+                if !amount.is_finite() || *amount < 0.0 {
+                    issues.error(format!("inventory.balances.{} must be a non-negative number", token));
+                }
+            }
+            if inventory.unheld == UnheldPathPolicy::Filter && inventory.balances.values().all(|amount| *amount <= 0.0) {
+                issues.warning("inventory is enabled with no positive balances, every opportunity will be filtered");
+            }
+        }
+
+        if let Some(coordinator) = &self.coordinator {
+            if coordinator.tick_interval_ms == 0 {
+                issues.error("coordinator.tick_interval_ms must be greater than 0");
+            }
+            if coordinator.high_threshold_percent.is_nan() || coordinator.high_threshold_percent <= 0.0 {
+                issues.error("coordinator.high_threshold_percent must be positive");
+            }
+            for (name, cooldown) in [("cooldown_ms", coordinator.cooldown_ms), ("per_pool_cooldown_ms", coordinator.per_pool_cooldown_ms)] {
+                if cooldown > coordinator.tick_interval_ms {
+                    issues.error(format!(
+                        "coordinator.{} ({}) must not exceed coordinator.tick_interval_ms ({}): event triggers would be slower than the clock",
+                        name, cooldown, coordinator.tick_interval_ms
+                    ));
+                }
+            }
+        }
+
+        if let Some(adaptive) = self.adaptive_threshold.as_ref().filter(|a| a.enabled) {
+            if adaptive.min_threshold_percent.is_nan() || adaptive.min_threshold_percent <= 0.0 {
+                issues.error("adaptive_threshold.min_threshold_percent must be positive");
+            }
+            if adaptive.max_threshold_percent.is_nan() || adaptive.max_threshold_percent < adaptive.min_threshold_percent {
+                issues.error("adaptive_threshold.max_threshold_percent must be >= min_threshold_percent");
+            }
+            if adaptive.ewma_alpha.is_nan() || adaptive.ewma_alpha <= 0.0 || adaptive.ewma_alpha > 1.0 {
+                issues.error("adaptive_threshold.ewma_alpha must be in (0, 1]");
+            }
+            if adaptive.max_rejection_rate.is_nan() || !(0.0..1.0).contains(&adaptive.max_rejection_rate) {
+                issues.error("adaptive_threshold.max_rejection_rate must be in [0, 1)");
+            }
+            if adaptive.raise_factor.is_nan() || adaptive.raise_factor <= 1.0 {
+                issues.error("adaptive_threshold.raise_factor must be greater than 1");
+            }
+            if adaptive.decay_factor.is_nan() || adaptive.decay_factor <= 0.0 || adaptive.decay_factor >= 1.0 {
+                issues.error("adaptive_threshold.decay_factor must be in (0, 1)");
+            }
+            if adaptive.idle_ticks == 0 {
+                issues.error("adaptive_threshold.idle_ticks must be at least 1");
+            }
+        }
+
+        if let Some(spread) = &self.spread_monitor {
+            if spread.threshold_bps.is_nan() || spread.threshold_bps <= 0.0 {
+                issues.error("spread_monitor.threshold_bps must be positive");
+            }
+        }
+
+        if let Some(heatmap) = &self.spread_heatmap {
+            if heatmap.window_secs == 0 {
+                issues.error("spread_heatmap.window_secs must be at least 1");
+            }
+            if !heatmap.threshold_bps.is_finite() {
+                issues.error("spread_heatmap.threshold_bps must be a finite number");
+            }
+            if heatmap.max_samples_per_cell == 0 {
+                issues.error("spread_heatmap.max_samples_per_cell must be at least 1");
+            }
+        }
+
+        if let Some(breaker) = &self.circuit_breaker {
+            let thresholds = [breaker.max_price_jump_percent, breaker.max_reserve_change_percent];
+            if thresholds.iter().any(|t| t.is_nan() || *t <= 0.0) {
+                issues.error("circuit_breaker thresholds must be positive");
+            }
+        }
+
+        if let Some(rpc) = &self.rpc {
+            if rpc.requests_per_second.is_some_and(|r| r.is_nan() || r < 0.0) {
+                issues.error("rpc.requests_per_second must not be negative");
+            }
+            if rpc.urls.iter().any(|u| u.is_empty()) {
+                issues.error("rpc.urls cannot contain empty URLs");
+            }
+        }
+
+        self.collect_isolated_pool_warnings(&mut issues);
+        self.collect_mint_conflict_warnings(&mut issues);
+        issues
+    }
+
+    /// 同名交易对（例如两个 "USDC/USDT"）配置的 mint 不同：其中一个其实是另一种代币
+    fn collect_mint_conflict_warnings(&self, issues: &mut ConfigIssues) {
+        // 规范 pair -> 第一个配置了 mint 的池子
+        let mut first: HashMap<(String, String), &PoolConfig> = HashMap::new();
+        for pool in &self.pools {
+            let (Some(base_mint), Some(quote_mint)) = (&pool.base_mint, &pool.quote_mint) else { continue };
+            let Some((base, quote)) = split_pair(&pool.pair) else { continue };
+            let key = (base.to_string(), quote.to_string());
+            let Some(other) = first.get(&key) else {
+                first.insert(key, pool);
+                continue;
+            };
+            let (other_base, other_quote) = (other.base_mint.as_deref(), other.quote_mint.as_deref());
+            let same = |a: &str, b: &str| (Some(a), Some(b)) == (other_base, other_quote) || (Some(b), Some(a)) == (other_base, other_quote);
+            if same(base_mint, quote_mint) {
+                continue;
+            }
+
+            // 与先配置的池子不同的一侧（两侧都不同时报告 base）
+            let differs_base = ![other_base, other_quote].contains(&Some(base_mint.as_str()));
+            let (symbol, mint, rename) = if differs_base {
+                (base, base_mint, format!("{}et/{}", base, quote))
+            } else {
+                (quote, quote_mint, format!("{}/{}et", base, quote))
+            };
+            let other_mint = if differs_base { other_base } else { other_quote }.unwrap_or_default();
+            issues.warning(format!(
+                "Pools {} and {} are both named \"{}\" but use different {} mints ({} vs {}); \
+                 rename the pair of the pool with mint {} after its real token (e.g. \"{}\")",
+                other.name, pool.name, pool.pair, symbol, other_mint, mint, mint, rename
+            ));
+        }
+    }
+
+    /// 某个代币在其他池子中都没有出现：该池子不可能处在任何循环里
+    fn collect_isolated_pool_warnings(&self, issues: &mut ConfigIssues) {
+        let aliases = self.router.as_ref()
+            .map(|r| r.token_aliases.clone())
+            .unwrap_or_else(crate::token_alias::default_aliases);
+        let canonical = |token: &str| aliases.get(token).cloned().unwrap_or_else(|| token.to_string());
+
+        let pool_tokens: Vec<Option<[String; 2]>> = self.pools.iter()
+            .map(|pool| split_pair(&pool.pair).map(|(base, quote)| [canonical(base), canonical(quote)]))
+            .collect();
+        let mut pools_per_token: HashMap<&str, usize> = HashMap::new();
+        for tokens in pool_tokens.iter().flatten() {
+            for token in tokens {
+                *pools_per_token.entry(token.as_str()).or_default() += 1;
+            }
+        }
+
+        for (pool, tokens) in self.pools.iter().zip(&pool_tokens) {
+            let Some(tokens) = tokens else { continue };
+            let isolated: Vec<&str> = tokens.iter()
+                .map(String::as_str)
+                .filter(|token| pools_per_token.get(token).copied().unwrap_or(0) < 2)
+                .collect();
+            if !isolated.is_empty() {
+                issues.warning(format!(
+                    "Pool {} ({}): {} appear{} in no other pool, so it cannot be part of any cycle",
+                    pool.name,
+                    pool.pair,
+                    isolated.join(" and "),
+                    if isolated.len() == 1 { "s" } else { "" }
+                ));
+            }
+        }
+    }
+
+    /// Get the primary WebSocket URL
     pub fn websocket_url(&self) -> &str {
-        &self.websocket.url
+        self.websocket.urls.first().map(String::as_str).unwrap_or("")
+    }
+
+    /// 🔀 All WebSocket endpoints in failover order
+    pub fn websocket_urls(&self) -> &[String] {
+        &self.websocket.urls
     }
 
     /// Get all pool configurations
@@ -464,9 +3005,7 @@ mod tests {
     #[test]
     fn test_config_validation() {
         let config = Config {
-            websocket: WebSocketConfig {
-                url: "wss://example.com".to_string(),
-            },
+            websocket: WebSocketConfig::new("wss://example.com".to_string()),
             proxy: None,
             database: None,
             router: None,
@@ -474,11 +3013,51 @@ mod tests {
             simulation: None,
             initialization: None,
             lst_detector: None,
+            state_layer: None,
+            slo: None,
+            alerts: None,
+            sharding: None,
+            synthetic_pools: Vec::new(),
+            whatif: None,
+            calculator: None,
+            discovery: None,
+            snapshot: None,
+            price_oracle: None,
+            dexes: None,
+            error_tracking: None,
+            output: None,
+            spread_monitor: None,
+            spread_heatmap: None,
+            circuit_breaker: None,
+            rpc: None,
+            notifications: None,
+            execution_cost: None,
+            chain_head: None,
+            supervisor: None,
+            focus: None,
+            validation: None,
+            debug_capture: None,
+            vault_audit: None,
+            history: None,
+            reference_prices: None,
+            inventory: None,
+            coordinator: None,
+            adaptive_threshold: None,
+            pool_naming: None,
             pools: vec![
                 PoolConfig {
                     address: "58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2".to_string(),
                     name: "SOL/USDC".to_string(),
+                    pair: "SOL/USDC".to_string(),
                     pool_type: "amm_v4".to_string(),
+                    fee_bps: None,
+                    max_age_ms: None,
+                    base_mint: None,
+                    quote_mint: None,
+                    max_price_jump_percent: None,
+                    commitment: None,
+                    encoding: None,
+                    priority: None,
                 },
             ],
         };
@@ -486,4 +3065,123 @@ mod tests {
         assert_eq!(config.websocket_url(), "wss://example.com");
         assert_eq!(config.pools().len(), 1);
     }
+    
+    #[test]
+    fn test_websocket_url_accepts_string_or_list() {
+        let single: WebSocketConfig = toml::from_str(r#"url = "wss://primary.example""#).unwrap();
+        assert_eq!(single.urls, vec!["wss://primary.example"]);
+        assert_eq!(single.reconnect_max_delay_ms, 60_000);
+        assert_eq!(single.max_subscriptions_per_connection, 100);
+        assert_eq!(single.resubscribe_silent_after(), Some(std::time::Duration::from_secs(600)));
+        
+        let multi: WebSocketConfig = toml::from_str(
+            r#"url = ["wss://primary.example", "wss://secondary.example"]"#
+        ).unwrap();
+        assert_eq!(multi.urls, vec!["wss://primary.example", "wss://secondary.example"]);
+    }
+
+    #[test]
+    fn test_validation_reports_every_problem_at_once() {
+        let source = r#"
+[websocket]
+url = "wss://example.com"
+
+[router]
+max_hops = 8
+
+[initialization]
+batch_size = 0
+
+[coordinator]
+tick_interval_ms = 100
+cooldown_ms = 250
+
+[[pools]]
+name = "SOL/USDC (Raydium)"
+address = "58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2"
+pool_type = "amm_v4"
+pair = "SOL/USDC"
+
+[[pools]]
+name = "SOL/USDC (Orca)"
+address = "58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2"
+pool_type = "whirlpoool"
+pair = "SOL/USDC"
+
+[[pools]]
+name = "USDC/USDT (Raydium CLMM)"
+address = "3nMFwZXwY1s1M5s8vYAHqd4wGs4iSxXE4LRoUMMYqEgF"
+pool_type = "clmmm"
+pair = "USDC-USDT"
+
+[[pools]]
+name = "BONK/SOL (Raydium)"
+address = "HVNwzt7Pxfu76KHCMQPTLuTCLTm6WnQ1esLv4eizseSv"
+pool_type = "raydium_v4"
+pair = "BONK/SOL"
+"#;
+        let config: Config = toml::from_str(source).unwrap();
+        let issues = config.collect_issues(Some(source));
+
+        assert_eq!(issues.errors.len(), 7, "{:#?}", issues.errors);
+        assert!(issues.errors.iter().any(|e| e.contains("Duplicate pool address 58oQ")
+            && e.contains("\"SOL/USDC (Raydium)\" (line 15)")
+            && e.contains("\"SOL/USDC (Orca)\" (line 21)")));
+        assert!(issues.errors.iter().any(|e| e.contains("unknown pool_type \"whirlpoool\"")));
+        assert!(issues.errors.iter().any(|e| e.contains("unknown pool_type \"clmmm\"")));
+        assert!(issues.errors.iter().any(|e| e.contains("pair \"USDC-USDT\"")));
+        assert!(issues.errors.iter().any(|e| e.contains("router.max_hops")));
+        assert!(issues.errors.iter().any(|e| e.contains("initialization.batch_size")));
+        assert!(issues.errors.iter().any(|e| e.contains("coordinator.cooldown_ms (250) must not exceed coordinator.tick_interval_ms (100)")));
+
+        // BONK 只出现在一个池子里：警告但不致命
+        assert_eq!(issues.warnings.len(), 1);
+        assert!(issues.warnings[0].contains("BONK appears in no other pool"));
+
+        let report = config.validate_source(Some(source)).unwrap_err().to_string();
+        assert!(report.starts_with("Invalid configuration (7 problems)"));
+        assert!(report.contains("whirlpoool") && report.contains("clmmm"));
+    }
+
+    #[test]
+    fn test_warns_on_same_pair_name_with_different_mints() {
+        let source = r#"
+[websocket]
+url = "wss://example.com"
+
+[[pools]]
+name = "USDC/USDT (Orca)"
+address = "4fuUiYxTQ6QCrdSq9ouBYcTM7bqSwYTSyLueGZLTy4T4"
+pool_type = "whirlpool"
+pair = "USDC/USDT"
+base_mint = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"
+quote_mint = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB"
+
+[[pools]]
+name = "USDC/USDT (Raydium CLMM)"
+address = "3nMFwZXwY1s1M5s8vYAHqd4wGs4iSxXE4LRoUMMYqEgF"
+pool_type = "clmm"
+pair = "USDC/USDT"
+base_mint = "A9mUU4qviSctJVPJdBJWkb28deg915LYJKrzQ19ji3FM"
+quote_mint = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB"
+
+[[pools]]
+name = "USDT/USDC (Meteora)"
+address = "32D4zRxNc1EssbJieVHfPhZM3rH6CzfUPrWUuWxD9prG"
+pool_type = "meteora_dlmm"
+pair = "USDC/USDT"
+base_mint = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB"
+quote_mint = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"
+"#;
+        let config: Config = toml::from_str(source).unwrap();
+        let issues = config.collect_issues(Some(source));
+
+        assert!(issues.errors.is_empty(), "{:#?}", issues.errors);
+        // 只有桥接 USDC 的池子被报告；mint 顺序相反的同一对代币不算冲突
+        let conflicts: Vec<&String> = issues.warnings.iter().filter(|w| w.contains("both named")).collect();
+        assert_eq!(conflicts.len(), 1, "{:#?}", issues.warnings);
+        assert!(conflicts[0].contains("USDC/USDT (Orca)") && conflicts[0].contains("USDC/USDT (Raydium CLMM)"));
+        assert!(conflicts[0].contains("different USDC mints (EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v vs A9mUU4qviSctJVPJdBJWkb28deg915LYJKrzQ19ji3FM)"));
+        assert!(conflicts[0].contains("\"USDCet/USDT\""));
+    }
 }
@@ -4,18 +4,26 @@
 ///
 /// 核心职责：
 /// 1. 混合触发模型：时钟驱动（兜底）+ 事件驱动（狙击）
-/// 2. 防止计算风暴：cooldown机制
+/// 2. 防止计算风暴：按池cooldown（可选全局cooldown兜底）
 /// 3. 统一调度：将计算任务发送给Calculator
 ///
 /// 这是系统的"神经中枢"，确保套利机会不被遗漏的同时防止系统过载
 /// ========================================================================
 
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
-use tokio::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use dashmap::DashMap;
+use tokio::sync::{broadcast, mpsc, Mutex};
+use serde::Serialize;
 use tokio::time::interval;
 use tracing::{debug, info, warn};
 
+use crate::config::AdaptiveThresholdConfig;
+use crate::latency_budget::IngestTiming;
+use crate::prometheus::{MetricKind, PrometheusWriter};
+use crate::router_direct::DirectArbTable;
+
 /// 价格变化事件
 ///
 /// 由Subscriber发送给Coordinator
@@ -35,6 +43,26 @@ pub struct PriceChangeEvent {
     pub new_price: f64,
     /// 事件发生时间
     pub timestamp: Instant,
+    /// ⏱️ WebSocket 侧耗时（收到通知 / 解析 / 缓存更新；回放与测试事件为 None）
+    pub ingest: Option<IngestTiming>,
+}
+
+impl PriceChangeEvent {
+    /// 相对上次价格的变化比例（0.002 表示 0.2%）
+    ///
+    /// 首次更新按 1% 处理以触发 Coordinator；一侧为 0 时有变化即视为 100%。
+    pub fn change_fraction(last_price: Option<f64>, price: f64) -> f64 {
+        match last_price {
+            None => 0.01,
+            Some(last_price) if last_price == 0.0 || price == 0.0 => {
+                if last_price != price { 1.0 } else { 0.0 }
+            }
+            Some(last_price) => {
+                let change = ((price - last_price) / last_price).abs();
+                if change.is_finite() { change } else { 0.0 }
+            }
+        }
+    }
 }
 
 /// 计算任务
@@ -50,6 +78,35 @@ pub struct CalculationTask {
     pub price_change_percent: Option<f64>,
     /// 任务创建时间
     pub created_at: Instant,
+    /// 定向扫描范围：触发事件所在交易对的代币（如 ["SOL", "USDC"]）
+    ///
+    /// None = 全量扫描（时钟兜底，或事件交易对无法解析）
+    pub scope: Option<Vec<String>>,
+    /// ⏱️ 事件触发时复制原始事件的时间戳（Coordinator 排队延迟）
+    pub event_timestamp: Option<Instant>,
+    /// ⏱️ 原始事件的 WebSocket 侧耗时
+    pub ingest: Option<IngestTiming>,
+}
+
+impl CalculationTask {
+    /// ⏱️ 端到端延迟的起点：收到通知的时刻，没有时依次退到事件时间、任务创建时间
+    pub fn origin(&self) -> Instant {
+        self.ingest
+            .map(|ingest| ingest.received_at)
+            .or(self.event_timestamp)
+            .unwrap_or(self.created_at)
+    }
+
+    /// 从交易对名称（"SOL/USDC"）提取定向扫描范围（别名归一到规范符号，"wSOL" -> "SOL"）
+    pub fn scope_from_pair(pair: &str) -> Option<Vec<String>> {
+        let tokens: Vec<String> = pair
+            .split('/')
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+            .map(|token| crate::token_alias::canonical(token).into_owned())
+            .collect();
+        if tokens.len() == 2 { Some(tokens) } else { None }
+    }
 }
 
 /// 触发类型
@@ -68,12 +125,16 @@ pub struct CoordinatorConfig {
     pub tick_interval_ms: u64,
     /// 高阈值：价格变化超过此值触发狙击
     pub high_threshold_percent: f64,
-    /// 冷却时间（防止计算风暴）
+    /// 全局冷却时间（任意池子触发后所有池子都等待；0 = 关闭）
     pub cooldown_ms: u64,
+    /// 按池冷却时间（同一池子的连续更新防抖动，不影响其他池子）
+    pub per_pool_cooldown_ms: u64,
     /// 计算任务channel容量（通常设为1，防止堆积）
     pub calc_channel_capacity: usize,
     /// 事件channel容量（通常设为1024）
     pub event_channel_capacity: usize,
+    /// 🎚️ 自适应阈值（None = 固定使用 high_threshold_percent）
+    pub adaptive: Option<AdaptiveThresholdConfig>,
 }
 
 impl Default for CoordinatorConfig {
@@ -81,13 +142,109 @@ impl Default for CoordinatorConfig {
         Self {
             tick_interval_ms: 100,      // 100ms兜底扫描
             high_threshold_percent: 0.2, // 0.2%变化触发狙击
-            cooldown_ms: 20,             // 20ms冷却防抖动
+            cooldown_ms: 0,              // 全局冷却默认关闭
+            per_pool_cooldown_ms: 20,    // 同一池子20ms冷却防抖动
             calc_channel_capacity: 1,    // 容量1，防止任务堆积
             event_channel_capacity: 1024, // 事件channel容量
+            adaptive: None,               // 固定阈值
         }
     }
 }
 
+/// 🎚️ 自适应触发阈值
+///
+/// 每个事件更新价格变化的 EWMA，每次触发尝试（以及每个时钟 tick 的发送结果）更新被拒率的 EWMA；
+/// 阈值只在 tick 上调整：被拒率过高时抬高，Calculator 连续空闲若干 tick 时降低。
+#[derive(Debug, Clone)]
+pub struct AdaptiveThreshold {
+    config: AdaptiveThresholdConfig,
+    /// 当前生效的阈值（百分比）
+    threshold_percent: f64,
+    /// 事件价格变化的 EWMA（百分比）
+    volatility_ewma_percent: f64,
+    /// 触发被拒率的 EWMA（0..=1）
+    rejection_ewma: f64,
+    /// Calculator 连续空闲的 tick 数
+    idle_ticks: u32,
+    /// 上个 tick 之后是否有事件触发过计算
+    triggered_since_tick: bool,
+    /// 上次打日志时的阈值
+    logged_percent: f64,
+}
+
+impl AdaptiveThreshold {
+    /// 从 `initial_percent`（限制在 [min, max] 内）开始
+    pub fn new(config: AdaptiveThresholdConfig, initial_percent: f64) -> Self {
+        let threshold_percent = initial_percent.clamp(config.min_threshold_percent, config.max_threshold_percent);
+        Self {
+            config,
+            threshold_percent,
+            volatility_ewma_percent: 0.0,
+            rejection_ewma: 0.0,
+            idle_ticks: 0,
+            triggered_since_tick: false,
+            logged_percent: threshold_percent,
+        }
+    }
+
+    pub fn threshold_percent(&self) -> f64 {
+        self.threshold_percent
+    }
+
+    pub fn volatility_ewma_percent(&self) -> f64 {
+        self.volatility_ewma_percent
+    }
+
+    pub fn rejection_ewma(&self) -> f64 {
+        self.rejection_ewma
+    }
+
+    /// 收到价格事件（`change_percent` 为百分比，0.2 表示 0.2%）
+    pub fn on_event(&mut self, change_percent: f64) {
+        if change_percent.is_finite() {
+            self.volatility_ewma_percent = self.ewma(self.volatility_ewma_percent, change_percent);
+        }
+    }
+
+    /// 事件触发的结果：`rejected` = Calculator 繁忙或处于 cooldown
+    pub fn on_trigger(&mut self, rejected: bool) {
+        self.triggered_since_tick = true;
+        self.rejection_ewma = self.ewma(self.rejection_ewma, if rejected { 1.0 } else { 0.0 });
+    }
+
+    /// 时钟 tick：`clock_sent` = 时钟任务成功送达 Calculator
+    ///
+    /// 返回阈值相对上次日志变化超过 `log_step_percent` 时的上次日志值
+    pub fn on_tick(&mut self, clock_sent: bool) -> Option<f64> {
+        self.rejection_ewma = self.ewma(self.rejection_ewma, if clock_sent { 0.0 } else { 1.0 });
+        let idle = clock_sent && !self.triggered_since_tick;
+        self.triggered_since_tick = false;
+        self.idle_ticks = if idle { self.idle_ticks + 1 } else { 0 };
+
+        let mut next = self.threshold_percent;
+        if self.rejection_ewma > self.config.max_rejection_rate {
+            next = (next * self.config.raise_factor)
+                .max(self.volatility_ewma_percent * self.config.volatility_multiplier);
+        } else if self.idle_ticks >= self.config.idle_ticks {
+            next *= self.config.decay_factor;
+            self.idle_ticks = 0;
+        }
+        self.threshold_percent = next.clamp(self.config.min_threshold_percent, self.config.max_threshold_percent);
+
+        if (self.threshold_percent - self.logged_percent).abs() > self.config.log_step_percent {
+            let previous = self.logged_percent;
+            self.logged_percent = self.threshold_percent;
+            Some(previous)
+        } else {
+            None
+        }
+    }
+
+    fn ewma(&self, current: f64, sample: f64) -> f64 {
+        current + self.config.ewma_alpha * (sample - current)
+    }
+}
+
 /// 协调器
 pub struct Coordinator {
     /// 配置
@@ -99,15 +256,30 @@ pub struct Coordinator {
     /// 发送计算任务给Calculator
     calc_tx: mpsc::Sender<CalculationTask>,
 
-    /// 上次触发时间（用于cooldown）
+    /// 上次触发时间（用于全局cooldown）
     last_trigger: Arc<Mutex<Instant>>,
 
+    /// 各池子上次触发时间（pool_id -> Instant，用于按池cooldown）
+    pool_last_trigger: Arc<DashMap<String, Instant>>,
+
     /// 统计信息
     stats: Arc<Mutex<CoordinatorStats>>,
+
+    /// 最近一次时钟tick（unix毫秒），供外部检测漏tick
+    tick_heartbeat: Arc<AtomicU64>,
+
+    /// 关闭信号（收到后停止派发任务，calc_tx 随之关闭）
+    shutdown_rx: Option<broadcast::Receiver<()>>,
+
+    /// 两跳直接套利表（每个事件检查受影响的交易对）
+    direct_table: Option<Arc<DirectArbTable>>,
+
+    /// 🎚️ 自适应阈值状态（未启用时为 None）
+    adaptive: Option<AdaptiveThreshold>,
 }
 
 /// 协调器统计
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct CoordinatorStats {
     /// 总接收事件数
     pub total_events: u64,
@@ -121,6 +293,20 @@ pub struct CoordinatorStats {
     pub event_triggers: u64,
     /// 计算任务发送失败次数（Calculator繁忙）
     pub failed_sends: u64,
+    /// 直接套利快速通道命中次数
+    pub direct_hits: u64,
+    /// 已派发的定向扫描任务数（只扫描触发交易对涉及的代币）
+    pub scoped_scans: u64,
+    /// 已派发的全量扫描任务数
+    pub full_scans: u64,
+    /// 🎚️ 当前生效的事件触发阈值（百分比）
+    pub effective_threshold_percent: f64,
+    /// 🎚️ 是否启用自适应阈值
+    pub adaptive: bool,
+    /// 🎚️ 事件价格变化的 EWMA（百分比，仅自适应模式）
+    pub volatility_ewma_percent: f64,
+    /// 🎚️ 触发被拒率的 EWMA（仅自适应模式）
+    pub rejection_rate_ewma: f64,
 }
 
 impl Coordinator {
@@ -144,33 +330,107 @@ impl Coordinator {
             .checked_sub(Duration::from_millis(config.cooldown_ms + 1))
             .unwrap_or_else(|| Instant::now() - Duration::from_millis(1));
 
+        let adaptive = config.adaptive.clone()
+            .filter(|adaptive| adaptive.enabled)
+            .map(|adaptive| AdaptiveThreshold::new(adaptive, config.high_threshold_percent));
+        let stats = CoordinatorStats {
+            effective_threshold_percent: adaptive.as_ref()
+                .map_or(config.high_threshold_percent, AdaptiveThreshold::threshold_percent),
+            adaptive: adaptive.is_some(),
+            ..Default::default()
+        };
+
         Self {
             config,
             event_rx,
             calc_tx,
             last_trigger: Arc::new(Mutex::new(safe_initial_time)),
-            stats: Arc::new(Mutex::new(CoordinatorStats::default())),
+            pool_last_trigger: Arc::new(DashMap::new()),
+            stats: Arc::new(Mutex::new(stats)),
+            tick_heartbeat: Arc::new(AtomicU64::new(0)),
+            shutdown_rx: None,
+            direct_table: None,
+            adaptive,
         }
     }
 
+    /// 接入关闭信号
+    pub fn with_shutdown(mut self, shutdown_rx: broadcast::Receiver<()>) -> Self {
+        self.shutdown_rx = Some(shutdown_rx);
+        self
+    }
+
+    /// 接入两跳直接套利表：命中时即使价格变化低于阈值也触发计算
+    pub fn with_direct_table(mut self, table: Arc<DirectArbTable>) -> Self {
+        self.direct_table = Some(table);
+        self
+    }
+
+    /// 获取时钟心跳句柄（在 run() 消费 self 之前调用）
+    ///
+    /// 值为最近一次 tick 的 unix 毫秒时间戳，0 表示尚未 tick
+    pub fn tick_heartbeat(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.tick_heartbeat)
+    }
+
+    /// 获取统计句柄（在 run() 消费 self 之前调用，供 /metrics 读取）
+    pub fn stats_handle(&self) -> Arc<Mutex<CoordinatorStats>> {
+        Arc::clone(&self.stats)
+    }
+
     /// 运行协调器主循环
     ///
     /// 同时监听两个触发源：
     /// 1. 时钟tick（兜底扫描）
     /// 2. 价格变化事件（狙击机会）
     pub async fn run(mut self) {
+        self.run_restartable().await;
+    }
+
+    /// 运行协调器主循环（不消费 self）
+    ///
+    /// 🛟 监督器把协调器放在 `Arc<Mutex<_>>` 里，panic 后在同一个协调器上重新运行：
+    /// 事件 / 计算任务 channel、冷却状态和统计都保留。
+    pub async fn run_restartable(&mut self) {
         info!("🎯 Coordinator started");
         info!("   └─ Tick interval: {}ms", self.config.tick_interval_ms);
         info!("   └─ High threshold: {}%", self.config.high_threshold_percent);
-        info!("   └─ Cooldown: {}ms", self.config.cooldown_ms);
+        if let Some(adaptive) = self.config.adaptive.as_ref().filter(|_| self.adaptive.is_some()) {
+            info!(
+                "   └─ Adaptive threshold: {}% .. {}% (starting at {:.4}%)",
+                adaptive.min_threshold_percent,
+                adaptive.max_threshold_percent,
+                self.threshold_percent()
+            );
+        }
+        info!("   └─ Cooldown: {}ms per pool, {}ms global", self.config.per_pool_cooldown_ms, self.config.cooldown_ms);
 
         let mut tick = interval(Duration::from_millis(self.config.tick_interval_ms));
+        let mut shutdown_rx = self.shutdown_rx.take();
+        // 留一个订阅副本：panic 重启后仍能收到本次运行期间发出的关闭信号
+        self.shutdown_rx = shutdown_rx.as_ref().map(|rx| rx.resubscribe());
 
         loop {
             tokio::select! {
+                // 关闭信号：停止派发，drop self 后 Calculator 的 calc_rx 随之结束
+                _ = async {
+                    match shutdown_rx.as_mut() {
+                        Some(rx) => { let _ = rx.recv().await; }
+                        None => std::future::pending::<()>().await,
+                    }
+                } => {
+                    info!("🛑 Coordinator received shutdown signal");
+                    break;
+                }
+
                 // [触发源 A]: 时钟驱动（兜底扫描）
                 _ = tick.tick() => {
                     debug!("(Coordinator) Clock tick");
+                    let now_ms = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map(|d| d.as_millis() as u64)
+                        .unwrap_or(0);
+                    self.tick_heartbeat.store(now_ms, Ordering::Relaxed);
 
                     // 立即发送计算任务（时钟触发是强制的）
                     let task = CalculationTask {
@@ -178,22 +438,29 @@ impl Coordinator {
                         trigger_source: "periodic_clock".to_string(),
                         price_change_percent: None,
                         created_at: Instant::now(),
+                        scope: None,
+                        event_timestamp: None,
+                        ingest: None,
                     };
 
-                    match self.calc_tx.try_send(task) {
+                    let clock_sent = match self.calc_tx.try_send(task) {
                         Ok(_) => {
                             info!("(Coordinator) Clock triggered calculation");
                             self.update_stats(|stats| {
                                 stats.clock_triggers += 1;
+                                stats.full_scans += 1;
                             }).await;
+                            true
                         }
                         Err(e) => {
                             warn!("(Coordinator) Calculator busy, clock trigger skipped: {}", e);
                             self.update_stats(|stats| {
                                 stats.failed_sends += 1;
                             }).await;
+                            false
                         }
-                    }
+                    };
+                    self.adapt_on_tick(clock_sent).await;
                 }
 
                 // [触发源 B]: 事件驱动（价格变化）
@@ -208,33 +475,33 @@ impl Coordinator {
                         stats.total_events += 1;
                     }).await;
 
-                    // 检查是否超过阈值
-                    if event.price_change_percent > self.config.high_threshold_percent / 100.0 {
-                        info!(
-                            "(Coordinator) High price change detected: {} ({}): {:.4}% > {:.4}%",
-                            event.pool_name,
-                            event.pair,
-                            event.price_change_percent * 100.0,
-                            self.config.high_threshold_percent
-                        );
+                    if let Some(adaptive) = self.adaptive.as_mut() {
+                        adaptive.on_event(event.price_change_percent * 100.0);
+                    }
+
+                    // ⚡ 快速通道：只检查受影响的交易对，命中即产生机会
+                    let direct_hit = self.check_direct(&event).await;
+
+                    // 检查是否超过阈值（直接套利命中时不看阈值）
+                    let threshold_percent = self.threshold_percent();
+                    let high_change = event.price_change_percent > threshold_percent / 100.0;
+                    if direct_hit || high_change {
+                        if high_change {
+                            info!(
+                                "(Coordinator) High price change detected: {} ({}): {:.4}% > {:.4}%",
+                                event.pool_name,
+                                event.pair,
+                                event.price_change_percent * 100.0,
+                                threshold_percent
+                            );
+                        }
 
                         self.update_stats(|stats| {
                             stats.triggered_events += 1;
                         }).await;
 
                         // 检查cooldown
-                        let should_trigger = {
-                            let mut last_trigger = self.last_trigger.lock().await;
-                            let elapsed = last_trigger.elapsed();
-                            let cooldown = Duration::from_millis(self.config.cooldown_ms);
-
-                            if elapsed >= cooldown {
-                                *last_trigger = Instant::now();
-                                true
-                            } else {
-                                false
-                            }
-                        };
+                        let should_trigger = self.check_cooldown(&event.pool_id).await;
 
                         if should_trigger {
                             info!(
@@ -247,17 +514,32 @@ impl Coordinator {
                                 trigger_source: format!("{} ({})", event.pool_name, event.pair),
                                 price_change_percent: Some(event.price_change_percent),
                                 created_at: Instant::now(),
+                                scope: CalculationTask::scope_from_pair(&event.pair),
+                                event_timestamp: Some(event.timestamp),
+                                ingest: event.ingest,
                             };
+                            let scoped = task.scope.is_some();
 
                             match self.calc_tx.try_send(task) {
                                 Ok(_) => {
                                     info!("(Coordinator) Successfully sent calculation task to calculator");
+                                    if let Some(adaptive) = self.adaptive.as_mut() {
+                                        adaptive.on_trigger(false);
+                                    }
                                     self.update_stats(|stats| {
                                         stats.event_triggers += 1;
+                                        if scoped {
+                                            stats.scoped_scans += 1;
+                                        } else {
+                                            stats.full_scans += 1;
+                                        }
                                     }).await;
                                 }
                                 Err(e) => {
                                     warn!("(Coordinator) Calculator busy, event trigger skipped: {}", e);
+                                    if let Some(adaptive) = self.adaptive.as_mut() {
+                                        adaptive.on_trigger(true);
+                                    }
                                     self.update_stats(|stats| {
                                         stats.failed_sends += 1;
                                     }).await;
@@ -265,6 +547,9 @@ impl Coordinator {
                             }
                         } else {
                             debug!("(Coordinator) Event trigger skipped (in cooldown)");
+                            if let Some(adaptive) = self.adaptive.as_mut() {
+                                adaptive.on_trigger(true);
+                            }
                             self.update_stats(|stats| {
                                 stats.skipped_triggers += 1;
                             }).await;
@@ -274,7 +559,7 @@ impl Coordinator {
                         debug!(
                             "(Coordinator) Price change below threshold: {:.4}% < {:.4}%, ignoring",
                             event.price_change_percent * 100.0,
-                            self.config.high_threshold_percent
+                            threshold_percent
                         );
                     }
                 }
@@ -282,17 +567,90 @@ impl Coordinator {
         }
     }
 
+    /// 当前生效的事件触发阈值（百分比）
+    fn threshold_percent(&self) -> f64 {
+        self.adaptive.as_ref()
+            .map_or(self.config.high_threshold_percent, AdaptiveThreshold::threshold_percent)
+    }
+
+    /// 🎚️ 时钟 tick 上调整自适应阈值，变化超过一个步长时打日志
+    async fn adapt_on_tick(&mut self, clock_sent: bool) {
+        let Some(adaptive) = self.adaptive.as_mut() else {
+            return;
+        };
+        if let Some(previous) = adaptive.on_tick(clock_sent) {
+            info!(
+                "🎚️ (Coordinator) Event threshold {:.4}% -> {:.4}% (price change EWMA {:.4}%, rejection rate EWMA {:.2})",
+                previous,
+                adaptive.threshold_percent(),
+                adaptive.volatility_ewma_percent(),
+                adaptive.rejection_ewma()
+            );
+        }
+        let (threshold, volatility, rejection) = (
+            adaptive.threshold_percent(),
+            adaptive.volatility_ewma_percent(),
+            adaptive.rejection_ewma(),
+        );
+        self.update_stats(|stats| {
+            stats.effective_threshold_percent = threshold;
+            stats.volatility_ewma_percent = volatility;
+            stats.rejection_rate_ewma = rejection;
+        }).await;
+    }
+
+    /// 检查并更新cooldown：同一池子在 per_pool_cooldown_ms 内只触发一次，其他池子不受影响；
+    /// cooldown_ms > 0 时再叠加全局冷却
+    async fn check_cooldown(&self, pool_id: &str) -> bool {
+        let now = Instant::now();
+        let pool_cooldown = Duration::from_millis(self.config.per_pool_cooldown_ms);
+        if let Some(last) = self.pool_last_trigger.get(pool_id) {
+            if now.duration_since(*last) < pool_cooldown {
+                return false;
+            }
+        }
+
+        {
+            let mut last_trigger = self.last_trigger.lock().await;
+            if last_trigger.elapsed() < Duration::from_millis(self.config.cooldown_ms) {
+                return false;
+            }
+            *last_trigger = now;
+        }
+        self.pool_last_trigger.insert(pool_id.to_string(), now);
+        true
+    }
+
+    /// 直接套利检查，返回是否命中
+    async fn check_direct(&self, event: &PriceChangeEvent) -> bool {
+        let table = match &self.direct_table {
+            Some(table) => table,
+            None => return false,
+        };
+        let started = Instant::now();
+        let opportunity = match table.on_pool_event(&event.pool_id) {
+            Some(opportunity) => opportunity,
+            None => return false,
+        };
+        info!(
+            "⚡ (Coordinator) Direct arbitrage {}: buy {} ({}) -> sell {} ({}), spread {:.4}% [{}μs]",
+            opportunity.pair,
+            opportunity.buy.pool_id,
+            opportunity.buy.dex_name,
+            opportunity.sell.pool_id,
+            opportunity.sell.dex_name,
+            opportunity.spread_percent,
+            started.elapsed().as_micros()
+        );
+        self.update_stats(|stats| {
+            stats.direct_hits += 1;
+        }).await;
+        true
+    }
+
     /// 获取统计信息
     pub async fn get_stats(&self) -> CoordinatorStats {
-        let stats = self.stats.lock().await;
-        CoordinatorStats {
-            total_events: stats.total_events,
-            triggered_events: stats.triggered_events,
-            skipped_triggers: stats.skipped_triggers,
-            clock_triggers: stats.clock_triggers,
-            event_triggers: stats.event_triggers,
-            failed_sends: stats.failed_sends,
-        }
+        self.stats.lock().await.clone()
     }
 
     /// 更新统计信息
@@ -305,6 +663,55 @@ impl Coordinator {
     }
 }
 
+impl CoordinatorStats {
+    /// 📈 Prometheus 计数器（触发按 kind 区分）
+    pub fn write_prometheus(&self, writer: &mut PrometheusWriter) {
+        writer.family(
+            "pool_cache_coordinator_events_total",
+            "Price change events received by the coordinator",
+            MetricKind::Counter,
+        );
+        writer.sample("pool_cache_coordinator_events_total", &[], self.total_events as f64);
+
+        writer.family(
+            "pool_cache_coordinator_triggers_total",
+            "Coordinator scan triggers by kind (clock, event, skipped_cooldown, failed_send)",
+            MetricKind::Counter,
+        );
+        for (kind, value) in [
+            ("clock", self.clock_triggers),
+            ("event", self.event_triggers),
+            ("skipped_cooldown", self.skipped_triggers),
+            ("failed_send", self.failed_sends),
+        ] {
+            writer.sample("pool_cache_coordinator_triggers_total", &[("kind", kind)], value as f64);
+        }
+
+        writer.family(
+            "pool_cache_coordinator_scans_total",
+            "Calculation tasks dispatched by scope (scoped = only paths touching the triggering pair)",
+            MetricKind::Counter,
+        );
+        for (scope, value) in [("scoped", self.scoped_scans), ("full", self.full_scans)] {
+            writer.sample("pool_cache_coordinator_scans_total", &[("scope", scope)], value as f64);
+        }
+
+        writer.family(
+            "pool_cache_direct_arbitrage_hits_total",
+            "Two-hop direct arbitrage opportunities found by the fast path",
+            MetricKind::Counter,
+        );
+        writer.sample("pool_cache_direct_arbitrage_hits_total", &[], self.direct_hits as f64);
+
+        writer.family(
+            "pool_cache_coordinator_threshold_percent",
+            "Price change percent currently required for an event-triggered scan (adaptive or fixed)",
+            MetricKind::Gauge,
+        );
+        writer.sample("pool_cache_coordinator_threshold_percent", &[], self.effective_threshold_percent);
+    }
+}
+
 /// 打印统计信息（格式化输出）
 pub fn print_coordinator_stats(stats: &CoordinatorStats) {
     println!("\n========================================");
@@ -318,6 +725,13 @@ pub fn print_coordinator_stats(stats: &CoordinatorStats) {
     println!("事件触发次数: {}", stats.event_triggers);
     println!();
     println!("发送失败次数: {}", stats.failed_sends);
+    println!("直接套利命中次数: {}", stats.direct_hits);
+    println!("定向扫描 / 全量扫描: {} / {}", stats.scoped_scans, stats.full_scans);
+    println!(
+        "事件触发阈值: {:.4}%{}",
+        stats.effective_threshold_percent,
+        if stats.adaptive { "（自适应）" } else { "" }
+    );
 
     if stats.total_events > 0 {
         let triggered_ratio = (stats.triggered_events as f64 / stats.total_events as f64) * 100.0;
@@ -353,20 +767,24 @@ mod tests {
             coordinator.run().await;
         });
 
-        // Wait for at least 2 clock ticks
-        tokio::time::sleep(Duration::from_millis(120)).await;
-
-        // Drop coordinator to stop it
-        drop(handle);
-
-        // Check received tasks
+        // Drain tasks for at least 2 clock ticks (calc channel holds only 1 task, so receive as they arrive)
         let mut clock_count = 0;
-        while let Ok(task) = calc_rx.try_recv() {
-            if task.trigger_type == TriggerType::Clock {
-                clock_count += 1;
+        let deadline = tokio::time::sleep(Duration::from_millis(120));
+        tokio::pin!(deadline);
+        loop {
+            tokio::select! {
+                _ = &mut deadline => break,
+                Some(task) = calc_rx.recv() => {
+                    if task.trigger_type == TriggerType::Clock {
+                        clock_count += 1;
+                    }
+                }
             }
         }
 
+        // Drop coordinator to stop it
+        drop(handle);
+
         // Should have received at least 2 clock triggers
         assert!(clock_count >= 2, "Expected at least 2 clock triggers, got {}", clock_count);
     }
@@ -399,6 +817,7 @@ mod tests {
                 old_price: Some(100.0),
                 new_price: 100.15,
                 timestamp: Instant::now(),
+                ingest: None,
             })
             .await
             .unwrap();
@@ -411,6 +830,9 @@ mod tests {
         assert_eq!(task.trigger_type, TriggerType::Event);
         assert_eq!(task.trigger_source, "SOL/USDC (SOL/USDC)");
         assert_eq!(task.price_change_percent, Some(0.15 / 100.0));
+        assert_eq!(task.scope, Some(vec!["SOL".to_string(), "USDC".to_string()]));
+        // ⏱️ 事件触发的任务带上原始事件时间
+        assert!(task.event_timestamp.is_some_and(|event_at| event_at <= task.created_at));
     }
 
     #[tokio::test]
@@ -427,6 +849,7 @@ mod tests {
         let coordinator = Coordinator::new(config, event_rx, calc_tx);
         let stats = coordinator.get_stats().await;
         assert_eq!(stats.total_events, 0);
+        let stats_handle = coordinator.stats_handle();
 
         // Run coordinator in background
         tokio::spawn(async move {
@@ -443,6 +866,7 @@ mod tests {
                 old_price: Some(100.0),
                 new_price: 100.1,
                 timestamp: Instant::now(),
+                ingest: None,
             })
             .await
             .unwrap();
@@ -457,6 +881,7 @@ mod tests {
                 old_price: Some(100.0),
                 new_price: 100.1,
                 timestamp: Instant::now(),
+                ingest: None,
             })
             .await
             .unwrap();
@@ -464,13 +889,82 @@ mod tests {
         // Wait a bit
         tokio::time::sleep(Duration::from_millis(50)).await;
 
-        // Check stats
-        // Note: This is a simplified test - in real scenario we'd need to access coordinator's stats
+        // 全局cooldown期间，其他池子的事件也被跳过
+        let stats = stats_handle.lock().await;
+        assert_eq!(stats.total_events, 2);
+        assert_eq!(stats.skipped_triggers, 1);
+    }
+
+    fn event(pool_id: &str, pair: &str) -> PriceChangeEvent {
+        PriceChangeEvent {
+            pool_id: pool_id.to_string(),
+            pool_name: pair.to_string(),
+            pair: pair.to_string(),
+            price_change_percent: 0.5 / 100.0,
+            old_price: Some(100.0),
+            new_price: 100.5,
+            timestamp: Instant::now(),
+            ingest: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_per_pool_cooldown() {
+        let config = CoordinatorConfig {
+            tick_interval_ms: 60_000, // 只有启动时的一次时钟tick
+            per_pool_cooldown_ms: 1_000,
+            calc_channel_capacity: 8,
+            ..Default::default()
+        };
+
+        let (event_tx, event_rx) = mpsc::channel(config.event_channel_capacity);
+        let (calc_tx, mut calc_rx) = mpsc::channel(config.calc_channel_capacity);
+
+        let coordinator = Coordinator::new(config, event_rx, calc_tx);
+        let stats_handle = coordinator.stats_handle();
+        tokio::spawn(async move {
+            coordinator.run().await;
+        });
+
+        // SOL/USDC 连续两次更新：第二次在本池cooldown内被跳过；mSOL/SOL 不受影响
+        event_tx.send(event("pool1", "SOL/USDC")).await.unwrap();
+        event_tx.send(event("pool1", "SOL/USDC")).await.unwrap();
+        event_tx.send(event("pool2", "mSOL/SOL")).await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let mut event_scopes = Vec::new();
+        while let Ok(task) = calc_rx.try_recv() {
+            match task.trigger_type {
+                TriggerType::Event => event_scopes.push(task.scope.unwrap()),
+                TriggerType::Clock => assert!(task.scope.is_none()),
+            }
+        }
+        assert_eq!(event_scopes, vec![
+            vec!["SOL".to_string(), "USDC".to_string()],
+            vec!["mSOL".to_string(), "SOL".to_string()],
+        ]);
+
+        let stats = stats_handle.lock().await;
+        assert_eq!(stats.skipped_triggers, 1);
+        assert_eq!(stats.scoped_scans, 2);
+        assert_eq!(stats.full_scans, stats.clock_triggers);
+    }
+
+    #[test]
+    fn test_scope_from_pair() {
+        assert_eq!(
+            CalculationTask::scope_from_pair("JitoSOL/SOL"),
+            Some(vec!["JitoSOL".to_string(), "SOL".to_string()])
+        );
+        assert_eq!(CalculationTask::scope_from_pair("UNKNOWN"), None);
+        assert_eq!(CalculationTask::scope_from_pair("SOL/"), None);
     }
 
     #[tokio::test]
     async fn test_coordinator_below_threshold() {
         let config = CoordinatorConfig {
+            tick_interval_ms: 60_000, // only the immediate first tick fires during the test
             high_threshold_percent: 0.2, // 0.2%
             ..Default::default()
         };
@@ -485,6 +979,10 @@ mod tests {
             coordinator.run().await;
         });
 
+        // Consume the first clock tick (interval fires immediately) so the channel has room for an event task
+        let first = calc_rx.recv().await.unwrap();
+        assert_eq!(first.trigger_type, TriggerType::Clock);
+
         // Send a low price change event (< threshold)
         event_tx
             .send(PriceChangeEvent {
@@ -495,6 +993,7 @@ mod tests {
                 old_price: Some(100.0),
                 new_price: 100.1,
                 timestamp: Instant::now(),
+                ingest: None,
             })
             .await
             .unwrap();
@@ -506,6 +1005,67 @@ mod tests {
         assert!(calc_rx.try_recv().is_err());
     }
 
+    #[test]
+    fn test_adaptive_threshold_rises_under_burst_and_decays_when_idle() {
+        let config = AdaptiveThresholdConfig {
+            min_threshold_percent: 0.05,
+            max_threshold_percent: 1.0,
+            idle_ticks: 5,
+            ..Default::default()
+        };
+        let mut adaptive = AdaptiveThreshold::new(config, 0.2);
+
+        // 突发：每个 tick 十个 ~0.6% 的事件，Calculator 饱和（时钟发送失败，大部分触发被拒）
+        let mut logged = 0;
+        for tick in 0..30 {
+            for i in 0..10 {
+                adaptive.on_event(0.5 + (tick + i) as f64 % 3.0 * 0.1);
+                adaptive.on_trigger(i % 4 != 0);
+            }
+            logged += adaptive.on_tick(false).is_some() as u32;
+        }
+        let peak = adaptive.threshold_percent();
+        assert!(peak > 0.6, "threshold should rise above the burst's typical move, got {}", peak);
+        assert!(peak <= 1.0);
+        assert!(adaptive.rejection_ewma() > 0.5);
+        assert!(logged >= 1);
+
+        // 平静：没有事件，时钟任务都被接收 -> 被拒率回落后按 idle_ticks 逐步降低到下限
+        let mut previous = peak;
+        for _ in 0..20 {
+            adaptive.on_tick(true);
+            assert!(adaptive.threshold_percent() <= previous);
+            previous = adaptive.threshold_percent();
+        }
+        assert!(adaptive.threshold_percent() < peak);
+        for _ in 0..500 {
+            adaptive.on_tick(true);
+        }
+        assert_eq!(adaptive.threshold_percent(), 0.05);
+        assert!(adaptive.rejection_ewma() < 0.01);
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_reports_effective_threshold() {
+        let config = CoordinatorConfig {
+            high_threshold_percent: 5.0, // 超出上限，按 max 生效
+            adaptive: Some(AdaptiveThresholdConfig::default()),
+            ..Default::default()
+        };
+        let (_event_tx, event_rx) = mpsc::channel(config.event_channel_capacity);
+        let (calc_tx, _calc_rx) = mpsc::channel(config.calc_channel_capacity);
+
+        let coordinator = Coordinator::new(config, event_rx, calc_tx);
+        let stats = coordinator.get_stats().await;
+        assert!(stats.adaptive);
+        assert_eq!(stats.effective_threshold_percent, 1.0);
+
+        let fixed = Coordinator::new(CoordinatorConfig::default(), mpsc::channel(1).1, mpsc::channel(1).0);
+        let stats = fixed.get_stats().await;
+        assert!(!stats.adaptive);
+        assert_eq!(stats.effective_threshold_percent, 0.2);
+    }
+
     #[test]
     fn test_trigger_type() {
         assert_eq!(TriggerType::Clock, TriggerType::Clock);
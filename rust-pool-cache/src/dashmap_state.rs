@@ -18,8 +18,8 @@ use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::broadcast;
 
-use crate::price_cache::{PriceUpdateEvent, PoolPrice};
-use crate::state_layer::StateLayer;
+use crate::price_cache::{PairIndex, PriceUpdateEvent, PoolPrice};
+use crate::state_layer::{fill_snapshot_from_map, SnapshotFilter, StateLayer};
 
 /// DashMap 状态层
 ///
@@ -30,6 +30,9 @@ pub struct DashMapStateLayer {
     /// 读操作可以并行，写操作只锁单个分片
     prices: Arc<DashMap<String, PoolPrice>>,
 
+    /// 交易对二级索引：pair -> pool_id 集合
+    pair_index: PairIndex,
+
     /// 事件广播器：用于通知订阅者价格更新
     update_tx: broadcast::Sender<PriceUpdateEvent>,
 }
@@ -40,6 +43,7 @@ impl DashMapStateLayer {
         let (update_tx, _) = broadcast::channel(1000);
         Self {
             prices: Arc::new(DashMap::new()),
+            pair_index: PairIndex::new(),
             update_tx,
         }
     }
@@ -100,8 +104,13 @@ impl StateLayer for DashMapStateLayer {
                 }
             };
 
-            // 插入新价格（只锁单个分片）
-            self.prices.insert(pool_price.pool_id.clone(), pool_price.clone());
+            // 插入新价格（只锁单个分片），并同步维护交易对索引
+            let previous = self.prices.insert(pool_price.pool_id.clone(), pool_price.clone());
+            self.pair_index.record(
+                &pool_price.pool_id,
+                &pool_price.pair,
+                previous.as_ref().map(|p| p.pair.as_str()),
+            );
 
             PriceUpdateEvent {
                 pool_id: pool_price.pool_id,
@@ -130,15 +139,15 @@ impl StateLayer for DashMapStateLayer {
     /// 获取指定交易对的所有池子
     ///
     /// # 性能特性
-    /// - 需要遍历（O(n)）
+    /// - 通过 pair 索引定位（O(1) + 该交易对的池子数）
     /// - 读操作并行
-    /// - 建议配合 pair 索引优化
     fn get_pools_by_pair(&self, pair: &str) -> Vec<PoolPrice> {
-        self.prices
-            .iter()
-            .filter(|entry| entry.pair == pair)
-            .map(|entry| entry.clone())
-            .collect()
+        self.pair_index.collect_pools(&self.prices, pair)
+    }
+
+    /// 获取所有交易对（直接读索引，不克隆 PoolPrice）
+    fn get_pairs(&self) -> Vec<String> {
+        self.pair_index.pairs()
     }
 
     /// 订阅价格更新事件
@@ -191,31 +200,17 @@ impl StateLayer for DashMapStateLayer {
 
     /// 组合方法：获取新鲜且slot对齐的数据
     fn get_consistent_snapshot(&self, max_age_ms: u64, max_slot_spread: u64) -> Vec<PoolPrice> {
-        let now = Instant::now();
-
-        // 找到最新的slot
-        let latest_slot = self.prices.iter().map(|entry| entry.slot).max().unwrap_or(0);
-
-        if latest_slot == 0 {
-            return Vec::new();
-        }
-
-        // 同时过滤时间和slot
-        self.prices
-            .iter()
-            .filter(|entry| {
-                // 检查数据新鲜度
-                let age_ms = now.duration_since(entry.last_update).as_millis() as u64;
-                if age_ms > max_age_ms {
-                    return false;
-                }
+        self.get_consistent_snapshot_detailed(max_age_ms, max_slot_spread).included
+    }
 
-                // 检查slot对齐
-                let slot_diff = latest_slot.saturating_sub(entry.slot);
-                slot_diff <= max_slot_spread
-            })
-            .map(|entry| entry.clone())
-            .collect()
+    /// 同步拉取到调用方缓冲区
+    ///
+    /// # 性能特性
+    /// - 在分片读锁内先过滤，只克隆满足条件的条目
+    /// - 指定交易对时只访问该交易对的池子
+    /// - 缓冲区复用，稳定状态下不再分配 Vec
+    fn snapshot_into(&self, buf: &mut Vec<PoolPrice>, filter: &SnapshotFilter) -> u64 {
+        fill_snapshot_from_map(&self.prices, &self.pair_index, buf, filter)
     }
 
     /// 获取当前最新的slot号
@@ -238,15 +233,7 @@ impl StateLayer for DashMapStateLayer {
 
     /// 获取统计信息
     fn get_stats(&self) -> (usize, Vec<String>) {
-        let pairs: Vec<String> = self
-            .prices
-            .iter()
-            .map(|entry| entry.pair.clone())
-            .collect::<std::collections::HashSet<_>>()
-            .into_iter()
-            .collect();
-
-        (self.prices.len(), pairs)
+        (self.prices.len(), self.pair_index.pairs())
     }
 }
 
@@ -258,6 +245,7 @@ impl Clone for DashMapStateLayer {
     fn clone(&self) -> Self {
         Self {
             prices: Arc::clone(&self.prices),
+            pair_index: self.pair_index.clone(),
             update_tx: self.update_tx.clone(),
         }
     }
@@ -274,16 +262,12 @@ mod tests {
 
     fn create_test_pool_price(pool_id: &str, pair: &str, price: f64, slot: u64) -> PoolPrice {
         PoolPrice {
-            pool_id: pool_id.to_string(),
             dex_name: "Test".to_string(),
-            pair: pair.to_string(),
             base_reserve: 1000,
             quote_reserve: 1000,
             base_decimals: 6,
-            quote_decimals: 6,
-            price,
-            last_update: Instant::now(),
             slot,
+            ..PoolPrice::for_test(pool_id, pair, price)
         }
     }
 
@@ -327,6 +311,30 @@ mod tests {
 
         // 验证所有价格都已更新
         assert_eq!(state_layer.get_all_prices().len(), 10);
+        // 索引与主表一致
+        assert_eq!(state_layer.get_pools_by_pair("SOL/USDC").len(), 10);
+        assert_eq!(state_layer.get_pairs(), vec!["SOL/USDC".to_string()]);
+    }
+
+    #[test]
+    fn test_dashmap_state_layer_pair_change_moves_index() {
+        let state_layer = DashMapStateLayer::new();
+
+        state_layer.update_price(create_test_pool_price("pool1", "SOL/USDC", 100.0, 1000));
+        state_layer.update_price(create_test_pool_price("pool2", "SOL/USDC", 101.0, 1000));
+
+        // pool1 的 pair 字符串改变（例如配置修正）
+        state_layer.update_price(create_test_pool_price("pool1", "USDC/SOL", 0.01, 1001));
+
+        let usdc_pools = state_layer.get_pools_by_pair("SOL/USDC");
+        assert_eq!(usdc_pools.len(), 1);
+        assert_eq!(usdc_pools[0].pool_id, "pool2");
+        assert_eq!(state_layer.get_pools_by_pair("USDC/SOL").len(), 1);
+
+        // 旧交易对清空后不再出现在 get_pairs 中
+        state_layer.update_price(create_test_pool_price("pool2", "USDC/SOL", 0.01, 1002));
+        assert_eq!(state_layer.get_pairs(), vec!["USDC/SOL".to_string()]);
+        assert!(state_layer.get_pools_by_pair("SOL/USDC").is_empty());
     }
 
     #[test]
@@ -383,6 +391,81 @@ mod tests {
         assert_eq!(aligned.len(), 2);
     }
 
+    #[test]
+    fn test_snapshot_into_incremental_pull() {
+        let state_layer = DashMapStateLayer::new();
+        state_layer.update_price(create_test_pool_price("pool1", "SOL/USDC", 100.0, 1000));
+        state_layer.update_price(create_test_pool_price("pool2", "SOL/USDT", 100.0, 1001));
+
+        // 首次全量拉取，水位 = 最大 slot
+        let mut buf = Vec::new();
+        let watermark = state_layer.snapshot_into(&mut buf, &SnapshotFilter::new());
+        assert_eq!(buf.len(), 2);
+        assert_eq!(watermark, 1001);
+
+        // 没有变化：缓冲区被清空，水位不变
+        let filter = SnapshotFilter::new().changed_since(watermark);
+        assert_eq!(state_layer.snapshot_into(&mut buf, &filter), 1001);
+        assert!(buf.is_empty());
+
+        // 只返回水位之后更新的池子
+        state_layer.update_price(create_test_pool_price("pool1", "SOL/USDC", 101.0, 1003));
+        state_layer.update_price(create_test_pool_price("pool3", "SOL/USDC", 99.0, 1002));
+        let watermark = state_layer.snapshot_into(&mut buf, &filter);
+        let mut ids: Vec<&str> = buf.iter().map(|p| p.pool_id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["pool1", "pool3"]);
+        assert_eq!(watermark, 1003);
+        assert_eq!(buf.iter().find(|p| p.pool_id == "pool1").unwrap().price, 101.0);
+
+        // 增量 + 交易对：走索引
+        let filter = SnapshotFilter::new().pair("SOL/USDC").changed_since(1002);
+        state_layer.snapshot_into(&mut buf, &filter);
+        assert_eq!(buf.len(), 1);
+        assert_eq!(buf[0].pool_id, "pool1");
+    }
+
+    #[test]
+    fn test_snapshot_into_filters_and_reuses_buffer() {
+        use crate::liquidity::LiquidityUsd;
+        use std::time::Duration;
+
+        let state_layer = DashMapStateLayer::new();
+        let mut deep = create_test_pool_price("pool1", "SOL/USDC", 100.0, 1000);
+        deep.liquidity_usd = Some(LiquidityUsd { usd: 50_000.0, approximate: false });
+        state_layer.update_price(deep);
+        let mut orca = create_test_pool_price("pool2", "SOL/USDC", 100.0, 1000);
+        orca.dex_name = "Orca".to_string();
+        orca.last_update = Instant::now() - Duration::from_secs(60);
+        state_layer.update_price(orca);
+
+        let mut buf = Vec::with_capacity(16);
+        state_layer.snapshot_into(&mut buf, &SnapshotFilter::new().dex("Orca"));
+        assert_eq!(buf.len(), 1);
+        assert_eq!(buf[0].pool_id, "pool2");
+
+        // 没有流动性估计的池子不满足最低流动性
+        state_layer.snapshot_into(&mut buf, &SnapshotFilter::new().min_liquidity_usd(10_000.0));
+        assert_eq!(buf.len(), 1);
+        assert_eq!(buf[0].pool_id, "pool1");
+
+        state_layer.snapshot_into(&mut buf, &SnapshotFilter::new().max_age(Duration::from_secs(5)));
+        assert_eq!(buf.len(), 1);
+        assert_eq!(buf[0].pool_id, "pool1");
+
+        assert!(buf.capacity() >= 16);
+
+        // 默认实现（基于 get_all_prices）与 DashMap 实现结果一致
+        let filter = SnapshotFilter::new().pair("SOL/USDC").max_age(Duration::from_secs(5));
+        let mut fallback = Vec::new();
+        crate::state_layer::fill_snapshot(state_layer.get_all_prices(), &mut fallback, &filter);
+        state_layer.snapshot_into(&mut buf, &filter);
+        assert_eq!(
+            fallback.iter().map(|p| &p.pool_id).collect::<Vec<_>>(),
+            buf.iter().map(|p| &p.pool_id).collect::<Vec<_>>(),
+        );
+    }
+
     #[test]
     fn test_dashmap_state_layer_event_notification() {
         use tokio::runtime::Runtime;
@@ -7,8 +7,20 @@
 use deadpool_postgres::{Config, Pool, Runtime};
 use tokio_postgres::NoTls;
 use chrono::{DateTime, Utc};
-use tracing::{info, debug};
+use tracing::{info, debug, warn};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 use crate::router::ArbitragePath;
+use crate::slo::{LedgerEntry, SloComponent};
+use crate::calibration::{CalibrationBucket, CalibrationTable};
+use crate::onchain_simulator::TransactionSimulationOutcome;
+use crate::opportunity_validator::Revalidation;
+use crate::price_cache::PoolPrice;
+use crate::latency_budget::LatencyBreakdown;
+use crate::inventory::CapitalRequirement;
+use crate::stake_pool_reader::LstRateSample;
+use crate::scan_diff::{EventKind, ScanRecord};
+use serde::Serialize;
 
 /// 数据库配置
 #[derive(Debug, Clone)]
@@ -16,10 +28,11 @@ pub struct DatabaseConfig {
     pub enabled: bool,
     pub url: String,
     pub record_opportunities: bool,
-    #[allow(dead_code)]
     pub record_pool_updates: bool,
     #[allow(dead_code)]
     pub record_performance: bool,
+    /// 机会生命周期：同一指纹在该时长内再次发现视为同一次机会（秒）
+    pub opportunity_lifecycle_ttl_secs: u64,
 }
 
 impl Default for DatabaseConfig {
@@ -30,6 +43,118 @@ impl Default for DatabaseConfig {
             record_opportunities: true,
             record_pool_updates: false,
             record_performance: true,
+            opportunity_lifecycle_ttl_secs: 30,
+        }
+    }
+}
+
+/// 机会的发现上下文（哪个任务触发、扫描耗时、验证置信度）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OpportunityContext {
+    /// clock | event
+    pub trigger_type: String,
+    /// 触发池子名称或 "periodic"
+    pub trigger_source: String,
+    pub trigger_price_change_percent: Option<f64>,
+    pub scan_latency_ms: f64,
+    pub confidence_score: Option<f64>,
+    /// 上报前按最新缓存重新定价的 ROI（roi_percent 为发现时的 ROI）
+    pub revalidated_roi_percent: Option<f64>,
+    /// confirmed | degraded
+    pub revalidation_status: Option<String>,
+    /// 净利润的美元价值（没有新鲜 USD 价格时为 None）
+    pub profit_usd: Option<f64>,
+    /// ⏱️ 检测延迟预算（分阶段 + 端到端，回放时为 None）
+    pub latency: Option<LatencyBreakdown>,
+    /// 💼 按持有余额实际需要的资金（未配置 [inventory] 时为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capital: Option<CapitalRequirement>,
+    /// 🗃️ 发现该机会的扫描编号（GET /scans/:id）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scan_id: Option<u64>,
+    /// 🗃️ 相对上一次扫描的状态（快速通道的直接套利不在扫描结果中，为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scan_status: Option<EventKind>,
+}
+
+/// 🗂️ 一次机会的生命周期（opportunity_lifecycle 表的一行）
+#[derive(Debug, Clone, Serialize)]
+pub struct OpportunityLifecycleRecord {
+    pub id: i64,
+    pub fingerprint: String,
+    pub path_signature: String,
+    pub path_summary: String,
+    pub hop_count: i32,
+    pub first_seen_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    /// 持续时长（last_seen_at - first_seen_at，毫秒）
+    pub duration_ms: i64,
+    pub detection_count: i32,
+    pub first_roi_percent: f64,
+    pub peak_roi_percent: f64,
+    pub last_roi_percent: f64,
+    /// active | invalidated
+    pub status: String,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub revalidated_roi_percent: Option<f64>,
+    /// confirmed | degraded | invalidated
+    pub revalidation_status: Option<String>,
+    pub invalidation_reason: Option<String>,
+    pub simulated_at: Option<DateTime<Utc>>,
+    /// simulated | reverted | unsupported
+    pub simulation_status: Option<String>,
+    pub simulation_success: Option<bool>,
+    /// 模拟得到的起始代币净变化（原始单位）
+    pub simulated_profit: Option<i64>,
+}
+
+/// 🎞️ 一次池子更新（pool_update_history 表的一行，回放时按原顺序写回 PriceCache）
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolUpdateRecord {
+    pub recorded_at: DateTime<Utc>,
+    pub pool_address: String,
+    pub pair: String,
+    pub dex_name: String,
+    pub price: f64,
+    pub base_reserve: u64,
+    pub quote_reserve: u64,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    pub slot: u64,
+}
+
+impl PoolUpdateRecord {
+    pub fn from_price(price: &PoolPrice, recorded_at: DateTime<Utc>) -> Self {
+        Self {
+            recorded_at,
+            pool_address: price.pool_id.clone(),
+            pair: price.pair.clone(),
+            dex_name: price.dex_name.clone(),
+            price: price.price,
+            base_reserve: price.base_reserve,
+            quote_reserve: price.quote_reserve,
+            base_decimals: price.base_decimals,
+            quote_decimals: price.quote_decimals,
+            slot: price.slot,
+        }
+    }
+
+    /// 还原为 PriceCache 条目（更新时间为写回时刻，与 WebSocket 路径一致）
+    #[allow(dead_code)]
+    pub fn to_pool_price(&self) -> PoolPrice {
+        PoolPrice {
+            pool_id: self.pool_address.clone(),
+            dex_name: self.dex_name.clone(),
+            pair: self.pair.clone(),
+            base_reserve: self.base_reserve,
+            quote_reserve: self.quote_reserve,
+            base_decimals: self.base_decimals,
+            quote_decimals: self.quote_decimals,
+            price: self.price,
+            last_update: std::time::Instant::now(),
+            slot: self.slot,
+            liquidity_usd: None,
+            commitment: None,
         }
     }
 }
@@ -39,6 +164,8 @@ pub struct DatabaseManager {
     pool: Pool,
     config: DatabaseConfig,
     subscription_started_at: Option<DateTime<Utc>>,
+    /// 本次运行已写入的记录数（关闭时汇报）
+    records_written: AtomicU64,
 }
 
 impl DatabaseManager {
@@ -48,6 +175,39 @@ impl DatabaseManager {
             return Err("Database is not enabled".into());
         }
 
+        let pool = Self::connect(&config).await?;
+
+        // 运行迁移
+        info!("Running database migrations...");
+        Self::run_migrations(&pool).await?;
+        info!("Migrations completed");
+
+        Ok(Self {
+            pool,
+            config,
+            subscription_started_at: None,
+            records_written: AtomicU64::new(0),
+        })
+    }
+
+    /// 🎞️ 回放模式：只建回放用的表，不运行会重建实时表的迁移（003）
+    #[allow(dead_code)]
+    pub async fn new_for_replay(config: DatabaseConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let pool = Self::connect(&config).await?;
+        pool.get().await?
+            .batch_execute(include_str!("../migrations/013_pool_update_history.sql"))
+            .await?;
+
+        Ok(Self {
+            pool,
+            config,
+            subscription_started_at: None,
+            records_written: AtomicU64::new(0),
+        })
+    }
+
+    /// 创建连接池并测试连接
+    async fn connect(config: &DatabaseConfig) -> Result<Pool, Box<dyn std::error::Error>> {
         info!("Connecting to database...");
         debug!("Database URL: {}", mask_password(&config.url));
 
@@ -76,17 +236,7 @@ impl DatabaseManager {
         // 测试连接
         let _client = pool.get().await?;
         info!("Database connected successfully");
-
-        // 运行迁移
-        info!("Running database migrations...");
-        Self::run_migrations(&pool).await?;
-        info!("Migrations completed");
-
-        Ok(Self {
-            pool,
-            config,
-            subscription_started_at: None,
-        })
+        Ok(pool)
     }
 
     /// 运行数据库迁移
@@ -98,10 +248,75 @@ impl DatabaseManager {
         
         // 执行迁移
         client.batch_execute(migration_sql).await?;
+        
+        // 📈 SLO账本（增量迁移，保留历史）
+        client.batch_execute(include_str!("../migrations/004_slo_ledger.sql")).await?;
+        
+        // 🎯 置信度校准表（增量迁移，保留历史）
+        client.batch_execute(include_str!("../migrations/005_validator_calibration.sql")).await?;
+        
+        // 🧮 机会触发来源 / 扫描延迟（003 重建表后追加列）
+        client.batch_execute(include_str!("../migrations/006_opportunity_triggers.sql")).await?;
+        
+        // 🔁 上报前重新定价的 ROI / 分类
+        client.batch_execute(include_str!("../migrations/007_opportunity_revalidation.sql")).await?;
+        
+        // 🧪 交易级模拟结果（增量迁移，保留历史）
+        client.batch_execute(include_str!("../migrations/008_transaction_simulations.sql")).await?;
+        
+        // 🗂️ 机会生命周期（建表 + 逐列追加，已有数据库原地升级）
+        client.batch_execute(include_str!("../migrations/009_opportunity_lifecycle.sql")).await?;
+        
+        // 🌊 每跳价格冲击 / 手续费（003 重建表后追加列）
+        client.batch_execute(include_str!("../migrations/010_hop_price_impact.sql")).await?;
+        
+        // 🪙 LST 每 epoch 赎回比率（增量迁移，保留历史）
+        client.batch_execute(include_str!("../migrations/011_lst_rate_history.sql")).await?;
+        
+        // 💲 净利润美元价值（003 重建表后追加列）
+        client.batch_execute(include_str!("../migrations/012_profit_usd.sql")).await?;
+        
+        // 🎞️ 池子更新历史 / 回放机会（增量迁移，保留历史）
+        client.batch_execute(include_str!("../migrations/013_pool_update_history.sql")).await?;
+        
+        // 🗃️ 扫描摘要 + 机会的扫描状态列（增量迁移，保留历史）
+        client.batch_execute(include_str!("../migrations/014_scan_summaries.sql")).await?;
 
         Ok(())
     }
 
+    /// 🩺 连通性探测（/health）
+    pub async fn ping(&self) -> bool {
+        match self.pool.get().await {
+            Ok(client) => client.simple_query("SELECT 1").await.is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /// 🛑 等待在途写入完成（连接全部归还连接池），返回本次运行写入的记录数
+    pub async fn flush(&self, timeout: Duration) -> u64 {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let status = self.pool.status();
+            let in_use = status.size as i64 - status.available as i64;
+            if in_use <= 0 {
+                break;
+            }
+            if tokio::time::Instant::now() >= deadline {
+                warn!("Database flush timed out with {} connection(s) still busy", in_use);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        self.records_written.load(Ordering::Relaxed)
+    }
+
+    /// 🛑 关闭连接池（之后的写入会立即失败）
+    pub fn close(&self) {
+        self.pool.close();
+        info!("Database connection pool closed");
+    }
+
     /// 设置订阅开始时间
     pub fn set_subscription_start(&mut self) {
         self.subscription_started_at = Some(Utc::now());
@@ -110,11 +325,23 @@ impl DatabaseManager {
     }
 
     /// 记录套利机会
+    #[allow(dead_code)]
     pub async fn record_opportunity(
         &self,
         path: &ArbitragePath,
         router_mode: &str,
         min_roi_threshold: f64,
+    ) -> Result<i32, Box<dyn std::error::Error>> {
+        self.record_opportunity_with_context(path, router_mode, min_roi_threshold, None).await
+    }
+
+    /// 记录套利机会，附带触发来源和扫描延迟
+    pub async fn record_opportunity_with_context(
+        &self,
+        path: &ArbitragePath,
+        router_mode: &str,
+        min_roi_threshold: f64,
+        context: Option<&OpportunityContext>,
     ) -> Result<i32, Box<dyn std::error::Error>> {
         if !self.config.record_opportunities {
             return Ok(0);
@@ -139,8 +366,14 @@ impl DatabaseManager {
                 arbitrage_type, start_token, end_token,
                 input_amount, output_amount, gross_profit, net_profit, roi_percent, estimated_fees,
                 hop_count, path_summary,
-                router_mode, min_roi_threshold
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+                router_mode, min_roi_threshold,
+                trigger_type, trigger_source, trigger_price_change_percent,
+                scan_latency_ms, confidence_score,
+                revalidated_roi_percent, revalidation_status,
+                worst_hop_impact_percent, profit_usd,
+                scan_id, scan_status
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16,
+                      $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27)
             RETURNING id
             "#,
             &[
@@ -160,6 +393,17 @@ impl DatabaseManager {
                 &path_summary,
                 &router_mode,
                 &min_roi_threshold,
+                &context.map(|c| c.trigger_type.as_str()),
+                &context.map(|c| c.trigger_source.as_str()),
+                &context.and_then(|c| c.trigger_price_change_percent),
+                &context.map(|c| c.scan_latency_ms),
+                &context.and_then(|c| c.confidence_score),
+                &context.and_then(|c| c.revalidated_roi_percent),
+                &context.and_then(|c| c.revalidation_status.as_deref()),
+                &path.worst_hop_impact(),
+                &context.and_then(|c| c.profit_usd),
+                &context.and_then(|c| c.scan_id).map(|id| id as i64),
+                &context.and_then(|c| c.scan_status).map(|s| s.as_str()),
             ],
         ).await?;
 
@@ -167,6 +411,7 @@ impl DatabaseManager {
 
         // 记录路径详情
         self.record_path_steps(&client, opportunity_id, path).await?;
+        self.records_written.fetch_add(1 + path.steps.len() as u64, Ordering::Relaxed);
 
         debug!("Recorded opportunity #{} - ROI: {:.4}% - Path: {}", 
             opportunity_id, path.roi_percent, path_summary);
@@ -189,8 +434,9 @@ impl DatabaseManager {
                     pool_id, dex_name,
                     input_token, output_token, price,
                     liquidity_base, liquidity_quote,
-                    expected_input, expected_output
-                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                    expected_input, expected_output,
+                    price_impact_percent, effective_fee_bps
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
                 "#,
                 &[
                     &opportunity_id,
@@ -204,6 +450,8 @@ impl DatabaseManager {
                     &(step.liquidity_quote as i64),
                     &step.expected_input,
                     &step.expected_output,
+                    &step.price_impact_percent,
+                    &step.effective_fee_bps,
                 ],
             ).await?;
         }
@@ -211,6 +459,234 @@ impl DatabaseManager {
         Ok(())
     }
 
+    /// 🧪 记录交易级模拟结果
+    pub async fn record_transaction_simulation(
+        &self,
+        path: &ArbitragePath,
+        outcome: &TransactionSimulationOutcome,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let client = self.pool.get().await?;
+        let simulation = match outcome {
+            TransactionSimulationOutcome::Simulated(sim) => Some(sim),
+            TransactionSimulationOutcome::CannotSimulate { .. } => None,
+        };
+        let error = match outcome {
+            TransactionSimulationOutcome::Simulated(sim) => sim.error.clone(),
+            TransactionSimulationOutcome::CannotSimulate { reason } => Some(reason.clone()),
+        };
+        let hop_deltas = simulation
+            .map(|sim| serde_json::to_string(&sim.hops))
+            .transpose()?;
+
+        client.execute(
+            r#"
+            INSERT INTO transaction_simulations (
+                simulated_at, path_signature, path_summary, roi_percent, status,
+                beat_input, compute_units, input_amount, net_delta, hop_deltas,
+                error, simulation_latency_ms
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            "#,
+            &[
+                &Utc::now().naive_utc(),
+                &path.signature().to_string(),
+                &self.generate_path_summary(path),
+                &path.roi_percent,
+                &outcome.status(),
+                &simulation.map(|sim| sim.beat_input).unwrap_or(false),
+                &simulation.and_then(|sim| sim.compute_units).map(|cu| cu as i64),
+                &simulation.map(|sim| sim.input_amount as i64),
+                &simulation.and_then(|sim| sim.net_delta).map(|delta| delta as i64),
+                &hop_deltas,
+                &error,
+                &simulation.map(|sim| sim.simulation_latency_ms as i64),
+            ],
+        ).await?;
+        self.records_written.fetch_add(1, Ordering::Relaxed);
+
+        // 🗂️ 写入该指纹最近一次生命周期
+        client.execute(
+            r#"
+            UPDATE opportunity_lifecycle
+            SET simulated_at = $2,
+                simulation_status = $3,
+                simulation_success = $4,
+                simulated_profit = $5
+            WHERE id = (
+                SELECT id FROM opportunity_lifecycle
+                WHERE fingerprint = $1
+                ORDER BY last_seen_at DESC
+                LIMIT 1
+            )
+            "#,
+            &[
+                &path.fingerprint(),
+                &Utc::now().naive_utc(),
+                &outcome.status(),
+                &simulation.map(|sim| sim.beat_input),
+                &simulation.and_then(|sim| sim.net_delta).map(|delta| delta as i64),
+            ],
+        ).await?;
+
+        Ok(())
+    }
+
+    /// 🗂️ 记录一次机会发现
+    ///
+    /// 同一指纹（路径签名 + 方向）在 TTL 内有未结束的生命周期时只更新 last_seen_at、
+    /// 峰值 ROI 和发现次数，否则插入新的一行。返回生命周期 id。
+    pub async fn observe_opportunity(
+        &self,
+        path: &ArbitragePath,
+    ) -> Result<i64, Box<dyn std::error::Error>> {
+        let client = self.pool.get().await?;
+        let fingerprint = path.fingerprint();
+        let now = Utc::now();
+        let cutoff = now - chrono::Duration::seconds(self.config.opportunity_lifecycle_ttl_secs as i64);
+
+        let updated = client.query_opt(
+            r#"
+            UPDATE opportunity_lifecycle
+            SET last_seen_at = $2,
+                detection_count = detection_count + 1,
+                peak_roi_percent = GREATEST(peak_roi_percent, $3),
+                last_roi_percent = $3
+            WHERE id = (
+                SELECT id FROM opportunity_lifecycle
+                WHERE fingerprint = $1 AND status = 'active' AND last_seen_at >= $4
+                ORDER BY last_seen_at DESC
+                LIMIT 1
+            )
+            RETURNING id
+            "#,
+            &[&fingerprint, &now.naive_utc(), &path.roi_percent, &cutoff.naive_utc()],
+        ).await?;
+        if let Some(row) = updated {
+            return Ok(row.get(0));
+        }
+
+        let row = client.query_one(
+            r#"
+            INSERT INTO opportunity_lifecycle (
+                fingerprint, path_signature, path_summary, hop_count,
+                first_seen_at, last_seen_at,
+                first_roi_percent, peak_roi_percent, last_roi_percent
+            ) VALUES ($1, $2, $3, $4, $5, $5, $6, $6, $6)
+            RETURNING id
+            "#,
+            &[
+                &fingerprint,
+                &path.signature().to_string(),
+                &self.generate_path_summary(path),
+                &(path.steps.len() as i32),
+                &now.naive_utc(),
+                &path.roi_percent,
+            ],
+        ).await?;
+        self.records_written.fetch_add(1, Ordering::Relaxed);
+
+        Ok(row.get(0))
+    }
+
+    /// 🗂️ 记录上报前重新定价的结果（invalidated 时结束该生命周期）
+    pub async fn record_opportunity_revalidation(
+        &self,
+        path: &ArbitragePath,
+        revalidation: &Revalidation,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let client = self.pool.get().await?;
+        let (status, closed_at, reason) = match revalidation {
+            Revalidation::Invalidated { reason, .. } => {
+                ("invalidated", Some(Utc::now().naive_utc()), Some(format!("{:?}", reason)))
+            }
+            _ => ("active", None, None),
+        };
+
+        client.execute(
+            r#"
+            UPDATE opportunity_lifecycle
+            SET status = $2,
+                closed_at = $3,
+                revalidated_roi_percent = $4,
+                revalidation_status = $5,
+                invalidation_reason = $6
+            WHERE id = (
+                SELECT id FROM opportunity_lifecycle
+                WHERE fingerprint = $1 AND status = 'active'
+                ORDER BY last_seen_at DESC
+                LIMIT 1
+            )
+            "#,
+            &[
+                &path.fingerprint(),
+                &status,
+                &closed_at,
+                &revalidation.revalidated_roi(),
+                &revalidation.status(),
+                &reason,
+            ],
+        ).await?;
+        self.records_written.fetch_add(1, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    /// 🗂️ 查询某个指纹的全部生命周期（最近的在前，最多 100 条）
+    pub async fn get_opportunity_history(
+        &self,
+        fingerprint: &str,
+    ) -> Result<Vec<OpportunityLifecycleRecord>, Box<dyn std::error::Error>> {
+        let client = self.pool.get().await?;
+
+        let rows = client.query(
+            r#"
+            SELECT id, fingerprint, path_signature, path_summary, hop_count,
+                   first_seen_at, last_seen_at, detection_count,
+                   first_roi_percent, peak_roi_percent, last_roi_percent,
+                   status, closed_at, revalidated_roi_percent, revalidation_status, invalidation_reason,
+                   simulated_at, simulation_status, simulation_success, simulated_profit
+            FROM opportunity_lifecycle
+            WHERE fingerprint = $1
+            ORDER BY first_seen_at DESC
+            LIMIT 100
+            "#,
+            &[&fingerprint],
+        ).await?;
+
+        let utc = |at: chrono::NaiveDateTime| DateTime::<Utc>::from_naive_utc_and_offset(at, Utc);
+        let records = rows
+            .iter()
+            .map(|row| {
+                let first_seen_at = utc(row.get(5));
+                let last_seen_at = utc(row.get(6));
+                OpportunityLifecycleRecord {
+                    id: row.get(0),
+                    fingerprint: row.get(1),
+                    path_signature: row.get(2),
+                    path_summary: row.get(3),
+                    hop_count: row.get(4),
+                    first_seen_at,
+                    last_seen_at,
+                    duration_ms: (last_seen_at - first_seen_at).num_milliseconds(),
+                    detection_count: row.get(7),
+                    first_roi_percent: row.get(8),
+                    peak_roi_percent: row.get(9),
+                    last_roi_percent: row.get(10),
+                    status: row.get(11),
+                    closed_at: row.get::<_, Option<chrono::NaiveDateTime>>(12).map(utc),
+                    revalidated_roi_percent: row.get(13),
+                    revalidation_status: row.get(14),
+                    invalidation_reason: row.get(15),
+                    simulated_at: row.get::<_, Option<chrono::NaiveDateTime>>(16).map(utc),
+                    simulation_status: row.get(17),
+                    simulation_success: row.get(18),
+                    simulated_profit: row.get(19),
+                }
+            })
+            .collect();
+
+        Ok(records)
+    }
+
     /// 生成路径摘要
     fn generate_path_summary(&self, path: &ArbitragePath) -> String {
         let mut tokens = vec![path.start_token.clone()];
@@ -220,40 +696,137 @@ impl DatabaseManager {
         tokens.join("→")
     }
 
-    /// 记录池子更新
-    #[allow(dead_code)]
-    pub async fn record_pool_update(
+    /// 🎞️ 批量记录池子更新（回放数据源）
+    pub async fn record_pool_updates(
         &self,
-        pool_address: &str,
-        pool_name: &str,
-        pool_type: &str,
-        price: f64,
-        base_reserve: u64,
-        quote_reserve: u64,
+        updates: &[PoolUpdateRecord],
     ) -> Result<(), Box<dyn std::error::Error>> {
-        if !self.config.record_pool_updates {
+        if !self.config.record_pool_updates || updates.is_empty() {
             return Ok(());
         }
 
+        let mut client = self.pool.get().await?;
+        let transaction = client.transaction().await?;
+        let statement = transaction.prepare(
+            r#"
+            INSERT INTO pool_update_history (
+                recorded_at, pool_address, pair, dex_name, price,
+                base_reserve, quote_reserve, base_decimals, quote_decimals, slot
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "#,
+        ).await?;
+        for update in updates {
+            transaction.execute(
+                &statement,
+                &[
+                    &update.recorded_at.naive_utc(),
+                    &update.pool_address,
+                    &update.pair,
+                    &update.dex_name,
+                    &update.price,
+                    &(update.base_reserve as i64),
+                    &(update.quote_reserve as i64),
+                    &(update.base_decimals as i16),
+                    &(update.quote_decimals as i16),
+                    &(update.slot as i64),
+                ],
+            ).await?;
+        }
+        transaction.commit().await?;
+        self.records_written.fetch_add(updates.len() as u64, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    /// 🎞️ 按 (recorded_at, id) 顺序读取 [from, to) 内的池子更新
+    ///
+    /// `after` 为上一页最后一行的 (recorded_at, id)，返回的每行附带自己的游标。
+    #[allow(dead_code)]
+    pub async fn load_pool_updates(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        after: Option<(DateTime<Utc>, i64)>,
+        limit: i64,
+    ) -> Result<Vec<(i64, PoolUpdateRecord)>, Box<dyn std::error::Error>> {
+        let client = self.pool.get().await?;
+        let (after_at, after_id) = after.unwrap_or((from, 0));
+
+        let rows = client.query(
+            r#"
+            SELECT id, recorded_at, pool_address, pair, dex_name, price,
+                   base_reserve, quote_reserve, base_decimals, quote_decimals, slot
+            FROM pool_update_history
+            WHERE recorded_at >= $1 AND recorded_at < $2
+              AND (recorded_at, id) > ($3, $4)
+            ORDER BY recorded_at ASC, id ASC
+            LIMIT $5
+            "#,
+            &[&from.naive_utc(), &to.naive_utc(), &after_at.naive_utc(), &after_id, &limit],
+        ).await?;
+
+        let updates = rows
+            .iter()
+            .map(|row| {
+                let recorded_at: chrono::NaiveDateTime = row.get(1);
+                (
+                    row.get::<_, i64>(0),
+                    PoolUpdateRecord {
+                        recorded_at: DateTime::<Utc>::from_naive_utc_and_offset(recorded_at, Utc),
+                        pool_address: row.get(2),
+                        pair: row.get(3),
+                        dex_name: row.get(4),
+                        price: row.get(5),
+                        base_reserve: row.get::<_, i64>(6) as u64,
+                        quote_reserve: row.get::<_, i64>(7) as u64,
+                        base_decimals: row.get::<_, i16>(8) as u8,
+                        quote_decimals: row.get::<_, i16>(9) as u8,
+                        slot: row.get::<_, i64>(10) as u64,
+                    },
+                )
+            })
+            .collect();
+
+        Ok(updates)
+    }
+
+    /// 🎞️ 记录回放发现的机会（replayed_opportunities，不写实时表）
+    #[allow(dead_code)]
+    pub async fn record_replayed_opportunity(
+        &self,
+        replay_run: &str,
+        market_time: DateTime<Utc>,
+        path: &ArbitragePath,
+        context: &OpportunityContext,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let client = self.pool.get().await?;
 
         client.execute(
             r#"
-            INSERT INTO pool_updates (
-                pool_address, pool_name, pool_type,
-                updated_at, price, base_reserve, quote_reserve
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+            INSERT INTO replayed_opportunities (
+                replay_run, market_time, replayed_at,
+                arbitrage_type, start_token, path_summary, path_signature, hop_count,
+                input_amount, net_profit, roi_percent,
+                trigger_source, confidence_score
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
             "#,
             &[
-                &pool_address,
-                &pool_name,
-                &pool_type,
+                &replay_run,
+                &market_time.naive_utc(),
                 &Utc::now().naive_utc(),
-                &price,
-                &(base_reserve as i64),
-                &(quote_reserve as i64),
+                &format!("{:?}", path.arb_type),
+                &path.start_token,
+                &self.generate_path_summary(path),
+                &path.signature().to_string(),
+                &(path.steps.len() as i32),
+                &path.input_amount,
+                &path.net_profit,
+                &path.roi_percent,
+                &context.trigger_source,
+                &context.confidence_score,
             ],
         ).await?;
+        self.records_written.fetch_add(1, Ordering::Relaxed);
 
         Ok(())
     }
@@ -292,6 +865,7 @@ impl DatabaseManager {
                 &(max_hops as i32),
             ],
         ).await?;
+        self.records_written.fetch_add(1, Ordering::Relaxed);
 
         Ok(())
     }
@@ -325,9 +899,236 @@ impl DatabaseManager {
                 &actual_profit,
             ],
         ).await?;
+        self.records_written.fetch_add(1, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    /// 📈 写入SLO账本条目（状态切换或检查点）
+    pub async fn record_slo_entries(
+        &self,
+        entries: &[LedgerEntry],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let client = self.pool.get().await?;
+
+        for entry in entries {
+            client.execute(
+                r#"
+                INSERT INTO slo_ledger (component, is_up, recorded_at)
+                VALUES ($1, $2, $3)
+                "#,
+                &[
+                    &entry.component.as_str(),
+                    &entry.up,
+                    &entry.at.naive_utc(),
+                ],
+            ).await?;
+            self.records_written.fetch_add(1, Ordering::Relaxed);
+        }
 
         Ok(())
     }
+
+    /// 🗃️ 记录一次路由扫描的摘要（耗时、涉及池子数、按状态计数）
+    pub async fn record_scan_summary(&self, record: &ScanRecord) -> Result<(), Box<dyn std::error::Error>> {
+        let client = self.pool.get().await?;
+
+        client.execute(
+            r#"
+            INSERT INTO scan_summaries (
+                scan_id, scanned_at, duration_ms, trigger_type, trigger_source, scoped,
+                pools_used, opportunity_count,
+                new_count, persisting_count, improved_count, worsened_count, gone_count
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            "#,
+            &[
+                &(record.id as i64),
+                &record.scanned_at.naive_utc(),
+                &record.duration_ms,
+                &record.trigger_type,
+                &record.trigger_source,
+                &record.scoped,
+                &(record.pools_used as i32),
+                &(record.opportunities as i32),
+                &(record.counts.new as i32),
+                &(record.counts.persisting as i32),
+                &(record.counts.improved as i32),
+                &(record.counts.worsened as i32),
+                &(record.counts.gone as i32),
+            ],
+        ).await?;
+        self.records_written.fetch_add(1, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    /// 🪙 记录 LST 每个 epoch 的赎回比率（同一 epoch 覆盖）
+    pub async fn record_lst_rates(
+        &self,
+        samples: &[LstRateSample],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let client = self.pool.get().await?;
+
+        for sample in samples {
+            client.execute(
+                r#"
+                INSERT INTO lst_rate_history (mint, epoch, rate, recorded_at)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (mint, epoch) DO UPDATE SET rate = EXCLUDED.rate, recorded_at = EXCLUDED.recorded_at
+                "#,
+                &[
+                    &sample.mint.to_string(),
+                    &(sample.epoch as i64),
+                    &sample.rate,
+                    &Utc::now().naive_utc(),
+                ],
+            ).await?;
+            self.records_written.fetch_add(1, Ordering::Relaxed);
+        }
+
+        Ok(())
+    }
+
+    /// 🪙 读取最近几个 epoch 的 LST 赎回比率（用于重启后恢复）
+    pub async fn load_lst_rates(&self, epochs: u64) -> Result<Vec<LstRateSample>, Box<dyn std::error::Error>> {
+        let client = self.pool.get().await?;
+
+        let rows = client.query(
+            r#"
+            SELECT mint, epoch, rate
+            FROM lst_rate_history
+            WHERE epoch >= (SELECT COALESCE(MAX(epoch), 0) FROM lst_rate_history) - $1
+            ORDER BY epoch ASC
+            "#,
+            &[&(epochs as i64)],
+        ).await?;
+
+        let samples = rows
+            .iter()
+            .filter_map(|row| {
+                let mint: String = row.get(0);
+                let epoch: i64 = row.get(1);
+                mint.parse().ok().map(|mint| LstRateSample {
+                    mint,
+                    epoch: epoch as u64,
+                    rate: row.get(2),
+                })
+            })
+            .collect();
+
+        Ok(samples)
+    }
+
+    /// 📈 读取最近7天的SLO账本（用于重启后恢复）
+    pub async fn load_slo_entries(&self) -> Result<Vec<LedgerEntry>, Box<dyn std::error::Error>> {
+        let client = self.pool.get().await?;
+
+        let rows = client.query(
+            r#"
+            SELECT component, is_up, recorded_at
+            FROM slo_ledger
+            WHERE recorded_at > $1
+            ORDER BY recorded_at ASC
+            "#,
+            &[&(Utc::now() - chrono::Duration::days(7)).naive_utc()],
+        ).await?;
+
+        let entries = rows
+            .iter()
+            .filter_map(|row| {
+                let component: String = row.get(0);
+                let up: bool = row.get(1);
+                let at: chrono::NaiveDateTime = row.get(2);
+                SloComponent::parse(&component).map(|component| LedgerEntry {
+                    component,
+                    up,
+                    at: DateTime::<Utc>::from_naive_utc_and_offset(at, Utc),
+                })
+            })
+            .collect();
+
+        Ok(entries)
+    }
+
+    /// 🎯 保存置信度校准表（按桶覆盖）
+    pub async fn save_calibration(
+        &self,
+        table: &CalibrationTable,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let client = self.pool.get().await?;
+        let fitted_at = table.fitted_at.unwrap_or_else(Utc::now).naive_utc();
+
+        for bucket in &table.buckets {
+            client.execute(
+                r#"
+                INSERT INTO validator_calibration
+                    (bucket, lower_bound, upper_bound, samples, persisted, empirical_rate, calibrated, fitted_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                ON CONFLICT (bucket) DO UPDATE SET
+                    lower_bound = EXCLUDED.lower_bound,
+                    upper_bound = EXCLUDED.upper_bound,
+                    samples = EXCLUDED.samples,
+                    persisted = EXCLUDED.persisted,
+                    empirical_rate = EXCLUDED.empirical_rate,
+                    calibrated = EXCLUDED.calibrated,
+                    fitted_at = EXCLUDED.fitted_at
+                "#,
+                &[
+                    &(bucket.bucket as i32),
+                    &bucket.lower,
+                    &bucket.upper,
+                    &(bucket.samples as i64),
+                    &(bucket.persisted as i64),
+                    &bucket.empirical_rate,
+                    &bucket.calibrated,
+                    &fitted_at,
+                ],
+            ).await?;
+            self.records_written.fetch_add(1, Ordering::Relaxed);
+        }
+
+        debug!("Saved calibration table ({} samples)", table.total_samples);
+        Ok(())
+    }
+
+    /// 🎯 读取置信度校准表（重启后恢复；表为空返回 None）
+    pub async fn load_calibration(&self) -> Result<Option<CalibrationTable>, Box<dyn std::error::Error>> {
+        let client = self.pool.get().await?;
+
+        let rows = client.query(
+            r#"
+            SELECT bucket, lower_bound, upper_bound, samples, persisted, empirical_rate, calibrated, fitted_at
+            FROM validator_calibration
+            ORDER BY bucket ASC
+            "#,
+            &[],
+        ).await?;
+
+        if rows.is_empty() {
+            return Ok(None);
+        }
+
+        let mut fitted_at: Option<DateTime<Utc>> = None;
+        let buckets: Vec<CalibrationBucket> = rows
+            .iter()
+            .map(|row| {
+                let at: chrono::NaiveDateTime = row.get(7);
+                let at = DateTime::<Utc>::from_naive_utc_and_offset(at, Utc);
+                fitted_at = Some(fitted_at.map_or(at, |prev| prev.max(at)));
+                CalibrationBucket {
+                    bucket: row.get::<_, i32>(0) as usize,
+                    lower: row.get(1),
+                    upper: row.get(2),
+                    samples: row.get::<_, i64>(3) as u64,
+                    persisted: row.get::<_, i64>(4) as u64,
+                    empirical_rate: row.get(5),
+                    calibrated: row.get(6),
+                }
+            })
+            .collect();
+
+        Ok(CalibrationTable::from_buckets(buckets, fitted_at))
+    }
 }
 
 /// 隐藏密码显示
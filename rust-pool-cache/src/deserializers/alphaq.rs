@@ -13,6 +13,13 @@ use crate::dex_interface::{DexPool, DexError};
 /// - 16 bytes: Pool identifier/name
 /// - 10 Pubkeys (320 bytes): Various accounts
 /// - 42 u64 fields (336 bytes): Reserves and configuration
+///
+/// Audit against the mainnet fixture (Pi9nzTjP..., "USDT-USDC"):
+/// - The real token mints are `pubkey_8` (offset 240, USDT) and `pubkey_9` (offset 272, USDC);
+///   the fields named `token_a_mint` / `token_b_mint` hold other accounts.
+/// - Reserves live in the pool account itself (offset 432 / 440) and the program rewrites
+///   them on every swap, so the pool subscription alone keeps them current — no vault
+///   subscription is needed (`get_vault_addresses` stays `None`).
 #[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
 pub struct AlphaQPoolState {
     /// Pool identifier/name (16 bytes)
@@ -42,24 +49,25 @@ pub struct AlphaQPoolState {
     pub pubkey_9: Pubkey,
     pub pubkey_10: Pubkey,
     
-    /// Padding fields (first 9 u64 = 72 bytes before real reserves)
+    /// Padding fields (12 u64 = 96 bytes before real reserves)
     /// Based on analysis: offsets 336-424 are NOT the actual reserves
-    pub padding_before_reserves: [u64; 9],
+    /// (offset 408 / 416 look like reserves but do not track swaps)
+    pub padding_before_reserves: [u64; 12],
     
-    /// Reserve A amount (offset 432, u64[9] in analysis)
+    /// Reserve A amount (offset 432)
     pub reserve_a: u64,
     
-    /// Reserve B amount (offset 440, u64[10] in analysis)
+    /// Reserve B amount (offset 440)
     pub reserve_b: u64,
     
-    /// LP supply and other fields (31 u64 values)
+    /// LP supply and other fields (28 u64 values)
     /// This includes LP supply and fee configuration
     /// This includes various pool parameters like:
     /// - Fee rates
     /// - Swap limits
     /// - Admin fees
     /// - etc.
-    pub config_fields: [u64; 31],
+    pub config_fields: [u64; 28],
 }
 
 #[allow(dead_code)]
@@ -173,6 +181,11 @@ impl DexPool for AlphaQPoolState {
             self.get_pool_name()
         ))
     }
+    
+    fn get_mints(&self) -> Option<(Pubkey, Pubkey)> {
+        // 真实 mint 在 pubkey_8 / pubkey_9（见结构体注释），顺序与 reserve_a / reserve_b 一致
+        Some((self.pubkey_8, self.pubkey_9))
+    }
 }
 
 #[cfg(test)]
@@ -186,11 +199,28 @@ mod tests {
         
         let expected = 16  // pool_name
             + 32 * 10      // 10 Pubkeys
-            + 8 * 9        // padding_before_reserves
+            + 8 * 12       // padding_before_reserves
             + 8 * 2        // reserve_a, reserve_b
-            + 8 * 31;      // config_fields (including lp_supply)
+            + 8 * 28;      // config_fields (including lp_supply)
         
         assert_eq!(expected, 672, "Structure size should be 672 bytes");
     }
+    
+    #[test]
+    fn test_fixture_mints_and_in_account_reserves() {
+        use crate::pool_fixture::{fixture_path, PoolFixture};
+        use std::path::Path;
+        
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let fixture = PoolFixture::load(&fixture_path(&dir, "alphaq")).unwrap();
+        let pool = AlphaQPoolState::from_account_data(&fixture.decode_data().unwrap()).unwrap();
+        
+        let (mint_a, mint_b) = pool.get_mints().unwrap();
+        assert_eq!(mint_a.to_string(), "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB");
+        assert_eq!(mint_b.to_string(), "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+        assert_eq!(pool.get_reserves(), (999_991_373_419, 1_000_008_626_580));
+        assert!(pool.get_vault_addresses().is_none());
+        assert!((pool.calculate_price() - 1.0).abs() < 0.001);
+    }
 }
 
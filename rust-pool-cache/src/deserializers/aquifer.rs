@@ -12,6 +12,9 @@ use crate::dex_interface::{DexPool, DexError};
 /// Structure (基于通用 AMM 模式):
 /// - Pubkey fields: Token mints, vaults, authority
 /// - u64 fields: Reserves, fees, configuration
+/// 
+/// 定价：amp 在账户中的偏移尚未确认，暂按恒定乘积（储备比例）处理，
+/// 不接入 stable_math；确认布局后再读取 amp 改为 StableSwap 报价
 #[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
 pub struct AquiferPoolState {
     /// Header fields (discriminator and version info)
@@ -0,0 +1,522 @@
+/*!
+ * Raydium CLMM 跨 tick 报价
+ *
+ * 池子账户只有当前 tick / sqrt_price / 活跃流动性；大额成交会穿过已初始化的 tick，
+ * 每穿过一个 tick 活跃流动性按 liquidity_net 变化。这里解析当前 tick 附近的
+ * TickArray 账户，按链上 swap_math 逐段计算精确输入报价（含手续费与价格限制）。
+ *
+ * 账户布局（Anchor zero_copy packed，含 8 字节判别符）:
+ * - TickArrayState 10240 bytes: pool_id @8, start_tick_index @40, 60 × TickState(168 bytes) @44
+ * - TickState: tick i32 @0, liquidity_net i128 @4, liquidity_gross u128 @20
+ *
+ * 计算用 f64（sqrt_price、流动性），与链上 Q64.64 整数结果的差异在取整级别。
+ */
+
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+use crate::deserializers::raydium_clmm::{RaydiumClmmPoolState, FEE_RATE_DENOMINATOR};
+use crate::dex_interface::{DexPool, DexError};
+
+pub const CLMM_PROGRAM_ID: &str = "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK";
+pub const TICK_ARRAY_LEN: usize = 10240;
+/// 每个 TickArray 包含的 tick 数
+pub const TICK_ARRAY_SIZE: i32 = 60;
+/// 链上允许的 sqrt_price 范围（Q64.64）
+pub const MIN_SQRT_PRICE_X64: u128 = 4_295_048_016;
+pub const MAX_SQRT_PRICE_X64: u128 = 79_226_673_521_066_979_257_578_248_091;
+
+const TICK_STATE_LEN: usize = 168;
+const TICKS_OFFSET: usize = 44;
+const Q64: f64 = (1u128 << 64) as f64;
+
+fn read_i32(data: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u128(data: &[u8], offset: usize) -> u128 {
+    u128::from_le_bytes(data[offset..offset + 16].try_into().unwrap())
+}
+
+/// 单个已初始化 tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tick {
+    pub tick: i32,
+    /// 从左向右穿过该 tick 时活跃流动性的变化
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+}
+
+/// TickArray 账户（60 个间隔 tick_spacing 的 tick，只保留已初始化的）
+#[derive(Debug, Clone)]
+pub struct TickArray {
+    pub pool_id: Pubkey,
+    pub start_tick_index: i32,
+    pub ticks: Vec<Tick>,
+}
+
+impl TickArray {
+    pub fn from_account_data(data: &[u8]) -> Result<Self, DexError> {
+        if data.len() != TICK_ARRAY_LEN {
+            return Err(DexError::InvalidData(format!(
+                "Raydium CLMM tick array must be {} bytes, got {}",
+                TICK_ARRAY_LEN,
+                data.len()
+            )));
+        }
+
+        let ticks = (0..TICK_ARRAY_SIZE as usize)
+            .map(|i| {
+                let offset = TICKS_OFFSET + i * TICK_STATE_LEN;
+                Tick {
+                    tick: read_i32(data, offset),
+                    liquidity_net: read_u128(data, offset + 4) as i128,
+                    liquidity_gross: read_u128(data, offset + 20),
+                }
+            })
+            .filter(|tick| tick.liquidity_gross > 0)
+            .collect();
+
+        Ok(Self {
+            pool_id: Pubkey::new_from_array(data[8..40].try_into().unwrap()),
+            start_tick_index: read_i32(data, 40),
+            ticks,
+        })
+    }
+}
+
+/// 一个 TickArray 覆盖的 tick 跨度
+pub fn ticks_per_array(tick_spacing: u16) -> i32 {
+    TICK_ARRAY_SIZE * tick_spacing as i32
+}
+
+/// tick 所在 TickArray 的起始 tick（向下取整）
+pub fn tick_array_start_index(tick: i32, tick_spacing: u16) -> i32 {
+    let span = ticks_per_array(tick_spacing);
+    tick.div_euclid(span) * span
+}
+
+/// TickArray PDA（seeds = ["tick_array", pool_id, start_tick_index(i32 BE)]）
+pub fn tick_array_address(pool_id: &Pubkey, start_tick_index: i32) -> Pubkey {
+    let program = Pubkey::from_str(CLMM_PROGRAM_ID).expect("valid CLMM program id");
+    Pubkey::find_program_address(
+        &[b"tick_array", pool_id.as_ref(), &start_tick_index.to_be_bytes()],
+        &program,
+    ).0
+}
+
+/// 当前 tick 所在数组及其两侧各 `radius` 个数组的起始 tick
+pub fn tick_array_starts_around(tick: i32, tick_spacing: u16, radius: i32) -> Vec<i32> {
+    let span = ticks_per_array(tick_spacing);
+    let center = tick_array_start_index(tick, tick_spacing);
+    (-radius..=radius).map(|offset| center + offset * span).collect()
+}
+
+/// tick 对应的 sqrt 价格（原始单位，非 Q64.64）：1.0001^(tick / 2)
+pub fn sqrt_price_at_tick(tick: i32) -> f64 {
+    1.0001f64.powf(tick as f64 / 2.0)
+}
+
+/// 精确输入报价结果（原始单位）
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClmmSwapQuote {
+    /// 实际消耗的输入（含手续费；触及价格限制时小于请求量）
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+    /// 成交后的 sqrt 价格（Q64.64）
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub ticks_crossed: u32,
+}
+
+/// 带 tick 流动性的 CLMM 池子报价器
+///
+/// 注册到 orderbook_cache 后，路由器的单跳计算走 `get_orderbook_quote`（跨 tick 报价），
+/// 当前 tick 所在的数组未加载时回退到 AMM 公式。交易对方向约定 base = token_0、quote = token_1。
+#[derive(Debug, Clone)]
+pub struct ClmmQuoter {
+    pub pool: RaydiumClmmPoolState,
+    /// 交易费率（1e-6 精度，2500 = 0.25%）
+    pub trade_fee_rate: u32,
+    /// 已初始化 tick -> liquidity_net
+    pub ticks: BTreeMap<i32, i128>,
+    /// 已加载的 TickArray 起始 tick
+    pub loaded_arrays: BTreeSet<i32>,
+}
+
+impl ClmmQuoter {
+    pub fn new(pool: RaydiumClmmPoolState, trade_fee_rate: u32) -> Self {
+        Self {
+            pool,
+            trade_fee_rate,
+            ticks: BTreeMap::new(),
+            loaded_arrays: BTreeSet::new(),
+        }
+    }
+
+    /// 载入一个 TickArray（覆盖该数组范围内的旧 tick）
+    pub fn load_tick_array(&mut self, array: &TickArray) {
+        let span = ticks_per_array(self.pool.tick_spacing);
+        let start = array.start_tick_index;
+        let stale: Vec<i32> = self.ticks.range(start..start + span).map(|(tick, _)| *tick).collect();
+        for tick in stale {
+            self.ticks.remove(&tick);
+        }
+        for tick in &array.ticks {
+            self.ticks.insert(tick.tick, tick.liquidity_net);
+        }
+        self.loaded_arrays.insert(start);
+    }
+
+    /// 包含 `tick` 的连续已加载区间 [lower, upper)，所在数组未加载时返回 None
+    fn loaded_range(&self, tick: i32) -> Option<(i32, i32)> {
+        let span = ticks_per_array(self.pool.tick_spacing);
+        let start = tick_array_start_index(tick, self.pool.tick_spacing);
+        if !self.loaded_arrays.contains(&start) {
+            return None;
+        }
+        let mut lower = start;
+        while self.loaded_arrays.contains(&(lower - span)) {
+            lower -= span;
+        }
+        let mut upper = start + span;
+        while self.loaded_arrays.contains(&upper) {
+            upper += span;
+        }
+        Some((lower, upper))
+    }
+
+    /// 下一个目标 tick 及其是否已初始化（已加载区间内没有已初始化 tick 时返回区间边界）
+    fn next_tick(&self, tick: i32, zero_for_one: bool) -> Option<(i32, bool)> {
+        let (lower, upper) = self.loaded_range(tick)?;
+        let next = if zero_for_one {
+            self.ticks.range(lower..=tick).next_back().map(|(t, _)| (*t, true))
+                .unwrap_or((lower, false))
+        } else {
+            self.ticks.range(tick + 1..upper).next().map(|(t, _)| (*t, true))
+                .unwrap_or((upper, false))
+        };
+        Some(next)
+    }
+
+    /// 精确输入报价（原始单位）
+    ///
+    /// `zero_for_one = true` 表示 token_0 换 token_1（价格下降）。`sqrt_price_limit_x64`
+    /// 为链上 swap 的价格限制（None = 不限制），触及限制时提前停止，`amount_in` 为实际消耗量。
+    /// 已加载的 TickArray 不足以成交时返回 None。
+    pub fn quote_exact_in(
+        &self,
+        amount_in: u64,
+        zero_for_one: bool,
+        sqrt_price_limit_x64: Option<u128>,
+    ) -> Option<ClmmSwapQuote> {
+        let current_x64 = self.pool.sqrt_price_x64;
+        let limit_x64 = sqrt_price_limit_x64.unwrap_or(if zero_for_one {
+            MIN_SQRT_PRICE_X64 + 1
+        } else {
+            MAX_SQRT_PRICE_X64 - 1
+        });
+        // 与链上一致：限制必须在当前价格的交易方向一侧
+        let limit_valid = if zero_for_one {
+            limit_x64 < current_x64 && limit_x64 > MIN_SQRT_PRICE_X64
+        } else {
+            limit_x64 > current_x64 && limit_x64 < MAX_SQRT_PRICE_X64
+        };
+        if current_x64 == 0 || !limit_valid {
+            return None;
+        }
+
+        let fee_rate = self.trade_fee_rate as f64 / FEE_RATE_DENOMINATOR;
+        let sqrt_limit = limit_x64 as f64 / Q64;
+        let mut sqrt_price = current_x64 as f64 / Q64;
+        let mut tick = self.pool.tick_current;
+        let mut liquidity = self.pool.liquidity as f64;
+        let mut remaining = amount_in as f64;
+        let mut amount_out = 0.0;
+        let mut fee_total = 0.0;
+        let mut ticks_crossed = 0;
+
+        // 剩余不足 1 个最小单位视为成交完毕
+        while remaining >= 1.0 && sqrt_price != sqrt_limit {
+            let (next_tick, initialized) = self.next_tick(tick, zero_for_one)?;
+            let sqrt_next_tick = sqrt_price_at_tick(next_tick);
+            let sqrt_target = if zero_for_one {
+                sqrt_next_tick.max(sqrt_limit)
+            } else {
+                sqrt_next_tick.min(sqrt_limit)
+            };
+
+            // swap_math::compute_swap_step（exact input）
+            let remaining_less_fee = remaining * (1.0 - fee_rate);
+            let max_in = if zero_for_one {
+                liquidity * (1.0 / sqrt_target - 1.0 / sqrt_price)
+            } else {
+                liquidity * (sqrt_target - sqrt_price)
+            };
+            let (sqrt_after, step_in, step_fee) = if remaining_less_fee >= max_in {
+                (sqrt_target, max_in, max_in * fee_rate / (1.0 - fee_rate))
+            } else {
+                let sqrt_after = if zero_for_one {
+                    liquidity * sqrt_price / (liquidity + remaining_less_fee * sqrt_price)
+                } else {
+                    sqrt_price + remaining_less_fee / liquidity
+                };
+                (sqrt_after, remaining_less_fee, remaining - remaining_less_fee)
+            };
+            amount_out += if zero_for_one {
+                liquidity * (sqrt_price - sqrt_after)
+            } else {
+                liquidity * (1.0 / sqrt_price - 1.0 / sqrt_after)
+            };
+            remaining -= step_in + step_fee;
+            fee_total += step_fee;
+            sqrt_price = sqrt_after;
+
+            if sqrt_price != sqrt_next_tick {
+                // 未到达下一个 tick：本段内成交完毕或触及价格限制
+                break;
+            }
+            if !initialized {
+                // 走到已加载区间边界，下一轮 loaded_range 会返回 None
+                tick = if zero_for_one { next_tick - 1 } else { next_tick };
+                continue;
+            }
+            let net = self.ticks[&next_tick] as f64;
+            liquidity += if zero_for_one { -net } else { net };
+            tick = if zero_for_one { next_tick - 1 } else { next_tick };
+            ticks_crossed += 1;
+        }
+
+        let consumed = amount_in as f64 - remaining.max(0.0);
+        Some(ClmmSwapQuote {
+            amount_in: consumed.ceil().min(amount_in as f64) as u64,
+            amount_out: amount_out.max(0.0).floor() as u64,
+            fee_amount: fee_total.ceil() as u64,
+            sqrt_price_x64: (sqrt_price * Q64) as u128,
+            tick_current: tick,
+            ticks_crossed,
+        })
+    }
+}
+
+impl DexPool for ClmmQuoter {
+    fn dex_name(&self) -> &'static str {
+        "Raydium CLMM"
+    }
+
+    /// 只解析池子账户（不含 tick），费率由调用方按 AmmConfig 设置
+    fn from_account_data(data: &[u8]) -> Result<Self, DexError>
+    where
+        Self: Sized,
+    {
+        Ok(Self::new(RaydiumClmmPoolState::from_account_data_manual(data)?, 0))
+    }
+
+    fn calculate_price(&self) -> f64 {
+        self.pool.calculate_price()
+    }
+
+    fn get_reserves(&self) -> (u64, u64) {
+        DexPool::get_reserves(&self.pool)
+    }
+
+    fn get_decimals(&self) -> (u8, u8) {
+        (self.pool.mint_decimals_0, self.pool.mint_decimals_1)
+    }
+
+    fn is_active(&self) -> bool {
+        self.pool.is_active()
+    }
+
+    fn get_mints(&self) -> Option<(Pubkey, Pubkey)> {
+        Some((self.pool.token_mint_0, self.pool.token_mint_1))
+    }
+
+    fn get_fee_rate(&self) -> Option<f64> {
+        Some(self.trade_fee_rate as f64 / FEE_RATE_DENOMINATOR)
+    }
+
+    fn has_orderbook(&self) -> bool {
+        self.loaded_range(self.pool.tick_current).is_some()
+    }
+
+    fn get_orderbook_quote(&self, amount_in: f64, is_buy: bool) -> Option<f64> {
+        // 买入 base（token_0）= 花费 token_1，即 zero_for_one = false
+        let (in_decimals, out_decimals) = if is_buy {
+            (self.pool.mint_decimals_1, self.pool.mint_decimals_0)
+        } else {
+            (self.pool.mint_decimals_0, self.pool.mint_decimals_1)
+        };
+        let amount_in_raw = (amount_in * 10f64.powi(in_decimals as i32)).floor() as u64;
+        let quote = self.quote_exact_in(amount_in_raw, !is_buy, None)?;
+        Some(quote.amount_out as f64 / 10f64.powi(out_decimals as i32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 仓库里抓取的 "SOL/USDC" CLMM 账户实际是 RAY/USDC（6/6 decimals，tick 6170，tick_spacing 60）
+    fn captured_pool() -> RaydiumClmmPoolState {
+        let data = std::fs::read("analysis-results/raydium_clmm-SOL-USDC-(Raydium-CLMM).bin").unwrap();
+        RaydiumClmmPoolState::from_account_data_manual(&data).unwrap()
+    }
+
+    /// 按链上布局构造 TickArray 账户
+    fn tick_array_bytes(pool_id: &Pubkey, start: i32, ticks: &[(i32, i128)]) -> Vec<u8> {
+        let mut data = vec![0u8; TICK_ARRAY_LEN];
+        data[8..40].copy_from_slice(pool_id.as_ref());
+        data[40..44].copy_from_slice(&start.to_le_bytes());
+        for (tick, net) in ticks {
+            let slot = ((tick - start) / 60) as usize;
+            let offset = TICKS_OFFSET + slot * TICK_STATE_LEN;
+            data[offset..offset + 4].copy_from_slice(&tick.to_le_bytes());
+            data[offset + 4..offset + 20].copy_from_slice(&net.to_le_bytes());
+            data[offset + 20..offset + 36].copy_from_slice(&net.unsigned_abs().to_le_bytes());
+        }
+        data
+    }
+
+    /// 三段仓位，当前 tick 处合计等于池子的活跃流动性
+    fn positions(liquidity: u128) -> Vec<(i32, i32, u128)> {
+        vec![
+            (0, 7200, liquidity / 2),
+            (5400, 6600, liquidity * 3 / 10),
+            (6120, 6240, liquidity - liquidity / 2 - liquidity * 3 / 10),
+        ]
+    }
+
+    /// 捕获的池子 + 覆盖当前 tick 两侧的三个 TickArray（0 / 3600 / 7200）
+    fn loaded_quoter(trade_fee_rate: u32) -> ClmmQuoter {
+        let pool = captured_pool();
+        let pool_id = Pubkey::new_unique();
+        let mut nets: BTreeMap<i32, i128> = BTreeMap::new();
+        for (lower, upper, liquidity) in positions(pool.liquidity) {
+            *nets.entry(lower).or_default() += liquidity as i128;
+            *nets.entry(upper).or_default() -= liquidity as i128;
+        }
+
+        let mut quoter = ClmmQuoter::new(pool.clone(), trade_fee_rate);
+        for start in tick_array_starts_around(pool.tick_current, pool.tick_spacing, 1) {
+            let ticks: Vec<(i32, i128)> = nets.range(start..start + 3600).map(|(t, n)| (*t, *n)).collect();
+            let array = TickArray::from_account_data(&tick_array_bytes(&pool_id, start, &ticks)).unwrap();
+            assert_eq!(array.pool_id, pool_id);
+            quoter.load_tick_array(&array);
+        }
+        quoter
+    }
+
+    /// 独立参考：直接按仓位区间逐段计算（不经过 TickArray 与 quoter）
+    fn reference_quote(pool: &RaydiumClmmPoolState, amount_in: f64, zero_for_one: bool, fee_rate: f64) -> f64 {
+        let ranges = positions(pool.liquidity);
+        let mut bounds: Vec<i32> = ranges.iter().flat_map(|(l, u, _)| [*l, *u]).collect();
+        bounds.sort();
+        bounds.dedup();
+        let active = |tick: i32| -> f64 {
+            ranges.iter().filter(|(l, u, _)| *l <= tick && tick < *u).map(|(_, _, liq)| *liq as f64).sum()
+        };
+
+        let mut sqrt_price = pool.sqrt_price_x64 as f64 / Q64;
+        let mut left = amount_in * (1.0 - fee_rate);
+        let mut out = 0.0;
+        let mut tick = pool.tick_current;
+        loop {
+            let liquidity = active(tick);
+            let boundary = if zero_for_one {
+                *bounds.iter().rev().find(|b| **b <= tick).unwrap()
+            } else {
+                *bounds.iter().find(|b| **b > tick).unwrap()
+            };
+            let sqrt_boundary = 1.0001f64.powf(boundary as f64 / 2.0);
+            if zero_for_one {
+                let need = liquidity * (1.0 / sqrt_boundary - 1.0 / sqrt_price);
+                if left < need {
+                    let sqrt_after = 1.0 / (1.0 / sqrt_price + left / liquidity);
+                    return out + liquidity * (sqrt_price - sqrt_after);
+                }
+                out += liquidity * (sqrt_price - sqrt_boundary);
+                left -= need;
+                tick = boundary - 1;
+            } else {
+                let need = liquidity * (sqrt_boundary - sqrt_price);
+                if left < need {
+                    let sqrt_after = sqrt_price + left / liquidity;
+                    return out + liquidity * (1.0 / sqrt_price - 1.0 / sqrt_after);
+                }
+                out += liquidity * (1.0 / sqrt_price - 1.0 / sqrt_boundary);
+                left -= need;
+                tick = boundary;
+            }
+            sqrt_price = sqrt_boundary;
+        }
+    }
+
+    #[test]
+    fn test_tick_array_addressing() {
+        assert_eq!(ticks_per_array(60), 3600);
+        assert_eq!(tick_array_start_index(6170, 60), 3600);
+        assert_eq!(tick_array_start_index(-1, 60), -3600);
+        assert_eq!(tick_array_start_index(-3600, 60), -3600);
+        assert_eq!(tick_array_starts_around(6170, 60, 1), vec![0, 3600, 7200]);
+
+        let pool_id = Pubkey::new_unique();
+        assert_ne!(tick_array_address(&pool_id, 0), tick_array_address(&pool_id, 3600));
+        assert_eq!(tick_array_address(&pool_id, -3600), tick_array_address(&pool_id, -3600));
+    }
+
+    #[test]
+    fn test_quote_matches_per_range_reference() {
+        // 没有抓取到链上 TickArray，按与池子活跃流动性一致的仓位构造，参考值逐段独立计算。
+        // 金额取 1 SOL / 100 SOL 的等值（≈$185 / $18.5k）：100 / 10,000 RAY 卖出，185 / 1,850 USDC 买入
+        let quoter = loaded_quoter(2500);
+        let fee = 0.0025;
+
+        for (amount_ui, zero_for_one, min_crossed) in [
+            (100.0, true, 0),
+            (10_000.0, true, 2),
+            (185.0, false, 0),
+            (1_850.0, false, 1),
+        ] {
+            let raw = (amount_ui * 1e6) as u64;
+            let quote = quoter.quote_exact_in(raw, zero_for_one, None).unwrap();
+            let expected = reference_quote(&quoter.pool, raw as f64, zero_for_one, fee);
+            let deviation = (quote.amount_out as f64 - expected).abs() / expected;
+            assert!(deviation < 0.001, "{} (zero_for_one={}): {} vs reference {:.0}", amount_ui, zero_for_one, quote.amount_out, expected);
+            assert!(quote.ticks_crossed >= min_crossed, "{:?}", quote);
+            assert_eq!(quote.amount_in, raw);
+            assert!((quote.fee_amount as f64 - raw as f64 * fee).abs() <= 2.0, "{:?}", quote);
+        }
+
+        // 路由器接口（UI 单位）：10,000 RAY 穿过 6120 和 5400 两个 tick，均价明显低于现价
+        let out = quoter.get_orderbook_quote(10_000.0, false).unwrap();
+        let spot = quoter.calculate_price();
+        assert!(out < 10_000.0 * spot * 0.95, "{} vs spot {}", out, spot);
+        assert!(quoter.has_orderbook());
+    }
+
+    #[test]
+    fn test_price_limit_and_missing_tick_arrays() {
+        let quoter = loaded_quoter(2500);
+        let current = quoter.pool.sqrt_price_x64;
+
+        // 价格限制设在 tick 6150（6120 之前）：在限制处停止，只消耗部分输入
+        let limit = (sqrt_price_at_tick(6150) * Q64) as u128;
+        let quote = quoter.quote_exact_in(10_000_000_000, true, Some(limit)).unwrap();
+        assert!(quote.amount_in < 10_000_000_000);
+        assert!(quote.sqrt_price_x64.abs_diff(limit) <= limit / 1_000_000_000);
+        assert_eq!(quote.ticks_crossed, 0);
+
+        // 限制在错误一侧 → 链上拒绝
+        assert!(quoter.quote_exact_in(1_000_000, true, Some(current + 1)).is_none());
+
+        // 买入超过 7200 以上已无流动性，走到已加载区间边界后无法成交
+        assert!(quoter.quote_exact_in(50_000_000_000, false, None).is_none());
+
+        // 当前 tick 所在数组未加载 → 不参与按 tick 报价
+        let empty = ClmmQuoter::new(captured_pool(), 2500);
+        assert!(!empty.has_orderbook());
+        assert!(empty.quote_exact_in(1_000_000, true, None).is_none());
+    }
+}
@@ -1,6 +1,10 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_sdk::pubkey::Pubkey;
-use crate::dex_interface::{DexPool, DexError};
+use crate::dex_interface::{ActivityStatus, DexPool, DexError};
+use crate::mint_decimals_cache;
+
+/// mint 元数据未缓存时的 decimals 猜测（USDC/SOL）
+const GUESSED_DECIMALS: (u8, u8) = (6, 9);
 
 /// GoonFi Pool State
 /// 
@@ -36,6 +40,18 @@ pub struct GoonFiPoolState {
 }
 
 impl GoonFiPoolState {
+    /// Token (A, B) mints
+    ///
+    /// USDC/SOL fixture 中 pubkey_9 = SOL、pubkey_10 = USDC；按储备顺序（A = USDC）返回
+    pub fn token_mints(&self) -> (Pubkey, Pubkey) {
+        (self.pubkey_10, self.pubkey_9)
+    }
+    
+    /// (decimals, 是否仍含猜测值)
+    fn resolved_decimals(&self) -> ((u8, u8), bool) {
+        mint_decimals_cache::resolve_decimals("GoonFi", Some(self.token_mints()), GUESSED_DECIMALS)
+    }
+    
     /// Get reserve A
     /// ⚠️ WARNING: GoonFi pools DO NOT store reserves in pool account  
     /// Real reserves must be read from token vault accounts
@@ -76,12 +92,11 @@ impl GoonFiPoolState {
         reserve_b as f64 / reserve_a as f64
     }
     
-    /// Get formatted reserves (assuming 6 and 9 decimals for USDC/SOL)
+    /// Get formatted reserves (decimals resolved from the mints, USDC/SOL guess until cached)
     pub fn get_reserves_formatted(&self) -> (f64, f64) {
-        // GoonFi typically has USDC/SOL pairs
-        // USDC = 6 decimals, SOL = 9 decimals
-        let reserve_a = self.get_reserve_a() as f64 / 10_f64.powi(6);
-        let reserve_b = self.get_reserve_b() as f64 / 10_f64.powi(9);
+        let (decimals_a, decimals_b) = self.resolved_decimals().0;
+        let reserve_a = self.get_reserve_a() as f64 / 10_f64.powi(decimals_a as i32);
+        let reserve_b = self.get_reserve_b() as f64 / 10_f64.powi(decimals_b as i32);
         (reserve_a, reserve_b)
     }
 }
@@ -119,8 +134,15 @@ impl DexPool for GoonFiPoolState {
     }
     
     fn get_decimals(&self) -> (u8, u8) {
-        // GoonFi typically USDC/SOL
-        (6, 9)
+        self.resolved_decimals().0
+    }
+    
+    fn get_mints(&self) -> Option<(Pubkey, Pubkey)> {
+        Some(self.token_mints())
+    }
+    
+    fn decimals_guessed(&self) -> bool {
+        self.resolved_decimals().1
     }
     
     fn is_active(&self) -> bool {
@@ -131,6 +153,15 @@ impl DexPool for GoonFiPoolState {
         self.pubkey_5 != Pubkey::default()
     }
     
+    fn activity_status(&self) -> ActivityStatus {
+        // 储备量只在 vault 中：池子账户本身最多是 AwaitingVaultData，收到 vault 余额后才是 Active
+        if self.is_active() {
+            ActivityStatus::AwaitingVaultData
+        } else {
+            ActivityStatus::Unknown { reason: "vault addresses not set".to_string() }
+        }
+    }
+    
     fn get_additional_info(&self) -> Option<String> {
         let (res_a, res_b) = self.get_reserves_formatted();
         Some(format!(
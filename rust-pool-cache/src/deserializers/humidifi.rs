@@ -1,6 +1,10 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_sdk::pubkey::Pubkey;
-use crate::dex_interface::{DexPool, DexError};
+use crate::dex_interface::{ActivityStatus, DexPool, DexError};
+use crate::mint_decimals_cache;
+
+/// mint 元数据未知时的 decimals 猜测（稳定币对）
+const GUESSED_DECIMALS: (u8, u8) = (6, 6);
 
 /// HumidiFi Pool State
 /// 
@@ -29,8 +33,8 @@ pub struct HumidiFiPoolState {
     
     /// Pubkey fields (25 Pubkeys = 800 bytes)
     pub pubkey_1: Pubkey,
-    pub pubkey_2: Pubkey,  // Likely token A mint
-    pub pubkey_3: Pubkey,  // Likely token B mint
+    pub pubkey_2: Pubkey,  // 不是 token mint（USDC/USDT 池子的 fixture 中不含两个 mint）
+    pub pubkey_3: Pubkey,
     pub pubkey_4: Pubkey,  // Likely token A vault
     pub pubkey_5: Pubkey,  // Likely token B vault
     pub pubkey_6: Pubkey,  // Likely LP mint
@@ -69,6 +73,18 @@ impl HumidiFiPoolState {
         &self.pubkey_5
     }
     
+    /// Token (A, B) mints, learned from the vault token accounts
+    ///
+    /// 池子账户本身不存 mint：VaultReader 收到 vault 数据后登记 vault → mint，之前为 None
+    pub fn token_mints(&self) -> Option<(Pubkey, Pubkey)> {
+        mint_decimals_cache::vault_mints(self.token_a_vault(), self.token_b_vault())
+    }
+    
+    /// (decimals, 是否仍含猜测值)
+    fn resolved_decimals(&self) -> ((u8, u8), bool) {
+        mint_decimals_cache::resolve_decimals("HumidiFi", self.token_mints(), GUESSED_DECIMALS)
+    }
+    
     /// Get reserve A amount
     /// ⚠️ WARNING: HumidiFi pools DO NOT store reserves in pool account
     /// Real reserves must be read from token vault accounts
@@ -103,9 +119,9 @@ impl HumidiFiPoolState {
     
     /// Get formatted reserves
     pub fn get_reserves_formatted(&self) -> (f64, f64) {
-        const DECIMALS: i32 = 6;
-        let reserve_a = self.get_reserve_a() as f64 / 10_f64.powi(DECIMALS);
-        let reserve_b = self.get_reserve_b() as f64 / 10_f64.powi(DECIMALS);
+        let (decimals_a, decimals_b) = self.resolved_decimals().0;
+        let reserve_a = self.get_reserve_a() as f64 / 10_f64.powi(decimals_a as i32);
+        let reserve_b = self.get_reserve_b() as f64 / 10_f64.powi(decimals_b as i32);
         (reserve_a, reserve_b)
     }
 }
@@ -143,7 +159,15 @@ impl DexPool for HumidiFiPoolState {
     }
     
     fn get_decimals(&self) -> (u8, u8) {
-        (6, 6)
+        self.resolved_decimals().0
+    }
+    
+    fn get_mints(&self) -> Option<(Pubkey, Pubkey)> {
+        self.token_mints()
+    }
+    
+    fn decimals_guessed(&self) -> bool {
+        self.resolved_decimals().1
     }
     
     fn is_active(&self) -> bool {
@@ -153,6 +177,15 @@ impl DexPool for HumidiFiPoolState {
         self.token_b_vault() != &Pubkey::default()
     }
     
+    fn activity_status(&self) -> ActivityStatus {
+        // 储备量只在 vault 中：池子账户本身最多是 AwaitingVaultData，收到 vault 余额后才是 Active
+        if self.is_active() {
+            ActivityStatus::AwaitingVaultData
+        } else {
+            ActivityStatus::Unknown { reason: "vault addresses not set".to_string() }
+        }
+    }
+    
     fn get_additional_info(&self) -> Option<String> {
         Some(format!(
             "Vault Reading Mode - Header[0]={}, Header[1]={}",
@@ -177,6 +210,37 @@ mod tests {
         let expected = 8 * 5 + 32 * 25 + 8 * 111;
         assert_eq!(expected, 1728);
     }
+    
+    #[test]
+    fn test_decimals_resolved_through_vault_mints() {
+        use crate::deserializers::spl_token::TokenProgram;
+        use crate::mint_decimals_cache::{get_global_mint_cache, init_global_mint_cache, register_vault_mint, MintInfo};
+        use crate::rpc_manager::RpcManager;
+        
+        let mut data = vec![0u8; 1728];
+        let (vault_a, vault_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        data[40 + 32 * 3..40 + 32 * 4].copy_from_slice(vault_a.as_ref());
+        data[40 + 32 * 4..40 + 32 * 5].copy_from_slice(vault_b.as_ref());
+        let pool = HumidiFiPoolState::from_account_data(&data).unwrap();
+        
+        init_global_mint_cache(std::sync::Arc::new(RpcManager::unlimited("http://127.0.0.1:8899")).handle("mint_cache"));
+        // vault 数据到达前不知道 mint，按稳定币猜测
+        assert_eq!(pool.get_mints(), None);
+        assert!(pool.decimals_guessed());
+        assert_eq!(pool.get_decimals(), (6, 6));
+        
+        // SOL 侧 9 位小数：猜测的 6 位会让 SOL 价格偏 1000 倍
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        register_vault_mint(vault_a, mint_a);
+        register_vault_mint(vault_b, mint_b);
+        assert_eq!(pool.get_mints(), Some((mint_a, mint_b)));
+        let cache = get_global_mint_cache().unwrap();
+        cache.insert_info(mint_a, MintInfo { decimals: 9, program: TokenProgram::SplToken, transfer_fee: None });
+        assert!(pool.decimals_guessed());
+        cache.insert_info(mint_b, MintInfo { decimals: 6, program: TokenProgram::SplToken, transfer_fee: None });
+        assert!(!pool.decimals_guessed());
+        assert_eq!(pool.get_decimals(), (9, 6));
+    }
 }
 
 
@@ -281,6 +281,10 @@ impl DexPool for MeteoraPoolState {
     fn get_additional_info(&self) -> Option<String> {
         Some(self.get_pool_info())
     }
+    
+    fn get_mints(&self) -> Option<(Pubkey, Pubkey)> {
+        Some((self.token_x_mint, self.token_y_mint))
+    }
 }
 
 #[cfg(test)]
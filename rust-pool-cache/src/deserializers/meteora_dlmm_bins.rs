@@ -0,0 +1,462 @@
+/*!
+ * Meteora DLMM bin 报价
+ *
+ * DLMM 的流动性集中在活跃 bin 附近的离散 bin 里，每个 bin 内是固定价格（恒定和）。
+ * 用 vault 余额当常数乘积储备量会严重高估滑点。这里解析 lb_pair 的报价参数和
+ * 相邻的 BinArray 账户，按 bin 逐个吃单并计算可变手续费（与链上 swap 逻辑一致）。
+ *
+ * 账户布局（Anchor，含 8 字节判别符）:
+ * - LbPair 904 bytes: StaticParameters @8, VariableParameters @40, active_id @76, bin_step @80,
+ *   token_x_mint @88, token_y_mint @120
+ * - BinArray 10136 bytes: index @8, lb_pair @24, 70 × Bin(144 bytes) @56
+ */
+
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use crate::dex_interface::{DexPool, DexError};
+
+pub const DLMM_PROGRAM_ID: &str = "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo";
+pub const LB_PAIR_LEN: usize = 904;
+pub const BIN_ARRAY_LEN: usize = 10136;
+/// 每个 BinArray 包含的 bin 数
+pub const MAX_BIN_PER_ARRAY: i32 = 70;
+
+const BIN_LEN: usize = 144;
+const BINS_OFFSET: usize = 56;
+const BASIS_POINT_MAX: u128 = 10_000;
+/// 手续费精度（1e9 = 100%）
+const FEE_PRECISION: u128 = 1_000_000_000;
+/// 手续费上限 10%
+const MAX_FEE_RATE: u128 = 100_000_000;
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap())
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_i32(data: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    Pubkey::new_from_array(data[offset..offset + 32].try_into().unwrap())
+}
+
+/// lb_pair 中报价需要的参数（静态 + 可变手续费参数）
+#[derive(Debug, Clone, PartialEq)]
+pub struct LbPairQuoteParams {
+    pub base_factor: u16,
+    pub filter_period: u16,
+    pub decay_period: u16,
+    pub reduction_factor: u16,
+    pub variable_fee_control: u32,
+    pub max_volatility_accumulator: u32,
+    pub base_fee_power_factor: u8,
+    pub volatility_accumulator: u32,
+    pub volatility_reference: u32,
+    pub index_reference: i32,
+    pub last_update_timestamp: i64,
+    pub active_id: i32,
+    pub bin_step: u16,
+    pub status: u8,
+    pub token_x_mint: Pubkey,
+    pub token_y_mint: Pubkey,
+}
+
+impl LbPairQuoteParams {
+    pub fn from_account_data(data: &[u8]) -> Result<Self, DexError> {
+        if data.len() != LB_PAIR_LEN {
+            return Err(DexError::InvalidData(format!(
+                "Meteora DLMM lb_pair must be {} bytes, got {}",
+                LB_PAIR_LEN,
+                data.len()
+            )));
+        }
+
+        Ok(Self {
+            base_factor: read_u16(data, 8),
+            filter_period: read_u16(data, 10),
+            decay_period: read_u16(data, 12),
+            reduction_factor: read_u16(data, 14),
+            variable_fee_control: read_u32(data, 16),
+            max_volatility_accumulator: read_u32(data, 20),
+            base_fee_power_factor: data[34],
+            volatility_accumulator: read_u32(data, 40),
+            volatility_reference: read_u32(data, 44),
+            index_reference: read_i32(data, 48),
+            last_update_timestamp: read_u64(data, 56) as i64,
+            active_id: read_i32(data, 76),
+            bin_step: read_u16(data, 80),
+            status: data[82],
+            token_x_mint: read_pubkey(data, 88),
+            token_y_mint: read_pubkey(data, 120),
+        })
+    }
+
+    /// 基础手续费率（FEE_PRECISION 精度）
+    pub fn base_fee_rate(&self) -> u128 {
+        self.base_factor as u128 * self.bin_step as u128 * 10 * 10u128.pow(self.base_fee_power_factor as u32)
+    }
+
+    /// 给定波动累积量下的可变手续费率（FEE_PRECISION 精度，向上取整）
+    pub fn variable_fee_rate(&self, volatility_accumulator: u32) -> u128 {
+        if self.variable_fee_control == 0 {
+            return 0;
+        }
+        let square_vfa_bin = (volatility_accumulator as u128 * self.bin_step as u128).pow(2);
+        let v_fee = self.variable_fee_control as u128 * square_vfa_bin;
+        v_fee.div_ceil(100_000_000_000)
+    }
+
+    pub fn total_fee_rate(&self, volatility_accumulator: u32) -> u128 {
+        (self.base_fee_rate() + self.variable_fee_rate(volatility_accumulator)).min(MAX_FEE_RATE)
+    }
+}
+
+/// 单个 bin 的流动性
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bin {
+    pub amount_x: u64,
+    pub amount_y: u64,
+    /// y/x 价格（原始单位，Q64.64）
+    pub price: u128,
+}
+
+/// BinArray 账户（70 个连续 bin）
+#[derive(Debug, Clone)]
+pub struct BinArray {
+    pub index: i64,
+    pub lb_pair: Pubkey,
+    pub bins: Vec<Bin>,
+}
+
+impl BinArray {
+    pub fn from_account_data(data: &[u8]) -> Result<Self, DexError> {
+        if data.len() != BIN_ARRAY_LEN {
+            return Err(DexError::InvalidData(format!(
+                "Meteora DLMM bin array must be {} bytes, got {}",
+                BIN_ARRAY_LEN,
+                data.len()
+            )));
+        }
+
+        let bins = (0..MAX_BIN_PER_ARRAY as usize)
+            .map(|i| {
+                let offset = BINS_OFFSET + i * BIN_LEN;
+                Bin {
+                    amount_x: read_u64(data, offset),
+                    amount_y: read_u64(data, offset + 8),
+                    price: u128::from_le_bytes(data[offset + 16..offset + 32].try_into().unwrap()),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            index: read_u64(data, 8) as i64,
+            lb_pair: read_pubkey(data, 24),
+            bins,
+        })
+    }
+
+    /// 数组中第一个 bin 的 id
+    pub fn lower_bin_id(&self) -> i32 {
+        self.index as i32 * MAX_BIN_PER_ARRAY
+    }
+}
+
+/// bin 所在的 BinArray 索引（向下取整）
+pub fn bin_array_index(bin_id: i32) -> i64 {
+    bin_id.div_euclid(MAX_BIN_PER_ARRAY) as i64
+}
+
+/// BinArray PDA（seeds = ["bin_array", lb_pair, index(i64 LE)]）
+pub fn bin_array_address(lb_pair: &Pubkey, index: i64) -> Pubkey {
+    let program = Pubkey::from_str(DLMM_PROGRAM_ID).expect("valid DLMM program id");
+    Pubkey::find_program_address(&[b"bin_array", lb_pair.as_ref(), &index.to_le_bytes()], &program).0
+}
+
+/// 活跃 bin 所在数组及其两侧各 `radius` 个数组的索引
+pub fn bin_array_indexes_around(active_id: i32, radius: i64) -> Vec<i64> {
+    let center = bin_array_index(active_id);
+    (center - radius..=center + radius).collect()
+}
+
+/// bin 价格（Q64.64）：(1 + bin_step / 10000) ^ bin_id
+pub fn price_from_id(bin_id: i32, bin_step: u16) -> u128 {
+    let price = (1.0 + bin_step as f64 / BASIS_POINT_MAX as f64).powi(bin_id);
+    (price * (1u128 << 64) as f64) as u128
+}
+
+/// (amount × price) >> 64，u64 × Q64.64 不经过 256 位中间值
+fn mul_shr_64(amount: u64, price: u128, round_up: bool) -> u128 {
+    let hi = price >> 64;
+    let lo = price & u64::MAX as u128;
+    let lo_product = amount as u128 * lo;
+    let mut result = amount as u128 * hi + (lo_product >> 64);
+    if round_up && lo_product & u64::MAX as u128 != 0 {
+        result += 1;
+    }
+    result
+}
+
+/// (amount << 64) / price
+fn shl_div_64(amount: u64, price: u128, round_up: bool) -> u128 {
+    if price == 0 {
+        return 0;
+    }
+    let numerator = (amount as u128) << 64;
+    if round_up {
+        numerator.div_ceil(price)
+    } else {
+        numerator / price
+    }
+}
+
+/// 带 bin 流动性的 DLMM 池子报价器
+///
+/// 注册到 orderbook_cache 后，路由器的单跳计算走 `get_orderbook_quote`（按 bin 吃单），
+/// 未加载 bin 时回退到 AMM 公式。交易对方向约定 base = token_x、quote = token_y。
+#[derive(Debug, Clone)]
+pub struct DlmmQuoter {
+    pub params: LbPairQuoteParams,
+    pub bins: BTreeMap<i32, Bin>,
+    pub decimals_x: u8,
+    pub decimals_y: u8,
+}
+
+impl DlmmQuoter {
+    pub fn new(params: LbPairQuoteParams, decimals_x: u8, decimals_y: u8) -> Self {
+        Self {
+            params,
+            bins: BTreeMap::new(),
+            decimals_x,
+            decimals_y,
+        }
+    }
+
+    /// 载入一个 BinArray 的全部 bin（覆盖旧值）
+    pub fn load_bin_array(&mut self, array: &BinArray) {
+        let lower = array.lower_bin_id();
+        for (offset, bin) in array.bins.iter().enumerate() {
+            self.bins.insert(lower + offset as i32, *bin);
+        }
+    }
+
+    /// 精确输入报价（原始单位）
+    ///
+    /// `swap_for_y = true` 表示 x 换 y（活跃 bin 向下移动）。从活跃 bin 开始逐个吃掉
+    /// 输出侧的流动性，每个 bin 按当时的波动累积量计算手续费。已加载的 bin 不足以
+    /// 成交全部输入时返回 None。
+    pub fn quote_exact_in(&self, amount_in: u64, swap_for_y: bool, now_ts: i64) -> Option<u64> {
+        if amount_in == 0 {
+            return Some(0);
+        }
+
+        // update_references：距上次更新超过 filter_period 时重置参考点
+        let params = &self.params;
+        let mut index_reference = params.index_reference;
+        let mut volatility_reference = params.volatility_reference;
+        let elapsed = now_ts.saturating_sub(params.last_update_timestamp);
+        if elapsed >= params.filter_period as i64 {
+            index_reference = params.active_id;
+            volatility_reference = if elapsed < params.decay_period as i64 {
+                (params.volatility_accumulator as u128 * params.reduction_factor as u128 / BASIS_POINT_MAX) as u32
+            } else {
+                0
+            };
+        }
+
+        let mut amount_left = amount_in as u128;
+        let mut amount_out: u128 = 0;
+        let mut active_id = params.active_id;
+
+        while amount_left > 0 {
+            let bin = self.bins.get(&active_id)?;
+
+            // update_volatility_accumulator
+            let delta_id = (index_reference as i64 - active_id as i64).unsigned_abs() as u128;
+            let volatility_accumulator = (volatility_reference as u128 + delta_id * BASIS_POINT_MAX)
+                .min(params.max_volatility_accumulator as u128) as u32;
+            let fee_rate = params.total_fee_rate(volatility_accumulator);
+
+            let max_out = if swap_for_y { bin.amount_y } else { bin.amount_x };
+            if max_out > 0 {
+                let price = if bin.price > 0 { bin.price } else { price_from_id(active_id, params.bin_step) };
+                let max_in = if swap_for_y {
+                    shl_div_64(max_out, price, true)
+                } else {
+                    mul_shr_64(max_out, price, true)
+                };
+                let max_fee = (max_in * fee_rate).div_ceil(FEE_PRECISION - fee_rate);
+                let max_in_with_fee = max_in + max_fee;
+
+                if amount_left >= max_in_with_fee {
+                    amount_left -= max_in_with_fee;
+                    amount_out += max_out as u128;
+                } else {
+                    let fee = (amount_left * fee_rate).div_ceil(FEE_PRECISION);
+                    let in_after_fee = u64::try_from(amount_left - fee).ok()?;
+                    let out = if swap_for_y {
+                        mul_shr_64(in_after_fee, price, false)
+                    } else {
+                        shl_div_64(in_after_fee, price, false)
+                    };
+                    amount_out += out.min(max_out as u128);
+                    amount_left = 0;
+                }
+            }
+
+            if amount_left > 0 {
+                active_id = if swap_for_y { active_id - 1 } else { active_id + 1 };
+            }
+        }
+
+        u64::try_from(amount_out).ok()
+    }
+
+    /// 已加载 bin 的 (x, y) 流动性合计
+    pub fn loaded_liquidity(&self) -> (u64, u64) {
+        self.bins.values().fold((0u64, 0u64), |(x, y), bin| {
+            (x.saturating_add(bin.amount_x), y.saturating_add(bin.amount_y))
+        })
+    }
+}
+
+impl DexPool for DlmmQuoter {
+    fn dex_name(&self) -> &'static str {
+        "Meteora DLMM"
+    }
+
+    /// 只解析 lb_pair（不含 bin），decimals 由调用方按 mint 设置
+    fn from_account_data(data: &[u8]) -> Result<Self, DexError>
+    where
+        Self: Sized,
+    {
+        Ok(Self::new(LbPairQuoteParams::from_account_data(data)?, 9, 6))
+    }
+
+    fn calculate_price(&self) -> f64 {
+        let raw = price_from_id(self.params.active_id, self.params.bin_step) as f64 / (1u128 << 64) as f64;
+        raw * 10f64.powi(self.decimals_x as i32 - self.decimals_y as i32)
+    }
+
+    fn get_reserves(&self) -> (u64, u64) {
+        self.loaded_liquidity()
+    }
+
+    fn get_decimals(&self) -> (u8, u8) {
+        (self.decimals_x, self.decimals_y)
+    }
+
+    fn is_active(&self) -> bool {
+        !self.bins.is_empty()
+    }
+
+    fn get_mints(&self) -> Option<(Pubkey, Pubkey)> {
+        Some((self.params.token_x_mint, self.params.token_y_mint))
+    }
+
+    fn get_fee_rate(&self) -> Option<f64> {
+        Some(self.params.total_fee_rate(self.params.volatility_accumulator) as f64 / FEE_PRECISION as f64)
+    }
+
+    fn has_orderbook(&self) -> bool {
+        !self.bins.is_empty()
+    }
+
+    fn get_orderbook_quote(&self, amount_in: f64, is_buy: bool) -> Option<f64> {
+        // 买入 base（x）= 花费 y，即 swap_for_y = false
+        let (in_decimals, out_decimals) = if is_buy {
+            (self.decimals_y, self.decimals_x)
+        } else {
+            (self.decimals_x, self.decimals_y)
+        };
+        let amount_in_raw = (amount_in * 10f64.powi(in_decimals as i32)).floor() as u64;
+        let now_ts = chrono::Utc::now().timestamp();
+        let out = self.quote_exact_in(amount_in_raw, !is_buy, now_ts)?;
+        Some(out as f64 / 10f64.powi(out_decimals as i32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex_interface::amm_calculator::calculate_amm_output_f64;
+
+    #[test]
+    fn test_lb_pair_params_from_captured_account() {
+        // account_data 里的 "SOL-USDC" DLMM 实际是 USDC/USDT 1bps 池
+        let data = std::fs::read("account_data/SOL-USDC-Meteora-DLMM_904.bin").unwrap();
+        let params = LbPairQuoteParams::from_account_data(&data).unwrap();
+
+        assert_eq!(params.bin_step, 1);
+        assert_eq!(params.active_id, 1);
+        assert_eq!(params.base_factor, 10_000);
+        assert_eq!(params.variable_fee_control, 2_000_000);
+        assert_eq!(params.token_x_mint.to_string(), "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+        // base_factor 10000 × bin_step 1 × 10 = 1e5 / 1e9 = 0.01%
+        assert_eq!(params.base_fee_rate(), 100_000);
+    }
+
+    #[test]
+    fn test_bin_walk_vs_constant_product() {
+        // SOL/USDC，bin_step 10（0.1% 一档），活跃 bin 价格 ≈ 185 USDC
+        let data = std::fs::read("account_data/SOL-USDC-Meteora-DLMM_904.bin").unwrap();
+        let mut params = LbPairQuoteParams::from_account_data(&data).unwrap();
+        params.bin_step = 10;
+        params.active_id = -1688;
+        params.variable_fee_control = 0; // 只测基础费率 0.1%
+        let fee = params.base_fee_rate() as f64 / FEE_PRECISION as f64;
+        let mut quoter = DlmmQuoter::new(params.clone(), 9, 6);
+
+        // 活跃 bin 及以下每个 bin 500 USDC，以上每个 bin 放等值 SOL（储备量比 = 活跃价格）
+        let active_price_raw = price_from_id(params.active_id, params.bin_step) as f64 / (1u128 << 64) as f64;
+        for id in params.active_id - 100..=params.active_id + 100 {
+            let price = price_from_id(id, params.bin_step);
+            let bin = if id <= params.active_id {
+                Bin { amount_x: 0, amount_y: 500_000_000, price }
+            } else {
+                Bin { amount_x: (500_000_000.0 * 101.0 / 100.0 / active_price_raw) as u64, amount_y: 0, price }
+            };
+            quoter.bins.insert(id, bin);
+        }
+
+        // 卖出 10 SOL
+        let out = quoter.get_orderbook_quote(10.0, false).unwrap();
+
+        // 逐 bin 的独立计算：整 bin 成交需要 500 / p / (1 - fee) SOL
+        let mut remaining = 10.0;
+        let mut expected = 0.0;
+        let mut id = params.active_id;
+        loop {
+            let p = price_from_id(id, params.bin_step) as f64 / (1u128 << 64) as f64 * 1e3;
+            let full = 500.0 / p / (1.0 - fee);
+            if remaining >= full {
+                remaining -= full;
+                expected += 500.0;
+                id -= 1;
+            } else {
+                expected += remaining * (1.0 - fee) * p;
+                break;
+            }
+        }
+        assert!((out - expected).abs() / expected < 1e-6, "bin walk {} vs expected {}", out, expected);
+
+        // 常数乘积近似（把已加载 bin 的合计当储备量）少报约 3.4%
+        let (x, y) = quoter.loaded_liquidity();
+        let cpmm = calculate_amm_output_f64(10.0, x as f64 / 1e9, y as f64 / 1e6, fee);
+        let gap = (out - cpmm) / out;
+        assert!(gap > 0.03 && gap < 0.04, "DLMM {} vs CPMM {} (gap {:.4})", out, cpmm, gap);
+
+        // 超出已加载 bin 的流动性时不报价
+        assert_eq!(quoter.get_orderbook_quote(1_000_000.0, false), None);
+    }
+}
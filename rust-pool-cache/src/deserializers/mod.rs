@@ -2,9 +2,11 @@ pub mod raydium;
 //pub mod raydium_clmm;  // 旧版本（结构错误）
 pub mod raydium_clmm_corrected;  // 修正版本
 pub use raydium_clmm_corrected as raydium_clmm;  // 临时替换
+pub mod clmm_quoter;  // 📈 Raydium CLMM tick array 解析 + 跨 tick 报价
 pub mod lifinity_v2;
 pub mod meteora_dlmm;
 pub mod meteora_dlmm_improved;
+pub mod meteora_dlmm_bins;  // 📊 DLMM bin array 解析 + 按 bin 报价
 // pub mod meteora_dlmm_with_reserves; // 可选功能，需要时手动启用
 pub mod spl_token;
 pub mod alphaq;
@@ -1,6 +1,6 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_sdk::pubkey::Pubkey;
-use crate::dex_interface::{DexPool, DexError};
+use crate::dex_interface::{ActivityStatus, DexPool, DexError};
 
 /// OpenBook V2 Market State
 /// 
@@ -272,13 +272,23 @@ impl DexPool for OpenBookMarketState {
     }
     
     fn is_active(&self) -> bool {
+        self.activity_status().is_active()
+    }
+    
+    fn activity_status(&self) -> ActivityStatus {
         // Market is active if not expired and not empty
         let current_time = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs() as i64;
         
-        !self.is_expired(current_time)
+        if self.is_expired(current_time) {
+            ActivityStatus::Expired
+        } else if self.is_empty() {
+            ActivityStatus::NoLiquidity
+        } else {
+            ActivityStatus::Active
+        }
     }
     
     fn get_additional_info(&self) -> Option<String> {
@@ -1,150 +1,36 @@
 use solana_sdk::pubkey::Pubkey;
-use crate::dex_interface::{DexPool, DexError};
+use crate::dex_interface::{ActivityStatus, DexPool, DexError};
+use super::raydium_clmm::RaydiumClmmPoolState;
 
 /// PancakeSwap Pool State (Solana)
-/// 
-/// PancakeSwap is a cross-chain DEX that expanded from BSC to Solana
-/// 
+///
+/// PancakeSwap v3 on Solana is a fork of Raydium CLMM: same 1544-byte account,
+/// same Anchor discriminator (0xf7ede3f5d7c3de46) and same field layout,
+/// only the program ID differs.
+///
 /// Program ID: HpNfyc2Saw7RKkQd8nEL4khUcuPhQ7WwY1B2qjx8jxFq
-/// Data size: 1544 bytes (实际测量，可能是CLMM版本)
-/// 
-/// Structure (基于真实链上数据):
-/// - Discriminator: 0xf7ede3f5d7c3de46
-/// - 大型数据结构，包含额外的配置和状态
+/// Data size: 1544 bytes
+///
+/// Verified against the mainnet fixture (22HUWiJa...，SOL/USDT):
+/// - Offset 73 / 105: token_mint_0 (SOL) / token_mint_1 (USDT)
+/// - Offset 137 / 169: token_vault_0 / token_vault_1
+/// - Offset 233 / 234: mint decimals (9 / 6)
+/// - Offset 253: sqrt_price_x64 (≈ 198.9 USDT per SOL)
+///
+/// 早期版本把 offset 256 / 280 当作储备读取，实际是 sqrt_price_x64 的中间字节；
+/// 储备在外部 vault 里，需要订阅 vault 才能持续更新。
 #[derive(Debug, Clone)]
 pub struct PancakeSwapPoolState {
-    /// Raw account data
-    pub data: Vec<u8>,
-    
-    /// Discriminator (offset 0-7)
-    pub discriminator: [u8; 8],
-    
-    /// Token A mint (从数据提取)
-    pub token_a_mint: Option<Pubkey>,
-    
-    /// Token B mint (从数据提取)
-    pub token_b_mint: Option<Pubkey>,
-    
-    /// Reserve A amount (offset 256)
-    pub reserve_a: u64,
-    
-    /// Reserve B amount (offset 280)
-    pub reserve_b: u64,
-    
-    /// LP supply (推测)
-    pub lp_supply: u64,
+    /// 与 Raydium CLMM 相同的池子状态
+    pub state: RaydiumClmmPoolState,
 }
 
-#[allow(dead_code)]
 impl PancakeSwapPoolState {
-    /// Parse from raw account data
-    /// 
-    /// PancakeSwap data structure (1544 bytes):
-    /// - Offset 0-7: Discriminator 0xf7ede3f5d7c3de46
-    /// - Offset 256: Reserve A (u64)
-    /// - Offset 280: Reserve B (u64)
-    /// - 包含大量配置和CLMM相关数据
-    pub fn from_bytes(data: &[u8]) -> Result<Self, std::io::Error> {
-        if data.len() != 1544 {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("PancakeSwap pool data should be exactly 1544 bytes, got {}", data.len()),
-            ));
-        }
-        
-        // 提取discriminator
-        let discriminator: [u8; 8] = data[0..8].try_into().unwrap();
-        
-        // 提取Token A mint (offset 40-72，推测)
-        let token_a_mint = if data.len() >= 72 {
-            Some(Pubkey::new_from_array(
-                data[40..72].try_into().unwrap()
-            ))
-        } else {
-            None
-        };
-        
-        // 提取Token B mint (offset 72-104，推测)
-        let token_b_mint = if data.len() >= 104 {
-            Some(Pubkey::new_from_array(
-                data[72..104].try_into().unwrap()
-            ))
-        } else {
-            None
-        };
-        
-        // 提取Reserve A (offset 256)
-        let reserve_a = if data.len() >= 264 {
-            u64::from_le_bytes(data[256..264].try_into().unwrap())
-        } else {
-            0
-        };
-        
-        // 提取Reserve B (offset 280)
-        let reserve_b = if data.len() >= 288 {
-            u64::from_le_bytes(data[280..288].try_into().unwrap())
-        } else {
-            0
-        };
-        
-        // 提取LP supply (offset 296，推测)
-        let lp_supply = if data.len() >= 304 {
-            u64::from_le_bytes(data[296..304].try_into().unwrap())
-        } else {
-            0
-        };
-        
-        Ok(PancakeSwapPoolState {
-            data: data.to_vec(),
-            discriminator,
-            token_a_mint,
-            token_b_mint,
-            reserve_a,
-            reserve_b,
-            lp_supply,
-        })
-    }
-    
-    /// Calculate price (token B per token A)
-    pub fn calculate_price(&self) -> f64 {
-        if self.reserve_a == 0 {
-            return 0.0;
-        }
-        
-        // USDC/USDT pair, both 6 decimals
-        let reserve_a_f64 = self.reserve_a as f64 / 1e6;
-        let reserve_b_f64 = self.reserve_b as f64 / 1e6;
-        
-        if reserve_a_f64 == 0.0 {
-            return 0.0;
-        }
-        
-        reserve_b_f64 / reserve_a_f64
-    }
-    
-    /// Get human-readable reserve amounts
-    pub fn get_reserves_formatted(&self) -> (f64, f64) {
-        const DECIMALS: i32 = 6;
-        let reserve_a = self.reserve_a as f64 / 10_f64.powi(DECIMALS);
-        let reserve_b = self.reserve_b as f64 / 10_f64.powi(DECIMALS);
-        (reserve_a, reserve_b)
-    }
-    
-    /// Get LP supply formatted
-    pub fn get_lp_supply_formatted(&self) -> f64 {
-        const DECIMALS: i32 = 6;
-        self.lp_supply as f64 / 10_f64.powi(DECIMALS)
-    }
-    
-    /// Get pool information for debugging
-    pub fn get_pool_info(&self) -> String {
-        let (reserve_a, reserve_b) = self.get_reserves_formatted();
-        format!(
-            "PancakeSwap Pool:\n  Reserve A: ${:.2}\n  Reserve B: ${:.2}\n  Price: {:.6}",
-            reserve_a,
-            reserve_b,
-            self.calculate_price()
-        )
+    /// Parse from raw account data (Raydium CLMM layout)
+    pub fn from_bytes(data: &[u8]) -> Result<Self, DexError> {
+        RaydiumClmmPoolState::from_account_data_manual(data)
+            .map(|state| Self { state })
+            .map_err(|e| DexError::DeserializationFailed(format!("PancakeSwap: {}", e)))
     }
 }
 
@@ -156,95 +42,106 @@ impl DexPool for PancakeSwapPoolState {
     fn dex_name(&self) -> &'static str {
         "PancakeSwap"
     }
-    
+
     fn from_account_data(data: &[u8]) -> Result<Self, DexError>
     where
         Self: Sized,
     {
         Self::from_bytes(data)
-            .map_err(|e| DexError::DeserializationFailed(format!("PancakeSwap: {}", e)))
     }
-    
+
     fn calculate_price(&self) -> f64 {
-        PancakeSwapPoolState::calculate_price(self)
+        self.state.calculate_price()
     }
-    
+
     fn get_reserves(&self) -> (u64, u64) {
-        (self.reserve_a, self.reserve_b)
+        // vault 余额到达前用流动性推导的近似储备
+        DexPool::get_reserves(&self.state)
     }
-    
+
     fn get_decimals(&self) -> (u8, u8) {
-        // PancakeSwap USDC/USDT 稳定币对
-        // 都是 6 decimals
-        (6, 6)
+        (self.state.mint_decimals_0, self.state.mint_decimals_1)
     }
-    
+
     fn is_active(&self) -> bool {
-        // Pool is active if it has reserves
-        self.reserve_a > 0 || self.reserve_b > 0
+        self.state.is_active()
+    }
+
+    fn activity_status(&self) -> ActivityStatus {
+        self.state.activity_status()
     }
-    
+
     fn get_additional_info(&self) -> Option<String> {
-        let (res_a, res_b) = self.get_reserves_formatted();
-        Some(format!(
-            "Reserves: ${:.0} / ${:.0}",
-            res_a,
-            res_b
-        ))
+        self.state.get_additional_info()
+    }
+
+    fn get_vault_addresses(&self) -> Option<(Pubkey, Pubkey)> {
+        // 储备在外部 vault 账户（与 Raydium CLMM 相同）
+        Some((self.state.token_vault_0, self.state.token_vault_1))
+    }
+
+    fn get_mints(&self) -> Option<(Pubkey, Pubkey)> {
+        Some((self.state.token_mint_0, self.state.token_mint_1))
+    }
+
+    fn get_fee_rate(&self) -> Option<f64> {
+        // 费率档在 amm_config 指向的配置账户里，布局与 Raydium CLMM AmmConfig 相同
+        self.state.get_fee_rate()
+    }
+
+    fn fee_config_account(&self) -> Option<Pubkey> {
+        self.state.fee_config_account()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+    use crate::deserializers::raydium_clmm::register_amm_config_fee;
+    use crate::pool_fixture::{fixture_path, PoolFixture};
+    use std::path::Path;
+    use std::str::FromStr;
+
+    fn fixture_pool() -> PancakeSwapPoolState {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let fixture = PoolFixture::load(&fixture_path(&dir, "pancakeswap")).unwrap();
+        PancakeSwapPoolState::from_account_data(&fixture.decode_data().unwrap()).unwrap()
+    }
+
     #[test]
     fn test_data_size() {
         // PancakeSwap池子固定1544字节
         let data = vec![0u8; 1544];
-        let result = PancakeSwapPoolState::from_bytes(&data);
-        assert!(result.is_ok(), "Should parse 1544 byte data");
-        
+        assert!(PancakeSwapPoolState::from_bytes(&data).is_ok(), "Should parse 1544 byte data");
+
         // 错误的大小应该失败
         let wrong_size = vec![0u8; 849];
-        let result = PancakeSwapPoolState::from_bytes(&wrong_size);
-        assert!(result.is_err(), "Should reject wrong size");
+        assert!(PancakeSwapPoolState::from_bytes(&wrong_size).is_err(), "Should reject wrong size");
     }
-    
+
     #[test]
-    fn test_price_calculation() {
-        let mut data = vec![0u8; 1544];
-        
-        // 在offset 256写入reserve_a (1M USDC = 1M * 1e6 microUSDC)
-        let reserve_a: u64 = 1_000_000_000_000;
-        data[256..264].copy_from_slice(&reserve_a.to_le_bytes());
-        
-        // 在offset 280写入reserve_b (1M USDT = 1M * 1e6 microUSDT)
-        let reserve_b: u64 = 1_000_000_000_000;
-        data[280..288].copy_from_slice(&reserve_b.to_le_bytes());
-        
-        let pool = PancakeSwapPoolState::from_bytes(&data).unwrap();
-        
-        assert_eq!(pool.reserve_a, 1_000_000_000_000);
-        assert_eq!(pool.reserve_b, 1_000_000_000_000);
-        
+    fn test_fixture_layout() {
+        let pool = fixture_pool();
+
+        let sol = Pubkey::from_str("So11111111111111111111111111111111111111112").unwrap();
+        let usdt = Pubkey::from_str("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB").unwrap();
+        assert_eq!(pool.get_mints(), Some((sol, usdt)));
+        assert_eq!(pool.get_decimals(), (9, 6));
+
+        let (vault_0, vault_1) = pool.get_vault_addresses().unwrap();
+        assert_eq!(vault_0.to_string(), "7YcR78PKBHE5divhPVJ668kmmaDzove2d9msY9NpAJL2");
+        assert_eq!(vault_1.to_string(), "FCBauqNLbEBUci1v3NT4gWyFZXS4YUGnX7ZsPmWVzrvV");
+
         let price = pool.calculate_price();
-        // For 1:1 stablecoin pool, price should be ~1.0
-        assert!((price - 1.0).abs() < 0.001, "Price should be close to 1.0");
+        assert!((price - 198.9).abs() < 0.1, "SOL/USDT price should be ~198.9, got {}", price);
+        assert!(pool.is_active());
     }
-    
+
     #[test]
-    fn test_is_active() {
-        let mut data = vec![0u8; 1544];
-        
-        // 添加储备
-        let reserve_a: u64 = 1_000_000_000;
-        data[256..264].copy_from_slice(&reserve_a.to_le_bytes());
-        
-        let pool = PancakeSwapPoolState::from_bytes(&data).unwrap();
-        assert!(pool.is_active(), "Pool with reserves should be active");
+    fn test_fee_rate_from_amm_config() {
+        let pool = fixture_pool();
+        register_amm_config_fee(pool.state.amm_config, 500);
+
+        assert!((pool.get_fee_rate().unwrap() - 0.0005).abs() < 1e-12);
     }
 }
-
-
-
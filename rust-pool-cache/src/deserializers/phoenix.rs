@@ -1,6 +1,6 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_sdk::pubkey::Pubkey;
-use crate::dex_interface::{DexPool, DexError};
+use crate::dex_interface::{ActivityStatus, DexPool, DexError};
 
 /// Phoenix DEX Market State
 /// 
@@ -197,6 +197,14 @@ impl DexPool for PhoenixMarketState {
         self.is_market_active()
     }
     
+    fn activity_status(&self) -> ActivityStatus {
+        if self.is_market_active() {
+            ActivityStatus::Active
+        } else {
+            ActivityStatus::Paused
+        }
+    }
+    
     fn get_additional_info(&self) -> Option<String> {
         Some(format!(
             "CLOB Market - Status: {}, Tick: {:.8}, Seats: {}",
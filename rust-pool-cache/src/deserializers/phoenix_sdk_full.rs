@@ -4,7 +4,7 @@
 
 use std::mem::size_of;
 use solana_sdk::pubkey::Pubkey;
-use crate::dex_interface::{DexPool, DexError};
+use crate::dex_interface::{ActivityStatus, DexPool, DexError};
 
 // Phoenix SDK依赖
 use phoenix::program::MarketHeader;
@@ -12,6 +12,153 @@ use phoenix::program::dispatch_market::load_with_dispatch;
 use phoenix::state::enums::Side;
 use phoenix::quantities::WrapperU64;
 
+/// 默认保留的订单簿档位数（每侧）
+pub const DEFAULT_BOOK_DEPTH: usize = 20;
+
+/// 订单簿单个价格档位（UI单位）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookLevel {
+    /// 价格（quote / base）
+    pub price: f64,
+    /// 该价位的 base 数量
+    pub size: f64,
+}
+
+/// 按档位吃单的结果（UI单位）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookFill {
+    /// 扣除 taker 费后的输出数量
+    pub amount_out: f64,
+    /// 实际成交的输入数量（订单簿不足时小于请求数量）
+    pub amount_in_filled: f64,
+    /// 消耗的档位数
+    pub levels_consumed: usize,
+    /// 输入是否全部成交
+    pub complete: bool,
+}
+
+/// Phoenix 订单簿快照
+///
+/// bids 按价格降序，asks 按价格升序；同一 tick 的订单合并为一个档位。
+#[derive(Debug, Clone, Default)]
+pub struct PhoenixOrderBook {
+    pub bids: Vec<BookLevel>,
+    pub asks: Vec<BookLevel>,
+    pub taker_fee_bps: u64,
+}
+
+impl PhoenixOrderBook {
+    /// 前 N 档买单
+    pub fn top_bids(&self, n: usize) -> &[BookLevel] {
+        &self.bids[..n.min(self.bids.len())]
+    }
+
+    /// 前 N 档卖单
+    pub fn top_asks(&self, n: usize) -> &[BookLevel] {
+        &self.asks[..n.min(self.asks.len())]
+    }
+
+    fn taker_fee_rate(&self) -> f64 {
+        self.taker_fee_bps as f64 / 10_000.0
+    }
+
+    /// 以精确输入吃单
+    ///
+    /// `side` 为 taker 方向：`Side::Bid` 用 quote 买入 base（吃 asks），
+    /// `Side::Ask` 卖出 base 换 quote（吃 bids）。taker 费以 quote 计收：
+    /// 买入时从输入中预留，卖出时从输出中扣除。
+    /// 订单簿深度不足时只计算已成交部分，`complete = false`。
+    pub fn quote_exact_in(&self, side: Side, amount_in: f64) -> BookFill {
+        let mut fill = BookFill {
+            amount_out: 0.0,
+            amount_in_filled: 0.0,
+            levels_consumed: 0,
+            complete: false,
+        };
+        if amount_in <= 0.0 || !amount_in.is_finite() {
+            return fill;
+        }
+
+        let fee = self.taker_fee_rate();
+        match side {
+            Side::Bid => {
+                // quote 预算扣除 taker 费后用于撮合
+                let mut budget = amount_in / (1.0 + fee);
+                for level in &self.asks {
+                    if budget <= 0.0 {
+                        break;
+                    }
+                    if level.price <= 0.0 || level.size <= 0.0 {
+                        continue;
+                    }
+                    let level_cost = level.price * level.size;
+                    fill.levels_consumed += 1;
+                    if budget <= level_cost {
+                        fill.amount_out += budget / level.price;
+                        budget = 0.0;
+                    } else {
+                        fill.amount_out += level.size;
+                        budget -= level_cost;
+                    }
+                }
+                fill.complete = budget <= 0.0;
+                fill.amount_in_filled = amount_in - budget * (1.0 + fee);
+            }
+            Side::Ask => {
+                let mut remaining = amount_in;
+                let mut quote_out = 0.0;
+                for level in &self.bids {
+                    if remaining <= 0.0 {
+                        break;
+                    }
+                    if level.price <= 0.0 || level.size <= 0.0 {
+                        continue;
+                    }
+                    let take = remaining.min(level.size);
+                    fill.levels_consumed += 1;
+                    quote_out += take * level.price;
+                    remaining -= take;
+                }
+                fill.complete = remaining <= 0.0;
+                fill.amount_in_filled = amount_in - remaining;
+                fill.amount_out = quote_out * (1.0 - fee);
+            }
+        }
+        fill
+    }
+}
+
+/// 把按优先级排列的 (price_in_ticks, num_base_lots) 合并成价格档位
+fn aggregate_levels<I>(
+    orders: I,
+    depth: usize,
+    quote_units_per_tick: f64,
+    base_units_per_lot: f64,
+) -> Vec<BookLevel>
+where
+    I: Iterator<Item = (u64, u64)>,
+{
+    let mut levels: Vec<BookLevel> = Vec::with_capacity(depth);
+    let mut last_ticks: Option<u64> = None;
+    for (ticks, lots) in orders {
+        if last_ticks == Some(ticks) {
+            if let Some(level) = levels.last_mut() {
+                level.size += lots as f64 * base_units_per_lot;
+            }
+            continue;
+        }
+        if levels.len() == depth {
+            break;
+        }
+        last_ticks = Some(ticks);
+        levels.push(BookLevel {
+            price: ticks as f64 * quote_units_per_tick,
+            size: lots as f64 * base_units_per_lot,
+        });
+    }
+    levels
+}
+
 /// Phoenix Market with Full SDK Integration
 #[derive(Debug, Clone)]
 pub struct PhoenixMarketFull {
@@ -39,11 +186,19 @@ pub struct PhoenixMarketFull {
     /// 买单和卖单数量
     pub num_bids: usize,
     pub num_asks: usize,
+    
+    /// 前 N 档订单簿（UI单位）
+    pub orderbook: PhoenixOrderBook,
 }
 
 impl PhoenixMarketFull {
     /// 从账户数据创建Phoenix市场实例
     pub fn from_account_data(data: &[u8]) -> Result<Self, DexError> {
+        Self::from_account_data_with_depth(data, DEFAULT_BOOK_DEPTH)
+    }
+    
+    /// 从账户数据创建Phoenix市场实例，每侧保留 `depth` 档
+    pub fn from_account_data_with_depth(data: &[u8], depth: usize) -> Result<Self, DexError> {
         // 步骤1: 检查最小大小
         let header_size = size_of::<MarketHeader>();
         if data.len() < header_size {
@@ -60,10 +215,18 @@ impl PhoenixMarketFull {
         let header: &MarketHeader = bytemuck::try_from_bytes(header_bytes)
             .map_err(|e| DexError::DeserializationFailed(format!("Phoenix header parse error: {:?}", e)))?;
         
-        // 步骤4: 计算价格转换参数
+        // 步骤4: 计算价格转换参数（精度超出 u64 范围说明不是 Phoenix 账户，直接拒绝）
         let tick_size_in_quote_atoms: u64 = header.get_tick_size_in_quote_atoms_per_base_unit().into();
         let raw_base_units_per_base_unit = header.raw_base_units_per_base_unit.max(1) as u64;
-        let quote_atoms_per_quote_unit = 10_u64.pow(header.quote_params.decimals);
+        let quote_atoms_per_quote_unit = 10_u64.checked_pow(header.quote_params.decimals)
+            .ok_or_else(|| DexError::InvalidData(format!(
+                "Phoenix quote decimals out of range: {}", header.quote_params.decimals
+            )))?;
+        if header.base_params.decimals > u8::MAX as u32 {
+            return Err(DexError::InvalidData(format!(
+                "Phoenix base decimals out of range: {}", header.base_params.decimals
+            )));
+        }
         
         // quote_units_per_raw_base_unit_per_tick = tick_size / (10^quote_decimals * raw_base_units_per_base_unit)
         let quote_units_per_raw_base_unit_per_tick = 
@@ -94,22 +257,41 @@ impl PhoenixMarketFull {
         // 步骤7: 计算订单簿总流动性
         let base_lot_size: u64 = header.get_base_lot_size().into();
         
-        let bid_lots: u64 = market.inner.get_book(Side::Bid)
-            .iter()
-            .map(|(_order_id, order)| order.num_base_lots.as_u64())
-            .sum();
-        let total_bid_liquidity = bid_lots * base_lot_size;
-        
-        let ask_lots: u64 = market.inner.get_book(Side::Ask)
-            .iter()
-            .map(|(_order_id, order)| order.num_base_lots.as_u64())
-            .sum();
-        let total_ask_liquidity = ask_lots * base_lot_size;
+        let book_liquidity = |side: Side| -> Result<u64, DexError> {
+            market.inner.get_book(side)
+                .iter()
+                .try_fold(0u64, |lots, (_order_id, order)| lots.checked_add(order.num_base_lots.as_u64()))
+                .and_then(|lots| lots.checked_mul(base_lot_size))
+                .ok_or_else(|| DexError::InvalidData(format!("Phoenix {:?} book liquidity overflows u64", side)))
+        };
+        let total_bid_liquidity = book_liquidity(Side::Bid)?;
+        let total_ask_liquidity = book_liquidity(Side::Ask)?;
         
         // 步骤8: 统计订单数量
         let num_bids = market.inner.get_book(Side::Bid).iter().count();
         let num_asks = market.inner.get_book(Side::Ask).iter().count();
         
+        // 步骤9: 提取前 N 档（UI单位）
+        let base_units_per_lot =
+            base_lot_size as f64 / 10f64.powi(header.base_params.decimals as i32);
+        let orderbook = PhoenixOrderBook {
+            bids: aggregate_levels(
+                market.inner.get_book(Side::Bid).iter()
+                    .map(|(order_id, order)| (order_id.price_in_ticks.as_u64(), order.num_base_lots.as_u64())),
+                depth,
+                quote_units_per_raw_base_unit_per_tick,
+                base_units_per_lot,
+            ),
+            asks: aggregate_levels(
+                market.inner.get_book(Side::Ask).iter()
+                    .map(|(order_id, order)| (order_id.price_in_ticks.as_u64(), order.num_base_lots.as_u64())),
+                depth,
+                quote_units_per_raw_base_unit_per_tick,
+                base_units_per_lot,
+            ),
+            taker_fee_bps: market.inner.get_taker_fee_bps(),
+        };
+        
         Ok(PhoenixMarketFull {
             base_mint: header.base_params.mint_key,
             quote_mint: header.quote_params.mint_key,
@@ -123,8 +305,14 @@ impl PhoenixMarketFull {
             total_ask_liquidity,
             num_bids,
             num_asks,
+            orderbook,
         })
     }
+    
+    /// 订单簿快照
+    pub fn orderbook(&self) -> &PhoenixOrderBook {
+        &self.orderbook
+    }
 }
 
 impl DexPool for PhoenixMarketFull {
@@ -163,6 +351,15 @@ impl DexPool for PhoenixMarketFull {
         self.best_bid.is_some() && self.best_ask.is_some()
     }
     
+    fn activity_status(&self) -> ActivityStatus {
+        // 订单簿任一侧为空即无法双向成交
+        if self.is_active() {
+            ActivityStatus::Active
+        } else {
+            ActivityStatus::NoLiquidity
+        }
+    }
+    
     fn get_additional_info(&self) -> Option<String> {
         let spread = match (self.best_bid, self.best_ask) {
             (Some(bid), Some(ask)) if bid > 0.0 => (ask - bid) / bid * 100.0,
@@ -193,12 +390,35 @@ impl DexPool for PhoenixMarketFull {
         // Correct behavior: Return actual vault Token account addresses from MarketHeader
         Some((self.base_vault, self.quote_vault))
     }
+    
+    fn has_orderbook(&self) -> bool {
+        true
+    }
+    
+    fn get_orderbook_quote(&self, amount_in: f64, is_buy: bool) -> Option<f64> {
+        let side = if is_buy { Side::Bid } else { Side::Ask };
+        Some(self.orderbook.quote_exact_in(side, amount_in).amount_out)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     
+    #[test]
+    fn test_out_of_range_decimals_rejected() {
+        // 非 Phoenix 账户被当成 MarketHeader 读出任意精度：返回错误而不是溢出 panic
+        let mut header: MarketHeader = bytemuck::Zeroable::zeroed();
+        header.quote_params.decimals = 200;
+        let mut data = bytemuck::bytes_of(&header).to_vec();
+        data.resize(data.len() + 1024, 0);
+        
+        assert!(matches!(
+            PhoenixMarketFull::from_account_data(&data),
+            Err(DexError::InvalidData(msg)) if msg.contains("decimals")
+        ));
+    }
+    
     #[test]
     fn test_price_calculation() {
         let market = PhoenixMarketFull {
@@ -214,11 +434,73 @@ mod tests {
             total_ask_liquidity: 1500000000,
             num_bids: 50,
             num_asks: 45,
+            orderbook: PhoenixOrderBook::default(),
         };
         
         let mid_price = market.calculate_price();
         assert!((mid_price - 100.55).abs() < 0.01);
         assert!(market.is_active());
     }
+    
+    fn sample_book(taker_fee_bps: u64) -> PhoenixOrderBook {
+        PhoenixOrderBook {
+            bids: vec![
+                BookLevel { price: 100.0, size: 1.0 },
+                BookLevel { price: 99.0, size: 2.0 },
+            ],
+            asks: vec![
+                BookLevel { price: 101.0, size: 1.0 },
+                BookLevel { price: 102.0, size: 2.0 },
+            ],
+            taker_fee_bps,
+        }
+    }
+    
+    #[test]
+    fn test_aggregate_levels_merges_equal_ticks() {
+        // 两个同价订单 + 两个价位，只保留 2 档
+        let orders = vec![(1000, 5), (1000, 3), (999, 4), (998, 10)];
+        let levels = aggregate_levels(orders.into_iter(), 2, 0.1, 0.01);
+        
+        assert_eq!(levels.len(), 2);
+        assert!((levels[0].price - 100.0).abs() < 1e-9);
+        assert!((levels[0].size - 0.08).abs() < 1e-9);
+        assert!((levels[1].price - 99.9).abs() < 1e-9);
+        assert!((levels[1].size - 0.04).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_quote_exact_in_walks_levels() {
+        let book = sample_book(0);
+        
+        // 买入：101 买满第一档，再用 102 在第二档买 1 个
+        let buy = book.quote_exact_in(Side::Bid, 203.0);
+        assert!(buy.complete);
+        assert_eq!(buy.levels_consumed, 2);
+        assert!((buy.amount_out - 2.0).abs() < 1e-9);
+        
+        // 卖出：1 个成交在 100，0.5 个成交在 99
+        let sell = book.quote_exact_in(Side::Ask, 1.5);
+        assert!(sell.complete);
+        assert!((sell.amount_out - 149.5).abs() < 1e-9);
+        
+        // 超过订单簿深度：只计算已成交部分
+        let too_big = book.quote_exact_in(Side::Ask, 10.0);
+        assert!(!too_big.complete);
+        assert!((too_big.amount_in_filled - 3.0).abs() < 1e-9);
+        assert!((too_big.amount_out - 298.0).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_quote_exact_in_applies_taker_fee() {
+        let book = sample_book(10); // 0.10%
+        
+        let sell = book.quote_exact_in(Side::Ask, 1.0);
+        assert!((sell.amount_out - 100.0 * 0.999).abs() < 1e-9);
+        
+        let buy = book.quote_exact_in(Side::Bid, 101.0 * 1.001);
+        assert!(buy.complete);
+        assert!((buy.amount_out - 1.0).abs() < 1e-9);
+    }
 }
 
@@ -1,6 +1,6 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_sdk::pubkey::Pubkey;
-use crate::dex_interface::{DexPool, DexError};
+use crate::dex_interface::{ActivityStatus, DexPool, DexError};
 
 /// Raydium AMM V4 pool state structure
 /// 
@@ -329,8 +329,20 @@ impl DexPool for RaydiumAmmInfo {
     }
     
     fn is_active(&self) -> bool {
-        // Pool is active if status is non-zero
-        self.status > 0
+        self.activity_status().is_active()
+    }
+    
+    fn activity_status(&self) -> ActivityStatus {
+        // AmmStatus: 0 Uninitialized, 1 Initialized, 2 Disabled, 3 WithdrawOnly,
+        // 4 LiquidityOnly, 5 OrderBookOnly, 6 SwapOnly, 7 WaitingTrade.
+        // Only 1 / 6 / 7 permit swaps.
+        match self.status {
+            0 => ActivityStatus::Unknown { reason: "uninitialized".to_string() },
+            2..=5 => ActivityStatus::Paused,
+            1 | 6 | 7 if self.coin_vault_amount == 0 || self.pc_vault_amount == 0 => ActivityStatus::NoLiquidity,
+            1 | 6 | 7 => ActivityStatus::Active,
+            status => ActivityStatus::Unknown { reason: format!("unrecognized status {}", status) },
+        }
     }
     
     fn get_additional_info(&self) -> Option<String> {
@@ -340,6 +352,10 @@ impl DexPool for RaydiumAmmInfo {
             self.status
         ))
     }
+    
+    fn get_mints(&self) -> Option<(Pubkey, Pubkey)> {
+        Some((self.coin_mint, self.pc_mint))
+    }
 }
 
 // ============================================
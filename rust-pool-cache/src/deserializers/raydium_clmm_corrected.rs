@@ -1,6 +1,150 @@
 use solana_sdk::pubkey::Pubkey;
-use crate::dex_interface::{DexPool, DexError};
+use crate::dex_interface::{ActivityStatus, DexPool, DexError};
+use crate::rpc_manager::RpcHandle;
+use dashmap::DashMap;
 use std::convert::TryInto;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Raydium CLMM 费率单位：1e-6（2500 = 0.25%）
+pub const FEE_RATE_DENOMINATOR: f64 = 1_000_000.0;
+
+/// Raydium CLMM AmmConfig 账户
+/// 
+/// 池子账户本身不存费率，费率档（1/5/25/100 bps）在 `amm_config` 指向的配置账户中。
+/// 
+/// Field offsets (after 8-byte discriminator):
+/// - 0: bump (u8)
+/// - 1-3: index (u16)
+/// - 3-35: owner (Pubkey)
+/// - 35-39: protocol_fee_rate (u32)
+/// - 39-43: trade_fee_rate (u32)
+/// - 43-45: tick_spacing (u16)
+/// - 45-49: fund_fee_rate (u32)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaydiumClmmAmmConfig {
+    pub index: u16,
+    pub protocol_fee_rate: u32,
+    pub trade_fee_rate: u32,
+    pub tick_spacing: u16,
+    pub fund_fee_rate: u32,
+}
+
+impl RaydiumClmmAmmConfig {
+    /// 最小长度：discriminator + 上述字段
+    const MIN_LEN: usize = 8 + 49;
+
+    pub fn from_account_data(data: &[u8]) -> Result<Self, DexError> {
+        if data.len() < Self::MIN_LEN {
+            return Err(DexError::InvalidData(format!(
+                "Raydium CLMM AmmConfig data too small: {} bytes (expected {}+)",
+                data.len(), Self::MIN_LEN
+            )));
+        }
+        let data = &data[8..];
+        let u32_at = |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+        let u16_at = |offset: usize| u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap());
+
+        Ok(Self {
+            index: u16_at(1),
+            protocol_fee_rate: u32_at(35),
+            trade_fee_rate: u32_at(39),
+            tick_spacing: u16_at(43),
+            fund_fee_rate: u32_at(45),
+        })
+    }
+
+    /// 交易费率（小数形式）
+    pub fn fee_rate(&self) -> f64 {
+        self.trade_fee_rate as f64 / FEE_RATE_DENOMINATOR
+    }
+}
+
+/// AmmConfig 拉取状态
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AmmConfigEntry {
+    /// 拉取成功的 trade_fee_rate
+    Fetched(u32),
+    /// 后台拉取中（`failures` 为此前连续失败次数）
+    Fetching { failures: u32 },
+    /// 拉取失败，`retry_at` 之后再试
+    Failed { failures: u32, retry_at: Instant },
+}
+
+/// 失败重试的首次退避
+const AMM_CONFIG_RETRY_BASE: Duration = Duration::from_secs(2);
+/// 失败重试的最大退避
+const AMM_CONFIG_RETRY_MAX: Duration = Duration::from_secs(300);
+
+/// amm_config -> 拉取状态（只有成功的结果会被当作费率使用）
+static AMM_CONFIG_FEES: OnceLock<DashMap<Pubkey, AmmConfigEntry>> = OnceLock::new();
+
+fn amm_config_fees() -> &'static DashMap<Pubkey, AmmConfigEntry> {
+    AMM_CONFIG_FEES.get_or_init(DashMap::new)
+}
+
+/// 手动登记 AmmConfig 费率（例如批量预取或测试）
+pub fn register_amm_config_fee(amm_config: Pubkey, trade_fee_rate: u32) {
+    amm_config_fees().insert(amm_config, AmmConfigEntry::Fetched(trade_fee_rate));
+}
+
+/// 已缓存的 AmmConfig 费率（不发起 RPC；未拉取或拉取失败时为 None，路由器回退到 DEX 默认费率）
+pub fn cached_amm_config_fee(amm_config: &Pubkey) -> Option<u32> {
+    match amm_config_fees().get(amm_config).as_deref() {
+        Some(AmmConfigEntry::Fetched(rate)) => Some(*rate),
+        _ => None,
+    }
+}
+
+/// 标记开始拉取：已有结果、正在拉取或退避未到期时返回 false（返回 true 后调用 `fetch_amm_config_fee`）
+pub fn begin_amm_config_fetch(amm_config: &Pubkey, now: Instant) -> bool {
+    let mut entry = amm_config_fees().entry(*amm_config).or_insert(AmmConfigEntry::Failed { failures: 0, retry_at: now });
+    match *entry {
+        AmmConfigEntry::Failed { failures, retry_at } if retry_at <= now => {
+            *entry = AmmConfigEntry::Fetching { failures };
+            true
+        }
+        _ => false,
+    }
+}
+
+/// 记录拉取结果：成功则缓存费率，失败则按连续失败次数指数退避（2s 起，最多 5 分钟）
+fn finish_amm_config_fetch(amm_config: &Pubkey, trade_fee_rate: Option<u32>, now: Instant) {
+    let failures = match amm_config_fees().get(amm_config).as_deref() {
+        Some(AmmConfigEntry::Fetching { failures }) => *failures,
+        _ => 0,
+    };
+    let entry = match trade_fee_rate {
+        Some(rate) => AmmConfigEntry::Fetched(rate),
+        None => {
+            let backoff = AMM_CONFIG_RETRY_BASE.saturating_mul(1 << failures.min(16)).min(AMM_CONFIG_RETRY_MAX);
+            AmmConfigEntry::Failed { failures: failures + 1, retry_at: now + backoff }
+        }
+    };
+    amm_config_fees().insert(*amm_config, entry);
+}
+
+/// 异步拉取 AmmConfig 费率并记录结果（池子激活时由 WebSocket 更新路径在后台调用，不阻塞 tokio worker）
+///
+/// 调用前先用 `begin_amm_config_fetch` 占位，同一账户同时只有一个请求。
+pub async fn fetch_amm_config_fee(rpc: &RpcHandle, amm_config: Pubkey) -> Option<u32> {
+    let fetched = match rpc.call(move |client| client.get_account_data(&amm_config).map_err(Box::new)).await {
+        Ok(data) => match RaydiumClmmAmmConfig::from_account_data(&data) {
+            Ok(config) => Some(config.trade_fee_rate),
+            Err(e) => {
+                warn!("Invalid Raydium CLMM AmmConfig {}: {}", amm_config, e);
+                None
+            }
+        },
+        Err(e) => {
+            debug!("Failed to fetch Raydium CLMM AmmConfig {}: {}", amm_config, e);
+            None
+        }
+    };
+    finish_amm_config_fetch(&amm_config, fetched, Instant::now());
+    fetched
+}
 
 /// Raydium CLMM (Concentrated Liquidity Market Maker) Pool State
 /// 
@@ -249,6 +393,14 @@ impl DexPool for RaydiumClmmPoolState {
         self.is_active()
     }
     
+    fn activity_status(&self) -> ActivityStatus {
+        if self.liquidity == 0 {
+            ActivityStatus::NoLiquidity
+        } else {
+            ActivityStatus::Active
+        }
+    }
+    
     fn get_additional_info(&self) -> Option<String> {
         Some(format!(
             "Tick: {}, Liquidity: {}, Price: {:.6}",
@@ -262,8 +414,99 @@ impl DexPool for RaydiumClmmPoolState {
         // Raydium CLMM stores reserves in external vault accounts
         Some((self.token_vault_0, self.token_vault_1))
     }
+    
+    fn get_mints(&self) -> Option<(Pubkey, Pubkey)> {
+        Some((self.token_mint_0, self.token_mint_1))
+    }
+    
+    fn get_fee_rate(&self) -> Option<f64> {
+        cached_amm_config_fee(&self.amm_config)
+            .map(|rate| rate as f64 / FEE_RATE_DENOMINATOR)
+    }
+    
+    fn fee_config_account(&self) -> Option<Pubkey> {
+        Some(self.amm_config)
+    }
 }
 
-
-
-
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 按链上布局构造 AmmConfig 账户数据
+    fn amm_config_bytes(index: u16, trade_fee_rate: u32, tick_spacing: u16) -> Vec<u8> {
+        let mut data = vec![0u8; 8 + 49 + 64];
+        let body = &mut data[8..];
+        body[1..3].copy_from_slice(&index.to_le_bytes());
+        body[35..39].copy_from_slice(&120_000u32.to_le_bytes());
+        body[39..43].copy_from_slice(&trade_fee_rate.to_le_bytes());
+        body[43..45].copy_from_slice(&tick_spacing.to_le_bytes());
+        body[45..49].copy_from_slice(&40_000u32.to_le_bytes());
+        data
+    }
+    
+    #[test]
+    fn test_amm_config_fee_tiers() {
+        let one_bps = RaydiumClmmAmmConfig::from_account_data(&amm_config_bytes(3, 100, 1)).unwrap();
+        let quarter_pct = RaydiumClmmAmmConfig::from_account_data(&amm_config_bytes(0, 2500, 60)).unwrap();
+        
+        assert_eq!(one_bps.tick_spacing, 1);
+        assert_eq!(quarter_pct.index, 0);
+        assert_eq!(quarter_pct.protocol_fee_rate, 120_000);
+        assert_eq!(quarter_pct.fund_fee_rate, 40_000);
+        assert!((one_bps.fee_rate() - 0.0001).abs() < 1e-12);
+        assert!((quarter_pct.fee_rate() - 0.0025).abs() < 1e-12);
+        
+        assert!(RaydiumClmmAmmConfig::from_account_data(&[0u8; 20]).is_err());
+    }
+    
+    #[test]
+    fn test_pool_fee_rate_uses_registered_amm_config() {
+        let amm_config = Pubkey::new_unique();
+        register_amm_config_fee(amm_config, 500);
+        
+        let pool = RaydiumClmmPoolState {
+            bump: 0,
+            amm_config,
+            owner: Pubkey::default(),
+            token_mint_0: Pubkey::default(),
+            token_mint_1: Pubkey::default(),
+            token_vault_0: Pubkey::default(),
+            token_vault_1: Pubkey::default(),
+            observation_key: Pubkey::default(),
+            mint_decimals_0: 9,
+            mint_decimals_1: 6,
+            tick_spacing: 10,
+            liquidity: 1,
+            sqrt_price_x64: 1 << 64,
+            tick_current: 0,
+        };
+        
+        assert!((DexPool::get_fee_rate(&pool).unwrap() - 0.0005).abs() < 1e-12);
+    }
+    
+    #[test]
+    fn test_failed_amm_config_fetch_is_retried_with_backoff() {
+        let amm_config = Pubkey::new_unique();
+        let start = Instant::now();
+        
+        // 失败不缓存为费率，退避期内不重复拉取
+        assert!(begin_amm_config_fetch(&amm_config, start));
+        assert!(!begin_amm_config_fetch(&amm_config, start));
+        finish_amm_config_fetch(&amm_config, None, start);
+        assert_eq!(cached_amm_config_fee(&amm_config), None);
+        assert!(!begin_amm_config_fetch(&amm_config, start + Duration::from_secs(1)));
+        
+        // 第二次失败：退避翻倍
+        assert!(begin_amm_config_fetch(&amm_config, start + AMM_CONFIG_RETRY_BASE));
+        finish_amm_config_fetch(&amm_config, None, start + AMM_CONFIG_RETRY_BASE);
+        let second_retry = start + AMM_CONFIG_RETRY_BASE * 3;
+        assert!(!begin_amm_config_fetch(&amm_config, second_retry - Duration::from_millis(1)));
+        
+        // 重试成功后缓存费率，不再拉取
+        assert!(begin_amm_config_fetch(&amm_config, second_retry));
+        finish_amm_config_fetch(&amm_config, Some(2500), second_retry);
+        assert_eq!(cached_amm_config_fee(&amm_config), Some(2500));
+        assert!(!begin_amm_config_fetch(&amm_config, second_retry + AMM_CONFIG_RETRY_MAX));
+    }
+}
@@ -1,6 +1,6 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_sdk::pubkey::Pubkey;
-use crate::dex_interface::{DexPool, DexError};
+use crate::dex_interface::{ActivityStatus, DexPool, DexError};
 
 /// SolFi V2 Pool State
 /// 
@@ -180,6 +180,15 @@ impl DexPool for SolFiV2PoolState {
         self.token_b_vault() != &Pubkey::default()
     }
     
+    fn activity_status(&self) -> ActivityStatus {
+        // 储备量只在 vault 中：池子账户本身最多是 AwaitingVaultData，收到 vault 余额后才是 Active
+        if self.is_active() {
+            ActivityStatus::AwaitingVaultData
+        } else {
+            ActivityStatus::Unknown { reason: "vault addresses not set".to_string() }
+        }
+    }
+    
     fn get_additional_info(&self) -> Option<String> {
         let (res_a, res_b) = self.get_reserves_formatted();
         Some(format!(
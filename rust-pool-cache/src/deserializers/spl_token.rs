@@ -2,9 +2,35 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use solana_sdk::pubkey::Pubkey;
 use crate::dex_interface::DexError;
 
+/// SPL Token / Token-2022 账户基础布局长度
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+
+/// Token-2022 扩展账户：基础布局后紧跟 1 字节 AccountType，再是扩展 TLV
+const ACCOUNT_TYPE_OFFSET: usize = TOKEN_ACCOUNT_LEN;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+const TLV_START: usize = ACCOUNT_TYPE_OFFSET + 1;
+
+/// SPL Token / Token-2022 mint 基础布局长度
+pub const MINT_LEN: usize = 82;
+
+/// Token-2022 扩展类型：TransferFeeConfig（mint 上的转账手续费参数）
+pub const EXTENSION_TRANSFER_FEE_CONFIG: u16 = 1;
+/// Token-2022 扩展类型：TransferFeeAmount（账户上累计的待提取转账手续费）
+pub const EXTENSION_TRANSFER_FEE_AMOUNT: u16 = 2;
+
+pub const SPL_TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+pub const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+
+/// 账户 owner 是否为 SPL Token 或 Token-2022 程序
+pub fn is_token_program(owner: &str) -> bool {
+    owner == SPL_TOKEN_PROGRAM_ID || owner == TOKEN_2022_PROGRAM_ID
+}
+
 /// SPL Token Account State
 /// 
-/// This matches the on-chain layout of an SPL Token account (165 bytes total)
+/// This matches the on-chain base layout of an SPL Token account (165 bytes).
+/// Token-2022 accounts share the same base layout and append extensions after it.
 #[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
 pub struct TokenAccount {
     /// The mint associated with this account
@@ -36,22 +62,48 @@ pub struct TokenAccount {
 impl TokenAccount {
     /// Deserialize from account data
     /// 
-    /// SPL Token accounts are 165 bytes:
+    /// SPL Token accounts are 165 bytes, Token-2022 accounts are 165 bytes plus extensions:
     /// - No discriminator (not an Anchor account)
-    /// - Direct Borsh deserialization
+    /// - Pack layout (COption = 4-byte tag), not Borsh
+    /// - Trailing Token-2022 extensions are ignored here, see `parse_token_account`
     pub fn from_account_data(data: &[u8]) -> Result<Self, DexError> {
-        if data.len() != 165 {
+        if data.len() < TOKEN_ACCOUNT_LEN {
             return Err(DexError::DeserializationFailed(format!(
-                "SPL Token account: Expected 165 bytes, got {} bytes",
+                "SPL Token account: Expected at least {} bytes, got {} bytes",
+                TOKEN_ACCOUNT_LEN,
                 data.len()
             )));
         }
         
-        Self::try_from_slice(data)
-            .map_err(|e| DexError::DeserializationFailed(format!(
-                "SPL Token account: Borsh deserialization failed: {}",
-                e
-            )))
+        let pubkey_at = |offset: usize| Pubkey::new_from_array(data[offset..offset + 32].try_into().unwrap());
+        let u64_at = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+        let coption_tag = |offset: usize| -> Result<bool, DexError> {
+            match u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) {
+                0 => Ok(false),
+                1 => Ok(true),
+                tag => Err(DexError::DeserializationFailed(format!(
+                    "SPL Token account: invalid COption tag {} at offset {}", tag, offset
+                ))),
+            }
+        };
+        
+        let state = data[108];
+        if state > 2 {
+            return Err(DexError::DeserializationFailed(format!(
+                "SPL Token account: invalid account state {}", state
+            )));
+        }
+        
+        Ok(Self {
+            mint: pubkey_at(0),
+            owner: pubkey_at(32),
+            amount: u64_at(64),
+            delegate: coption_tag(72)?.then(|| pubkey_at(76)),
+            state,
+            is_native: coption_tag(109)?.then(|| u64_at(113)),
+            delegated_amount: u64_at(121),
+            close_authority: coption_tag(129)?.then(|| pubkey_at(133)),
+        })
     }
     
     /// Get the token amount as a human-readable value
@@ -70,6 +122,243 @@ impl TokenAccount {
     }
 }
 
+/// Token 程序（按账户布局判断）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenProgram {
+    SplToken,
+    Token2022,
+}
+
+/// 解析后的 token 账户：基础字段 + Token-2022 扩展
+#[derive(Debug, Clone)]
+pub struct ParsedTokenAccount {
+    pub account: TokenAccount,
+    pub program: TokenProgram,
+    /// 账户上的扩展类型（按 TLV 顺序）
+    pub extensions: Vec<u16>,
+    /// TransferFeeAmount 扩展中累计的待提取手续费
+    pub withheld_transfer_fee: Option<u64>,
+}
+
+impl ParsedTokenAccount {
+    /// 可用于交换的余额：冻结账户视为 0
+    pub fn effective_amount(&self) -> u64 {
+        if self.account.is_frozen() {
+            0
+        } else {
+            self.account.amount
+        }
+    }
+    
+    pub fn has_extension(&self, extension_type: u16) -> bool {
+        self.extensions.contains(&extension_type)
+    }
+}
+
+/// 解析 SPL Token / Token-2022 账户
+///
+/// 165 字节为经典布局；更长的账户按 Token-2022 解析：
+/// 第 165 字节为 AccountType（必须是 Account），其后是 (type u16, length u16, value) TLV 扩展。
+/// 余额只取基础布局中的 amount，与尾部扩展无关。
+pub fn parse_token_account(data: &[u8]) -> Result<ParsedTokenAccount, DexError> {
+    let account = TokenAccount::from_account_data(data)?;
+    if !account.is_initialized() {
+        return Err(DexError::DeserializationFailed(
+            "SPL Token account: account is not initialized".to_string()
+        ));
+    }
+    
+    if data.len() == TOKEN_ACCOUNT_LEN {
+        return Ok(ParsedTokenAccount {
+            account,
+            program: TokenProgram::SplToken,
+            extensions: Vec::new(),
+            withheld_transfer_fee: None,
+        });
+    }
+    
+    if data[ACCOUNT_TYPE_OFFSET] != ACCOUNT_TYPE_ACCOUNT {
+        return Err(DexError::DeserializationFailed(format!(
+            "Token-2022 account: unexpected account type {}",
+            data[ACCOUNT_TYPE_OFFSET]
+        )));
+    }
+    
+    let mut extensions = Vec::new();
+    let mut withheld_transfer_fee = None;
+    for (extension_type, value) in parse_extensions(data, "Token-2022 account")? {
+        if extension_type == EXTENSION_TRANSFER_FEE_AMOUNT && value.len() >= 8 {
+            withheld_transfer_fee = Some(u64::from_le_bytes(value[..8].try_into().unwrap()));
+        }
+        extensions.push(extension_type);
+    }
+    
+    Ok(ParsedTokenAccount {
+        account,
+        program: TokenProgram::Token2022,
+        extensions,
+        withheld_transfer_fee,
+    })
+}
+
+/// 遍历 Token-2022 扩展 TLV：(type u16, length u16, value)
+///
+/// 账户和 mint 的 TLV 都从第 166 字节开始；类型 0 表示未初始化的尾部填充。
+fn parse_extensions<'a>(data: &'a [u8], kind: &str) -> Result<Vec<(u16, &'a [u8])>, DexError> {
+    let mut extensions = Vec::new();
+    let mut offset = TLV_START;
+    while offset + 4 <= data.len() {
+        let extension_type = u16::from_le_bytes([data[offset], data[offset + 1]]);
+        let length = u16::from_le_bytes([data[offset + 2], data[offset + 3]]) as usize;
+        if extension_type == 0 {
+            break;
+        }
+        let value_start = offset + 4;
+        let value_end = value_start + length;
+        if value_end > data.len() {
+            return Err(DexError::DeserializationFailed(format!(
+                "{}: extension {} overruns account data ({} > {})",
+                kind, extension_type, value_end, data.len()
+            )));
+        }
+        extensions.push((extension_type, &data[value_start..value_end]));
+        offset = value_end;
+    }
+    Ok(extensions)
+}
+
+/// 单个 epoch 区间的转账手续费参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferFee {
+    /// 从该 epoch 开始生效
+    pub epoch: u64,
+    /// 单笔手续费上限（base units）
+    pub maximum_fee: u64,
+    pub basis_points: u16,
+}
+
+impl TransferFee {
+    /// 转账 amount 时扣除的手续费（与链上一致：向上取整，不超过上限）
+    #[allow(dead_code)]
+    pub fn calculate_fee(&self, amount: u64) -> u64 {
+        if self.basis_points == 0 || amount == 0 {
+            return 0;
+        }
+        let fee = (amount as u128 * self.basis_points as u128 + 9_999) / 10_000;
+        (fee as u64).min(self.maximum_fee)
+    }
+}
+
+/// Token-2022 TransferFeeConfig 扩展
+///
+/// 链上保存新旧两组参数：newer 在其 epoch 到达后生效，之前仍按 older 收费。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferFeeConfig {
+    #[allow(dead_code)]
+    pub withheld_amount: u64,
+    pub older_transfer_fee: TransferFee,
+    pub newer_transfer_fee: TransferFee,
+}
+
+impl TransferFeeConfig {
+    /// 扩展数据长度：两个 authority + withheld + 两组 TransferFee
+    pub const LEN: usize = 32 + 32 + 8 + 18 + 18;
+    
+    fn unpack(value: &[u8]) -> Result<Self, DexError> {
+        if value.len() < Self::LEN {
+            return Err(DexError::DeserializationFailed(format!(
+                "TransferFeeConfig: expected {} bytes, got {}", Self::LEN, value.len()
+            )));
+        }
+        let u64_at = |offset: usize| u64::from_le_bytes(value[offset..offset + 8].try_into().unwrap());
+        let fee_at = |offset: usize| TransferFee {
+            epoch: u64_at(offset),
+            maximum_fee: u64_at(offset + 8),
+            basis_points: u16::from_le_bytes([value[offset + 16], value[offset + 17]]),
+        };
+        Ok(Self {
+            withheld_amount: u64_at(64),
+            older_transfer_fee: fee_at(72),
+            newer_transfer_fee: fee_at(90),
+        })
+    }
+    
+    /// 指定 epoch 生效的手续费参数
+    pub fn fee_for_epoch(&self, epoch: u64) -> &TransferFee {
+        if epoch >= self.newer_transfer_fee.epoch {
+            &self.newer_transfer_fee
+        } else {
+            &self.older_transfer_fee
+        }
+    }
+    
+    /// epoch 未知时取两组中较高的费率（宁可低估套利收益）
+    pub fn max_fee(&self) -> &TransferFee {
+        if self.newer_transfer_fee.basis_points >= self.older_transfer_fee.basis_points {
+            &self.newer_transfer_fee
+        } else {
+            &self.older_transfer_fee
+        }
+    }
+}
+
+/// 解析后的 mint：decimals + Token-2022 扩展
+#[derive(Debug, Clone)]
+pub struct ParsedMint {
+    pub decimals: u8,
+    pub program: TokenProgram,
+    #[allow(dead_code)]
+    pub extensions: Vec<u16>,
+    pub transfer_fee_config: Option<TransferFeeConfig>,
+}
+
+/// 解析 SPL Token / Token-2022 mint 账户
+///
+/// 基础布局 82 字节（decimals 在第 44 字节）；Token-2022 mint 带扩展时
+/// 填充到 165 字节，第 165 字节为 AccountType（Mint），其后是 TLV 扩展。
+pub fn parse_mint(data: &[u8]) -> Result<ParsedMint, DexError> {
+    if data.len() < MINT_LEN {
+        return Err(DexError::DeserializationFailed(format!(
+            "Mint account: Expected at least {} bytes, got {} bytes",
+            MINT_LEN,
+            data.len()
+        )));
+    }
+    let decimals = data[44];
+    
+    if data.len() <= ACCOUNT_TYPE_OFFSET {
+        return Ok(ParsedMint {
+            decimals,
+            program: TokenProgram::SplToken,
+            extensions: Vec::new(),
+            transfer_fee_config: None,
+        });
+    }
+    
+    if data[ACCOUNT_TYPE_OFFSET] != ACCOUNT_TYPE_MINT {
+        return Err(DexError::DeserializationFailed(format!(
+            "Token-2022 mint: unexpected account type {}",
+            data[ACCOUNT_TYPE_OFFSET]
+        )));
+    }
+    
+    let mut extensions = Vec::new();
+    let mut transfer_fee_config = None;
+    for (extension_type, value) in parse_extensions(data, "Token-2022 mint")? {
+        if extension_type == EXTENSION_TRANSFER_FEE_CONFIG {
+            transfer_fee_config = Some(TransferFeeConfig::unpack(value)?);
+        }
+        extensions.push(extension_type);
+    }
+    
+    Ok(ParsedMint {
+        decimals,
+        program: TokenProgram::Token2022,
+        extensions,
+        transfer_fee_config,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,7 +395,116 @@ mod tests {
         assert!(account.is_initialized());
         assert!(!account.is_frozen());
     }
+    
+    /// 经典 SPL Token 账户（165 字节）
+    fn vanilla_account(amount: u64, state: u8) -> Vec<u8> {
+        let mut data = vec![0u8; TOKEN_ACCOUNT_LEN];
+        data[0..32].copy_from_slice(&[7u8; 32]);   // mint
+        data[32..64].copy_from_slice(&[9u8; 32]);  // owner
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        data[108] = state;
+        data
+    }
+    
+    /// Token-2022 账户：TransferFeeAmount + ImmutableOwner 扩展
+    fn token_2022_account_with_transfer_fee(amount: u64, withheld: u64) -> Vec<u8> {
+        let mut data = vanilla_account(amount, 1);
+        data.push(ACCOUNT_TYPE_ACCOUNT);
+        data.extend_from_slice(&EXTENSION_TRANSFER_FEE_AMOUNT.to_le_bytes());
+        data.extend_from_slice(&8u16.to_le_bytes());
+        data.extend_from_slice(&withheld.to_le_bytes());
+        data.extend_from_slice(&7u16.to_le_bytes());  // ImmutableOwner
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data
+    }
+    
+    #[test]
+    fn test_parse_vanilla_account() {
+        let parsed = parse_token_account(&vanilla_account(1_500_000, 1)).unwrap();
+        assert_eq!(parsed.program, TokenProgram::SplToken);
+        assert_eq!(parsed.account.amount, 1_500_000);
+        assert_eq!(parsed.account.mint, Pubkey::new_from_array([7u8; 32]));
+        assert_eq!(parsed.account.delegate, None);
+        assert_eq!(parsed.effective_amount(), 1_500_000);
+        assert!(parsed.extensions.is_empty());
+    }
+    
+    #[test]
+    fn test_parse_token_2022_account_with_transfer_fee() {
+        let data = token_2022_account_with_transfer_fee(42_000_000, 1_234);
+        assert_eq!(data.len(), 182);
+        
+        let parsed = parse_token_account(&data).unwrap();
+        assert_eq!(parsed.program, TokenProgram::Token2022);
+        assert_eq!(parsed.account.amount, 42_000_000);
+        assert_eq!(parsed.extensions, vec![EXTENSION_TRANSFER_FEE_AMOUNT, 7]);
+        assert!(parsed.has_extension(EXTENSION_TRANSFER_FEE_AMOUNT));
+        assert_eq!(parsed.withheld_transfer_fee, Some(1_234));
+        
+        // 尾部未初始化的填充不影响解析
+        let mut padded = data.clone();
+        padded.extend_from_slice(&[0u8; 16]);
+        assert_eq!(parse_token_account(&padded).unwrap().extensions.len(), 2);
+        
+        // 扩展长度越界
+        assert!(parse_token_account(&data[..175]).is_err());
+    }
+    
+    #[test]
+    fn test_frozen_account_has_zero_effective_amount() {
+        let parsed = parse_token_account(&vanilla_account(5_000, 2)).unwrap();
+        assert!(parsed.account.is_frozen());
+        assert_eq!(parsed.account.amount, 5_000);
+        assert_eq!(parsed.effective_amount(), 0);
+        
+        assert!(parse_token_account(&vanilla_account(5_000, 0)).is_err());
+        assert!(parse_token_account(&[0u8; 82]).is_err());
+    }
+    
+    /// Token-2022 mint：TransferFeeConfig（older 0.5%，newer 从 epoch 600 起 1%，上限 5000）
+    fn token_2022_mint_with_transfer_fee() -> Vec<u8> {
+        let mut data = vec![0u8; TOKEN_ACCOUNT_LEN];
+        data[44] = 6;   // decimals
+        data[45] = 1;   // is_initialized
+        data.push(ACCOUNT_TYPE_MINT);
+        data.extend_from_slice(&EXTENSION_TRANSFER_FEE_CONFIG.to_le_bytes());
+        data.extend_from_slice(&(TransferFeeConfig::LEN as u16).to_le_bytes());
+        data.extend_from_slice(&[0u8; 64]);                  // authorities
+        data.extend_from_slice(&77u64.to_le_bytes());        // withheld_amount
+        for (epoch, bps) in [(0u64, 50u16), (600, 100)] {
+            data.extend_from_slice(&epoch.to_le_bytes());
+            data.extend_from_slice(&5_000u64.to_le_bytes());
+            data.extend_from_slice(&bps.to_le_bytes());
+        }
+        data
+    }
+    
+    #[test]
+    fn test_parse_mint_transfer_fee_config() {
+        let mut classic = vec![0u8; MINT_LEN];
+        classic[44] = 9;
+        let parsed = parse_mint(&classic).unwrap();
+        assert_eq!(parsed.decimals, 9);
+        assert_eq!(parsed.program, TokenProgram::SplToken);
+        assert!(parsed.transfer_fee_config.is_none());
+        
+        let parsed = parse_mint(&token_2022_mint_with_transfer_fee()).unwrap();
+        assert_eq!(parsed.decimals, 6);
+        assert_eq!(parsed.program, TokenProgram::Token2022);
+        assert_eq!(parsed.extensions, vec![EXTENSION_TRANSFER_FEE_CONFIG]);
+        
+        let config = parsed.transfer_fee_config.unwrap();
+        assert_eq!(config.withheld_amount, 77);
+        assert_eq!(config.fee_for_epoch(599).basis_points, 50);
+        assert_eq!(config.fee_for_epoch(600).basis_points, 100);
+        assert_eq!(config.max_fee().basis_points, 100);
+        
+        // 向上取整，且不超过上限
+        let fee = config.fee_for_epoch(600);
+        assert_eq!(fee.calculate_fee(1_001), 11);
+        assert_eq!(fee.calculate_fee(10_000_000), 5_000);
+        
+        // account 类型的 TLV 不能当作 mint 解析
+        assert!(parse_mint(&token_2022_account_with_transfer_fee(1, 1)).is_err());
+    }
 }
-
-
-
@@ -1,5 +1,7 @@
 use solana_sdk::pubkey::Pubkey;
 use crate::dex_interface::{DexPool, DexError};
+use crate::fee_registry::FeeRegistry;
+use crate::stable_math;
 
 /// Stabble Stable Swap Pool State
 /// 
@@ -108,8 +110,13 @@ impl StabblePoolState {
     }
     
     /// Calculate price (token B per token A)
-    /// For stable swap pools, price should be close to 1.0
+    /// 
+    /// StableSwap 边际价格（当前余额下的 dy/dx），锚定附近远比储备比例平缓；
+    /// amp 为 0 时回退到储备比例
     pub fn calculate_price(&self) -> f64 {
+        if let Some(price) = stable_math::marginal_price((self.reserve_a, self.reserve_b), self.amplification_coefficient) {
+            return price;
+        }
         if self.reserve_a == 0 {
             return 0.0;
         }
@@ -187,6 +194,22 @@ impl DexPool for StabblePoolState {
         self.reserve_a > 0 || self.reserve_b > 0
     }
     
+    /// 有储备和 amp 时按 StableSwap 曲线报价（经 orderbook_cache 接入路由器单跳计算）
+    fn has_orderbook(&self) -> bool {
+        stable_math::compute_d((self.reserve_a, self.reserve_b), self.amplification_coefficient).is_some()
+    }
+    
+    fn get_orderbook_quote(&self, amount_in: f64, is_buy: bool) -> Option<f64> {
+        stable_math::quote_ui(
+            amount_in,
+            is_buy,
+            (self.reserve_a, self.reserve_b),
+            self.get_decimals(),
+            self.amplification_coefficient,
+            FeeRegistry::dex_default(self.dex_name()),
+        )
+    }
+    
     fn get_additional_info(&self) -> Option<String> {
         let (res_a, res_b) = self.get_reserves_formatted();
         Some(format!(
@@ -258,6 +281,27 @@ mod tests {
         let pool = StabblePoolState::from_bytes(&data).unwrap();
         assert!(pool.is_active(), "Pool with reserves should be active");
     }
+    
+    #[test]
+    fn test_stable_curve_quote_and_price() {
+        let mut data = vec![0u8; 438];
+        // 1.5M / 0.5M，amp 100
+        data[104..112].copy_from_slice(&1_500_000_000_000u64.to_le_bytes());
+        data[168..176].copy_from_slice(&500_000_000_000u64.to_le_bytes());
+        data[272..280].copy_from_slice(&100u64.to_le_bytes());
+        let pool = StabblePoolState::from_bytes(&data).unwrap();
+        
+        // 边际价格远离储备比例 0.333
+        let price = DexPool::calculate_price(&pool);
+        assert!(price > 0.95 && price < 1.0, "price {}", price);
+        
+        // 卖出 1000 A：接近边际价格减手续费；反方向（花 B 买 A）多于 1:1
+        assert!(pool.has_orderbook());
+        let sell = pool.get_orderbook_quote(1_000.0, false).unwrap();
+        assert!((sell - 1_000.0 * price * (1.0 - 0.0004)).abs() < 1.0, "sell {}", sell);
+        let buy = pool.get_orderbook_quote(1_000.0, true).unwrap();
+        assert!(buy > 1_000.0, "buy {}", buy);
+    }
 }
 
 
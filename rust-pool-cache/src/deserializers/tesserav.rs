@@ -1,5 +1,9 @@
 use solana_sdk::pubkey::Pubkey;
 use crate::dex_interface::{DexPool, DexError};
+use crate::mint_decimals_cache;
+
+/// mint 元数据未缓存时的 decimals 猜测（已知池子都是 SOL/USDC）
+const GUESSED_DECIMALS: (u8, u8) = (9, 6);
 
 /// TesseraV Pool State
 /// 
@@ -97,17 +101,28 @@ impl TesseraVPoolState {
             return 0.0;
         }
         
-        // USDC/SOL 池子，需要调整decimals
-        // reserve_a 是 SOL (9 decimals)
-        // reserve_b 是 USDC (6 decimals)
-        let reserve_a_f64 = self.reserve_a as f64 / 1e9; // SOL
-        let reserve_b_f64 = self.reserve_b as f64 / 1e6; // USDC
+        // 📐 decimals 按 mint 解析（未缓存时按 SOL 9 / USDC 6 猜测）
+        let (decimals_a, decimals_b) = self.decimals();
+        let (reserve_a_f64, reserve_b_f64) = self.get_reserves_formatted(decimals_a as i32, decimals_b as i32);
         
         if reserve_a_f64 == 0.0 {
             return 0.0;
         }
         
-        reserve_b_f64 / reserve_a_f64 // USDC per SOL
+        reserve_b_f64 / reserve_a_f64 // token B per token A
+    }
+    
+    /// (decimals, 是否仍含猜测值)
+    fn resolved_decimals(&self) -> ((u8, u8), bool) {
+        mint_decimals_cache::resolve_decimals("TesseraV", self.mints(), GUESSED_DECIMALS)
+    }
+    
+    fn decimals(&self) -> (u8, u8) {
+        self.resolved_decimals().0
+    }
+    
+    fn mints(&self) -> Option<(Pubkey, Pubkey)> {
+        Some((self.token_a_mint?, self.token_b_mint?))
     }
     
     /// Get human-readable reserve amounts
@@ -119,9 +134,10 @@ impl TesseraVPoolState {
     
     /// Get pool information for debugging
     pub fn get_pool_info(&self) -> String {
-        let (reserve_a, reserve_b) = self.get_reserves_formatted(9, 6);
+        let (decimals_a, decimals_b) = self.decimals();
+        let (reserve_a, reserve_b) = self.get_reserves_formatted(decimals_a as i32, decimals_b as i32);
         format!(
-            "TesseraV Pool:\n  Reserve A: {:.2} SOL\n  Reserve B: {:.2} USDC\n  Price: {:.2} USDC/SOL",
+            "TesseraV Pool:\n  Reserve A: {:.2}\n  Reserve B: {:.2}\n  Price: {:.6} B/A",
             reserve_a,
             reserve_b,
             self.calculate_price()
@@ -155,9 +171,15 @@ impl DexPool for TesseraVPoolState {
     }
     
     fn get_decimals(&self) -> (u8, u8) {
-        // TesseraV USDC/SOL池子
-        // SOL = 9 decimals, USDC = 6 decimals
-        (9, 6)
+        self.decimals()
+    }
+    
+    fn get_mints(&self) -> Option<(Pubkey, Pubkey)> {
+        self.mints()
+    }
+    
+    fn decimals_guessed(&self) -> bool {
+        self.resolved_decimals().1
     }
     
     fn is_active(&self) -> bool {
@@ -166,9 +188,10 @@ impl DexPool for TesseraVPoolState {
     }
     
     fn get_additional_info(&self) -> Option<String> {
-        let (res_a, res_b) = self.get_reserves_formatted(9, 6);
+        let (decimals_a, decimals_b) = self.decimals();
+        let (res_a, res_b) = self.get_reserves_formatted(decimals_a as i32, decimals_b as i32);
         Some(format!(
-            "Reserves: A={:.2} SOL, B={:.2} USDC, Price: {:.2}",
+            "Reserves: A={:.2}, B={:.2}, Price: {:.6}",
             res_a,
             res_b,
             self.calculate_price()
@@ -216,6 +239,38 @@ mod tests {
         assert!((price - 150.0).abs() < 0.01, "Price should be around $150");
     }
     
+    #[test]
+    fn test_decimals_resolved_from_mint_cache() {
+        use crate::deserializers::spl_token::TokenProgram;
+        use crate::mint_decimals_cache::{get_global_mint_cache, init_global_mint_cache, MintInfo};
+        use crate::pool_fixture::{fixture_path, PoolFixture};
+        use crate::rpc_manager::RpcManager;
+        use std::path::Path;
+        
+        // 真实 TesseraV 账户，token A 换成一个 8 位小数的 mint：猜测的 9 位会让价格偏 10 倍
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let mut data = PoolFixture::load(&fixture_path(&dir, "tesserav")).unwrap().decode_data().unwrap();
+        let mint_a = Pubkey::new_unique();
+        data[24..56].copy_from_slice(mint_a.as_ref());
+        
+        let pool = TesseraVPoolState::from_bytes(&data).unwrap();
+        let guessed_price = pool.calculate_price();
+        assert!(guessed_price > 0.0);
+        assert!(pool.decimals_guessed());
+        assert_eq!(pool.get_decimals(), (9, 6));
+        
+        init_global_mint_cache(std::sync::Arc::new(RpcManager::unlimited("http://127.0.0.1:8899")).handle("mint_cache"));
+        let cache = get_global_mint_cache().unwrap();
+        let usdc = pool.token_b_mint.unwrap();
+        cache.insert_info(mint_a, MintInfo { decimals: 8, program: TokenProgram::SplToken, transfer_fee: None });
+        cache.insert_info(usdc, MintInfo { decimals: 6, program: TokenProgram::SplToken, transfer_fee: None });
+        
+        assert!(!pool.decimals_guessed());
+        assert_eq!(pool.get_decimals(), (8, 6));
+        let price = pool.calculate_price();
+        assert!((price - guessed_price / 10.0).abs() < guessed_price * 1e-9, "price {} vs guess {}", price, guessed_price);
+    }
+    
     #[test]
     fn test_is_active() {
         let mut data = vec![0u8; 1264];
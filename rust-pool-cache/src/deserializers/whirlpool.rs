@@ -1,8 +1,11 @@
 use borsh::BorshDeserialize;
 use solana_sdk::pubkey::Pubkey;
-use crate::dex_interface::{DexPool, DexError};
+use crate::dex_interface::{ActivityStatus, DexPool, DexError};
 use crate::mint_decimals_cache::get_global_mint_cache;
 
+/// Whirlpool fee_rate 以百分之一 bps 存储（3000 = 0.30%）
+pub const FEE_RATE_DENOMINATOR: f64 = 1_000_000.0;
+
 /// Orca Whirlpool Pool State (wrapper for official Orca SDK type)
 /// 
 /// Whirlpool is Orca's concentrated liquidity market maker (CLMM)
@@ -46,14 +49,17 @@ impl WhirlpoolState {
     /// // 如果 sqrt_price 计算得到 0.014，最终价格为 0.014 × 1000 = 14.0
     /// ```
     pub fn calculate_price_with_decimals(&self, base_decimals: u8, quote_decimals: u8) -> f64 {
-        if self.inner.sqrt_price == 0 {
-            return 0.0;
-        }
-
         // 1. 计算 sqrt_price 部分: (sqrt_price / 2^64)^2
-        const Q64: f64 = (1u128 << 64) as f64;
-        let sqrt_price_f64 = self.inner.sqrt_price as f64 / Q64;
-        let sqrt_price_squared = sqrt_price_f64 * sqrt_price_f64;
+        //    sqrt_price 缺失时由当前 tick 推导（1.0001^tick），未初始化的池子才返回 0
+        let sqrt_price_squared = if self.inner.sqrt_price > 0 {
+            const Q64: f64 = (1u128 << 64) as f64;
+            let sqrt_price_f64 = self.inner.sqrt_price as f64 / Q64;
+            sqrt_price_f64 * sqrt_price_f64
+        } else if self.is_initialized() {
+            self.tick_price()
+        } else {
+            return 0.0;
+        };
 
         // 2. 计算 decimals 调整因子: 10^(base_decimals - quote_decimals)
         //    当 base_decimals > quote_decimals 时，需要放大价格
@@ -65,6 +71,16 @@ impl WhirlpoolState {
         sqrt_price_squared * decimals_factor
     }
 
+    /// 当前 tick 对应的原始价格（未做 decimals 调整）：1.0001^tick
+    pub fn tick_price(&self) -> f64 {
+        1.0001f64.powi(self.inner.tick_current_index)
+    }
+    
+    /// 池子已初始化（tick_spacing 在初始化时写入，全零账户为 0）
+    pub fn is_initialized(&self) -> bool {
+        self.inner.tick_spacing > 0
+    }
+
     /// 兼容旧接口的 calculate_price 方法（默认 decimals）
     ///
     /// 对于 Orca Whirlpool，默认假设：
@@ -123,10 +139,10 @@ impl DexPool for WhirlpoolState {
     fn get_decimals(&self) -> (u8, u8) {
         if let Some(cache) = get_global_mint_cache() {
             let base = cache
-                .get_or_fetch_decimals(&self.inner.token_mint_a)
+                .get_or_fetch_decimals(&Pubkey::new_from_array(self.inner.token_mint_a.to_bytes()))
                 .unwrap_or(9);
             let quote = cache
-                .get_or_fetch_decimals(&self.inner.token_mint_b)
+                .get_or_fetch_decimals(&Pubkey::new_from_array(self.inner.token_mint_b.to_bytes()))
                 .unwrap_or(6);
             (base, quote)
         } else {
@@ -139,17 +155,34 @@ impl DexPool for WhirlpoolState {
         self.inner.liquidity > 0
     }
     
+    fn activity_status(&self) -> ActivityStatus {
+        if self.inner.liquidity == 0 {
+            ActivityStatus::NoLiquidity
+        } else {
+            ActivityStatus::Active
+        }
+    }
+    
     fn get_additional_info(&self) -> Option<String> {
         Some(format!(
-            "Liquidity: {:.2}, Tick: {}, Fee: {}bps",
+            "Liquidity: {:.2}, Tick: {} (spacing {}), Fee: {:.4}%",
             self.inner.liquidity as f64,
             self.inner.tick_current_index,
-            self.inner.fee_rate
+            self.inner.tick_spacing,
+            self.inner.fee_rate as f64 / FEE_RATE_DENOMINATOR * 100.0
         ))
     }
     
+    fn get_fee_rate(&self) -> Option<f64> {
+        Some(self.inner.fee_rate as f64 / FEE_RATE_DENOMINATOR)
+    }
+    
     fn get_vault_addresses(&self) -> Option<(Pubkey, Pubkey)> {
-        Some((self.inner.token_vault_a, self.inner.token_vault_b))
+        Some((Pubkey::new_from_array(self.inner.token_vault_a.to_bytes()), Pubkey::new_from_array(self.inner.token_vault_b.to_bytes())))
+    }
+    
+    fn get_mints(&self) -> Option<(Pubkey, Pubkey)> {
+        Some((Pubkey::new_from_array(self.inner.token_mint_a.to_bytes()), Pubkey::new_from_array(self.inner.token_mint_b.to_bytes())))
     }
 }
 
@@ -194,13 +227,91 @@ mod tests {
 
                 println!("📊 Liquidity: {}", pool.inner.liquidity);
                 println!("📊 Tick: {}", pool.inner.tick_current_index);
-                println!("📊 Fee: {} bps", pool.inner.fee_rate);
+                println!("📊 Fee rate: {} (1e-6)", pool.inner.fee_rate);
                 println!("📊 Sqrt Price: {}", pool.inner.sqrt_price);
                 println!("✅ Price calculation test passed!");
             }
         }
     }
+    
+    /// fee_rate 位于 discriminator(8) + whirlpools_config(32) + bump(1) + tick_spacing(2) + fee_tier_index_seed(2)
+    const FEE_RATE_OFFSET: usize = 45;
+    const SQRT_PRICE_OFFSET: usize = 65;
+    
+    /// 抓取时（2025-10 末）的 SOL 市场价
+    const SOL_MARKET_PRICE: f64 = 185.7;
+    
+    fn load_fixture(file_path: &str) -> Vec<u8> {
+        fs::read(file_path).unwrap_or_else(|e| panic!("missing fixture {}: {}", file_path, e))
+    }
+    
+    #[test]
+    fn test_fee_rate_from_captured_accounts() {
+        // 1 bps 稳定币池 vs 30 bps 池
+        let low = WhirlpoolState::from_account_data(&load_fixture("account_data/USDC-USDT-Whirlpool_653.bin")).unwrap();
+        let high = WhirlpoolState::from_account_data(&load_fixture("account_data/USDC-USDT-Whirlpool-2_653.bin")).unwrap();
+        
+        assert!((low.get_fee_rate().unwrap() - 0.0001).abs() < 1e-12);
+        assert!((high.get_fee_rate().unwrap() - 0.003).abs() < 1e-12);
+    }
+    
+    #[test]
+    fn test_fee_rate_distinguishes_25bps_and_1bps() {
+        let one_bps = load_fixture("account_data/USDC-USDT-Whirlpool_653.bin");
+        
+        // 同一份账户数据，只把 fee_rate 改为 2500（0.25% 费率档）
+        let mut quarter_pct = one_bps.clone();
+        quarter_pct[FEE_RATE_OFFSET..FEE_RATE_OFFSET + 2].copy_from_slice(&2500u16.to_le_bytes());
+        
+        let low = WhirlpoolState::from_account_data(&one_bps).unwrap();
+        let high = WhirlpoolState::from_account_data(&quarter_pct).unwrap();
+        
+        assert_eq!(high.inner().fee_rate, 2500);
+        assert!((low.get_fee_rate().unwrap() - 0.0001).abs() < 1e-12);
+        assert!((high.get_fee_rate().unwrap() - 0.0025).abs() < 1e-12);
+        // 其余字段不受影响
+        assert_eq!(low.inner().sqrt_price, high.inner().sqrt_price);
+    }
+    
+    fn assert_within(price: f64, expected: f64, tolerance: f64, what: &str) {
+        let deviation = (price - expected).abs() / expected;
+        assert!(deviation < tolerance, "{}: price {:.6} deviates {:.3}% from {:.6}", what, price, deviation * 100.0, expected);
+    }
+    
+    #[test]
+    fn test_price_matches_market_price_of_captured_accounts() {
+        for (file, decimals, expected) in [
+            ("account_data/SOL-USDC-Whirlpool_653.bin", (9, 6), SOL_MARKET_PRICE),
+            ("account_data/SOL-USDT-Whirlpool_653.bin", (9, 6), SOL_MARKET_PRICE),
+            ("account_data/USDC-USDT-Whirlpool_653.bin", (6, 6), 1.0),
+        ] {
+            let pool = WhirlpoolState::from_account_data(&load_fixture(file)).unwrap();
+            let price = pool.calculate_price_with_decimals(decimals.0, decimals.1);
+            assert_within(price, expected, 0.005, file);
+            
+            // tick 推导的价格与 sqrt_price 一致（tick 粒度内）
+            let factor = 10f64.powi(decimals.0 as i32 - decimals.1 as i32);
+            assert_within(pool.tick_price() * factor, price, 0.005, file);
+        }
+    }
+    
+    #[test]
+    fn test_initialized_pool_without_sqrt_price_falls_back_to_tick() {
+        let mut data = load_fixture("account_data/SOL-USDC-Whirlpool_653.bin");
+        data[SQRT_PRICE_OFFSET..SQRT_PRICE_OFFSET + 16].fill(0);
+        
+        let pool = WhirlpoolState::from_account_data(&data).unwrap();
+        assert_eq!(pool.inner().sqrt_price, 0);
+        assert!(pool.is_initialized());
+        assert_within(pool.calculate_price_with_decimals(9, 6), SOL_MARKET_PRICE, 0.005, "tick fallback");
+        
+        // 全零账户（未初始化）→ 0
+        let empty = WhirlpoolState::from_account_data(&vec![0u8; data.len()]).unwrap();
+        assert!(!empty.is_initialized());
+        assert_eq!(empty.calculate_price_with_decimals(9, 6), 0.0);
+        
+        // 调试信息包含当前 tick
+        let info = pool.get_additional_info().unwrap();
+        assert!(info.contains(&format!("Tick: {}", pool.inner().tick_current_index)), "{}", info);
+    }
 }
-
-
-
@@ -1,4 +1,5 @@
 use std::fmt;
+use serde::Serialize;
 use solana_sdk::pubkey::Pubkey;
 
 /// Unified interface for all DEX pool types
@@ -36,6 +37,18 @@ pub trait DexPool: Send + Sync {
     /// Check if the pool is active and ready for trading
     fn is_active(&self) -> bool;
     
+    /// Pool-type-specific activity status, including why a pool is not usable
+    /// 
+    /// The default maps `is_active` for backward compatibility. Pools that know
+    /// *why* they are inactive override this and derive `is_active` from it.
+    fn activity_status(&self) -> ActivityStatus {
+        if self.is_active() {
+            ActivityStatus::Active
+        } else {
+            ActivityStatus::Unknown { reason: "is_active() returned false".to_string() }
+        }
+    }
+    
     /// Get additional pool-specific information for logging (optional)
     fn get_additional_info(&self) -> Option<String> {
         None
@@ -57,6 +70,122 @@ pub trait DexPool: Send + Sync {
     fn get_vault_addresses(&self) -> Option<(Pubkey, Pubkey)> {
         None // Default: no external vaults
     }
+    
+    /// Get the (base, quote) token mints, if the pool account stores them
+    /// 
+    /// Used to look up mint metadata such as Token-2022 transfer fees.
+    fn get_mints(&self) -> Option<(Pubkey, Pubkey)> {
+        None
+    }
+    
+    /// Whether `get_decimals` is still a hardcoded guess because the mint
+    /// metadata is not cached yet
+    /// 
+    /// The WebSocket update path fetches the mints of such pools in the background;
+    /// the next update then uses the real decimals.
+    fn decimals_guessed(&self) -> bool {
+        false
+    }
+    
+    /// On-chain swap fee rate as a decimal (e.g. 0.0025 for 0.25%), if the
+    /// pool account (or its config account) stores one
+    /// 
+    /// Routers prefer this over the name-based DEX default.
+    fn get_fee_rate(&self) -> Option<f64> {
+        None
+    }
+    
+    /// Separate account holding the pool's fee tier (e.g. the Raydium CLMM
+    /// `amm_config`), if `get_fee_rate` depends on one
+    /// 
+    /// The WebSocket update path prefetches it asynchronously; until it is
+    /// cached `get_fee_rate` returns None and routers use the DEX default.
+    fn fee_config_account(&self) -> Option<Pubkey> {
+        None
+    }
+    
+    /// Whether this pool exposes real order book levels (CLOBs like Phoenix)
+    fn has_orderbook(&self) -> bool {
+        false
+    }
+    
+    /// Quote an exact-in swap by walking order book levels (optional)
+    /// 
+    /// `amount_in` is in UI units of the input token. `is_buy = true` means
+    /// spending quote to buy base; `false` means selling base for quote.
+    /// 
+    /// # Returns
+    /// * `Some(amount_out)` - Output in UI units, taker fee included
+    /// * `None` - No order book; callers fall back to the AMM formula
+    fn get_orderbook_quote(&self, _amount_in: f64, _is_buy: bool) -> Option<f64> {
+        None
+    }
+}
+
+/// Why a pool is (or is not) usable for routing
+/// 
+/// Anything other than `Active` keeps the pool out of the routing snapshot;
+/// the variant is the exclusion reason shown in `/snapshot` and `/pools`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ActivityStatus {
+    /// Open for trading with liquidity
+    Active,
+    /// Open, but holds no liquidity (zero reserves / zero CLMM liquidity / empty book)
+    NoLiquidity,
+    /// Reserves live in external vault accounts that have not been read yet
+    AwaitingVaultData,
+    /// Market is past its expiry time
+    Expired,
+    /// Trading disabled by the pool's own status flags
+    Paused,
+    /// Not usable for another pool-specific reason
+    Unknown { reason: String },
+}
+
+impl ActivityStatus {
+    pub fn is_active(&self) -> bool {
+        matches!(self, ActivityStatus::Active)
+    }
+    
+    /// Whether the pool should stay subscribed and cached: active, or only
+    /// waiting for its vault balances (vault subscriptions start from here)
+    pub fn is_trackable(&self) -> bool {
+        matches!(self, ActivityStatus::Active | ActivityStatus::AwaitingVaultData)
+    }
+    
+    /// Short label for logs, metrics and API responses
+    pub fn label(&self) -> &'static str {
+        match self {
+            ActivityStatus::Active => "active",
+            ActivityStatus::NoLiquidity => "no_liquidity",
+            ActivityStatus::AwaitingVaultData => "awaiting_vault_data",
+            ActivityStatus::Expired => "expired",
+            ActivityStatus::Paused => "paused",
+            ActivityStatus::Unknown { .. } => "unknown",
+        }
+    }
+    
+    /// Resolve `AwaitingVaultData` once the vault balances are known
+    /// 
+    /// `vault_reserves` is what `VaultReader` has read for the pool (None while
+    /// no vault update has arrived). Other statuses are returned unchanged.
+    pub fn with_vault_reserves(self, vault_reserves: Option<(u64, u64)>) -> Self {
+        match (self, vault_reserves) {
+            (ActivityStatus::AwaitingVaultData, Some((0, _) | (_, 0))) => ActivityStatus::NoLiquidity,
+            (ActivityStatus::AwaitingVaultData, Some(_)) => ActivityStatus::Active,
+            (status, _) => status,
+        }
+    }
+}
+
+impl fmt::Display for ActivityStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActivityStatus::Unknown { reason } => write!(f, "unknown ({})", reason),
+            status => f.write_str(status.label()),
+        }
+    }
 }
 
 /// Errors that can occur during DEX pool operations
@@ -81,6 +210,12 @@ pub enum DexError {
     
     /// Validation failed (e.g., struct size mismatch)
     ValidationFailed(String),
+    
+    /// Account owner does not match the program expected for the pool type
+    OwnerMismatch { expected: String, actual: String },
+    
+    /// DEX disabled via the [dexes] config table
+    DexDisabled(String),
 }
 
 impl fmt::Display for DexError {
@@ -104,6 +239,12 @@ impl fmt::Display for DexError {
             DexError::ValidationFailed(msg) => {
                 write!(f, "Validation failed: {}", msg)
             }
+            DexError::OwnerMismatch { expected, actual } => {
+                write!(f, "Owner mismatch: expected {}, got {}", expected, actual)
+            }
+            DexError::DexDisabled(dex) => {
+                write!(f, "DEX disabled: {}", dex)
+            }
         }
     }
 }
@@ -196,19 +337,52 @@ pub mod amm_calculator {
         numerator / denominator
     }
     
+    /// Calculate one routing hop, preferring order book levels when available
+    /// 
+    /// Pools registered in `orderbook_cache` (Phoenix) are quoted by walking
+    /// their bid/ask levels; everything else uses the constant product formula.
+    /// `pair` is the pool's canonical "BASE/QUOTE" key (aliases already
+    /// applied, see `token_graph::pair_key_in`) and `input_token` is one of
+    /// its two sides; it decides the book side.
+    /// 
+    /// Token-2022 transfer fees (from the global mint cache) are deducted
+    /// twice: on the transfer into the pool and on the transfer out of it.
+    pub fn calculate_hop_output_f64(
+        pool_id: &str,
+        pair: &str,
+        input_token: &str,
+        amount_in: f64,
+        reserve_in: f64,
+        reserve_out: f64,
+        fee_rate: f64,
+    ) -> f64 {
+        let (base, quote) = pair.split_once('/').unwrap_or((pair, ""));
+        let is_buy = quote == input_token;
+        let output_token = if is_buy { base } else { quote };
+        
+        let amount_in = apply_transfer_fee(input_token, amount_in);
+        let amount_out = crate::orderbook_cache::quote(pool_id, amount_in, is_buy)
+            .unwrap_or_else(|| calculate_amm_output_f64(amount_in, reserve_in, reserve_out, fee_rate));
+        apply_transfer_fee(output_token, amount_out)
+    }
+    
+    /// Amount received after transferring `amount` of `token` (UI units)
+    /// 
+    /// Returns `amount` unchanged for tokens without a Token-2022 transfer fee.
+    pub fn apply_transfer_fee(token: &str, amount: f64) -> f64 {
+        match crate::mint_decimals_cache::transfer_fee_for_token(token) {
+            Some(fee) => fee.apply(amount),
+            None => amount,
+        }
+    }
+    
     /// Get standard DEX fee rates
+    /// 
+    /// DEX-level defaults only; use `fee_registry::fee_rate` when the pool id
+    /// is known so per-pool `fee_bps` overrides apply.
+    #[allow(dead_code)]
     pub fn get_dex_fee_rate(dex_name: &str) -> f64 {
-        match dex_name.to_lowercase().as_str() {
-            "raydium" | "raydium amm v4" | "raydium_v4" | "amm_v4" => 0.0025,  // 0.25%
-            "orca" | "orca whirlpool" | "whirlpool" => 0.003,                   // 0.30%
-            "lifinity" | "lifinity v2" => 0.0,                                  // 0% (Market Maker model)
-            "meteora" | "meteora dlmm" => 0.0025,                               // 0.25%
-            "phoenix" | "openbook" | "openbook_v2" => 0.0,                      // 0% taker (CLOB)
-            "pancakeswap" | "pcs" => 0.0025,                                    // 0.25%
-            "aldrin" => 0.002,                                                  // 0.20%
-            "saber" => 0.0004,                                                  // 0.04% (stableswap)
-            _ => 0.003,                                                         // Default 0.30%
-        }
+        crate::fee_registry::FeeRegistry::dex_default(dex_name)
     }
     
     #[cfg(test)]
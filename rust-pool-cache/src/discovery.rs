@@ -0,0 +1,412 @@
+/*!
+ * 🔭 池子自动发现
+ *
+ * 启动时对每个启用的 DEX program 做 getProgramAccounts：
+ * dataSize = 池子账户大小，memcmp = 两个 mint 字段分别等于一对目标代币。
+ * 候选池子经 PoolFactory 解析、读取 vault 余额后按美元流动性筛选，
+ * 每个 DEX 保留流动性最高的若干个，合成名称 "SOL/USDC (Raydium V4) [auto]"，
+ * 再与静态配置合并走正常的订阅流程（静态配置中已有的地址不重复添加）。
+ */
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use tracing::{info, warn};
+
+use crate::config::{DiscoveryConfig, PoolConfig};
+use crate::deserializers::spl_token;
+use crate::pool_factory::PoolFactory;
+use crate::pool_initializer::fetch_accounts_batched;
+use crate::rpc_manager::RpcHandle;
+
+/// 支持发现的 DEX 账户布局（两个 mint 字段的偏移，链上布局）
+#[derive(Debug, Clone, Copy)]
+pub struct DexLayout {
+    pub pool_type: &'static str,
+    /// 合成名称中的 DEX 标签
+    pub label: &'static str,
+    pub data_size: u64,
+    /// base mint 偏移（coin / token_0 / token_mint_a / token_x）
+    pub base_mint_offset: usize,
+    /// quote mint 偏移
+    pub quote_mint_offset: usize,
+}
+
+const DEX_LAYOUTS: &[DexLayout] = &[
+    DexLayout { pool_type: "amm_v4", label: "Raydium V4", data_size: 752, base_mint_offset: 400, quote_mint_offset: 432 },
+    DexLayout { pool_type: "clmm", label: "Raydium CLMM", data_size: 1544, base_mint_offset: 73, quote_mint_offset: 105 },
+    DexLayout { pool_type: "whirlpool", label: "Orca Whirlpool", data_size: 653, base_mint_offset: 101, quote_mint_offset: 181 },
+    DexLayout { pool_type: "meteora_dlmm", label: "Meteora DLMM", data_size: 904, base_mint_offset: 88, quote_mint_offset: 120 },
+];
+
+/// 按 pool_type（任意别名）查找账户布局
+pub fn dex_layout(pool_type: &str) -> Option<&'static DexLayout> {
+    let canonical = PoolFactory::canonical_pool_type(pool_type)?;
+    DEX_LAYOUTS.iter().find(|layout| layout.pool_type == canonical)
+}
+
+/// 稳定币按 1 美元计价
+fn is_stablecoin(symbol: &str) -> bool {
+    matches!(symbol, "USDC" | "USDT")
+}
+
+/// 解析并读取储备后的候选池子
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub address: String,
+    pub layout: &'static DexLayout,
+    pub base_symbol: String,
+    pub quote_symbol: String,
+    pub base_mint: String,
+    pub quote_mint: String,
+    /// 储备量（UI 单位）
+    pub base_reserve: f64,
+    pub quote_reserve: f64,
+    /// 现价（quote / base）
+    pub price: f64,
+}
+
+impl Candidate {
+    pub fn pair(&self) -> String {
+        format!("{}/{}", self.base_symbol, self.quote_symbol)
+    }
+
+    /// 美元流动性：两边都能计价时求和，只有一边能计价时按该边的两倍估算
+    pub fn liquidity_usd(&self, usd_prices: &HashMap<String, f64>) -> Option<f64> {
+        let base = usd_prices.get(&self.base_symbol).map(|p| self.base_reserve * p);
+        let quote = usd_prices.get(&self.quote_symbol).map(|p| self.quote_reserve * p);
+        match (base, quote) {
+            (Some(b), Some(q)) => Some(b + q),
+            (Some(v), None) | (None, Some(v)) => Some(v * 2.0),
+            (None, None) => None,
+        }
+    }
+}
+
+/// 发现结果（GET /pools?source=auto 展示）
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveredPool {
+    pub address: String,
+    pub name: String,
+    pub pair: String,
+    pub pool_type: String,
+    pub base_mint: String,
+    pub quote_mint: String,
+    pub liquidity_usd: f64,
+}
+
+impl DiscoveredPool {
+    pub fn to_pool_config(&self) -> PoolConfig {
+        PoolConfig {
+            address: self.address.clone(),
+            name: self.name.clone(),
+            pair: self.pair.clone(),
+            pool_type: self.pool_type.clone(),
+            fee_bps: None,
+            max_age_ms: None,
+            base_mint: Some(self.base_mint.clone()),
+            quote_mint: Some(self.quote_mint.clone()),
+            max_price_jump_percent: None,
+            commitment: None,
+            encoding: None,
+            priority: None,
+        }
+    }
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    Some(values[values.len() / 2])
+}
+
+/// 目标代币的美元参考价
+///
+/// 稳定币 = 1；其余代币取与已计价代币直接配对的候选池子现价中位数
+/// （先和稳定币配对的，例如 SOL/USDC；再和已计价代币配对的，例如 mSOL/SOL）
+pub fn reference_usd_prices(candidates: &[Candidate]) -> HashMap<String, f64> {
+    let mut prices: HashMap<String, f64> = HashMap::new();
+    for candidate in candidates {
+        for symbol in [&candidate.base_symbol, &candidate.quote_symbol] {
+            if is_stablecoin(symbol) {
+                prices.insert(symbol.clone(), 1.0);
+            }
+        }
+    }
+
+    for _ in 0..2 {
+        let mut samples: HashMap<String, Vec<f64>> = HashMap::new();
+        for c in candidates.iter().filter(|c| c.price.is_finite() && c.price > 0.0) {
+            match (prices.get(&c.base_symbol), prices.get(&c.quote_symbol)) {
+                (None, Some(quote_usd)) => samples.entry(c.base_symbol.clone()).or_default().push(c.price * quote_usd),
+                (Some(base_usd), None) => samples.entry(c.quote_symbol.clone()).or_default().push(base_usd / c.price),
+                _ => {}
+            }
+        }
+        for (symbol, values) in samples {
+            if let Some(price) = median(values) {
+                prices.insert(symbol, price);
+            }
+        }
+    }
+    prices
+}
+
+/// 按流动性筛选、每个 DEX 截断，并跳过静态配置中已有的地址
+pub fn select_pools(
+    candidates: &[Candidate],
+    static_pools: &[PoolConfig],
+    config: &DiscoveryConfig,
+) -> Vec<DiscoveredPool> {
+    let usd_prices = reference_usd_prices(candidates);
+    let static_addresses: HashSet<&str> = static_pools.iter().map(|p| p.address.as_str()).collect();
+
+    let mut by_dex: HashMap<&'static str, Vec<DiscoveredPool>> = HashMap::new();
+    let mut seen = HashSet::new();
+    for candidate in candidates {
+        if static_addresses.contains(candidate.address.as_str()) || !seen.insert(candidate.address.as_str()) {
+            continue;
+        }
+        let liquidity_usd = match candidate.liquidity_usd(&usd_prices) {
+            Some(value) if value >= config.min_liquidity_usd => value,
+            _ => continue,
+        };
+        by_dex.entry(candidate.layout.pool_type).or_default().push(DiscoveredPool {
+            address: candidate.address.clone(),
+            name: format!("{} ({}) [auto]", candidate.pair(), candidate.layout.label),
+            pair: candidate.pair(),
+            pool_type: candidate.layout.pool_type.to_string(),
+            base_mint: candidate.base_mint.clone(),
+            quote_mint: candidate.quote_mint.clone(),
+            liquidity_usd,
+        });
+    }
+
+    let mut selected = Vec::new();
+    for layout in DEX_LAYOUTS {
+        if let Some(mut pools) = by_dex.remove(layout.pool_type) {
+            pools.sort_by(|a, b| {
+                b.liquidity_usd.partial_cmp(&a.liquidity_usd)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then_with(|| a.address.cmp(&b.address))
+            });
+            pools.truncate(config.cap_for(layout.pool_type));
+            selected.extend(pools);
+        }
+    }
+    selected
+}
+
+/// 某个 program 下 base/quote mint 为指定代币的池子账户
+async fn fetch_program_pools(
+    rpc: &RpcHandle,
+    program: Pubkey,
+    layout: &'static DexLayout,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+) -> Result<Vec<(Pubkey, Vec<u8>)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize(layout.data_size),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(layout.base_mint_offset, &base_mint.to_bytes())),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(layout.quote_mint_offset, &quote_mint.to_bytes())),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            commitment: Some(CommitmentConfig::confirmed()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let accounts = rpc.call(move |client| client.get_program_accounts_with_config(&program, config.clone()).map_err(Box::new))
+        .await
+        .with_context(|| format!("getProgramAccounts failed for {}", program))?;
+
+    Ok(accounts.into_iter().map(|(address, account)| (address, account.data)).collect())
+}
+
+/// 启动时发现池子
+///
+/// 单个 DEX / 代币对查询失败只记录警告，其余照常返回
+pub async fn discover_pools(
+    config: &DiscoveryConfig,
+    static_pools: &[PoolConfig],
+    rpc: &RpcHandle,
+) -> Vec<DiscoveredPool> {
+    let targets: Vec<(String, Pubkey)> = config.target_mints.iter()
+        .filter_map(|target| match Pubkey::from_str(&target.mint) {
+            Ok(mint) => Some((target.symbol.clone(), mint)),
+            Err(e) => {
+                warn!("🔭 Invalid discovery mint {} ({}): {}", target.mint, target.symbol, e);
+                None
+            }
+        })
+        .collect();
+
+    // 目标代币 decimals（储备量换算为 UI 单位）
+    let mint_keys: Vec<Pubkey> = targets.iter().map(|(_, mint)| *mint).collect();
+    let mint_accounts = fetch_accounts_batched(rpc, &mint_keys).await;
+    let decimals: HashMap<Pubkey, u8> = mint_accounts.accounts.iter()
+        .filter_map(|(mint, account)| spl_token::parse_mint(&account.data).ok().map(|m| (*mint, m.decimals)))
+        .collect();
+
+    // (地址, 布局, base 下标, quote 下标, 池子)
+    let mut parsed = Vec::new();
+    for dex in &config.dexes {
+        let layout = match dex_layout(dex) {
+            Some(layout) => layout,
+            None => {
+                warn!("🔭 Discovery does not support pool type {}", dex);
+                continue;
+            }
+        };
+        let program = match PoolFactory::expected_owner(layout.pool_type).and_then(|p| Pubkey::from_str(p).ok()) {
+            Some(program) => program,
+            None => continue,
+        };
+
+        let mut found = 0;
+        for (base_index, (_, base_mint)) in targets.iter().enumerate() {
+            for (quote_index, (_, quote_mint)) in targets.iter().enumerate() {
+                if base_index == quote_index {
+                    continue;
+                }
+                let accounts = match fetch_program_pools(rpc, program, layout, *base_mint, *quote_mint).await {
+                    Ok(accounts) => accounts,
+                    Err(e) => {
+                        warn!("🔭 {:#}", e);
+                        continue;
+                    }
+                };
+                for (address, data) in accounts {
+                    match PoolFactory::create_pool(layout.pool_type, &data) {
+                        Ok(pool) if pool.activity_status().is_trackable() => {
+                            found += 1;
+                            parsed.push((address, layout, base_index, quote_index, pool));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        info!("🔭 {}: {} candidate pools", layout.label, found);
+    }
+
+    // vault 型池子的储备量来自 vault 账户余额
+    let vault_keys: Vec<Pubkey> = parsed.iter()
+        .filter_map(|(_, _, _, _, pool)| pool.get_vault_addresses())
+        .flat_map(|(a, b)| [a, b])
+        .collect();
+    let vaults = fetch_accounts_batched(rpc, &vault_keys).await;
+    let vault_amount = |vault: &Pubkey| -> Option<u64> {
+        let account = vaults.accounts.get(vault)?;
+        spl_token::parse_token_account(&account.data).ok().map(|a| a.effective_amount())
+    };
+
+    let mut candidates = Vec::new();
+    for (address, layout, base_index, quote_index, pool) in parsed {
+        let (base_symbol, base_mint) = &targets[base_index];
+        let (quote_symbol, quote_mint) = &targets[quote_index];
+        let (base_decimals, quote_decimals) = match (decimals.get(base_mint), decimals.get(quote_mint)) {
+            (Some(b), Some(q)) => (*b, *q),
+            _ => continue,
+        };
+        let (base_raw, quote_raw) = match pool.get_vault_addresses() {
+            Some((vault_a, vault_b)) => match (vault_amount(&vault_a), vault_amount(&vault_b)) {
+                (Some(a), Some(b)) => (a, b),
+                _ => continue,
+            },
+            None => pool.get_reserves(),
+        };
+        candidates.push(Candidate {
+            address: address.to_string(),
+            layout,
+            base_symbol: base_symbol.clone(),
+            quote_symbol: quote_symbol.clone(),
+            base_mint: base_mint.to_string(),
+            quote_mint: quote_mint.to_string(),
+            base_reserve: base_raw as f64 / 10f64.powi(base_decimals as i32),
+            quote_reserve: quote_raw as f64 / 10f64.powi(quote_decimals as i32),
+            price: pool.calculate_price(),
+        });
+    }
+
+    let selected = select_pools(&candidates, static_pools, config);
+    info!(
+        "🔭 Discovery: {} candidates, {} pools selected (min liquidity ${:.0})",
+        candidates.len(), selected.len(), config.min_liquidity_usd
+    );
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(address: &str, pool_type: &str, pair: (&str, &str), reserves: (f64, f64), price: f64) -> Candidate {
+        Candidate {
+            address: address.to_string(),
+            layout: dex_layout(pool_type).unwrap(),
+            base_symbol: pair.0.to_string(),
+            quote_symbol: pair.1.to_string(),
+            base_mint: format!("{}-mint", pair.0),
+            quote_mint: format!("{}-mint", pair.1),
+            base_reserve: reserves.0,
+            quote_reserve: reserves.1,
+            price,
+        }
+    }
+
+    fn config() -> DiscoveryConfig {
+        toml::from_str("enabled = true\nmax_pools_per_dex = 2\nmin_liquidity_usd = 100000\n").unwrap()
+    }
+
+    #[test]
+    fn test_select_pools_by_liquidity_and_cap() {
+        let candidates = vec![
+            candidate("ray-1", "amm_v4", ("SOL", "USDC"), (1_000.0, 185_000.0), 185.0),
+            candidate("ray-2", "amm_v4", ("SOL", "USDC"), (5_000.0, 925_000.0), 185.0),
+            candidate("ray-3", "amm_v4", ("SOL", "USDT"), (2_000.0, 370_000.0), 185.0),
+            candidate("ray-dust", "amm_v4", ("SOL", "USDC"), (10.0, 1_850.0), 185.0),
+            // mSOL 通过 SOL 间接计价：2000 mSOL * 1.2 * 185 + 2400 SOL * 185
+            candidate("orca-msol", "orca", ("mSOL", "SOL"), (2_000.0, 2_400.0), 1.2),
+            candidate("static", "clmm", ("SOL", "USDC"), (10_000.0, 1_850_000.0), 185.0),
+        ];
+        let static_pools = vec![PoolConfig {
+            address: "static".to_string(),
+            name: "SOL/USDC (Raydium CLMM)".to_string(),
+            pair: "SOL/USDC".to_string(),
+            pool_type: "clmm".to_string(),
+            fee_bps: None,
+            max_age_ms: None,
+            base_mint: None,
+            quote_mint: None,
+            max_price_jump_percent: None,
+            commitment: None,
+            encoding: None,
+            priority: None,
+        }];
+
+        let prices = reference_usd_prices(&candidates);
+        assert!((prices["SOL"] - 185.0).abs() < 1e-9);
+        assert!((prices["mSOL"] - 222.0).abs() < 1e-9);
+
+        let selected = select_pools(&candidates, &static_pools, &config());
+        let addresses: Vec<&str> = selected.iter().map(|p| p.address.as_str()).collect();
+        assert_eq!(addresses, vec!["ray-2", "ray-3", "orca-msol"]);
+        assert_eq!(selected[0].name, "SOL/USDC (Raydium V4) [auto]");
+        assert_eq!(selected[2].name, "mSOL/SOL (Orca Whirlpool) [auto]");
+        assert_eq!(selected[2].pool_type, "whirlpool");
+
+        let pool_config = selected[1].to_pool_config();
+        assert_eq!(pool_config.pair, "SOL/USDT");
+        assert_eq!(pool_config.pool_type, "amm_v4");
+    }
+}
@@ -0,0 +1,148 @@
+/*!
+ * Meteora DLMM bin 注册表
+ *
+ * lb_pair 账户只有活跃 bin id，真实流动性在相邻的 BinArray 账户里。WebSocket 收到
+ * lb_pair 更新时在这里登记活跃 bin 两侧的 BinArray（新出现的复用 vault 订阅通道订阅），
+ * BinArray 更新后重建报价器并注册到 orderbook_cache，路由器按 bin 报价。
+ */
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use dashmap::DashMap;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::deserializers::meteora_dlmm_bins::{
+    bin_array_address, bin_array_indexes_around, BinArray, DlmmQuoter, LbPairQuoteParams,
+};
+use crate::mint_decimals_cache::get_global_mint_cache;
+
+/// 活跃 bin 所在数组两侧各订阅的 BinArray 数（共 3 个数组 / 210 个 bin）
+pub const BIN_ARRAY_RADIUS: i64 = 1;
+
+struct DlmmPoolBins {
+    params: LbPairQuoteParams,
+    decimals: (u8, u8),
+    /// BinArray 索引 -> 最新数据（尚未收到数据的为 None）
+    arrays: HashMap<i64, Option<BinArray>>,
+}
+
+static DLMM_POOLS: OnceLock<DashMap<String, DlmmPoolBins>> = OnceLock::new();
+/// BinArray 地址 -> (pool_id, 索引)
+static BIN_ARRAYS: OnceLock<DashMap<String, (String, i64)>> = OnceLock::new();
+
+fn pools() -> &'static DashMap<String, DlmmPoolBins> {
+    DLMM_POOLS.get_or_init(DashMap::new)
+}
+
+fn bin_arrays() -> &'static DashMap<String, (String, i64)> {
+    BIN_ARRAYS.get_or_init(DashMap::new)
+}
+
+/// 处理 lb_pair 更新，返回需要新订阅的 BinArray 地址
+///
+/// 活跃 bin 移动后新进入窗口的数组才会返回；已登记的数组保持订阅。
+pub fn observe_lb_pair(pool_id: &str, data: &[u8]) -> Vec<String> {
+    let params = match LbPairQuoteParams::from_account_data(data) {
+        Ok(params) => params,
+        Err(_) => return Vec::new(),
+    };
+    let lb_pair = match Pubkey::from_str(pool_id) {
+        Ok(pubkey) => pubkey,
+        Err(_) => return Vec::new(),
+    };
+
+    // decimals 可能需要 RPC，不在持有分片锁时查询
+    if !pools().contains_key(pool_id) {
+        let decimals = mint_decimals(&params);
+        pools().entry(pool_id.to_string()).or_insert_with(|| DlmmPoolBins {
+            params: params.clone(),
+            decimals,
+            arrays: HashMap::new(),
+        });
+    }
+    let mut entry = match pools().get_mut(pool_id) {
+        Some(entry) => entry,
+        None => return Vec::new(),
+    };
+    entry.params = params;
+
+    let mut new_addresses = Vec::new();
+    for index in bin_array_indexes_around(entry.params.active_id, BIN_ARRAY_RADIUS) {
+        if entry.arrays.contains_key(&index) {
+            continue;
+        }
+        entry.arrays.insert(index, None);
+        let address = bin_array_address(&lb_pair, index).to_string();
+        bin_arrays().insert(address.clone(), (pool_id.to_string(), index));
+        new_addresses.push(address);
+    }
+    publish(pool_id, &entry);
+    new_addresses
+}
+
+/// 是否为已登记的 BinArray 账户
+pub fn is_bin_array(address: &str) -> bool {
+    bin_arrays().contains_key(address)
+}
+
+/// 处理 BinArray 更新，返回是否解析成功
+pub fn update_bin_array(address: &str, data: &[u8]) -> bool {
+    let (pool_id, index) = match bin_arrays().get(address) {
+        Some(entry) => entry.value().clone(),
+        None => return false,
+    };
+    let array = match BinArray::from_account_data(data) {
+        Ok(array) if array.index == index => array,
+        _ => return false,
+    };
+
+    match pools().get_mut(&pool_id) {
+        Some(mut entry) => {
+            entry.arrays.insert(index, Some(array));
+            publish(&pool_id, &entry);
+            true
+        }
+        None => false,
+    }
+}
+
+/// 已登记的 (BinArray 地址, pool_id)，重连后重放订阅
+pub fn subscriptions() -> Vec<(String, String)> {
+    bin_arrays().iter()
+        .map(|entry| (entry.key().clone(), entry.value().0.clone()))
+        .collect()
+}
+
+/// 移除池子（热重载删除时调用），返回需要退订的 BinArray 地址
+pub fn remove_pool(pool_id: &str) -> Vec<String> {
+    pools().remove(pool_id);
+    let addresses: Vec<String> = bin_arrays().iter()
+        .filter(|entry| entry.value().0 == pool_id)
+        .map(|entry| entry.key().clone())
+        .collect();
+    for address in &addresses {
+        bin_arrays().remove(address);
+    }
+    addresses
+}
+
+/// 用最新 lb_pair 参数和已收到的 bin 重建报价器
+fn publish(pool_id: &str, entry: &DlmmPoolBins) {
+    let mut quoter = DlmmQuoter::new(entry.params.clone(), entry.decimals.0, entry.decimals.1);
+    for array in entry.arrays.values().flatten() {
+        quoter.load_bin_array(array);
+    }
+    // 没有 bin 的报价器 has_orderbook() = false，orderbook_cache 会忽略
+    crate::orderbook_cache::register(pool_id, Box::new(quoter));
+}
+
+fn mint_decimals(params: &LbPairQuoteParams) -> (u8, u8) {
+    match get_global_mint_cache() {
+        Some(cache) => (
+            cache.get_or_fetch_decimals(&params.token_x_mint).unwrap_or(9),
+            cache.get_or_fetch_decimals(&params.token_y_mint).unwrap_or(6),
+        ),
+        None => (9, 6),
+    }
+}
@@ -0,0 +1,262 @@
+/*!
+ * 多端点 WebSocket 故障转移
+ *
+ * 每个端点维护一个简单的健康分：连续失败次数、最近一次收到消息的时间、
+ * 平均通知延迟。连接失败或流中断时切换到健康分最好的端点
+ * （分数越低越好，平手时按配置顺序，优先主端点）。
+ *
+ * 各条连接（分片）各自记住所连的端点，只有自己断开时才切换；
+ * 池子的活跃端点（HTTP RPC / vault 拉取用）跟随最近一次切换。
+ *
+ * HTTP RPC 由 rpc_manager 单独做故障转移；未配置 `[rpc] urls` 时
+ * 它的端点列表包含这里每个 WebSocket 地址对应的 HTTP 地址（ws(s):// 换成 http(s)://）。
+ */
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+
+/// 收到过消息的端点超过这么久没有新消息，视为失去活性
+const STALE_AFTER_MS: u64 = 30_000;
+/// 平均通知延迟每满这么多微秒，健康分加 1
+const LATENCY_PENALTY_MICROS: u64 = 500_000;
+/// 一次连续失败折算的健康分（高于过期 / 延迟惩罚，失败次数是主要信号）
+const FAILURE_WEIGHT: u64 = 2;
+
+/// 单个端点的运行状态
+#[derive(Debug)]
+struct Endpoint {
+    url: String,
+    consecutive_failures: AtomicU32,
+    /// 最近一次收到消息的时间（Unix 毫秒，0 表示从未收到）
+    last_message_ms: AtomicU64,
+    /// 最近一次连接期间的平均通知延迟（微秒）
+    avg_latency_micros: AtomicU64,
+}
+
+/// 端点健康状态（日志 / API 输出）
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointHealth {
+    pub url: String,
+    pub active: bool,
+    pub consecutive_failures: u32,
+    pub last_message_ms_ago: Option<u64>,
+    pub avg_latency_micros: u64,
+    /// 健康分（越低越好）
+    pub score: u64,
+}
+
+/// WebSocket 端点池
+#[derive(Debug)]
+pub struct EndpointPool {
+    endpoints: Vec<Endpoint>,
+    active: AtomicUsize,
+}
+
+/// WebSocket 地址对应的 HTTP RPC 地址
+pub fn rpc_url_for(ws_url: &str) -> String {
+    if let Some(rest) = ws_url.strip_prefix("wss://") {
+        format!("https://{}", rest)
+    } else if let Some(rest) = ws_url.strip_prefix("ws://") {
+        format!("http://{}", rest)
+    } else {
+        ws_url.to_string()
+    }
+}
+
+impl EndpointPool {
+    /// 按配置顺序创建（第一个为主端点）
+    ///
+    /// # Panics
+    /// `urls` 为空时 panic（配置加载时已校验至少一个端点）
+    pub fn new(urls: Vec<String>) -> Self {
+        assert!(!urls.is_empty(), "EndpointPool requires at least one endpoint");
+        Self {
+            endpoints: urls.into_iter()
+                .map(|url| Endpoint {
+                    url,
+                    consecutive_failures: AtomicU32::new(0),
+                    last_message_ms: AtomicU64::new(0),
+                    avg_latency_micros: AtomicU64::new(0),
+                })
+                .collect(),
+            active: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn endpoint_count(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    pub fn active_index(&self) -> usize {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    pub fn active_url(&self) -> String {
+        self.url(self.active_index()).to_string()
+    }
+
+    pub fn url(&self, index: usize) -> &str {
+        self.endpoints.get(index).map(|e| e.url.as_str()).unwrap_or("")
+    }
+
+    /// 当前活跃端点的 HTTP RPC 地址
+    pub fn active_rpc_url(&self) -> String {
+        rpc_url_for(&self.endpoints[self.active_index()].url)
+    }
+
+    /// 收到消息：端点健康，连续失败清零
+    pub fn record_message(&self, index: usize) {
+        if let Some(endpoint) = self.endpoints.get(index) {
+            endpoint.last_message_ms.store(chrono::Utc::now().timestamp_millis() as u64, Ordering::Relaxed);
+            endpoint.consecutive_failures.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// 记录端点在上一次连接期间的平均通知延迟
+    pub fn record_latency(&self, index: usize, avg_latency_micros: u64) {
+        if let Some(endpoint) = self.endpoints.get(index) {
+            endpoint.avg_latency_micros.store(avg_latency_micros, Ordering::Relaxed);
+        }
+    }
+
+    /// 记录一次连接失败 / 流中断
+    ///
+    /// 返回 true 表示该端点连续失败次数刚好超过 alert_after（每轮故障只告警一次）
+    pub fn record_failure(&self, index: usize, alert_after: u32) -> bool {
+        match self.endpoints.get(index) {
+            Some(endpoint) => {
+                let failures = endpoint.consecutive_failures.fetch_add(1, Ordering::Relaxed).saturating_add(1);
+                failures == alert_after.saturating_add(1)
+            }
+            None => false,
+        }
+    }
+
+    pub fn consecutive_failures(&self, index: usize) -> u32 {
+        self.endpoints.get(index)
+            .map(|e| e.consecutive_failures.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    /// 健康分（越低越好）：连续失败 × FAILURE_WEIGHT + 消息过期 1 分 + 每 LATENCY_PENALTY_MICROS 平均延迟 1 分
+    ///
+    /// 从未收到过消息的端点不算过期（备用端点在切换前本来就没有消息）。
+    pub fn score(&self, index: usize) -> u64 {
+        self.score_at(index, chrono::Utc::now().timestamp_millis() as u64)
+    }
+
+    fn score_at(&self, index: usize, now_ms: u64) -> u64 {
+        let Some(endpoint) = self.endpoints.get(index) else { return u64::MAX };
+        let last = endpoint.last_message_ms.load(Ordering::Relaxed);
+        let stale = last > 0 && now_ms.saturating_sub(last) > STALE_AFTER_MS;
+        u64::from(endpoint.consecutive_failures.load(Ordering::Relaxed)) * FAILURE_WEIGHT
+            + u64::from(stale)
+            + endpoint.avg_latency_micros.load(Ordering::Relaxed) / LATENCY_PENALTY_MICROS
+    }
+
+    /// 切换到健康分最好的端点，返回新的活跃索引
+    ///
+    /// 分数最低者优先，平手时按配置顺序；所有端点都故障时自然轮询。
+    pub fn fail_over(&self) -> usize {
+        let now_ms = chrono::Utc::now().timestamp_millis() as u64;
+        let best = (0..self.endpoints.len())
+            .min_by_key(|&i| (self.score_at(i, now_ms), i))
+            .unwrap_or(0);
+        self.active.store(best, Ordering::SeqCst);
+        best
+    }
+
+    pub fn health(&self) -> Vec<EndpointHealth> {
+        let now_ms = chrono::Utc::now().timestamp_millis() as u64;
+        let active = self.active_index();
+        self.endpoints.iter()
+            .enumerate()
+            .map(|(i, e)| {
+                let last = e.last_message_ms.load(Ordering::Relaxed);
+                EndpointHealth {
+                    url: e.url.clone(),
+                    active: i == active,
+                    consecutive_failures: e.consecutive_failures.load(Ordering::Relaxed),
+                    last_message_ms_ago: (last > 0).then(|| now_ms.saturating_sub(last)),
+                    avg_latency_micros: e.avg_latency_micros.load(Ordering::Relaxed),
+                    score: self.score_at(i, now_ms),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool() -> EndpointPool {
+        EndpointPool::new(vec![
+            "wss://primary.example".to_string(),
+            "wss://secondary.example".to_string(),
+            "ws://tertiary.example:8900".to_string(),
+        ])
+    }
+
+    #[test]
+    fn test_fails_over_to_secondary_then_back() {
+        let endpoints = pool();
+        assert_eq!(endpoints.active_url(), "wss://primary.example");
+
+        // 主端点断开：切到备用
+        endpoints.record_failure(0, 10);
+        assert_eq!(endpoints.fail_over(), 1);
+        assert_eq!(endpoints.active_rpc_url(), "https://secondary.example");
+        endpoints.record_message(1);
+
+        // 备用端点断开：主端点与备用各失败一次，按配置顺序回到主端点
+        endpoints.record_failure(1, 10);
+        endpoints.record_failure(2, 10);
+        assert_eq!(endpoints.fail_over(), 0);
+
+        let health = endpoints.health();
+        assert!(health[0].active);
+        assert!(health[1].last_message_ms_ago.is_some());
+        assert!(health[0].last_message_ms_ago.is_none());
+    }
+
+    #[test]
+    fn test_alert_fires_once_past_threshold() {
+        let endpoints = pool();
+        let alerts: Vec<bool> = (0..6).map(|_| endpoints.record_failure(0, 3)).collect();
+        assert_eq!(alerts, vec![false, false, false, true, false, false]);
+
+        // 收到消息后重新计数
+        endpoints.record_message(0);
+        assert_eq!(endpoints.consecutive_failures(0), 0);
+    }
+
+    #[test]
+    fn test_score_weighs_staleness_and_latency_below_failures() {
+        let endpoints = pool();
+        let now_ms = chrono::Utc::now().timestamp_millis() as u64;
+
+        // 主端点通知延迟 1.2s：平均延迟 2 分，切到延迟正常的备用端点
+        endpoints.record_latency(0, 1_200_000);
+        assert_eq!(endpoints.score_at(0, now_ms), 2);
+        assert_eq!(endpoints.fail_over(), 1);
+
+        // 备用端点一分钟前收到过消息之后再没动静：过期 1 分，切到第三个端点
+        endpoints.endpoints[1].last_message_ms.store(now_ms - 60_000, Ordering::Relaxed);
+        assert_eq!(endpoints.score_at(1, now_ms), 1);
+        assert_eq!(endpoints.fail_over(), 2);
+
+        // 失败权重高于过期 / 延迟：第三个端点失败两次后回到备用端点
+        endpoints.record_failure(2, 10);
+        endpoints.record_failure(2, 10);
+        assert_eq!(endpoints.fail_over(), 1);
+        assert_eq!(endpoints.health()[2].score, 4);
+    }
+
+    #[test]
+    fn test_rpc_url_for() {
+        assert_eq!(rpc_url_for("wss://mainnet.helius-rpc.com/?api-key=x"), "https://mainnet.helius-rpc.com/?api-key=x");
+        assert_eq!(rpc_url_for("ws://127.0.0.1:8900"), "http://127.0.0.1:8900");
+        assert_eq!(rpc_url_for("https://already.http"), "https://already.http");
+    }
+}
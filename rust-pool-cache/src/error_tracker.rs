@@ -1,9 +1,17 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use dashmap::DashMap;
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::warn;
+use tracing::{info, warn};
+
+use crate::config::ErrorTrackingConfig;
+use crate::prometheus::CacheSize;
+
+/// 反序列化失败的 error_key 后缀（"{pool_type}_deserialize_failed"）
+const DESERIALIZE_FAILED_SUFFIX: &str = "_deserialize_failed";
 
 /// Statistics for a specific error type
 #[derive(Clone, Debug, Serialize)]
@@ -16,6 +24,40 @@ pub struct ErrorStats {
     pub last_seen: DateTime<Utc>,
     /// Sample error messages (up to 5)
     pub samples: Vec<String>,
+    /// 速率窗口内的发生时间（环形缓冲，有上限）
+    #[serde(skip)]
+    occurrences: VecDeque<DateTime<Utc>>,
+}
+
+impl ErrorStats {
+    /// cutoff 之后的发生次数
+    fn count_since(&self, cutoff: DateTime<Utc>) -> usize {
+        self.occurrences.iter().rev().take_while(|at| **at > cutoff).count()
+    }
+}
+
+/// GET /errors 中每个 error_key 的摘要
+#[derive(Clone, Debug, Serialize)]
+pub struct ErrorSummary {
+    pub error_key: String,
+    pub count: usize,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub example: Option<String>,
+    /// 从示例消息中解析出的池子（"{pool}: {reason}" 格式）
+    pub pool: Option<String>,
+    /// 最近 window_secs 内的次数
+    pub recent_count: usize,
+    pub window_secs: u64,
+}
+
+/// 反序列化失败速率超过阈值的 pool_type
+#[derive(Clone, Debug, Serialize)]
+pub struct DegradedPoolType {
+    pub pool_type: String,
+    pub errors_in_window: usize,
+    pub threshold: usize,
+    pub since: DateTime<Utc>,
 }
 
 /// Error tracker for aggregating and deduplicating errors
@@ -23,32 +65,80 @@ pub struct ErrorStats {
 pub struct ErrorTracker {
     errors: Arc<RwLock<HashMap<String, ErrorStats>>>,
     alert_thresholds: Vec<usize>, // Alert at these counts (e.g., 10, 50, 100)
+    /// 速率统计窗口
+    rate_window: Duration,
+    /// 每个 error_key 最多保留的发生时间数
+    max_occurrences_per_key: usize,
+    /// 最多保留的 error_key 数（超过时淘汰最久未发生的 key）
+    max_keys: usize,
+    /// 累计淘汰的 error_key 数
+    evicted_keys: Arc<AtomicU64>,
+    /// 窗口内反序列化失败超过该次数的 pool_type 标记为降级
+    deserialize_failure_threshold: usize,
+    /// 当前降级的 pool_type（/health 报告）
+    degraded: Arc<DashMap<String, DegradedPoolType>>,
 }
 
 impl ErrorTracker {
     /// Create a new error tracker
     pub fn new() -> Self {
+        Self::from_config(&ErrorTrackingConfig::default())
+    }
+
+    /// 按 [error_tracking] 配置创建
+    pub fn from_config(config: &ErrorTrackingConfig) -> Self {
         Self {
             errors: Arc::new(RwLock::new(HashMap::new())),
             alert_thresholds: vec![10, 50, 100, 500, 1000],
+            rate_window: Duration::seconds(config.rate_window_secs.max(1) as i64),
+            max_occurrences_per_key: config.max_occurrences_per_key.max(1),
+            max_keys: config.max_error_keys.max(1),
+            evicted_keys: Arc::new(AtomicU64::new(0)),
+            deserialize_failure_threshold: config.deserialize_failure_threshold,
+            degraded: Arc::new(DashMap::new()),
         }
     }
 
     /// Record an error occurrence
     pub async fn record_error(&self, error_type: &str, message: String) {
+        self.record_error_at(error_type, message, Utc::now()).await;
+    }
+
+    async fn record_error_at(&self, error_type: &str, message: String, now: DateTime<Utc>) {
         let mut errors = self.errors.write().await;
         
+        // 新 key 且已达上限：淘汰最久未发生的 key
+        if errors.len() >= self.max_keys && !errors.contains_key(error_type) {
+            let oldest = errors.iter()
+                .min_by(|a, b| a.1.last_seen.cmp(&b.1.last_seen).then_with(|| a.0.cmp(b.0)))
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                errors.remove(&oldest);
+                self.evicted_keys.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        
         let stats = errors.entry(error_type.to_string()).or_insert_with(|| {
             ErrorStats {
                 count: 0,
-                first_seen: Utc::now(),
-                last_seen: Utc::now(),
+                first_seen: now,
+                last_seen: now,
                 samples: Vec::new(),
+                occurrences: VecDeque::new(),
             }
         });
 
         stats.count += 1;
-        stats.last_seen = Utc::now();
+        stats.last_seen = now;
+
+        // 只保留窗口内、且不超过上限的发生时间
+        let cutoff = now - self.rate_window;
+        stats.occurrences.push_back(now);
+        while stats.occurrences.len() > self.max_occurrences_per_key
+            || stats.occurrences.front().is_some_and(|at| *at <= cutoff)
+        {
+            stats.occurrences.pop_front();
+        }
 
         // Keep only the first 5 sample messages
         if stats.samples.len() < 5 && !stats.samples.contains(&message) {
@@ -63,6 +153,28 @@ impl ErrorTracker {
                 "Error threshold reached"
             );
         }
+
+        // 🚨 某个 pool_type 的反序列化失败速率超过阈值：标记降级
+        if let Some(pool_type) = error_type.strip_suffix(DESERIALIZE_FAILED_SUFFIX) {
+            let errors_in_window = stats.occurrences.len();
+            if errors_in_window > self.deserialize_failure_threshold {
+                match self.degraded.get_mut(pool_type) {
+                    Some(mut degraded) => degraded.errors_in_window = errors_in_window,
+                    None => {
+                        warn!(
+                            "🚨 Pool type {} degraded: {} deserialization failures in the last {}s (threshold {})",
+                            pool_type, errors_in_window, self.rate_window.num_seconds(), self.deserialize_failure_threshold
+                        );
+                        self.degraded.insert(pool_type.to_string(), DegradedPoolType {
+                            pool_type: pool_type.to_string(),
+                            errors_in_window,
+                            threshold: self.deserialize_failure_threshold,
+                            since: now,
+                        });
+                    }
+                }
+            }
+        }
     }
 
     /// Get all error statistics
@@ -70,6 +182,56 @@ impl ErrorTracker {
         self.errors.read().await.clone()
     }
 
+    /// 每个 error_key 的摘要（最近发生的排在前面）
+    pub async fn summaries(&self) -> Vec<ErrorSummary> {
+        self.summaries_at(Utc::now()).await
+    }
+
+    async fn summaries_at(&self, now: DateTime<Utc>) -> Vec<ErrorSummary> {
+        let cutoff = now - self.rate_window;
+        let mut summaries: Vec<ErrorSummary> = self.errors.read().await
+            .iter()
+            .map(|(key, stats)| {
+                let example = stats.samples.first().cloned();
+                ErrorSummary {
+                    error_key: key.clone(),
+                    count: stats.count,
+                    first_seen: stats.first_seen,
+                    last_seen: stats.last_seen,
+                    pool: example.as_deref().and_then(parse_affected_pool),
+                    example,
+                    recent_count: stats.count_since(cutoff),
+                    window_secs: self.rate_window.num_seconds() as u64,
+                }
+            })
+            .collect();
+        summaries.sort_by(|a, b| b.last_seen.cmp(&a.last_seen).then_with(|| a.error_key.cmp(&b.error_key)));
+        summaries
+    }
+
+    /// 当前降级的 pool_type（速率回落到阈值以内的自动恢复）
+    pub async fn degraded_pool_types(&self) -> Vec<DegradedPoolType> {
+        self.degraded_pool_types_at(Utc::now()).await
+    }
+
+    async fn degraded_pool_types_at(&self, now: DateTime<Utc>) -> Vec<DegradedPoolType> {
+        let cutoff = now - self.rate_window;
+        let errors = self.errors.read().await;
+        self.degraded.retain(|pool_type, degraded| {
+            let key = format!("{}{}", pool_type, DESERIALIZE_FAILED_SUFFIX);
+            degraded.errors_in_window = errors.get(&key).map_or(0, |stats| stats.count_since(cutoff));
+            let still_degraded = degraded.errors_in_window > self.deserialize_failure_threshold;
+            if !still_degraded {
+                info!("✅ Pool type {} recovered ({} deserialization failures in window)", pool_type, degraded.errors_in_window);
+            }
+            still_degraded
+        });
+
+        let mut degraded: Vec<DegradedPoolType> = self.degraded.iter().map(|entry| entry.value().clone()).collect();
+        degraded.sort_by(|a, b| a.pool_type.cmp(&b.pool_type));
+        degraded
+    }
+
     /// Get total error count
     pub async fn get_total_errors(&self) -> usize {
         self.errors.read().await.values().map(|s| s.count).sum()
@@ -80,13 +242,32 @@ impl ErrorTracker {
         self.errors.read().await.len()
     }
 
+    /// /metrics 报告的 error_key 存储大小
+    pub async fn cache_size(&self) -> CacheSize {
+        CacheSize {
+            cache: "error_keys",
+            entries: self.errors.read().await.len(),
+            capacity: Some(self.max_keys),
+            bytes: None,
+            evictions: self.evicted_keys.load(Ordering::Relaxed),
+        }
+    }
+
     /// Clear all error statistics
     #[allow(dead_code)]
     pub async fn clear(&self) {
         self.errors.write().await.clear();
+        self.degraded.clear();
     }
 }
 
+/// 从 "{pool}: {reason}" 格式的错误消息中解析池子名称
+fn parse_affected_pool(message: &str) -> Option<String> {
+    let (pool, _) = message.split_once(": ")?;
+    let pool = pool.trim();
+    (!pool.is_empty() && !pool.contains("://")).then(|| pool.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,10 +287,49 @@ mod tests {
         assert_eq!(stats.count, 3);
         assert_eq!(stats.samples.len(), 3);
     }
-}
 
+    #[tokio::test]
+    async fn test_deserialize_failure_rate_degrades_pool_type() {
+        let tracker = ErrorTracker::from_config(&ErrorTrackingConfig {
+            rate_window_secs: 300,
+            max_occurrences_per_key: 4,
+            deserialize_failure_threshold: 2,
+            max_error_keys: 16,
+        });
+        let start = Utc::now();
 
+        // 3 次失败 > 阈值 2：标记降级
+        for i in 0..3 {
+            tracker.record_error_at(
+                "goonfi_deserialize_failed",
+                format!("GOON/USDC (GoonFi): Invalid data {}, Expected vs Actual size issue", i),
+                start + Duration::seconds(i),
+            ).await;
+        }
+        tracker.record_error_at("websocket_endpoint_down", "wss://a failed 3 consecutive times".to_string(), start).await;
 
+        let degraded = tracker.degraded_pool_types_at(start + Duration::seconds(10)).await;
+        assert_eq!(degraded.len(), 1);
+        assert_eq!(degraded[0].pool_type, "goonfi");
+        assert_eq!(degraded[0].errors_in_window, 3);
 
+        let summaries = tracker.summaries_at(start + Duration::seconds(10)).await;
+        assert_eq!(summaries[0].error_key, "goonfi_deserialize_failed");
+        assert_eq!(summaries[0].pool.as_deref(), Some("GOON/USDC (GoonFi)"));
+        assert_eq!(summaries[0].recent_count, 3);
+        assert_eq!(summaries[1].pool, None);
 
+        // 环形缓冲有上限，总数仍然累计
+        for i in 0..10 {
+            tracker.record_error_at("goonfi_deserialize_failed", "x".to_string(), start + Duration::seconds(20 + i)).await;
+        }
+        let report = tracker.get_error_report().await;
+        assert_eq!(report["goonfi_deserialize_failed"].count, 13);
+        assert_eq!(report["goonfi_deserialize_failed"].occurrences.len(), 4);
 
+        // 窗口过后速率回落：自动恢复
+        let later = start + Duration::seconds(29 + 301);
+        assert!(tracker.degraded_pool_types_at(later).await.is_empty());
+        assert_eq!(tracker.summaries_at(later).await[0].recent_count, 0);
+    }
+}
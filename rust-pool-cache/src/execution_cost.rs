@@ -0,0 +1,357 @@
+/*!
+ * ⛽ 执行成本模型
+ *
+ * 之前各路由器按跳数扣一个常数 "gas"（0.0001–0.0005 个起始代币），单位不对，
+ * 拥堵时也远低于实际成本。这里按 lamports 估算一笔套利交易的执行成本：
+ *
+ * - 签名费：base_fee_lamports × signatures
+ * - 优先费：(固定开销 + Σ 每跳 compute units) × 优先费率（micro-lamports/CU）
+ *   - 每跳 CU 按 DEX 区分（Whirlpool 一跳远比 Raydium V4 贵），可在配置中覆盖
+ *   - 优先费率由 getRecentPrioritizationFees 定期刷新（取百分位），取不到时用静态值
+ * - 可选的固定 Jito 小费
+ *
+ * 再按 USD/SOL 价格换算成路径的起始代币（`ArbitragePath::execution_fees`）。
+ * 没有 USD 汇率时用路径自身经过的 SOL 池子换算；两者都没有的路径无法定价，
+ * 路由器不上报（绝不按 0 成本计）。
+ *
+ * 模型由 app 创建后以 `Arc` 传给各路由器（`with_execution_cost`），没有全局实例。
+ */
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::config::ExecutionCostConfig;
+use crate::price_oracle::PriceOracle;
+use crate::router::RouteStep;
+use crate::rpc_manager::RpcHandle;
+use crate::supervisor::{spawn_supervised, Supervisor};
+use crate::token_alias;
+
+/// 1 SOL = 10^9 lamports
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+/// 1 lamport = 10^6 micro-lamports
+const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
+
+/// 执行成本模型
+#[derive(Debug)]
+pub struct ExecutionCostModel {
+    config: ExecutionCostConfig,
+    /// getRecentPrioritizationFees 得到的实时费率（micro-lamports/CU），0 = 尚未取到或最近没有优先费（用静态值兜底）
+    live_priority_fee: AtomicU64,
+    /// 代币 -> 1 SOL 可换得的代币数量
+    sol_rates: DashMap<String, f64>,
+    /// 已告警过无法定价的起始代币（每个代币只告警一次）
+    unpriced_warned: DashMap<String, ()>,
+}
+
+impl ExecutionCostModel {
+    pub fn new(config: ExecutionCostConfig) -> Self {
+        Self {
+            config,
+            live_priority_fee: AtomicU64::new(0),
+            sol_rates: DashMap::new(),
+            unpriced_warned: DashMap::new(),
+        }
+    }
+
+    /// 默认配置的共享模型（组合路由器在 `with_execution_cost` 之前使用，以及测试 / 基准）
+    ///
+    /// 没有任何 SOL 汇率，也不刷新：起始代币不是 SOL、路径也不经过 SOL 池子时无法定价，
+    /// 这些路径会被丢弃（每个代币警告一次）。实时扫描应传入 app 创建、后台刷新汇率的那一份模型。
+    pub fn shared_default() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// DEX 一跳 swap 的 compute units：配置覆盖（小写子串匹配）> 内置估算
+    pub fn compute_units(&self, dex_name: &str) -> u64 {
+        let name = dex_name.to_lowercase();
+        let config = &self.config;
+        config.compute_units.iter()
+            .filter(|(key, _)| name.contains(&key.to_lowercase()))
+            .max_by_key(|(key, _)| key.len())
+            .map(|(_, units)| *units)
+            .unwrap_or_else(|| Self::dex_default_compute_units(&name))
+    }
+
+    /// 内置每跳 CU 估算（主网交易的典型消耗，含 token 转账）
+    pub fn dex_default_compute_units(dex_name: &str) -> u64 {
+        let name = dex_name.to_lowercase();
+        match name.as_str() {
+            s if s.contains("raydium clmm") || s == "clmm" => 100_000,
+            s if s.contains("raydium") || s.contains("amm_v4") => 40_000,
+            s if s.contains("orca") || s.contains("whirlpool") => 120_000,
+            s if s.contains("meteora") || s.contains("dlmm") => 90_000,
+            s if s.contains("pancakeswap") || s == "pcs" => 100_000,
+            s if s.contains("lifinity") => 60_000,                     // 读 oracle
+            s if s.contains("stabble") || s.contains("saber") => 80_000,
+            s if s.contains("phoenix") || s.contains("openbook") => 50_000,
+            s if s.contains("solfi") || s.contains("humidifi") || s.contains("goonfi")
+                || s.contains("tessera") || s.contains("alphaq") || s.contains("aquifer") => 30_000,
+            _ => 80_000,
+        }
+    }
+
+    /// 当前优先费率（micro-lamports/CU）：实时值优先，否则静态兜底
+    pub fn priority_fee_micro_lamports(&self) -> u64 {
+        match self.live_priority_fee.load(Ordering::Relaxed) {
+            0 => self.config.priority_fee_micro_lamports,
+            live => live,
+        }
+    }
+
+    /// 记录实时优先费率
+    pub fn set_priority_fee(&self, micro_lamports: u64) {
+        self.live_priority_fee.store(micro_lamports, Ordering::Relaxed);
+    }
+
+    /// 记录代币汇率：1 SOL 可换得的代币数量
+    pub fn set_sol_rate(&self, token: &str, tokens_per_sol: f64) {
+        if tokens_per_sol.is_finite() && tokens_per_sol > 0.0 {
+            self.sol_rates.insert(token_alias::canonical(token).into_owned(), tokens_per_sol);
+        }
+    }
+
+    /// 1 SOL 可换得的代币数量（SOL / wSOL 为 1，未定价返回 None）
+    pub fn sol_rate(&self, token: &str) -> Option<f64> {
+        let token = token_alias::canonical(token);
+        if token == "SOL" {
+            return Some(1.0);
+        }
+        self.sol_rates.get(token.as_ref()).map(|rate| *rate)
+    }
+
+    /// 按路径经过的 DEX 估算执行成本（lamports）
+    pub fn cost_lamports<'a>(&self, dex_names: impl IntoIterator<Item = &'a str>) -> u64 {
+        let hop_units: u64 = dex_names.into_iter().map(|dex| self.compute_units(dex)).sum();
+        let priority_fee = self.priority_fee_micro_lamports();
+        let config = &self.config;
+
+        let compute_units = config.overhead_compute_units + hop_units;
+        let priority_lamports = (compute_units * priority_fee).div_ceil(MICRO_LAMPORTS_PER_LAMPORT);
+        config.base_fee_lamports * config.signatures + priority_lamports + config.jito_tip_lamports
+    }
+
+    /// 路径的执行成本，按起始代币计
+    ///
+    /// 汇率优先用 USD 定价换算的值，其次用路径自身经过的 SOL 池子的成交价；
+    /// 都没有时返回 None（路径无法定价），每个代币第一次时告警。
+    pub fn cost_in_token(&self, steps: &[RouteStep], token: &str) -> Option<f64> {
+        let Some(rate) = self.sol_rate(token).or_else(|| path_sol_rate(steps, token)) else {
+            if self.unpriced_warned.insert(token.to_string(), ()).is_none() {
+                warn!(
+                    "⛽ No SOL rate for {}: paths starting from it are not reported (pass a priced ExecutionCostModel via with_execution_cost)",
+                    token
+                );
+            }
+            return None;
+        };
+        let lamports = self.cost_lamports(steps.iter().map(|s| s.dex_name.as_str()));
+        Some(lamports as f64 / LAMPORTS_PER_SOL * rate)
+    }
+
+    fn refresh_secs(&self) -> u64 {
+        self.config.refresh_secs
+    }
+
+    fn priority_fee_percentile(&self) -> u8 {
+        self.config.priority_fee_percentile
+    }
+}
+
+impl Default for ExecutionCostModel {
+    fn default() -> Self {
+        Self::new(ExecutionCostConfig::default())
+    }
+}
+
+/// 路径中 token ↔ SOL 一跳的成交价：1 SOL 可换得的 token 数量
+fn path_sol_rate(steps: &[RouteStep], token: &str) -> Option<f64> {
+    let token = token_alias::canonical(token);
+    let is_sol = |symbol: &str| token_alias::canonical(symbol) == "SOL";
+    let rate = steps.iter().find_map(|step| {
+        if is_sol(&step.output_token) && token_alias::canonical(&step.input_token) == token && step.expected_output > 0.0 {
+            Some(step.expected_input / step.expected_output)
+        } else if is_sol(&step.input_token) && token_alias::canonical(&step.output_token) == token && step.expected_input > 0.0 {
+            Some(step.expected_output / step.expected_input)
+        } else {
+            None
+        }
+    })?;
+    (rate.is_finite() && rate > 0.0).then_some(rate)
+}
+
+/// 最近区块优先费的百分位（nearest-rank），空样本返回 None
+pub fn percentile(mut fees: Vec<u64>, percentile: u8) -> Option<u64> {
+    if fees.is_empty() {
+        return None;
+    }
+    fees.sort_unstable();
+    let rank = (percentile.min(100) as f64 / 100.0 * fees.len() as f64).ceil() as usize;
+    Some(fees[rank.saturating_sub(1).min(fees.len() - 1)])
+}
+
+/// 定期刷新优先费率与起始代币的 SOL 汇率
+///
+/// `tokens`：需要换算执行成本的起始代币（路由器 start_tokens / Calculator base_token）
+pub fn spawn_refresher(
+    model: Arc<ExecutionCostModel>,
+    rpc: RpcHandle,
+    oracle: Arc<PriceOracle>,
+    tokens: Vec<String>,
+    supervisor: &Supervisor,
+) -> JoinHandle<()> {
+    spawn_supervised("execution_cost_refresher", supervisor, move || {
+        let (model, rpc, oracle, tokens) = (model.clone(), rpc.clone(), oracle.clone(), tokens.clone());
+        async move {
+            loop {
+                let refresh_secs = model.refresh_secs();
+                if refresh_secs > 0 {
+                    match rpc.call(|client| client.get_recent_prioritization_fees(&[]).map_err(Box::new)).await {
+                        Ok(samples) => {
+                            let fees = samples.iter().map(|s| s.prioritization_fee).collect();
+                            if let Some(fee) = percentile(fees, model.priority_fee_percentile()) {
+                                debug!("⛽ Priority fee p{}: {} micro-lamports/CU", model.priority_fee_percentile(), fee);
+                                model.set_priority_fee(fee);
+                            }
+                        }
+                        Err(e) => warn!("⛽ Failed to fetch recent prioritization fees: {}", e),
+                    }
+                }
+
+                refresh_sol_rates(&model, &oracle, &tokens);
+                tokio::time::sleep(Duration::from_secs(refresh_secs.max(1))).await;
+            }
+        }
+    })
+}
+
+/// 按 USD 价格换算：1 SOL = sol_usd / token_usd 个代币
+pub fn refresh_sol_rates(model: &ExecutionCostModel, oracle: &PriceOracle, tokens: &[String]) {
+    let Some(sol_usd) = oracle.get_usd_price("SOL") else { return };
+    for token in tokens {
+        if let Some(token_usd) = oracle.get_usd_price(token) {
+            model.set_sol_rate(token, sol_usd / token_usd);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::fee_registry::FeeRegistry;
+    use crate::price_cache::PoolPrice;
+    use crate::router_bellman_ford::BellmanFordScanner;
+    use crate::token_graph::TokenFilter;
+
+    fn step(dex_name: &str) -> RouteStep {
+        RouteStep {
+            pool_id: format!("{}-pool", dex_name),
+            dex_name: dex_name.to_string(),
+            input_token: "USDC".to_string(),
+            output_token: "USDC".to_string(),
+            price: 1.0,
+            liquidity_base: 0,
+            liquidity_quote: 0,
+            expected_input: 0.0,
+            expected_output: 0.0,
+            price_impact_percent: 0.0,
+            effective_fee_bps: 0.0,
+            raw_input_token: None,
+            raw_output_token: None,
+        }
+    }
+
+    #[test]
+    fn test_cost_lamports_by_dex() {
+        let model = ExecutionCostModel::new(ExecutionCostConfig {
+            compute_units: HashMap::from([("whirlpool".to_string(), 150_000)]),
+            ..ExecutionCostConfig::default()
+        });
+        assert!(model.compute_units("Orca Whirlpool") > model.compute_units("Raydium AMM V4"));
+        assert_eq!(model.compute_units("Orca Whirlpool"), 150_000);
+
+        // 5000 签名费 + (20k 开销 + 40k + 150k) CU × 10_000 micro-lamports = 2100 lamports
+        assert_eq!(model.cost_lamports(["Raydium AMM V4", "Orca Whirlpool"]), 5_000 + 2_100);
+
+        // 实时费率覆盖静态值；SOL 起始代币直接按 lamports 计
+        model.set_priority_fee(1_000_000);
+        let steps = [step("Raydium AMM V4"), step("Orca Whirlpool")];
+        assert!((model.cost_in_token(&steps, "wSOL").unwrap() - (5_000.0 + 210_000.0) / 1e9).abs() < 1e-12);
+        // 没有汇率、路径也不经过 SOL 的代币无法定价（不按 0 计）
+        assert_eq!(model.cost_in_token(&steps, "USDC"), None);
+        // 同一代币只告警一次
+        assert_eq!(model.cost_in_token(&steps, "USDC"), None);
+        assert_eq!(model.unpriced_warned.len(), 1);
+
+        assert_eq!(percentile(vec![0, 0, 100, 5_000, 20_000], 75), Some(5_000));
+        assert_eq!(percentile(vec![], 75), None);
+    }
+
+    fn pool(pool_id: &str, dex_name: &str, pair: &str, price: f64) -> PoolPrice {
+        let base_reserve = 1_000_000_000_000_000u64;
+        PoolPrice {
+            dex_name: dex_name.to_string(),
+            base_reserve,
+            quote_reserve: (base_reserve as f64 * price) as u64,
+            base_decimals: 6,
+            slot: 0,
+            ..PoolPrice::for_test(pool_id, pair, price)
+        }
+    }
+
+    #[test]
+    fn test_realistic_priority_fees_flip_thin_five_hop_path() {
+        // 100 USDC 投入，5 跳（含 3 个 Whirlpool），扣除 DEX 手续费后毛利 0.2%，
+        // 深池子价格冲击可忽略：USDC → TA → TB → TC → TD → USDC
+        let dexes = ["Orca Whirlpool", "Raydium CLMM", "Orca Whirlpool", "Meteora DLMM", "Orca Whirlpool"];
+        let tokens = ["USDC", "TA", "TB", "TC", "TD"];
+        let fee_factor: f64 = dexes.iter().map(|dex| 1.0 - FeeRegistry::dex_default(dex)).product();
+        let pools: Vec<PoolPrice> = dexes.iter().enumerate()
+            .map(|(i, dex)| {
+                let (quote, base) = (tokens[i], tokens[(i + 1) % tokens.len()]);
+                let (pair, price) = if i + 1 < tokens.len() {
+                    (format!("{}/{}", base, quote), 1.0)
+                } else {
+                    (format!("{}/USDC", quote), 1.002 / fee_factor)
+                };
+                pool(&format!("five-hop-{}", i), dex, &pair, price)
+            })
+            .collect();
+        let scan = |model: ExecutionCostModel| {
+            let paths = BellmanFordScanner::new(6, -100.0, Arc::new(model))
+                .with_token_filter(TokenFilter { start_tokens: vec!["USDC".to_string()], ..TokenFilter::default() })
+                .find_all_cycles(&pools, 100.0);
+            paths.into_iter().find(|p| p.steps.len() == 5 && p.start_token == "USDC")
+        };
+
+        // 平静时：默认优先费，SOL = $150
+        let calm = ExecutionCostModel::default();
+        calm.set_sol_rate("USDC", 150.0);
+        let path = scan(calm).expect("five-hop cycle");
+        assert!((path.gross_profit - 0.2).abs() < 1e-3, "gross {}", path.gross_profit);
+        assert!(path.roi_percent > 0.19, "roi {}", path.roi_percent);
+
+        // 拥堵时：p75 优先费 1 lamport/CU + 0.001 SOL Jito 小费
+        let congested = ExecutionCostModel::new(ExecutionCostConfig {
+            jito_tip_lamports: 1_000_000,
+            ..ExecutionCostConfig::default()
+        });
+        congested.set_priority_fee(1_000_000);
+        congested.set_sol_rate("USDC", 150.0);
+        // (20k + 120k×3 + 100k + 90k) CU × 1 lamport + 5000 + 1_000_000 = 1_575_000 lamports ≈ $0.236，超过毛利
+        let execution_fees = congested.cost_in_token(&path.steps, "USDC").unwrap();
+        assert!((execution_fees - 1_575_000.0 / 1e9 * 150.0).abs() < 1e-9);
+        assert!(path.gross_profit - execution_fees < 0.0);
+        // 同一条循环在拥堵模型下净利润为负，扫描器不再上报
+        assert!(scan(congested).is_none());
+
+        // 没有 USDC 汇率、路径也不经过 SOL：无法定价，不上报
+        assert!(scan(ExecutionCostModel::default()).is_none());
+    }
+}
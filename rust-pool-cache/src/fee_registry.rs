@@ -0,0 +1,204 @@
+/*!
+ * 统一手续费注册表
+ *
+ * 之前 DEX 手续费在 Router、BellmanFordScanner、SplitOptimizer、
+ * LstEnhancedDetector 和 amm_calculator 里各有一份硬编码表，数值还互相矛盾。
+ * 这里合并为一处，按优先级：
+ * 1. 池子级覆盖（config.toml 中 `[[pools]] fee_bps = 1`），例如 Raydium CLMM 的 1/5/25/100 bps 费率档
+ * 2. 链上费率（`DexPool::get_fee_rate`，Whirlpool / Raydium CLMM / PancakeSwap 更新缓存时写入）
+ * 3. DEX 级默认值（按 dex_name 模糊匹配）
+ */
+
+use std::sync::OnceLock;
+use dashmap::DashMap;
+
+use crate::config::PoolConfig;
+
+/// bps -> 小数费率
+const BPS_DENOMINATOR: f64 = 10_000.0;
+
+static GLOBAL_REGISTRY: OnceLock<FeeRegistry> = OnceLock::new();
+
+/// 全局注册表（路由器、拆单优化器、LST 检测器共用）
+pub fn global() -> &'static FeeRegistry {
+    GLOBAL_REGISTRY.get_or_init(FeeRegistry::new)
+}
+
+/// 便捷函数：查询某个池子的费率（小数形式，0.0025 = 0.25%）
+pub fn fee_rate(pool_id: &str, dex_name: &str) -> f64 {
+    global().fee_rate(pool_id, dex_name)
+}
+
+/// 手续费注册表
+#[derive(Debug, Default)]
+pub struct FeeRegistry {
+    /// pool_id -> fee_bps
+    pool_overrides: DashMap<String, u32>,
+    /// pool_id -> 链上读取的费率（小数形式）
+    onchain_rates: DashMap<String, f64>,
+}
+
+impl FeeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从池子配置加载 fee_bps 覆盖，返回加载数量
+    pub fn load_from_pools(&self, pools: &[PoolConfig]) -> usize {
+        let mut loaded = 0;
+        for pool in pools {
+            if let Some(bps) = pool.fee_bps {
+                self.set_pool_fee_bps(&pool.address, bps);
+                loaded += 1;
+            }
+        }
+        loaded
+    }
+
+    /// 设置单个池子的费率（bps）
+    pub fn set_pool_fee_bps(&self, pool_id: &str, fee_bps: u32) {
+        self.pool_overrides.insert(pool_id.to_string(), fee_bps);
+    }
+
+    /// 按新的池子配置重新登记覆盖（热重载时调用，删除 fee_bps 的池子回退到链上 / 默认费率）
+    pub fn reload_pool(&self, pool: &PoolConfig) {
+        match pool.fee_bps {
+            Some(bps) => self.set_pool_fee_bps(&pool.address, bps),
+            None => {
+                self.pool_overrides.remove(&pool.address);
+            }
+        }
+    }
+
+    /// 清除池子的所有费率记录（池子被删除时调用）
+    pub fn clear_pool(&self, pool_id: &str) {
+        self.pool_overrides.remove(pool_id);
+        self.onchain_rates.remove(pool_id);
+    }
+
+    /// 池子级覆盖（bps），未配置返回 None
+    pub fn pool_fee_bps(&self, pool_id: &str) -> Option<u32> {
+        self.pool_overrides.get(pool_id).map(|entry| *entry)
+    }
+
+    /// 记录从池子账户读取的链上费率
+    pub fn set_onchain_fee_rate(&self, pool_id: &str, fee_rate: f64) {
+        if fee_rate.is_finite() && (0.0..1.0).contains(&fee_rate) {
+            self.onchain_rates.insert(pool_id.to_string(), fee_rate);
+        }
+    }
+
+    /// 链上费率，未读取到返回 None
+    pub fn onchain_fee_rate(&self, pool_id: &str) -> Option<f64> {
+        self.onchain_rates.get(pool_id).map(|entry| *entry)
+    }
+
+    /// 池子费率：池子覆盖 > 链上费率 > DEX 默认值
+    pub fn fee_rate(&self, pool_id: &str, dex_name: &str) -> f64 {
+        if let Some(bps) = self.pool_fee_bps(pool_id) {
+            return bps as f64 / BPS_DENOMINATOR;
+        }
+        self.onchain_fee_rate(pool_id)
+            .unwrap_or_else(|| Self::dex_default(dex_name))
+    }
+
+    /// DEX 级默认费率
+    ///
+    /// 同时兼容展示名（"Raydium AMM V4"）和配置名（"amm_v4"）。
+    /// CLMM 类为最常见费率档，精确值请用池子级 fee_bps 覆盖。
+    pub fn dex_default(dex_name: &str) -> f64 {
+        let name = dex_name.to_lowercase();
+        match name.as_str() {
+            s if s.contains("raydium clmm") || s == "clmm" => 0.0001,   // 0.01% (可变)
+            s if s.contains("raydium") || s.contains("amm_v4") => 0.0025, // 0.25%
+            s if s.contains("orca") || s.contains("whirlpool") => 0.0001, // 0.01% (可变)
+            s if s.contains("meteora") || s.contains("dlmm") => 0.0002,   // 0.02% (可变)
+            s if s.contains("solfi") => 0.0030,                           // 0.30% (保守估计)
+            s if s.contains("alphaq") => 0.0001,                          // 0.01% (稳定币专用)
+            s if s.contains("humidifi") => 0.0010,                        // 0.10%
+            s if s.contains("lifinity") => 0.0,                           // 动态定价
+            s if s.contains("goonfi") => 0.0025,                          // 0.25% (估计)
+            s if s.contains("tessera") => 0.0020,                         // 0.20% (估计)
+            s if s.contains("stabble") || s.contains("saber") => 0.0004,  // 0.04% (稳定币)
+            s if s.contains("pancakeswap") || s == "pcs" => 0.0025,       // 0.25%
+            s if s.contains("aldrin") => 0.0020,                          // 0.20%
+            s if s.contains("phoenix") => 0.0005,                         // CLOB taker（有订单簿时按实际 taker 费）
+            _ => 0.0025,                                                  // 默认 0.25%
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dex_defaults() {
+        assert_eq!(FeeRegistry::dex_default("Raydium AMM V4"), 0.0025);
+        assert_eq!(FeeRegistry::dex_default("Raydium CLMM"), 0.0001);
+        assert_eq!(FeeRegistry::dex_default("Orca Whirlpool"), 0.0001);
+        assert_eq!(FeeRegistry::dex_default("Lifinity V2"), 0.0);
+        assert_eq!(FeeRegistry::dex_default("PancakeSwap"), 0.0025);
+        assert_eq!(FeeRegistry::dex_default("AlphaQ"), 0.0001);
+        assert_eq!(FeeRegistry::dex_default("Unknown DEX"), 0.0025);
+    }
+
+    #[test]
+    fn test_pool_override_takes_precedence() {
+        let registry = FeeRegistry::new();
+        let pools = vec![
+            PoolConfig {
+                address: "clmm-1bps".to_string(),
+                name: "SOL/USDC (CLMM 1bps)".to_string(),
+                pair: "SOL/USDC".to_string(),
+                pool_type: "clmm".to_string(),
+                fee_bps: Some(1),
+                max_age_ms: None,
+                base_mint: None,
+                quote_mint: None,
+                max_price_jump_percent: None,
+                commitment: None,
+                encoding: None,
+                priority: None,
+            },
+            PoolConfig {
+                address: "clmm-default".to_string(),
+                name: "SOL/USDC (CLMM)".to_string(),
+                pair: "SOL/USDC".to_string(),
+                pool_type: "clmm".to_string(),
+                fee_bps: None,
+                max_age_ms: None,
+                base_mint: None,
+                quote_mint: None,
+                max_price_jump_percent: None,
+                commitment: None,
+                encoding: None,
+                priority: None,
+            },
+        ];
+
+        assert_eq!(registry.load_from_pools(&pools), 1);
+        assert_eq!(registry.pool_fee_bps("clmm-1bps"), Some(1));
+        assert!((registry.fee_rate("clmm-1bps", "Raydium AMM V4") - 0.0001).abs() < 1e-12);
+        assert_eq!(registry.fee_rate("clmm-default", "Raydium AMM V4"), 0.0025);
+
+        registry.set_pool_fee_bps("clmm-default", 100);
+        assert!((registry.fee_rate("clmm-default", "Raydium CLMM") - 0.01).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_onchain_rate_beats_dex_default_but_not_config() {
+        let registry = FeeRegistry::new();
+
+        registry.set_onchain_fee_rate("whirlpool-25bps", 0.0025);
+        assert_eq!(registry.fee_rate("whirlpool-25bps", "Whirlpool (Orca)"), 0.0025);
+        assert_eq!(registry.fee_rate("whirlpool-unknown", "Whirlpool (Orca)"), 0.0001);
+
+        // 异常值直接忽略
+        registry.set_onchain_fee_rate("garbage", 42.0);
+        assert_eq!(registry.onchain_fee_rate("garbage"), None);
+
+        registry.set_pool_fee_bps("whirlpool-25bps", 5);
+        assert!((registry.fee_rate("whirlpool-25bps", "Whirlpool (Orca)") - 0.0005).abs() < 1e-12);
+    }
+}
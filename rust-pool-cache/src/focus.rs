@@ -0,0 +1,485 @@
+/*!
+ * 🎯 重点交易对盯盘
+ *
+ * `[focus]` 列出的少数交易对由独立的轻量任务盯盘，不经过 Coordinator（不受冷却 / 去抖
+ * 限制）：订阅状态层的价格更新广播，其中任一池子每更新一次，就重算该交易对所有池子
+ * 两两之间的价差矩阵：
+ *
+ * - 价差：在 buy 池买入（ask，含手续费）、在 sell 池卖出（bid，含手续费）的百分比
+ * - 价差为正的组合按 `trade_size` 用 AMM 公式算两跳路径，得到扣除执行成本后的净 ROI
+ * - 净 ROI 达到阈值的组合立即上报（日志 + 写库）；持续存在的同一组合只上报一次，
+ *   回落到阈值以下后重新上报
+ *
+ * 每个重点交易对的收到更新数、评估次数单独计数（/metrics），广播滞后丢掉的事件计入
+ * `pool_cache_focus_missed_updates_total`；该值保持为 0 即说明每次更新都被评估过。
+ */
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use dashmap::DashMap;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
+
+use crate::config::FocusConfig;
+use crate::database::{DatabaseManager, OpportunityContext};
+use crate::execution_cost::ExecutionCostModel;
+use crate::price_cache::PriceCache;
+use crate::prometheus::{MetricKind, PrometheusWriter};
+use crate::router::ArbitragePath;
+use crate::router_direct::{live_pool, DirectOpportunity, DirectQuote};
+use crate::token_alias;
+use crate::token_graph::pair_key;
+
+/// 配置中的交易对名称 → 规范交易对（两边按代币别名归一）
+pub fn focus_key(pair: &str) -> String {
+    pair.split('/')
+        .map(|token| token_alias::canonical(token.trim()).into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 评估的触发方式（/metrics 的 trigger 标签）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusTrigger {
+    /// 交易对中某个池子的价格更新
+    Update,
+    /// 启动 / 广播滞后后重新评估全部重点交易对
+    Resync,
+}
+
+impl FocusTrigger {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FocusTrigger::Update => "update",
+            FocusTrigger::Resync => "resync",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PairCounters {
+    updates: AtomicU64,
+    resyncs: AtomicU64,
+    opportunities: AtomicU64,
+}
+
+/// 重点交易对的计数（/metrics）
+#[derive(Debug, Default)]
+pub struct FocusStats {
+    pairs: DashMap<String, Arc<PairCounters>>,
+    /// 广播滞后丢掉的价格事件（不区分交易对，是漏评估次数的上界）
+    missed: AtomicU64,
+}
+
+impl FocusStats {
+    /// 预先登记所有重点交易对，没有更新的交易对也导出 0
+    pub fn new(pairs: &[String]) -> Self {
+        let stats = Self::default();
+        for pair in pairs {
+            stats.pairs.entry(focus_key(pair)).or_default();
+        }
+        stats
+    }
+
+    fn pair(&self, pair: &str) -> Arc<PairCounters> {
+        if let Some(counters) = self.pairs.get(pair) {
+            return counters.clone();
+        }
+        self.pairs.entry(pair.to_string()).or_default().clone()
+    }
+
+    fn record_evaluation(&self, pair: &str, trigger: FocusTrigger) {
+        let counters = self.pair(pair);
+        let counter = match trigger {
+            FocusTrigger::Update => &counters.updates,
+            FocusTrigger::Resync => &counters.resyncs,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_opportunities(&self, pair: &str, count: usize) {
+        self.pair(pair).opportunities.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    fn record_missed(&self, skipped: u64) {
+        self.missed.fetch_add(skipped, Ordering::Relaxed);
+    }
+
+    /// 交易对按触发方式的评估次数
+    pub fn evaluations(&self, pair: &str, trigger: FocusTrigger) -> u64 {
+        let Some(counters) = self.pairs.get(pair) else { return 0 };
+        match trigger {
+            FocusTrigger::Update => counters.updates.load(Ordering::Relaxed),
+            FocusTrigger::Resync => counters.resyncs.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn opportunities(&self, pair: &str) -> u64 {
+        self.pairs.get(pair).map_or(0, |c| c.opportunities.load(Ordering::Relaxed))
+    }
+
+    pub fn missed(&self) -> u64 {
+        self.missed.load(Ordering::Relaxed)
+    }
+
+    pub fn write_prometheus(&self, writer: &mut PrometheusWriter) {
+        writer.family(
+            "pool_cache_focus_missed_updates_total",
+            "Price events dropped by the focus watcher because the broadcast lagged",
+            MetricKind::Counter,
+        );
+        writer.sample("pool_cache_focus_missed_updates_total", &[], self.missed() as f64);
+
+        let mut pairs: Vec<(String, Arc<PairCounters>)> = self.pairs.iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        if pairs.is_empty() {
+            return;
+        }
+        pairs.sort_by(|a, b| a.0.cmp(&b.0));
+
+        writer.family(
+            "pool_cache_focus_evaluations_total",
+            "Spread matrix evaluations of focused pairs by trigger",
+            MetricKind::Counter,
+        );
+        for (pair, counters) in &pairs {
+            for (trigger, counter) in [(FocusTrigger::Update, &counters.updates), (FocusTrigger::Resync, &counters.resyncs)] {
+                writer.sample(
+                    "pool_cache_focus_evaluations_total",
+                    &[("pair", pair.as_str()), ("trigger", trigger.as_str())],
+                    counter.load(Ordering::Relaxed) as f64,
+                );
+            }
+        }
+
+        writer.family(
+            "pool_cache_focus_opportunities_total",
+            "Opportunities reported by the focus watcher",
+            MetricKind::Counter,
+        );
+        for (pair, counters) in &pairs {
+            writer.sample(
+                "pool_cache_focus_opportunities_total",
+                &[("pair", pair.as_str())],
+                counters.opportunities.load(Ordering::Relaxed) as f64,
+            );
+        }
+    }
+}
+
+/// 价差矩阵中的一个有序组合（在 buy 池买入，在 sell 池卖出）
+#[derive(Debug, Clone, Serialize)]
+pub struct FocusSpread {
+    pub buy_pool: String,
+    pub sell_pool: String,
+    /// 扣除两边手续费后的价差（百分比）
+    pub spread_percent: f64,
+    /// 按 trade_size 计算的净 ROI（价差不为正或池子不可用时为 None）
+    pub roi_percent: Option<f64>,
+}
+
+/// 一次评估的结果
+#[derive(Debug, Clone)]
+pub struct FocusEvaluation {
+    pub pair: String,
+    pub matrix: Vec<FocusSpread>,
+    /// 本次新达到阈值的机会（按 ROI 从高到低）
+    pub opportunities: Vec<ArbitragePath>,
+}
+
+/// 价差矩阵计算（不含 IO，便于测试）
+pub struct FocusWatcher {
+    price_cache: Arc<PriceCache>,
+    trade_size: f64,
+    min_roi_percent: f64,
+    /// 规范交易对 -> 池子
+    pools: HashMap<String, BTreeSet<String>>,
+    /// 规范交易对 -> 当前达到阈值的组合签名（持续存在时不重复上报）
+    active: HashMap<String, HashSet<u64>>,
+    stats: Arc<FocusStats>,
+    execution_cost: Arc<ExecutionCostModel>,
+}
+
+impl FocusWatcher {
+    pub fn new(config: &FocusConfig, price_cache: Arc<PriceCache>, stats: Arc<FocusStats>) -> Self {
+        let pools = config.pairs.iter().map(|pair| (focus_key(pair), BTreeSet::new())).collect();
+        let mut watcher = Self {
+            price_cache,
+            trade_size: config.trade_size,
+            min_roi_percent: config.min_roi_percent,
+            pools,
+            active: HashMap::new(),
+            stats,
+            execution_cost: ExecutionCostModel::shared_default(),
+        };
+        watcher.seed();
+        watcher
+    }
+
+    /// ⛽ 与生产路由器共用执行成本模型
+    pub fn with_execution_cost(mut self, execution_cost: Arc<ExecutionCostModel>) -> Self {
+        self.execution_cost = execution_cost;
+        self
+    }
+
+    /// 规范化后的重点交易对
+    pub fn pairs(&self) -> Vec<String> {
+        let mut pairs: Vec<String> = self.pools.keys().cloned().collect();
+        pairs.sort();
+        pairs
+    }
+
+    /// 从缓存中已有的池子建立交易对 -> 池子索引
+    fn seed(&mut self) {
+        for pool in self.price_cache.get_all_prices() {
+            if let Some(ids) = self.pools.get_mut(&pair_key(&pool)) {
+                ids.insert(pool.pool_id);
+            }
+        }
+    }
+
+    /// 价格事件入口：池子属于重点交易对时评估该交易对，否则返回 None
+    pub fn on_update(&mut self, pool_id: &str) -> Option<FocusEvaluation> {
+        let pair = self.price_cache.get_price(pool_id).map(|pool| pair_key(&pool));
+        // 池子被移除或改名：从原交易对中去掉
+        for (focused, ids) in self.pools.iter_mut() {
+            if pair.as_deref() != Some(focused.as_str()) {
+                ids.remove(pool_id);
+            }
+        }
+        let pair = pair?;
+        self.pools.get_mut(&pair)?.insert(pool_id.to_string());
+
+        self.stats.record_evaluation(&pair, FocusTrigger::Update);
+        Some(self.evaluate(&pair))
+    }
+
+    /// 重新评估全部重点交易对（启动 / 广播滞后之后）
+    pub fn resync(&mut self) -> Vec<FocusEvaluation> {
+        self.seed();
+        self.pairs()
+            .into_iter()
+            .map(|pair| {
+                self.stats.record_evaluation(&pair, FocusTrigger::Resync);
+                self.evaluate(&pair)
+            })
+            .collect()
+    }
+
+    /// 重算交易对的价差矩阵，返回新达到阈值的机会
+    pub fn evaluate(&mut self, pair: &str) -> FocusEvaluation {
+        let quotes: Vec<DirectQuote> = self.pools.get(pair)
+            .into_iter()
+            .flatten()
+            .filter_map(|pool_id| live_pool(&self.price_cache, pool_id))
+            .filter_map(|pool| DirectQuote::from_pool(&pool))
+            .collect();
+
+        let detected_at = Instant::now();
+        let mut matrix = Vec::new();
+        let mut clearing = Vec::new();
+        for buy in &quotes {
+            for sell in quotes.iter().filter(|sell| sell.pool_id != buy.pool_id) {
+                let opportunity = DirectOpportunity {
+                    pair: pair.to_string(),
+                    buy: buy.clone(),
+                    sell: sell.clone(),
+                    spread_percent: (sell.bid - buy.ask) / buy.ask * 100.0,
+                    detected_at,
+                    aliases: token_alias::global().clone(),
+                    execution_cost: self.execution_cost.clone(),
+                };
+                let path = (opportunity.spread_percent > 0.0)
+                    .then(|| opportunity.to_path(&self.price_cache, self.trade_size))
+                    .flatten();
+                matrix.push(FocusSpread {
+                    buy_pool: buy.pool_id.clone(),
+                    sell_pool: sell.pool_id.clone(),
+                    spread_percent: opportunity.spread_percent,
+                    roi_percent: path.as_ref().map(|p| p.roi_percent),
+                });
+                if let Some(path) = path.filter(|p| p.roi_percent >= self.min_roi_percent) {
+                    clearing.push(path);
+                }
+            }
+        }
+
+        let active = self.active.entry(pair.to_string()).or_default();
+        let previous = std::mem::replace(active, clearing.iter().map(|p| p.signature().key()).collect());
+        let mut opportunities: Vec<ArbitragePath> = clearing.into_iter()
+            .filter(|path| !previous.contains(&path.signature().key()))
+            .collect();
+        opportunities.sort_by(|a, b| b.roi_percent.total_cmp(&a.roi_percent));
+        self.stats.record_opportunities(pair, opportunities.len());
+
+        FocusEvaluation {
+            pair: pair.to_string(),
+            matrix,
+            opportunities,
+        }
+    }
+}
+
+/// 写库所需的连接和路由模式
+#[derive(Clone)]
+pub struct FocusRecorder {
+    pub db: Arc<tokio::sync::Mutex<DatabaseManager>>,
+    pub router_mode: String,
+    pub min_roi_threshold: f64,
+}
+
+/// 上报：info 日志，配置了数据库时在后台写库（不阻塞下一次评估）
+fn report(evaluation: FocusEvaluation, trigger_source: &str, started: Instant, recorder: Option<&FocusRecorder>) {
+    if evaluation.opportunities.is_empty() {
+        return;
+    }
+    for path in &evaluation.opportunities {
+        info!(
+            "🎯 Focus {} opportunity {}: ROI {:.4}% ({:.4} {} on {:.2})",
+            evaluation.pair, path.signature(), path.roi_percent,
+            path.net_profit, path.start_token, path.input_amount
+        );
+    }
+
+    let Some(recorder) = recorder.cloned() else {
+        return;
+    };
+    let context = OpportunityContext {
+        trigger_type: "focus".to_string(),
+        trigger_source: trigger_source.to_string(),
+        scan_latency_ms: started.elapsed().as_secs_f64() * 1000.0,
+        ..Default::default()
+    };
+    tokio::spawn(async move {
+        let db = recorder.db.lock().await;
+        for path in &evaluation.opportunities {
+            if let Err(e) = db.record_opportunity_with_context(path, &recorder.router_mode, recorder.min_roi_threshold, Some(&context)).await {
+                warn!("Failed to record focus opportunity {}: {}", path.signature(), e);
+            }
+        }
+    });
+}
+
+/// 盯盘任务：订阅价格更新，重点交易对的每次更新都立即评估；收到关闭信号时退出
+///
+/// 每次（重新）启动都先评估一遍全部交易对，补上订阅之前错过的更新。
+pub async fn run_focus_watcher(
+    config: FocusConfig,
+    price_cache: Arc<PriceCache>,
+    stats: Arc<FocusStats>,
+    recorder: Option<FocusRecorder>,
+    execution_cost: Arc<ExecutionCostModel>,
+    mut shutdown: broadcast::Receiver<()>,
+) {
+    let mut updates = price_cache.subscribe_updates();
+    let mut watcher = FocusWatcher::new(&config, price_cache, stats.clone()).with_execution_cost(execution_cost);
+    let started = Instant::now();
+    for evaluation in watcher.resync() {
+        report(evaluation, "resync", started, recorder.as_ref());
+    }
+
+    loop {
+        tokio::select! {
+            event = updates.recv() => match event {
+                Ok(event) => {
+                    let started = Instant::now();
+                    if let Some(evaluation) = watcher.on_update(&event.pool_id) {
+                        report(evaluation, &event.pool_id, started, recorder.as_ref());
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("🎯 Focus watcher lagged {} price events, re-evaluating focused pairs", skipped);
+                    stats.record_missed(skipped);
+                    let started = Instant::now();
+                    for evaluation in watcher.resync() {
+                        report(evaluation, "resync", started, recorder.as_ref());
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = shutdown.recv() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price_cache::PoolPrice;
+
+    fn config(pairs: &[&str]) -> FocusConfig {
+        FocusConfig {
+            pairs: pairs.iter().map(|p| p.to_string()).collect(),
+            trade_size: 100.0,
+            min_roi_percent: 0.3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_every_update_evaluates_matrix_and_reports_once() {
+        let cache = Arc::new(PriceCache::new());
+        cache.update_price(PoolPrice::for_test("focus-a", "JTO/USDC", 2.00));
+        let stats = Arc::new(FocusStats::new(&["JTO/USDC".to_string()]));
+        let mut watcher = FocusWatcher::new(&config(&["JTO/USDC"]), cache.clone(), stats.clone())
+            .with_execution_cost(crate::router_fixture::priced_execution_cost(&[("USDC", 150.0)]));
+
+        // 非重点交易对的更新不评估
+        cache.update_price(PoolPrice::for_test("other", "BONK/USDC", 0.00002));
+        assert!(watcher.on_update("other").is_none());
+
+        // 价差扣掉两边手续费后低于阈值：照样评估，矩阵包含两个方向
+        cache.update_price(PoolPrice::for_test("focus-b", "JTO/USDC", 2.01));
+        let evaluation = watcher.on_update("focus-b").unwrap();
+        assert_eq!(evaluation.matrix.len(), 2);
+        assert!(evaluation.opportunities.is_empty());
+
+        cache.update_price(PoolPrice::for_test("focus-b", "JTO/USDC", 2.04));
+        let evaluation = watcher.on_update("focus-b").unwrap();
+        assert_eq!(evaluation.opportunities.len(), 1);
+        assert_eq!(evaluation.opportunities[0].signature(), "focus-a->focus-b");
+        let reverse = evaluation.matrix.iter().find(|s| s.buy_pool == "focus-b").unwrap();
+        assert!(reverse.spread_percent < 0.0 && reverse.roi_percent.is_none());
+
+        // 机会持续存在时不重复上报，回落后再次出现时重新上报
+        assert!(watcher.on_update("focus-a").unwrap().opportunities.is_empty());
+        cache.update_price(PoolPrice::for_test("focus-b", "JTO/USDC", 2.00));
+        assert!(watcher.on_update("focus-b").unwrap().opportunities.is_empty());
+        cache.update_price(PoolPrice::for_test("focus-b", "JTO/USDC", 2.04));
+        assert_eq!(watcher.on_update("focus-b").unwrap().opportunities.len(), 1);
+
+        assert_eq!(stats.evaluations("JTO/USDC", FocusTrigger::Update), 5);
+        assert_eq!(stats.opportunities("JTO/USDC"), 2);
+        assert_eq!(stats.missed(), 0);
+    }
+
+    #[test]
+    fn test_resync_covers_aliased_pairs_and_exports_metrics() {
+        let cache = Arc::new(PriceCache::new());
+        cache.update_price(PoolPrice::for_test("sol-a", "SOL/USDC", 185.0));
+        cache.update_price(PoolPrice::for_test("sol-b", "SOL/USDC", 185.1));
+        // 配置写 wSOL/USDC，与 SOL/USDC 是同一个交易对
+        let pairs = vec!["wSOL/USDC".to_string(), "JUP/USDC".to_string()];
+        let stats = Arc::new(FocusStats::new(&pairs));
+        let mut watcher = FocusWatcher::new(&config(&["wSOL/USDC", "JUP/USDC"]), cache, stats.clone());
+        assert_eq!(watcher.pairs(), ["JUP/USDC", "SOL/USDC"]);
+
+        let evaluations = watcher.resync();
+        assert_eq!(evaluations.len(), 2);
+        assert_eq!(evaluations.iter().find(|e| e.pair == "SOL/USDC").unwrap().matrix.len(), 2);
+        stats.record_missed(3);
+
+        let mut writer = PrometheusWriter::new();
+        stats.write_prometheus(&mut writer);
+        let output = writer.finish();
+        assert!(output.contains("pool_cache_focus_missed_updates_total 3"));
+        assert!(output.contains("pool_cache_focus_evaluations_total{pair=\"SOL/USDC\",trigger=\"resync\"} 1"));
+        assert!(output.contains("pool_cache_focus_evaluations_total{pair=\"JUP/USDC\",trigger=\"update\"} 0"));
+    }
+}
@@ -0,0 +1,300 @@
+/*!
+ * 就绪探测（GET /health）
+ *
+ * 每个后台任务更新一个心跳时间戳（epoch 毫秒的 AtomicU64），/health 按各自阈值评估：
+ *
+ * - websocket：已连接且最近收到过消息（断开或长时间无消息 → down）
+ * - pools：配置的池子中价格新鲜的数量（一个都没有 → down，不到一半 → degraded）
+ * - coordinator：100ms 周期的 tick（超过 10 秒没有 tick → down）
+ * - calculator：最近一次扫描完成时间（扫描由价格事件触发，只报 degraded）
+ * - database / stake_pool_reader / deserializers：可选组件，异常时 degraded
+ * - quarantine：有池子被熔断隔离时 degraded
+ * - pool_owners：有池子因 owner 不匹配被拒绝激活时 degraded
+ *
+ * 整体状态取最差的组件；down 时返回 503，供 Kubernetes 探针使用。
+ */
+
+use serde::Serialize;
+
+use crate::circuit_breaker::Quarantine;
+use crate::pool_stats::DegradedPool;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// WebSocket 超过该时间没有消息 → degraded
+const WS_STALE_MS: u64 = 30_000;
+/// WebSocket 超过该时间没有消息 → down
+const WS_DOWN_MS: u64 = 120_000;
+/// 池子价格在该时间内更新过才算新鲜
+pub const FRESH_PRICE_MS: u64 = 60_000;
+/// Coordinator tick 间隔 100ms：超过 1 秒 degraded，超过 10 秒 down
+const COORDINATOR_STALE_MS: u64 = 1_000;
+const COORDINATOR_DOWN_MS: u64 = 10_000;
+/// Calculator 超过该时间没有完成扫描 → degraded
+const CALCULATOR_STALE_MS: u64 = 60_000;
+/// Stake pool 缓存超过 N 个刷新周期没有更新 → degraded
+const STAKE_POOL_STALE_INTERVALS: u32 = 3;
+
+/// 当前时间（epoch 毫秒，与心跳时间戳一致）
+pub fn now_ms() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
+/// 组件 / 整体状态（按严重程度排序）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    Degraded,
+    Down,
+}
+
+/// 单个组件的状态
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentHealth {
+    pub component: &'static str,
+    pub status: HealthStatus,
+    /// 距离最近一次心跳的时间（没有心跳的组件为 None）
+    pub age_ms: Option<u64>,
+    pub detail: String,
+}
+
+impl ComponentHealth {
+    pub fn new(component: &'static str, status: HealthStatus, age_ms: Option<u64>, detail: impl Into<String>) -> Self {
+        Self { component, status, age_ms, detail: detail.into() }
+    }
+}
+
+/// 整体状态：取最差的组件
+pub fn overall_status(components: &[ComponentHealth]) -> HealthStatus {
+    components.iter().map(|c| c.status).max().unwrap_or(HealthStatus::Ok)
+}
+
+/// 📡 WebSocket 心跳（连接状态 + 最近消息时间）
+#[derive(Clone, Default)]
+pub struct WsHeartbeat {
+    connected: Arc<AtomicBool>,
+    last_message_ms: Arc<AtomicU64>,
+}
+
+impl WsHeartbeat {
+    pub fn set_connected(&self, connected: bool) {
+        self.connected.store(connected, Ordering::Relaxed);
+    }
+
+    pub fn record_message(&self) {
+        self.last_message_ms.store(now_ms(), Ordering::Relaxed);
+    }
+
+    /// WebSocket 组件状态
+    pub fn evaluate(&self, now_ms: u64) -> ComponentHealth {
+        let last = self.last_message_ms.load(Ordering::Relaxed);
+        let age_ms = (last > 0).then(|| now_ms.saturating_sub(last));
+
+        if !self.connected.load(Ordering::Relaxed) {
+            return ComponentHealth::new("websocket", HealthStatus::Down, age_ms, "disconnected");
+        }
+        match age_ms {
+            None => ComponentHealth::new("websocket", HealthStatus::Down, None, "connected, no messages yet"),
+            Some(age) if age > WS_DOWN_MS => {
+                ComponentHealth::new("websocket", HealthStatus::Down, age_ms, format!("no messages for {}s", age / 1000))
+            }
+            Some(age) if age > WS_STALE_MS => {
+                ComponentHealth::new("websocket", HealthStatus::Degraded, age_ms, format!("no messages for {}s", age / 1000))
+            }
+            Some(_) => ComponentHealth::new("websocket", HealthStatus::Ok, age_ms, "receiving"),
+        }
+    }
+}
+
+/// 后台任务心跳（API 只读）
+#[derive(Clone, Default)]
+pub struct Heartbeats {
+    pub websocket: WsHeartbeat,
+    /// Coordinator 最近一次 tick
+    pub coordinator_tick: Arc<AtomicU64>,
+    /// Calculator 最近一次完成扫描
+    pub calculator_scan: Arc<AtomicU64>,
+}
+
+impl Heartbeats {
+    /// 心跳类组件（websocket / coordinator / calculator）
+    pub fn evaluate(&self, now_ms: u64) -> Vec<ComponentHealth> {
+        vec![
+            self.websocket.evaluate(now_ms),
+            heartbeat_health(
+                "coordinator",
+                self.coordinator_tick.load(Ordering::Relaxed),
+                now_ms,
+                COORDINATOR_STALE_MS,
+                Some(COORDINATOR_DOWN_MS),
+            ),
+            heartbeat_health(
+                "calculator",
+                self.calculator_scan.load(Ordering::Relaxed),
+                now_ms,
+                CALCULATOR_STALE_MS,
+                None,
+            ),
+        ]
+    }
+}
+
+/// 按心跳时间评估：超过 stale_ms → degraded，超过 down_ms（设置时）或从未心跳 → down / degraded
+fn heartbeat_health(
+    component: &'static str,
+    last_ms: u64,
+    now_ms: u64,
+    stale_ms: u64,
+    down_ms: Option<u64>,
+) -> ComponentHealth {
+    let worst = if down_ms.is_some() { HealthStatus::Down } else { HealthStatus::Degraded };
+    if last_ms == 0 {
+        return ComponentHealth::new(component, worst, None, "no heartbeat yet");
+    }
+    let age = now_ms.saturating_sub(last_ms);
+    let status = match down_ms {
+        Some(down) if age > down => HealthStatus::Down,
+        _ if age > stale_ms => HealthStatus::Degraded,
+        _ => HealthStatus::Ok,
+    };
+    ComponentHealth::new(component, status, Some(age), format!("last heartbeat {}ms ago", age))
+}
+
+/// 配置的池子中价格新鲜的比例
+pub fn pools_health(configured: usize, fresh: usize) -> ComponentHealth {
+    let status = if configured > 0 && fresh == 0 {
+        HealthStatus::Down
+    } else if fresh * 2 < configured {
+        HealthStatus::Degraded
+    } else {
+        HealthStatus::Ok
+    };
+    ComponentHealth::new("pools", status, None, format!("{}/{} configured pools fresh", fresh, configured))
+}
+
+/// 🧯 熔断隔离的池子（有任何一个 → degraded）
+pub fn quarantine_health(quarantined: &[Quarantine]) -> ComponentHealth {
+    if quarantined.is_empty() {
+        return ComponentHealth::new("quarantine", HealthStatus::Ok, None, "no pools quarantined");
+    }
+    let pools: Vec<String> = quarantined.iter()
+        .map(|q| format!("{} ({} {:.1}%)", q.pair, q.reason.as_str(), q.change_percent))
+        .collect();
+    ComponentHealth::new(
+        "quarantine",
+        HealthStatus::Degraded,
+        None,
+        format!("{} quarantined: {}", quarantined.len(), pools.join(", ")),
+    )
+}
+
+/// 🔒 owner 不匹配被拒绝激活的池子（有任何一个 → degraded）
+pub fn degraded_pools_health(degraded: &[DegradedPool]) -> ComponentHealth {
+    if degraded.is_empty() {
+        return ComponentHealth::new("pool_owners", HealthStatus::Ok, None, "all pool owners verified");
+    }
+    let pools: Vec<String> = degraded.iter()
+        .map(|d| format!("{} ({})", d.pool_name, d.reason))
+        .collect();
+    ComponentHealth::new(
+        "pool_owners",
+        HealthStatus::Degraded,
+        None,
+        format!("{} degraded: {}", degraded.len(), pools.join(", ")),
+    )
+}
+
+/// Stake pool 缓存年龄（相对刷新周期）
+pub fn stake_pool_health(age: Duration, refresh_interval: Duration) -> ComponentHealth {
+    let status = if age > refresh_interval * STAKE_POOL_STALE_INTERVALS {
+        HealthStatus::Degraded
+    } else {
+        HealthStatus::Ok
+    };
+    ComponentHealth::new(
+        "stake_pool_reader",
+        status,
+        Some(age.as_millis() as u64),
+        format!("cache refreshed {}s ago (interval {}s)", age.as_secs(), refresh_interval.as_secs()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_component_thresholds_and_overall_status() {
+        let now = 1_000_000;
+        let heartbeats = Heartbeats::default();
+
+        // 启动时：WebSocket 未连接、Coordinator 没有 tick → down；Calculator 只是 degraded
+        let components = heartbeats.evaluate(now);
+        assert_eq!(components[0].status, HealthStatus::Down);
+        assert_eq!(components[1].status, HealthStatus::Down);
+        assert_eq!(components[2].status, HealthStatus::Degraded);
+        assert_eq!(overall_status(&components), HealthStatus::Down);
+
+        heartbeats.websocket.set_connected(true);
+        heartbeats.websocket.last_message_ms.store(now - 500, Ordering::Relaxed);
+        heartbeats.coordinator_tick.store(now - 100, Ordering::Relaxed);
+        heartbeats.calculator_scan.store(now - 2_000, Ordering::Relaxed);
+        let mut components = heartbeats.evaluate(now);
+        assert_eq!(overall_status(&components), HealthStatus::Ok);
+
+        // 只有一半以下的池子新鲜 → degraded；一个都没有 → down
+        components.push(pools_health(10, 4));
+        assert_eq!(overall_status(&components), HealthStatus::Degraded);
+        assert_eq!(pools_health(10, 0).status, HealthStatus::Down);
+        assert_eq!(pools_health(0, 0).status, HealthStatus::Ok);
+
+        // 消息停了 60 秒 → degraded，5 分钟 → down；Coordinator 卡住 → down
+        assert_eq!(heartbeats.websocket.evaluate(now + 60_000).status, HealthStatus::Degraded);
+        assert_eq!(heartbeats.websocket.evaluate(now + 300_000).status, HealthStatus::Down);
+        assert_eq!(heartbeats.evaluate(now + 20_000)[1].status, HealthStatus::Down);
+        assert_eq!(heartbeats.evaluate(now + 20_000)[2].status, HealthStatus::Ok);
+
+        heartbeats.websocket.set_connected(false);
+        assert_eq!(heartbeats.websocket.evaluate(now).status, HealthStatus::Down);
+
+        let interval = Duration::from_secs(60);
+        assert_eq!(stake_pool_health(Duration::from_secs(90), interval).status, HealthStatus::Ok);
+        assert_eq!(stake_pool_health(Duration::from_secs(400), interval).status, HealthStatus::Degraded);
+    }
+
+    #[test]
+    fn test_quarantine_degrades() {
+        use crate::circuit_breaker::TripReason;
+
+        assert_eq!(quarantine_health(&[]).status, HealthStatus::Ok);
+        let now = std::time::Instant::now();
+        let quarantined = quarantine_health(&[Quarantine {
+            pool_id: "pool".to_string(),
+            pair: "SOL/USDC".to_string(),
+            reason: TripReason::PriceJump,
+            change_percent: 90.0,
+            trips: 1,
+            sane_updates: 0,
+            since: now,
+            until: now,
+        }]);
+        assert_eq!(quarantined.status, HealthStatus::Degraded);
+        assert_eq!(quarantined.detail, "1 quarantined: SOL/USDC (price_jump 90.0%)");
+    }
+
+    #[test]
+    fn test_owner_mismatch_degrades() {
+        use crate::pool_stats::DegradedReason;
+
+        assert_eq!(degraded_pools_health(&[]).status, HealthStatus::Ok);
+        let degraded = degraded_pools_health(&[DegradedPool {
+            pool_name: "SOL/USDC (Raydium V4)".to_string(),
+            reason: DegradedReason::OwnerMismatch { expected: "675k".to_string(), actual: "whirL".to_string() },
+            since: chrono::Utc::now(),
+        }]);
+        assert_eq!(degraded.status, HealthStatus::Degraded);
+        assert_eq!(degraded.detail, "1 degraded: SOL/USDC (Raydium V4) (owner_mismatch{expected: 675k, actual: whirL})");
+    }
+}
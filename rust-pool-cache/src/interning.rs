@@ -0,0 +1,161 @@
+/*!
+ * 代币 / 池子 ID 驻留（interning）
+ *
+ * 路由器热路径（BFS 扩展、Bellman-Ford 松弛、三角套利枚举）只比较和哈希 `u32`：
+ * 每次扫描把规范代币符号 intern 成 `TokenId`（按 mint 配置的池子已由 `pool_mints`
+ * 反查成符号），池子用它在本次扫描池子列表中的下标作为 `PoolId`，交易对是一组
+ * `TokenId`（`PairId`）。
+ *
+ * ID 只在一次扫描内有效，不跨扫描、不序列化：字符串只在生成 `ArbitragePath`
+ * （API / 数据库 / 通知的边界）时从 `TokenRegistry` 解析回来。
+ */
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// 驻留后的代币（`TokenRegistry` 中的下标）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TokenId(u32);
+
+impl TokenId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// 池子在本次扫描池子列表中的下标
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PoolId(u32);
+
+impl PoolId {
+    pub fn new(index: usize) -> Self {
+        PoolId(index as u32)
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// 有方向的交易对（base / quote 各一个 `TokenId`），对应 `token_graph::pair_key` 的 "BASE/QUOTE"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PairId {
+    pub base: TokenId,
+    pub quote: TokenId,
+}
+
+impl PairId {
+    pub fn new(base: TokenId, quote: TokenId) -> Self {
+        Self { base, quote }
+    }
+
+    /// 反方向的交易对（QUOTE/BASE）
+    pub fn reversed(self) -> Self {
+        Self { base: self.quote, quote: self.base }
+    }
+}
+
+/// 代币符号 ↔ `TokenId` 双向映射
+#[derive(Debug, Clone, Default)]
+pub struct TokenRegistry {
+    symbols: Vec<Arc<str>>,
+    ids: HashMap<Arc<str>, TokenId>,
+}
+
+impl TokenRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 按给定顺序 intern
+    ///
+    /// 传入排序好的代币时 `TokenId` 的大小顺序与符号的字典序一致，
+    /// 按 ID 排序 / 去重的结果与按字符串时相同（路由结果保持确定性）。
+    pub fn from_symbols<S: AsRef<str>>(symbols: &[S]) -> Self {
+        let mut registry = Self::new();
+        for symbol in symbols {
+            registry.intern(symbol.as_ref());
+        }
+        registry
+    }
+
+    pub fn intern(&mut self, symbol: &str) -> TokenId {
+        if let Some(&id) = self.ids.get(symbol) {
+            return id;
+        }
+        let id = TokenId(self.symbols.len() as u32);
+        let symbol: Arc<str> = Arc::from(symbol);
+        self.symbols.push(symbol.clone());
+        self.ids.insert(symbol, id);
+        id
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<TokenId> {
+        self.ids.get(symbol).copied()
+    }
+
+    pub fn symbol(&self, id: TokenId) -> &str {
+        &self.symbols[id.index()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// 全部 `TokenId`，按符号字典序排列
+    pub fn sorted_ids(&self) -> Vec<TokenId> {
+        let mut ids: Vec<TokenId> = (0..self.symbols.len() as u32).map(TokenId).collect();
+        ids.sort_by(|a, b| self.symbol(*a).cmp(self.symbol(*b)));
+        ids
+    }
+}
+
+/// 路径签名：代币序列 + 池子序列的 64 位哈希（替代 "A->B::p1|p2" 拼接字符串）
+///
+/// 只在同一次扫描（同一个 `TokenRegistry` / 池子列表）内可比较。
+pub fn path_signature(tokens: &[TokenId], pools: impl IntoIterator<Item = PoolId>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    tokens.hash(&mut hasher);
+    for pool in pools {
+        pool.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_round_trip_and_ordering() {
+        let mut registry = TokenRegistry::from_symbols(&["JUP", "SOL", "USDC"]);
+        let sol = registry.get("SOL").unwrap();
+        assert_eq!(registry.intern("SOL"), sol);
+        assert_eq!(registry.symbol(sol), "SOL");
+        assert!(registry.get("BONK").is_none());
+
+        // 排序输入 → ID 顺序与字典序一致；之后追加的代币仍可按符号排序
+        assert!(registry.get("JUP").unwrap() < sol);
+        let bonk = registry.intern("BONK");
+        assert_eq!(registry.len(), 4);
+        assert_eq!(registry.sorted_ids()[0], bonk);
+
+        let usdc = registry.get("USDC").unwrap();
+        let forward = path_signature(&[sol, usdc, sol], [PoolId::new(0), PoolId::new(1)]);
+        assert_eq!(forward, path_signature(&[sol, usdc, sol], [PoolId::new(0), PoolId::new(1)]));
+        assert_ne!(forward, path_signature(&[sol, usdc, sol], [PoolId::new(1), PoolId::new(0)]));
+        assert_ne!(forward, path_signature(&[usdc, sol, usdc], [PoolId::new(0), PoolId::new(1)]));
+
+        // 交易对有方向：SOL/USDC 与 USDC/SOL 不同，反转后相同
+        let sol_usdc = PairId::new(sol, usdc);
+        assert_ne!(sol_usdc, PairId::new(usdc, sol));
+        assert_eq!(sol_usdc.reversed(), PairId::new(usdc, sol));
+        assert_eq!(sol_usdc.reversed().reversed(), sol_usdc);
+    }
+}
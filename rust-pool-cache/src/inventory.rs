@@ -0,0 +1,361 @@
+/*!
+ * 💼 库存感知（[inventory]）
+ *
+ * 路由器扫描时假设起始代币无限供应。Calculator 在验证之前按实际持有的余额对路径做后处理：
+ *
+ * - 起始代币有持有：投入超过余额时截断到余额，按最新缓存逐跳重新定价，低于 ROI 阈值的丢弃
+ * - 起始代币没有持有：`convert_from_held` 时尝试从持有的代币经最深的池子前置兑换一跳
+ *   （兑换的手续费和价格冲击按现价折算后计入 ROI），否则按 `unheld` 过滤或排到最后
+ *
+ * 保留下来的每条路径都带一个 `CapitalRequirement`（实际需要的资金、来源库存、前置兑换），
+ * 写入机会输出的 `capital` 字段。
+ */
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+
+use crate::config::{InventoryConfig, UnheldPathPolicy};
+use crate::fee_registry;
+use crate::price_cache::{PoolPrice, PriceCache};
+use crate::router::{ArbitragePath, RouteStep};
+use crate::router_direct::{live_pool, swap_step};
+use crate::token_alias;
+use crate::token_graph::pool_tokens;
+
+/// 一条机会实际需要的资金
+#[derive(Debug, Clone, Serialize)]
+pub struct CapitalRequirement {
+    /// 动用的代币（有前置兑换时是兑换的源代币，否则是起始代币）
+    pub token: String,
+    /// 需要的数量（`token` 计）
+    pub amount: f64,
+    /// 来源库存（起始代币没有持有、按 down_rank 保留的路径为 None）
+    pub bucket: Option<String>,
+    /// 该库存的可用余额
+    pub available: Option<f64>,
+    /// 投入是否按余额截断过
+    pub capped: bool,
+    /// 前置兑换（持有的代币 -> 起始代币）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversion: Option<RouteStep>,
+    /// 按实际投入计算的 ROI（含前置兑换损耗）
+    pub roi_percent: f64,
+}
+
+/// 后处理结果
+#[derive(Debug, Default)]
+pub struct InventoryPlan {
+    /// 可执行的路径在前（保持原顺序），没有持有的路径（down_rank）在后
+    pub paths: Vec<ArbitragePath>,
+    /// 路径签名键（`PathSignature::key`）-> 资金需求
+    pub capital: HashMap<u64, CapitalRequirement>,
+    /// 丢弃的路径数（没有持有，或按余额重新定价后低于阈值）
+    pub dropped: usize,
+}
+
+/// 持有余额 + 处理策略
+#[derive(Debug, Clone)]
+pub struct Inventory {
+    /// 规范代币符号 -> 可用数量（只保留正余额；有序，前置兑换的候选顺序稳定）
+    balances: BTreeMap<String, f64>,
+    unheld: UnheldPathPolicy,
+    convert_from_held: bool,
+}
+
+impl Inventory {
+    pub fn from_config(config: &InventoryConfig) -> Self {
+        let mut balances = BTreeMap::new();
+        for (token, amount) in &config.balances {
+            if amount.is_finite() && *amount > 0.0 {
+                *balances.entry(token_alias::canonical(token).into_owned()).or_insert(0.0) += amount;
+            }
+        }
+        Self {
+            balances,
+            unheld: config.unheld,
+            convert_from_held: config.convert_from_held,
+        }
+    }
+
+    /// 代币的可用余额（没有持有为 None）
+    pub fn available(&self, token: &str) -> Option<f64> {
+        self.balances.get(token_alias::canonical(token).as_ref()).copied()
+    }
+
+    /// 按持有余额处理一批路径（`min_roi_percent` 为重新定价后的保留阈值）
+    pub fn plan(&self, paths: Vec<ArbitragePath>, price_cache: &PriceCache, min_roi_percent: f64) -> InventoryPlan {
+        let mut plan = InventoryPlan::default();
+        let mut unheld = Vec::new();
+        // 前置兑换要在全部池子里找最深的，一批路径只取一次
+        let pools = if self.convert_from_held { price_cache.get_all_prices() } else { Vec::new() };
+
+        for path in paths {
+            let start = token_alias::canonical(&path.start_token).into_owned();
+            let planned = match self.available(&start) {
+                Some(available) => match self.size_to_balance(&path, &start, available, price_cache, min_roi_percent) {
+                    Some(planned) => planned,
+                    None => {
+                        plan.dropped += 1;
+                        continue;
+                    }
+                },
+                None => match self.convert_from_held(&path, &start, &pools, price_cache, min_roi_percent) {
+                    Some(planned) => planned,
+                    None if self.unheld == UnheldPathPolicy::DownRank => {
+                        let capital = CapitalRequirement {
+                            token: start,
+                            amount: path.input_amount,
+                            bucket: None,
+                            available: None,
+                            capped: false,
+                            conversion: None,
+                            roi_percent: path.roi_percent,
+                        };
+                        plan.capital.insert(path.signature().key(), capital);
+                        unheld.push(path);
+                        continue;
+                    }
+                    None => {
+                        plan.dropped += 1;
+                        continue;
+                    }
+                },
+            };
+            let (path, capital) = planned;
+            plan.capital.insert(path.signature().key(), capital);
+            plan.paths.push(path);
+        }
+
+        plan.paths.extend(unheld);
+        plan
+    }
+
+    /// 起始代币有持有：余额够用时原样保留，不够时截断到余额重新定价
+    fn size_to_balance(
+        &self,
+        path: &ArbitragePath,
+        start: &str,
+        available: f64,
+        price_cache: &PriceCache,
+        min_roi_percent: f64,
+    ) -> Option<(ArbitragePath, CapitalRequirement)> {
+        let capped = path.input_amount > available;
+        let path = if capped {
+            resize(path, available, price_cache).filter(|p| p.roi_percent >= min_roi_percent)?
+        } else {
+            path.clone()
+        };
+        let capital = CapitalRequirement {
+            token: start.to_string(),
+            amount: path.input_amount,
+            bucket: Some(start.to_string()),
+            available: Some(available),
+            capped,
+            conversion: None,
+            roi_percent: path.roi_percent,
+        };
+        Some((path, capital))
+    }
+
+    /// 起始代币没有持有：从每个持有的代币经最深的直连池子兑换成起始代币，取 ROI 最高且过阈值的
+    fn convert_from_held(
+        &self,
+        path: &ArbitragePath,
+        start: &str,
+        pools: &[PoolPrice],
+        price_cache: &PriceCache,
+        min_roi_percent: f64,
+    ) -> Option<(ArbitragePath, CapitalRequirement)> {
+        if !self.convert_from_held {
+            return None;
+        }
+
+        let mut best: Option<(ArbitragePath, CapitalRequirement)> = None;
+        for (token, &available) in &self.balances {
+            let Some(pool) = deepest_pool(pools, price_cache, token, start) else {
+                continue;
+            };
+            // 现价：1 个持有代币换多少起始代币（price 为 quote / base）
+            let rate = match pool_tokens(&pool) {
+                Some((base, _)) if &base == token => pool.price,
+                _ => 1.0 / pool.price,
+            };
+            if !rate.is_finite() || rate <= 0.0 {
+                continue;
+            }
+
+            let needed = path.input_amount / rate;
+            let amount = needed.min(available);
+            let fee_rate = fee_registry::fee_rate(&pool.pool_id, &pool.dex_name);
+            let conversion = swap_step(token_alias::global(), &pool, token, start, amount, fee_rate);
+            let Some(mut converted) = resize(path, conversion.expected_output, price_cache) else {
+                continue;
+            };
+
+            // 兑换损耗（手续费 + 价格冲击）按现价折算成起始代币，从净利润中扣除
+            let spot_value = amount * rate;
+            let conversion_loss = spot_value - conversion.expected_output;
+            let net_profit = converted.net_profit - conversion_loss;
+            let roi_percent = net_profit / spot_value * 100.0;
+            if roi_percent.is_nan() || roi_percent < min_roi_percent || best.as_ref().is_some_and(|(_, c)| c.roi_percent >= roi_percent) {
+                continue;
+            }
+
+            converted.net_profit = net_profit;
+            converted.roi_percent = roi_percent;
+            let capital = CapitalRequirement {
+                token: token.clone(),
+                amount,
+                bucket: Some(token.clone()),
+                available: Some(available),
+                capped: amount < needed,
+                conversion: Some(conversion),
+                roi_percent,
+            };
+            best = Some((converted, capital));
+        }
+        best
+    }
+}
+
+/// 直接连接两个代币、美元流动性最高的可用池子（没有美元流动性估计的视为 0）
+fn deepest_pool(pools: &[PoolPrice], price_cache: &PriceCache, a: &str, b: &str) -> Option<PoolPrice> {
+    pools.iter()
+        .filter(|pool| pool.price > 0.0)
+        .filter(|pool| pool_tokens(pool).is_some_and(|(base, quote)| {
+            (base == a && quote == b) || (base == b && quote == a)
+        }))
+        .max_by(|x, y| {
+            let usd = |pool: &PoolPrice| pool.liquidity_usd.map_or(0.0, |l| l.usd);
+            usd(x).total_cmp(&usd(y))
+        })
+        .and_then(|pool| live_pool(price_cache, &pool.pool_id))
+}
+
+/// 按新的投入金额、用缓存中每个池子的最新状态逐跳重新计算路径
+///
+/// DEX 手续费按投入比例缩放，执行成本等固定成本（gross_profit - net_profit）原样保留。
+/// 任一池子不可用时返回 None。
+pub fn resize(path: &ArbitragePath, amount: f64, price_cache: &PriceCache) -> Option<ArbitragePath> {
+    if amount <= 0.0 || path.input_amount <= 0.0 {
+        return None;
+    }
+
+    let mut steps = Vec::with_capacity(path.steps.len());
+    let mut current = amount;
+    for step in &path.steps {
+        let pool = live_pool(price_cache, &step.pool_id)?;
+        let fee_rate = fee_registry::fee_rate(&pool.pool_id, &pool.dex_name);
+        let resized = swap_step(token_alias::global(), &pool, &step.input_token, &step.output_token, current, fee_rate);
+        current = resized.expected_output;
+        steps.push(resized);
+    }
+
+    let fixed_costs = path.gross_profit - path.net_profit;
+    let dex_fees = path.dex_fees * amount / path.input_amount;
+    let gross_profit = current - amount;
+    let net_profit = gross_profit - fixed_costs;
+    Some(ArbitragePath {
+        steps,
+        input_amount: amount,
+        output_amount: current,
+        gross_profit,
+        dex_fees,
+        estimated_fees: dex_fees + path.execution_fees,
+        net_profit,
+        roi_percent: net_profit / amount * 100.0,
+        ..path.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router_direct::DirectArbTable;
+    use std::sync::Arc;
+
+    fn pool(pool_id: &str, pair: &str, price: f64, base_amount: f64) -> PoolPrice {
+        PoolPrice {
+            base_reserve: (base_amount * 1_000_000_000.0) as u64,
+            quote_reserve: (base_amount * price * 1_000_000.0) as u64,
+            ..PoolPrice::for_test(pool_id, pair, price)
+        }
+    }
+
+    /// JTO/USDC 两个池子之间 2% 价差的直接套利（投入 1000 USDC）
+    fn jto_path(cache: &Arc<PriceCache>) -> ArbitragePath {
+        let table = DirectArbTable::new(cache.clone(), 0.3)
+            .with_execution_cost(crate::router_fixture::priced_execution_cost(&[("USDC", 150.0)]));
+        cache.update_price(pool("inv-jto-a", "JTO/USDC", 2.00, 1_000_000.0));
+        cache.update_price(pool("inv-jto-b", "JTO/USDC", 2.04, 1_000_000.0));
+        table.on_pool_event("inv-jto-a");
+        table.on_pool_event("inv-jto-b").unwrap().to_path(cache, 1_000.0).unwrap()
+    }
+
+    fn inventory(balances: &[(&str, f64)], unheld: UnheldPathPolicy, convert_from_held: bool) -> Inventory {
+        Inventory::from_config(&InventoryConfig {
+            enabled: true,
+            balances: balances.iter().map(|(token, amount)| (token.to_string(), *amount)).collect(),
+            unheld,
+            convert_from_held,
+        })
+    }
+
+    #[test]
+    fn test_caps_input_at_held_balance() {
+        let cache = Arc::new(PriceCache::new());
+        let path = jto_path(&cache);
+
+        // 余额够用：原样保留
+        let plan = inventory(&[("USDC", 5_000.0)], UnheldPathPolicy::Filter, false).plan(vec![path.clone()], &cache, 0.3);
+        let capital = &plan.capital[&path.signature().key()];
+        assert_eq!(plan.paths[0].input_amount, 1_000.0);
+        assert_eq!((capital.bucket.as_deref(), capital.capped), (Some("USDC"), false));
+
+        // 余额不够：按余额重新定价，深池子里 ROI 基本不变
+        let plan = inventory(&[("USDC", 250.0)], UnheldPathPolicy::Filter, false).plan(vec![path.clone()], &cache, 0.3);
+        let resized = &plan.paths[0];
+        assert_eq!(resized.input_amount, 250.0);
+        assert_eq!(resized.steps[0].expected_input, 250.0);
+        assert!((resized.roi_percent - path.roi_percent).abs() < 0.1);
+        let capital = &plan.capital[&path.signature().key()];
+        assert_eq!((capital.amount, capital.capped), (250.0, true));
+
+        // 按余额重新定价后低于阈值的丢弃
+        let plan = inventory(&[("USDC", 250.0)], UnheldPathPolicy::Filter, false).plan(vec![path], &cache, 5.0);
+        assert!(plan.paths.is_empty());
+        assert_eq!(plan.dropped, 1);
+    }
+
+    #[test]
+    fn test_unheld_start_token_filtered_down_ranked_or_converted() {
+        let cache = Arc::new(PriceCache::new());
+        let path = jto_path(&cache);
+
+        let plan = inventory(&[("SOL", 10.0)], UnheldPathPolicy::Filter, false).plan(vec![path.clone()], &cache, 0.3);
+        assert!(plan.paths.is_empty());
+
+        let plan = inventory(&[("SOL", 10.0)], UnheldPathPolicy::DownRank, false).plan(vec![path.clone()], &cache, 0.3);
+        assert_eq!(plan.paths.len(), 1);
+        assert_eq!(plan.capital[&path.signature().key()].bucket, None);
+
+        // 持有 SOL：经最深的 SOL/USDC 池子兑换 5 SOL ≈ 1000 USDC，兑换损耗计入 ROI
+        let mut shallow = pool("inv-sol-shallow", "SOL/USDC", 200.0, 1_000.0);
+        shallow.liquidity_usd = Some(crate::liquidity::LiquidityUsd { usd: 400_000.0, approximate: false });
+        let mut deep = pool("inv-sol-deep", "SOL/USDC", 200.0, 100_000.0);
+        deep.liquidity_usd = Some(crate::liquidity::LiquidityUsd { usd: 40_000_000.0, approximate: false });
+        cache.update_price(shallow);
+        cache.update_price(deep);
+
+        let plan = inventory(&[("SOL", 10.0)], UnheldPathPolicy::Filter, true).plan(vec![path.clone()], &cache, 0.3);
+        let capital = &plan.capital[&path.signature().key()];
+        let conversion = capital.conversion.as_ref().unwrap();
+        assert_eq!(conversion.pool_id, "inv-sol-deep");
+        assert_eq!((capital.token.as_str(), capital.bucket.as_deref()), ("SOL", Some("SOL")));
+        assert!((capital.amount - 5.0).abs() < 1e-9);
+        assert!(!capital.capped);
+        assert_eq!(plan.paths[0].input_amount, conversion.expected_output);
+        assert!(capital.roi_percent < path.roi_percent && capital.roi_percent >= 0.3);
+    }
+}
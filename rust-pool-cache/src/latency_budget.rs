@@ -0,0 +1,191 @@
+/*!
+ * ⏱️ 检测延迟预算
+ *
+ * 从收到 WebSocket 通知到上报机会，按阶段拆分耗时：
+ * - deserialize：收到通知 → 账户数据解析完成（含 JSON / base64 解码）
+ * - cache_update：解析完成 → PriceCache 更新并发出 PriceChangeEvent
+ * - coordinator_queue：事件发出 → Coordinator 创建计算任务
+ * - calculator_wait：任务创建 → Calculator 从 channel 取出
+ * - snapshot：路由快照组装（所有金额档位合计）
+ * - graph_scan：图扫描（扫描总耗时减去快照）
+ * - validation：去重 + 逐跳验证 + 上报前重新定价
+ *
+ * 前两个阶段由 PriceChangeEvent::ingest 经 CalculationTask 带到 Calculator。
+ * 时钟触发的任务没有前三个阶段，端到端从任务创建算起。
+ */
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::coordinator::CalculationTask;
+
+/// 延迟预算的阶段（/metrics 的 stage 标签）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LatencyStage {
+    Deserialize,
+    CacheUpdate,
+    CoordinatorQueue,
+    CalculatorWait,
+    Snapshot,
+    GraphScan,
+    Validation,
+}
+
+impl LatencyStage {
+    pub const ALL: [LatencyStage; 7] = [
+        LatencyStage::Deserialize,
+        LatencyStage::CacheUpdate,
+        LatencyStage::CoordinatorQueue,
+        LatencyStage::CalculatorWait,
+        LatencyStage::Snapshot,
+        LatencyStage::GraphScan,
+        LatencyStage::Validation,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LatencyStage::Deserialize => "deserialize",
+            LatencyStage::CacheUpdate => "cache_update",
+            LatencyStage::CoordinatorQueue => "coordinator_queue",
+            LatencyStage::CalculatorWait => "calculator_wait",
+            LatencyStage::Snapshot => "snapshot",
+            LatencyStage::GraphScan => "graph_scan",
+            LatencyStage::Validation => "validation",
+        }
+    }
+}
+
+/// WebSocket 侧的耗时（随 PriceChangeEvent 传给 Coordinator）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IngestTiming {
+    /// 收到通知的时刻
+    pub received_at: Instant,
+    pub deserialize: Duration,
+    pub cache_update: Duration,
+}
+
+/// 一次扫描的分阶段耗时（微秒；只有事件触发才有的阶段为 None）
+///
+/// 写入 /metrics 的直方图，也随每条机会输出（OpportunityContext::latency）。
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LatencyBreakdown {
+    pub deserialize_us: Option<u64>,
+    pub cache_update_us: Option<u64>,
+    pub coordinator_queue_us: Option<u64>,
+    pub calculator_wait_us: u64,
+    pub snapshot_us: u64,
+    pub graph_scan_us: u64,
+    pub validation_us: u64,
+    /// 收到通知（时钟触发：任务创建）→ 上报机会；上报前为 0
+    pub end_to_end_us: u64,
+}
+
+fn micros(duration: Duration) -> u64 {
+    duration.as_micros() as u64
+}
+
+impl LatencyBreakdown {
+    /// 由任务携带的时间戳填入 Calculator 之前的阶段（`dequeued_at` 为取出任务的时刻）
+    pub fn from_task(task: &CalculationTask, dequeued_at: Instant) -> Self {
+        Self {
+            deserialize_us: task.ingest.map(|ingest| micros(ingest.deserialize)),
+            cache_update_us: task.ingest.map(|ingest| micros(ingest.cache_update)),
+            coordinator_queue_us: task.event_timestamp.map(|event_at| micros(task.created_at.saturating_duration_since(event_at))),
+            calculator_wait_us: micros(dequeued_at.saturating_duration_since(task.created_at)),
+            ..Default::default()
+        }
+    }
+
+    /// 记录扫描阶段：`scan` 为所有档位的扫描总耗时，其中 `snapshot` 花在组装快照上
+    pub fn set_scan(&mut self, scan: Duration, snapshot: Duration) {
+        self.snapshot_us = micros(snapshot);
+        self.graph_scan_us = micros(scan.saturating_sub(snapshot));
+    }
+
+    /// 上报时填入端到端耗时（起点见 `CalculationTask::origin`）
+    pub fn complete(mut self, origin: Instant, now: Instant) -> Self {
+        self.end_to_end_us = micros(now.saturating_duration_since(origin));
+        self
+    }
+
+    /// 已测量的阶段（时钟触发的任务不含前三个阶段）
+    pub fn stages(&self) -> Vec<(LatencyStage, Duration)> {
+        let values = [
+            self.deserialize_us,
+            self.cache_update_us,
+            self.coordinator_queue_us,
+            Some(self.calculator_wait_us),
+            Some(self.snapshot_us),
+            Some(self.graph_scan_us),
+            Some(self.validation_us),
+        ];
+        LatencyStage::ALL
+            .into_iter()
+            .zip(values)
+            .filter_map(|(stage, value)| value.map(|us| (stage, Duration::from_micros(us))))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinator::TriggerType;
+
+    #[test]
+    fn test_event_task_breakdown_covers_every_stage() {
+        let received_at = Instant::now();
+        let event_at = received_at + Duration::from_micros(300);
+        let task = CalculationTask {
+            trigger_type: TriggerType::Event,
+            trigger_source: "SOL/USDC (Raydium)".to_string(),
+            price_change_percent: Some(0.003),
+            created_at: event_at + Duration::from_micros(50),
+            scope: None,
+            event_timestamp: Some(event_at),
+            ingest: Some(IngestTiming {
+                received_at,
+                deserialize: Duration::from_micros(200),
+                cache_update: Duration::from_micros(100),
+            }),
+        };
+        assert_eq!(task.origin(), received_at);
+
+        let mut breakdown = LatencyBreakdown::from_task(&task, task.created_at + Duration::from_micros(1_000));
+        breakdown.set_scan(Duration::from_micros(4_000), Duration::from_micros(500));
+        breakdown.validation_us = 250;
+        let breakdown = breakdown.complete(task.origin(), received_at + Duration::from_millis(6));
+
+        assert_eq!(breakdown.deserialize_us, Some(200));
+        assert_eq!(breakdown.coordinator_queue_us, Some(50));
+        assert_eq!(breakdown.calculator_wait_us, 1_000);
+        assert_eq!((breakdown.snapshot_us, breakdown.graph_scan_us), (500, 3_500));
+        assert_eq!(breakdown.end_to_end_us, 6_000);
+        assert_eq!(breakdown.stages().len(), LatencyStage::ALL.len());
+
+        let json = serde_json::to_value(&breakdown).unwrap();
+        assert_eq!(json["cache_update_us"], 100);
+        assert_eq!(json["end_to_end_us"], 6_000);
+    }
+
+    #[test]
+    fn test_clock_task_has_no_ingest_stages() {
+        let created_at = Instant::now();
+        let task = CalculationTask {
+            trigger_type: TriggerType::Clock,
+            trigger_source: "periodic_clock".to_string(),
+            price_change_percent: None,
+            created_at,
+            scope: None,
+            event_timestamp: None,
+            ingest: None,
+        };
+        assert_eq!(task.origin(), created_at);
+
+        let breakdown = LatencyBreakdown::from_task(&task, created_at + Duration::from_micros(10));
+        assert_eq!(breakdown.coordinator_queue_us, None);
+        let stages: Vec<&str> = breakdown.stages().iter().map(|(stage, _)| stage.name()).collect();
+        assert_eq!(stages, ["calculator_wait", "snapshot", "graph_scan", "validation"]);
+    }
+}
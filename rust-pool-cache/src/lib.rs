@@ -6,16 +6,19 @@ pub mod price_cache;
 pub mod dashmap_state;          // 🔥 DashMap状态层实现
 pub mod coordinator;            // 🔥 协调器（混合触发）
 pub mod calculator;             // 🧮 计算器（独立计算层）
+pub mod calculator_loop;        // 🧮 Calculator 主循环（扫描 -> 差异 / 去重 / 验证 -> 上报，状态跨 panic 重启保留）
 pub mod router;
 pub mod router_bellman_ford;
 pub mod router_bfs;            // 🔥 BFS路由器（快速2-3跳）
 pub mod router_split_optimizer;
 pub mod router_cache;          // 🔥 路径缓存（60-80%延迟降低）
 pub mod router_advanced;
+pub mod backpressure;           // 🔥 下游反压信号（模拟器 -> Calculator）
 pub mod database;
 pub mod error_tracker;
 pub mod arbitrage;              // 套利检测
 pub mod opportunity_validator;  // 🎯 套利机会验证器
+pub mod validation_rules;       // 🧱 机会验证规则流水线（命名规则 + 按规则拒绝计数）
 pub mod onchain_simulator;      // 🎯 链上模拟器
 pub mod dex_interface;          // DEX接口trait
 pub mod pool_factory;           // 池子工厂
@@ -29,10 +32,70 @@ pub mod lst_arbitrage;          // 🔥 LST折价套利模块（旧版）
 pub mod stake_pool_reader;      // 🔥 Stake Pool实时数据读取（新增）
 pub mod lst_enhanced_detector;  // 🔥 LST增强检测器（新增）
 pub mod opportunity_merger;     // 🔥 机会合并与去重（新增）
-pub mod mint_decimals_cache;    // 🔥 全局 Mint Decimals 缓存模块
-
-
-
-
-
+pub mod opportunity_output;     // 🧾 结构化机会输出（[output] jsonl 文件 / stdout）
+pub mod mint_decimals_cache;    // 🔥 全局 Mint 元数据缓存（decimals / 转账手续费）
+pub mod slo;                    // 📈 可用性SLO追踪
+pub mod alerts;                 // 🔔 告警分发（production / firehose）
+pub mod sharding;               // 🧩 多实例池子分片（一致性哈希）
+pub mod scan_diff;              // 🔄 扫描间差异追踪（New / Gone 事件，最近 N 次扫描记录 /scans）
+pub mod scan_capture;           // 🧊 扫描输入抓取（快照 + 路由配置写 JSON，scan_from_snapshot 离线重放）
+pub mod synthetic;              // 🧪 合成池子 what-if 扫描
+pub mod calibration;            // 🎯 验证器置信度校准（历史结果 -> 概率）
+pub mod quote;                  // 📐 分档报价 / 深度曲线
+pub mod stable_math;            // 📐 StableSwap 不变量（Stabble 报价与边际价格）
+pub mod orderbook_cache;        // 📖 CLOB 订单簿池子注册表（按档位报价）
+pub mod fee_registry;           // 💸 统一手续费注册表（池子级 fee_bps 覆盖 + DEX 默认值）
+pub mod execution_cost;         // ⛽ 执行成本模型（签名费 + 按 DEX 的 CU × 优先费率 + Jito 小费）
+pub mod scan_tiers;             // 💵 扫描金额档位（美元金额 -> base_token 数量，按档位合并 ROI）
+pub mod reconnect_backoff;      // 🔄 WebSocket 重连指数退避（full jitter）
+pub mod account_subscription;   // 📡 accountSubscribe 参数（commitment / encoding，base64+zstd 解压）
+pub mod endpoint_pool;          // 🔀 多端点 WebSocket 故障转移（健康分）
+pub mod rpc_budget;             // 🪣 RPC 令牌桶限速（速率 + 突发，rpc_manager 共用）
+pub mod rpc_manager;            // 🛰️ 共享 RPC 管理器（端点故障转移 + 令牌桶限速 + 按调用方计数）
+pub mod chain_head;             // ⛓️ 链头 slot 跟踪（getSlot 轮询，池子通知 head_lag）
+pub mod token_graph;            // 🕸️ 代币图构建（Bellman-Ford 与 GET /graph 共用）
+pub mod interning;              // 🔢 TokenId / PoolId / PairId 驻留（路由热路径只比较 u32，边界处再解析回字符串）
+pub mod pipeline;               // 🎯 扫描管线（Coordinator -> Calculator，单一创建点）
+pub mod latency_budget;         // ⏱️ 检测延迟预算（通知 -> 解析 -> 缓存 -> 排队 -> 快照 -> 扫描 -> 验证）
+pub mod staleness;              // ⏱️ 按池子类型的新鲜度策略（CLOB / vault 依赖型放宽预算）
+pub mod tx_builder;             // 🧪 交易构建器（ArbitragePath -> swap 交易，供 simulateTransaction）
+pub mod dlmm_bin_cache;         // 📊 Meteora DLMM bin 注册表（活跃 bin 附近的 BinArray -> 按 bin 报价）
+pub mod clmm_tick_cache;        // 📈 Raydium CLMM tick 注册表（当前 tick 附近的 TickArray -> 跨 tick 报价）
+pub mod price_snapshot;         // 💾 价格缓存快照（定期落盘，启动预热）
+pub mod prometheus;             // 📈 Prometheus 文本格式导出（GET /metrics，pool_cache_* 指标）
+pub mod router_direct;          // ⚡ 两跳直接套利快速通道（按交易对的最优买卖价表）
+pub mod lst_registry;           // 🪙 LST 注册表（mint / stake pool 账户 / 赎回费用与等待时间）
+pub mod pool_mints;             // 🧭 池子 mint 方向注册表（base_mint / quote_mint，替代 pair 前缀猜测）
+pub mod token_alias;            // 🪞 代币别名归一化（wSOL → SOL，建图 / 交易对分组 / start_tokens 共用）
+pub mod price_oracle;           // 💲 USD 定价服务（最深稳定币池子 + 锚定代币三角换算）
+pub mod liquidity;              // 💧 池子美元流动性估算（储备 × 美元价格，CLMM / CLOB 标记为近似）
+pub mod health;                 // 🩺 就绪探测（组件心跳 -> ok / degraded / down）
+pub mod supervisor;             // 🛟 任务监督（panic 后退避重启，超过每小时上限时 /health 报 down）
+pub mod pool_fixture;           // 🧪 池子账户 fixture（base64 主网账户，反序列化器 golden 测试）
+pub mod webhook;                // 🪝 最小 webhook 客户端（POST JSON）
+pub mod spread_monitor;         // 📏 交易对价差持续超阈值告警
+pub mod spread_heatmap;         // 🌡️ 有序池子组合的滚动价差统计（mean / p95 / max / 超阈值时长）
+pub mod focus;                  // 🎯 重点交易对盯盘（每次更新都重算价差矩阵，绕过 Coordinator 冷却）
+pub mod notifications;          // 📣 机会通知（webhook / Telegram，按 sink 过滤 + 指纹冷却 + 重试）
+pub mod pool_update_log;        // 🎞️ 池子更新记录器（WebSocket 更新 -> pool_update_history）
+pub mod replay;                 // 🎞️ 回放：按时间顺序把记录的池子更新写回 PriceCache 并驱动扫描管线
+pub mod circuit_breaker;        // 🧯 池子级熔断（价格 / 储备异常跳变的池子隔离，不进快照和路由图）
+pub mod proxy;                  // 🌐 WebSocket 连接（直连 / HTTP 代理）
+pub mod vault_reader;           // 🏦 vault 账户读取（vault 依赖型池子的储备量）
+pub mod vault_audit;            // 🔍 缓存储备与 vault 余额的一致性检查（/pools/{address}/consistency + 定期巡检）
+pub mod pool_history;           // 🕰️ 逐池价格历史（内存环形缓冲 + OHLC 降采样，/pools/{address}/history）
+pub mod reference_price;        // 🧭 外部参考价格交叉校验（偏离标记，超过硬上限排除出路由）
+pub mod inventory;              // 💼 库存感知（按持有余额过滤 / 截断投入 / 前置兑换，机会附带资金需求）
+pub mod pool_data_cache;        // 🌐 池子原始账户数据缓存（vault 更新时重新解析，LRU 有上限）
+pub mod websocket;              // 🔌 WebSocket 订阅客户端（分片 / 重连 / 动态订阅）
+pub mod discovery;              // 🔭 池子自动发现（getProgramAccounts）
+pub mod pool_initializer;       // 🚀 池子初始化器（启动时 RPC 批量查询）
+pub mod pair_naming;            // 🏷️ pair 为空的池子按链上 mint 自动命名（内置符号表 / 元数据 / mint 缩写）
+pub mod pool_reload;            // ♻️ 池子列表热重载（POST /reload）
+pub mod api;                    // 🌐 HTTP API（/health、/metrics、/graph ...）
+pub mod app;                    // 🧩 Application：进程装配（main.rs 与嵌入方共用）
+pub mod ranking;                // 📐 机会排序（美元期望值 × 执行概率）
+#[cfg(test)]
+pub mod router_fixture;         // 🧪 路由器测试 fixture（按精确汇率构造合成池子图）
 
+pub use app::{Application, ApplicationOptions};
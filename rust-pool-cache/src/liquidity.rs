@@ -0,0 +1,180 @@
+/*!
+ * 💧 池子美元流动性估算
+ *
+ * PriceCache 每次写入池子时按 储备 × 代币美元价格 估算 `PoolPrice::liquidity_usd`：
+ *
+ * - 两侧都有美元价格：两侧价值相加
+ * - 只有一侧有美元价格：另一侧按池子自身价格折算
+ * - CLMM / CLOB：储备是 vault / 订单簿合计，不是当前价位附近的可成交深度，
+ *   结果只是尽力估计，标记为近似值（`approximate = true`）
+ *
+ * 代币美元价格来自 `UsdPriceTable`：稳定币恒为 1 美元，其余由后台任务定期从
+ * PriceOracle 刷新（每次写入都查询 oracle 需要遍历全部池子，放不进热路径）。
+ * 没有美元价格的池子 liquidity_usd 为 None，路由的最低流动性过滤不排除这类池子。
+ */
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use crate::price_cache::{PoolPrice, PriceCache};
+use crate::price_oracle::{is_stablecoin, PriceOracle};
+use crate::staleness::PoolClass;
+use crate::supervisor::{spawn_supervised, Supervisor};
+use crate::token_graph::pool_tokens;
+
+/// 美元价格表的默认刷新间隔
+pub const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 池子的美元流动性估计
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LiquidityUsd {
+    pub usd: f64,
+    /// CLMM / CLOB 的尽力估计（储备不等于可成交深度）
+    pub approximate: bool,
+}
+
+/// 代币 -> 美元价格（规范符号；稳定币不需要写入）
+#[derive(Debug, Default)]
+pub struct UsdPriceTable {
+    prices: DashMap<String, f64>,
+}
+
+impl UsdPriceTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, token: &str) -> Option<f64> {
+        if is_stablecoin(token) {
+            return Some(1.0);
+        }
+        self.prices.get(token).map(|price| *price)
+    }
+
+    /// 写入代币价格（非正数 / 非有限值忽略）
+    pub fn set(&self, token: &str, usd: f64) {
+        if usd.is_finite() && usd > 0.0 {
+            self.prices.insert(token.to_string(), usd);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.prices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prices.is_empty()
+    }
+}
+
+/// 估算池子的美元流动性；两侧代币都没有美元价格或池子价格无效时返回 None
+pub fn estimate(pool: &PoolPrice, usd_prices: &UsdPriceTable) -> Option<LiquidityUsd> {
+    if !(pool.price.is_finite() && pool.price > 0.0) {
+        return None;
+    }
+    let (base, quote) = pool_tokens(pool)?;
+    let (base_usd, quote_usd) = match (usd_prices.get(&base), usd_prices.get(&quote)) {
+        (Some(base_usd), Some(quote_usd)) => (base_usd, quote_usd),
+        // price = 1 base 值多少 quote
+        (Some(base_usd), None) => (base_usd, base_usd / pool.price),
+        (None, Some(quote_usd)) => (quote_usd * pool.price, quote_usd),
+        (None, None) => return None,
+    };
+
+    let (base_decimals, quote_decimals) = pool.get_decimals();
+    let base_amount = pool.base_reserve as f64 / 10f64.powi(base_decimals as i32);
+    let quote_amount = pool.quote_reserve as f64 / 10f64.powi(quote_decimals as i32);
+    let usd = base_amount * base_usd + quote_amount * quote_usd;
+    if !usd.is_finite() {
+        return None;
+    }
+
+    let approximate = matches!(PoolClass::from_dex_name(&pool.dex_name), PoolClass::Clmm | PoolClass::Clob);
+    Some(LiquidityUsd { usd, approximate })
+}
+
+/// 池子是否达到流动性下限（`min_usd <= 0` 表示不过滤；流动性未知的池子保留）
+pub fn meets_floor(pool: &PoolPrice, min_usd: f64) -> bool {
+    min_usd <= 0.0 || pool.liquidity_usd.is_none_or(|liquidity| liquidity.usd >= min_usd)
+}
+
+/// 用 oracle 刷新缓存中所有代币的美元价格，再重算全部池子的流动性
+pub fn refresh(price_cache: &PriceCache, oracle: &PriceOracle) -> usize {
+    let tokens: BTreeSet<String> = price_cache.get_all_prices()
+        .iter()
+        .filter_map(pool_tokens)
+        .flat_map(|(base, quote)| [base, quote])
+        .filter(|token| !is_stablecoin(token))
+        .collect();
+    let tokens: Vec<String> = tokens.into_iter().collect();
+
+    let prices = oracle.get_usd_prices(&tokens);
+    for (token, usd) in &prices {
+        price_cache.usd_prices().set(token, *usd);
+    }
+    price_cache.refresh_liquidity();
+    prices.len()
+}
+
+/// 后台任务：定期刷新美元价格表和池子流动性
+pub fn spawn_refresher(
+    price_cache: Arc<PriceCache>,
+    oracle: Arc<PriceOracle>,
+    interval: Duration,
+    supervisor: &Supervisor,
+) -> JoinHandle<()> {
+    spawn_supervised("liquidity_refresher", supervisor, move || {
+        let (price_cache, oracle) = (price_cache.clone(), oracle.clone());
+        async move {
+            loop {
+                let priced = refresh(&price_cache, &oracle);
+                debug!("💧 Refreshed USD prices for {} tokens", priced);
+                tokio::time::sleep(interval).await;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(dex_name: &str, pair: &str, price: f64, reserves: (f64, f64)) -> PoolPrice {
+        PoolPrice {
+            dex_name: dex_name.to_string(),
+            base_reserve: (reserves.0 * 1e9) as u64,
+            quote_reserve: (reserves.1 * 1e6) as u64,
+            ..PoolPrice::for_test(&format!("{}-{}", dex_name, pair), pair, price)
+        }
+    }
+
+    #[test]
+    fn test_estimate_from_reserves_and_usd_prices() {
+        let table = UsdPriceTable::new();
+
+        // 稳定币一侧恒为 1 美元，另一侧按池子价格折算：100 SOL × 150 + 15_000 USDC
+        let amm = pool("Raydium AMM V4", "SOL/USDC", 150.0, (100.0, 15_000.0));
+        assert_eq!(estimate(&amm, &table), Some(LiquidityUsd { usd: 30_000.0, approximate: false }));
+
+        // 两侧都有价格时各自计价；CLMM 标记为近似
+        table.set("SOL", 160.0);
+        let clmm = pool("Raydium CLMM", "SOL/USDC", 150.0, (100.0, 15_000.0));
+        assert_eq!(estimate(&clmm, &table), Some(LiquidityUsd { usd: 31_000.0, approximate: true }));
+
+        // 两侧都没有价格 / 价格无效
+        assert_eq!(estimate(&pool("Raydium AMM V4", "FOO/BAR", 2.0, (1.0, 1.0)), &table), None);
+        assert_eq!(estimate(&pool("Raydium AMM V4", "SOL/USDC", 0.0, (1.0, 1.0)), &table), None);
+
+        // 下限：未知流动性保留，0 表示不过滤
+        let thin = PoolPrice { liquidity_usd: Some(LiquidityUsd { usd: 300.0, approximate: false }), ..amm };
+        assert!(!meets_floor(&thin, 1_000.0));
+        assert!(meets_floor(&thin, 0.0));
+        assert!(meets_floor(&pool("Raydium AMM V4", "FOO/BAR", 2.0, (1.0, 1.0)), 1_000.0));
+    }
+}
@@ -1,26 +1,95 @@
 /*!
  * LST Enhanced Detector
+ *
+ * 匹配到的 LST 池子按对手代币分类（SOL / USDC / USDT）：SOL 计价的池子直接和
+ * 赎回比率比较；稳定币计价的池子先用 PriceCache 中的 SOL 美元价格把公允价值
+ * 换算成该币种再算折价。对手代币无法定价的池子跳过，不做比较。
  */
 
-use crate::lst_arbitrage::{LstToken, LstArbitrageType};
+use crate::config::PriceOracleConfig;
+use crate::lst_arbitrage::LstArbitrageType;
+use crate::lst_registry::{LstEntry, LstRegistry};
+use crate::pool_mints;
+use crate::price_oracle::{resolve_usd_price, PriceOracle};
 use crate::price_cache::{PoolPrice, PriceCache};
-use crate::stake_pool_reader::StakePoolReader;
+use crate::token_alias;
+use crate::stake_pool_reader::{LstFairValue, StakePoolReader};
 use crate::router::{ArbitragePath, ArbitrageType, RouteStep};
-use std::collections::HashMap;
+use crate::validation_rules::{RoiSanity, RoiVerdict};
 use std::sync::Arc;
 use std::time::Instant;
 use tracing::{info, debug};
 
+/// LST 池子的计价（对手）代币
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LstQuote {
+    Sol,
+    Usdc,
+    Usdt,
+}
+
+impl LstQuote {
+    /// 按规范符号分类（wSOL 归一为 SOL），其他代币返回 None
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        match token_alias::canonical(symbol).as_ref() {
+            "SOL" => Some(Self::Sol),
+            "USDC" => Some(Self::Usdc),
+            "USDT" => Some(Self::Usdt),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sol => "SOL",
+            Self::Usdc => "USDC",
+            Self::Usdt => "USDT",
+        }
+    }
+}
+
+/// 按计价代币分类后的 LST 池子
+#[derive(Debug, Clone)]
+struct QuotedPool {
+    pool: PoolPrice,
+    quote: LstQuote,
+    /// 1 LST 值多少计价代币
+    price: f64,
+    /// 计价代币一侧储备（UI 单位）
+    quote_reserve: f64,
+}
+
+impl QuotedPool {
+    /// 计价代币一侧储备 × 2，折算成 SOL 的总流动性
+    fn liquidity_sol(&self, sol_usd: Option<f64>) -> Option<f64> {
+        match self.quote {
+            LstQuote::Sol => Some(self.quote_reserve * 2.0),
+            LstQuote::Usdc | LstQuote::Usdt => sol_usd.map(|sol_usd| self.quote_reserve * 2.0 / sol_usd),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct LstOpportunity {
     pub lst_name: String,
+    /// `market_price` / `fair_value` 的计价代币
+    pub quote: LstQuote,
+    /// 1 LST 的市场价格（计价代币）
     pub market_price: f64,
+    /// 比较用的公允价值，已换算成计价代币（启用 epoch 插值时介于当前比率和推算比率之间）
     pub fair_value: f64,
+    /// 推算的下一 epoch 赎回比率
+    pub projected_rate: f64,
+    /// 检测时的 epoch 进度（0-1）
+    pub epoch_progress: Option<f64>,
     pub discount_percent: f64,
     pub estimated_profit_percent: f64,
     pub arbitrage_type: LstArbitrageType,
     pub path_description: String,
-    pub recommended_amount_usd: f64,
+    /// 推荐金额（SOL）
+    pub recommended_amount_sol: f64,
+    /// 推荐金额（美元），没有新鲜 SOL 美元价格时为 None
+    pub recommended_amount_usd: Option<f64>,
     pub route_steps: Option<Vec<RouteStep>>,
     pub input_amount: f64,
     pub output_amount: f64,
@@ -45,6 +114,9 @@ impl LstOpportunity {
             input_amount: self.input_amount,
             output_amount: self.output_amount,
             gross_profit,
+            // 1% 综合费用（swap + 赎回）按交易手续费计
+            dex_fees: estimated_fees,
+            execution_fees: 0.0,
             estimated_fees,
             net_profit,
             roi_percent: self.estimated_profit_percent,
@@ -59,8 +131,10 @@ pub struct LstDetectorConfig {
     pub enable_triangle_arbitrage: bool,
     pub enable_multi_lst_arbitrage: bool,
     pub enable_redemption_path: bool,
-    pub marinade_unstake_fee: f64,
-    pub jito_unstake_fee: f64,
+    /// 公允价值按 epoch 进度向下一 epoch 比率插值
+    pub epoch_interpolation: bool,
+    /// ROI 合理性阈值（与验证规则 max_total_roi_sanity 共用，默认 >15% 拒绝、8-15% 警告）
+    pub roi_sanity: RoiSanity,
 }
 
 impl Default for LstDetectorConfig {
@@ -70,8 +144,8 @@ impl Default for LstDetectorConfig {
             enable_triangle_arbitrage: true,
             enable_multi_lst_arbitrage: true,
             enable_redemption_path: true,
-            marinade_unstake_fee: 0.003,
-            jito_unstake_fee: 0.001,
+            epoch_interpolation: true,
+            roi_sanity: RoiSanity::default(),
         }
     }
 }
@@ -80,9 +154,9 @@ impl Default for LstDetectorConfig {
 pub struct LstEnhancedDetector {
     price_cache: Arc<PriceCache>,
     stake_pool_reader: Arc<StakePoolReader>,
-    lst_tokens: Vec<LstToken>,
+    registry: Arc<LstRegistry>,
     config: LstDetectorConfig,
-    dex_fees: HashMap<String, f64>,
+    price_oracle: Option<Arc<PriceOracle>>,
 }
 
 impl LstEnhancedDetector {
@@ -91,20 +165,21 @@ impl LstEnhancedDetector {
         stake_pool_reader: Arc<StakePoolReader>,
         config: LstDetectorConfig,
     ) -> Self {
-        let mut dex_fees = HashMap::new();
-        dex_fees.insert("Raydium AMM V4".to_string(), 0.0025);
-        dex_fees.insert("Raydium CLMM".to_string(), 0.0001);
-        dex_fees.insert("Orca Whirlpool".to_string(), 0.0001);
-        dex_fees.insert("Phoenix (CLOB)".to_string(), 0.0005);
-        
+        let registry = stake_pool_reader.registry().clone();
         Self {
             price_cache,
             stake_pool_reader,
-            lst_tokens: LstToken::all_supported(),
+            registry,
             config,
-            dex_fees,
+            price_oracle: None,
         }
     }
+
+    /// 💲 用实时美元价格换算推荐金额（未设置时只给出 SOL 数量）
+    pub fn with_price_oracle(mut self, price_oracle: Arc<PriceOracle>) -> Self {
+        self.price_oracle = Some(price_oracle);
+        self
+    }
     
     pub fn detect_all_opportunities(&self, initial_amount: f64) -> Vec<LstOpportunity> {
         let mut all_opportunities = Vec::new();
@@ -137,23 +212,26 @@ impl LstEnhancedDetector {
     fn detect_cross_dex_opportunities(&self) -> Result<Vec<LstOpportunity>, anyhow::Error> {
         let mut opportunities = Vec::new();
         let all_prices = self.price_cache.get_all_prices();
-        let (msol_fair, jitosol_fair) = self.stake_pool_reader.get_all_rates()?;
+        let fair_values = self.stake_pool_reader.get_fair_values(self.config.epoch_interpolation)?;
+        let sol_usd = self.sol_usd_price();
         
-        for lst in &self.lst_tokens {
+        for lst in self.registry.entries() {
             let lst_pools = self.find_lst_pools(lst, &all_prices);
             if lst_pools.len() < 2 { continue; }
             
-            let fair_value = match lst.name {
-                "mSOL" => msol_fair,
-                "jitoSOL" => jitosol_fair,
-                _ => lst.theoretical_rate,
-            };
+            // 还没有读到赎回比率的 LST 跳过
+            let Some(fair) = fair_values.get(&lst.mint) else { continue };
             
             for i in 0..lst_pools.len() {
                 for j in (i + 1)..lst_pools.len() {
                     let pool_a = &lst_pools[i];
                     let pool_b = &lst_pools[j];
                     
+                    // 只比较同一计价代币的池子（跨币种需要额外一跳，不是直接价差）
+                    if pool_a.quote != pool_b.quote {
+                        continue;
+                    }
+                    
                     // ✅ 数据验证：跳过异常价格
                     if pool_a.price <= 0.0 || pool_a.price.is_nan() || pool_a.price.is_infinite() {
                         continue;
@@ -163,15 +241,23 @@ impl LstEnhancedDetector {
                     }
                     
                     // ✅ 数据验证：跳过零流动性池子
-                    if pool_a.base_reserve == 0 || pool_a.quote_reserve == 0 {
+                    if pool_a.pool.base_reserve == 0 || pool_a.pool.quote_reserve == 0 {
                         continue;
                     }
-                    if pool_b.base_reserve == 0 || pool_b.quote_reserve == 0 {
+                    if pool_b.pool.base_reserve == 0 || pool_b.pool.quote_reserve == 0 {
                         continue;
                     }
                     
+                    let Some(fair_value) = quote_fair_value(fair.fair_value, pool_a.quote, sol_usd) else {
+                        debug!(
+                            "Skipping {} cross-DEX pair {} / {}: no SOL price in {}",
+                            lst.symbol, pool_a.pool.pool_id, pool_b.pool.pool_id, pool_a.quote.as_str()
+                        );
+                        continue;
+                    };
+                    
                     if let Some(opp) = self.calculate_cross_dex_opportunity(
-                        lst, pool_a, pool_b, fair_value
+                        lst, pool_a, pool_b, fair, fair_value, sol_usd
                     ) {
                         opportunities.push(opp);
                     }
@@ -194,21 +280,20 @@ impl LstEnhancedDetector {
     
     fn detect_discount_opportunities(&self) -> Result<Vec<LstOpportunity>, anyhow::Error> {
         let mut opportunities = Vec::new();
-        let (msol_fair, jitosol_fair) = self.stake_pool_reader.get_all_rates()?;
+        let fair_values = self.stake_pool_reader.get_fair_values(self.config.epoch_interpolation)?;
         let all_prices = self.price_cache.get_all_prices();
+        let sol_usd = self.sol_usd_price();
         
-        for lst in &self.lst_tokens {
-            let fair_value = match lst.name {
-                "mSOL" => msol_fair,
-                "jitoSOL" => jitosol_fair,
-                _ => lst.theoretical_rate,
-            };
+        for lst in self.registry.entries() {
+            let Some(fair) = fair_values.get(&lst.mint) else { continue };
             
             let lst_pools = self.find_lst_pools(lst, &all_prices);
             
-            for pool in lst_pools {
+            for quoted in lst_pools {
+                let pool = &quoted.pool;
+                
                 // ✅ 数据验证：跳过异常价格
-                if pool.price <= 0.0 || pool.price.is_nan() || pool.price.is_infinite() {
+                if quoted.price <= 0.0 || quoted.price.is_nan() || quoted.price.is_infinite() {
                     debug!("Skipping LST pool {} with invalid price: {}", pool.pool_id, pool.price);
                     continue;
                 }
@@ -219,86 +304,81 @@ impl LstEnhancedDetector {
                     continue;
                 }
                 
-                // 🔥 关键修复：标准化价格方向再比较
-                // fair_value总是表示：1 LST值多少SOL（例如：1.029 SOL/mSOL）
-                // pool.price的含义取决于池子方向：
-                //   - SOL/mSOL池子：price = mSOL/SOL（需要取倒数）
-                //   - mSOL/SOL池子：price = SOL/mSOL（直接使用）
-                
-                let market_price_normalized = if pool.pair.starts_with("SOL/") || pool.pair.starts_with("SOL ") {
-                    // SOL/LST池子，price是LST/SOL，需要取倒数得到SOL/LST
-                    if pool.price > 0.0 { 1.0 / pool.price } else { 0.0 }
-                } else {
-                    // LST/SOL池子，price已经是SOL/LST格式
-                    pool.price
+                // 🔥 公允价值换算成池子的计价代币再比较
+                // fair.fair_value 总是 "SOL per LST"（例如 1.029 SOL/mSOL）；
+                // mSOL/USDC 池子需要乘以 SOL 美元价格（1.029 × 150 = 154.35 USDC/mSOL）
+                let Some(fair_value) = quote_fair_value(fair.fair_value, quoted.quote, sol_usd) else {
+                    debug!(
+                        "Skipping LST pool {} ({}): no SOL price in {}",
+                        pool.pool_id, pool.pair, quoted.quote.as_str()
+                    );
+                    continue;
                 };
                 
-                // 现在两个价格都是"SOL per LST"格式，可以安全比较
-                // fair_value: 1.029 SOL/mSOL
-                // market_price_normalized: 1.222 SOL/mSOL
-                // 折价 = (1.029 - 1.222) / 1.222 * 100 = -15.8%（实际是溢价）
-                let discount = if market_price_normalized > 0.0 {
-                    ((fair_value - market_price_normalized) / market_price_normalized) * 100.0
-                } else {
-                    0.0
-                };
+                // 市场价格已按池子方向（mint 或 pair 名称）标准化为 "quote per LST"
+                // 折价 = (公允价值 - 市场价格) / 市场价格
+                let market_price = quoted.price;
+                let discount = ((fair_value - market_price) / market_price) * 100.0;
                 
                 // 🔍 Debug日志：诊断折价计算
                 debug!(
-                    "LST discount calculation: pool={}, pair={}, original_price={}, normalized_price={}, fair_value={}, discount={}%",
-                    pool.pool_id, pool.pair, pool.price, market_price_normalized, fair_value, discount
+                    "LST discount calculation: pool={}, pair={}, quote={}, original_price={}, normalized_price={}, fair_value={} (current={}, projected={}), discount={}%",
+                    pool.pool_id, pool.pair, quoted.quote.as_str(), pool.price, market_price, fair_value,
+                    fair.current_rate, fair.projected_rate, discount
                 );
                 
                 if discount < self.config.min_discount_percent {
                     continue;
                 }
                 
-                let unstake_fee = match lst.name {
-                    "mSOL" => self.config.marinade_unstake_fee,
-                    "jitoSOL" => self.config.jito_unstake_fee,
-                    _ => 0.003,
-                };
-                
-                let net_profit = discount - unstake_fee * 100.0;
+                let net_profit = discount - lst.unstake_fee * 100.0;
                 
-                // 🔥 严格的合理性检查
-                if net_profit > 15.0 {
-                    // LST折价赎回ROI >15% 几乎不可能（市场太高效）
-                    debug!(
-                        "❌ Rejecting unrealistic LST discount: {} at {} with {}% profit (likely calculation error)",
-                        lst.name, pool.dex_name, net_profit
-                    );
-                    continue;
-                } else if net_profit > 8.0 {
-                    // ROI 8-15% 值得怀疑，记录警告但保留
-                    info!(
-                        "⚠️  Suspicious LST discount: {} at {} with {}% profit (verify manually!)",
-                        lst.name, pool.dex_name, net_profit
-                    );
+                // 🔥 严格的合理性检查（LST折价赎回的高 ROI 几乎不可能，市场太高效）
+                match self.config.roi_sanity.verdict(net_profit) {
+                    RoiVerdict::Unrealistic => {
+                        debug!(
+                            "❌ Rejecting unrealistic LST discount: {} at {} with {}% profit (likely calculation error)",
+                            lst.symbol, pool.dex_name, net_profit
+                        );
+                        continue;
+                    }
+                    RoiVerdict::Suspicious => {
+                        info!(
+                            "⚠️  Suspicious LST discount: {} at {} with {}% profit (verify manually!)",
+                            lst.symbol, pool.dex_name, net_profit
+                        );
+                    }
+                    RoiVerdict::Plausible => {}
                 }
                 
                 if net_profit > 0.0 {
+                    let liquidity_sol = quoted.liquidity_sol(sol_usd).unwrap_or(0.0);
+                    let (amount_sol, amount_usd) = self.calculate_optimal_amount_by_liquidity(liquidity_sol, net_profit, sol_usd);
                     let path_description = format!(
-                        "Buy {} at {} → Redeem for SOL",
-                        lst.name, pool.dex_name
+                        "Buy {} with {} at {} → Redeem for SOL",
+                        lst.symbol, quoted.quote.as_str(), pool.dex_name
                     );
                     
                     opportunities.push(LstOpportunity {
-                        lst_name: lst.name.to_string(),
-                        market_price: market_price_normalized,  // 🔥 使用标准化后的价格
+                        lst_name: lst.symbol.clone(),
+                        quote: quoted.quote,
+                        market_price,  // 🔥 使用标准化后的价格
                         fair_value,
+                        projected_rate: fair.projected_rate,
+                        epoch_progress: fair.epoch_progress,
                         discount_percent: discount,
                         estimated_profit_percent: net_profit,
                         arbitrage_type: LstArbitrageType::DiscountPurchase {
                             buy_pool: pool.pool_id.clone(),
-                            unstake_delay_days: lst.unstake_delay_seconds / (24 * 3600),
+                            unstake_delay_days: lst.unstake_delay_days(),
                             expected_profit: net_profit,
                         },
                         path_description,
-                        recommended_amount_usd: self.calculate_recommended_amount(net_profit),
+                        recommended_amount_sol: amount_sol,
+                        recommended_amount_usd: amount_usd,
                         route_steps: None,
-                        input_amount: 1000.0,
-                        output_amount: 1000.0 * (1.0 + net_profit / 100.0),
+                        input_amount: amount_sol,
+                        output_amount: amount_sol * (1.0 + net_profit / 100.0),
                     });
                 }
             }
@@ -307,181 +387,160 @@ impl LstEnhancedDetector {
         Ok(opportunities)
     }
     
-    fn find_lst_pools(&self, lst: &LstToken, all_prices: &[PoolPrice]) -> Vec<PoolPrice> {
-        all_prices.iter().filter(|p| p.pair.contains(lst.name)).cloned().collect()
+    /// 包含该 LST 的池子，按对手代币分类；对手代币不是 SOL / USDC / USDT 的池子跳过
+    fn find_lst_pools(&self, lst: &LstEntry, all_prices: &[PoolPrice]) -> Vec<QuotedPool> {
+        let mint = lst.mint.to_string();
+        let registry = pool_mints::global();
+        all_prices.iter()
+            .filter(|p| registry.pool_has_token(p, &mint, &lst.symbol))
+            .filter_map(|p| {
+                let quoted = classify_lst_pool(p, &mint, &lst.symbol);
+                if quoted.is_none() {
+                    debug!("Skipping LST pool {} ({}): unsupported counter token", p.pool_id, p.pair);
+                }
+                quoted
+            })
+            .collect()
+    }
+    
+    /// SOL 的美元价格：优先用 PriceOracle，未设置时直接从 PriceCache 的新鲜 SOL/稳定币池子推导
+    fn sol_usd_price(&self) -> Option<f64> {
+        let price = match &self.price_oracle {
+            Some(oracle) => oracle.get_usd_price("SOL"),
+            None => {
+                let fresh = self.price_cache.get_fresh_prices(PriceOracleConfig::default().max_age_ms);
+                resolve_usd_price(&fresh, "SOL", &[])
+            }
+        };
+        price.filter(|price| *price > 0.0)
     }
     
     fn calculate_cross_dex_opportunity(
         &self,
-        lst: &LstToken,
-        pool_a: &PoolPrice,
-        pool_b: &PoolPrice,
+        lst: &LstEntry,
+        pool_a: &QuotedPool,
+        pool_b: &QuotedPool,
+        fair: &LstFairValue,
         fair_value: f64,
+        sol_usd: Option<f64>,
     ) -> Option<LstOpportunity> {
-        // 🔥 关键修复：标准化价格方向
-        // LST池子可能有两种方向：SOL/mSOL 或 mSOL/SOL
-        // 需要将它们都转换为统一方向（SOL/mSOL，即1 mSOL值多少SOL）
-        
-        // 检查池子名称方向，标准化为 "SOL per LST" 格式
-        let price_a_normalized = if pool_a.pair.starts_with("SOL/") || pool_a.pair.starts_with("SOL ") {
-            // SOL/mSOL -> price是mSOL/SOL，需要取倒数得到SOL/mSOL
-            if pool_a.price > 0.0 { 1.0 / pool_a.price } else { 0.0 }
-        } else {
-            // mSOL/SOL -> price已经是SOL/mSOL
-            pool_a.price
-        };
-        
-        let price_b_normalized = if pool_b.pair.starts_with("SOL/") || pool_b.pair.starts_with("SOL ") {
-            // SOL/mSOL -> 取倒数
-            if pool_b.price > 0.0 { 1.0 / pool_b.price } else { 0.0 }
+        // 两个池子的价格都已标准化为 "quote per LST"（同一计价代币），可以直接比较
+        let (buy, sell) = if pool_a.price < pool_b.price {
+            (pool_a, pool_b)
         } else {
-            // mSOL/SOL -> 已是正确方向
-            pool_b.price
-        };
-        
-        // 标准化后价格都表示：1 LST值多少SOL
-        // 现在可以安全比较了
-        let (buy_pool, sell_pool, buy_price, sell_price) = if price_a_normalized < price_b_normalized {
-            (pool_a, pool_b, price_a_normalized, price_b_normalized)
-        } else {
-            (pool_b, pool_a, price_b_normalized, price_a_normalized)
+            (pool_b, pool_a)
         };
+        let (buy_pool, sell_pool, buy_price, sell_price) = (&buy.pool, &sell.pool, buy.price, sell.price);
         
         // 计算价差（基于标准化后的价格）
         let price_diff_percent = ((sell_price - buy_price) / buy_price) * 100.0;
-        let fee_buy = self.get_dex_fee(&buy_pool.dex_name);
-        let fee_sell = self.get_dex_fee(&sell_pool.dex_name);
+        let fee_buy = self.get_pool_fee(buy_pool);
+        let fee_sell = self.get_pool_fee(sell_pool);
         let net_profit = price_diff_percent - (fee_buy + fee_sell) * 100.0;
         
         // 🔥 严格的合理性检查：LST跨DEX套利
-        if net_profit > 15.0 {
-            // ROI >15% 几乎不可能，拒绝
-            debug!(
-                "❌ Rejecting unrealistic LST cross-DEX: {} → {} with {}% profit (price_a={}, price_b={}, normalized: {} vs {})",
-                buy_pool.dex_name, sell_pool.dex_name, net_profit,
-                pool_a.price, pool_b.price, price_a_normalized, price_b_normalized
-            );
-            return None;
-        } else if net_profit > 8.0 {
-            // ROI 8-15% 值得怀疑，记录警告
-            info!(
-                "⚠️  Suspicious LST cross-DEX: {} → {} with {}% profit (verify manually!)",
-                buy_pool.dex_name, sell_pool.dex_name, net_profit
-            );
+        match self.config.roi_sanity.verdict(net_profit) {
+            RoiVerdict::Unrealistic => {
+                debug!(
+                    "❌ Rejecting unrealistic LST cross-DEX: {} → {} with {}% profit (price_a={}, price_b={}, normalized: {} vs {} {})",
+                    buy_pool.dex_name, sell_pool.dex_name, net_profit,
+                    pool_a.pool.price, pool_b.pool.price, pool_a.price, pool_b.price, pool_a.quote.as_str()
+                );
+                return None;
+            }
+            RoiVerdict::Suspicious => {
+                info!(
+                    "⚠️  Suspicious LST cross-DEX: {} → {} with {}% profit (verify manually!)",
+                    buy_pool.dex_name, sell_pool.dex_name, net_profit
+                );
+            }
+            RoiVerdict::Plausible => {}
         }
         
         if net_profit < self.config.min_discount_percent {
             return None;
         }
         
-        // 🔥 使用基于流动性的智能金额计算
-        let recommended_amount = self.calculate_optimal_amount_by_liquidity(buy_pool, sell_pool, net_profit);
+        // 🔥 使用基于流动性的智能金额计算（取两个池子中较小的流动性）
+        let liquidity_sol = buy.liquidity_sol(sol_usd).unwrap_or(0.0)
+            .min(sell.liquidity_sol(sol_usd).unwrap_or(0.0));
+        let (amount_sol, amount_usd) = self.calculate_optimal_amount_by_liquidity(liquidity_sol, net_profit, sol_usd);
         
         Some(LstOpportunity {
-            lst_name: lst.name.to_string(),
+            lst_name: lst.symbol.clone(),
+            quote: buy.quote,
             market_price: buy_price,  // 使用标准化后的价格
             fair_value,
+            projected_rate: fair.projected_rate,
+            epoch_progress: fair.epoch_progress,
             discount_percent: price_diff_percent,
             estimated_profit_percent: net_profit,
             arbitrage_type: LstArbitrageType::Instant {
                 path: vec![format!("Buy {} → Sell {}", buy_pool.dex_name, sell_pool.dex_name)],
                 expected_profit: net_profit,
             },
-            path_description: format!("Cross-DEX: {} → {}", buy_pool.dex_name, sell_pool.dex_name),
-            recommended_amount_usd: recommended_amount,
+            path_description: format!(
+                "Cross-DEX ({}): {} → {}",
+                buy.quote.as_str(), buy_pool.dex_name, sell_pool.dex_name
+            ),
+            recommended_amount_sol: amount_sol,
+            recommended_amount_usd: amount_usd,
             route_steps: None,
-            input_amount: recommended_amount,
-            output_amount: recommended_amount * (1.0 + net_profit / 100.0),
+            input_amount: amount_sol,
+            output_amount: amount_sol * (1.0 + net_profit / 100.0),
         })
     }
     
-    fn get_dex_fee(&self, dex_name: &str) -> f64 {
-        *self.dex_fees.get(dex_name).unwrap_or(&0.0025)
+    fn get_pool_fee(&self, pool: &PoolPrice) -> f64 {
+        crate::fee_registry::fee_rate(&pool.pool_id, &pool.dex_name)
     }
     
-    /// 🔥 智能计算推荐套利金额（基于流动性和滑点）
+    /// 🔥 基于池子流动性计算推荐套利金额
     /// 
     /// 原则：
     /// - 交易额不应超过池子流动性的1-2%（控制滑点<1%）
     /// - 对于流动性差的池子，降低推荐金额
     /// - 考虑ROI和风险的平衡
-    fn calculate_recommended_amount(&self, roi: f64) -> f64 {
-        // 🔥 简化版本：基于ROI的保守推荐
-        // TODO: 改进为基于实际池子流动性计算
-        
-        // 对于可疑的高ROI（8-15%），大幅降低推荐金额
-        if roi > 8.0 {
-            // 高ROI往往意味着流动性差或数据问题
-            100.0  // 只推荐$100
-        } else if roi > 5.0 {
-            500.0  // $500
-        } else if roi > 2.0 {
-            1000.0  // $1,000
-        } else if roi > 1.0 {
-            2000.0  // $2,000
-        } else {
-            500.0  // 默认$500
-        }
-    }
-    
-    /// 🔥 新增：基于池子流动性计算最优套利金额
     /// 
-    /// # Arguments
-    /// * `pool_a` - 买入池子
-    /// * `pool_b` - 卖出池子
-    /// * `roi` - 预期ROI
+    /// `min_liquidity_sol` 是参与池子中较小的流动性（限制因素，以 SOL 计）
     /// 
     /// # Returns
-    /// 最优套利金额（USD）
-    #[allow(dead_code)]
+    /// (推荐金额 SOL, 推荐金额 USD)；没有新鲜的 SOL 美元价格时 USD 为 None，
+    /// 按最保守的 0.5% 比例给出 SOL 数量
     fn calculate_optimal_amount_by_liquidity(
         &self,
-        pool_a: &PoolPrice,
-        pool_b: &PoolPrice,
+        min_liquidity_sol: f64,
         roi: f64,
-    ) -> f64 {
-        // 计算两个池子的最小流动性（限制因素）
-        let pool_a_liquidity_usd = {
-            let min_reserve = pool_a.base_reserve.min(pool_a.quote_reserve) as f64;
-            let decimals = pool_a.base_decimals.min(pool_a.quote_decimals);
-            let amount = min_reserve / 10f64.powi(decimals as i32);
-            // 假设SOL价格$200，粗略转USD
-            amount * 200.0
-        };
-        
-        let pool_b_liquidity_usd = {
-            let min_reserve = pool_b.base_reserve.min(pool_b.quote_reserve) as f64;
-            let decimals = pool_b.base_decimals.min(pool_b.quote_decimals);
-            let amount = min_reserve / 10f64.powi(decimals as i32);
-            amount * 200.0
-        };
-        
-        // 取两个池子中较小的流动性
-        let min_liquidity = pool_a_liquidity_usd.min(pool_b_liquidity_usd);
-        
-        // 🔥 关键规则：交易额不超过流动性的1-2%（控制滑点）
-        // 流动性越大，可以用的比例越高
-        let safe_percentage = if min_liquidity > 100_000.0 {
-            0.02  // 大池子：2%
-        } else if min_liquidity > 10_000.0 {
-            0.01  // 中池子：1%
-        } else {
-            0.005 // 小池子：0.5%
-        };
-        
-        let max_safe_amount = min_liquidity * safe_percentage;
-        
+        sol_usd: Option<f64>,
+    ) -> (f64, Option<f64>) {
         // 根据ROI调整（ROI越高，越保守）
-        let roi_adjusted = if roi > 8.0 {
-            max_safe_amount * 0.1  // 高ROI可疑，只用10%
+        let roi_factor = if roi > 8.0 {
+            0.1  // 高ROI可疑，只用10%
         } else if roi > 5.0 {
-            max_safe_amount * 0.3  // 中高ROI，用30%
+            0.3  // 中高ROI，用30%
         } else {
-            max_safe_amount  // 正常ROI，用100%
+            1.0  // 正常ROI，用100%
         };
         
-        // 限制范围：$50-$5000
-        roi_adjusted.max(50.0).min(5000.0)
+        match sol_usd {
+            Some(sol_usd) => {
+                let min_liquidity = min_liquidity_sol * sol_usd;
+                
+                // 🔥 关键规则：流动性越大，可以用的比例越高
+                let safe_percentage = if min_liquidity > 100_000.0 {
+                    0.02  // 大池子：2%
+                } else if min_liquidity > 10_000.0 {
+                    0.01  // 中池子：1%
+                } else {
+                    0.005 // 小池子：0.5%
+                };
+                
+                // 限制范围：$50-$5000
+                let amount_usd = (min_liquidity * safe_percentage * roi_factor).clamp(50.0, 5000.0);
+                (amount_usd / sol_usd, Some(amount_usd))
+            }
+            None => (min_liquidity_sol * 0.005 * roi_factor, None),
+        }
     }
     
     pub fn generate_report(&self, opportunities: &[LstOpportunity]) -> String {
@@ -495,12 +554,26 @@ impl LstEnhancedDetector {
         report.push_str("╠════════════════════════════════════════════════════════════════╣\n");
         
         for (idx, opp) in opportunities.iter().enumerate() {
+            let amount = match opp.recommended_amount_usd {
+                Some(amount_usd) => format!("${:.0}", amount_usd),
+                None => format!("{:.3} SOL", opp.recommended_amount_sol),
+            };
             report.push_str(&format!(
-                "║ #{:<2} {} │ ROI {:>5.2}% │ ${:<6.0}                   ║\n",
-                idx + 1, opp.lst_name, opp.estimated_profit_percent, opp.recommended_amount_usd
+                "║ #{:<2} {} │ ROI {:>5.2}% │ {:<11}               ║\n",
+                idx + 1, opp.lst_name, opp.estimated_profit_percent, amount
             ));
             
             report.push_str(&format!("║     {:<59}║\n", &opp.path_description));
+            let epoch_progress = opp.epoch_progress
+                .map(|p| format!("{:.1}%", p * 100.0))
+                .unwrap_or_else(|| "n/a".to_string());
+            report.push_str(&format!(
+                "║     {:<59}║\n",
+                format!(
+                    "公允 {:.6} {} │ 下一epoch {:.6} │ epoch进度 {}",
+                    opp.fair_value, opp.quote.as_str(), opp.projected_rate, epoch_progress
+                )
+            ));
             
             match &opp.arbitrage_type {
                 LstArbitrageType::Instant { .. } => {
@@ -521,3 +594,102 @@ impl LstEnhancedDetector {
     }
 }
 
+/// 按对手代币给 LST 池子分类，并把价格标准化为 "quote per LST"
+///
+/// 配置了 mint 时按 mint 判断 LST 在哪一侧，否则按 pair 中的符号。
+fn classify_lst_pool(pool: &PoolPrice, lst_mint: &str, lst_symbol: &str) -> Option<QuotedPool> {
+    let registry = pool_mints::global();
+    let (base, quote) = registry.pool_tokens(pool)?;
+    let lst_is_base = match registry.mint_is_base(&pool.pool_id, lst_mint) {
+        Some(is_base) => is_base,
+        None if base == lst_symbol => true,
+        None if quote == lst_symbol => false,
+        None => return None,
+    };
+    let counter = if lst_is_base { &quote } else { &base };
+    let quote_token = LstQuote::from_symbol(counter)?;
+    
+    let (base_decimals, quote_decimals) = pool.get_decimals();
+    let (price, quote_reserve) = if lst_is_base {
+        (pool.price, pool.quote_reserve as f64 / 10f64.powi(quote_decimals as i32))
+    } else {
+        let price = if pool.price > 0.0 { 1.0 / pool.price } else { 0.0 };
+        (price, pool.base_reserve as f64 / 10f64.powi(base_decimals as i32))
+    };
+    Some(QuotedPool { pool: pool.clone(), quote: quote_token, price, quote_reserve })
+}
+
+/// 把 "SOL per LST" 的公允价值换算成计价代币；稳定币计价需要 SOL 美元价格
+fn quote_fair_value(fair_value_sol: f64, quote: LstQuote, sol_usd: Option<f64>) -> Option<f64> {
+    match quote {
+        LstQuote::Sol => Some(fair_value_sol),
+        LstQuote::Usdc | LstQuote::Usdt => sol_usd.map(|sol_usd| fair_value_sol * sol_usd),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc_manager::RpcManager;
+
+    /// 深度 10,000 base 代币，储备量与价格一致
+    fn pool(pool_id: &str, pair: &str, price: f64, base_decimals: u8, quote_decimals: u8) -> PoolPrice {
+        let depth = 10_000.0;
+        PoolPrice {
+            dex_name: "Whirlpool (Orca)".to_string(),
+            base_reserve: (depth * 10f64.powi(base_decimals as i32)) as u64,
+            quote_reserve: (depth * price * 10f64.powi(quote_decimals as i32)) as u64,
+            base_decimals,
+            quote_decimals,
+            slot: 1000,
+            ..PoolPrice::for_test(pool_id, pair, price)
+        }
+    }
+
+    /// 离线检测器：RPC 指向本地关闭端口，mSOL 使用兜底比率 1.05 SOL
+    fn offline_detector(pools: Vec<PoolPrice>) -> LstEnhancedDetector {
+        let cache = Arc::new(PriceCache::new());
+        for p in pools {
+            cache.update_price(p);
+        }
+        let rpc = Arc::new(RpcManager::unlimited("http://127.0.0.1:9")).handle("test");
+        let reader = StakePoolReader::new(rpc, 3600, Arc::new(LstRegistry::default())).unwrap();
+        LstEnhancedDetector::new(cache, Arc::new(reader), LstDetectorConfig::default())
+    }
+
+    #[test]
+    fn test_usdc_quoted_pool_at_fair_price_has_no_phantom_discount() {
+        // SOL = 150 USDC → mSOL 公允价值 157.5 USDC；两种方向的 mSOL/USDC 池子都按公允价格报价
+        let detector = offline_detector(vec![
+            pool("lst-test-sol-usdc", "SOL/USDC", 150.0, 9, 6),
+            pool("lst-test-msol-usdc", "mSOL/USDC", 157.5, 9, 6),
+            pool("lst-test-usdc-msol", "USDC/mSOL", 1.0 / 157.5, 6, 9),
+            pool("lst-test-msol-sol", "mSOL/SOL", 1.05, 9, 9),
+        ]);
+        assert!(detector.detect_all_opportunities(1.0).is_empty());
+
+        // 没有 SOL 美元价格时稳定币计价的池子跳过，而不是和 SOL 计价的公允价值比较
+        let unpriced = offline_detector(vec![pool("lst-test-msol-usdc-unpriced", "USDC/mSOL", 1.0 / 140.0, 6, 9)]);
+        assert!(unpriced.detect_all_opportunities(1.0).is_empty());
+    }
+
+    #[test]
+    fn test_discount_detected_in_usdc_terms() {
+        // 公允 157.5 USDC，市场 155.925 USDC（约 1% 折价），扣除 0.3% 赎回费后仍有利润
+        let detector = offline_detector(vec![
+            pool("lst-test-discount-sol-usdc", "SOL/USDC", 150.0, 9, 6),
+            pool("lst-test-discount-msol-usdc", "mSOL/USDC", 155.925, 9, 6),
+        ]);
+        let opportunities = detector.detect_all_opportunities(1.0);
+        assert_eq!(opportunities.len(), 1);
+
+        let opp = &opportunities[0];
+        assert_eq!(opp.lst_name, "mSOL");
+        assert_eq!(opp.quote, LstQuote::Usdc);
+        assert!((opp.fair_value - 157.5).abs() < 1e-9);
+        assert!((opp.market_price - 155.925).abs() < 1e-9);
+        assert!((opp.discount_percent - 1.0101).abs() < 0.001);
+        assert!((opp.estimated_profit_percent - 0.7101).abs() < 0.001);
+        assert!(matches!(opp.arbitrage_type, LstArbitrageType::DiscountPurchase { .. }));
+    }
+}
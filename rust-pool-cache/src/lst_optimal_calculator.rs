@@ -215,18 +215,8 @@ impl LstOptimalCalculator {
     /// # Returns
     /// 标准化后的价格（SOL/LST格式）
     fn normalize_price(&self, pool: &PoolPrice) -> f64 {
-        // 检查池子名称方向
-        if pool.pair.starts_with("SOL/") || pool.pair.starts_with("SOL ") {
-            // SOL/mSOL池子 → price是mSOL/SOL → 取倒数得SOL/mSOL
-            if pool.price > 0.0 {
-                1.0 / pool.price
-            } else {
-                0.0
-            }
-        } else {
-            // mSOL/SOL池子 → price已经是SOL/mSOL
-            pool.price
-        }
+        // 配置了 mint 时按 mint 判断方向，否则看池子名称
+        crate::pool_mints::sol_per_lst_price(pool)
     }
     
     /// 辅助：根据流动性确定安全百分比
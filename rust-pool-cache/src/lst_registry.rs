@@ -0,0 +1,174 @@
+/*!
+ * LST 注册表
+ *
+ * 列出需要追踪理论赎回比率的 LST：mint、stake pool 账户、账户布局、赎回费用与等待时间。
+ * 默认只包含 mSOL / jitoSOL（沿用 [lst_detector] 的 marinade / jito 段），
+ * 其余基于 SPL stake pool 的 LST（bSOL、jucySOL 等）通过 `tokens` 追加。
+ */
+
+use crate::config::{LstDetectorConfig, LstPoolType, LstTokenConfig};
+use anyhow::{Context, Result};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+/// mSOL mint
+pub const MSOL_MINT: &str = "mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So";
+/// Marinade State账户地址
+pub const MARINADE_STATE: &str = "8szGkuLTAux9XMgZ2vtY39jVSowEcpBfFfD8hXSEqdGC";
+/// jitoSOL mint
+pub const JITOSOL_MINT: &str = "J1toso1uCk3RLmjorhTtrVwY9HJ7X8V9yYac6Y7kGCPn";
+/// Jito Stake Pool账户地址
+pub const JITO_STAKE_POOL: &str = "Jito4APyf642JPZPx3hGc6WWJ8zPKtRbRs4P815Awbb";
+
+/// 一个已注册的 LST
+#[derive(Debug, Clone)]
+pub struct LstEntry {
+    /// 池子交易对中使用的代币符号
+    pub symbol: String,
+    pub mint: Pubkey,
+    pub stake_pool: Pubkey,
+    pub pool_type: LstPoolType,
+    /// 赎回费率（小数，0.003 = 0.3%）
+    pub unstake_fee: f64,
+    pub unstake_delay_secs: u64,
+    /// 链上读取失败且没有缓存值时使用的比率（仅内置的 mSOL / jitoSOL 有）
+    pub fallback_rate: Option<f64>,
+    /// 单独配置的年化收益（百分比）
+    pub apy_percent: Option<f64>,
+}
+
+impl LstEntry {
+    fn from_config(token: &LstTokenConfig) -> Result<Self> {
+        Ok(Self {
+            symbol: token.symbol.clone(),
+            mint: Pubkey::from_str(&token.mint)
+                .with_context(|| format!("invalid mint for LST {}", token.symbol))?,
+            stake_pool: Pubkey::from_str(&token.stake_pool_address)
+                .with_context(|| format!("invalid stake pool address for LST {}", token.symbol))?,
+            pool_type: token.pool_type,
+            unstake_fee: token.unstake_fee,
+            unstake_delay_secs: token.unstake_delay_secs,
+            fallback_rate: None,
+            apy_percent: token.apy_percent,
+        })
+    }
+
+    pub fn unstake_delay_days(&self) -> u64 {
+        self.unstake_delay_secs / (24 * 3600)
+    }
+}
+
+/// LST 注册表（按配置顺序）
+#[derive(Debug, Clone)]
+pub struct LstRegistry {
+    entries: Vec<LstEntry>,
+}
+
+impl Default for LstRegistry {
+    fn default() -> Self {
+        Self::new(vec![
+            builtin_msol(MARINADE_STATE, 0.003),
+            builtin_jitosol(JITO_STAKE_POOL, 0.001),
+        ])
+    }
+}
+
+impl LstRegistry {
+    pub fn new(entries: Vec<LstEntry>) -> Self {
+        Self { entries }
+    }
+
+    /// 从 [lst_detector] 构建：mSOL / jitoSOL 始终在列，`tokens` 中相同 mint 的条目覆盖它们
+    pub fn from_config(config: &LstDetectorConfig) -> Result<Self> {
+        let marinade_state = config.marinade.as_ref()
+            .map(|m| m.state_address.as_str())
+            .unwrap_or(MARINADE_STATE);
+        let marinade_fee = config.marinade.as_ref().map(|m| m.unstake_fee).unwrap_or(0.003);
+        let jito_pool = config.jito.as_ref()
+            .map(|j| j.stake_pool_address.as_str())
+            .unwrap_or(JITO_STAKE_POOL);
+        let jito_fee = config.jito.as_ref().map(|j| j.unstake_fee).unwrap_or(0.001);
+
+        let mut entries = vec![
+            builtin_msol(marinade_state, marinade_fee),
+            builtin_jitosol(jito_pool, jito_fee),
+        ];
+        for token in &config.tokens {
+            let entry = LstEntry::from_config(token)?;
+            match entries.iter_mut().find(|e| e.mint == entry.mint) {
+                Some(existing) => {
+                    let fallback_rate = existing.fallback_rate;
+                    *existing = LstEntry { fallback_rate, ..entry };
+                }
+                None => entries.push(entry),
+            }
+        }
+
+        Ok(Self::new(entries))
+    }
+
+    pub fn entries(&self) -> &[LstEntry] {
+        &self.entries
+    }
+}
+
+fn builtin_msol(state_address: &str, unstake_fee: f64) -> LstEntry {
+    LstEntry {
+        symbol: "mSOL".to_string(),
+        mint: Pubkey::from_str(MSOL_MINT).unwrap(),
+        stake_pool: Pubkey::from_str(state_address)
+            .unwrap_or_else(|_| Pubkey::from_str(MARINADE_STATE).unwrap()),
+        pool_type: LstPoolType::Marinade,
+        unstake_fee,
+        unstake_delay_secs: 2 * 24 * 3600, // 2天解锁期
+        fallback_rate: Some(1.05),
+        apy_percent: None,
+    }
+}
+
+fn builtin_jitosol(stake_pool_address: &str, unstake_fee: f64) -> LstEntry {
+    LstEntry {
+        symbol: "jitoSOL".to_string(),
+        mint: Pubkey::from_str(JITOSOL_MINT).unwrap(),
+        stake_pool: Pubkey::from_str(stake_pool_address)
+            .unwrap_or_else(|_| Pubkey::from_str(JITO_STAKE_POOL).unwrap()),
+        pool_type: LstPoolType::SplStakePool,
+        unstake_fee,
+        unstake_delay_secs: 24 * 3600, // 1天解锁期
+        fallback_rate: Some(1.04),
+        apy_percent: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_config_keeps_defaults_and_appends_tokens() {
+        let config: LstDetectorConfig = toml::from_str(r#"
+            [jito]
+            stake_pool_address = "Jito4APyf642JPZPx3hGc6WWJ8zPKtRbRs4P815Awbb"
+            unstake_fee = 0.002
+
+            [[tokens]]
+            symbol = "bSOL"
+            mint = "bSo13r4TkiE4KumL71LsHTPpL2euBYLFx6h9HP3piy1"
+            stake_pool_address = "stk9ApL5HeVAwPLr3TLhDXdZS8ptVu7zp6ov8HFDuMi"
+        "#).unwrap();
+        let registry = LstRegistry::from_config(&config).unwrap();
+
+        let symbols: Vec<&str> = registry.entries().iter().map(|e| e.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["mSOL", "jitoSOL", "bSOL"]);
+
+        let jitosol = &registry.entries()[1];
+        assert_eq!(jitosol.mint, Pubkey::from_str(JITOSOL_MINT).unwrap());
+        assert_eq!(jitosol.unstake_fee, 0.002);
+        assert_eq!(jitosol.fallback_rate, Some(1.04));
+
+        let bsol = &registry.entries()[2];
+        assert_eq!(bsol.pool_type, LstPoolType::SplStakePool);
+        assert_eq!(bsol.unstake_delay_days(), 2);
+        assert_eq!(bsol.fallback_rate, None);
+    }
+}
@@ -1,6 +1,16 @@
 use chrono::{DateTime, Utc};
+use dashmap::DashMap;
 use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::account_subscription::MalformedKind;
+use crate::latency_budget::{LatencyBreakdown, LatencyStage};
+use crate::prometheus::{
+    Histogram, MetricKind, PrometheusWriter, LATENCY_BUDGET_BUCKETS, POOL_UPDATE_LATENCY_BUCKETS,
+    SCAN_DURATION_BUCKETS,
+};
 
 #[derive(Clone, Debug)]
 pub struct LatencyMeasurement {
@@ -10,10 +20,36 @@ pub struct LatencyMeasurement {
     pub pool_name: String,
 }
 
+/// 🔀 单条 WebSocket 连接（分片）的消息计数
+#[derive(Debug)]
+struct ShardMessages {
+    total: AtomicU64,
+    /// 上次读取速率的时间和当时的计数
+    window: Mutex<(Instant, u64)>,
+}
+
+impl ShardMessages {
+    fn new() -> Self {
+        Self {
+            total: AtomicU64::new(0),
+            window: Mutex::new((Instant::now(), 0)),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct MetricsCollector {
     measurements: Arc<Mutex<VecDeque<LatencyMeasurement>>>,
     max_measurements: usize,
+    reconnect_attempts: Arc<AtomicU64>,  // 🔄 WebSocket 重连次数
+    websocket_messages: Arc<AtomicU64>,  // 📡 WebSocket 文本消息数
+    shard_messages: Arc<DashMap<usize, ShardMessages>>,  // 🔀 按连接分片的消息数
+    malformed_messages: Arc<[AtomicU64; 4]>,  // 🧩 畸形通知数（按 MalformedKind::ALL 顺序）
+    pool_update_latency: Arc<DashMap<String, Histogram>>,  // 📊 按池子的更新处理延迟
+    scan_duration: Arc<Histogram>,  // 🧮 Calculator 全量扫描耗时
+    scoped_scan_duration: Arc<Histogram>,  // 🎯 Calculator 定向扫描耗时（事件触发）
+    latency_stages: Arc<Vec<Histogram>>,  // ⏱️ 延迟预算各阶段（按 LatencyStage::ALL 顺序）
+    detection_latency: Arc<[Histogram; 2]>,  // ⏱️ 通知 -> 上报机会的端到端延迟（[event, clock]）
 }
 
 impl MetricsCollector {
@@ -21,11 +57,107 @@ impl MetricsCollector {
         Self {
             measurements: Arc::new(Mutex::new(VecDeque::with_capacity(max_measurements))),
             max_measurements,
+            reconnect_attempts: Arc::new(AtomicU64::new(0)),
+            websocket_messages: Arc::new(AtomicU64::new(0)),
+            shard_messages: Arc::new(DashMap::new()),
+            malformed_messages: Arc::new(Default::default()),
+            pool_update_latency: Arc::new(DashMap::new()),
+            scan_duration: Arc::new(Histogram::new(SCAN_DURATION_BUCKETS)),
+            scoped_scan_duration: Arc::new(Histogram::new(SCAN_DURATION_BUCKETS)),
+            latency_stages: Arc::new(LatencyStage::ALL.iter().map(|_| Histogram::new(LATENCY_BUDGET_BUCKETS)).collect()),
+            detection_latency: Arc::new([Histogram::new(LATENCY_BUDGET_BUCKETS), Histogram::new(LATENCY_BUDGET_BUCKETS)]),
+        }
+    }
+    
+    /// 📡 Record a WebSocket text message received on connection shard `shard`
+    pub fn record_websocket_message(&self, shard: usize) {
+        self.websocket_messages.fetch_add(1, Ordering::Relaxed);
+        // 读锁命中时不持有写锁；首次出现的分片才走 entry()
+        if let Some(counter) = self.shard_messages.get(&shard) {
+            counter.total.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        self.shard_messages.entry(shard).or_insert_with(ShardMessages::new)
+            .total.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    /// 🧩 Record a malformed WebSocket message (content error, the connection keeps reading)
+    pub fn record_malformed_message(&self, kind: MalformedKind) {
+        if let Some(index) = MalformedKind::ALL.iter().position(|k| *k == kind) {
+            self.malformed_messages[index].fetch_add(1, Ordering::Relaxed);
         }
     }
     
+    /// 🧩 Malformed WebSocket messages of one kind since startup
+    pub fn malformed_messages(&self, kind: MalformedKind) -> u64 {
+        MalformedKind::ALL.iter()
+            .position(|k| *k == kind)
+            .map_or(0, |index| self.malformed_messages[index].load(Ordering::Relaxed))
+    }
+    
+    /// 🔀 Per-shard WebSocket message rate (messages/sec since the previous call), sorted by shard
+    pub fn shard_message_rates(&self) -> Vec<(usize, f64)> {
+        let mut rates: Vec<(usize, f64)> = self.shard_messages.iter()
+            .map(|entry| {
+                let total = entry.total.load(Ordering::Relaxed);
+                let mut window = entry.window.lock().unwrap();
+                let elapsed = window.0.elapsed().as_secs_f64();
+                let rate = if elapsed > 0.0 { (total - window.1) as f64 / elapsed } else { 0.0 };
+                *window = (Instant::now(), total);
+                (*entry.key(), rate)
+            })
+            .collect();
+        rates.sort_by_key(|(shard, _)| *shard);
+        rates
+    }
+    
+    /// 🧮 Record the duration of one calculator scan (`scoped` = only paths through the triggering pair)
+    pub fn record_scan_duration(&self, duration: Duration, scoped: bool) {
+        if scoped {
+            self.scoped_scan_duration.observe(duration);
+        } else {
+            self.scan_duration.observe(duration);
+        }
+    }
+    
+    /// ⏱️ Record the per-stage latency budget of one calculator scan
+    pub fn record_latency_breakdown(&self, breakdown: &LatencyBreakdown) {
+        for (stage, duration) in breakdown.stages() {
+            if let Some(index) = LatencyStage::ALL.iter().position(|s| *s == stage) {
+                self.latency_stages[index].observe(duration);
+            }
+        }
+    }
+    
+    /// ⏱️ Record notification-to-report latency of a scan that reported opportunities
+    pub fn record_detection_latency(&self, end_to_end: Duration, event_triggered: bool) {
+        self.detection_latency[if event_triggered { 0 } else { 1 }].observe(end_to_end);
+    }
+    
+    /// 🔄 Record a WebSocket reconnect attempt
+    pub fn record_reconnect_attempt(&self) {
+        self.reconnect_attempts.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    /// 🔄 Total WebSocket reconnect attempts since startup
+    pub fn reconnect_attempts(&self) -> u64 {
+        self.reconnect_attempts.load(Ordering::Relaxed)
+    }
+    
     /// Record a new latency measurement
     pub fn record(&self, pool_name: String, latency_micros: u64) {
+        let latency = Duration::from_micros(latency_micros);
+        // 读锁命中时不持有写锁；首次出现的池子才走 entry()
+        let observed = self.pool_update_latency.get(&pool_name)
+            .map(|histogram| histogram.observe(latency))
+            .is_some();
+        if !observed {
+            self.pool_update_latency
+                .entry(pool_name.clone())
+                .or_insert_with(|| Histogram::new(POOL_UPDATE_LATENCY_BUCKETS))
+                .observe(latency);
+        }
+        
         let measurement = LatencyMeasurement {
             timestamp: Utc::now(),
             latency_micros,
@@ -93,6 +225,13 @@ impl MetricsCollector {
         println!("├───────────────────────────────────────────────────────┤");
         println!("│  Total Updates:     {:>8}                         │", stats.total_updates);
         println!("│  Update Rate:       {:>8.2} updates/sec             │", stats.update_rate_per_second);
+        println!("│  Reconnects:        {:>8}                         │", self.reconnect_attempts());
+        let shard_rates = self.shard_message_rates();
+        if shard_rates.len() > 1 {
+            for (shard, rate) in &shard_rates {
+                println!("│  Shard {:<3} msgs:   {:>8.2} msgs/sec                │", shard + 1, rate);
+            }
+        }
         println!("├───────────────────────────────────────────────────────┤");
         println!("│  Latency (microseconds):                            │");
         println!("│    Average:         {:>8.2} μs ({:.2} ms)          │", 
@@ -111,6 +250,89 @@ impl MetricsCollector {
     }
 }
 
+impl MetricsCollector {
+    /// 📈 Write collector-owned metrics in Prometheus text format
+    pub fn write_prometheus(&self, writer: &mut PrometheusWriter) {
+        writer.family(
+            "pool_cache_pool_update_latency_seconds",
+            "Time from receiving a pool account notification to updating the price cache",
+            MetricKind::Histogram,
+        );
+        let mut pools: Vec<_> = self.pool_update_latency.iter()
+            .map(|entry| (entry.key().clone(), entry.value().snapshot()))
+            .collect();
+        pools.sort_by(|a, b| a.0.cmp(&b.0));
+        for (pool, snapshot) in &pools {
+            writer.histogram("pool_cache_pool_update_latency_seconds", &[("pool", pool)], snapshot);
+        }
+        
+        writer.family(
+            "pool_cache_websocket_messages_total",
+            "WebSocket text messages received (use rate() for messages/sec)",
+            MetricKind::Counter,
+        );
+        writer.sample("pool_cache_websocket_messages_total", &[], self.websocket_messages.load(Ordering::Relaxed) as f64);
+        
+        writer.family(
+            "pool_cache_websocket_shard_messages_total",
+            "WebSocket text messages received per connection shard",
+            MetricKind::Counter,
+        );
+        let mut shards: Vec<(usize, u64)> = self.shard_messages.iter()
+            .map(|entry| (*entry.key(), entry.total.load(Ordering::Relaxed)))
+            .collect();
+        shards.sort_unstable();
+        for (shard, total) in shards {
+            writer.sample("pool_cache_websocket_shard_messages_total", &[("shard", &shard.to_string())], total as f64);
+        }
+        
+        writer.family(
+            "pool_cache_websocket_malformed_messages_total",
+            "Malformed WebSocket messages dropped without reconnecting, by kind (bad_json, missing_field, bad_base64, unexpected_encoding)",
+            MetricKind::Counter,
+        );
+        for kind in MalformedKind::ALL {
+            writer.sample(
+                "pool_cache_websocket_malformed_messages_total",
+                &[("kind", kind.as_str())],
+                self.malformed_messages(kind) as f64,
+            );
+        }
+        
+        writer.family(
+            "pool_cache_websocket_reconnects_total",
+            "WebSocket reconnect attempts since startup",
+            MetricKind::Counter,
+        );
+        writer.sample("pool_cache_websocket_reconnects_total", &[], self.reconnect_attempts() as f64);
+        
+        writer.family(
+            "pool_cache_scan_duration_seconds",
+            "Calculator arbitrage scan duration (all amount tiers) by scope (full, scoped)",
+            MetricKind::Histogram,
+        );
+        writer.histogram("pool_cache_scan_duration_seconds", &[("scope", "full")], &self.scan_duration.snapshot());
+        writer.histogram("pool_cache_scan_duration_seconds", &[("scope", "scoped")], &self.scoped_scan_duration.snapshot());
+        
+        writer.family(
+            "pool_cache_latency_stage_seconds",
+            "Detection latency budget per stage (deserialize, cache_update, coordinator_queue, calculator_wait, snapshot, graph_scan, validation)",
+            MetricKind::Histogram,
+        );
+        for (stage, histogram) in LatencyStage::ALL.iter().zip(self.latency_stages.iter()) {
+            writer.histogram("pool_cache_latency_stage_seconds", &[("stage", stage.name())], &histogram.snapshot());
+        }
+        
+        writer.family(
+            "pool_cache_detection_latency_seconds",
+            "Time from receiving the triggering notification (clock: task creation) to reporting opportunities",
+            MetricKind::Histogram,
+        );
+        writer.histogram("pool_cache_detection_latency_seconds", &[("trigger", "event")], &self.detection_latency[0].snapshot());
+        writer.histogram("pool_cache_detection_latency_seconds", &[("trigger", "clock")], &self.detection_latency[1].snapshot());
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct MetricsStats {
     pub total_updates: usize,
@@ -143,6 +365,54 @@ mod tests {
         assert_eq!(stats.total_updates, 3);
         assert_eq!(stats.avg_latency_micros, 2000);
     }
+    
+    #[test]
+    fn test_shard_message_rates() {
+        let collector = MetricsCollector::new(100);
+        for _ in 0..30 {
+            collector.record_websocket_message(1);
+        }
+        collector.record_websocket_message(0);
+        
+        thread::sleep(Duration::from_millis(10));
+        
+        let rates = collector.shard_message_rates();
+        assert_eq!(rates.iter().map(|(shard, _)| *shard).collect::<Vec<_>>(), vec![0, 1]);
+        assert!(rates[1].1 > rates[0].1 && rates[0].1 > 0.0);
+        // 速率按上次读取以来的增量计算
+        assert!(collector.shard_message_rates().iter().all(|(_, rate)| *rate == 0.0));
+    }
+    
+    #[test]
+    fn test_scan_duration_split_by_scope() {
+        let collector = MetricsCollector::new(100);
+        collector.record_scan_duration(Duration::from_millis(20), false);
+        collector.record_scan_duration(Duration::from_millis(2), true);
+        collector.record_scan_duration(Duration::from_millis(3), true);
+        
+        let mut writer = PrometheusWriter::new();
+        collector.write_prometheus(&mut writer);
+        let text = writer.finish();
+        assert!(text.contains("pool_cache_scan_duration_seconds_count{scope=\"full\"} 1\n"));
+        assert!(text.contains("pool_cache_scan_duration_seconds_count{scope=\"scoped\"} 2\n"));
+    }
+    
+    #[test]
+    fn test_latency_budget_exported_per_stage() {
+        let collector = MetricsCollector::new(100);
+        // 时钟触发：没有 deserialize / cache_update / coordinator_queue
+        let breakdown = LatencyBreakdown { calculator_wait_us: 40, graph_scan_us: 3_000, ..Default::default() };
+        collector.record_latency_breakdown(&breakdown);
+        collector.record_detection_latency(Duration::from_millis(4), true);
+        
+        let mut writer = PrometheusWriter::new();
+        collector.write_prometheus(&mut writer);
+        let text = writer.finish();
+        assert!(text.contains("pool_cache_latency_stage_seconds_count{stage=\"graph_scan\"} 1\n"), "{}", text);
+        assert!(text.contains("pool_cache_latency_stage_seconds_count{stage=\"deserialize\"} 0\n"));
+        assert!(text.contains("pool_cache_detection_latency_seconds_bucket{trigger=\"event\",le=\"0.005\"} 1\n"));
+        assert!(text.contains("pool_cache_detection_latency_seconds_count{trigger=\"clock\"} 0\n"));
+    }
 }
 
 
@@ -1,71 +1,338 @@
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+/*!
+ * Mint 元数据缓存
+ *
+ * 按需通过 RPC 拉取 mint 账户并缓存：decimals、所属 token 程序，
+ * 以及 Token-2022 的 TransferFeeConfig。
+ *
+ * 路由器只认识交易对里的代币符号，所以 WebSocket 解析池子时把
+ * 符号 → mint 登记进来，路由计算时按符号查转账手续费（不触发 RPC）。
+ * 手续费参数可能按 epoch 变化：`observe_epoch` 在 epoch 切换时
+ * 返回带转账手续费的 mint，由调用方重新拉取。
+ *
+ * 📐 长尾 DEX（TesseraV / HumidiFi / GoonFi）的 decimals 通过 `resolve_decimals`
+ * 按 mint 查缓存，未缓存时退回猜测值；HumidiFi 池子账户不存 mint，
+ * 由 VaultReader 从 vault 代币账户登记 vault → mint。
+ */
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
 
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
+use tracing::warn;
 
+use crate::deserializers::spl_token::{self, TokenProgram, TransferFeeConfig};
 use crate::dex_interface::DexError;
+use crate::rpc_manager::RpcHandle;
+
+/// epoch 尚未观测到
+const EPOCH_UNKNOWN: u64 = u64::MAX;
+
+/// 缓存的 mint 元数据
+#[derive(Debug, Clone)]
+pub struct MintInfo {
+    pub decimals: u8,
+    #[allow(dead_code)]
+    pub program: TokenProgram,
+    pub transfer_fee: Option<TransferFeeConfig>,
+}
+
+/// 某个代币当前生效的转账手续费（UI 单位，供路由器的 f64 计算使用）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenTransferFee {
+    pub basis_points: u16,
+    /// 单笔手续费上限（UI 单位）
+    pub maximum_fee: f64,
+}
+
+impl TokenTransferFee {
+    /// 转账 amount 后接收方实际到账的数量
+    pub fn apply(&self, amount: f64) -> f64 {
+        if amount <= 0.0 {
+            return amount;
+        }
+        let fee = (amount * self.basis_points as f64 / 10_000.0).min(self.maximum_fee);
+        (amount - fee).max(0.0)
+    }
+}
 
-pub struct MintDecimalsCache {
-    rpc_client: Arc<RpcClient>,
-    cache: Arc<RwLock<HashMap<Pubkey, u8>>>,
+pub struct MintInfoCache {
+    rpc: RpcHandle,
+    cache: Arc<RwLock<HashMap<Pubkey, MintInfo>>>,
+    /// 交易对代币符号 → mint
+    token_mints: RwLock<HashMap<String, Pubkey>>,
+    /// 是否出现过带转账手续费的 mint（路由热路径的快速判断）
+    has_transfer_fees: AtomicBool,
+    current_epoch: AtomicU64,
+    /// vault 代币账户 → mint（从 vault 账户数据登记）
+    vault_mints: RwLock<HashMap<Pubkey, Pubkey>>,
+    /// 正在后台拉取的 mint（避免每次池子更新都重复发起）
+    pending: Mutex<HashSet<Pubkey>>,
 }
 
-impl MintDecimalsCache {
-    pub fn new(rpc_url: &str) -> Self {
-        let rpc_client = Arc::new(RpcClient::new(rpc_url.to_string()));
+impl MintInfoCache {
+    pub fn new(rpc: RpcHandle) -> Self {
         Self {
-            rpc_client,
+            rpc,
             cache: Arc::new(RwLock::new(HashMap::new())),
+            token_mints: RwLock::new(HashMap::new()),
+            has_transfer_fees: AtomicBool::new(false),
+            current_epoch: AtomicU64::new(EPOCH_UNKNOWN),
+            vault_mints: RwLock::new(HashMap::new()),
+            pending: Mutex::new(HashSet::new()),
         }
     }
 
+    /// 共享的 RPC 句柄（供其他按需读取的小缓存复用）
+    pub fn rpc(&self) -> &RpcHandle {
+        &self.rpc
+    }
+
     pub fn get_or_fetch_decimals(&self, mint: &Pubkey) -> Result<u8, DexError> {
-        {
-            let cache = self.cache.read().map_err(|e| {
-                DexError::DeserializationFailed(format!("Mint decimals cache poisoned: {}", e))
-            })?;
-            if let Some(dec) = cache.get(mint) {
-                return Ok(*dec);
-            }
+        self.get_or_fetch_info(mint).map(|info| info.decimals)
+    }
+
+    /// 读取 mint 元数据，未缓存时通过 RPC 拉取（阻塞，含限速等待）
+    pub fn get_or_fetch_info(&self, mint: &Pubkey) -> Result<MintInfo, DexError> {
+        if let Some(info) = self.cached_info(mint) {
+            return Ok(info);
         }
+        self.refresh(mint)
+    }
 
+    /// 只读缓存，不触发 RPC
+    pub fn cached_info(&self, mint: &Pubkey) -> Option<MintInfo> {
+        self.cache.read().ok()?.get(mint).cloned()
+    }
+
+    /// 重新拉取 mint 账户并覆盖缓存（epoch 切换后刷新手续费参数）
+    pub fn refresh(&self, mint: &Pubkey) -> Result<MintInfo, DexError> {
         let account_data = self
-            .rpc_client
-            .get_account_data(mint)
+            .rpc
+            .call_blocking(|client| client.get_account_data(mint).map_err(Box::new))
             .map_err(|e| DexError::DeserializationFailed(format!(
                 "Failed to fetch mint account {}: {}",
                 mint, e
             )))?;
 
-        if account_data.len() < 45 {
-            return Err(DexError::DeserializationFailed(
-                "Mint account data too short".to_string(),
-            ));
+        let parsed = spl_token::parse_mint(&account_data)?;
+        let info = MintInfo {
+            decimals: parsed.decimals,
+            program: parsed.program,
+            transfer_fee: parsed.transfer_fee_config,
+        };
+        self.insert_info(*mint, info.clone());
+        Ok(info)
+    }
+
+    /// 写入 mint 元数据（RPC 拉取结果或预先已知的数据）
+    pub fn insert_info(&self, mint: Pubkey, info: MintInfo) {
+        if info.transfer_fee.is_some() {
+            self.has_transfer_fees.store(true, Ordering::Relaxed);
         }
+        if let Ok(mut cache) = self.cache.write() {
+            cache.insert(mint, info);
+        }
+    }
 
-        let decimals = account_data[44];
+    /// 登记交易对代币符号对应的 mint
+    ///
+    /// 返回 true 表示该 mint 尚未缓存，调用方应在后台拉取
+    pub fn register_token_mint(&self, token: &str, mint: Pubkey) -> bool {
+        let changed = {
+            let mut token_mints = self.token_mints.write().unwrap();
+            token_mints.insert(token.to_string(), mint) != Some(mint)
+        };
+        changed && self.cached_info(&mint).is_none()
+    }
 
-        {
-            if let Ok(mut cache) = self.cache.write() {
-                cache.insert(*mint, decimals);
-            }
+    /// 登记 vault 代币账户所属的 mint
+    pub fn register_vault_mint(&self, vault: Pubkey, mint: Pubkey) {
+        if let Ok(mut vault_mints) = self.vault_mints.write() {
+            vault_mints.insert(vault, mint);
+        }
+    }
+
+    /// vault 代币账户所属的 mint（尚未收到 vault 数据时为 None）
+    pub fn vault_mint(&self, vault: &Pubkey) -> Option<Pubkey> {
+        self.vault_mints.read().ok()?.get(vault).copied()
+    }
+
+    /// 标记 mint 开始后台拉取；已缓存或已在拉取中返回 false
+    pub fn begin_fetch(&self, mint: &Pubkey) -> bool {
+        self.cached_info(mint).is_none() && self.pending.lock().unwrap().insert(*mint)
+    }
+
+    /// 后台拉取结束（无论成功与否，失败时下次更新会重新发起）
+    pub fn finish_fetch(&self, mint: &Pubkey) {
+        self.pending.lock().unwrap().remove(mint);
+    }
+
+    /// 代币当前 epoch 生效的转账手续费（只读缓存，未登记或无手续费返回 None）
+    pub fn transfer_fee_for_token(&self, token: &str) -> Option<TokenTransferFee> {
+        if !self.has_transfer_fees.load(Ordering::Relaxed) {
+            return None;
+        }
+        let mint = *self.token_mints.read().ok()?.get(token)?;
+        let info = self.cached_info(&mint)?;
+        let config = info.transfer_fee?;
+
+        let epoch = self.current_epoch.load(Ordering::Relaxed);
+        let fee = if epoch == EPOCH_UNKNOWN {
+            config.max_fee()
+        } else {
+            config.fee_for_epoch(epoch)
+        };
+        if fee.basis_points == 0 {
+            return None;
+        }
+        Some(TokenTransferFee {
+            basis_points: fee.basis_points,
+            maximum_fee: fee.maximum_fee as f64 / 10f64.powi(info.decimals as i32),
+        })
+    }
+
+    /// 记录当前 epoch
+    ///
+    /// epoch 变化时返回带转账手续费的 mint 列表，调用方应逐个 `refresh`
+    pub fn observe_epoch(&self, epoch: u64) -> Vec<Pubkey> {
+        let previous = self.current_epoch.swap(epoch, Ordering::Relaxed);
+        if previous == epoch {
+            return Vec::new();
         }
+        self.cache.read()
+            .map(|cache| cache.iter()
+                .filter(|(_, info)| info.transfer_fee.is_some())
+                .map(|(mint, _)| *mint)
+                .collect())
+            .unwrap_or_default()
+    }
+}
 
-        Ok(decimals)
+static GLOBAL_MINT_CACHE: OnceLock<Arc<MintInfoCache>> = OnceLock::new();
+
+pub fn init_global_mint_cache(rpc: RpcHandle) {
+    GLOBAL_MINT_CACHE.get_or_init(|| Arc::new(MintInfoCache::new(rpc)));
+}
+
+pub fn get_global_mint_cache() -> Option<Arc<MintInfoCache>> {
+    GLOBAL_MINT_CACHE.get().cloned()
+}
+
+/// 按代币符号查询当前生效的转账手续费（全局缓存未初始化时返回 None）
+pub fn transfer_fee_for_token(token: &str) -> Option<TokenTransferFee> {
+    GLOBAL_MINT_CACHE.get()?.transfer_fee_for_token(token)
+}
+
+/// 登记 vault → mint（全局缓存未初始化时忽略）
+pub fn register_vault_mint(vault: Pubkey, mint: Pubkey) {
+    if let Some(cache) = GLOBAL_MINT_CACHE.get() {
+        cache.register_vault_mint(vault, mint);
     }
 }
 
-static mut GLOBAL_MINT_CACHE: Option<Arc<MintDecimalsCache>> = None;
+/// 两个 vault 所属的 (base, quote) mint，任一侧未知时返回 None
+pub fn vault_mints(vault_a: &Pubkey, vault_b: &Pubkey) -> Option<(Pubkey, Pubkey)> {
+    let cache = GLOBAL_MINT_CACHE.get()?;
+    Some((cache.vault_mint(vault_a)?, cache.vault_mint(vault_b)?))
+}
 
-pub fn init_global_mint_cache(rpc_url: &str) {
-    unsafe {
-        if GLOBAL_MINT_CACHE.is_none() {
-            GLOBAL_MINT_CACHE = Some(Arc::new(MintDecimalsCache::new(rpc_url)));
+/// 📐 按 mint 解析池子的 (base, quote) decimals（只读缓存，不触发 RPC）
+///
+/// 没有 mint 或 mint 尚未缓存的一侧退回 `guess`，第二项为 true 表示仍含猜测值。
+/// 每个 mint（没有 mint 时每个 DEX）只在第一次退回猜测值时警告一次。
+pub fn resolve_decimals(dex: &str, mints: Option<(Pubkey, Pubkey)>, guess: (u8, u8)) -> ((u8, u8), bool) {
+    let cache = GLOBAL_MINT_CACHE.get();
+    let resolve = |mint: Option<Pubkey>, guess: u8| -> (u8, bool) {
+        let known = mint
+            .filter(|mint| *mint != Pubkey::default())
+            .and_then(|mint| cache?.cached_info(&mint));
+        match known {
+            Some(info) => (info.decimals, false),
+            None => {
+                warn_guessed(dex, mint, guess);
+                (guess, true)
+            }
         }
+    };
+    let (base, base_guessed) = resolve(mints.map(|(base, _)| base), guess.0);
+    let (quote, quote_guessed) = resolve(mints.map(|(_, quote)| quote), guess.1);
+    ((base, quote), base_guessed || quote_guessed)
+}
+
+fn warn_guessed(dex: &str, mint: Option<Pubkey>, guess: u8) {
+    static WARNED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    let key = match mint {
+        Some(mint) if mint != Pubkey::default() => mint.to_string(),
+        _ => dex.to_string(),
+    };
+    if WARNED.get_or_init(Default::default).lock().unwrap().insert(key.clone()) {
+        warn!("📐 {}: decimals of mint {} not cached yet, assuming {}", dex, key, guess);
     }
 }
 
-pub fn get_global_mint_cache() -> Option<Arc<MintDecimalsCache>> {
-    unsafe { GLOBAL_MINT_CACHE.clone() }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deserializers::spl_token::TransferFee;
+    use crate::rpc_manager::RpcManager;
+
+    fn offline_cache() -> MintInfoCache {
+        MintInfoCache::new(Arc::new(RpcManager::unlimited("http://127.0.0.1:8899")).handle("mint_cache"))
+    }
+
+    fn transfer_fee_config(basis_points: u16, maximum_fee: u64) -> TransferFeeConfig {
+        let fee = TransferFee { epoch: 0, maximum_fee, basis_points };
+        TransferFeeConfig {
+            withheld_amount: 0,
+            older_transfer_fee: fee,
+            newer_transfer_fee: fee,
+        }
+    }
+
+    #[test]
+    fn test_transfer_fee_lookup_by_token() {
+        let cache = offline_cache();
+        let fee_mint = Pubkey::new_unique();
+        let plain_mint = Pubkey::new_unique();
+
+        cache.insert_info(plain_mint, MintInfo { decimals: 6, program: TokenProgram::SplToken, transfer_fee: None });
+        assert!(!cache.register_token_mint("USDC", plain_mint));
+        assert!(cache.register_token_mint("FEE", fee_mint));
+        assert_eq!(cache.transfer_fee_for_token("FEE"), None);
+
+        // 1%，上限 5 个代币（6 位小数）
+        cache.insert_info(fee_mint, MintInfo {
+            decimals: 6,
+            program: TokenProgram::Token2022,
+            transfer_fee: Some(transfer_fee_config(100, 5_000_000)),
+        });
+        let fee = cache.transfer_fee_for_token("FEE").unwrap();
+        assert_eq!(fee.basis_points, 100);
+        assert!((fee.apply(100.0) - 99.0).abs() < 1e-9);
+        assert!((fee.apply(1_000.0) - 995.0).abs() < 1e-9);
+        assert_eq!(cache.transfer_fee_for_token("USDC"), None);
+        assert_eq!(cache.transfer_fee_for_token("UNKNOWN"), None);
+
+        // epoch 切换：只需刷新带手续费的 mint
+        assert_eq!(cache.observe_epoch(600), vec![fee_mint]);
+        assert!(cache.observe_epoch(600).is_empty());
+    }
+
+    #[test]
+    fn test_scheduled_fee_applies_from_its_epoch() {
+        let cache = offline_cache();
+        let mint = Pubkey::new_unique();
+        let mut config = transfer_fee_config(50, u64::MAX);
+        config.newer_transfer_fee = TransferFee { epoch: 700, maximum_fee: u64::MAX, basis_points: 200 };
+        cache.insert_info(mint, MintInfo { decimals: 9, program: TokenProgram::Token2022, transfer_fee: Some(config) });
+        cache.register_token_mint("SCHED", mint);
+
+        // epoch 未知时按较高费率
+        assert_eq!(cache.transfer_fee_for_token("SCHED").unwrap().basis_points, 200);
+        cache.observe_epoch(699);
+        assert_eq!(cache.transfer_fee_for_token("SCHED").unwrap().basis_points, 50);
+        cache.observe_epoch(700);
+        assert_eq!(cache.transfer_fee_for_token("SCHED").unwrap().basis_points, 200);
+    }
 }
@@ -0,0 +1,614 @@
+/*!
+ * 📣 机会通知
+ *
+ * 验证通过的机会推送到外部（不用盯日志）：
+ *
+ * - sink：通用 webhook（POST JSON 负载）和 Telegram 机器人（sendMessage）
+ * - 每个 sink 独立过滤（min_roi_percent / min_profit_usd / max_hops），
+ *   同一机会指纹（`ArbitragePath::fingerprint`，与机会生命周期表同一个键）在冷却时间内只推送一次
+ * - Calculator 经有界通道 `try_send` 交给后台分发任务，通道满时丢弃并计数，
+ *   webhook 延迟永远不会阻塞扫描
+ * - 投递失败按指数退避重试有限次数，结果按 (sink, outcome) 导出到 /metrics
+ */
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use dashmap::DashMap;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::config::{NotificationSinkConfig, NotificationSinkKind, NotificationsConfig};
+use crate::database::OpportunityContext;
+use crate::prometheus::{MetricKind, PrometheusWriter};
+use crate::router::ArbitragePath;
+use crate::scan_diff::EventKind;
+use crate::webhook;
+
+/// 推送的机会摘要（webhook 的 JSON 负载）
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub fingerprint: String,
+    pub signature: String,
+    /// 代币路线，如 "USDC → SOL → USDT → USDC"
+    pub route: String,
+    pub start_token: String,
+    pub hops: usize,
+    pub input_amount: f64,
+    pub net_profit: f64,
+    pub roi_percent: f64,
+    /// 上报前按最新缓存重新定价的 ROI
+    pub revalidated_roi_percent: Option<f64>,
+    /// 净利润的美元价值（没有新鲜 USD 价格时为 None）
+    pub profit_usd: Option<f64>,
+    pub confidence_score: Option<f64>,
+    pub trigger_source: String,
+    /// 🗃️ 相对上一次扫描的状态（new / persisting / improved / worsened）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scan_status: Option<EventKind>,
+    /// 发现时刻（Unix 毫秒）
+    pub detected_at_ms: i64,
+}
+
+impl Notification {
+    pub fn new(path: &ArbitragePath, context: &OpportunityContext) -> Self {
+        let route = std::iter::once(path.start_token.as_str())
+            .chain(path.steps.iter().map(|s| s.output_token.as_str()))
+            .collect::<Vec<_>>()
+            .join(" → ");
+        Self {
+            fingerprint: path.fingerprint(),
+            signature: path.signature().to_string(),
+            route,
+            start_token: path.start_token.clone(),
+            hops: path.steps.len(),
+            input_amount: path.input_amount,
+            net_profit: path.net_profit,
+            roi_percent: path.roi_percent,
+            revalidated_roi_percent: context.revalidated_roi_percent,
+            profit_usd: context.profit_usd,
+            confidence_score: context.confidence_score,
+            trigger_source: context.trigger_source.clone(),
+            scan_status: context.scan_status,
+            detected_at_ms: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+
+    /// 过滤使用的 ROI：有重新定价结果时用重新定价后的值
+    pub fn effective_roi(&self) -> f64 {
+        self.revalidated_roi_percent.unwrap_or(self.roi_percent)
+    }
+
+    /// Telegram 消息正文（纯文本）
+    fn text(&self) -> String {
+        let profit = match self.profit_usd {
+            Some(usd) => format!("+${:.2}", usd),
+            None => format!("+{:.4} {}", self.net_profit, self.start_token),
+        };
+        format!(
+            "💰 {:.3}% ROI ({})\n{}\n{} hops │ input {:.4} {} │ trigger {}\nfp {}",
+            self.effective_roi(), profit, self.route, self.hops,
+            self.input_amount, self.start_token, self.trigger_source, self.fingerprint
+        )
+    }
+}
+
+/// 单个 sink 的过滤条件
+#[derive(Debug, Clone, Default)]
+pub struct NotificationFilter {
+    pub min_roi_percent: f64,
+    /// 设置后，没有美元价格的机会不推送
+    pub min_profit_usd: Option<f64>,
+    pub max_hops: Option<usize>,
+}
+
+impl NotificationFilter {
+    pub fn matches(&self, notification: &Notification) -> bool {
+        if notification.effective_roi() < self.min_roi_percent {
+            return false;
+        }
+        if let Some(min_usd) = self.min_profit_usd {
+            if notification.profit_usd.is_none_or(|usd| usd < min_usd) {
+                return false;
+            }
+        }
+        self.max_hops.is_none_or(|max| notification.hops <= max)
+    }
+}
+
+/// 投递目标
+#[derive(Debug, Clone)]
+pub enum SinkTarget {
+    Webhook { url: String },
+    Telegram { api_url: String, bot_token: String, chat_id: String },
+}
+
+impl SinkTarget {
+    /// (POST 地址, JSON 负载)
+    fn request(&self, notification: &Notification) -> Result<(String, String)> {
+        match self {
+            Self::Webhook { url } => Ok((url.clone(), serde_json::to_string(notification)?)),
+            Self::Telegram { api_url, bot_token, chat_id } => {
+                let url = format!("{}/bot{}/sendMessage", api_url.trim_end_matches('/'), bot_token);
+                let body = serde_json::json!({
+                    "chat_id": chat_id,
+                    "text": notification.text(),
+                    "disable_web_page_preview": true,
+                });
+                Ok((url, body.to_string()))
+            }
+        }
+    }
+
+    /// 错误信息里可能带完整 URL（含 Telegram bot token），记录日志前打码
+    fn redact(&self, message: String) -> String {
+        match self {
+            Self::Telegram { bot_token, .. } => message.replace(bot_token.as_str(), "***"),
+            Self::Webhook { .. } => message,
+        }
+    }
+}
+
+/// 单个 sink：目标 + 过滤 + 按指纹冷却
+pub struct NotificationSink {
+    name: String,
+    target: SinkTarget,
+    filter: NotificationFilter,
+    cooldown: Duration,
+    /// 指纹 -> 上次推送时间
+    last_sent: HashMap<String, Instant>,
+}
+
+impl NotificationSink {
+    pub fn new(name: impl Into<String>, target: SinkTarget, filter: NotificationFilter, cooldown: Duration) -> Self {
+        Self {
+            name: name.into(),
+            target,
+            filter,
+            cooldown,
+            last_sent: HashMap::new(),
+        }
+    }
+
+    pub fn from_config(config: &NotificationSinkConfig) -> Result<Self> {
+        let non_empty = |value: &Option<String>| value.clone().filter(|v| !v.is_empty());
+        let target = match config.kind {
+            NotificationSinkKind::Webhook => match non_empty(&config.url) {
+                Some(url) => SinkTarget::Webhook { url },
+                None => bail!("Notification sink '{}' of type webhook requires a url", config.name),
+            },
+            NotificationSinkKind::Telegram => match (non_empty(&config.bot_token), non_empty(&config.chat_id)) {
+                (Some(bot_token), Some(chat_id)) => SinkTarget::Telegram {
+                    api_url: config.telegram_api_url.clone(),
+                    bot_token,
+                    chat_id,
+                },
+                _ => bail!("Notification sink '{}' of type telegram requires bot_token and chat_id", config.name),
+            },
+        };
+        let filter = NotificationFilter {
+            min_roi_percent: config.min_roi_percent,
+            min_profit_usd: config.min_profit_usd,
+            max_hops: config.max_hops,
+        };
+        Ok(Self::new(config.name.clone(), target, filter, Duration::from_secs(config.cooldown_secs)))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 过滤 + 冷却；接受时记录推送时间
+    fn accept(&mut self, notification: &Notification, now: Instant) -> Result<(), Outcome> {
+        if !self.filter.matches(notification) {
+            return Err(Outcome::Filtered);
+        }
+        self.last_sent.retain(|_, sent| now.duration_since(*sent) < self.cooldown);
+        if self.last_sent.contains_key(&notification.fingerprint) {
+            return Err(Outcome::RateLimited);
+        }
+        self.last_sent.insert(notification.fingerprint.clone(), now);
+        Ok(())
+    }
+}
+
+/// 重试策略：首次等待 `initial_backoff`，之后每次翻倍
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// 一条通知在某个 sink 上的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Delivered,
+    Failed,
+    Filtered,
+    RateLimited,
+}
+
+impl Outcome {
+    const ALL: [Outcome; 4] = [Outcome::Delivered, Outcome::Failed, Outcome::Filtered, Outcome::RateLimited];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Delivered => "delivered",
+            Self::Failed => "failed",
+            Self::Filtered => "filtered",
+            Self::RateLimited => "rate_limited",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct SinkCounters {
+    delivered: AtomicU64,
+    failed: AtomicU64,
+    filtered: AtomicU64,
+    rate_limited: AtomicU64,
+    retries: AtomicU64,
+}
+
+impl SinkCounters {
+    fn counter(&self, outcome: Outcome) -> &AtomicU64 {
+        match outcome {
+            Outcome::Delivered => &self.delivered,
+            Outcome::Failed => &self.failed,
+            Outcome::Filtered => &self.filtered,
+            Outcome::RateLimited => &self.rate_limited,
+        }
+    }
+}
+
+/// 通知计数（/metrics）
+#[derive(Debug, Default)]
+pub struct NotificationMetrics {
+    /// 通道满被丢弃的通知
+    dropped: AtomicU64,
+    sinks: DashMap<String, Arc<SinkCounters>>,
+}
+
+impl NotificationMetrics {
+    fn sink(&self, name: &str) -> Arc<SinkCounters> {
+        if let Some(counters) = self.sinks.get(name) {
+            return counters.clone();
+        }
+        self.sinks.entry(name.to_string()).or_default().clone()
+    }
+
+    fn record(&self, sink: &str, outcome: Outcome) {
+        self.sink(sink).counter(outcome).fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// (sink, outcome) 的计数，outcome 为 delivered / failed / filtered / rate_limited
+    pub fn count(&self, sink: &str, outcome: &str) -> u64 {
+        let Some(counters) = self.sinks.get(sink) else { return 0 };
+        Outcome::ALL.iter()
+            .find(|o| o.as_str() == outcome)
+            .map_or(0, |o| counters.counter(*o).load(Ordering::Relaxed))
+    }
+
+    pub fn retries(&self, sink: &str) -> u64 {
+        self.sinks.get(sink).map_or(0, |c| c.retries.load(Ordering::Relaxed))
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn write_prometheus(&self, writer: &mut PrometheusWriter) {
+        writer.family(
+            "pool_cache_notifications_dropped_total",
+            "Notifications dropped because the dispatcher queue was full",
+            MetricKind::Counter,
+        );
+        writer.sample("pool_cache_notifications_dropped_total", &[], self.dropped() as f64);
+
+        let mut sinks: Vec<(String, Arc<SinkCounters>)> = self.sinks.iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        if sinks.is_empty() {
+            return;
+        }
+        sinks.sort_by(|a, b| a.0.cmp(&b.0));
+
+        writer.family(
+            "pool_cache_notifications_total",
+            "Opportunity notifications by sink and outcome",
+            MetricKind::Counter,
+        );
+        for (sink, counters) in &sinks {
+            for outcome in Outcome::ALL {
+                writer.sample(
+                    "pool_cache_notifications_total",
+                    &[("sink", sink.as_str()), ("outcome", outcome.as_str())],
+                    counters.counter(outcome).load(Ordering::Relaxed) as f64,
+                );
+            }
+        }
+        writer.family(
+            "pool_cache_notification_retries_total",
+            "Notification delivery retries by sink",
+            MetricKind::Counter,
+        );
+        for (sink, counters) in &sinks {
+            writer.sample(
+                "pool_cache_notification_retries_total",
+                &[("sink", sink.as_str())],
+                counters.retries.load(Ordering::Relaxed) as f64,
+            );
+        }
+    }
+}
+
+/// Calculator 一侧的发送端（非阻塞）
+#[derive(Clone)]
+pub struct NotificationSender {
+    tx: mpsc::Sender<Notification>,
+    metrics: Arc<NotificationMetrics>,
+}
+
+impl NotificationSender {
+    /// 交给分发任务；通道满时丢弃并计数，返回是否入队
+    pub fn notify(&self, notification: Notification) -> bool {
+        match self.tx.try_send(notification) {
+            Ok(()) => true,
+            Err(mpsc::error::TrySendError::Full(notification)) => {
+                self.metrics.dropped.fetch_add(1, Ordering::Relaxed);
+                debug!("📣 Notification queue full, dropping {}", notification.fingerprint);
+                false
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => false,
+        }
+    }
+}
+
+/// 通知分发器：每个 sink 独立评估同一条机会流
+pub struct NotificationDispatcher {
+    sinks: Vec<NotificationSink>,
+    retry: RetryPolicy,
+    metrics: Arc<NotificationMetrics>,
+}
+
+impl NotificationDispatcher {
+    pub fn new(sinks: Vec<NotificationSink>, retry: RetryPolicy, metrics: Arc<NotificationMetrics>) -> Self {
+        Self { sinks, retry, metrics }
+    }
+
+    pub fn from_config(config: &NotificationsConfig, metrics: Arc<NotificationMetrics>) -> Result<Self> {
+        let sinks = config.sinks.iter()
+            .map(NotificationSink::from_config)
+            .collect::<Result<Vec<_>>>()?;
+        let retry = RetryPolicy {
+            max_retries: config.max_retries,
+            initial_backoff: Duration::from_millis(config.retry_backoff_ms),
+        };
+        Ok(Self::new(sinks, retry, metrics))
+    }
+
+    pub fn sink_names(&self) -> Vec<&str> {
+        self.sinks.iter().map(|s| s.name()).collect()
+    }
+
+    /// 评估所有 sink，返回需要投递的 (sink 名称, 目标)；过滤和冷却计入指标
+    fn route(&mut self, notification: &Notification, now: Instant) -> Vec<(String, SinkTarget)> {
+        let mut targets = Vec::new();
+        for sink in &mut self.sinks {
+            match sink.accept(notification, now) {
+                Ok(()) => targets.push((sink.name.clone(), sink.target.clone())),
+                Err(outcome) => self.metrics.record(&sink.name, outcome),
+            }
+        }
+        targets
+    }
+
+    /// 启动后台分发任务；所有发送端被丢弃后任务退出
+    pub fn spawn(mut self, queue_capacity: usize) -> (NotificationSender, JoinHandle<()>) {
+        let (tx, mut rx) = mpsc::channel::<Notification>(queue_capacity.max(1));
+        let sender = NotificationSender { tx, metrics: self.metrics.clone() };
+
+        let handle = tokio::spawn(async move {
+            while let Some(notification) = rx.recv().await {
+                let notification = Arc::new(notification);
+                for (sink, target) in self.route(&notification, Instant::now()) {
+                    // 每次投递独立任务：一个慢 sink 不拖住其他 sink 和后续通知
+                    tokio::spawn(deliver(sink, target, notification.clone(), self.retry, self.metrics.clone()));
+                }
+            }
+            info!("📣 Notification dispatcher stopped");
+        });
+        (sender, handle)
+    }
+}
+
+/// 投递一条通知，失败按指数退避重试
+async fn deliver(
+    sink: String,
+    target: SinkTarget,
+    notification: Arc<Notification>,
+    retry: RetryPolicy,
+    metrics: Arc<NotificationMetrics>,
+) {
+    let (url, body) = match target.request(&notification) {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("📣 Failed to build notification for sink {}: {}", sink, e);
+            metrics.record(&sink, Outcome::Failed);
+            return;
+        }
+    };
+
+    let mut backoff = retry.initial_backoff;
+    for attempt in 0..=retry.max_retries {
+        match webhook::post_json(&url, &body).await {
+            Ok(_) => {
+                debug!("📣 Notified {} about {}", sink, notification.fingerprint);
+                metrics.record(&sink, Outcome::Delivered);
+                return;
+            }
+            Err(e) if attempt < retry.max_retries => {
+                let error = target.redact(format!("{:#}", e));
+                debug!("📣 Notification to {} failed (attempt {}), retrying in {:?}: {}", sink, attempt + 1, backoff, error);
+                metrics.sink(&sink).retries.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+            Err(e) => {
+                let error = target.redact(format!("{:#}", e));
+                warn!("📣 Notification to {} failed after {} attempts: {}", sink, attempt + 1, error);
+                metrics.record(&sink, Outcome::Failed);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn notification(fingerprint: &str, roi_percent: f64, profit_usd: Option<f64>, hops: usize) -> Notification {
+        Notification {
+            fingerprint: fingerprint.to_string(),
+            signature: "a->b->c".to_string(),
+            route: "USDC → SOL → USDT → USDC".to_string(),
+            start_token: "USDC".to_string(),
+            hops,
+            input_amount: 1000.0,
+            net_profit: roi_percent * 10.0,
+            roi_percent,
+            revalidated_roi_percent: None,
+            profit_usd,
+            confidence_score: Some(0.9),
+            trigger_source: "test".to_string(),
+            scan_status: None,
+            detected_at_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_per_sink_filters_and_fingerprint_cooldown() {
+        let metrics = Arc::new(NotificationMetrics::default());
+        let webhook = SinkTarget::Webhook { url: "http://127.0.0.1:9/hook".to_string() };
+        let strict = NotificationFilter { min_roi_percent: 0.5, min_profit_usd: Some(5.0), max_hops: Some(3) };
+        let mut dispatcher = NotificationDispatcher::new(
+            vec![
+                NotificationSink::new("all", webhook.clone(), NotificationFilter::default(), Duration::from_secs(600)),
+                NotificationSink::new("strict", webhook, strict, Duration::from_secs(600)),
+            ],
+            RetryPolicy::default(),
+            metrics.clone(),
+        );
+        let now = Instant::now();
+        let routed = |dispatcher: &mut NotificationDispatcher, n: &Notification, at: Instant| -> Vec<String> {
+            dispatcher.route(n, at).into_iter().map(|(sink, _)| sink).collect()
+        };
+
+        assert_eq!(routed(&mut dispatcher, &notification("fp1", 0.8, Some(12.0), 3), now), vec!["all", "strict"]);
+        // 低 ROI / 没有美元价格 / 跳数太多：只有 "all" 收到
+        assert_eq!(routed(&mut dispatcher, &notification("fp2", 0.2, Some(12.0), 3), now), vec!["all"]);
+        assert_eq!(routed(&mut dispatcher, &notification("fp3", 0.8, None, 3), now), vec!["all"]);
+        assert_eq!(routed(&mut dispatcher, &notification("fp4", 0.8, Some(12.0), 4), now), vec!["all"]);
+
+        // 同一指纹冷却期内不再推送，过期后重新推送
+        assert!(routed(&mut dispatcher, &notification("fp1", 0.9, Some(15.0), 3), now + Duration::from_secs(60)).is_empty());
+        assert_eq!(
+            routed(&mut dispatcher, &notification("fp1", 0.9, Some(15.0), 3), now + Duration::from_secs(601)),
+            vec!["all", "strict"]
+        );
+
+        assert_eq!(metrics.count("strict", "filtered"), 3);
+        assert_eq!(metrics.count("all", "rate_limited"), 1);
+        assert_eq!(metrics.count("strict", "rate_limited"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_delivery_retries_with_backoff_and_formats_telegram() {
+        // 每个连接读一个请求：/hook 第一次返回 500，其余返回 200
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (requests_tx, mut requests) = mpsc::unbounded_channel::<String>();
+        tokio::spawn(async move {
+            let mut hook_attempts = 0;
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buffer = vec![0u8; 8192];
+                let n = socket.read(&mut buffer).await.unwrap();
+                let request = String::from_utf8_lossy(&buffer[..n]).into_owned();
+                let status = if request.starts_with("POST /hook ") {
+                    hook_attempts += 1;
+                    if hook_attempts == 1 { "500 Internal Server Error" } else { "200 OK" }
+                } else {
+                    "200 OK"
+                };
+                socket.write_all(format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status).as_bytes()).await.unwrap();
+                requests_tx.send(request).unwrap();
+            }
+        });
+
+        let metrics = Arc::new(NotificationMetrics::default());
+        let sinks = vec![
+            NotificationSink::new(
+                "ops",
+                SinkTarget::Webhook { url: format!("http://{}/hook", addr) },
+                NotificationFilter::default(),
+                Duration::from_secs(600),
+            ),
+            NotificationSink::new(
+                "chat",
+                SinkTarget::Telegram {
+                    api_url: format!("http://{}/", addr),
+                    bot_token: "123:abc".to_string(),
+                    chat_id: "-100".to_string(),
+                },
+                NotificationFilter::default(),
+                Duration::from_secs(600),
+            ),
+        ];
+        let retry = RetryPolicy { max_retries: 2, initial_backoff: Duration::from_millis(10) };
+        let (sender, _handle) = NotificationDispatcher::new(sinks, retry, metrics.clone()).spawn(8);
+        assert!(sender.notify(notification("fp-delivery", 0.8, Some(12.0), 3)));
+
+        let mut received = Vec::new();
+        for _ in 0..3 {
+            let request = tokio::time::timeout(Duration::from_secs(5), requests.recv()).await.unwrap().unwrap();
+            received.push(request);
+        }
+        let telegram = received.iter().find(|r| r.starts_with("POST /bot123:abc/sendMessage ")).unwrap();
+        assert!(telegram.contains(r#""chat_id":"-100""#));
+        assert!(telegram.contains("USDC → SOL → USDT → USDC"));
+        assert_eq!(received.iter().filter(|r| r.starts_with("POST /hook ")).count(), 2);
+        assert!(received.iter().any(|r| r.contains(r#""fingerprint":"fp-delivery""#)));
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while metrics.count("ops", "delivered") + metrics.count("chat", "delivered") < 2 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(metrics.retries("ops"), 1);
+        assert_eq!(metrics.count("ops", "failed"), 0);
+
+        let mut writer = PrometheusWriter::new();
+        metrics.write_prometheus(&mut writer);
+        let text = writer.finish();
+        assert!(text.contains(r#"pool_cache_notifications_total{sink="ops",outcome="delivered"} 1"#));
+        assert!(text.contains(r#"pool_cache_notification_retries_total{sink="ops"} 1"#));
+    }
+}
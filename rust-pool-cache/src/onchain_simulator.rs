@@ -9,15 +9,24 @@
  */
 
 use anyhow::{anyhow, Result};
-use solana_client::rpc_client::RpcClient;
-use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use serde::Serialize;
+use solana_client::rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig};
+use solana_sdk::{account::Account, commitment_config::CommitmentConfig, pubkey::Pubkey};
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Instant;
 use tracing::{debug, warn, info};
 
 use crate::arbitrage::ArbitrageOpportunity;
+use crate::backpressure::BackpressureMonitor;
+use crate::calibration::Calibrator;
+use crate::config::SimulationConfig;
 use crate::pool_factory::PoolFactory;
+use crate::price_cache::PriceCache;
+use crate::router::{ArbitragePath, PathSignature};
+use crate::rpc_manager::{RpcHandle, RpcManager};
+use crate::tx_builder::{self, PlannedHop, TransactionBuilder};
 
 /// 模拟结果
 #[derive(Debug, Clone)]
@@ -38,6 +47,61 @@ pub struct SimulationResult {
     pub still_profitable: bool,
 }
 
+/// 交易级模拟中单跳的代币变化（原始单位）
+#[derive(Debug, Clone, Serialize)]
+pub struct HopDelta {
+    pub pool_id: String,
+    pub input_mint: String,
+    pub output_mint: String,
+    pub amount_in: u64,
+    pub min_amount_out: u64,
+    /// 由 payer 余额变化反推；无法区分时为 None
+    pub amount_out: Option<u64>,
+    /// 输出是中间代币时该 mint 的余额净变化（实际输出超出下一跳输入的剩余）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leftover: Option<i128>,
+}
+
+/// 交易级模拟报告（simulateTransaction）
+#[derive(Debug, Clone)]
+pub struct TransactionSimulation {
+    pub compute_units: Option<u64>,
+    pub hops: Vec<HopDelta>,
+    /// 第一跳输入（起始代币原始单位）
+    pub input_amount: u64,
+    /// 起始代币余额变化（最终输出 - 输入）
+    pub net_delta: Option<i128>,
+    /// 中间代币的余额变化（后续跳按最小输出定额输入，多出的部分留在这些 mint 上）
+    pub leftover_deltas: Vec<(Pubkey, i128)>,
+    /// 交易成功，且按每跳实际兑换率连乘的回报超过输入
+    ///
+    /// 后续跳只花上一跳的最小输出，起始代币的 net_delta 会少算留在中间代币的部分，
+    /// 所以用兑换率判断；兑换率无法反推时退回 net_delta > 0。
+    pub beat_input: bool,
+    /// 交易错误（含最后一条程序日志）
+    pub error: Option<String>,
+    pub simulation_latency_ms: u64,
+}
+
+/// 交易级模拟结果
+#[derive(Debug, Clone)]
+pub enum TransactionSimulationOutcome {
+    Simulated(TransactionSimulation),
+    /// 路径无法构建交易（不支持的 DEX、账户读取失败等），不是 panic
+    CannotSimulate { reason: String },
+}
+
+impl TransactionSimulationOutcome {
+    /// simulated | reverted | unsupported（日志和数据库使用）
+    pub fn status(&self) -> &'static str {
+        match self {
+            TransactionSimulationOutcome::Simulated(sim) if sim.error.is_none() => "simulated",
+            TransactionSimulationOutcome::Simulated(_) => "reverted",
+            TransactionSimulationOutcome::CannotSimulate { .. } => "unsupported",
+        }
+    }
+}
+
 /// 链上模拟器配置
 #[derive(Debug, Clone)]
 pub struct SimulatorConfig {
@@ -47,6 +111,8 @@ pub struct SimulatorConfig {
     pub timeout_ms: u64,
     /// 最大并发模拟数
     pub max_concurrent: usize,
+    /// 期望值下限（%）：接入校准器后，用 校准概率 × 理论利润 ≥ 下限 替代置信度阈值
+    pub min_expected_value_pct: f64,
 }
 
 impl Default for SimulatorConfig {
@@ -55,40 +121,111 @@ impl Default for SimulatorConfig {
             min_confidence_for_simulation: 80.0,  // 只模拟>80分的机会
             timeout_ms: 500,
             max_concurrent: 10,
+            min_expected_value_pct: 0.1,
+        }
+    }
+}
+
+impl From<&SimulationConfig> for SimulatorConfig {
+    fn from(config: &SimulationConfig) -> Self {
+        Self {
+            min_confidence_for_simulation: config.min_confidence_for_simulation,
+            timeout_ms: config.simulation_timeout_ms,
+            max_concurrent: config.max_concurrent_simulations,
+            min_expected_value_pct: config.min_expected_value_pct,
         }
     }
 }
 
 /// 链上模拟器
 /// 
-/// 注意：这是"虚拟模拟"而非交易模拟
+/// 注意：verify_* 是"虚拟模拟"而非交易模拟；配置 payer 后 simulate_path 构建真实交易
 /// 通过重新读取池子账户状态来验证价格，而非模拟真实交易
 /// 优势：
 /// - 不需要构建复杂的DEX交易指令
 /// - 适用于所有DEX类型
 /// - 延迟低（只需getAccountInfo）
 pub struct OnChainSimulator {
-    rpc_client: Arc<RpcClient>,
+    rpc: RpcHandle,
     config: SimulatorConfig,
+    /// 反压监视器（向 Calculator 暴露排队/在途负载）
+    backpressure: Option<Arc<BackpressureMonitor>>,
+    /// 置信度校准器（门控 + 记录复核结果）
+    calibrator: Option<Arc<Calibrator>>,
+    /// 交易构建器（配置 payer 后启用 simulate_path）
+    tx_builder: Option<Arc<TransactionBuilder>>,
 }
 
 impl OnChainSimulator {
-    /// 创建新的模拟器
-    pub fn new(rpc_url: String, config: SimulatorConfig) -> Self {
-        let rpc_client = Arc::new(RpcClient::new_with_commitment(
-            rpc_url,
-            CommitmentConfig::confirmed(),
-        ));
-        
+    /// 创建新的模拟器（RPC 经共享句柄限速）
+    pub fn new(rpc: RpcHandle, config: SimulatorConfig) -> Self {
         Self {
-            rpc_client,
+            rpc,
             config,
+            backpressure: None,
+            calibrator: None,
+            tx_builder: None,
+        }
+    }
+    
+    /// 🔥 接入反压监视器（并发上限 = max_concurrent）
+    pub fn with_backpressure(mut self, monitor: Arc<BackpressureMonitor>) -> Self {
+        monitor.set_in_flight_limit(self.config.max_concurrent);
+        self.backpressure = Some(monitor);
+        self
+    }
+    
+    /// 🎯 接入置信度校准器：门控改为期望值，复核结果回流为校准样本
+    pub fn with_calibrator(mut self, calibrator: Arc<Calibrator>) -> Self {
+        self.calibrator = Some(calibrator);
+        self
+    }
+    
+    /// 🧪 接入交易构建器：simulate_path 用该 payer 构建真实 swap 交易
+    pub fn with_transaction_payer(mut self, payer: Pubkey) -> Self {
+        self.tx_builder = Some(Arc::new(TransactionBuilder::new(self.rpc.clone(), payer)));
+        self
+    }
+    
+    /// 是否值得模拟
+    fn should_simulate(&self, opportunity: &ArbitrageOpportunity, confidence_score: f64) -> bool {
+        match &self.calibrator {
+            Some(calibrator) => {
+                let simulate = calibrator.should_simulate(
+                    confidence_score,
+                    opportunity.estimated_profit_pct,
+                    self.config.min_expected_value_pct,
+                    self.config.min_confidence_for_simulation,
+                );
+                if !simulate {
+                    debug!(
+                        "Skipping simulation: EV {:.4}% < {:.4}% (confidence {:.1}, p={:.3}, calibrated={})",
+                        calibrator.expected_value(confidence_score, opportunity.estimated_profit_pct),
+                        self.config.min_expected_value_pct,
+                        confidence_score,
+                        calibrator.probability(confidence_score),
+                        calibrator.is_calibrated(confidence_score)
+                    );
+                }
+                simulate
+            }
+            None => {
+                if confidence_score < self.config.min_confidence_for_simulation {
+                    debug!(
+                        "Skipping simulation for low confidence opportunity ({:.1}%)",
+                        confidence_score
+                    );
+                    return false;
+                }
+                true
+            }
         }
     }
     
     /// 使用默认配置创建
     pub fn with_defaults(rpc_url: String) -> Self {
-        Self::new(rpc_url, SimulatorConfig::default())
+        let rpc = Arc::new(RpcManager::unlimited(&rpc_url)).handle("simulator");
+        Self::new(rpc, SimulatorConfig::default())
     }
     
     /// 验证套利机会是否仍然有效
@@ -106,12 +243,8 @@ impl OnChainSimulator {
         opportunity: &ArbitrageOpportunity,
         confidence_score: f64,
     ) -> Option<SimulationResult> {
-        // 🎯 智能过滤：低置信度不模拟
-        if confidence_score < self.config.min_confidence_for_simulation {
-            debug!(
-                "Skipping simulation for low confidence opportunity ({:.1}%)",
-                confidence_score
-            );
+        // 🎯 智能过滤：低置信度 / 低期望值不模拟
+        if !self.should_simulate(opportunity, confidence_score) {
             return None;
         }
         
@@ -141,6 +274,11 @@ impl OnChainSimulator {
                 
                 let verified_slot = slot_a.max(slot_b);
                 
+                // 复核结果即校准样本
+                if let Some(calibrator) = &self.calibrator {
+                    calibrator.record_outcome(confidence_score, still_profitable);
+                }
+                
                 if still_profitable {
                     info!(
                         "✅ Simulation passed: {} profit={:.2}% (cached={:.2}%) latency={}ms slot={}",
@@ -186,8 +324,9 @@ impl OnChainSimulator {
             .map_err(|e| anyhow!("Invalid pubkey: {}", e))?;
         
         // 获取账户信息（包含slot）
-        let response = self.rpc_client
-            .get_account_with_commitment(&pubkey, CommitmentConfig::confirmed())
+        let response = self.rpc
+            .call(move |client| client.get_account_with_commitment(&pubkey, CommitmentConfig::confirmed()).map_err(Box::new))
+            .await
             .map_err(|e| anyhow!("RPC error: {}", e))?;
         
         let account = response.value
@@ -205,6 +344,49 @@ impl OnChainSimulator {
         Ok((price, slot))
     }
     
+    /// 🧪 为路径构建真实 swap 交易并 simulateTransaction
+    /// 
+    /// 每跳的代币变化由 payer ATA 模拟前后的余额反推。未配置 payer、路径包含不支持的
+    /// DEX 或账户读取失败时返回 CannotSimulate。
+    pub async fn simulate_path(&self, path: ArbitragePath, cache: Arc<PriceCache>) -> TransactionSimulationOutcome {
+        let builder = match &self.tx_builder {
+            Some(builder) => builder.clone(),
+            None => return TransactionSimulationOutcome::CannotSimulate {
+                reason: "no payer configured".to_string(),
+            },
+        };
+        let rpc = self.rpc.clone();
+        let signature = path.signature().to_string();
+        // ⏳ 模拟期间路由器跳过该路径（返回时清除标记）
+        let _pending = self.backpressure.as_ref().map(|monitor| monitor.mark_pending([path.signature().key()]));
+        
+        // 构建和模拟都是阻塞 RPC
+        let outcome = tokio::task::spawn_blocking(move || {
+            simulate_path_blocking(&rpc, &builder, &path, &cache)
+        }).await.unwrap_or_else(|e| TransactionSimulationOutcome::CannotSimulate {
+            reason: format!("simulation task failed: {}", e),
+        });
+        
+        match &outcome {
+            TransactionSimulationOutcome::Simulated(sim) if sim.beat_input => info!(
+                "🧪 Transaction simulation {}: {:+} raw, leftovers {:?}, {} CU, {}ms",
+                signature,
+                sim.net_delta.unwrap_or_default(),
+                sim.leftover_deltas,
+                sim.compute_units.unwrap_or_default(),
+                sim.simulation_latency_ms
+            ),
+            TransactionSimulationOutcome::Simulated(sim) => warn!(
+                "🧪 Transaction simulation {} did not beat input: delta={:?}, CU={:?}, error={:?}",
+                signature, sim.net_delta, sim.compute_units, sim.error
+            ),
+            TransactionSimulationOutcome::CannotSimulate { reason } => {
+                debug!("🧪 Cannot simulate {}: {}", signature, reason);
+            }
+        }
+        outcome
+    }
+    
     /// 批量验证多个机会（并发）
     /// 
     /// # Arguments
@@ -221,12 +403,26 @@ impl OnChainSimulator {
         let semaphore = Arc::new(Semaphore::new(self.config.max_concurrent));
         let mut tasks = Vec::new();
         
+        // 🔥 反压：整批进入排队
+        if let Some(monitor) = &self.backpressure {
+            monitor.enqueue(opportunities.len());
+        }
+        
         for (opp, confidence) in opportunities {
             let sem = semaphore.clone();
             let simulator = self.clone();
+            // ⏳ 入队即标记等待模拟，任务结束时清除
+            let pending = self.backpressure.as_ref().map(|monitor| monitor.mark_pending(opportunity_path_keys(&opp)));
             
             let task = tokio::spawn(async move {
-                let _permit = sem.acquire().await.ok()?;
+                let _pending = pending;
+                let permit = sem.acquire().await;
+                // 出队 -> 在途（guard drop 时自动减少）
+                let _in_flight = simulator.backpressure.as_ref().map(|monitor| {
+                    monitor.dequeue(1);
+                    monitor.begin_simulation()
+                });
+                let _permit = permit.ok()?;
                 let result = simulator.verify_opportunity(&opp, confidence).await?;
                 Some((opp, result))
             });
@@ -246,11 +442,148 @@ impl OnChainSimulator {
     }
 }
 
+/// 两池机会对应的路径签名键（路由器的直接套利路径可能是任一方向）
+fn opportunity_path_keys(opportunity: &ArbitrageOpportunity) -> [u64; 2] {
+    let (a, b) = (&opportunity.pool_a_id, &opportunity.pool_b_id);
+    [
+        PathSignature::key_of(&format!("{}->{}", a, b)),
+        PathSignature::key_of(&format!("{}->{}", b, a)),
+    ]
+}
+
+fn simulate_path_blocking(
+    rpc: &RpcHandle,
+    builder: &TransactionBuilder,
+    path: &ArbitragePath,
+    cache: &PriceCache,
+) -> TransactionSimulationOutcome {
+    let cannot_simulate = |reason: String| TransactionSimulationOutcome::CannotSimulate { reason };
+    
+    let built = match builder.build(path, cache) {
+        Ok(built) => built,
+        Err(e) => return cannot_simulate(e.to_string()),
+    };
+    let start = Instant::now();
+    
+    // 模拟前余额（ATA 不存在视为 0）
+    let addresses: Vec<Pubkey> = built.token_accounts.iter().map(|(_, ata)| *ata).collect();
+    let pre_balances = match rpc.call_blocking(|client| client.get_multiple_accounts(&addresses).map_err(Box::new)) {
+        Ok(accounts) => accounts.iter()
+            .map(|account| account.as_ref().map(|a| token_account_amount(&a.data)).unwrap_or(0))
+            .collect::<Vec<_>>(),
+        Err(e) => return cannot_simulate(format!("failed to fetch token balances: {}", e)),
+    };
+    
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        commitment: Some(CommitmentConfig::confirmed()),
+        accounts: Some(RpcSimulateTransactionAccountsConfig {
+            encoding: None,  // 服务端默认 base64
+            addresses: addresses.iter().map(|a| a.to_string()).collect(),
+        }),
+        ..Default::default()
+    };
+    let result = match rpc.call_blocking(|client| client.simulate_transaction_with_config(&built.transaction, config.clone()).map_err(Box::new)) {
+        Ok(response) => response.value,
+        Err(e) => return cannot_simulate(format!("simulateTransaction failed: {}", e)),
+    };
+    
+    // 模拟后余额
+    let post_balances: Vec<Option<u64>> = match &result.accounts {
+        Some(accounts) => accounts.iter()
+            .map(|account| {
+                account.as_ref()
+                    .and_then(|ui| ui.decode::<Account>())
+                    .map(|a| token_account_amount(&a.data))
+            })
+            .collect(),
+        None => vec![None; addresses.len()],
+    };
+    let deltas: HashMap<Pubkey, i128> = built.token_accounts.iter()
+        .zip(pre_balances.iter().zip(&post_balances))
+        .filter_map(|((mint, _), (pre, post))| post.map(|post| (*mint, post as i128 - *pre as i128)))
+        .collect();
+    
+    let error = result.err.as_ref().map(|err| {
+        match result.logs.as_ref().and_then(|logs| logs.last()) {
+            Some(last_log) => format!("{} ({})", err, last_log),
+            None => err.to_string(),
+        }
+    });
+    let amounts_out = if error.is_none() {
+        tx_builder::hop_outputs(&built.hops, |mint| deltas.get(mint).copied())
+    } else {
+        vec![None; built.hops.len()]
+    };
+    let realized_return = realized_return(&built.hops, &amounts_out);
+    let leftover_deltas: Vec<(Pubkey, i128)> = if error.is_none() {
+        built.token_accounts.iter()
+            .filter(|(mint, _)| *mint != built.start_mint)
+            .filter_map(|(mint, _)| deltas.get(mint).map(|delta| (*mint, *delta)))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let hops = built.hops.iter()
+        .zip(amounts_out)
+        .map(|(hop, amount_out)| HopDelta {
+            pool_id: hop.pool_id.clone(),
+            input_mint: hop.input_mint.to_string(),
+            output_mint: hop.output_mint.to_string(),
+            amount_in: hop.amount_in,
+            min_amount_out: hop.min_amount_out,
+            amount_out,
+            leftover: leftover_deltas.iter()
+                .find(|(mint, _)| *mint == hop.output_mint)
+                .map(|(_, delta)| *delta),
+        })
+        .collect();
+    let net_delta = if error.is_none() { deltas.get(&built.start_mint).copied() } else { None };
+    let beat_input = error.is_none() && match realized_return {
+        Some(ratio) => ratio > 1.0,
+        None => net_delta.is_some_and(|delta| delta > 0),
+    };
+    
+    TransactionSimulationOutcome::Simulated(TransactionSimulation {
+        compute_units: result.units_consumed,
+        hops,
+        input_amount: built.hops.first().map(|hop| hop.amount_in).unwrap_or(0),
+        net_delta,
+        leftover_deltas,
+        beat_input,
+        error,
+        simulation_latency_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+/// 每跳实际兑换率（out / in，原始单位）连乘；任一跳输出无法反推时为 None
+fn realized_return(hops: &[PlannedHop], amounts_out: &[Option<u64>]) -> Option<f64> {
+    if hops.is_empty() {
+        return None;
+    }
+    hops.iter().zip(amounts_out).try_fold(1.0, |ratio, (hop, amount_out)| {
+        let out = (*amount_out)?;
+        (hop.amount_in > 0).then(|| ratio * out as f64 / hop.amount_in as f64)
+    })
+}
+
+/// SPL Token 账户的 amount 字段（offset 64）
+fn token_account_amount(data: &[u8]) -> u64 {
+    data.get(64..72)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .unwrap_or(0)
+}
+
 impl Clone for OnChainSimulator {
     fn clone(&self) -> Self {
         Self {
-            rpc_client: Arc::clone(&self.rpc_client),
+            rpc: self.rpc.clone(),
             config: self.config.clone(),
+            backpressure: self.backpressure.clone(),
+            calibrator: self.calibrator.clone(),
+            tx_builder: self.tx_builder.clone(),
         }
     }
 }
@@ -264,6 +597,98 @@ mod tests {
         let config = SimulatorConfig::default();
         assert_eq!(config.min_confidence_for_simulation, 80.0);
         assert_eq!(config.max_concurrent, 10);
+        assert_eq!(config.min_expected_value_pct, 0.1);
     }
-}
+    
+    #[tokio::test]
+    async fn test_unsupported_dex_cannot_simulate() {
+        let simulator = OnChainSimulator::with_defaults("http://127.0.0.1:8899".to_string())
+            .with_transaction_payer(Pubkey::new_unique());
+        let step = crate::router::RouteStep {
+            pool_id: Pubkey::new_unique().to_string(),
+            dex_name: "Phoenix (CLOB)".to_string(),
+            input_token: "SOL".to_string(),
+            output_token: "USDC".to_string(),
+            price: 185.0,
+            liquidity_base: 0,
+            liquidity_quote: 0,
+            expected_input: 1.0,
+            expected_output: 185.0,
+            price_impact_percent: 0.0,
+            effective_fee_bps: 0.0,
+            raw_input_token: None,
+            raw_output_token: None,
+        };
+        let path = ArbitragePath {
+            arb_type: crate::router::ArbitrageType::Direct,
+            steps: vec![step],
+            start_token: "SOL".to_string(),
+            end_token: "SOL".to_string(),
+            input_amount: 1.0,
+            output_amount: 1.01,
+            gross_profit: 0.01,
+            dex_fees: 0.0,
+            execution_fees: 0.0,
+            estimated_fees: 0.0,
+            net_profit: 0.01,
+            roi_percent: 1.0,
+            discovered_at: Instant::now(),
+        };
+        
+        // 不支持的 DEX 在任何 RPC 之前就返回 CannotSimulate
+        let outcome = simulator.simulate_path(path, Arc::new(PriceCache::new())).await;
+        match outcome {
+            TransactionSimulationOutcome::CannotSimulate { reason } => assert!(reason.contains("Phoenix")),
+            other => panic!("expected CannotSimulate, got {:?}", other),
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_verify_batch_clears_pending_markers_when_done() {
+        use crate::backpressure::BackpressurePolicy;
+
+        let monitor = Arc::new(BackpressureMonitor::new(BackpressurePolicy::default()));
+        let simulator = OnChainSimulator::with_defaults("http://127.0.0.1:9".to_string())
+            .with_backpressure(monitor.clone());
+        let opportunity = ArbitrageOpportunity {
+            pool_a_id: "pool_a".to_string(),
+            pool_a_dex: "Raydium".to_string(),
+            pool_a_price: 180.0,
+            pool_b_id: "pool_b".to_string(),
+            pool_b_dex: "Orca".to_string(),
+            pool_b_price: 181.0,
+            pair: "SOL/USDC".to_string(),
+            price_diff_pct: 0.55,
+            estimated_profit_pct: 0.05,
+            detected_at: Instant::now(),
+        };
+
+        // 两个方向的直接套利路径签名都对应这个机会
+        let pending = monitor.mark_pending(opportunity_path_keys(&opportunity));
+        assert!(monitor.is_pending(PathSignature::key_of("pool_a->pool_b")));
+        assert!(monitor.is_pending(PathSignature::key_of("pool_b->pool_a")));
+        drop(pending);
 
+        // 低置信度机会不模拟，任务结束后标记清除
+        assert!(simulator.verify_batch(vec![(opportunity, 10.0)]).await.is_empty());
+        assert_eq!(monitor.pending_count(), 0);
+        assert_eq!(monitor.queue_depth(), 0);
+    }
+    
+    #[test]
+    fn test_realized_return_ignores_leftovers_in_intermediate_mints() {
+        let sol = Pubkey::new_unique();
+        let usdc = Pubkey::new_unique();
+        let hop = |input_mint, output_mint, amount_in, min_amount_out| PlannedHop {
+            pool_id: "p".into(), input_mint, output_mint, amount_in, min_amount_out,
+        };
+        // 第一跳换出 200 USDC（最小 199），第二跳只花 199 USDC 换回 1.0015 SOL：
+        // SOL 余额少了 1.5M，但兑换率连乘 200/1 × 1.0015/199 > 1
+        let hops = vec![hop(sol, usdc, 1_000_000_000, 199_000_000), hop(usdc, sol, 199_000_000, 995_000_000)];
+        let ratio = realized_return(&hops, &[Some(200_000_000), Some(1_001_500_000)]).unwrap();
+        assert!(ratio > 1.0, "ratio {}", ratio);
+        
+        assert_eq!(realized_return(&hops, &[Some(200_000_000), None]), None);
+        assert_eq!(realized_return(&[], &[]), None);
+    }
+}
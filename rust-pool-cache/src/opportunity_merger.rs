@@ -2,10 +2,15 @@
  * Opportunity Merger
  */
 
-use crate::router::ArbitragePath;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use crate::router::{fnv1a, ArbitragePath, FNV_OFFSET_BASIS};
 use crate::lst_enhanced_detector::LstOpportunity;
 use tracing::debug;
 
+/// 跨扫描去重的默认TTL：同一路径30秒后再次发现视为新机会
+const DEFAULT_DEDUP_TTL: Duration = Duration::from_secs(30);
+
 #[derive(Debug, Clone)]
 pub struct UnifiedOpportunity {
     pub source: OpportunitySource,
@@ -54,11 +59,81 @@ impl UnifiedOpportunity {
 #[derive(Clone)]
 pub struct OpportunityMerger {
     similarity_threshold: f64,
+    /// 跨扫描去重窗口
+    dedup_ttl: Duration,
+    /// 路径签名 -> 首次记录时间
+    recently_seen: HashMap<u64, Instant>,
 }
 
 impl OpportunityMerger {
     pub fn new() -> Self {
-        Self { similarity_threshold: 5.0 }
+        Self {
+            similarity_threshold: 5.0,
+            dedup_ttl: DEFAULT_DEDUP_TTL,
+            recently_seen: HashMap::new(),
+        }
+    }
+    
+    /// 设置跨扫描去重TTL
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.dedup_ttl = ttl;
+        self
+    }
+    
+    /// 机会指纹：路径签名 + 方向（代币序列）的 FNV-1a 哈希，16 位十六进制
+    ///
+    /// 同一组池子按不同起点走的环路指纹不同；扫描历史、生命周期表和结构化输出都用它做键。
+    ///
+    /// 按 "签名|A>B>A" 的字节序列分段哈希，不拼接中间字符串。
+    pub fn fingerprint(path: &ArbitragePath) -> String {
+        format!("{:016x}", Self::fingerprint_key(path))
+    }
+
+    /// `fingerprint` 的数值形式
+    pub fn fingerprint_key(path: &ArbitragePath) -> u64 {
+        let direction = std::iter::once(path.start_token.as_str())
+            .chain(path.steps.iter().map(|s| s.output_token.as_str()))
+            .enumerate()
+            .flat_map(|(i, token)| {
+                let separator: &[u8] = if i == 0 { b"|" } else { b">" };
+                separator.iter().chain(token.as_bytes()).copied()
+            });
+        let hash = fnv1a(FNV_OFFSET_BASIS, path.signature().bytes());
+        fnv1a(hash, direction)
+    }
+
+    /// 跨扫描去重：只返回TTL窗口内首次出现的路径
+    /// 
+    /// 同一扫描内相同签名只保留第一条；连续扫描中持续存在的路径在TTL内不会重复返回，
+    /// TTL过期后再次发现则作为新机会返回。
+    ///
+    /// 只应传入已通过验证的路径：被拒绝的路径一旦记入去重表，TTL 内即使变为有效也不会再上报。
+    pub fn filter_new_paths(&mut self, paths: Vec<ArbitragePath>, now: Instant) -> Vec<ArbitragePath> {
+        self.filter_new(paths, now, |path| path)
+    }
+    
+    /// 同 `filter_new_paths`，条目携带验证结果等附加数据（`path` 取出其中的路径）
+    pub fn filter_new<T>(&mut self, items: Vec<T>, now: Instant, path: impl Fn(&T) -> &ArbitragePath) -> Vec<T> {
+        let ttl = self.dedup_ttl;
+        self.recently_seen.retain(|_, first_seen| now.duration_since(*first_seen) < ttl);
+        
+        let mut fresh = Vec::new();
+        for item in items {
+            let signature = path(&item).signature().key();
+            if self.recently_seen.contains_key(&signature) {
+                continue;
+            }
+            self.recently_seen.insert(signature, now);
+            fresh.push(item);
+        }
+        
+        debug!("Cross-scan dedup: {} new paths ({} tracked)", fresh.len(), self.recently_seen.len());
+        fresh
+    }
+    
+    /// 当前TTL窗口内追踪的路径数
+    pub fn tracked_count(&self) -> usize {
+        self.recently_seen.len()
     }
     
     pub fn merge(
@@ -157,3 +232,80 @@ impl Default for OpportunityMerger {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::{ArbitrageType, RouteStep};
+    
+    fn path(pools: &[&str], roi: f64) -> ArbitragePath {
+        ArbitragePath {
+            arb_type: ArbitrageType::Triangle,
+            steps: pools.iter().map(|pool_id| RouteStep {
+                pool_id: pool_id.to_string(),
+                dex_name: "Test".to_string(),
+                input_token: "USDC".to_string(),
+                output_token: "SOL".to_string(),
+                price: 1.0,
+                liquidity_base: 1_000_000,
+                liquidity_quote: 1_000_000,
+                expected_input: 100.0,
+                expected_output: 100.0,
+                price_impact_percent: 0.0,
+                effective_fee_bps: 0.0,
+                raw_input_token: None,
+                raw_output_token: None,
+            }).collect(),
+            start_token: "USDC".to_string(),
+            end_token: "USDC".to_string(),
+            input_amount: 100.0,
+            output_amount: 100.0 + roi,
+            gross_profit: roi,
+            dex_fees: 0.0,
+            execution_fees: 0.0,
+            estimated_fees: 0.0,
+            net_profit: roi,
+            roi_percent: roi,
+            discovered_at: Instant::now(),
+        }
+    }
+    
+    #[test]
+    fn test_filter_new_paths_respects_ttl() {
+        let mut merger = OpportunityMerger::new().with_ttl(Duration::from_secs(30));
+        let t0 = Instant::now();
+        
+        // 同一扫描内重复路径只保留一条
+        let first = merger.filter_new_paths(vec![path(&["a", "b"], 1.0), path(&["a", "b"], 1.1), path(&["c"], 0.5)], t0);
+        assert_eq!(first.len(), 2);
+        
+        // 下一次扫描：已见过的路径被过滤
+        let second = merger.filter_new_paths(vec![path(&["a", "b"], 1.2)], t0 + Duration::from_secs(5));
+        assert!(second.is_empty());
+        assert_eq!(merger.tracked_count(), 2);
+        
+        // 30秒后再次发现：视为新机会
+        let third = merger.filter_new_paths(vec![path(&["a", "b"], 1.2)], t0 + Duration::from_secs(31));
+        assert_eq!(third.len(), 1);
+        assert_eq!(merger.tracked_count(), 1);
+    }
+    
+    #[test]
+    fn test_rejected_path_is_not_suppressed_by_dedup() {
+        let mut merger = OpportunityMerger::new().with_ttl(Duration::from_secs(30));
+        let t0 = Instant::now();
+        let valid = |path: &ArbitragePath| path.roi_percent >= 1.0;
+        
+        // 第一次扫描验证未通过：不进入去重表
+        let scan = |roi: f64| vec![(path(&["a", "b"], roi), roi)];
+        let first: Vec<_> = scan(0.5).into_iter().filter(|(p, _)| valid(p)).collect();
+        assert!(merger.filter_new(first, t0, |(p, _)| p).is_empty());
+        assert_eq!(merger.tracked_count(), 0);
+        
+        // 5 秒后同一路径变为有效：立即上报，附带数据原样保留
+        let second: Vec<_> = scan(1.2).into_iter().filter(|(p, _)| valid(p)).collect();
+        let reported = merger.filter_new(second, t0 + Duration::from_secs(5), |(p, _)| p);
+        assert_eq!(reported.len(), 1);
+        assert_eq!(reported[0].1, 1.2);
+    }
+}
@@ -0,0 +1,329 @@
+/*!
+ * 🧾 结构化机会输出
+ *
+ * 下游工具不必再解析控制台的彩色框：每个通过验证的机会序列化为一行 JSON，
+ * 写到 [output] 配置的 sink（jsonl 文件按大小轮转 / stdout）。一行包含完整路径、
+ * 拆分方案、所用池子的 slot 与更新时间、触发来源和验证状态。
+ */
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tracing::warn;
+
+use crate::config::{OutputConfig, OutputSinkConfig, OutputSinkKind};
+use crate::database::OpportunityContext;
+use crate::price_cache::PriceCache;
+use crate::router::ArbitragePath;
+use crate::router_split_optimizer::OptimizedPath;
+
+/// 路径中一个池子在输出时刻的缓存状态
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolObservation {
+    pub pool_id: String,
+    pub slot: u64,
+    /// 最近一次更新的 Unix 毫秒时间戳（由更新距今的时长推算）
+    pub updated_at_ms: i64,
+    pub age_ms: u64,
+    pub price: f64,
+}
+
+/// 一条机会记录（jsonl 的一行）
+#[derive(Debug, Clone, Serialize)]
+pub struct OpportunityRecord<'a> {
+    /// 输出时刻（Unix 毫秒）
+    pub emitted_at_ms: i64,
+    pub fingerprint: String,
+    pub signature: String,
+    pub path: &'a ArbitragePath,
+    /// 拆分优化结果（快速通道发现的直接套利没有）
+    pub optimized: Option<&'a OptimizedPath>,
+    /// 路径每一跳的池子（缓存中已没有的池子省略）
+    pub pools: Vec<PoolObservation>,
+    /// 触发来源与验证状态
+    #[serde(flatten)]
+    pub context: &'a OpportunityContext,
+}
+
+impl<'a> OpportunityRecord<'a> {
+    pub fn new(
+        path: &'a ArbitragePath,
+        optimized: Option<&'a OptimizedPath>,
+        context: &'a OpportunityContext,
+        price_cache: &PriceCache,
+    ) -> Self {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let pools = path.steps.iter()
+            .filter_map(|step| price_cache.get_price(&step.pool_id))
+            .map(|price| {
+                let age_ms = price.last_update.elapsed().as_millis() as u64;
+                PoolObservation {
+                    pool_id: price.pool_id,
+                    slot: price.slot,
+                    updated_at_ms: now_ms - age_ms as i64,
+                    age_ms,
+                    price: price.price,
+                }
+            })
+            .collect();
+
+        Self {
+            emitted_at_ms: now_ms,
+            fingerprint: path.fingerprint(),
+            signature: path.signature().to_string(),
+            path,
+            optimized,
+            pools,
+            context,
+        }
+    }
+}
+
+/// 按大小轮转的 jsonl 文件：`path` 写满后依次改名为 `path.1`、`path.2` ...
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    pub fn open(path: &Path, max_bytes: u64, max_files: usize) -> Result<Self> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let file = Self::open_append(path)?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_bytes,
+            max_files,
+            file,
+            size,
+        })
+    }
+
+    fn open_append(path: &Path) -> Result<File> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open output file {}", path.display()))
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    /// 写入一行（自动追加换行），超过大小上限时先轮转
+    pub fn write_line(&mut self, line: &str) -> Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        self.file.flush()?;
+        if self.max_files == 0 {
+            // 不保留历史：直接截断
+            self.file = File::create(&self.path)?;
+        } else {
+            let _ = std::fs::remove_file(self.rotated_path(self.max_files));
+            for index in (1..self.max_files).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    std::fs::rename(&from, self.rotated_path(index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+            self.file = Self::open_append(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// 单个输出目标
+pub enum OutputSink {
+    JsonlFile(RotatingFile),
+    StdoutJson,
+}
+
+impl OutputSink {
+    pub fn from_config(config: &OutputSinkConfig) -> Result<Self> {
+        match config.kind {
+            OutputSinkKind::StdoutJson => Ok(OutputSink::StdoutJson),
+            OutputSinkKind::JsonlFile => {
+                let path = config.path.as_deref()
+                    .context("jsonl_file output sink requires a path")?;
+                Ok(OutputSink::JsonlFile(RotatingFile::open(Path::new(path), config.max_bytes, config.max_files)?))
+            }
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        match self {
+            OutputSink::JsonlFile(file) => file.write_line(line),
+            OutputSink::StdoutJson => {
+                let mut stdout = io::stdout().lock();
+                writeln!(stdout, "{}", line)?;
+                stdout.flush()?;
+                Ok(())
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            OutputSink::JsonlFile(_) => "jsonl_file",
+            OutputSink::StdoutJson => "stdout_json",
+        }
+    }
+}
+
+/// 机会输出分发器（Calculator 持有，每条验证通过的机会写一行到所有 sink）
+pub struct OpportunityOutput {
+    sinks: Vec<OutputSink>,
+}
+
+impl OpportunityOutput {
+    /// 按配置创建；未启用或没有 sink 时返回 None
+    pub fn from_config(config: &OutputConfig) -> Result<Option<Self>> {
+        if !config.enabled || config.sinks.is_empty() {
+            return Ok(None);
+        }
+        let sinks = config.sinks.iter()
+            .map(OutputSink::from_config)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Some(Self { sinks }))
+    }
+
+    pub fn sink_names(&self) -> Vec<&'static str> {
+        self.sinks.iter().map(OutputSink::name).collect()
+    }
+
+    /// 序列化一次后写入所有 sink（单个 sink 失败只记录警告）
+    pub fn emit(&mut self, record: &OpportunityRecord) {
+        let line = match serde_json::to_string(record) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Failed to serialize opportunity {}: {}", record.signature, e);
+                return;
+            }
+        };
+        for sink in &mut self.sinks {
+            if let Err(e) = sink.write_line(&line) {
+                warn!("🧾 Output sink {} failed: {:#}", sink.name(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::latency_budget::LatencyBreakdown;
+    use crate::price_cache::PoolPrice;
+    use crate::router::{ArbitrageType, RouteStep};
+    use std::time::Instant;
+
+    fn step(pool_id: &str, input: &str, output: &str) -> RouteStep {
+        RouteStep {
+            pool_id: pool_id.to_string(),
+            dex_name: "Raydium AMM V4".to_string(),
+            input_token: input.to_string(),
+            output_token: output.to_string(),
+            price: 185.0,
+            liquidity_base: 1_000_000,
+            liquidity_quote: 185_000_000,
+            expected_input: 1.0,
+            expected_output: 185.0,
+            price_impact_percent: 0.1,
+            effective_fee_bps: 25.0,
+            raw_input_token: None,
+            raw_output_token: None,
+        }
+    }
+
+    #[test]
+    fn test_record_serializes_path_pools_and_context() {
+        let cache = PriceCache::new();
+        cache.update_price(PoolPrice {
+            base_reserve: 1_000_000,
+            quote_reserve: 185_000_000,
+            slot: 42,
+            ..PoolPrice::for_test("pool-a", "SOL/USDC", 185.0)
+        });
+        let path = ArbitragePath {
+            arb_type: ArbitrageType::Direct,
+            steps: vec![step("pool-a", "SOL", "USDC"), step("pool-b", "USDC", "SOL")],
+            start_token: "SOL".to_string(),
+            end_token: "SOL".to_string(),
+            input_amount: 1.0,
+            output_amount: 1.01,
+            gross_profit: 0.01,
+            dex_fees: 0.001,
+            execution_fees: 0.0,
+            estimated_fees: 0.001,
+            net_profit: 0.009,
+            roi_percent: 0.9,
+            discovered_at: Instant::now(),
+        };
+        let context = OpportunityContext {
+            trigger_type: "event".to_string(),
+            trigger_source: "SOL/USDC (Raydium)".to_string(),
+            revalidation_status: Some("confirmed".to_string()),
+            latency: Some(LatencyBreakdown { calculator_wait_us: 80, end_to_end_us: 4_200, ..Default::default() }),
+            ..Default::default()
+        };
+
+        let record = OpportunityRecord::new(&path, None, &context, &cache);
+        let json: serde_json::Value = serde_json::to_value(&record).unwrap();
+
+        assert_eq!(json["path"]["arb_type"], "direct");
+        assert_eq!(json["path"]["steps"][1]["pool_id"], "pool-b");
+        assert!(json["path"]["discovered_age_ms"].is_u64());
+        assert_eq!(json["trigger_source"], "SOL/USDC (Raydium)");
+        assert_eq!(json["revalidation_status"], "confirmed");
+        assert_eq!(json["latency"]["end_to_end_us"], 4_200);
+        assert!(json["latency"]["deserialize_us"].is_null());
+        assert_eq!(json["signature"], "pool-a->pool-b");
+        // 只有仍在缓存中的池子
+        assert_eq!(json["pools"].as_array().unwrap().len(), 1);
+        assert_eq!(json["pools"][0]["slot"], 42);
+        assert!(json["optimized"].is_null());
+    }
+
+    #[test]
+    fn test_jsonl_file_rotates_by_size() {
+        let dir = std::env::temp_dir().join(format!("opportunity-output-test-{}", std::process::id()));
+        let path = dir.join("opportunities.jsonl");
+        let mut file = RotatingFile::open(&path, 25, 2).unwrap();
+
+        // 每行 12 字节（含换行），上限 25 字节：每个文件两行，第三行触发轮转
+        for i in 0..7 {
+            file.write_line(&format!("{{\"n\":{:05}}}", i)).unwrap();
+        }
+
+        let read = |p: PathBuf| std::fs::read_to_string(p).unwrap_or_default();
+        assert_eq!(read(path.clone()).lines().count(), 1);
+        assert_eq!(read(file.rotated_path(1)).lines().count(), 2);
+        assert_eq!(read(file.rotated_path(2)).lines().count(), 2);
+        // 最早的两行超出 max_files 被丢弃
+        assert!(!file.rotated_path(3).exists());
+        assert!(read(file.rotated_path(2)).contains("00002"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
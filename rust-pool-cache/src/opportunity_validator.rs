@@ -8,12 +8,31 @@
  * 2. Slot一致性 - 路径上所有池子的slot必须接近
  * 3. 价格稳定性 - 池子价格不能剧烈波动
  * 4. 流动性充足性 - 储备量必须足够执行交易
+ * 5. 池子不重复 - 同一池子（或共用 vault 的包装池）不能在一条路径中出现两次
+ *
+ * 多跳路径（`validate_path`）的检查拆成 `validation_rules` 中的命名规则，
+ * 按配置的顺序执行，并按规则统计拒绝次数。
+ *
+ * 上报 / 持久化之前还会用 `revalidate` 按缓存中最新的池子状态重新执行一遍路径，
+ * 确认发现到上报之间价格变化后机会是否仍然成立。
  */
 
 use std::sync::Arc;
 use std::time::Instant;
+use crate::account_subscription::Commitment;
 use crate::price_cache::PriceCache;
 use crate::arbitrage::ArbitrageOpportunity;
+use crate::calibration::Calibrator;
+use crate::dex_interface::amm_calculator;
+use crate::config::ValidationConfig;
+use crate::router::{ArbitragePath, PoolReuse};
+use crate::token_graph::{pair_key, pool_tokens};
+use crate::staleness::StaleReason;
+use crate::validation_rules::{self, RoiSanity, RuleContext, RuleResult, RuleStats, ValidationRule};
+use crate::vault_reader::VaultReader;
+
+/// 重新定价后 ROI 不低于原 ROI 的该比例视为 Confirmed
+const CONFIRMED_ROI_RATIO: f64 = 0.8;
 
 /// 验证结果
 #[derive(Debug, Clone)]
@@ -21,6 +40,7 @@ pub enum ValidationResult {
     /// 通过验证，可以执行
     Valid {
         confidence_score: f64,  // 0-100，置信度评分
+        calibrated_probability: f64,  // 0-1，按历史结果校准后的持续概率
         data_quality: DataQuality,
     },
     /// 数据过期
@@ -50,6 +70,166 @@ pub enum ValidationResult {
     PoolNotFound {
         pool_id: String,
     },
+    /// 某一跳价格冲击过大（穿过薄池）
+    ExcessivePriceImpact {
+        pool_id: String,
+        impact_percent: f64,
+        cap_percent: f64,
+    },
+    /// 🔁 路径重复使用同一池子（第一跳成交后后面那一跳的价格已经不成立）
+    PoolReused {
+        reuse: PoolReuse,
+    },
+    /// ROI 超过合理性上限（几乎总是计算或数据错误）
+    UnrealisticRoi {
+        roi_percent: f64,
+        cap_percent: f64,
+    },
+    /// 名义金额超过阈值但没有交易级模拟
+    SimulationRequired {
+        notional_usd: f64,
+        threshold_usd: f64,
+    },
+    /// 📡 名义金额超过阈值但池子数据来自 processed 订阅（可能被回滚）
+    UnconfirmedData {
+        pool_id: String,
+        commitment: Commitment,
+        notional_usd: f64,
+        threshold_usd: f64,
+    },
+}
+
+impl ValidationResult {
+    /// 拒绝该路径的规则名（通过时为 None）
+    pub fn rejected_by(&self) -> Option<&'static str> {
+        match self {
+            ValidationResult::Valid { .. } => None,
+            ValidationResult::Stale { .. } => Some(validation_rules::MAX_DATA_AGE),
+            ValidationResult::SlotMismatch { .. } => Some(validation_rules::SLOT_ALIGNMENT),
+            ValidationResult::InsufficientLiquidity { .. } => Some(validation_rules::MIN_LIQUIDITY),
+            ValidationResult::PriceChanged { .. } => Some(validation_rules::PRICE_DEVIATION),
+            ValidationResult::PoolNotFound { .. } => Some(validation_rules::POOL_NOT_FOUND),
+            ValidationResult::ExcessivePriceImpact { .. } => Some(validation_rules::MAX_SINGLE_HOP_IMPACT),
+            ValidationResult::PoolReused { .. } => Some(validation_rules::NO_POOL_REUSE),
+            ValidationResult::UnrealisticRoi { .. } => Some(validation_rules::MAX_TOTAL_ROI_SANITY),
+            ValidationResult::SimulationRequired { .. } => Some(validation_rules::SIMULATION_REQUIRED_ABOVE_USD),
+            ValidationResult::UnconfirmedData { .. } => Some(validation_rules::CONFIRMED_REQUIRED_ABOVE_USD),
+        }
+    }
+}
+
+/// 重新定价失败的原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum InvalidationReason {
+    /// 池子已不在缓存中
+    PoolMissing { pool_id: String },
+    /// 池子数据超出新鲜度预算
+    Stale { pool_id: String, reason: StaleReason },
+    /// 按最新价格已不盈利
+    Unprofitable,
+}
+
+/// 按最新缓存重新定价的结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum Revalidation {
+    /// ROI 仍在原 ROI 的 80% 以上
+    Confirmed { original_roi: f64, revalidated_roi: f64 },
+    /// 仍盈利但 ROI 明显下降
+    Degraded { original_roi: f64, revalidated_roi: f64 },
+    /// 亏损、池子缺失或过期
+    Invalidated {
+        original_roi: f64,
+        revalidated_roi: Option<f64>,
+        reason: InvalidationReason,
+    },
+}
+
+impl Revalidation {
+    pub fn revalidated_roi(&self) -> Option<f64> {
+        match self {
+            Revalidation::Confirmed { revalidated_roi, .. }
+            | Revalidation::Degraded { revalidated_roi, .. } => Some(*revalidated_roi),
+            Revalidation::Invalidated { revalidated_roi, .. } => *revalidated_roi,
+        }
+    }
+
+    pub fn is_invalidated(&self) -> bool {
+        matches!(self, Revalidation::Invalidated { .. })
+    }
+
+    /// confirmed / degraded / invalidated（日志和数据库使用）
+    pub fn status(&self) -> &'static str {
+        match self {
+            Revalidation::Confirmed { .. } => "confirmed",
+            Revalidation::Degraded { .. } => "degraded",
+            Revalidation::Invalidated { .. } => "invalidated",
+        }
+    }
+}
+
+/// 按缓存中同一 pool_id 的最新状态重新执行路径的每一跳
+///
+/// 与路由器相同的单跳计算（手续费注册表 + 订单簿 / 常数乘积 + 转账手续费），
+/// 路由器在兑换之外扣除的成本（执行成本等，gross_profit - net_profit）原样保留。
+/// 新鲜度按 PriceCache 的池子类型策略判断。
+pub fn revalidate(path: &ArbitragePath, cache: &PriceCache) -> Revalidation {
+    let original_roi = path.roi_percent;
+    let invalidated = |revalidated_roi, reason| Revalidation::Invalidated {
+        original_roi,
+        revalidated_roi,
+        reason,
+    };
+
+    let now = Instant::now();
+    let latest_slot = cache.get_latest_slot();
+    let mut amount = path.input_amount;
+    for step in &path.steps {
+        let pool = match cache.get_price(&step.pool_id) {
+            Some(pool) => pool,
+            None => return invalidated(None, InvalidationReason::PoolMissing { pool_id: step.pool_id.clone() }),
+        };
+
+        let age_ms = now.duration_since(pool.last_update).as_millis() as u64;
+        let slot_diff = latest_slot.saturating_sub(pool.slot);
+        if let Some(reason) = cache.staleness().check(&pool.pool_id, &pool.dex_name, age_ms, slot_diff) {
+            return invalidated(None, InvalidationReason::Stale { pool_id: step.pool_id.clone(), reason });
+        }
+
+        let (base_decimals, quote_decimals) = pool.get_decimals();
+        let base_reserve = pool.base_reserve as f64 / 10f64.powi(base_decimals as i32);
+        let quote_reserve = pool.quote_reserve as f64 / 10f64.powi(quote_decimals as i32);
+        let input_is_base = pool_tokens(&pool).is_some_and(|(base, _)| base == step.input_token);
+        let (reserve_in, reserve_out) = if input_is_base {
+            (base_reserve, quote_reserve)
+        } else {
+            (quote_reserve, base_reserve)
+        };
+
+        amount = amm_calculator::calculate_hop_output_f64(
+            &pool.pool_id,
+            &pair_key(&pool),
+            &step.input_token,
+            amount,
+            reserve_in,
+            reserve_out,
+            crate::fee_registry::fee_rate(&pool.pool_id, &pool.dex_name),
+        );
+    }
+
+    let other_costs = path.gross_profit - path.net_profit;
+    let revalidated_roi = if path.input_amount > 0.0 {
+        (amount - path.input_amount - other_costs) / path.input_amount * 100.0
+    } else {
+        0.0
+    };
+
+    if revalidated_roi.is_nan() || revalidated_roi <= 0.0 {
+        invalidated(Some(revalidated_roi), InvalidationReason::Unprofitable)
+    } else if revalidated_roi >= original_roi * CONFIRMED_ROI_RATIO {
+        Revalidation::Confirmed { original_roi, revalidated_roi }
+    } else {
+        Revalidation::Degraded { original_roi, revalidated_roi }
+    }
 }
 
 /// 数据质量详情
@@ -73,6 +253,18 @@ pub struct ValidatorConfig {
     pub max_price_deviation_pct: f64,
     /// 最小流动性倍数（相对于交易金额）
     pub min_liquidity_multiplier: f64,
+    /// 单跳最大价格冲击（百分比）
+    pub max_hop_impact_percent: f64,
+    /// 允许路径重复使用同一池子（[router] allow_pool_reuse，开启时跳过 no_pool_reuse）
+    pub allow_pool_reuse: bool,
+    /// ROI 合理性上限（max_total_roi_sanity）
+    pub roi_sanity: RoiSanity,
+    /// 名义金额超过该值（美元）的路径必须有交易级模拟（0 = 不检查）
+    pub simulation_required_above_usd: f64,
+    /// 📡 名义金额超过该值（美元）的路径不接受 processed 级别的池子数据（0 = 不检查）
+    pub confirmed_required_above_usd: f64,
+    /// `validate_path` 执行的规则及顺序（见 `validation_rules::DEFAULT_RULES`）
+    pub rules: Vec<String>,
 }
 
 impl Default for ValidatorConfig {
@@ -82,22 +274,144 @@ impl Default for ValidatorConfig {
             max_slot_spread: 5,         // 5个slot (约2秒)
             max_price_deviation_pct: 5.0,  // 5%价格变化
             min_liquidity_multiplier: 10.0,  // 储备量至少是交易额的10倍
+            max_hop_impact_percent: 2.0,  // 任意一跳冲击不超过2%
+            allow_pool_reuse: false,
+            roi_sanity: RoiSanity::default(),  // >15% 拒绝，8-15% 警告
+            simulation_required_above_usd: 0.0,
+            confirmed_required_above_usd: 1_000.0,
+            rules: validation_rules::DEFAULT_RULES.iter().map(|rule| rule.to_string()).collect(),
+        }
+    }
+}
+
+impl From<&ValidationConfig> for ValidatorConfig {
+    fn from(config: &ValidationConfig) -> Self {
+        let defaults = Self::default();
+        Self {
+            max_age_ms: config.max_data_age_ms,
+            max_slot_spread: config.max_slot_spread,
+            max_price_deviation_pct: config.max_price_deviation_percent,
+            min_liquidity_multiplier: config.min_liquidity_multiplier,
+            max_hop_impact_percent: config.max_single_hop_impact_percent.unwrap_or(defaults.max_hop_impact_percent),
+            allow_pool_reuse: defaults.allow_pool_reuse,
+            roi_sanity: RoiSanity {
+                max_roi_percent: config.max_total_roi_percent,
+                suspicious_roi_percent: config.suspicious_roi_percent,
+            },
+            simulation_required_above_usd: config.simulation_required_above_usd,
+            confirmed_required_above_usd: config.confirmed_required_above_usd,
+            rules: config.rules.clone(),
         }
     }
 }
 
+impl ValidatorConfig {
+    /// 按配置构建规则流水线（未知规则名跳过，配置校验已报告）
+    fn build_rules(&self) -> Vec<Box<dyn ValidationRule>> {
+        self.rules.iter()
+            .filter_map(|name| -> Option<Box<dyn ValidationRule>> {
+                match name.as_str() {
+                    validation_rules::MAX_DATA_AGE => Some(Box::new(validation_rules::MaxDataAge {
+                        max_age_ms: self.max_age_ms,
+                    })),
+                    validation_rules::SLOT_ALIGNMENT => Some(Box::new(validation_rules::SlotAlignment {
+                        max_slot_spread: self.max_slot_spread,
+                    })),
+                    validation_rules::PRICE_DEVIATION => Some(Box::new(validation_rules::PriceDeviation {
+                        max_deviation_pct: self.max_price_deviation_pct,
+                    })),
+                    validation_rules::MIN_LIQUIDITY => Some(Box::new(validation_rules::MinLiquidity {
+                        multiplier: self.min_liquidity_multiplier,
+                    })),
+                    validation_rules::MAX_SINGLE_HOP_IMPACT => Some(Box::new(validation_rules::MaxSingleHopImpact {
+                        cap_percent: self.max_hop_impact_percent,
+                    })),
+                    validation_rules::NO_POOL_REUSE if !self.allow_pool_reuse => Some(Box::new(validation_rules::NoPoolReuse)),
+                    validation_rules::MAX_TOTAL_ROI_SANITY => Some(Box::new(validation_rules::MaxTotalRoiSanity {
+                        sanity: self.roi_sanity,
+                    })),
+                    validation_rules::SIMULATION_REQUIRED_ABOVE_USD => {
+                        Some(Box::new(validation_rules::SimulationRequiredAboveUsd {
+                            threshold_usd: self.simulation_required_above_usd,
+                        }))
+                    }
+                    validation_rules::CONFIRMED_REQUIRED_ABOVE_USD => {
+                        Some(Box::new(validation_rules::ConfirmedRequiredAboveUsd {
+                            threshold_usd: self.confirmed_required_above_usd,
+                        }))
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
+
 /// 套利机会验证器
 pub struct OpportunityValidator {
     price_cache: Arc<PriceCache>,
     config: ValidatorConfig,
+    /// 置信度校准器（未接入时概率 = 置信度 / 100）
+    calibrator: Option<Arc<Calibrator>>,
+    /// 🔁 vault 登记表（识别共用 vault 的包装池；未接入时只比较 pool_id）
+    vault_reader: Option<Arc<VaultReader>>,
+    /// 🧱 `validate_path` 的规则流水线（按配置顺序）
+    rules: Vec<Box<dyn ValidationRule>>,
+    /// 🧱 每条规则的拒绝次数（/metrics、GET /stats/validation）
+    rule_stats: Arc<RuleStats>,
+    /// 是否配置了交易级模拟（simulation_required_above_usd）
+    simulation_available: bool,
 }
 
 impl OpportunityValidator {
     /// 创建新的验证器
     pub fn new(price_cache: Arc<PriceCache>, config: ValidatorConfig) -> Self {
+        let rules = config.build_rules();
+        let rule_stats = Arc::new(RuleStats::new(rules.iter().map(|rule| rule.name()).collect()));
         Self {
             price_cache,
             config,
+            calibrator: None,
+            vault_reader: None,
+            rules,
+            rule_stats,
+            simulation_available: false,
+        }
+    }
+    
+    /// 接入置信度校准器
+    pub fn with_calibrator(mut self, calibrator: Arc<Calibrator>) -> Self {
+        self.calibrator = Some(calibrator);
+        self
+    }
+    
+    /// 🔁 接入 vault 登记表（共用 vault 的池子视为同一池子）
+    pub fn with_vault_reader(mut self, vault_reader: Arc<VaultReader>) -> Self {
+        self.vault_reader = Some(vault_reader);
+        self
+    }
+    
+    /// 🧪 配置了交易级模拟（simulation_required_above_usd 不再拒绝大额路径）
+    pub fn with_simulation(mut self, available: bool) -> Self {
+        self.simulation_available = available;
+        self
+    }
+    
+    /// 🧱 规则拒绝计数（与验证器共享，可交给 API）
+    pub fn rule_stats(&self) -> Arc<RuleStats> {
+        self.rule_stats.clone()
+    }
+    
+    /// 当前生效的规则（执行顺序）
+    pub fn active_rules(&self) -> &[&'static str] {
+        self.rule_stats.active_rules()
+    }
+    
+    /// 置信度 -> 校准概率
+    pub fn calibrated_probability(&self, confidence_score: f64) -> f64 {
+        match &self.calibrator {
+            Some(calibrator) => calibrator.probability(confidence_score),
+            None => (confidence_score / 100.0).clamp(0.0, 1.0),
         }
     }
     
@@ -222,6 +536,68 @@ impl OpportunityValidator {
         
         ValidationResult::Valid {
             confidence_score,
+            calibrated_probability: self.calibrated_probability(confidence_score),
+            data_quality: DataQuality {
+                average_age_ms: avg_age,
+                max_age_ms: max_age,
+                slot_spread,
+                freshness_score,
+                alignment_score,
+            },
+        }
+    }
+    
+    /// 验证路由器输出的多跳路径
+    /// 
+    /// 先确认每一跳的池子都在缓存中，再按配置顺序执行规则流水线
+    /// （数据年龄、slot对齐、价格偏离、输入侧流动性、单跳冲击、池子重复、ROI 合理性、
+    /// 大额模拟要求），第一条拒绝的规则决定结果（`ValidationResult::rejected_by`）。
+    /// 每条路径的结果都记入 `rule_stats`。
+    pub fn validate_path(&self, path: &ArbitragePath) -> ValidationResult {
+        let result = self.run_rules(path);
+        self.rule_stats.record(result.rejected_by());
+        result
+    }
+    
+    fn run_rules(&self, path: &ArbitragePath) -> ValidationResult {
+        let mut pools = Vec::with_capacity(path.steps.len());
+        for step in &path.steps {
+            match self.price_cache.get_price(&step.pool_id) {
+                Some(pool) => pools.push(pool),
+                None => return ValidationResult::PoolNotFound {
+                    pool_id: step.pool_id.clone(),
+                },
+            }
+        }
+        if pools.is_empty() {
+            return ValidationResult::PoolNotFound { pool_id: String::new() };
+        }
+        
+        let ctx = RuleContext {
+            pools: &pools,
+            now: Instant::now(),
+            vault_reader: self.vault_reader.as_deref(),
+            input_usd_price: self.price_cache.usd_prices().get(&path.start_token),
+            simulation_available: self.simulation_available,
+        };
+        for rule in &self.rules {
+            if let RuleResult::Reject(result) = rule.check(path, &ctx) {
+                return result;
+            }
+        }
+        
+        // 规则可能被关闭，评分截断到 0-100
+        let ages = ctx.ages_ms();
+        let max_age = ages.iter().copied().max().unwrap_or(0);
+        let avg_age = ages.iter().sum::<u64>() / ages.len() as u64;
+        let slot_spread = ctx.slot_spread();
+        let freshness_score = (100.0 * (1.0 - (max_age as f64 / self.config.max_age_ms as f64))).clamp(0.0, 100.0);
+        let alignment_score = (100.0 * (1.0 - (slot_spread as f64 / self.config.max_slot_spread as f64))).clamp(0.0, 100.0);
+        let confidence_score = (freshness_score + alignment_score) / 2.0;
+        
+        ValidationResult::Valid {
+            confidence_score,
+            calibrated_probability: self.calibrated_probability(confidence_score),
             data_quality: DataQuality {
                 average_age_ms: avg_age,
                 max_age_ms: max_age,
@@ -275,11 +651,28 @@ impl OpportunityValidator {
                     stats.pool_not_found += 1;
                     invalid.push((opp, result));
                 }
+                ValidationResult::ExcessivePriceImpact { .. } => {
+                    stats.excessive_impact += 1;
+                    invalid.push((opp, result));
+                }
+                ValidationResult::PoolReused { .. } => {
+                    stats.pool_reused += 1;
+                    invalid.push((opp, result));
+                }
+                ValidationResult::UnrealisticRoi { .. }
+                | ValidationResult::SimulationRequired { .. }
+                | ValidationResult::UnconfirmedData { .. } => {
+                    invalid.push((opp, result));
+                }
             }
         }
         
-        // 按置信度排序（高到低）
-        valid.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        // 按期望值排序（校准概率 × 理论利润，高到低）
+        valid.sort_by(|a, b| {
+            let ev_a = self.calibrated_probability(a.1) * a.0.estimated_profit_pct;
+            let ev_b = self.calibrated_probability(b.1) * b.0.estimated_profit_pct;
+            ev_b.total_cmp(&ev_a)
+        });
         
         (valid, invalid, stats)
     }
@@ -295,6 +688,8 @@ pub struct ValidationStats {
     pub insufficient_liquidity: usize,
     pub price_changed: usize,
     pub pool_not_found: usize,
+    pub excessive_impact: usize,
+    pub pool_reused: usize,
     pub total_confidence: f64,
 }
 
@@ -328,5 +723,356 @@ mod tests {
         assert_eq!(config.max_age_ms, 2000);
         assert_eq!(config.max_slot_spread, 5);
     }
+    
+    #[test]
+    fn test_validate_path_checks_every_hop() {
+        use crate::price_cache::PoolPrice;
+        use crate::router::{ArbitrageType, RouteStep};
+        
+        let cache = Arc::new(PriceCache::new());
+        for (pool_id, pair, price, slot) in [("p1", "SOL/USDC", 100.0, 1000), ("p2", "SOL/USDC", 101.0, 1001)] {
+            cache.update_price(PoolPrice {
+                dex_name: "Test".to_string(),
+                base_reserve: 10_000 * 1_000_000_000,
+                quote_reserve: 1_000_000 * 1_000_000,
+                slot,
+                ..PoolPrice::for_test(pool_id, pair, price)
+            });
+        }
+        let step = |pool_id: &str, input: &str, output: &str, price: f64, amount: f64| RouteStep {
+            pool_id: pool_id.to_string(),
+            dex_name: "Test".to_string(),
+            input_token: input.to_string(),
+            output_token: output.to_string(),
+            price,
+            liquidity_base: 0,
+            liquidity_quote: 0,
+            expected_input: amount,
+            expected_output: amount,
+            price_impact_percent: 0.0,
+            effective_fee_bps: 0.0,
+            raw_input_token: None,
+            raw_output_token: None,
+        };
+        let mut path = ArbitragePath {
+            arb_type: ArbitrageType::Direct,
+            steps: vec![
+                step("p1", "USDC", "SOL", 100.0, 1000.0),
+                step("p2", "SOL", "USDC", 101.0, 10.0),
+            ],
+            start_token: "USDC".to_string(),
+            end_token: "USDC".to_string(),
+            input_amount: 1000.0,
+            output_amount: 1010.0,
+            gross_profit: 10.0,
+            dex_fees: 0.0,
+            execution_fees: 0.0,
+            estimated_fees: 0.0,
+            net_profit: 10.0,
+            roi_percent: 1.0,
+            discovered_at: Instant::now(),
+        };
+        
+        let validator = OpportunityValidator::with_defaults(cache);
+        assert!(matches!(validator.validate_path(&path), ValidationResult::Valid { .. }));
+        
+        // 第二跳的价格已经大幅变化
+        path.steps[1].price = 80.0;
+        assert!(matches!(
+            validator.validate_path(&path),
+            ValidationResult::PriceChanged { ref pool_id, .. } if pool_id == "p2"
+        ));
+        
+        // 第二跳输入（SOL）超过储备量 / 10
+        path.steps[1].price = 101.0;
+        path.steps[1].expected_input = 5_000.0;
+        assert!(matches!(
+            validator.validate_path(&path),
+            ValidationResult::InsufficientLiquidity { ref pool_id, .. } if pool_id == "p2"
+        ));
+        
+        // 未知池子
+        path.steps[0].pool_id = "missing".to_string();
+        assert!(matches!(validator.validate_path(&path), ValidationResult::PoolNotFound { .. }));
+    }
+    
+    #[test]
+    fn test_rule_pipeline_reports_rejecting_rule() {
+        use crate::price_cache::PoolPrice;
+        use crate::router::{ArbitrageType, RouteStep};
+        use crate::validation_rules::{MAX_DATA_AGE, MAX_TOTAL_ROI_SANITY, SIMULATION_REQUIRED_ABOVE_USD};
+        
+        let cache = Arc::new(PriceCache::new());
+        for pool_id in ["p1", "p2"] {
+            cache.update_price(PoolPrice {
+                dex_name: "Test".to_string(),
+                base_reserve: 10_000 * 1_000_000_000,
+                quote_reserve: 1_000_000 * 1_000_000,
+                slot: 1000,
+                ..PoolPrice::for_test(pool_id, "SOL/USDC", 100.0)
+            });
+        }
+        let step = |pool_id: &str, input: &str, output: &str| RouteStep {
+            pool_id: pool_id.to_string(),
+            dex_name: "Test".to_string(),
+            input_token: input.to_string(),
+            output_token: output.to_string(),
+            price: 100.0,
+            liquidity_base: 0,
+            liquidity_quote: 0,
+            expected_input: 1.0,
+            expected_output: 1.0,
+            price_impact_percent: 0.0,
+            effective_fee_bps: 0.0,
+            raw_input_token: None,
+            raw_output_token: None,
+        };
+        let mut path = ArbitragePath {
+            arb_type: ArbitrageType::Direct,
+            steps: vec![step("p1", "USDC", "SOL"), step("p2", "SOL", "USDC")],
+            start_token: "USDC".to_string(),
+            end_token: "USDC".to_string(),
+            input_amount: 1000.0,
+            output_amount: 1200.0,
+            gross_profit: 200.0,
+            dex_fees: 0.0,
+            execution_fees: 0.0,
+            estimated_fees: 0.0,
+            net_profit: 200.0,
+            roi_percent: 20.0,
+            discovered_at: Instant::now(),
+        };
+        
+        // 20% ROI 超过合理性上限（原 LST 检测器的 15%）
+        let validator = OpportunityValidator::with_defaults(cache.clone());
+        let rejected = validator.validate_path(&path);
+        assert!(matches!(rejected, ValidationResult::UnrealisticRoi { cap_percent, .. } if cap_percent == 15.0));
+        assert_eq!(rejected.rejected_by(), Some(MAX_TOTAL_ROI_SANITY));
+        
+        // 规则集合由配置决定：去掉 ROI 检查后通过
+        let without_sanity = OpportunityValidator::new(cache.clone(), ValidatorConfig {
+            rules: vec![MAX_DATA_AGE.to_string()],
+            ..Default::default()
+        });
+        assert_eq!(without_sanity.active_rules(), [MAX_DATA_AGE]);
+        assert!(matches!(without_sanity.validate_path(&path), ValidationResult::Valid { .. }));
+        
+        // 1000 USDC 超过 500 美元的模拟阈值：没有交易级模拟时拒绝
+        path.roi_percent = 1.0;
+        let guarded = OpportunityValidator::new(cache.clone(), ValidatorConfig {
+            simulation_required_above_usd: 500.0,
+            ..Default::default()
+        });
+        let rejected = guarded.validate_path(&path);
+        assert!(matches!(rejected, ValidationResult::SimulationRequired { notional_usd, .. } if notional_usd == 1000.0));
+        assert_eq!(rejected.rejected_by(), Some(SIMULATION_REQUIRED_ABOVE_USD));
+        let guarded = guarded.with_simulation(true);
+        assert!(matches!(guarded.validate_path(&path), ValidationResult::Valid { .. }));
+        
+        // 每条路径只记入第一条拒绝的规则
+        let stats = guarded.rule_stats();
+        assert_eq!(stats.rejected(SIMULATION_REQUIRED_ABOVE_USD), 1);
+        assert_eq!(stats.rejected(MAX_TOTAL_ROI_SANITY), 0);
+        let snapshot = stats.snapshot();
+        assert_eq!((snapshot.checked, snapshot.passed), (2, 1));
+    }
+    
+    #[test]
+    fn test_validate_path_rejects_thin_pool_impact() {
+        use crate::price_cache::PoolPrice;
+        use crate::router_direct::DirectArbTable;
+        
+        let cache = Arc::new(PriceCache::new());
+        let pool = |pool_id: &str, price: f64, base_units: f64| PoolPrice {
+            base_reserve: (base_units * 1_000_000_000.0) as u64,
+            quote_reserve: (base_units * price * 1_000_000.0) as u64,
+            ..PoolPrice::for_test(pool_id, "JTO/USDC", price)
+        };
+        let table = DirectArbTable::new(cache.clone(), 0.3)
+            .with_execution_cost(crate::router_fixture::priced_execution_cost(&[("USDC", 150.0)]));
+        cache.update_price(pool("thick", 2.00, 1_000_000.0));
+        cache.update_price(pool("thin", 2.10, 1_500.0));
+        table.on_pool_event("thick");
+        let opportunity = table.on_pool_event("thin").expect("spread above threshold");
+        
+        // 100 USDC 买入约 50 JTO，卖进只有 1500 JTO 的薄池
+        let path = opportunity.to_path(&cache, 100.0).unwrap();
+        assert!(path.steps[0].price_impact_percent < 0.01);
+        assert!(path.steps[1].price_impact_percent > 2.0 && path.steps[1].price_impact_percent < 5.0);
+        assert_eq!(path.steps[1].effective_fee_bps, 25.0);
+        assert_eq!(path.worst_hop_impact(), path.steps[1].price_impact_percent);
+        
+        let validator = OpportunityValidator::with_defaults(cache.clone());
+        assert!(matches!(
+            validator.validate_path(&path),
+            ValidationResult::ExcessivePriceImpact { ref pool_id, cap_percent, .. }
+                if pool_id == "thin" && cap_percent == 2.0
+        ));
+        
+        // 放宽上限后通过
+        let relaxed = OpportunityValidator::new(cache, ValidatorConfig {
+            max_hop_impact_percent: 5.0,
+            ..Default::default()
+        });
+        assert!(matches!(relaxed.validate_path(&path), ValidationResult::Valid { .. }));
+    }
+    
+    #[test]
+    fn test_validate_path_rejects_pool_reuse() {
+        use crate::price_cache::PoolPrice;
+        use crate::router::{ArbitrageType, RouteStep};
+        
+        let cache = Arc::new(PriceCache::new());
+        for (pool_id, pair) in [("x", "SOL/USDC"), ("y", "JUP/USDC"), ("wrapper", "JUP/USDC")] {
+            cache.update_price(PoolPrice {
+                dex_name: "Test".to_string(),
+                base_reserve: 10_000 * 1_000_000_000,
+                quote_reserve: 1_000_000 * 1_000_000,
+                slot: 1000,
+                ..PoolPrice::for_test(pool_id, pair, 100.0)
+            });
+        }
+        let step = |pool_id: &str, input: &str, output: &str| RouteStep {
+            pool_id: pool_id.to_string(),
+            dex_name: "Test".to_string(),
+            input_token: input.to_string(),
+            output_token: output.to_string(),
+            price: 0.0,
+            liquidity_base: 0,
+            liquidity_quote: 0,
+            expected_input: 1.0,
+            expected_output: 1.0,
+            price_impact_percent: 0.0,
+            effective_fee_bps: 25.0,
+            raw_input_token: None,
+            raw_output_token: None,
+        };
+        let cycle = |steps: Vec<RouteStep>| ArbitragePath {
+            arb_type: ArbitrageType::Triangle,
+            steps,
+            start_token: "USDC".to_string(),
+            end_token: "USDC".to_string(),
+            input_amount: 100.0,
+            output_amount: 101.0,
+            gross_profit: 1.0,
+            dex_fees: 0.0,
+            execution_fees: 0.0,
+            estimated_fees: 0.0,
+            net_profit: 1.0,
+            roi_percent: 1.0,
+            discovered_at: Instant::now(),
+        };
+        
+        // USDC→SOL 走 x，JUP 绕一圈后 SOL→USDC 又走 x
+        let degenerate = cycle(vec![
+            step("x", "USDC", "SOL"),
+            step("y", "SOL", "JUP"),
+            step("y", "JUP", "SOL"),
+            step("x", "SOL", "USDC"),
+        ]);
+        let validator = OpportunityValidator::with_defaults(cache.clone());
+        let rejected = validator.validate_path(&degenerate);
+        assert!(matches!(
+            &rejected,
+            ValidationResult::PoolReused { reuse } if reuse.pool_id == "y" && (reuse.first_hop, reuse.second_hop) == (1, 2)
+        ));
+        assert!(format!("{:?}", rejected).contains("PoolReused"));
+        
+        // 包装池：pool_id 不同但共用 vault，接入 VaultReader 后同样拒绝
+        let wrapped = cycle(vec![step("y", "USDC", "JUP"), step("x", "JUP", "SOL"), step("wrapper", "SOL", "USDC")]);
+        let vaults = Arc::new(VaultReader::new());
+        vaults.register_pool_vaults("y", "vault_jup", "vault_usdc");
+        vaults.register_pool_vaults("wrapper", "vault_jup", "vault_usdc");
+        let plain = OpportunityValidator::with_defaults(cache.clone());
+        assert!(!matches!(plain.validate_path(&wrapped), ValidationResult::PoolReused { .. }));
+        match plain.with_vault_reader(vaults).validate_path(&wrapped) {
+            ValidationResult::PoolReused { reuse } => {
+                assert_eq!(reuse.to_string(), "pools y (hop 0) and wrapper (hop 2) share vault vault_jup");
+            }
+            other => panic!("expected PoolReused, got {:?}", other),
+        }
+        
+        // allow_pool_reuse 时不检查
+        let permissive = OpportunityValidator::new(cache, ValidatorConfig {
+            allow_pool_reuse: true,
+            ..Default::default()
+        });
+        assert!(!matches!(permissive.validate_path(&degenerate), ValidationResult::PoolReused { .. }));
+    }
+    
+    #[test]
+    fn test_revalidate_flips_to_invalidated_on_adverse_move() {
+        use crate::price_cache::PoolPrice;
+        use crate::router::{ArbitrageType, RouteStep};
+        
+        let cache = PriceCache::new();
+        let pool = |pool_id: &str, quote_reserve: u64| PoolPrice {
+            dex_name: "Test".to_string(),
+            base_reserve: 10_000 * 1_000_000_000,
+            quote_reserve: quote_reserve * 1_000_000,
+            slot: 1000,
+            ..PoolPrice::for_test(pool_id, "SOL/USDC", quote_reserve as f64 / 10_000.0)
+        };
+        cache.update_price(pool("p1", 1_000_000));  // 100 USDC/SOL
+        cache.update_price(pool("p2", 1_010_000));  // 101 USDC/SOL
+        
+        let step = |pool_id: &str, input: &str, output: &str| RouteStep {
+            pool_id: pool_id.to_string(),
+            dex_name: "Test".to_string(),
+            input_token: input.to_string(),
+            output_token: output.to_string(),
+            price: 0.0,
+            liquidity_base: 0,
+            liquidity_quote: 0,
+            expected_input: 0.0,
+            expected_output: 0.0,
+            price_impact_percent: 0.0,
+            effective_fee_bps: 0.0,
+            raw_input_token: None,
+            raw_output_token: None,
+        };
+        let mut path = ArbitragePath {
+            arb_type: ArbitrageType::Direct,
+            steps: vec![step("p1", "USDC", "SOL"), step("p2", "SOL", "USDC")],
+            start_token: "USDC".to_string(),
+            end_token: "USDC".to_string(),
+            input_amount: 1000.0,
+            output_amount: 0.0,
+            gross_profit: 0.0,
+            dex_fees: 0.0,
+            execution_fees: 0.0,
+            estimated_fees: 0.0,
+            net_profit: 0.0,
+            roi_percent: 0.0,
+            discovered_at: Instant::now(),
+        };
+        
+        // 发现时的 ROI 即当前缓存下的 ROI：约 +0.3%（扣除两跳 0.25% 手续费）
+        let roi = revalidate(&path, &cache).revalidated_roi().unwrap();
+        assert!(roi > 0.2 && roi < 0.4, "roi = {}", roi);
+        path.roi_percent = roi;
+        assert_eq!(revalidate(&path, &cache).status(), "confirmed");
+        
+        // 原 ROI 明显更高：仍盈利，但降级
+        path.roi_percent = roi * 2.0;
+        assert_eq!(revalidate(&path, &cache).status(), "degraded");
+        path.roi_percent = roi;
+        
+        // 第二跳价格不利变动 1%（101 -> 99.99）：机会失效
+        cache.update_price(pool("p2", 999_900));
+        match revalidate(&path, &cache) {
+            Revalidation::Invalidated { revalidated_roi: Some(r), reason: InvalidationReason::Unprofitable, .. } => {
+                assert!(r < 0.0);
+            }
+            other => panic!("expected Invalidated, got {:?}", other),
+        }
+        
+        // 池子被移除
+        cache.remove_price("p1");
+        assert!(matches!(
+            revalidate(&path, &cache),
+            Revalidation::Invalidated { reason: InvalidationReason::PoolMissing { .. }, .. }
+        ));
+    }
 }
 
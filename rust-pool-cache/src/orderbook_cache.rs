@@ -0,0 +1,101 @@
+/*!
+ * 订单簿池子注册表
+ *
+ * PriceCache 只保存单一价格和"储备量"，CLOB（Phoenix）的真实档位无法放进
+ * PoolPrice。这里按 pool_id 保存最新解析出的 CLOB 池子，路由器计算每一跳时
+ * 先查这里，命中则按档位吃单报价，否则回退到 AMM 公式。
+ */
+
+use std::sync::{Arc, OnceLock};
+use dashmap::DashMap;
+
+use crate::dex_interface::DexPool;
+
+static ORDERBOOK_POOLS: OnceLock<DashMap<String, Arc<dyn DexPool>>> = OnceLock::new();
+
+fn pools() -> &'static DashMap<String, Arc<dyn DexPool>> {
+    ORDERBOOK_POOLS.get_or_init(DashMap::new)
+}
+
+/// 注册最新的池子状态（非订单簿池子直接忽略）
+pub fn register(pool_id: &str, pool: Box<dyn DexPool>) {
+    if pool.has_orderbook() {
+        pools().insert(pool_id.to_string(), Arc::from(pool));
+    }
+}
+
+/// 移除池子（池子下线时调用）
+#[allow(dead_code)]
+pub fn remove(pool_id: &str) {
+    pools().remove(pool_id);
+}
+
+/// 按订单簿报价；未注册的池子返回 None
+pub fn quote(pool_id: &str, amount_in: f64, is_buy: bool) -> Option<f64> {
+    let pool = pools().get(pool_id)?.clone();
+    pool.get_orderbook_quote(amount_in, is_buy)
+}
+
+/// 已注册的订单簿池子数量
+#[allow(dead_code)]
+pub fn len() -> usize {
+    pools().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex_interface::DexError;
+
+    struct FlatBook {
+        price: f64,
+    }
+
+    impl DexPool for FlatBook {
+        fn dex_name(&self) -> &'static str {
+            "FlatBook"
+        }
+        fn from_account_data(_data: &[u8]) -> Result<Self, DexError> {
+            Err(DexError::InvalidData("test".to_string()))
+        }
+        fn calculate_price(&self) -> f64 {
+            self.price
+        }
+        fn get_reserves(&self) -> (u64, u64) {
+            (0, 0)
+        }
+        fn get_decimals(&self) -> (u8, u8) {
+            (9, 6)
+        }
+        fn is_active(&self) -> bool {
+            true
+        }
+        fn has_orderbook(&self) -> bool {
+            true
+        }
+        fn get_orderbook_quote(&self, amount_in: f64, is_buy: bool) -> Option<f64> {
+            Some(if is_buy { amount_in / self.price } else { amount_in * self.price })
+        }
+    }
+
+    #[test]
+    fn test_hop_prefers_orderbook_then_falls_back() {
+        use crate::dex_interface::amm_calculator::calculate_hop_output_f64;
+
+        register("book-pool", Box::new(FlatBook { price: 100.0 }));
+
+        // quote → base 走订单簿
+        let out = calculate_hop_output_f64("book-pool", "SOL/USDC", "USDC", 200.0, 1.0, 1.0, 0.0);
+        assert!((out - 2.0).abs() < 1e-9);
+        // base → quote
+        let out = calculate_hop_output_f64("book-pool", "SOL/USDC", "SOL", 2.0, 1.0, 1.0, 0.0);
+        assert!((out - 200.0).abs() < 1e-9);
+
+        // 未注册的池子回退到 AMM
+        let out = calculate_hop_output_f64("amm-pool", "SOL/USDC", "SOL", 1.0, 1000.0, 185000.0, 0.0025);
+        assert!(out > 184.3 && out < 184.4);
+
+        remove("book-pool");
+        assert!(quote("book-pool", 1.0, true).is_none());
+    }
+}
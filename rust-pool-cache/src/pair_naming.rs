@@ -0,0 +1,341 @@
+/*!
+ * 🏷️ 池子交易对自动命名
+ *
+ * `[[pools]]` 只写地址、pair 留空（或省略）时，启动和热重载时查询池子账户，
+ * 按反序列化出的 base / quote mint 生成 pair 和名称：
+ * 1. 内置符号表（常见代币 + [discovery] 目标 + LST，见 `pool_mints`）
+ * 2. Metaplex 元数据账户中的 symbol（`[pool_naming] fetch_metadata`，共用 RPC 令牌桶）
+ * 3. 都没有时用 mint 缩写，例如 "7xKX…/EPjF…"
+ *
+ * pair 顺序跟随链上 base / quote（base_reserve 一侧在前），同时补上 base_mint / quote_mint，
+ * 建图按 mint 区分代币，缩写符号的池子也能正常参与路由。生成结果按地址保存，
+ * 热重载时直接复用（不重复查询，也不会被当成改名），并在 GET /pools 中展示，方便抄回配置固定下来。
+ */
+
+use dashmap::DashMap;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use tracing::{info, warn};
+
+use crate::config::{PoolConfig, UNKNOWN_PAIR};
+use crate::pool_factory::PoolFactory;
+use crate::pool_initializer::fetch_accounts_batched;
+use crate::pool_mints;
+use crate::rpc_manager::RpcHandle;
+
+/// Metaplex Token Metadata 程序
+pub const METADATA_PROGRAM_ID: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+
+/// 元数据账户中 name 字段的偏移（key + update_authority + mint）
+const METADATA_NAME_OFFSET: usize = 1 + 32 + 32;
+
+/// 元数据符号的长度上限（Metaplex 限制为 10 字节，放宽一些兼容旧账户）
+const MAX_SYMBOL_LEN: usize = 16;
+
+/// 符号来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolSource {
+    /// 内置符号表 / 发现目标 / LST
+    Registry,
+    /// 链上元数据
+    Metadata,
+    /// mint 缩写
+    Abbreviated,
+}
+
+/// 自动生成的池子名称（GET /pools 展示，可直接抄回配置）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AutoPairName {
+    pub pair: String,
+    pub name: String,
+    pub base_mint: String,
+    pub quote_mint: String,
+    pub base_source: SymbolSource,
+    pub quote_source: SymbolSource,
+}
+
+/// pair 留空或是占位值时需要自动命名
+pub fn needs_naming(pool: &PoolConfig) -> bool {
+    let pair = pool.pair.trim();
+    pair.is_empty() || pair == UNKNOWN_PAIR
+}
+
+/// mint 缩写："7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU" -> "7xKX…"
+pub fn abbreviate_mint(mint: &str) -> String {
+    let prefix: String = mint.chars().take(4).collect();
+    format!("{}…", prefix)
+}
+
+/// 可以放进 pair 的符号：去掉补齐用的 '\0' 和空白，拒绝会破坏 pair 解析的字符
+fn usable_symbol(raw: &str) -> Option<String> {
+    let symbol = raw.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    let valid = !symbol.is_empty()
+        && symbol.len() <= MAX_SYMBOL_LEN
+        && !symbol.chars().any(|c| c.is_whitespace() || c.is_control() || "/@()…".contains(c));
+    valid.then(|| symbol.to_string())
+}
+
+/// 读取 borsh 字符串（u32 长度 + UTF-8 字节），返回 (字符串, 下一个偏移)
+fn read_borsh_string(data: &[u8], offset: usize) -> Option<(String, usize)> {
+    let len_bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+    let start = offset + 4;
+    let end = start.checked_add(u32::from_le_bytes(len_bytes) as usize)?;
+    let bytes = data.get(start..end)?;
+    Some((String::from_utf8_lossy(bytes).into_owned(), end))
+}
+
+/// 从 Metaplex 元数据账户中解析 symbol（布局：key、update_authority、mint、name、symbol、uri ...）
+pub fn parse_metadata_symbol(data: &[u8]) -> Option<String> {
+    let (_, symbol_offset) = read_borsh_string(data, METADATA_NAME_OFFSET)?;
+    let (symbol, _) = read_borsh_string(data, symbol_offset)?;
+    usable_symbol(&symbol)
+}
+
+/// mint 的元数据账户地址（PDA: ["metadata", program, mint]）
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    let program = Pubkey::from_str(METADATA_PROGRAM_ID).expect("valid metadata program id");
+    Pubkey::find_program_address(&[b"metadata", program.as_ref(), mint.as_ref()], &program).0
+}
+
+/// 按 mint 生成 pair 和名称；`lookup` 返回已知符号及其来源，没有时用 mint 缩写
+pub fn synthesize(
+    base_mint: &str,
+    quote_mint: &str,
+    dex_label: &str,
+    lookup: impl Fn(&str) -> Option<(String, SymbolSource)>,
+) -> AutoPairName {
+    let resolve = |mint: &str| lookup(mint).unwrap_or_else(|| (abbreviate_mint(mint), SymbolSource::Abbreviated));
+    let (base_symbol, base_source) = resolve(base_mint);
+    let (quote_symbol, quote_source) = resolve(quote_mint);
+    let pair = format!("{}/{}", base_symbol, quote_symbol);
+    AutoPairName {
+        name: format!("{} ({})", pair, dex_label),
+        pair,
+        base_mint: base_mint.to_string(),
+        quote_mint: quote_mint.to_string(),
+        base_source,
+        quote_source,
+    }
+}
+
+/// 把生成结果写回池子配置：名称只在留空时填写，mint 只在都没配置时填写
+pub fn apply(pool: &mut PoolConfig, named: &AutoPairName) {
+    pool.pair = named.pair.clone();
+    if pool.name.is_empty() {
+        pool.name = named.name.clone();
+    }
+    if pool.base_mint.is_none() && pool.quote_mint.is_none() {
+        pool.base_mint = Some(named.base_mint.clone());
+        pool.quote_mint = Some(named.quote_mint.clone());
+    }
+}
+
+/// 自动命名器（启动时使用，之后由热重载器和 API 共享）
+pub struct PairNamer {
+    rpc: RpcHandle,
+    fetch_metadata: bool,
+    /// 池子地址 -> 生成结果（热重载时复用）
+    named: DashMap<String, AutoPairName>,
+    /// mint -> 元数据中的符号（None = 查询过但没有可用符号）
+    metadata_symbols: DashMap<String, Option<String>>,
+}
+
+impl PairNamer {
+    pub fn new(rpc: RpcHandle, fetch_metadata: bool) -> Self {
+        Self {
+            rpc,
+            fetch_metadata,
+            named: DashMap::new(),
+            metadata_symbols: DashMap::new(),
+        }
+    }
+
+    /// 池子的生成结果（不是自动命名的池子返回 None）
+    pub fn get(&self, address: &str) -> Option<AutoPairName> {
+        self.named.get(address).map(|entry| entry.clone())
+    }
+
+    /// 给 pair 为空的池子命名，返回本次新查询并命名的池子数
+    ///
+    /// 已经命名过的地址直接复用之前的结果；账户查询或解析失败的池子保持原样
+    /// （名称为空时用池子地址），下一次热重载时重试。
+    pub async fn resolve(&self, pools: &mut [PoolConfig]) -> usize {
+        let mut pending = Vec::new();
+        for (index, pool) in pools.iter_mut().enumerate() {
+            if !needs_naming(pool) {
+                continue;
+            }
+            match self.get(&pool.address) {
+                Some(named) => apply(pool, &named),
+                None => pending.push(index),
+            }
+        }
+        if pending.is_empty() {
+            return 0;
+        }
+
+        // 🪣 批量查询池子账户，解析出 base / quote mint
+        let pubkeys: Vec<Pubkey> = pending.iter()
+            .filter_map(|&index| Pubkey::from_str(&pools[index].address).ok())
+            .collect();
+        let accounts = fetch_accounts_batched(&self.rpc, &pubkeys).await;
+
+        let mut parsed = Vec::new();
+        for &index in &pending {
+            let pool = &pools[index];
+            let account = Pubkey::from_str(&pool.address).ok().and_then(|key| accounts.accounts.get(&key));
+            let decoded = account.map(|account| {
+                PoolFactory::create_pool_for(&pool.pool_type, Some(&account.owner.to_string()), &account.data)
+            });
+            let (chain_mints, dex_label) = match decoded {
+                Some(Ok(dex_pool)) => (dex_pool.get_mints(), dex_pool.dex_name().to_string()),
+                _ => (None, pool.pool_type.clone()),
+            };
+            // 配置了 mint 时以配置为准（决定 base / quote 方向）
+            let mints = match (&pool.base_mint, &pool.quote_mint) {
+                (Some(base), Some(quote)) => Some((base.clone(), quote.clone())),
+                _ => chain_mints.map(|(base, quote)| (base.to_string(), quote.to_string())),
+            };
+            match mints {
+                Some((base, quote)) => parsed.push((index, base, quote, dex_label)),
+                None => {
+                    let pool = &mut pools[index];
+                    warn!("🏷️  Could not resolve mints for pool {}, leaving pair unnamed", pool.address);
+                    if pool.name.is_empty() {
+                        pool.name = pool.address.clone();
+                    }
+                }
+            }
+        }
+
+        if self.fetch_metadata {
+            let mints: Vec<&str> = parsed.iter().flat_map(|(_, base, quote, _)| [base.as_str(), quote.as_str()]).collect();
+            self.fetch_metadata_symbols(&mints).await;
+        }
+
+        let registry = pool_mints::global();
+        let lookup = |mint: &str| {
+            registry.registered_symbol(mint)
+                .map(|symbol| (symbol, SymbolSource::Registry))
+                .or_else(|| {
+                    self.metadata_symbols.get(mint)
+                        .and_then(|entry| entry.clone())
+                        .map(|symbol| (symbol, SymbolSource::Metadata))
+                })
+        };
+        for (index, base, quote, dex_label) in &parsed {
+            let named = synthesize(base, quote, dex_label, lookup);
+            let pool = &mut pools[*index];
+            apply(pool, &named);
+            info!(
+                "🏷️  Named pool {} as {} (base {} from {:?}, quote {} from {:?})",
+                pool.address, pool.name, named.base_mint, named.base_source, named.quote_mint, named.quote_source
+            );
+            self.named.insert(pool.address.clone(), named);
+        }
+        parsed.len()
+    }
+
+    /// 查询内置符号表里没有、尚未查询过的 mint 的元数据符号
+    async fn fetch_metadata_symbols(&self, mints: &[&str]) {
+        let registry = pool_mints::global();
+        let unknown: Vec<(String, Pubkey)> = mints.iter()
+            .filter(|mint| registry.registered_symbol(mint).is_none() && !self.metadata_symbols.contains_key(**mint))
+            .filter_map(|mint| Pubkey::from_str(mint).ok().map(|key| (mint.to_string(), metadata_address(&key))))
+            .collect();
+        if unknown.is_empty() {
+            return;
+        }
+
+        let addresses: Vec<Pubkey> = unknown.iter().map(|(_, address)| *address).collect();
+        let accounts = fetch_accounts_batched(&self.rpc, &addresses).await;
+        for (mint, address) in unknown {
+            let symbol = accounts.accounts.get(&address).and_then(|account| parse_metadata_symbol(&account.data));
+            self.metadata_symbols.insert(mint, symbol);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    const UNKNOWN_MINT: &str = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU";
+
+    fn metadata_account(name: &str, symbol: &str) -> Vec<u8> {
+        let mut data = vec![4u8];
+        data.extend_from_slice(&[1u8; 64]);
+        for (value, padded) in [(name, 32), (symbol, 10)] {
+            let mut bytes = value.as_bytes().to_vec();
+            bytes.resize(padded, 0);
+            data.extend_from_slice(&(padded as u32).to_le_bytes());
+            data.extend_from_slice(&bytes);
+        }
+        data
+    }
+
+    fn pool(pair: &str, name: &str) -> PoolConfig {
+        PoolConfig {
+            address: "PoolAddr111".to_string(),
+            name: name.to_string(),
+            pair: pair.to_string(),
+            pool_type: "amm_v4".to_string(),
+            fee_bps: None,
+            max_age_ms: None,
+            base_mint: None,
+            quote_mint: None,
+            max_price_jump_percent: None,
+            commitment: None,
+            encoding: None,
+            priority: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_metadata_symbol() {
+        assert_eq!(parse_metadata_symbol(&metadata_account("Bonk", "Bonk")), Some("Bonk".to_string()));
+        // 含 '/' 或空白的符号会破坏 pair 解析
+        assert_eq!(parse_metadata_symbol(&metadata_account("Weird", "A/B")), None);
+        assert_eq!(parse_metadata_symbol(&metadata_account("Empty", "")), None);
+        assert_eq!(parse_metadata_symbol(&[4u8; 70]), None);
+    }
+
+    #[test]
+    fn test_synthesize_falls_back_to_abbreviated_mint() {
+        let lookup = |mint: &str| (mint == USDC).then(|| ("USDC".to_string(), SymbolSource::Registry));
+        let named = synthesize(UNKNOWN_MINT, USDC, "Raydium AMM V4", lookup);
+
+        assert_eq!(named.pair, "7xKX…/USDC");
+        assert_eq!(named.name, "7xKX…/USDC (Raydium AMM V4)");
+        assert_eq!((named.base_source, named.quote_source), (SymbolSource::Abbreviated, SymbolSource::Registry));
+        // 缩写后的 pair 仍然能拆出两侧符号
+        assert_eq!(
+            pool_mints::pair_symbols(&named.name),
+            Some(("7xKX…".to_string(), "USDC".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_apply_keeps_configured_name_and_mints() {
+        assert!(needs_naming(&pool("", "")));
+        assert!(needs_naming(&pool(UNKNOWN_PAIR, "My pool")));
+        assert!(!needs_naming(&pool("SOL/USDC", "SOL/USDC")));
+
+        let named = synthesize(UNKNOWN_MINT, USDC, "Orca Whirlpool", |_| None);
+        let mut unnamed = pool("", "");
+        apply(&mut unnamed, &named);
+        assert_eq!(unnamed.pair, "7xKX…/EPjF…");
+        assert_eq!(unnamed.name, "7xKX…/EPjF… (Orca Whirlpool)");
+        assert_eq!(unnamed.base_mint.as_deref(), Some(UNKNOWN_MINT));
+
+        let mut custom = pool("", "My pool");
+        custom.base_mint = Some(USDC.to_string());
+        custom.quote_mint = Some(UNKNOWN_MINT.to_string());
+        apply(&mut custom, &named);
+        assert_eq!(custom.name, "My pool");
+        assert_eq!(custom.base_mint.as_deref(), Some(USDC));
+    }
+}
@@ -0,0 +1,233 @@
+/*!
+ * 扫描管线：WebSocket 事件 → Coordinator → Calculator
+ *
+ * 事件 channel、计算任务 channel、Coordinator 和 Calculator 任务只在这里创建一次，
+ * 交给 WebSocket 客户端的 event_tx 与 Calculator 消费的计算任务必然属于同一条管线。
+ *
+ * 🛟 两个任务都受监督：panic 后重新启动时沿用同一组 channel，Calculator 的状态
+ * （去重窗口、告警冷却等）由管线持有，重启后交给新的 Calculator 继续使用。
+ */
+
+use std::future::Future;
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc, Mutex, OwnedMutexGuard};
+use tokio::task::JoinHandle;
+use tracing::info;
+
+use crate::coordinator::{CalculationTask, Coordinator, CoordinatorConfig, CoordinatorStats, PriceChangeEvent};
+use crate::router_direct::DirectArbTable;
+use crate::supervisor::{spawn_supervised, Supervisor};
+
+/// Calculator 侧的计算任务流（重启后的 Calculator 拿到同一条流）
+#[derive(Clone)]
+pub struct CalculationTasks {
+    calc_rx: Arc<Mutex<mpsc::Receiver<CalculationTask>>>,
+    shutdown_rx: Arc<Mutex<broadcast::Receiver<()>>>,
+}
+
+impl CalculationTasks {
+    /// 等待下一个计算任务
+    ///
+    /// Coordinator 退出或收到关闭信号时返回 None。调用方只在两次扫描之间调用，
+    /// 进行中的扫描总是完整结束。
+    pub async fn next(&mut self) -> Option<CalculationTask> {
+        let mut calc_rx = self.calc_rx.lock().await;
+        let mut shutdown_rx = self.shutdown_rx.lock().await;
+        tokio::select! {
+            task = calc_rx.recv() => task,
+            _ = shutdown_rx.recv() => {
+                info!("🛑 Calculator received shutdown signal");
+                None
+            }
+        }
+    }
+}
+
+/// 管线句柄
+pub struct PipelineHandles {
+    /// 价格变化事件发送端（注册给 WebSocket 客户端）
+    pub event_tx: mpsc::Sender<PriceChangeEvent>,
+    /// Coordinator 时钟心跳（unix 毫秒，SLO 漏tick检测）
+    pub coordinator_tick_heartbeat: Arc<AtomicU64>,
+    /// Coordinator 触发统计（/metrics）
+    pub coordinator_stats: Arc<tokio::sync::Mutex<CoordinatorStats>>,
+    pub coordinator: JoinHandle<()>,
+    pub calculator: JoinHandle<()>,
+}
+
+/// 创建 channel 并启动 Coordinator 与 Calculator
+///
+/// `calculator` 拿到计算任务流和 `state` 的独占访问后运行自己的主循环，任务流结束时返回；
+/// panic 后监督器再次调用它（同一条任务流、同一份 state）。
+/// 传入 `direct_table` 时 Coordinator 对每个事件做两跳直接套利检查。
+pub fn spawn<S, F, Fut>(
+    config: CoordinatorConfig,
+    shutdown_tx: &broadcast::Sender<()>,
+    direct_table: Option<Arc<DirectArbTable>>,
+    supervisor: &Supervisor,
+    state: S,
+    calculator: F,
+) -> PipelineHandles
+where
+    S: Send + 'static,
+    F: Fn(CalculationTasks, OwnedMutexGuard<S>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (event_tx, event_rx) = mpsc::channel(config.event_channel_capacity);
+    let (calc_tx, calc_rx) = mpsc::channel(config.calc_channel_capacity);
+    info!("   └─ Event channel capacity: {}", config.event_channel_capacity);
+    info!("   └─ Calculation channel capacity: {} (prevents task堆积)", config.calc_channel_capacity);
+
+    let coordinator = Coordinator::new(config, event_rx, calc_tx)
+        .with_shutdown(shutdown_tx.subscribe());
+    let coordinator = match direct_table {
+        Some(table) => coordinator.with_direct_table(table),
+        None => coordinator,
+    };
+    let coordinator_tick_heartbeat = coordinator.tick_heartbeat();
+    let coordinator_stats = coordinator.stats_handle();
+    // 正常退出后监督任务结束，协调器随之 drop，Calculator 的 calc_rx 结束
+    let coordinator = Arc::new(Mutex::new(coordinator));
+    let coordinator = spawn_supervised("coordinator", supervisor, move || {
+        let coordinator = coordinator.clone();
+        async move {
+            info!("🎯 Coordinator task started");
+            coordinator.lock().await.run_restartable().await;
+        }
+    });
+
+    let tasks = CalculationTasks {
+        calc_rx: Arc::new(Mutex::new(calc_rx)),
+        shutdown_rx: Arc::new(Mutex::new(shutdown_tx.subscribe())),
+    };
+    let state = Arc::new(Mutex::new(state));
+    let calculator = Arc::new(calculator);
+    let calculator = spawn_supervised("calculator", supervisor, move || {
+        let tasks = tasks.clone();
+        let state = state.clone();
+        let calculator = calculator.clone();
+        async move {
+            let state = state.lock_owned().await;
+            calculator(tasks, state).await
+        }
+    });
+
+    PipelineHandles {
+        event_tx,
+        coordinator_tick_heartbeat,
+        coordinator_stats,
+        coordinator,
+        calculator,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinator::TriggerType;
+    use std::time::{Duration, Instant};
+
+    async fn next_task(rx: &mut mpsc::UnboundedReceiver<CalculationTask>) -> Option<CalculationTask> {
+        tokio::time::timeout(Duration::from_secs(2), rx.recv()).await.ok().flatten()
+    }
+
+    #[tokio::test]
+    async fn test_price_event_reaches_calculator() {
+        let config = CoordinatorConfig {
+            tick_interval_ms: 60_000, // 只有启动时的第一次 tick
+            ..Default::default()
+        };
+        let (shutdown_tx, _) = broadcast::channel(1);
+        let (seen_tx, mut seen_rx) = mpsc::unbounded_channel();
+
+        let pipeline = spawn(config, &shutdown_tx, None, &Supervisor::default(), seen_tx, |mut tasks, seen_tx| async move {
+            while let Some(task) = tasks.next().await {
+                let _ = seen_tx.send(task);
+            }
+        });
+
+        // 启动时的时钟 tick 先被 Calculator 取走，计算 channel 空出来
+        let first = next_task(&mut seen_rx).await.expect("clock task");
+        assert_eq!(first.trigger_type, TriggerType::Clock);
+
+        pipeline.event_tx.send(PriceChangeEvent {
+            pool_id: "pool".to_string(),
+            pool_name: "SOL/USDC (Raydium)".to_string(),
+            pair: "SOL/USDC".to_string(),
+            price_change_percent: 0.01, // 1% > 0.2% 阈值
+            old_price: Some(185.0),
+            new_price: 186.85,
+            timestamp: Instant::now(),
+            ingest: None,
+        }).await.unwrap();
+
+        let task = next_task(&mut seen_rx).await.expect("event task");
+        assert_eq!(task.trigger_type, TriggerType::Event);
+        assert_eq!(task.trigger_source, "SOL/USDC (Raydium) (SOL/USDC)");
+
+        // 关闭信号：两个任务都退出
+        shutdown_tx.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(2), async {
+            pipeline.coordinator.await.unwrap();
+            pipeline.calculator.await.unwrap();
+        }).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_calculator_restarts_after_panic_with_same_state() {
+        use crate::config::SupervisorConfig;
+        use crate::supervisor::SupervisorPolicy;
+
+        let config = CoordinatorConfig {
+            tick_interval_ms: 60_000,
+            ..Default::default()
+        };
+        let (shutdown_tx, _) = broadcast::channel(1);
+        let supervisor = Supervisor::new(SupervisorPolicy::from_config(&SupervisorConfig {
+            max_restarts_per_hour: 3,
+            restart_backoff_ms: 1,
+            max_restart_backoff_ms: 1,
+        }));
+        let (seen_tx, mut seen_rx) = mpsc::unbounded_channel();
+
+        // state = (处理过的任务数, 输出)；第一个任务（启动时的时钟 tick）让 Calculator panic
+        let pipeline = spawn(config, &shutdown_tx, None, &supervisor, (0u32, seen_tx), |mut tasks, mut state| async move {
+            while let Some(task) = tasks.next().await {
+                state.0 += 1;
+                if state.0 == 1 {
+                    panic!("scan failed");
+                }
+                let _ = state.1.send((state.0, task));
+            }
+        });
+
+        let restarted = || supervisor.statuses(Instant::now()).iter().any(|t| t.name == "calculator" && t.restarts == 1);
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while !restarted() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        }).await.expect("calculator restarted");
+
+        // 重启后的 Calculator 接着消费同一条任务流，计数延续
+        pipeline.event_tx.send(PriceChangeEvent {
+            pool_id: "pool".to_string(),
+            pool_name: "SOL/USDC (Raydium)".to_string(),
+            pair: "SOL/USDC".to_string(),
+            price_change_percent: 0.01,
+            old_price: Some(185.0),
+            new_price: 186.85,
+            timestamp: Instant::now(),
+            ingest: None,
+        }).await.unwrap();
+        let (count, task) = tokio::time::timeout(Duration::from_secs(2), seen_rx.recv()).await.unwrap().unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(task.trigger_type, TriggerType::Event);
+
+        shutdown_tx.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(2), async {
+            pipeline.coordinator.await.unwrap();
+            pipeline.calculator.await.unwrap();
+        }).await.unwrap();
+    }
+}
@@ -0,0 +1,130 @@
+/*!
+ * 🌐 池子原始账户数据缓存
+ *
+ * vault 依赖型池子收到 vault 更新时，需要用最近一次的池子账户数据重新解析价格。
+ * 每次池子通知都会刷新缓存；超过容量时淘汰最久没有收到通知的池子，
+ * 被淘汰的池子在下一次账户通知时重新缓存（期间它的 vault 更新不触发重算）。
+ */
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::prometheus::CacheSize;
+
+/// 默认最多缓存的池子数（840–1500 字节的账户，约 1.5 MB）
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// 按最近更新时间 LRU 淘汰的池子账户数据
+#[derive(Debug)]
+pub struct PoolDataCache {
+    /// 池子地址 -> (更新序号, 账户数据)
+    entries: HashMap<String, (u64, Vec<u8>)>,
+    /// 更新序号 -> 池子地址（序号最小的最久未更新）
+    recency: BTreeMap<u64, String>,
+    next_seq: u64,
+    capacity: usize,
+    bytes: usize,
+    evictions: u64,
+}
+
+impl Default for PoolDataCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl PoolDataCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            next_seq: 0,
+            capacity: capacity.max(1),
+            bytes: 0,
+            evictions: 0,
+        }
+    }
+
+    /// 写入（或刷新）池子数据，超过容量时淘汰最久未更新的池子
+    pub fn insert(&mut self, address: String, data: Vec<u8>) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.bytes += data.len();
+        if let Some((old_seq, old_data)) = self.entries.insert(address.clone(), (seq, data)) {
+            self.recency.remove(&old_seq);
+            self.bytes -= old_data.len();
+        }
+        self.recency.insert(seq, address);
+
+        while self.entries.len() > self.capacity {
+            let Some((_, oldest)) = self.recency.pop_first() else { break };
+            if let Some((_, data)) = self.entries.remove(&oldest) {
+                self.bytes -= data.len();
+                self.evictions += 1;
+            }
+        }
+    }
+
+    /// 读取不改变淘汰顺序（只按更新时间淘汰）
+    pub fn get(&self, address: &str) -> Option<&Vec<u8>> {
+        self.entries.get(address).map(|(_, data)| data)
+    }
+
+    pub fn remove(&mut self, address: &str) -> Option<Vec<u8>> {
+        let (seq, data) = self.entries.remove(address)?;
+        self.recency.remove(&seq);
+        self.bytes -= data.len();
+        Some(data)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// /metrics 报告的大小
+    pub fn size(&self) -> CacheSize {
+        CacheSize {
+            cache: "pool_data",
+            entries: self.entries.len(),
+            capacity: Some(self.capacity),
+            bytes: Some(self.bytes),
+            evictions: self.evictions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicts_least_recently_updated() {
+        let mut cache = PoolDataCache::new(2);
+        cache.insert("a".to_string(), vec![0; 10]);
+        cache.insert("b".to_string(), vec![0; 20]);
+        // 刷新 a：b 变成最久未更新
+        cache.insert("a".to_string(), vec![0; 30]);
+        assert!(cache.get("b").is_some());
+        cache.insert("c".to_string(), vec![0; 40]);
+
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.get("a").map(Vec::len), Some(30));
+        assert_eq!(cache.size(), CacheSize {
+            cache: "pool_data",
+            entries: 2,
+            capacity: Some(2),
+            bytes: Some(70),
+            evictions: 1,
+        });
+
+        assert_eq!(cache.remove("a").map(|d| d.len()), Some(30));
+        assert_eq!((cache.len(), cache.size().bytes), (1, Some(40)));
+    }
+}
@@ -1,4 +1,11 @@
+use crate::config::DexesConfig;
 use crate::dex_interface::{DexError, DexPool};
+use crate::pool_stats::DegradedReason;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::panic::AssertUnwindSafe;
+use std::sync::{OnceLock, RwLock};
+use tracing::warn;
 use crate::deserializers::{
     LifinityV2PoolState, MeteoraPoolState, MeteoraPoolStateImproved, RaydiumAmmInfo, RaydiumClmmPoolState, 
     AlphaQPoolState, SolFiV2PoolState, HumidiFiPoolState, GoonFiPoolState,
@@ -6,6 +13,118 @@ use crate::deserializers::{
     PancakeSwapPoolState, PhoenixMarketState, PhoenixMarketSDK, PhoenixMarketFull, OpenBookMarketState
 };
 
+/// 🔒 池子类型 -> 期望的 owner program id
+///
+/// 配置的 pool_type 指向错误地址时，数据可能因布局大小巧合"反序列化成功"，
+/// 产生静默错误的价格。激活前用 owner 校验拦截这类配置错误。
+/// Aquifer 的 program id 尚未确认，暂不校验。
+const EXPECTED_OWNERS: &[(&str, &str)] = &[
+    ("amm_v4", "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8"),
+    ("clmm", "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK"),
+    ("lifinity_v2", "2wT8Yq49kHgDzXuPxZSaeLaH1qbmGXtEyPy64bL7aD3c"),
+    ("meteora_dlmm", "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo"),
+    ("alphaq", "ALPHAQmeA7bjrVuccPsYPiCvsi428SNwte66Srvs4pHA"),
+    ("solfi_v2", "SV2EYYJyRz2YhfXwXnhNAevDEui5Q6yrfyo13WtupPF"),
+    ("humidifi", "9H6tua7jkLhdm3w8BvgpTn5LZNU7g4ZynDmCiNN3q6Rp"),
+    ("goonfi", "goonERTdGsjnkZqWuVjs73BZ3Pb9qoCUdBUL17BnS5j"),
+    ("tesserav", "TessVdML9pBGgG9yGks7o4HewRaXVAMuoVj4x83GLQH"),
+    ("stabble", "swapNyd8XiQwJ6ianp9snpu4brUqFxadzvHebnAXjJZ"),
+    ("whirlpool", "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc"),
+    ("pancakeswap", "HpNfyc2Saw7RKkQd8nEL4khUcuPhQ7WwY1B2qjx8jxFq"),
+    ("phoenix", "PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY"),
+    ("openbook_v2", "opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb"),
+];
+
+/// 所有已知反序列化器的规范名称（与 canonical_pool_type 一致，/dexes 按此顺序列出）
+pub const KNOWN_POOL_TYPES: &[&str] = &[
+    "amm_v4", "clmm", "lifinity_v2", "meteora_dlmm", "alphaq", "solfi_v2", "humidifi", "goonfi",
+    "tesserav", "stabble", "aquifer", "whirlpool", "pancakeswap", "phoenix", "openbook_v2",
+];
+
+static DEX_FILTER: OnceLock<RwLock<DexFilter>> = OnceLock::new();
+
+fn dex_filter() -> &'static RwLock<DexFilter> {
+    DEX_FILTER.get_or_init(|| RwLock::new(DexFilter::default()))
+}
+
+/// 🔌 DEX 启用开关（来自 [dexes]，按规范名称比较）
+#[derive(Debug, Clone, Default)]
+pub struct DexFilter {
+    disabled: HashSet<&'static str>,
+    allowlist: Option<HashSet<&'static str>>,
+}
+
+impl DexFilter {
+    /// 从配置构建；无法识别的 DEX 名称会被忽略并警告
+    pub fn from_config(config: &DexesConfig) -> Self {
+        let canonical = |name: &str| {
+            let canonical = PoolFactory::canonical_pool_type(name);
+            if canonical.is_none() {
+                warn!("⚠️  [dexes] unknown DEX '{}' ignored (known: {})", name, KNOWN_POOL_TYPES.join(", "));
+            }
+            canonical
+        };
+        
+        Self {
+            disabled: config.switches.iter()
+                .filter(|(_, enabled)| !**enabled)
+                .filter_map(|(name, _)| canonical(name))
+                .collect(),
+            allowlist: config.allowlist.as_ref()
+                .map(|names| names.iter().filter_map(|name| canonical(name)).collect()),
+        }
+    }
+    
+    /// pool_type 对应的 DEX 是否启用（未知类型不在这里拦截，交给 create_pool 报 UnknownPoolType）
+    pub fn is_enabled(&self, pool_type: &str) -> bool {
+        match PoolFactory::canonical_pool_type(pool_type) {
+            Some(canonical) => {
+                !self.disabled.contains(canonical)
+                    && self.allowlist.as_ref().is_none_or(|allowed| allowed.contains(canonical))
+            }
+            None => true,
+        }
+    }
+    
+    /// 被禁用的已知 DEX（按 KNOWN_POOL_TYPES 顺序）
+    pub fn disabled_dexes(&self) -> Vec<&'static str> {
+        KNOWN_POOL_TYPES.iter().copied().filter(|t| !self.is_enabled(t)).collect()
+    }
+}
+
+/// owner 校验结果（激活前记录，供 /pools 调试端点展示）
+#[derive(Debug, Clone, Serialize)]
+pub struct OwnerCheck {
+    pub pool_type: String,
+    pub owner: String,
+    pub expected_owner: Option<String>,
+    /// true = 校验通过（或类型未登记 program id）
+    pub verified: bool,
+    /// 不匹配时推断的正确类型
+    pub suggested_type: Option<String>,
+}
+
+impl OwnerCheck {
+    /// 拒绝激活时池子降级的原因
+    pub fn degraded_reason(&self) -> Option<DegradedReason> {
+        if self.verified {
+            return None;
+        }
+        Some(DegradedReason::OwnerMismatch {
+            expected: self.expected_owner.clone().unwrap_or_else(|| "?".to_string()),
+            actual: self.owner.clone(),
+        })
+    }
+
+    /// 拒绝激活的原因（owner_mismatch{expected, actual}）
+    pub fn reason(&self) -> Option<String> {
+        self.degraded_reason().map(|reason| reason.to_string())
+    }
+}
+
+/// create_pool_all 的单项结果：(pool_type, 该布局的解析结果)
+pub type PoolParseAttempt = (&'static str, Result<Box<dyn DexPool>, DexError>);
+
 /// Factory for creating DEX pool instances
 /// 
 /// This factory enables dynamic creation of pool instances based on pool type,
@@ -13,6 +132,25 @@ use crate::deserializers::{
 pub struct PoolFactory;
 
 impl PoolFactory {
+    /// 设置全局 DEX 开关（启动时按 [dexes] 调用）
+    pub fn set_dex_filter(filter: DexFilter) {
+        *dex_filter().write().unwrap() = filter;
+    }
+    
+    /// pool_type 对应的 DEX 是否启用
+    pub fn is_dex_enabled(pool_type: &str) -> bool {
+        dex_filter().read().unwrap().is_enabled(pool_type)
+    }
+    
+    /// 被禁用的 DEX 返回 DexDisabled
+    fn ensure_dex_enabled(pool_type: &str) -> Result<(), DexError> {
+        if Self::is_dex_enabled(pool_type) {
+            return Ok(());
+        }
+        let dex = Self::canonical_pool_type(pool_type).unwrap_or(pool_type);
+        Err(DexError::DexDisabled(dex.to_string()))
+    }
+    
     /// Create a pool instance from account data
     /// 
     /// # Arguments
@@ -28,6 +166,8 @@ impl PoolFactory {
     /// let price = pool.calculate_price();
     /// ```
     pub fn create_pool(pool_type: &str, data: &[u8]) -> Result<Box<dyn DexPool>, DexError> {
+        Self::ensure_dex_enabled(pool_type)?;
+        
         match pool_type.to_lowercase().as_str() {
             // Raydium AMM V4
             "amm_v4" | "ammv4" | "raydium_v4" | "raydiumv4" => {
@@ -127,6 +267,127 @@ impl PoolFactory {
         }
     }
     
+    /// 🔑 按账户 owner（program id）选择反序列化器
+    ///
+    /// 数据大小相同的 DEX 仅凭长度无法区分（曾把 Lifinity 池子识别成 AlphaQ），
+    /// owner 在 EXPECTED_OWNERS 中时只尝试该 program 的反序列化器；
+    /// owner 未登记时才回退到 create_pool_auto_detect 的大小探测。
+    pub fn create_pool_with_owner(owner: &str, data: &[u8]) -> Result<Box<dyn DexPool>, DexError> {
+        let mut last_error = None;
+        for (pool_type, _) in EXPECTED_OWNERS.iter().filter(|(_, program)| *program == owner) {
+            match Self::create_pool(pool_type, data) {
+                Ok(pool) => return Ok(pool),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        match last_error {
+            Some(e) => Err(e),
+            None => Self::create_pool_auto_detect(data),
+        }
+    }
+
+    /// 按配置的 pool_type 解析；pool_type 为 "unknown" / 空时按 owner（有的话）或数据大小探测
+    pub fn create_pool_for(pool_type: &str, owner: Option<&str>, data: &[u8]) -> Result<Box<dyn DexPool>, DexError> {
+        if pool_type.is_empty() || pool_type == "unknown" {
+            match owner {
+                Some(owner) => Self::create_pool_with_owner(owner, data),
+                None => Self::create_pool_auto_detect(data),
+            }
+        } else {
+            Self::create_pool(pool_type, data)
+        }
+    }
+
+    /// 将 pool_type 别名归一化为规范名称（与 create_pool 的匹配分支一致）
+    pub fn canonical_pool_type(pool_type: &str) -> Option<&'static str> {
+        match pool_type.to_lowercase().as_str() {
+            "amm_v4" | "ammv4" | "raydium_v4" | "raydiumv4" => Some("amm_v4"),
+            "clmm" | "raydium_clmm" | "raydiumclmm" => Some("clmm"),
+            "lifinity_v2" | "lifinityv2" | "lifinity" => Some("lifinity_v2"),
+            "meteora_dlmm" | "meteora" | "dlmm" => Some("meteora_dlmm"),
+            "alphaq" | "alpha_q" => Some("alphaq"),
+            "solfi_v2" | "solfiv2" | "solfi" => Some("solfi_v2"),
+            "humidifi" | "humidi_fi" | "humid" => Some("humidifi"),
+            "goonfi" | "goon_fi" | "goon" => Some("goonfi"),
+            "tesserav" | "tessera_v" | "tessera" => Some("tesserav"),
+            "stabble" | "stabble_swap" | "stabbleswap" => Some("stabble"),
+            "aquifer" | "aqui_fer" | "aqui" => Some("aquifer"),
+            "whirlpool" | "orca_whirlpool" | "orcawhirlpool" | "orca" => Some("whirlpool"),
+            "pancakeswap" | "pancake_swap" | "pancake" | "pcs" => Some("pancakeswap"),
+            "phoenix" | "phoenix_full" | "phoenix_sdk" | "phoenix_placeholder"
+            | "phoenix_simple" | "phoenix_clob" | "phoenixclob" => Some("phoenix"),
+            "openbook_v2" | "openbookv2" | "openbook" | "obv2" => Some("openbook_v2"),
+            _ => None,
+        }
+    }
+    
+    /// 获取 pool_type 期望的 owner program id（未登记的类型返回 None）
+    pub fn expected_owner(pool_type: &str) -> Option<&'static str> {
+        let canonical = Self::canonical_pool_type(pool_type)?;
+        EXPECTED_OWNERS
+            .iter()
+            .find(|(t, _)| *t == canonical)
+            .map(|(_, owner)| *owner)
+    }
+    
+    /// 🔒 校验账户 owner 是否与 pool_type 期望的 program 一致
+    ///
+    /// 未登记 program id 的类型（如 Aquifer、unknown）直接放行
+    pub fn verify_owner(pool_type: &str, owner: &str) -> Result<(), DexError> {
+        match Self::expected_owner(pool_type) {
+            Some(expected) if expected != owner => Err(DexError::OwnerMismatch {
+                expected: expected.to_string(),
+                actual: owner.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+    
+    /// 完整的 owner 检查：校验 + 不匹配时给出建议类型
+    pub fn check_owner(pool_type: &str, owner: &str, data: &[u8]) -> OwnerCheck {
+        let verified = Self::verify_owner(pool_type, owner).is_ok();
+        OwnerCheck {
+            pool_type: pool_type.to_string(),
+            owner: owner.to_string(),
+            expected_owner: Self::expected_owner(pool_type).map(|s| s.to_string()),
+            verified,
+            suggested_type: if verified {
+                None
+            } else {
+                Self::suggest_pool_type(owner, data).map(|s| s.to_string())
+            },
+        }
+    }
+    
+    /// owner 不匹配时，根据实际 owner 推断正确的 pool_type
+    ///
+    /// 只建议该 program 下能成功解析这份数据的登记类型；都解析不了时返回 None
+    pub fn suggest_pool_type(owner: &str, data: &[u8]) -> Option<&'static str> {
+        EXPECTED_OWNERS
+            .iter()
+            .filter(|(_, program)| *program == owner)
+            .map(|(t, _)| *t)
+            .find(|t| Self::create_pool(t, data).is_ok())
+    }
+    
+    /// 🔍 用每个已知反序列化器尝试解析（按 KNOWN_POOL_TYPES 顺序，pool-probe 识别未知账户用）
+    ///
+    /// 与 create_pool_auto_detect 不同，不在第一个成功处停止：同一份数据可能被多个
+    /// 大小相同的布局"解析成功"，由调用方结合 owner 判断哪个可信。
+    /// 任意字节喂给不匹配的布局时，某个反序列化器 panic 只算作该类型不匹配。
+    pub fn create_pool_all(data: &[u8]) -> Vec<PoolParseAttempt> {
+        KNOWN_POOL_TYPES
+            .iter()
+            .map(|pool_type| {
+                let parsed = std::panic::catch_unwind(AssertUnwindSafe(|| Self::create_pool(pool_type, data)))
+                    .unwrap_or_else(|_| Err(DexError::DeserializationFailed(format!(
+                        "{} deserializer panicked on {} bytes", pool_type, data.len()
+                    ))));
+                (*pool_type, parsed)
+            })
+            .collect()
+    }
+
     /// Create a pool with automatic type detection based on data length
     /// 
     /// This is useful when pool_type is "unknown" or when you want to
@@ -134,27 +395,46 @@ impl PoolFactory {
     pub fn create_pool_auto_detect(data: &[u8]) -> Result<Box<dyn DexPool>, DexError> {
         let len = data.len();
         
+        // 🔌 被禁用的 DEX 不参与探测；只有它们能解析时返回 DexDisabled
+        let mut disabled_match: Option<&'static str> = None;
+        let mut try_type = |pool_type: &'static str, parsed: Option<Box<dyn DexPool>>| -> Option<Box<dyn DexPool>> {
+            let pool = parsed?;
+            if Self::is_dex_enabled(pool_type) {
+                return Some(pool);
+            }
+            disabled_match.get_or_insert(pool_type);
+            None
+        };
+        
         // Try to detect based on common data lengths
         if len >= 1500 && len <= 1600 {
             // Likely CLMM (around 1544 bytes)
-            if let Ok(pool) = RaydiumClmmPoolState::from_account_data(data) {
-                return Ok(Box::new(pool));
+            let parsed = RaydiumClmmPoolState::from_account_data(data).ok().map(|p| Box::new(p) as Box<dyn DexPool>);
+            if let Some(pool) = try_type("clmm", parsed) {
+                return Ok(pool);
             }
         }
         
         // Try AMM V4 (around 752 bytes)
-        if let Ok(pool) = RaydiumAmmInfo::from_account_data(data) {
-            return Ok(Box::new(pool));
+        let parsed = RaydiumAmmInfo::from_account_data(data).ok().map(|p| Box::new(p) as Box<dyn DexPool>);
+        if let Some(pool) = try_type("amm_v4", parsed) {
+            return Ok(pool);
         }
         
         // Try CLMM as fallback
-        if let Ok(pool) = RaydiumClmmPoolState::from_account_data(data) {
-            return Ok(Box::new(pool));
+        let parsed = RaydiumClmmPoolState::from_account_data(data).ok().map(|p| Box::new(p) as Box<dyn DexPool>);
+        if let Some(pool) = try_type("clmm", parsed) {
+            return Ok(pool);
         }
         
         // Try Lifinity V2
-        if let Ok(pool) = LifinityV2PoolState::from_account_data(data) {
-            return Ok(Box::new(pool));
+        let parsed = LifinityV2PoolState::from_account_data(data).ok().map(|p| Box::new(p) as Box<dyn DexPool>);
+        if let Some(pool) = try_type("lifinity_v2", parsed) {
+            return Ok(pool);
+        }
+        
+        if let Some(dex) = disabled_match {
+            return Err(DexError::DexDisabled(dex.to_string()));
         }
         
         Err(DexError::InvalidData(format!(
@@ -200,7 +480,124 @@ mod tests {
             }
         }
     }
-}
-
+    
+    #[test]
+    fn test_owner_mismatch_refused_with_suggestion() {
+        let whirlpool_program = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+        let dummy_data = vec![0u8; 653];
+        
+        // Whirlpool 账户被错误配置为 raydium_v4
+        match PoolFactory::verify_owner("raydium_v4", whirlpool_program) {
+            Err(DexError::OwnerMismatch { expected, actual }) => {
+                assert_eq!(expected, "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+                assert_eq!(actual, whirlpool_program);
+            }
+            other => panic!("Expected owner mismatch, got {:?}", other),
+        }
+        
+        assert_eq!(
+            PoolFactory::suggest_pool_type(whirlpool_program, &dummy_data),
+            Some("whirlpool")
+        );
+        // 数据解析不了时不猜类型
+        assert_eq!(PoolFactory::suggest_pool_type(whirlpool_program, &[0u8; 16]), None);
+        
+        let check = PoolFactory::check_owner("raydium_v4", whirlpool_program, &dummy_data);
+        assert!(!check.verified);
+        assert_eq!(check.suggested_type.as_deref(), Some("whirlpool"));
+        assert!(check.reason().unwrap().starts_with("owner_mismatch"));
+        
+        // 修正配置后通过校验
+        assert!(PoolFactory::verify_owner("orca_whirlpool", whirlpool_program).is_ok());
+        let check = PoolFactory::check_owner("whirlpool", whirlpool_program, &dummy_data);
+        assert!(check.verified);
+        assert!(check.reason().is_none());
+    }
+    
+    #[test]
+    fn test_owner_verification_skips_unregistered_types() {
+        assert!(PoolFactory::expected_owner("aquifer").is_none());
+        assert!(PoolFactory::verify_owner("aquifer", "11111111111111111111111111111111").is_ok());
+        assert!(PoolFactory::verify_owner("unknown", "11111111111111111111111111111111").is_ok());
+        assert_eq!(
+            PoolFactory::expected_owner("PHOENIX_SIMPLE"),
+            Some("PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY")
+        );
+    }
+    
+    #[test]
+    fn test_dex_filter_switches_and_allowlist() {
+        let mut config = DexesConfig::default();
+        config.switches.insert("goon".to_string(), false);
+        config.switches.insert("whirlpool".to_string(), true);
+        config.switches.insert("not_a_dex".to_string(), false);
+        
+        // 别名按规范名称禁用，未知名称被忽略
+        let filter = DexFilter::from_config(&config);
+        assert!(!filter.is_enabled("goonfi"));
+        assert!(!filter.is_enabled("GOON_FI"));
+        assert!(filter.is_enabled("orca"));
+        assert!(filter.is_enabled("amm_v4"));
+        assert!(filter.is_enabled("unknown_dex"));
+        assert_eq!(filter.disabled_dexes(), vec!["goonfi"]);
+        
+        // allowlist：只启用列出的 DEX，开关表中的 false 仍然生效
+        config.allowlist = Some(vec!["raydium_v4".to_string(), "goonfi".to_string()]);
+        let filter = DexFilter::from_config(&config);
+        assert!(filter.is_enabled("amm_v4"));
+        assert!(!filter.is_enabled("goonfi"));
+        assert!(!filter.is_enabled("whirlpool"));
+        
+        // toml：开关表与 allowlist 共存
+        let parsed: DexesConfig = toml::from_str("allowlist = [\"amm_v4\"]\ntesserav = false\n").unwrap();
+        assert_eq!(parsed.allowlist, Some(vec!["amm_v4".to_string()]));
+        assert_eq!(parsed.switches.get("tesserav"), Some(&false));
+        
+        // 全局开关在 create_pool 中返回 DexDisabled（而不是反序列化错误）
+        // 只禁用 tesserav，避免并行运行的其他测试受全局过滤器影响
+        let parsed: DexesConfig = toml::from_str("tesserav = false\n").unwrap();
+        PoolFactory::set_dex_filter(DexFilter::from_config(&parsed));
+        let result = PoolFactory::create_pool("tessera", &[0u8; 100]);
+        PoolFactory::set_dex_filter(DexFilter::default());
+        assert!(matches!(result, Err(DexError::DexDisabled(dex)) if dex == "tesserav"));
+    }
+    
+    #[test]
+    fn test_owner_routes_ambiguous_size_account() {
+        // 1544 字节：Raydium CLMM 与 PancakeSwap 大小相同，按大小探测会落到 CLMM
+        let ambiguous = vec![0u8; 1544];
+        let pancakeswap_program = PoolFactory::expected_owner("pancakeswap").unwrap();
+        
+        let pool = PoolFactory::create_pool_with_owner(pancakeswap_program, &ambiguous).unwrap();
+        assert_eq!(pool.dex_name(), "PancakeSwap");
+        assert_eq!(PoolFactory::create_pool_auto_detect(&ambiguous).unwrap().dex_name(), "Raydium CLMM");
+        
+        // owner 未登记 → 回退到大小探测
+        let pool = PoolFactory::create_pool_with_owner("11111111111111111111111111111111", &ambiguous).unwrap();
+        assert_eq!(pool.dex_name(), "Raydium CLMM");
+        
+        // owner 已登记但数据不符 → 报该 DEX 的错误，不回退
+        let alphaq_program = PoolFactory::expected_owner("alphaq").unwrap();
+        assert!(PoolFactory::create_pool_with_owner(alphaq_program, &ambiguous).is_err());
+        
+        // 配置了 pool_type 时忽略 owner；"unknown" 时按 owner 路由
+        let pool = PoolFactory::create_pool_for("clmm", Some(pancakeswap_program), &ambiguous).unwrap();
+        assert_eq!(pool.dex_name(), "Raydium CLMM");
+        let pool = PoolFactory::create_pool_for("unknown", Some(pancakeswap_program), &ambiguous).unwrap();
+        assert_eq!(pool.dex_name(), "PancakeSwap");
+        let pool = PoolFactory::create_pool_for("", None, &ambiguous).unwrap();
+        assert_eq!(pool.dex_name(), "Raydium CLMM");
+    }
 
+    #[test]
+    fn test_create_pool_all_tries_every_deserializer() {
+        let results = PoolFactory::create_pool_all(&[0u8; 1544]);
+        let types: Vec<&str> = results.iter().map(|(t, _)| *t).collect();
+        assert_eq!(types, KNOWN_POOL_TYPES);
 
+        // 大小相同的两个布局都能"解析"，不会在第一个成功处停止
+        let parsed: Vec<&str> = results.iter().filter(|(_, r)| r.is_ok()).map(|(t, _)| *t).collect();
+        assert!(parsed.contains(&"clmm") && parsed.contains(&"pancakeswap"), "{:?}", parsed);
+        assert!(results.iter().any(|(_, r)| r.is_err()));
+    }
+}
@@ -0,0 +1,286 @@
+/*!
+ * 🕰️ 池子价格历史（内存环形缓冲）
+ *
+ * 事后分析只需要最近几个小时的逐池价格走势，不需要数据库：
+ *
+ * - 每个池子一个环形缓冲，保存 (时间, slot, 价格, 储备)，最多 `max_entries_per_pool` 条
+ * - 相对上一条样本的价格变化达到 `min_change_percent` 时记录（订阅 PriceCache 的价格更新广播，
+ *   在独立任务中写入，不占用 update_price 的热路径）
+ * - 每隔 `heartbeat_secs` 为没有新样本的池子补一条心跳样本，价格平稳时走势也是连续的
+ * - 查询：`GET /pools/{address}/history?since_secs=3600&max_points=500`，样本数超过
+ *   `max_points`（或指定 `bucket_secs`）时降采样为 OHLC K 线
+ *
+ * 与数据库是否开启无关；用来判断机会出现时是真实的行情变化还是数据异常。
+ */
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use dashmap::DashMap;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::debug;
+
+use crate::config::HistoryConfig;
+use crate::price_cache::{PoolPrice, PriceCache};
+
+/// 一条历史样本
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct HistorySample {
+    /// Unix 毫秒
+    pub timestamp_ms: i64,
+    pub slot: u64,
+    pub price: f64,
+    pub base_reserve: u64,
+    pub quote_reserve: u64,
+}
+
+impl HistorySample {
+    fn from_pool(pool: &PoolPrice, timestamp_ms: i64) -> Self {
+        Self {
+            timestamp_ms,
+            slot: pool.slot,
+            price: pool.price,
+            base_reserve: pool.base_reserve,
+            quote_reserve: pool.quote_reserve,
+        }
+    }
+}
+
+/// 降采样后的 OHLC K 线（储备取桶内最后一条样本）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Candle {
+    /// 桶起点（Unix 毫秒）
+    pub start_ms: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub first_slot: u64,
+    pub last_slot: u64,
+    pub base_reserve: u64,
+    pub quote_reserve: u64,
+    /// 桶内样本数
+    pub samples: usize,
+}
+
+/// GET /pools/{address}/history 的响应
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryResponse {
+    pub pool_id: String,
+    /// 时间窗口内的样本数（降采样前）
+    pub total_samples: usize,
+    /// 降采样的桶宽（毫秒）；未降采样时为 None，结果在 `samples` 中
+    pub bucket_ms: Option<i64>,
+    pub samples: Vec<HistorySample>,
+    pub candles: Vec<Candle>,
+}
+
+/// 全部池子的价格历史
+pub struct PoolHistory {
+    histories: DashMap<String, Mutex<VecDeque<HistorySample>>>,
+    max_entries: usize,
+    min_change: f64,
+    heartbeat_ms: i64,
+}
+
+impl PoolHistory {
+    pub fn new(config: &HistoryConfig) -> Self {
+        Self {
+            histories: DashMap::new(),
+            max_entries: config.max_entries_per_pool.max(1),
+            min_change: config.min_change_percent / 100.0,
+            heartbeat_ms: (config.heartbeat_secs * 1000) as i64,
+        }
+    }
+
+    /// 价格更新：相对上一条样本变化达到阈值时记录，返回是否写入
+    pub fn record_update(&self, pool: &PoolPrice, timestamp_ms: i64) -> bool {
+        if !(pool.price.is_finite() && pool.price > 0.0) {
+            return false;
+        }
+        let entry = self.histories.entry(pool.pool_id.clone()).or_default();
+        let mut samples = entry.lock().unwrap();
+        let significant = samples.back().is_none_or(|last| {
+            (pool.price - last.price).abs() / last.price >= self.min_change
+        });
+        if significant {
+            self.push(&mut samples, HistorySample::from_pool(pool, timestamp_ms));
+        }
+        significant
+    }
+
+    /// 心跳：最近 `heartbeat_secs` 内没有样本的池子补一条当前值；不在缓存中的池子丢弃历史
+    pub fn heartbeat(&self, pools: &[PoolPrice], timestamp_ms: i64) -> usize {
+        let live: HashMap<&str, &PoolPrice> = pools.iter().map(|p| (p.pool_id.as_str(), p)).collect();
+        self.histories.retain(|pool_id, _| live.contains_key(pool_id.as_str()));
+
+        let mut written = 0;
+        for pool in pools.iter().filter(|p| p.price.is_finite() && p.price > 0.0) {
+            let entry = self.histories.entry(pool.pool_id.clone()).or_default();
+            let mut samples = entry.lock().unwrap();
+            if samples.back().is_none_or(|last| timestamp_ms - last.timestamp_ms >= self.heartbeat_ms) {
+                self.push(&mut samples, HistorySample::from_pool(pool, timestamp_ms));
+                written += 1;
+            }
+        }
+        written
+    }
+
+    fn push(&self, samples: &mut VecDeque<HistorySample>, sample: HistorySample) {
+        if samples.len() >= self.max_entries {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+
+    /// 样本总数（所有池子）
+    pub fn len(&self) -> usize {
+        self.histories.iter().map(|entry| entry.lock().unwrap().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 查询 `since_ms` 之后的样本；超过 `max_points` 或指定 `bucket_ms` 时按时间桶降采样为 OHLC
+    pub fn query(&self, pool_id: &str, since_ms: i64, max_points: usize, bucket_ms: Option<i64>) -> Option<HistoryResponse> {
+        let samples: Vec<HistorySample> = {
+            let entry = self.histories.get(pool_id)?;
+            let samples = entry.lock().unwrap();
+            samples.iter().filter(|s| s.timestamp_ms >= since_ms).copied().collect()
+        };
+        let max_points = max_points.max(1);
+
+        // 指定桶宽时按整点对齐（不同池子的 K 线可以对比）；自动降采样时从第一条样本起算，保证不超过 max_points 根
+        let (bucket_ms, origin_ms) = match bucket_ms.filter(|b| *b > 0) {
+            Some(bucket) => (Some(bucket), 0),
+            None if samples.len() > max_points => {
+                let span = samples.last().unwrap().timestamp_ms - samples[0].timestamp_ms;
+                (Some(span / max_points as i64 + 1), samples[0].timestamp_ms)
+            }
+            None => (None, 0),
+        };
+        let candles = bucket_ms.map(|bucket| to_candles(&samples, bucket, origin_ms)).unwrap_or_default();
+        Some(HistoryResponse {
+            pool_id: pool_id.to_string(),
+            total_samples: samples.len(),
+            bucket_ms,
+            samples: if bucket_ms.is_some() { Vec::new() } else { samples },
+            candles,
+        })
+    }
+}
+
+/// 按 `bucket_ms` 宽、从 `origin_ms` 起算的时间桶聚合样本（样本按时间升序）
+pub fn to_candles(samples: &[HistorySample], bucket_ms: i64, origin_ms: i64) -> Vec<Candle> {
+    let mut candles: Vec<Candle> = Vec::new();
+    for sample in samples {
+        let start_ms = sample.timestamp_ms - (sample.timestamp_ms - origin_ms).rem_euclid(bucket_ms);
+        match candles.last_mut() {
+            Some(candle) if candle.start_ms == start_ms => {
+                candle.high = candle.high.max(sample.price);
+                candle.low = candle.low.min(sample.price);
+                candle.close = sample.price;
+                candle.last_slot = sample.slot;
+                candle.base_reserve = sample.base_reserve;
+                candle.quote_reserve = sample.quote_reserve;
+                candle.samples += 1;
+            }
+            _ => candles.push(Candle {
+                start_ms,
+                open: sample.price,
+                high: sample.price,
+                low: sample.price,
+                close: sample.price,
+                first_slot: sample.slot,
+                last_slot: sample.slot,
+                base_reserve: sample.base_reserve,
+                quote_reserve: sample.quote_reserve,
+                samples: 1,
+            }),
+        }
+    }
+    candles
+}
+
+/// 记录任务：订阅价格更新写入历史，并定期写心跳样本；收到关闭信号时退出
+pub async fn run_history_recorder(
+    history: Arc<PoolHistory>,
+    price_cache: Arc<PriceCache>,
+    heartbeat_interval: Duration,
+    mut shutdown: broadcast::Receiver<()>,
+) {
+    let mut updates = price_cache.subscribe_updates();
+    let mut ticker = tokio::time::interval(heartbeat_interval);
+    loop {
+        tokio::select! {
+            event = updates.recv() => match event {
+                Ok(event) => {
+                    if let Some(pool) = price_cache.get_price(&event.pool_id) {
+                        history.record_update(&pool, chrono::Utc::now().timestamp_millis());
+                    }
+                }
+                // 丢失的更新由下一次心跳补上当前值
+                Err(RecvError::Lagged(skipped)) => debug!("🕰️ History recorder lagged {} price events", skipped),
+                Err(RecvError::Closed) => break,
+            },
+            _ = ticker.tick() => {
+                history.heartbeat(&price_cache.get_all_prices(), chrono::Utc::now().timestamp_millis());
+            }
+            _ = shutdown.recv() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(price: f64, slot: u64) -> PoolPrice {
+        PoolPrice {
+            base_reserve: 1_000,
+            quote_reserve: (1_000.0 * price) as u64,
+            slot,
+            ..PoolPrice::for_test("pool-a", "SOL/USDC", price)
+        }
+    }
+
+    #[test]
+    fn test_significant_changes_heartbeat_and_ohlc() {
+        let history = PoolHistory::new(&HistoryConfig {
+            max_entries_per_pool: 4,
+            min_change_percent: 0.1,
+            heartbeat_secs: 30,
+            ..HistoryConfig::default()
+        });
+
+        // 变化不足 0.1% 的更新不记录；30 秒内不补心跳
+        assert!(history.record_update(&pool(100.0, 1), 0));
+        assert!(!history.record_update(&pool(100.05, 2), 1_000));
+        assert_eq!(history.heartbeat(&[pool(100.05, 2)], 10_000), 0);
+        assert_eq!(history.heartbeat(&[pool(100.05, 2)], 30_000), 1);
+        assert!(history.record_update(&pool(101.0, 3), 40_000));
+        assert!(history.record_update(&pool(99.0, 4), 70_000));
+
+        // 环形缓冲只保留最新的 4 条
+        assert!(history.record_update(&pool(98.0, 5), 80_000));
+        let raw = history.query("pool-a", 0, 10, None).unwrap();
+        assert_eq!(raw.samples.iter().map(|s| s.slot).collect::<Vec<_>>(), vec![2, 3, 4, 5]);
+        assert!(raw.candles.is_empty());
+
+        // 60 秒一桶：[30s, 40s] / [70s, 80s]
+        let ohlc = history.query("pool-a", 0, 10, Some(60_000)).unwrap();
+        assert_eq!(ohlc.total_samples, 4);
+        assert_eq!(ohlc.candles.len(), 2);
+        assert_eq!((ohlc.candles[0].open, ohlc.candles[0].high, ohlc.candles[0].close), (100.05, 101.0, 101.0));
+        assert_eq!((ohlc.candles[1].low, ohlc.candles[1].close, ohlc.candles[1].last_slot), (98.0, 98.0, 5));
+        assert_eq!(history.query("pool-a", 0, 2, None).unwrap().candles.len(), 2);
+
+        // 离开缓存的池子在心跳时丢弃历史
+        history.heartbeat(&[], 100_000);
+        assert!(history.query("pool-a", 0, 10, None).is_none());
+    }
+}
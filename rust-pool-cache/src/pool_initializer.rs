@@ -1,43 +1,46 @@
-use solana_client::rpc_client::RpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
-use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
 use tracing::{info, warn};
 use anyhow::Result;
 
+use crate::dex_interface::{DexError, DexPool};
+use crate::pool_factory::PoolFactory;
+use crate::rpc_manager::RpcHandle;
+
+/// getMultipleAccounts 单次请求的账户上限
+pub const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+
+/// RPC 查询到的池子账户（数据 + owner，用于激活前校验）
+#[derive(Debug, Clone)]
+pub struct PoolAccount {
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+}
+
+impl PoolAccount {
+    /// 按配置的 pool_type 解析；pool_type 为 unknown 时按 owner 选择反序列化器
+    pub fn create_pool(&self, pool_type: &str) -> Result<Box<dyn DexPool>, DexError> {
+        PoolFactory::create_pool_for(pool_type, Some(&self.owner.to_string()), &self.data)
+    }
+}
+
 /// 池子初始化器：启动时主动批量查询池子账户
 pub struct PoolInitializer {
-    rpc_clients: Vec<RpcClient>,
-    current_index: AtomicUsize,
+    rpc: RpcHandle,
 }
 
 impl PoolInitializer {
     /// 创建新的池子初始化器
     /// 
     /// # 参数
-    /// * `rpc_urls` - RPC端点列表（支持多个API key轮询）
-    /// * `timeout_ms` - 请求超时时间（毫秒）
-    pub fn new(rpc_urls: Vec<String>, timeout_ms: u64) -> Self {
-        let rpc_clients = rpc_urls
-            .iter()
-            .map(|url| {
-                RpcClient::new_with_timeout(url.clone(), Duration::from_millis(timeout_ms))
-            })
-            .collect();
-
-        info!("🚀 Pool initializer created with {} RPC endpoint(s)", rpc_urls.len());
-
-        Self {
-            rpc_clients,
-            current_index: AtomicUsize::new(0),
-        }
-    }
-
-    /// 轮询获取下一个RPC客户端（负载均衡）
-    fn get_next_client(&self) -> &RpcClient {
-        let index = self.current_index.fetch_add(1, Ordering::Relaxed);
-        &self.rpc_clients[index % self.rpc_clients.len()]
+    /// * `rpc` - 共享 RPC 句柄（端点故障转移与限速由 RpcManager 负责）
+    pub fn new(rpc: RpcHandle) -> Self {
+        info!("🚀 Pool initializer using {} RPC endpoint(s)", rpc.manager().endpoint_count());
+        Self { rpc }
     }
 
     /// 批量查询池子账户数据
@@ -47,12 +50,12 @@ impl PoolInitializer {
     /// * `max_retries` - 最大重试次数
     /// 
     /// # 返回
-    /// 每个池子的账户数据（如果存在）
+    /// 每个池子的账户数据和 owner（如果存在）
     pub async fn fetch_pool_accounts(
         &self,
         pool_addresses: &[String],
         max_retries: usize,
-    ) -> Result<Vec<Option<Vec<u8>>>> {
+    ) -> Result<Vec<Option<PoolAccount>>> {
         // 转换字符串地址为Pubkey
         let pubkeys: Vec<Pubkey> = pool_addresses
             .iter()
@@ -70,11 +73,10 @@ impl PoolInitializer {
 
         info!("🔍 Fetching {} pool accounts via RPC...", pubkeys.len());
 
-        // 重试逻辑
+        // 重试逻辑（单次调用内的端点故障转移由 RpcManager 处理）
         for attempt in 0..=max_retries {
-            let client = self.get_next_client();
-
-            match client.get_multiple_accounts(&pubkeys) {
+            let keys = pubkeys.clone();
+            match self.rpc.call(move |client| client.get_multiple_accounts(&keys).map_err(Box::new)).await {
                 Ok(accounts) => {
                     let valid_count = accounts.iter().filter(|a| a.is_some()).count();
                     info!(
@@ -85,10 +87,10 @@ impl PoolInitializer {
                         max_retries + 1
                     );
 
-                    // 提取account data
-                    let data: Vec<Option<Vec<u8>>> = accounts
+                    // 提取account data + owner
+                    let data: Vec<Option<PoolAccount>> = accounts
                         .into_iter()
-                        .map(|acc| acc.map(|a| a.data))
+                        .map(|acc| acc.map(|a| PoolAccount { data: a.data, owner: a.owner }))
                         .collect();
 
                     return Ok(data);
@@ -117,27 +119,43 @@ impl PoolInitializer {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_round_robin() {
-        let urls = vec![
-            "https://api1.example.com".to_string(),
-            "https://api2.example.com".to_string(),
-            "https://api3.example.com".to_string(),
-        ];
-
-        let initializer = PoolInitializer::new(urls, 5000);
-
-        // 测试轮询
-        for i in 0..10 {
-            let client = initializer.get_next_client();
-            let expected_idx = i % 3;
-            // 我们不能直接比较client，但可以验证不会panic
-            assert!(client as *const _ as usize > 0);
+/// 批量查询结果
+#[derive(Debug, Default)]
+pub struct BatchedAccounts {
+    pub accounts: HashMap<Pubkey, Account>,
+    /// 各批次响应中最大的 slot
+    pub slot: u64,
+    /// 实际发出的 RPC 请求数
+    pub rpc_calls: usize,
+}
+
+/// 按 getMultipleAccounts 分批查询任意账户（每批最多 100 个，每批消耗一次 RPC 预算）
+///
+/// 单批失败只记录警告，其余批次照常返回；重复地址只查询一次
+pub async fn fetch_accounts_batched(rpc: &RpcHandle, pubkeys: &[Pubkey]) -> BatchedAccounts {
+    let mut seen = HashSet::new();
+    let unique: Vec<Pubkey> = pubkeys.iter().filter(|k| seen.insert(**k)).copied().collect();
+
+    let mut result = BatchedAccounts::default();
+    for chunk in unique.chunks(MAX_ACCOUNTS_PER_REQUEST) {
+        result.rpc_calls += 1;
+
+        let keys = chunk.to_vec();
+        let response = rpc.call(move |client| {
+            client.get_multiple_accounts_with_commitment(&keys, CommitmentConfig::confirmed()).map_err(Box::new)
+        }).await;
+
+        match response {
+            Ok(response) => {
+                result.slot = result.slot.max(response.context.slot);
+                for (pubkey, account) in chunk.iter().zip(response.value) {
+                    if let Some(account) = account {
+                        result.accounts.insert(*pubkey, account);
+                    }
+                }
+            }
+            Err(e) => warn!("⚠️  getMultipleAccounts failed for {} accounts ({}): {}", chunk.len(), rpc.caller(), e),
         }
     }
+    result
 }
-
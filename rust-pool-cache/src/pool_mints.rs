@@ -0,0 +1,448 @@
+/*!
+ * 池子 mint 方向注册表
+ *
+ * 之前价格方向全靠交易对字符串猜：`pair.split('/')` 决定哪边是 base，
+ * LST 检测器用 `starts_with("SOL/")` 判断要不要取倒数。池子名写成
+ * "mSOL-SOL (Orca)" 或者 pair 顺序和链上 token A/B 相反时，方向就会算反。
+ *
+ * `[[pools]]` 配置了 `base_mint` / `quote_mint` 时按 mint 判断：
+ * base_reserve 对应 base_mint，代币符号由 mint 反查（内置常见代币 + 启动时登记的
+ * 发现目标 / LST；都没有时用池子 pair 里的名字）。没配置时回退到 pair 字符串，
+ * 并且每个池子只警告一次。
+ *
+ * 建图用的代币键按 mint 区分：同一个符号对应多个 mint 时（例如两个都叫 "USDC/USDT"
+ * 的池子，其中一个其实是桥接的 USDCet），只有最先登记该符号的 mint 使用裸符号，
+ * 其他 mint 的代币键是 "USDC@<mint>"，不会被当成同一个代币串成循环。
+ */
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{OnceLock, RwLock};
+use dashmap::{DashMap, DashSet};
+use tracing::warn;
+
+use crate::config::PoolConfig;
+use crate::price_cache::PoolPrice;
+
+/// Wrapped SOL mint
+pub const WSOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+/// 内置的 mint -> 符号（与 [discovery] 默认目标一致）
+const WELL_KNOWN_SYMBOLS: &[(&str, &str)] = &[
+    (WSOL_MINT, "SOL"),
+    ("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", "USDC"),
+    ("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB", "USDT"),
+    ("mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So", "mSOL"),
+    ("J1toso1uCk3RLmjorhTtrVwY9HJ7X8V9yYac6Y7kGCPn", "jitoSOL"),
+];
+
+static GLOBAL_REGISTRY: OnceLock<PoolMintRegistry> = OnceLock::new();
+
+/// 全局注册表（建图、路由器、LST 检测器共用）
+pub fn global() -> &'static PoolMintRegistry {
+    GLOBAL_REGISTRY.get_or_init(PoolMintRegistry::new)
+}
+
+/// 便捷函数：池子的 (base, quote) 代币符号
+pub fn pool_tokens(pool: &PoolPrice) -> Option<(String, String)> {
+    global().pool_tokens(pool)
+}
+
+/// 便捷函数：LST 池子的 "SOL per LST" 价格
+///
+/// SOL 在 base 一侧时 price 是 LST/SOL，需要取倒数；否则直接使用。
+pub fn sol_per_lst_price(pool: &PoolPrice) -> f64 {
+    if !global().sol_is_base(pool) {
+        pool.price
+    } else if pool.price > 0.0 {
+        1.0 / pool.price
+    } else {
+        0.0
+    }
+}
+
+/// 便捷函数：机会输出中的代币（符号有歧义时附带 mint 地址）
+pub fn describe_token(pool_id: &str, token: &str) -> String {
+    global().describe_token(pool_id, token)
+}
+
+/// pair 名称中的 (base, quote) 符号；"SOL/USDC (Raydium)" 只取 '/' 两侧的第一个词
+pub fn pair_symbols(pair: &str) -> Option<(String, String)> {
+    let (base, quote) = pair.split_once('/')?;
+    let base = base.split_whitespace().next()?;
+    let quote = quote.split(|c: char| c.is_whitespace() || c == '(').find(|part| !part.is_empty())?;
+    (!quote.contains('/')).then(|| (base.to_string(), quote.to_string()))
+}
+
+/// 池子配置的 base / quote mint
+#[derive(Debug, Clone)]
+struct PoolMints {
+    base_mint: String,
+    quote_mint: String,
+    /// pair 名称中的 (base, quote) 符号（未配置 pair 时为 None）
+    named: Option<(String, String)>,
+}
+
+/// 由池子 pair 名称推断的符号（池子登记 / 重载时整体重建）
+#[derive(Debug, Default)]
+struct InferredSymbols {
+    /// 未登记符号的 mint -> pair 名称中的符号
+    by_mint: HashMap<String, String>,
+    /// 符号 -> 池子中使用该符号的 mint
+    mints: HashMap<String, BTreeSet<String>>,
+}
+
+/// mint 方向注册表
+#[derive(Debug, Default)]
+pub struct PoolMintRegistry {
+    /// pool_id -> 配置的 mint
+    pools: DashMap<String, PoolMints>,
+    /// mint -> 代币符号
+    symbols: DashMap<String, String>,
+    /// 符号 -> 最先登记它的 mint（内置代币优先），代币键使用裸符号
+    owners: DashMap<String, String>,
+    inferred: RwLock<InferredSymbols>,
+    /// 已经警告过“没配 mint”的池子
+    warned: DashSet<String>,
+}
+
+impl PoolMintRegistry {
+    pub fn new() -> Self {
+        let registry = Self::default();
+        for (mint, symbol) in WELL_KNOWN_SYMBOLS {
+            registry.register_symbol(mint, symbol);
+        }
+        registry
+    }
+
+    /// 从池子配置加载 base_mint / quote_mint，返回加载数量（两者都配置才生效）
+    pub fn load_from_pools(&self, pools: &[PoolConfig]) -> usize {
+        pools.iter().filter(|pool| self.register_pool(pool)).count()
+    }
+
+    /// 按新的池子配置重新登记（热重载时调用，删掉 mint 的池子回退到 pair 字符串）
+    pub fn reload_pool(&self, pool: &PoolConfig) {
+        if !self.register_pool(pool) && self.pools.remove(&pool.address).is_some() {
+            self.rebuild_inferred();
+        }
+        self.warned.remove(&pool.address);
+    }
+
+    /// 清除池子的 mint 记录（池子被删除时调用）
+    pub fn clear_pool(&self, pool_id: &str) {
+        if self.pools.remove(pool_id).is_some() {
+            self.rebuild_inferred();
+        }
+        self.warned.remove(pool_id);
+    }
+
+    fn register_pool(&self, pool: &PoolConfig) -> bool {
+        match (&pool.base_mint, &pool.quote_mint) {
+            (Some(base_mint), Some(quote_mint)) => {
+                self.insert_pool(&pool.address, base_mint, quote_mint, pair_symbols(&pool.pair));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn set_pool_mints(&self, pool_id: &str, base_mint: &str, quote_mint: &str) {
+        self.insert_pool(pool_id, base_mint, quote_mint, None);
+    }
+
+    fn insert_pool(&self, pool_id: &str, base_mint: &str, quote_mint: &str, named: Option<(String, String)>) {
+        self.pools.insert(pool_id.to_string(), PoolMints {
+            base_mint: base_mint.to_string(),
+            quote_mint: quote_mint.to_string(),
+            named,
+        });
+        self.rebuild_inferred();
+    }
+
+    /// 登记 mint 对应的代币符号（发现目标、LST 注册表）
+    pub fn register_symbol(&self, mint: &str, symbol: &str) {
+        self.symbols.insert(mint.to_string(), symbol.to_string());
+        self.owners.entry(symbol.to_string()).or_insert_with(|| mint.to_string());
+        self.rebuild_inferred();
+    }
+
+    /// 重建 pair 名称推断的符号和 符号 -> mint 索引
+    ///
+    /// pair 写反时（登记过的一侧符号出现在另一侧的位置）按 mint 对齐后再推断。
+    fn rebuild_inferred(&self) {
+        let registered = |mint: &str| self.symbols.get(mint).map(|entry| entry.clone());
+        let mut inferred = InferredSymbols::default();
+
+        let pools: Vec<PoolMints> = self.pools.iter().map(|entry| entry.value().clone()).collect();
+        for pool in &pools {
+            let Some((base_name, quote_name)) = &pool.named else { continue };
+            let reversed = registered(&pool.base_mint).as_ref() == Some(quote_name)
+                || registered(&pool.quote_mint).as_ref() == Some(base_name);
+            let named = if reversed {
+                [(&pool.base_mint, quote_name), (&pool.quote_mint, base_name)]
+            } else {
+                [(&pool.base_mint, base_name), (&pool.quote_mint, quote_name)]
+            };
+            for (mint, name) in named {
+                if registered(mint).is_none() && name != "UNKNOWN" {
+                    inferred.by_mint.entry(mint.clone()).or_insert_with(|| name.clone());
+                }
+            }
+        }
+        for mint in pools.iter().flat_map(|pool| [&pool.base_mint, &pool.quote_mint]) {
+            if let Some(symbol) = registered(mint).or_else(|| inferred.by_mint.get(mint).cloned()) {
+                inferred.mints.entry(symbol).or_default().insert(mint.clone());
+            }
+        }
+
+        *self.inferred.write().unwrap() = inferred;
+    }
+
+    /// 登记过的符号（内置代币、发现目标、LST；不含 pair 名称推断的符号）
+    pub fn registered_symbol(&self, mint: &str) -> Option<String> {
+        self.symbols.get(mint).map(|entry| entry.clone())
+    }
+
+    /// mint 对应的代币符号：登记的符号，其次是池子 pair 名称中的符号，都没有时直接用 mint 地址
+    pub fn symbol(&self, mint: &str) -> String {
+        if let Some(symbol) = self.symbols.get(mint) {
+            return symbol.clone();
+        }
+        self.inferred.read().unwrap().by_mint.get(mint)
+            .cloned()
+            .unwrap_or_else(|| mint.to_string())
+    }
+
+    /// 建图用的代币键
+    ///
+    /// 符号只属于这个 mint 时是裸符号；同一符号还对应其他 mint 时，除了最先登记该符号的
+    /// mint 之外都写成 "符号@mint"。
+    pub fn token_key(&self, mint: &str) -> String {
+        let symbol = self.symbol(mint);
+        if symbol == mint {
+            return symbol;
+        }
+        let owns_symbol = match self.owners.get(&symbol) {
+            Some(owner) => *owner == mint,
+            None => self.inferred.read().unwrap().mints.get(&symbol).is_none_or(|mints| mints.len() <= 1),
+        };
+        if owns_symbol {
+            symbol
+        } else {
+            format!("{}@{}", symbol, mint)
+        }
+    }
+
+    /// 符号是否对应多个 mint（池子中出现过的 mint，加上登记该符号的 mint）
+    pub fn is_ambiguous(&self, symbol: &str) -> bool {
+        let inferred = self.inferred.read().unwrap();
+        let Some(mints) = inferred.mints.get(symbol) else {
+            return false;
+        };
+        match self.owners.get(symbol) {
+            Some(owner) => mints.iter().any(|mint| *mint != *owner),
+            None => mints.len() > 1,
+        }
+    }
+
+    /// 池子上代币键为 `token` 的 mint（池子没配置 mint 时为 None）
+    pub fn token_mint(&self, pool_id: &str, token: &str) -> Option<String> {
+        let mints = self.pools.get(pool_id)?.clone();
+        [mints.base_mint, mints.quote_mint].into_iter()
+            .find(|mint| {
+                let key = self.token_key(mint);
+                key == token || crate::token_alias::canonical(&key) == token
+            })
+    }
+
+    /// 机会输出中的代币：符号有歧义（多个 mint）时附带这一跳池子上的 mint 地址
+    pub fn describe_token(&self, pool_id: &str, token: &str) -> String {
+        if let Some((symbol, mint)) = token.split_once('@') {
+            return format!("{} ({})", symbol, mint);
+        }
+        if !self.is_ambiguous(token) {
+            return token.to_string();
+        }
+        match self.token_mint(pool_id, token) {
+            Some(mint) => format!("{} ({})", token, mint),
+            None => format!("{} (mint unknown)", token),
+        }
+    }
+
+    /// 池子的 (base, quote) 代币
+    ///
+    /// 配置了 mint 时按 mint 给出代币键（见 `token_key`）；否则按 '/' 拆分 pair（格式不对返回 None）。
+    pub fn pool_tokens(&self, pool: &PoolPrice) -> Option<(String, String)> {
+        if let Some(mints) = self.pools.get(&pool.pool_id) {
+            return Some((self.token_key(&mints.base_mint), self.token_key(&mints.quote_mint)));
+        }
+        self.warn_pair_fallback(pool);
+
+        let mut parts = pool.pair.split('/');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(base), Some(quote), None) => Some((base.to_string(), quote.to_string())),
+            _ => None,
+        }
+    }
+
+    /// 指定 mint 是否为池子的 base 一侧
+    ///
+    /// `Some(true)` = base，`Some(false)` = quote；池子没配置 mint 或 mint 不在池子里返回 None。
+    pub fn mint_is_base(&self, pool_id: &str, mint: &str) -> Option<bool> {
+        let mints = self.pools.get(pool_id)?;
+        if mints.base_mint == mint {
+            Some(true)
+        } else if mints.quote_mint == mint {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// SOL 是否在池子的 base 一侧
+    ///
+    /// 配置了 mint 时比较 wSOL mint；否则按 pair 前缀（"SOL/xxx"、"SOL xxx"）判断。
+    pub fn sol_is_base(&self, pool: &PoolPrice) -> bool {
+        if self.pools.contains_key(&pool.pool_id) {
+            return self.mint_is_base(&pool.pool_id, WSOL_MINT) == Some(true);
+        }
+        self.warn_pair_fallback(pool);
+        pool.pair.starts_with("SOL/") || pool.pair.starts_with("SOL ")
+    }
+
+    /// 池子是否包含某个代币：配置了 mint 时比较 mint，否则在 pair 中查找符号
+    pub fn pool_has_token(&self, pool: &PoolPrice, mint: &str, symbol: &str) -> bool {
+        if self.pools.contains_key(&pool.pool_id) {
+            return self.mint_is_base(&pool.pool_id, mint).is_some();
+        }
+        pool.pair.contains(symbol)
+    }
+
+    /// 回退到 pair 字符串时每个池子只警告一次
+    fn warn_pair_fallback(&self, pool: &PoolPrice) {
+        if !self.warned.contains(&pool.pool_id) && self.warned.insert(pool.pool_id.clone()) {
+            warn!(
+                "⚠️  Pool {} ({}) has no base_mint/quote_mint configured, guessing price direction from the pair name",
+                pool.pool_id, pool.pair
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(pool_id: &str, pair: &str) -> PoolPrice {
+        PoolPrice {
+            dex_name: "Whirlpool (Orca)".to_string(),
+            base_reserve: 2_000_000_000_000,
+            quote_reserve: 2_400_000_000_000,
+            quote_decimals: 9,
+            slot: 0,
+            ..PoolPrice::for_test(pool_id, pair, 1.2)
+        }
+    }
+
+    #[test]
+    fn test_mints_override_pair_naming() {
+        let registry = PoolMintRegistry::new();
+        let msol = "mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So";
+        registry.set_pool_mints("orca-msol", msol, WSOL_MINT);
+
+        // pair 写反、分隔符不对，都按 mint 给出 mSOL/SOL
+        for pair in ["mSOL/SOL", "SOL/mSOL", "SOL-mSOL (Orca)"] {
+            assert_eq!(
+                registry.pool_tokens(&pool("orca-msol", pair)),
+                Some(("mSOL".to_string(), "SOL".to_string()))
+            );
+        }
+        assert_eq!(registry.mint_is_base("orca-msol", WSOL_MINT), Some(false));
+        assert_eq!(registry.mint_is_base("orca-msol", msol), Some(true));
+
+        // 没配置 mint：回退到 pair 字符串
+        assert_eq!(
+            registry.pool_tokens(&pool("unconfigured", "SOL/mSOL")),
+            Some(("SOL".to_string(), "mSOL".to_string()))
+        );
+        assert_eq!(registry.pool_tokens(&pool("unconfigured", "SOL-mSOL")), None);
+        assert_eq!(registry.mint_is_base("unconfigured", WSOL_MINT), None);
+
+        // 未登记的 mint 直接用地址作为符号
+        registry.set_pool_mints("exotic", "Mint111", WSOL_MINT);
+        assert_eq!(
+            registry.pool_tokens(&pool("exotic", "X/SOL")),
+            Some(("Mint111".to_string(), "SOL".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_same_symbol_with_different_mints_gets_distinct_tokens() {
+        use crate::config::PoolConfig;
+
+        let usdc = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+        let usdt = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
+        let usdcet = "A9mUU4qviSctJVPJdBJWkb28deg915LYJKrzQ19ji3FM";
+        let config = |address: &str, pair: &str, base_mint: &str, quote_mint: &str| PoolConfig {
+            address: address.to_string(),
+            name: format!("{} (Orca)", pair),
+            pair: pair.to_string(),
+            pool_type: "whirlpool".to_string(),
+            fee_bps: None,
+            max_age_ms: None,
+            base_mint: Some(base_mint.to_string()),
+            quote_mint: Some(quote_mint.to_string()),
+            max_price_jump_percent: None,
+            commitment: None,
+            encoding: None,
+            priority: None,
+        };
+        let registry = PoolMintRegistry::new();
+        registry.load_from_pools(&[
+            config("native", "USDC/USDT", usdc, usdt),
+            config("bridged", "USDC/USDT", usdcet, usdt),
+            // pair 写反：按登记过的 USDT 对齐，JUP 的 mint 仍然推断为 JUP
+            config("jup", "USDT/JUP", "JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN", usdt),
+        ]);
+
+        assert_eq!(registry.pool_tokens(&pool("native", "USDC/USDT")), Some(("USDC".to_string(), "USDT".to_string())));
+        assert_eq!(
+            registry.pool_tokens(&pool("bridged", "USDC/USDT")),
+            Some((format!("USDC@{}", usdcet), "USDT".to_string()))
+        );
+        assert_eq!(registry.pool_tokens(&pool("jup", "USDT/JUP")).unwrap().0, "JUP");
+
+        // 输出时有歧义的符号附带 mint
+        assert!(registry.is_ambiguous("USDC"));
+        assert!(!registry.is_ambiguous("USDT"));
+        assert_eq!(registry.describe_token("native", "USDC"), format!("USDC ({})", usdc));
+        assert_eq!(registry.describe_token("bridged", &format!("USDC@{}", usdcet)), format!("USDC ({})", usdcet));
+        assert_eq!(registry.describe_token("native", "USDT"), "USDT");
+        assert_eq!(registry.describe_token("unconfigured", "USDC"), "USDC (mint unknown)");
+
+        // 没有登记符号的 mint：只有一个 mint 用这个名字时是裸符号，第二个出现后都带上 mint
+        registry.load_from_pools(&[config("jup-2", "JUP/USDC", "JUPmint2", usdc)]);
+        assert_eq!(
+            registry.pool_tokens(&pool("jup", "USDT/JUP")).unwrap().0,
+            "JUP@JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN"
+        );
+        registry.clear_pool("jup-2");
+        assert_eq!(registry.pool_tokens(&pool("jup", "USDT/JUP")).unwrap().0, "JUP");
+    }
+
+    #[test]
+    fn test_reversed_pair_naming_normalizes_lst_price_identically() {
+        let msol = "mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So";
+        global().set_pool_mints("lst-msol-oriented", msol, WSOL_MINT);
+
+        // price = 1.2 SOL per mSOL（base 是 mSOL），各种命名都不应取倒数
+        for pair in ["mSOL/SOL", "SOL/mSOL", "SOL mSOL (Orca)"] {
+            let price = sol_per_lst_price(&pool("lst-msol-oriented", pair));
+            assert!((price - 1.2).abs() < 1e-12, "{} -> {}", pair, price);
+        }
+        assert!(global().pool_has_token(&pool("lst-msol-oriented", "SOL-X"), msol, "mSOL"));
+
+        // 没配置 mint 时沿用 pair 前缀
+        assert!((sol_per_lst_price(&pool("lst-msol-unoriented", "SOL/mSOL")) - 1.0 / 1.2).abs() < 1e-12);
+        assert!((sol_per_lst_price(&pool("lst-msol-unoriented", "mSOL/SOL")) - 1.2).abs() < 1e-12);
+    }
+}
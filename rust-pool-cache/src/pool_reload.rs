@@ -0,0 +1,216 @@
+/*!
+ * 池子列表热重载
+ *
+ * POST /reload 重新读取 config.toml，按地址对比新旧池子列表：
+ * - 新增池子：通过订阅 channel 发送 accountSubscribe
+ * - 删除池子：退订并从 PriceCache / PoolStatsCollector / VaultReader 中移除
+ * - 修改池子（改名、pool_type 修正、fee_bps）：只重新登记元数据，不重新订阅
+ * - 🏷️ pair 留空的池子沿用启动时生成的名称，新增的按链上 mint 命名
+ *
+ * PriceCache 的温数据和已有 vault 订阅全部保留。
+ */
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use tracing::info;
+
+use crate::config::{Config, PoolConfig};
+use crate::pair_naming::PairNamer;
+use crate::sharding::{ShardAssignment, ShardRing};
+use crate::websocket::WebSocketClient;
+
+/// 新旧池子列表的差异（按地址匹配）
+#[derive(Debug, Clone, Default)]
+pub struct PoolDiff {
+    pub added: Vec<PoolConfig>,
+    pub removed: Vec<PoolConfig>,
+    /// (旧配置, 新配置)
+    pub updated: Vec<(PoolConfig, PoolConfig)>,
+    pub unchanged: usize,
+    /// 🪦 重载时恢复的退役池子（也计入 unchanged / updated）
+    pub revived: Vec<PoolConfig>,
+}
+
+/// 热重载结果（API 输出）
+#[derive(Debug, Clone, Serialize)]
+pub struct ReloadSummary {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub updated: Vec<String>,
+    pub unchanged: usize,
+    pub revived: Vec<String>,
+    pub total_pools: usize,
+}
+
+impl PoolDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty() && self.revived.is_empty()
+    }
+
+    pub fn summary(&self) -> ReloadSummary {
+        ReloadSummary {
+            added: self.added.iter().map(|p| p.name.clone()).collect(),
+            removed: self.removed.iter().map(|p| p.name.clone()).collect(),
+            updated: self.updated.iter().map(|(_, new)| new.name.clone()).collect(),
+            unchanged: self.unchanged,
+            revived: self.revived.iter().map(|p| p.name.clone()).collect(),
+            total_pools: self.added.len() + self.updated.len() + self.unchanged,
+        }
+    }
+}
+
+/// 对比新旧池子列表（保持新列表中的顺序）
+pub fn diff_pools(current: &[PoolConfig], new: &[PoolConfig]) -> PoolDiff {
+    let current_by_address: HashMap<&str, &PoolConfig> = current.iter()
+        .map(|p| (p.address.as_str(), p))
+        .collect();
+    let new_by_address: HashMap<&str, &PoolConfig> = new.iter()
+        .map(|p| (p.address.as_str(), p))
+        .collect();
+
+    let mut diff = PoolDiff::default();
+    for pool in new {
+        match current_by_address.get(pool.address.as_str()) {
+            None => diff.added.push(pool.clone()),
+            Some(old) if *old != pool => diff.updated.push(((*old).clone(), pool.clone())),
+            Some(_) => diff.unchanged += 1,
+        }
+    }
+    diff.removed = current.iter()
+        .filter(|p| !new_by_address.contains_key(p.address.as_str()))
+        .cloned()
+        .collect();
+    diff
+}
+
+/// 把自动发现的池子追加到静态池子列表（静态配置中已有的地址优先）
+pub fn merge_discovered(pools: &mut Vec<PoolConfig>, discovered: &[PoolConfig]) {
+    let existing: HashSet<String> = pools.iter().map(|p| p.address.clone()).collect();
+    pools.extend(discovered.iter().filter(|p| !existing.contains(&p.address)).cloned());
+}
+
+/// 本实例需要订阅的池子（与启动时的分片规则一致）
+pub fn subscribed_pools(config: &Config) -> Vec<PoolConfig> {
+    match config.sharding.as_ref().filter(|s| s.enabled) {
+        Some(s) => {
+            let ring = ShardRing::new(s.shard_count, s.virtual_nodes);
+            ShardAssignment::assign(&ring, s.shard_index, config.pools(), &s.anchor_pools)
+                .subscribed_pools(config.pools())
+        }
+        None => config.pools().to_vec(),
+    }
+}
+
+/// 池子列表热重载器（API 持有）
+pub struct PoolReloader {
+    config_path: String,
+    ws_client: Arc<WebSocketClient>,
+    /// 配置中的全部池子（/pools 展示用，与 ApiState 共享）
+    configured_pools: Arc<RwLock<Vec<PoolConfig>>>,
+    /// 启动时自动发现的池子（配置文件里没有，重载时保留）
+    discovered_pools: Vec<PoolConfig>,
+    /// 🏷️ pair 为空的池子的自动命名（与启动时共用，已命名的地址不重复查询）
+    pair_namer: Option<Arc<PairNamer>>,
+}
+
+impl PoolReloader {
+    pub fn new(
+        config_path: String,
+        ws_client: Arc<WebSocketClient>,
+        configured_pools: Arc<RwLock<Vec<PoolConfig>>>,
+    ) -> Self {
+        Self {
+            config_path,
+            ws_client,
+            configured_pools,
+            discovered_pools: Vec::new(),
+            pair_namer: None,
+        }
+    }
+
+    pub fn with_discovered_pools(mut self, pools: Vec<PoolConfig>) -> Self {
+        self.discovered_pools = pools;
+        self
+    }
+
+    pub fn with_pair_namer(mut self, pair_namer: Arc<PairNamer>) -> Self {
+        self.pair_namer = Some(pair_namer);
+        self
+    }
+
+    /// 重新读取配置文件并应用池子差异
+    ///
+    /// 配置无效时返回错误，当前订阅保持不变
+    pub async fn reload(&self) -> Result<ReloadSummary> {
+        let mut config = Config::load_from_file(&self.config_path)?;
+        merge_discovered(&mut config.pools, &self.discovered_pools);
+        if let Some(pair_namer) = &self.pair_namer {
+            pair_namer.resolve(&mut config.pools).await;
+        }
+        let diff = self.ws_client.apply_pool_list(subscribed_pools(&config));
+        *self.configured_pools.write().unwrap() = config.pools().to_vec();
+
+        let summary = diff.summary();
+        if diff.is_empty() {
+            info!("♻️  Pool list reloaded from {}: no changes", self.config_path);
+        } else {
+            info!(
+                "♻️  Pool list reloaded from {}: +{} added, -{} removed, ~{} updated, {} unchanged, {} revived",
+                self.config_path,
+                summary.added.len(), summary.removed.len(), summary.updated.len(), summary.unchanged,
+                summary.revived.len()
+            );
+        }
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(address: &str, name: &str, pool_type: &str) -> PoolConfig {
+        PoolConfig {
+            address: address.to_string(),
+            name: name.to_string(),
+            pair: "SOL/USDC".to_string(),
+            pool_type: pool_type.to_string(),
+            fee_bps: None,
+            max_age_ms: None,
+            base_mint: None,
+            quote_mint: None,
+            max_price_jump_percent: None,
+            commitment: None,
+            encoding: None,
+            priority: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_pools_by_address() {
+        let current = vec![
+            pool("A", "SOL/USDC (Raydium)", "amm_v4"),
+            pool("B", "SOL/USDC (Orca)", "amm_v4"),
+            pool("C", "SOL/USDT (Raydium)", "amm_v4"),
+        ];
+        let new = vec![
+            pool("A", "SOL/USDC (Raydium)", "amm_v4"),
+            pool("B", "SOL/USDC (Orca)", "whirlpool"),
+            pool("D", "JUP/USDC (Meteora)", "meteora_dlmm"),
+        ];
+
+        let diff = diff_pools(&current, &new);
+        assert_eq!(diff.added.iter().map(|p| p.address.as_str()).collect::<Vec<_>>(), vec!["D"]);
+        assert_eq!(diff.removed.iter().map(|p| p.address.as_str()).collect::<Vec<_>>(), vec!["C"]);
+        assert_eq!(diff.updated.len(), 1);
+        assert_eq!(diff.updated[0].0.pool_type, "amm_v4");
+        assert_eq!(diff.updated[0].1.pool_type, "whirlpool");
+        assert_eq!(diff.unchanged, 1);
+
+        let summary = diff.summary();
+        assert_eq!(summary.total_pools, 3);
+        assert!(diff_pools(&new, &new).is_empty());
+    }
+}
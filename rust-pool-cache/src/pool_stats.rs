@@ -4,14 +4,22 @@
 /// - 追踪每个池子的订阅次数
 /// - 记录价格更新频率
 /// - 监控价格变化幅度
-/// - 提供时间窗口统计
-/// - 生成专业级分析报告
+/// - 提供时间窗口统计（每池按分钟计数的环形缓冲区，保留最近 24 小时）
+/// - ⛓️ 追踪通知相对链头的 slot 延迟（每池最近 HEAD_LAG_SAMPLES 次更新的 p50 / p95）
+/// - 🪦 池子生命周期：账户被关闭 / 重新分配 / 换 owner 后标记为 Retired，
+///   配置的 pool_type 与账户 owner 不符时标记为 Degraded
+/// - 生成专业级分析报告（/stats/pools、/stats/dex 与退出时的表格共用同一组查询）
 
 use chrono::{DateTime, Utc};
 use dashmap::DashMap;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use tracing::info;
 
+use crate::circuit_breaker::TripReason;
+use crate::prometheus::{MetricKind, PrometheusWriter};
+
 /// 单个池子的统计信息
 #[derive(Debug, Clone)]
 pub struct PoolStats {
@@ -39,6 +47,12 @@ pub struct PoolStats {
     pub vault_updates: u64,
     /// 错误次数
     pub error_count: u64,
+    /// 因长时间没有通知而重新订阅的次数
+    pub resubscribes: u64,
+    /// 🧯 熔断触发次数（价格 / 储备异常跳变）
+    pub quarantine_trips: u64,
+    /// 🧯 当前隔离的原因（未隔离为 None）
+    pub quarantined: Option<TripReason>,
 }
 
 impl PoolStats {
@@ -58,6 +72,9 @@ impl PoolStats {
             cumulative_price_change: 0.0,
             vault_updates: 0,
             error_count: 0,
+            resubscribes: 0,
+            quarantine_trips: 0,
+            quarantined: None,
         }
     }
 
@@ -67,11 +84,11 @@ impl PoolStats {
         self.last_subscription = Utc::now();
     }
 
-    /// 记录价格更新
-    pub fn record_price_update(&mut self, new_price: f64, threshold: f64) {
+    /// 记录价格更新，返回相对上次价格的变化（%，首次更新为 None）
+    pub fn record_price_update(&mut self, new_price: f64, threshold: f64) -> Option<f64> {
         self.price_updates += 1;
 
-        if let Some(last_price) = self.last_price {
+        let change = self.last_price.map(|last_price| {
             let change_percent = ((new_price - last_price) / last_price * 100.0).abs();
             
             // 累计价格变化
@@ -86,9 +103,11 @@ impl PoolStats {
             if change_percent >= threshold {
                 self.significant_price_changes += 1;
             }
-        }
+            change_percent
+        });
 
         self.last_price = Some(new_price);
+        change
     }
 
     /// 记录vault更新
@@ -101,31 +120,27 @@ impl PoolStats {
         self.error_count += 1;
     }
 
+    /// 记录重新订阅
+    pub fn record_resubscribe(&mut self) {
+        self.resubscribes += 1;
+    }
+
+    /// 🧯 记录熔断触发（进入隔离或隔离中再次触发）
+    pub fn record_quarantine(&mut self, reason: TripReason) {
+        self.quarantine_trips += 1;
+        self.quarantined = Some(reason);
+    }
+
     /// 计算活跃度分数 (0-100)
     pub fn activity_score(&self) -> f64 {
-        let now = Utc::now();
-        let duration_secs = (now - self.first_subscription).num_seconds().max(1) as f64;
-        
-        // 更新频率得分 (0-40分)
-        let update_rate = (self.price_updates as f64 / duration_secs) * 60.0; // 每分钟更新次数
-        let update_score = (update_rate * 10.0).min(40.0);
-        
-        // 订阅频率得分 (0-20分)
-        let sub_rate = (self.total_subscriptions as f64 / duration_secs) * 3600.0; // 每小时订阅次数
-        let sub_score = (sub_rate * 2.0).min(20.0);
-        
-        // 价格活跃度得分 (0-30分)
-        let price_activity = if self.price_updates > 0 {
-            (self.significant_price_changes as f64 / self.price_updates as f64) * 100.0
-        } else {
-            0.0
-        };
-        let price_score = (price_activity * 0.3).min(30.0);
-        
-        // Vault活跃度得分 (0-10分)
-        let vault_score = (self.vault_updates as f64).min(10.0);
-        
-        update_score + sub_score + price_score + vault_score
+        let duration_secs = (Utc::now() - self.first_subscription).num_seconds().max(1) as f64;
+        activity_score(
+            self.price_updates,
+            self.total_subscriptions,
+            self.significant_price_changes,
+            self.vault_updates,
+            duration_secs,
+        )
     }
 
     /// 获取运行时长（秒）
@@ -134,6 +149,219 @@ impl PoolStats {
     }
 }
 
+/// 活跃度分数 (0-100)：按 `duration_secs` 内的计数计算
+fn activity_score(price_updates: u64, subscriptions: u64, significant_changes: u64, vault_updates: u64, duration_secs: f64) -> f64 {
+    // 更新频率得分 (0-40分)
+    let update_rate = (price_updates as f64 / duration_secs) * 60.0; // 每分钟更新次数
+    let update_score = (update_rate * 10.0).min(40.0);
+    
+    // 订阅频率得分 (0-20分)
+    let sub_rate = (subscriptions as f64 / duration_secs) * 3600.0; // 每小时订阅次数
+    let sub_score = (sub_rate * 2.0).min(20.0);
+    
+    // 价格活跃度得分 (0-30分)
+    let price_activity = if price_updates > 0 {
+        (significant_changes as f64 / price_updates as f64) * 100.0
+    } else {
+        0.0
+    };
+    let price_score = (price_activity * 0.3).min(30.0);
+    
+    // Vault活跃度得分 (0-10分)
+    let vault_score = (vault_updates as f64).min(10.0);
+    
+    update_score + sub_score + price_score + vault_score
+}
+
+/// 从池子名称提取 DEX 名称（括号内的部分，如 "SOL/USDC (Raydium V4)" -> "Raydium V4"）
+pub fn dex_of(pool_name: &str) -> &str {
+    match (pool_name.rfind('('), pool_name.rfind(')')) {
+        (Some(start), Some(end)) if start < end => &pool_name[start + 1..end],
+        _ => "Unknown",
+    }
+}
+
+/// 每分钟计数环形缓冲区的容量（24 小时）
+pub const HISTORY_MINUTES: usize = 24 * 60;
+
+/// 一分钟内的活动计数
+#[derive(Debug, Clone, Copy, Default)]
+struct MinuteBucket {
+    /// unix 分钟
+    minute: i64,
+    subscriptions: u64,
+    price_updates: u64,
+    significant_price_changes: u64,
+    max_price_change_percent: f64,
+    vault_updates: u64,
+    errors: u64,
+}
+
+/// 单个池子的每分钟计数（按分钟升序，只保存有活动的分钟，最多 HISTORY_MINUTES 分钟）
+#[derive(Debug, Default)]
+struct ActivityHistory {
+    buckets: VecDeque<MinuteBucket>,
+}
+
+impl ActivityHistory {
+    /// 当前分钟的计数桶（顺带淘汰超过 24 小时的桶；时钟回拨时记到最新的桶）
+    fn bucket_mut(&mut self, minute: i64) -> &mut MinuteBucket {
+        if self.buckets.back().is_none_or(|last| last.minute < minute) {
+            self.buckets.push_back(MinuteBucket { minute, ..Default::default() });
+            while self.buckets.front().is_some_and(|first| first.minute <= minute - HISTORY_MINUTES as i64) {
+                self.buckets.pop_front();
+            }
+        }
+        self.buckets.back_mut().expect("bucket just pushed")
+    }
+
+    /// `from_minute`（含）之后的计数之和
+    fn sum_since(&self, from_minute: i64) -> MinuteBucket {
+        self.buckets.iter()
+            .rev()
+            .take_while(|bucket| bucket.minute >= from_minute)
+            .fold(MinuteBucket { minute: from_minute, ..Default::default() }, |mut total, bucket| {
+                total.subscriptions += bucket.subscriptions;
+                total.price_updates += bucket.price_updates;
+                total.significant_price_changes += bucket.significant_price_changes;
+                total.max_price_change_percent = total.max_price_change_percent.max(bucket.max_price_change_percent);
+                total.vault_updates += bucket.vault_updates;
+                total.errors += bucket.errors;
+                total
+            })
+    }
+}
+
+/// 每个池子保留的链头延迟样本数（滚动窗口）
+pub const HEAD_LAG_SAMPLES: usize = 256;
+
+/// 池子通知相对链头的 slot 延迟（最近 HEAD_LAG_SAMPLES 次更新）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct HeadLag {
+    pub samples: usize,
+    pub last: u64,
+    pub p50: u64,
+    pub p95: u64,
+}
+
+/// 单个池子的链头延迟样本
+#[derive(Debug, Default)]
+struct HeadLagWindow {
+    samples: VecDeque<u64>,
+}
+
+impl HeadLagWindow {
+    fn push(&mut self, lag: u64) {
+        if self.samples.len() == HEAD_LAG_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(lag);
+    }
+
+    fn summary(&self) -> Option<HeadLag> {
+        let last = *self.samples.back()?;
+        let mut sorted: Vec<u64> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+        // nearest-rank 百分位
+        let rank = |percent: usize| sorted[(sorted.len() * percent).div_ceil(100).max(1) - 1];
+        Some(HeadLag { samples: sorted.len(), last, p50: rank(50), p95: rank(95) })
+    }
+}
+
+/// 🪦 连续多少次账户长度与解析器不符的通知后把池子标记为 Retired
+pub const RETIRE_AFTER_MISMATCHES: u32 = 3;
+
+/// 🪦 池子生命周期（/pools 展示）
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum PoolLifecycle {
+    #[default]
+    Active,
+    /// 账户已关闭或迁移：不再解析、不参与路由、不计入错误，热重载后恢复
+    Retired { reason: String, since: DateTime<Utc> },
+    /// 🔒 配置与链上账户不符：拒绝激活、不参与路由，修正配置后重新校验通过即恢复
+    Degraded { reason: DegradedReason, since: DateTime<Utc> },
+}
+
+impl PoolLifecycle {
+    /// active / retired / degraded（/pools?lifecycle= 过滤用）
+    pub fn label(&self) -> &'static str {
+        match self {
+            PoolLifecycle::Active => "active",
+            PoolLifecycle::Retired { .. } => "retired",
+            PoolLifecycle::Degraded { .. } => "degraded",
+        }
+    }
+}
+
+/// 🔒 池子被降级的原因
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DegradedReason {
+    /// 账户 owner 不是 pool_type 对应的 program
+    OwnerMismatch { expected: String, actual: String },
+}
+
+impl std::fmt::Display for DegradedReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DegradedReason::OwnerMismatch { expected, actual } => {
+                write!(f, "owner_mismatch{{expected: {}, actual: {}}}", expected, actual)
+            }
+        }
+    }
+}
+
+/// 🔒 被降级的池子（/health 展示）
+#[derive(Debug, Clone, Serialize)]
+pub struct DegradedPool {
+    pub pool_name: String,
+    pub reason: DegradedReason,
+    pub since: DateTime<Utc>,
+}
+
+/// 单个池子的生命周期跟踪
+#[derive(Debug, Default)]
+struct LifecycleTracker {
+    state: PoolLifecycle,
+    /// 最近一次成功解析的账户长度
+    decoded_len: Option<usize>,
+    /// 连续的长度不符通知数
+    mismatches: u32,
+}
+
+/// 时间窗口内的池子活动（GET /stats/pools）
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolWindowStats {
+    pub pool_name: String,
+    pub pool_address: String,
+    pub dex: String,
+    /// 实际覆盖的秒数（按分钟对齐，且不早于首次订阅）
+    pub window_secs: u64,
+    pub subscriptions: u64,
+    pub price_updates: u64,
+    pub significant_price_changes: u64,
+    pub max_price_change_percent: f64,
+    pub vault_updates: u64,
+    pub error_count: u64,
+    pub activity_score: f64,
+    pub updates_per_minute: f64,
+    /// ⛓️ 通知相对链头的 slot 延迟（未启用链头跟踪时为 None；不按窗口截取，取最近的样本）
+    pub head_lag: Option<HeadLag>,
+}
+
+/// 时间窗口内按 DEX 汇总的活动（GET /stats/dex）
+#[derive(Debug, Clone, Serialize)]
+pub struct DexWindowStats {
+    pub dex: String,
+    pub pools: usize,
+    pub subscriptions: u64,
+    pub price_updates: u64,
+    pub avg_subscriptions_per_pool: u64,
+    /// 占全部订阅的百分比
+    pub subscription_share_percent: f64,
+}
+
 /// 池子统计收集器
 #[derive(Clone)]
 pub struct PoolStatsCollector {
@@ -141,6 +369,14 @@ pub struct PoolStatsCollector {
     stats: Arc<DashMap<String, PoolStats>>,
     /// 价格变化阈值（百分比）
     price_change_threshold: f64,
+    /// 因 DEX 被禁用而跳过的账户数（按规范 DEX 名称，不计入错误）
+    skipped_disabled: Arc<DashMap<String, u64>>,
+    /// 每池每分钟计数（时间窗口查询用，与 stats 分开存放，get_all_stats 不复制历史）
+    history: Arc<DashMap<String, ActivityHistory>>,
+    /// ⛓️ 每池链头延迟样本
+    head_lag: Arc<DashMap<String, HeadLagWindow>>,
+    /// 🪦 每池生命周期（没有条目 = Active）
+    lifecycle: Arc<DashMap<String, LifecycleTracker>>,
 }
 
 impl PoolStatsCollector {
@@ -149,9 +385,137 @@ impl PoolStatsCollector {
         Self {
             stats: Arc::new(DashMap::new()),
             price_change_threshold,
+            skipped_disabled: Arc::new(DashMap::new()),
+            history: Arc::new(DashMap::new()),
+            head_lag: Arc::new(DashMap::new()),
+            lifecycle: Arc::new(DashMap::new()),
         }
     }
 
+    /// 🪦 记录一次成功解析（重置长度不符计数）
+    pub fn record_decoded(&self, pool_name: &str, data_len: usize) {
+        if let Some(mut tracker) = self.lifecycle.get_mut(pool_name) {
+            tracker.decoded_len = Some(data_len);
+            tracker.mismatches = 0;
+            return;
+        }
+        self.lifecycle.insert(
+            pool_name.to_string(),
+            LifecycleTracker { decoded_len: Some(data_len), ..Default::default() },
+        );
+    }
+
+    /// 🪦 记录一次解析失败，返回本次导致退役时的原因
+    ///
+    /// 账户长度与最近一次成功解析时相同的失败（数据暂时异常）不计数；
+    /// 连续 RETIRE_AFTER_MISMATCHES 次长度不符才退役。
+    pub fn record_decode_failure(&self, pool_name: &str, data_len: usize) -> Option<String> {
+        let mut tracker = self.lifecycle.entry(pool_name.to_string()).or_default();
+        if matches!(tracker.state, PoolLifecycle::Retired { .. }) {
+            return None;
+        }
+        if tracker.decoded_len == Some(data_len) {
+            tracker.mismatches = 0;
+            return None;
+        }
+        tracker.mismatches += 1;
+        if tracker.mismatches < RETIRE_AFTER_MISMATCHES {
+            return None;
+        }
+        let reason = match tracker.decoded_len {
+            Some(expected) => format!(
+                "account data length changed from {} to {} bytes ({} consecutive notifications)",
+                expected, data_len, tracker.mismatches
+            ),
+            None => format!(
+                "account data ({} bytes) never decoded ({} consecutive notifications)",
+                data_len, tracker.mismatches
+            ),
+        };
+        tracker.state = PoolLifecycle::Retired { reason: reason.clone(), since: Utc::now() };
+        Some(reason)
+    }
+
+    /// 🪦 直接把池子标记为 Retired（owner 变化等），返回是否是新的状态转换
+    pub fn retire(&self, pool_name: &str, reason: &str) -> bool {
+        let mut tracker = self.lifecycle.entry(pool_name.to_string()).or_default();
+        if matches!(tracker.state, PoolLifecycle::Retired { .. }) {
+            return false;
+        }
+        tracker.state = PoolLifecycle::Retired { reason: reason.to_string(), since: Utc::now() };
+        true
+    }
+
+    /// 🔒 把池子标记为 Degraded（已退役的池子保持 Retired），返回是否是新的状态转换
+    pub fn degrade(&self, pool_name: &str, reason: DegradedReason) -> bool {
+        let mut tracker = self.lifecycle.entry(pool_name.to_string()).or_default();
+        match &tracker.state {
+            PoolLifecycle::Retired { .. } => false,
+            PoolLifecycle::Degraded { reason: current, .. } if *current == reason => false,
+            _ => {
+                tracker.state = PoolLifecycle::Degraded { reason, since: Utc::now() };
+                true
+            }
+        }
+    }
+
+    /// 🔒 校验通过后恢复 Degraded 的池子，返回池子之前是否处于 Degraded
+    pub fn clear_degraded(&self, pool_name: &str) -> bool {
+        match self.lifecycle.get_mut(pool_name) {
+            Some(mut tracker) if matches!(tracker.state, PoolLifecycle::Degraded { .. }) => {
+                tracker.state = PoolLifecycle::Active;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 🔒 所有被降级的池子（按名称排序）
+    pub fn degraded_pools(&self) -> Vec<DegradedPool> {
+        let mut pools: Vec<DegradedPool> = self.lifecycle
+            .iter()
+            .filter_map(|entry| match &entry.state {
+                PoolLifecycle::Degraded { reason, since } => Some(DegradedPool {
+                    pool_name: entry.key().clone(),
+                    reason: reason.clone(),
+                    since: *since,
+                }),
+                _ => None,
+            })
+            .collect();
+        pools.sort_by(|a, b| a.pool_name.cmp(&b.pool_name));
+        pools
+    }
+
+    /// 🪦 池子是否已退役
+    pub fn is_retired(&self, pool_name: &str) -> bool {
+        self.lifecycle
+            .get(pool_name)
+            .is_some_and(|tracker| matches!(tracker.state, PoolLifecycle::Retired { .. }))
+    }
+
+    /// 🪦 池子当前的生命周期状态
+    pub fn lifecycle(&self, pool_name: &str) -> PoolLifecycle {
+        self.lifecycle
+            .get(pool_name)
+            .map(|tracker| tracker.state.clone())
+            .unwrap_or_default()
+    }
+
+    /// 🪦 恢复退役的池子（热重载时调用），返回池子之前是否处于 Retired
+    pub fn revive(&self, pool_name: &str) -> bool {
+        self.lifecycle
+            .remove_if(pool_name, |_, tracker| matches!(tracker.state, PoolLifecycle::Retired { .. }))
+            .is_some()
+    }
+
+    /// 记入池子当前分钟的计数桶
+    fn record_history(&self, pool_name: &str, now: DateTime<Utc>, f: impl FnOnce(&mut MinuteBucket)) {
+        let minute = now.timestamp().div_euclid(60);
+        let mut history = self.history.entry(pool_name.to_string()).or_default();
+        f(history.bucket_mut(minute));
+    }
+
     /// 记录池子订阅
     pub fn record_subscription(&self, pool_name: &str, pool_address: &str) {
         let key = pool_name.to_string();
@@ -160,28 +524,160 @@ impl PoolStatsCollector {
             .entry(key.clone())
             .and_modify(|stats| stats.record_subscription())
             .or_insert_with(|| PoolStats::new(key, pool_address.to_string()));
+        self.record_history(pool_name, Utc::now(), |bucket| bucket.subscriptions += 1);
     }
 
     /// 记录价格更新
     pub fn record_price_update(&self, pool_name: &str, price: f64) {
-        if let Some(mut stats) = self.stats.get_mut(pool_name) {
-            stats.record_price_update(price, self.price_change_threshold);
-            // 🔥 每次价格更新也算一次订阅活动（WebSocket消息接收）
-            stats.record_subscription();
+        self.record_price_update_at(pool_name, price, Utc::now());
+    }
+
+    fn record_price_update_at(&self, pool_name: &str, price: f64, now: DateTime<Utc>) {
+        let change = match self.stats.get_mut(pool_name) {
+            Some(mut stats) => {
+                let change = stats.record_price_update(price, self.price_change_threshold);
+                // 🔥 每次价格更新也算一次订阅活动（WebSocket消息接收）
+                stats.record_subscription();
+                change
+            }
+            None => return,
+        };
+        let threshold = self.price_change_threshold;
+        self.record_history(pool_name, now, |bucket| {
+            bucket.price_updates += 1;
+            bucket.subscriptions += 1;
+            if let Some(change_percent) = change {
+                bucket.max_price_change_percent = bucket.max_price_change_percent.max(change_percent);
+                if change_percent >= threshold {
+                    bucket.significant_price_changes += 1;
+                }
+            }
+        });
+    }
+
+    /// ⛓️ 记录一次更新相对链头的延迟（链头未知时不记录）
+    pub fn record_head_lag(&self, pool_name: &str, slot: u64) {
+        if let Some(lag) = crate::chain_head::global().lag(slot) {
+            self.record_head_lag_slots(pool_name, lag);
+        }
+    }
+
+    fn record_head_lag_slots(&self, pool_name: &str, lag: u64) {
+        if !self.stats.contains_key(pool_name) {
+            return;
         }
+        self.head_lag.entry(pool_name.to_string()).or_default().push(lag);
+    }
+
+    /// ⛓️ 池子的链头延迟（没有样本时为 None）
+    pub fn head_lag(&self, pool_name: &str) -> Option<HeadLag> {
+        self.head_lag.get(pool_name).and_then(|window| window.summary())
+    }
+
+    /// ⛓️ 所有有样本的池子的链头延迟（按 p95 降序）
+    pub fn head_lags(&self) -> Vec<(String, HeadLag)> {
+        let mut lags: Vec<(String, HeadLag)> = self.head_lag
+            .iter()
+            .filter_map(|entry| Some((entry.key().clone(), entry.value().summary()?)))
+            .collect();
+        lags.sort_by(|a, b| b.1.p95.cmp(&a.1.p95).then_with(|| a.0.cmp(&b.0)));
+        lags
+    }
+
+    /// ⛓️ p95 链头延迟超过 `max_p95_slots` 的池子（按 p95 降序）
+    pub fn lagging_pools(&self, max_p95_slots: u64) -> Vec<(String, HeadLag)> {
+        let mut lags = self.head_lags();
+        lags.retain(|(_, lag)| lag.p95 > max_p95_slots);
+        lags
     }
 
     /// 记录vault更新
     pub fn record_vault_update(&self, pool_name: &str) {
         if let Some(mut stats) = self.stats.get_mut(pool_name) {
             stats.record_vault_update();
+        } else {
+            return;
         }
+        self.record_history(pool_name, Utc::now(), |bucket| bucket.vault_updates += 1);
     }
 
     /// 记录错误
     pub fn record_error(&self, pool_name: &str) {
         if let Some(mut stats) = self.stats.get_mut(pool_name) {
             stats.record_error();
+        } else {
+            return;
+        }
+        self.record_history(pool_name, Utc::now(), |bucket| bucket.errors += 1);
+    }
+
+    /// 🔁 记录池子因长时间没有通知而重新订阅
+    pub fn record_resubscribe(&self, pool_name: &str) {
+        if let Some(mut stats) = self.stats.get_mut(pool_name) {
+            stats.record_resubscribe();
+        }
+    }
+
+    /// 🧯 记录熔断触发
+    pub fn record_quarantine(&self, pool_name: &str, reason: TripReason) {
+        if let Some(mut stats) = self.stats.get_mut(pool_name) {
+            stats.record_quarantine(reason);
+        }
+    }
+
+    /// 🧯 记录解除隔离
+    pub fn record_quarantine_cleared(&self, pool_name: &str) {
+        if let Some(mut stats) = self.stats.get_mut(pool_name) {
+            stats.quarantined = None;
+        }
+    }
+
+    /// 🔁 重新订阅至少 `min_resubscribes` 次的池子（按次数降序）
+    pub fn flaky_subscriptions(&self, min_resubscribes: u64) -> Vec<PoolStats> {
+        let mut flaky: Vec<PoolStats> = self.stats
+            .iter()
+            .filter(|entry| entry.value().resubscribes >= min_resubscribes)
+            .map(|entry| entry.value().clone())
+            .collect();
+        flaky.sort_by(|a, b| b.resubscribes.cmp(&a.resubscribes).then_with(|| a.pool_name.cmp(&b.pool_name)));
+        flaky
+    }
+
+    /// 记录因 DEX 被禁用而跳过的池子 / 账户
+    pub fn record_skipped_disabled(&self, dex: &str) {
+        *self.skipped_disabled.entry(dex.to_string()).or_insert(0) += 1;
+    }
+
+    /// 某个 DEX 被跳过的次数
+    pub fn skipped_disabled(&self, dex: &str) -> u64 {
+        self.skipped_disabled.get(dex).map(|count| *count).unwrap_or(0)
+    }
+
+    /// 移除池子统计（池子从配置中删除时调用）
+    pub fn remove(&self, pool_name: &str) -> Option<PoolStats> {
+        self.history.remove(pool_name);
+        self.head_lag.remove(pool_name);
+        self.lifecycle.remove(pool_name);
+        self.stats.remove(pool_name).map(|(_, stats)| stats)
+    }
+
+    /// 池子改名：统计随名称迁移
+    pub fn rename(&self, old_name: &str, new_name: &str) {
+        if old_name == new_name {
+            return;
+        }
+        if let Some((_, mut stats)) = self.stats.remove(old_name) {
+            stats.pool_name = new_name.to_string();
+            self.stats.insert(new_name.to_string(), stats);
+        }
+        if let Some((_, history)) = self.history.remove(old_name) {
+            self.history.insert(new_name.to_string(), history);
+        }
+        if let Some((_, lag)) = self.head_lag.remove(old_name) {
+            self.head_lag.insert(new_name.to_string(), lag);
+        }
+        if let Some((_, lifecycle)) = self.lifecycle.remove(old_name) {
+            self.lifecycle.insert(new_name.to_string(), lifecycle);
         }
     }
 
@@ -193,6 +689,66 @@ impl PoolStatsCollector {
             .collect()
     }
 
+    /// 📈 按池子的价格 / vault 更新计数（Prometheus 格式）
+    pub fn write_prometheus(&self, writer: &mut PrometheusWriter) {
+        let mut stats = self.get_all_stats();
+        stats.sort_by(|a, b| a.pool_name.cmp(&b.pool_name));
+
+        writer.family(
+            "pool_cache_pool_price_updates_total",
+            "Price updates applied per pool",
+            MetricKind::Counter,
+        );
+        for s in &stats {
+            writer.sample("pool_cache_pool_price_updates_total", &[("pool", &s.pool_name)], s.price_updates as f64);
+        }
+
+        writer.family(
+            "pool_cache_vault_updates_total",
+            "Vault account updates received per pool",
+            MetricKind::Counter,
+        );
+        for s in &stats {
+            writer.sample("pool_cache_vault_updates_total", &[("pool", &s.pool_name)], s.vault_updates as f64);
+        }
+
+        writer.family(
+            "pool_cache_pool_quarantine_trips_total",
+            "Circuit breaker trips per pool (suspicious price / reserve jumps)",
+            MetricKind::Counter,
+        );
+        for s in &stats {
+            writer.sample("pool_cache_pool_quarantine_trips_total", &[("pool", &s.pool_name)], s.quarantine_trips as f64);
+        }
+
+        let lags: Vec<(&str, HeadLag)> = stats.iter()
+            .filter_map(|s| Some((s.pool_name.as_str(), self.head_lag(&s.pool_name)?)))
+            .collect();
+        writer.family(
+            "pool_cache_pool_head_lag_slots",
+            "Slots between the chain head and a pool's account notifications (rolling window)",
+            MetricKind::Gauge,
+        );
+        for (pool, lag) in &lags {
+            writer.sample("pool_cache_pool_head_lag_slots", &[("pool", pool), ("quantile", "0.5")], lag.p50 as f64);
+            writer.sample("pool_cache_pool_head_lag_slots", &[("pool", pool), ("quantile", "0.95")], lag.p95 as f64);
+        }
+
+        let mut skipped: Vec<(String, u64)> = self.skipped_disabled
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect();
+        skipped.sort();
+        writer.family(
+            "pool_cache_pool_skipped_disabled_total",
+            "Pools / account updates skipped because their DEX is disabled",
+            MetricKind::Counter,
+        );
+        for (dex, count) in &skipped {
+            writer.sample("pool_cache_pool_skipped_disabled_total", &[("dex", dex)], *count as f64);
+        }
+    }
+
     /// 获取单个池子统计
     pub fn get_pool_stats(&self, pool_name: &str) -> Option<PoolStats> {
         self.stats.get(pool_name).map(|entry| entry.value().clone())
@@ -219,197 +775,226 @@ impl PoolStatsCollector {
             .sum()
     }
 
-    /// 打印统计摘要
+    /// 时间窗口内每个池子的活动（按窗口内订阅次数降序）
     ///
-    /// 活跃池子统计口径：最近 `time_window_seconds` 秒内有订阅/更新
-    pub fn print_summary(&self, time_window_seconds: i64) {
-        let all_stats = self.get_all_stats();
-        let now = Utc::now();
-        
-        // 过滤时间窗口内「最近有订阅/更新」的池子
-        let active_stats: Vec<_> = all_stats
+    /// 窗口按分钟对齐，且不早于池子首次订阅；超过 24 小时的窗口按 24 小时计。
+    pub fn window_stats(&self, window_secs: u64) -> Vec<PoolWindowStats> {
+        self.window_stats_at(window_secs, Utc::now())
+    }
+
+    fn window_stats_at(&self, window_secs: u64, now: DateTime<Utc>) -> Vec<PoolWindowStats> {
+        let window_secs = window_secs.clamp(1, HISTORY_MINUTES as u64 * 60) as i64;
+        let from_minute = (now.timestamp() - window_secs).div_euclid(60);
+
+        let mut pools: Vec<PoolWindowStats> = self.stats
             .iter()
-            .filter(|s| {
-                let seconds_since_last_sub = (now - s.last_subscription).num_seconds();
-                seconds_since_last_sub <= time_window_seconds
+            .map(|entry| {
+                let stats = entry.value();
+                let totals = self.history
+                    .get(entry.key())
+                    .map(|history| history.sum_since(from_minute))
+                    .unwrap_or_default();
+                let start = (from_minute * 60).max(stats.first_subscription.timestamp());
+                let duration_secs = (now.timestamp() - start).max(1) as f64;
+                PoolWindowStats {
+                    pool_name: stats.pool_name.clone(),
+                    pool_address: stats.pool_address.clone(),
+                    dex: dex_of(&stats.pool_name).to_string(),
+                    window_secs: duration_secs as u64,
+                    subscriptions: totals.subscriptions,
+                    price_updates: totals.price_updates,
+                    significant_price_changes: totals.significant_price_changes,
+                    max_price_change_percent: totals.max_price_change_percent,
+                    vault_updates: totals.vault_updates,
+                    error_count: totals.errors,
+                    activity_score: activity_score(
+                        totals.price_updates,
+                        totals.subscriptions,
+                        totals.significant_price_changes,
+                        totals.vault_updates,
+                        duration_secs,
+                    ),
+                    updates_per_minute: totals.price_updates as f64 / duration_secs * 60.0,
+                    head_lag: self.head_lag(entry.key()),
+                }
             })
             .collect();
 
+        pools.sort_by(|a, b| b.subscriptions.cmp(&a.subscriptions).then_with(|| a.pool_name.cmp(&b.pool_name)));
+        pools
+    }
+
+    /// 时间窗口内按 DEX 汇总（按订阅次数降序）
+    pub fn dex_stats(&self, window_secs: u64) -> Vec<DexWindowStats> {
+        Self::group_by_dex(&self.window_stats(window_secs))
+    }
+
+    fn group_by_dex(pools: &[PoolWindowStats]) -> Vec<DexWindowStats> {
+        let mut groups: HashMap<&str, (usize, u64, u64)> = HashMap::new();
+        for pool in pools {
+            let group = groups.entry(pool.dex.as_str()).or_default();
+            group.0 += 1;
+            group.1 += pool.subscriptions;
+            group.2 += pool.price_updates;
+        }
+
+        let grand_total: u64 = pools.iter().map(|p| p.subscriptions).sum();
+        let mut dexes: Vec<DexWindowStats> = groups
+            .into_iter()
+            .map(|(dex, (pool_count, subscriptions, price_updates))| DexWindowStats {
+                dex: dex.to_string(),
+                pools: pool_count,
+                subscriptions,
+                price_updates,
+                avg_subscriptions_per_pool: subscriptions / pool_count as u64,
+                subscription_share_percent: if grand_total > 0 {
+                    subscriptions as f64 / grand_total as f64 * 100.0
+                } else {
+                    0.0
+                },
+            })
+            .collect();
+
+        dexes.sort_by(|a, b| b.subscriptions.cmp(&a.subscriptions).then_with(|| a.dex.cmp(&b.dex)));
+        dexes
+    }
+
+    /// 打印统计摘要
+    ///
+    /// 活跃池子统计口径：最近 `time_window_seconds` 秒内有订阅/更新
+    pub fn print_summary(&self, time_window_seconds: i64) {
+        let pools = self.window_stats(time_window_seconds.max(1) as u64);
+        let active = pools.iter().filter(|p| p.subscriptions > 0).count();
+
         println!("\n╔═══════════════════════════════════════════════════════════════════════════╗");
         println!("║              🔥 池子活跃度统计报告 - 时间窗口: {}秒             ║", time_window_seconds);
         println!("╠═══════════════════════════════════════════════════════════════════════════╣");
-        println!("║  总池子数:          {:>8}                                              ║", all_stats.len());
-        println!("║  活跃池子数:        {:>8}                                              ║", active_stats.len());
-        println!("║  总订阅次数:        {:>8}                                              ║", self.total_subscriptions());
-        println!("║  总更新次数:        {:>8}                                              ║", self.total_updates());
+        println!("║  总池子数:          {:>8}                                              ║", pools.len());
+        println!("║  活跃池子数:        {:>8}                                              ║", active);
+        println!("║  窗口订阅次数:      {:>8}                                              ║", pools.iter().map(|p| p.subscriptions).sum::<u64>());
+        println!("║  窗口更新次数:      {:>8}                                              ║", pools.iter().map(|p| p.price_updates).sum::<u64>());
         println!("╚═══════════════════════════════════════════════════════════════════════════╝\n");
     }
 
     /// 打印详细统计（TOP N池子）- 显示每分钟订阅次数
     pub fn print_detailed_stats(&self, top_n: usize, time_window_seconds: i64) {
-        let mut all_stats = self.get_all_stats();
-        
-        // 过滤时间窗口内的池子
-        all_stats.retain(|s| s.uptime_seconds() <= time_window_seconds);
-        
-        // 按订阅次数排序（显示最活跃的）
-        all_stats.sort_by(|a, b| {
-            b.total_subscriptions
-                .cmp(&a.total_subscriptions)
-        });
-
-        let display_count = all_stats.len().min(top_n);
+        let mut pools = self.window_stats(time_window_seconds.max(1) as u64);
+        pools.retain(|p| p.subscriptions > 0);
+        let display_count = pools.len().min(top_n);
 
         println!("\n╔═══════════════════════════════════════════════════════════════════════════════════════════════════════════════════╗");
         println!("║                          🏆 TOP {} 最活跃池子详细统计（按订阅次数排序）                                           ║", display_count);
         println!("╠═══════════════════════════════════════════════════════════════════════════════════════════════════════════════════╣");
-        println!("║ 排名 │ 池子名称                    │ 累计订阅 │ 每分钟订阅 │ 更新  │ 显著变化 │ 最大变化% │ Vault │ 活跃度 ║");
+        println!("║ 排名 │ 池子名称                    │ 窗口订阅 │ 每分钟订阅 │ 更新  │ 显著变化 │ 最大变化% │ Vault │ 活跃度 ║");
         println!("╠═══════════════════════════════════════════════════════════════════════════════════════════════════════════════════╣");
 
-        for (idx, stats) in all_stats.iter().take(display_count).enumerate() {
-            let pool_name_display = if stats.pool_name.len() > 25 {
-                format!("{}...", &stats.pool_name[..22])
+        for (idx, pool) in pools.iter().take(display_count).enumerate() {
+            let pool_name_display = if pool.pool_name.len() > 25 {
+                format!("{}...", &pool.pool_name[..22])
             } else {
-                format!("{:<25}", stats.pool_name)
+                format!("{:<25}", pool.pool_name)
             };
 
             // 计算每分钟订阅次数
-            let duration_mins = (stats.uptime_seconds() as f64 / 60.0).max(0.0001);
-            let subs_per_min = (stats.total_subscriptions as f64 / duration_mins) as u64;
+            let subs_per_min = (pool.subscriptions as f64 / pool.window_secs as f64 * 60.0) as u64;
 
             println!(
                 "║ {:>4} │ {} │ {:>8} │ {:>10} │ {:>5} │ {:>8} │ {:>8.2}% │ {:>5} │ {:>6.1} ║",
                 idx + 1,
                 pool_name_display,
-                stats.total_subscriptions,
+                pool.subscriptions,
                 subs_per_min,
-                stats.price_updates,
-                stats.significant_price_changes,
-                stats.max_price_change_percent,
-                stats.vault_updates,
-                stats.activity_score()
+                pool.price_updates,
+                pool.significant_price_changes,
+                pool.max_price_change_percent,
+                pool.vault_updates,
+                pool.activity_score
             );
         }
 
         println!("╚═══════════════════════════════════════════════════════════════════════════════════════════════════════════════════╝\n");
+
+        // 🔁 反复重新订阅的池子：订阅不稳定（服务器丢弃订阅或池子长期无交易）
+        let flaky = self.flaky_subscriptions(2);
+        if !flaky.is_empty() {
+            println!("⚠️  {} pools resubscribed repeatedly after going silent:", flaky.len());
+            for stats in flaky.iter().take(top_n) {
+                println!(
+                    "   🔁 {:<30} {:>4} resubscribes, last update {}s ago ({})",
+                    stats.pool_name,
+                    stats.resubscribes,
+                    (Utc::now() - stats.last_subscription).num_seconds(),
+                    stats.pool_address
+                );
+            }
+            println!();
+        }
     }
 
     /// 打印每分钟统计 + DEX分组统计
     pub fn print_per_minute_stats(&self) {
-        let all_stats = self.get_all_stats();
-        
-        // 只统计最近1分钟内有更新的池子
-        let recent_stats: Vec<_> = all_stats
-            .iter()
-            .filter(|s| {
-                let seconds_since_last_sub = (Utc::now() - s.last_subscription).num_seconds();
-                seconds_since_last_sub <= 60
-            })
-            .collect();
+        let pools = self.window_stats(60);
 
-        if recent_stats.is_empty() {
+        // 只统计最近1分钟内有更新的池子
+        let recent: Vec<_> = pools.iter().filter(|p| p.subscriptions > 0).collect();
+        if recent.is_empty() {
             return;
         }
 
-        let total_subs_per_min: u64 = recent_stats
+        let total_subs_per_min: u64 = recent
             .iter()
-            .map(|s| {
-                let duration = (Utc::now() - s.first_subscription).num_seconds().max(1) as f64;
-                ((s.total_subscriptions as f64 / duration) * 60.0) as u64
-            })
-            .sum();
-
-        let total_updates_per_min: u64 = recent_stats
-            .iter()
-            .map(|s| {
-                let duration = (Utc::now() - s.first_subscription).num_seconds().max(1) as f64;
-                ((s.price_updates as f64 / duration) * 60.0) as u64
-            })
+            .map(|p| (p.subscriptions as f64 / p.window_secs as f64 * 60.0) as u64)
             .sum();
+        let total_updates_per_min: u64 = recent.iter().map(|p| p.updates_per_minute as u64).sum();
 
         println!("\n┌─────────────────────────────────────────────────────────┐");
         println!("│  📊 每分钟统计 (最近60秒活跃的池子)                   │");
         println!("├─────────────────────────────────────────────────────────┤");
-        println!("│  活跃池子:          {:>8}                           │", recent_stats.len());
+        println!("│  活跃池子:          {:>8}                           │", recent.len());
         println!("│  订阅/分钟:         {:>8}                           │", total_subs_per_min);
         println!("│  更新/分钟:         {:>8}                           │", total_updates_per_min);
         println!("└─────────────────────────────────────────────────────────┘\n");
         
         // 🔥 按DEX分组统计
-        self.print_dex_group_stats();
+        Self::print_dex_table(&Self::group_by_dex(&pools));
     }
     
-    /// 🔥 按DEX分组统计每个池子的订阅次数
+    /// 🔥 按DEX分组统计每个池子的订阅次数（最近 24 小时）
     pub fn print_dex_group_stats(&self) {
-        use std::collections::HashMap;
-        
-        let all_stats = self.get_all_stats();
-        
-        // 按DEX分组
-        let mut dex_groups: HashMap<String, Vec<&PoolStats>> = HashMap::new();
-        
-        for stats in &all_stats {
-            // 从池子名称提取DEX名称（括号内的部分）
-            let dex_name = if let Some(start) = stats.pool_name.rfind('(') {
-                if let Some(end) = stats.pool_name.rfind(')') {
-                    stats.pool_name[start+1..end].to_string()
-                } else {
-                    "Unknown".to_string()
-                }
-            } else {
-                "Unknown".to_string()
-            };
-            
-            dex_groups.entry(dex_name).or_insert_with(Vec::new).push(stats);
-        }
-        
-        // 按DEX的总订阅次数排序
-        let mut dex_list: Vec<_> = dex_groups.iter().collect();
-        dex_list.sort_by(|a, b| {
-            let a_total: u64 = a.1.iter().map(|s| s.total_subscriptions).sum();
-            let b_total: u64 = b.1.iter().map(|s| s.total_subscriptions).sum();
-            b_total.cmp(&a_total)
-        });
-        
+        Self::print_dex_table(&self.dex_stats(HISTORY_MINUTES as u64 * 60));
+    }
+
+    fn print_dex_table(dexes: &[DexWindowStats]) {
         println!("╔════════════════════════════════════════════════════════════════════════╗");
         println!("║                  📊 按DEX分组统计（订阅活跃度）                       ║");
         println!("╠════════════════════════════════════════════════════════════════════════╣");
-        println!("║ DEX名称              │ 池子数 │ 累计订阅  │ 平均订阅/池 │ 占比      ║");
+        println!("║ DEX名称              │ 池子数 │ 窗口订阅  │ 平均订阅/池 │ 占比      ║");
         println!("╠════════════════════════════════════════════════════════════════════════╣");
-        
-        let grand_total: u64 = all_stats.iter().map(|s| s.total_subscriptions).sum();
-        
-        for (dex_name, pools) in dex_list {
-            let pool_count = pools.len();
-            let total_subs: u64 = pools.iter().map(|s| s.total_subscriptions).sum();
-            let avg_subs = if pool_count > 0 { total_subs / pool_count as u64 } else { 0 };
-            let percentage = if grand_total > 0 {
-                (total_subs as f64 / grand_total as f64) * 100.0
-            } else {
-                0.0
-            };
-            
-            let dex_display = if dex_name.len() > 18 {
-                format!("{}...", &dex_name[..15])
+
+        for dex in dexes {
+            let dex_display = if dex.dex.len() > 18 {
+                format!("{}...", &dex.dex[..15])
             } else {
-                format!("{:<18}", dex_name)
+                format!("{:<18}", dex.dex)
             };
             
             println!(
                 "║ {}   │ {:>6} │ {:>9} │ {:>11} │ {:>6.1}%  ║",
                 dex_display,
-                pool_count,
-                total_subs,
-                avg_subs,
-                percentage
+                dex.pools,
+                dex.subscriptions,
+                dex.avg_subscriptions_per_pool,
+                dex.subscription_share_percent
             );
         }
-        
+
+        let pool_count: usize = dexes.iter().map(|d| d.pools).sum();
+        let grand_total: u64 = dexes.iter().map(|d| d.subscriptions).sum();
         println!("╠════════════════════════════════════════════════════════════════════════╣");
         println!("║ 合计                 │ {:>6} │ {:>9} │ {:>11} │ 100.0%  ║",
-            all_stats.len(),
+            pool_count,
             grand_total,
-            if all_stats.len() > 0 { grand_total / all_stats.len() as u64 } else { 0 }
+            if pool_count > 0 { grand_total / pool_count as u64 } else { 0 }
         );
         println!("╚════════════════════════════════════════════════════════════════════════╝\n");
     }
@@ -432,6 +1017,9 @@ impl PoolStatsCollector {
     "cumulative_price_change": {:.4},
     "vault_updates": {},
     "error_count": {},
+    "resubscribes": {},
+    "quarantine_trips": {},
+    "quarantined": {},
     "activity_score": {:.2},
     "uptime_seconds": {}
 }}"#,
@@ -444,6 +1032,9 @@ impl PoolStatsCollector {
                     s.cumulative_price_change,
                     s.vault_updates,
                     s.error_count,
+                    s.resubscribes,
+                    s.quarantine_trips,
+                    s.quarantined.map_or("null".to_string(), |reason| format!("\"{}\"", reason.as_str())),
                     s.activity_score(),
                     s.uptime_seconds()
                 )
@@ -514,6 +1105,181 @@ mod tests {
         assert_eq!(stats.price_updates, 1);
     }
 
+    #[test]
+    fn test_skipped_disabled_not_counted_as_errors() {
+        let collector = PoolStatsCollector::new(0.1);
+        collector.record_subscription("GOON/USDC", "addr1");
+        collector.record_skipped_disabled("goonfi");
+        collector.record_skipped_disabled("goonfi");
+
+        assert_eq!(collector.skipped_disabled("goonfi"), 2);
+        assert_eq!(collector.skipped_disabled("whirlpool"), 0);
+        assert_eq!(collector.get_pool_stats("GOON/USDC").unwrap().error_count, 0);
+    }
+
+    #[test]
+    fn test_flaky_subscriptions_sorted_by_resubscribes() {
+        let collector = PoolStatsCollector::new(0.1);
+        collector.record_subscription("SOL/USDC", "addr1");
+        collector.record_subscription("BONK/SOL", "addr2");
+        collector.record_subscription("JUP/USDC", "addr3");
+        collector.record_resubscribe("SOL/USDC");
+        for _ in 0..3 {
+            collector.record_resubscribe("BONK/SOL");
+        }
+        collector.record_resubscribe("unknown-pool");
+
+        let flaky: Vec<String> = collector.flaky_subscriptions(1).into_iter().map(|s| s.pool_name).collect();
+        assert_eq!(flaky, vec!["BONK/SOL", "SOL/USDC"]);
+        assert_eq!(collector.flaky_subscriptions(2).len(), 1);
+        assert!(collector.generate_json_report().contains("\"resubscribes\": 3"));
+    }
+
+    #[test]
+    fn test_quarantine_trips_and_clear() {
+        let collector = PoolStatsCollector::new(0.1);
+        collector.record_subscription("SOL/USDC", "addr1");
+        collector.record_quarantine("SOL/USDC", TripReason::PriceJump);
+        collector.record_quarantine("SOL/USDC", TripReason::ReserveJump);
+
+        let stats = collector.get_pool_stats("SOL/USDC").unwrap();
+        assert_eq!((stats.quarantine_trips, stats.quarantined), (2, Some(TripReason::ReserveJump)));
+        assert!(collector.generate_json_report().contains("\"quarantined\": \"reserve_jump\""));
+
+        collector.record_quarantine_cleared("SOL/USDC");
+        let stats = collector.get_pool_stats("SOL/USDC").unwrap();
+        assert_eq!((stats.quarantine_trips, stats.quarantined), (2, None));
+        assert!(collector.generate_json_report().contains("\"quarantine_trips\": 2"));
+    }
+
+    #[test]
+    fn test_window_stats_only_counts_recent_minutes() {
+        let collector = PoolStatsCollector::new(0.5);
+        let now = Utc::now();
+        let mut stats = PoolStats::new("SOL/USDC (Raydium V4)".to_string(), "addr1".to_string());
+        stats.first_subscription = now - chrono::Duration::hours(3);
+        collector.stats.insert(stats.pool_name.clone(), stats);
+
+        // 两小时前的一次更新不计入 1 小时窗口
+        collector.record_price_update_at("SOL/USDC (Raydium V4)", 100.0, now - chrono::Duration::hours(2));
+        collector.record_price_update_at("SOL/USDC (Raydium V4)", 101.0, now - chrono::Duration::seconds(30));
+        collector.record_price_update_at("SOL/USDC (Raydium V4)", 101.1, now);
+
+        let pool = &collector.window_stats_at(3600, now)[0];
+        assert_eq!((pool.price_updates, pool.significant_price_changes), (2, 1));
+        assert!((pool.max_price_change_percent - 1.0).abs() < 1e-9);
+        assert_eq!(pool.dex, "Raydium V4");
+        assert!(pool.window_secs >= 3600 && pool.window_secs < 3660);
+
+        let day = &collector.window_stats_at(86_400, now)[0];
+        assert_eq!(day.price_updates, 3);
+    }
+
+    #[test]
+    fn test_history_keeps_last_24_hours() {
+        let mut history = ActivityHistory::default();
+        for minute in 0..(HISTORY_MINUTES as i64 + 30) {
+            history.bucket_mut(minute).price_updates += 1;
+        }
+        assert_eq!(history.buckets.len(), HISTORY_MINUTES);
+        assert_eq!(history.buckets.front().unwrap().minute, 30);
+        assert_eq!(history.sum_since(0).price_updates, HISTORY_MINUTES as u64);
+    }
+
+    #[test]
+    fn test_head_lag_percentiles_and_lagging_pools() {
+        let collector = PoolStatsCollector::new(0.1);
+        collector.record_subscription("fast", "addr1");
+        collector.record_subscription("slow", "addr2");
+        collector.record_head_lag_slots("unknown", 50); // 未订阅的池子不记录
+
+        for lag in 0..100 {
+            collector.record_head_lag_slots("fast", lag % 3);
+            collector.record_head_lag_slots("slow", 5 + lag / 10);
+        }
+        assert_eq!(collector.head_lag("fast").map(|l| (l.p50, l.p95)), Some((1, 2)));
+        assert_eq!(collector.head_lag("slow"), Some(HeadLag { samples: 100, last: 14, p50: 9, p95: 14 }));
+        assert!(collector.head_lag("unknown").is_none());
+
+        let lagging = collector.lagging_pools(8);
+        assert_eq!(lagging.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), vec!["slow"]);
+
+        // 滚动窗口只保留最近 HEAD_LAG_SAMPLES 个样本
+        for _ in 0..HEAD_LAG_SAMPLES {
+            collector.record_head_lag_slots("slow", 1);
+        }
+        assert_eq!(collector.head_lag("slow").map(|l| (l.samples, l.p95)), Some((HEAD_LAG_SAMPLES, 1)));
+        assert!(collector.lagging_pools(8).is_empty());
+    }
+
+    #[test]
+    fn test_lifecycle_retires_after_consecutive_length_mismatches() {
+        let collector = PoolStatsCollector::new(0.1);
+        collector.record_decoded("pool", 752);
+
+        // 同长度的失败不计数，中间一次成功解析会重置计数
+        assert!(collector.record_decode_failure("pool", 752).is_none());
+        assert!(collector.record_decode_failure("pool", 1_024).is_none());
+        collector.record_decoded("pool", 752);
+        for _ in 1..RETIRE_AFTER_MISMATCHES {
+            assert!(collector.record_decode_failure("pool", 1_024).is_none());
+        }
+        assert!(!collector.is_retired("pool"));
+
+        let reason = collector.record_decode_failure("pool", 1_024).unwrap();
+        assert!(reason.contains("752 to 1024"));
+        assert!(collector.is_retired("pool"));
+        // 只转换一次
+        assert!(collector.record_decode_failure("pool", 1_024).is_none());
+        assert!(!collector.retire("pool", "owner changed"));
+
+        collector.rename("pool", "renamed");
+        assert!(matches!(collector.lifecycle("renamed"), PoolLifecycle::Retired { .. }));
+        assert!(collector.revive("renamed"));
+        assert_eq!(collector.lifecycle("renamed"), PoolLifecycle::Active);
+        assert!(!collector.revive("renamed"));
+    }
+
+    #[test]
+    fn test_lifecycle_degraded_until_owner_verified() {
+        let collector = PoolStatsCollector::new(0.1);
+        let mismatch = DegradedReason::OwnerMismatch {
+            expected: "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8".to_string(),
+            actual: "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc".to_string(),
+        };
+        assert!(collector.degrade("pool", mismatch.clone()));
+        assert!(!collector.degrade("pool", mismatch.clone()));
+        assert_eq!(collector.lifecycle("pool").label(), "degraded");
+        let degraded = collector.degraded_pools();
+        assert_eq!(degraded.len(), 1);
+        assert!(degraded[0].reason.to_string().starts_with("owner_mismatch{expected: 675k"));
+
+        assert!(collector.clear_degraded("pool"));
+        assert!(!collector.clear_degraded("pool"));
+        assert_eq!(collector.lifecycle("pool"), PoolLifecycle::Active);
+        assert!(collector.degraded_pools().is_empty());
+
+        // 退役优先于降级
+        collector.retire("retired", "account closed");
+        assert!(!collector.degrade("retired", mismatch));
+        assert!(collector.is_retired("retired"));
+    }
+
+    #[test]
+    fn test_dex_stats_group_by_name_suffix() {
+        let collector = PoolStatsCollector::new(0.1);
+        collector.record_subscription("SOL/USDC (Raydium V4)", "addr1");
+        collector.record_subscription("SOL/USDC (Raydium V4)", "addr1");
+        collector.record_subscription("SOL/USDT (Raydium V4)", "addr2");
+        collector.record_subscription("JUP/USDC (Whirlpool)", "addr3");
+        collector.record_subscription("mystery", "addr4");
+
+        let dexes = collector.dex_stats(3600);
+        let summary: Vec<_> = dexes.iter().map(|d| (d.dex.as_str(), d.pools, d.subscriptions)).collect();
+        assert_eq!(summary, vec![("Raydium V4", 2, 3), ("Unknown", 1, 1), ("Whirlpool", 1, 1)]);
+        assert!((dexes[0].subscription_share_percent - 60.0).abs() < 1e-9);
+    }
+
     #[test]
     fn test_activity_score() {
         let mut stats = PoolStats::new("SOL/USDC".to_string(), "test_addr".to_string());
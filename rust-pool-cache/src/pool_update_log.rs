@@ -0,0 +1,107 @@
+/*!
+ * 🎞️ 池子更新记录器（回放数据源）
+ *
+ * WebSocket 路径每次写 PriceCache 时把同一条更新（含 slot）投递到这里，后台任务攒批写入
+ * pool_update_history。投递用 try_send，写库跟不上时丢弃并计数，绝不阻塞更新路径。
+ */
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::sync::{broadcast, mpsc, Mutex};
+use tokio::task::JoinHandle;
+use tokio::time::interval;
+use tracing::{info, warn};
+
+use crate::database::{DatabaseManager, PoolUpdateRecord};
+use crate::price_cache::PoolPrice;
+
+/// 投递 channel 容量
+const RECORDER_CHANNEL_CAPACITY: usize = 10_000;
+/// 单批最多写入的更新数
+const RECORDER_BATCH_SIZE: usize = 500;
+/// 不满一批时的最长等待
+const RECORDER_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 更新记录器句柄（可克隆，交给 WebSocket 客户端）
+#[derive(Clone)]
+pub struct PoolUpdateRecorder {
+    tx: mpsc::Sender<PoolUpdateRecord>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl PoolUpdateRecorder {
+    /// 记录一次写入 PriceCache 的更新
+    pub fn record(&self, price: &PoolPrice) {
+        if self.tx.try_send(PoolUpdateRecord::from_price(price, Utc::now())).is_err() {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            if dropped.is_power_of_two() {
+                warn!("🎞️ Pool update recorder is falling behind, {} updates dropped so far", dropped);
+            }
+        }
+    }
+}
+
+/// 启动记录任务；收到关闭信号后写完 channel 中剩余的更新再退出
+pub fn spawn_recorder(
+    db: Arc<Mutex<DatabaseManager>>,
+    mut shutdown: broadcast::Receiver<()>,
+) -> (PoolUpdateRecorder, JoinHandle<()>) {
+    let (tx, mut rx) = mpsc::channel(RECORDER_CHANNEL_CAPACITY);
+    let recorder = PoolUpdateRecorder {
+        tx,
+        dropped: Arc::new(AtomicU64::new(0)),
+    };
+    let dropped = recorder.dropped.clone();
+
+    let handle = tokio::spawn(async move {
+        let mut batch: Vec<PoolUpdateRecord> = Vec::with_capacity(RECORDER_BATCH_SIZE);
+        let mut ticker = interval(RECORDER_FLUSH_INTERVAL);
+        let mut written: u64 = 0;
+
+        loop {
+            let stop = tokio::select! {
+                update = rx.recv() => match update {
+                    Some(update) => {
+                        batch.push(update);
+                        if batch.len() < RECORDER_BATCH_SIZE {
+                            continue;
+                        }
+                        false
+                    }
+                    None => true,
+                },
+                _ = ticker.tick() => false,
+                _ = shutdown.recv() => {
+                    while let Ok(update) = rx.try_recv() {
+                        batch.push(update);
+                    }
+                    true
+                }
+            };
+
+            // 关闭时剩余的更新可能超过一批，分批写完
+            for chunk in batch.chunks(RECORDER_BATCH_SIZE) {
+                match db.lock().await.record_pool_updates(chunk).await {
+                    Ok(()) => written += chunk.len() as u64,
+                    Err(e) => warn!("🎞️ Failed to record {} pool updates: {}", chunk.len(), e),
+                }
+            }
+            batch.clear();
+
+            if stop {
+                break;
+            }
+        }
+
+        info!(
+            "🎞️ Pool update recorder stopped: {} written, {} dropped",
+            written,
+            dropped.load(Ordering::Relaxed)
+        );
+    });
+
+    (recorder, handle)
+}
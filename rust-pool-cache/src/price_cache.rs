@@ -1,13 +1,23 @@
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tokio::sync::broadcast;
-use dashmap::DashMap;
+use dashmap::{DashMap, DashSet};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::state_layer::StateLayer;
+use crate::chain_head::ChainHead;
+use crate::circuit_breaker::{BreakerEvent, CircuitBreaker};
+use crate::dex_interface::ActivityStatus;
+use crate::liquidity::{self, LiquidityUsd, UsdPriceTable};
+use crate::staleness::{StalenessPolicy, StaleReason};
+use crate::state_layer::{exclusion_reason, fill_snapshot_from_map, ExclusionReason, SnapshotFilter, SnapshotResult, StateLayer};
+use crate::account_subscription::Commitment;
 
 /// Pool price information
-#[derive(Clone, Debug)]
+///
+/// 序列化时 `last_update` 写成相对序列化时刻的年龄 `age_ms`，反序列化时按当前时间回拨
+/// （🧊 扫描抓取文件离线重放时年龄保持不变）。
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PoolPrice {
     pub pool_id: String,
     pub dex_name: String,
@@ -19,8 +29,30 @@ pub struct PoolPrice {
     #[allow(dead_code)]
     pub quote_decimals: u8,
     pub price: f64,
+    #[serde(rename = "age_ms", with = "instant_as_age_ms")]
     pub last_update: Instant,
     pub slot: u64,  // 🎯 Solana区块slot，用于数据一致性
+    /// 💧 估算的美元流动性（PriceCache 写入时计算，见 `liquidity`；没有美元价格时为 None）
+    #[serde(default)]
+    pub liquidity_usd: Option<LiquidityUsd>,
+    /// 📡 产生该更新的订阅 commitment（None = 不是来自订阅：RPC 初始化 / 快照预热 / 合成池子）
+    #[serde(default)]
+    pub commitment: Option<Commitment>,
+}
+
+/// `Instant` <-> 年龄（毫秒）
+mod instant_as_age_ms {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(instant: &Instant, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(instant.elapsed().as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Instant, D::Error> {
+        let age_ms = u64::deserialize(deserializer)?;
+        let now = Instant::now();
+        Ok(now.checked_sub(Duration::from_millis(age_ms)).unwrap_or(now))
+    }
 }
 
 /// Price update event for event-driven arbitrage
@@ -65,10 +97,116 @@ impl PoolPrice {
     }
 }
 
+#[cfg(test)]
+impl PoolPrice {
+    /// 测试用池子：Raydium AMM V4、精度 9 / 6、base 侧 100 万个代币且储备与价格一致、slot 1、刚更新
+    ///
+    /// 测试只写关心的字段：`PoolPrice { slot: 7, ..PoolPrice::for_test("pool", "SOL/USDC", 185.0) }`
+    pub fn for_test(pool_id: &str, pair: &str, price: f64) -> Self {
+        Self {
+            pool_id: pool_id.to_string(),
+            dex_name: "Raydium AMM V4".to_string(),
+            pair: pair.to_string(),
+            base_reserve: 1_000_000 * 1_000_000_000,
+            quote_reserve: (1_000_000.0 * price * 1_000_000.0) as u64,
+            base_decimals: 9,
+            quote_decimals: 6,
+            price,
+            last_update: Instant::now(),
+            slot: 1,
+            liquidity_usd: None,
+            commitment: None,
+        }
+    }
+}
+
+/// 交易对二级索引：pair -> pool_id 集合
+///
+/// 在 update_price 中维护，使按交易对查询不再遍历全部池子。
+/// 并发更新同一池子且 pair 变化时索引可能短暂残留旧条目，
+/// 因此查询方需要用主表中的 pair 再校验一次（见 `PriceCache::get_pools_by_pair`）。
+#[derive(Clone, Default)]
+pub struct PairIndex {
+    pairs: Arc<DashMap<String, DashSet<String>>>,
+}
+
+impl PairIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录池子写入；`old_pair` 为写入前主表中的 pair
+    pub fn record(&self, pool_id: &str, new_pair: &str, old_pair: Option<&str>) {
+        if let Some(old) = old_pair {
+            if old == new_pair {
+                return;
+            }
+            if let Some(set) = self.pairs.get(old) {
+                set.remove(pool_id);
+            }
+            // 空集合顺手清理（remove_if 持有分片写锁，不会误删刚插入的条目）
+            self.pairs.remove_if(old, |_, set| set.is_empty());
+        }
+        self.pairs
+            .entry(new_pair.to_string())
+            .or_default()
+            .insert(pool_id.to_string());
+    }
+
+    /// 移除池子（池子被删除时调用）
+    pub fn remove(&self, pool_id: &str, pair: &str) {
+        if let Some(set) = self.pairs.get(pair) {
+            set.remove(pool_id);
+        }
+        self.pairs.remove_if(pair, |_, set| set.is_empty());
+    }
+
+    /// 某交易对下的池子 ID（可能包含待校验的残留条目）
+    pub fn pool_ids(&self, pair: &str) -> Vec<String> {
+        self.pairs
+            .get(pair)
+            .map(|set| set.iter().map(|id| id.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// 所有非空交易对
+    pub fn pairs(&self) -> Vec<String> {
+        self.pairs
+            .iter()
+            .filter(|entry| !entry.value().is_empty())
+            .map(|entry| entry.key().clone())
+            .collect()
+    }
+
+    /// 按索引从主表取出该交易对的池子，并校验 pair 仍然匹配
+    pub fn collect_pools(&self, prices: &DashMap<String, PoolPrice>, pair: &str) -> Vec<PoolPrice> {
+        self.pool_ids(pair)
+            .into_iter()
+            .filter_map(|id| prices.get(&id).map(|entry| entry.clone()))
+            .filter(|pool| pool.pair == pair)
+            .collect()
+    }
+}
+
 /// Thread-safe price cache
 pub struct PriceCache {
     prices: Arc<DashMap<String, PoolPrice>>,
+    pair_index: PairIndex,
     update_tx: broadcast::Sender<PriceUpdateEvent>,
+    /// 按池子类型的新鲜度策略（Complete 扫描使用）
+    staleness: Arc<StalenessPolicy>,
+    /// 💾 从快照恢复、尚未收到实时更新的池子（过期但存在）
+    restored: Arc<DashSet<String>>,
+    /// 🧯 池子级熔断（可疑跳变的池子不进快照）
+    circuit_breaker: Arc<CircuitBreaker>,
+    /// 🧭 与外部参考价格偏离超过排除阈值的池子（由 `reference_price` 任务维护，不进路由和快照）
+    reference_excluded: Arc<DashSet<String>>,
+    /// 🚦 池子最近一次解析出的活跃状态（非 Active 的池子不进路由和快照）
+    activity: Arc<DashMap<String, ActivityStatus>>,
+    /// ⛓️ 设置后快照以链头为最新 slot（见 `with_chain_head_anchor`）
+    chain_head: Option<&'static ChainHead>,
+    /// 💧 代币美元价格（估算池子流动性，由 `liquidity::spawn_refresher` 定期刷新）
+    usd_prices: Arc<UsdPriceTable>,
 }
 
 impl PriceCache {
@@ -76,17 +214,128 @@ impl PriceCache {
         let (update_tx, _) = broadcast::channel(1000);
         Self {
             prices: Arc::new(DashMap::new()),
+            pair_index: PairIndex::new(),
             update_tx,
+            staleness: Arc::new(StalenessPolicy::new()),
+            restored: Arc::new(DashSet::new()),
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
+            reference_excluded: Arc::new(DashSet::new()),
+            activity: Arc::new(DashMap::new()),
+            chain_head: None,
+            usd_prices: Arc::new(UsdPriceTable::new()),
+        }
+    }
+    
+    /// 🧯 启用池子级熔断（默认不启用）
+    pub fn with_circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Arc::new(circuit_breaker);
+        self
+    }
+    
+    /// ⛓️ 一致性快照以链头（而非缓存中的最大 slot）作为最新 slot
+    ///
+    /// 整个数据源一起落后时，默认快照看不出来（池子之间的相对 slot 仍然一致）；
+    /// 锚定链头后这些池子会按 `StaleBySlot` 排除。链头尚未获取时退回缓存中的最大 slot。
+    pub fn with_chain_head_anchor(mut self, chain_head: &'static ChainHead) -> Self {
+        self.chain_head = Some(chain_head);
+        self
+    }
+    
+    /// 快照的参考 slot：缓存中的最大 slot，锚定链头时取两者较大值
+    fn snapshot_reference_slot(&self) -> u64 {
+        let latest_slot = self.get_latest_slot();
+        match self.chain_head.and_then(|head| head.head_slot()) {
+            Some(head_slot) if latest_slot > 0 => latest_slot.max(head_slot),
+            _ => latest_slot,
         }
     }
     
+    /// 新鲜度策略（加载 / 热重载池子级 max_age_ms 覆盖）
+    pub fn staleness(&self) -> &StalenessPolicy {
+        &self.staleness
+    }
+    
+    /// 池子级熔断（加载 / 热重载池子级阈值覆盖，查询隔离状态）
+    pub fn circuit_breaker(&self) -> &CircuitBreaker {
+        &self.circuit_breaker
+    }
+    
+    /// 💧 估算流动性使用的代币美元价格
+    pub fn usd_prices(&self) -> &UsdPriceTable {
+        &self.usd_prices
+    }
+    
+    /// 💧 按当前美元价格重算所有池子的流动性（价格表刷新后调用，不触发价格事件）
+    pub fn refresh_liquidity(&self) {
+        for mut entry in self.prices.iter_mut() {
+            entry.liquidity_usd = liquidity::estimate(&entry, &self.usd_prices);
+        }
+    }
+    
+    /// 池子是否被熔断隔离
+    pub fn is_quarantined(&self, pool_id: &str) -> bool {
+        self.circuit_breaker.is_quarantined(pool_id)
+    }
+    
+    /// 🧭 标记 / 解除池子的参考价格偏离排除，返回状态是否变化
+    pub fn set_reference_excluded(&self, pool_id: &str, excluded: bool) -> bool {
+        if excluded {
+            self.reference_excluded.insert(pool_id.to_string())
+        } else {
+            self.reference_excluded.remove(pool_id).is_some()
+        }
+    }
+    
+    /// 池子是否因偏离外部参考价格被排除
+    pub fn is_reference_excluded(&self, pool_id: &str) -> bool {
+        self.reference_excluded.contains(pool_id)
+    }
+    
+    /// 🚦 记录池子的活跃状态，返回状态是否变化
+    pub fn set_activity_status(&self, pool_id: &str, status: ActivityStatus) -> bool {
+        match self.activity.insert(pool_id.to_string(), status.clone()) {
+            Some(previous) => previous != status,
+            None => true,
+        }
+    }
+    
+    /// 池子最近一次的活跃状态（尚未解析过时为 None）
+    pub fn activity_status(&self, pool_id: &str) -> Option<ActivityStatus> {
+        self.activity.get(pool_id).map(|status| status.clone())
+    }
+    
+    /// 非 Active 状态的标签（Active 或未知时为 None）
+    fn inactive_label(&self, pool_id: &str) -> Option<&'static str> {
+        self.activity.get(pool_id).filter(|status| !status.is_active()).map(|status| status.label())
+    }
+    
+    /// 路由不可用：熔断隔离、参考价格偏离排除或池子非 Active
+    fn is_excluded(&self, pool_id: &str) -> bool {
+        self.circuit_breaker.is_quarantined(pool_id)
+            || self.reference_excluded.contains(pool_id)
+            || self.inactive_label(pool_id).is_some()
+    }
+    
     /// Subscribe to price update events
     pub fn subscribe_updates(&self) -> broadcast::Receiver<PriceUpdateEvent> {
         self.update_tx.subscribe()
     }
     
     /// Update price for a pool
-    pub fn update_price(&self, pool_price: PoolPrice) {
+    ///
+    /// 🧯 写入前交给熔断器对比上一次更新；被隔离的池子照常写入，只是不进快照。
+    /// 💧 写入时按当前美元价格估算流动性（覆盖调用方传入的值）。
+    /// 返回熔断状态变化（供池子统计记录）。
+    pub fn update_price(&self, mut pool_price: PoolPrice) -> Option<BreakerEvent> {
+        pool_price.liquidity_usd = liquidity::estimate(&pool_price, &self.usd_prices);
+        let breaker_event = if self.circuit_breaker.is_enabled() {
+            let previous = self.prices.get(&pool_price.pool_id)
+                .filter(|_| !self.restored.contains(&pool_price.pool_id));
+            self.circuit_breaker.observe(previous.as_deref(), &pool_price, Instant::now())
+        } else {
+            None
+        };
+
         let event = {
             let old_price = self.prices.get(&pool_price.pool_id).map(|p| p.price);
             let new_price = pool_price.price;
@@ -117,7 +366,13 @@ impl PriceCache {
                 }
             };
 
-            self.prices.insert(pool_price.pool_id.clone(), pool_price.clone());
+            let previous = self.prices.insert(pool_price.pool_id.clone(), pool_price.clone());
+            self.restored.remove(&pool_price.pool_id);
+            self.pair_index.record(
+                &pool_price.pool_id,
+                &pool_price.pair,
+                previous.as_ref().map(|p| p.pair.as_str()),
+            );
 
             PriceUpdateEvent {
                 pool_id: pool_price.pool_id,
@@ -131,6 +386,46 @@ impl PriceCache {
 
         // Send event (ignore if no receivers)
         let _ = self.update_tx.send(event);
+        breaker_event
+    }
+    
+    /// 移除池子（热重载删除池子时调用），返回被移除的条目
+    pub fn remove_price(&self, pool_id: &str) -> Option<PoolPrice> {
+        let (_, removed) = self.prices.remove(pool_id)?;
+        self.pair_index.remove(pool_id, &removed.pair);
+        self.restored.remove(pool_id);
+        self.reference_excluded.remove(pool_id);
+        self.activity.remove(pool_id);
+        Some(removed)
+    }
+    
+    /// 💾 从快照恢复池子（不广播价格事件）
+    ///
+    /// 恢复的条目在收到实时更新前一直标记为过期：/prices 可见，但所有新鲜度过滤都会排除。
+    pub fn restore_price(&self, mut pool_price: PoolPrice) {
+        pool_price.liquidity_usd = liquidity::estimate(&pool_price, &self.usd_prices);
+        let pool_id = pool_price.pool_id.clone();
+        let pair = pool_price.pair.clone();
+        let previous = self.prices.insert(pool_id.clone(), pool_price);
+        self.pair_index.record(&pool_id, &pair, previous.as_ref().map(|p| p.pair.as_str()));
+        self.restored.insert(pool_id);
+    }
+    
+    /// 池子是否仍是快照恢复的数据（尚未收到实时更新）
+    pub fn is_restored(&self, pool_id: &str) -> bool {
+        self.restored.contains(pool_id)
+    }
+    
+    /// 修改已缓存池子的交易对名称（不触发价格变化事件）
+    pub fn rename_pair(&self, pool_id: &str, new_pair: &str) -> bool {
+        let old_pair = match self.prices.get_mut(pool_id) {
+            Some(mut entry) if entry.pair != new_pair => {
+                std::mem::replace(&mut entry.pair, new_pair.to_string())
+            }
+            _ => return false,
+        };
+        self.pair_index.record(pool_id, new_pair, Some(&old_pair));
+        true
     }
     
     /// Get price for a specific pool
@@ -139,12 +434,14 @@ impl PriceCache {
         self.prices.get(pool_id).map(|entry| entry.clone())
     }
     
-    /// Get all pools for a specific pair
+    /// Get all pools for a specific pair (O(1) lookup via the pair index)
     pub fn get_pools_by_pair(&self, pair: &str) -> Vec<PoolPrice> {
-        self.prices.iter()
-            .filter(|entry| entry.pair == pair)
-            .map(|entry| entry.clone())
-            .collect()
+        self.pair_index.collect_pools(&self.prices, pair)
+    }
+    
+    /// Get all known pairs without cloning any PoolPrice
+    pub fn get_pairs(&self) -> Vec<String> {
+        self.pair_index.pairs()
     }
     
     /// Get the age (in milliseconds) of the latest price update for a pool
@@ -168,13 +465,17 @@ impl PriceCache {
             .collect()
     }
     
+    /// 路由用的全部价格：排除被熔断隔离、偏离参考价格和非 Active 的池子（不做新鲜度过滤）
+    pub fn get_routable_prices(&self) -> Vec<PoolPrice> {
+        self.prices.iter()
+            .filter(|entry| !self.is_excluded(entry.key()))
+            .map(|entry| entry.clone())
+            .collect()
+    }
+    
     /// Get statistics
     pub fn get_stats(&self) -> (usize, Vec<String>) {
-        let pairs: std::collections::HashSet<String> = self.prices.iter()
-            .map(|entry| entry.pair.clone())
-            .collect();
-
-        (self.prices.len(), pairs.into_iter().collect())
+        (self.prices.len(), self.get_pairs())
     }
     
     // ============================================
@@ -198,6 +499,8 @@ impl PriceCache {
         let now = Instant::now();
 
         self.prices.iter()
+            .filter(|entry| !self.restored.contains(entry.key()))
+            .filter(|entry| !self.is_excluded(entry.key()))
             .filter(|entry| {
                 let age_ms = now.duration_since(entry.last_update).as_millis() as u64;
                 age_ms <= max_age_ms
@@ -222,11 +525,8 @@ impl PriceCache {
     /// let snapshot = price_cache.get_slot_aligned_snapshot(5);
     /// ```
     pub fn get_slot_aligned_snapshot(&self, max_slot_spread: u64) -> Vec<PoolPrice> {
-        // 找到最新的slot
-        let latest_slot = self.prices.iter()
-            .map(|entry| entry.slot)
-            .max()
-            .unwrap_or(0);
+        // 找到最新的slot（锚定链头时为链头）
+        let latest_slot = self.snapshot_reference_slot();
 
         if latest_slot == 0 {
             return Vec::new();
@@ -234,6 +534,8 @@ impl PriceCache {
 
         // 只返回与最新slot差异 <= max_slot_spread 的数据
         self.prices.iter()
+            .filter(|entry| !self.restored.contains(entry.key()))
+            .filter(|entry| !self.is_excluded(entry.key()))
             .filter(|entry| {
                 let slot_diff = latest_slot.saturating_sub(entry.slot);
                 slot_diff <= max_slot_spread
@@ -251,33 +553,82 @@ impl PriceCache {
     /// # Returns
     /// 同时满足时间新鲜度和slot一致性的数据
     pub fn get_consistent_snapshot(&self, max_age_ms: u64, max_slot_spread: u64) -> Vec<PoolPrice> {
+        self.get_consistent_snapshot_detailed(max_age_ms, max_slot_spread).included
+    }
+    
+    /// 与 `get_consistent_snapshot` 相同的过滤，同时记录每个被排除池子的原因
+    ///
+    /// 快照恢复、尚未收到实时更新的池子按时间过期处理；熔断隔离单独归类。
+    pub fn get_consistent_snapshot_detailed(&self, max_age_ms: u64, max_slot_spread: u64) -> SnapshotResult {
         let now = Instant::now();
+        let latest_slot = self.snapshot_reference_slot();
+        let mut result = SnapshotResult::default();
+        if latest_slot == 0 {
+            return result;
+        }
 
-        // 找到最新的slot
-        let latest_slot = self.prices.iter()
-            .map(|entry| entry.slot)
-            .max()
-            .unwrap_or(0);
-
+        for entry in self.prices.iter() {
+            let reason = if self.restored.contains(entry.key()) {
+                Some(ExclusionReason::StaleByTime {
+                    age_ms: now.duration_since(entry.last_update).as_millis() as u64,
+                })
+            } else if self.circuit_breaker.is_quarantined(entry.key()) {
+                Some(ExclusionReason::Quarantined)
+            } else if self.reference_excluded.contains(entry.key()) {
+                Some(ExclusionReason::ReferenceDeviation)
+            } else if let Some(status) = self.inactive_label(entry.key()) {
+                Some(ExclusionReason::Inactive { status })
+            } else {
+                exclusion_reason(&entry, now, latest_slot, max_age_ms, max_slot_spread)
+            };
+            match reason {
+                None => result.included.push(entry.clone()),
+                Some(reason) => result.excluded.push((entry.pool_id.clone(), reason)),
+            }
+        }
+        result
+    }
+    
+    /// 🎯 按池子类型的新鲜度策略获取快照
+    ///
+    /// 与 `get_consistent_snapshot_detailed` 相同的排除规则，但时间 / slot 预算按池子取：
+    /// AMM / CLMM 2 秒，vault 依赖型 5 秒，CLOB 10 秒（RPC 刷新器每 5 秒拉取一次），
+    /// `[[pools]] max_age_ms` 覆盖单个池子。逐池记录排除原因，便于调整预算。
+    pub fn get_policy_snapshot(&self) -> SnapshotResult {
+        let now = Instant::now();
+        let latest_slot = self.snapshot_reference_slot();
+        let mut snapshot = SnapshotResult::default();
         if latest_slot == 0 {
-            return Vec::new();
+            return snapshot;
         }
 
-        // 同时过滤时间和slot
-        self.prices.iter()
-            .filter(|entry| {
-                // 检查数据新鲜度
-                let age_ms = now.duration_since(entry.last_update).as_millis() as u64;
-                if age_ms > max_age_ms {
-                    return false;
+        for entry in self.prices.iter() {
+            let age_ms = now.duration_since(entry.last_update).as_millis() as u64;
+            let behind_by = latest_slot.saturating_sub(entry.slot);
+            // 💾 快照恢复的数据按时间过期处理
+            let reason = if self.restored.contains(entry.key()) {
+                Some(ExclusionReason::StaleByTime { age_ms })
+            } else if self.circuit_breaker.is_quarantined(entry.key()) {
+                Some(ExclusionReason::Quarantined)
+            } else if self.reference_excluded.contains(entry.key()) {
+                Some(ExclusionReason::ReferenceDeviation)
+            } else if let Some(status) = self.inactive_label(entry.key()) {
+                Some(ExclusionReason::Inactive { status })
+            } else if entry.price == 0.0 {
+                Some(ExclusionReason::ZeroPrice)
+            } else {
+                match self.staleness.check(&entry.pool_id, &entry.dex_name, age_ms, behind_by) {
+                    None => None,
+                    Some(StaleReason::Time) => Some(ExclusionReason::StaleByTime { age_ms }),
+                    Some(StaleReason::Slot) => Some(ExclusionReason::StaleBySlot { behind_by }),
                 }
-
-                // 检查slot对齐
-                let slot_diff = latest_slot.saturating_sub(entry.slot);
-                slot_diff <= max_slot_spread
-            })
-            .map(|entry| entry.clone())
-            .collect()
+            };
+            match reason {
+                None => snapshot.included.push(entry.clone()),
+                Some(reason) => snapshot.excluded.push((entry.pool_id.clone(), reason)),
+            }
+        }
+        snapshot
     }
     
     /// 获取当前最新的slot号
@@ -332,7 +683,15 @@ impl Clone for PriceCache {
     fn clone(&self) -> Self {
         Self {
             prices: Arc::clone(&self.prices),
+            pair_index: self.pair_index.clone(),
             update_tx: self.update_tx.clone(),
+            staleness: Arc::clone(&self.staleness),
+            restored: Arc::clone(&self.restored),
+            circuit_breaker: Arc::clone(&self.circuit_breaker),
+            reference_excluded: Arc::clone(&self.reference_excluded),
+            activity: Arc::clone(&self.activity),
+            chain_head: self.chain_head,
+            usd_prices: Arc::clone(&self.usd_prices),
         }
     }
 }
@@ -355,6 +714,10 @@ impl StateLayer for PriceCache {
         self.get_pools_by_pair(pair)
     }
 
+    fn get_pairs(&self) -> Vec<String> {
+        self.get_pairs()
+    }
+
     fn subscribe_updates(&self) -> broadcast::Receiver<PriceUpdateEvent> {
         // 复用现有的 subscribe_updates 方法
         self.subscribe_updates()
@@ -380,6 +743,16 @@ impl StateLayer for PriceCache {
         self.get_consistent_snapshot(max_age_ms, max_slot_spread)
     }
 
+    fn get_consistent_snapshot_detailed(&self, max_age_ms: u64, max_slot_spread: u64) -> SnapshotResult {
+        // 覆盖默认实现：额外排除快照恢复和熔断隔离的池子
+        self.get_consistent_snapshot_detailed(max_age_ms, max_slot_spread)
+    }
+
+    fn snapshot_into(&self, buf: &mut Vec<PoolPrice>, filter: &SnapshotFilter) -> u64 {
+        // 与 DashMapStateLayer 相同：直接在主表上过滤（不做隔离 / 新鲜度排除，与 get_all_prices 一致）
+        fill_snapshot_from_map(&self.prices, &self.pair_index, buf, filter)
+    }
+
     fn get_latest_slot(&self) -> u64 {
         // 复用现有的 get_latest_slot 方法
         self.get_latest_slot()
@@ -423,16 +796,11 @@ mod tests {
         let cache = PriceCache::new();
         
         let price = PoolPrice {
-            pool_id: "raydium_sol_usdc".to_string(),
             dex_name: "Raydium".to_string(),
-            pair: "SOL/USDC".to_string(),
             base_reserve: 100_000 * 1_000_000_000,
             quote_reserve: 18_500_000 * 1_000_000,
-            base_decimals: 9,
-            quote_decimals: 6,
-            price: 185.0,
-            last_update: Instant::now(),
             slot: 1000,
+            ..PoolPrice::for_test("raydium_sol_usdc", "SOL/USDC", 185.0)
         };
         
         cache.update_price(price.clone());
@@ -449,29 +817,23 @@ mod tests {
         
         // 添加不同slot的数据
         cache.update_price(PoolPrice {
-            pool_id: "pool1".to_string(),
             dex_name: "Raydium".to_string(),
-            pair: "SOL/USDC".to_string(),
             base_reserve: 1000,
             quote_reserve: 1000,
             base_decimals: 6,
-            quote_decimals: 6,
-            price: 1.0,
             last_update: now,
             slot: 1000,  // 旧slot
+            ..PoolPrice::for_test("pool1", "SOL/USDC", 1.0)
         });
         
         cache.update_price(PoolPrice {
-            pool_id: "pool2".to_string(),
             dex_name: "Orca".to_string(),
-            pair: "SOL/USDT".to_string(),
             base_reserve: 1000,
             quote_reserve: 1000,
             base_decimals: 6,
-            quote_decimals: 6,
-            price: 1.0,
             last_update: now,
             slot: 1005,  // 最新slot
+            ..PoolPrice::for_test("pool2", "SOL/USDT", 1.0)
         });
         
         // 只返回slot差异<=3的数据，应该只有pool2
@@ -483,7 +845,214 @@ mod tests {
         let aligned = cache.get_slot_aligned_snapshot(5);
         assert_eq!(aligned.len(), 2);
     }
+    
+    #[test]
+    fn test_consistent_snapshot_reports_exclusion_reasons() {
+        use crate::dashmap_state::DashMapStateLayer;
+        
+        let now = Instant::now();
+        let pool = |pool_id: &str, price: f64, last_update: Instant, slot: u64| PoolPrice {
+            base_reserve: 1000,
+            quote_reserve: 1000,
+            base_decimals: 6,
+            last_update,
+            slot,
+            ..PoolPrice::for_test(pool_id, "SOL/USDC", price)
+        };
+        let pools = [
+            pool("fresh", 1.0, now, 1000),
+            pool("old", 1.0, now - std::time::Duration::from_secs(5), 1000),
+            pool("behind", 1.0, now, 980),
+            pool("empty", 0.0, now, 1000),
+        ];
+        
+        let cache = PriceCache::new();
+        let dashmap = DashMapStateLayer::new();
+        for p in &pools {
+            cache.update_price(p.clone());
+            StateLayer::update_price(&dashmap, p.clone());
+        }
+        
+        // PriceCache 覆盖实现与 DashMapStateLayer 默认实现给出相同结果
+        let layers: [&dyn StateLayer; 2] = [&cache, &dashmap];
+        for layer in layers {
+            let result = layer.get_consistent_snapshot_detailed(2000, 10);
+            assert_eq!(result.included.len(), 1);
+            assert_eq!(result.included[0].pool_id, "fresh");
+            assert_eq!(layer.get_consistent_snapshot(2000, 10).len(), 1);
+            
+            let mut excluded = result.excluded.clone();
+            excluded.sort_by(|a, b| a.0.cmp(&b.0));
+            assert_eq!(excluded[0], ("behind".to_string(), ExclusionReason::StaleBySlot { behind_by: 20 }));
+            assert_eq!(excluded[1], ("empty".to_string(), ExclusionReason::ZeroPrice));
+            assert_eq!(excluded[2].0, "old");
+            assert!(matches!(excluded[2].1, ExclusionReason::StaleByTime { age_ms } if age_ms >= 5000));
+            
+            let counts = result.counts();
+            assert_eq!((counts.stale_by_time, counts.stale_by_slot, counts.zero_price, counts.total()), (1, 1, 1, 3));
+        }
+    }
+    
+    #[test]
+    fn test_chain_head_anchor_excludes_lagging_feed() {
+        let now = Instant::now();
+        let chain_head: &'static ChainHead = Box::leak(Box::new(ChainHead::new()));
+        let cache = PriceCache::new().with_chain_head_anchor(chain_head);
+        for (pool_id, slot) in [("a", 1000), ("b", 1002)] {
+            cache.update_price(PoolPrice {
+                base_reserve: 1000,
+                quote_reserve: 1000,
+                base_decimals: 6,
+                last_update: now,
+                slot,
+                ..PoolPrice::for_test(pool_id, "SOL/USDC", 1.0)
+            });
+        }
+        
+        // 链头未知：与不锚定时相同
+        assert_eq!(cache.get_consistent_snapshot(2000, 10).len(), 2);
+        
+        // 整个数据源落后链头 20+ slot：池子之间仍然一致，但锚定后全部排除
+        chain_head.observe(1022);
+        let result = cache.get_consistent_snapshot_detailed(2000, 10);
+        assert!(result.included.is_empty());
+        assert!(result.excluded.contains(&("b".to_string(), ExclusionReason::StaleBySlot { behind_by: 20 })));
+        assert_eq!(cache.clone().get_consistent_snapshot(2000, 10).len(), 0);
+    }
+    
+    #[test]
+    fn test_remove_and_rename_pool() {
+        let cache = PriceCache::new();
+        cache.update_price(PoolPrice {
+            dex_name: "Raydium".to_string(),
+            base_reserve: 1000,
+            quote_reserve: 1000,
+            base_decimals: 6,
+            slot: 1000,
+            ..PoolPrice::for_test("pool1", "SOL/USDC", 1.0)
+        });
+        
+        assert!(cache.rename_pair("pool1", "SOL/USDC (Raydium)"));
+        assert!(!cache.rename_pair("pool1", "SOL/USDC (Raydium)"));
+        assert!(cache.get_pools_by_pair("SOL/USDC").is_empty());
+        assert_eq!(cache.get_pools_by_pair("SOL/USDC (Raydium)").len(), 1);
+        
+        assert!(cache.remove_price("pool1").is_some());
+        assert!(cache.get_price("pool1").is_none());
+        assert!(cache.get_pools_by_pair("SOL/USDC (Raydium)").is_empty());
+        assert!(cache.remove_price("pool1").is_none());
+    }
+    
+    #[test]
+    fn test_policy_snapshot_per_pool_type() {
+        let cache = PriceCache::new();
+        let now = Instant::now();
+        let six_seconds_ago = now - std::time::Duration::from_secs(6);
+        let pool = |pool_id: &str, dex_name: &str, last_update: Instant, slot: u64| PoolPrice {
+            dex_name: dex_name.to_string(),
+            base_reserve: 1000,
+            quote_reserve: 1000,
+            base_decimals: 6,
+            last_update,
+            slot,
+            ..PoolPrice::for_test(pool_id, "SOL/USDC", 1.0)
+        };
+        
+        cache.update_price(pool("phoenix", "Phoenix (CLOB)", six_seconds_ago, 995));
+        cache.update_price(pool("raydium_old", "Raydium AMM V4", six_seconds_ago, 995));
+        cache.update_price(pool("raydium_behind", "Raydium AMM V4", now, 980));
+        cache.update_price(pool("raydium_fresh", "Raydium AMM V4", now, 1000));
+        
+        let snapshot = cache.get_policy_snapshot();
+        let mut ids: Vec<&str> = snapshot.included.iter().map(|p| p.pool_id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["phoenix", "raydium_fresh"]);
+        assert_eq!(snapshot.counts().stale_by_time, 1);
+        assert_eq!(snapshot.counts().stale_by_slot, 1);
+    }
+    
+    #[test]
+    fn test_restored_pool_is_stale_until_live_update() {
+        let cache = PriceCache::new();
+        let mut events = cache.subscribe_updates();
+        let pool = |slot: u64| PoolPrice {
+            base_reserve: 1000,
+            quote_reserve: 1000,
+            base_decimals: 6,
+            slot,
+            ..PoolPrice::for_test("pool1", "SOL/USDC", 1.0)
+        };
+        
+        cache.restore_price(pool(1000));
+        assert!(cache.is_restored("pool1"));
+        assert!(events.try_recv().is_err());
+        assert_eq!(cache.get_all_prices().len(), 1);
+        assert_eq!(cache.get_pools_by_pair("SOL/USDC").len(), 1);
+        assert!(cache.get_fresh_prices(60_000).is_empty());
+        assert!(cache.get_consistent_snapshot(60_000, 100).is_empty());
+        assert_eq!(cache.get_policy_snapshot().counts().stale_by_time, 1);
+        
+        cache.update_price(pool(1001));
+        assert!(!cache.is_restored("pool1"));
+        assert_eq!(cache.get_fresh_prices(60_000).len(), 1);
+    }
+    
+    #[test]
+    fn test_quarantined_pool_excluded_from_snapshots() {
+        use crate::circuit_breaker::TripReason;
+        use crate::config::CircuitBreakerConfig;
+        
+        let cache = PriceCache::new().with_circuit_breaker(CircuitBreaker::new(&CircuitBreakerConfig::default()));
+        let pool = |price: f64, slot: u64| PoolPrice {
+            base_reserve: 1_000_000,
+            quote_reserve: 185_000_000,
+            base_decimals: 6,
+            slot,
+            ..PoolPrice::for_test("pool1", "SOL/USDC", price)
+        };
+        
+        // 💾 快照恢复的旧价格不参与比较
+        cache.restore_price(pool(100.0, 900));
+        assert_eq!(cache.update_price(pool(185.0, 1000)), None);
+        assert_eq!(cache.update_price(pool(18.5, 1001)), Some(BreakerEvent::Tripped(TripReason::PriceJump)));
+        
+        // 照常写入缓存，但不进任何快照
+        assert!(cache.is_quarantined("pool1"));
+        assert_eq!(cache.get_price("pool1").unwrap().price, 18.5);
+        assert!(cache.get_fresh_prices(60_000).is_empty());
+        assert!(cache.get_consistent_snapshot(60_000, 100).is_empty());
+        assert!(cache.get_routable_prices().is_empty());
+        assert_eq!(cache.get_policy_snapshot().counts().quarantined, 1);
+        assert_eq!(cache.circuit_breaker().quarantined()[0].pair, "SOL/USDC");
+    }
+    
+    #[test]
+    fn test_inactive_pool_excluded_from_snapshots() {
+        let cache = PriceCache::new();
+        cache.update_price(PoolPrice {
+            dex_name: "SolFi V2".to_string(),
+            base_reserve: 1_000_000,
+            quote_reserve: 185_000_000,
+            base_decimals: 6,
+            slot: 1000,
+            ..PoolPrice::for_test("solfi-pool", "SOL/USDC", 185.0)
+        });
+        
+        // 🚦 等待 vault 数据：写入缓存但不进路由，快照按状态标签归类
+        assert!(cache.set_activity_status("solfi-pool", ActivityStatus::AwaitingVaultData));
+        assert!(!cache.set_activity_status("solfi-pool", ActivityStatus::AwaitingVaultData));
+        assert!(cache.get_routable_prices().is_empty());
+        let snapshot = cache.get_consistent_snapshot_detailed(60_000, 100);
+        assert_eq!(snapshot.excluded, vec![(
+            "solfi-pool".to_string(),
+            ExclusionReason::Inactive { status: "awaiting_vault_data" },
+        )]);
+        assert_eq!(cache.get_policy_snapshot().counts().inactive, 1);
+        
+        // vault 余额到达后恢复
+        let status = ActivityStatus::AwaitingVaultData.with_vault_reserves(Some((1_000_000, 185_000_000)));
+        assert!(cache.set_activity_status("solfi-pool", status));
+        assert_eq!(cache.get_routable_prices().len(), 1);
+        assert_eq!(ActivityStatus::AwaitingVaultData.with_vault_reserves(Some((0, 5))), ActivityStatus::NoLiquidity);
+    }
 }
-
-
-
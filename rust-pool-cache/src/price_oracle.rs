@@ -0,0 +1,162 @@
+/*!
+ * USD 定价服务
+ *
+ * 之前美元金额全靠硬编码（LST 检测器假设 SOL = $200，Calculator 兜底 $140）。
+ * 这里从 PriceCache 推导：
+ *
+ * - 稳定币（USDC / USDT）按 1 美元
+ * - 与稳定币直接配对的代币：取稳定币一侧储备最深的新鲜池子（SOL/USDC、SOL/USDT）
+ * - 其余代币经锚定代币三角换算：mSOL = mSOL/SOL × SOL/USDC
+ *
+ * 只使用新鲜（未超过 max_age_ms、非快照恢复）的池子；没有可用报价时返回 None，
+ * 调用方改用原生代币数量展示。
+ */
+
+use std::sync::Arc;
+
+use crate::config::PriceOracleConfig;
+use crate::price_cache::{PoolPrice, PriceCache};
+use crate::token_graph::pool_tokens;
+
+/// 按 1 美元计价的代币
+const USD_STABLECOINS: &[&str] = &["USDC", "USDT"];
+
+/// 是否为按 1 美元计价的稳定币
+pub fn is_stablecoin(token: &str) -> bool {
+    USD_STABLECOINS.contains(&token.to_uppercase().as_str())
+}
+
+/// USD 定价服务
+pub struct PriceOracle {
+    price_cache: Arc<PriceCache>,
+    /// 三角换算使用的锚定代币（按顺序尝试）
+    anchor_tokens: Vec<String>,
+    /// 池子价格的新鲜度上限（毫秒）
+    max_age_ms: u64,
+}
+
+impl PriceOracle {
+    pub fn new(price_cache: Arc<PriceCache>, config: &PriceOracleConfig) -> Self {
+        Self {
+            price_cache,
+            anchor_tokens: config.anchor_tokens.clone(),
+            max_age_ms: config.max_age_ms,
+        }
+    }
+
+    /// 代币的美元价格（只用新鲜池子，无可用报价返回 None）
+    pub fn get_usd_price(&self, token: &str) -> Option<f64> {
+        if is_stablecoin(token) {
+            return Some(1.0);
+        }
+        resolve_usd_price(&self.fresh_pools(), token, &self.anchor_tokens)
+    }
+
+    /// 批量定价（只取一次新鲜池子，无可用报价的代币不出现在结果中）
+    pub fn get_usd_prices(&self, tokens: &[String]) -> Vec<(String, f64)> {
+        let fresh = self.fresh_pools();
+        tokens.iter()
+            .filter_map(|token| Some((token.clone(), resolve_usd_price(&fresh, token, &self.anchor_tokens)?)))
+            .collect()
+    }
+
+    fn fresh_pools(&self) -> Vec<PoolPrice> {
+        self.price_cache.get_all_prices()
+            .into_iter()
+            .filter(|p| {
+                p.last_update.elapsed().as_millis() as u64 <= self.max_age_ms
+                    && !self.price_cache.is_restored(&p.pool_id)
+            })
+            .collect()
+    }
+}
+
+/// 从给定池子推导代币的美元价格：稳定币 → 直接配对 → 经锚定代币三角换算
+pub fn resolve_usd_price(pools: &[PoolPrice], token: &str, anchor_tokens: &[String]) -> Option<f64> {
+    if is_stablecoin(token) {
+        return Some(1.0);
+    }
+    if let Some(price) = direct_usd_price(pools, token) {
+        return Some(price);
+    }
+    anchor_tokens.iter()
+        .filter(|anchor| !anchor.eq_ignore_ascii_case(token))
+        .find_map(|anchor| {
+            let anchor_usd = direct_usd_price(pools, anchor)?;
+            let rate = deepest_rate(pools, token, |counter| counter == anchor.as_str())?;
+            Some(rate * anchor_usd)
+        })
+}
+
+/// 与稳定币直接配对的美元价格
+fn direct_usd_price(pools: &[PoolPrice], token: &str) -> Option<f64> {
+    deepest_rate(pools, token, is_stablecoin)
+}
+
+/// 1 单位 token 可换得的对手代币数量，取对手代币一侧储备最深的池子
+fn deepest_rate(pools: &[PoolPrice], token: &str, is_counter: impl Fn(&str) -> bool) -> Option<f64> {
+    pools.iter()
+        .filter(|p| p.price.is_finite() && p.price > 0.0)
+        .filter_map(|p| {
+            let (base, quote) = pool_tokens(p)?;
+            let (base_decimals, quote_decimals) = p.get_decimals();
+            if base == token && is_counter(&quote) {
+                let depth = p.quote_reserve as f64 / 10f64.powi(quote_decimals as i32);
+                Some((depth, p.price))
+            } else if quote == token && is_counter(&base) {
+                let depth = p.base_reserve as f64 / 10f64.powi(base_decimals as i32);
+                Some((depth, 1.0 / p.price))
+            } else {
+                None
+            }
+        })
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, rate)| rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(pool_id: &str, pair: &str, price: f64, reserves: (u64, u64), decimals: (u8, u8)) -> PoolPrice {
+        PoolPrice {
+            dex_name: "Test".to_string(),
+            base_reserve: reserves.0,
+            quote_reserve: reserves.1,
+            base_decimals: decimals.0,
+            quote_decimals: decimals.1,
+            ..PoolPrice::for_test(pool_id, pair, price)
+        }
+    }
+
+    #[test]
+    fn test_triangulates_lst_through_sol() {
+        let anchors = vec!["SOL".to_string()];
+        let pools = vec![
+            // 浅池报价偏离，应选最深的 SOL/USDC
+            pool("sol-usdc-shallow", "SOL/USDC", 120.0, (10 * 1_000_000_000, 1_200 * 1_000_000), (9, 6)),
+            pool("sol-usdc-deep", "SOL/USDC", 150.0, (10_000 * 1_000_000_000, 1_500_000 * 1_000_000), (9, 6)),
+            // 反向命名：1 SOL = 0.8 mSOL
+            pool("sol-msol", "SOL/mSOL", 0.8, (2_000 * 1_000_000_000, 1_600 * 1_000_000_000), (9, 9)),
+        ];
+
+        assert_eq!(resolve_usd_price(&pools, "USDC", &anchors), Some(1.0));
+        assert_eq!(resolve_usd_price(&pools, "SOL", &anchors), Some(150.0));
+        let msol = resolve_usd_price(&pools, "mSOL", &anchors).unwrap();
+        assert!((msol - 150.0 / 0.8).abs() < 1e-9);
+
+        // 没有锚定代币或没有稳定币池子时无法定价
+        assert_eq!(resolve_usd_price(&pools, "mSOL", &[]), None);
+        assert_eq!(resolve_usd_price(&pools[2..], "mSOL", &anchors), None);
+        assert_eq!(resolve_usd_price(&pools, "BONK", &anchors), None);
+
+        // 缓存为空时返回 None，写入新鲜池子后可以定价
+        let cache = Arc::new(PriceCache::new());
+        let oracle = PriceOracle::new(cache.clone(), &PriceOracleConfig { anchor_tokens: anchors, max_age_ms: 10_000 });
+        assert_eq!(oracle.get_usd_price("SOL"), None);
+        for p in pools {
+            cache.update_price(p);
+        }
+        assert_eq!(oracle.get_usd_price("SOL"), Some(150.0));
+    }
+}
@@ -0,0 +1,280 @@
+/*!
+ * PriceCache 快照（重启预热）
+ *
+ * 冷启动时要等几分钟才能收齐 WebSocket 更新，低活跃池子更久，路由器在此之前
+ * 拿不到可用的代币图。这里定期把所有 PoolPrice（连同采集时的墙钟时间和 slot）
+ * 用 bincode 写入本地文件，启动时先用快照预热缓存：
+ *
+ * - `last_update` 按"快照内年龄 + 停机时长"回拨，/prices 的 age_ms 反映真实年龄
+ * - 恢复的条目标记为过期但存在，新鲜度过滤一律排除，直到收到实时更新
+ * - 主动 RPC 刷新优先查询这些池子
+ *
+ * 不依赖数据库模块。
+ */
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio::time::interval;
+use tracing::{debug, info, warn};
+
+use crate::config::SnapshotConfig;
+use crate::price_cache::{PoolPrice, PriceCache};
+
+/// 文件格式版本（字段变化时递增，旧快照直接忽略）
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// 单个池子的快照条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SnapshotEntry {
+    pool_id: String,
+    dex_name: String,
+    pair: String,
+    base_reserve: u64,
+    quote_reserve: u64,
+    base_decimals: u8,
+    quote_decimals: u8,
+    price: f64,
+    /// 采集时该条目的年龄（毫秒）
+    age_ms: u64,
+    slot: u64,
+}
+
+/// 价格缓存快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceSnapshot {
+    version: u32,
+    /// 采集时的墙钟时间（Unix 毫秒）
+    pub captured_at_ms: u64,
+    /// 采集时缓存中的最新 slot
+    pub latest_slot: u64,
+    entries: Vec<SnapshotEntry>,
+}
+
+impl PriceSnapshot {
+    /// 采集当前缓存（仍是快照恢复数据的池子也一并保留，年龄照常累加）
+    pub fn capture(cache: &PriceCache) -> Self {
+        let entries: Vec<SnapshotEntry> = cache.get_all_prices()
+            .into_iter()
+            .map(|p| SnapshotEntry {
+                age_ms: p.last_update.elapsed().as_millis() as u64,
+                pool_id: p.pool_id,
+                dex_name: p.dex_name,
+                pair: p.pair,
+                base_reserve: p.base_reserve,
+                quote_reserve: p.quote_reserve,
+                base_decimals: p.base_decimals,
+                quote_decimals: p.quote_decimals,
+                price: p.price,
+                slot: p.slot,
+            })
+            .collect();
+        let latest_slot = entries.iter().map(|e| e.slot).max().unwrap_or(0);
+
+        Self {
+            version: SNAPSHOT_VERSION,
+            captured_at_ms: unix_millis(),
+            latest_slot,
+            entries,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 写入文件（先写临时文件再 rename，崩溃时不会留下半个快照）
+    pub fn write_to(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create snapshot directory {}", dir.display()))?;
+        }
+        let bytes = bincode::serialize(self).context("Failed to encode price snapshot")?;
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, &bytes)
+            .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, path)
+            .with_context(|| format!("Failed to move snapshot into {}", path.display()))?;
+        Ok(())
+    }
+
+    /// 读取快照文件
+    pub fn read_from(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let snapshot: Self = bincode::deserialize(&bytes)
+            .with_context(|| format!("Failed to decode price snapshot {}", path.display()))?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            bail!(
+                "Unsupported snapshot version {} (expected {})",
+                snapshot.version,
+                SNAPSHOT_VERSION
+            );
+        }
+        Ok(snapshot)
+    }
+
+    /// 用快照预热缓存，只恢复 `pool_ids` 中的池子（已从配置移除的池子不恢复），返回恢复数量
+    ///
+    /// `last_update` 回拨为"快照内年龄 + 快照至今的时长"，缓存里已有的池子不覆盖。
+    pub fn restore_into(self, cache: &PriceCache, pool_ids: &HashSet<String>) -> usize {
+        let downtime_ms = unix_millis().saturating_sub(self.captured_at_ms);
+        let now = Instant::now();
+        let mut restored = 0;
+
+        for entry in self.entries {
+            if !pool_ids.contains(&entry.pool_id) || cache.get_price(&entry.pool_id).is_some() {
+                continue;
+            }
+            let age = Duration::from_millis(entry.age_ms.saturating_add(downtime_ms));
+            // 系统启动时间短于数据年龄时 Instant 无法回拨，丢弃该条目而不是当作新鲜数据
+            let last_update = match now.checked_sub(age) {
+                Some(last_update) => last_update,
+                None => {
+                    debug!("Skipping snapshot entry {}: age {:?} predates the monotonic clock", entry.pool_id, age);
+                    continue;
+                }
+            };
+            cache.restore_price(PoolPrice {
+                pool_id: entry.pool_id,
+                dex_name: entry.dex_name,
+                pair: entry.pair,
+                base_reserve: entry.base_reserve,
+                quote_reserve: entry.quote_reserve,
+                base_decimals: entry.base_decimals,
+                quote_decimals: entry.quote_decimals,
+                price: entry.price,
+                last_update,
+                slot: entry.slot,
+                liquidity_usd: None,
+                commitment: None,
+            });
+            restored += 1;
+        }
+        restored
+    }
+}
+
+/// 启动时从快照预热，返回恢复的池子数（文件不存在视为 0）
+pub fn restore(config: &SnapshotConfig, cache: &PriceCache, pool_ids: &HashSet<String>) -> usize {
+    let path = Path::new(&config.path);
+    if !path.exists() {
+        info!("💾 No price snapshot at {}, starting cold", path.display());
+        return 0;
+    }
+    match PriceSnapshot::read_from(path) {
+        Ok(snapshot) => {
+            let total = snapshot.len();
+            let age_secs = unix_millis().saturating_sub(snapshot.captured_at_ms) / 1000;
+            let restored = snapshot.restore_into(cache, pool_ids);
+            info!(
+                "💾 Restored {}/{} pools from price snapshot ({}s old), marked stale until live updates arrive",
+                restored, total, age_secs
+            );
+            restored
+        }
+        Err(e) => {
+            warn!("Ignoring price snapshot: {:#}", e);
+            0
+        }
+    }
+}
+
+/// 写入一次快照（空缓存不覆盖已有文件）
+pub fn save(config: &SnapshotConfig, cache: &PriceCache) -> Result<usize> {
+    let snapshot = PriceSnapshot::capture(cache);
+    if snapshot.is_empty() {
+        return Ok(0);
+    }
+    snapshot.write_to(Path::new(&config.path))?;
+    Ok(snapshot.len())
+}
+
+/// 后台定期写快照，收到关闭信号时再写最后一次
+pub fn spawn_snapshot_task(
+    config: SnapshotConfig,
+    cache: Arc<PriceCache>,
+    mut shutdown: broadcast::Receiver<()>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(config.interval_secs.max(1)));
+        // 第一次 tick 立即触发，此时缓存只有恢复的数据，跳过
+        ticker.tick().await;
+        loop {
+            let shutting_down = tokio::select! {
+                _ = ticker.tick() => false,
+                _ = shutdown.recv() => true,
+            };
+            match save(&config, &cache) {
+                Ok(count) => debug!("💾 Wrote price snapshot ({} pools) to {}", count, config.path),
+                Err(e) => warn!("Failed to write price snapshot: {:#}", e),
+            }
+            if shutting_down {
+                break;
+            }
+        }
+    })
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(pool_id: &str, age: Duration, slot: u64) -> PoolPrice {
+        PoolPrice {
+            base_reserve: 100_000 * 1_000_000_000,
+            quote_reserve: 18_500_000 * 1_000_000,
+            last_update: Instant::now() - age,
+            slot,
+            ..PoolPrice::for_test(pool_id, "SOL/USDC", 185.0)
+        }
+    }
+
+    #[test]
+    fn test_snapshot_round_trip_restores_stale_entries() {
+        let cache = PriceCache::new();
+        cache.update_price(pool("pool1", Duration::from_secs(3), 1000));
+        cache.update_price(pool("removed", Duration::from_secs(1), 1001));
+
+        let path = std::env::temp_dir().join(format!("price_snapshot_test_{}.bin", std::process::id()));
+        let mut snapshot = PriceSnapshot::capture(&cache);
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot.latest_slot, 1001);
+        // 模拟停机 60 秒
+        snapshot.captured_at_ms -= 60_000;
+        snapshot.write_to(&path).unwrap();
+        let loaded = PriceSnapshot::read_from(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        let restored_cache = PriceCache::new();
+        let pool_ids: HashSet<String> = ["pool1".to_string()].into_iter().collect();
+        assert_eq!(loaded.restore_into(&restored_cache, &pool_ids), 1);
+
+        let restored = restored_cache.get_price("pool1").unwrap();
+        assert_eq!(restored.slot, 1000);
+        assert_eq!(restored.base_reserve, 100_000 * 1_000_000_000);
+        let age_ms = restored.last_update.elapsed().as_millis() as u64;
+        assert!((63_000..65_000).contains(&age_ms), "age {}ms", age_ms);
+        assert!(restored_cache.is_restored("pool1"));
+        assert!(restored_cache.get_price("removed").is_none());
+        assert!(restored_cache.get_fresh_prices(u64::MAX).is_empty());
+    }
+}
@@ -0,0 +1,249 @@
+/*!
+ * Prometheus 文本格式导出（GET /metrics）
+ *
+ * 手写 text exposition format 0.0.4，不引入 prometheus crate。指标名统一以
+ * `pool_cache_` 为前缀，改名会破坏已有的看板和告警规则，只增不改。
+ */
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// /metrics 响应的 Content-Type
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// 池子更新处理延迟的桶（秒）：10μs ~ 50ms
+pub const POOL_UPDATE_LATENCY_BUCKETS: &[f64] = &[
+    0.00001, 0.000025, 0.00005, 0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.05,
+];
+
+/// Calculator 扫描耗时的桶（秒）：1ms ~ 5s
+pub const SCAN_DURATION_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+/// 检测延迟预算各阶段 / 端到端的桶（秒）：10μs ~ 1s（"10ms 内检测"落在 0.01 桶附近）
+pub const LATENCY_BUDGET_BUCKETS: &[f64] = &[
+    0.00001, 0.00005, 0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 1.0,
+];
+
+/// 无锁直方图（累计桶在导出时计算）
+#[derive(Debug)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    /// 每个桶（含 +Inf）的非累计计数
+    buckets: Vec<AtomicU64>,
+    sum_micros: AtomicU64,
+}
+
+/// 直方图某一时刻的读数
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramSnapshot {
+    /// (上界, 累计计数)，不含 +Inf
+    pub buckets: Vec<(f64, u64)>,
+    pub count: u64,
+    pub sum_seconds: f64,
+}
+
+impl Histogram {
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+            sum_micros: AtomicU64::new(0),
+        }
+    }
+
+    pub fn observe(&self, value: Duration) {
+        let seconds = value.as_secs_f64();
+        let index = self.bounds.iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(self.bounds.len());
+        self.buckets[index].fetch_add(1, Ordering::Relaxed);
+        self.sum_micros.fetch_add(value.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        let mut cumulative = 0;
+        let mut buckets = Vec::with_capacity(self.bounds.len());
+        for (bound, count) in self.bounds.iter().zip(&self.buckets) {
+            cumulative += count.load(Ordering::Relaxed);
+            buckets.push((*bound, cumulative));
+        }
+        let count = cumulative + self.buckets[self.bounds.len()].load(Ordering::Relaxed);
+
+        HistogramSnapshot {
+            buckets,
+            count,
+            sum_seconds: self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0,
+        }
+    }
+}
+
+/// 指标类型
+#[derive(Debug, Clone, Copy)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl MetricKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+            MetricKind::Histogram => "histogram",
+        }
+    }
+}
+
+/// 有上限的内部缓存的当前大小（pool_cache_internal_cache_* 指标）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CacheSize {
+    pub cache: &'static str,
+    pub entries: usize,
+    /// 条目上限（随配置变化、没有固定上限的为 None）
+    pub capacity: Option<usize>,
+    /// 缓存的原始字节数（不适用时为 None）
+    pub bytes: Option<usize>,
+    /// 因达到上限而淘汰的累计条目数
+    pub evictions: u64,
+}
+
+/// 文本格式编码器
+#[derive(Debug, Default)]
+pub struct PrometheusWriter {
+    out: String,
+}
+
+impl PrometheusWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 指标族的 HELP / TYPE 头（每个指标名只写一次，之后写样本）
+    pub fn family(&mut self, name: &str, help: &str, kind: MetricKind) {
+        let _ = writeln!(self.out, "# HELP {} {}", name, escape_help(help));
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind.as_str());
+    }
+
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.out.push_str(name);
+        write_labels(&mut self.out, labels, None);
+        let _ = writeln!(self.out, " {}", format_value(value));
+    }
+
+    /// 直方图样本（_bucket / _sum / _count）
+    pub fn histogram(&mut self, name: &str, labels: &[(&str, &str)], snapshot: &HistogramSnapshot) {
+        for (bound, cumulative) in &snapshot.buckets {
+            let _ = write!(self.out, "{}_bucket", name);
+            write_labels(&mut self.out, labels, Some(&format_value(*bound)));
+            let _ = writeln!(self.out, " {}", cumulative);
+        }
+        let _ = write!(self.out, "{}_bucket", name);
+        write_labels(&mut self.out, labels, Some("+Inf"));
+        let _ = writeln!(self.out, " {}", snapshot.count);
+
+        let _ = write!(self.out, "{}_sum", name);
+        write_labels(&mut self.out, labels, None);
+        let _ = writeln!(self.out, " {}", format_value(snapshot.sum_seconds));
+        let _ = write!(self.out, "{}_count", name);
+        write_labels(&mut self.out, labels, None);
+        let _ = writeln!(self.out, " {}", snapshot.count);
+    }
+
+    /// 内部缓存的条目数 / 上限 / 字节数 / 淘汰数（每个缓存一组 cache 标签）
+    pub fn cache_sizes(&mut self, sizes: &[CacheSize]) {
+        self.family("pool_cache_internal_cache_entries", "Entries in bounded internal caches", MetricKind::Gauge);
+        for size in sizes {
+            self.sample("pool_cache_internal_cache_entries", &[("cache", size.cache)], size.entries as f64);
+        }
+        self.family("pool_cache_internal_cache_capacity", "Entry cap of bounded internal caches", MetricKind::Gauge);
+        for size in sizes {
+            if let Some(capacity) = size.capacity {
+                self.sample("pool_cache_internal_cache_capacity", &[("cache", size.cache)], capacity as f64);
+            }
+        }
+        self.family("pool_cache_internal_cache_bytes", "Raw bytes held by internal caches", MetricKind::Gauge);
+        for size in sizes {
+            if let Some(bytes) = size.bytes {
+                self.sample("pool_cache_internal_cache_bytes", &[("cache", size.cache)], bytes as f64);
+            }
+        }
+        self.family(
+            "pool_cache_internal_cache_evictions_total",
+            "Entries evicted from internal caches after reaching the cap",
+            MetricKind::Counter,
+        );
+        for size in sizes {
+            self.sample("pool_cache_internal_cache_evictions_total", &[("cache", size.cache)], size.evictions as f64);
+        }
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
+
+fn write_labels(out: &mut String, labels: &[(&str, &str)], le: Option<&str>) {
+    if labels.is_empty() && le.is_none() {
+        return;
+    }
+    out.push('{');
+    let mut first = true;
+    for (key, value) in labels.iter().copied().chain(le.map(|le| ("le", le))) {
+        if !first {
+            out.push(',');
+        }
+        first = false;
+        let _ = write!(out, "{}=\"{}\"", key, escape_label(value));
+    }
+    out.push('}');
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn escape_help(help: &str) -> String {
+    help.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_encoding() {
+        let histogram = Histogram::new(&[0.01, 0.1]);
+        histogram.observe(Duration::from_millis(5));
+        histogram.observe(Duration::from_millis(50));
+        histogram.observe(Duration::from_secs(2));
+
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.buckets, vec![(0.01, 1), (0.1, 2)]);
+        assert_eq!(snapshot.count, 3);
+        assert!((snapshot.sum_seconds - 2.055).abs() < 1e-9);
+
+        let mut writer = PrometheusWriter::new();
+        writer.family("pool_cache_scan_duration_seconds", "Scan duration", MetricKind::Histogram);
+        writer.histogram("pool_cache_scan_duration_seconds", &[("pool", "SOL/USDC \"x\"")], &snapshot);
+        let text = writer.finish();
+
+        assert!(text.starts_with("# HELP pool_cache_scan_duration_seconds Scan duration\n# TYPE pool_cache_scan_duration_seconds histogram\n"));
+        assert!(text.contains("pool_cache_scan_duration_seconds_bucket{pool=\"SOL/USDC \\\"x\\\"\",le=\"0.01\"} 1\n"));
+        assert!(text.contains("pool_cache_scan_duration_seconds_bucket{pool=\"SOL/USDC \\\"x\\\"\",le=\"+Inf\"} 3\n"));
+        assert!(text.contains("pool_cache_scan_duration_seconds_count{pool=\"SOL/USDC \\\"x\\\"\"} 3\n"));
+    }
+}
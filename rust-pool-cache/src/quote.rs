@@ -0,0 +1,432 @@
+/*!
+ * 分档报价（深度曲线）
+ *
+ * 单一金额的报价会掩盖深度曲线。对一组金额（或以请求金额为中心的几何阶梯，
+ * 默认 0.1× ~ 10×，8 档）同时给出：
+ * - 最优单池输出、最优两跳输出
+ * - 有效价格、相对现货（零金额边际价格）的累计价格影响
+ * - 相邻档位之间的边际价格，以及价格影响开始非线性的位置
+ *
+ * 所有档位共用同一份缓存快照，每个池子的报价使用 amm_calculator 的恒定乘积公式。
+ * 超过池子可用深度的金额不外推，直接标记 truncated。
+ */
+
+use crate::dex_interface::amm_calculator;
+use crate::price_cache::PoolPrice;
+use crate::token_graph::pool_tokens;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// 默认阶梯：请求金额的 0.1× ~ 10×
+pub const DEFAULT_LADDER_STEPS: usize = 8;
+const LADDER_LOW: f64 = 0.1;
+const LADDER_HIGH: f64 = 10.0;
+
+/// 单笔输入超过输入侧储备的该比例视为超出深度
+pub const MAX_DEPTH_RATIO: f64 = 0.5;
+
+/// 边际价格低于现货该比例（百分比）视为进入非线性区
+const NONLINEAR_THRESHOLD_PCT: f64 = 1.0;
+
+/// 几何阶梯：amount × [0.1, ..., 10]
+pub fn geometric_ladder(amount: f64, steps: usize) -> Vec<f64> {
+    if steps <= 1 {
+        return vec![amount];
+    }
+    let ratio = (LADDER_HIGH / LADDER_LOW).powf(1.0 / (steps - 1) as f64);
+    (0..steps)
+        .map(|i| amount * LADDER_LOW * ratio.powi(i as i32))
+        .collect()
+}
+
+/// 单个池子的一个方向（UI单位）
+#[derive(Debug, Clone)]
+struct Leg {
+    pool_id: String,
+    dex_name: String,
+    to_token: String,
+    reserve_in: f64,
+    reserve_out: f64,
+    fee: f64,
+}
+
+impl Leg {
+    /// 超出深度返回 None
+    fn output(&self, amount_in: f64) -> Option<f64> {
+        if amount_in > self.reserve_in * MAX_DEPTH_RATIO {
+            return None;
+        }
+        Some(amm_calculator::calculate_amm_output_f64(
+            amount_in,
+            self.reserve_in,
+            self.reserve_out,
+            self.fee,
+        ))
+    }
+
+    /// 零金额边际汇率（含手续费）
+    fn spot_rate(&self) -> f64 {
+        self.reserve_out / self.reserve_in * (1.0 - self.fee)
+    }
+}
+
+/// 一条报价路径的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteQuote {
+    pub pools: Vec<String>,
+    pub dexes: Vec<String>,
+    /// 两跳路径的中间代币
+    pub via: Option<String>,
+    pub amount_out: f64,
+    /// 输出 / 输入
+    pub effective_price: f64,
+    /// 相对该路径现货汇率的累计价格影响（%）
+    pub price_impact_pct: f64,
+}
+
+/// 一个金额档位
+#[derive(Debug, Clone, Serialize)]
+pub struct QuoteTier {
+    pub amount_in: f64,
+    pub best_single: Option<RouteQuote>,
+    pub best_two_hop: Option<RouteQuote>,
+    /// 两者中输出更高的
+    pub best_amount_out: Option<f64>,
+    /// 金额超过所有路径的可用深度（不外推）
+    pub truncated: bool,
+}
+
+/// 边际价格曲线上的一点（相邻两个档位之间）
+#[derive(Debug, Clone, Serialize)]
+pub struct MarginalPoint {
+    pub from_amount: f64,
+    pub to_amount: f64,
+    pub marginal_price: f64,
+    /// 相对最优现货汇率的偏离（%，负数 = 更差）
+    pub deviation_pct: f64,
+}
+
+/// 深度曲线
+#[derive(Debug, Clone, Serialize)]
+pub struct DepthCurve {
+    pub input_token: String,
+    pub output_token: String,
+    pub snapshot_pools: usize,
+    /// 最优现货汇率（零金额）
+    pub spot_rate: Option<f64>,
+    pub tiers: Vec<QuoteTier>,
+    pub marginal_curve: Vec<MarginalPoint>,
+    /// 价格影响开始非线性的最小金额
+    pub nonlinear_from: Option<f64>,
+}
+
+/// 报价引擎：基于一份快照构建的有向邻接表
+pub struct QuoteEngine {
+    /// from_token -> 出边
+    legs: HashMap<String, Vec<Leg>>,
+    pool_count: usize,
+}
+
+impl QuoteEngine {
+    /// 从缓存快照构建（所有档位共用）
+    pub fn from_snapshot(snapshot: &[PoolPrice]) -> Self {
+        let mut legs: HashMap<String, Vec<Leg>> = HashMap::new();
+        let mut pool_count = 0;
+
+        for pool in snapshot {
+            let (base_token, quote_token) = match pool_tokens(pool) {
+                Some((base, quote)) if base != quote => (base, quote),
+                _ => continue,
+            };
+            let (base_decimals, quote_decimals) = pool.get_decimals();
+            let base = pool.base_reserve as f64 / 10f64.powi(base_decimals as i32);
+            let quote = pool.quote_reserve as f64 / 10f64.powi(quote_decimals as i32);
+            if base <= 0.0 || quote <= 0.0 {
+                continue;
+            }
+
+            let fee = crate::fee_registry::fee_rate(&pool.pool_id, &pool.dex_name);
+            pool_count += 1;
+
+            // base -> quote
+            legs.entry(base_token.clone()).or_default().push(Leg {
+                pool_id: pool.pool_id.clone(),
+                dex_name: pool.dex_name.clone(),
+                to_token: quote_token.clone(),
+                reserve_in: base,
+                reserve_out: quote,
+                fee,
+            });
+            // quote -> base
+            legs.entry(quote_token).or_default().push(Leg {
+                pool_id: pool.pool_id.clone(),
+                dex_name: pool.dex_name.clone(),
+                to_token: base_token,
+                reserve_in: quote,
+                reserve_out: base,
+                fee,
+            });
+        }
+
+        Self { legs, pool_count }
+    }
+
+    pub fn pool_count(&self) -> usize {
+        self.pool_count
+    }
+
+    fn legs_from(&self, token: &str) -> impl Iterator<Item = &Leg> {
+        self.legs.get(token).into_iter().flatten()
+    }
+
+    fn route_quote(legs: &[&Leg], via: Option<&str>, amount_in: f64, amount_out: f64) -> RouteQuote {
+        let spot: f64 = legs.iter().map(|l| l.spot_rate()).product();
+        let effective_price = amount_out / amount_in;
+        RouteQuote {
+            pools: legs.iter().map(|l| l.pool_id.clone()).collect(),
+            dexes: legs.iter().map(|l| l.dex_name.clone()).collect(),
+            via: via.map(String::from),
+            amount_out,
+            effective_price,
+            price_impact_pct: (1.0 - effective_price / spot) * 100.0,
+        }
+    }
+
+    /// 最优单池报价
+    fn best_single(&self, from: &str, to: &str, amount_in: f64) -> Option<RouteQuote> {
+        self.legs_from(from)
+            .filter(|leg| leg.to_token == to)
+            .filter_map(|leg| leg.output(amount_in).map(|out| (leg, out)))
+            .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.pool_id.cmp(&a.0.pool_id)))
+            .map(|(leg, out)| Self::route_quote(&[leg], None, amount_in, out))
+    }
+
+    /// 最优两跳报价
+    ///
+    /// 第二跳输出随输入单调递增，因此对每个中间代币只需取第一跳输出最大的池子，
+    /// 再在第二跳中取最优，复杂度与边数线性相关。
+    fn best_two_hop(&self, from: &str, to: &str, amount_in: f64) -> Option<RouteQuote> {
+        let mut best_first: HashMap<&str, (&Leg, f64)> = HashMap::new();
+        for leg in self.legs_from(from) {
+            if leg.to_token == to || leg.to_token == from {
+                continue;
+            }
+            if let Some(out) = leg.output(amount_in) {
+                let entry = best_first.entry(leg.to_token.as_str()).or_insert((leg, out));
+                if out > entry.1 || (out == entry.1 && leg.pool_id < entry.0.pool_id) {
+                    *entry = (leg, out);
+                }
+            }
+        }
+
+        let mut best: Option<(&Leg, &Leg, f64)> = None;
+        for (mid, (first, mid_amount)) in &best_first {
+            for second in self.legs_from(mid).filter(|leg| leg.to_token == to) {
+                let Some(out) = second.output(*mid_amount) else { continue };
+                let better = match &best {
+                    None => true,
+                    Some((bf, bs, best_out)) => {
+                        out > *best_out
+                            || (out == *best_out
+                                && (&first.pool_id, &second.pool_id) < (&bf.pool_id, &bs.pool_id))
+                    }
+                };
+                if better {
+                    best = Some((*first, second, out));
+                }
+            }
+        }
+
+        best.map(|(first, second, out)| {
+            Self::route_quote(&[first, second], Some(&first.to_token), amount_in, out)
+        })
+    }
+
+    /// 最优现货汇率（单池与两跳中的最大值）
+    fn best_spot_rate(&self, from: &str, to: &str) -> Option<f64> {
+        let single = self.legs_from(from)
+            .filter(|leg| leg.to_token == to)
+            .map(|leg| leg.spot_rate());
+        let two_hop = self.legs_from(from)
+            .filter(|leg| leg.to_token != to && leg.to_token != from)
+            .flat_map(|first| {
+                self.legs_from(&first.to_token)
+                    .filter(|second| second.to_token == to)
+                    .map(move |second| first.spot_rate() * second.spot_rate())
+            });
+        single.chain(two_hop).max_by(|a, b| a.total_cmp(b))
+    }
+
+    /// 单个档位
+    pub fn quote(&self, from: &str, to: &str, amount_in: f64) -> QuoteTier {
+        let best_single = self.best_single(from, to, amount_in);
+        let best_two_hop = self.best_two_hop(from, to, amount_in);
+        let best_amount_out = best_single.iter()
+            .chain(best_two_hop.iter())
+            .map(|q| q.amount_out)
+            .max_by(|a, b| a.total_cmp(b));
+
+        QuoteTier {
+            amount_in,
+            truncated: best_amount_out.is_none(),
+            best_single,
+            best_two_hop,
+            best_amount_out,
+        }
+    }
+
+    /// 多个金额的深度曲线（金额按升序评估）
+    pub fn depth_curve(&self, from: &str, to: &str, amounts: &[f64]) -> DepthCurve {
+        let mut amounts: Vec<f64> = amounts.iter()
+            .copied()
+            .filter(|a| a.is_finite() && *a > 0.0)
+            .collect();
+        amounts.sort_by(|a, b| a.total_cmp(b));
+        amounts.dedup();
+
+        let spot_rate = self.best_spot_rate(from, to);
+        let tiers: Vec<QuoteTier> = amounts.iter().map(|&a| self.quote(from, to, a)).collect();
+
+        // 边际价格：从零开始，依次连接各个未截断的档位
+        let mut marginal_curve = Vec::new();
+        let mut nonlinear_from = None;
+        let mut prev = (0.0, 0.0);
+        for tier in &tiers {
+            let Some(out) = tier.best_amount_out else { break };
+            let marginal_price = (out - prev.1) / (tier.amount_in - prev.0);
+            let deviation_pct = match spot_rate {
+                Some(spot) if spot > 0.0 => (marginal_price / spot - 1.0) * 100.0,
+                _ => 0.0,
+            };
+            if nonlinear_from.is_none() && deviation_pct < -NONLINEAR_THRESHOLD_PCT {
+                nonlinear_from = Some(tier.amount_in);
+            }
+            marginal_curve.push(MarginalPoint {
+                from_amount: prev.0,
+                to_amount: tier.amount_in,
+                marginal_price,
+                deviation_pct,
+            });
+            prev = (tier.amount_in, out);
+        }
+
+        DepthCurve {
+            input_token: from.to_string(),
+            output_token: to.to_string(),
+            snapshot_pools: self.pool_count,
+            spot_rate,
+            tiers,
+            marginal_curve,
+            nonlinear_from,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn pool(pool_id: &str, dex: &str, pair: &str, base: f64, quote: f64) -> PoolPrice {
+        let scale = 1_000_000.0;
+        PoolPrice {
+            dex_name: dex.to_string(),
+            base_reserve: (base * scale) as u64,
+            quote_reserve: (quote * scale) as u64,
+            base_decimals: 6,
+            slot: 1000,
+            ..PoolPrice::for_test(pool_id, pair, quote / base)
+        }
+    }
+
+    #[test]
+    fn test_geometric_ladder() {
+        let ladder = geometric_ladder(100.0, DEFAULT_LADDER_STEPS);
+        assert_eq!(ladder.len(), 8);
+        assert!((ladder[0] - 10.0).abs() < 1e-9);
+        assert!((ladder[7] - 1000.0).abs() < 1e-6);
+        for pair in ladder.windows(2) {
+            assert!((pair[1] / pair[0] - ladder[1] / ladder[0]).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_depth_curve_single_and_two_hop() {
+        let snapshot = vec![
+            // 浅的直连池 + 深的两跳路径
+            pool("direct", "Raydium", "SOL/USDC", 100.0, 18_000.0),
+            pool("sol_usdt", "Raydium", "SOL/USDT", 100_000.0, 18_000_000.0),
+            pool("usdt_usdc", "Saber", "USDT/USDC", 50_000_000.0, 50_000_000.0),
+        ];
+        let engine = QuoteEngine::from_snapshot(&snapshot);
+        let curve = engine.depth_curve("SOL", "USDC", &geometric_ladder(10.0, 8));
+
+        assert_eq!(curve.snapshot_pools, 3);
+        assert_eq!(curve.tiers.len(), 8);
+
+        // 小金额：两种路径都可用
+        let small = &curve.tiers[0];
+        assert_eq!(small.best_single.as_ref().unwrap().pools, vec!["direct".to_string()]);
+        let two_hop = small.best_two_hop.as_ref().unwrap();
+        assert_eq!(two_hop.via.as_deref(), Some("USDT"));
+
+        // 大金额：直连池超出深度（50 SOL），只剩两跳
+        let large = curve.tiers.last().unwrap();
+        assert!(large.amount_in > 50.0);
+        assert!(large.best_single.is_none());
+        assert!(large.best_two_hop.is_some());
+        assert!(!large.truncated);
+
+        // 价格影响随金额单调增加
+        let impacts: Vec<f64> = curve.tiers.iter()
+            .filter_map(|t| t.best_single.as_ref().map(|q| q.price_impact_pct))
+            .collect();
+        assert!(impacts.windows(2).all(|w| w[1] > w[0]));
+
+        // 深的两跳路径兜底，边际价格保持线性
+        assert_eq!(curve.marginal_curve.len(), 8);
+        assert!(curve.nonlinear_from.is_none());
+    }
+
+    #[test]
+    fn test_sizes_beyond_depth_are_truncated() {
+        let snapshot = vec![pool("tiny", "Raydium", "SOL/USDC", 10.0, 1_800.0)];
+        let engine = QuoteEngine::from_snapshot(&snapshot);
+        let curve = engine.depth_curve("SOL", "USDC", &[1.0, 4.0, 6.0, 100.0]);
+
+        let truncated: Vec<bool> = curve.tiers.iter().map(|t| t.truncated).collect();
+        assert_eq!(truncated, vec![false, false, true, true]);
+        assert!(curve.tiers[3].best_amount_out.is_none());
+        // 边际曲线在第一个截断档位处停止；浅池从第一档起就是非线性的
+        assert_eq!(curve.marginal_curve.len(), 2);
+        assert_eq!(curve.nonlinear_from, Some(1.0));
+
+        // 未知代币：没有路径，全部截断
+        let none = engine.depth_curve("SOL", "BONK", &[1.0]);
+        assert!(none.tiers[0].truncated);
+        assert!(none.spot_rate.is_none());
+    }
+
+    #[test]
+    fn test_ladder_latency_budget_300_pools() {
+        let pairs = ["SOL/USDC", "SOL/USDT", "USDC/USDT", "SOL/RAY", "RAY/USDC", "JitoSOL/SOL", "mSOL/SOL", "JitoSOL/USDC"];
+        let dexes = ["Raydium", "Orca", "Meteora", "Lifinity"];
+        let snapshot: Vec<PoolPrice> = (0..300)
+            .map(|i| {
+                let depth = 1_000.0 + i as f64 * 37.0;
+                pool(&format!("pool_{}", i), dexes[i % dexes.len()], pairs[i % pairs.len()], depth, depth * 180.0)
+            })
+            .collect();
+
+        let start = Instant::now();
+        let engine = QuoteEngine::from_snapshot(&snapshot);
+        let curve = engine.depth_curve("SOL", "USDC", &geometric_ladder(100.0, DEFAULT_LADDER_STEPS));
+        let elapsed = start.elapsed();
+
+        assert_eq!(curve.tiers.len(), 8);
+        assert!(curve.tiers.iter().all(|t| !t.truncated));
+        // 预算 5ms（release）；debug 构建放宽到 10 倍
+        let budget_ms = if cfg!(debug_assertions) { 50 } else { 5 };
+        assert!(elapsed.as_millis() < budget_ms, "8-size ladder took {:?}", elapsed);
+    }
+}
@@ -0,0 +1,294 @@
+/*!
+ * 📐 机会排序：资本调整后的期望值
+ *
+ * 按 ROI 排序会让 $100 微型路径上的 $3 利润排在 $20k 路径上的 $150 利润前面。
+ * 这里改为按期望值排序：
+ *
+ *   期望值 = 美元净利润（PriceOracle 换算起点代币）× 执行概率
+ *   执行概率 = 跳数因子 × 价格冲击因子 × 数据年龄因子
+ *
+ * 权重来自 [router.ranking]（见 `RankingConfig`）。起点代币没有美元价格的路径排在最后（彼此按 ROI）。
+ */
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::config::{PriceOracleConfig, RankingConfig};
+use crate::price_cache::PriceCache;
+use crate::price_oracle::PriceOracle;
+use crate::router_split_optimizer::OptimizedPath;
+
+/// 一条路径的期望值及其组成（Display 用于调试输出，解释排序原因）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExpectedValue {
+    /// 美元净利润（起点代币没有美元价格时为 None）
+    pub expected_profit_usd: Option<f64>,
+    /// `hop_success_rate ^ 跳数`
+    pub hop_factor: f64,
+    /// 各跳价格冲击之和（%，有拆分策略时按分配金额下的冲击）
+    pub total_impact_percent: f64,
+    /// `exp(-impact_weight × total_impact_percent)`
+    pub impact_factor: f64,
+    /// 路径中最旧池子的数据年龄（池子不在缓存中时为 None）
+    pub stalest_age_ms: Option<u64>,
+    /// `0.5 ^ (stalest_age_ms / age_half_life_ms)`
+    pub age_factor: f64,
+}
+
+impl ExpectedValue {
+    /// 执行概率
+    pub fn execution_probability(&self) -> f64 {
+        self.hop_factor * self.impact_factor * self.age_factor
+    }
+
+    /// 期望值（美元）
+    pub fn expected_value_usd(&self) -> Option<f64> {
+        self.expected_profit_usd.map(|profit| profit * self.execution_probability())
+    }
+
+    /// 降序比较：期望值高的在前，没有美元价格的排最后
+    pub fn cmp_desc(&self, other: &Self) -> Ordering {
+        match (self.expected_value_usd(), other.expected_value_usd()) {
+            (Some(a), Some(b)) => b.total_cmp(&a),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+impl fmt::Display for ExpectedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.expected_value_usd() {
+            Some(ev) => write!(f, "EV ${:.4}", ev)?,
+            None => write!(f, "EV n/a")?,
+        }
+        match self.expected_profit_usd {
+            Some(profit) => write!(f, " = profit ${:.4}", profit)?,
+            None => write!(f, " = profit n/a (no USD price)")?,
+        }
+        write!(
+            f,
+            " × P {:.4} [hops {:.4} × impact {:.4} ({:.4}%) × age {:.4} (",
+            self.execution_probability(),
+            self.hop_factor,
+            self.impact_factor,
+            self.total_impact_percent,
+            self.age_factor
+        )?;
+        match self.stalest_age_ms {
+            Some(age_ms) => write!(f, "{}ms)]", age_ms),
+            None => write!(f, "unknown)]"),
+        }
+    }
+}
+
+/// 按期望值给机会排序
+pub struct OpportunityRanker {
+    config: RankingConfig,
+    price_cache: Arc<PriceCache>,
+    oracle: Arc<PriceOracle>,
+}
+
+impl OpportunityRanker {
+    pub fn new(price_cache: Arc<PriceCache>, oracle: Arc<PriceOracle>, config: RankingConfig) -> Self {
+        Self { config, price_cache, oracle }
+    }
+
+    /// 使用默认定价配置的 PriceOracle
+    pub fn with_default_oracle(price_cache: Arc<PriceCache>, config: RankingConfig) -> Self {
+        let oracle = Arc::new(PriceOracle::new(price_cache.clone(), &PriceOracleConfig::default()));
+        Self::new(price_cache, oracle, config)
+    }
+
+    /// 计算一条路径的期望值
+    pub fn evaluate(&self, path: &OptimizedPath) -> ExpectedValue {
+        let usd_price = self.oracle.get_usd_price(&path.base_path.start_token);
+        self.evaluate_with_price(path, usd_price)
+    }
+
+    /// 按给定的起点代币美元价格计算期望值
+    pub fn evaluate_with_price(&self, path: &OptimizedPath, usd_price: Option<f64>) -> ExpectedValue {
+        let hops = path.base_path.steps.len() as i32;
+        let hop_factor = self.config.hop_success_rate.clamp(0.0, 1.0).powi(hops);
+
+        let total_impact_percent: f64 = match &path.split_strategy {
+            Some(strategy) if !strategy.hop_impacts_percent.is_empty() => strategy.hop_impacts_percent.iter().sum(),
+            _ => path.base_path.steps.iter().map(|s| s.price_impact_percent).sum(),
+        };
+        let impact_factor = (-self.config.impact_weight * total_impact_percent.max(0.0)).exp();
+
+        let stalest_age_ms = path.base_path.steps.iter()
+            .filter_map(|step| self.price_cache.get_price(&step.pool_id))
+            .map(|pool| pool.last_update.elapsed().as_millis() as u64)
+            .max();
+        let age_factor = match stalest_age_ms {
+            Some(age_ms) if self.config.age_half_life_ms > 0 => {
+                0.5f64.powf(age_ms as f64 / self.config.age_half_life_ms as f64)
+            }
+            _ => 1.0,
+        };
+
+        ExpectedValue {
+            expected_profit_usd: usd_price.map(|price| path.optimized_net_profit * price),
+            hop_factor,
+            total_impact_percent,
+            impact_factor,
+            stalest_age_ms,
+            age_factor,
+        }
+    }
+
+    /// 按期望值降序排序（同一起点代币只查询一次美元价格）
+    ///
+    /// 期望值相同（或都没有美元价格）时按 ROI 降序，再按路径签名保证确定性。
+    pub fn rank<T>(&self, items: Vec<T>, path_of: impl Fn(&T) -> &OptimizedPath) -> Vec<T> {
+        let mut usd_prices: HashMap<String, Option<f64>> = HashMap::new();
+        let mut keyed: Vec<(ExpectedValue, T)> = items.into_iter()
+            .map(|item| {
+                let path = path_of(&item);
+                let usd_price = *usd_prices.entry(path.base_path.start_token.clone())
+                    .or_insert_with(|| self.oracle.get_usd_price(&path.base_path.start_token));
+                (self.evaluate_with_price(path, usd_price), item)
+            })
+            .collect();
+
+        keyed.sort_by(|(a_ev, a), (b_ev, b)| {
+            let (a, b) = (path_of(a), path_of(b));
+            a_ev.cmp_desc(b_ev)
+                .then_with(|| b.optimized_roi.total_cmp(&a.optimized_roi))
+                .then_with(|| a.base_path.signature().cmp(&b.base_path.signature()))
+        });
+        keyed.into_iter().map(|(_, item)| item).collect()
+    }
+
+    /// 期望值最高的路径
+    pub fn select_best<'a>(
+        &self,
+        paths: impl IntoIterator<Item = &'a OptimizedPath>,
+    ) -> Option<(&'a OptimizedPath, ExpectedValue)> {
+        paths.into_iter()
+            .map(|path| (path, self.evaluate(path)))
+            .min_by(|(a, a_ev), (b, b_ev)| {
+                a_ev.cmp_desc(b_ev).then_with(|| b.optimized_roi.total_cmp(&a.optimized_roi))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price_cache::PoolPrice;
+    use crate::router::{ArbitragePath, ArbitrageType, RouteStep};
+    use std::time::{Duration, Instant};
+
+    /// USDC 起点的环路：每跳价格冲击相同，池子按 `age` 写入缓存
+    fn usdc_cycle(cache: &PriceCache, prefix: &str, hops: usize, input: f64, roi: f64, impact: f64, age: Duration) -> OptimizedPath {
+        let steps: Vec<RouteStep> = (0..hops).map(|hop| {
+            let pool_id = format!("{}-{}", prefix, hop);
+            cache.update_price(PoolPrice {
+                dex_name: "Test".to_string(),
+                base_reserve: 1_000_000,
+                quote_reserve: 1_000_000,
+                base_decimals: 6,
+                last_update: Instant::now() - age,
+                ..PoolPrice::for_test(&pool_id, "TKN/USDC", 1.0)
+            });
+            RouteStep {
+                pool_id,
+                dex_name: "Test".to_string(),
+                input_token: "USDC".to_string(),
+                output_token: "USDC".to_string(),
+                price: 1.0,
+                liquidity_base: 0,
+                liquidity_quote: 0,
+                expected_input: input,
+                expected_output: input,
+                price_impact_percent: impact,
+                effective_fee_bps: 25.0,
+                raw_input_token: None,
+                raw_output_token: None,
+            }
+        }).collect();
+        let profit = input * roi / 100.0;
+
+        OptimizedPath {
+            base_path: ArbitragePath {
+                arb_type: ArbitrageType::Triangle,
+                steps,
+                start_token: "USDC".to_string(),
+                end_token: "USDC".to_string(),
+                input_amount: input,
+                output_amount: input + profit,
+                gross_profit: profit,
+                dex_fees: 0.0,
+                execution_fees: 0.0,
+                estimated_fees: 0.0,
+                net_profit: profit,
+                roi_percent: roi,
+                discovered_at: Instant::now(),
+            },
+            split_strategy: None,
+            optimized_net_profit: profit,
+            optimized_roi: roi,
+        }
+    }
+
+    #[test]
+    fn test_deep_two_hop_beats_thin_five_hop() {
+        let cache = Arc::new(PriceCache::new());
+        let ranker = OpportunityRanker::with_default_oracle(cache.clone(), RankingConfig::default());
+
+        // $20k 两跳 0.75% = $150；$100 五跳 3% = $3，每跳 1% 冲击
+        let deep = usdc_cycle(&cache, "deep", 2, 20_000.0, 0.75, 0.05, Duration::ZERO);
+        let thin = usdc_cycle(&cache, "thin", 5, 100.0, 3.0, 1.0, Duration::ZERO);
+        assert!(thin.optimized_roi > deep.optimized_roi);
+
+        let deep_ev = ranker.evaluate(&deep);
+        let thin_ev = ranker.evaluate(&thin);
+        assert!((deep_ev.expected_profit_usd.unwrap() - 150.0).abs() < 1e-9);
+        assert!(deep_ev.expected_value_usd().unwrap() > thin_ev.expected_value_usd().unwrap());
+
+        let paths = ranker.rank(vec![thin.clone(), deep.clone()], |p| p);
+        assert_eq!(paths[0].base_path.signature(), deep.base_path.signature());
+
+        let (best, ev) = ranker.select_best(&paths).unwrap();
+        assert_eq!(best.base_path.signature(), deep.base_path.signature());
+        assert!(ev.to_string().contains("hops 0.9025"), "{}", ev);
+    }
+
+    #[test]
+    fn test_stale_pool_data_lowers_execution_probability() {
+        let cache = Arc::new(PriceCache::new());
+        let ranker = OpportunityRanker::with_default_oracle(cache.clone(), RankingConfig::default());
+
+        let fresh = usdc_cycle(&cache, "fresh", 3, 1_000.0, 1.0, 0.1, Duration::ZERO);
+        let stale = usdc_cycle(&cache, "stale", 3, 1_000.0, 1.0, 0.1, Duration::from_millis(2_000));
+
+        let fresh_ev = ranker.evaluate(&fresh);
+        let stale_ev = ranker.evaluate(&stale);
+        assert!(stale_ev.stalest_age_ms.unwrap() >= 2_000);
+        // 数据年龄达到半衰期：执行概率约减半
+        assert!(stale_ev.age_factor <= 0.5 + 1e-9);
+        assert!(fresh_ev.age_factor > 0.99);
+        assert_eq!(fresh_ev.cmp_desc(&stale_ev), Ordering::Less);
+    }
+
+    #[test]
+    fn test_paths_without_usd_price_rank_last() {
+        let cache = Arc::new(PriceCache::new());
+        let ranker = OpportunityRanker::with_default_oracle(cache.clone(), RankingConfig::default());
+
+        let priced = usdc_cycle(&cache, "priced", 2, 100.0, 0.5, 0.0, Duration::ZERO);
+        let mut unpriced = usdc_cycle(&cache, "unpriced", 2, 100.0, 5.0, 0.0, Duration::ZERO);
+        unpriced.base_path.start_token = "NOPRICE".to_string();
+
+        let paths = ranker.rank(vec![unpriced, priced], |p| p);
+        assert_eq!(paths[0].base_path.start_token, "USDC");
+        assert!(ranker.evaluate(&paths[1]).to_string().contains("no USD price"));
+    }
+}
@@ -0,0 +1,146 @@
+/*!
+ * WebSocket 重连退避
+ *
+ * 指数退避 + full jitter：第 n 次重连等待 [0, min(max, initial × multiplier^n)] 内的随机时长。
+ * 连接存活超过 stable_after 视为恢复正常，退避重置。
+ */
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::WebSocketConfig;
+
+/// 退避策略
+#[derive(Debug, Clone)]
+pub struct BackoffPolicy {
+    pub initial: Duration,
+    pub multiplier: f64,
+    pub max: Duration,
+    /// 存活超过该时长的连接视为稳定
+    pub stable_after: Duration,
+    /// 同一端点连续失败超过该次数时告警（由 EndpointPool 按端点计数）
+    pub alert_after_failures: u32,
+}
+
+impl Default for BackoffPolicy {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            multiplier: 2.0,
+            max: Duration::from_secs(60),
+            stable_after: Duration::from_secs(30),
+            alert_after_failures: 10,
+        }
+    }
+}
+
+impl BackoffPolicy {
+    pub fn from_config(config: &WebSocketConfig) -> Self {
+        Self {
+            initial: Duration::from_millis(config.reconnect_initial_delay_ms),
+            multiplier: config.reconnect_multiplier.max(1.0),
+            max: Duration::from_millis(config.reconnect_max_delay_ms),
+            stable_after: Duration::from_secs(config.reconnect_stable_secs),
+            alert_after_failures: config.reconnect_alert_after_failures,
+        }
+    }
+}
+
+/// 重连退避状态
+#[derive(Debug, Clone)]
+pub struct ReconnectBackoff {
+    policy: BackoffPolicy,
+    /// 当前退避指数（稳定连接后归零）
+    attempt: u32,
+    /// xorshift64 状态（jitter 不需要密码学随机数）
+    rng_state: u64,
+}
+
+impl ReconnectBackoff {
+    pub fn new(policy: BackoffPolicy) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0x9E37_79B9_7F4A_7C15);
+        Self::with_seed(policy, seed)
+    }
+
+    pub fn with_seed(policy: BackoffPolicy, seed: u64) -> Self {
+        Self {
+            policy,
+            attempt: 0,
+            rng_state: seed | 1,
+        }
+    }
+
+    pub fn policy(&self) -> &BackoffPolicy {
+        &self.policy
+    }
+
+    /// 记录一次断开（connected_for 为本次连接的存活时长），稳定连接重置退避
+    pub fn record_disconnect(&mut self, connected_for: Duration) {
+        if connected_for >= self.policy.stable_after {
+            self.attempt = 0;
+        }
+    }
+
+    /// 当前退避上限（未加 jitter）
+    pub fn current_cap(&self) -> Duration {
+        let factor = self.policy.multiplier.powi(self.attempt.min(64) as i32);
+        let cap_secs = self.policy.initial.as_secs_f64() * factor;
+        if !cap_secs.is_finite() || cap_secs >= self.policy.max.as_secs_f64() {
+            self.policy.max
+        } else {
+            Duration::from_secs_f64(cap_secs)
+        }
+    }
+
+    /// 下一次重连前的等待时长（full jitter），并推进退避指数
+    pub fn next_delay(&mut self) -> Duration {
+        let cap = self.current_cap();
+        self.attempt = self.attempt.saturating_add(1);
+        cap.mul_f64(self.next_unit())
+    }
+
+    /// [0, 1) 均匀分布
+    fn next_unit(&mut self) -> f64 {
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng_state = x;
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_grows_to_cap_with_jitter() {
+        let mut backoff = ReconnectBackoff::with_seed(BackoffPolicy::default(), 42);
+
+        let caps: Vec<u64> = (0..8)
+            .map(|_| {
+                let cap = backoff.current_cap();
+                let delay = backoff.next_delay();
+                assert!(delay <= cap);
+                cap.as_secs()
+            })
+            .collect();
+        assert_eq!(caps, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+    }
+
+    #[test]
+    fn test_stable_connection_resets_backoff() {
+        let mut backoff = ReconnectBackoff::with_seed(BackoffPolicy::default(), 7);
+        for _ in 0..5 {
+            backoff.record_disconnect(Duration::from_secs(1));
+            backoff.next_delay();
+        }
+        assert_eq!(backoff.current_cap(), Duration::from_secs(32));
+
+        backoff.record_disconnect(Duration::from_secs(45));
+        assert_eq!(backoff.current_cap(), Duration::from_secs(1));
+    }
+}
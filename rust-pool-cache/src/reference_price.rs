@@ -0,0 +1,415 @@
+/*!
+ * 🧭 外部参考价格交叉校验
+ *
+ * 定期从配置的 REST 接口（Pyth Hermes 或任何 JSON 价格接口）拉取主要交易对的参考价格，
+ * 与缓存中同一交易对的每个池子比较：
+ *
+ * - 偏离超过 `warn_deviation_percent`：标记为 Flagged 并告警（新反序列化器的小数位错误、
+ *   base/quote 颠倒通常偏离几个数量级，几秒内就能发现）
+ * - 偏离超过 `exclude_deviation_percent`：标记为 Excluded，`PriceCache` 将其排除出路由和快照
+ *
+ * 报价代币 "USD" 匹配所有稳定币；池子方向与参考价格相反时按倒数比较。
+ * 外部接口失败只记录警告：参考价格超过 `max_age_secs` 后不再参与比较，已排除的池子随之恢复，
+ * 主流程不受影响。
+ */
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use dashmap::DashMap;
+use serde::Serialize;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::config::{ReferenceFeedConfig, ReferencePriceConfig};
+use crate::price_cache::{PoolPrice, PriceCache};
+use crate::price_oracle::is_stablecoin;
+use crate::prometheus::{MetricKind, PrometheusWriter};
+use crate::supervisor::{spawn_supervised, Supervisor};
+use crate::token_alias;
+use crate::token_graph::pool_tokens;
+
+/// 参考价格的报价代币 "USD" 匹配任意稳定币
+const USD: &str = "USD";
+
+/// 一个交易对的最新参考价格
+#[derive(Debug, Clone)]
+pub struct ReferencePrice {
+    pub base: String,
+    pub quote: String,
+    /// 1 base 值多少 quote
+    pub price: f64,
+    pub fetched_at: Instant,
+}
+
+/// 池子相对参考价格的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviationStatus {
+    Ok,
+    /// 超过告警阈值
+    Flagged,
+    /// 超过排除阈值，不进路由
+    Excluded,
+}
+
+/// 一个池子的最近一次比较结果
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolDeviation {
+    pub pool_id: String,
+    pub dex_name: String,
+    pub pair: String,
+    /// 参考价格的交易对（"SOL/USD"）
+    pub reference_pair: String,
+    /// 池子价格（已换算到参考价格的方向）
+    pub pool_price: f64,
+    pub reference_price: f64,
+    pub deviation_percent: f64,
+    pub status: DeviationStatus,
+}
+
+/// GET /reference_prices 的响应
+#[derive(Debug, Clone, Serialize)]
+pub struct ReferenceReport {
+    /// 交易对 -> (参考价格, 距上次成功拉取的秒数)
+    pub references: BTreeMap<String, (f64, f64)>,
+    /// Flagged / Excluded 的池子，按偏离从大到小
+    pub deviations: Vec<PoolDeviation>,
+    pub compared_pools: usize,
+}
+
+/// 从 JSON 响应中取出一个 feed 的价格（数字或数字字符串，可选十进制指数）
+pub fn parse_feed(body: &str, feed: &ReferenceFeedConfig) -> Result<f64> {
+    let json: serde_json::Value = serde_json::from_str(body).context("Reference response is not JSON")?;
+    let mut price = number_at(&json, &feed.price_pointer)?;
+    if let Some(pointer) = &feed.exponent_pointer {
+        price *= 10f64.powi(number_at(&json, pointer)? as i32);
+    }
+    if !(price.is_finite() && price > 0.0) {
+        return Err(anyhow!("Reference price for {} is not positive: {}", feed.pair, price));
+    }
+    Ok(price)
+}
+
+fn number_at(json: &serde_json::Value, pointer: &str) -> Result<f64> {
+    let value = json.pointer(pointer).ok_or_else(|| anyhow!("Missing field {}", pointer))?;
+    value.as_f64()
+        .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+        .ok_or_else(|| anyhow!("Field {} is not a number: {}", pointer, value))
+}
+
+/// 参考代币与池子代币是否相同（"USD" 匹配稳定币）
+fn token_matches(reference: &str, pool: &str) -> bool {
+    reference == pool || (reference == USD && is_stablecoin(pool))
+}
+
+/// 偏离百分比：|pool - reference| / reference × 100
+pub fn deviation_percent(pool_price: f64, reference_price: f64) -> f64 {
+    (pool_price - reference_price).abs() / reference_price * 100.0
+}
+
+/// 参考价格拉取与池子偏离判定
+pub struct ReferencePriceChecker {
+    config: ReferencePriceConfig,
+    /// "BASE/QUOTE" -> 参考价格
+    references: DashMap<String, ReferencePrice>,
+    /// 池子 -> 最近一次比较结果
+    deviations: DashMap<String, PoolDeviation>,
+    fetch_successes: AtomicU64,
+    fetch_failures: AtomicU64,
+    /// 池子从 Ok 变为 Flagged / Excluded 的次数
+    flag_events: AtomicU64,
+}
+
+impl ReferencePriceChecker {
+    pub fn new(config: ReferencePriceConfig) -> Self {
+        Self {
+            config,
+            references: DashMap::new(),
+            deviations: DashMap::new(),
+            fetch_successes: AtomicU64::new(0),
+            fetch_failures: AtomicU64::new(0),
+            flag_events: AtomicU64::new(0),
+        }
+    }
+
+    /// 写入一个交易对的参考价格
+    pub fn set_reference(&self, pair: &str, price: f64, fetched_at: Instant) {
+        let Some((base, quote)) = pair.split_once('/') else {
+            return;
+        };
+        let (base, quote) = (token_alias::canonical(base.trim()).into_owned(), token_alias::canonical(quote.trim()).into_owned());
+        self.references.insert(
+            pair.to_string(),
+            ReferencePrice { base, quote, price, fetched_at },
+        );
+    }
+
+    /// 拉取全部 feed；同一 URL 每轮只请求一次。返回成功的 feed 数量
+    pub async fn fetch_all(&self) -> usize {
+        let mut by_url: BTreeMap<&str, Vec<&ReferenceFeedConfig>> = BTreeMap::new();
+        for feed in &self.config.feeds {
+            by_url.entry(feed.url.as_str()).or_default().push(feed);
+        }
+
+        let mut fetched = 0;
+        for (url, feeds) in by_url {
+            let body = match crate::webhook::get_json(url).await {
+                Ok(body) => body,
+                Err(e) => {
+                    self.fetch_failures.fetch_add(feeds.len() as u64, Ordering::Relaxed);
+                    warn!("🧭 Reference price fetch failed for {}: {:#}", url, e);
+                    continue;
+                }
+            };
+            for feed in feeds {
+                match parse_feed(&body, feed) {
+                    Ok(price) => {
+                        self.set_reference(&feed.pair, price, Instant::now());
+                        self.fetch_successes.fetch_add(1, Ordering::Relaxed);
+                        fetched += 1;
+                    }
+                    Err(e) => {
+                        self.fetch_failures.fetch_add(1, Ordering::Relaxed);
+                        warn!("🧭 Cannot parse reference price for {}: {:#}", feed.pair, e);
+                    }
+                }
+            }
+        }
+        fetched
+    }
+
+    /// 未过期的参考价格
+    fn fresh_references(&self, now: Instant) -> Vec<(String, ReferencePrice)> {
+        let max_age = Duration::from_secs(self.config.max_age_secs);
+        self.references.iter()
+            .filter(|entry| now.saturating_duration_since(entry.fetched_at) <= max_age)
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect()
+    }
+
+    /// 池子相对参考价格的比较结果；没有匹配的参考价格或价格无效时为 None
+    fn compare(&self, pool: &PoolPrice, references: &[(String, ReferencePrice)]) -> Option<PoolDeviation> {
+        if !(pool.price.is_finite() && pool.price > 0.0) {
+            return None;
+        }
+        let (base, quote) = pool_tokens(pool)?;
+        let (reference_pair, reference, pool_price) = references.iter().find_map(|(pair, reference)| {
+            if token_matches(&reference.base, &base) && token_matches(&reference.quote, &quote) {
+                Some((pair, reference, pool.price))
+            } else if token_matches(&reference.base, &quote) && token_matches(&reference.quote, &base) {
+                Some((pair, reference, 1.0 / pool.price))
+            } else {
+                None
+            }
+        })?;
+
+        let deviation = deviation_percent(pool_price, reference.price);
+        let status = if deviation > self.config.exclude_deviation_percent {
+            DeviationStatus::Excluded
+        } else if deviation > self.config.warn_deviation_percent {
+            DeviationStatus::Flagged
+        } else {
+            DeviationStatus::Ok
+        };
+        Some(PoolDeviation {
+            pool_id: pool.pool_id.clone(),
+            dex_name: pool.dex_name.clone(),
+            pair: pool.pair.clone(),
+            reference_pair: reference_pair.clone(),
+            pool_price,
+            reference_price: reference.price,
+            deviation_percent: deviation,
+            status,
+        })
+    }
+
+    /// 比较缓存中的全部池子，更新偏离状态和 PriceCache 的路由排除。返回比较过的池子数量
+    pub fn check(&self, price_cache: &PriceCache) -> usize {
+        let references = self.fresh_references(Instant::now());
+        let mut compared = 0;
+
+        for pool in price_cache.get_all_prices() {
+            let Some(result) = self.compare(&pool, &references) else {
+                // 没有可用的参考价格：不再判定，解除之前的排除
+                if self.deviations.remove(&pool.pool_id).is_some()
+                    && price_cache.set_reference_excluded(&pool.pool_id, false)
+                {
+                    info!("🧭 {} ({}) no longer checked against a reference price, re-enabled for routing", pool.pair, pool.pool_id);
+                }
+                continue;
+            };
+            compared += 1;
+
+            let previous = self.deviations.get(&pool.pool_id).map(|d| d.status).unwrap_or(DeviationStatus::Ok);
+            if previous == DeviationStatus::Ok && result.status != DeviationStatus::Ok {
+                self.flag_events.fetch_add(1, Ordering::Relaxed);
+            }
+            if result.status != previous {
+                match result.status {
+                    DeviationStatus::Ok => info!(
+                        "🧭 {} ({}) back within {:.1}% of reference {} ({:.6} vs {:.6})",
+                        result.pair, result.pool_id, self.config.warn_deviation_percent,
+                        result.reference_pair, result.pool_price, result.reference_price,
+                    ),
+                    status => warn!(
+                        "🧭 {} ({}, {}) deviates {:.2}% from reference {}: pool {:.6} vs reference {:.6}{}",
+                        result.pair, result.pool_id, result.dex_name, result.deviation_percent,
+                        result.reference_pair, result.pool_price, result.reference_price,
+                        if status == DeviationStatus::Excluded { " — excluded from routing" } else { "" },
+                    ),
+                }
+            }
+            price_cache.set_reference_excluded(&pool.pool_id, result.status == DeviationStatus::Excluded);
+            self.deviations.insert(pool.pool_id.clone(), result);
+        }
+
+        // 已从缓存移除的池子
+        self.deviations.retain(|pool_id, _| price_cache.get_price(pool_id).is_some());
+        compared
+    }
+
+    /// 当前参考价格和偏离的池子
+    pub fn report(&self) -> ReferenceReport {
+        let now = Instant::now();
+        let references = self.references.iter()
+            .map(|entry| {
+                let age_secs = now.saturating_duration_since(entry.fetched_at).as_secs_f64();
+                (entry.key().clone(), (entry.price, age_secs))
+            })
+            .collect();
+        let mut deviations: Vec<PoolDeviation> = self.deviations.iter()
+            .filter(|entry| entry.status != DeviationStatus::Ok)
+            .map(|entry| entry.value().clone())
+            .collect();
+        deviations.sort_by(|a, b| b.deviation_percent.total_cmp(&a.deviation_percent));
+        ReferenceReport {
+            references,
+            deviations,
+            compared_pools: self.deviations.len(),
+        }
+    }
+
+    /// 各状态的池子数量
+    fn status_count(&self, status: DeviationStatus) -> usize {
+        self.deviations.iter().filter(|entry| entry.status == status).count()
+    }
+
+    pub fn write_prometheus(&self, writer: &mut PrometheusWriter) {
+        writer.family(
+            "pool_cache_reference_fetches_total",
+            "Reference price feed fetches by outcome",
+            MetricKind::Counter,
+        );
+        writer.sample(
+            "pool_cache_reference_fetches_total",
+            &[("outcome", "success")],
+            self.fetch_successes.load(Ordering::Relaxed) as f64,
+        );
+        writer.sample(
+            "pool_cache_reference_fetches_total",
+            &[("outcome", "failure")],
+            self.fetch_failures.load(Ordering::Relaxed) as f64,
+        );
+        writer.family(
+            "pool_cache_reference_deviation_pools",
+            "Pools checked against a reference price, by status",
+            MetricKind::Gauge,
+        );
+        for (status, label) in [
+            (DeviationStatus::Ok, "ok"),
+            (DeviationStatus::Flagged, "flagged"),
+            (DeviationStatus::Excluded, "excluded"),
+        ] {
+            writer.sample("pool_cache_reference_deviation_pools", &[("status", label)], self.status_count(status) as f64);
+        }
+        writer.family(
+            "pool_cache_reference_deviation_flags_total",
+            "Times a pool started deviating from its reference price",
+            MetricKind::Counter,
+        );
+        writer.sample("pool_cache_reference_deviation_flags_total", &[], self.flag_events.load(Ordering::Relaxed) as f64);
+    }
+}
+
+/// 后台任务：每隔 `poll_interval_secs` 拉取参考价格并比较全部池子
+pub fn spawn_checker(
+    checker: Arc<ReferencePriceChecker>,
+    price_cache: Arc<PriceCache>,
+    supervisor: &Supervisor,
+) -> JoinHandle<()> {
+    let interval = Duration::from_secs(checker.config.poll_interval_secs);
+    spawn_supervised("reference_prices", supervisor, move || {
+        let (checker, price_cache) = (checker.clone(), price_cache.clone());
+        async move {
+            loop {
+                let fetched = checker.fetch_all().await;
+                let compared = checker.check(&price_cache);
+                debug!("🧭 Fetched {} reference prices, compared {} pools", fetched, compared);
+                tokio::time::sleep(interval).await;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(pool_id: &str, pair: &str, price: f64) -> PoolPrice {
+        PoolPrice {
+            base_reserve: 1_000_000_000,
+            quote_reserve: 150_000_000,
+            ..PoolPrice::for_test(pool_id, pair, price)
+        }
+    }
+
+    #[test]
+    fn test_parse_feed_with_exponent() {
+        let feed = ReferenceFeedConfig {
+            pair: "SOL/USD".to_string(),
+            url: "http://localhost/price".to_string(),
+            price_pointer: "/parsed/0/price/price".to_string(),
+            exponent_pointer: Some("/parsed/0/price/expo".to_string()),
+        };
+        let body = r#"{"parsed":[{"price":{"price":"15025000000","expo":-8}}]}"#;
+        assert!((parse_feed(body, &feed).unwrap() - 150.25).abs() < 1e-9);
+
+        assert!(parse_feed(r#"{"parsed":[]}"#, &feed).is_err());
+        assert!(parse_feed("not json", &feed).is_err());
+    }
+
+    #[test]
+    fn test_check_flags_and_excludes_deviating_pools() {
+        let cache = PriceCache::new();
+        cache.update_price(pool("good", "SOL/USDC", 150.5));
+        cache.update_price(pool("drifting", "SOL/USDT", 155.0));
+        // 小数位错误（差 1000 倍）和 base/quote 颠倒
+        cache.update_price(pool("decimals", "SOL/USDC", 0.15));
+        cache.update_price(pool("inverted", "USDC/SOL", 1.0 / 150.0));
+        cache.update_price(pool("unrelated", "BONK/USDC", 0.00002));
+
+        let checker = ReferencePriceChecker::new(ReferencePriceConfig::default());
+        checker.set_reference("SOL/USD", 150.0, Instant::now());
+        assert_eq!(checker.check(&cache), 4);
+
+        let report = checker.report();
+        let statuses: Vec<(&str, DeviationStatus)> = report.deviations.iter()
+            .map(|d| (d.pool_id.as_str(), d.status))
+            .collect();
+        assert_eq!(statuses, vec![("decimals", DeviationStatus::Excluded), ("drifting", DeviationStatus::Flagged)]);
+        assert!(cache.is_reference_excluded("decimals"));
+        assert!(!cache.is_reference_excluded("drifting"));
+        assert!(!cache.is_reference_excluded("inverted"));
+        assert!(cache.get_routable_prices().iter().all(|p| p.pool_id != "decimals"));
+
+        // 参考价格过期后不再判定，排除随之解除
+        checker.set_reference("SOL/USD", 150.0, Instant::now() - Duration::from_secs(300));
+        assert_eq!(checker.check(&cache), 0);
+        assert!(!cache.is_reference_excluded("decimals"));
+        assert!(checker.report().deviations.is_empty());
+    }
+}
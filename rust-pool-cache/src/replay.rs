@@ -0,0 +1,362 @@
+/*!
+ * 🎞️ 回放：按记录顺序把池子更新写回 PriceCache，驱动 Coordinator -> Calculator
+ *
+ * 数据来自 pool_update_history（`[database] record_pool_updates = true` 时由 WebSocket 路径写入）。
+ * 更新源抽象为 `PoolUpdateSource`，回放时不需要 WebSocket / RPC / 模拟器：每条更新按
+ * WebSocket 路径的方式写入缓存（含 slot）并发出同样的 PriceChangeEvent。
+ */
+
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, info};
+
+use crate::coordinator::PriceChangeEvent;
+use crate::database::{DatabaseManager, PoolUpdateRecord};
+use crate::price_cache::PriceCache;
+
+/// 池子更新来源（按 recorded_at 升序）
+pub trait PoolUpdateSource: Send {
+    /// 下一批更新；返回空 Vec 表示已经读完
+    fn next_batch(&mut self) -> impl Future<Output = Result<Vec<PoolUpdateRecord>>> + Send;
+}
+
+/// 从 pool_update_history 按 (recorded_at, id) 分页读取
+pub struct DatabaseUpdateSource {
+    db: Arc<DatabaseManager>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    cursor: Option<(DateTime<Utc>, i64)>,
+    page_size: i64,
+}
+
+impl DatabaseUpdateSource {
+    pub fn new(db: Arc<DatabaseManager>, from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        Self {
+            db,
+            from,
+            to,
+            cursor: None,
+            page_size: 5_000,
+        }
+    }
+}
+
+impl PoolUpdateSource for DatabaseUpdateSource {
+    async fn next_batch(&mut self) -> Result<Vec<PoolUpdateRecord>> {
+        let rows = self.db
+            .load_pool_updates(self.from, self.to, self.cursor, self.page_size)
+            .await
+            .map_err(|e| anyhow!("Failed to load pool updates: {}", e))?;
+        if let Some((id, last)) = rows.last() {
+            self.cursor = Some((last.recorded_at, *id));
+        }
+        Ok(rows.into_iter().map(|(_, update)| update).collect())
+    }
+}
+
+/// 内存中的更新序列（测试 / 嵌入方自行构造的场景）
+pub struct MemoryUpdateSource {
+    updates: Vec<PoolUpdateRecord>,
+}
+
+impl MemoryUpdateSource {
+    pub fn new(mut updates: Vec<PoolUpdateRecord>) -> Self {
+        updates.sort_by_key(|update| update.recorded_at);
+        Self { updates }
+    }
+}
+
+impl PoolUpdateSource for MemoryUpdateSource {
+    async fn next_batch(&mut self) -> Result<Vec<PoolUpdateRecord>> {
+        Ok(std::mem::take(&mut self.updates))
+    }
+}
+
+/// 回放速度
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// 按记录的时间间隔回放，倍数 > 1 为加速（1.0 = 实时）
+    Scaled(f64),
+    /// 不等待，尽快回放
+    Max,
+}
+
+impl ReplaySpeed {
+    /// "max" / "10" / "10x"
+    pub fn parse(s: &str) -> Result<Self> {
+        if s.eq_ignore_ascii_case("max") {
+            return Ok(ReplaySpeed::Max);
+        }
+        let factor: f64 = s.trim_end_matches(['x', 'X']).parse()
+            .map_err(|_| anyhow!("Invalid replay speed '{}' (expected a multiplier like 10 or 'max')", s))?;
+        if !factor.is_finite() || factor <= 0.0 {
+            bail!("Replay speed must be positive, got {}", s);
+        }
+        Ok(ReplaySpeed::Scaled(factor))
+    }
+
+    /// 从回放起点到该市场时间应经过的墙钟时长（Max 为 None）
+    fn wall_offset(&self, market_elapsed: chrono::Duration) -> Option<Duration> {
+        match self {
+            ReplaySpeed::Max => None,
+            ReplaySpeed::Scaled(factor) => {
+                let millis = market_elapsed.num_milliseconds().max(0) as f64 / factor;
+                Some(Duration::from_secs_f64(millis / 1000.0))
+            }
+        }
+    }
+}
+
+/// 解析回放时间范围的端点（UTC）：RFC 3339、"YYYY-MM-DD HH:MM:SS" 或 "YYYY-MM-DD"
+pub fn parse_timestamp(s: &str) -> Result<DateTime<Utc>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(s) {
+        return Ok(at.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(at) = NaiveDateTime::parse_from_str(s, format) {
+            return Ok(DateTime::<Utc>::from_naive_utc_and_offset(at, Utc));
+        }
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|at| DateTime::<Utc>::from_naive_utc_and_offset(at, Utc))
+        .ok_or_else(|| anyhow!("Invalid timestamp '{}' (expected RFC 3339 or YYYY-MM-DD[ HH:MM:SS])", s))
+}
+
+/// 回放统计
+#[derive(Debug, Clone, Default)]
+pub struct ReplaySummary {
+    pub updates: u64,
+    pub pools: usize,
+    pub first_update: Option<DateTime<Utc>>,
+    pub last_update: Option<DateTime<Utc>>,
+    /// 回放耗时（墙钟）
+    pub elapsed: Duration,
+    /// 收到关闭信号提前结束
+    pub interrupted: bool,
+}
+
+/// 回放驱动：更新写回 PriceCache，价格变化事件发给 Coordinator
+pub struct Replayer {
+    price_cache: Arc<PriceCache>,
+    event_tx: mpsc::Sender<PriceChangeEvent>,
+    speed: ReplaySpeed,
+    /// 与 WebSocket 路径相同：按交易对记录上次显著变化时的价格
+    last_prices: HashMap<String, f64>,
+    /// 显著变化阈值（%），对应 [logging] price_change_threshold_percent
+    price_change_threshold: f64,
+    /// 最近一次应用的更新的 recorded_at（unix 毫秒，Calculator 标记机会的市场时间）
+    market_clock: Arc<AtomicI64>,
+}
+
+impl Replayer {
+    pub fn new(
+        price_cache: Arc<PriceCache>,
+        event_tx: mpsc::Sender<PriceChangeEvent>,
+        speed: ReplaySpeed,
+        price_change_threshold: f64,
+    ) -> Self {
+        Self {
+            price_cache,
+            event_tx,
+            speed,
+            last_prices: HashMap::new(),
+            price_change_threshold,
+            market_clock: Arc::new(AtomicI64::new(0)),
+        }
+    }
+
+    /// 使用外部创建的市场时钟（Calculator 先于 Replayer 创建时）
+    pub fn with_market_clock(mut self, clock: Arc<AtomicI64>) -> Self {
+        self.market_clock = clock;
+        self
+    }
+
+    /// 当前回放到的市场时间（Calculator 读取）
+    pub fn market_clock(&self) -> Arc<AtomicI64> {
+        self.market_clock.clone()
+    }
+
+    /// 读取市场时间
+    pub fn market_time(clock: &AtomicI64) -> Option<DateTime<Utc>> {
+        match clock.load(Ordering::Relaxed) {
+            0 => None,
+            millis => Utc.timestamp_millis_opt(millis).single(),
+        }
+    }
+
+    /// 应用一条更新：写缓存 + 发事件。Coordinator 已退出时返回 false
+    async fn apply(&mut self, update: &PoolUpdateRecord) -> bool {
+        let price = update.price;
+        let last_price = self.last_prices.get(&update.pair).copied();
+        let price_change_percent = PriceChangeEvent::change_fraction(last_price, price);
+
+        self.price_cache.update_price(update.to_pool_price());
+        self.market_clock.store(update.recorded_at.timestamp_millis(), Ordering::Relaxed);
+
+        let significant = match last_price {
+            Some(last) if last != 0.0 && price != 0.0 => {
+                ((price - last) / last * 100.0).abs() >= self.price_change_threshold
+            }
+            Some(last) => last != price,
+            None => true,
+        };
+        if significant {
+            self.last_prices.insert(update.pair.clone(), price);
+        }
+
+        let event = PriceChangeEvent {
+            pool_id: update.pool_address.clone(),
+            pool_name: update.pair.clone(),
+            pair: update.pair.clone(),
+            price_change_percent,
+            old_price: if price_change_percent > 0.0 { Some(last_price.unwrap_or(0.0)) } else { None },
+            new_price: price,
+            timestamp: Instant::now(),
+            ingest: None,
+        };
+        // 回放不丢事件：channel 满时等待 Coordinator 消费
+        self.event_tx.send(event).await.is_ok()
+    }
+
+    /// 读完数据源或收到关闭信号时返回
+    pub async fn run<S: PoolUpdateSource>(
+        &mut self,
+        source: &mut S,
+        shutdown: &mut broadcast::Receiver<()>,
+    ) -> Result<ReplaySummary> {
+        let started = tokio::time::Instant::now();
+        let mut summary = ReplaySummary::default();
+        let mut pools: HashSet<String> = HashSet::new();
+
+        'replay: loop {
+            let batch = source.next_batch().await?;
+            if batch.is_empty() {
+                break;
+            }
+            debug!("🎞️ Replaying batch of {} updates", batch.len());
+
+            for update in batch {
+                let first = *summary.first_update.get_or_insert(update.recorded_at);
+                if let Some(offset) = self.speed.wall_offset(update.recorded_at - first) {
+                    tokio::select! {
+                        _ = tokio::time::sleep_until(started + offset) => {}
+                        _ = shutdown.recv() => {
+                            summary.interrupted = true;
+                            break 'replay;
+                        }
+                    }
+                } else if shutdown.try_recv().is_ok() {
+                    summary.interrupted = true;
+                    break 'replay;
+                }
+
+                if !self.apply(&update).await {
+                    info!("🎞️ Coordinator stopped, ending replay");
+                    break 'replay;
+                }
+                pools.insert(update.pool_address);
+                summary.updates += 1;
+                summary.last_update = Some(update.recorded_at);
+            }
+        }
+
+        summary.pools = pools.len();
+        summary.elapsed = started.elapsed();
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(pool: &str, pair: &str, secs: i64, price: f64, slot: u64) -> PoolUpdateRecord {
+        PoolUpdateRecord {
+            recorded_at: Utc.timestamp_opt(1_700_000_000 + secs, 0).unwrap(),
+            pool_address: pool.to_string(),
+            pair: pair.to_string(),
+            dex_name: "Raydium AMM V4".to_string(),
+            price,
+            base_reserve: 1_000_000_000,
+            quote_reserve: (price * 1_000_000.0) as u64,
+            base_decimals: 9,
+            quote_decimals: 6,
+            slot,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_feeds_cache_and_coordinator_in_order() {
+        let cache = Arc::new(PriceCache::new());
+        let (event_tx, mut event_rx) = mpsc::channel(16);
+        let (_shutdown_tx, mut shutdown_rx) = broadcast::channel(1);
+        let mut replayer = Replayer::new(cache.clone(), event_tx, ReplaySpeed::Max, 1.0);
+        let clock = replayer.market_clock();
+
+        // 乱序传入，按 recorded_at 回放
+        let mut source = MemoryUpdateSource::new(vec![
+            update("pool-a", "SOL/USDC", 2, 186.0, 102),
+            update("pool-a", "SOL/USDC", 0, 185.0, 100),
+            update("pool-b", "SOL/USDT", 1, 185.1, 101),
+        ]);
+        let summary = replayer.run(&mut source, &mut shutdown_rx).await.unwrap();
+
+        assert_eq!(summary.updates, 3);
+        assert_eq!(summary.pools, 2);
+        assert!(!summary.interrupted);
+        assert_eq!(Replayer::market_time(&clock), summary.last_update);
+
+        let cached = cache.get_price("pool-a").unwrap();
+        assert_eq!(cached.price, 186.0);
+        assert_eq!(cached.slot, 102);
+
+        let events: Vec<PriceChangeEvent> = (0..3).map(|_| event_rx.try_recv().unwrap()).collect();
+        assert_eq!(events[0].new_price, 185.0);
+        assert_eq!(events[0].price_change_percent, 0.01); // 首次更新
+        assert_eq!(events[1].pool_id, "pool-b");
+        assert!((events[2].price_change_percent - 1.0 / 185.0).abs() < 1e-9);
+        assert_eq!(events[2].old_price, Some(185.0));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scaled_replay_paces_by_recorded_time() {
+        let cache = Arc::new(PriceCache::new());
+        let (event_tx, _event_rx) = mpsc::channel(16);
+        let (_shutdown_tx, mut shutdown_rx) = broadcast::channel(1);
+        let mut replayer = Replayer::new(cache, event_tx, ReplaySpeed::Scaled(10.0), 1.0);
+
+        // 市场时间跨度 60 秒，10 倍速 -> 6 秒
+        let mut source = MemoryUpdateSource::new(vec![
+            update("pool-a", "SOL/USDC", 0, 185.0, 1),
+            update("pool-a", "SOL/USDC", 60, 185.5, 2),
+        ]);
+        let summary = replayer.run(&mut source, &mut shutdown_rx).await.unwrap();
+        assert_eq!(summary.updates, 2);
+        assert!(summary.elapsed >= Duration::from_secs(6) && summary.elapsed < Duration::from_secs(7));
+    }
+
+    #[test]
+    fn test_parse_speed_and_timestamps() {
+        assert_eq!(ReplaySpeed::parse("max").unwrap(), ReplaySpeed::Max);
+        assert_eq!(ReplaySpeed::parse("10x").unwrap(), ReplaySpeed::Scaled(10.0));
+        assert!(ReplaySpeed::parse("0").is_err());
+
+        let expected = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        assert_eq!(parse_timestamp("2023-11-14T22:13:20Z").unwrap(), expected);
+        assert_eq!(parse_timestamp("2023-11-14 22:13:20").unwrap(), expected);
+        assert_eq!(
+            parse_timestamp("2023-11-14").unwrap(),
+            Utc.timestamp_opt(1_699_920_000, 0).unwrap()
+        );
+        assert!(parse_timestamp("yesterday").is_err());
+    }
+}
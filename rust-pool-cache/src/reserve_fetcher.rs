@@ -1,6 +1,8 @@
-///! 储备金获取模块
-///! 
-///! 为Meteora DLMM等需要从外部vault账户读取储备金的DEX提供支持
+/*!
+ * 储备金获取模块
+ *
+ * 为Meteora DLMM等需要从外部vault账户读取储备金的DEX提供支持
+ */
 
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
@@ -8,13 +8,23 @@
  * 3. 多跳套利（Multi-hop Arbitrage）- 通过多个中间代币的复杂路径
  */
 
+use crate::execution_cost::ExecutionCostModel;
+use crate::interning::{PairId, TokenId, TokenRegistry};
 use crate::price_cache::{PoolPrice, PriceCache};
+use crate::pool_mints;
+use crate::token_graph::{pair_key, pool_tokens, raw_token};
+use crate::vault_reader::VaultReader;
+use serde::{Serialize, Serializer};
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt;
 use std::sync::Arc;
 use std::time::Instant;
+use tracing::debug;
 
 /// 套利路径类型
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ArbitrageType {
     /// 直接套利：同一交易对在不同DEX之间
     Direct,
@@ -25,7 +35,7 @@ pub enum ArbitrageType {
 }
 
 /// 路由步骤
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct RouteStep {
     /// 池子ID
     pub pool_id: String,
@@ -45,10 +55,43 @@ pub struct RouteStep {
     pub expected_input: f64,
     /// 预期输出金额
     pub expected_output: f64,
+    /// 本跳价格冲击（%，已扣除手续费，相对池子现价）
+    pub price_impact_percent: f64,
+    /// 本跳实际手续费率（基点）
+    pub effective_fee_bps: f64,
+    /// 池子上的原始输入代币，仅在与 input_token（规范符号）不同时记录，例如 wSOL
+    ///
+    /// 执行器据此判断这一跳前是否需要 wrap SOL。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_input_token: Option<String>,
+    /// 池子上的原始输出代币（同上，例如输出 wSOL 时可能需要 unwrap）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_output_token: Option<String>,
+}
+
+/// 🔥 单跳价格冲击（%）
+///
+/// 以池子现价 `reserve_out / reserve_in` 为基准，扣除手续费后的理想输出与实际输出之差。
+/// 储备或金额无效时返回 0（无法评估，不视为冲击）。
+pub fn hop_price_impact_percent(
+    amount_in: f64,
+    amount_out: f64,
+    reserve_in: f64,
+    reserve_out: f64,
+    fee_rate: f64,
+) -> f64 {
+    if amount_in <= 0.0 || reserve_in <= 0.0 || reserve_out <= 0.0 {
+        return 0.0;
+    }
+    let ideal_out = amount_in * (1.0 - fee_rate) * (reserve_out / reserve_in);
+    if ideal_out <= 0.0 || !amount_out.is_finite() {
+        return 0.0;
+    }
+    ((1.0 - amount_out / ideal_out) * 100.0).max(0.0)
 }
 
 /// 完整的套利路径
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ArbitragePath {
     /// 套利类型
     pub arb_type: ArbitrageType,
@@ -64,17 +107,128 @@ pub struct ArbitragePath {
     pub output_amount: f64,
     /// 毛利润
     pub gross_profit: f64,
-    /// 估算的总费用（包括swap费用、gas等）
+    /// DEX 交易手续费（起始代币计，已体现在兑换输出中）
+    pub dex_fees: f64,
+    /// 执行成本：签名费 + 优先费 + Jito 小费（起始代币计，见 execution_cost）
+    pub execution_fees: f64,
+    /// 估算的总费用（dex_fees + execution_fees）
     pub estimated_fees: f64,
     /// 净利润
     pub net_profit: f64,
     /// ROI百分比
     pub roi_percent: f64,
-    /// 发现时间
+    /// 发现时间（序列化为距今毫秒数 `discovered_age_ms`）
     #[allow(dead_code)]
+    #[serde(rename = "discovered_age_ms", serialize_with = "serialize_age_ms")]
     pub discovered_at: Instant,
 }
 
+/// 路径签名：池子ID序列的借用视图
+///
+/// 比较、排序、哈希与文本形式 "p1->p2" 完全一致（按同样的字节序列进行），
+/// 但不分配字符串。
+#[derive(Clone, Copy)]
+pub struct PathSignature<'a>(&'a [RouteStep]);
+
+impl PathSignature<'_> {
+    /// 文本形式的字节序列（池子ID 之间以 "->" 分隔）
+    pub(crate) fn bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.0.iter().enumerate().flat_map(|(i, step)| {
+            let separator: &[u8] = if i == 0 { b"" } else { b"->" };
+            separator.iter().chain(step.pool_id.as_bytes()).copied()
+        })
+    }
+
+    /// 签名的 64 位键（FNV-1a，跨进程稳定），替代拼接字符串做 HashMap / HashSet 的键
+    pub fn key(&self) -> u64 {
+        fnv1a(FNV_OFFSET_BASIS, self.bytes())
+    }
+
+    /// 文本形式签名（例如扫描事件里保存的 "p1->p2"）的键，与 `key()` 一致
+    pub fn key_of(text: &str) -> u64 {
+        fnv1a(FNV_OFFSET_BASIS, text.bytes())
+    }
+}
+
+/// FNV-1a 初始值
+pub(crate) const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// FNV-1a：在 `hash` 上继续哈希 `bytes`（可以分段喂入，结果与一次喂完相同）
+pub(crate) fn fnv1a(mut hash: u64, bytes: impl IntoIterator<Item = u8>) -> u64 {
+    for byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+impl fmt::Display for PathSignature<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, step) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("->")?;
+            }
+            f.write_str(&step.pool_id)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for PathSignature<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\"", self)
+    }
+}
+
+impl PartialEq for PathSignature<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes().eq(other.bytes())
+    }
+}
+
+impl Eq for PathSignature<'_> {}
+
+impl PartialEq<str> for PathSignature<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.bytes().eq(other.bytes())
+    }
+}
+
+impl PartialEq<&str> for PathSignature<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl PartialEq<String> for PathSignature<'_> {
+    fn eq(&self, other: &String) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialOrd for PathSignature<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PathSignature<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.bytes().cmp(other.bytes())
+    }
+}
+
+impl std::hash::Hash for PathSignature<'_> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        state.write_u64(self.key());
+    }
+}
+
+/// Instant 没有绝对时间，序列化为距今的毫秒数
+fn serialize_age_ms<S: Serializer>(instant: &Instant, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(instant.elapsed().as_millis() as u64)
+}
+
 impl ArbitragePath {
     /// 计算路径的有效性分数（用于排序）
     pub fn score(&self) -> f64 {
@@ -87,6 +241,43 @@ impl ArbitragePath {
         profit_score * 0.6 + roi_score * 0.3 + complexity_penalty * 0.1
     }
     
+    /// 所有跳中最大的价格冲击（%），验证器据此拒绝穿过薄池的路径
+    pub fn worst_hop_impact(&self) -> f64 {
+        self.steps.iter()
+            .map(|s| s.price_impact_percent)
+            .fold(0.0, f64::max)
+    }
+
+    /// 路径签名（池子ID序列），用于去重和跨扫描追踪同一条路径
+    ///
+    /// 借用 `steps`，不拼接字符串；需要文本时（日志 / 数据库 / API）用 `Display`，
+    /// 需要跨扫描保存的键用 `PathSignature::key`。
+    pub fn signature(&self) -> PathSignature<'_> {
+        PathSignature(&self.steps)
+    }
+
+    /// 机会指纹：路径签名 + 方向（代币序列）的 FNV-1a 哈希，16 位十六进制
+    ///
+    /// 不依赖 std 的随机哈希种子，跨进程重启稳定，用作数据库中机会生命周期的键。
+    /// 计算逻辑在 `OpportunityMerger::fingerprint`（去重、扫描历史与持久化共用）。
+    pub fn fingerprint(&self) -> String {
+        crate::opportunity_merger::OpportunityMerger::fingerprint(self)
+    }
+
+    /// 🎯 确定性排序：ROI降序 → 跳数升序 → 签名字典序
+    ///
+    /// 同一份数据多次扫描输出顺序完全一致（去重时保留的也是同一条）
+    pub fn deterministic_cmp(a: &ArbitragePath, b: &ArbitragePath) -> Ordering {
+        b.roi_percent.total_cmp(&a.roi_percent)
+            .then_with(|| Self::tie_break(a, b))
+    }
+
+    /// 主排序键相同时的平局决胜：跳数升序 → 签名字典序
+    pub fn tie_break(a: &ArbitragePath, b: &ArbitragePath) -> Ordering {
+        a.steps.len().cmp(&b.steps.len())
+            .then_with(|| a.signature().cmp(&b.signature()))
+    }
+
     /// 检查路径是否有效
     pub fn is_valid(&self) -> bool {
         // 必须是循环（起始=结束）
@@ -111,6 +302,97 @@ impl ArbitragePath {
         
         true
     }
+
+    /// 🔁 路径中第一处重复使用的池子
+    ///
+    /// 同一个 pool_id 出现两次（第一跳成交后价格已经变了，后面那一跳按旧价格计算不成立），
+    /// 或者两个池子共用同一个 vault 账户（包装池，按 VaultReader 登记的 vault 判断）。
+    pub fn pool_reuse(&self, vaults: Option<&VaultReader>) -> Option<PoolReuse> {
+        for (second_hop, step) in self.steps.iter().enumerate() {
+            for (first_hop, earlier) in self.steps[..second_hop].iter().enumerate() {
+                let shared_vault = if earlier.pool_id == step.pool_id {
+                    None
+                } else {
+                    match vaults.and_then(|v| shared_vault(v, &earlier.pool_id, &step.pool_id)) {
+                        Some(vault) => Some(vault),
+                        None => continue,
+                    }
+                };
+                return Some(PoolReuse {
+                    pool_id: earlier.pool_id.clone(),
+                    reused_by: step.pool_id.clone(),
+                    first_hop,
+                    second_hop,
+                    shared_vault,
+                });
+            }
+        }
+        None
+    }
+}
+
+/// 🔁 路径重复使用池子的位置（拒绝原因）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PoolReuse {
+    pub pool_id: String,
+    /// 第二次使用的池子（同一池子时与 pool_id 相同）
+    pub reused_by: String,
+    pub first_hop: usize,
+    pub second_hop: usize,
+    /// 两个不同池子共用的 vault 账户
+    pub shared_vault: Option<String>,
+}
+
+impl fmt::Display for PoolReuse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.shared_vault {
+            Some(vault) => write!(
+                f,
+                "pools {} (hop {}) and {} (hop {}) share vault {}",
+                self.pool_id, self.first_hop, self.reused_by, self.second_hop, vault
+            ),
+            None => write!(f, "pool {} used at hops {} and {}", self.pool_id, self.first_hop, self.second_hop),
+        }
+    }
+}
+
+/// 🔁 扫描器输出前的池子重复检查（BFS / Bellman-Ford 共用）
+#[derive(Clone, Default)]
+pub struct PoolReusePolicy {
+    /// [router] allow_pool_reuse：为 true 时不检查
+    pub allow: bool,
+    /// 识别共用 vault 的包装池（未接入时只比较 pool_id）
+    pub vault_reader: Option<Arc<VaultReader>>,
+}
+
+impl PoolReusePolicy {
+    /// 路径是否因重复使用池子被丢弃（丢弃时记录 debug 日志）
+    pub fn rejects(&self, path: &ArbitragePath, scanner: &str) -> bool {
+        if self.allow {
+            return false;
+        }
+        match path.pool_reuse(self.vault_reader.as_deref()) {
+            Some(reuse) => {
+                debug!("{}: dropping cycle {} ({})", scanner, path.signature(), reuse);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// 两个池子共用的 vault（任一池子没有登记 vault 时为 None）
+fn shared_vault(vaults: &VaultReader, pool_a: &str, pool_b: &str) -> Option<String> {
+    let (a0, a1) = vaults.get_pool_vault_addresses(pool_a)?;
+    let (b0, b1) = vaults.get_pool_vault_addresses(pool_b)?;
+    [a0, a1].into_iter().find(|vault| *vault == b0 || *vault == b1)
+}
+
+/// 三角套利用的代币图：代币驻留为 `TokenId`，出边按 TokenId 下标存放
+struct TriangleGraph {
+    registry: TokenRegistry,
+    /// (目标代币, 池子)；反向边的池子价格已取倒数
+    adjacency: Vec<Vec<(TokenId, PoolPrice)>>,
 }
 
 /// 智能路由器
@@ -123,38 +405,27 @@ pub struct Router {
     /// 最大路径深度
     #[allow(dead_code)]
     max_depth: usize,
-    /// DEX手续费配置
-    dex_fees: HashMap<String, f64>,
+    /// ⛽ 执行成本模型
+    execution_cost: Arc<ExecutionCostModel>,
 }
 
 impl Router {
     /// 创建新的路由器
     pub fn new(price_cache: Arc<PriceCache>) -> Self {
-        let mut dex_fees = HashMap::new();
-        
-        // 配置各个DEX的手续费
-        dex_fees.insert("Raydium AMM V4".to_string(), 0.0025);      // 0.25%
-        dex_fees.insert("Raydium CLMM".to_string(), 0.0001);        // 0.01% (可变)
-        dex_fees.insert("Orca Whirlpool".to_string(), 0.0001);      // 0.01% (可变)
-        dex_fees.insert("Meteora DLMM".to_string(), 0.0002);        // 0.02% (可变)
-        dex_fees.insert("SolFi V2".to_string(), 0.0030);            // 0.30% (保守估计)
-        dex_fees.insert("AlphaQ".to_string(), 0.0001);              // 0.01% (稳定币专用)
-        dex_fees.insert("HumidiFi".to_string(), 0.0010);            // 0.10%
-        dex_fees.insert("Lifinity V2".to_string(), 0.0000);         // 动态定价
-        dex_fees.insert("GoonFi".to_string(), 0.0025);              // 0.25% (估计)
-        dex_fees.insert("TesseraV".to_string(), 0.0020);            // 0.20% (估计)
-        dex_fees.insert("Stabble".to_string(), 0.0004);             // 0.04% (稳定币)
-        dex_fees.insert("Whirlpool".to_string(), 0.0001);           // 0.01%
-        dex_fees.insert("PancakeSwap".to_string(), 0.0025);         // 0.25%
-        
         Self {
             price_cache,
             min_roi_percent: 0.3, // 最小30%的ROI
             max_depth: 4,          // 最多4跳
-            dex_fees,
+            execution_cost: ExecutionCostModel::shared_default(),
         }
     }
     
+    /// ⛽ 设置执行成本模型（app 启动时创建、后台刷新优先费和汇率的那一份）
+    pub fn with_execution_cost(mut self, execution_cost: Arc<ExecutionCostModel>) -> Self {
+        self.execution_cost = execution_cost;
+        self
+    }
+    
     /// 设置最小ROI阈值
     #[allow(dead_code)]
     pub fn set_min_roi(&mut self, min_roi_percent: f64) {
@@ -167,12 +438,16 @@ impl Router {
         self.max_depth = max_depth;
     }
     
-    /// 🔥 核心方法：寻找所有套利机会
+    /// 🔥 核心方法：寻找所有套利机会（缓存中未被熔断隔离的全部池子）
     pub fn find_all_opportunities(&self, initial_amount: f64) -> Vec<ArbitragePath> {
+        self.find_opportunities_in(&self.price_cache.get_routable_prices(), initial_amount)  // 🧯 排除熔断隔离的池子
+    }
+
+    /// 在给定的池子快照上寻找套利机会（AdvancedRouter 传入一致性快照，🧊 离线重放传入抓取文件）
+    pub fn find_opportunities_in(&self, all_prices: &[PoolPrice], initial_amount: f64) -> Vec<ArbitragePath> {
         let mut all_paths = Vec::new();
 
         // 🔥 数据质量监控 - 调试日志
-        let all_prices = self.price_cache.get_all_prices();
         println!("📊 [路由调试] 缓存数据质量报告:");
         println!("   - 总池子数量: {}", all_prices.len());
 
@@ -180,7 +455,7 @@ impl Router {
         let mut pair_count: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
         let mut dex_count: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
 
-        for price in &all_prices {
+        for price in all_prices {
             *pair_count.entry(price.pair.clone()).or_insert(0) += 1;
             *dex_count.entry(price.dex_name.clone()).or_insert(0) += 1;
         }
@@ -214,7 +489,7 @@ impl Router {
 
         // 1. 寻找直接套利机会（最简单，最快）
         let direct_start = std::time::Instant::now();
-        let direct_paths = self.find_direct_arbitrage(initial_amount);
+        let direct_paths = self.find_direct_arbitrage(all_prices, initial_amount);
         println!("   ⏱️  直接套利扫描完成: {} 条路径 (耗时: {:?})", direct_paths.len(), direct_start.elapsed());
 
         // 🔍 显示直接套利的详细路径
@@ -230,7 +505,7 @@ impl Router {
 
         // 2. 寻找三角套利机会
         let triangle_start = std::time::Instant::now();
-        let triangle_paths = self.find_triangle_arbitrage(initial_amount);
+        let triangle_paths = self.find_triangle_arbitrage(all_prices, initial_amount);
         println!("   ⏱️  三角套利扫描完成: {} 条路径 (耗时: {:?})", triangle_paths.len(), triangle_start.elapsed());
 
         // 🔍 显示三角套利的详细路径
@@ -252,27 +527,36 @@ impl Router {
         all_paths.retain(|p| p.is_valid());
         
         // 按得分排序
-        all_paths.sort_by(|a, b| b.score().partial_cmp(&a.score()).unwrap());
+        all_paths.sort_by(|a, b| {
+            b.score().total_cmp(&a.score()).then_with(|| ArbitragePath::tie_break(a, b))
+        });
         
         all_paths
     }
     
     /// 策略1：直接套利
     /// 寻找同一交易对在不同DEX之间的价差
-    fn find_direct_arbitrage(&self, initial_amount: f64) -> Vec<ArbitragePath> {
+    fn find_direct_arbitrage(&self, prices: &[PoolPrice], initial_amount: f64) -> Vec<ArbitragePath> {
         let mut paths = Vec::new();
-        let all_prices = self.price_cache.get_all_prices();
+        let mut all_prices = prices.to_vec();
+        all_prices.sort_by(|a, b| a.pool_id.cmp(&b.pool_id));  // 🎯 确定性顺序
         
-        // 按交易对分组
-        let mut pairs_map: HashMap<String, Vec<PoolPrice>> = HashMap::new();
+        // 按交易对分组（规范符号：wSOL/USDC 与 SOL/USDC 同组），交易对驻留为 PairId
+        // 解析不出代币的池子建不出路径，直接跳过
+        let mut registry = TokenRegistry::new();
+        let mut pairs_map: HashMap<PairId, Vec<PoolPrice>> = HashMap::new();
         for price in all_prices {
-            pairs_map.entry(price.pair.clone())
-                .or_insert_with(Vec::new)
+            let Some((base, quote)) = pool_tokens(&price) else { continue };
+            let pair = PairId::new(registry.intern(&base), registry.intern(&quote));
+            pairs_map.entry(pair)
+                .or_default()
                 .push(price);
         }
         
-        // 检查每个交易对
-        for (_pair, pools) in pairs_map.iter() {
+        // 检查每个交易对（按交易对名称顺序）
+        let mut pairs: Vec<_> = pairs_map.iter().collect();
+        pairs.sort_by_key(|(pair, _)| (registry.symbol(pair.base), registry.symbol(pair.quote)));
+        for (_pair, pools) in pairs {
             if pools.len() < 2 {
                 continue;
             }
@@ -315,14 +599,10 @@ impl Router {
             return None; // 价差太小
         }
         
-        // 解析交易对（例如 "SOL/USDC" -> base=SOL, quote=USDC）
-        let tokens: Vec<&str> = buy_pool.pair.split('/').collect();
-        if tokens.len() != 2 {
-            return None;
-        }
-        
-        let base_token = tokens[0];
-        let quote_token = tokens[1];
+        // 解析交易对（例如 "SOL/USDC" -> base=SOL, quote=USDC；配置了 mint 时按 mint 判断）
+        let (base, quote) = pool_tokens(buy_pool)?;
+        let (base_token, quote_token) = (base.as_str(), quote.as_str());
+        let pair = format!("{}/{}", base_token, quote_token);
         
         // 路径：quote → base (买入) → quote (卖出)
         // 例如：USDC → SOL → USDC
@@ -331,7 +611,7 @@ impl Router {
         use crate::dex_interface::amm_calculator;
         
         // 步骤1：在低价池买入 base_token
-        let fee1 = self.get_dex_fee(&buy_pool.dex_name);
+        let fee1 = self.get_pool_fee(buy_pool);
         
         // 转换储备量为浮点数
         let (base_decimals, quote_decimals) = buy_pool.get_decimals();
@@ -339,7 +619,10 @@ impl Router {
         let buy_quote_reserve = buy_pool.quote_reserve as f64 / 10f64.powi(quote_decimals as i32);
         
         // 使用AMM公式：quote → base
-        let base_amount = amm_calculator::calculate_amm_output_f64(
+        let base_amount = amm_calculator::calculate_hop_output_f64(
+            &buy_pool.pool_id,
+            &pair,
+            quote_token,
             initial_amount,
             buy_quote_reserve,  // reserve_in (USDC)
             buy_base_reserve,   // reserve_out (SOL)
@@ -356,10 +639,16 @@ impl Router {
             liquidity_quote: buy_pool.quote_reserve,
             expected_input: initial_amount,
             expected_output: base_amount,
+            price_impact_percent: hop_price_impact_percent(
+                initial_amount, base_amount, buy_quote_reserve, buy_base_reserve, fee1,
+            ),
+            effective_fee_bps: fee1 * 10_000.0,
+            raw_input_token: raw_token(buy_pool, quote_token),
+            raw_output_token: raw_token(buy_pool, base_token),
         };
         
         // 步骤2：在高价池卖出 base_token
-        let fee2 = self.get_dex_fee(&sell_pool.dex_name);
+        let fee2 = self.get_pool_fee(sell_pool);
         
         // 转换储备量为浮点数
         let (sell_base_decimals, sell_quote_decimals) = sell_pool.get_decimals();
@@ -367,7 +656,10 @@ impl Router {
         let sell_quote_reserve = sell_pool.quote_reserve as f64 / 10f64.powi(sell_quote_decimals as i32);
         
         // 使用AMM公式：base → quote
-        let final_amount = amm_calculator::calculate_amm_output_f64(
+        let final_amount = amm_calculator::calculate_hop_output_f64(
+            &sell_pool.pool_id,
+            &pair,
+            base_token,
             base_amount,
             sell_base_reserve,  // reserve_in (SOL)
             sell_quote_reserve, // reserve_out (USDC)
@@ -384,13 +676,21 @@ impl Router {
             liquidity_quote: sell_pool.quote_reserve,
             expected_input: base_amount,
             expected_output: final_amount,
+            price_impact_percent: hop_price_impact_percent(
+                base_amount, final_amount, sell_base_reserve, sell_quote_reserve, fee2,
+            ),
+            effective_fee_bps: fee2 * 10_000.0,
+            raw_input_token: raw_token(sell_pool, base_token),
+            raw_output_token: raw_token(sell_pool, quote_token),
         };
         
         // 计算利润
         let gross_profit = final_amount - initial_amount;
-        let total_fees = initial_amount * (fee1 + fee2);
-        let gas_estimate = 0.0001; // Solana上约0.0001 SOL的gas费（需转换为quote代币）
-        let net_profit = gross_profit - gas_estimate;
+        let dex_fees = initial_amount * (fee1 + fee2);
+        let steps = vec![step1, step2];
+        // 起始代币无法换算成 SOL 时路径无法定价，不上报
+        let execution_fees = self.execution_cost.cost_in_token(&steps, quote_token)?;
+        let net_profit = gross_profit - execution_fees;
         let roi_percent = (net_profit / initial_amount) * 100.0;
         
         // 检查是否满足最小ROI
@@ -400,13 +700,15 @@ impl Router {
         
         Some(ArbitragePath {
             arb_type: ArbitrageType::Direct,
-            steps: vec![step1, step2],
+            steps,
             start_token: quote_token.to_string(),
             end_token: quote_token.to_string(),
             input_amount: initial_amount,
             output_amount: final_amount,
             gross_profit,
-            estimated_fees: total_fees + gas_estimate,
+            dex_fees,
+            execution_fees,
+            estimated_fees: dex_fees + execution_fees,
             net_profit,
             roi_percent,
             discovered_at: Instant::now(),
@@ -415,14 +717,14 @@ impl Router {
     
     /// 策略2：三角套利
     /// 寻找 A→B→C→A 的循环路径
-    fn find_triangle_arbitrage(&self, initial_amount: f64) -> Vec<ArbitragePath> {
+    fn find_triangle_arbitrage(&self, prices: &[PoolPrice], initial_amount: f64) -> Vec<ArbitragePath> {
         let mut paths = Vec::new();
         
         // 构建代币图
-        let token_graph = self.build_token_graph();
+        let token_graph = self.build_token_graph(prices);
         
-        // 对每个代币作为起点
-        for start_token in token_graph.keys() {
+        // 对每个代币作为起点（按代币名称顺序）
+        for start_token in token_graph.registry.sorted_ids() {
             // 寻找从该代币出发的三角套利
             let triangle_paths = self.find_triangles_from_token(
                 start_token,
@@ -439,32 +741,29 @@ impl Router {
     /// 
     /// 🔥 优化：保留同一交易对的所有池子，不去重
     /// 这样可以在三角套利中尝试所有可能的池子组合，避免遗漏5-10%的机会
-    fn build_token_graph(&self) -> HashMap<String, Vec<(String, PoolPrice)>> {
-        let mut graph: HashMap<String, Vec<(String, PoolPrice)>> = HashMap::new();
-        let all_prices = self.price_cache.get_all_prices();
+    fn build_token_graph(&self, prices: &[PoolPrice]) -> TriangleGraph {
+        let mut graph = TriangleGraph { registry: TokenRegistry::new(), adjacency: Vec::new() };
+        let mut all_prices = prices.to_vec();
+        all_prices.sort_by(|a, b| a.pool_id.cmp(&b.pool_id));  // 🎯 邻接表顺序确定
         
         for pool in all_prices {
-            let tokens: Vec<&str> = pool.pair.split('/').collect();
-            if tokens.len() != 2 {
-                continue;
-            }
-            
-            let base = tokens[0].to_string();
-            let quote = tokens[1].to_string();
+            let (base, quote) = match pool_tokens(&pool) {
+                Some(tokens) => tokens,
+                None => continue,
+            };
+            let base = graph.registry.intern(&base);
+            let quote = graph.registry.intern(&quote);
+            graph.adjacency.resize_with(graph.registry.len(), Vec::new);
             
             // 🔥 添加正向边：quote → base
             // 每个池子都单独添加，即使同一交易对有多个池子
-            graph.entry(quote.clone())
-                .or_insert_with(Vec::new)
-                .push((base.clone(), pool.clone()));
+            graph.adjacency[quote.index()].push((base, pool.clone()));
             
             // 🔥 添加反向边：base → quote
             // 同样保留所有池子
-            let mut reverse_pool = pool.clone();
-            reverse_pool.price = 1.0 / pool.price;
-            graph.entry(base)
-                .or_insert_with(Vec::new)
-                .push((quote, reverse_pool));
+            let mut reverse_pool = pool;
+            reverse_pool.price = 1.0 / reverse_pool.price;
+            graph.adjacency[base.index()].push((quote, reverse_pool));
         }
         
         graph
@@ -473,50 +772,41 @@ impl Router {
     /// 从指定代币寻找三角套利路径
     fn find_triangles_from_token(
         &self,
-        start_token: &str,
-        graph: &HashMap<String, Vec<(String, PoolPrice)>>,
+        start_token: TokenId,
+        graph: &TriangleGraph,
         initial_amount: f64,
     ) -> Vec<ArbitragePath> {
         let mut paths = Vec::new();
         
         // 获取从起始代币出发的所有可能第一步
-        let first_hops = match graph.get(start_token) {
-            Some(hops) => hops,
-            None => return paths,
-        };
+        let first_hops = &graph.adjacency[start_token.index()];
         
         // 尝试每个第一步
         for (token_b, pool_ab) in first_hops {
             // 获取从token_b出发的第二步
-            let second_hops = match graph.get(token_b) {
-                Some(hops) => hops,
-                None => continue,
-            };
+            let second_hops = &graph.adjacency[token_b.index()];
             
             // 尝试每个第二步
             for (token_c, pool_bc) in second_hops {
                 // 跳过回到起点的情况（这是第一步的反向）
-                if token_c == start_token {
+                if *token_c == start_token {
                     continue;
                 }
                 
                 // 获取从token_c回到起点的第三步
-                let third_hops = match graph.get(token_c) {
-                    Some(hops) => hops,
-                    None => continue,
-                };
+                let third_hops = &graph.adjacency[token_c.index()];
                 
                 // 查找回到起点的路径
                 for (token_end, pool_ca) in third_hops {
-                    if token_end != start_token {
+                    if *token_end != start_token {
                         continue;
                     }
                     
-                    // 找到了完整的三角：start → B → C → start
+                    // 找到了完整的三角：start → B → C → start（在这里解析回代币符号）
                     if let Some(path) = self.calculate_triangle_path(
-                        start_token,
-                        token_b,
-                        token_c,
+                        graph.registry.symbol(start_token),
+                        graph.registry.symbol(*token_b),
+                        graph.registry.symbol(*token_c),
                         pool_ab,
                         pool_bc,
                         pool_ca,
@@ -546,12 +836,15 @@ impl Router {
         use crate::dex_interface::amm_calculator;
         
         // 步骤1：A → B
-        let fee1 = self.get_dex_fee(&pool_ab.dex_name);
+        let fee1 = self.get_pool_fee(pool_ab);
         let (reserve_in_ab, reserve_out_ab) = self.get_directional_reserves_for_pair(
             pool_ab, token_a, token_b
         );
         
-        let amount_b = amm_calculator::calculate_amm_output_f64(
+        let amount_b = amm_calculator::calculate_hop_output_f64(
+            &pool_ab.pool_id,
+            &pair_key(pool_ab),
+            token_a,
             initial_amount,
             reserve_in_ab,
             reserve_out_ab,
@@ -568,15 +861,24 @@ impl Router {
             liquidity_quote: pool_ab.quote_reserve,
             expected_input: initial_amount,
             expected_output: amount_b,
+            price_impact_percent: hop_price_impact_percent(
+                initial_amount, amount_b, reserve_in_ab, reserve_out_ab, fee1,
+            ),
+            effective_fee_bps: fee1 * 10_000.0,
+            raw_input_token: raw_token(pool_ab, token_a),
+            raw_output_token: raw_token(pool_ab, token_b),
         };
         
         // 步骤2：B → C
-        let fee2 = self.get_dex_fee(&pool_bc.dex_name);
+        let fee2 = self.get_pool_fee(pool_bc);
         let (reserve_in_bc, reserve_out_bc) = self.get_directional_reserves_for_pair(
             pool_bc, token_b, token_c
         );
         
-        let amount_c = amm_calculator::calculate_amm_output_f64(
+        let amount_c = amm_calculator::calculate_hop_output_f64(
+            &pool_bc.pool_id,
+            &pair_key(pool_bc),
+            token_b,
             amount_b,
             reserve_in_bc,
             reserve_out_bc,
@@ -593,15 +895,24 @@ impl Router {
             liquidity_quote: pool_bc.quote_reserve,
             expected_input: amount_b,
             expected_output: amount_c,
+            price_impact_percent: hop_price_impact_percent(
+                amount_b, amount_c, reserve_in_bc, reserve_out_bc, fee2,
+            ),
+            effective_fee_bps: fee2 * 10_000.0,
+            raw_input_token: raw_token(pool_bc, token_b),
+            raw_output_token: raw_token(pool_bc, token_c),
         };
         
         // 步骤3：C → A
-        let fee3 = self.get_dex_fee(&pool_ca.dex_name);
+        let fee3 = self.get_pool_fee(pool_ca);
         let (reserve_in_ca, reserve_out_ca) = self.get_directional_reserves_for_pair(
             pool_ca, token_c, token_a
         );
         
-        let final_amount = amm_calculator::calculate_amm_output_f64(
+        let final_amount = amm_calculator::calculate_hop_output_f64(
+            &pool_ca.pool_id,
+            &pair_key(pool_ca),
+            token_c,
             amount_c,
             reserve_in_ca,
             reserve_out_ca,
@@ -618,13 +929,20 @@ impl Router {
             liquidity_quote: pool_ca.quote_reserve,
             expected_input: amount_c,
             expected_output: final_amount,
+            price_impact_percent: hop_price_impact_percent(
+                amount_c, final_amount, reserve_in_ca, reserve_out_ca, fee3,
+            ),
+            effective_fee_bps: fee3 * 10_000.0,
+            raw_input_token: raw_token(pool_ca, token_c),
+            raw_output_token: raw_token(pool_ca, token_a),
         };
         
         // 计算利润
         let gross_profit = final_amount - initial_amount;
-        let total_fees = initial_amount * (fee1 + fee2 + fee3);
-        let gas_estimate = 0.0002; // 3跳的gas费更高
-        let net_profit = gross_profit - gas_estimate;
+        let dex_fees = initial_amount * (fee1 + fee2 + fee3);
+        let steps = vec![step1, step2, step3];
+        let execution_fees = self.execution_cost.cost_in_token(&steps, token_a)?;
+        let net_profit = gross_profit - execution_fees;
         let roi_percent = (net_profit / initial_amount) * 100.0;
         
         // 检查是否满足最小ROI
@@ -634,22 +952,24 @@ impl Router {
         
         Some(ArbitragePath {
             arb_type: ArbitrageType::Triangle,
-            steps: vec![step1, step2, step3],
+            steps,
             start_token: token_a.to_string(),
             end_token: token_a.to_string(),
             input_amount: initial_amount,
             output_amount: final_amount,
             gross_profit,
-            estimated_fees: total_fees + gas_estimate,
+            dex_fees,
+            execution_fees,
+            estimated_fees: dex_fees + execution_fees,
             net_profit,
             roi_percent,
             discovered_at: Instant::now(),
         })
     }
     
-    /// 获取DEX的手续费率
-    fn get_dex_fee(&self, dex_name: &str) -> f64 {
-        *self.dex_fees.get(dex_name).unwrap_or(&0.003) // 默认0.3%
+    /// 获取池子的手续费率（池子级覆盖优先，其次DEX默认值）
+    fn get_pool_fee(&self, pool: &PoolPrice) -> f64 {
+        crate::fee_registry::fee_rate(&pool.pool_id, &pool.dex_name)
     }
     
     /// 获取交易方向的储备量
@@ -669,13 +989,10 @@ impl Router {
         let quote_reserve_f64 = quote_reserve as f64 / 10f64.powi(quote_decimals as i32);
         
         // 解析交易对
-        let pair_tokens: Vec<&str> = pool.pair.split('/').collect();
-        if pair_tokens.len() != 2 {
-            return (base_reserve_f64, quote_reserve_f64);
-        }
-        
-        let base_token = pair_tokens[0];
-        let quote_token = pair_tokens[1];
+        let (base_token, quote_token) = match pool_tokens(pool) {
+            Some(tokens) => tokens,
+            None => return (base_reserve_f64, quote_reserve_f64),
+        };
         
         // 确定交易方向
         if from_token == quote_token && to_token == base_token {
@@ -715,11 +1032,12 @@ impl Router {
         output.push_str("   路径:\n");
 
         for (idx, step) in path.steps.iter().enumerate() {
+            // 符号对应多个 mint 时附带 mint 地址
             output.push_str(&format!("     {}. [{}] {} → {} (价格: {:.6})\n",
                 idx + 1,
                 step.dex_name,
-                step.input_token,
-                step.output_token,
+                pool_mints::describe_token(&step.pool_id, &step.input_token),
+                pool_mints::describe_token(&step.pool_id, &step.output_token),
                 step.price));
         }
 
@@ -750,9 +1068,11 @@ impl Router {
             path.start_token
         ));
         output.push_str(&format!(
-            "   💸 估算费用: {:.6} {}\n",
+            "   💸 估算费用: {:.6} {} (DEX: {:.6} + 执行: {:.6})\n",
             path.estimated_fees,
-            path.start_token
+            path.start_token,
+            path.dex_fees,
+            path.execution_fees
         ));
         output.push_str(&format!(
             "   🎯 净利润: {:.6} {}\n",
@@ -825,6 +1145,8 @@ mod tests {
             input_amount: 100.0,
             output_amount: 101.0,
             gross_profit: 1.0,
+            dex_fees: 0.3,
+            execution_fees: 0.0,
             estimated_fees: 0.3,
             net_profit: 0.7,
             roi_percent: 0.7,
@@ -835,6 +1157,68 @@ mod tests {
         assert!(!path.is_valid());
     }
     
+    #[test]
+    fn test_fingerprint_tracks_pools_and_direction() {
+        let step = |pool_id: &str, input: &str, output: &str| RouteStep {
+            pool_id: pool_id.to_string(),
+            dex_name: "Raydium AMM V4".to_string(),
+            input_token: input.to_string(),
+            output_token: output.to_string(),
+            price: 1.0,
+            liquidity_base: 0,
+            liquidity_quote: 0,
+            expected_input: 1.0,
+            expected_output: 1.0,
+            price_impact_percent: 0.0,
+            effective_fee_bps: 25.0,
+            raw_input_token: None,
+            raw_output_token: None,
+        };
+        let path = |start: &str, steps: Vec<RouteStep>| ArbitragePath {
+            arb_type: ArbitrageType::Triangle,
+            steps,
+            start_token: start.to_string(),
+            end_token: start.to_string(),
+            input_amount: 100.0,
+            output_amount: 101.0,
+            gross_profit: 1.0,
+            dex_fees: 0.3,
+            execution_fees: 0.0,
+            estimated_fees: 0.3,
+            net_profit: 0.7,
+            roi_percent: 0.7,
+            discovered_at: Instant::now(),
+        };
+        
+        let forward = path("SOL", vec![step("a", "SOL", "USDC"), step("b", "USDC", "SOL")]);
+        let mut redetected = forward.clone();
+        redetected.roi_percent = 1.2;
+        // 同一组池子，起点不同（方向不同）
+        let rotated = path("USDC", vec![step("a", "USDC", "SOL"), step("b", "SOL", "USDC")]);
+        
+        assert_eq!(forward.fingerprint().len(), 16);
+        assert_eq!(forward.fingerprint(), redetected.fingerprint());
+        assert_eq!(forward.signature(), rotated.signature());
+        assert_ne!(forward.fingerprint(), rotated.fingerprint());
+        
+        // 签名不拼接字符串，但比较 / 键 / 文本都与 "a->b" 一致；指纹与拼接 "a->b|SOL>USDC>SOL" 后哈希相同
+        assert_eq!(forward.signature(), "a->b");
+        assert_eq!(forward.signature().to_string(), "a->b");
+        assert_eq!(forward.signature().key(), PathSignature::key_of("a->b"));
+        let longer = path("SOL", vec![step("a", "SOL", "USDC"), step("b-2", "USDC", "SOL")]);
+        assert_eq!(forward.signature().cmp(&longer.signature()), "a->b".cmp("a->b-2"));
+        assert_eq!(forward.fingerprint(), format!("{:016x}", fnv1a(FNV_OFFSET_BASIS, "a->b|SOL>USDC>SOL".bytes())));
+        
+        // 🔁 同一池子先 SOL→USDC 再 USDC→SOL：第一跳之后价格已变
+        assert!(forward.pool_reuse(None).is_none());
+        let degenerate = path("SOL", vec![step("a", "SOL", "USDC"), step("b", "USDC", "JUP"), step("c", "JUP", "USDC"), step("a", "USDC", "SOL")]);
+        let reuse = degenerate.pool_reuse(None).unwrap();
+        assert_eq!((reuse.first_hop, reuse.second_hop, reuse.shared_vault.as_deref()), (0, 3, None));
+        assert_eq!(reuse.to_string(), "pool a used at hops 0 and 3");
+        assert!(!PoolReusePolicy { allow: true, vault_reader: None }.rejects(&degenerate, "test"));
+        assert!(PoolReusePolicy::default().rejects(&degenerate, "test"));
+    }
+    
     #[test]
     fn test_router_creation() {
         use std::sync::Arc;
@@ -843,7 +1227,7 @@ mod tests {
         
         assert_eq!(router.min_roi_percent, 0.3);
         assert_eq!(router.max_depth, 4);
-        assert!(router.dex_fees.contains_key("Raydium AMM V4"));
+        assert_eq!(crate::fee_registry::FeeRegistry::dex_default("Raydium AMM V4"), 0.0025);
     }
 }
 
@@ -17,12 +17,26 @@ use crate::router_bellman_ford::BellmanFordScanner;
 use crate::router_bfs::BfsScanner;  // 🔥 新增：BFS扫描器
 use crate::router_split_optimizer::{SplitOptimizer, OptimizedPath};
 use crate::router_cache::RouterCache;  // 🔥 新增：路径缓存
-use crate::price_cache::PriceCache;
+use crate::router::{ArbitragePath, PoolReusePolicy};
+use crate::config::{PathCacheConfig, RankingConfig, RouterConfig};
+use crate::execution_cost::ExecutionCostModel;
+use crate::price_cache::{PoolPrice, PriceCache};
+use crate::price_oracle::PriceOracle;
+use crate::ranking::OpportunityRanker;
+use crate::token_graph::TokenFilter;
+use crate::pool_mints;
+use crate::vault_reader::VaultReader;
+use crate::backpressure::{BackpressureMonitor, LoadLevel, PendingFilter, ScanMetrics};  // 🔥 下游反压信号
+use crate::scan_capture::{CaptureControl, ScanCapture};  // 🧊 扫描输入抓取 / 离线重放
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tracing::{info, debug};
 
 /// 路由器模式
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum RouterMode {
     /// 快速模式：仅2-3跳（~4ms）
     Fast,
@@ -43,8 +57,9 @@ impl RouterMode {
     }
 }
 
-/// 高级路由器配置
-#[derive(Debug, Clone)]
+/// 高级路由器配置（🧊 随扫描抓取文件一起保存）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AdvancedRouterConfig {
     pub mode: RouterMode,
     pub min_roi_percent: f64,
@@ -52,6 +67,16 @@ pub struct AdvancedRouterConfig {
     pub enable_split_optimization: bool,
     pub max_splits: usize,
     pub min_split_amount: f64,
+    /// 起点代币 / 中间代币白名单 / 黑名单（BFS 和 Bellman-Ford 共用）
+    pub token_filter: TokenFilter,
+    /// 路径骨架缓存（complete_scan 先重算缓存路径）
+    pub path_cache: PathCacheConfig,
+    /// 期望值排序权重
+    pub ranking: RankingConfig,
+    /// 🔁 允许路径重复使用同一池子
+    pub allow_pool_reuse: bool,
+    /// 💧 美元流动性低于该值的池子不进 BFS / Bellman-Ford 的图（0 = 不过滤）
+    pub min_pool_liquidity_usd: f64,
 }
 
 impl Default for AdvancedRouterConfig {
@@ -63,6 +88,37 @@ impl Default for AdvancedRouterConfig {
             enable_split_optimization: true,
             max_splits: 5,
             min_split_amount: 100.0,
+            token_filter: TokenFilter::default(),
+            path_cache: PathCacheConfig::default(),
+            ranking: RankingConfig::default(),
+            allow_pool_reuse: false,
+            min_pool_liquidity_usd: 0.0,
+        }
+    }
+}
+
+impl AdvancedRouterConfig {
+    /// 按 [router] 配置创建（未配置时使用默认值）
+    pub fn from_config(router: Option<&RouterConfig>) -> Self {
+        let Some(router) = router else {
+            return Self::default();
+        };
+        Self {
+            mode: RouterMode::from_str(&router.mode),
+            min_roi_percent: router.min_roi_percent,
+            max_hops: router.max_hops,
+            enable_split_optimization: router.enable_split_optimization,
+            max_splits: router.split_optimizer.as_ref().map(|s| s.max_splits).unwrap_or(5),
+            min_split_amount: router.split_optimizer.as_ref().map(|s| s.min_split_amount).unwrap_or(100.0),
+            token_filter: TokenFilter {
+                start_tokens: router.start_tokens.clone(),
+                intermediate_whitelist: router.intermediate_whitelist.clone(),
+                token_blacklist: router.token_blacklist.clone(),
+            },
+            path_cache: router.path_cache.clone().unwrap_or_default(),
+            ranking: router.ranking.clone().unwrap_or_default(),
+            allow_pool_reuse: router.allow_pool_reuse,
+            min_pool_liquidity_usd: router.min_pool_liquidity_usd,
         }
     }
 }
@@ -85,16 +141,38 @@ pub struct AdvancedRouter {
     config: AdvancedRouterConfig,
     /// 价格缓存（用于实时获取数据）
     price_cache: Arc<PriceCache>,
+    /// 反压监视器（可选，来自模拟器/验证器）
+    backpressure: Option<Arc<BackpressureMonitor>>,
+    /// 📐 期望值排序（select_best 与 Calculator 输出排序）
+    ranker: OpportunityRanker,
+    /// ⏱️ 累计的快照组装耗时（微秒，Calculator 每次扫描后 take_snapshot_time 取出并清零）
+    snapshot_micros: AtomicU64,
+    /// 🧊 扫描输入抓取（可选，扫描前把快照和配置写入 JSON 文件）
+    capture: Option<Arc<CaptureControl>>,
 }
 
 impl AdvancedRouter {
     /// 创建新的高级路由器
     pub fn new(price_cache: Arc<PriceCache>, config: AdvancedRouterConfig) -> Self {
         let quick_scanner = Router::new(price_cache.clone());
-        let bfs_scanner = BfsScanner::new(3, config.min_roi_percent);  // 🔥 BFS限制3跳
-        let bf_scanner = BellmanFordScanner::new(config.max_hops, config.min_roi_percent);
-        let split_optimizer = SplitOptimizer::new(config.max_splits, config.min_split_amount);
-        let path_cache = Arc::new(Mutex::new(RouterCache::new(30, 1000)));  // 🔥 30秒TTL，1000条目
+        let pool_reuse = PoolReusePolicy { allow: config.allow_pool_reuse, vault_reader: None };
+        let execution_cost = ExecutionCostModel::shared_default();
+        let bfs_scanner = BfsScanner::new(3, config.min_roi_percent, execution_cost.clone())  // 🔥 BFS限制3跳
+            .with_token_filter(config.token_filter.clone())
+            .with_pool_reuse(pool_reuse.clone())
+            .with_min_liquidity_usd(config.min_pool_liquidity_usd);
+        let bf_scanner = BellmanFordScanner::new(config.max_hops, config.min_roi_percent, execution_cost)
+            .with_token_filter(config.token_filter.clone())
+            .with_pool_reuse(pool_reuse)
+            .with_min_liquidity_usd(config.min_pool_liquidity_usd);
+        let split_optimizer = SplitOptimizer::new(config.max_splits, config.min_split_amount)
+            .with_price_cache(price_cache.clone());
+        let path_cache = Arc::new(Mutex::new(
+            RouterCache::new(config.path_cache.ttl_secs, config.path_cache.max_entries)
+                .with_invalidation_percent(config.path_cache.invalidation_percent),
+        ));
+        
+        let ranker = OpportunityRanker::with_default_oracle(price_cache.clone(), config.ranking.clone());
         
         Self {
             quick_scanner,
@@ -104,25 +182,215 @@ impl AdvancedRouter {
             path_cache,  // 🔥 新增
             config,
             price_cache,
+            backpressure: None,
+            ranker,
+            snapshot_micros: AtomicU64::new(0),
+            capture: None,
         }
     }
+
+    /// 🧊 接入扫描输入抓取（[debug_capture] 或 POST /debug/capture 开启时写文件）
+    pub fn with_capture(mut self, capture: Arc<CaptureControl>) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// 🧊 离线重放抓取的扫描输入
+    ///
+    /// 只使用文件中的池子和路由配置，不读取实时缓存；路径缓存、反压不参与，
+    /// 与抓取时的扫描（抓取那次同样跳过路径缓存）得到相同的路径。执行成本不在文件里，
+    /// 由调用方传入与抓取时相同的模型。
+    pub async fn scan_from_snapshot(
+        snapshot: &ScanCapture,
+        amount: f64,
+        execution_cost: Arc<ExecutionCostModel>,
+    ) -> Vec<OptimizedPath> {
+        let router = Self::new(Arc::new(PriceCache::new()), snapshot.router.clone())
+            .with_execution_cost(execution_cost);
+        let (mode, min_roi) = (router.config.mode, router.config.min_roi_percent);
+        router.scan_pools(mode, &snapshot.pools, amount, min_roi, false, None).await
+    }
+
+    /// 🔁 接入 vault 登记表：共用 vault 的包装池视为同一池子
+    pub fn with_vault_reader(mut self, vault_reader: Arc<VaultReader>) -> Self {
+        let pool_reuse = PoolReusePolicy { allow: self.config.allow_pool_reuse, vault_reader: Some(vault_reader) };
+        self.bfs_scanner = self.bfs_scanner.with_pool_reuse(pool_reuse.clone());
+        self.bf_scanner = self.bf_scanner.with_pool_reuse(pool_reuse);
+        self
+    }
+
+    /// ⛽ 三个扫描器共用的执行成本模型（默认是未刷新的默认配置模型）
+    pub fn with_execution_cost(mut self, execution_cost: Arc<ExecutionCostModel>) -> Self {
+        self.quick_scanner = self.quick_scanner.with_execution_cost(execution_cost.clone());
+        self.bfs_scanner = self.bfs_scanner.with_execution_cost(execution_cost.clone());
+        self.bf_scanner = self.bf_scanner.with_execution_cost(execution_cost);
+        self
+    }
+
+    /// 📐 排序使用与 Calculator 相同的 USD 定价（默认使用默认定价配置）
+    pub fn with_price_oracle(mut self, oracle: Arc<PriceOracle>) -> Self {
+        self.ranker = OpportunityRanker::new(self.price_cache.clone(), oracle, self.config.ranking.clone());
+        self
+    }
+
+    /// 期望值排序器
+    pub fn ranker(&self) -> &OpportunityRanker {
+        &self.ranker
+    }
+
+    /// ⏱️ 上次取出以来组装路由快照的总耗时（延迟预算的 snapshot 阶段）
+    pub fn take_snapshot_time(&self) -> Duration {
+        Duration::from_micros(self.snapshot_micros.swap(0, Ordering::Relaxed))
+    }
+
+    /// 🔥 接入反压监视器：扫描前采样下游负载并自适应调整
+    pub fn with_backpressure(mut self, monitor: Arc<BackpressureMonitor>) -> Self {
+        self.backpressure = Some(monitor);
+        self
+    }
     
+    /// 🔥 订阅价格更新：池子价格变动超过阈值时让经过它的路径骨架失效
+    pub fn spawn_path_cache_invalidation(&self) -> tokio::task::JoinHandle<()> {
+        let mut updates = self.price_cache.subscribe_updates();
+        let path_cache = self.path_cache.clone();
+
+        tokio::spawn(async move {
+            loop {
+                match updates.recv().await {
+                    Ok(event) => {
+                        let removed = path_cache.lock().unwrap().on_price_update(&event);
+                        if removed > 0 {
+                            debug!("Path cache: {} skeletons invalidated by {} price move", removed, event.pool_id);
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        // 丢了事件就无法判断哪些骨架失效，全部清空
+                        debug!("Path cache: lagged {} price events, clearing skeletons", skipped);
+                        path_cache.lock().unwrap().clear_skeletons();
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
     /// 寻找最优路径（主入口）
     pub async fn find_optimal_routes(&self, amount: f64) -> Vec<OptimizedPath> {
-        match self.config.mode {
-            RouterMode::Fast => self.fast_scan(amount).await,
-            RouterMode::Complete => self.complete_scan(amount).await,
-            RouterMode::Hybrid => self.hybrid_scan(amount).await,
+        self.find_routes(amount, None).await
+    }
+
+    /// 🎯 定向扫描入口：只搜索经过 `tokens` 的环路（事件触发时由 Calculator 调用）
+    pub async fn find_scoped_routes(&self, amount: f64, tokens: &[String]) -> Vec<OptimizedPath> {
+        self.find_routes(amount, Some(tokens)).await
+    }
+
+    /// 全量 / 定向扫描共用的反压处理
+    async fn find_routes(&self, amount: f64, scope: Option<&[String]>) -> Vec<OptimizedPath> {
+        let monitor = match &self.backpressure {
+            Some(monitor) => monitor,
+            None => return self.scan_with_scope(self.config.mode, amount, self.config.min_roi_percent, scope, None).await,
+        };
+
+        // 🎯 扫描前采样下游负载
+        let load_factor = monitor.load_factor();
+        let level = monitor.level_for(load_factor);
+        let min_roi = monitor.adaptive_min_roi(self.config.min_roi_percent, level);
+        let mode_downgraded = self.config.mode == RouterMode::Hybrid && monitor.should_downgrade_hybrid(level);
+        let mode = if mode_downgraded { RouterMode::Fast } else { self.config.mode };
+
+        if level != LoadLevel::Normal {
+            info!(
+                "Backpressure {:?} (load={:.2}): min_roi {:.3}% -> {:.3}%, mode {:?}",
+                level, load_factor, self.config.min_roi_percent, min_roi, mode
+            );
         }
+
+        // ⏳ 负载非 Normal 时，各扫描阶段跳过仍在等待模拟的路径（标记由模拟器维护）
+        let pending = (level != LoadLevel::Normal).then(|| PendingFilter::new(monitor));
+        let paths = self.scan_with_scope(mode, amount, min_roi, scope, pending.as_ref()).await;
+        let skipped_pending = pending.as_ref().map_or(0, PendingFilter::skipped);
+        if skipped_pending > 0 {
+            debug!("Backpressure: skipped {} paths already awaiting simulation", skipped_pending);
+        }
+
+        monitor.record_scan(ScanMetrics {
+            load_factor,
+            load_level: level,
+            base_min_roi_percent: self.config.min_roi_percent,
+            effective_min_roi_percent: min_roi,
+            mode: format!("{:?}", mode),
+            mode_downgraded,
+            skipped_pending,
+            paths_found: paths.len(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        });
+
+        paths
     }
-    
+
+    /// 有定向范围时只做定向 BFS，否则按模式全量扫描
+    async fn scan_with_scope(
+        &self,
+        mode: RouterMode,
+        amount: f64,
+        min_roi: f64,
+        scope: Option<&[String]>,
+        pending: Option<&PendingFilter<'_>>,
+    ) -> Vec<OptimizedPath> {
+        match scope {
+            Some(tokens) => self.scoped_scan(amount, min_roi, tokens, pending).await,
+            None => self.scan_with_mode(mode, amount, min_roi, pending).await,
+        }
+    }
+
+    /// 按指定模式和阈值扫描（先组装快照；被抓取的扫描跳过路径缓存，保证可以离线重放）
+    async fn scan_with_mode(&self, mode: RouterMode, amount: f64, min_roi: f64, pending: Option<&PendingFilter<'_>>) -> Vec<OptimizedPath> {
+        let all_prices = self.routing_snapshot();
+        if all_prices.is_empty() {
+            return Vec::new();
+        }
+        let captured = self.capture_if_armed(&all_prices, amount);
+        self.scan_pools(mode, &all_prices, amount, min_roi, !captured, pending).await
+    }
+
+    /// 在给定快照上按模式扫描（所有扫描器只读取 `pools`；`pending` 为 None 时不跳过任何路径）
+    async fn scan_pools(
+        &self,
+        mode: RouterMode,
+        pools: &[PoolPrice],
+        amount: f64,
+        min_roi: f64,
+        use_path_cache: bool,
+        pending: Option<&PendingFilter<'_>>,
+    ) -> Vec<OptimizedPath> {
+        match mode {
+            RouterMode::Fast => self.fast_scan(pools, amount, min_roi, pending).await,
+            RouterMode::Complete => self.complete_scan(pools, amount, min_roi, use_path_cache, pending).await,
+            RouterMode::Hybrid => self.hybrid_scan(pools, amount, min_roi, use_path_cache, pending).await,
+        }
+    }
+
+    /// 🧊 抓取开启时把本次扫描的快照和配置写入文件（返回是否已抓取）
+    fn capture_if_armed(&self, pools: &[PoolPrice], amount: f64) -> bool {
+        match &self.capture {
+            Some(capture) if capture.should_capture() => {
+                capture.write(&ScanCapture::new(&self.config, pools, amount));
+                true
+            }
+            _ => false,
+        }
+    }
+
     /// 快速扫描（仅2-3跳）
-    async fn fast_scan(&self, amount: f64) -> Vec<OptimizedPath> {
+    async fn fast_scan(&self, pools: &[PoolPrice], amount: f64, min_roi: f64, pending: Option<&PendingFilter<'_>>) -> Vec<OptimizedPath> {
         println!("   🚀 Fast scan mode: 2-3 hop only");
         
         let scan_start = tokio::time::Instant::now();
-        let paths = self.quick_scanner.find_all_opportunities(amount);
+        let mut paths = self.quick_scanner.find_opportunities_in(pools, amount);
         println!("   ⚡ Found {} raw paths in {:?}", paths.len(), scan_start.elapsed());
+        if let Some(pending) = pending {
+            pending.retain(&mut paths);
+        }
         
         // 转换为OptimizedPath
         let optimized: Vec<OptimizedPath> = paths.into_iter()
@@ -147,37 +415,46 @@ impl AdvancedRouter {
         
         let before_filter = optimized.len();
         let filtered: Vec<OptimizedPath> = optimized.into_iter()
-            .filter(|p| p.optimized_roi >= self.config.min_roi_percent)
+            .filter(|p| p.optimized_roi >= min_roi)
             .collect();
         
         let filtered_out = before_filter - filtered.len();
         if filtered_out > 0 {
-            println!("   ⛔ Filtered out {} paths (ROI < {}%)", filtered_out, self.config.min_roi_percent);
+            println!("   ⛔ Filtered out {} paths (ROI < {}%)", filtered_out, min_roi);
         }
         
-        // 如果启用拆分优化
-        if self.config.enable_split_optimization && !filtered.is_empty() {
-            println!("   💎 Applying split optimization...");
-            self.split_optimizer.optimize_all(&filtered.iter().map(|o| o.base_path.clone()).collect::<Vec<_>>(), amount)
-        } else {
-            filtered
-        }
+        self.apply_split_optimization(filtered, amount, pools)
     }
     
-    /// 完整扫描（2-6跳全覆盖）
-    async fn complete_scan(&self, amount: f64) -> Vec<OptimizedPath> {
+    /// 路由用价格快照（⏱️ 组装耗时计入 snapshot_micros）
+    fn routing_snapshot(&self) -> Vec<PoolPrice> {
+        let started = Instant::now();
+        let prices = self.assemble_snapshot();
+        self.snapshot_micros.fetch_add(started.elapsed().as_micros() as u64, Ordering::Relaxed);
+        prices
+    }
+
+    /// 一致性快照太小时降级到仅新鲜度过滤，按 pool_id 排序
+    fn assemble_snapshot(&self) -> Vec<PoolPrice> {
         println!("   📡 Fetching price data...");
         
-        // 🎯 数据一致性：收紧阈值确保价格新鲜度（减少过期机会）
-        // 参数：2000ms新鲜度（2秒），10 slot差异（约4秒）
-        let consistent_prices = self.price_cache.get_consistent_snapshot(2000, 10);
-        
+        // 🎯 数据一致性：按池子类型的新鲜度预算（AMM/CLMM 2秒，vault 依赖型 5秒，CLOB 10秒）
+        let snapshot = self.price_cache.get_policy_snapshot();
+        let excluded = snapshot.counts();
+        let consistent_prices = snapshot.included;
+
         // 如果一致性数据太少，降级到仅新鲜度过滤
         let all_prices = if consistent_prices.len() < 10 {
-            println!("   ⚠️  Consistent snapshot too small ({}), falling back to fresh prices", consistent_prices.len());
+            println!(
+                "   ⚠️  Consistent snapshot too small ({}, excluded: {}), falling back to fresh prices (see GET /snapshot)",
+                consistent_prices.len(), excluded
+            );
             self.price_cache.get_fresh_prices(5000)  // 降级也收紧到5秒
         } else {
-            println!("   ✅ Using consistent snapshot with {} pools", consistent_prices.len());
+            println!(
+                "   ✅ Using consistent snapshot with {} pools (excluded: {})",
+                consistent_prices.len(), excluded
+            );
             consistent_prices
         };
         
@@ -186,8 +463,67 @@ impl AdvancedRouter {
             return Vec::new();
         }
         
+        // 🎯 确定性：快照按 pool_id 排序，与缓存迭代顺序无关
+        let mut all_prices = all_prices;
+        all_prices.sort_by(|a, b| a.pool_id.cmp(&b.pool_id));
+        all_prices
+    }
+
+    /// 🎯 定向扫描：只从触发交易对的代币发起 BFS（不查路径缓存，也不写入骨架）
+    async fn scoped_scan(&self, amount: f64, min_roi: f64, tokens: &[String], pending: Option<&PendingFilter<'_>>) -> Vec<OptimizedPath> {
+        let all_prices = self.routing_snapshot();
+        if all_prices.is_empty() {
+            return Vec::new();
+        }
+
+        let bfs_start = tokio::time::Instant::now();
+        let mut paths = self.bfs_scanner.find_opportunities_from(&all_prices, amount, tokens);
+        println!("   🎯 Scoped BFS ({}): {} paths in {:?}", tokens.join(", "), paths.len(), bfs_start.elapsed());
+        if let Some(pending) = pending {
+            pending.retain(&mut paths);
+        }
+
+        let filtered: Vec<OptimizedPath> = paths.into_iter()
+            .filter(|p| p.roi_percent >= min_roi)
+            .map(|p| OptimizedPath {
+                optimized_net_profit: p.net_profit,
+                optimized_roi: p.roi_percent,
+                base_path: p,
+                split_strategy: None,
+            })
+            .collect();
+
+        self.apply_split_optimization(filtered, amount, &all_prices)
+    }
+
+    /// 完整扫描（2-6跳全覆盖）
+    async fn complete_scan(
+        &self,
+        all_prices: &[PoolPrice],
+        amount: f64,
+        min_roi: f64,
+        use_path_cache: bool,
+        pending: Option<&PendingFilter<'_>>,
+    ) -> Vec<OptimizedPath> {
+        // ♻️ 路径缓存：沿已知的边按当前价格重算，有达标路径且缓存未过期时跳过完整扫描
+        if self.config.path_cache.enabled && use_path_cache {
+            let cache_start = tokio::time::Instant::now();
+            if let Some(paths) = self.scan_cached_paths(all_prices, amount, min_roi, pending) {
+                println!("   ♻️  Path cache: {} paths re-evaluated in {:?}, skipping full scan", paths.len(), cache_start.elapsed());
+                let cached: Vec<OptimizedPath> = paths.into_iter()
+                    .map(|p| OptimizedPath {
+                        optimized_net_profit: p.net_profit,
+                        optimized_roi: p.roi_percent,
+                        base_path: p,
+                        split_strategy: None,
+                    })
+                    .collect();
+                return self.apply_split_optimization(cached, amount, all_prices);
+            }
+        }
+        
         // 记录数据质量统计
-        let latest_slot = self.price_cache.get_latest_slot();
+        let latest_slot = all_prices.iter().map(|p| p.slot).max().unwrap_or(0);
         println!("   📊 Latest slot: {}, using {} pools for routing", latest_slot, all_prices.len());
         
         // 🔥 三路并行扫描：Quick + BFS + Bellman-Ford
@@ -195,17 +531,17 @@ impl AdvancedRouter {
         
         let quick_start = tokio::time::Instant::now();
         let quick_future = async {
-            self.quick_scanner.find_all_opportunities(amount)
+            self.quick_scanner.find_opportunities_in(all_prices, amount)
         };
         
         let bfs_start = tokio::time::Instant::now();
         let bfs_future = async {
-            self.bfs_scanner.find_all_opportunities(&all_prices, amount)
+            self.bfs_scanner.find_all_opportunities(all_prices, amount)
         };
         
         let deep_start = tokio::time::Instant::now();
         let deep_future = async {
-            self.bf_scanner.find_all_cycles(&all_prices, amount)
+            self.bf_scanner.find_all_cycles(all_prices, amount)
         };
         
         let (quick_paths, bfs_paths, deep_paths) = tokio::join!(quick_future, bfs_future, deep_future);
@@ -221,12 +557,23 @@ impl AdvancedRouter {
         let total_before_dedup = all_paths.len();
         
         // 去重（可能同一个机会被两个算法都发现）
+        // 先确定性排序（ROI → 跳数 → 签名），保证重复路径中保留的是同一条
+        all_paths.sort_by(ArbitragePath::deterministic_cmp);
         all_paths = self.deduplicate_paths(all_paths);
         let duplicates_removed = total_before_dedup - all_paths.len();
         if duplicates_removed > 0 {
             println!("   🔄 Removed {} duplicate paths", duplicates_removed);
         }
         
+        // 写入路径骨架，下次扫描先重算这些路径
+        if self.config.path_cache.enabled {
+            self.path_cache.lock().unwrap().store_skeletons(&all_paths, all_prices);
+        }
+        // 骨架保留所有路径，等待模拟的路径不进入 ROI 过滤和拆分优化
+        if let Some(pending) = pending {
+            pending.retain(&mut all_paths);
+        }
+        
         // 转换为OptimizedPath
         let base_optimized: Vec<OptimizedPath> = all_paths.into_iter()
             .map(|p| OptimizedPath {
@@ -248,10 +595,10 @@ impl AdvancedRouter {
                 println!("   🎯 候选路径 #{} (ROI: {:.6}%)", idx + 1, path.optimized_roi);
 
                 // 标记是否会被过滤
-                if path.optimized_roi >= self.config.min_roi_percent {
-                    println!("   ✅ 保留 (ROI ≥ {}%阈值)", self.config.min_roi_percent);
+                if path.optimized_roi >= min_roi {
+                    println!("   ✅ 保留 (ROI ≥ {}%阈值)", min_roi);
                 } else {
-                    println!("   ❌ 将被过滤 (ROI < {}%阈值)", self.config.min_roi_percent);
+                    println!("   ❌ 将被过滤 (ROI < {}%阈值)", min_roi);
                 }
 
                 println!("{}", self.format_optimized_path_for_debug(path));
@@ -267,27 +614,32 @@ impl AdvancedRouter {
 
         // Filter by ROI threshold
         let filtered: Vec<OptimizedPath> = base_optimized.into_iter()
-            .filter(|p| p.optimized_roi >= self.config.min_roi_percent)
+            .filter(|p| p.optimized_roi >= min_roi)
             .collect();
 
         let filtered_out = before_filter - filtered.len();
         if filtered_out > 0 {
-            println!("   ⛔ 过滤结果: {} 条路径中，{} 条因 ROI < {}% 被移除", before_filter, filtered_out, self.config.min_roi_percent);
+            println!("   ⛔ 过滤结果: {} 条路径中，{} 条因 ROI < {}% 被移除", before_filter, filtered_out, min_roi);
             if !filtered.is_empty() {
                 println!("   ✅ 最终保留: {} 条路径", filtered.len());
             } else {
                 println!("   ⚠️  警告: 过滤后没有路径剩余！");
             }
         } else if before_filter > 0 {
-            println!("   ✅ 过滤结果: 所有 {} 条路径都满足 ROI ≥ {}% 阈值", before_filter, self.config.min_roi_percent);
+            println!("   ✅ 过滤结果: 所有 {} 条路径都满足 ROI ≥ {}% 阈值", before_filter, min_roi);
         }
         
-        // 应用拆分优化
+        self.apply_split_optimization(filtered, amount, all_prices)
+    }
+    
+    /// 应用拆分优化（未启用或没有路径时原样返回；同pair的候选池子取自本次快照）
+    fn apply_split_optimization(&self, filtered: Vec<OptimizedPath>, amount: f64, pools: &[PoolPrice]) -> Vec<OptimizedPath> {
         if self.config.enable_split_optimization && !filtered.is_empty() {
             println!("   💎 Applying split optimization to {} paths...", filtered.len());
-            let optimized = self.split_optimizer.optimize_all(
+            let optimized = self.split_optimizer.optimize_all_with_pools(
                 &filtered.iter().map(|o| o.base_path.clone()).collect::<Vec<_>>(),
-                amount
+                amount,
+                pools,
             );
             println!("   ✅ Split optimization complete: {} final paths", optimized.len());
             optimized
@@ -296,10 +648,37 @@ impl AdvancedRouter {
         }
     }
     
-    /// 混合扫描（智能选择）
-    async fn hybrid_scan(&self, amount: f64) -> Vec<OptimizedPath> {
+    /// 按当前价格重算缓存的路径骨架
+    ///
+    /// 距上次完整扫描超过TTL，或没有路径达到 min_roi 时返回 None（需要完整扫描）。
+    /// 等待模拟的骨架不重算；有骨架因此被跳过时即使没有达标路径也不做完整扫描
+    /// （完整扫描只会再找到这些等待中的路径）。
+    fn scan_cached_paths(&self, pools: &[PoolPrice], amount: f64, min_roi: f64, pending: Option<&PendingFilter<'_>>) -> Option<Vec<ArbitragePath>> {
+        let mut cache = self.path_cache.lock().unwrap();
+        if cache.needs_full_scan() {
+            return None;
+        }
+        
+        let skipped_before = pending.map_or(0, PendingFilter::skipped);
+        let mut paths = cache.reevaluate(pools, amount, min_roi, |path| pending.is_some_and(|pending| pending.skip(path)));
+        if paths.is_empty() && pending.map_or(0, PendingFilter::skipped) == skipped_before {
+            return None;
+        }
+        paths.sort_by(ArbitragePath::deterministic_cmp);
+        Some(self.deduplicate_paths(paths))
+    }
+    
+    /// 混合扫描（智能选择，两个阶段使用同一个快照）
+    async fn hybrid_scan(
+        &self,
+        pools: &[PoolPrice],
+        amount: f64,
+        min_roi: f64,
+        use_path_cache: bool,
+        pending: Option<&PendingFilter<'_>>,
+    ) -> Vec<OptimizedPath> {
         // 先快速扫描
-        let quick_results = self.fast_scan(amount).await;
+        let quick_results = self.fast_scan(pools, amount, min_roi, pending).await;
         
         // 如果找到高质量机会（ROI > 1%），直接返回
         if let Some(best) = quick_results.first() {
@@ -311,11 +690,11 @@ impl AdvancedRouter {
         
         // 否则进行完整扫描
         debug!("Hybrid mode: No excellent quick opportunity, running complete scan...");
-        self.complete_scan(amount).await
+        self.complete_scan(pools, amount, min_roi, use_path_cache, pending).await
     }
     
     /// 去重路径（基于步骤序列）
-    fn deduplicate_paths(&self, paths: Vec<crate::router::ArbitragePath>) -> Vec<crate::router::ArbitragePath> {
+    fn deduplicate_paths(&self, paths: Vec<ArbitragePath>) -> Vec<ArbitragePath> {
         let mut unique = Vec::new();
         let mut seen = std::collections::HashSet::new();
         
@@ -347,19 +726,21 @@ impl AdvancedRouter {
             path.optimized_net_profit, path.base_path.start_token, path.optimized_roi));
         
         if let Some(strategy) = &path.split_strategy {
-            output.push_str(&format!("   拆分策略: {} 条路径\n", strategy.allocations.len()));
-            for (idx, amount) in &strategy.allocations {
-                output.push_str(&format!("     - 路径{}: {:.2} 资金\n", idx + 1, amount));
+            output.push_str(&format!("   拆分策略: {} 个池子子步骤\n", strategy.allocations.len()));
+            for allocation in &strategy.allocations {
+                output.push_str(&format!("     - 第{}跳 [{}] {}: {:.6} 资金\n",
+                    allocation.hop + 1, allocation.dex_name, allocation.pool_id, allocation.amount_in));
             }
         }
 
         output.push_str(&format!("   路径（{}跳）:\n", path.base_path.steps.len()));
         for (idx, step) in path.base_path.steps.iter().enumerate() {
+            // 符号对应多个 mint 时附带 mint 地址
             output.push_str(&format!("     {}. [{}] {} → {} (价格: {:.6})\n",
                 idx + 1,
                 step.dex_name,
-                step.input_token,
-                step.output_token,
+                pool_mints::describe_token(&step.pool_id, &step.input_token),
+                pool_mints::describe_token(&step.pool_id, &step.output_token),
                 step.price));
         }
 
@@ -380,13 +761,16 @@ impl AdvancedRouter {
         output.push_str(&format!("   📈 毛利润: {:.6} {}\n",
             path.base_path.gross_profit,
             path.base_path.start_token));
-        output.push_str(&format!("   💸 估算费用: {:.6} {}\n",
+        output.push_str(&format!("   💸 估算费用: {:.6} {} (DEX: {:.6} + 执行: {:.6})\n",
             path.base_path.estimated_fees,
-            path.base_path.start_token));
+            path.base_path.start_token,
+            path.base_path.dex_fees,
+            path.base_path.execution_fees));
         output.push_str(&format!("   🎯 净利润: {:.6} {}\n",
             path.base_path.net_profit,
             path.base_path.start_token));
         output.push_str(&format!("   📊 ROI: {:.6}%\n", path.optimized_roi));
+        output.push_str(&format!("   📐 排序: {}\n", self.ranker.evaluate(path)));
 
         // 显示有效性检查
         output.push_str(&format!("   ✅ 有效性检查:"));
@@ -409,25 +793,40 @@ impl AdvancedRouter {
             output.push_str(&format!("        流动性: base={:.2}, quote={:.2}\n",
                 step.liquidity_base as f64 / 1e6,
                 step.liquidity_quote as f64 / 1e6));
+            output.push_str(&format!("        价格冲击: {:.4}%  手续费: {:.1} bps\n",
+                step.price_impact_percent,
+                step.effective_fee_bps));
         }
+        output.push_str(&format!("   🌊 最大单跳冲击: {:.4}%\n", path.base_path.worst_hop_impact()));
 
         // 如果启用了拆分策略，显示拆分详情
         if let Some(strategy) = &path.split_strategy {
             output.push_str(&format!("\n   💎 拆分优化策略:\n"));
-            output.push_str(&format!("      总路径数: {}\n", strategy.allocations.len()));
-            for (idx, amount) in &strategy.allocations {
-                output.push_str(&format!("      - 路径{}: {:.2} USD\n", idx + 1, amount));
+            output.push_str(&format!("      投入: {:.6} {}\n", strategy.input_amount(), path.base_path.start_token));
+            for allocation in &strategy.allocations {
+                output.push_str(&format!("      - 第{}跳 [{}] {}: {:.6} → {:.6}\n",
+                    allocation.hop + 1,
+                    allocation.dex_name,
+                    allocation.pool_id,
+                    allocation.amount_in,
+                    allocation.expected_output));
+            }
+            if !strategy.hop_impacts_percent.is_empty() {
+                let impacts: Vec<String> = strategy.hop_impacts_percent.iter()
+                    .map(|pct| format!("{:.4}%", pct))
+                    .collect();
+                output.push_str(&format!("      分配金额下每跳冲击: [{}]\n", impacts.join(", ")));
             }
         }
 
         output
     }
 
-    /// 选择最优路径
+    /// 选择最优路径（有效路径中期望值最高的一条）
     pub fn select_best<'a>(&self, paths: &'a [OptimizedPath]) -> Option<&'a OptimizedPath> {
-        paths.iter()
-            .filter(|p| p.is_valid())
-            .max_by(|a, b| a.score().partial_cmp(&b.score()).unwrap())
+        let (best, ev) = self.ranker.select_best(paths.iter().filter(|p| p.is_valid()))?;
+        debug!("📐 Best path {}: {}", best.base_path.signature(), ev);
+        Some(best)
     }
 }
 
@@ -450,11 +849,321 @@ mod tests {
         assert_eq!(config.max_hops, 6);
         assert!(config.enable_split_optimization);
     }
-}
 
+    fn dummy_optimized_path(pool_ids: &[&str], roi: f64) -> OptimizedPath {
+        use crate::router::{ArbitragePath, ArbitrageType, RouteStep};
+
+        let steps = pool_ids.iter().map(|id| RouteStep {
+            pool_id: id.to_string(),
+            dex_name: "Test".to_string(),
+            input_token: "SOL".to_string(),
+            output_token: "USDC".to_string(),
+            price: 1.0,
+            liquidity_base: 1_000_000,
+            liquidity_quote: 1_000_000,
+            expected_input: 1.0,
+            expected_output: 1.0,
+            price_impact_percent: 0.0,
+            effective_fee_bps: 0.0,
+            raw_input_token: None,
+            raw_output_token: None,
+        }).collect();
+
+        OptimizedPath {
+            base_path: ArbitragePath {
+                arb_type: ArbitrageType::Triangle,
+                steps,
+                start_token: "SOL".to_string(),
+                end_token: "SOL".to_string(),
+                input_amount: 100.0,
+                output_amount: 100.0 + roi,
+                gross_profit: roi,
+                dex_fees: 0.0,
+                execution_fees: 0.0,
+                estimated_fees: 0.0,
+                net_profit: roi,
+                roi_percent: roi,
+                discovered_at: std::time::Instant::now(),
+            },
+            split_strategy: None,
+            optimized_net_profit: roi,
+            optimized_roi: roi,
+        }
+    }
+
+    fn backpressure_router(mode: RouterMode) -> (AdvancedRouter, Arc<BackpressureMonitor>) {
+        use crate::backpressure::BackpressurePolicy;
 
+        let monitor = Arc::new(BackpressureMonitor::new(BackpressurePolicy::default()));
+        monitor.set_in_flight_limit(10);
 
+        let config = AdvancedRouterConfig {
+            mode,
+            min_roi_percent: 0.3,
+            ..Default::default()
+        };
+        let router = AdvancedRouter::new(Arc::new(PriceCache::new()), config)
+            .with_backpressure(monitor.clone());
+
+        (router, monitor)
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_raises_threshold_and_recovers() {
+        let (router, monitor) = backpressure_router(RouterMode::Hybrid);
+
+        // 模拟下游饱和：排队满
+        monitor.enqueue(50);
+        router.find_optimal_routes(1000.0).await;
+
+        let scan = monitor.last_scan().expect("scan should be recorded");
+        assert_eq!(scan.load_level, LoadLevel::High);
+        assert!((scan.effective_min_roi_percent - 0.9).abs() < 1e-9);
+        assert!(scan.mode_downgraded);
+        assert_eq!(scan.mode, "Fast");
+
+        // 负载清空后回到基线
+        monitor.dequeue(50);
+        router.find_optimal_routes(1000.0).await;
+
+        let scan = monitor.last_scan().unwrap();
+        assert_eq!(scan.load_level, LoadLevel::Normal);
+        assert_eq!(scan.effective_min_roi_percent, 0.3);
+        assert!(!scan.mode_downgraded);
+        assert_eq!(scan.mode, "Hybrid");
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_skips_paths_awaiting_simulation() {
+        use crate::backpressure::BackpressurePolicy;
 
+        let monitor = Arc::new(BackpressureMonitor::new(BackpressurePolicy::default()));
+        let config = AdvancedRouterConfig { mode: RouterMode::Complete, ..Default::default() };
+        let router = AdvancedRouter::new(fixture_snapshot(), config).with_backpressure(monitor.clone());
+        let signatures = |paths: &[OptimizedPath]| -> Vec<String> {
+            paths.iter().map(|p| p.base_path.signature().to_string()).collect()
+        };
+
+        // 排队满 -> High
+        monitor.enqueue(50);
+        let first = router.find_optimal_routes(1000.0).await;
+        assert!(!first.is_empty());
+        assert_eq!(monitor.last_scan().unwrap().skipped_pending, 0);
+
+        // 模拟器把最优路径入队：下一次扫描（路径缓存重算）不再产出它
+        let top = first[0].base_path.signature().to_string();
+        let simulation = monitor.mark_pending([first[0].base_path.signature().key()]);
+        let second = router.find_optimal_routes(1000.0).await;
+        assert!(!signatures(&second).contains(&top));
+        assert_eq!(monitor.last_scan().unwrap().skipped_pending, 1);
+
+        // 模拟结束：标记清除，路径重新出现
+        drop(simulation);
+        let third = router.find_optimal_routes(1000.0).await;
+        assert!(signatures(&third).contains(&top));
+        assert_eq!(monitor.last_scan().unwrap().skipped_pending, 0);
+    }
+
+    /// 固定快照：包含 ROI 完全相同的重复路径（pool_b / pool_c 参数一致）
+    fn fixture_snapshot() -> Arc<PriceCache> {
+        use crate::price_cache::PoolPrice;
 
+        let cache = Arc::new(PriceCache::new());
+        let now = std::time::Instant::now();
+        let pools = [
+            ("pool_a", "SOL/USDC", 100_000u64, 18_000_000u64, 180.0),
+            ("pool_b", "SOL/USDC", 100_000, 19_000_000, 190.0),
+            ("pool_c", "SOL/USDC", 100_000, 19_000_000, 190.0),
+            ("pool_d", "SOL/USDT", 100_000, 18_500_000, 185.0),
+            ("pool_e", "USDT/USDC", 10_000_000, 10_050_000, 1.005),
+            ("pool_f", "USDT/USDC", 10_000_000, 10_000_000, 1.0),
+        ];
 
+        for (pool_id, pair, base, quote, price) in pools {
+            let (base_decimals, quote_decimals) = if pair.starts_with("SOL") { (9, 6) } else { (6, 6) };
+            cache.update_price(PoolPrice {
+                dex_name: "Raydium".to_string(),
+                base_reserve: base * 10u64.pow(base_decimals as u32),
+                quote_reserve: quote * 10u64.pow(quote_decimals as u32),
+                base_decimals,
+                quote_decimals,
+                last_update: now,
+                slot: 1000,
+                ..PoolPrice::for_test(pool_id, pair, price)
+            });
+        }
+
+        cache
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_complete_scan_is_deterministic() {
+        let router = AdvancedRouter::new(fixture_snapshot(), AdvancedRouterConfig {
+            mode: RouterMode::Complete,
+            ..Default::default()
+        });
 
+        let signatures = |paths: Vec<OptimizedPath>| -> Vec<String> {
+            paths.iter().map(|p| p.base_path.signature().to_string()).collect()
+        };
+
+        let first = signatures(router.find_optimal_routes(1000.0).await);
+        assert!(!first.is_empty());
+
+        for run in 1..10 {
+            let output = signatures(router.find_optimal_routes(1000.0).await);
+            assert_eq!(output, first, "run {} produced a different ordering", run);
+        }
+    }
+
+    /// 🧊 回归 fixture：抓取的三角循环快照离线重放，最优路径固定为 FXA → FXB → FXC → FXA
+    #[tokio::test]
+    async fn test_scan_from_snapshot_fixture_top_path() {
+        use crate::router_fixture::{hops_from, TRIANGLE_HOPS};
+
+        let capture: ScanCapture = serde_json::from_str(include_str!("../fixtures/captures/triangle.json")).unwrap();
+        let execution_cost = crate::router_fixture::priced_fixture_tokens();
+        let paths = AdvancedRouter::scan_from_snapshot(&capture, capture.amount, execution_cost).await;
+
+        let top = paths.iter()
+            .max_by(|a, b| a.optimized_roi.total_cmp(&b.optimized_roi))
+            .expect("captured triangle should produce a path");
+        assert_eq!(hops_from(&top.base_path, "FXA"), TRIANGLE_HOPS);
+        assert!(top.optimized_roi >= capture.router.min_roi_percent);
+    }
+
+    #[tokio::test]
+    async fn test_captured_scan_replays_identically() {
+        use crate::config::DebugCaptureConfig;
+
+        let cache = Arc::new(PriceCache::new());
+        for pool in crate::router_fixture::triangle(1.015).build() {
+            cache.update_price(PoolPrice { slot: 1000, ..pool });
+        }
+        let dir = std::env::temp_dir().join(format!("scan-capture-test-{}", std::process::id()));
+        let capture = Arc::new(CaptureControl::new(&DebugCaptureConfig {
+            enabled: false,
+            dir: dir.display().to_string(),
+        }));
+        capture.arm(1);
+
+        let config = AdvancedRouterConfig { min_roi_percent: 0.1, ..Default::default() };
+        let execution_cost = crate::router_fixture::priced_fixture_tokens();
+        let router = AdvancedRouter::new(cache, config)
+            .with_execution_cost(execution_cost.clone())
+            .with_capture(capture.clone());
+        let outcome = |paths: Vec<OptimizedPath>| -> Vec<(String, f64)> {
+            paths.iter().map(|p| (p.base_path.signature().to_string(), p.optimized_net_profit)).collect()
+        };
+        let live = outcome(router.find_optimal_routes(10.0).await);
+        assert!(!live.is_empty());
+
+        let file = capture.status().last_file.expect("armed scan should be captured");
+        let snapshot = ScanCapture::read_from(std::path::Path::new(&file)).unwrap();
+        assert_eq!(capture.status().pending, 0);
+        let replayed = AdvancedRouter::scan_from_snapshot(&snapshot, snapshot.amount, execution_cost).await;
+        assert_eq!(outcome(replayed), live);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// 200 个池子：66 个代币各有 /SOL、/USDC、/USDT 三个池子，每 5 个代币的 USDC 池子偏高 1.5%
+    fn bench_fixture() -> Arc<PriceCache> {
+        use crate::price_cache::PoolPrice;
+
+        let cache = Arc::new(PriceCache::new());
+        let now = std::time::Instant::now();
+        let add = |pool_id: String, pair: String, base_units: f64, price: f64, decimals: (u8, u8)| {
+            cache.update_price(PoolPrice {
+                dex_name: "Raydium".to_string(),
+                base_reserve: (base_units * 10f64.powi(decimals.0 as i32)) as u64,
+                quote_reserve: (base_units * price * 10f64.powi(decimals.1 as i32)) as u64,
+                base_decimals: decimals.0,
+                quote_decimals: decimals.1,
+                last_update: now,
+                slot: 1000,
+                ..PoolPrice::for_test(&pool_id, &pair, price)
+            });
+        };
+
+        add("bench_sol_usdc".to_string(), "SOL/USDC".to_string(), 100_000.0, 150.0, (9, 6));
+        add("bench_sol_usdt".to_string(), "SOL/USDT".to_string(), 100_000.0, 150.0, (9, 6));
+        for i in 0..66 {
+            let token = format!("TK{}", i);
+            let usd = 1.0 + i as f64 * 0.5;
+            let skew = if i % 5 == 0 { 1.015 } else { 1.0 };
+            add(format!("bench_{}_sol", token), format!("{}/SOL", token), 1_000_000.0, usd / 150.0, (6, 9));
+            add(format!("bench_{}_usdc", token), format!("{}/USDC", token), 1_000_000.0, usd * skew, (6, 6));
+            add(format!("bench_{}_usdt", token), format!("{}/USDT", token), 1_000_000.0, usd, (6, 6));
+        }
+        assert_eq!(cache.get_all_prices().len(), 200);
+
+        cache
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_path_cache_reevaluation_beats_full_scan() {
+        let router = AdvancedRouter::new(bench_fixture(), AdvancedRouterConfig {
+            mode: RouterMode::Complete,
+            enable_split_optimization: false,
+            ..Default::default()
+        });
+
+        // 冷缓存：完整扫描并写入骨架
+        let pools = router.routing_snapshot();
+        let full_start = std::time::Instant::now();
+        let full = router.complete_scan(&pools, 1000.0, 0.3, true, None).await;
+        let full_elapsed = full_start.elapsed();
+        assert!(!full.is_empty());
+        assert!(router.path_cache.lock().unwrap().skeleton_count() > 0);
+
+        // 热缓存：只沿已知的边重算
+        let runs = 5;
+        let cached_start = std::time::Instant::now();
+        for _ in 0..runs {
+            let cached = router.complete_scan(&pools, 1000.0, 0.3, true, None).await;
+            assert_eq!(cached.len(), full.len());
+        }
+        let cached_elapsed = cached_start.elapsed() / runs;
+        assert!(router.path_cache.lock().unwrap().get_stats().hits >= runs as usize);
+
+        assert!(
+            full_elapsed >= cached_elapsed * 5,
+            "re-evaluation {:?} should be at least 5x faster than a full scan {:?}",
+            cached_elapsed, full_elapsed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scoped_scan_only_returns_paths_through_scope() {
+        let router = AdvancedRouter::new(bench_fixture(), AdvancedRouterConfig {
+            mode: RouterMode::Complete,
+            enable_split_optimization: false,
+            ..Default::default()
+        });
+
+        // TK5 的 USDC 池子偏高 1.5%：经过 TK5 的环路有利可图
+        let scoped = router.find_scoped_routes(1000.0, &["TK5".to_string()]).await;
+        assert!(!scoped.is_empty());
+        assert!(scoped.iter().all(|p| p.base_path.steps.iter().any(|s| s.input_token == "TK5")));
+
+        // TK1 三个池子价格一致，定向扫描不应带出其他代币的机会
+        assert!(router.find_scoped_routes(1000.0, &["TK1".to_string()]).await.is_empty());
+    }
+
+    #[test]
+    fn test_deterministic_cmp_tie_breakers() {
+        use crate::router::ArbitragePath;
+
+        let a = dummy_optimized_path(&["p2", "p1"], 1.0).base_path;
+        let b = dummy_optimized_path(&["p1", "p2"], 1.0).base_path;
+        let c = dummy_optimized_path(&["p1", "p2", "p3"], 1.0).base_path;
+        let d = dummy_optimized_path(&["p9"], 2.0).base_path;
+
+        let mut paths = vec![c, a, d, b];
+        paths.sort_by(ArbitragePath::deterministic_cmp);
+
+        let order: Vec<_> = paths.iter().map(|p| p.signature()).collect();
+        assert_eq!(order, vec!["p9", "p1->p2", "p2->p1", "p1->p2->p3"]);
+    }
+}
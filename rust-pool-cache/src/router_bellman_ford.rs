@@ -7,26 +7,64 @@
  * 1. 将汇率转换为负对数：-ln(rate)
  * 2. 运行Bellman-Ford算法寻找负权环
  * 3. 负权环 = 套利机会（因为乘积>1 → 对数和<0）
+ * 
+ * 🔢 松弛循环只操作 `TokenId` / `PoolId`（距离和父节点按下标存 Vec），
+ * 代币字符串只在生成 `ArbitragePath` 时解析回来
  */
 
+use crate::execution_cost::ExecutionCostModel;
+use crate::interning::{PoolId, TokenId, TokenRegistry};
 use crate::price_cache::PoolPrice;
-use crate::router::{ArbitragePath, ArbitrageType, RouteStep};
-use std::collections::HashMap;
+use crate::router::{hop_price_impact_percent, ArbitragePath, ArbitrageType, PoolReusePolicy, RouteStep};
+use crate::token_alias::{self, TokenAliases};
+use crate::token_graph::{pool_tokens_in, raw_token_in, TokenFilter, TokenGraph};
+use std::collections::HashSet;
+use std::sync::Arc;
 use std::time::Instant;
+use tracing::debug;
 
-/// 图的边（代表一个交易池）
-#[derive(Debug, Clone)]
+/// 图的边（代表一个交易池的一个方向）
+#[derive(Debug, Clone, Copy)]
 struct Edge {
     /// 起始代币
-    from: String,
+    from: TokenId,
     /// 目标代币
-    to: String,
+    to: TokenId,
     /// 负对数权重：-ln(汇率)
     weight: f64,
     /// 原始价格
     original_price: f64,
-    /// 池子信息
-    pool: PoolPrice,
+    /// 池子（`TokenGraph::pools` 中的下标）
+    pool: PoolId,
+    /// 方向性储备量（UI 单位）
+    reserve_in: f64,
+    reserve_out: f64,
+}
+
+/// 驻留后的图：代币按 `TokenGraph::tokens` 的顺序 intern，TokenId 下标与之一一对应
+struct ScanGraph {
+    graph: TokenGraph,
+    registry: TokenRegistry,
+    /// 参与松弛的边（沿用 TokenGraph 的规范排序）
+    edges: Vec<Edge>,
+    /// 通过代币过滤的代币
+    tokens: Vec<TokenId>,
+    /// 按别名表归一后的代币过滤
+    filter: TokenFilter,
+}
+
+impl ScanGraph {
+    fn pool(&self, edge: &Edge) -> &PoolPrice {
+        &self.graph.pools[edge.pool.index()]
+    }
+
+    fn pair(&self, edge: &Edge) -> &str {
+        &self.graph.pairs[edge.pool.index()]
+    }
+
+    fn symbol(&self, token: TokenId) -> &str {
+        self.registry.symbol(token)
+    }
 }
 
 /// 负循环（套利机会）
@@ -34,7 +72,7 @@ struct Edge {
 #[allow(dead_code)]
 struct NegativeCycle {
     /// 循环中的代币序列
-    tokens: Vec<String>,
+    tokens: Vec<TokenId>,
     /// 循环中的边（池子）
     edges: Vec<Edge>,
     /// 总权重（负值表示有利可图）
@@ -42,6 +80,24 @@ struct NegativeCycle {
     total_weight: f64,
 }
 
+impl NegativeCycle {
+    /// 池子ID序列（排序平局决胜用；PoolId 顺序与 pool_id 字典序一致）
+    fn pool_signature(&self) -> impl Iterator<Item = PoolId> + '_ {
+        self.edges.iter().map(|e| e.pool)
+    }
+
+    /// 旋转循环，使其从 `index` 处的代币开始（循环本身不变）
+    fn rotate_to(&mut self, index: usize) {
+        let hops = self.edges.len();
+        if index == 0 || index >= hops {
+            return;
+        }
+        self.edges.rotate_left(index);
+        self.tokens = self.edges.iter().map(|e| e.from).collect();
+        self.tokens.push(self.edges[0].from);
+    }
+}
+
 /// Bellman-Ford 扫描器
 #[derive(Clone)]
 pub struct BellmanFordScanner {
@@ -51,24 +107,69 @@ pub struct BellmanFordScanner {
     min_roi_percent: f64,
     /// 收敛阈值
     convergence_threshold: f64,
+    /// 起点/中间代币过滤
+    token_filter: TokenFilter,
+    /// 🔁 重复使用池子的循环不输出
+    pool_reuse: PoolReusePolicy,
+    /// 💧 美元流动性低于该值的池子不建边（0 = 不过滤）
+    min_liquidity_usd: f64,
+    /// 🪞 代币别名表（建图、代币过滤和 raw_* 字段共用）
+    aliases: Arc<TokenAliases>,
+    /// ⛽ 执行成本模型
+    execution_cost: Arc<ExecutionCostModel>,
 }
 
 impl BellmanFordScanner {
-    /// 创建新的扫描器
-    pub fn new(max_hops: usize, min_roi_percent: f64) -> Self {
+    /// 创建新的扫描器（⛽ 执行成本模型由调用方传入，路径按它定价）
+    pub fn new(max_hops: usize, min_roi_percent: f64, execution_cost: Arc<ExecutionCostModel>) -> Self {
         Self {
             max_hops,
             min_roi_percent,
             convergence_threshold: 0.0001,
+            token_filter: TokenFilter::default(),
+            pool_reuse: PoolReusePolicy::default(),
+            min_liquidity_usd: 0.0,
+            aliases: token_alias::global().clone(),
+            execution_cost,
         }
     }
+
+    /// 设置代币过滤（黑名单 / 非白名单代币不建边，只保留经过起点代币的循环）
+    pub fn with_token_filter(mut self, token_filter: TokenFilter) -> Self {
+        self.token_filter = token_filter;
+        self
+    }
+    
+    /// 🔁 设置池子重复检查（同一池子 / 共用 vault 的包装池）
+    pub fn with_pool_reuse(mut self, pool_reuse: PoolReusePolicy) -> Self {
+        self.pool_reuse = pool_reuse;
+        self
+    }
+    
+    /// 💧 设置池子最低美元流动性（流动性未知的池子保留）
+    pub fn with_min_liquidity_usd(mut self, min_liquidity_usd: f64) -> Self {
+        self.min_liquidity_usd = min_liquidity_usd;
+        self
+    }
+    
+    /// 🪞 设置代币别名表（默认是启动时安装的 `token_alias::global()`）
+    pub fn with_token_aliases(mut self, aliases: Arc<TokenAliases>) -> Self {
+        self.aliases = aliases;
+        self
+    }
+    
+    /// ⛽ 替换执行成本模型（组合路由器的 with_execution_cost 转发到这里）
+    pub fn with_execution_cost(mut self, execution_cost: Arc<ExecutionCostModel>) -> Self {
+        self.execution_cost = execution_cost;
+        self
+    }
     
     /// 扫描所有负循环（套利机会）
     pub fn find_all_cycles(&self, pools: &[PoolPrice], initial_amount: f64) -> Vec<ArbitragePath> {
         // 1. 构建图
-        let (edges, tokens) = self.build_graph(pools);
+        let graph = self.build_graph(pools);
         
-        if tokens.is_empty() || edges.is_empty() {
+        if graph.tokens.is_empty() || graph.edges.is_empty() {
             return Vec::new();
         }
         
@@ -76,116 +177,123 @@ impl BellmanFordScanner {
         // 使用rayon实现CPU多核并行，性能提升2-4x
         use rayon::prelude::*;
         
-        let all_cycles: Vec<NegativeCycle> = tokens
+        let all_cycles: Vec<NegativeCycle> = graph.tokens
             .par_iter()  // 并行迭代器
-            .filter_map(|start_token| {
-                self.detect_cycles_from_token(start_token, &edges, &tokens)
+            .filter_map(|&start_token| {
+                self.detect_cycles_from_token(start_token, &graph)
             })
             .flatten()
             .collect();
         
         // 3. 去重（同一个循环可能从不同起点被发现）
-        let all_cycles = self.deduplicate_cycles(all_cycles);
+        //    先按 (权重, 池子序列) 排序，保证保留的是同一条循环
+        let mut all_cycles = all_cycles;
+        all_cycles.sort_by(|a, b| {
+            a.total_weight.total_cmp(&b.total_weight)
+                .then_with(|| a.pool_signature().cmp(b.pool_signature()))
+        });
+        let mut all_cycles = self.deduplicate_cycles(all_cycles);
+        
+        // 🔥 只保留经过起点代币的循环，并从起点代币开始
+        if !graph.filter.start_tokens.is_empty() {
+            all_cycles.retain_mut(|cycle| {
+                let symbols = cycle.tokens.iter().map(|t| graph.symbol(*t));
+                match graph.filter.anchor_index(symbols) {
+                    Some(index) => {
+                        cycle.rotate_to(index);
+                        true
+                    }
+                    None => false,
+                }
+            });
+        }
         
         // 4. 转换为ArbitragePath
         let mut paths = Vec::new();
         for cycle in all_cycles {
-            if let Some(path) = self.cycle_to_path(cycle, initial_amount) {
+            if let Some(path) = self.cycle_to_path(&graph, cycle, initial_amount) {
                 paths.push(path);
             }
         }
         
         // 5. 过滤和排序
-        paths.retain(|p| p.is_valid() && p.roi_percent >= self.min_roi_percent);
-        paths.sort_by(|a, b| b.score().partial_cmp(&a.score()).unwrap());
+        paths.retain(|p| {
+            p.is_valid() && p.roi_percent >= self.min_roi_percent && !self.pool_reuse.rejects(p, "Bellman-Ford")
+        });
+        paths.sort_by(|a, b| {
+            b.score().total_cmp(&a.score()).then_with(|| ArbitragePath::tie_break(a, b))
+        });
         
         paths
     }
     
     /// 构建图（边和代币列表）
-    fn build_graph(&self, pools: &[PoolPrice]) -> (Vec<Edge>, Vec<String>) {
-        let mut edges = Vec::new();
-        let mut token_set = std::collections::HashSet::new();
-        
-        for pool in pools {
-            // 解析交易对
-            let tokens: Vec<&str> = pool.pair.split('/').collect();
-            if tokens.len() != 2 {
-                continue;
-            }
-            
-            let base = tokens[0].to_string();
-            let quote = tokens[1].to_string();
-            
-            token_set.insert(base.clone());
-            token_set.insert(quote.clone());
-            
-            // 正向边：quote → base (买入base)
-            // 价格表示：1 quote = price base
-            // 所以 quote → base 的汇率是 1/price
-            let rate_quote_to_base = 1.0 / pool.price;
-            let weight_quote_to_base = -rate_quote_to_base.ln();
-            
-            edges.push(Edge {
-                from: quote.clone(),
-                to: base.clone(),
-                weight: weight_quote_to_base,
-                original_price: rate_quote_to_base,  // ← 修复: 使用汇率，不是价格
-                pool: pool.clone(),
-            });
-            
-            // 反向边：base → quote (卖出base)
-            // 汇率是 price
-            let rate_base_to_quote = pool.price;
-            let weight_base_to_quote = -rate_base_to_quote.ln();
-            
-            let mut reverse_pool = pool.clone();
-            reverse_pool.price = rate_base_to_quote;
-            
-            edges.push(Edge {
-                from: base,
-                to: quote,
-                weight: weight_base_to_quote,
-                original_price: rate_base_to_quote,  // ← 这个是正确的
-                pool: reverse_pool,
-            });
-        }
+    ///
+    /// 建图规则与 GET /graph 共用 `token_graph::TokenGraph`；代币按其排序后的顺序驻留
+    fn build_graph(&self, pools: &[PoolPrice]) -> ScanGraph {
+        let graph = TokenGraph::build_in(&self.aliases, pools, self.min_liquidity_usd);
+        let registry = TokenRegistry::from_symbols(&graph.tokens);
+        let filter = self.token_filter.canonicalized(&self.aliases);
+        
+        // 负对数权重：-ln(rate)，边顺序沿用 TokenGraph 的规范排序（松弛顺序决定 parent 链）
+        // 🔥 涉及黑名单 / 非白名单代币的边不建
+        let edges: Vec<Edge> = graph.edges.iter()
+            .filter(|edge| filter.allows_token(&edge.from) && filter.allows_token(&edge.to))
+            .filter_map(|edge| {
+                let pool = graph.pool(edge);
+                let (reserve_in, reserve_out) = self.get_directional_reserves(pool, &edge.from, &edge.to);
+                Some(Edge {
+                    from: registry.get(&edge.from)?,
+                    to: registry.get(&edge.to)?,
+                    weight: -edge.rate.ln(),
+                    original_price: edge.rate,
+                    pool: PoolId::new(edge.pool_index),
+                    reserve_in,
+                    reserve_out,
+                })
+            })
+            .collect();
         
-        let tokens: Vec<String> = token_set.into_iter().collect();
-        (edges, tokens)
+        let tokens: Vec<TokenId> = graph.tokens.iter()
+            .filter(|t| filter.allows_token(t))
+            .filter_map(|t| registry.get(t))
+            .collect();
+        if !filter.is_empty() {
+            debug!(
+                "Bellman-Ford token filter: pruned {} tokens / {} edges",
+                graph.tokens.len() - tokens.len(),
+                graph.edges.len() - edges.len()
+            );
+        }
+        ScanGraph { graph, registry, edges, tokens, filter }
     }
     
     /// 从指定代币运行Bellman-Ford检测负循环
     fn detect_cycles_from_token(
         &self,
-        start_token: &str,
-        edges: &[Edge],
-        tokens: &[String],
+        start_token: TokenId,
+        graph: &ScanGraph,
     ) -> Option<Vec<NegativeCycle>> {
-        let n = tokens.len();
+        let n = graph.tokens.len();
+        let edges = &graph.edges;
         
-        // 初始化距离和父节点
-        let mut dist: HashMap<String, f64> = HashMap::new();
-        let mut parent: HashMap<String, Option<(String, Edge)>> = HashMap::new();
+        // 初始化距离和父节点（按 TokenId 下标；父节点记录松弛它的边在 edges 中的下标）
+        let mut dist = vec![f64::INFINITY; graph.registry.len()];
+        let mut parent: Vec<Option<usize>> = vec![None; graph.registry.len()];
         
-        for token in tokens {
-            dist.insert(token.clone(), f64::INFINITY);
-            parent.insert(token.clone(), None);
-        }
-        
-        dist.insert(start_token.to_string(), 0.0);
+        dist[start_token.index()] = 0.0;
         
         // Bellman-Ford: V-1 轮松弛
         for _iteration in 0..n - 1 {
             let mut updated = false;
             
-            for edge in edges {
-                let d_from = dist.get(&edge.from).copied().unwrap_or(f64::INFINITY);
-                let d_to = dist.get(&edge.to).copied().unwrap_or(f64::INFINITY);
+            for (edge_index, edge) in edges.iter().enumerate() {
+                let d_from = dist[edge.from.index()];
+                let d_to = dist[edge.to.index()];
                 
                 if d_from + edge.weight < d_to - self.convergence_threshold {
-                    dist.insert(edge.to.clone(), d_from + edge.weight);
-                    parent.insert(edge.to.clone(), Some((edge.from.clone(), edge.clone())));
+                    dist[edge.to.index()] = d_from + edge.weight;
+                    parent[edge.to.index()] = Some(edge_index);
                     updated = true;
                 }
             }
@@ -198,11 +306,11 @@ impl BellmanFordScanner {
         
         // 第 V 轮：检测负循环
         let mut negative_cycles = Vec::new();
-        let mut detected_tokens = std::collections::HashSet::new();
+        let mut detected_tokens = HashSet::new();
         
         for edge in edges {
-            let d_from = dist.get(&edge.from).copied().unwrap_or(f64::INFINITY);
-            let d_to = dist.get(&edge.to).copied().unwrap_or(f64::INFINITY);
+            let d_from = dist[edge.from.index()];
+            let d_to = dist[edge.to.index()];
             
             // 如果还能松弛，说明存在负循环
             if d_from + edge.weight < d_to - self.convergence_threshold {
@@ -212,10 +320,10 @@ impl BellmanFordScanner {
                 }
                 
                 // 提取负循环路径
-                if let Some(cycle) = self.extract_cycle(&parent, &edge.to, edge) {
-                    // 检查跳数限制
-                    if cycle.tokens.len() >= 2 && cycle.tokens.len() <= self.max_hops {
-                        detected_tokens.insert(edge.to.clone());
+                if let Some(cycle) = self.extract_cycle(graph, &parent, edge.to, edge) {
+                    // 检查跳数限制（tokens 首尾是同一个代币，跳数 = 边数）
+                    if cycle.edges.len() >= 2 && cycle.edges.len() <= self.max_hops {
+                        detected_tokens.insert(edge.to);
                         negative_cycles.push(cycle);
                     }
                 }
@@ -232,15 +340,16 @@ impl BellmanFordScanner {
     /// 提取负循环路径
     fn extract_cycle(
         &self,
-        parent: &HashMap<String, Option<(String, Edge)>>,
-        start_token: &str,
+        graph: &ScanGraph,
+        parent: &[Option<usize>],
+        start_token: TokenId,
         trigger_edge: &Edge,
     ) -> Option<NegativeCycle> {
         let mut cycle_tokens = Vec::new();
         let mut cycle_edges = Vec::new();
-        let mut visited = std::collections::HashSet::new();
+        let mut visited = HashSet::new();
         
-        let mut current = start_token.to_string();
+        let mut current = start_token;
         
         // 回溯parent链找到循环
         const MAX_CYCLE_ITERATIONS: usize = 20;
@@ -253,22 +362,21 @@ impl BellmanFordScanner {
                 warn!(
                     "Cycle extraction exceeded max iterations ({}), possible graph corruption. Start token: {}",
                     MAX_CYCLE_ITERATIONS,
-                    start_token
+                    graph.symbol(start_token)
                 );
                 return None;  // 安全退出，不返回可能损坏的路径
             }
             
-            if visited.contains(&current) {
+            if !visited.insert(current) {
                 // 找到循环起点
                 break;
             }
             
-            visited.insert(current.clone());
-            
-            if let Some(Some((prev_token, edge))) = parent.get(&current) {
-                cycle_tokens.push(current.clone());
-                cycle_edges.push(edge.clone());
-                current = prev_token.clone();
+            if let Some(edge_index) = parent[current.index()] {
+                let edge = graph.edges[edge_index];
+                cycle_tokens.push(current);
+                cycle_edges.push(edge);
+                current = edge.from;
             } else {
                 break;
             }
@@ -277,10 +385,10 @@ impl BellmanFordScanner {
         }
         
         // 添加触发边闭合循环
-        cycle_tokens.push(trigger_edge.to.clone());
-        cycle_edges.push(trigger_edge.clone());
+        cycle_tokens.push(trigger_edge.to);
+        cycle_edges.push(*trigger_edge);
         
-        // 反转（因为是从后往前追踪的）
+        // 反转（因为是从后往前追踪的）：此时 cycle_edges[i] 是流入 cycle_tokens[i] 的边
         cycle_tokens.reverse();
         cycle_edges.reverse();
         
@@ -296,11 +404,20 @@ impl BellmanFordScanner {
                 .map(|p| p + start_idx + 1);
             
             if let Some(end_idx) = cycle_end {
+                // 边取流入 start_idx+1..=end_idx 的那些：cycle_edges[i] 从 cycle_tokens[i] 流向 cycle_tokens[i+1]
                 cycle_tokens = cycle_tokens[start_idx..=end_idx].to_vec();
-                cycle_edges = cycle_edges[start_idx..end_idx].to_vec();
+                cycle_edges = cycle_edges[start_idx + 1..=end_idx].to_vec();
             }
         }
         
+        // 验证边首尾相接（代币与边对齐，锚定旋转才正确）
+        let connected = cycle_edges.len() + 1 == cycle_tokens.len()
+            && cycle_edges.iter().enumerate()
+                .all(|(i, e)| e.from == cycle_tokens[i] && e.to == cycle_tokens[i + 1]);
+        if !connected {
+            return None;
+        }
+        
         // 计算总权重
         let total_weight: f64 = cycle_edges.iter().map(|e| e.weight).sum();
         
@@ -329,16 +446,14 @@ impl BellmanFordScanner {
     /// 去重负循环（同一个循环可能从不同起点发现）
     fn deduplicate_cycles(&self, cycles: Vec<NegativeCycle>) -> Vec<NegativeCycle> {
         let mut unique_cycles = Vec::new();
-        let mut seen_signatures = std::collections::HashSet::new();
+        let mut seen_signatures = HashSet::new();
         
         for cycle in cycles {
-            // 创建循环签名（代币序列的规范化表示）
-            let mut sorted_tokens = cycle.tokens.clone();
-            sorted_tokens.sort();
-            let signature = sorted_tokens.join("->");
+            // 创建循环签名（排序后的代币序列，TokenId 顺序与符号字典序一致）
+            let mut signature = cycle.tokens.clone();
+            signature.sort();
             
-            if !seen_signatures.contains(&signature) {
-                seen_signatures.insert(signature);
+            if seen_signatures.insert(signature) {
                 unique_cycles.push(cycle);
             }
         }
@@ -346,28 +461,35 @@ impl BellmanFordScanner {
         unique_cycles
     }
     
-    /// 将负循环转换为套利路径
-    fn cycle_to_path(&self, cycle: NegativeCycle, initial_amount: f64) -> Option<ArbitragePath> {
+    /// 将负循环转换为套利路径（在这里把 TokenId / PoolId 解析回字符串）
+    fn cycle_to_path(&self, graph: &ScanGraph, cycle: NegativeCycle, initial_amount: f64) -> Option<ArbitragePath> {
         if cycle.edges.is_empty() || cycle.tokens.is_empty() {
             return None;
         }
         
-        let start_token = cycle.tokens[0].clone();
+        let start_token = graph.symbol(cycle.tokens[0]).to_string();
         let mut current_amount = initial_amount;
         let mut steps = Vec::new();
         
         // 计算每一跳的实际输出
         for edge in &cycle.edges {
+            let pool = graph.pool(edge);
+            let from_token = graph.symbol(edge.from);
+            let to_token = graph.symbol(edge.to);
+            
             // 获取DEX手续费（从pool信息中）
-            let dex_fee = self.get_dex_fee(&edge.pool.dex_name);
+            let dex_fee = self.get_pool_fee(pool);
             
             // 🔥 使用精确AMM恒定乘积公式（x * y = k）
             // 替代线性近似，消除2-5%的大额交易误差
             use crate::dex_interface::amm_calculator;
             
-            let (reserve_in, reserve_out) = self.get_directional_reserves(&edge);
+            let (reserve_in, reserve_out) = (edge.reserve_in, edge.reserve_out);
             
-            let output_amount = amm_calculator::calculate_amm_output_f64(
+            let output_amount = amm_calculator::calculate_hop_output_f64(
+                &pool.pool_id,
+                graph.pair(edge),
+                from_token,
                 current_amount,
                 reserve_in,
                 reserve_out,
@@ -375,15 +497,21 @@ impl BellmanFordScanner {
             );
             
             steps.push(RouteStep {
-                pool_id: edge.pool.pool_id.clone(),
-                dex_name: edge.pool.dex_name.clone(),
-                input_token: edge.from.clone(),
-                output_token: edge.to.clone(),
+                pool_id: pool.pool_id.clone(),
+                dex_name: pool.dex_name.clone(),
+                input_token: from_token.to_string(),
+                output_token: to_token.to_string(),
                 price: edge.original_price,
-                liquidity_base: edge.pool.base_reserve,
-                liquidity_quote: edge.pool.quote_reserve,
+                liquidity_base: pool.base_reserve,
+                liquidity_quote: pool.quote_reserve,
                 expected_input: current_amount,
                 expected_output: output_amount,
+                price_impact_percent: hop_price_impact_percent(
+                    current_amount, output_amount, reserve_in, reserve_out, dex_fee,
+                ),
+                effective_fee_bps: dex_fee * 10_000.0,
+                raw_input_token: raw_token_in(&self.aliases, pool, from_token),
+                raw_output_token: raw_token_in(&self.aliases, pool, to_token),
             });
             
             current_amount = output_amount;
@@ -396,20 +524,15 @@ impl BellmanFordScanner {
         
         // 估算总费用
         let total_dex_fees: f64 = cycle.edges.iter()
-            .map(|e| self.get_dex_fee(&e.pool.dex_name))
+            .map(|e| self.get_pool_fee(graph.pool(e)))
             .sum();
-        let estimated_fees = initial_amount * total_dex_fees;
-        
-        // Gas费估算（根据跳数）
-        let gas_fee = match steps.len() {
-            2 => 0.0001,
-            3 => 0.0002,
-            4 => 0.0003,
-            5 => 0.0004,
-            _ => 0.0005,
-        };
+        let dex_fees = initial_amount * total_dex_fees;
+        
+        // ⛽ 执行成本（签名费 + 优先费 + Jito 小费），按起始代币计
+        // 起始代币无法换算成 SOL 时路径无法定价，不上报
+        let execution_fees = self.execution_cost.cost_in_token(&steps, &start_token)?;
         
-        let net_profit = gross_profit - gas_fee;
+        let net_profit = gross_profit - execution_fees;
         let roi_percent = (net_profit / initial_amount) * 100.0;
         
         // 确定套利类型
@@ -423,38 +546,28 @@ impl BellmanFordScanner {
             arb_type,
             steps,
             start_token: start_token.clone(),
-            end_token: cycle.tokens.last().unwrap().clone(),
+            end_token: graph.symbol(*cycle.tokens.last().unwrap()).to_string(),
             input_amount: initial_amount,
             output_amount: final_amount,
             gross_profit,
-            estimated_fees: estimated_fees + gas_fee,
+            dex_fees,
+            execution_fees,
+            estimated_fees: dex_fees + execution_fees,
             net_profit,
             roi_percent,
             discovered_at: Instant::now(),
         })
     }
     
-    /// 获取DEX手续费
-    fn get_dex_fee(&self, dex_name: &str) -> f64 {
-        match dex_name {
-            s if s.contains("Raydium AMM V4") => 0.0025,
-            s if s.contains("Raydium CLMM") => 0.0001,
-            s if s.contains("Orca") || s.contains("Whirlpool") => 0.0001,
-            s if s.contains("Meteora") => 0.0002,
-            s if s.contains("SolFi") => 0.0030,
-            s if s.contains("AlphaQ") => 0.0001,
-            s if s.contains("HumidiFi") => 0.0010,
-            s if s.contains("Lifinity") => 0.0000,
-            s if s.contains("Stabble") => 0.0004,
-            _ => 0.0025, // 默认0.25%
-        }
+    /// 获取池子手续费（池子级覆盖优先，其次DEX默认值）
+    fn get_pool_fee(&self, pool: &PoolPrice) -> f64 {
+        crate::fee_registry::fee_rate(&pool.pool_id, &pool.dex_name)
     }
     
     /// 获取交易方向的储备量
     /// 
-    /// 根据交易方向（from → to），正确提取输入和输出储备量
-    fn get_directional_reserves(&self, edge: &Edge) -> (f64, f64) {
-        let pool = &edge.pool;
+    /// 根据交易方向（from → to），正确提取输入和输出储备量（建图时每条边算一次）
+    fn get_directional_reserves(&self, pool: &PoolPrice, from: &str, to: &str) -> (f64, f64) {
         let (base_reserve, quote_reserve) = pool.get_reserves();
         let (base_decimals, quote_decimals) = pool.get_decimals();
         
@@ -463,19 +576,16 @@ impl BellmanFordScanner {
         let quote_reserve_f64 = quote_reserve as f64 / 10f64.powi(quote_decimals as i32);
         
         // 解析交易对
-        let pair_tokens: Vec<&str> = pool.pair.split('/').collect();
-        if pair_tokens.len() != 2 {
-            return (base_reserve_f64, quote_reserve_f64);
-        }
-        
-        let base_token = pair_tokens[0];
-        let quote_token = pair_tokens[1];
+        let (base_token, quote_token) = match pool_tokens_in(&self.aliases, pool) {
+            Some(tokens) => tokens,
+            None => return (base_reserve_f64, quote_reserve_f64),
+        };
         
         // 确定交易方向
-        if edge.from == quote_token && edge.to == base_token {
+        if from == quote_token && to == base_token {
             // quote → base (买入base)
             (quote_reserve_f64, base_reserve_f64)
-        } else if edge.from == base_token && edge.to == quote_token {
+        } else if from == base_token && to == quote_token {
             // base → quote (卖出base)
             (base_reserve_f64, quote_reserve_f64)
         } else {
@@ -511,6 +621,211 @@ mod tests {
         let total_weight = -rate.ln() * 3.0;
         assert!(total_weight < 0.0);
     }
+    
+    fn stables_priced() -> Arc<crate::execution_cost::ExecutionCostModel> {
+        crate::router_fixture::priced_execution_cost(&[("USDC", 150.0), ("USDT", 150.0)])
+    }
+    
+    fn tfee_pool(pool_id: &str, price: f64) -> PoolPrice {
+        PoolPrice {
+            base_reserve: 1_000_000_000_000,                      // 1,000,000 TFEE
+            quote_reserve: (1_000_000_000_000.0 * price) as u64, // USDC
+            base_decimals: 6,
+            slot: 0,
+            ..PoolPrice::for_test(pool_id, "TFEE/USDC", price)
+        }
+    }
+    
+    #[test]
+    fn test_transfer_fee_rejects_two_hop_cycle() {
+        use crate::dex_interface::amm_calculator::{calculate_amm_output_f64, calculate_hop_output_f64};
+        use crate::deserializers::spl_token::{TokenProgram, TransferFee, TransferFeeConfig};
+        use crate::mint_decimals_cache::{get_global_mint_cache, init_global_mint_cache, MintInfo};
+        use crate::rpc_manager::RpcManager;
+        use solana_sdk::pubkey::Pubkey;
+        
+        // 两个池子价差 2%：USDC → TFEE → USDC 毛利约 1.5%
+        let pools = vec![tfee_pool("tfee-pool-a", 1.0), tfee_pool("tfee-pool-b", 1.02)];
+        let scanner = BellmanFordScanner::new(4, 0.1, stables_priced());
+        let involves_tfee = |paths: &[ArbitragePath]| paths.iter()
+            .any(|p| p.steps.iter().any(|s| s.pool_id.starts_with("tfee-pool")));
+        assert!(involves_tfee(&scanner.find_all_cycles(&pools, 100.0)));
+        
+        let sell = calculate_hop_output_f64("tfee-pool-b", "TFEE/USDC", "TFEE", 100.0, 1e6, 1.02e6, 0.0);
+        let buy = calculate_hop_output_f64("tfee-pool-a", "TFEE/USDC", "USDC", 100.0, 1e6, 1e6, 0.0);
+        
+        // 合成 1% 转账手续费代币（无上限）
+        init_global_mint_cache(std::sync::Arc::new(RpcManager::unlimited("http://127.0.0.1:8899")).handle("mint_cache"));
+        let cache = get_global_mint_cache().unwrap();
+        let mint = Pubkey::new_unique();
+        let fee = TransferFee { epoch: 0, maximum_fee: u64::MAX, basis_points: 100 };
+        cache.insert_info(mint, MintInfo {
+            decimals: 6,
+            program: TokenProgram::Token2022,
+            transfer_fee: Some(TransferFeeConfig {
+                withheld_amount: 0,
+                older_transfer_fee: fee,
+                newer_transfer_fee: fee,
+            }),
+        });
+        cache.register_token_mint("TFEE", mint);
+        
+        // 卖出 TFEE：转入池子时扣 1%；买入 TFEE：转出池子时扣 1%
+        let sell_with_fee = calculate_hop_output_f64("tfee-pool-b", "TFEE/USDC", "TFEE", 100.0, 1e6, 1.02e6, 0.0);
+        assert!((sell_with_fee - calculate_amm_output_f64(99.0, 1e6, 1.02e6, 0.0)).abs() < 1e-9);
+        assert!(sell_with_fee < sell);
+        let buy_with_fee = calculate_hop_output_f64("tfee-pool-a", "TFEE/USDC", "USDC", 100.0, 1e6, 1e6, 0.0);
+        assert!((buy_with_fee - buy * 0.99).abs() < 1e-9);
+        
+        // 两次转账共扣约 2%，吃掉价差
+        assert!(!involves_tfee(&scanner.find_all_cycles(&pools, 100.0)));
+    }
+    
+    #[test]
+    fn test_token_filter_prunes_and_anchors_cycles() {
+        let two_pools = |base: &str| -> Vec<PoolPrice> {
+            [("a", 1.0), ("b", 1.02)].iter()
+                .map(|(suffix, price)| PoolPrice {
+                    pool_id: format!("{}-pool-{}", base.to_lowercase(), suffix),
+                    pair: format!("{}/USDC", base),
+                    ..tfee_pool("", *price)
+                })
+                .collect()
+        };
+        let mut pools = two_pools("SCAM");
+        pools.extend(two_pools("JUP"));
+        let scanner = BellmanFordScanner::new(4, 0.1, stables_priced());
+        let tokens_of = |paths: &[ArbitragePath]| -> Vec<String> {
+            paths.iter().flat_map(|p| p.steps.iter().map(|s| s.input_token.clone())).collect()
+        };
+        // BF 每次负环检测只报告一个环：未过滤时分别确认两组池子各自成环
+        for base in ["SCAM", "JUP"] {
+            assert!(tokens_of(&scanner.find_all_cycles(&two_pools(base), 100.0)).contains(&base.to_string()));
+        }
+        
+        let filtered = scanner.with_token_filter(TokenFilter {
+            start_tokens: vec!["USDC".to_string()],
+            intermediate_whitelist: None,
+            token_blacklist: vec!["SCAM".to_string()],
+        });
+        let paths = filtered.find_all_cycles(&pools, 100.0);
+        assert!(!paths.is_empty());
+        assert!(!tokens_of(&paths).contains(&"SCAM".to_string()));
+        assert!(paths.iter().all(|p| p.start_token == "USDC" && p.steps[0].input_token == "USDC"));
+    }
+    
+    #[test]
+    fn test_distinct_usdc_mints_are_not_bridged() {
+        use crate::config::PoolConfig;
+        
+        let usdt = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
+        let usdcet = "A9mUU4qviSctJVPJdBJWkb28deg915LYJKrzQ19ji3FM";
+        let pool = |pool_id: &str, price: f64| PoolPrice {
+            dex_name: "Whirlpool (Orca)".to_string(),
+            base_reserve: 1_000_000_000_000,
+            quote_reserve: (1_000_000_000_000.0 * price) as u64,
+            base_decimals: 6,
+            slot: 0,
+            ..PoolPrice::for_test(pool_id, "USDC/USDT", price)
+        };
+        let config = |address: &str, base_mint: &str| PoolConfig {
+            address: address.to_string(),
+            name: "USDC/USDT".to_string(),
+            pair: "USDC/USDT".to_string(),
+            pool_type: "whirlpool".to_string(),
+            fee_bps: None,
+            max_age_ms: None,
+            base_mint: Some(base_mint.to_string()),
+            quote_mint: Some(usdt.to_string()),
+            max_price_jump_percent: None,
+            commitment: None,
+            encoding: None,
+            priority: None,
+        };
+        
+        // 名称相同、没配置 mint：两个 "USDC" 被当成同一个代币，3% 的价差成了循环
+        let unconfigured = vec![pool("bf-usdc-plain-a", 1.0), pool("bf-usdc-plain-b", 1.03)];
+        let scanner = BellmanFordScanner::new(4, 0.1, stables_priced());
+        assert_eq!(scanner.find_all_cycles(&unconfigured, 10.0).len(), 1);
+        
+        // 配置了 mint：桥接 USDC 是另一个节点，没有循环
+        crate::pool_mints::global().load_from_pools(&[
+            config("bf-usdc-native", "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"),
+            config("bf-usdc-bridged", usdcet),
+        ]);
+        let pools = vec![pool("bf-usdc-native", 1.0), pool("bf-usdc-bridged", 1.03)];
+        let graph = TokenGraph::build(&pools);
+        assert_eq!(graph.tokens, vec!["USDC".to_string(), format!("USDC@{}", usdcet), "USDT".to_string()]);
+        let scanner = BellmanFordScanner::new(4, 0.0, stables_priced());
+        assert!(scanner.find_all_cycles(&pools, 10.0).is_empty());
+    }
+    
+    #[test]
+    fn test_fixture_triangle_matches_hand_computed_roi() {
+        use crate::router_fixture::{self, TRIANGLE_HOPS};
+        
+        // 汇率 2 × 4 × 1.015/8 = 1.015，三跳各扣 0.25%：10 × 1.015 × 0.9975³ ≈ 10.0741，再扣执行成本
+        let pools = router_fixture::triangle(1.015).build();
+        let paths = BellmanFordScanner::new(4, 0.1, router_fixture::priced_fixture_tokens())
+            .find_all_cycles(&pools, 10.0);
+        assert_eq!(paths.len(), 1);
+        
+        let path = &paths[0];
+        assert_eq!(router_fixture::hops_from(path, "FXA"), TRIANGLE_HOPS);
+        assert_eq!(path.arb_type, ArbitrageType::Triangle);
+        router_fixture::assert_hop_rates(path, &pools);
+        let expected = router_fixture::expected_roi_percent(&[2.0, 4.0, 1.015 / 8.0], 0.0025, 10.0, path.execution_fees);
+        assert!((path.roi_percent - expected).abs() < 1e-4, "roi {} != {}", path.roi_percent, expected);
+        
+        // 汇率一致（乘积 = 1）时没有负循环
+        let consistent = router_fixture::triangle(1.0).build();
+        assert!(BellmanFordScanner::new(4, 0.0, router_fixture::priced_fixture_tokens())
+            .find_all_cycles(&consistent, 10.0)
+            .is_empty());
+    }
+    
+    #[test]
+    fn test_liquidity_floor_prunes_cycle_through_thin_pool() {
+        use crate::liquidity::LiquidityUsd;
+        use crate::router_fixture;
+        
+        let mut pools = router_fixture::triangle(1.015).build();
+        for pool in pools.iter_mut().filter(|p| p.pool_id == "fx-ca") {
+            pool.liquidity_usd = Some(LiquidityUsd { usd: 300.0, approximate: false });
+        }
+        let cycles = |floor: f64| {
+            BellmanFordScanner::new(4, 0.1, router_fixture::priced_fixture_tokens())
+                .with_min_liquidity_usd(floor)
+                .find_all_cycles(&pools, 10.0)
+                .len()
+        };
+        
+        // $300 的池子低于 $1000 下限：循环消失，下限调低后恢复
+        assert_eq!(cycles(1_000.0), 0);
+        assert_eq!(cycles(250.0), 1);
+        
+        // GET /graph 用同一建图逻辑，记录排除原因
+        let graph = TokenGraph::build_with_min_liquidity(&pools, 1_000.0);
+        let excluded: Vec<(&str, &str)> = graph.excluded.iter()
+            .map(|e| (e.pool_id.as_str(), e.reason.as_str()))
+            .collect();
+        assert_eq!(excluded, vec![("fx-ca", "low_liquidity")]);
+    }
+    
+    #[test]
+    fn test_arbitrage_free_graphs_report_nothing() {
+        use crate::router_fixture::{self, FixtureRng, RateGraph};
+        
+        let scanner = BellmanFordScanner::new(6, 0.0, router_fixture::priced_fixture_tokens());
+        for seed in 0..64 {
+            let mut rng = FixtureRng::new(seed);
+            let pools = RateGraph::arbitrage_free(&mut rng, 5, 12).build();
+            let paths = scanner.find_all_cycles(&pools, 10.0);
+            assert!(
+                paths.is_empty(),
+                "seed {}: false positive {} ({:.6}% ROI)",
+                seed, paths[0].signature(), paths[0].roi_percent
+            );
+        }
+    }
 }
-
-
@@ -7,19 +7,29 @@
  * - 按层级遍历，优先发现短路径
  * - 早期剪枝，减少不必要的计算
  * - 路径去重，避免重复探索
+ * 
+ * 🔢 扩展路径只操作 `TokenId` / `PoolId`（见 `interning`），
+ * 代币字符串只在生成 `ArbitragePath` 时解析回来
  */
 
+use crate::execution_cost::ExecutionCostModel;
+use crate::interning::{path_signature, PoolId, TokenId, TokenRegistry};
+use crate::liquidity;
 use crate::price_cache::PoolPrice;
-use crate::router::{ArbitragePath, ArbitrageType, RouteStep};
+use crate::router::{hop_price_impact_percent, ArbitragePath, ArbitrageType, PoolReusePolicy, RouteStep};
 use crate::dex_interface::amm_calculator;
+use crate::token_alias::{self, TokenAliases};
+use crate::token_graph::{pool_tokens_in, raw_token_in, TokenFilter};
 use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
 use std::time::Instant;
+use tracing::debug;
 
 /// BFS路径节点
 #[derive(Debug, Clone)]
 struct PathNode {
     /// 代币序列
-    tokens: Vec<String>,
+    tokens: Vec<TokenId>,
     /// 当前金额
     amount: f64,
     /// 已经过的池子
@@ -28,12 +38,51 @@ struct PathNode {
     total_fees: f64,
 }
 
-/// 池子边信息
-#[derive(Debug, Clone)]
+impl PathNode {
+    /// 路径签名（用于去重）：代币序列 + 池子序列的哈希
+    fn signature(&self) -> u64 {
+        path_signature(&self.tokens, self.edges.iter().map(|e| e.pool))
+    }
+}
+
+/// 池子边信息（Copy：扩展路径时不再克隆 PoolPrice 和代币字符串）
+#[derive(Debug, Clone, Copy)]
 struct PoolEdge {
-    pool: PoolPrice,
-    from_token: String,
-    to_token: String,
+    pool: PoolId,
+    from_token: TokenId,
+    to_token: TokenId,
+    /// 方向性储备量（UI 单位）
+    reserve_in: f64,
+    reserve_out: f64,
+    /// 手续费率（建图时从 fee_registry 读取一次）
+    fee: f64,
+}
+
+/// 一次扫描的驻留图
+struct ScanGraph {
+    registry: TokenRegistry,
+    /// 可路由的池子（按 pool_id 排序，下标即 PoolId）
+    pools: Vec<PoolPrice>,
+    /// 池子的规范交易对 "BASE/QUOTE"（按 PoolId 索引，逐跳报价不再归一别名）
+    pairs: Vec<String>,
+    /// 按 TokenId 索引的出边：池子顺序，同一池子先 quote → base 再 base → quote
+    adjacency: Vec<Vec<PoolEdge>>,
+    /// 起点代币（按符号排序）
+    start_tokens: Vec<TokenId>,
+}
+
+impl ScanGraph {
+    fn pool(&self, edge: &PoolEdge) -> &PoolPrice {
+        &self.pools[edge.pool.index()]
+    }
+
+    fn pair(&self, edge: &PoolEdge) -> &str {
+        &self.pairs[edge.pool.index()]
+    }
+
+    fn symbol(&self, token: TokenId) -> &str {
+        self.registry.symbol(token)
+    }
 }
 
 /// BFS扫描器
@@ -43,54 +92,191 @@ pub struct BfsScanner {
     max_depth: usize,
     /// 最小ROI阈值
     min_roi_percent: f64,
-    /// 早期剪枝阈值（如果当前利润已经<此值，提前放弃）
-    early_stop_threshold: f64,
+    /// 起点/中间代币过滤
+    token_filter: TokenFilter,
+    /// 🔁 重复使用池子的环路不输出
+    pool_reuse: PoolReusePolicy,
+    /// 💧 美元流动性低于该值的池子不建边（0 = 不过滤）
+    min_liquidity_usd: f64,
+    /// 🪞 代币别名表（建图、代币过滤和 raw_* 字段共用）
+    aliases: Arc<TokenAliases>,
+    /// ⛽ 执行成本模型
+    execution_cost: Arc<ExecutionCostModel>,
 }
 
 impl BfsScanner {
-    /// 创建新的BFS扫描器
-    pub fn new(max_depth: usize, min_roi_percent: f64) -> Self {
+    /// 创建新的BFS扫描器（⛽ 执行成本模型由调用方传入，路径按它定价）
+    pub fn new(max_depth: usize, min_roi_percent: f64, execution_cost: Arc<ExecutionCostModel>) -> Self {
         Self {
             max_depth,
             min_roi_percent,
-            early_stop_threshold: -0.5, // 如果亏损>0.5%，提前剪枝
+            token_filter: TokenFilter::default(),
+            pool_reuse: PoolReusePolicy::default(),
+            min_liquidity_usd: 0.0,
+            aliases: token_alias::global().clone(),
+            execution_cost,
         }
     }
+
+    /// 设置代币过滤（只从起点代币发起搜索，跳过黑名单 / 非白名单代币的池子）
+    pub fn with_token_filter(mut self, token_filter: TokenFilter) -> Self {
+        self.token_filter = token_filter;
+        self
+    }
+    
+    /// 🔁 设置池子重复检查（同一池子 / 共用 vault 的包装池）
+    pub fn with_pool_reuse(mut self, pool_reuse: PoolReusePolicy) -> Self {
+        self.pool_reuse = pool_reuse;
+        self
+    }
+    
+    /// 💧 设置池子最低美元流动性（流动性未知的池子保留）
+    pub fn with_min_liquidity_usd(mut self, min_liquidity_usd: f64) -> Self {
+        self.min_liquidity_usd = min_liquidity_usd;
+        self
+    }
+    
+    /// 🪞 设置代币别名表（默认是启动时安装的 `token_alias::global()`）
+    pub fn with_token_aliases(mut self, aliases: Arc<TokenAliases>) -> Self {
+        self.aliases = aliases;
+        self
+    }
+    
+    /// ⛽ 替换执行成本模型（组合路由器的 with_execution_cost 转发到这里）
+    pub fn with_execution_cost(mut self, execution_cost: Arc<ExecutionCostModel>) -> Self {
+        self.execution_cost = execution_cost;
+        self
+    }
     
     /// 从所有代币发现套利机会
     pub fn find_all_opportunities(&self, pools: &[PoolPrice], initial_amount: f64) -> Vec<ArbitragePath> {
-        let mut all_paths = Vec::new();
+        let graph = self.build_graph(pools);
+        self.search_from(&graph, &graph.start_tokens, initial_amount)
+    }
+
+    /// 🎯 定向扫描：只从 `seeds` 中的代币发起BFS（只返回经过这些代币的环路）
+    ///
+    /// 种子同样受代币过滤约束：不是起点代币的种子被忽略，这部分环路留给全量扫描。
+    pub fn find_opportunities_from(
+        &self,
+        pools: &[PoolPrice],
+        initial_amount: f64,
+        seeds: &[String],
+    ) -> Vec<ArbitragePath> {
+        let graph = self.build_graph(pools);
+        let seeds: Vec<TokenId> = graph.start_tokens.iter()
+            .copied()
+            .filter(|t| seeds.iter().any(|s| s == graph.symbol(*t)))
+            .collect();
+        self.search_from(&graph, &seeds, initial_amount)
+    }
+
+    /// 排序池子、应用代币过滤并驻留代币，建出本次扫描的邻接表
+    fn build_graph(&self, pools: &[PoolPrice]) -> ScanGraph {
+        // 🎯 确定性：按 pool_id 排序，边的扩展顺序与缓存迭代顺序无关
+        let mut pools = pools.to_vec();
+        pools.sort_by(|a, b| a.pool_id.cmp(&b.pool_id));
+        
+        // 💧 流动性下限：吃不下交易金额的浅池不建边
+        if self.min_liquidity_usd > 0.0 {
+            let before = pools.len();
+            pools.retain(|p| liquidity::meets_floor(p, self.min_liquidity_usd));
+            if pools.len() < before {
+                debug!("BFS liquidity floor ${:.0}: pruned {} pools", self.min_liquidity_usd, before - pools.len());
+            }
+        }
+        
+        // 🔥 代币过滤：去掉涉及被排除代币的池子，只从起点代币发起BFS
+        let filter = self.token_filter.canonicalized(&self.aliases);
+        let unfiltered = (!filter.is_empty())
+            .then(|| (self.extract_unique_tokens(&pools).len(), pools.len()));
+        if unfiltered.is_some() {
+            pools.retain(|p| filter.allows_pool(&self.aliases, p));
+        }
+        
+        // 🔢 按排序后的代币驻留：TokenId 的顺序与符号字典序一致
+        let tokens = self.extract_unique_tokens(&pools);
+        let registry = TokenRegistry::from_symbols(&tokens);
+        let start_tokens: Vec<TokenId> = tokens.iter()
+            .filter(|t| filter.is_start_token(t))
+            .filter_map(|t| registry.get(t))
+            .collect();
+        if let Some((total_tokens, total_pools)) = unfiltered {
+            debug!(
+                "BFS token filter: pruned {} tokens / {} edges, seeding from {} of {} tokens",
+                total_tokens - tokens.len(),
+                (total_pools - pools.len()) * 2,
+                start_tokens.len(),
+                total_tokens
+            );
+        }
+        
+        let mut adjacency = vec![Vec::new(); registry.len()];
+        let mut routable = Vec::with_capacity(pools.len());
+        let mut pairs = Vec::with_capacity(pools.len());
+        for pool in pools {
+            let Some((base, quote)) = pool_tokens_in(&self.aliases, &pool) else { continue };
+            let pair = format!("{}/{}", base, quote);
+            let (Some(base), Some(quote)) = (registry.get(&base), registry.get(&quote)) else { continue };
+            
+            let pool_id = PoolId::new(routable.len());
+            let (base_reserve, quote_reserve) = self.ui_reserves(&pool);
+            let fee = crate::fee_registry::fee_rate(&pool.pool_id, &pool.dex_name);
+            
+            // 正向：quote → base（买入base）
+            adjacency[quote.index()].push(PoolEdge {
+                pool: pool_id,
+                from_token: quote,
+                to_token: base,
+                reserve_in: quote_reserve,
+                reserve_out: base_reserve,
+                fee,
+            });
+            // 反向：base → quote（卖出base）
+            adjacency[base.index()].push(PoolEdge {
+                pool: pool_id,
+                from_token: base,
+                to_token: quote,
+                reserve_in: base_reserve,
+                reserve_out: quote_reserve,
+                fee,
+            });
+            routable.push(pool);
+            pairs.push(pair);
+        }
         
-        // 构建代币集合
-        let tokens = self.extract_unique_tokens(pools);
+        ScanGraph { registry, pools: routable, pairs, adjacency, start_tokens }
+    }
+
+    /// 对每个起点代币进行BFS，确定性排序后去重
+    fn search_from(&self, graph: &ScanGraph, tokens: &[TokenId], initial_amount: f64) -> Vec<ArbitragePath> {
+        let mut all_paths = Vec::new();
         
         // 对每个代币作为起点进行BFS
-        for start_token in &tokens {
-            let paths = self.bfs_from_token(start_token, pools, initial_amount);
+        for &start_token in tokens {
+            let paths = self.bfs_from_token(graph, start_token, initial_amount);
             all_paths.extend(paths);
         }
         
-        // 去重并排序
-        all_paths = self.deduplicate_paths(all_paths);
-        all_paths.sort_by(|a, b| b.roi_percent.partial_cmp(&a.roi_percent).unwrap());
-        
-        all_paths
+        // 先确定性排序再去重（重复路径中保留排序靠前的一条）
+        all_paths.sort_by(|(_, a), (_, b)| ArbitragePath::deterministic_cmp(a, b));
+        self.deduplicate_paths(all_paths)
     }
     
-    /// 从指定代币开始BFS搜索
+    /// 从指定代币开始BFS搜索，返回 (路径签名, 套利路径)
     fn bfs_from_token(
         &self,
-        start_token: &str,
-        pools: &[PoolPrice],
+        graph: &ScanGraph,
+        start_token: TokenId,
         initial_amount: f64,
-    ) -> Vec<ArbitragePath> {
+    ) -> Vec<(u64, ArbitragePath)> {
         let mut results = Vec::new();
         let mut queue = VecDeque::new();
         let mut visited_paths = HashSet::new();
         
         // 初始化：起点
         queue.push_back(PathNode {
-            tokens: vec![start_token.to_string()],
+            tokens: vec![start_token],
             amount: initial_amount,
             edges: Vec::new(),
             total_fees: 0.0,
@@ -100,35 +286,30 @@ impl BfsScanner {
         while let Some(current_path) = queue.pop_front() {
             let depth = current_path.tokens.len() - 1;
             
-            // 🔥 深度限制剪枝
-            if depth >= self.max_depth {
-                continue;
-            }
+            // ⚠️ 不做中途ROI剪枝：回到起点前 amount 是中间代币的数量，
+            // 与 initial_amount（起点代币）不可比，ROI 只在闭环时计算
             
-            // 🔥 早期剪枝：如果当前亏损严重，不再扩展
-            if depth > 0 {
-                let current_roi = ((current_path.amount - initial_amount) / initial_amount) * 100.0;
-                if current_roi < self.early_stop_threshold {
-                    continue;
-                }
-            }
-            
-            let current_token = current_path.tokens.last().unwrap();
+            let current_token = *current_path.tokens.last().unwrap();
             
             // 🔥 检查是否回到起点（找到套利循环）
             if depth >= 2 && current_token == start_token {
                 // 计算最终利润
-                if let Some(arb_path) = self.convert_to_arbitrage_path(&current_path, initial_amount) {
-                    if arb_path.roi_percent >= self.min_roi_percent {
-                        results.push(arb_path);
+                if let Some(arb_path) = self.convert_to_arbitrage_path(graph, &current_path, initial_amount) {
+                    if arb_path.roi_percent >= self.min_roi_percent && !self.pool_reuse.rejects(&arb_path, "BFS") {
+                        results.push((current_path.signature(), arb_path));
                     }
                 }
                 continue;  // 不再扩展
             }
             
+            // 🔥 深度限制剪枝（放在闭环检查之后：恰好 max_depth 跳的环路也要输出）
+            if depth >= self.max_depth {
+                continue;
+            }
+            
             // 🔥 扩展路径：尝试所有可能的下一跳
-            for edge in self.get_next_edges(current_token, pools, &current_path.tokens) {
-                let next_token = edge.to_token.clone();
+            for edge in &graph.adjacency[current_token.index()] {
+                let next_token = edge.to_token;
                 
                 // 🔥 避免立即回头（例如 A→B→A，至少要3跳才能形成套利）
                 if depth >= 1 && next_token == start_token && depth < 2 {
@@ -141,26 +322,25 @@ impl BfsScanner {
                 }
                 
                 // 计算下一跳的金额
-                let (reserve_in, reserve_out) = self.get_directional_reserves(&edge);
-                let fee = amm_calculator::get_dex_fee_rate(&edge.pool.dex_name);
-                
-                let next_amount = amm_calculator::calculate_amm_output_f64(
+                let pool = graph.pool(edge);
+                let next_amount = amm_calculator::calculate_hop_output_f64(
+                    &pool.pool_id,
+                    graph.pair(edge),
+                    graph.symbol(edge.from_token),
                     current_path.amount,
-                    reserve_in,
-                    reserve_out,
-                    fee,
+                    edge.reserve_in,
+                    edge.reserve_out,
+                    edge.fee,
                 );
                 
                 // 🔥 路径签名去重
                 let mut new_path = current_path.clone();
-                new_path.tokens.push(next_token.clone());
+                new_path.tokens.push(next_token);
                 new_path.amount = next_amount;
-                new_path.edges.push(edge);
-                new_path.total_fees += fee * current_path.amount;
+                new_path.edges.push(*edge);
+                new_path.total_fees += edge.fee * current_path.amount;
                 
-                let path_signature = self.generate_path_signature(&new_path);
-                if !visited_paths.contains(&path_signature) {
-                    visited_paths.insert(path_signature);
+                if visited_paths.insert(new_path.signature()) {
                     queue.push_back(new_path);
                 }
             }
@@ -169,83 +349,21 @@ impl BfsScanner {
         results
     }
     
-    /// 获取从当前代币出发的所有可能的边
-    fn get_next_edges(
-        &self,
-        current_token: &str,
-        pools: &[PoolPrice],
-        _visited_tokens: &[String],
-    ) -> Vec<PoolEdge> {
-        let mut edges = Vec::new();
-        
-        for pool in pools {
-            let pair_tokens: Vec<&str> = pool.pair.split('/').collect();
-            if pair_tokens.len() != 2 {
-                continue;
-            }
-            
-            let base = pair_tokens[0];
-            let quote = pair_tokens[1];
-            
-            // 正向：current_token → 其他代币
-            if current_token == quote {
-                edges.push(PoolEdge {
-                    pool: pool.clone(),
-                    from_token: quote.to_string(),
-                    to_token: base.to_string(),
-                });
-            }
-            
-            if current_token == base {
-                edges.push(PoolEdge {
-                    pool: pool.clone(),
-                    from_token: base.to_string(),
-                    to_token: quote.to_string(),
-                });
-            }
-        }
-        
-        edges
-    }
-    
-    /// 获取方向性储备量
-    fn get_directional_reserves(&self, edge: &PoolEdge) -> (f64, f64) {
-        let (base_reserve, quote_reserve) = edge.pool.get_reserves();
-        let (base_decimals, quote_decimals) = edge.pool.get_decimals();
-        
-        let base_reserve_f64 = base_reserve as f64 / 10f64.powi(base_decimals as i32);
-        let quote_reserve_f64 = quote_reserve as f64 / 10f64.powi(quote_decimals as i32);
-        
-        let pair_tokens: Vec<&str> = edge.pool.pair.split('/').collect();
-        if pair_tokens.len() != 2 {
-            return (base_reserve_f64, quote_reserve_f64);
-        }
-        
-        let base_token = pair_tokens[0];
-        let quote_token = pair_tokens[1];
-        
-        if edge.from_token == quote_token && edge.to_token == base_token {
-            (quote_reserve_f64, base_reserve_f64)
-        } else if edge.from_token == base_token && edge.to_token == quote_token {
-            (base_reserve_f64, quote_reserve_f64)
-        } else {
-            (base_reserve_f64, quote_reserve_f64)
-        }
-    }
-    
-    /// 生成路径签名（用于去重）
-    fn generate_path_signature(&self, path: &PathNode) -> String {
-        // 使用代币序列 + 池子ID序列作为签名
-        let token_part = path.tokens.join("->");
-        let pool_ids: Vec<String> = path.edges.iter().map(|e| e.pool.pool_id.clone()).collect();
-        let pool_part = pool_ids.join("|");
+    /// 池子储备量（UI 单位）
+    fn ui_reserves(&self, pool: &PoolPrice) -> (f64, f64) {
+        let (base_reserve, quote_reserve) = pool.get_reserves();
+        let (base_decimals, quote_decimals) = pool.get_decimals();
         
-        format!("{}::{}", token_part, pool_part)
+        (
+            base_reserve as f64 / 10f64.powi(base_decimals as i32),
+            quote_reserve as f64 / 10f64.powi(quote_decimals as i32),
+        )
     }
     
-    /// 转换为标准套利路径格式
+    /// 转换为标准套利路径格式（在这里把 TokenId / PoolId 解析回字符串）
     fn convert_to_arbitrage_path(
         &self,
+        graph: &ScanGraph,
         path_node: &PathNode,
         initial_amount: f64,
     ) -> Option<ArbitragePath> {
@@ -257,10 +375,15 @@ impl BfsScanner {
         let mut current_amount = initial_amount;
         
         for edge in &path_node.edges {
-            let (reserve_in, reserve_out) = self.get_directional_reserves(edge);
-            let fee = amm_calculator::get_dex_fee_rate(&edge.pool.dex_name);
+            let pool = graph.pool(edge);
+            let from_token = graph.symbol(edge.from_token);
+            let to_token = graph.symbol(edge.to_token);
+            let (reserve_in, reserve_out, fee) = (edge.reserve_in, edge.reserve_out, edge.fee);
             
-            let output_amount = amm_calculator::calculate_amm_output_f64(
+            let output_amount = amm_calculator::calculate_hop_output_f64(
+                &pool.pool_id,
+                graph.pair(edge),
+                from_token,
                 current_amount,
                 reserve_in,
                 reserve_out,
@@ -268,15 +391,21 @@ impl BfsScanner {
             );
             
             steps.push(RouteStep {
-                pool_id: edge.pool.pool_id.clone(),
-                dex_name: edge.pool.dex_name.clone(),
-                input_token: edge.from_token.clone(),
-                output_token: edge.to_token.clone(),
-                price: edge.pool.price,
-                liquidity_base: edge.pool.base_reserve,
-                liquidity_quote: edge.pool.quote_reserve,
+                pool_id: pool.pool_id.clone(),
+                dex_name: pool.dex_name.clone(),
+                input_token: from_token.to_string(),
+                output_token: to_token.to_string(),
+                price: pool.price,
+                liquidity_base: pool.base_reserve,
+                liquidity_quote: pool.quote_reserve,
                 expected_input: current_amount,
                 expected_output: output_amount,
+                price_impact_percent: hop_price_impact_percent(
+                    current_amount, output_amount, reserve_in, reserve_out, fee,
+                ),
+                effective_fee_bps: fee * 10_000.0,
+                raw_input_token: raw_token_in(&self.aliases, pool, from_token),
+                raw_output_token: raw_token_in(&self.aliases, pool, to_token),
             });
             
             current_amount = output_amount;
@@ -284,8 +413,10 @@ impl BfsScanner {
         
         let final_amount = current_amount;
         let gross_profit = final_amount - initial_amount;
-        let gas_estimate = 0.0001 * steps.len() as f64; // Gas费随跳数增加
-        let net_profit = gross_profit - gas_estimate;
+        let start_token = graph.symbol(path_node.tokens[0]).to_string();
+        // 起始代币无法换算成 SOL 时路径无法定价，不上报
+        let execution_fees = self.execution_cost.cost_in_token(&steps, &start_token)?;
+        let net_profit = gross_profit - execution_fees;
         let roi_percent = (net_profit / initial_amount) * 100.0;
         
         // 判断套利类型
@@ -298,12 +429,14 @@ impl BfsScanner {
         Some(ArbitragePath {
             arb_type,
             steps,
-            start_token: path_node.tokens.first().unwrap().clone(),
-            end_token: path_node.tokens.last().unwrap().clone(),
+            start_token,
+            end_token: graph.symbol(*path_node.tokens.last().unwrap()).to_string(),
             input_amount: initial_amount,
             output_amount: final_amount,
             gross_profit,
-            estimated_fees: path_node.total_fees + gas_estimate,
+            dex_fees: path_node.total_fees,
+            execution_fees,
+            estimated_fees: path_node.total_fees + execution_fees,
             net_profit,
             roi_percent,
             discovered_at: Instant::now(),
@@ -315,42 +448,25 @@ impl BfsScanner {
         let mut tokens = HashSet::new();
         
         for pool in pools {
-            let pair_tokens: Vec<&str> = pool.pair.split('/').collect();
-            if pair_tokens.len() == 2 {
-                tokens.insert(pair_tokens[0].to_string());
-                tokens.insert(pair_tokens[1].to_string());
+            if let Some((base, quote)) = pool_tokens_in(&self.aliases, pool) {
+                tokens.insert(base);
+                tokens.insert(quote);
             }
         }
         
-        tokens.into_iter().collect()
+        let mut tokens: Vec<String> = tokens.into_iter().collect();
+        tokens.sort();
+        tokens
     }
     
-    /// 路径去重
-    fn deduplicate_paths(&self, paths: Vec<ArbitragePath>) -> Vec<ArbitragePath> {
+    /// 路径去重（签名 = 代币序列 + 池子序列的哈希，同一次扫描内可比较）
+    fn deduplicate_paths(&self, paths: Vec<(u64, ArbitragePath)>) -> Vec<ArbitragePath> {
         let mut seen = HashSet::new();
-        let mut unique_paths = Vec::new();
-        
-        for path in paths {
-            let signature = self.generate_arbitrage_signature(&path);
-            if !seen.contains(&signature) {
-                seen.insert(signature);
-                unique_paths.push(path);
-            }
-        }
         
-        unique_paths
-    }
-    
-    /// 生成套利路径签名
-    fn generate_arbitrage_signature(&self, path: &ArbitragePath) -> String {
-        let tokens: Vec<String> = path.steps.iter()
-            .map(|s| s.input_token.clone())
-            .collect();
-        let pools: Vec<String> = path.steps.iter()
-            .map(|s| s.pool_id.clone())
-            .collect();
-        
-        format!("{}::{}", tokens.join("->"), pools.join("|"))
+        paths.into_iter()
+            .filter(|(signature, _)| seen.insert(*signature))
+            .map(|(_, path)| path)
+            .collect()
     }
 }
 
@@ -360,26 +476,111 @@ mod tests {
     
     #[test]
     fn test_path_signature_uniqueness() {
-        let scanner = BfsScanner::new(4, 0.1);
+        let mut registry = TokenRegistry::new();
+        let (sol, usdc, usdt) = (registry.intern("SOL"), registry.intern("USDC"), registry.intern("USDT"));
         
         let path1 = PathNode {
-            tokens: vec!["SOL".to_string(), "USDC".to_string()],
+            tokens: vec![sol, usdc],
             amount: 100.0,
             edges: vec![],
             total_fees: 0.0,
         };
         
         let path2 = PathNode {
-            tokens: vec!["SOL".to_string(), "USDT".to_string()],
+            tokens: vec![sol, usdt],
             amount: 100.0,
             edges: vec![],
             total_fees: 0.0,
         };
         
-        let sig1 = scanner.generate_path_signature(&path1);
-        let sig2 = scanner.generate_path_signature(&path2);
+        assert_ne!(path1.signature(), path2.signature());
+        assert_eq!(path1.signature(), path1.clone().signature());
+    }
+    
+    #[test]
+    fn test_fixture_triangle_matches_hand_computed_roi() {
+        use crate::router_fixture::{self, TRIANGLE_HOPS};
+        
+        // 汇率乘积 1.015，三跳各扣 0.25%，再扣执行成本
+        let pools = router_fixture::triangle(1.015).build();
+        let paths = BfsScanner::new(4, 0.1, router_fixture::priced_fixture_tokens())
+            .find_all_opportunities(&pools, 10.0);
+        assert!(paths.iter().all(|p| router_fixture::hops_from(p, "FXA") == TRIANGLE_HOPS));
+        
+        let path = paths.iter().find(|p| p.start_token == "FXA").expect("cycle from FXA not found");
+        assert_eq!(router_fixture::hops_from(path, "FXA"), TRIANGLE_HOPS);
+        router_fixture::assert_hop_rates(path, &pools);
+        let expected = router_fixture::expected_roi_percent(&[2.0, 4.0, 1.015 / 8.0], 0.0025, 10.0, path.execution_fees);
+        assert!((path.roi_percent - expected).abs() < 1e-4, "roi {} != {}", path.roi_percent, expected);
+        
+        let consistent = router_fixture::triangle(1.0).build();
+        assert!(BfsScanner::new(4, 0.0, router_fixture::priced_fixture_tokens())
+            .find_all_opportunities(&consistent, 10.0)
+            .is_empty());
+    }
+    
+    #[test]
+    fn test_seeded_scan_only_starts_from_scope_tokens() {
+        use crate::router_fixture;
+        
+        let pools = router_fixture::triangle(1.015).build();
+        let scanner = BfsScanner::new(4, 0.1, router_fixture::priced_fixture_tokens());
+        let full: Vec<String> = scanner.find_all_opportunities(&pools, 10.0).iter()
+            .map(|p| p.signature().to_string())
+            .collect();
         
-        assert_ne!(sig1, sig2);
+        let scoped = scanner.find_opportunities_from(&pools, 10.0, &["FXB".to_string()]);
+        assert!(!scoped.is_empty());
+        assert!(scoped.iter().all(|p| p.start_token == "FXB"));
+        assert!(scoped.iter().all(|p| full.contains(&p.signature().to_string())));
+        
+        // 不在图里的代币没有种子
+        assert!(scanner.find_opportunities_from(&pools, 10.0, &["FXZ".to_string()]).is_empty());
+    }
+    
+    #[test]
+    fn test_liquidity_floor_prunes_cycle_through_thin_pool() {
+        use crate::liquidity::LiquidityUsd;
+        use crate::router_fixture;
+        
+        // fx-ca 只有 $300 流动性；其余池子流动性未知，不受下限影响
+        let mut pools = router_fixture::triangle(1.015).build();
+        for pool in pools.iter_mut().filter(|p| p.pool_id == "fx-ca") {
+            pool.liquidity_usd = Some(LiquidityUsd { usd: 300.0, approximate: false });
+        }
+        let cycles = |floor: f64| {
+            BfsScanner::new(4, 0.1, router_fixture::priced_fixture_tokens())
+                .with_min_liquidity_usd(floor)
+                .find_all_opportunities(&pools, 10.0)
+                .len()
+        };
+        
+        assert!(cycles(0.0) > 0);
+        assert_eq!(cycles(1_000.0), 0);
+        assert_eq!(cycles(250.0), cycles(0.0));
+    }
+    
+    #[test]
+    fn test_arbitrage_free_graphs_stay_below_fee_floor() {
+        use crate::router_fixture::{self, FixtureRng, RateGraph};
+        
+        // min_roi 放到 -100%：亏损循环也会报告，逐条检查 ROI 不高于只付手续费的下限
+        let scanner = BfsScanner::new(4, -100.0, router_fixture::priced_fixture_tokens());
+        let mut checked = 0;
+        for seed in 0..64 {
+            let mut rng = FixtureRng::new(seed);
+            let pools = RateGraph::arbitrage_free(&mut rng, 5, 12).build();
+            for path in scanner.find_all_opportunities(&pools, 10.0) {
+                let floor = router_fixture::fee_floor_roi_percent(&path);
+                assert!(
+                    path.roi_percent <= floor + 1e-9,
+                    "seed {}: {} reports {:.6}% ROI above fee floor {:.6}%",
+                    seed, path.signature(), path.roi_percent, floor
+                );
+                checked += 1;
+            }
+        }
+        assert!(checked > 0, "no cycles survived pruning, the property is vacuous");
     }
 }
 
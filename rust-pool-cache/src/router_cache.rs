@@ -7,10 +7,17 @@
  * - 常见交易对查询延迟降低60-80%
  * - 减少重复计算，节省CPU资源
  * - TTL机制确保数据新鲜度
+ * 
+ * 路径骨架：完整扫描发现的路径按经过的池子集合缓存。下次扫描先沿已知的边
+ * 按当前价格重算输出（不搜索），仍达到ROI阈值就直接返回；池子价格相对缓存时
+ * 变动超过阈值（PriceUpdateEvent）时，经过它的骨架失效。
  */
 
-use crate::router::ArbitragePath;
-use std::collections::HashMap;
+use crate::dex_interface::amm_calculator;
+use crate::price_cache::{PoolPrice, PriceUpdateEvent};
+use crate::router::{hop_price_impact_percent, ArbitragePath};
+use crate::token_graph::pool_tokens;
+use std::collections::{HashMap, HashSet};
 use std::time::{Duration, Instant};
 
 /// 缓存键
@@ -28,6 +35,15 @@ struct CacheEntry {
     hit_count: usize,
 }
 
+/// 路径骨架条目（同一组池子可能有正反两个方向）
+#[derive(Debug, Clone)]
+struct SkeletonEntry {
+    paths: Vec<ArbitragePath>,
+    /// 缓存时各池子的价格，用于判断价格变动是否超过失效阈值
+    reference_prices: HashMap<String, f64>,
+    cached_at: Instant,
+}
+
 /// 路径缓存器
 pub struct RouterCache {
     /// 缓存存储
@@ -38,6 +54,14 @@ pub struct RouterCache {
     max_entries: usize,
     /// 统计信息
     stats: CacheStats,
+    /// 🔥 路径骨架：池子集合 -> 条目
+    skeletons: HashMap<String, SkeletonEntry>,
+    /// pool_id -> 经过它的骨架键
+    pool_index: HashMap<String, HashSet<String>>,
+    /// 上次写入完整扫描结果的时间
+    last_full_scan: Option<Instant>,
+    /// 池子价格变动超过该值（%）时骨架失效
+    invalidation_percent: f64,
 }
 
 /// 缓存统计
@@ -66,9 +90,148 @@ impl RouterCache {
             cache_ttl: Duration::from_secs(cache_ttl_secs),
             max_entries,
             stats: CacheStats::default(),
+            skeletons: HashMap::new(),
+            pool_index: HashMap::new(),
+            last_full_scan: None,
+            invalidation_percent: 0.5,
         }
     }
-    
+
+    /// 设置骨架失效的价格变动阈值（%）
+    pub fn with_invalidation_percent(mut self, invalidation_percent: f64) -> Self {
+        self.invalidation_percent = invalidation_percent;
+        self
+    }
+
+    /// 骨架键：路径经过的池子集合（排序去重）
+    pub fn pool_set_key(path: &ArbitragePath) -> String {
+        let mut pool_ids: Vec<&str> = path.steps.iter().map(|s| s.pool_id.as_str()).collect();
+        pool_ids.sort_unstable();
+        pool_ids.dedup();
+        pool_ids.join(",")
+    }
+
+    /// 写入完整扫描发现的路径骨架，记录各池子当前价格作为失效基准
+    pub fn store_skeletons(&mut self, paths: &[ArbitragePath], pools: &[PoolPrice]) {
+        let prices: HashMap<&str, f64> = pools.iter().map(|p| (p.pool_id.as_str(), p.price)).collect();
+        let now = Instant::now();
+
+        for path in paths {
+            let key = Self::pool_set_key(path);
+            if !self.skeletons.contains_key(&key) && self.skeletons.len() >= self.max_entries {
+                self.stats.evictions += 1;
+                continue;
+            }
+
+            let entry = self.skeletons.entry(key.clone()).or_insert_with(|| SkeletonEntry {
+                paths: Vec::new(),
+                reference_prices: HashMap::new(),
+                cached_at: now,
+            });
+            let signature = path.signature();
+            entry.paths.retain(|p| p.signature() != signature);
+            entry.paths.push(path.clone());
+            entry.cached_at = now;
+            for step in &path.steps {
+                if let Some(&price) = prices.get(step.pool_id.as_str()) {
+                    entry.reference_prices.insert(step.pool_id.clone(), price);
+                }
+                self.pool_index.entry(step.pool_id.clone()).or_default().insert(key.clone());
+            }
+        }
+
+        self.last_full_scan = Some(now);
+    }
+
+    /// 距上次完整扫描是否超过TTL（从未扫描也算过期）
+    pub fn needs_full_scan(&self) -> bool {
+        !matches!(self.last_full_scan, Some(at) if at.elapsed() <= self.cache_ttl)
+    }
+
+    /// 按当前价格重算所有未过期的骨架，返回ROI达到 min_roi 的路径（`skip` 命中的骨架不重算）
+    pub fn reevaluate(
+        &mut self,
+        pools: &[PoolPrice],
+        amount: f64,
+        min_roi: f64,
+        skip: impl Fn(&ArbitragePath) -> bool,
+    ) -> Vec<ArbitragePath> {
+        let expired: Vec<String> = self.skeletons.iter()
+            .filter(|(_, entry)| entry.cached_at.elapsed() > self.cache_ttl)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &expired {
+            self.remove_skeleton(key);
+        }
+
+        let by_id: HashMap<&str, &PoolPrice> = pools.iter().map(|p| (p.pool_id.as_str(), p)).collect();
+        let paths: Vec<ArbitragePath> = self.skeletons.values()
+            .flat_map(|entry| entry.paths.iter())
+            .filter(|skeleton| !skip(skeleton))
+            .filter_map(|skeleton| reevaluate_path(skeleton, &by_id, amount))
+            .filter(|path| path.roi_percent >= min_roi)
+            .collect();
+
+        if paths.is_empty() {
+            self.stats.misses += 1;
+        } else {
+            self.stats.hits += 1;
+        }
+        paths
+    }
+
+    /// 价格更新：变动超过阈值时移除经过该池子的骨架，返回移除数量
+    pub fn on_price_update(&mut self, event: &PriceUpdateEvent) -> usize {
+        let keys: Vec<String> = match self.pool_index.get(&event.pool_id) {
+            Some(keys) => keys.iter().cloned().collect(),
+            None => return 0,
+        };
+
+        let stale: Vec<String> = keys.into_iter()
+            .filter(|key| {
+                let reference = self.skeletons.get(key)
+                    .and_then(|entry| entry.reference_prices.get(&event.pool_id).copied());
+                match reference {
+                    Some(reference) if reference > 0.0 => {
+                        ((event.new_price - reference) / reference * 100.0).abs() > self.invalidation_percent
+                    }
+                    _ => true,
+                }
+            })
+            .collect();
+
+        for key in &stale {
+            self.remove_skeleton(key);
+        }
+        stale.len()
+    }
+
+    /// 清空所有骨架（价格事件丢失时无法判断哪些失效）
+    pub fn clear_skeletons(&mut self) {
+        self.skeletons.clear();
+        self.pool_index.clear();
+        self.last_full_scan = None;
+    }
+
+    /// 当前缓存的骨架路径数量
+    pub fn skeleton_count(&self) -> usize {
+        self.skeletons.values().map(|entry| entry.paths.len()).sum()
+    }
+
+    fn remove_skeleton(&mut self, key: &str) {
+        let Some(entry) = self.skeletons.remove(key) else {
+            return;
+        };
+        for pool_id in entry.reference_prices.keys() {
+            if let Some(keys) = self.pool_index.get_mut(pool_id) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.pool_index.remove(pool_id);
+                }
+            }
+        }
+    }
+
     /// 获取缓存的路径
     pub fn get_cached_paths(
         &mut self,
@@ -153,6 +316,7 @@ impl RouterCache {
     /// 清空所有缓存
     pub fn clear(&mut self) {
         self.cache.clear();
+        self.clear_skeletons();
         self.stats = CacheStats::default();
     }
     
@@ -167,6 +331,69 @@ impl RouterCache {
     }
 }
 
+/// 沿骨架的已知边按当前价格重算路径（与扫描器相同的 AMM 公式，不搜索）
+///
+/// 任一池子不在快照中或方向对不上时返回 None；执行成本沿用扫描时的估算。
+pub fn reevaluate_path(
+    skeleton: &ArbitragePath,
+    pools: &HashMap<&str, &PoolPrice>,
+    amount: f64,
+) -> Option<ArbitragePath> {
+    let mut path = skeleton.clone();
+    let mut current_amount = amount;
+    let mut dex_fees = 0.0;
+
+    for step in path.steps.iter_mut() {
+        let pool = pools.get(step.pool_id.as_str())?;
+        let (base, quote) = pool_tokens(pool)?;
+        let (base_reserve, quote_reserve) = pool.get_reserves();
+        let (base_decimals, quote_decimals) = pool.get_decimals();
+        let base_reserve = base_reserve as f64 / 10f64.powi(base_decimals as i32);
+        let quote_reserve = quote_reserve as f64 / 10f64.powi(quote_decimals as i32);
+        let (reserve_in, reserve_out) = if step.input_token == base && step.output_token == quote {
+            (base_reserve, quote_reserve)
+        } else if step.input_token == quote && step.output_token == base {
+            (quote_reserve, base_reserve)
+        } else {
+            return None;
+        };
+
+        let fee = crate::fee_registry::fee_rate(&pool.pool_id, &pool.dex_name);
+        let output_amount = amm_calculator::calculate_hop_output_f64(
+            &pool.pool_id,
+            &format!("{}/{}", base, quote),
+            &step.input_token,
+            current_amount,
+            reserve_in,
+            reserve_out,
+            fee,
+        );
+
+        step.price = pool.price;
+        step.liquidity_base = pool.base_reserve;
+        step.liquidity_quote = pool.quote_reserve;
+        step.expected_input = current_amount;
+        step.expected_output = output_amount;
+        step.price_impact_percent = hop_price_impact_percent(
+            current_amount, output_amount, reserve_in, reserve_out, fee,
+        );
+        step.effective_fee_bps = fee * 10_000.0;
+
+        dex_fees += fee * current_amount;
+        current_amount = output_amount;
+    }
+
+    path.input_amount = amount;
+    path.output_amount = current_amount;
+    path.gross_profit = current_amount - amount;
+    path.dex_fees = dex_fees;
+    path.estimated_fees = dex_fees + path.execution_fees;
+    path.net_profit = path.gross_profit - path.execution_fees;
+    path.roi_percent = (path.net_profit / amount) * 100.0;
+    path.discovered_at = Instant::now();
+    Some(path)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -182,6 +409,8 @@ mod tests {
             input_amount: 1000.0,
             output_amount: 1010.0,
             gross_profit: 10.0,
+            dex_fees: 0.5,
+            execution_fees: 0.0,
             estimated_fees: 0.5,
             net_profit: 9.5,
             roi_percent: 0.95,
@@ -245,6 +474,77 @@ mod tests {
         assert!(cache.get_cached_paths("SOL", "USDT").is_none());  // 被淘汰
         assert!(cache.get_cached_paths("SOL", "RAY").is_some());   // 新添加
     }
-}
 
+    fn sol_usdc_pool(pool_id: &str, price: f64) -> PoolPrice {
+        PoolPrice {
+            dex_name: "Raydium".to_string(),
+            base_reserve: 10_000 * 1_000_000_000,
+            quote_reserve: (10_000.0 * price * 1_000_000.0) as u64,
+            ..PoolPrice::for_test(pool_id, "SOL/USDC", price)
+        }
+    }
+
+    fn price_event(pool_id: &str, new_price: f64) -> PriceUpdateEvent {
+        PriceUpdateEvent {
+            pool_id: pool_id.to_string(),
+            pair: "SOL/USDC".to_string(),
+            old_price: None,
+            new_price,
+            price_change_percent: 0.0,
+            timestamp: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_skeletons_reevaluate_and_invalidate_on_price_move() {
+        use crate::router::RouteStep;
+
+        let pools = vec![sol_usdc_pool("cheap", 150.0), sol_usdc_pool("rich", 153.0)];
+        let step = |pool_id: &str, input: &str, output: &str| RouteStep {
+            pool_id: pool_id.to_string(),
+            dex_name: "Raydium".to_string(),
+            input_token: input.to_string(),
+            output_token: output.to_string(),
+            price: 0.0,
+            liquidity_base: 0,
+            liquidity_quote: 0,
+            expected_input: 0.0,
+            expected_output: 0.0,
+            price_impact_percent: 0.0,
+            effective_fee_bps: 0.0,
+            raw_input_token: None,
+            raw_output_token: None,
+        };
+        let skeleton = ArbitragePath {
+            steps: vec![step("cheap", "USDC", "SOL"), step("rich", "SOL", "USDC")],
+            start_token: "USDC".to_string(),
+            end_token: "USDC".to_string(),
+            gross_profit: 10.0,
+            execution_fees: 0.0001,
+            net_profit: 9.9999,
+            ..create_dummy_path()
+        };
+        assert_eq!(RouterCache::pool_set_key(&skeleton), "cheap,rich");
 
+        let mut cache = RouterCache::new(60, 100).with_invalidation_percent(0.5);
+        assert!(cache.needs_full_scan());
+        cache.store_skeletons(std::slice::from_ref(&skeleton), &pools);
+        assert!(!cache.needs_full_scan());
+
+        // 沿已知的边重算：约 2% 价差扣掉手续费后仍有利润，执行成本沿用扫描时的估算
+        let paths = cache.reevaluate(&pools, 1_000.0, 0.3, |_| false);
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].input_amount, 1_000.0);
+        assert!(paths[0].roi_percent > 0.3 && paths[0].roi_percent < 2.0);
+        assert!((paths[0].gross_profit - paths[0].net_profit - 0.0001).abs() < 1e-9);
+        assert!(cache.reevaluate(&pools[..1], 1_000.0, 0.3, |_| false).is_empty());
+
+        // 小于阈值的变动不失效，超过阈值移除骨架
+        assert_eq!(cache.on_price_update(&price_event("rich", 153.5)), 0);
+        assert_eq!(cache.on_price_update(&price_event("other", 1.0)), 0);
+        assert_eq!(cache.skeleton_count(), 1);
+        assert_eq!(cache.on_price_update(&price_event("cheap", 151.0)), 1);
+        assert_eq!(cache.skeleton_count(), 0);
+        assert!(cache.reevaluate(&pools, 1_000.0, 0.3, |_| false).is_empty());
+    }
+}
@@ -0,0 +1,384 @@
+/*!
+ * 两跳直接套利快速通道
+ *
+ * 大部分实际利润来自同一交易对在两个池子之间的 A→B→A。这里按交易对维护
+ * 增量更新的"最优买价 / 最优卖价"表（手续费已计入）：
+ *
+ * - 卖出 base 的有效价格：price × (1 - fee)，取最高者（best bid）
+ * - 买入 base 的有效价格：price / (1 - fee)，取最低者（best ask）
+ *
+ * Coordinator 收到价格事件时只刷新受影响的池子并检查该交易对，best bid > best ask
+ * 且价差超过阈值时立即产生机会（不等完整扫描），Calculator 在下一次扫描时
+ * 把这些机会与完整扫描结果一起交给 OpportunityMerger 去重。
+ */
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use dashmap::DashMap;
+use tracing::debug;
+
+use crate::execution_cost::ExecutionCostModel;
+use crate::liquidity;
+use crate::dex_interface::amm_calculator;
+use crate::price_cache::{PoolPrice, PriceCache};
+use crate::router::{hop_price_impact_percent, ArbitragePath, ArbitrageType, RouteStep};
+use crate::token_alias::{self, TokenAliases};
+use crate::token_graph::{pair_key_in, pool_tokens_in, raw_token_in};
+
+/// 单个池子的有效报价（手续费已计入）
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectQuote {
+    pub pool_id: String,
+    pub dex_name: String,
+    pub price: f64,
+    pub fee_rate: f64,
+    /// 卖出 1 base 实得 quote
+    pub bid: f64,
+    /// 买入 1 base 实付 quote
+    pub ask: f64,
+}
+
+impl DirectQuote {
+    /// 按池子当前价格和手续费计算买卖价；价格无效或费率不在 [0, 1) 时为 None
+    pub fn from_pool(pool: &PoolPrice) -> Option<Self> {
+        if !(pool.price.is_finite() && pool.price > 0.0) {
+            return None;
+        }
+        let fee_rate = crate::fee_registry::fee_rate(&pool.pool_id, &pool.dex_name);
+        if !(0.0..1.0).contains(&fee_rate) {
+            return None;
+        }
+        Some(Self {
+            pool_id: pool.pool_id.clone(),
+            dex_name: pool.dex_name.clone(),
+            price: pool.price,
+            fee_rate,
+            bid: pool.price * (1.0 - fee_rate),
+            ask: pool.price / (1.0 - fee_rate),
+        })
+    }
+}
+
+/// 一个交易对的所有池子报价和当前最优买卖价
+#[derive(Debug, Default)]
+struct PairBook {
+    quotes: HashMap<String, DirectQuote>,
+    best_bid: Option<DirectQuote>,
+    best_ask: Option<DirectQuote>,
+}
+
+impl PairBook {
+    fn refresh_best(&mut self) {
+        // 平局按 pool_id 决胜，结果与 HashMap 迭代顺序无关
+        self.best_bid = self.quotes.values()
+            .max_by(|a, b| a.bid.total_cmp(&b.bid).then_with(|| b.pool_id.cmp(&a.pool_id)))
+            .cloned();
+        self.best_ask = self.quotes.values()
+            .min_by(|a, b| a.ask.total_cmp(&b.ask).then_with(|| a.pool_id.cmp(&b.pool_id)))
+            .cloned();
+    }
+}
+
+/// 直接套利机会：在 buy 池买入 base，在 sell 池卖出
+#[derive(Debug, Clone)]
+pub struct DirectOpportunity {
+    pub pair: String,
+    pub buy: DirectQuote,
+    pub sell: DirectQuote,
+    /// 扣除两边手续费后的价差（百分比）
+    pub spread_percent: f64,
+    pub detected_at: Instant,
+    /// 发现机会的报价表所用的别名表（转换路径时解析原始代币）
+    pub aliases: Arc<TokenAliases>,
+    /// 报价表的执行成本模型（转换路径时扣除执行成本）
+    pub execution_cost: Arc<ExecutionCostModel>,
+}
+
+impl DirectOpportunity {
+    /// 路径签名（与 ArbitragePath::signature 一致，用于待处理队列去重）
+    pub fn signature(&self) -> String {
+        format!("{}->{}", self.buy.pool_id, self.sell.pool_id)
+    }
+
+    /// 按最新缓存数据和 AMM 公式转换成两跳路径（输入金额以 quote 代币计）
+    ///
+    /// 任一池子已不在缓存中或仍是快照恢复数据时返回 None。
+    pub fn to_path(&self, price_cache: &PriceCache, amount: f64) -> Option<ArbitragePath> {
+        let buy_pool = live_pool(price_cache, &self.buy.pool_id)?;
+        let sell_pool = live_pool(price_cache, &self.sell.pool_id)?;
+        let (base_token, quote_token) = pool_tokens_in(&self.aliases, &buy_pool)?;
+        let aliases = self.aliases.as_ref();
+
+        let step1 = swap_step(aliases, &buy_pool, &quote_token, &base_token, amount, self.buy.fee_rate);
+        let step2 = swap_step(aliases, &sell_pool, &base_token, &quote_token, step1.expected_output, self.sell.fee_rate);
+
+        let final_amount = step2.expected_output;
+        let gross_profit = final_amount - amount;
+        let dex_fees = amount * (self.buy.fee_rate + self.sell.fee_rate);
+        let steps = vec![step1, step2];
+        // quote 代币无法换算成 SOL 时路径无法定价，不上报
+        let execution_fees = self.execution_cost.cost_in_token(&steps, &quote_token)?;
+        let net_profit = gross_profit - execution_fees;
+
+        Some(ArbitragePath {
+            arb_type: ArbitrageType::Direct,
+            steps,
+            start_token: quote_token.clone(),
+            end_token: quote_token,
+            input_amount: amount,
+            output_amount: final_amount,
+            gross_profit,
+            dex_fees,
+            execution_fees,
+            estimated_fees: dex_fees + execution_fees,
+            net_profit,
+            roi_percent: net_profit / amount * 100.0,
+            discovered_at: self.detected_at,
+        })
+    }
+}
+
+/// 缓存中可用于报价的池子（排除快照恢复、熔断隔离和偏离参考价格的数据）
+pub fn live_pool(price_cache: &PriceCache, pool_id: &str) -> Option<PoolPrice> {
+    price_cache.get_price(pool_id)
+        .filter(|_| !price_cache.is_restored(pool_id) && !price_cache.is_quarantined(pool_id))
+        .filter(|_| !price_cache.is_reference_excluded(pool_id))
+}
+
+/// 按池子当前储备计算一跳兑换（库存后处理按新金额重新定价时也使用）
+///
+/// `input` / `output` 是 `aliases` 下的规范符号。
+pub fn swap_step(
+    aliases: &TokenAliases,
+    pool: &PoolPrice,
+    input: &str,
+    output: &str,
+    amount: f64,
+    fee_rate: f64,
+) -> RouteStep {
+    let (base_decimals, quote_decimals) = pool.get_decimals();
+    let base_reserve = pool.base_reserve as f64 / 10f64.powi(base_decimals as i32);
+    let quote_reserve = pool.quote_reserve as f64 / 10f64.powi(quote_decimals as i32);
+    let input_is_base = pool_tokens_in(aliases, pool).is_some_and(|(base, _)| base == input);
+    let (reserve_in, reserve_out) = if input_is_base {
+        (base_reserve, quote_reserve)
+    } else {
+        (quote_reserve, base_reserve)
+    };
+    let pair = if input_is_base { format!("{}/{}", input, output) } else { format!("{}/{}", output, input) };
+
+    let expected_output = amm_calculator::calculate_hop_output_f64(
+        &pool.pool_id,
+        &pair,
+        input,
+        amount,
+        reserve_in,
+        reserve_out,
+        fee_rate,
+    );
+
+    RouteStep {
+        pool_id: pool.pool_id.clone(),
+        dex_name: pool.dex_name.clone(),
+        input_token: input.to_string(),
+        output_token: output.to_string(),
+        price: pool.price,
+        liquidity_base: pool.base_reserve,
+        liquidity_quote: pool.quote_reserve,
+        expected_input: amount,
+        expected_output,
+        price_impact_percent: hop_price_impact_percent(
+            amount, expected_output, reserve_in, reserve_out, fee_rate,
+        ),
+        effective_fee_bps: fee_rate * 10_000.0,
+        raw_input_token: raw_token_in(aliases, pool, input),
+        raw_output_token: raw_token_in(aliases, pool, output),
+    }
+}
+
+/// 按交易对的最优买卖价表
+pub struct DirectArbTable {
+    price_cache: Arc<PriceCache>,
+    min_spread_percent: f64,
+    /// pair -> 报价簿
+    books: DashMap<String, PairBook>,
+    /// pool_id -> pair（池子改名 / 移除时定位旧报价簿）
+    pool_pairs: DashMap<String, String>,
+    /// 尚未被 Calculator 取走的机会（按签名保留最新一条）
+    pending: Mutex<HashMap<String, DirectOpportunity>>,
+    /// 💧 美元流动性低于该值的池子不进报价簿（0 = 不过滤）
+    min_liquidity_usd: f64,
+    /// 🪞 代币别名表（决定哪些池子落在同一个报价簿）
+    aliases: Arc<TokenAliases>,
+    /// ⛽ 执行成本模型
+    execution_cost: Arc<ExecutionCostModel>,
+}
+
+impl DirectArbTable {
+    pub fn new(price_cache: Arc<PriceCache>, min_spread_percent: f64) -> Self {
+        Self {
+            price_cache,
+            min_spread_percent,
+            books: DashMap::new(),
+            pool_pairs: DashMap::new(),
+            pending: Mutex::new(HashMap::new()),
+            min_liquidity_usd: 0.0,
+            aliases: token_alias::global().clone(),
+            execution_cost: ExecutionCostModel::shared_default(),
+        }
+    }
+
+    /// 💧 设置池子最低美元流动性（与 [router] min_pool_liquidity_usd 一致，流动性未知的池子保留）
+    pub fn with_min_liquidity_usd(mut self, min_liquidity_usd: f64) -> Self {
+        self.min_liquidity_usd = min_liquidity_usd;
+        self
+    }
+
+    /// 🪞 设置代币别名表（默认是启动时安装的 `token_alias::global()`）
+    pub fn with_token_aliases(mut self, aliases: Arc<TokenAliases>) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// ⛽ 设置执行成本模型（app 启动时创建、后台刷新优先费和汇率的那一份）
+    pub fn with_execution_cost(mut self, execution_cost: Arc<ExecutionCostModel>) -> Self {
+        self.execution_cost = execution_cost;
+        self
+    }
+
+    /// 价格事件入口：从缓存刷新该池子的报价并检查所在交易对
+    ///
+    /// 发现机会时放入待处理队列并返回。
+    pub fn on_pool_event(&self, pool_id: &str) -> Option<DirectOpportunity> {
+        let pair = match live_pool(&self.price_cache, pool_id) {
+            Some(pool) => {
+                self.update(&pool);
+                pair_key_in(&self.aliases, &pool)
+            }
+            None => {
+                self.remove_pool(pool_id);
+                return None;
+            }
+        };
+
+        let opportunity = self.check_pair(&pair)?;
+        self.pending.lock().unwrap().insert(opportunity.signature(), opportunity.clone());
+        Some(opportunity)
+    }
+
+    /// 写入 / 刷新一个池子的报价（按规范交易对分组，wSOL/USDC 与 SOL/USDC 共用一个报价簿）
+    pub fn update(&self, pool: &PoolPrice) {
+        let pair = pair_key_in(&self.aliases, pool);
+        let previous_pair = self.pool_pairs.insert(pool.pool_id.clone(), pair.clone());
+        if let Some(previous_pair) = previous_pair.filter(|p| *p != pair) {
+            self.remove_from_book(&previous_pair, &pool.pool_id);
+        }
+
+        let mut book = self.books.entry(pair).or_default();
+        match DirectQuote::from_pool(pool).filter(|_| liquidity::meets_floor(pool, self.min_liquidity_usd)) {
+            Some(quote) => {
+                book.quotes.insert(pool.pool_id.clone(), quote);
+            }
+            None => {
+                book.quotes.remove(&pool.pool_id);
+            }
+        }
+        book.refresh_best();
+    }
+
+    /// 移除池子（热重载删除 / 缓存中已不存在）
+    pub fn remove_pool(&self, pool_id: &str) {
+        if let Some((_, pair)) = self.pool_pairs.remove(pool_id) {
+            self.remove_from_book(&pair, pool_id);
+        }
+    }
+
+    fn remove_from_book(&self, pair: &str, pool_id: &str) {
+        if let Some(mut book) = self.books.get_mut(pair) {
+            if book.quotes.remove(pool_id).is_some() {
+                book.refresh_best();
+            }
+        }
+    }
+
+    /// 检查一个交易对：best bid 与 best ask 来自不同池子且价差达到阈值时返回机会
+    pub fn check_pair(&self, pair: &str) -> Option<DirectOpportunity> {
+        let (buy, sell) = {
+            let book = self.books.get(pair)?;
+            (book.best_ask.clone()?, book.best_bid.clone()?)
+        };
+        if buy.pool_id == sell.pool_id {
+            return None;
+        }
+
+        let spread_percent = (sell.bid - buy.ask) / buy.ask * 100.0;
+        if spread_percent < self.min_spread_percent {
+            return None;
+        }
+        debug!(
+            "⚡ Direct spread {} {:.4}%: buy {} @ {:.6}, sell {} @ {:.6}",
+            pair, spread_percent, buy.pool_id, buy.ask, sell.pool_id, sell.bid
+        );
+
+        Some(DirectOpportunity {
+            pair: pair.to_string(),
+            buy,
+            sell,
+            spread_percent,
+            detected_at: Instant::now(),
+            aliases: self.aliases.clone(),
+            execution_cost: self.execution_cost.clone(),
+        })
+    }
+
+    /// 取走待处理的机会（按发现时间排序）
+    pub fn take_pending(&self) -> Vec<DirectOpportunity> {
+        let mut opportunities: Vec<DirectOpportunity> = self.pending.lock().unwrap()
+            .drain()
+            .map(|(_, opportunity)| opportunity)
+            .collect();
+        opportunities.sort_by_key(|o| o.detected_at);
+        opportunities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_best_bid_ask_and_direct_path() {
+        let cache = Arc::new(PriceCache::new());
+        let table = DirectArbTable::new(cache.clone(), 0.3)
+            .with_execution_cost(crate::router_fixture::priced_execution_cost(&[("USDC", 150.0)]));
+
+        cache.update_price(PoolPrice::for_test("direct-a", "JTO/USDC", 2.00));
+        cache.update_price(PoolPrice::for_test("direct-b", "JTO/USDC", 2.01));
+        assert!(table.on_pool_event("direct-a").is_none());
+        // 0.5% 价差扣掉两边 0.25% 手续费后低于阈值
+        assert!(table.on_pool_event("direct-b").is_none());
+
+        cache.update_price(PoolPrice::for_test("direct-b", "JTO/USDC", 2.04));
+        let opportunity = table.on_pool_event("direct-b").expect("spread above threshold");
+        assert_eq!(opportunity.buy.pool_id, "direct-a");
+        assert_eq!(opportunity.sell.pool_id, "direct-b");
+        assert!(opportunity.spread_percent > 1.0 && opportunity.spread_percent < 2.0);
+
+        let path = opportunity.to_path(&cache, 100.0).unwrap();
+        assert_eq!(path.signature(), opportunity.signature());
+        assert_eq!(path.start_token, "USDC");
+        assert_eq!(path.steps[0].output_token, "JTO");
+        assert!(path.roi_percent > 0.3);
+
+        // 同一签名只保留一条，取走后清空
+        assert_eq!(table.take_pending().len(), 1);
+        assert!(table.take_pending().is_empty());
+
+        // 卖价池子被移除后交易对不再有机会
+        cache.remove_price("direct-b");
+        assert!(table.on_pool_event("direct-b").is_none());
+        assert!(table.check_pair("JTO/USDC").is_none());
+    }
+}
@@ -0,0 +1,303 @@
+/*!
+ * 路由器测试 fixture：按精确汇率构造合成 PoolPrice
+ *
+ * `RateGraph` 描述一张汇率图（代币精度 + 池子价格），`build()` 生成储备量与价格一致的
+ * PoolPrice：base 侧储备为 `depth` 个代币，quote 侧为 `depth * price` 个，
+ * 小额交易的成交率 ≈ 汇率 × (1 - 手续费)，可以手算 ROI。
+ *
+ * `RateGraph::arbitrage_free` 从同一组代币估值导出所有价格，图中任何循环的汇率乘积都是 1，
+ * 扫描器报告的 ROI 不可能高于只付手续费的下限（`fee_floor_roi_percent`）。
+ *
+ * 代币用 fixture 专用符号（FX* / FR*），避免与其他测试注册的 mint / 转账手续费冲突。
+ */
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::config::ExecutionCostConfig;
+use crate::execution_cost::ExecutionCostModel;
+use crate::price_cache::PoolPrice;
+use crate::router::ArbitragePath;
+
+/// 默认池子深度（base 侧代币数量）
+pub const DEFAULT_DEPTH: f64 = 1_000_000.0;
+/// 未声明精度的代币默认 6 位
+const DEFAULT_DECIMALS: u8 = 6;
+/// 随机图里的 DEX（默认费率 0.01% / 0.25%），低费率居多，循环才不会全被 BFS 早期剪枝
+const RANDOM_DEXES: [&str; 4] = ["Raydium CLMM", "Raydium CLMM", "Raydium CLMM", "Raydium AMM V4"];
+
+/// `triangle` 的期望跳序（pool_id:输入->输出，从 FXA 开始）
+pub const TRIANGLE_HOPS: [&str; 3] = ["fx-ab:FXA->FXB", "fx-bc:FXB->FXC", "fx-ca:FXC->FXA"];
+
+/// 三池循环 FXA → FXB → FXC → FXA，汇率依次为 2、4、`cycle_rate / 8`，乘积 = `cycle_rate`
+///
+/// 三个代币精度各不相同（6 / 9 / 8）；中间金额都大于输入，不会触发 BFS 的早期剪枝。
+/// 深度 1e9，10 个 FXA 的价格冲击可以忽略（< 1e-6）。
+pub fn triangle(cycle_rate: f64) -> RateGraph {
+    RateGraph::new()
+        .with_depth(1e9)
+        .token("FXA", 6)
+        .token("FXB", 9)
+        .token("FXC", 8)
+        .pool("fx-ab", "FXB/FXA", 0.5)               // FXA → FXB：×2
+        .pool("fx-bc", "FXB/FXC", 4.0)               // FXB → FXC：×4
+        .pool("fx-ca", "FXC/FXA", cycle_rate / 8.0)  // FXC → FXA：×cycle_rate/8
+}
+
+/// 合成池子（价格 = 1 base 值多少 quote）
+#[derive(Debug, Clone)]
+struct FixturePool {
+    pool_id: String,
+    dex_name: String,
+    pair: String,
+    price: f64,
+}
+
+/// 汇率图构建器
+#[derive(Debug, Clone)]
+pub struct RateGraph {
+    decimals: HashMap<String, u8>,
+    pools: Vec<FixturePool>,
+    depth: f64,
+    dex_name: String,
+}
+
+impl Default for RateGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RateGraph {
+    pub fn new() -> Self {
+        Self {
+            decimals: HashMap::new(),
+            pools: Vec::new(),
+            depth: DEFAULT_DEPTH,
+            dex_name: "Raydium AMM V4".to_string(),
+        }
+    }
+
+    /// 池子深度（base 侧代币数量，作用于所有池子）
+    pub fn with_depth(mut self, depth: f64) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// 之后添加的池子所属 DEX（决定默认手续费）
+    pub fn with_dex(mut self, dex_name: &str) -> Self {
+        self.dex_name = dex_name.to_string();
+        self
+    }
+
+    /// 声明代币精度
+    pub fn token(mut self, symbol: &str, decimals: u8) -> Self {
+        self.decimals.insert(symbol.to_string(), decimals);
+        self
+    }
+
+    /// 添加池子：`pair` 为 "BASE/QUOTE"，`price` 为 1 BASE 值多少 QUOTE
+    pub fn pool(mut self, pool_id: &str, pair: &str, price: f64) -> Self {
+        self.pools.push(FixturePool {
+            pool_id: pool_id.to_string(),
+            dex_name: self.dex_name.clone(),
+            pair: pair.to_string(),
+            price,
+        });
+        self
+    }
+
+    fn decimals_of(&self, token: &str) -> u8 {
+        self.decimals.get(token).copied().unwrap_or(DEFAULT_DECIMALS)
+    }
+
+    /// 生成 PoolPrice（储备量按精度换算成最小单位）
+    pub fn build(&self) -> Vec<PoolPrice> {
+        self.pools.iter().map(|pool| {
+            let (base, quote) = pool.pair.split_once('/').expect("fixture pair must be BASE/QUOTE");
+            let base_decimals = self.decimals_of(base);
+            let quote_decimals = self.decimals_of(quote);
+            let base_reserve = self.depth * 10f64.powi(base_decimals as i32);
+            let quote_reserve = self.depth * pool.price * 10f64.powi(quote_decimals as i32);
+            assert!(
+                base_reserve < u64::MAX as f64 && quote_reserve < u64::MAX as f64,
+                "fixture reserves of {} overflow u64, lower the depth",
+                pool.pool_id
+            );
+
+            PoolPrice {
+                dex_name: pool.dex_name.clone(),
+                base_reserve: base_reserve.round() as u64,
+                quote_reserve: quote_reserve.round() as u64,
+                base_decimals,
+                quote_decimals,
+                slot: 0,
+                ..PoolPrice::for_test(&pool.pool_id, &pool.pair, pool.price)
+            }
+        }).collect()
+    }
+
+    /// 随机无套利图：每个代币一个估值 v，池子价格 = v(base) / v(quote)
+    ///
+    /// 前 `token_count` 个池子把代币连成环（保证存在循环），其余池子随机连边，
+    /// 同一交易对可能有多个池子（不同费率）。
+    pub fn arbitrage_free(rng: &mut FixtureRng, token_count: usize, pool_count: usize) -> Self {
+        assert!(token_count >= 3, "need at least 3 tokens to form a cycle");
+        let decimals = [6u8, 8, 9];
+        let tokens: Vec<(String, f64)> = (0..token_count)
+            .map(|i| (format!("FR{}", i), (rng.unit() * 6.0 - 3.0).exp())) // 估值 e^-3 .. e^3
+            .collect();
+
+        let mut graph = Self::new();
+        for (symbol, _) in &tokens {
+            graph = graph.token(symbol, decimals[rng.below(decimals.len())]);
+        }
+        for k in 0..pool_count {
+            let (base, quote) = if k < token_count {
+                (k, (k + 1) % token_count)
+            } else {
+                let base = rng.below(token_count);
+                (base, (base + 1 + rng.below(token_count - 1)) % token_count)
+            };
+            let ((base_symbol, base_value), (quote_symbol, quote_value)) = (&tokens[base], &tokens[quote]);
+            graph = graph
+                .with_dex(RANDOM_DEXES[rng.below(RANDOM_DEXES.len())])
+                .pool(
+                    &format!("fr-{:02}", k),
+                    &format!("{}/{}", base_symbol, quote_symbol),
+                    base_value / quote_value,
+                );
+        }
+        graph
+    }
+}
+
+/// 确定性伪随机数（xorshift64*），随机化测试失败时按种子复现
+pub struct FixtureRng(u64);
+
+impl FixtureRng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// [0, 1) 均匀分布
+    pub fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// [0, n) 均匀整数
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// 路径的跳序（"pool_id:输入->输出"），旋转到从 `start` 开始
+///
+/// 扫描器可能从循环中任意代币开始报告同一个循环，比较跳序前先对齐起点。
+pub fn hops_from(path: &ArbitragePath, start: &str) -> Vec<String> {
+    let mut hops: Vec<String> = path.steps.iter()
+        .map(|s| format!("{}:{}->{}", s.pool_id, s.input_token, s.output_token))
+        .collect();
+    if let Some(index) = path.steps.iter().position(|s| s.input_token == start) {
+        hops.rotate_left(index);
+    }
+    hops
+}
+
+/// 默认配置的执行成本模型，按给定汇率（1 SOL 可换得的代币数量）为代币定价
+///
+/// 测试代币没有 USD 价格，默认模型下起点是它们的路径无法定价、不会上报。
+pub fn priced_execution_cost(tokens_per_sol: &[(&str, f64)]) -> Arc<ExecutionCostModel> {
+    let model = ExecutionCostModel::new(ExecutionCostConfig::default());
+    for (token, rate) in tokens_per_sol {
+        model.set_sol_rate(token, *rate);
+    }
+    Arc::new(model)
+}
+
+/// 为 fixture 代币（`triangle` 的 FX*、`arbitrage_free` 的 FR*）定价的执行成本模型
+///
+/// 汇率数值无关紧要（ROI 断言都扣除路径自己的 execution_fees），只要路径能定价。
+pub fn priced_fixture_tokens() -> Arc<ExecutionCostModel> {
+    let symbols: Vec<String> = ["FXA", "FXB", "FXC"].iter().map(|s| s.to_string())
+        .chain((0..16).map(|i| format!("FR{}", i)))
+        .collect();
+    let rates: Vec<(&str, f64)> = symbols.iter().map(|s| (s.as_str(), 150.0)).collect();
+    priced_execution_cost(&rates)
+}
+
+/// 手算 ROI：每跳成交率 = 汇率 × (1 - 手续费)，忽略价格冲击，扣除 gas
+pub fn expected_roi_percent(rates: &[f64], fee_rate: f64, amount: f64, gas: f64) -> f64 {
+    let output = rates.iter().fold(amount, |acc, rate| acc * rate * (1.0 - fee_rate));
+    (output - amount - gas) / amount * 100.0
+}
+
+/// 无套利图中该路径 ROI 的上限：汇率乘积为 1，只付手续费（gas 与价格冲击只会更低）
+pub fn fee_floor_roi_percent(path: &ArbitragePath) -> f64 {
+    let kept: f64 = path.steps.iter()
+        .map(|s| 1.0 - crate::fee_registry::fee_rate(&s.pool_id, &s.dex_name))
+        .product();
+    (kept - 1.0) * 100.0
+}
+
+/// 每一跳的成交率都符合池子价格和方向：base → quote 为 price，quote → base 为 1/price
+///
+/// 边权方向写反（例如把 1/price 当成 base → quote 的汇率）会在这里失败。
+pub fn assert_hop_rates(path: &ArbitragePath, pools: &[PoolPrice]) {
+    for step in &path.steps {
+        let pool = pools.iter()
+            .find(|p| p.pool_id == step.pool_id)
+            .unwrap_or_else(|| panic!("step uses unknown pool {}", step.pool_id));
+        let (base, _) = pool.pair.split_once('/').unwrap();
+        let rate = if step.input_token == base { pool.price } else { 1.0 / pool.price };
+        let fee = crate::fee_registry::fee_rate(&pool.pool_id, &pool.dex_name);
+
+        let actual = step.expected_output / step.expected_input;
+        let expected = rate * (1.0 - fee);
+        assert!(
+            (actual / expected - 1.0).abs() < 1e-5,
+            "{} {} -> {}: rate {} != expected {}",
+            step.pool_id, step.input_token, step.output_token, actual, expected
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_reserves_match_price() {
+        let pools = triangle(1.015).build();
+        assert_eq!(pools.len(), 3);
+
+        for pool in &pools {
+            let base = pool.base_reserve as f64 / 10f64.powi(pool.base_decimals as i32);
+            let quote = pool.quote_reserve as f64 / 10f64.powi(pool.quote_decimals as i32);
+            assert!((quote / base / pool.price - 1.0).abs() < 1e-12, "{}", pool.pool_id);
+        }
+        let fx_bc = pools.iter().find(|p| p.pool_id == "fx-bc").unwrap();
+        assert_eq!((fx_bc.base_decimals, fx_bc.quote_decimals), (9, 8));
+        assert_eq!(fx_bc.base_reserve, 1_000_000_000_000_000_000);
+    }
+
+    #[test]
+    fn test_arbitrage_free_cycles_multiply_to_one() {
+        let mut rng = FixtureRng::new(7);
+        let pools = RateGraph::arbitrage_free(&mut rng, 4, 4).build();
+
+        // 前 4 个池子组成环 FR0 → FR1 → FR2 → FR3 → FR0（沿 base → quote 方向）
+        let product: f64 = pools.iter().map(|p| p.price).product();
+        assert!((product - 1.0).abs() < 1e-12);
+        assert_eq!(pools[3].pair, "FR3/FR0");
+    }
+}
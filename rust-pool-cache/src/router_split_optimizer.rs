@@ -10,25 +10,71 @@
  * - 滑点随交易额非线性增长（AMM公式）
  * - 拆分资金到多个池子可以减少总滑点
  * - 使用DP找到最优拆分比例
+ * 
+ * 同pair多池拆分：每一跳从 PriceCache 找出同一交易对（方向一致）的所有池子，
+ * 让各池子的边际输出相等 —— 两个池子有闭式解，更多池子用梯度下降。
+ *
+ * 🔢 每次优化先把池子快照按 `PairId` 建索引（代币驻留为 `TokenId`，池子下标即 `PoolId`），
+ * 每一跳直接查本交易对的池子；池子ID / DEX 字符串只在生成 `PoolAllocation` 时克隆。
  */
 
-use crate::router::ArbitragePath;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::interning::{PairId, PoolId, TokenRegistry};
+use crate::price_cache::{PoolPrice, PriceCache};
+use crate::router::{hop_price_impact_percent, ArbitragePath, RouteStep};
+use crate::token_graph::pool_tokens;
+
+/// 单个池子的子步骤：在第 hop 跳向 pool_id 投入 amount_in
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolAllocation {
+    /// 所属跳（base_path.steps 的下标）
+    pub hop: usize,
+    /// 池子ID
+    pub pool_id: String,
+    /// DEX名称
+    pub dex_name: String,
+    /// 投入金额（该跳输入代币）
+    pub amount_in: f64,
+    /// 预期输出（该跳输出代币）
+    pub expected_output: f64,
+}
 
 /// 拆分策略
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SplitStrategy {
-    /// 路径索引 -> 分配金额
-    pub allocations: Vec<(usize, f64)>,
+    /// 池子级子步骤（按跳排序，同一跳拆到多个池子时有多条）
+    pub allocations: Vec<PoolAllocation>,
     /// 预期总输出
     #[allow(dead_code)]
     pub expected_output: f64,
     /// 优化后的ROI
     #[allow(dead_code)]
     pub optimized_roi: f64,
+    /// 按分配金额模拟的每跳价格冲击（%，拆分时取该跳最大值）
+    pub hop_impacts_percent: Vec<f64>,
+}
+
+impl SplitStrategy {
+    /// 路径的总投入（第一跳各池子投入之和）
+    pub fn input_amount(&self) -> f64 {
+        self.allocations.iter()
+            .filter(|a| a.hop == 0)
+            .map(|a| a.amount_in)
+            .sum()
+    }
+
+    /// 是否有某一跳拆到了多个池子
+    pub fn is_split(&self) -> bool {
+        self.allocations.windows(2).any(|w| w[0].hop == w[1].hop)
+    }
 }
 
 /// 优化后的路径（包含拆分信息）
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct OptimizedPath {
     /// 原始路径
     pub base_path: ArbitragePath,
@@ -41,15 +87,6 @@ pub struct OptimizedPath {
 }
 
 impl OptimizedPath {
-    /// 计算优化后的得分
-    pub fn score(&self) -> f64 {
-        let profit_score = self.optimized_net_profit;
-        let roi_score = self.optimized_roi / 100.0;
-        let complexity_penalty = 1.0 / (self.base_path.steps.len() as f64);
-        
-        profit_score * 0.6 + roi_score * 0.3 + complexity_penalty * 0.1
-    }
-    
     /// 检查是否有效
     pub fn is_valid(&self) -> bool {
         self.optimized_net_profit > 0.0 && self.optimized_roi > 0.1
@@ -59,13 +96,14 @@ impl OptimizedPath {
 /// 动态规划拆分优化器
 #[derive(Clone)]
 pub struct SplitOptimizer {
-    /// 最大拆分数量
-    #[allow(dead_code)]
+    /// 最大拆分数量（每跳最多使用的池子数）
     max_splits: usize,
     /// 最小拆分金额（太小的拆分不值得）
     min_split_amount: f64,
     /// 滑点模型类型
     slippage_model: SlippageModel,
+    /// 价格缓存（查找同pair的其他池子，未设置时不做多池拆分）
+    price_cache: Option<Arc<PriceCache>>,
 }
 
 /// 滑点模型
@@ -89,8 +127,15 @@ impl SplitOptimizer {
             max_splits,
             min_split_amount,
             slippage_model: SlippageModel::ConstantProduct,
+            price_cache: None,
         }
     }
+
+    /// 接入价格缓存，启用同pair多池拆分
+    pub fn with_price_cache(mut self, price_cache: Arc<PriceCache>) -> Self {
+        self.price_cache = Some(price_cache);
+        self
+    }
     
     /// 设置滑点模型
     #[allow(dead_code)]
@@ -104,37 +149,154 @@ impl SplitOptimizer {
         paths: &[ArbitragePath],
         total_amount: f64,
     ) -> Vec<OptimizedPath> {
+        let pools = self.price_cache.as_ref()
+            .map(|cache| cache.get_all_prices())
+            .unwrap_or_default();
+        self.optimize_all_with_pools(paths, total_amount, &pools)
+    }
+
+    /// 在给定的池子快照上优化所有路径（扫描快照 / 🧊 离线重放）
+    pub fn optimize_all_with_pools(
+        &self,
+        paths: &[ArbitragePath],
+        total_amount: f64,
+        pools: &[PoolPrice],
+    ) -> Vec<OptimizedPath> {
+        let index = PoolIndex::new(pools);
         let mut optimized = Vec::new();
         
         for path in paths {
-            let opt_path = self.optimize_single_path(path, total_amount);
+            let opt_path = self.optimize_single_path(path, &index);
             optimized.push(opt_path);
         }
         
         // 如果有多条路径，进行多路径资金分配优化
         if paths.len() > 1 {
-            optimized = self.optimize_multi_path_allocation(optimized, total_amount);
+            optimized = self.optimize_multi_path_allocation(optimized, total_amount, &index);
         }
         
         optimized
     }
     
     /// 优化单条路径（在同一个pair的多个池子间拆分）
+    ///
+    /// 拆分后的输出比只走原路径池子更多时，把增量计入净利润；否则保持原路径。
     fn optimize_single_path(
         &self,
         path: &ArbitragePath,
-        _amount: f64,
+        index: &PoolIndex,
     ) -> OptimizedPath {
-        // 对于每一步，检查是否有同pair的其他池子可以拆分
-        // 这里先实现简化版：不拆分，直接返回
-        // TODO: 实现同pair多池拆分
-        
-        OptimizedPath {
+        let mut optimized = OptimizedPath {
             base_path: path.clone(),
             split_strategy: None,
             optimized_net_profit: path.net_profit,
             optimized_roi: path.roi_percent,
+        };
+        
+        let Some((mut strategy, single_pool_output)) = self.split_path(path, path.input_amount, index) else {
+            return optimized;
+        };
+        if !strategy.is_split() || strategy.expected_output <= single_pool_output {
+            return optimized;
+        }
+        
+        optimized.optimized_net_profit = path.net_profit + (strategy.expected_output - single_pool_output);
+        if path.input_amount > 0.0 {
+            optimized.optimized_roi = optimized.optimized_net_profit / path.input_amount * 100.0;
         }
+        strategy.optimized_roi = optimized.optimized_roi;
+        optimized.split_strategy = Some(strategy);
+        optimized
+    }
+    
+    /// 按跳在同pair池子间拆分 amount
+    ///
+    /// 返回 (拆分策略, 只走原路径池子的输出)；某一跳的原池子不在缓存中时返回 None。
+    fn split_path(
+        &self,
+        path: &ArbitragePath,
+        amount: f64,
+        index: &PoolIndex,
+    ) -> Option<(SplitStrategy, f64)> {
+        let mut current = amount;
+        let mut single_pool_output = amount;
+        let mut allocations = Vec::new();
+        let mut hop_impacts_percent = Vec::with_capacity(path.steps.len());
+        
+        for (hop, step) in path.steps.iter().enumerate() {
+            let mut candidates = self.hop_pools(index, step);
+            let own = candidates.iter().find(|p| index.pool(p.pool).pool_id == step.pool_id)?;
+            single_pool_output = own.output(single_pool_output);
+            
+            // 最深的 max_splits 个池子参与拆分
+            candidates.sort_by(|a, b| b.reserve_in.total_cmp(&a.reserve_in));
+            candidates.truncate(self.max_splits.max(1));
+            
+            // 最小拆分金额按第一跳计价，换算到本跳输入代币
+            let min_amount = if amount > 0.0 { self.min_split_amount * current / amount } else { 0.0 };
+            let split = split_across_pools(candidates, current, min_amount);
+            
+            let mut hop_output = 0.0;
+            let mut worst_impact: f64 = 0.0;
+            for (pool, amount_in) in split {
+                let expected_output = pool.output(amount_in);
+                hop_output += expected_output;
+                worst_impact = worst_impact.max(hop_price_impact_percent(
+                    amount_in, expected_output, pool.reserve_in, pool.reserve_out, pool.fee_rate,
+                ));
+                let pool = index.pool(pool.pool);
+                allocations.push(PoolAllocation {
+                    hop,
+                    pool_id: pool.pool_id.clone(),
+                    dex_name: pool.dex_name.clone(),
+                    amount_in,
+                    expected_output,
+                });
+            }
+            hop_impacts_percent.push(worst_impact);
+            current = hop_output;
+        }
+        
+        Some((SplitStrategy {
+            allocations,
+            expected_output: current,
+            optimized_roi: 0.0,
+            hop_impacts_percent,
+        }, single_pool_output))
+    }
+    
+    /// 与该跳同pair、可按 input → output 方向交易的池子（储备已按方向排列，按快照顺序）
+    fn hop_pools(&self, index: &PoolIndex, step: &RouteStep) -> Vec<HopPool> {
+        let Some(pair) = index.pair(&step.input_token, &step.output_token) else {
+            return Vec::new();
+        };
+        // base == 输入代币的池子按 base → quote 交易，反方向的池子按 quote → base
+        let forward = index.pools_of(pair).iter().map(|&id| (id, true));
+        let reverse = index.pools_of(pair.reversed()).iter().map(|&id| (id, false));
+        let mut candidates: Vec<HopPool> = forward.chain(reverse)
+            .filter_map(|(id, base_in)| {
+                let p = index.pool(id);
+                let (base_decimals, quote_decimals) = p.get_decimals();
+                let base_reserve = p.base_reserve as f64 / 10f64.powi(base_decimals as i32);
+                let quote_reserve = p.quote_reserve as f64 / 10f64.powi(quote_decimals as i32);
+                let (reserve_in, reserve_out) = if base_in {
+                    (base_reserve, quote_reserve)
+                } else {
+                    (quote_reserve, base_reserve)
+                };
+                if reserve_in <= 0.0 || reserve_out <= 0.0 {
+                    return None;
+                }
+                Some(HopPool {
+                    pool: id,
+                    reserve_in,
+                    reserve_out,
+                    fee_rate: self.get_pool_fee(&p.pool_id, &p.dex_name),
+                })
+            })
+            .collect();
+        candidates.sort_by_key(|p| p.pool);
+        candidates
     }
     
     /// 🔥 梯度下降优化资金分配（快速近似，10-20x性能提升）
@@ -212,6 +374,7 @@ impl SplitOptimizer {
         &self,
         paths: Vec<OptimizedPath>,
         total_amount: f64,
+        index: &PoolIndex,
     ) -> Vec<OptimizedPath> {
         let n = paths.len();
         
@@ -222,11 +385,9 @@ impl SplitOptimizer {
         if n == 1 {
             // 只有一条路径，全部分配
             let mut result = paths;
-            result[0].split_strategy = Some(SplitStrategy {
-                allocations: vec![(0, total_amount)],
-                expected_output: result[0].optimized_net_profit + total_amount,
-                optimized_roi: result[0].optimized_roi,
-            });
+            let mut strategy = self.allocate_path(&result[0], total_amount, index);
+            strategy.optimized_roi = result[0].optimized_roi;
+            result[0].split_strategy = Some(strategy);
             return result;
         }
         
@@ -244,17 +405,47 @@ impl SplitOptimizer {
         let mut result = paths;
         for (i, &allocated) in allocations.iter().enumerate() {
             if allocated > 0.0 {
-                result[i].split_strategy = Some(SplitStrategy {
-                    allocations: vec![(i, allocated)],
-                    expected_output: self.simulate_path_output(&result[i], allocated),
-                    optimized_roi: result[i].optimized_roi,
-                });
+                let mut strategy = self.allocate_path(&result[i], allocated, index);
+                strategy.optimized_roi = result[i].optimized_roi;
+                result[i].split_strategy = Some(strategy);
             }
         }
         
         result
     }
     
+    /// 路径分到 amount 资金后的池子级子步骤
+    ///
+    /// 能在缓存中找到各跳池子时按同pair拆分，否则按原路径池子逐跳模拟。
+    fn allocate_path(&self, path: &OptimizedPath, amount: f64, index: &PoolIndex) -> SplitStrategy {
+        if let Some((strategy, _)) = self.split_path(&path.base_path, amount, index) {
+            return strategy;
+        }
+        
+        let mut current = amount;
+        let mut allocations = Vec::with_capacity(path.base_path.steps.len());
+        let mut hop_impacts_percent = Vec::with_capacity(path.base_path.steps.len());
+        for (hop, step) in path.base_path.steps.iter().enumerate() {
+            let (output, slippage) = self.simulate_step(path, step, current);
+            allocations.push(PoolAllocation {
+                hop,
+                pool_id: step.pool_id.clone(),
+                dex_name: step.dex_name.clone(),
+                amount_in: current,
+                expected_output: output,
+            });
+            hop_impacts_percent.push(slippage * 100.0);
+            current = output;
+        }
+        
+        SplitStrategy {
+            allocations,
+            expected_output: current,
+            optimized_roi: 0.0,
+            hop_impacts_percent,
+        }
+    }
+    
     /// 完整DP算法（用于小金额）
     fn optimize_with_dp(
         &self,
@@ -321,30 +512,43 @@ impl SplitOptimizer {
     
     /// 模拟路径在指定金额下的输出（考虑滑点）
     fn simulate_path_output(&self, path: &OptimizedPath, amount: f64) -> f64 {
+        self.simulate_path_hops(path, amount).0
+    }
+    
+    /// 模拟路径输出，同时返回每跳的滑点（%）
+    fn simulate_path_hops(&self, path: &OptimizedPath, amount: f64) -> (f64, Vec<f64>) {
         let mut current_amount = amount;
+        let mut hop_impacts = Vec::with_capacity(path.base_path.steps.len());
         
         for step in &path.base_path.steps {
-            // 计算此步骤的滑点
-            let slippage = self.calculate_slippage(
-                step.liquidity_base,
-                step.liquidity_quote,
-                current_amount,
-                &step.input_token,
-                &path.base_path.steps[0].input_token, // 起始代币作为参考
-            );
-            
-            // 获取DEX费用
-            let dex_fee = self.get_dex_fee(&step.dex_name);
-            
-            // 应用费用和滑点
-            let after_fee = current_amount * (1.0 - dex_fee);
-            let after_slippage = after_fee * (1.0 - slippage);
-            
-            // 计算输出
-            current_amount = after_slippage * step.price;
+            let (output, slippage) = self.simulate_step(path, step, current_amount);
+            current_amount = output;
+            hop_impacts.push(slippage * 100.0);
         }
         
-        current_amount
+        (current_amount, hop_impacts)
+    }
+    
+    /// 模拟单跳：返回 (输出, 滑点比例)
+    fn simulate_step(&self, path: &OptimizedPath, step: &RouteStep, amount: f64) -> (f64, f64) {
+        // 计算此步骤的滑点
+        let slippage = self.calculate_slippage(
+            step.liquidity_base,
+            step.liquidity_quote,
+            amount,
+            &step.input_token,
+            &path.base_path.steps[0].input_token, // 起始代币作为参考
+        );
+        
+        // 获取DEX费用
+        let dex_fee = self.get_pool_fee(&step.pool_id, &step.dex_name);
+        
+        // 应用费用和滑点
+        let after_fee = amount * (1.0 - dex_fee);
+        let after_slippage = after_fee * (1.0 - slippage);
+        
+        // 计算输出
+        (after_slippage * step.price, slippage)
     }
     
     /// 使用AMM公式计算滑点
@@ -390,26 +594,223 @@ impl SplitOptimizer {
         }
     }
     
-    /// 获取DEX手续费
-    fn get_dex_fee(&self, dex_name: &str) -> f64 {
-        match dex_name {
-            s if s.contains("Raydium AMM V4") => 0.0025,
-            s if s.contains("Raydium CLMM") => 0.0001,
-            s if s.contains("Orca") || s.contains("Whirlpool") => 0.0001,
-            s if s.contains("Meteora") => 0.0002,
-            s if s.contains("SolFi") => 0.0030,
-            s if s.contains("AlphaQ") => 0.0001,
-            s if s.contains("HumidiFi") => 0.0010,
-            s if s.contains("Lifinity") => 0.0000,
-            s if s.contains("Stabble") => 0.0004,
-            _ => 0.0025,
+    /// 获取池子手续费（池子级覆盖优先，其次DEX默认值）
+    fn get_pool_fee(&self, pool_id: &str, dex_name: &str) -> f64 {
+        crate::fee_registry::fee_rate(pool_id, dex_name)
+    }
+}
+
+/// 一次优化的池子索引：代币驻留为 `TokenId`，池子按有方向的 `PairId` 分组
+struct PoolIndex<'a> {
+    /// 池子快照（下标即 `PoolId`）
+    pools: &'a [PoolPrice],
+    registry: TokenRegistry,
+    by_pair: HashMap<PairId, Vec<PoolId>>,
+}
+
+impl<'a> PoolIndex<'a> {
+    fn new(pools: &'a [PoolPrice]) -> Self {
+        let mut registry = TokenRegistry::new();
+        let mut by_pair: HashMap<PairId, Vec<PoolId>> = HashMap::new();
+        for (i, pool) in pools.iter().enumerate() {
+            let Some((base, quote)) = pool_tokens(pool) else { continue };
+            let pair = PairId::new(registry.intern(&base), registry.intern(&quote));
+            by_pair.entry(pair).or_default().push(PoolId::new(i));
         }
+        Self { pools, registry, by_pair }
+    }
+
+    fn pool(&self, id: PoolId) -> &'a PoolPrice {
+        &self.pools[id.index()]
+    }
+
+    /// input → output 对应的交易对（快照中没有这两个代币时为 None）
+    fn pair(&self, input: &str, output: &str) -> Option<PairId> {
+        Some(PairId::new(self.registry.get(input)?, self.registry.get(output)?))
     }
+
+    fn pools_of(&self, pair: PairId) -> &[PoolId] {
+        self.by_pair.get(&pair).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+/// 一跳的候选池子（储备已按交易方向排列，UI 单位）
+#[derive(Debug, Clone)]
+struct HopPool {
+    /// `PoolIndex` 中的池子
+    pool: PoolId,
+    reserve_in: f64,
+    reserve_out: f64,
+    fee_rate: f64,
+}
+
+impl HopPool {
+    /// 恒定乘积输出：y * Δx' / (x + Δx')，Δx' 为扣费后的输入
+    fn output(&self, amount_in: f64) -> f64 {
+        let effective_in = amount_in * (1.0 - self.fee_rate);
+        if effective_in <= 0.0 {
+            return 0.0;
+        }
+        self.reserve_out * effective_in / (self.reserve_in + effective_in)
+    }
+    
+    /// 边际输出 d(output)/d(amount_in) = x * y * γ / (x + γΔx)²
+    fn marginal_output(&self, amount_in: f64) -> f64 {
+        let gamma = 1.0 - self.fee_rate;
+        let denominator = self.reserve_in + gamma * amount_in;
+        self.reserve_in * self.reserve_out * gamma / (denominator * denominator)
+    }
+}
+
+/// 把 amount 拆到多个池子，丢弃低于 min_amount 的分配后重新求解
+fn split_across_pools(mut pools: Vec<HopPool>, amount: f64, min_amount: f64) -> Vec<(HopPool, f64)> {
+    loop {
+        let split = optimal_split(&pools, amount);
+        let keep: Vec<bool> = split.iter().map(|&x| x >= min_amount && x > 0.0).collect();
+        if pools.len() <= 1 || keep.iter().all(|&k| k) {
+            return pools.into_iter().zip(split).filter(|(_, x)| *x > 0.0).collect();
+        }
+        if !keep.iter().any(|&k| k) {
+            // 每份都太小：全部投入最深的池子（已按深度排序）
+            pools.truncate(1);
+            continue;
+        }
+        let mut keep = keep.into_iter();
+        pools.retain(|_| keep.next().unwrap_or(false));
+    }
+}
+
+/// 使各池子边际输出相等的最优拆分（两个池子闭式解，更多池子梯度下降）
+fn optimal_split(pools: &[HopPool], amount: f64) -> Vec<f64> {
+    let split = match pools.len() {
+        0 => return Vec::new(),
+        1 => return vec![amount],
+        2 => two_pool_split(&pools[0], &pools[1], amount),
+        _ => gradient_split(pools, amount),
+    };
+    
+    // 兜底：不劣于全部投入单个最好的池子
+    let total = |alloc: &[f64]| -> f64 { pools.iter().zip(alloc).map(|(p, &x)| p.output(x)).sum() };
+    let best_single = (0..pools.len())
+        .max_by(|&a, &b| pools[a].output(amount).total_cmp(&pools[b].output(amount)))
+        .unwrap_or(0);
+    if pools[best_single].output(amount) > total(&split) {
+        let mut single = vec![0.0; pools.len()];
+        single[best_single] = amount;
+        return single;
+    }
+    split
+}
+
+/// 两个池子的闭式解
+///
+/// 令 s = √(x·y·γ)，边际相等 s₁/(x₁+γ₁a) = s₂/(x₂+γ₂(A−a)) 解得
+/// a = (s₁(x₂+γ₂A) − s₂x₁) / (s₂γ₁ + s₁γ₂)，再截断到 [0, A]。
+fn two_pool_split(a: &HopPool, b: &HopPool, amount: f64) -> Vec<f64> {
+    let (gamma_a, gamma_b) = (1.0 - a.fee_rate, 1.0 - b.fee_rate);
+    let s_a = (a.reserve_in * a.reserve_out * gamma_a).sqrt();
+    let s_b = (b.reserve_in * b.reserve_out * gamma_b).sqrt();
+    let denominator = s_b * gamma_a + s_a * gamma_b;
+    if denominator <= 0.0 {
+        return vec![amount, 0.0];
+    }
+    let to_a = ((s_a * (b.reserve_in + gamma_b * amount) - s_b * a.reserve_in) / denominator)
+        .clamp(0.0, amount);
+    vec![to_a, amount - to_a]
+}
+
+/// 多个池子：投影梯度下降（保持总和 = amount，分配 ≥ 0）
+///
+/// 步长按池子深度缩放：边际输出对投入的弹性是 -2γ/(x + γΔx)，
+/// 深池需要更大的调整量才能拉平相同的边际差。
+fn gradient_split(pools: &[HopPool], amount: f64) -> Vec<f64> {
+    let n = pools.len();
+    let mut allocations = vec![amount / n as f64; n];
+    let learning_rate = 0.5;
+    let max_iterations = 500;
+    let convergence_threshold = 1e-9;
+    
+    for _ in 0..max_iterations {
+        let marginals: Vec<f64> = pools.iter()
+            .zip(&allocations)
+            .map(|(pool, &x)| pool.marginal_output(x))
+            .collect();
+        let mean = marginals.iter().sum::<f64>() / n as f64;
+        if mean <= 0.0 {
+            break;
+        }
+        let max_gap = marginals.iter().map(|m| (m - mean).abs() / mean).fold(0.0, f64::max);
+        if max_gap < convergence_threshold {
+            break;
+        }
+        
+        for ((x, m), pool) in allocations.iter_mut().zip(&marginals).zip(pools) {
+            let gamma = 1.0 - pool.fee_rate;
+            let depth = (pool.reserve_in + gamma * *x) / (2.0 * gamma);
+            *x = (*x + learning_rate * depth * (m - mean) / mean).max(0.0);
+        }
+        
+        // 归一化：确保总和 = amount
+        let sum: f64 = allocations.iter().sum();
+        if sum > 0.0 {
+            for x in allocations.iter_mut() {
+                *x *= amount / sum;
+            }
+        }
+    }
+    
+    allocations
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Instant;
+    use crate::router::ArbitrageType;
+    
+    fn sol_usdc_pool(pool_id: &str, sol: u64, usdc: u64) -> PoolPrice {
+        PoolPrice {
+            base_reserve: sol * 1_000_000_000,
+            quote_reserve: usdc * 1_000_000,
+            ..PoolPrice::for_test(pool_id, "SOL/USDC", usdc as f64 / sol as f64)
+        }
+    }
+    
+    #[test]
+    fn test_hop_pools_match_both_orientations() {
+        let mut reversed = sol_usdc_pool("reversed", 2_000, 300_000);
+        reversed.pair = "USDC/SOL".to_string();
+        (reversed.base_reserve, reversed.quote_reserve) = (300_000 * 1_000_000, 2_000 * 1_000_000_000);
+        (reversed.base_decimals, reversed.quote_decimals) = (6, 9);
+        let mut other = sol_usdc_pool("other", 1_000, 150_000);
+        other.pair = "JUP/USDC".to_string();
+        let pools = vec![sol_usdc_pool("forward", 1_000, 150_000), other, reversed];
+        let index = PoolIndex::new(&pools);
+        
+        let step = |input: &str, output: &str| RouteStep {
+            pool_id: "forward".to_string(),
+            dex_name: "Raydium AMM V4".to_string(),
+            input_token: input.to_string(),
+            output_token: output.to_string(),
+            price: 0.0,
+            liquidity_base: 0,
+            liquidity_quote: 0,
+            expected_input: 0.0,
+            expected_output: 0.0,
+            price_impact_percent: 0.0,
+            effective_fee_bps: 25.0,
+            raw_input_token: None,
+            raw_output_token: None,
+        };
+        let optimizer = SplitOptimizer::new(5, 100.0);
+        let candidates = optimizer.hop_pools(&index, &step("USDC", "SOL"));
+        let ids: Vec<&str> = candidates.iter().map(|p| index.pool(p.pool).pool_id.as_str()).collect();
+        assert_eq!(ids, vec!["forward", "reversed"]);
+        // 储备按 USDC → SOL 方向排列（UI 单位）
+        assert_eq!((candidates[0].reserve_in, candidates[0].reserve_out), (150_000.0, 1_000.0));
+        assert_eq!((candidates[1].reserve_in, candidates[1].reserve_out), (300_000.0, 2_000.0));
+        assert!(optimizer.hop_pools(&index, &step("USDC", "BONK")).is_empty());
+    }
     
     #[test]
     fn test_slippage_calculation() {
@@ -435,11 +836,83 @@ mod tests {
         
         // TODO: 添加实际的DP测试用例
     }
+    
+    #[test]
+    fn test_splits_same_pair_toward_deeper_pool() {
+        let cache = Arc::new(PriceCache::new());
+        cache.update_price(sol_usdc_pool("shallow", 1_000, 150_000));
+        cache.update_price(sol_usdc_pool("deep", 10_000, 1_500_000));
+        
+        // 路径只走浅池：10,000 USDC → SOL
+        let amount = 10_000.0;
+        let path = ArbitragePath {
+            arb_type: ArbitrageType::Direct,
+            steps: vec![RouteStep {
+                pool_id: "shallow".to_string(),
+                dex_name: "Raydium AMM V4".to_string(),
+                input_token: "USDC".to_string(),
+                output_token: "SOL".to_string(),
+                price: 1.0 / 150.0,
+                liquidity_base: 1_000 * 1_000_000_000,
+                liquidity_quote: 150_000 * 1_000_000,
+                expected_input: amount,
+                expected_output: amount / 150.0,
+                price_impact_percent: 0.0,
+                effective_fee_bps: 25.0,
+                raw_input_token: None,
+                raw_output_token: None,
+            }],
+            start_token: "USDC".to_string(),
+            end_token: "SOL".to_string(),
+            input_amount: amount,
+            output_amount: amount / 150.0,
+            gross_profit: 0.0,
+            dex_fees: 0.0,
+            execution_fees: 0.0,
+            estimated_fees: 0.0,
+            net_profit: 0.0,
+            roi_percent: 0.0,
+            discovered_at: Instant::now(),
+        };
+        
+        let optimizer = SplitOptimizer::new(5, 100.0).with_price_cache(cache);
+        let optimized = optimizer.optimize_all(std::slice::from_ref(&path), amount);
+        let strategy = optimized[0].split_strategy.as_ref().expect("should split across both pools");
+        
+        let amount_to = |pool_id: &str| strategy.allocations.iter()
+            .find(|a| a.pool_id == pool_id)
+            .map(|a| a.amount_in)
+            .unwrap_or(0.0);
+        assert!(amount_to("deep") > amount_to("shallow"));
+        assert!(amount_to("shallow") > 0.0);
+        assert!((strategy.input_amount() - amount).abs() < 1e-6);
+        
+        // 拆分后的总输出严格大于只走原路径池子，且增量计入净利润
+        let pools = optimizer.price_cache.as_ref().unwrap().get_all_prices();
+        let index = PoolIndex::new(&pools);
+        let (_, single_pool_output) = optimizer.split_path(&path, amount, &index).unwrap();
+        let shallow = optimizer.hop_pools(&index, &path.steps[0]).into_iter()
+            .find(|p| index.pool(p.pool).pool_id == "shallow")
+            .unwrap();
+        assert!((single_pool_output - shallow.output(amount)).abs() < 1e-9);
+        assert!(strategy.expected_output > single_pool_output);
+        assert!(optimized[0].optimized_net_profit > path.net_profit);
+        
+        // 三个池子走梯度下降：各池边际输出趋于相等
+        let pools: Vec<HopPool> = [(1_000.0, 150_000.0), (10_000.0, 1_500_000.0), (4_000.0, 600_000.0)]
+            .iter()
+            .enumerate()
+            .map(|(i, &(sol, usdc))| HopPool {
+                pool: PoolId::new(i),
+                reserve_in: usdc,
+                reserve_out: sol,
+                fee_rate: 0.0025,
+            })
+            .collect();
+        let split = optimal_split(&pools, amount);
+        assert!((split.iter().sum::<f64>() - amount).abs() < 1e-6);
+        assert!(split[1] > split[2] && split[2] > split[0]);
+        let marginals: Vec<f64> = pools.iter().zip(&split).map(|(p, &x)| p.marginal_output(x)).collect();
+        assert!((marginals[0] - marginals[1]).abs() / marginals[1] < 1e-3);
+    }
 }
-
-
-
-
-
-
-
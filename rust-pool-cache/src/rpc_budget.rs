@@ -0,0 +1,121 @@
+/*!
+ * RPC 请求预算（令牌桶）
+ *
+ * 活跃端点的 HTTP RPC 由 vault 预取、Phoenix 刷新、池子初始化等共用，
+ * 突发请求很容易触发服务商的 429。这里按令牌桶放行请求：
+ * 长期速率不超过 requests_per_second，空闲后最多允许 burst 个请求连续发出，
+ * 超出的调用方等待（异步调用方 sleep，同步调用方阻塞当前线程）。
+ *
+ * 实现为 GCRA：只记录"理论到达时间"，不需要后台补充令牌的定时器。
+ */
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 共享的 RPC 请求预算
+#[derive(Debug)]
+pub struct RpcBudget {
+    /// 相邻两次请求的平均间隔（0 = 不限速）
+    interval: Duration,
+    /// 允许提前放行的时长：(burst - 1) * interval
+    burst_tolerance: Duration,
+    /// 理论到达时间（下一个请求"按速率"应该发出的时刻）
+    next_slot: Mutex<Option<Instant>>,
+}
+
+impl RpcBudget {
+    /// requests_per_second <= 0 表示不限速（不允许突发）
+    pub fn new(requests_per_second: f64) -> Self {
+        Self::with_burst(requests_per_second, 1)
+    }
+
+    /// 令牌桶：速率 requests_per_second，桶容量 burst（至少为 1）
+    pub fn with_burst(requests_per_second: f64, burst: u32) -> Self {
+        let interval = if requests_per_second > 0.0 && requests_per_second.is_finite() {
+            Duration::from_secs_f64(1.0 / requests_per_second)
+        } else {
+            Duration::ZERO
+        };
+        Self {
+            interval,
+            burst_tolerance: interval * burst.max(1).saturating_sub(1),
+            next_slot: Mutex::new(None),
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Self::new(0.0)
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.interval.is_zero()
+    }
+
+    /// 等待直到可以发出下一次请求，返回实际等待的时长
+    pub async fn acquire(&self) -> Duration {
+        let wait = self.reserve_at(Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        wait
+    }
+
+    /// 同步版本（在 spawn_blocking 线程或启动阶段的同步代码中使用）
+    pub fn acquire_blocking(&self) -> Duration {
+        let wait = self.reserve_at(Instant::now());
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+        wait
+    }
+
+    /// 预约一次请求，返回需要等待的时长
+    fn reserve_at(&self, now: Instant) -> Duration {
+        if self.interval.is_zero() {
+            return Duration::ZERO;
+        }
+        let mut next_slot = self.next_slot.lock().unwrap();
+        let tat = next_slot.map_or(now, |slot| slot.max(now));
+        *next_slot = Some(tat + self.interval);
+        (tat - now).saturating_sub(self.burst_tolerance)
+    }
+}
+
+impl Default for RpcBudget {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_are_spaced_by_budget() {
+        let budget = RpcBudget::new(10.0);
+        let now = Instant::now();
+
+        let waits: Vec<u128> = (0..4).map(|_| budget.reserve_at(now).as_millis()).collect();
+        assert_eq!(waits, vec![0, 100, 200, 300]);
+
+        // 空闲一段时间后不累积额度，也不需要等待
+        assert_eq!(budget.reserve_at(now + Duration::from_secs(5)), Duration::ZERO);
+        assert!(RpcBudget::unlimited().reserve_at(now).is_zero());
+    }
+
+    #[test]
+    fn test_burst_then_steady_rate() {
+        let budget = RpcBudget::with_burst(10.0, 3);
+        let now = Instant::now();
+
+        // 桶满：前 3 个立即放行，之后按 100ms 间隔
+        let waits: Vec<u128> = (0..6).map(|_| budget.reserve_at(now).as_millis()).collect();
+        assert_eq!(waits, vec![0, 0, 0, 100, 200, 300]);
+
+        // 空闲足够久后桶重新装满，但不会超过 burst
+        let later = now + Duration::from_secs(10);
+        let waits: Vec<u128> = (0..4).map(|_| budget.reserve_at(later).as_millis()).collect();
+        assert_eq!(waits, vec![0, 0, 0, 100]);
+    }
+}
@@ -0,0 +1,549 @@
+/*!
+ * 共享 RPC 管理器
+ *
+ * 池子初始化、vault 预取、Phoenix 刷新、Stake Pool 读取、mint 元数据和链上模拟
+ * 以前各自创建 RpcClient，彼此看不到对方的请求速率，启动阶段一起撞上服务商的 429。
+ * 这里把它们收拢到一个共享句柄：
+ *
+ * - 端点列表 + 故障转移：连接失败 / 超时 / 429 时本次调用换下一个端点重试；
+ *   同一端点连续失败 `failover_after_failures` 次（429 立即）后切换全局活跃端点
+ * - 令牌桶限速（`RpcBudget`）：所有调用方共用一个桶
+ * - 同步 RpcClient 调用自动包进 spawn_blocking，调用方保持 async
+ * - 每个调用方一个标签，按 (caller, outcome) 计数导出到 /metrics
+ *
+ * ```ignore
+ * let rpc = manager.handle("phoenix_refresh");
+ * let response = rpc.call(move |client| {
+ *     client.get_account_with_commitment(&pubkey, CommitmentConfig::confirmed()).map_err(Box::new)
+ * }).await?;
+ * ```
+ */
+
+use std::collections::HashSet;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use serde::Serialize;
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcError;
+use solana_sdk::commitment_config::CommitmentConfig;
+use tracing::{debug, info, warn};
+
+use crate::config::Config;
+use crate::endpoint_pool::rpc_url_for;
+use crate::prometheus::{MetricKind, PrometheusWriter};
+use crate::rpc_budget::RpcBudget;
+
+/// 单个请求的默认超时
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 默认：同一端点连续失败 3 次后切换
+pub const DEFAULT_FAILOVER_AFTER: u32 = 3;
+
+/// RPC 调用结果：`ClientError` 有好几百字节，装箱后再在调用方之间传递
+pub type RpcResult<T> = Result<T, Box<ClientError>>;
+
+/// 限速等待超过该时长时以 info 级别记录（其余为 debug）
+const THROTTLE_LOG_THRESHOLD: Duration = Duration::from_millis(500);
+
+/// 一次 RPC 调用的结果分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Ok,
+    /// 端点正常响应但请求本身失败（账户不存在、模拟失败等），不换端点
+    RpcError,
+    /// 连接失败 / 超时
+    TransportError,
+    /// HTTP 429
+    RateLimited,
+}
+
+impl Outcome {
+    fn classify<T>(result: &RpcResult<T>) -> Self {
+        let error = match result {
+            Ok(_) => return Outcome::Ok,
+            Err(error) => error,
+        };
+        match error.kind() {
+            ClientErrorKind::Reqwest(e) if e.status().map(|s| s.as_u16()) == Some(429) => Outcome::RateLimited,
+            ClientErrorKind::Reqwest(_) | ClientErrorKind::Io(_) => Outcome::TransportError,
+            _ if error.to_string().contains("429") => Outcome::RateLimited,
+            // RpcClient 先查 cluster 版本时，连接错误被压成 RpcRequestError 字符串
+            ClientErrorKind::RpcError(RpcError::RpcRequestError(message))
+                if TRANSPORT_ERROR_MARKERS.iter().any(|marker| message.contains(marker)) => Outcome::TransportError,
+            _ => Outcome::RpcError,
+        }
+    }
+
+    /// 是否是端点的问题（换端点可能成功）
+    fn is_endpoint_failure(&self) -> bool {
+        matches!(self, Outcome::TransportError | Outcome::RateLimited)
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Ok => "ok",
+            Outcome::RpcError => "rpc_error",
+            Outcome::TransportError => "transport_error",
+            Outcome::RateLimited => "rate_limited",
+        }
+    }
+}
+
+/// reqwest 连接 / 超时错误的文本特征（错误被转成字符串后只能按文本识别）
+const TRANSPORT_ERROR_MARKERS: [&str; 3] = ["error sending request", "error trying to connect", "operation timed out"];
+
+const OUTCOMES: [Outcome; 4] = [Outcome::Ok, Outcome::RpcError, Outcome::TransportError, Outcome::RateLimited];
+
+struct RpcEndpoint {
+    url: String,
+    client: Arc<RpcClient>,
+    consecutive_failures: AtomicU32,
+}
+
+/// 单个调用方的计数
+#[derive(Debug, Default)]
+struct CallerCounters {
+    /// 按 OUTCOMES 顺序
+    outcomes: [AtomicU64; 4],
+    /// 在令牌桶上等待过的请求数
+    throttled: AtomicU64,
+    throttle_wait_micros: AtomicU64,
+}
+
+/// 调用方请求统计（日志 / 测试）
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CallerStats {
+    pub caller: &'static str,
+    pub ok: u64,
+    pub rpc_errors: u64,
+    pub transport_errors: u64,
+    pub rate_limited: u64,
+    pub throttled: u64,
+    pub throttle_wait_secs: f64,
+}
+
+/// 所有 RPC 消费者共用的端点、限速器和计数器
+pub struct RpcManager {
+    endpoints: Vec<RpcEndpoint>,
+    active: AtomicUsize,
+    budget: Arc<RpcBudget>,
+    failover_after: u32,
+    /// 派生管理器（`derive`）共用同一张计数表
+    callers: Arc<DashMap<&'static str, Arc<CallerCounters>>>,
+}
+
+impl RpcManager {
+    /// 按配置顺序创建（重复地址只保留一个；第一个为初始活跃端点）
+    ///
+    /// # Panics
+    /// `urls` 为空时 panic
+    pub fn new(urls: Vec<String>, timeout: Duration, budget: Arc<RpcBudget>) -> Self {
+        let mut seen = HashSet::new();
+        let endpoints: Vec<RpcEndpoint> = urls.into_iter()
+            .filter(|url| seen.insert(url.clone()))
+            .map(|url| RpcEndpoint {
+                client: Arc::new(RpcClient::new_with_timeout_and_commitment(
+                    url.clone(),
+                    timeout,
+                    CommitmentConfig::confirmed(),
+                )),
+                url,
+                consecutive_failures: AtomicU32::new(0),
+            })
+            .collect();
+        assert!(!endpoints.is_empty(), "RpcManager requires at least one endpoint");
+
+        Self {
+            endpoints,
+            active: AtomicUsize::new(0),
+            budget,
+            failover_after: DEFAULT_FAILOVER_AFTER,
+            callers: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// 单端点、不限速（测试和独立工具）
+    pub fn unlimited(url: &str) -> Self {
+        Self::new(vec![url.to_string()], DEFAULT_TIMEOUT, Arc::new(RpcBudget::unlimited()))
+    }
+
+    /// 按 `[rpc]` 配置创建（未配置时使用默认值，端点来自 `[initialization]` 和 WebSocket 地址）
+    pub fn from_config(config: &Config) -> Self {
+        let settings = config.rpc.clone().unwrap_or_default();
+        let urls = if settings.urls.is_empty() {
+            config.initialization.iter()
+                .flat_map(|init| init.rpc_urls.iter().cloned())
+                .chain(config.websocket_urls().iter().map(|url| rpc_url_for(url)))
+                .collect()
+        } else {
+            settings.urls.clone()
+        };
+        let requests_per_second = settings.requests_per_second
+            .unwrap_or(config.websocket.rpc_requests_per_second);
+
+        let manager = Self::new(
+            urls,
+            Duration::from_millis(settings.timeout_ms),
+            Arc::new(RpcBudget::with_burst(requests_per_second, settings.burst)),
+        )
+        .with_failover_after(settings.failover_after_failures);
+
+        if manager.budget.is_unlimited() {
+            info!("🛰️  RPC manager: {} endpoint(s), no rate limit", manager.endpoints.len());
+        } else {
+            info!(
+                "🛰️  RPC manager: {} endpoint(s), {} req/s (burst {})",
+                manager.endpoints.len(), requests_per_second, settings.burst.max(1)
+            );
+        }
+        manager
+    }
+
+    pub fn with_failover_after(mut self, failures: u32) -> Self {
+        self.failover_after = failures.max(1);
+        self
+    }
+
+    /// 单独配置了端点的组件（发现、模拟）：独立的端点列表和超时，共用令牌桶和计数
+    pub fn derive(&self, urls: Vec<String>, timeout: Duration) -> Self {
+        Self {
+            callers: Arc::clone(&self.callers),
+            ..Self::new(urls, timeout, self.budget()).with_failover_after(self.failover_after)
+        }
+    }
+
+    /// 带调用方标签的句柄（标签用于日志和 /metrics）
+    pub fn handle(self: &Arc<Self>, caller: &'static str) -> RpcHandle {
+        RpcHandle {
+            manager: Arc::clone(self),
+            caller,
+        }
+    }
+
+    /// 共享的令牌桶（不经过 RpcClient 的请求也应消耗预算）
+    pub fn budget(&self) -> Arc<RpcBudget> {
+        Arc::clone(&self.budget)
+    }
+
+    pub fn endpoint_count(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    pub fn urls(&self) -> Vec<String> {
+        self.endpoints.iter().map(|e| e.url.clone()).collect()
+    }
+
+    pub fn active_url(&self) -> &str {
+        &self.endpoints[self.active.load(Ordering::SeqCst)].url
+    }
+
+    /// 各调用方的请求统计（按标签排序）
+    pub fn caller_stats(&self) -> Vec<CallerStats> {
+        let mut stats: Vec<CallerStats> = self.callers.iter()
+            .map(|entry| {
+                let counters = entry.value();
+                let outcome = |i: usize| counters.outcomes[i].load(Ordering::Relaxed);
+                CallerStats {
+                    caller: entry.key(),
+                    ok: outcome(0),
+                    rpc_errors: outcome(1),
+                    transport_errors: outcome(2),
+                    rate_limited: outcome(3),
+                    throttled: counters.throttled.load(Ordering::Relaxed),
+                    throttle_wait_secs: counters.throttle_wait_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0,
+                }
+            })
+            .collect();
+        stats.sort_by_key(|s| s.caller);
+        stats
+    }
+
+    /// 🛰️ 按调用方的请求计数和限速等待（Prometheus 格式）
+    pub fn write_prometheus(&self, writer: &mut PrometheusWriter) {
+        let stats = self.caller_stats();
+
+        writer.family(
+            "pool_cache_rpc_requests_total",
+            "HTTP RPC requests per caller and outcome (ok, rpc_error, transport_error, rate_limited)",
+            MetricKind::Counter,
+        );
+        for s in &stats {
+            let counts = [s.ok, s.rpc_errors, s.transport_errors, s.rate_limited];
+            for (outcome, count) in OUTCOMES.iter().zip(counts) {
+                writer.sample("pool_cache_rpc_requests_total", &[("caller", s.caller), ("outcome", outcome.as_str())], count as f64);
+            }
+        }
+
+        writer.family(
+            "pool_cache_rpc_throttled_total",
+            "HTTP RPC requests delayed by the shared rate limiter",
+            MetricKind::Counter,
+        );
+        for s in &stats {
+            writer.sample("pool_cache_rpc_throttled_total", &[("caller", s.caller)], s.throttled as f64);
+        }
+
+        writer.family(
+            "pool_cache_rpc_throttle_wait_seconds_total",
+            "Total time callers waited on the shared rate limiter",
+            MetricKind::Counter,
+        );
+        for s in &stats {
+            writer.sample("pool_cache_rpc_throttle_wait_seconds_total", &[("caller", s.caller)], s.throttle_wait_secs);
+        }
+    }
+
+    fn counters(&self, caller: &'static str) -> Arc<CallerCounters> {
+        Arc::clone(&self.callers.entry(caller).or_default())
+    }
+
+    fn record_wait(&self, caller: &'static str, wait: Duration) {
+        if wait.is_zero() {
+            return;
+        }
+        let counters = self.counters(caller);
+        counters.throttled.fetch_add(1, Ordering::Relaxed);
+        counters.throttle_wait_micros.fetch_add(wait.as_micros() as u64, Ordering::Relaxed);
+        if wait >= THROTTLE_LOG_THRESHOLD {
+            info!("🪣 RPC throttled: {} waited {}ms", caller, wait.as_millis());
+        } else {
+            debug!("🪣 RPC throttled: {} waited {}ms", caller, wait.as_millis());
+        }
+    }
+
+    /// 记录一次尝试的结果，必要时切换活跃端点
+    fn record_outcome(&self, caller: &'static str, index: usize, outcome: Outcome) {
+        let position = OUTCOMES.iter().position(|o| *o == outcome).unwrap_or(0);
+        self.counters(caller).outcomes[position].fetch_add(1, Ordering::Relaxed);
+
+        let endpoint = &self.endpoints[index];
+        match outcome {
+            Outcome::Ok | Outcome::RpcError => endpoint.consecutive_failures.store(0, Ordering::Relaxed),
+            Outcome::TransportError => {
+                let failures = endpoint.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+                if failures >= self.failover_after {
+                    self.fail_over_from(index, outcome);
+                }
+            }
+            Outcome::RateLimited => self.fail_over_from(index, outcome),
+        }
+    }
+
+    /// 活跃端点仍是 `index` 时切到下一个（并发调用方只切一次）
+    fn fail_over_from(&self, index: usize, outcome: Outcome) {
+        if self.endpoints.len() < 2 {
+            return;
+        }
+        let next = (index + 1) % self.endpoints.len();
+        if self.active.compare_exchange(index, next, Ordering::SeqCst, Ordering::SeqCst).is_ok() {
+            self.endpoints[index].consecutive_failures.store(0, Ordering::Relaxed);
+            warn!(
+                "🛰️  RPC endpoint {} {}, switching to {}",
+                self.endpoints[index].url, outcome.as_str(), self.endpoints[next].url
+            );
+        }
+    }
+}
+
+/// 带调用方标签的共享 RPC 句柄（clone 开销很小）
+#[derive(Clone)]
+pub struct RpcHandle {
+    manager: Arc<RpcManager>,
+    caller: &'static str,
+}
+
+impl RpcHandle {
+    pub fn caller(&self) -> &'static str {
+        self.caller
+    }
+
+    pub fn manager(&self) -> &Arc<RpcManager> {
+        &self.manager
+    }
+
+    /// 同一管理器、不同调用方标签
+    pub fn with_caller(&self, caller: &'static str) -> RpcHandle {
+        self.manager.handle(caller)
+    }
+
+    /// 限速后在 spawn_blocking 中执行 `f`；端点故障时换下一个端点重试（每个端点最多一次）
+    ///
+    /// `f` 返回的 `ClientError` 用 `.map_err(Box::new)` 装箱（见 [`RpcResult`]）
+    pub async fn call<T, F>(&self, f: F) -> RpcResult<T>
+    where
+        T: Send + 'static,
+        F: Fn(&RpcClient) -> RpcResult<T> + Send + Sync + 'static,
+    {
+        let f = Arc::new(f);
+        let start = self.manager.active.load(Ordering::SeqCst);
+        for attempt in 0..self.manager.endpoints.len() {
+            let index = (start + attempt) % self.manager.endpoints.len();
+            let wait = self.manager.budget.acquire().await;
+            self.manager.record_wait(self.caller, wait);
+
+            let client = Arc::clone(&self.manager.endpoints[index].client);
+            let f = Arc::clone(&f);
+            let result = match tokio::task::spawn_blocking(move || f(&client)).await {
+                Ok(result) => result,
+                Err(e) => Err(Box::new(ClientError::from(ClientErrorKind::Custom(format!("RPC task failed: {}", e))))),
+            };
+            if let ControlFlow::Break(result) = self.settle(index, attempt, result) {
+                return result;
+            }
+        }
+        unreachable!("settle breaks on the last attempt")
+    }
+
+    /// 同步版本：只能在 spawn_blocking 线程或没有 tokio 运行时的代码中调用
+    /// （限速等待会阻塞当前线程）
+    pub fn call_blocking<T, F>(&self, f: F) -> RpcResult<T>
+    where
+        F: Fn(&RpcClient) -> RpcResult<T>,
+    {
+        let start = self.manager.active.load(Ordering::SeqCst);
+        for attempt in 0..self.manager.endpoints.len() {
+            let index = (start + attempt) % self.manager.endpoints.len();
+            let wait = self.manager.budget.acquire_blocking();
+            self.manager.record_wait(self.caller, wait);
+
+            let result = f(&self.manager.endpoints[index].client);
+            if let ControlFlow::Break(result) = self.settle(index, attempt, result) {
+                return result;
+            }
+        }
+        unreachable!("settle breaks on the last attempt")
+    }
+
+    /// 记录结果；端点故障且还有其他端点可试时继续
+    fn settle<T>(&self, index: usize, attempt: usize, result: RpcResult<T>) -> ControlFlow<RpcResult<T>> {
+        let outcome = Outcome::classify(&result);
+        self.manager.record_outcome(self.caller, index, outcome);
+
+        let has_next = attempt + 1 < self.manager.endpoints.len();
+        if outcome.is_endpoint_failure() && has_next {
+            if let Err(e) = &result {
+                debug!("🛰️  {} RPC via {} failed ({}), trying next endpoint", self.caller, self.manager.endpoints[index].url, e);
+            }
+            return ControlFlow::Continue(());
+        }
+        ControlFlow::Break(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// 最小 JSON-RPC HTTP 端点：按方法名应答（`results` 为 方法 → result 的对象）
+    ///
+    /// RpcClient 在部分调用前会先查 `getVersion`，这里总是应答一个版本号；
+    /// 未列出的方法返回 JSON-RPC "Method not found"。
+    fn spawn_rpc_endpoint(results: serde_json::Value) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                // 读完请求头和 Content-Length 指定的请求体
+                let request = loop {
+                    let n = match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break None,
+                        Ok(n) => n,
+                    };
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request);
+                    let Some((head, body)) = text.split_once("\r\n\r\n") else { continue };
+                    let length = head.lines()
+                        .find_map(|line| line.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap_or(0)))
+                        .unwrap_or(0);
+                    if body.len() >= length {
+                        break Some(serde_json::from_str::<serde_json::Value>(body).unwrap_or_default());
+                    }
+                };
+                let Some(request) = request else { continue };
+                let method = request["method"].as_str().unwrap_or_default();
+                let response = match (method, results.get(method)) {
+                    (_, Some(result)) => serde_json::json!({ "jsonrpc": "2.0", "result": result, "id": request["id"] }),
+                    ("getVersion", None) => serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "result": { "solana-core": "1.18.26", "feature-set": 3241752014u32 },
+                        "id": request["id"],
+                    }),
+                    _ => serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "error": { "code": -32601, "message": "Method not found" },
+                        "id": request["id"],
+                    }),
+                };
+                let body = response.to_string();
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_fails_over_to_healthy_endpoint_and_counts_by_caller() {
+        let healthy = spawn_rpc_endpoint(serde_json::json!({ "getSlot": 42 }));
+        let manager = Arc::new(
+            RpcManager::new(
+                vec!["http://127.0.0.1:9".to_string(), healthy.clone(), healthy.clone()],
+                Duration::from_secs(2),
+                Arc::new(RpcBudget::unlimited()),
+            )
+            .with_failover_after(1),
+        );
+        assert_eq!(manager.endpoint_count(), 2);
+
+        // 第一个端点拒绝连接：本次调用换到第二个端点，全局活跃端点随之切换
+        let rpc = manager.handle("pool_initializer");
+        assert_eq!(rpc.call_blocking(|client| client.get_slot().map_err(Box::new)).unwrap(), 42);
+        assert_eq!(manager.active_url(), healthy);
+
+        assert_eq!(rpc.with_caller("phoenix_refresh").call_blocking(|client| client.get_slot().map_err(Box::new)).unwrap(), 42);
+
+        let stats = manager.caller_stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!((stats[0].caller, stats[0].ok, stats[0].transport_errors), ("phoenix_refresh", 1, 0));
+        assert_eq!((stats[1].caller, stats[1].ok, stats[1].transport_errors), ("pool_initializer", 1, 1));
+
+        let mut writer = PrometheusWriter::new();
+        manager.write_prometheus(&mut writer);
+        let text = writer.finish();
+        assert!(text.contains("pool_cache_rpc_requests_total{caller=\"pool_initializer\",outcome=\"transport_error\"} 1\n"));
+        assert!(text.contains("pool_cache_rpc_requests_total{caller=\"phoenix_refresh\",outcome=\"ok\"} 1\n"));
+    }
+
+    #[tokio::test]
+    async fn test_async_calls_share_the_rate_limit() {
+        let healthy = spawn_rpc_endpoint(serde_json::json!({ "getSlot": 7 }));
+        let manager = Arc::new(RpcManager::new(
+            vec![healthy],
+            Duration::from_secs(2),
+            Arc::new(RpcBudget::with_burst(5.0, 1)),
+        ));
+
+        let started = std::time::Instant::now();
+        for caller in ["vault_prefetch", "stake_pool", "vault_prefetch"] {
+            assert_eq!(manager.handle(caller).call(|client| client.get_slot().map_err(Box::new)).await.unwrap(), 7);
+        }
+        // 5 req/s、无突发：不同调用方共用一个桶，第 2、3 个请求都要排队
+        assert!(started.elapsed() >= Duration::from_millis(350));
+
+        let throttled: u64 = manager.caller_stats().iter().map(|s| s.throttled).sum();
+        assert_eq!(throttled, 2);
+    }
+}
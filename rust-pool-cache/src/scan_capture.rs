@@ -0,0 +1,169 @@
+/*!
+ * 🧊 扫描输入抓取 / 离线重放
+ *
+ * 路由报出奇怪路径时需要冻结当时的输入离线重跑：抓取开启时 AdvancedRouter 在扫描前把
+ * 一致性快照（全部 PoolPrice，含 slot 和年龄）和路由配置写成带时间戳的 JSON 文件，
+ * `AdvancedRouter::scan_from_snapshot` 读取同一份文件、不依赖实时缓存得到相同的路径。
+ *
+ * 抓取方式：`[debug_capture] enabled = true` 抓取每一次扫描；或 `POST /debug/capture?scans=N`
+ * 只抓取接下来的 N 次扫描。
+ *
+ * 注意：文件只包含池子和路由配置，进程级登记表（fee 覆盖、订单簿、tick array）不在其中，
+ * 重放环境的这些登记表需要与抓取时一致（测试中通常都为空）；执行成本模型由重放调用方传入。
+ */
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::config::DebugCaptureConfig;
+use crate::price_cache::PoolPrice;
+use crate::router_advanced::AdvancedRouterConfig;
+
+/// 抓取文件格式版本（字段不兼容变更时递增）
+pub const CAPTURE_VERSION: u32 = 1;
+
+/// 一次扫描的完整输入
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanCapture {
+    pub version: u32,
+    /// 抓取时刻（Unix 毫秒）
+    pub captured_at_ms: i64,
+    /// 扫描金额
+    pub amount: f64,
+    /// 快照中最新的 slot
+    pub latest_slot: u64,
+    /// 抓取时的路由配置
+    pub router: AdvancedRouterConfig,
+    /// 路由快照（已按 pool_id 排序）
+    pub pools: Vec<PoolPrice>,
+}
+
+impl ScanCapture {
+    pub fn new(config: &AdvancedRouterConfig, pools: &[PoolPrice], amount: f64) -> Self {
+        Self {
+            version: CAPTURE_VERSION,
+            captured_at_ms: chrono::Utc::now().timestamp_millis(),
+            amount,
+            latest_slot: pools.iter().map(|p| p.slot).max().unwrap_or(0),
+            router: config.clone(),
+            pools: pools.to_vec(),
+        }
+    }
+
+    /// 写入 `dir/scan-<毫秒时间戳>-<金额>.json`，返回文件路径
+    pub fn write_to_dir(&self, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = dir.join(format!("scan-{}-{}.json", self.captured_at_ms, self.amount));
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize scan capture")?;
+        std::fs::write(&path, json)
+            .with_context(|| format!("Failed to write scan capture {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// 读取抓取文件（版本不一致时报错）
+    pub fn read_from(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read scan capture {}", path.display()))?;
+        let capture: Self = serde_json::from_str(&json)
+            .with_context(|| format!("Invalid scan capture {}", path.display()))?;
+        if capture.version != CAPTURE_VERSION {
+            bail!(
+                "Scan capture {} has version {}, expected {}",
+                path.display(), capture.version, CAPTURE_VERSION
+            );
+        }
+        Ok(capture)
+    }
+}
+
+/// 抓取状态（POST /debug/capture 的响应）
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureStatus {
+    pub dir: String,
+    /// 每次扫描都抓取（[debug_capture] enabled）
+    pub always: bool,
+    /// 还要抓取的扫描次数
+    pub pending: u64,
+    /// 已写入的文件数
+    pub written: u64,
+    pub last_file: Option<String>,
+}
+
+/// 抓取开关（AdvancedRouter 与 API 共享）
+pub struct CaptureControl {
+    dir: PathBuf,
+    always: bool,
+    pending: AtomicU64,
+    written: AtomicU64,
+    last_file: Mutex<Option<PathBuf>>,
+}
+
+impl CaptureControl {
+    pub fn new(config: &DebugCaptureConfig) -> Self {
+        Self {
+            dir: PathBuf::from(&config.dir),
+            always: config.enabled,
+            pending: AtomicU64::new(0),
+            written: AtomicU64::new(0),
+            last_file: Mutex::new(None),
+        }
+    }
+
+    /// 抓取接下来的 `scans` 次扫描（累加到尚未用完的次数上）
+    pub fn arm(&self, scans: u64) {
+        self.pending.fetch_add(scans, Ordering::Relaxed);
+    }
+
+    /// 本次扫描是否需要抓取（按次抓取时消耗一次）
+    pub fn should_capture(&self) -> bool {
+        self.always
+            || self.pending
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+                .is_ok()
+    }
+
+    /// 写入抓取文件；失败只记录警告，不影响扫描
+    pub fn write(&self, capture: &ScanCapture) {
+        match capture.write_to_dir(&self.dir) {
+            Ok(path) => {
+                info!("🧊 Captured scan input ({} pools) to {}", capture.pools.len(), path.display());
+                self.written.fetch_add(1, Ordering::Relaxed);
+                *self.last_file.lock().unwrap() = Some(path);
+            }
+            Err(e) => warn!("🧊 Failed to write scan capture: {:#}", e),
+        }
+    }
+
+    pub fn status(&self) -> CaptureStatus {
+        CaptureStatus {
+            dir: self.dir.display().to_string(),
+            always: self.always,
+            pending: self.pending.load(Ordering::Relaxed),
+            written: self.written.load(Ordering::Relaxed),
+            last_file: self.last_file.lock().unwrap().as_ref().map(|p| p.display().to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_armed_scans_are_consumed() {
+        let control = CaptureControl::new(&DebugCaptureConfig::default());
+        assert!(!control.should_capture());
+
+        control.arm(2);
+        assert!(control.should_capture());
+        assert!(control.should_capture());
+        assert!(!control.should_capture());
+        assert_eq!(control.status().pending, 0);
+    }
+}
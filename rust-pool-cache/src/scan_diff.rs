@@ -0,0 +1,631 @@
+/*!
+ * 扫描间差异追踪
+ *
+ * 告警消费方关心的是状态变化而不是重复观测。按路径指纹对比相邻两次扫描：
+ * - New：本次首次出现
+ * - Improved / Worsened：ROI 变化超过配置的阈值
+ * - Persisting：仍然存在，ROI 变化不显著
+ * - Gone：上次存在、本次消失（区分被过滤 vs 数据过期）
+ *
+ * 同时维护生命周期元数据（first_seen / last_seen / peak_roi / scan_count），
+ * 已结束的机会进入固定容量的环形缓冲区。
+ *
+ * 全量和定向扫描都经过这里，每次扫描留一条 `ScanRecord`（最近 N 条，GET /scans/latest、
+ * GET /scans/:id；数据库开启时摘要写入 scan_summaries）。定向扫描只覆盖部分路径，不判断 Gone。
+ * 机会按指纹（路径 + 方向）区分，签名只用于展示和告警。
+ */
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::router::{ArbitragePath, RouteStep};
+use crate::scan_tiers::TierRoi;
+
+/// 默认保留的最近扫描记录数
+pub const DEFAULT_HISTORY_CAPACITY: usize = 100;
+
+/// 单跳的滑点假设（路由器发现时的估算）
+#[derive(Debug, Clone, Serialize)]
+pub struct HopAssumption {
+    pub pool_id: String,
+    pub price_impact_percent: f64,
+    pub effective_fee_bps: f64,
+}
+
+impl HopAssumption {
+    pub fn from_step(step: &RouteStep) -> Self {
+        Self {
+            pool_id: step.pool_id.clone(),
+            price_impact_percent: step.price_impact_percent,
+            effective_fee_bps: step.effective_fee_bps,
+        }
+    }
+}
+
+/// 单次扫描中的一个机会
+#[derive(Debug, Clone)]
+pub struct ScanObservation {
+    /// 路径 + 方向指纹（`OpportunityMerger::fingerprint`）
+    pub fingerprint: String,
+    pub signature: String,
+    pub roi_percent: f64,
+    pub pool_ids: Vec<String>,
+    /// 各金额档位的 ROI（单档位扫描时可为空）
+    pub roi_by_amount: Vec<TierRoi>,
+    /// 每跳的价格冲击 / 手续费假设
+    pub hops: Vec<HopAssumption>,
+}
+
+impl ScanObservation {
+    /// `roi_percent` 由调用方给出（拆分优化后的 ROI 与基础路径的 ROI 不同）
+    pub fn from_path(path: &ArbitragePath, roi_percent: f64, roi_by_amount: Vec<TierRoi>) -> Self {
+        Self {
+            fingerprint: path.fingerprint(),
+            signature: path.signature().to_string(),
+            roi_percent,
+            pool_ids: path.steps.iter().map(|s| s.pool_id.clone()).collect(),
+            roi_by_amount,
+            hops: path.steps.iter().map(HopAssumption::from_step).collect(),
+        }
+    }
+}
+
+/// 一次扫描的元数据
+#[derive(Debug, Clone)]
+pub struct ScanMeta {
+    pub scanned_at: DateTime<Utc>,
+    pub duration_ms: f64,
+    /// clock | event
+    pub trigger_type: String,
+    pub trigger_source: String,
+    /// 定向扫描只覆盖部分路径，不判断 Gone
+    pub scoped: bool,
+}
+
+/// 机会消失的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GoneReason {
+    /// 数据仍新鲜，机会被过滤或价差消失
+    Filtered,
+    /// 路径上有池子数据过期 / 缺失
+    DataStale,
+}
+
+/// 事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    New,
+    Persisting,
+    Improved,
+    Worsened,
+    Gone(GoneReason),
+}
+
+impl EventKind {
+    /// 状态名（写库 / 通知），Gone 不区分原因
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::New => "new",
+            EventKind::Persisting => "persisting",
+            EventKind::Improved => "improved",
+            EventKind::Worsened => "worsened",
+            EventKind::Gone(_) => "gone",
+        }
+    }
+}
+
+/// 机会生命周期
+#[derive(Debug, Clone, Serialize)]
+pub struct Lifecycle {
+    pub fingerprint: String,
+    pub signature: String,
+    pub pool_ids: Vec<String>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub peak_roi: f64,
+    pub last_roi: f64,
+    pub scan_count: u64,
+    /// 最近一次观测中各金额档位的 ROI
+    pub roi_by_amount: Vec<TierRoi>,
+    /// 最近一次观测中每跳的价格冲击 / 手续费假设
+    pub hops: Vec<HopAssumption>,
+}
+
+/// 扫描差异事件
+#[derive(Debug, Clone, Serialize)]
+pub struct OpportunityEvent {
+    pub kind: EventKind,
+    pub signature: String,
+    /// 当前ROI（Gone 时为最后一次观测的ROI）
+    pub roi_percent: f64,
+    /// 相对上次扫描的ROI变化（New / Gone 为 0）
+    pub roi_delta: f64,
+    pub lifecycle: Lifecycle,
+}
+
+impl OpportunityEvent {
+    /// 单行事件标题（告警消息头）
+    pub fn headline(&self) -> String {
+        match self.kind {
+            EventKind::New => format!("🆕 NEW {} {:.4}% ROI", self.signature, self.roi_percent),
+            EventKind::Improved => format!(
+                "📈 IMPROVED {} {:.4}% ROI ({:+.4})", self.signature, self.roi_percent, self.roi_delta
+            ),
+            EventKind::Worsened => format!(
+                "📉 WORSENED {} {:.4}% ROI ({:+.4})", self.signature, self.roi_percent, self.roi_delta
+            ),
+            EventKind::Persisting => format!(
+                "🔁 PERSISTING {} {:.4}% ROI ({:+.4}, scan #{})",
+                self.signature, self.roi_percent, self.roi_delta, self.lifecycle.scan_count
+            ),
+            EventKind::Gone(reason) => format!(
+                "👋 GONE {} ({}) after {} scans, peak {:.4}% ROI",
+                self.signature,
+                match reason {
+                    GoneReason::Filtered => "filtered",
+                    GoneReason::DataStale => "data stale",
+                },
+                self.lifecycle.scan_count,
+                self.lifecycle.peak_roi
+            ),
+        }
+    }
+}
+
+/// 扫描记录中的一条机会
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanEntry {
+    pub fingerprint: String,
+    pub signature: String,
+    pub status: EventKind,
+    /// 本次 ROI（Gone 时为最后一次看到的 ROI）
+    pub roi_percent: f64,
+    /// 上一次看到的 ROI（New 时为 None）
+    pub previous_roi_percent: Option<f64>,
+}
+
+impl From<&OpportunityEvent> for ScanEntry {
+    fn from(event: &OpportunityEvent) -> Self {
+        Self {
+            fingerprint: event.lifecycle.fingerprint.clone(),
+            signature: event.signature.clone(),
+            status: event.kind,
+            roi_percent: event.roi_percent,
+            previous_roi_percent: match event.kind {
+                EventKind::New => None,
+                _ => Some(event.roi_percent - event.roi_delta),
+            },
+        }
+    }
+}
+
+/// 按状态计数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ScanCounts {
+    pub new: usize,
+    pub persisting: usize,
+    pub improved: usize,
+    pub worsened: usize,
+    pub gone: usize,
+}
+
+impl ScanCounts {
+    fn add(&mut self, kind: EventKind) {
+        match kind {
+            EventKind::New => self.new += 1,
+            EventKind::Persisting => self.persisting += 1,
+            EventKind::Improved => self.improved += 1,
+            EventKind::Worsened => self.worsened += 1,
+            EventKind::Gone(_) => self.gone += 1,
+        }
+    }
+}
+
+/// 一次扫描的结果与差异
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanRecord {
+    /// 进程内递增的扫描编号（重启后从 1 开始）
+    pub id: u64,
+    pub scanned_at: DateTime<Utc>,
+    pub duration_ms: f64,
+    pub trigger_type: String,
+    pub trigger_source: String,
+    pub scoped: bool,
+    /// 本次发现的机会涉及的不同池子数
+    pub pools_used: usize,
+    /// 本次发现的机会数（不含 Gone）
+    pub opportunities: usize,
+    pub counts: ScanCounts,
+    /// 与事件顺序一致：本次的机会按输入顺序，随后是按签名排序的 Gone
+    pub entries: Vec<ScanEntry>,
+}
+
+impl ScanRecord {
+    /// 本次扫描中某条机会的状态
+    pub fn status_of(&self, fingerprint: &str) -> Option<EventKind> {
+        self.entries.iter()
+            .find(|entry| entry.fingerprint == fingerprint)
+            .map(|entry| entry.status)
+    }
+}
+
+/// 一次扫描的差异：扫描记录 + 生命周期事件
+#[derive(Debug, Clone)]
+pub struct ScanOutcome {
+    pub record: ScanRecord,
+    pub events: Vec<OpportunityEvent>,
+}
+
+/// 生命周期快照（API输出）
+#[derive(Debug, Clone, Serialize)]
+pub struct LifecycleSnapshot {
+    pub active: Vec<Lifecycle>,
+    pub recent: Vec<Lifecycle>,
+}
+
+/// 扫描差异追踪器
+pub struct ScanDiffer {
+    /// ROI 变化超过该值（百分点）视为 Improved / Worsened
+    material_delta: f64,
+    /// 当前存在的机会（按指纹）
+    active: HashMap<String, Lifecycle>,
+    /// 已结束机会的环形缓冲区
+    recent: VecDeque<Lifecycle>,
+    recent_capacity: usize,
+    /// 最近 N 次扫描记录
+    history: VecDeque<ScanRecord>,
+    history_capacity: usize,
+    next_scan_id: u64,
+}
+
+impl ScanDiffer {
+    pub fn new(material_delta: f64, recent_capacity: usize) -> Self {
+        Self {
+            material_delta,
+            active: HashMap::new(),
+            recent: VecDeque::with_capacity(recent_capacity),
+            recent_capacity,
+            history: VecDeque::new(),
+            history_capacity: DEFAULT_HISTORY_CAPACITY,
+            next_scan_id: 1,
+        }
+    }
+
+    /// 设置保留的最近扫描记录数（至少 1）
+    pub fn with_history_capacity(mut self, capacity: usize) -> Self {
+        self.history_capacity = capacity.max(1);
+        self
+    }
+
+    /// 对比本次扫描与上次扫描，并记入扫描历史
+    ///
+    /// `is_stale` 判断已消失机会的路径数据是否过期，用于区分 Gone 的原因。
+    /// 同一扫描内重复的指纹只保留第一条；定向扫描不产生 Gone。
+    /// 事件顺序：本次观测按输入顺序，随后是按签名排序的 Gone 事件。
+    pub fn observe<F>(
+        &mut self,
+        meta: ScanMeta,
+        observations: Vec<ScanObservation>,
+        is_stale: F,
+    ) -> ScanOutcome
+    where
+        F: Fn(&Lifecycle) -> bool,
+    {
+        let now = meta.scanned_at;
+        let mut events = Vec::new();
+        let mut seen = HashSet::new();
+        let mut pools = HashSet::new();
+
+        for obs in observations {
+            if !seen.insert(obs.fingerprint.clone()) {
+                continue;
+            }
+            pools.extend(obs.pool_ids.iter().cloned());
+
+            let event = match self.active.get_mut(&obs.fingerprint) {
+                Some(lifecycle) => {
+                    let delta = obs.roi_percent - lifecycle.last_roi;
+                    let kind = if delta > self.material_delta {
+                        EventKind::Improved
+                    } else if delta < -self.material_delta {
+                        EventKind::Worsened
+                    } else {
+                        EventKind::Persisting
+                    };
+
+                    lifecycle.last_seen = now;
+                    lifecycle.last_roi = obs.roi_percent;
+                    lifecycle.peak_roi = lifecycle.peak_roi.max(obs.roi_percent);
+                    lifecycle.scan_count += 1;
+                    lifecycle.pool_ids = obs.pool_ids;
+                    lifecycle.roi_by_amount = obs.roi_by_amount;
+                    lifecycle.hops = obs.hops;
+
+                    OpportunityEvent {
+                        kind,
+                        signature: obs.signature,
+                        roi_percent: obs.roi_percent,
+                        roi_delta: delta,
+                        lifecycle: lifecycle.clone(),
+                    }
+                }
+                None => {
+                    let lifecycle = Lifecycle {
+                        fingerprint: obs.fingerprint.clone(),
+                        signature: obs.signature.clone(),
+                        pool_ids: obs.pool_ids,
+                        first_seen: now,
+                        last_seen: now,
+                        peak_roi: obs.roi_percent,
+                        last_roi: obs.roi_percent,
+                        scan_count: 1,
+                        roi_by_amount: obs.roi_by_amount,
+                        hops: obs.hops,
+                    };
+                    self.active.insert(obs.fingerprint, lifecycle.clone());
+
+                    OpportunityEvent {
+                        kind: EventKind::New,
+                        signature: obs.signature,
+                        roi_percent: obs.roi_percent,
+                        roi_delta: 0.0,
+                        lifecycle,
+                    }
+                }
+            };
+            events.push(event);
+        }
+
+        let opportunities = events.len();
+
+        // 全量扫描：上次存在、本次消失的机会（按签名、指纹排序）
+        if !meta.scoped {
+            let mut gone: Vec<(String, String)> = self.active.values()
+                .filter(|lifecycle| !seen.contains(&lifecycle.fingerprint))
+                .map(|lifecycle| (lifecycle.signature.clone(), lifecycle.fingerprint.clone()))
+                .collect();
+            gone.sort();
+
+            for (signature, fingerprint) in gone {
+                if let Some(lifecycle) = self.active.remove(&fingerprint) {
+                    let reason = if is_stale(&lifecycle) {
+                        GoneReason::DataStale
+                    } else {
+                        GoneReason::Filtered
+                    };
+
+                    events.push(OpportunityEvent {
+                        kind: EventKind::Gone(reason),
+                        signature,
+                        roi_percent: lifecycle.last_roi,
+                        roi_delta: 0.0,
+                        lifecycle: lifecycle.clone(),
+                    });
+                    self.push_recent(lifecycle);
+                }
+            }
+        }
+
+        let mut counts = ScanCounts::default();
+        for event in &events {
+            counts.add(event.kind);
+        }
+        let record = ScanRecord {
+            id: self.next_scan_id,
+            scanned_at: meta.scanned_at,
+            duration_ms: meta.duration_ms,
+            trigger_type: meta.trigger_type,
+            trigger_source: meta.trigger_source,
+            scoped: meta.scoped,
+            pools_used: pools.len(),
+            opportunities,
+            counts,
+            entries: events.iter().map(ScanEntry::from).collect(),
+        };
+        self.next_scan_id += 1;
+
+        if self.history.len() >= self.history_capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(record.clone());
+
+        ScanOutcome { record, events }
+    }
+
+    fn push_recent(&mut self, lifecycle: Lifecycle) {
+        if self.recent_capacity == 0 {
+            return;
+        }
+        if self.recent.len() >= self.recent_capacity {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(lifecycle);
+    }
+
+    pub fn active_count(&self) -> usize {
+        self.active.len()
+    }
+
+    /// 最近一次扫描
+    pub fn latest_scan(&self) -> Option<&ScanRecord> {
+        self.history.back()
+    }
+
+    /// 按编号查找扫描（已被挤出历史的返回 None）
+    pub fn scan(&self, id: u64) -> Option<&ScanRecord> {
+        let oldest = self.history.front()?.id;
+        let index = id.checked_sub(oldest)?;
+        self.history.get(index as usize)
+    }
+
+    pub fn scan_count(&self) -> usize {
+        self.history.len()
+    }
+
+    /// 活跃机会（按 ROI 降序）+ 最近结束的机会（最新在前）
+    pub fn snapshot(&self) -> LifecycleSnapshot {
+        let mut active: Vec<Lifecycle> = self.active.values().cloned().collect();
+        active.sort_by(|a, b| {
+            b.last_roi.total_cmp(&a.last_roi).then_with(|| a.signature.cmp(&b.signature))
+        });
+
+        LifecycleSnapshot {
+            active,
+            recent: self.recent.iter().rev().cloned().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn obs(signature: &str, roi: f64) -> ScanObservation {
+        ScanObservation {
+            fingerprint: format!("fp-{}", signature),
+            signature: signature.to_string(),
+            roi_percent: roi,
+            pool_ids: signature.split("->").map(String::from).collect(),
+            roi_by_amount: Vec::new(),
+            hops: Vec::new(),
+        }
+    }
+
+    fn meta(scanned_at: DateTime<Utc>, scoped: bool) -> ScanMeta {
+        ScanMeta {
+            scanned_at,
+            duration_ms: 1.5,
+            trigger_type: if scoped { "event" } else { "clock" }.to_string(),
+            trigger_source: "test".to_string(),
+            scoped,
+        }
+    }
+
+    fn statuses(record: &ScanRecord) -> Vec<(&str, EventKind)> {
+        record.entries.iter().map(|e| (e.signature.as_str(), e.status)).collect()
+    }
+
+    #[test]
+    fn test_three_scan_event_sequence() {
+        let mut differ = ScanDiffer::new(0.1, 10);
+        let t0 = Utc::now();
+        let t1 = t0 + chrono::Duration::seconds(1);
+        let t2 = t0 + chrono::Duration::seconds(2);
+        // p3 的数据在第三次扫描时过期
+        let stale = |l: &Lifecycle| l.pool_ids.iter().any(|p| p == "p3");
+
+        // 扫描1：a、b、c 首次出现
+        let first = differ.observe(meta(t0, false), vec![obs("p1->p2", 0.5), obs("p3->p4", 0.8), obs("p5->p6", 0.4)], stale);
+        let kinds: Vec<EventKind> = first.events.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![EventKind::New; 3]);
+        assert_eq!((first.record.id, first.record.counts.new, first.record.pools_used), (1, 3, 6));
+
+        // 扫描2：a 小幅变化，b 显著提升，c 显著下降，d 新增
+        let second = differ.observe(
+            meta(t1, false),
+            vec![obs("p1->p2", 0.55), obs("p3->p4", 1.2), obs("p5->p6", 0.25), obs("p1->p7", 0.6)],
+            stale,
+        );
+        let events = &second.events;
+        let kinds: Vec<EventKind> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![EventKind::Persisting, EventKind::Improved, EventKind::Worsened, EventKind::New]);
+        assert!((events[0].roi_delta - 0.05).abs() < 1e-9);
+        assert!((events[1].roi_delta - 0.4).abs() < 1e-9);
+        assert_eq!(second.record.pools_used, 7);
+        assert!((second.record.entries[1].previous_roi_percent.unwrap() - 0.8).abs() < 1e-9);
+        assert_eq!(second.record.entries[3].previous_roi_percent, None);
+
+        // 扫描3：仅 d 保留；b 因数据过期消失，a、c 被过滤
+        let third = differ.observe(meta(t2, false), vec![obs("p1->p7", 0.6)], stale);
+        assert_eq!(statuses(&third.record), vec![
+            ("p1->p7", EventKind::Persisting),
+            ("p1->p2", EventKind::Gone(GoneReason::Filtered)),
+            ("p3->p4", EventKind::Gone(GoneReason::DataStale)),
+            ("p5->p6", EventKind::Gone(GoneReason::Filtered)),
+        ]);
+        assert_eq!(third.record.counts, ScanCounts { persisting: 1, gone: 3, ..Default::default() });
+        assert_eq!(third.record.opportunities, 1);
+        assert_eq!(third.record.status_of("fp-p1->p7"), Some(EventKind::Persisting));
+
+        // 生命周期字段
+        let b = &third.events[2].lifecycle;
+        assert_eq!(b.first_seen, t0);
+        assert_eq!(b.last_seen, t1);
+        assert_eq!(b.scan_count, 2);
+        assert!((b.peak_roi - 1.2).abs() < 1e-9);
+
+        let d = &third.events[0].lifecycle;
+        assert_eq!(d.first_seen, t1);
+        assert_eq!(d.last_seen, t2);
+        assert_eq!(d.scan_count, 2);
+
+        let snapshot = differ.snapshot();
+        assert_eq!(snapshot.active.len(), 1);
+        assert_eq!(snapshot.recent.len(), 3);
+        assert_eq!(snapshot.recent[0].signature, "p5->p6");
+
+        // 消失后再次出现是新机会
+        let fourth = differ.observe(meta(t2, false), vec![obs("p1->p2", 0.5)], stale);
+        assert_eq!(fourth.record.status_of("fp-p1->p2"), Some(EventKind::New));
+    }
+
+    #[test]
+    fn test_scoped_scans_never_report_gone() {
+        let mut differ = ScanDiffer::new(0.1, 10);
+        let now = Utc::now();
+        differ.observe(meta(now, false), vec![obs("a", 1.0), obs("b", 1.0)], |_| false);
+
+        // 定向扫描：范围外的机会不算消失，范围内的照常更新
+        let scoped = differ.observe(meta(now, true), vec![obs("b", 1.5)], |_| false);
+        assert_eq!(statuses(&scoped.record), vec![("b", EventKind::Improved)]);
+        assert_eq!(differ.active_count(), 2);
+
+        let full = differ.observe(meta(now, false), vec![obs("b", 1.5)], |_| false);
+        assert_eq!(statuses(&full.record), vec![
+            ("b", EventKind::Persisting),
+            ("a", EventKind::Gone(GoneReason::Filtered)),
+        ]);
+    }
+
+    #[test]
+    fn test_same_pools_in_opposite_direction_are_distinct() {
+        let mut differ = ScanDiffer::new(0.1, 10);
+        let reversed = ScanObservation { fingerprint: "fp-reversed".to_string(), ..obs("p1->p2", 0.7) };
+
+        let outcome = differ.observe(meta(Utc::now(), false), vec![obs("p1->p2", 0.5), reversed], |_| false);
+        assert_eq!(outcome.record.counts.new, 2);
+        assert_eq!(differ.active_count(), 2);
+    }
+
+    #[test]
+    fn test_recent_ring_buffer_is_bounded() {
+        let mut differ = ScanDiffer::new(0.1, 2);
+        let now = Utc::now();
+
+        differ.observe(meta(now, false), vec![obs("a", 1.0), obs("b", 1.0), obs("c", 1.0)], |_| false);
+        differ.observe(meta(now, false), Vec::new(), |_| false);
+
+        let snapshot = differ.snapshot();
+        assert!(snapshot.active.is_empty());
+        assert_eq!(snapshot.recent.len(), 2);
+        assert_eq!(snapshot.recent[0].signature, "c");
+        assert_eq!(snapshot.recent[1].signature, "b");
+    }
+
+    #[test]
+    fn test_history_is_bounded_and_addressable_by_id() {
+        let mut differ = ScanDiffer::new(0.1, 10).with_history_capacity(2);
+        for _ in 0..3 {
+            differ.observe(meta(Utc::now(), false), Vec::new(), |_| false);
+        }
+
+        assert_eq!(differ.scan_count(), 2);
+        assert!(differ.scan(1).is_none());
+        assert_eq!(differ.scan(2).map(|r| r.id), Some(2));
+        assert_eq!(differ.latest_scan().map(|r| r.id), Some(3));
+        assert!(differ.scan(4).is_none());
+    }
+}
@@ -0,0 +1,216 @@
+/*!
+ * 扫描金额档位
+ *
+ * 把 [calculator] 中的美元金额按 PriceOracle 的实时价格换算成 base_token 数量，
+ * 路由器按每个档位各扫描一次，再按路径签名合并，保留每个档位的 ROI。
+ * 没有新鲜美元价格时改用 fallback_amounts（原生代币数量），此时档位不带美元金额。
+ */
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::config::CalculatorConfig;
+use crate::price_oracle::PriceOracle;
+use crate::router_split_optimizer::OptimizedPath;
+
+/// 一个扫描金额档位
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AmountTier {
+    /// 美元金额（没有美元价格、使用原生数量时为 None）
+    pub amount_usd: Option<f64>,
+    /// base_token 数量（传给路由器）
+    pub amount: f64,
+}
+
+impl AmountTier {
+    /// 日志展示：有美元金额时显示 "$100"，否则显示原生数量
+    pub fn label(&self, base_token: &str) -> String {
+        match self.amount_usd {
+            Some(amount_usd) => format!("${:.0}", amount_usd),
+            None => format!("{} {}", self.amount, base_token),
+        }
+    }
+}
+
+/// 某个金额档位下的 ROI
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TierRoi {
+    pub amount_usd: Option<f64>,
+    /// base_token 数量
+    pub amount: f64,
+    pub roi_percent: f64,
+}
+
+/// 多档位合并后的路径
+#[derive(Debug, Clone)]
+pub struct TieredPath {
+    /// ROI 最高的档位对应的路径
+    pub path: OptimizedPath,
+    /// 该路径所在档位
+    pub tier: AmountTier,
+    /// 每个出现过该路径的档位的 ROI（按金额升序）
+    pub roi_by_amount: Vec<TierRoi>,
+}
+
+/// 按当前价格解析所有金额档位
+///
+/// base_token 没有新鲜美元价格时使用 fallback_amounts；两者都没有时返回空。
+pub fn resolve_tiers(config: &CalculatorConfig, oracle: &PriceOracle) -> Vec<AmountTier> {
+    match oracle.get_usd_price(&config.base_token) {
+        Some(price) if price > 0.0 => config.amounts_usd.iter()
+            .filter(|amount_usd| **amount_usd > 0.0)
+            .map(|&amount_usd| AmountTier {
+                amount_usd: Some(amount_usd),
+                amount: amount_usd / price,
+            })
+            .collect(),
+        _ => config.fallback_amounts.iter()
+            .filter(|amount| **amount > 0.0)
+            .map(|&amount| AmountTier { amount_usd: None, amount })
+            .collect(),
+    }
+}
+
+/// 按路径签名合并各档位的扫描结果
+///
+/// 每条路径保留 ROI 最高的档位，结果按 ROI 降序（Calculator 再按期望值重排，见 `ranking`）。
+pub fn merge_tiers(results: Vec<(AmountTier, Vec<OptimizedPath>)>) -> Vec<TieredPath> {
+    let mut merged: HashMap<u64, TieredPath> = HashMap::new();
+
+    for (tier, paths) in results {
+        for path in paths {
+            let tier_roi = TierRoi {
+                amount_usd: tier.amount_usd,
+                amount: tier.amount,
+                roi_percent: path.optimized_roi,
+            };
+            match merged.get_mut(&path.base_path.signature().key()) {
+                Some(existing) => {
+                    existing.roi_by_amount.push(tier_roi);
+                    if path.optimized_roi > existing.path.optimized_roi {
+                        existing.path = path;
+                        existing.tier = tier;
+                    }
+                }
+                None => {
+                    merged.insert(path.base_path.signature().key(), TieredPath {
+                        path,
+                        tier,
+                        roi_by_amount: vec![tier_roi],
+                    });
+                }
+            }
+        }
+    }
+
+    let mut paths: Vec<TieredPath> = merged.into_values()
+        .map(|mut tiered| {
+            tiered.roi_by_amount.sort_by(|a, b| a.amount.total_cmp(&b.amount));
+            tiered
+        })
+        .collect();
+    paths.sort_by(|a, b| {
+        b.path.optimized_roi.total_cmp(&a.path.optimized_roi)
+            .then_with(|| a.path.base_path.signature().cmp(&b.path.base_path.signature()))
+    });
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PriceOracleConfig;
+    use crate::price_cache::{PoolPrice, PriceCache};
+    use crate::router::{ArbitragePath, ArbitrageType, RouteStep};
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    fn pool(pool_id: &str, price: f64, quote_reserve: u64) -> PoolPrice {
+        PoolPrice {
+            dex_name: "Test".to_string(),
+            base_reserve: 1_000 * 1_000_000_000,
+            quote_reserve,
+            ..PoolPrice::for_test(pool_id, "SOL/USDC", price)
+        }
+    }
+
+    fn optimized(pools: &[&str], roi: f64) -> OptimizedPath {
+        OptimizedPath {
+            base_path: ArbitragePath {
+                arb_type: ArbitrageType::Direct,
+                steps: pools.iter().map(|pool_id| RouteStep {
+                    pool_id: pool_id.to_string(),
+                    dex_name: "Test".to_string(),
+                    input_token: "USDC".to_string(),
+                    output_token: "SOL".to_string(),
+                    price: 1.0,
+                    liquidity_base: 0,
+                    liquidity_quote: 0,
+                    expected_input: 100.0,
+                    expected_output: 100.0,
+                    price_impact_percent: 0.0,
+                    effective_fee_bps: 0.0,
+                    raw_input_token: None,
+                    raw_output_token: None,
+                }).collect(),
+                start_token: "USDC".to_string(),
+                end_token: "USDC".to_string(),
+                input_amount: 100.0,
+                output_amount: 100.0,
+                gross_profit: 0.0,
+                dex_fees: 0.0,
+                execution_fees: 0.0,
+                estimated_fees: 0.0,
+                net_profit: 0.0,
+                roi_percent: roi,
+                discovered_at: Instant::now(),
+            },
+            split_strategy: None,
+            optimized_net_profit: 0.0,
+            optimized_roi: roi,
+        }
+    }
+
+    #[test]
+    fn test_resolve_tiers_uses_oracle_price() {
+        let cache = Arc::new(PriceCache::new());
+        cache.update_price(pool("shallow", 120.0, 10_000 * 1_000_000));
+        cache.update_price(pool("deep", 150.0, 5_000_000 * 1_000_000));
+        let oracle = PriceOracle::new(cache, &PriceOracleConfig::default());
+
+        let mut config = CalculatorConfig::default();
+        let tiers = resolve_tiers(&config, &oracle);
+        assert_eq!(tiers.iter().map(|t| t.amount).collect::<Vec<_>>(), vec![100.0, 1_000.0, 10_000.0]);
+
+        config.base_token = "SOL".to_string();
+        let tiers = resolve_tiers(&config, &oracle);
+        assert!((tiers[1].amount - 1_000.0 / 150.0).abs() < 1e-9);
+        assert_eq!(tiers[1].label("SOL"), "$1000");
+
+        // 没有价格的代币：没有 fallback_amounts 时不扫描，否则按原生数量扫描
+        config.base_token = "BONK".to_string();
+        assert!(resolve_tiers(&config, &oracle).is_empty());
+        config.fallback_amounts = vec![1_000_000.0];
+        let tiers = resolve_tiers(&config, &oracle);
+        assert_eq!(tiers, vec![AmountTier { amount_usd: None, amount: 1_000_000.0 }]);
+        assert_eq!(tiers[0].label("BONK"), "1000000 BONK");
+    }
+
+    #[test]
+    fn test_merge_tiers_keeps_roi_per_amount() {
+        let small = AmountTier { amount_usd: Some(100.0), amount: 100.0 };
+        let large = AmountTier { amount_usd: Some(10_000.0), amount: 10_000.0 };
+        let merged = merge_tiers(vec![
+            (large, vec![optimized(&["a", "b"], 0.1)]),
+            (small, vec![optimized(&["a", "b"], 0.9), optimized(&["c", "d"], 0.5)]),
+        ]);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].tier, small);
+        assert_eq!(merged[0].roi_by_amount, vec![
+            TierRoi { amount_usd: Some(100.0), amount: 100.0, roi_percent: 0.9 },
+            TierRoi { amount_usd: Some(10_000.0), amount: 10_000.0, roi_percent: 0.1 },
+        ]);
+        assert_eq!(merged[1].roi_by_amount.len(), 1);
+    }
+}
@@ -0,0 +1,257 @@
+/*!
+ * 池子分片（多实例部署）
+ *
+ * 多个进程实例各自订阅一部分池子，下游共享同一条机会流：
+ * - 一致性哈希环（按池子地址）决定每个池子的订阅归属实例
+ * - 新增池子只影响新池子本身，不会导致已有池子整体迁移
+ * - 锚定池（anchor）在每个实例上都订阅，保证跨交易对的腿总能定价
+ *
+ * 非本实例订阅的池子（mirrored）的数据由外部状态总线写入价格缓存，
+ * 路由器统一从价格缓存读取，不区分数据来源。
+ */
+
+use crate::config::PoolConfig;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// FNV-1a 64位哈希（跨进程、跨编译器版本稳定）
+fn stable_hash(input: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in input.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// 一致性哈希环
+#[derive(Debug, Clone)]
+pub struct ShardRing {
+    shard_count: usize,
+    /// (哈希点, 分片编号)，按哈希点排序
+    points: Vec<(u64, usize)>,
+}
+
+impl ShardRing {
+    /// 创建哈希环，每个分片放置 virtual_nodes 个虚拟节点
+    pub fn new(shard_count: usize, virtual_nodes: usize) -> Self {
+        let shard_count = shard_count.max(1);
+        let mut points: Vec<(u64, usize)> = (0..shard_count)
+            .flat_map(|shard| {
+                (0..virtual_nodes.max(1)).map(move |vnode| {
+                    (stable_hash(&format!("shard-{}-vnode-{}", shard, vnode)), shard)
+                })
+            })
+            .collect();
+        points.sort_unstable();
+
+        Self { shard_count, points }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shard_count
+    }
+
+    /// 池子地址 -> 归属分片（顺时针第一个虚拟节点）
+    pub fn shard_for(&self, pool_address: &str) -> usize {
+        let hash = stable_hash(pool_address);
+        let idx = self.points.partition_point(|(point, _)| *point < hash);
+        self.points.get(idx).unwrap_or(&self.points[0]).1
+    }
+}
+
+/// 池子在本实例中的角色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PoolRole {
+    /// 本实例拥有订阅
+    Owned,
+    /// 归属其他实例，但作为锚定池在本实例也订阅
+    Anchor,
+    /// 归属其他实例，数据来自外部状态总线
+    Mirrored,
+}
+
+/// 分片状态（/status 输出）
+#[derive(Debug, Clone, Serialize)]
+pub struct ShardStatus {
+    pub shard_index: usize,
+    pub shard_count: usize,
+    pub owned_pools: usize,
+    pub anchor_pools: usize,
+    pub mirrored_pools: usize,
+    pub subscribed_pools: usize,
+}
+
+/// 本实例的分片分配结果
+#[derive(Debug, Clone)]
+pub struct ShardAssignment {
+    shard_index: usize,
+    shard_count: usize,
+    /// 池子地址 -> 归属分片
+    owners: HashMap<String, usize>,
+    /// 锚定池地址
+    anchors: HashSet<String>,
+}
+
+impl ShardAssignment {
+    /// 计算分配；anchors 可以是池子地址或名称
+    pub fn assign(
+        ring: &ShardRing,
+        shard_index: usize,
+        pools: &[PoolConfig],
+        anchors: &[String],
+    ) -> Self {
+        let owners = pools.iter()
+            .map(|pool| (pool.address.clone(), ring.shard_for(&pool.address)))
+            .collect();
+
+        let anchors = pools.iter()
+            .filter(|pool| anchors.iter().any(|a| a == &pool.address || a == &pool.name))
+            .map(|pool| pool.address.clone())
+            .collect();
+
+        Self {
+            shard_index,
+            shard_count: ring.shard_count(),
+            owners,
+            anchors,
+        }
+    }
+
+    /// 池子归属的分片
+    pub fn owner_of(&self, pool_address: &str) -> Option<usize> {
+        self.owners.get(pool_address).copied()
+    }
+
+    pub fn role(&self, pool_address: &str) -> PoolRole {
+        if self.owner_of(pool_address) == Some(self.shard_index) {
+            PoolRole::Owned
+        } else if self.anchors.contains(pool_address) {
+            PoolRole::Anchor
+        } else {
+            PoolRole::Mirrored
+        }
+    }
+
+    /// 本实例是否需要订阅该池子
+    pub fn is_subscribed(&self, pool_address: &str) -> bool {
+        self.role(pool_address) != PoolRole::Mirrored
+    }
+
+    /// 过滤出本实例需要订阅的池子（保持配置顺序）
+    pub fn subscribed_pools(&self, pools: &[PoolConfig]) -> Vec<PoolConfig> {
+        pools.iter()
+            .filter(|pool| self.is_subscribed(&pool.address))
+            .cloned()
+            .collect()
+    }
+
+    pub fn status(&self) -> ShardStatus {
+        let mut status = ShardStatus {
+            shard_index: self.shard_index,
+            shard_count: self.shard_count,
+            owned_pools: 0,
+            anchor_pools: 0,
+            mirrored_pools: 0,
+            subscribed_pools: 0,
+        };
+
+        for address in self.owners.keys() {
+            match self.role(address) {
+                PoolRole::Owned => status.owned_pools += 1,
+                PoolRole::Anchor => status.anchor_pools += 1,
+                PoolRole::Mirrored => status.mirrored_pools += 1,
+            }
+        }
+        status.subscribed_pools = status.owned_pools + status.anchor_pools;
+
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(i: usize) -> PoolConfig {
+        PoolConfig {
+            address: format!("PooL{:04}Address{}", i, i * 7919),
+            name: format!("pool-{}", i),
+            pair: "SOL/USDC".to_string(),
+            pool_type: "amm_v4".to_string(),
+            fee_bps: None,
+            max_age_ms: None,
+            base_mint: None,
+            quote_mint: None,
+            max_price_jump_percent: None,
+            commitment: None,
+            encoding: None,
+            priority: None,
+        }
+    }
+
+    fn pools(n: usize) -> Vec<PoolConfig> {
+        (0..n).map(pool).collect()
+    }
+
+    #[test]
+    fn test_assignment_stable_when_adding_pools() {
+        let ring = ShardRing::new(2, 128);
+        let before = ShardAssignment::assign(&ring, 0, &pools(100), &[]);
+        let after = ShardAssignment::assign(&ring, 0, &pools(150), &[]);
+
+        for p in pools(100) {
+            assert_eq!(before.owner_of(&p.address), after.owner_of(&p.address));
+        }
+
+        // 扩容 2 -> 3：只有部分池子迁移到新分片，不会整体重排
+        let ring3 = ShardRing::new(3, 128);
+        let moved = pools(150).iter()
+            .filter(|p| ring.shard_for(&p.address) != ring3.shard_for(&p.address))
+            .count();
+        assert!(moved < 100, "too many pools moved: {}", moved);
+        for p in pools(150) {
+            let old = ring.shard_for(&p.address);
+            let new = ring3.shard_for(&p.address);
+            assert!(new == old || new == 2, "pool moved between existing shards");
+        }
+    }
+
+    #[test]
+    fn test_anchors_subscribed_in_every_shard() {
+        let ring = ShardRing::new(3, 64);
+        let all = pools(30);
+        let anchors = vec![all[0].address.clone(), all[1].name.clone()];
+
+        for shard in 0..3 {
+            let assignment = ShardAssignment::assign(&ring, shard, &all, &anchors);
+            assert!(assignment.is_subscribed(&all[0].address));
+            assert!(assignment.is_subscribed(&all[1].address));
+        }
+    }
+
+    #[test]
+    fn test_two_instances_cover_all_pools_exactly_once() {
+        let ring = ShardRing::new(2, 128);
+        let all = pools(200);
+        let anchors = vec![all[5].address.clone()];
+
+        let shard0 = ShardAssignment::assign(&ring, 0, &all, &anchors);
+        let shard1 = ShardAssignment::assign(&ring, 1, &all, &anchors);
+
+        for p in &all {
+            let owners = [&shard0, &shard1].iter()
+                .filter(|a| a.role(&p.address) == PoolRole::Owned)
+                .count();
+            assert_eq!(owners, 1, "pool {} owned by {} shards", p.address, owners);
+        }
+
+        let s0 = shard0.status();
+        let s1 = shard1.status();
+        assert_eq!(s0.owned_pools + s1.owned_pools, 200);
+        assert_eq!(s0.anchor_pools + s1.anchor_pools, 1);
+        assert!(s0.owned_pools > 50 && s1.owned_pools > 50);
+        assert_eq!(s0.subscribed_pools + s0.mirrored_pools, 200);
+    }
+}
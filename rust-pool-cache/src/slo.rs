@@ -0,0 +1,423 @@
+/*!
+ * 可用性 SLO 追踪
+ *
+ * 为关键组件维护可用性账本（状态切换 + 时间戳），计算窗口可用率：
+ * - 1h / 24h / 7d 窗口可用率
+ * - 累计可用率
+ * - 低于目标时触发告警（每个违约周期只告警一次）
+ *
+ * 时间由调用方传入（DateTime<Utc>），方便测试中模拟时钟。
+ * 账本可导出为 LedgerEntry 持久化到数据库，重启后恢复；
+ * 进程停止期间（最后一次检查点到恢复时刻）计为不可用。
+ */
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use tracing::{info, warn};
+
+/// 被追踪的关键组件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum SloComponent {
+    /// WebSocket 数据源连接
+    FeedConnection,
+    /// 缓存新鲜度
+    CacheHealth,
+    /// Coordinator 时钟 tick 规律性（漏 tick 检测）
+    CoordinatorTick,
+    /// Calculator 扫描成功率
+    CalculatorScan,
+    /// API 响应（自探测）
+    ApiResponsiveness,
+    /// 数据库可写
+    DatabaseWritability,
+}
+
+impl SloComponent {
+    pub const ALL: [SloComponent; 6] = [
+        SloComponent::FeedConnection,
+        SloComponent::CacheHealth,
+        SloComponent::CoordinatorTick,
+        SloComponent::CalculatorScan,
+        SloComponent::ApiResponsiveness,
+        SloComponent::DatabaseWritability,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SloComponent::FeedConnection => "feed_connection",
+            SloComponent::CacheHealth => "cache_health",
+            SloComponent::CoordinatorTick => "coordinator_tick",
+            SloComponent::CalculatorScan => "calculator_scan",
+            SloComponent::ApiResponsiveness => "api_responsiveness",
+            SloComponent::DatabaseWritability => "database_writability",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|c| c.as_str() == s)
+    }
+}
+
+/// 持久化条目（一次状态切换或检查点）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LedgerEntry {
+    pub component: SloComponent,
+    pub up: bool,
+    pub at: DateTime<Utc>,
+}
+
+/// 单个组件的可用性账本
+#[derive(Debug, Clone)]
+pub struct AvailabilityLedger {
+    /// 状态切换记录（按时间升序）
+    transitions: Vec<(DateTime<Utc>, bool)>,
+}
+
+impl AvailabilityLedger {
+    pub fn new() -> Self {
+        Self {
+            transitions: Vec::new(),
+        }
+    }
+
+    /// 记录状态，返回是否发生了状态切换
+    pub fn record(&mut self, at: DateTime<Utc>, up: bool) -> bool {
+        match self.transitions.last() {
+            Some((_, last_up)) if *last_up == up => false,
+            Some((last_at, _)) if at < *last_at => false, // 忽略乱序样本
+            _ => {
+                self.transitions.push((at, up));
+                true
+            }
+        }
+    }
+
+    /// 当前状态（无记录时为 None）
+    pub fn current(&self) -> Option<bool> {
+        self.transitions.last().map(|(_, up)| *up)
+    }
+
+    /// 首次记录时间
+    pub fn first_seen(&self) -> Option<DateTime<Utc>> {
+        self.transitions.first().map(|(at, _)| *at)
+    }
+
+    /// [start, now] 区间内的可用率（百分比）
+    ///
+    /// 首次记录之前的时间视为未知，不计入分母；无观测时返回 100%
+    fn uptime_between(&self, start: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+        let mut up_ms: i64 = 0;
+        let mut observed_ms: i64 = 0;
+
+        for (idx, (at, up)) in self.transitions.iter().enumerate() {
+            let segment_end = self.transitions
+                .get(idx + 1)
+                .map(|(next_at, _)| *next_at)
+                .unwrap_or(now)
+                .min(now);
+            let segment_start = (*at).max(start);
+
+            if segment_end <= segment_start {
+                continue;
+            }
+
+            let ms = (segment_end - segment_start).num_milliseconds();
+            observed_ms += ms;
+            if *up {
+                up_ms += ms;
+            }
+        }
+
+        if observed_ms == 0 {
+            100.0
+        } else {
+            up_ms as f64 / observed_ms as f64 * 100.0
+        }
+    }
+
+    /// 窗口可用率
+    pub fn uptime_percent(&self, now: DateTime<Utc>, window: Duration) -> f64 {
+        self.uptime_between(now - window, now)
+    }
+
+    /// 累计可用率
+    pub fn cumulative_percent(&self, now: DateTime<Utc>) -> f64 {
+        match self.first_seen() {
+            Some(first) => self.uptime_between(first, now),
+            None => 100.0,
+        }
+    }
+
+    /// 裁剪早于 cutoff 的记录（保留 cutoff 时刻的状态）
+    fn prune(&mut self, cutoff: DateTime<Utc>) {
+        let keep_from = self.transitions
+            .iter()
+            .rposition(|(at, _)| *at <= cutoff)
+            .unwrap_or(0);
+        if keep_from > 0 {
+            self.transitions.drain(..keep_from);
+        }
+    }
+}
+
+impl Default for AvailabilityLedger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// SLO 违约告警
+#[derive(Debug, Clone, Serialize)]
+pub struct SloBreach {
+    pub component: SloComponent,
+    pub uptime_24h: f64,
+    pub target: f64,
+}
+
+/// /slo 表格行
+#[derive(Debug, Clone, Serialize)]
+pub struct SloRow {
+    pub component: SloComponent,
+    pub up: Option<bool>,
+    pub uptime_1h: f64,
+    pub uptime_24h: f64,
+    pub uptime_7d: f64,
+    pub cumulative: f64,
+    pub target: f64,
+    pub breached: bool,
+}
+
+/// SLO 追踪器
+pub struct SloTracker {
+    ledgers: HashMap<SloComponent, AvailabilityLedger>,
+    targets: HashMap<SloComponent, f64>,
+    /// 当前处于违约周期的组件（用于告警去重）
+    breached: HashSet<SloComponent>,
+}
+
+impl SloTracker {
+    /// 创建追踪器，未配置目标的组件使用 default_target
+    pub fn new(targets: HashMap<SloComponent, f64>, default_target: f64) -> Self {
+        let targets = SloComponent::ALL
+            .iter()
+            .map(|c| (*c, targets.get(c).copied().unwrap_or(default_target)))
+            .collect();
+
+        Self {
+            ledgers: HashMap::new(),
+            targets,
+            breached: HashSet::new(),
+        }
+    }
+
+    /// 记录组件状态，返回是否发生状态切换
+    pub fn record(&mut self, component: SloComponent, up: bool, now: DateTime<Utc>) -> bool {
+        let changed = self.ledgers.entry(component).or_default().record(now, up);
+        if changed {
+            info!("SLO: {} -> {}", component.as_str(), if up { "UP" } else { "DOWN" });
+        }
+        changed
+    }
+
+    /// 检查违约：低于目标时每个违约周期只返回一次，恢复后重置
+    pub fn evaluate_breaches(&mut self, now: DateTime<Utc>) -> Vec<SloBreach> {
+        let mut fired = Vec::new();
+
+        for (component, ledger) in &self.ledgers {
+            let target = self.targets.get(component).copied().unwrap_or(99.0);
+            let uptime_24h = ledger.uptime_percent(now, Duration::hours(24));
+
+            if uptime_24h < target {
+                if self.breached.insert(*component) {
+                    warn!(
+                        "🚨 SLO breach: {} 24h availability {:.3}% < target {:.3}%",
+                        component.as_str(), uptime_24h, target
+                    );
+                    fired.push(SloBreach {
+                        component: *component,
+                        uptime_24h,
+                        target,
+                    });
+                }
+            } else {
+                self.breached.remove(component);
+            }
+        }
+
+        fired
+    }
+
+    /// 当前 SLO 表格
+    pub fn table(&self, now: DateTime<Utc>) -> Vec<SloRow> {
+        SloComponent::ALL
+            .iter()
+            .map(|component| {
+                let ledger = self.ledgers.get(component).cloned().unwrap_or_default();
+                SloRow {
+                    component: *component,
+                    up: ledger.current(),
+                    uptime_1h: ledger.uptime_percent(now, Duration::hours(1)),
+                    uptime_24h: ledger.uptime_percent(now, Duration::hours(24)),
+                    uptime_7d: ledger.uptime_percent(now, Duration::days(7)),
+                    cumulative: ledger.cumulative_percent(now),
+                    target: self.targets.get(component).copied().unwrap_or(99.0),
+                    breached: self.breached.contains(component),
+                }
+            })
+            .collect()
+    }
+
+    /// 检查点：每个组件在 now 时刻的当前状态（周期性持久化）
+    pub fn checkpoint(&self, now: DateTime<Utc>) -> Vec<LedgerEntry> {
+        self.ledgers
+            .iter()
+            .filter_map(|(component, ledger)| {
+                ledger.current().map(|up| LedgerEntry {
+                    component: *component,
+                    up,
+                    at: now,
+                })
+            })
+            .collect()
+    }
+
+    /// 导出全部状态切换记录
+    pub fn export(&self) -> Vec<LedgerEntry> {
+        let mut entries: Vec<LedgerEntry> = self.ledgers
+            .iter()
+            .flat_map(|(component, ledger)| {
+                ledger.transitions.iter().map(move |(at, up)| LedgerEntry {
+                    component: *component,
+                    up: *up,
+                    at: *at,
+                })
+            })
+            .collect();
+        entries.sort_by_key(|e| e.at);
+        entries
+    }
+
+    /// 从持久化记录恢复
+    ///
+    /// 进程在最后一条记录之后停止，因此在该时刻为所有组件补一条 DOWN，
+    /// 使停机间隔计入不可用时间
+    pub fn restore(&mut self, mut entries: Vec<LedgerEntry>) {
+        entries.sort_by_key(|e| e.at);
+
+        let last_seen = match entries.last() {
+            Some(entry) => entry.at,
+            None => return,
+        };
+
+        for entry in &entries {
+            self.ledgers.entry(entry.component).or_default().record(entry.at, entry.up);
+        }
+
+        for ledger in self.ledgers.values_mut() {
+            ledger.record(last_seen, false);
+        }
+
+        info!("SLO: restored {} ledger entries (last checkpoint {})", entries.len(), last_seen);
+    }
+
+    /// 裁剪超过 7 天的记录
+    pub fn prune(&mut self, now: DateTime<Utc>) {
+        let cutoff = now - Duration::days(7);
+        for ledger in self.ledgers.values_mut() {
+            ledger.prune(cutoff);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn t0() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_windowed_uptime() {
+        let mut tracker = SloTracker::new(HashMap::new(), 99.0);
+        let start = t0();
+
+        // 0-50分钟 UP，50-60分钟 DOWN
+        tracker.record(SloComponent::FeedConnection, true, start);
+        tracker.record(SloComponent::FeedConnection, false, start + Duration::minutes(50));
+        let now = start + Duration::minutes(60);
+
+        let row = tracker.table(now).into_iter()
+            .find(|r| r.component == SloComponent::FeedConnection)
+            .unwrap();
+        assert!((row.uptime_1h - 83.333).abs() < 0.01);
+        assert_eq!(row.up, Some(false));
+
+        // 恢复后再运行 60 分钟：1h 窗口全部 UP，24h 窗口 110/120
+        tracker.record(SloComponent::FeedConnection, true, now);
+        let later = now + Duration::minutes(60);
+        let row = tracker.table(later).into_iter()
+            .find(|r| r.component == SloComponent::FeedConnection)
+            .unwrap();
+        assert!((row.uptime_1h - 100.0).abs() < 1e-9);
+        assert!((row.uptime_24h - 110.0 / 120.0 * 100.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_breach_alert_deduplication() {
+        let mut targets = HashMap::new();
+        targets.insert(SloComponent::CalculatorScan, 99.0);
+        let mut tracker = SloTracker::new(targets, 99.0);
+        let start = t0();
+
+        tracker.record(SloComponent::CalculatorScan, true, start);
+        tracker.record(SloComponent::CalculatorScan, false, start + Duration::minutes(30));
+
+        // 第一次评估触发告警，后续同一违约周期内不再重复
+        let now = start + Duration::minutes(40);
+        assert_eq!(tracker.evaluate_breaches(now).len(), 1);
+        assert!(tracker.evaluate_breaches(now + Duration::minutes(1)).is_empty());
+
+        // 恢复并长时间保持 UP -> 退出违约周期
+        tracker.record(SloComponent::CalculatorScan, true, now);
+        assert!(tracker.evaluate_breaches(now + Duration::hours(25)).is_empty());
+        assert!(!tracker.table(now + Duration::hours(25))[3].breached);
+
+        // 再次违约 -> 新周期，再告警一次
+        let again = now + Duration::hours(25);
+        tracker.record(SloComponent::CalculatorScan, false, again);
+        assert_eq!(tracker.evaluate_breaches(again + Duration::minutes(30)).len(), 1);
+    }
+
+    #[test]
+    fn test_persistence_round_trip_counts_gap_as_downtime() {
+        let mut tracker = SloTracker::new(HashMap::new(), 99.0);
+        let start = t0();
+
+        tracker.record(SloComponent::DatabaseWritability, true, start);
+        let checkpoint_at = start + Duration::minutes(30);
+        let mut persisted = tracker.export();
+        persisted.extend(tracker.checkpoint(checkpoint_at));
+
+        // 模拟崩溃后 30 分钟重启
+        let mut restored = SloTracker::new(HashMap::new(), 99.0);
+        restored.restore(persisted);
+        let restart_at = checkpoint_at + Duration::minutes(30);
+        restored.record(SloComponent::DatabaseWritability, true, restart_at);
+
+        let row = restored.table(restart_at).into_iter()
+            .find(|r| r.component == SloComponent::DatabaseWritability)
+            .unwrap();
+        // 30分钟UP + 30分钟停机
+        assert!((row.cumulative - 50.0).abs() < 0.01);
+        assert_eq!(row.up, Some(true));
+
+        // 导出的切换记录：UP, DOWN(停机), UP
+        let exported = restored.export();
+        assert_eq!(exported.len(), 3);
+        assert!(!exported[1].up);
+        assert_eq!(exported[1].at, checkpoint_at);
+    }
+}
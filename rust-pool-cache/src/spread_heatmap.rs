@@ -0,0 +1,601 @@
+/*!
+ * 🌡️ 交易对价差热力图
+ *
+ * 不看单个机会，而是看结构：对同一交易对上的每一个有序池子组合（在 buy 池买入、
+ * 在 sell 池卖出），持续统计配置窗口内观察到的价差分布：
+ *
+ * - 价差：(sell.bid - buy.ask) / buy.ask，单位 bps，手续费已计入（与 `[focus]` 的矩阵一致）
+ * - 统计按时间加权：每个样本一直有效到该组合的下一个样本（阶梯函数），
+ *   所以高频更新的池子不会把均值拉向自己
+ * - mean / p95 / max、超过阈值的累计时长与占比
+ *
+ * 数据来自状态层的价格更新广播（独立任务，不依赖完整扫描）：某个池子每更新一次，
+ * 只对包含它的组合采样——其余组合的价差没有变化。每个组合最多保留
+ * `max_samples_per_cell` 个样本，超出时丢弃最旧的。
+ *
+ * GET /stats/spreads?pair=SOL/USDC 返回矩阵；定期指标日志输出超阈值时间最长的组合，
+ * 用来判断该往哪里加池子、哪些场所系统性地过期。
+ */
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tokio::time::interval;
+use tracing::{debug, info};
+
+use crate::config::SpreadHeatmapConfig;
+use crate::price_cache::PriceCache;
+use crate::router_direct::{live_pool, DirectQuote};
+use crate::token_graph::pair_key;
+
+/// 一个组合在窗口内的价差统计
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SpreadStats {
+    /// 窗口内的样本数
+    pub samples: usize,
+    /// 统计覆盖的时长（第一个样本到最新样本 / 组合结束）
+    pub covered_secs: f64,
+    pub mean_bps: f64,
+    pub p95_bps: f64,
+    pub max_bps: f64,
+    pub last_bps: f64,
+    /// 价差超过阈值的累计时长
+    pub time_above_threshold_secs: f64,
+    /// time_above_threshold_secs / covered_secs
+    pub above_ratio: f64,
+}
+
+/// 单个有序组合的滚动窗口（阶梯函数样本）
+#[derive(Debug, Default)]
+pub struct SpreadWindow {
+    samples: VecDeque<(Instant, f64)>,
+    /// 组合中的池子离开交易对 / 不再可报价的时间；最后一个样本只持续到这里
+    ended_at: Option<Instant>,
+}
+
+impl SpreadWindow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 追加样本；超过 `max_samples` 时丢弃最旧的
+    pub fn push(&mut self, at: Instant, spread_bps: f64, max_samples: usize) {
+        self.samples.push_back((at, spread_bps));
+        self.ended_at = None;
+        while self.samples.len() > max_samples.max(1) {
+            self.samples.pop_front();
+        }
+    }
+
+    /// 组合不再有效（之后不再累计时长，直到下一个样本）
+    pub fn end(&mut self, at: Instant) {
+        if self.ended_at.is_none() && !self.samples.is_empty() {
+            self.ended_at = Some(at);
+        }
+    }
+
+    /// 丢弃窗口外的样本
+    ///
+    /// 保留窗口开始前的最后一个样本：它的值一直持续到窗口里的第一个样本。
+    pub fn prune(&mut self, window_start: Instant) {
+        while self.samples.len() > 1 && self.samples[1].0 <= window_start {
+            self.samples.pop_front();
+        }
+        if self.ended_at.is_some_and(|ended| ended <= window_start) {
+            self.samples.clear();
+            self.ended_at = None;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// 窗口内的统计；窗口内没有样本时为 None
+    pub fn stats(&self, now: Instant, window: Duration, threshold_bps: f64) -> Option<SpreadStats> {
+        let window_start = now.checked_sub(window).unwrap_or(now);
+        let horizon = self.ended_at.map_or(now, |ended| ended.min(now));
+
+        // (值, 在窗口内持续的秒数)
+        let mut segments = Vec::with_capacity(self.samples.len());
+        for (i, &(at, spread_bps)) in self.samples.iter().enumerate() {
+            let end = self.samples.get(i + 1).map_or(horizon, |next| next.0.min(horizon));
+            if end < window_start {
+                continue;
+            }
+            let start = at.max(window_start);
+            segments.push((spread_bps, end.saturating_duration_since(start).as_secs_f64()));
+        }
+        let last_bps = segments.last()?.0;
+
+        let covered: f64 = segments.iter().map(|(_, secs)| secs).sum();
+        // 只有刚到达的样本（时长为 0）时按样本数等权
+        let weight = |secs: f64| if covered > 0.0 { secs } else { 1.0 };
+        let total: f64 = segments.iter().map(|&(_, secs)| weight(secs)).sum();
+
+        let mean_bps = segments.iter().map(|&(v, secs)| v * weight(secs)).sum::<f64>() / total;
+        let max_bps = segments.iter().map(|(v, _)| *v).fold(f64::NEG_INFINITY, f64::max);
+        let time_above: f64 = segments.iter()
+            .filter(|(v, _)| *v > threshold_bps)
+            .map(|(_, secs)| secs)
+            .sum();
+
+        let mut sorted = segments.clone();
+        sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let target = total * 0.95;
+        let mut cumulative = 0.0;
+        let mut p95_bps = max_bps;
+        for &(v, secs) in &sorted {
+            cumulative += weight(secs);
+            if cumulative >= target {
+                p95_bps = v;
+                break;
+            }
+        }
+
+        Some(SpreadStats {
+            samples: segments.len(),
+            covered_secs: covered,
+            mean_bps,
+            p95_bps,
+            max_bps,
+            last_bps,
+            time_above_threshold_secs: time_above,
+            above_ratio: if covered > 0.0 { time_above / covered } else { 0.0 },
+        })
+    }
+}
+
+/// 矩阵中的一个池子
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HeatmapVenue {
+    pub pool_id: String,
+    pub dex_name: String,
+}
+
+/// 一个有序组合（buy -> sell）
+#[derive(Debug, Clone, Serialize)]
+pub struct SpreadCell {
+    pub pair: String,
+    pub buy_pool: String,
+    pub buy_dex: String,
+    pub sell_pool: String,
+    pub sell_dex: String,
+    #[serde(flatten)]
+    pub stats: SpreadStats,
+}
+
+/// 一个交易对的价差矩阵（GET /stats/spreads）
+#[derive(Debug, Clone, Serialize)]
+pub struct PairSpreadMatrix {
+    pub pair: String,
+    pub window_secs: u64,
+    pub threshold_bps: f64,
+    pub venues: Vec<HeatmapVenue>,
+    /// 按 buy / sell 在 venues 中的顺序排列
+    pub cells: Vec<SpreadCell>,
+}
+
+#[derive(Debug, Default)]
+struct PairSpreads {
+    /// pool_id -> dex_name（曾出现在交易对中的池子）
+    venues: BTreeMap<String, String>,
+    /// (buy pool, sell pool) -> 价差窗口
+    cells: HashMap<(String, String), SpreadWindow>,
+}
+
+#[derive(Debug, Default)]
+struct HeatmapState {
+    /// pool_id -> 交易对分组键
+    pool_pairs: HashMap<String, String>,
+    pairs: HashMap<String, PairSpreads>,
+}
+
+/// 各交易对的价差热力图
+pub struct SpreadHeatmap {
+    window: Duration,
+    threshold_bps: f64,
+    max_samples_per_cell: usize,
+    state: Mutex<HeatmapState>,
+}
+
+impl SpreadHeatmap {
+    pub fn new(config: &SpreadHeatmapConfig) -> Self {
+        Self {
+            window: Duration::from_secs(config.window_secs),
+            threshold_bps: config.threshold_bps,
+            max_samples_per_cell: config.max_samples_per_cell,
+            state: Mutex::new(HeatmapState::default()),
+        }
+    }
+
+    /// 用交易对当前的报价采样
+    ///
+    /// `updated` 为 Some 时只采样包含该池子的组合；报价中缺席的池子的组合在 `now` 结束。
+    pub fn observe(&self, pair: &str, quotes: &[DirectQuote], updated: Option<&str>, now: Instant) {
+        let window_start = now.checked_sub(self.window).unwrap_or(now);
+        let mut state = self.state.lock().unwrap();
+        let spreads = state.pairs.entry(pair.to_string()).or_default();
+
+        for quote in quotes {
+            spreads.venues.insert(quote.pool_id.clone(), quote.dex_name.clone());
+        }
+        for buy in quotes {
+            for sell in quotes.iter().filter(|sell| sell.pool_id != buy.pool_id) {
+                if updated.is_some_and(|id| id != buy.pool_id && id != sell.pool_id) {
+                    continue;
+                }
+                let spread_bps = (sell.bid - buy.ask) / buy.ask * 10_000.0;
+                spreads.cells
+                    .entry((buy.pool_id.clone(), sell.pool_id.clone()))
+                    .or_default()
+                    .push(now, spread_bps, self.max_samples_per_cell);
+            }
+        }
+
+        let live = |pool_id: &str| quotes.iter().any(|q| q.pool_id == pool_id);
+        for ((buy, sell), window) in spreads.cells.iter_mut() {
+            if !live(buy) || !live(sell) {
+                window.end(now);
+            }
+            window.prune(window_start);
+        }
+        Self::drop_empty(spreads);
+    }
+
+    /// 价格事件入口：按池子当前所属交易对采样包含它的组合
+    pub fn on_update(&self, price_cache: &PriceCache, pool_id: &str, now: Instant) {
+        let pair = price_cache.get_price(pool_id).map(|pool| pair_key(&pool));
+        let previous = {
+            let mut state = self.state.lock().unwrap();
+            match &pair {
+                Some(pair) => state.pool_pairs.insert(pool_id.to_string(), pair.clone()),
+                None => state.pool_pairs.remove(pool_id),
+            }
+        };
+
+        // 池子被移除或改名：原交易对里它的组合结束
+        if let Some(previous) = previous.filter(|previous| pair.as_ref() != Some(previous)) {
+            let quotes = self.quotes(price_cache, &previous);
+            self.observe(&previous, &quotes, Some(pool_id), now);
+        }
+        if let Some(pair) = pair {
+            let quotes = self.quotes(price_cache, &pair);
+            self.observe(&pair, &quotes, Some(pool_id), now);
+        }
+    }
+
+    /// 重新采样全部交易对（启动 / 广播滞后之后）
+    pub fn resync(&self, price_cache: &PriceCache, now: Instant) {
+        let mut grouped: HashMap<String, Vec<String>> = HashMap::new();
+        for pool in price_cache.get_all_prices() {
+            grouped.entry(pair_key(&pool)).or_default().push(pool.pool_id);
+        }
+        let pairs: Vec<String> = {
+            let mut state = self.state.lock().unwrap();
+            state.pool_pairs = grouped.iter()
+                .flat_map(|(pair, ids)| ids.iter().map(move |id| (id.clone(), pair.clone())))
+                .collect();
+            state.pairs.keys().cloned().chain(grouped.keys().cloned()).collect()
+        };
+        for pair in pairs {
+            let quotes = self.quotes(price_cache, &pair);
+            self.observe(&pair, &quotes, None, now);
+        }
+    }
+
+    /// 交易对中当前可报价的池子
+    fn quotes(&self, price_cache: &PriceCache, pair: &str) -> Vec<DirectQuote> {
+        let ids: Vec<String> = {
+            let state = self.state.lock().unwrap();
+            state.pool_pairs.iter()
+                .filter(|(_, p)| p.as_str() == pair)
+                .map(|(id, _)| id.clone())
+                .collect()
+        };
+        ids.iter()
+            .filter_map(|id| live_pool(price_cache, id))
+            .filter_map(|pool| DirectQuote::from_pool(&pool))
+            .collect()
+    }
+
+    /// 丢弃所有交易对中窗口外的样本
+    pub fn prune(&self, now: Instant) {
+        let window_start = now.checked_sub(self.window).unwrap_or(now);
+        let mut state = self.state.lock().unwrap();
+        for spreads in state.pairs.values_mut() {
+            for window in spreads.cells.values_mut() {
+                window.prune(window_start);
+            }
+            Self::drop_empty(spreads);
+        }
+        state.pairs.retain(|_, spreads| !spreads.cells.is_empty());
+    }
+
+    /// 没有样本的组合不再保留；不在任何组合中的池子从 venues 移除
+    fn drop_empty(spreads: &mut PairSpreads) {
+        spreads.cells.retain(|_, window| !window.is_empty());
+        let cells = &spreads.cells;
+        spreads.venues.retain(|pool_id, _| cells.keys().any(|(buy, sell)| buy == pool_id || sell == pool_id));
+    }
+
+    /// 有样本的交易对
+    pub fn pairs(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        let mut pairs: Vec<String> = state.pairs.iter()
+            .filter(|(_, spreads)| !spreads.cells.is_empty())
+            .map(|(pair, _)| pair.clone())
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    /// 交易对的价差矩阵；没有样本时为 None
+    pub fn matrix(&self, pair: &str, now: Instant) -> Option<PairSpreadMatrix> {
+        let state = self.state.lock().unwrap();
+        let spreads = state.pairs.get(pair).filter(|spreads| !spreads.cells.is_empty())?;
+
+        let venues: Vec<HeatmapVenue> = spreads.venues.iter()
+            .map(|(pool_id, dex_name)| HeatmapVenue { pool_id: pool_id.clone(), dex_name: dex_name.clone() })
+            .collect();
+        let mut cells = Vec::new();
+        for buy in &venues {
+            for sell in venues.iter().filter(|sell| sell.pool_id != buy.pool_id) {
+                let Some(stats) = spreads.cells
+                    .get(&(buy.pool_id.clone(), sell.pool_id.clone()))
+                    .and_then(|window| window.stats(now, self.window, self.threshold_bps))
+                else {
+                    continue;
+                };
+                cells.push(SpreadCell {
+                    pair: pair.to_string(),
+                    buy_pool: buy.pool_id.clone(),
+                    buy_dex: buy.dex_name.clone(),
+                    sell_pool: sell.pool_id.clone(),
+                    sell_dex: sell.dex_name.clone(),
+                    stats,
+                });
+            }
+        }
+
+        Some(PairSpreadMatrix {
+            pair: pair.to_string(),
+            window_secs: self.window.as_secs(),
+            threshold_bps: self.threshold_bps,
+            venues,
+            cells,
+        })
+    }
+
+    /// 超过阈值累计时间最长的组合（只含超过过阈值的）
+    pub fn top_persistent(&self, limit: usize, now: Instant) -> Vec<SpreadCell> {
+        let mut cells: Vec<SpreadCell> = self.pairs()
+            .iter()
+            .filter_map(|pair| self.matrix(pair, now))
+            .flat_map(|matrix| matrix.cells)
+            .filter(|cell| cell.stats.time_above_threshold_secs > 0.0)
+            .collect();
+        cells.sort_by(|a, b| {
+            b.stats.time_above_threshold_secs.total_cmp(&a.stats.time_above_threshold_secs)
+                .then_with(|| b.stats.mean_bps.total_cmp(&a.stats.mean_bps))
+        });
+        cells.truncate(limit);
+        cells
+    }
+
+    /// 定期指标日志：超阈值时间最长的组合
+    pub fn log_top_persistent(&self, limit: usize) {
+        let top = self.top_persistent(limit, Instant::now());
+        if top.is_empty() {
+            return;
+        }
+        info!("🌡️ Top persistent spreads (> {:.1} bps, last {}s):", self.threshold_bps, self.window.as_secs());
+        for cell in &top {
+            info!(
+                "   {} buy {} ({}) -> sell {} ({}): above {:.0}s ({:.0}%), mean {:.1} bps, p95 {:.1} bps, max {:.1} bps",
+                cell.pair, cell.buy_dex, cell.buy_pool, cell.sell_dex, cell.sell_pool,
+                cell.stats.time_above_threshold_secs, cell.stats.above_ratio * 100.0,
+                cell.stats.mean_bps, cell.stats.p95_bps, cell.stats.max_bps
+            );
+        }
+    }
+}
+
+/// 后台任务：订阅价格更新采样价差，定期丢弃窗口外的样本；收到关闭信号时退出
+pub fn spawn_spread_heatmap(
+    heatmap: Arc<SpreadHeatmap>,
+    price_cache: Arc<PriceCache>,
+    mut shutdown: broadcast::Receiver<()>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut updates = price_cache.subscribe_updates();
+        let mut ticker = interval(Duration::from_secs(60));
+        heatmap.resync(&price_cache, Instant::now());
+
+        loop {
+            tokio::select! {
+                event = updates.recv() => match event {
+                    Ok(event) => heatmap.on_update(&price_cache, &event.pool_id, Instant::now()),
+                    Err(RecvError::Lagged(skipped)) => {
+                        debug!("Spread heatmap lagged {} price events, re-sampling all pairs", skipped);
+                        heatmap.resync(&price_cache, Instant::now());
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = ticker.tick() => heatmap.prune(Instant::now()),
+                _ = shutdown.recv() => break,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price_cache::PoolPrice;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    fn config(window_secs: u64, max_samples_per_cell: usize) -> SpreadHeatmapConfig {
+        SpreadHeatmapConfig {
+            enabled: true,
+            window_secs,
+            threshold_bps: 10.0,
+            max_samples_per_cell,
+            top_n: 5,
+        }
+    }
+
+    fn quote(pool_id: &str, price: f64) -> DirectQuote {
+        DirectQuote {
+            pool_id: pool_id.to_string(),
+            dex_name: format!("DEX {}", pool_id),
+            price,
+            fee_rate: 0.0,
+            bid: price,
+            ask: price,
+        }
+    }
+
+    #[test]
+    fn test_window_stats_are_time_weighted() {
+        let start = Instant::now();
+        let mut window = SpreadWindow::new();
+        // 0-10s: 20 bps, 10-40s: 0 bps, 40-50s: 50 bps
+        window.push(start, 20.0, 100);
+        window.push(start + secs(10), 0.0, 100);
+        window.push(start + secs(40), 50.0, 100);
+
+        let stats = window.stats(start + secs(50), secs(100), 10.0).unwrap();
+        assert_eq!(stats.samples, 3);
+        assert!((stats.covered_secs - 50.0).abs() < 1e-9);
+        // (20 × 10 + 0 × 30 + 50 × 10) / 50
+        assert!((stats.mean_bps - 14.0).abs() < 1e-9);
+        assert_eq!(stats.max_bps, 50.0);
+        assert_eq!(stats.last_bps, 50.0);
+        assert!((stats.time_above_threshold_secs - 20.0).abs() < 1e-9);
+        assert!((stats.above_ratio - 0.4).abs() < 1e-9);
+        // 按时间排序：0 bps 占 60%，20 bps 到 80%，95% 落在 50 bps
+        assert_eq!(stats.p95_bps, 50.0);
+
+        // 高频样本不改变时间加权均值
+        let mut bursty = SpreadWindow::new();
+        bursty.push(start, 0.0, 100);
+        for i in 0..20 {
+            bursty.push(start + secs(90) + Duration::from_millis(i * 100), 100.0, 100);
+        }
+        let stats = bursty.stats(start + secs(100), secs(100), 10.0).unwrap();
+        assert!((stats.mean_bps - 10.0).abs() < 1e-9);
+        assert_eq!(stats.p95_bps, 100.0);
+
+        // 只有刚到达的样本：按样本等权
+        let mut fresh = SpreadWindow::new();
+        fresh.push(start, 7.0, 100);
+        let stats = fresh.stats(start, secs(100), 10.0).unwrap();
+        assert_eq!((stats.mean_bps, stats.p95_bps, stats.covered_secs), (7.0, 7.0, 0.0));
+    }
+
+    #[test]
+    fn test_window_clips_to_window_and_bounds_memory() {
+        let start = Instant::now();
+        let mut window = SpreadWindow::new();
+        window.push(start, 30.0, 100);
+        window.push(start + secs(50), 0.0, 100);
+
+        // 窗口 [40, 60]：30 bps 在窗口内只持续 10s
+        let now = start + secs(60);
+        window.prune(now - secs(20));
+        assert_eq!(window.samples.len(), 2, "sample before the window start still carries into it");
+        let stats = window.stats(now, secs(20), 10.0).unwrap();
+        assert!((stats.covered_secs - 20.0).abs() < 1e-9);
+        assert!((stats.time_above_threshold_secs - 10.0).abs() < 1e-9);
+        assert!((stats.mean_bps - 15.0).abs() < 1e-9);
+
+        // 组合结束后不再累计时长，结束点滑出窗口后清空
+        window.end(now);
+        let stats = window.stats(now + secs(10), secs(20), 10.0).unwrap();
+        assert!((stats.covered_secs - 10.0).abs() < 1e-9, "window [50, 70] is only covered until 60");
+        window.prune(now - secs(5));
+        assert!(!window.is_empty());
+        window.prune(now + secs(1));
+        assert!(window.is_empty());
+
+        // 每个组合最多保留 max_samples 个样本
+        let mut capped = SpreadWindow::new();
+        for i in 0..10 {
+            capped.push(start + secs(i), i as f64, 4);
+        }
+        assert_eq!(capped.samples.len(), 4);
+        assert_eq!(capped.samples.front().unwrap().1, 6.0);
+    }
+
+    #[test]
+    fn test_heatmap_matrix_and_top_persistent() {
+        let heatmap = SpreadHeatmap::new(&config(600, 64));
+        let start = Instant::now();
+
+        let quotes = vec![quote("a", 100.0), quote("b", 100.5), quote("c", 100.0)];
+        heatmap.observe("SOL/USDC", &quotes, None, start);
+        // c 更新：只采样包含 c 的组合
+        let quotes = vec![quote("a", 100.0), quote("b", 100.5), quote("c", 100.05)];
+        heatmap.observe("SOL/USDC", &quotes, Some("c"), start + secs(30));
+
+        let now = start + secs(60);
+        let matrix = heatmap.matrix("SOL/USDC", now).unwrap();
+        assert_eq!(matrix.venues.iter().map(|v| v.pool_id.as_str()).collect::<Vec<_>>(), ["a", "b", "c"]);
+        assert_eq!(matrix.cells.len(), 6);
+        let cell = |buy: &str, sell: &str| {
+            matrix.cells.iter().find(|c| c.buy_pool == buy && c.sell_pool == sell).unwrap().stats
+        };
+        assert_eq!(cell("a", "b").samples, 1, "a -> b not resampled on c's update");
+        assert!((cell("a", "b").mean_bps - 50.0).abs() < 1e-9);
+        assert!(cell("b", "a").mean_bps < 0.0);
+        assert_eq!(cell("b", "c").samples, 2);
+        assert!((cell("c", "b").last_bps - 44.98).abs() < 0.01);
+
+        let top = heatmap.top_persistent(2, now);
+        assert_eq!(top.len(), 2);
+        assert_eq!((top[0].buy_pool.as_str(), top[0].sell_pool.as_str()), ("a", "b"));
+        assert_eq!((top[1].buy_pool.as_str(), top[1].sell_pool.as_str()), ("c", "b"));
+        let json = serde_json::to_value(&top[0]).unwrap();
+        assert_eq!(json["time_above_threshold_secs"], 60.0);
+
+        // 池子离开交易对：它的组合结束，窗口过后从矩阵中消失
+        heatmap.observe("SOL/USDC", &[quote("a", 100.0), quote("b", 100.5)], None, now);
+        heatmap.prune(now + secs(601));
+        assert!(heatmap.matrix("SOL/USDC", now + secs(601)).unwrap().venues.len() == 2);
+        assert!(heatmap.matrix("BONK/SOL", now).is_none());
+    }
+
+    #[test]
+    fn test_on_update_groups_pools_by_pair() {
+        let price_cache = PriceCache::new();
+        let pool = |pool_id: &str, pair: &str, price: f64| PoolPrice {
+            base_reserve: 1_000_000_000_000,
+            quote_reserve: 185_000_000_000,
+            ..PoolPrice::for_test(pool_id, pair, price)
+        };
+        price_cache.update_price(pool("p1", "SOL/USDC", 185.0));
+        price_cache.update_price(pool("p2", "SOL/USDC", 186.0));
+        price_cache.update_price(pool("p3", "BONK/SOL", 0.00001));
+
+        let heatmap = SpreadHeatmap::new(&config(600, 64));
+        let now = Instant::now();
+        heatmap.resync(&price_cache, now);
+        assert_eq!(heatmap.pairs(), vec!["SOL/USDC"]);
+
+        price_cache.update_price(pool("p2", "SOL/USDC", 187.0));
+        heatmap.on_update(&price_cache, "p2", now + secs(5));
+        let matrix = heatmap.matrix("SOL/USDC", now + secs(5)).unwrap();
+        let p1_p2 = matrix.cells.iter().find(|c| c.buy_pool == "p1").unwrap();
+        assert_eq!(p1_p2.stats.samples, 2);
+        assert!(p1_p2.stats.last_bps > p1_p2.stats.mean_bps);
+    }
+}
@@ -0,0 +1,271 @@
+/*!
+ * 📏 交易对价差监控
+ *
+ * 独立于完整的套利检测：订阅状态层的价格更新广播，对每个至少有 2 个池子的交易对
+ * 计算各场所之间的最大价差。价差持续超过阈值 `sustain_secs` 秒才告警（warn 日志 +
+ * 可选 webhook）——持续的价差通常说明某个数据源出了问题，而不是白送的利润，
+ * 所以告警里带上每个池子的 slot 和数据年龄，方便区分过期数据和真实偏离。
+ */
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tokio::time::interval;
+use tracing::{debug, info, warn};
+
+use crate::config::SpreadMonitorConfig;
+use crate::price_cache::{PoolPrice, PriceCache};
+
+/// 告警中的单个池子
+#[derive(Debug, Clone, Serialize)]
+pub struct SpreadVenue {
+    pub pool_id: String,
+    pub dex_name: String,
+    pub price: f64,
+    pub slot: u64,
+    pub age_ms: u64,
+}
+
+impl SpreadVenue {
+    fn from_price(price: &PoolPrice) -> Self {
+        Self {
+            pool_id: price.pool_id.clone(),
+            dex_name: price.dex_name.clone(),
+            price: price.price,
+            slot: price.slot,
+            age_ms: price.last_update.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+/// 价差告警（webhook 的 JSON body）
+#[derive(Debug, Clone, Serialize)]
+pub struct SpreadAlert {
+    pub pair: String,
+    pub spread_bps: f64,
+    pub threshold_bps: f64,
+    /// 价差持续超过阈值的时长
+    pub sustained_secs: f64,
+    /// 最低价场所（买入侧）
+    pub cheapest: SpreadVenue,
+    /// 最高价场所（卖出侧）
+    pub richest: SpreadVenue,
+    /// 该交易对的所有池子
+    pub pools: Vec<SpreadVenue>,
+}
+
+/// 单个交易对的持续计时
+#[derive(Debug, Default)]
+struct PairState {
+    /// 价差首次超过阈值的时间（回落到阈值以下时清空）
+    exceeded_since: Option<Instant>,
+    /// 本轮超阈值已经告警过
+    alerted: bool,
+}
+
+/// 价差判定（不含 IO，便于测试）
+pub struct SpreadMonitor {
+    threshold_bps: f64,
+    sustain: Duration,
+    pairs: HashMap<String, PairState>,
+}
+
+impl SpreadMonitor {
+    pub fn new(threshold_bps: f64, sustain: Duration) -> Self {
+        Self {
+            threshold_bps,
+            sustain,
+            pairs: HashMap::new(),
+        }
+    }
+
+    /// (最低价池子, 最高价池子, 价差 bps)；有效池子不足 2 个时为 None
+    fn max_spread(pools: &[PoolPrice]) -> Option<(&PoolPrice, &PoolPrice, f64)> {
+        let valid = || pools.iter().filter(|p| p.price.is_finite() && p.price > 0.0);
+        if valid().count() < 2 {
+            return None;
+        }
+        let cheapest = valid().min_by(|a, b| a.price.total_cmp(&b.price))?;
+        let richest = valid().max_by(|a, b| a.price.total_cmp(&b.price))?;
+        let spread_bps = (richest.price - cheapest.price) / cheapest.price * 10_000.0;
+        Some((cheapest, richest, spread_bps))
+    }
+
+    /// 用交易对的最新池子价格更新状态；价差持续超过阈值且本轮尚未告警时返回告警
+    pub fn observe(&mut self, pair: &str, pools: &[PoolPrice], now: Instant) -> Option<SpreadAlert> {
+        let spread = Self::max_spread(pools);
+        let state = self.pairs.entry(pair.to_string()).or_default();
+
+        let (cheapest, richest, spread_bps) = match spread {
+            Some(spread) if spread.2 > self.threshold_bps => spread,
+            _ => {
+                if state.alerted {
+                    info!("📏 Spread on {} back under {:.1} bps", pair, self.threshold_bps);
+                }
+                *state = PairState::default();
+                return None;
+            }
+        };
+
+        let since = *state.exceeded_since.get_or_insert(now);
+        let sustained = now.duration_since(since);
+        if state.alerted || sustained < self.sustain {
+            return None;
+        }
+        state.alerted = true;
+
+        Some(SpreadAlert {
+            pair: pair.to_string(),
+            spread_bps,
+            threshold_bps: self.threshold_bps,
+            sustained_secs: sustained.as_secs_f64(),
+            cheapest: SpreadVenue::from_price(cheapest),
+            richest: SpreadVenue::from_price(richest),
+            pools: pools.iter().map(SpreadVenue::from_price).collect(),
+        })
+    }
+
+    /// 正在计时、尚未告警的交易对（没有新事件时由定时器复查）
+    pub fn pending_pairs(&self) -> Vec<String> {
+        self.pairs
+            .iter()
+            .filter(|(_, state)| state.exceeded_since.is_some() && !state.alerted)
+            .map(|(pair, _)| pair.clone())
+            .collect()
+    }
+}
+
+/// 输出告警：warn 日志，配置了 webhook 时在后台 POST
+fn emit(alert: &SpreadAlert, webhook_url: Option<&str>) {
+    let ages: Vec<String> = alert.pools.iter()
+        .map(|p| format!("{} {:.6} slot {} age {}ms", p.dex_name, p.price, p.slot, p.age_ms))
+        .collect();
+    warn!(
+        "📏 Sustained spread on {}: {:.1} bps for {:.0}s (buy {} @ {:.6}, sell {} @ {:.6}) [{}]",
+        alert.pair, alert.spread_bps, alert.sustained_secs,
+        alert.cheapest.dex_name, alert.cheapest.price,
+        alert.richest.dex_name, alert.richest.price,
+        ages.join("; ")
+    );
+
+    let Some(url) = webhook_url else {
+        return;
+    };
+    let body = match serde_json::to_string(alert) {
+        Ok(body) => body,
+        Err(e) => {
+            warn!("Failed to serialize spread alert for {}: {}", alert.pair, e);
+            return;
+        }
+    };
+    let url = url.to_string();
+    let pair = alert.pair.clone();
+    tokio::spawn(async move {
+        if let Err(e) = crate::webhook::post_json(&url, &body).await {
+            warn!("📏 Spread alert webhook for {} failed: {:#}", pair, e);
+        }
+    });
+}
+
+/// 后台任务：订阅价格更新，按交易对评估价差；收到关闭信号时退出
+pub fn spawn_spread_monitor(
+    config: SpreadMonitorConfig,
+    price_cache: Arc<PriceCache>,
+    mut shutdown: broadcast::Receiver<()>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut updates = price_cache.subscribe_updates();
+        let mut monitor = SpreadMonitor::new(config.threshold_bps, Duration::from_secs(config.sustain_secs));
+        // 价差超阈值后没有新事件也要按时告警
+        let mut ticker = interval(Duration::from_secs(1));
+        let webhook_url = config.webhook_url.as_deref().filter(|url| !url.is_empty());
+
+        loop {
+            let pairs = tokio::select! {
+                event = updates.recv() => match event {
+                    Ok(event) => vec![event.pair],
+                    Err(RecvError::Lagged(skipped)) => {
+                        // 丢了事件：所有交易对重新评估一次
+                        debug!("Spread monitor lagged {} price events, re-evaluating all pairs", skipped);
+                        price_cache.get_pairs()
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = ticker.tick() => monitor.pending_pairs(),
+                _ = shutdown.recv() => break,
+            };
+
+            let now = Instant::now();
+            for pair in pairs {
+                let pools = price_cache.get_pools_by_pair(&pair);
+                if let Some(alert) = monitor.observe(&pair, &pools, now) {
+                    emit(&alert, webhook_url);
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(pool_id: &str, price: f64, slot: u64) -> PoolPrice {
+        PoolPrice {
+            dex_name: format!("DEX {}", pool_id),
+            base_reserve: 1_000_000_000,
+            quote_reserve: 185_000_000,
+            slot,
+            ..PoolPrice::for_test(pool_id, "SOL/USDC", price)
+        }
+    }
+
+    #[test]
+    fn test_alerts_once_after_spread_is_sustained() {
+        let mut monitor = SpreadMonitor::new(50.0, Duration::from_secs(10));
+        let start = Instant::now();
+        let wide = vec![pool("a", 185.0, 100), pool("b", 186.5, 90), pool("c", 185.4, 101)];
+
+        // 81 bps，但还没持续够 10 秒
+        assert!(monitor.observe("SOL/USDC", &wide, start).is_none());
+        assert_eq!(monitor.pending_pairs(), vec!["SOL/USDC"]);
+        assert!(monitor.observe("SOL/USDC", &wide, start + Duration::from_secs(5)).is_none());
+
+        let alert = monitor.observe("SOL/USDC", &wide, start + Duration::from_secs(10)).unwrap();
+        assert_eq!(alert.cheapest.pool_id, "a");
+        assert_eq!(alert.richest.pool_id, "b");
+        assert_eq!(alert.richest.slot, 90);
+        assert!((alert.spread_bps - 81.08).abs() < 0.01);
+        assert_eq!(alert.pools.len(), 3);
+        let json = serde_json::to_value(&alert).unwrap();
+        assert_eq!(json["pools"][1]["slot"], 90);
+
+        // 同一轮只告警一次
+        assert!(monitor.observe("SOL/USDC", &wide, start + Duration::from_secs(20)).is_none());
+        assert!(monitor.pending_pairs().is_empty());
+    }
+
+    #[test]
+    fn test_timer_resets_when_spread_closes() {
+        let mut monitor = SpreadMonitor::new(50.0, Duration::from_secs(10));
+        let start = Instant::now();
+        let wide = vec![pool("a", 185.0, 1), pool("b", 186.5, 1)];
+        let tight = vec![pool("a", 185.0, 2), pool("b", 185.2, 2)];
+
+        assert!(monitor.observe("SOL/USDC", &wide, start).is_none());
+        assert!(monitor.observe("SOL/USDC", &tight, start + Duration::from_secs(8)).is_none());
+        // 重新开始计时
+        assert!(monitor.observe("SOL/USDC", &wide, start + Duration::from_secs(12)).is_none());
+        assert!(monitor.observe("SOL/USDC", &wide, start + Duration::from_secs(22)).is_some());
+
+        // 只有一个有效池子的交易对不计算价差
+        let single = vec![pool("a", 185.0, 1), pool("b", 0.0, 1)];
+        assert!(monitor.observe("BONK/SOL", &single, start).is_none());
+        assert!(monitor.observe("BONK/SOL", &single, start + Duration::from_secs(60)).is_none());
+    }
+}
@@ -0,0 +1,166 @@
+/*!
+ * StableSwap 不变量（两币 Curve 曲线）
+ *
+ * 稳定币 / LST 池子（如 Stabble）不是恒定乘积：在锚定价附近曲线更平，
+ * 按储备比例定价会在偏离锚定时严重失真。这里实现两币 StableSwap：
+ *
+ *   A·n^n·(x + y) + D = A·D·n^n + D^(n+1) / (n^n·x·y)，n = 2
+ *
+ * - `compute_d`：当前余额下的不变量 D（牛顿迭代）
+ * - `get_amount_out`：exact-in 报价，手续费从输出中扣除（与 Curve 一致）
+ * - `marginal_price`：当前余额下的边际价格 dy/dx（零金额报价）
+ *
+ * 余额必须是同一精度（两边 decimals 相同；不同时由调用方先归一化）。
+ * 迭代使用 u128 整数运算，溢出或不收敛时返回 None。
+ */
+
+/// 两币池子（n = 2）
+const N_COINS: u128 = 2;
+/// 牛顿迭代上限（Curve 同样使用 255）
+const MAX_ITERATIONS: usize = 255;
+
+/// 不变量 D；任一余额为 0 或 amp 为 0 时返回 None
+pub fn compute_d(balances: (u64, u64), amp: u64) -> Option<u128> {
+    let (x, y) = (balances.0 as u128, balances.1 as u128);
+    if x == 0 || y == 0 || amp == 0 {
+        return None;
+    }
+    let sum = x + y;
+    let ann = amp as u128 * N_COINS * N_COINS;
+
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        // d_p = D^3 / (n^n · x · y)，分两步避免溢出
+        let d_p = d.checked_mul(d)? / (x * N_COINS);
+        let d_p = d_p.checked_mul(d)? / (y * N_COINS);
+        let previous = d;
+        let numerator = ann.checked_mul(sum)?.checked_add(d_p * N_COINS)?.checked_mul(d)?;
+        let denominator = (ann - 1).checked_mul(d)?.checked_add((N_COINS + 1) * d_p)?;
+        d = numerator / denominator;
+        if d.abs_diff(previous) <= 1 {
+            return Some(d);
+        }
+    }
+    None
+}
+
+/// 输入侧余额变为 `x` 后，保持不变量 D 所需的输出侧余额
+fn compute_y(x: u128, d: u128, ann: u128) -> Option<u128> {
+    // c = D^3 / (n^n · x · Ann)，b = x + D / Ann
+    let c = d.checked_mul(d)? / (x * N_COINS);
+    let c = c.checked_mul(d)? / (ann * N_COINS);
+    let b = x + d / ann;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let previous = y;
+        let denominator = (2 * y + b).checked_sub(d)?;
+        y = y.checked_mul(y)?.checked_add(c)? / denominator;
+        if y.abs_diff(previous) <= 1 {
+            return Some(y);
+        }
+    }
+    None
+}
+
+/// exact-in 报价
+///
+/// `balances` 为 (输入侧余额, 输出侧余额)，`fee` 为小数费率（0.0004 = 0.04%）。
+/// 返回扣除手续费后的输出数量（与余额同精度）。
+pub fn get_amount_out(amount_in: u64, balances: (u64, u64), amp: u64, fee: f64) -> Option<u64> {
+    if amount_in == 0 {
+        return Some(0);
+    }
+    let d = compute_d(balances, amp)?;
+    let ann = amp as u128 * N_COINS * N_COINS;
+    let new_x = balances.0 as u128 + amount_in as u128;
+    let new_y = compute_y(new_x, d, ann)?;
+    // 向下取整多扣 1，避免因舍入报出池子实际给不出的数量
+    let dy = (balances.1 as u128).checked_sub(new_y)?.saturating_sub(1);
+    Some((dy as f64 * (1.0 - fee)).floor() as u64)
+}
+
+/// 当前余额下的边际价格：每单位输入侧代币可换得的输出侧代币（不含手续费）
+///
+/// 对不变量隐函数求导：dy/dx = (Ann + D³/(4x²y)) / (Ann + D³/(4xy²))。
+/// 余额相等时恰好为 1，偏离锚定时远比储备比例平缓。
+pub fn marginal_price(balances: (u64, u64), amp: u64) -> Option<f64> {
+    let d = compute_d(balances, amp)? as f64;
+    let (x, y) = (balances.0 as f64, balances.1 as f64);
+    let ann = amp as f64 * 4.0;
+    let d3 = d * d * d;
+    Some((ann + d3 / (4.0 * x * x * y)) / (ann + d3 / (4.0 * x * y * y)))
+}
+
+/// 以 UI 单位报价（DexPool::get_orderbook_quote 的约定）
+///
+/// `reserves` / `decimals` 为 (base, quote)；`is_buy = true` 表示花 quote 买 base。
+/// 两边 decimals 不同时按较高精度归一化余额后再计算。
+pub fn quote_ui(
+    amount_in: f64,
+    is_buy: bool,
+    reserves: (u64, u64),
+    decimals: (u8, u8),
+    amp: u64,
+    fee: f64,
+) -> Option<f64> {
+    let (reserve_in, reserve_out, in_decimals, out_decimals) = if is_buy {
+        (reserves.1, reserves.0, decimals.1, decimals.0)
+    } else {
+        (reserves.0, reserves.1, decimals.0, decimals.1)
+    };
+    let precision = in_decimals.max(out_decimals);
+    let scale = |amount: u64, decimals: u8| amount.checked_mul(10u64.checked_pow((precision - decimals) as u32)?);
+
+    let amount_in_raw = (amount_in * 10f64.powi(precision as i32)).floor();
+    if !amount_in_raw.is_finite() || amount_in_raw < 0.0 || amount_in_raw > u64::MAX as f64 {
+        return None;
+    }
+    let balances = (scale(reserve_in, in_decimals)?, scale(reserve_out, out_decimals)?);
+    let amount_out = get_amount_out(amount_in_raw as u64, balances, amp, fee)?;
+    Some(amount_out as f64 / 10f64.powi(precision as i32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MILLION: u64 = 1_000_000 * 1_000_000; // 1M（6 decimals）
+
+    #[test]
+    fn test_balanced_pool_quotes_one_to_one_minus_fee() {
+        let amount_in = 1_000 * 1_000_000;
+        let out = get_amount_out(amount_in, (MILLION, MILLION), 100, 0.0004).unwrap();
+        let expected = amount_in as f64 * (1.0 - 0.0004);
+        assert!((out as f64 - expected).abs() / expected < 1e-4, "out {} expected ≈ {}", out, expected);
+
+        assert!((marginal_price((MILLION, MILLION), 100).unwrap() - 1.0).abs() < 1e-9);
+        let ui = quote_ui(1_000.0, false, (MILLION, MILLION), (6, 6), 100, 0.0004).unwrap();
+        assert!((ui - 999.6).abs() < 0.1, "{}", ui);
+    }
+
+    #[test]
+    fn test_imbalanced_pool_price_moves_far_less_than_constant_product() {
+        // 1.5M / 0.5M：恒定乘积价格 0.333，偏离锚定 67%
+        let balances = (MILLION * 3 / 2, MILLION / 2);
+        let constant_product = balances.1 as f64 / balances.0 as f64;
+        let stable = marginal_price(balances, 100).unwrap();
+        assert!(stable < 1.0);
+        assert!((1.0 - stable) < (1.0 - constant_product) / 10.0, "stable {} vs cp {}", stable, constant_product);
+
+        // 小额报价接近边际价格
+        let amount_in = 1_000 * 1_000_000;
+        let out = get_amount_out(amount_in, balances, 100, 0.0).unwrap();
+        assert!((out as f64 / amount_in as f64 - stable).abs() < 1e-3);
+
+        // amp 越大曲线越平
+        assert!(marginal_price(balances, 1_000).unwrap() > stable);
+    }
+
+    #[test]
+    fn test_empty_pool_or_zero_amp_has_no_quote() {
+        assert_eq!(compute_d((0, MILLION), 100), None);
+        assert_eq!(compute_d((MILLION, MILLION), 0), None);
+        assert_eq!(get_amount_out(1, (MILLION, 0), 100, 0.0), None);
+    }
+}
@@ -0,0 +1,212 @@
+/*!
+ * 按池子类型的新鲜度策略
+ *
+ * 单一的 2000ms / 10 slot 预算对 CLOB 池子不公平：Phoenix 由 RPC 刷新器每 5 秒
+ * 拉取一次，按 AMM 的标准永远是"过期"的，Complete 扫描因此丢掉所有 Phoenix 腿。
+ * 这里按池子类型（从 dex_name 推断）给出各自的预算，`[[pools]] max_age_ms`
+ * 可以覆盖单个池子。
+ */
+
+use dashmap::DashMap;
+
+use crate::config::PoolConfig;
+
+/// 一个 slot 约 400ms（用于把时间预算折算成 slot 预算）
+const SLOT_MS: u64 = 400;
+
+/// 池子类型（决定新鲜度预算）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PoolClass {
+    /// 常数乘积 AMM，每个 slot 推送更新
+    Amm,
+    /// 集中流动性（CLMM / Whirlpool / DLMM）
+    Clmm,
+    /// 订单簿（Phoenix / OpenBook），由 RPC 刷新器定时拉取
+    Clob,
+    /// 储备量来自 vault 账户（池子和 vault 两路更新）
+    VaultDependent,
+}
+
+impl PoolClass {
+    /// 按 dex_name 推断池子类型（兼容展示名和配置名）
+    pub fn from_dex_name(dex_name: &str) -> Self {
+        let name = dex_name.to_lowercase();
+        match name.as_str() {
+            s if s.contains("clob") || s.contains("phoenix") || s.contains("openbook") => PoolClass::Clob,
+            s if s.contains("clmm") || s.contains("whirlpool") || s.contains("dlmm")
+                || s.contains("pancakeswap") => PoolClass::Clmm,
+            s if s.contains("solfi") || s.contains("goonfi") || s.contains("humidifi")
+                || s.contains("lifinity") => PoolClass::VaultDependent,
+            _ => PoolClass::Amm,
+        }
+    }
+}
+
+/// 新鲜度预算
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StalenessBudget {
+    pub max_age_ms: u64,
+    /// 与全局最新 slot 的最大差距
+    pub max_slot_spread: u64,
+}
+
+impl StalenessBudget {
+    /// 按时间预算折算 slot 预算（不低于 10 slot）
+    pub fn from_max_age_ms(max_age_ms: u64) -> Self {
+        Self {
+            max_age_ms,
+            max_slot_spread: (max_age_ms / SLOT_MS).max(10),
+        }
+    }
+}
+
+/// 池子被判定过期的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaleReason {
+    /// 超过时间预算
+    Time,
+    /// 落后最新 slot 太多
+    Slot,
+}
+
+/// 新鲜度策略：类型默认值 + 池子级覆盖
+#[derive(Debug)]
+pub struct StalenessPolicy {
+    amm: StalenessBudget,
+    clmm: StalenessBudget,
+    clob: StalenessBudget,
+    vault_dependent: StalenessBudget,
+    /// pool_id -> max_age_ms
+    pool_overrides: DashMap<String, u64>,
+}
+
+impl Default for StalenessPolicy {
+    fn default() -> Self {
+        Self {
+            amm: StalenessBudget { max_age_ms: 2_000, max_slot_spread: 10 },
+            clmm: StalenessBudget { max_age_ms: 2_000, max_slot_spread: 10 },
+            clob: StalenessBudget::from_max_age_ms(10_000),
+            vault_dependent: StalenessBudget::from_max_age_ms(5_000),
+            pool_overrides: DashMap::new(),
+        }
+    }
+}
+
+impl StalenessPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从池子配置加载 max_age_ms 覆盖，返回加载数量
+    pub fn load_from_pools(&self, pools: &[PoolConfig]) -> usize {
+        let mut loaded = 0;
+        for pool in pools {
+            if let Some(max_age_ms) = pool.max_age_ms {
+                self.pool_overrides.insert(pool.address.clone(), max_age_ms);
+                loaded += 1;
+            }
+        }
+        loaded
+    }
+
+    /// 按新的池子配置重新登记覆盖（热重载时调用）
+    pub fn reload_pool(&self, pool: &PoolConfig) {
+        match pool.max_age_ms {
+            Some(max_age_ms) => {
+                self.pool_overrides.insert(pool.address.clone(), max_age_ms);
+            }
+            None => {
+                self.pool_overrides.remove(&pool.address);
+            }
+        }
+    }
+
+    /// 清除池子覆盖（池子被删除时调用）
+    pub fn clear_pool(&self, pool_id: &str) {
+        self.pool_overrides.remove(pool_id);
+    }
+
+    pub fn class_budget(&self, class: PoolClass) -> StalenessBudget {
+        match class {
+            PoolClass::Amm => self.amm,
+            PoolClass::Clmm => self.clmm,
+            PoolClass::Clob => self.clob,
+            PoolClass::VaultDependent => self.vault_dependent,
+        }
+    }
+
+    /// 池子的预算：池子覆盖 > 类型默认值
+    pub fn budget(&self, pool_id: &str, dex_name: &str) -> StalenessBudget {
+        let class_budget = self.class_budget(PoolClass::from_dex_name(dex_name));
+        match self.pool_overrides.get(pool_id) {
+            Some(max_age_ms) => {
+                let budget = StalenessBudget::from_max_age_ms(*max_age_ms);
+                StalenessBudget {
+                    max_age_ms: budget.max_age_ms,
+                    max_slot_spread: budget.max_slot_spread.max(class_budget.max_slot_spread),
+                }
+            }
+            None => class_budget,
+        }
+    }
+
+    /// 判定池子是否过期，新鲜时返回 None
+    pub fn check(
+        &self,
+        pool_id: &str,
+        dex_name: &str,
+        age_ms: u64,
+        slot_diff: u64,
+    ) -> Option<StaleReason> {
+        let budget = self.budget(pool_id, dex_name);
+        if age_ms > budget.max_age_ms {
+            Some(StaleReason::Time)
+        } else if slot_diff > budget.max_slot_spread {
+            Some(StaleReason::Slot)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_classes() {
+        assert_eq!(PoolClass::from_dex_name("Phoenix (CLOB)"), PoolClass::Clob);
+        assert_eq!(PoolClass::from_dex_name("OpenBook V2 (CLOB)"), PoolClass::Clob);
+        assert_eq!(PoolClass::from_dex_name("Raydium CLMM"), PoolClass::Clmm);
+        assert_eq!(PoolClass::from_dex_name("Whirlpool (Orca)"), PoolClass::Clmm);
+        assert_eq!(PoolClass::from_dex_name("SolFi V2"), PoolClass::VaultDependent);
+        assert_eq!(PoolClass::from_dex_name("Raydium AMM V4"), PoolClass::Amm);
+    }
+
+    #[test]
+    fn test_pool_override() {
+        let policy = StalenessPolicy::new();
+        let pool = PoolConfig {
+            address: "slow-pool".to_string(),
+            name: "SOL/USDC (Raydium)".to_string(),
+            pair: "SOL/USDC".to_string(),
+            pool_type: "amm_v4".to_string(),
+            fee_bps: None,
+            max_age_ms: Some(8_000),
+            base_mint: None,
+            quote_mint: None,
+            max_price_jump_percent: None,
+            commitment: None,
+            encoding: None,
+            priority: None,
+        };
+
+        assert_eq!(policy.check("slow-pool", "Raydium AMM V4", 6_000, 0), Some(StaleReason::Time));
+        assert_eq!(policy.load_from_pools(&[pool.clone()]), 1);
+        assert_eq!(policy.check("slow-pool", "Raydium AMM V4", 6_000, 0), None);
+        assert_eq!(policy.check("slow-pool", "Raydium AMM V4", 6_000, 25), Some(StaleReason::Slot));
+
+        policy.reload_pool(&PoolConfig { max_age_ms: None, ..pool });
+        assert_eq!(policy.check("slow-pool", "Raydium AMM V4", 6_000, 0), Some(StaleReason::Time));
+    }
+}
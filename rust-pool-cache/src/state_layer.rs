@@ -10,10 +10,258 @@
 ///
 /// ================================================================
 
-use crate::price_cache::{PriceUpdateEvent, PoolPrice};
+use crate::price_cache::{PairIndex, PriceUpdateEvent, PoolPrice};
 use anyhow::Result;
+use dashmap::DashMap;
+use serde::Serialize;
+use std::fmt;
+use std::time::{Duration, Instant};
 use tokio::sync::broadcast;
 
+/// 池子被排除出一致性快照的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum ExclusionReason {
+    /// 超过时间预算（快照恢复、尚未收到实时更新的池子也归入此类）
+    StaleByTime { age_ms: u64 },
+    /// 落后最新 slot 太多
+    StaleBySlot { behind_by: u64 },
+    /// 价格为 0（未初始化或储备量为空）
+    ZeroPrice,
+    /// 🧯 被熔断隔离
+    Quarantined,
+    /// 🧭 偏离外部参考价格超过排除阈值
+    ReferenceDeviation,
+    /// 🚦 池子不是 Active（`ActivityStatus` 的标签：paused / expired / no_liquidity ...）
+    Inactive { status: &'static str },
+}
+
+/// 按原因汇总的排除数量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ExclusionCounts {
+    pub stale_by_time: usize,
+    pub stale_by_slot: usize,
+    pub zero_price: usize,
+    pub quarantined: usize,
+    pub reference_deviation: usize,
+    pub inactive: usize,
+}
+
+impl ExclusionCounts {
+    pub fn total(&self) -> usize {
+        self.stale_by_time + self.stale_by_slot + self.zero_price + self.quarantined + self.reference_deviation + self.inactive
+    }
+}
+
+impl fmt::Display for ExclusionCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} stale-by-time, {} stale-by-slot, {} zero-price, {} quarantined, {} reference-deviation, {} inactive",
+            self.stale_by_time, self.stale_by_slot, self.zero_price, self.quarantined, self.reference_deviation,
+            self.inactive,
+        )
+    }
+}
+
+/// 一致性快照及每个被排除池子的原因
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotResult {
+    pub included: Vec<PoolPrice>,
+    pub excluded: Vec<(String, ExclusionReason)>,
+}
+
+impl SnapshotResult {
+    pub fn counts(&self) -> ExclusionCounts {
+        let mut counts = ExclusionCounts::default();
+        for (_, reason) in &self.excluded {
+            match reason {
+                ExclusionReason::StaleByTime { .. } => counts.stale_by_time += 1,
+                ExclusionReason::StaleBySlot { .. } => counts.stale_by_slot += 1,
+                ExclusionReason::ZeroPrice => counts.zero_price += 1,
+                ExclusionReason::Quarantined => counts.quarantined += 1,
+                ExclusionReason::ReferenceDeviation => counts.reference_deviation += 1,
+                ExclusionReason::Inactive { .. } => counts.inactive += 1,
+            }
+        }
+        counts
+    }
+}
+
+/// 时间 + slot 双重过滤（`get_consistent_snapshot` 的共用逻辑）
+///
+/// 依次检查零价格、时间、slot；`latest_slot` 为 0（还没有任何数据）时返回空结果。
+pub fn filter_consistent(
+    prices: impl IntoIterator<Item = PoolPrice>,
+    latest_slot: u64,
+    max_age_ms: u64,
+    max_slot_spread: u64,
+) -> SnapshotResult {
+    let mut result = SnapshotResult::default();
+    if latest_slot == 0 {
+        return result;
+    }
+
+    let now = Instant::now();
+    for price in prices {
+        match exclusion_reason(&price, now, latest_slot, max_age_ms, max_slot_spread) {
+            None => result.included.push(price),
+            Some(reason) => result.excluded.push((price.pool_id, reason)),
+        }
+    }
+    result
+}
+
+/// 单个池子的排除原因（`None` = 保留）
+pub fn exclusion_reason(
+    price: &PoolPrice,
+    now: Instant,
+    latest_slot: u64,
+    max_age_ms: u64,
+    max_slot_spread: u64,
+) -> Option<ExclusionReason> {
+    if price.price == 0.0 {
+        return Some(ExclusionReason::ZeroPrice);
+    }
+    let age_ms = now.duration_since(price.last_update).as_millis() as u64;
+    if age_ms > max_age_ms {
+        return Some(ExclusionReason::StaleByTime { age_ms });
+    }
+    let behind_by = latest_slot.saturating_sub(price.slot);
+    if behind_by > max_slot_spread {
+        return Some(ExclusionReason::StaleBySlot { behind_by });
+    }
+    None
+}
+
+/// `snapshot_into` 的过滤条件
+///
+/// 所有条件同时满足才保留，`SnapshotFilter::default()` 不做任何过滤。
+///
+/// ```ignore
+/// let filter = SnapshotFilter::new().pair("SOL/USDC").changed_since(last_slot);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SnapshotFilter {
+    /// 只保留该交易对（按 `PoolPrice.pair` 精确匹配，走交易对索引）
+    pub pair: Option<String>,
+    /// 只保留该 DEX（按 `PoolPrice.dex_name` 精确匹配）
+    pub dex: Option<String>,
+    /// 最低美元流动性；没有流动性估计的池子不满足该条件
+    pub min_liquidity_usd: Option<f64>,
+    /// 最大数据年龄（按 `last_update` 计算）
+    pub max_age: Option<Duration>,
+    /// 增量拉取：只保留 slot 严格大于该值的池子
+    pub changed_since: Option<u64>,
+}
+
+impl SnapshotFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pair(mut self, pair: impl Into<String>) -> Self {
+        self.pair = Some(pair.into());
+        self
+    }
+
+    pub fn dex(mut self, dex: impl Into<String>) -> Self {
+        self.dex = Some(dex.into());
+        self
+    }
+
+    pub fn min_liquidity_usd(mut self, usd: f64) -> Self {
+        self.min_liquidity_usd = Some(usd);
+        self
+    }
+
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn changed_since(mut self, slot: u64) -> Self {
+        self.changed_since = Some(slot);
+        self
+    }
+
+    /// 池子是否满足全部条件（`now` 由调用方在一次快照内固定）
+    ///
+    /// 先比较 slot 等廉价字段，增量拉取时大多数池子在这里就被跳过。
+    pub fn matches(&self, price: &PoolPrice, now: Instant) -> bool {
+        if self.changed_since.is_some_and(|slot| price.slot <= slot) {
+            return false;
+        }
+        if self.pair.as_deref().is_some_and(|pair| price.pair != pair) {
+            return false;
+        }
+        if self.dex.as_deref().is_some_and(|dex| price.dex_name != dex) {
+            return false;
+        }
+        if let Some(min_usd) = self.min_liquidity_usd {
+            if price.liquidity_usd.is_none_or(|liquidity| liquidity.usd < min_usd) {
+                return false;
+            }
+        }
+        if let Some(max_age) = self.max_age {
+            if now.saturating_duration_since(price.last_update) > max_age {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// 增量拉取的下一个 `changed_since`：本次保留的池子中最大的 slot（没有保留任何池子时沿用原值）
+fn next_watermark(buf: &[PoolPrice], filter: &SnapshotFilter) -> u64 {
+    let floor = filter.changed_since.unwrap_or(0);
+    buf.iter().map(|price| price.slot).fold(floor, u64::max)
+}
+
+/// 从已克隆的价格列表填充缓冲区（`StateLayer::snapshot_into` 的默认实现）
+pub fn fill_snapshot(
+    prices: impl IntoIterator<Item = PoolPrice>,
+    buf: &mut Vec<PoolPrice>,
+    filter: &SnapshotFilter,
+) -> u64 {
+    buf.clear();
+    let now = Instant::now();
+    buf.extend(prices.into_iter().filter(|price| filter.matches(price, now)));
+    next_watermark(buf, filter)
+}
+
+/// 直接在 DashMap 主表上填充缓冲区：只克隆满足条件的条目
+///
+/// 指定交易对时只访问索引中该交易对的池子，否则遍历全表。
+pub fn fill_snapshot_from_map(
+    prices: &DashMap<String, PoolPrice>,
+    pair_index: &PairIndex,
+    buf: &mut Vec<PoolPrice>,
+    filter: &SnapshotFilter,
+) -> u64 {
+    buf.clear();
+    let now = Instant::now();
+    match filter.pair.as_deref() {
+        Some(pair) => {
+            for pool_id in pair_index.pool_ids(pair) {
+                if let Some(entry) = prices.get(&pool_id) {
+                    if filter.matches(&entry, now) {
+                        buf.push(entry.clone());
+                    }
+                }
+            }
+        }
+        None => {
+            for entry in prices.iter() {
+                if filter.matches(&entry, now) {
+                    buf.push(entry.clone());
+                }
+            }
+        }
+    }
+    next_watermark(buf, filter)
+}
+
 /// 通用状态层接口
 ///
 /// 所有状态层实现都必须实现这个trait
@@ -42,6 +290,12 @@ pub trait StateLayer: Send + Sync {
     /// 该交易对的所有池子价格列表
     fn get_pools_by_pair(&self, pair: &str) -> Vec<PoolPrice>;
 
+    /// 获取所有交易对（不克隆 PoolPrice）
+    ///
+    /// # 返回
+    /// 当前缓存中出现过的交易对列表
+    fn get_pairs(&self) -> Vec<String>;
+
     /// 订阅价格更新事件
     ///
     /// # 返回
@@ -84,6 +338,35 @@ pub trait StateLayer: Send + Sync {
     /// 同时满足时间新鲜度和slot一致性的数据
     fn get_consistent_snapshot(&self, max_age_ms: u64, max_slot_spread: u64) -> Vec<PoolPrice>;
 
+    /// 与 `get_consistent_snapshot` 相同的过滤，同时返回每个被排除池子的原因
+    ///
+    /// 默认实现对 `get_all_prices` 做时间 + slot 过滤；有额外排除规则的实现
+    /// （如熔断隔离）应当覆盖。
+    fn get_consistent_snapshot_detailed(&self, max_age_ms: u64, max_slot_spread: u64) -> SnapshotResult {
+        filter_consistent(self.get_all_prices(), self.get_latest_slot(), max_age_ms, max_slot_spread)
+    }
+
+    /// 同步拉取：把满足 `filter` 的池子写入调用方的缓冲区（先清空，保留容量）
+    ///
+    /// 适合嵌入方按需轮询而不订阅事件。返回本次保留的池子中最大的 slot，
+    /// 作为下一次 `changed_since` 传回即可增量拉取。
+    ///
+    /// # 一致性
+    /// - 单个条目是原子的：每个 `PoolPrice` 都是某一次完整写入的副本
+    /// - 条目之间没有快照保证：遍历期间的并发写入可能被看到也可能看不到，
+    ///   结果可能混合不同 slot 的数据；需要 slot 对齐时用 `get_consistent_snapshot`
+    /// - `changed_since` 按 slot 严格大于比较：与水位同一 slot、在拉取之后才写入的池子
+    ///   不会出现在下一次增量结果中（可传 `watermark - 1` 换取少量重复）
+    ///
+    /// 默认实现基于 `get_all_prices`，DashMap 实现会覆盖为只克隆满足条件的条目。
+    fn snapshot_into(&self, buf: &mut Vec<PoolPrice>, filter: &SnapshotFilter) -> u64 {
+        let prices = match filter.pair.as_deref() {
+            Some(pair) => self.get_pools_by_pair(pair),
+            None => self.get_all_prices(),
+        };
+        fill_snapshot(prices, buf, filter)
+    }
+
     /// 获取当前最新的slot号
     ///
     /// # 返回
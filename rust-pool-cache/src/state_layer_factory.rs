@@ -256,7 +256,7 @@ mod tests {
 
         let result = StateLayerFactory::create_from_config("invalid");
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("无效的状态层配置"));
+        assert!(result.err().unwrap().contains("无效的状态层配置"));
     }
 
     #[test]
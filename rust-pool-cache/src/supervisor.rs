@@ -0,0 +1,329 @@
+/*!
+ * 🛟 长时间运行任务的监督重启
+ *
+ * `spawn_supervised(name, supervisor, factory)` 用 factory 创建任务并等待它结束：
+ * - 正常返回：监督结束，返回任务的输出
+ * - panic：记录 panic 信息和重启次数，按退避等待后再调用 factory 重新创建任务
+ * - 一小时内重启超过 `max_restarts_per_hour`：放弃重启，该组件在 /health 报 down，返回 `T::default()`
+ *
+ * 任务的输入由 factory 在每次（重新）启动时提供：共享状态放在 Arc 里，独占的
+ * channel 接收端放在 `Arc<tokio::sync::Mutex<_>>` 里（panic 时锁随栈展开释放）。
+ * 中止监督任务的 JoinHandle 时，正在运行的任务一起中止。
+ */
+
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::task::{JoinError, JoinHandle};
+use tracing::{error, warn};
+
+use crate::config::SupervisorConfig;
+use crate::health::{ComponentHealth, HealthStatus};
+use crate::prometheus::{MetricKind, PrometheusWriter};
+use crate::reconnect_backoff::{BackoffPolicy, ReconnectBackoff};
+
+/// 重启次数的统计窗口
+const RESTART_WINDOW: Duration = Duration::from_secs(3600);
+
+/// 重启策略
+#[derive(Debug, Clone)]
+pub struct SupervisorPolicy {
+    /// 一小时内最多重启次数，超过后放弃
+    pub max_restarts_per_hour: u32,
+    /// 两次重启之间的退避（运行超过 stable_after 后退避重置）
+    pub backoff: BackoffPolicy,
+}
+
+impl SupervisorPolicy {
+    pub fn from_config(config: &SupervisorConfig) -> Self {
+        Self {
+            max_restarts_per_hour: config.max_restarts_per_hour,
+            backoff: BackoffPolicy {
+                initial: Duration::from_millis(config.restart_backoff_ms),
+                multiplier: 2.0,
+                max: Duration::from_millis(config.max_restart_backoff_ms.max(config.restart_backoff_ms)),
+                stable_after: Duration::from_secs(60),
+                alert_after_failures: config.max_restarts_per_hour,
+            },
+        }
+    }
+}
+
+impl Default for SupervisorPolicy {
+    fn default() -> Self {
+        Self::from_config(&SupervisorConfig::default())
+    }
+}
+
+/// 单个受监督任务的状态（/health、/metrics）
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskStatus {
+    pub name: &'static str,
+    /// 累计重启次数
+    pub restarts: u64,
+    /// 最近一小时内的重启次数
+    pub restarts_last_hour: usize,
+    pub last_panic: Option<String>,
+    /// 超过每小时重启上限，已放弃
+    pub gave_up: bool,
+}
+
+#[derive(Debug, Default)]
+struct TaskState {
+    restarts: u64,
+    recent: VecDeque<Instant>,
+    last_panic: Option<String>,
+    gave_up: bool,
+}
+
+impl TaskState {
+    fn prune(&mut self, now: Instant) {
+        while self.recent.front().is_some_and(|t| now.duration_since(*t) > RESTART_WINDOW) {
+            self.recent.pop_front();
+        }
+    }
+}
+
+/// 受监督任务的登记表（进程内共享）
+#[derive(Clone)]
+pub struct Supervisor {
+    policy: SupervisorPolicy,
+    tasks: Arc<Mutex<BTreeMap<&'static str, TaskState>>>,
+}
+
+impl Default for Supervisor {
+    fn default() -> Self {
+        Self::new(SupervisorPolicy::default())
+    }
+}
+
+impl Supervisor {
+    pub fn new(policy: SupervisorPolicy) -> Self {
+        Self {
+            policy,
+            tasks: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    pub fn policy(&self) -> &SupervisorPolicy {
+        &self.policy
+    }
+
+    fn register(&self, name: &'static str) {
+        self.tasks.lock().unwrap().entry(name).or_default();
+    }
+
+    /// 记录一次 panic，返回是否继续重启
+    pub fn record_panic(&self, name: &'static str, message: String, now: Instant) -> bool {
+        let mut tasks = self.tasks.lock().unwrap();
+        let state = tasks.entry(name).or_default();
+        state.restarts += 1;
+        state.recent.push_back(now);
+        state.prune(now);
+        state.last_panic = Some(message);
+        if state.recent.len() > self.policy.max_restarts_per_hour as usize {
+            state.gave_up = true;
+        }
+        !state.gave_up
+    }
+
+    pub fn statuses(&self, now: Instant) -> Vec<TaskStatus> {
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks
+            .iter_mut()
+            .map(|(name, state)| {
+                state.prune(now);
+                TaskStatus {
+                    name,
+                    restarts: state.restarts,
+                    restarts_last_hour: state.recent.len(),
+                    last_panic: state.last_panic.clone(),
+                    gave_up: state.gave_up,
+                }
+            })
+            .collect()
+    }
+
+    /// 最近一小时内重启过的任务 → degraded，放弃重启的任务 → down（运行正常的任务不列出）
+    pub fn health(&self, now: Instant) -> Vec<ComponentHealth> {
+        self.statuses(now)
+            .into_iter()
+            .filter(|task| task.gave_up || task.restarts_last_hour > 0)
+            .map(|task| {
+                let last_panic = task.last_panic.as_deref().unwrap_or("?");
+                if task.gave_up {
+                    ComponentHealth::new(
+                        task.name,
+                        HealthStatus::Down,
+                        None,
+                        format!("gave up after {} restarts, last panic: {}", task.restarts, last_panic),
+                    )
+                } else {
+                    ComponentHealth::new(
+                        task.name,
+                        HealthStatus::Degraded,
+                        None,
+                        format!("{} restarts in the last hour, last panic: {}", task.restarts_last_hour, last_panic),
+                    )
+                }
+            })
+            .collect()
+    }
+
+    pub fn write_prometheus(&self, writer: &mut PrometheusWriter) {
+        let statuses = self.statuses(Instant::now());
+        writer.family(
+            "pool_cache_task_restarts_total",
+            "Restarts of supervised tasks after a panic",
+            MetricKind::Counter,
+        );
+        for task in &statuses {
+            writer.sample("pool_cache_task_restarts_total", &[("task", task.name)], task.restarts as f64);
+        }
+        writer.family(
+            "pool_cache_task_gave_up",
+            "1 when a supervised task exceeded its restart limit and is no longer running",
+            MetricKind::Gauge,
+        );
+        for task in &statuses {
+            writer.sample("pool_cache_task_gave_up", &[("task", task.name)], if task.gave_up { 1.0 } else { 0.0 });
+        }
+    }
+}
+
+/// 中止监督任务时一起中止正在运行的任务
+struct AbortOnDrop<T>(JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// panic 信息（&str / String 之外的 payload 只记录类型未知）
+fn panic_message(error: JoinError) -> String {
+    let payload = error.into_panic();
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "non-string panic payload".to_string())
+}
+
+/// 🛟 启动受监督的任务：panic 后按退避用 factory 重新创建，超过每小时重启上限后放弃
+pub fn spawn_supervised<T, F, Fut>(name: &'static str, supervisor: &Supervisor, mut factory: F) -> JoinHandle<T>
+where
+    T: Default + Send + 'static,
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = T> + Send + 'static,
+{
+    let supervisor = supervisor.clone();
+    supervisor.register(name);
+    tokio::spawn(async move {
+        let mut backoff = ReconnectBackoff::new(supervisor.policy.backoff.clone());
+        loop {
+            let started = Instant::now();
+            let mut task = AbortOnDrop(tokio::spawn(factory()));
+            let error = match (&mut task.0).await {
+                Ok(output) => return output,
+                Err(e) if e.is_cancelled() => {
+                    warn!("🛟 Task '{}' was cancelled", name);
+                    return T::default();
+                }
+                Err(e) => e,
+            };
+
+            let message = panic_message(error);
+            error!("🛟 Task '{}' panicked: {}", name, message);
+            if !supervisor.record_panic(name, message, Instant::now()) {
+                error!(
+                    "🛟 Task '{}' exceeded {} restarts per hour, not restarting",
+                    name, supervisor.policy.max_restarts_per_hour
+                );
+                return T::default();
+            }
+
+            backoff.record_disconnect(started.elapsed());
+            let delay = backoff.next_delay();
+            warn!("🛟 Restarting task '{}' in {}ms", name, delay.as_millis());
+            tokio::time::sleep(delay).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast_supervisor(max_restarts_per_hour: u32) -> Supervisor {
+        Supervisor::new(SupervisorPolicy::from_config(&SupervisorConfig {
+            max_restarts_per_hour,
+            restart_backoff_ms: 1,
+            max_restart_backoff_ms: 1,
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_panicking_task_is_restarted_with_same_inputs() {
+        let supervisor = fast_supervisor(5);
+        let attempts = Arc::new(AtomicU32::new(0));
+        let attempts_task = attempts.clone();
+
+        // 前两次 panic（模拟反序列化越界），第三次正常返回
+        let handle = spawn_supervised("refresher", &supervisor, move || {
+            let attempts = attempts_task.clone();
+            async move {
+                let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+                let data = [0u8; 4];
+                if attempt < 2 {
+                    let index = 4 + attempt as usize;
+                    let _ = data[index];
+                }
+                attempt
+            }
+        });
+
+        assert_eq!(handle.await.unwrap(), 2);
+        let status = &supervisor.statuses(Instant::now())[0];
+        assert_eq!((status.name, status.restarts, status.gave_up), ("refresher", 2, false));
+        assert!(status.last_panic.as_deref().unwrap().contains("index out of bounds"));
+
+        let health = supervisor.health(Instant::now());
+        assert_eq!(health.len(), 1);
+        assert_eq!(health[0].status, HealthStatus::Degraded);
+        // 一小时后不再计入
+        assert!(supervisor.health(Instant::now() + RESTART_WINDOW + Duration::from_secs(1)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_restarts_per_hour() {
+        let supervisor = fast_supervisor(2);
+        let attempts = Arc::new(AtomicU32::new(0));
+        let attempts_task = attempts.clone();
+
+        let handle: JoinHandle<()> = spawn_supervised("calculator", &supervisor, move || {
+            let attempts = attempts_task.clone();
+            async move {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                panic!("boom");
+            }
+        });
+
+        // 放弃时返回 T::default()
+        handle.await.unwrap();
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        let health = supervisor.health(Instant::now());
+        assert_eq!(health[0].status, HealthStatus::Down);
+        assert!(health[0].detail.contains("boom"));
+
+        let mut writer = PrometheusWriter::new();
+        supervisor.write_prometheus(&mut writer);
+        let text = writer.finish();
+        assert!(text.contains("pool_cache_task_restarts_total{task=\"calculator\"} 3"), "{}", text);
+        assert!(text.contains("pool_cache_task_gave_up{task=\"calculator\"} 1"), "{}", text);
+    }
+}
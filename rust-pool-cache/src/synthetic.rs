@@ -0,0 +1,315 @@
+/*!
+ * 合成池子（What-if 扫描）
+ *
+ * 研究问题："如果某个 DEX 上线了 X/Y 交易对并提供 $Z 流动性，
+ * 某条 4 跳路径会不会变得可行？"
+ *
+ * - `[[synthetic_pools]]` 配置的池子只注入独立的叠加层，不写入生产价格缓存
+ * - 价格可锚定某个实时交易对（中位数价格 × (1 + 偏移)），随市场移动
+ * - What-if 扫描 = 实时池子 + 叠加层，结果只保留经过合成池子的路径，
+ *   仅通过日志和 GET /whatif/opportunities 输出，绝不进入告警或生产机会流
+ */
+
+use crate::price_cache::{PoolPrice, PriceCache};
+use crate::execution_cost::ExecutionCostModel;
+use crate::router_advanced::{AdvancedRouter, AdvancedRouterConfig};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// 合成池子 pool_id 前缀
+pub const SYNTHETIC_PREFIX: &str = "synthetic:";
+
+/// 合成池子储备量精度（UI单位 -> u64）
+const SYNTHETIC_DECIMALS: u8 = 6;
+
+/// 合成池子定义
+#[derive(Debug, Clone)]
+pub struct SyntheticPoolSpec {
+    pub name: String,
+    /// 交易对，如 "SOL/USDT"
+    pub pair: String,
+    /// DEX 标签（手续费按现有扫描器的 DEX 名称规则推断）
+    pub dex: String,
+    /// 基础代币储备（UI单位）
+    pub base_reserve: f64,
+    /// 报价代币储备（UI单位）
+    pub quote_reserve: f64,
+    /// 价格锚定的实时交易对（为空则使用储备比例定价）
+    pub anchor_pair: Option<String>,
+    /// 相对锚定价格的偏移（百分比）
+    pub price_offset_percent: f64,
+}
+
+impl SyntheticPoolSpec {
+    pub fn pool_id(&self) -> String {
+        format!("{}{}", SYNTHETIC_PREFIX, self.name)
+    }
+
+    /// 锚定价格：锚定交易对所有实时池子的中位数价格
+    fn anchor_price(&self, live: &[PoolPrice]) -> Option<f64> {
+        let anchor_pair = self.anchor_pair.as_ref()?;
+        let mut prices: Vec<f64> = live.iter()
+            .filter(|p| &p.pair == anchor_pair && p.price.is_finite() && p.price > 0.0)
+            .map(|p| p.price)
+            .collect();
+        if prices.is_empty() {
+            return None;
+        }
+        prices.sort_by(|a, b| a.total_cmp(b));
+        Some(prices[prices.len() / 2])
+    }
+
+    /// 按当前实时数据生成池子快照
+    ///
+    /// 锚定时保持报价侧流动性不变，按锚定价格重新计算基础侧储备
+    pub fn materialize(&self, live: &[PoolPrice], slot: u64) -> PoolPrice {
+        let (base_reserve, quote_reserve, price) = match self.anchor_price(live) {
+            Some(anchor) => {
+                let price = anchor * (1.0 + self.price_offset_percent / 100.0);
+                (self.quote_reserve / price, self.quote_reserve, price)
+            }
+            None => {
+                let price = if self.base_reserve > 0.0 { self.quote_reserve / self.base_reserve } else { 0.0 };
+                (self.base_reserve, self.quote_reserve, price)
+            }
+        };
+
+        let scale = 10f64.powi(SYNTHETIC_DECIMALS as i32);
+        PoolPrice {
+            pool_id: self.pool_id(),
+            dex_name: format!("Synthetic {}", self.dex),
+            pair: self.pair.clone(),
+            base_reserve: (base_reserve * scale) as u64,
+            quote_reserve: (quote_reserve * scale) as u64,
+            base_decimals: SYNTHETIC_DECIMALS,
+            quote_decimals: SYNTHETIC_DECIMALS,
+            price,
+            last_update: Instant::now(),
+            slot,
+            liquidity_usd: None,
+            commitment: None,
+        }
+    }
+}
+
+/// What-if 机会（始终标记为 synthetic）
+#[derive(Debug, Clone, Serialize)]
+pub struct WhatIfOpportunity {
+    pub label: &'static str,
+    pub signature: String,
+    pub hops: usize,
+    pub roi_percent: f64,
+    pub net_profit: f64,
+    /// 路径中使用的合成池子
+    pub synthetic_pools: Vec<String>,
+}
+
+/// 合成池子当前状态
+#[derive(Debug, Clone, Serialize)]
+pub struct SyntheticPoolView {
+    pub pool_id: String,
+    pub pair: String,
+    pub dex: String,
+    pub price: f64,
+    pub anchored: bool,
+}
+
+/// 最近一次 what-if 扫描报告
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WhatIfReport {
+    pub scanned_at: Option<String>,
+    pub scans: u64,
+    pub synthetic_pools: Vec<SyntheticPoolView>,
+    pub opportunities: Vec<WhatIfOpportunity>,
+}
+
+/// What-if 扫描器：实时池子 + 合成叠加层
+pub struct WhatIfScanner {
+    live: Arc<PriceCache>,
+    specs: Vec<SyntheticPoolSpec>,
+    router_config: AdvancedRouterConfig,
+    report: Arc<Mutex<WhatIfReport>>,
+    execution_cost: Arc<ExecutionCostModel>,
+}
+
+impl WhatIfScanner {
+    pub fn new(live: Arc<PriceCache>, specs: Vec<SyntheticPoolSpec>, router_config: AdvancedRouterConfig) -> Self {
+        Self {
+            live,
+            specs,
+            router_config,
+            report: Arc::new(Mutex::new(WhatIfReport::default())),
+            execution_cost: ExecutionCostModel::shared_default(),
+        }
+    }
+
+    /// ⛽ 与生产路由器共用执行成本模型
+    pub fn with_execution_cost(mut self, execution_cost: Arc<ExecutionCostModel>) -> Self {
+        self.execution_cost = execution_cost;
+        self
+    }
+
+    /// 共享报告（用于 API）
+    pub fn report(&self) -> Arc<Mutex<WhatIfReport>> {
+        self.report.clone()
+    }
+
+    /// 构建叠加层：实时数据的副本 + 合成池子（生产缓存不受影响）
+    fn build_overlay(&self) -> (Arc<PriceCache>, Vec<SyntheticPoolView>) {
+        let live = self.live.get_all_prices();
+        let slot = self.live.get_latest_slot();
+        let overlay = Arc::new(PriceCache::new());
+
+        for pool in &live {
+            overlay.update_price(pool.clone());
+        }
+
+        let views = self.specs.iter()
+            .map(|spec| {
+                let pool = spec.materialize(&live, slot);
+                let view = SyntheticPoolView {
+                    pool_id: pool.pool_id.clone(),
+                    pair: pool.pair.clone(),
+                    dex: spec.dex.clone(),
+                    price: pool.price,
+                    anchored: spec.anchor_price(&live).is_some(),
+                };
+                overlay.update_price(pool);
+                view
+            })
+            .collect();
+
+        (overlay, views)
+    }
+
+    /// 运行一次 what-if 扫描，只保留经过合成池子的路径
+    pub async fn scan(&self, amount: f64) -> Vec<WhatIfOpportunity> {
+        let (overlay, views) = self.build_overlay();
+        let router = AdvancedRouter::new(overlay, self.router_config.clone())
+            .with_execution_cost(self.execution_cost.clone());
+        let paths = router.find_optimal_routes(amount).await;
+
+        let opportunities: Vec<WhatIfOpportunity> = paths.iter()
+            .filter_map(|p| {
+                let synthetic_pools: Vec<String> = p.base_path.steps.iter()
+                    .filter(|s| s.pool_id.starts_with(SYNTHETIC_PREFIX))
+                    .map(|s| s.pool_id.clone())
+                    .collect();
+                if synthetic_pools.is_empty() {
+                    return None;
+                }
+                Some(WhatIfOpportunity {
+                    label: "synthetic",
+                    signature: p.base_path.signature().to_string(),
+                    hops: p.base_path.steps.len(),
+                    roi_percent: p.optimized_roi,
+                    net_profit: p.optimized_net_profit,
+                    synthetic_pools,
+                })
+            })
+            .collect();
+
+        let mut report = self.report.lock().unwrap();
+        report.scanned_at = Some(chrono::Utc::now().to_rfc3339());
+        report.scans += 1;
+        report.synthetic_pools = views;
+        report.opportunities = opportunities.clone();
+
+        opportunities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router_advanced::RouterMode;
+
+    fn live_pool(pool_id: &str, pair: &str, base: f64, quote: f64) -> PoolPrice {
+        let scale = 1_000_000.0;
+        PoolPrice {
+            base_reserve: (base * scale) as u64,
+            quote_reserve: (quote * scale) as u64,
+            base_decimals: 6,
+            slot: 1000,
+            ..PoolPrice::for_test(pool_id, pair, quote / base)
+        }
+    }
+
+    /// 断开的三角：SOL/USDC 与 USDT/USDC 存在，缺少 SOL/USDT
+    fn broken_triangle() -> Arc<PriceCache> {
+        let cache = Arc::new(PriceCache::new());
+        cache.update_price(live_pool("sol_usdc", "SOL/USDC", 100_000.0, 18_000_000.0));
+        cache.update_price(live_pool("usdt_usdc", "USDT/USDC", 20_000_000.0, 20_000_000.0));
+        cache
+    }
+
+    fn bridge_spec() -> SyntheticPoolSpec {
+        SyntheticPoolSpec {
+            name: "sol_usdt_bridge".to_string(),
+            pair: "SOL/USDT".to_string(),
+            dex: "Raydium AMM V4".to_string(),
+            base_reserve: 0.0,
+            quote_reserve: 18_000_000.0,
+            anchor_pair: Some("SOL/USDC".to_string()),
+            price_offset_percent: 3.0,
+        }
+    }
+
+    fn router_config() -> AdvancedRouterConfig {
+        AdvancedRouterConfig {
+            mode: RouterMode::Complete,
+            min_roi_percent: 0.3,
+            enable_split_optimization: false,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_synthetic_price_tracks_anchor() {
+        let live = vec![
+            live_pool("a", "SOL/USDC", 100_000.0, 18_000_000.0),
+            live_pool("b", "SOL/USDC", 100_000.0, 18_200_000.0),
+            live_pool("c", "SOL/USDC", 100_000.0, 18_100_000.0),
+        ];
+        let pool = bridge_spec().materialize(&live, 42);
+
+        assert_eq!(pool.pool_id, "synthetic:sol_usdt_bridge");
+        assert!((pool.price - 181.0 * 1.03).abs() < 1e-6);
+        assert_eq!(pool.slot, 42);
+
+        // 无锚定数据时使用储备比例
+        let unanchored = SyntheticPoolSpec {
+            base_reserve: 1_000.0,
+            quote_reserve: 150_000.0,
+            ..bridge_spec()
+        };
+        assert!((unanchored.materialize(&[], 0).price - 150.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_whatif_scan_completes_broken_triangle() {
+        let live = broken_triangle();
+
+        // 生产扫描：三角不完整，没有机会
+        let production = AdvancedRouter::new(live.clone(), router_config());
+        assert!(production.find_optimal_routes(1000.0).await.is_empty());
+
+        // What-if 扫描：合成桥接池补全三角
+        let scanner = WhatIfScanner::new(live.clone(), vec![bridge_spec()], router_config());
+        let opportunities = scanner.scan(1000.0).await;
+
+        assert!(!opportunities.is_empty());
+        assert!(opportunities.iter().all(|o| o.label == "synthetic"));
+        assert!(opportunities.iter().all(|o| o.synthetic_pools.contains(&"synthetic:sol_usdt_bridge".to_string())));
+
+        // 生产缓存未被写入合成池子
+        assert!(live.get_price("synthetic:sol_usdt_bridge").is_none());
+
+        let report = scanner.report();
+        let report = report.lock().unwrap();
+        assert_eq!(report.scans, 1);
+        assert_eq!(report.opportunities.len(), opportunities.len());
+        assert!(report.synthetic_pools[0].anchored);
+    }
+}
@@ -0,0 +1,122 @@
+/*!
+ * 代币别名归一化
+ *
+ * 很多池子按 wrapped SOL 报价（pair 写作 "wSOL/USDT"），另一些配置成 "SOL"，
+ * 建图时被当成两个代币，跨越两者的循环完全看不到。这里把别名映射到规范符号，
+ * 建图（BFS / Bellman-Ford / 快速扫描）、router_direct 的交易对分组、start_tokens
+ * 匹配和机会输出都使用规范符号；`RouteStep` 另外保留池子上的原始代币
+ * （`raw_input_token` / `raw_output_token`），执行器据此判断是否需要 wrap / unwrap。
+ *
+ * 默认只有 wSOL → SOL。不同桥的 USDC 变体（USDCet、USDCpo 等）不是同一资产，
+ * 默认不合并，需要时在 `[router] token_aliases` 中显式配置。
+ *
+ * 扫描器（BFS / Bellman-Ford / `DirectArbTable`）各自持有一份 `Arc<TokenAliases>`
+ * （`with_token_aliases`），别名表本身构建后不可变。`global()` 只是启动时按配置
+ * `install` 一次的默认表，未安装时为默认别名。
+ */
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+static GLOBAL_ALIASES: OnceLock<Arc<TokenAliases>> = OnceLock::new();
+
+/// 进程默认别名表（扫描器未显式传入别名表时使用）
+pub fn global() -> &'static Arc<TokenAliases> {
+    GLOBAL_ALIASES.get_or_init(|| Arc::new(TokenAliases::new()))
+}
+
+/// 启动时按配置安装默认别名表（只能安装一次，之后不可修改），返回是否安装成功
+pub fn install(aliases: TokenAliases) -> bool {
+    GLOBAL_ALIASES.set(Arc::new(aliases)).is_ok()
+}
+
+/// 便捷函数：代币在默认别名表下的规范符号
+pub fn canonical(token: &str) -> Cow<'_, str> {
+    global().canonical(token)
+}
+
+/// 默认别名（`[router] token_aliases` 未配置时使用）
+pub fn default_aliases() -> HashMap<String, String> {
+    [("wSOL", "SOL"), ("WSOL", "SOL")]
+        .into_iter()
+        .map(|(alias, symbol)| (alias.to_string(), symbol.to_string()))
+        .collect()
+}
+
+/// 别名 -> 规范符号（构建后不可变）
+#[derive(Debug, Clone, Default)]
+pub struct TokenAliases {
+    aliases: HashMap<String, String>,
+}
+
+impl TokenAliases {
+    /// 带默认别名
+    pub fn new() -> Self {
+        Self::from_map(&default_aliases())
+    }
+
+    /// 不做任何归一化
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// 按配置构建（空表 = 关闭归一化）
+    ///
+    /// 只做一层映射；指向自身的条目忽略。
+    pub fn from_map(aliases: &HashMap<String, String>) -> Self {
+        let aliases = aliases.iter()
+            .filter(|(alias, symbol)| alias != symbol)
+            .map(|(alias, symbol)| (alias.clone(), symbol.clone()))
+            .collect();
+        Self { aliases }
+    }
+
+    /// 代币的规范符号（没有别名时原样借用，不分配）
+    pub fn canonical<'a>(&'a self, token: &'a str) -> Cow<'a, str> {
+        match self.aliases.get(token) {
+            Some(symbol) => Cow::Borrowed(symbol.as_str()),
+            None => Cow::Borrowed(token),
+        }
+    }
+
+    /// 同 `canonical`，接收已有的 String（没有别名时原样返回，不再复制）
+    pub fn canonicalize(&self, token: String) -> String {
+        match self.aliases.get(&token) {
+            Some(symbol) => symbol.clone(),
+            None => token,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.aliases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aliases_map_wsol_but_keep_bridged_usdc_distinct() {
+        let aliases = TokenAliases::new();
+        assert_eq!(aliases.canonical("wSOL"), "SOL");
+        assert_eq!(aliases.canonical("SOL"), "SOL");
+        assert_eq!(aliases.canonical("USDCet"), "USDCet");
+
+        let custom: HashMap<String, String> = [("USDCet".to_string(), "USDC".to_string()), ("X".to_string(), "X".to_string())]
+            .into_iter()
+            .collect();
+        let custom = TokenAliases::from_map(&custom);
+        assert_eq!(custom.len(), 1);
+        assert_eq!(custom.canonical("USDCet"), "USDC");
+        assert_eq!(custom.canonical("wSOL"), "wSOL");
+
+        assert!(TokenAliases::disabled().is_empty());
+        assert!(matches!(aliases.canonical("JUP"), Cow::Borrowed("JUP")));
+    }
+}
@@ -0,0 +1,413 @@
+/*!
+ * 代币图构建
+ *
+ * 路由器（Bellman-Ford）和 GET /graph 共用同一份建图逻辑：
+ * 节点 = 代币（配置了 base_mint / quote_mint 的池子按 mint 反查，否则 pair 按 '/' 拆分，
+ * 再按别名表 `TokenAliases` 归一到规范符号，例如 wSOL → SOL），
+ * 每个池子产生两条有向边
+ * quote → base（汇率 1/price）和 base → quote（汇率 price）。
+ * pair 格式不对、两侧归一后是同一代币、或价格为 0 / 非有限值的池子不进图，并记录排除原因；
+ * 设置了 [router] min_pool_liquidity_usd 时，美元流动性低于下限的池子同样排除（low_liquidity）。
+ *
+ * `TokenFilter` 是路由器的代币白名单/黑名单（[router] start_tokens /
+ * intermediate_whitelist / token_blacklist），BFS 和 Bellman-Ford 共用。
+ */
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use crate::liquidity;
+use crate::price_cache::PoolPrice;
+use crate::token_alias::{self, TokenAliases};
+
+/// 有向边（一个池子的一个交易方向）
+#[derive(Debug, Clone)]
+pub struct DirectedEdge {
+    pub from: String,
+    pub to: String,
+    /// 1 单位 from 换得的 to 数量（不含手续费）
+    pub rate: f64,
+    /// 所属池子在 `TokenGraph::pools` 中的下标
+    pub pool_index: usize,
+}
+
+/// 未进图的池子
+#[derive(Debug, Clone, Serialize)]
+pub struct ExcludedPool {
+    pub pool_id: String,
+    pub pair: String,
+    pub reason: String,
+}
+
+/// 代币图
+#[derive(Debug, Clone, Default)]
+pub struct TokenGraph {
+    /// 进图的池子（按 pool_id 排序）
+    pub pools: Vec<PoolPrice>,
+    /// 代币（排序）
+    pub tokens: Vec<String>,
+    /// 有向边（按 from, to, pool_id 排序）
+    pub edges: Vec<DirectedEdge>,
+    /// 每个进图池子的规范交易对 "BASE/QUOTE"（与 `pools` 下标一一对应，供逐跳报价使用）
+    pub pairs: Vec<String>,
+    pub excluded: Vec<ExcludedPool>,
+}
+
+/// 解析池子的 (base, quote) 规范代币（见 `pool_mints`，别名按默认别名表归一）
+pub fn pool_tokens(pool: &PoolPrice) -> Option<(String, String)> {
+    pool_tokens_in(token_alias::global(), pool)
+}
+
+/// 同 `pool_tokens`，别名按 `aliases` 归一（扫描器用自己持有的别名表）
+pub fn pool_tokens_in(aliases: &TokenAliases, pool: &PoolPrice) -> Option<(String, String)> {
+    let (base, quote) = raw_pool_tokens(pool)?;
+    Some((aliases.canonicalize(base), aliases.canonicalize(quote)))
+}
+
+/// 池子上的原始 (base, quote) 代币（不做别名归一，例如 "wSOL"）
+pub fn raw_pool_tokens(pool: &PoolPrice) -> Option<(String, String)> {
+    crate::pool_mints::pool_tokens(pool)
+}
+
+/// 池子上对应规范代币 `token` 的原始代币，仅在与规范符号不同时返回
+///
+/// 执行器据此判断这一跳是否需要 wrap / unwrap（例如池子报价用 wSOL，路径上记为 SOL）。
+pub fn raw_token(pool: &PoolPrice, token: &str) -> Option<String> {
+    raw_token_in(token_alias::global(), pool, token)
+}
+
+/// 同 `raw_token`，别名按 `aliases` 归一
+pub fn raw_token_in(aliases: &TokenAliases, pool: &PoolPrice, token: &str) -> Option<String> {
+    let (base, quote) = raw_pool_tokens(pool)?;
+    [base, quote].into_iter().find(|raw| raw != token && aliases.canonical(raw) == token)
+}
+
+/// 交易对分组键："BASE/QUOTE"（规范符号），pair 无法解析时用原始 pair
+///
+/// "wSOL/USDC" 与 "SOL/USDC" 的池子落在同一组。
+pub fn pair_key(pool: &PoolPrice) -> String {
+    pair_key_in(token_alias::global(), pool)
+}
+
+/// 同 `pair_key`，别名按 `aliases` 归一
+pub fn pair_key_in(aliases: &TokenAliases, pool: &PoolPrice) -> String {
+    match pool_tokens_in(aliases, pool) {
+        Some((base, quote)) => format!("{}/{}", base, quote),
+        None => pool.pair.clone(),
+    }
+}
+
+/// 路由器代币过滤
+///
+/// 全部为空时不做任何过滤（与未配置时行为一致）。起点代币不受中间代币白名单约束，
+/// 但黑名单优先级最高。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TokenFilter {
+    /// 只保留以这些代币为起点的循环（空 = 所有代币）
+    pub start_tokens: Vec<String>,
+    /// 设置后每一跳的代币都必须在其中（起点代币除外）
+    pub intermediate_whitelist: Option<Vec<String>>,
+    /// 涉及这些代币的池子不参与路由
+    pub token_blacklist: Vec<String>,
+}
+
+impl TokenFilter {
+    pub fn is_empty(&self) -> bool {
+        self.start_tokens.is_empty()
+            && self.intermediate_whitelist.is_none()
+            && self.token_blacklist.is_empty()
+    }
+
+    /// 把配置里的代币统一成 `aliases` 下的规范符号（配置写 "wSOL" 也能匹配规范代币 SOL）
+    ///
+    /// 扫描器每次建图前调用一次，之后的匹配都是精确比较。
+    pub fn canonicalized(&self, aliases: &TokenAliases) -> TokenFilter {
+        let canonical = |tokens: &[String]| -> Vec<String> {
+            tokens.iter().map(|t| aliases.canonical(t).into_owned()).collect()
+        };
+        TokenFilter {
+            start_tokens: canonical(&self.start_tokens),
+            intermediate_whitelist: self.intermediate_whitelist.as_deref().map(canonical),
+            token_blacklist: canonical(&self.token_blacklist),
+        }
+    }
+
+    /// 是否可作为循环起点
+    pub fn is_start_token(&self, token: &str) -> bool {
+        self.start_tokens.is_empty() || contains_token(&self.start_tokens, token)
+    }
+
+    /// 代币能否出现在路径中
+    pub fn allows_token(&self, token: &str) -> bool {
+        if contains_token(&self.token_blacklist, token) {
+            return false;
+        }
+        match &self.intermediate_whitelist {
+            Some(whitelist) => contains_token(whitelist, token) || contains_token(&self.start_tokens, token),
+            None => true,
+        }
+    }
+
+    /// 池子两侧代币（按 `aliases` 归一）都允许时才保留（pair 格式不对的交给建图逻辑处理）
+    pub fn allows_pool(&self, aliases: &TokenAliases, pool: &PoolPrice) -> bool {
+        match pool_tokens_in(aliases, pool) {
+            Some((base, quote)) => self.allows_token(&base) && self.allows_token(&quote),
+            None => true,
+        }
+    }
+
+    /// 循环中第一个起点代币的位置（循环需从这里旋转开始）
+    pub fn anchor_index<S: AsRef<str>>(&self, tokens: impl IntoIterator<Item = S>) -> Option<usize> {
+        tokens.into_iter().position(|t| self.is_start_token(t.as_ref()))
+    }
+}
+
+/// 配置的代币列表是否包含 `token`（列表先经 `canonicalized` 归一）
+fn contains_token(tokens: &[String], token: &str) -> bool {
+    tokens.iter().any(|t| t == token)
+}
+
+impl TokenGraph {
+    pub fn build(pools: &[PoolPrice]) -> Self {
+        Self::build_with_min_liquidity(pools, 0.0)
+    }
+
+    /// 同 `build`，另外排除美元流动性低于 `min_liquidity_usd` 的池子（0 = 不过滤，流动性未知的保留）
+    pub fn build_with_min_liquidity(pools: &[PoolPrice], min_liquidity_usd: f64) -> Self {
+        Self::build_in(token_alias::global(), pools, min_liquidity_usd)
+    }
+
+    /// 同 `build_with_min_liquidity`，代币按 `aliases` 归一
+    pub fn build_in(aliases: &TokenAliases, pools: &[PoolPrice], min_liquidity_usd: f64) -> Self {
+        let mut sorted: Vec<&PoolPrice> = pools.iter().collect();
+        sorted.sort_by(|a, b| a.pool_id.cmp(&b.pool_id));
+
+        let mut graph = TokenGraph::default();
+        let mut token_set = BTreeSet::new();
+
+        for pool in sorted {
+            let (base, quote) = match pool_tokens_in(aliases, pool) {
+                Some(tokens) => tokens,
+                None => {
+                    graph.exclude(pool, "invalid_pair");
+                    continue;
+                }
+            };
+            if base == quote {
+                // 例如 "wSOL/SOL"：归一后两侧相同，不构成可交易的边
+                graph.exclude(pool, "same_token");
+                continue;
+            }
+            if !(pool.price.is_finite() && pool.price > 0.0) {
+                graph.exclude(pool, "zero_price");
+                continue;
+            }
+            if !liquidity::meets_floor(pool, min_liquidity_usd) {
+                graph.exclude(pool, "low_liquidity");
+                continue;
+            }
+
+            let pool_index = graph.pools.len();
+            token_set.insert(base.clone());
+            token_set.insert(quote.clone());
+            graph.pairs.push(format!("{}/{}", base, quote));
+
+            // quote → base（买入base）：1 quote = 1/price base
+            graph.edges.push(DirectedEdge {
+                from: quote.clone(),
+                to: base.clone(),
+                rate: 1.0 / pool.price,
+                pool_index,
+            });
+            // base → quote（卖出base）
+            graph.edges.push(DirectedEdge {
+                from: base,
+                to: quote,
+                rate: pool.price,
+                pool_index,
+            });
+            graph.pools.push(pool.clone());
+        }
+
+        // 🎯 确定性：代币和边按规范键排序
+        graph.tokens = token_set.into_iter().collect();
+        let pools = &graph.pools;
+        graph.edges.sort_by(|a, b| {
+            (&a.from, &a.to, &pools[a.pool_index].pool_id)
+                .cmp(&(&b.from, &b.to, &pools[b.pool_index].pool_id))
+        });
+        graph
+    }
+
+    fn exclude(&mut self, pool: &PoolPrice, reason: &str) {
+        self.excluded.push(ExcludedPool {
+            pool_id: pool.pool_id.clone(),
+            pair: pool.pair.clone(),
+            reason: reason.to_string(),
+        });
+    }
+
+    pub fn pool(&self, edge: &DirectedEdge) -> &PoolPrice {
+        &self.pools[edge.pool_index]
+    }
+
+    /// 无向邻接表
+    fn adjacency(&self) -> HashMap<&str, Vec<&str>> {
+        let mut adjacency: HashMap<&str, Vec<&str>> = HashMap::new();
+        for edge in &self.edges {
+            adjacency.entry(edge.from.as_str()).or_default().push(edge.to.as_str());
+        }
+        adjacency
+    }
+
+    /// 从 start 出发可达的代币（含 start 本身；start 不在图中时为空）
+    pub fn reachable_from(&self, start: &str) -> BTreeSet<String> {
+        let adjacency = self.adjacency();
+        let mut visited = BTreeSet::new();
+        if !self.tokens.iter().any(|t| t == start) {
+            return visited;
+        }
+
+        let mut queue = VecDeque::from([start]);
+        visited.insert(start.to_string());
+        while let Some(token) = queue.pop_front() {
+            for &next in adjacency.get(token).map(|v| v.as_slice()).unwrap_or(&[]) {
+                if visited.insert(next.to_string()) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        visited
+    }
+
+    /// 连通分量（每个分量内代币排序，分量按大小降序、首个代币升序）
+    pub fn connected_components(&self) -> Vec<Vec<String>> {
+        let mut seen: BTreeSet<String> = BTreeSet::new();
+        let mut components: Vec<Vec<String>> = Vec::new();
+        for token in &self.tokens {
+            if seen.contains(token) {
+                continue;
+            }
+            let component = self.reachable_from(token);
+            seen.extend(component.iter().cloned());
+            components.push(component.into_iter().collect());
+        }
+        components.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        components
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(pool_id: &str, pair: &str, price: f64) -> PoolPrice {
+        PoolPrice {
+            base_reserve: 1_000_000,
+            quote_reserve: 1_000_000,
+            base_decimals: 6,
+            ..PoolPrice::for_test(pool_id, pair, price)
+        }
+    }
+
+    #[test]
+    fn test_build_graph_and_components() {
+        let graph = TokenGraph::build(&[
+            pool("p3", "JUP/USDC", 0.8),
+            pool("p1", "SOL/USDC", 185.0),
+            pool("p2", "SOL/USDT", 185.1),
+            pool("p4", "BONK/WIF", 0.0001),
+            pool("p5", "RAY/USDC", 0.0),
+            pool("p6", "bad-pair", 1.0),
+        ]);
+
+        assert_eq!(graph.tokens, vec!["BONK", "JUP", "SOL", "USDC", "USDT", "WIF"]);
+        assert_eq!(graph.edges.len(), 8);
+        assert_eq!(graph.edges[0].from, "BONK");
+        let sol_usdc = graph.edges.iter().find(|e| e.from == "USDC" && e.to == "SOL").unwrap();
+        assert!((sol_usdc.rate - 1.0 / 185.0).abs() < 1e-12);
+        assert_eq!(graph.pool(sol_usdc).pool_id, "p1");
+
+        let reasons: Vec<(&str, &str)> = graph.excluded.iter()
+            .map(|e| (e.pool_id.as_str(), e.reason.as_str()))
+            .collect();
+        assert_eq!(reasons, vec![("p5", "zero_price"), ("p6", "invalid_pair")]);
+
+        let components = graph.connected_components();
+        assert_eq!(components, vec![
+            vec!["JUP".to_string(), "SOL".to_string(), "USDC".to_string(), "USDT".to_string()],
+            vec!["BONK".to_string(), "WIF".to_string()],
+        ]);
+        assert!(!graph.reachable_from("USDC").contains("WIF"));
+        assert!(graph.reachable_from("RAY").is_empty());
+    }
+
+    #[test]
+    fn test_token_filter() {
+        assert!(TokenFilter::default().is_empty());
+        let aliases = TokenAliases::new();
+        assert!(TokenFilter::default().allows_pool(&aliases, &pool("p1", "SCAM/SOL", 1.0)));
+
+        let filter = TokenFilter {
+            start_tokens: vec!["SOL".to_string(), "USDC".to_string()],
+            intermediate_whitelist: Some(vec!["USDT".to_string(), "JUP".to_string()]),
+            token_blacklist: vec!["JUP".to_string()],
+        };
+        assert!(filter.allows_pool(&aliases, &pool("p1", "SOL/USDC", 185.0)));
+        assert!(filter.allows_pool(&aliases, &pool("p2", "USDT/USDC", 1.0)));
+        assert!(!filter.allows_pool(&aliases, &pool("p3", "SCAM/SOL", 1.0)));
+        assert!(!filter.allows_pool(&aliases, &pool("p4", "JUP/USDC", 0.8)));
+
+        let cycle: Vec<String> = ["USDT", "USDC", "SOL", "USDT"].iter().map(|t| t.to_string()).collect();
+        assert_eq!(filter.anchor_index(&cycle), Some(1));
+        assert!(!filter.is_start_token("USDT"));
+    }
+
+    #[test]
+    fn test_wsol_alias_merges_nodes_and_keeps_raw_token() {
+        let wsol_usdt = pool("p2", "wSOL/USDT", 151.0);
+        let graph = TokenGraph::build(&[
+            pool("p1", "SOL/USDC", 150.0),
+            wsol_usdt.clone(),
+            pool("p3", "wSOL/SOL", 1.0),
+        ]);
+
+        assert_eq!(graph.tokens, vec!["SOL", "USDC", "USDT"]);
+        assert_eq!(graph.excluded.iter().map(|e| e.reason.as_str()).collect::<Vec<_>>(), vec!["same_token"]);
+        assert_eq!(pair_key(&wsol_usdt), "SOL/USDT");
+        assert_eq!(raw_token(&wsol_usdt, "SOL"), Some("wSOL".to_string()));
+        assert_eq!(raw_token(&wsol_usdt, "USDT"), None);
+
+        let filter = TokenFilter { start_tokens: vec!["wSOL".to_string()], ..Default::default() };
+        assert!(filter.canonicalized(&TokenAliases::new()).is_start_token("SOL"));
+        assert!(!filter.canonicalized(&TokenAliases::disabled()).is_start_token("SOL"));
+
+        // 显式传入的别名表与默认表互不影响
+        let disabled = TokenAliases::disabled();
+        let graph = TokenGraph::build_in(&disabled, &[pool("p1", "SOL/USDC", 150.0), wsol_usdt.clone()], 0.0);
+        assert_eq!(graph.tokens, vec!["SOL", "USDC", "USDT", "wSOL"]);
+        assert_eq!(graph.pairs, vec!["SOL/USDC", "wSOL/USDT"]);
+        assert_eq!(raw_token_in(&disabled, &wsol_usdt, "SOL"), None);
+    }
+
+    #[test]
+    fn test_reversed_pair_naming_builds_same_edges_with_mints() {
+        // 同一个 mSOL/SOL 池子（base_reserve 是 mSOL），pair 名称写反也按 mint 建图
+        crate::pool_mints::global().set_pool_mints(
+            "graph-msol-oriented",
+            "mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So",
+            crate::pool_mints::WSOL_MINT,
+        );
+        let edges = |pair: &str| -> Vec<(String, String, f64)> {
+            TokenGraph::build(&[pool("graph-msol-oriented", pair, 1.2)]).edges.iter()
+                .map(|e| (e.from.clone(), e.to.clone(), e.rate))
+                .collect()
+        };
+
+        let expected = edges("mSOL/SOL");
+        assert_eq!(expected[0], ("SOL".to_string(), "mSOL".to_string(), 1.0 / 1.2));
+        assert_eq!(edges("SOL/mSOL"), expected);
+        assert_eq!(edges("SOL-mSOL (Orca)"), expected);
+    }
+}
@@ -0,0 +1,484 @@
+/*!
+ * 交易构建器：ArbitragePath → 真实 swap 交易
+ *
+ * 目前支持 Raydium AMM V4（SwapBaseInV2，不需要 OpenBook 账户）和 Orca Whirlpool（swap v1）。
+ * 池子账户从链上重新读取（vault / tick array 等不在价格缓存里），第一跳按路径的
+ * expected_input 精确输入；每一跳的 min_out 是路由报价的 expected_output 扣除容差，
+ * 下一跳的输入就是上一跳的 min_out（链上一定拿得到），实际多出的部分留在中间代币
+ * 的 ATA 里，由模拟器按 mint 报告。最终编译成一笔 v0 版本化交易。
+ *
+ * 方向与路由器一致：输入代币等于缓存中 pair 的 base 时，从池子的第一个 mint 换出。
+ */
+
+use anyhow::{anyhow, Context};
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::message::{v0, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::deserializers::spl_token::{TokenProgram, SPL_TOKEN_PROGRAM_ID};
+use crate::deserializers::{RaydiumAmmInfo, WhirlpoolState};
+use crate::dex_interface::DexPool;
+use crate::mint_decimals_cache::get_global_mint_cache;
+use crate::price_cache::PriceCache;
+use crate::router::ArbitragePath;
+use crate::rpc_manager::RpcHandle;
+use crate::token_graph::pool_tokens;
+
+pub const RAYDIUM_AMM_V4_PROGRAM_ID: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+/// Raydium AMM V4 的池子权限 PDA（seed = "amm authority"）
+pub const RAYDIUM_AMM_AUTHORITY: &str = "5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1";
+pub const WHIRLPOOL_PROGRAM_ID: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+
+/// Raydium SwapBaseInV2 指令标签
+const RAYDIUM_SWAP_BASE_IN_V2: u8 = 16;
+/// Anchor 判别符 sha256("global:swap")[..8]
+const WHIRLPOOL_SWAP_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+/// Whirlpool 每个 tick array 包含的 tick 数
+const TICK_ARRAY_SIZE: i32 = 88;
+const MIN_SQRT_PRICE: u128 = 4_295_048_016;
+const MAX_SQRT_PRICE: u128 = 79_226_673_515_401_279_992_447_579_055;
+/// 多跳交易的计算单元上限（默认 200k 不够 3 跳 CLMM）
+const COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+/// 每跳最小输出相对报价的默认容差（基点）
+pub const DEFAULT_MIN_OUT_TOLERANCE_BPS: u16 = 50;
+
+/// 能构建 swap 指令的 DEX
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapVenue {
+    RaydiumAmmV4,
+    Whirlpool,
+}
+
+impl SwapVenue {
+    /// 按 dex_name 识别（兼容展示名和配置名），不支持的 DEX 返回 None
+    pub fn from_dex_name(dex_name: &str) -> Option<Self> {
+        let name = dex_name.to_lowercase();
+        if name.contains("whirlpool") {
+            Some(SwapVenue::Whirlpool)
+        } else if name.contains("raydium") && (name.contains("amm v4") || name.contains("amm_v4")) {
+            Some(SwapVenue::RaydiumAmmV4)
+        } else {
+            None
+        }
+    }
+}
+
+/// 从链上读取的池子状态
+enum PoolState {
+    Raydium(RaydiumAmmInfo),
+    Whirlpool(WhirlpoolState),
+}
+
+/// 无法为路径构建交易的原因
+#[derive(Debug)]
+pub enum BuildError {
+    /// 路径包含尚不支持的 DEX / 代币程序（不是错误，只是不能模拟）
+    Unsupported(String),
+    /// 账户读取或反序列化失败
+    Failed(anyhow::Error),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::Unsupported(reason) => write!(f, "unsupported: {}", reason),
+            BuildError::Failed(e) => write!(f, "build failed: {:#}", e),
+        }
+    }
+}
+
+impl From<anyhow::Error> for BuildError {
+    fn from(e: anyhow::Error) -> Self {
+        BuildError::Failed(e)
+    }
+}
+
+/// 交易中的一跳（原始单位）
+#[derive(Debug, Clone)]
+pub struct PlannedHop {
+    pub pool_id: String,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub amount_in: u64,
+    /// 报价输出扣除容差，也是下一跳的输入
+    pub min_amount_out: u64,
+}
+
+/// 构建好的交易（签名为占位符，只用于 sigVerify=false 的模拟）
+#[derive(Debug, Clone)]
+pub struct BuiltTransaction {
+    pub transaction: VersionedTransaction,
+    pub hops: Vec<PlannedHop>,
+    /// payer 在每个 mint 上的 ATA（模拟前后读取余额）
+    pub token_accounts: Vec<(Pubkey, Pubkey)>,
+    pub start_mint: Pubkey,
+}
+
+/// 交易构建器
+pub struct TransactionBuilder {
+    rpc: RpcHandle,
+    payer: Pubkey,
+    min_out_tolerance_bps: u16,
+}
+
+impl TransactionBuilder {
+    pub fn new(rpc: RpcHandle, payer: Pubkey) -> Self {
+        Self { rpc, payer, min_out_tolerance_bps: DEFAULT_MIN_OUT_TOLERANCE_BPS }
+    }
+
+    /// 每跳最小输出相对报价的容差（基点）
+    pub fn with_min_out_tolerance_bps(mut self, bps: u16) -> Self {
+        self.min_out_tolerance_bps = bps.min(10_000);
+        self
+    }
+
+    /// 为路径构建 v0 交易（阻塞 RPC：池子账户 + 最新 blockhash）
+    pub fn build(&self, path: &ArbitragePath, cache: &PriceCache) -> Result<BuiltTransaction, BuildError> {
+        // 先检查所有跳，避免为注定不能模拟的路径发 RPC
+        let mut venues = Vec::with_capacity(path.steps.len());
+        for step in &path.steps {
+            let venue = SwapVenue::from_dex_name(&step.dex_name).ok_or_else(|| {
+                BuildError::Unsupported(format!("{} ({}) has no swap builder", step.pool_id, step.dex_name))
+            })?;
+            venues.push(venue);
+        }
+
+        let pool_keys = path.steps.iter()
+            .map(|step| Pubkey::from_str(&step.pool_id).with_context(|| format!("invalid pool id {}", step.pool_id)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let accounts = self.rpc.call_blocking(|client| client.get_multiple_accounts(&pool_keys).map_err(Box::new))
+            .context("failed to fetch pool accounts")?;
+
+        let token_program = Pubkey::from_str(SPL_TOKEN_PROGRAM_ID).expect("valid token program id");
+        let mut instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNIT_LIMIT)];
+        let mut swaps = Vec::with_capacity(path.steps.len());
+        let mut hops: Vec<PlannedHop> = Vec::with_capacity(path.steps.len());
+        let mut mints: Vec<Pubkey> = Vec::new();
+
+        for (((step, venue), pool_key), account) in path.steps.iter().zip(&venues).zip(&pool_keys).zip(accounts) {
+            let account = account.ok_or_else(|| anyhow!("pool account {} not found", step.pool_id))?;
+            let cached = cache.get_price(&step.pool_id)
+                .ok_or_else(|| anyhow!("pool {} missing from price cache", step.pool_id))?;
+            let input_is_base = pool_tokens(&cached).is_some_and(|(base, _)| base == step.input_token);
+
+            let state = match venue {
+                SwapVenue::RaydiumAmmV4 => RaydiumAmmInfo::from_account_data(&account.data).map(PoolState::Raydium),
+                SwapVenue::Whirlpool => WhirlpoolState::from_account_data(&account.data).map(PoolState::Whirlpool),
+            }.map_err(|e| anyhow!("{}: {:?}", step.pool_id, e))?;
+            let pool: &dyn DexPool = match &state {
+                PoolState::Raydium(raydium) => raydium,
+                PoolState::Whirlpool(whirlpool) => whirlpool,
+            };
+            let (base_mint, quote_mint) = pool.get_mints()
+                .ok_or_else(|| anyhow!("{}: pool state has no mints", step.pool_id))?;
+            let (base_decimals, quote_decimals) = pool.get_decimals();
+            let (input_mint, output_mint, input_decimals, output_decimals) = if input_is_base {
+                (base_mint, quote_mint, base_decimals, quote_decimals)
+            } else {
+                (quote_mint, base_mint, quote_decimals, base_decimals)
+            };
+            ensure_spl_token(&input_mint)?;
+            ensure_spl_token(&output_mint)?;
+
+            // 第一跳按路径输入；之后每跳只花上一跳保证拿到的数量
+            let amount_in = match hops.last() {
+                None => to_raw(step.expected_input, input_decimals),
+                Some(prev) if prev.output_mint == input_mint => prev.min_amount_out,
+                Some(prev) => {
+                    return Err(anyhow!(
+                        "{}: input mint {} does not follow previous output {}",
+                        step.pool_id, input_mint, prev.output_mint
+                    ).into());
+                }
+            };
+            if amount_in == 0 {
+                return Err(anyhow!("{}: hop input rounds to zero", step.pool_id).into());
+            }
+            let min_amount_out = quoted_min_out(step.expected_output, output_decimals, self.min_out_tolerance_bps);
+            let user_source = associated_token_address(&self.payer, &input_mint, &token_program);
+            let user_destination = associated_token_address(&self.payer, &output_mint, &token_program);
+
+            let swap = match &state {
+                PoolState::Raydium(raydium) => {
+                    raydium_swap_instruction(pool_key, raydium, &user_source, &user_destination, &self.payer, amount_in, min_amount_out)
+                }
+                PoolState::Whirlpool(whirlpool) => {
+                    let (owner_a, owner_b) = if input_is_base {
+                        (user_source, user_destination)
+                    } else {
+                        (user_destination, user_source)
+                    };
+                    whirlpool_swap_instruction(pool_key, whirlpool, input_is_base, &owner_a, &owner_b, &self.payer, amount_in, min_amount_out)
+                }
+            };
+
+            for mint in [input_mint, output_mint] {
+                if !mints.contains(&mint) {
+                    mints.push(mint);
+                }
+            }
+            swaps.push(swap);
+            hops.push(PlannedHop {
+                pool_id: step.pool_id.clone(),
+                input_mint,
+                output_mint,
+                amount_in,
+                min_amount_out,
+            });
+        }
+
+        // payer 可能还没有中间代币的 ATA：幂等创建
+        let token_accounts: Vec<(Pubkey, Pubkey)> = mints.iter()
+            .map(|mint| (*mint, associated_token_address(&self.payer, mint, &token_program)))
+            .collect();
+        for (mint, _) in &token_accounts {
+            instructions.push(create_ata_idempotent_instruction(&self.payer, &self.payer, mint, &token_program));
+        }
+        instructions.extend(swaps);
+
+        let blockhash = self.rpc.call_blocking(|client| client.get_latest_blockhash().map_err(Box::new))
+            .context("failed to fetch latest blockhash")?;
+        let transaction = compile_transaction(&self.payer, &instructions, blockhash)?;
+        let start_mint = hops.first().map(|hop| hop.input_mint).unwrap_or_default();
+
+        Ok(BuiltTransaction {
+            transaction,
+            hops,
+            token_accounts,
+            start_mint,
+        })
+    }
+}
+
+/// UI 数量 → 原始单位（向下取整）
+fn to_raw(amount: f64, decimals: u8) -> u64 {
+    (amount * 10f64.powi(decimals as i32)).floor() as u64
+}
+
+/// 报价输出扣除容差后的最小输出（原始单位）
+pub fn quoted_min_out(expected_output: f64, decimals: u8, tolerance_bps: u16) -> u64 {
+    let tolerance = tolerance_bps.min(10_000) as f64 / 10_000.0;
+    to_raw(expected_output * (1.0 - tolerance), decimals)
+}
+
+/// swap v1 指令只支持 SPL Token（Token-2022 需要 swap_v2 / 额外账户）
+fn ensure_spl_token(mint: &Pubkey) -> Result<(), BuildError> {
+    let program = get_global_mint_cache()
+        .and_then(|cache| cache.get_or_fetch_info(mint).ok())
+        .map(|info| info.program);
+    match program {
+        Some(TokenProgram::Token2022) => Err(BuildError::Unsupported(format!("{} is a Token-2022 mint", mint))),
+        _ => Ok(()),
+    }
+}
+
+/// 编译 v0 交易；签名用占位符填充（模拟时 sigVerify=false）
+pub fn compile_transaction(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    blockhash: solana_sdk::hash::Hash,
+) -> anyhow::Result<VersionedTransaction> {
+    let message = v0::Message::try_compile(payer, instructions, &[], blockhash)
+        .map_err(|e| anyhow!("failed to compile message: {}", e))?;
+    let signatures = vec![Signature::default(); message.header.num_required_signatures as usize];
+    Ok(VersionedTransaction {
+        signatures,
+        message: VersionedMessage::V0(message),
+    })
+}
+
+/// 关联代币账户地址
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    let ata_program = Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_ID).expect("valid ATA program id");
+    Pubkey::find_program_address(&[owner.as_ref(), token_program.as_ref(), mint.as_ref()], &ata_program).0
+}
+
+/// 幂等创建 ATA（ATA 程序指令 1 = CreateIdempotent）
+fn create_ata_idempotent_instruction(
+    funder: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_ID).expect("valid ATA program id"),
+        accounts: vec![
+            AccountMeta::new(*funder, true),
+            AccountMeta::new(associated_token_address(owner, mint, token_program), false),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        data: vec![1],
+    }
+}
+
+/// Raydium AMM V4 SwapBaseInV2（8 个账户，不需要 OpenBook 市场）
+pub fn raydium_swap_instruction(
+    pool: &Pubkey,
+    state: &RaydiumAmmInfo,
+    user_source: &Pubkey,
+    user_destination: &Pubkey,
+    owner: &Pubkey,
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Instruction {
+    let mut data = Vec::with_capacity(17);
+    data.push(RAYDIUM_SWAP_BASE_IN_V2);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&min_amount_out.to_le_bytes());
+
+    Instruction {
+        program_id: Pubkey::from_str(RAYDIUM_AMM_V4_PROGRAM_ID).expect("valid Raydium program id"),
+        accounts: vec![
+            AccountMeta::new_readonly(Pubkey::from_str(SPL_TOKEN_PROGRAM_ID).expect("valid token program id"), false),
+            AccountMeta::new(*pool, false),
+            AccountMeta::new_readonly(Pubkey::from_str(RAYDIUM_AMM_AUTHORITY).expect("valid authority"), false),
+            AccountMeta::new(state.coin_vault, false),
+            AccountMeta::new(state.pc_vault, false),
+            AccountMeta::new(*user_source, false),
+            AccountMeta::new(*user_destination, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data,
+    }
+}
+
+/// tick array 起始 tick（offset 以 array 为单位）
+pub fn tick_array_start_index(tick_current_index: i32, tick_spacing: u16, offset: i32) -> i32 {
+    let ticks_in_array = TICK_ARRAY_SIZE * tick_spacing as i32;
+    (tick_current_index.div_euclid(ticks_in_array) + offset) * ticks_in_array
+}
+
+/// swap 需要的三个 tick array（a→b 向下遍历，b→a 向上遍历）
+pub fn whirlpool_tick_arrays(pool: &Pubkey, tick_current_index: i32, tick_spacing: u16, a_to_b: bool) -> [Pubkey; 3] {
+    let program = Pubkey::from_str(WHIRLPOOL_PROGRAM_ID).expect("valid Whirlpool program id");
+    // b→a 时当前 tick 恰好在 array 边界上也要从下一个 array 开始
+    let (tick, direction) = if a_to_b {
+        (tick_current_index, -1)
+    } else {
+        (tick_current_index + tick_spacing as i32, 1)
+    };
+    [0, 1, 2].map(|i| {
+        let start = tick_array_start_index(tick, tick_spacing, i * direction);
+        Pubkey::find_program_address(
+            &[b"tick_array", pool.as_ref(), start.to_string().as_bytes()],
+            &program,
+        ).0
+    })
+}
+
+/// Orca Whirlpool swap（v1，精确输入）
+#[allow(clippy::too_many_arguments)]
+pub fn whirlpool_swap_instruction(
+    pool: &Pubkey,
+    state: &WhirlpoolState,
+    a_to_b: bool,
+    owner_account_a: &Pubkey,
+    owner_account_b: &Pubkey,
+    owner: &Pubkey,
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Instruction {
+    let whirlpool = state.inner();
+    let program = Pubkey::from_str(WHIRLPOOL_PROGRAM_ID).expect("valid Whirlpool program id");
+    let [tick_array_0, tick_array_1, tick_array_2] =
+        whirlpool_tick_arrays(pool, whirlpool.tick_current_index, whirlpool.tick_spacing, a_to_b);
+    let oracle = Pubkey::find_program_address(&[b"oracle", pool.as_ref()], &program).0;
+    let sqrt_price_limit = if a_to_b { MIN_SQRT_PRICE } else { MAX_SQRT_PRICE };
+
+    let mut data = Vec::with_capacity(42);
+    data.extend_from_slice(&WHIRLPOOL_SWAP_DISCRIMINATOR);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&min_amount_out.to_le_bytes());
+    data.extend_from_slice(&sqrt_price_limit.to_le_bytes());
+    data.push(1); // amount_specified_is_input
+    data.push(a_to_b as u8);
+
+    Instruction {
+        program_id: program,
+        accounts: vec![
+            AccountMeta::new_readonly(Pubkey::from_str(SPL_TOKEN_PROGRAM_ID).expect("valid token program id"), false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(*pool, false),
+            AccountMeta::new(*owner_account_a, false),
+            AccountMeta::new(Pubkey::new_from_array(whirlpool.token_vault_a.to_bytes()), false),
+            AccountMeta::new(*owner_account_b, false),
+            AccountMeta::new(Pubkey::new_from_array(whirlpool.token_vault_b.to_bytes()), false),
+            AccountMeta::new(tick_array_0, false),
+            AccountMeta::new(tick_array_1, false),
+            AccountMeta::new(tick_array_2, false),
+            AccountMeta::new_readonly(oracle, false),
+        ],
+        data,
+    }
+}
+
+/// 从 payer 各 mint 余额的前后变化反推每一跳的输出
+///
+/// 每一跳的输入是精确值，所以 out_k = Δ(out_mint) + 从 out_mint 流出的输入 - 其他跳流入 out_mint 的输出。
+/// 同一个 mint 被多跳作为输出时无法区分，返回 None。
+pub fn hop_outputs(hops: &[PlannedHop], balance_delta: impl Fn(&Pubkey) -> Option<i128>) -> Vec<Option<u64>> {
+    hops.iter()
+        .map(|hop| {
+            let producers = hops.iter().filter(|h| h.output_mint == hop.output_mint).count();
+            if producers != 1 {
+                return None;
+            }
+            let consumed: i128 = hops.iter()
+                .filter(|h| h.input_mint == hop.output_mint)
+                .map(|h| h.amount_in as i128)
+                .sum();
+            let out = balance_delta(&hop.output_mint)? + consumed;
+            u64::try_from(out).ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_array_start_index() {
+        // tick_spacing 64 → 每个 array 覆盖 5632 个 tick
+        assert_eq!(tick_array_start_index(100, 64, 0), 0);
+        assert_eq!(tick_array_start_index(-1, 64, 0), -5632);
+        assert_eq!(tick_array_start_index(6000, 64, -1), 0);
+        assert_eq!(tick_array_start_index(6000, 64, 2), 16896);
+        assert_eq!(SwapVenue::from_dex_name("Raydium AMM V4"), Some(SwapVenue::RaydiumAmmV4));
+        assert_eq!(SwapVenue::from_dex_name("Whirlpool (Orca)"), Some(SwapVenue::Whirlpool));
+        assert_eq!(SwapVenue::from_dex_name("Raydium CLMM"), None);
+    }
+
+    #[test]
+    fn test_hop_outputs_from_balance_deltas() {
+        let sol = Pubkey::new_unique();
+        let usdc = Pubkey::new_unique();
+        let hops = vec![
+            PlannedHop { pool_id: "a".into(), input_mint: sol, output_mint: usdc, amount_in: 1_000_000_000, min_amount_out: 185_000_000 },
+            PlannedHop { pool_id: "b".into(), input_mint: usdc, output_mint: sol, amount_in: 185_000_000, min_amount_out: 0 },
+        ];
+        // 第一跳换出 185.2 USDC，第二跳用 185 USDC 换回 1.002 SOL
+        let delta = |mint: &Pubkey| -> Option<i128> {
+            if *mint == sol { Some(2_000_000) } else if *mint == usdc { Some(200_000) } else { None }
+        };
+
+        assert_eq!(hop_outputs(&hops, delta), vec![Some(185_200_000), Some(1_002_000_000)]);
+    }
+
+    #[test]
+    fn test_quoted_min_out_sizes_next_hop() {
+        // 报价 200 USDC，容差 50bps → 至少 199 USDC，下一跳就花这么多
+        assert_eq!(quoted_min_out(200.0, 6, 50), 199_000_000);
+        assert_eq!(quoted_min_out(200.0, 6, 0), 200_000_000);
+        assert_eq!(quoted_min_out(200.0, 6, 20_000), 0);
+    }
+}
@@ -1,7 +1,10 @@
-///! 工具模块
-///! 提供结构体验证、数据探测等辅助功能
+/*!
+ * 工具模块
+ * 提供结构体验证、数据探测等辅助功能
+ */
 
 pub mod struct_validator;
+pub mod pool_probe;
 
 pub use struct_validator::{
     StructSizeValidator,
@@ -10,6 +13,8 @@ pub use struct_validator::{
     DataAnalysis,
 };
 
+pub use pool_probe::{PoolProbeReport, ProbeMatch, PoolSummary};
+
 
 
 
@@ -0,0 +1,256 @@
+/*!
+ * 未知池子账户探测
+ * 用所有已知反序列化器解析同一份账户数据，结合 owner 和账户大小挑出最可能的类型，
+ * 并生成可直接粘贴的 config.toml 片段（`pool-probe <address>` 的核心逻辑）
+ */
+
+use std::fmt::Write;
+
+use crate::pool_factory::PoolFactory;
+use crate::utils::struct_validator::{DataAnalysis, SizeValidationResult, StructProbe, StructSizeValidator};
+
+/// 每个 pool_type 期望的账户大小（空 = 变长，不检查；与 tests/golden_fixtures.rs 的 SPECS 一致）
+pub const EXPECTED_ACCOUNT_SIZES: &[(&str, &[usize])] = &[
+    ("amm_v4", &[752, 388]),
+    ("clmm", &[1544]),
+    ("lifinity_v2", &[911]),
+    ("meteora_dlmm", &[904]),
+    ("alphaq", &[672]),
+    ("solfi_v2", &[1728]),
+    ("humidifi", &[1728]),
+    ("goonfi", &[856]),
+    ("tesserav", &[1264]),
+    ("stabble", &[438]),
+    ("aquifer", &[]),
+    ("whirlpool", &[653]),
+    ("pancakeswap", &[1544]),
+    ("phoenix", &[]),
+    ("openbook_v2", &[]),
+];
+
+/// 解析成功时的摘要
+#[derive(Debug, Clone)]
+pub struct PoolSummary {
+    pub dex_name: &'static str,
+    pub decimals: (u8, u8),
+    pub reserves: (u64, u64),
+    pub price: f64,
+    pub vaults: Option<(String, String)>,
+    pub mints: Option<(String, String)>,
+    pub active: bool,
+}
+
+impl PoolSummary {
+    /// 价格有限且为正、decimals 非零：像是一个真实的池子
+    pub fn looks_plausible(&self) -> bool {
+        self.price.is_finite() && self.price > 0.0 && self.decimals.0 > 0 && self.decimals.1 > 0
+    }
+}
+
+/// 单个反序列化器的探测结果
+#[derive(Debug, Clone)]
+pub struct ProbeMatch {
+    pub pool_type: &'static str,
+    /// 该类型登记的 program id（未登记为 None）
+    pub expected_owner: Option<&'static str>,
+    /// 账户大小是否符合期望（变长类型为 None）
+    pub size_check: Option<SizeValidationResult>,
+    /// 解析结果：成功为摘要，失败为错误信息
+    pub result: Result<PoolSummary, String>,
+}
+
+/// ProbeMatch::rank 的排序键：(owner 等级, 大小一致, 价格合理, 解析出 vault)
+type ProbeRank = (u8, bool, bool, bool);
+
+impl ProbeMatch {
+    /// owner 是否与该类型的 program 一致（未登记 program 时为 None）
+    pub fn owner_matches(&self, owner: &str) -> Option<bool> {
+        self.expected_owner.map(|expected| expected == owner)
+    }
+
+    /// 排序键：owner 一致 > owner 未登记 > owner 不一致，其次大小、价格合理性、vault
+    fn rank(&self, owner: &str) -> Option<ProbeRank> {
+        let summary = self.result.as_ref().ok()?;
+        let owner_rank = match self.owner_matches(owner) {
+            Some(true) => 2,
+            None => 1,
+            Some(false) => 0,
+        };
+        let size_ok = self.size_check.as_ref().is_none_or(|check| check.matches);
+        Some((owner_rank, size_ok, summary.looks_plausible(), summary.vaults.is_some()))
+    }
+}
+
+/// 一个账户的完整探测报告
+#[derive(Debug)]
+pub struct PoolProbeReport {
+    pub address: String,
+    pub owner: String,
+    pub data_len: usize,
+    pub matches: Vec<ProbeMatch>,
+    pub analysis: DataAnalysis,
+}
+
+/// 按 pool_type 检查账户大小（多个期望大小时取与实际最接近的一个）
+fn size_check(pool_type: &str, actual: usize) -> Option<SizeValidationResult> {
+    let (_, sizes) = EXPECTED_ACCOUNT_SIZES.iter().find(|(t, _)| *t == pool_type)?;
+    let expected = sizes.iter().copied().min_by_key(|size| size.abs_diff(actual))?;
+    StructSizeValidator::validate_batch(vec![(pool_type, expected, actual)]).pop()
+}
+
+impl PoolProbeReport {
+    /// 用每个已知反序列化器探测账户数据
+    pub fn probe(address: &str, owner: &str, data: &[u8]) -> Self {
+        let matches = PoolFactory::create_pool_all(data)
+            .into_iter()
+            .map(|(pool_type, result)| ProbeMatch {
+                pool_type,
+                expected_owner: PoolFactory::expected_owner(pool_type),
+                size_check: size_check(pool_type, data.len()),
+                result: result
+                    .map(|pool| PoolSummary {
+                        dex_name: pool.dex_name(),
+                        decimals: pool.get_decimals(),
+                        reserves: pool.get_reserves(),
+                        price: pool.calculate_price(),
+                        vaults: pool.get_vault_addresses().map(|(a, b)| (a.to_string(), b.to_string())),
+                        mints: pool.get_mints().map(|(a, b)| (a.to_string(), b.to_string())),
+                        active: pool.is_active(),
+                    })
+                    .map_err(|e| e.to_string()),
+            })
+            .collect();
+
+        Self {
+            address: address.to_string(),
+            owner: owner.to_string(),
+            data_len: data.len(),
+            matches,
+            analysis: StructProbe::analyze_data(data),
+        }
+    }
+
+    /// 解析成功的反序列化器
+    pub fn parsed(&self) -> impl Iterator<Item = &ProbeMatch> {
+        self.matches.iter().filter(|m| m.result.is_ok())
+    }
+
+    /// 最可能的类型（并列时取 KNOWN_POOL_TYPES 中靠前的）
+    pub fn best_match(&self) -> Option<&ProbeMatch> {
+        let mut best: Option<(&ProbeMatch, ProbeRank)> = None;
+        for candidate in &self.matches {
+            let Some(rank) = candidate.rank(&self.owner) else { continue };
+            if best.is_none_or(|(_, best_rank)| rank > best_rank) {
+                best = Some((candidate, rank));
+            }
+        }
+        best.map(|(candidate, _)| candidate)
+    }
+
+    /// 最佳匹配的 config.toml 片段（name / pair 需要人工填写代币符号）
+    pub fn config_snippet(&self) -> Option<String> {
+        let best = self.best_match()?;
+        let summary = best.result.as_ref().ok()?;
+        let mut snippet = String::new();
+        let _ = writeln!(snippet, "[[pools]]");
+        let _ = writeln!(snippet, "address = \"{}\"", self.address);
+        let _ = writeln!(snippet, "name = \"BASE/QUOTE ({})\"", summary.dex_name);
+        let _ = writeln!(snippet, "pair = \"BASE/QUOTE\"");
+        let _ = writeln!(snippet, "pool_type = \"{}\"", best.pool_type);
+        if let Some((base_mint, quote_mint)) = &summary.mints {
+            let _ = writeln!(snippet, "base_mint = \"{}\"", base_mint);
+            let _ = writeln!(snippet, "quote_mint = \"{}\"", quote_mint);
+        }
+        Some(snippet)
+    }
+
+    /// 人类可读的报告（pool-probe 的输出）
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "🔍 Pool account {}", self.address);
+        let _ = writeln!(out, "   Owner program: {}", self.owner);
+        let _ = writeln!(out, "   Data length:   {} bytes", self.data_len);
+        let _ = writeln!(out);
+
+        for m in &self.matches {
+            let owner_note = match m.owner_matches(&self.owner) {
+                Some(true) => "owner ✅",
+                Some(false) => "owner ❌",
+                None => "owner ?",
+            };
+            match &m.result {
+                Ok(summary) => {
+                    let _ = writeln!(out, "✅ {:<13} {} ({})", m.pool_type, summary.dex_name, owner_note);
+                    let _ = writeln!(out, "      decimals: {:?}  reserves: {:?}  price: {:.6}  active: {}",
+                        summary.decimals, summary.reserves, summary.price, summary.active);
+                    if let Some((vault_a, vault_b)) = &summary.vaults {
+                        let _ = writeln!(out, "      vaults: {} / {}", vault_a, vault_b);
+                    }
+                    if let Some((mint_a, mint_b)) = &summary.mints {
+                        let _ = writeln!(out, "      mints:  {} / {}", mint_a, mint_b);
+                    }
+                }
+                Err(e) => {
+                    let _ = writeln!(out, "❌ {:<13} {} ({})", m.pool_type, e, owner_note);
+                }
+            }
+            if let Some(check) = &m.size_check {
+                let _ = writeln!(out, "      {}", check.to_string());
+            }
+        }
+        let _ = writeln!(out);
+
+        match (self.best_match(), self.config_snippet()) {
+            (Some(best), Some(snippet)) => {
+                let _ = writeln!(out, "🏆 Best match: {}", best.pool_type);
+                let _ = writeln!(out, "\n# config.toml\n{}", snippet);
+            }
+            _ => {
+                let _ = writeln!(out, "⚠️  No deserializer could parse this account");
+                let _ = writeln!(out, "{}", self.analysis.to_string());
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pool_factory::KNOWN_POOL_TYPES;
+    use crate::pool_fixture::load_all;
+    use std::path::Path;
+
+    #[test]
+    fn test_probe_identifies_golden_fixtures() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        for fixture in load_all(&dir).unwrap() {
+            let data = fixture.decode_data().unwrap();
+            let address = fixture.address.clone().unwrap_or_default();
+            let owner = fixture.owner.clone().unwrap_or_default();
+            let report = PoolProbeReport::probe(&address, &owner, &data);
+
+            // fixture 自己的类型总在解析成功的列表里，且大小符合期望
+            let own = report.parsed().find(|m| m.pool_type == fixture.pool_type);
+            let own = own.unwrap_or_else(|| panic!("{} not parsed", fixture.pool_type));
+            assert!(own.size_check.as_ref().is_none_or(|c| c.matches), "{}", fixture.pool_type);
+
+            // 记录了 owner 的 fixture：最佳匹配就是它的类型（大小相同的布局靠 owner 区分）
+            if fixture.owner.is_some() {
+                assert_eq!(report.best_match().map(|m| m.pool_type), Some(fixture.pool_type.as_str()));
+                let snippet = report.config_snippet().unwrap();
+                assert!(snippet.contains(&format!("pool_type = \"{}\"", fixture.pool_type)), "{}", snippet);
+                assert!(report.render().contains("🏆 Best match"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_probe_without_match_reports_analysis() {
+        let report = PoolProbeReport::probe("addr", "11111111111111111111111111111111", &[7u8; 10]);
+        assert_eq!(report.matches.len(), KNOWN_POOL_TYPES.len());
+        assert!(report.best_match().is_none());
+        assert!(report.config_snippet().is_none());
+        assert!(report.render().contains("No deserializer could parse"));
+    }
+}
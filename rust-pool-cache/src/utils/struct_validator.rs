@@ -1,5 +1,7 @@
-///! 结构体大小自动验证工具
-///! 用于在运行时验证Rust结构体大小是否与链上数据匹配
+/*!
+ * 结构体大小自动验证工具
+ * 用于在运行时验证Rust结构体大小是否与链上数据匹配
+ */
 
 use std::mem::size_of;
 use crate::dex_interface::DexError;
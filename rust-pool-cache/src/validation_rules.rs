@@ -0,0 +1,583 @@
+/*!
+ * 🧱 机会验证规则流水线
+ *
+ * `OpportunityValidator::validate_path` 按配置顺序依次执行命名规则，第一条拒绝的规则
+ * 决定结果（`ValidationResult::rejected_by` 返回规则名）。规则集合和参数来自 [validation]：
+ *
+ * - max_data_age：路径上最旧的池子超过数据年龄上限
+ * - slot_alignment：池子之间的 slot 差异过大
+ * - price_deviation：池子价格相对发现时（step.price）偏离过大
+ * - min_liquidity：输入侧储备不足该跳输入的 N 倍
+ * - max_single_hop_impact：任意一跳价格冲击超过上限（穿过薄池）
+ * - no_pool_reuse：同一池子（或共用 vault 的包装池）出现两次
+ * - max_total_roi_sanity：ROI 高得不现实（几乎总是计算或数据错误）
+ * - simulation_required_above_usd：名义金额超过阈值但没有配置交易级模拟
+ * - confirmed_required_above_usd：名义金额超过阈值但路径上有 processed 级别的池子数据
+ *
+ * 每条规则的拒绝次数记入 `RuleStats`（/metrics 和 GET /stats/validation），
+ * 便于看出原始路径主要死在哪一步（例如 80% 因数据过期被拒）。
+ */
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use serde::Serialize;
+use tracing::info;
+
+use crate::opportunity_validator::ValidationResult;
+use crate::price_cache::PoolPrice;
+use crate::prometheus::{MetricKind, PrometheusWriter};
+use crate::router::ArbitragePath;
+use crate::token_graph::pool_tokens;
+use crate::vault_reader::VaultReader;
+
+pub const MAX_DATA_AGE: &str = "max_data_age";
+pub const SLOT_ALIGNMENT: &str = "slot_alignment";
+pub const PRICE_DEVIATION: &str = "price_deviation";
+pub const MIN_LIQUIDITY: &str = "min_liquidity";
+pub const MAX_SINGLE_HOP_IMPACT: &str = "max_single_hop_impact";
+pub const NO_POOL_REUSE: &str = "no_pool_reuse";
+pub const MAX_TOTAL_ROI_SANITY: &str = "max_total_roi_sanity";
+pub const SIMULATION_REQUIRED_ABOVE_USD: &str = "simulation_required_above_usd";
+pub const CONFIRMED_REQUIRED_ABOVE_USD: &str = "confirmed_required_above_usd";
+/// 不是规则：路径上的池子已不在缓存中（在所有规则之前检查）
+pub const POOL_NOT_FOUND: &str = "pool_not_found";
+
+/// 默认规则及执行顺序（便宜且拒绝率高的检查在前）
+pub const DEFAULT_RULES: [&str; 9] = [
+    NO_POOL_REUSE,
+    MAX_DATA_AGE,
+    SLOT_ALIGNMENT,
+    PRICE_DEVIATION,
+    MIN_LIQUIDITY,
+    MAX_SINGLE_HOP_IMPACT,
+    MAX_TOTAL_ROI_SANITY,
+    SIMULATION_REQUIRED_ABOVE_USD,
+    CONFIRMED_REQUIRED_ABOVE_USD,
+];
+
+/// 规则名是否已知（配置校验使用）
+pub fn is_known_rule(name: &str) -> bool {
+    DEFAULT_RULES.contains(&name)
+}
+
+/// 单条规则的结果
+#[derive(Debug, Clone)]
+pub enum RuleResult {
+    Pass,
+    Reject(ValidationResult),
+}
+
+/// 规则执行上下文（验证器每条路径组装一次，所有规则共用）
+pub struct RuleContext<'a> {
+    /// 每一跳对应池子的缓存状态（与 path.steps 一一对应）
+    pub pools: &'a [PoolPrice],
+    pub now: Instant,
+    /// 🔁 vault 登记表（识别共用 vault 的包装池；未接入时只比较 pool_id）
+    pub vault_reader: Option<&'a VaultReader>,
+    /// 起点代币的美元价格（未知时为 None）
+    pub input_usd_price: Option<f64>,
+    /// 是否配置了交易级模拟
+    pub simulation_available: bool,
+}
+
+impl RuleContext<'_> {
+    /// 每个池子的数据年龄（毫秒）
+    pub fn ages_ms(&self) -> Vec<u64> {
+        self.pools.iter()
+            .map(|pool| self.now.duration_since(pool.last_update).as_millis() as u64)
+            .collect()
+    }
+
+    /// 池子之间的最大 slot 差
+    pub fn slot_spread(&self) -> u64 {
+        let min_slot = self.pools.iter().map(|pool| pool.slot).min().unwrap_or(0);
+        let max_slot = self.pools.iter().map(|pool| pool.slot).max().unwrap_or(0);
+        max_slot - min_slot
+    }
+}
+
+/// 验证规则
+pub trait ValidationRule: Send + Sync {
+    /// 规则名（配置、日志和 /metrics 的 rule 标签）
+    fn name(&self) -> &'static str;
+
+    fn check(&self, path: &ArbitragePath, ctx: &RuleContext) -> RuleResult;
+}
+
+/// 路径上最旧的池子不能超过 `max_age_ms`
+pub struct MaxDataAge {
+    pub max_age_ms: u64,
+}
+
+impl ValidationRule for MaxDataAge {
+    fn name(&self) -> &'static str {
+        MAX_DATA_AGE
+    }
+
+    fn check(&self, path: &ArbitragePath, ctx: &RuleContext) -> RuleResult {
+        let oldest = ctx.ages_ms()
+            .into_iter()
+            .enumerate()
+            .max_by_key(|(_, age)| *age);
+        match oldest {
+            Some((hop, age_ms)) if age_ms > self.max_age_ms => RuleResult::Reject(ValidationResult::Stale {
+                oldest_pool: path.steps[hop].pool_id.clone(),
+                age_ms,
+            }),
+            _ => RuleResult::Pass,
+        }
+    }
+}
+
+/// 池子之间的 slot 差异不能超过 `max_slot_spread`
+pub struct SlotAlignment {
+    pub max_slot_spread: u64,
+}
+
+impl ValidationRule for SlotAlignment {
+    fn name(&self) -> &'static str {
+        SLOT_ALIGNMENT
+    }
+
+    fn check(&self, path: &ArbitragePath, ctx: &RuleContext) -> RuleResult {
+        let slot_spread = ctx.slot_spread();
+        if slot_spread > self.max_slot_spread {
+            return RuleResult::Reject(ValidationResult::SlotMismatch {
+                slot_spread,
+                pools_count: path.steps.len(),
+            });
+        }
+        RuleResult::Pass
+    }
+}
+
+/// 池子当前价格相对发现时（step.price）的偏离不能超过 `max_deviation_pct`
+pub struct PriceDeviation {
+    pub max_deviation_pct: f64,
+}
+
+impl ValidationRule for PriceDeviation {
+    fn name(&self) -> &'static str {
+        PRICE_DEVIATION
+    }
+
+    fn check(&self, path: &ArbitragePath, ctx: &RuleContext) -> RuleResult {
+        for (step, pool) in path.steps.iter().zip(ctx.pools) {
+            if step.price <= 0.0 {
+                continue;
+            }
+            let deviation_pct = ((pool.price - step.price).abs() / step.price) * 100.0;
+            if deviation_pct > self.max_deviation_pct {
+                return RuleResult::Reject(ValidationResult::PriceChanged {
+                    pool_id: step.pool_id.clone(),
+                    expected: step.price,
+                    current: pool.price,
+                    deviation_pct,
+                });
+            }
+        }
+        RuleResult::Pass
+    }
+}
+
+/// 输入侧储备至少是该跳输入（expected_input）的 `multiplier` 倍
+pub struct MinLiquidity {
+    pub multiplier: f64,
+}
+
+impl ValidationRule for MinLiquidity {
+    fn name(&self) -> &'static str {
+        MIN_LIQUIDITY
+    }
+
+    fn check(&self, path: &ArbitragePath, ctx: &RuleContext) -> RuleResult {
+        for (step, pool) in path.steps.iter().zip(ctx.pools) {
+            let (base_decimals, quote_decimals) = pool.get_decimals();
+            let input_is_base = pool_tokens(pool).is_some_and(|(base, _)| base == step.input_token);
+            let available = if input_is_base {
+                pool.base_reserve as f64 / 10f64.powi(base_decimals as i32)
+            } else {
+                pool.quote_reserve as f64 / 10f64.powi(quote_decimals as i32)
+            };
+            let required = step.expected_input * self.multiplier;
+            if available < required {
+                return RuleResult::Reject(ValidationResult::InsufficientLiquidity {
+                    pool_id: step.pool_id.clone(),
+                    required,
+                    available,
+                });
+            }
+        }
+        RuleResult::Pass
+    }
+}
+
+/// 任意一跳的价格冲击不能超过 `cap_percent`
+pub struct MaxSingleHopImpact {
+    pub cap_percent: f64,
+}
+
+impl ValidationRule for MaxSingleHopImpact {
+    fn name(&self) -> &'static str {
+        MAX_SINGLE_HOP_IMPACT
+    }
+
+    fn check(&self, path: &ArbitragePath, _ctx: &RuleContext) -> RuleResult {
+        match path.steps.iter().find(|step| step.price_impact_percent > self.cap_percent) {
+            Some(step) => RuleResult::Reject(ValidationResult::ExcessivePriceImpact {
+                pool_id: step.pool_id.clone(),
+                impact_percent: step.price_impact_percent,
+                cap_percent: self.cap_percent,
+            }),
+            None => RuleResult::Pass,
+        }
+    }
+}
+
+/// 🔁 路径不能重复使用同一池子（第一跳成交后后面那一跳的价格已经不成立）
+pub struct NoPoolReuse;
+
+impl ValidationRule for NoPoolReuse {
+    fn name(&self) -> &'static str {
+        NO_POOL_REUSE
+    }
+
+    fn check(&self, path: &ArbitragePath, ctx: &RuleContext) -> RuleResult {
+        match path.pool_reuse(ctx.vault_reader) {
+            Some(reuse) => RuleResult::Reject(ValidationResult::PoolReused { reuse }),
+            None => RuleResult::Pass,
+        }
+    }
+}
+
+/// ROI 合理性上限
+///
+/// 超过 `max_roi_percent` 几乎总是计算或数据错误（市场没有这么低效），直接拒绝；
+/// 介于 `suspicious_roi_percent` 和上限之间的保留但记录警告，需要人工核实。
+/// 路径验证和 LST 检测器共用同一组阈值。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoiSanity {
+    pub max_roi_percent: f64,
+    pub suspicious_roi_percent: f64,
+}
+
+impl Default for RoiSanity {
+    fn default() -> Self {
+        Self {
+            max_roi_percent: 15.0,
+            suspicious_roi_percent: 8.0,
+        }
+    }
+}
+
+/// ROI 合理性判断结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoiVerdict {
+    Plausible,
+    /// 值得怀疑，保留但需要人工核实
+    Suspicious,
+    /// 不现实，拒绝
+    Unrealistic,
+}
+
+impl RoiSanity {
+    pub fn verdict(&self, roi_percent: f64) -> RoiVerdict {
+        if roi_percent.is_nan() || roi_percent > self.max_roi_percent {
+            RoiVerdict::Unrealistic
+        } else if roi_percent > self.suspicious_roi_percent {
+            RoiVerdict::Suspicious
+        } else {
+            RoiVerdict::Plausible
+        }
+    }
+}
+
+/// 路径总 ROI 的合理性检查
+pub struct MaxTotalRoiSanity {
+    pub sanity: RoiSanity,
+}
+
+impl ValidationRule for MaxTotalRoiSanity {
+    fn name(&self) -> &'static str {
+        MAX_TOTAL_ROI_SANITY
+    }
+
+    fn check(&self, path: &ArbitragePath, _ctx: &RuleContext) -> RuleResult {
+        match self.sanity.verdict(path.roi_percent) {
+            RoiVerdict::Unrealistic => RuleResult::Reject(ValidationResult::UnrealisticRoi {
+                roi_percent: path.roi_percent,
+                cap_percent: self.sanity.max_roi_percent,
+            }),
+            RoiVerdict::Suspicious => {
+                info!(
+                    "⚠️  Suspicious opportunity {} with {:.2}% ROI (verify manually!)",
+                    path.signature(), path.roi_percent
+                );
+                RuleResult::Pass
+            }
+            RoiVerdict::Plausible => RuleResult::Pass,
+        }
+    }
+}
+
+/// 名义金额超过 `threshold_usd` 的路径必须有交易级模拟（[simulation] 配置了 payer）
+///
+/// `threshold_usd <= 0` 表示不检查；起点代币没有美元价格时无法判断，放行。
+pub struct SimulationRequiredAboveUsd {
+    pub threshold_usd: f64,
+}
+
+impl ValidationRule for SimulationRequiredAboveUsd {
+    fn name(&self) -> &'static str {
+        SIMULATION_REQUIRED_ABOVE_USD
+    }
+
+    fn check(&self, path: &ArbitragePath, ctx: &RuleContext) -> RuleResult {
+        if self.threshold_usd <= 0.0 || ctx.simulation_available {
+            return RuleResult::Pass;
+        }
+        match ctx.input_usd_price.map(|usd| path.input_amount * usd) {
+            Some(notional_usd) if notional_usd > self.threshold_usd => {
+                RuleResult::Reject(ValidationResult::SimulationRequired {
+                    notional_usd,
+                    threshold_usd: self.threshold_usd,
+                })
+            }
+            _ => RuleResult::Pass,
+        }
+    }
+}
+
+/// 名义金额超过 `threshold_usd` 的路径，每个池子的数据都必须来自 confirmed 及以上的订阅
+///
+/// processed 数据可能被回滚，只适合小额机会。`threshold_usd <= 0` 表示不检查；
+/// 起点代币没有美元价格、或池子数据不是来自订阅（commitment 未知）时放行。
+pub struct ConfirmedRequiredAboveUsd {
+    pub threshold_usd: f64,
+}
+
+impl ValidationRule for ConfirmedRequiredAboveUsd {
+    fn name(&self) -> &'static str {
+        CONFIRMED_REQUIRED_ABOVE_USD
+    }
+
+    fn check(&self, path: &ArbitragePath, ctx: &RuleContext) -> RuleResult {
+        if self.threshold_usd <= 0.0 {
+            return RuleResult::Pass;
+        }
+        let notional_usd = match ctx.input_usd_price.map(|usd| path.input_amount * usd) {
+            Some(notional_usd) if notional_usd > self.threshold_usd => notional_usd,
+            _ => return RuleResult::Pass,
+        };
+        let unconfirmed = ctx.pools.iter()
+            .find_map(|pool| pool.commitment.filter(|c| !c.is_confirmed()).map(|c| (pool, c)));
+        match unconfirmed {
+            Some((pool, commitment)) => RuleResult::Reject(ValidationResult::UnconfirmedData {
+                pool_id: pool.pool_id.clone(),
+                commitment,
+                notional_usd,
+                threshold_usd: self.threshold_usd,
+            }),
+            None => RuleResult::Pass,
+        }
+    }
+}
+
+/// 每条规则的拒绝次数（规则名固定，计数无锁）
+#[derive(Debug)]
+pub struct RuleStats {
+    /// 当前生效的规则（执行顺序）
+    active_rules: Vec<&'static str>,
+    checked: AtomicU64,
+    passed: AtomicU64,
+    rejections: Vec<(&'static str, AtomicU64)>,
+}
+
+/// GET /stats/validation 的单条规则计数
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleRejections {
+    pub rule: &'static str,
+    pub rejected: u64,
+    /// 占全部已检查路径的百分比
+    pub percent_of_checked: f64,
+}
+
+/// GET /stats/validation
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleStatsSnapshot {
+    pub checked: u64,
+    pub passed: u64,
+    /// 当前生效的规则（执行顺序）
+    pub active_rules: Vec<&'static str>,
+    pub rejections: Vec<RuleRejections>,
+}
+
+impl RuleStats {
+    pub fn new(active_rules: Vec<&'static str>) -> Self {
+        Self {
+            active_rules,
+            checked: AtomicU64::new(0),
+            passed: AtomicU64::new(0),
+            rejections: std::iter::once(POOL_NOT_FOUND)
+                .chain(DEFAULT_RULES)
+                .map(|rule| (rule, AtomicU64::new(0)))
+                .collect(),
+        }
+    }
+
+    pub fn active_rules(&self) -> &[&'static str] {
+        &self.active_rules
+    }
+
+    /// 记录一条路径的结果（`rejected_by` 为 None 表示通过）
+    pub fn record(&self, rejected_by: Option<&str>) {
+        self.checked.fetch_add(1, Ordering::Relaxed);
+        match rejected_by {
+            None => {
+                self.passed.fetch_add(1, Ordering::Relaxed);
+            }
+            Some(rule) => {
+                if let Some((_, counter)) = self.rejections.iter().find(|(name, _)| *name == rule) {
+                    counter.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    pub fn rejected(&self, rule: &str) -> u64 {
+        self.rejections.iter()
+            .find(|(name, _)| *name == rule)
+            .map_or(0, |(_, counter)| counter.load(Ordering::Relaxed))
+    }
+
+    pub fn snapshot(&self) -> RuleStatsSnapshot {
+        let checked = self.checked.load(Ordering::Relaxed);
+        let rejections = self.rejections.iter()
+            .map(|(rule, counter)| {
+                let rejected = counter.load(Ordering::Relaxed);
+                RuleRejections {
+                    rule,
+                    rejected,
+                    percent_of_checked: if checked > 0 { rejected as f64 / checked as f64 * 100.0 } else { 0.0 },
+                }
+            })
+            .collect();
+        RuleStatsSnapshot {
+            checked,
+            passed: self.passed.load(Ordering::Relaxed),
+            active_rules: self.active_rules.clone(),
+            rejections,
+        }
+    }
+
+    pub fn write_prometheus(&self, writer: &mut PrometheusWriter) {
+        writer.family(
+            "pool_cache_validation_paths_total",
+            "Paths run through the validation rule pipeline by outcome",
+            MetricKind::Counter,
+        );
+        let checked = self.checked.load(Ordering::Relaxed);
+        let passed = self.passed.load(Ordering::Relaxed);
+        writer.sample("pool_cache_validation_paths_total", &[("outcome", "passed")], passed as f64);
+        writer.sample("pool_cache_validation_paths_total", &[("outcome", "rejected")], (checked - passed) as f64);
+
+        writer.family(
+            "pool_cache_validation_rejections_total",
+            "Paths rejected by each validation rule (first rejecting rule only)",
+            MetricKind::Counter,
+        );
+        for (rule, counter) in &self.rejections {
+            writer.sample(
+                "pool_cache_validation_rejections_total",
+                &[("rule", *rule)],
+                counter.load(Ordering::Relaxed) as f64,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roi_sanity_verdicts() {
+        let sanity = RoiSanity::default();
+        assert_eq!(sanity.verdict(1.0), RoiVerdict::Plausible);
+        assert_eq!(sanity.verdict(8.0), RoiVerdict::Plausible);
+        assert_eq!(sanity.verdict(10.0), RoiVerdict::Suspicious);
+        assert_eq!(sanity.verdict(15.5), RoiVerdict::Unrealistic);
+        assert_eq!(sanity.verdict(f64::NAN), RoiVerdict::Unrealistic);
+    }
+
+    #[test]
+    fn test_rule_stats_counts_first_rejecting_rule() {
+        let stats = RuleStats::new(DEFAULT_RULES.to_vec());
+        for _ in 0..8 {
+            stats.record(Some(MAX_DATA_AGE));
+        }
+        stats.record(Some(MIN_LIQUIDITY));
+        stats.record(None);
+
+        let snapshot = stats.snapshot();
+        assert_eq!((snapshot.checked, snapshot.passed), (10, 1));
+        let stale = snapshot.rejections.iter().find(|r| r.rule == MAX_DATA_AGE).unwrap();
+        assert_eq!(stale.rejected, 8);
+        assert_eq!(stale.percent_of_checked, 80.0);
+        assert_eq!(stats.rejected(NO_POOL_REUSE), 0);
+
+        let mut writer = PrometheusWriter::new();
+        stats.write_prometheus(&mut writer);
+        let text = writer.finish();
+        assert!(text.contains("pool_cache_validation_rejections_total{rule=\"max_data_age\"} 8"));
+        assert!(text.contains("pool_cache_validation_paths_total{outcome=\"rejected\"} 9"));
+    }
+
+    #[test]
+    fn test_large_notional_requires_confirmed_data() {
+        use crate::account_subscription::Commitment;
+        use crate::router::ArbitrageType;
+
+        let path = |input_amount: f64| ArbitragePath {
+            arb_type: ArbitrageType::Triangle,
+            steps: Vec::new(),
+            start_token: "USDC".to_string(),
+            end_token: "USDC".to_string(),
+            input_amount,
+            output_amount: input_amount,
+            gross_profit: 0.0,
+            dex_fees: 0.0,
+            execution_fees: 0.0,
+            estimated_fees: 0.0,
+            net_profit: 0.0,
+            roi_percent: 0.0,
+            discovered_at: Instant::now(),
+        };
+        let pool = |commitment: Option<Commitment>| PoolPrice {
+            dex_name: "SolFi V2".to_string(),
+            base_reserve: 1_000,
+            quote_reserve: 150_000,
+            commitment,
+            ..PoolPrice::for_test("hot-pool", "SOL/USDC", 150.0)
+        };
+        let rule = ConfirmedRequiredAboveUsd { threshold_usd: 1_000.0 };
+        let check = |pools: &[PoolPrice], amount: f64| rule.check(&path(amount), &RuleContext {
+            pools,
+            now: Instant::now(),
+            vault_reader: None,
+            input_usd_price: Some(1.0),
+            simulation_available: false,
+        });
+
+        // processed 数据只允许小额；confirmed / RPC 数据（None）不受限
+        let processed = [pool(Some(Commitment::Processed))];
+        assert!(matches!(check(&processed, 500.0), RuleResult::Pass));
+        assert!(matches!(
+            check(&processed, 5_000.0),
+            RuleResult::Reject(ValidationResult::UnconfirmedData { commitment: Commitment::Processed, .. })
+        ));
+        assert!(matches!(check(&[pool(Some(Commitment::Confirmed))], 5_000.0), RuleResult::Pass));
+        assert!(matches!(check(&[pool(None)], 5_000.0), RuleResult::Pass));
+    }
+}
@@ -0,0 +1,212 @@
+/*!
+ * 🔍 vault 型池子的储备一致性检查
+ *
+ * SolFi V2、GoonFi 等池子的储备来自外部 vault 账户：vault 更新后按缓存的池子账户重算价格。
+ * 池子账户被 LRU 淘汰、重算失败或熔断拒绝更新时，缓存中的储备会停留在旧值，
+ * 而 vault 余额已经变化 —— 这正是 SolFi 幻影价格的成因。
+ *
+ * - `check_pool`：比较缓存储备、VaultReader 余额和最近一次池子账户解析出的储备
+ *   （GET /pools/{address}/consistency）
+ * - `spawn_auditor`：定期巡检全部 vault 型池子，缓存储备与 vault 余额的相对差超过阈值时告警
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::price_cache::{PoolPrice, PriceCache};
+use crate::supervisor::{spawn_supervised, Supervisor};
+use crate::vault_reader::VaultReader;
+use crate::websocket::WsPoolAccounts;
+
+/// 单侧储备的比较
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReserveComparison {
+    /// PriceCache 中的储备
+    pub cached: u64,
+    /// VaultReader 最近一次看到的 vault 余额（尚未收到 vault 数据时为 None）
+    pub vault: Option<u64>,
+    /// 最近一次池子账户解析出的储备（没有缓存账户数据时为 None）
+    pub raw: Option<u64>,
+    /// |cached - vault| / max(cached, vault)
+    pub vault_diff: Option<f64>,
+    /// |cached - raw| / max(cached, raw)
+    pub raw_diff: Option<f64>,
+}
+
+impl ReserveComparison {
+    fn new(cached: u64, vault: Option<u64>, raw: Option<u64>) -> Self {
+        Self {
+            cached,
+            vault,
+            raw,
+            vault_diff: vault.map(|v| relative_diff(cached, v)),
+            raw_diff: raw.map(|r| relative_diff(cached, r)),
+        }
+    }
+}
+
+/// 一个池子的一致性报告
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolConsistency {
+    pub pool_id: String,
+    pub dex_name: String,
+    pub pair: String,
+    /// 缓存条目的 slot 和年龄
+    pub cached_slot: u64,
+    pub cached_age_ms: u128,
+    /// (vault_a, vault_b)；不是 vault 型池子时为 None
+    pub vaults: Option<(String, String)>,
+    /// vault 余额所在的最新 slot
+    pub vault_slot: Option<u64>,
+    pub base: ReserveComparison,
+    pub quote: ReserveComparison,
+}
+
+impl PoolConsistency {
+    /// 缓存储备与 vault 余额的最大相对差（没有 vault 数据时为 None）
+    pub fn max_vault_diff(&self) -> Option<f64> {
+        match (self.base.vault_diff, self.quote.vault_diff) {
+            (Some(base), Some(quote)) => Some(base.max(quote)),
+            _ => None,
+        }
+    }
+}
+
+/// 相对差：|a - b| / max(a, b)，两者都为 0 时为 0
+pub fn relative_diff(a: u64, b: u64) -> f64 {
+    let max = a.max(b);
+    if max == 0 {
+        return 0.0;
+    }
+    a.abs_diff(b) as f64 / max as f64
+}
+
+/// 比较一个池子的缓存储备、vault 余额和池子账户解析结果
+pub fn check_pool(pool: &PoolPrice, vault_reader: &VaultReader, raw_reserves: Option<(u64, u64)>) -> PoolConsistency {
+    let vaults = vault_reader.get_pool_vault_addresses(&pool.pool_id);
+    // 尚未收到数据的 vault（登记时余额初始化为 0）不参与比较
+    let infos = vaults.as_ref().map(|(a, b)| {
+        [a, b].map(|address| vault_reader.get_vault_info(address).filter(|info| info.last_updated > 0))
+    });
+    let [vault_a, vault_b] = infos.unwrap_or([None, None]);
+
+    PoolConsistency {
+        pool_id: pool.pool_id.clone(),
+        dex_name: pool.dex_name.clone(),
+        pair: pool.pair.clone(),
+        cached_slot: pool.slot,
+        cached_age_ms: pool.last_update.elapsed().as_millis(),
+        vault_slot: vault_a.iter().chain(vault_b.iter()).map(|info| info.slot).max(),
+        base: ReserveComparison::new(pool.base_reserve, vault_a.map(|info| info.amount), raw_reserves.map(|r| r.0)),
+        quote: ReserveComparison::new(pool.quote_reserve, vault_b.map(|info| info.amount), raw_reserves.map(|r| r.1)),
+        vaults,
+    }
+}
+
+/// 缓存储备与 vault 余额相对差超过 `max_diff`（比例）的 vault 型池子，按差值从大到小
+pub fn divergent_pools(
+    price_cache: &PriceCache,
+    vault_reader: &VaultReader,
+    raw_reserves: impl Fn(&str) -> Option<(u64, u64)>,
+    max_diff: f64,
+) -> Vec<PoolConsistency> {
+    let mut divergent: Vec<PoolConsistency> = price_cache.get_all_prices()
+        .iter()
+        .filter(|pool| vault_reader.has_pool_vaults(&pool.pool_id))
+        .map(|pool| check_pool(pool, vault_reader, raw_reserves(&pool.pool_id)))
+        .filter(|report| report.max_vault_diff().is_some_and(|diff| diff > max_diff))
+        .collect();
+    divergent.sort_by(|a, b| b.max_vault_diff().partial_cmp(&a.max_vault_diff()).unwrap_or(std::cmp::Ordering::Equal));
+    divergent
+}
+
+/// 后台任务：每隔 `interval` 巡检一次，缓存储备偏离 vault 余额超过 `max_divergence_percent` 的池子打警告日志
+pub fn spawn_auditor(
+    price_cache: Arc<PriceCache>,
+    vault_reader: Arc<VaultReader>,
+    pool_accounts: WsPoolAccounts,
+    interval: Duration,
+    max_divergence_percent: f64,
+    supervisor: &Supervisor,
+) -> JoinHandle<()> {
+    spawn_supervised("vault_auditor", supervisor, move || {
+        let (price_cache, vault_reader, pool_accounts) = (price_cache.clone(), vault_reader.clone(), pool_accounts.clone());
+        async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let divergent = divergent_pools(
+                    &price_cache,
+                    &vault_reader,
+                    |address| pool_accounts.raw_reserves(address),
+                    max_divergence_percent / 100.0,
+                );
+                if divergent.is_empty() {
+                    debug!("🔍 Vault audit: cached reserves match vault balances");
+                }
+                for report in &divergent {
+                    warn!(
+                        "🔍 {} ({}) cached reserves diverge from vault balances by {:.2}%: cached {}/{} @slot {}, vaults {:?}/{:?} @slot {:?}, raw parse {:?}/{:?}",
+                        report.pair, report.pool_id,
+                        report.max_vault_diff().unwrap_or(0.0) * 100.0,
+                        report.base.cached, report.quote.cached, report.cached_slot,
+                        report.base.vault, report.quote.vault, report.vault_slot,
+                        report.base.raw, report.quote.raw,
+                    );
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_account(amount: u64) -> Vec<u8> {
+        let mut data = vec![0u8; 165];
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        data[108] = 1;
+        data
+    }
+
+    #[test]
+    fn test_divergent_pools_compares_cache_with_vaults() {
+        let vault_a = "5Gdp3vUcLnXU8d8kzHSBxgpNiyo3CYXbSq7k5BXNWgfN";
+        let vault_b = "9oZ5dxRzTsvomzJtLHzWvBMHbC7k4PBpzNKVr7yFoXmY";
+        let reader = VaultReader::new();
+        reader.register_pool_vaults("solfi-pool", vault_a, vault_b);
+
+        let cache = PriceCache::new();
+        cache.update_price(PoolPrice {
+            dex_name: "SolFi V2".to_string(),
+            base_reserve: 1_000_000,
+            quote_reserve: 150_000_000,
+            slot: 10,
+            ..PoolPrice::for_test("solfi-pool", "SOL/USDC", 150.0)
+        });
+
+        // vault 尚未收到数据：不报告
+        assert!(divergent_pools(&cache, &reader, |_| None, 0.01).is_empty());
+
+        // 一侧 vault 变化后缓存没有重算：偏离 50%
+        reader.update_vault(vault_a, &token_account(1_000_000), 11).unwrap();
+        reader.update_vault(vault_b, &token_account(300_000_000), 12).unwrap();
+        let divergent = divergent_pools(&cache, &reader, |_| Some((1_000_000, 150_000_000)), 0.01);
+        assert_eq!(divergent.len(), 1);
+        let report = &divergent[0];
+        assert_eq!(report.max_vault_diff(), Some(0.5));
+        assert_eq!(report.vault_slot, Some(12));
+        assert_eq!(report.quote, ReserveComparison {
+            cached: 150_000_000,
+            vault: Some(300_000_000),
+            raw: Some(150_000_000),
+            vault_diff: Some(0.5),
+            raw_diff: Some(0.0),
+        });
+        assert!(divergent_pools(&cache, &reader, |_| None, 0.6).is_empty());
+    }
+}
@@ -10,12 +10,14 @@
  * 总共增加 43% 的套利机会覆盖率
  */
 
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use serde::Serialize;
 use solana_sdk::pubkey::Pubkey;
-use std::collections::HashMap;
 use std::str::FromStr;
-use tracing::debug;
-use spl_token::state::Account as SplTokenAccount;
-use solana_program::program_pack::Pack;
+use tracing::{debug, warn};
+
+use crate::deserializers::spl_token::{parse_token_account, TokenProgram, TOKEN_ACCOUNT_LEN};
 
 /// Vault 信息
 #[derive(Debug, Clone)]
@@ -27,19 +29,29 @@ pub struct VaultInfo {
     pub amount: u64,
     /// 最后更新时间戳
     pub last_updated: u64,
+    /// 最后更新的 slot（0 = 尚未收到数据或来源没有 slot）
+    pub slot: u64,
+    /// 🧊 账户被冻结（amount 按 0 计入储备）
+    pub frozen: bool,
 }
 
 /// VaultReader - 管理所有 vault 账户的余额
+///
+/// 内部使用 DashMap，所有方法只需 `&self`：调用方共享 `Arc<VaultReader>`，
+/// 不会在持锁期间再去查其他映射（每个方法内部只短暂持有分片锁）。
 pub struct VaultReader {
     /// 存储所有 vault 的余额
     /// Key: vault 地址字符串
     /// Value: VaultInfo
-    vaults: HashMap<String, VaultInfo>,
+    vaults: DashMap<String, VaultInfo>,
     
     /// 池子到 vault 的映射
     /// Key: pool 地址
     /// Value: (vault_a 地址, vault_b 地址)
-    pool_to_vaults: HashMap<String, (String, String)>,
+    pool_to_vaults: DashMap<String, (String, String)>,
+    
+    /// vault 到池子的反向索引（与 pool_to_vaults 同步维护）
+    vault_to_pools: DashMap<String, Vec<String>>,
 }
 
 #[allow(dead_code)]
@@ -47,8 +59,9 @@ impl VaultReader {
     /// 创建新的 VaultReader
     pub fn new() -> Self {
         Self {
-            vaults: HashMap::new(),
-            pool_to_vaults: HashMap::new(),
+            vaults: DashMap::new(),
+            pool_to_vaults: DashMap::new(),
+            vault_to_pools: DashMap::new(),
         }
     }
     
@@ -59,16 +72,32 @@ impl VaultReader {
     /// * `vault_a` - Token A 的 vault 地址
     /// * `vault_b` - Token B 的 vault 地址
     pub fn register_pool_vaults(
-        &mut self,
+        &self,
         pool_address: &str,
         vault_a: &str,
         vault_b: &str,
     ) {
-        self.pool_to_vaults.insert(
+        let previous = self.pool_to_vaults.insert(
             pool_address.to_string(),
             (vault_a.to_string(), vault_b.to_string())
         );
         
+        // 重新登记时先从旧 vault 的反向索引中移除
+        if let Some((old_a, old_b)) = previous {
+            for vault in [old_a, old_b] {
+                if let Some(mut pools) = self.vault_to_pools.get_mut(&vault) {
+                    pools.retain(|p| p != pool_address);
+                }
+                self.vault_to_pools.remove_if(&vault, |_, pools| pools.is_empty());
+            }
+        }
+        for vault in [vault_a, vault_b] {
+            let mut pools = self.vault_to_pools.entry(vault.to_string()).or_default();
+            if !pools.iter().any(|p| p == pool_address) {
+                pools.push(pool_address.to_string());
+            }
+        }
+        
         // 初始化 vault 信息
         if let Ok(pubkey) = Pubkey::from_str(vault_a) {
             self.vaults.insert(
@@ -77,6 +106,8 @@ impl VaultReader {
                     address: pubkey,
                     amount: 0,
                     last_updated: 0,
+                    slot: 0,
+                    frozen: false,
                 }
             );
         }
@@ -88,6 +119,8 @@ impl VaultReader {
                     address: pubkey,
                     amount: 0,
                     last_updated: 0,
+                    slot: 0,
+                    frozen: false,
                 }
             );
         }
@@ -97,12 +130,13 @@ impl VaultReader {
     /// 
     /// # Arguments
     /// * `vault_address` - Vault 地址
-    /// * `data` - SPL Token 账户数据（165 字节）
+    /// * `data` - SPL Token / Token-2022 账户数据（165 字节，Token-2022 带扩展时更长）
+    /// * `slot` - 数据所在的 slot
     /// 
     /// # Returns
-    /// * `Ok(amount)` - 更新成功，返回余额
+    /// * `Ok(amount)` - 更新成功，返回有效余额（冻结账户为 0）
     /// * `Err(error)` - 解析失败
-    pub fn update_vault(&mut self, vault_address: &str, data: &[u8]) -> Result<u64, String> {
+    pub fn update_vault(&self, vault_address: &str, data: &[u8], slot: u64) -> Result<u64, String> {
         // 🔥 修复：支持多种数据长度
         // SPL Token 账户: 165 字节
         // SPL Token-2022 with Extensions: 165+ 字节
@@ -113,65 +147,76 @@ impl VaultReader {
             // 82-byte Mint accounts should have been filtered earlier
             debug!(vault = vault_address, len = data.len(), "Received Mint account data, skipping");
             return Ok(0);
-        } else if data.len() < 165 {
+        } else if data.len() < TOKEN_ACCOUNT_LEN {
             return Err(format!(
-                "Invalid token account size: expected >= 165 bytes, got {}",
+                "Invalid token account size: expected >= {} bytes, got {}",
+                TOKEN_ACCOUNT_LEN,
                 data.len()
             ));
         }
         
-        // ✅ FIX: SPL Token accounts use Pack trait, NOT Borsh!
-        // Standard SPL Token: 165 bytes
-        // Token-2022 with extensions: 165-400+ bytes (extensions are appended after base 165 bytes)
-        // We only need the first 165 bytes for the base Account structure
-        let base_data = if data.len() > 165 {
+        // SPL Token（165 字节）和 Token-2022（165 字节 + 扩展 TLV）共用基础布局，
+        // 余额只取基础布局中的 amount，与尾部扩展无关
+        let parsed = parse_token_account(data)
+            .map_err(|e| format!("Failed to parse token account: {}", e))?;
+        
+        if parsed.program == TokenProgram::Token2022 {
             debug!(
-                vault = vault_address, 
-                total_len = data.len(), 
-                extensions_len = data.len() - 165,
-                "Token-2022 account with extensions detected, using base 165 bytes"
+                vault = vault_address,
+                total_len = data.len(),
+                extensions = ?parsed.extensions,
+                withheld_transfer_fee = ?parsed.withheld_transfer_fee,
+                "Token-2022 vault with extensions"
             );
-            &data[0..165]
-        } else {
-            data
-        };
+        }
         
-        // ⭐ CRITICAL FIX: Use spl_token::state::Account::unpack() instead of Borsh
-        // SPL Token accounts use the Pack trait for serialization, not Borsh
-        let token_account = SplTokenAccount::unpack(base_data)
-            .map_err(|e| format!("Failed to unpack SPL Token account: {:?}", e))?;
+        // 🧊 冻结的 vault 无法转出，按 0 流动性处理
+        let frozen = parsed.account.is_frozen();
+        let amount = parsed.effective_amount();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
         
-        // 更新 vault 信息
-        if let Some(vault_info) = self.vaults.get_mut(vault_address) {
-            vault_info.amount = token_account.amount;
-            vault_info.last_updated = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
-            
-            Ok(token_account.amount)
-        } else {
-            // Vault 未注册，但我们仍然更新它
-            if let Ok(pubkey) = Pubkey::from_str(vault_address) {
-                let amount = token_account.amount;
-                self.vaults.insert(
-                    vault_address.to_string(),
-                    VaultInfo {
+        match self.vaults.entry(vault_address.to_string()) {
+            Entry::Occupied(mut entry) => {
+                let vault_info = entry.get_mut();
+                if frozen && !vault_info.frozen {
+                    warn!(vault = vault_address, balance = parsed.account.amount, "Vault account is frozen, treating as zero liquidity");
+                }
+                vault_info.amount = amount;
+                vault_info.frozen = frozen;
+                vault_info.last_updated = now;
+                vault_info.slot = slot;
+                // 📐 vault → mint（HumidiFi 等池子账户不存 mint，按 vault 解析 decimals）
+                crate::mint_decimals_cache::register_vault_mint(vault_info.address, parsed.account.mint);
+                
+                Ok(amount)
+            }
+            Entry::Vacant(entry) => {
+                // Vault 未注册，但我们仍然更新它
+                if let Ok(pubkey) = Pubkey::from_str(vault_address) {
+                    crate::mint_decimals_cache::register_vault_mint(pubkey, parsed.account.mint);
+                    entry.insert(VaultInfo {
                         address: pubkey,
                         amount,
-                        last_updated: std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs(),
-                    }
-                );
-                Ok(amount)
-            } else {
-                Err(format!("Invalid vault address: {}", vault_address))
+                        last_updated: now,
+                        slot,
+                        frozen,
+                    });
+                    Ok(amount)
+                } else {
+                    Err(format!("Invalid vault address: {}", vault_address))
+                }
             }
         }
     }
     
+    /// 🧊 vault 是否被冻结
+    pub fn is_vault_frozen(&self, vault_address: &str) -> bool {
+        self.vaults.get(vault_address).is_some_and(|v| v.frozen)
+    }
+    
     /// 获取池子的储备量（从 vault 读取）
     /// 
     /// # Arguments
@@ -181,19 +226,48 @@ impl VaultReader {
     /// * `Some((reserve_a, reserve_b))` - 成功读取
     /// * `None` - 池子未注册或 vault 数据不可用
     pub fn get_pool_reserves(&self, pool_address: &str) -> Option<(u64, u64)> {
-        let (vault_a, vault_b) = self.pool_to_vaults.get(pool_address)?;
+        let (vault_a, vault_b) = self.pool_to_vaults.get(pool_address)?.clone();
         
-        let amount_a = self.vaults.get(vault_a)?.amount;
-        let amount_b = self.vaults.get(vault_b)?.amount;
+        let amount_a = self.vaults.get(&vault_a)?.amount;
+        let amount_b = self.vaults.get(&vault_b)?.amount;
         
         Some((amount_a, amount_b))
     }
     
+    /// 两个 vault 都已收到过数据时的储备量（登记时余额初始化为 0，尚未更新的 vault 不算数）
+    pub fn get_received_pool_reserves(&self, pool_address: &str) -> Option<(u64, u64)> {
+        let (vault_a, vault_b) = self.pool_to_vaults.get(pool_address)?.clone();
+        let received = |vault: &str| self.vaults.get(vault).filter(|v| v.last_updated > 0).map(|v| v.amount);
+        Some((received(&vault_a)?, received(&vault_b)?))
+    }
+    
     /// 获取单个 vault 的余额
     pub fn get_vault_amount(&self, vault_address: &str) -> Option<u64> {
         self.vaults.get(vault_address).map(|v| v.amount)
     }
     
+    /// 获取单个 vault 的完整信息
+    pub fn get_vault_info(&self, vault_address: &str) -> Option<VaultInfo> {
+        self.vaults.get(vault_address).map(|v| v.clone())
+    }
+    
+    /// 🔍 所有已登记 vault 的余额与所属池子（GET /vaults，按 vault 地址排序）
+    pub fn snapshot(&self) -> Vec<VaultSnapshot> {
+        let mut vaults: Vec<VaultSnapshot> = self.vaults
+            .iter()
+            .map(|entry| VaultSnapshot {
+                address: entry.key().clone(),
+                amount: entry.amount,
+                frozen: entry.frozen,
+                last_updated: entry.last_updated,
+                slot: entry.slot,
+                pools: self.get_pools_for_vault(entry.key()),
+            })
+            .collect();
+        vaults.sort_by(|a, b| a.address.cmp(&b.address));
+        vaults
+    }
+    
     /// 检查池子是否有 vault 配置
     pub fn has_pool_vaults(&self, pool_address: &str) -> bool {
         self.pool_to_vaults.contains_key(pool_address)
@@ -206,12 +280,58 @@ impl VaultReader {
     
     /// 获取所有 vault 地址（用于订阅）
     pub fn get_all_vault_addresses(&self) -> Vec<String> {
-        self.vaults.keys().cloned().collect()
+        self.vaults.iter().map(|entry| entry.key().clone()).collect()
+    }
+    
+    /// 获取所有 vault 及其所属池子（用于重连后重新订阅）
+    /// 
+    /// 多个池子共用的 vault 只返回一次；按池子、vault 地址排序，保证重放顺序稳定
+    pub fn vault_subscriptions(&self) -> Vec<(String, String)> {
+        let mut pairs: Vec<(String, String)> = self.pool_to_vaults
+            .iter()
+            .flat_map(|entry| {
+                let pool = entry.key();
+                let (vault_a, vault_b) = entry.value();
+                [(vault_a.clone(), pool.clone()), (vault_b.clone(), pool.clone())]
+            })
+            .collect();
+        pairs.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        
+        let mut seen = std::collections::HashSet::new();
+        pairs.retain(|(vault, _)| seen.insert(vault.clone()));
+        pairs
+    }
+    
+    /// 注销池子的 vault（池子从配置中删除时调用）
+    /// 
+    /// 返回不再被任何池子使用、已一并移除的 vault 地址（调用方需要取消订阅）
+    pub fn deregister_pool(&self, pool_address: &str) -> Vec<String> {
+        let (vault_a, vault_b) = match self.pool_to_vaults.remove(pool_address) {
+            Some((_, vaults)) => vaults,
+            None => return Vec::new(),
+        };
+        
+        let mut orphaned = Vec::new();
+        for vault in [vault_a, vault_b] {
+            if orphaned.contains(&vault) {
+                continue;
+            }
+            // 在反向索引条目上原子地移除池子；最后一个池子移除时连同余额一起删除
+            if let Entry::Occupied(mut entry) = self.vault_to_pools.entry(vault.clone()) {
+                entry.get_mut().retain(|p| p != pool_address);
+                if entry.get().is_empty() {
+                    entry.remove();
+                    self.vaults.remove(&vault);
+                    orphaned.push(vault);
+                }
+            }
+        }
+        orphaned
     }
     
     /// 获取池子关联的 vault 地址
     pub fn get_pool_vault_addresses(&self, pool_address: &str) -> Option<(String, String)> {
-        self.pool_to_vaults.get(pool_address).cloned()
+        self.pool_to_vaults.get(pool_address).map(|entry| entry.clone())
     }
     
     /// 获取使用特定 vault 的所有池子地址
@@ -222,13 +342,10 @@ impl VaultReader {
     /// # Returns
     /// 使用该 vault 的所有池子地址列表
     pub fn get_pools_for_vault(&self, vault_address: &str) -> Vec<String> {
-        self.pool_to_vaults
-            .iter()
-            .filter(|(_, (vault_a, vault_b))| {
-                vault_a == vault_address || vault_b == vault_address
-            })
-            .map(|(pool_addr, _)| pool_addr.clone())
-            .collect()
+        self.vault_to_pools
+            .get(vault_address)
+            .map(|pools| pools.clone())
+            .unwrap_or_default()
     }
     
     /// 获取统计信息
@@ -236,11 +353,24 @@ impl VaultReader {
         VaultReaderStats {
             total_pools: self.pool_to_vaults.len(),
             total_vaults: self.vaults.len(),
-            vaults_with_data: self.vaults.values().filter(|v| v.amount > 0).count(),
+            vaults_with_data: self.vaults.iter().filter(|v| v.amount > 0).count(),
         }
     }
 }
 
+/// 单个 vault 的状态（GET /vaults）
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultSnapshot {
+    pub address: String,
+    pub amount: u64,
+    pub frozen: bool,
+    /// 最后更新时间（Unix 秒，0 = 尚未收到数据）
+    pub last_updated: u64,
+    pub slot: u64,
+    /// 使用该 vault 的池子地址
+    pub pools: Vec<String>,
+}
+
 /// VaultReader 统计信息
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -256,38 +386,110 @@ impl Default for VaultReader {
     }
 }
 
+/// 🌐 vault 订阅登记表（vault / DLMM bin array 共用的动态订阅通道）
+///
+/// request_id -> 地址（等待服务器确认），确认后转为 subscription_id -> 地址
+#[derive(Default)]
+pub struct VaultSubscriptions {
+    pending: DashMap<u64, String>,
+    confirmed: DashMap<u64, String>,
+}
+
+impl VaultSubscriptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 发送订阅请求前登记
+    pub fn add_pending(&self, request_id: u64, address: &str) {
+        self.pending.insert(request_id, address.to_string());
+    }
+
+    /// 订阅请求发送失败时撤销
+    pub fn cancel_pending(&self, request_id: u64) {
+        self.pending.remove(&request_id);
+    }
+
+    /// 服务器确认订阅：从 pending 转到 confirmed，返回地址（不是 vault 请求时返回 None）
+    pub fn confirm(&self, request_id: u64, subscription_id: u64) -> Option<String> {
+        let (_, address) = self.pending.remove(&request_id)?;
+        self.confirmed.insert(subscription_id, address.clone());
+        Some(address)
+    }
+
+    /// subscription_id 对应的地址
+    pub fn address(&self, subscription_id: u64) -> Option<String> {
+        self.confirmed.get(&subscription_id).map(|entry| entry.clone())
+    }
+
+    /// 所有已确认的 subscription_id
+    pub fn subscription_ids(&self) -> Vec<u64> {
+        self.confirmed.iter().map(|entry| *entry.key()).collect()
+    }
+
+    /// 移除这些地址的订阅（含等待确认的），返回需要退订的 subscription_id
+    pub fn remove_addresses(&self, addresses: &[String]) -> Vec<u64> {
+        let mut removed = Vec::new();
+        self.confirmed.retain(|id, address| {
+            let keep = !addresses.contains(address);
+            if !keep {
+                removed.push(*id);
+            }
+            keep
+        });
+        self.pending.retain(|_, address| !addresses.contains(address));
+        removed
+    }
+
+    /// 已确认订阅失效（退订完成 / 连接断开）
+    pub fn clear_confirmed(&self) {
+        self.confirmed.clear();
+    }
+
+    /// 新连接：旧连接的 request_id / subscription_id 全部失效
+    pub fn clear(&self) {
+        self.confirmed.clear();
+        self.pending.clear();
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn confirmed_len(&self) -> usize {
+        self.confirmed.len()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     
     #[test]
     fn test_vault_reader() {
-        let mut reader = VaultReader::new();
+        let reader = VaultReader::new();
+        // vault 地址必须是合法 Pubkey，否则不会登记余额
+        let vault_a = Pubkey::new_unique().to_string();
+        let vault_b = Pubkey::new_unique().to_string();
         
         // 注册池子
-        reader.register_pool_vaults(
-            "pool123",
-            "vaultA456",
-            "vaultB789"
-        );
+        reader.register_pool_vaults("pool123", &vault_a, &vault_b);
         
         assert!(reader.has_pool_vaults("pool123"));
         assert!(!reader.has_pool_vaults("pool999"));
         
-        assert!(reader.is_vault_account("vaultA456"));
-        assert!(reader.is_vault_account("vaultB789"));
+        assert!(reader.is_vault_account(&vault_a));
+        assert!(reader.is_vault_account(&vault_b));
         assert!(!reader.is_vault_account("unknown"));
     }
     
     #[test]
     fn test_get_pool_reserves() {
-        let mut reader = VaultReader::new();
+        let reader = VaultReader::new();
+        let vault_a = Pubkey::new_unique().to_string();
+        let vault_b = Pubkey::new_unique().to_string();
         
-        reader.register_pool_vaults(
-            "pool123",
-            "vaultA456",
-            "vaultB789"
-        );
+        reader.register_pool_vaults("pool123", &vault_a, &vault_b);
         
         // 模拟更新 vault（实际使用需要真实的 token account 数据）
         // 这里只测试逻辑
@@ -296,7 +498,86 @@ mod tests {
         // 初始应该是 (0, 0)
         assert_eq!(reserves, Some((0, 0)));
     }
+    
+    #[test]
+    fn test_update_vault_token_2022_and_frozen() {
+        let reader = VaultReader::new();
+        let vault_a = "5Gdp3vUcLnXU8d8kzHSBxgpNiyo3CYXbSq7k5BXNWgfN";
+        let vault_b = "9oZ5dxRzTsvomzJtLHzWvBMHbC7k4PBpzNKVr7yFoXmY";
+        reader.register_pool_vaults("pool123", vault_a, vault_b);
+        
+        // Token-2022：基础布局 + AccountType + TransferFeeAmount 扩展
+        let mut token_2022 = vec![0u8; 165];
+        token_2022[64..72].copy_from_slice(&2_500u64.to_le_bytes());
+        token_2022[108] = 1;
+        token_2022.push(2);
+        token_2022.extend_from_slice(&2u16.to_le_bytes());
+        token_2022.extend_from_slice(&8u16.to_le_bytes());
+        token_2022.extend_from_slice(&10u64.to_le_bytes());
+        assert_eq!(reader.update_vault(vault_a, &token_2022, 100), Ok(2_500));
+        
+        // 冻结账户：余额按 0 计入储备
+        let mut frozen = vec![0u8; 165];
+        frozen[64..72].copy_from_slice(&9_000u64.to_le_bytes());
+        frozen[108] = 2;
+        assert_eq!(reader.update_vault(vault_b, &frozen, 101), Ok(0));
+        assert!(reader.is_vault_frozen(vault_b));
+        assert!(!reader.is_vault_frozen(vault_a));
+        assert_eq!(reader.get_pool_reserves("pool123"), Some((2_500, 0)));
+        
+        let snapshot = reader.snapshot();
+        assert_eq!(snapshot.iter().map(|v| v.slot).collect::<Vec<_>>(), vec![100, 101]);
+        assert!(snapshot.iter().all(|v| v.pools == vec!["pool123".to_string()]));
+    }
+    
+    #[test]
+    fn test_deregister_pool_keeps_shared_vaults() {
+        let reader = VaultReader::new();
+        let shared = "5Gdp3vUcLnXU8d8kzHSBxgpNiyo3CYXbSq7k5BXNWgfN";
+        let own_a = "9oZ5dxRzTsvomzJtLHzWvBMHbC7k4PBpzNKVr7yFoXmY";
+        let own_b = "EUuUbDcafPrmVTD5M6qoJAoyyNbihBhugADAxRMn5he9";
+        reader.register_pool_vaults("pool_a", shared, own_a);
+        reader.register_pool_vaults("pool_b", shared, own_b);
+        
+        assert_eq!(reader.deregister_pool("pool_a"), vec![own_a.to_string()]);
+        assert!(reader.is_vault_account(shared));
+        assert!(!reader.is_vault_account(own_a));
+        
+        let mut orphaned = reader.deregister_pool("pool_b");
+        orphaned.sort();
+        assert_eq!(orphaned, vec![shared.to_string(), own_b.to_string()]);
+        assert_eq!(reader.get_stats().total_vaults, 0);
+        assert!(reader.deregister_pool("pool_b").is_empty());
+    }
+    
+    #[test]
+    fn test_reregister_moves_reverse_index_and_subscription_registry() {
+        let reader = VaultReader::new();
+        let vault_a = "5Gdp3vUcLnXU8d8kzHSBxgpNiyo3CYXbSq7k5BXNWgfN";
+        let vault_b = "9oZ5dxRzTsvomzJtLHzWvBMHbC7k4PBpzNKVr7yFoXmY";
+        let vault_c = "EUuUbDcafPrmVTD5M6qoJAoyyNbihBhugADAxRMn5he9";
+        reader.register_pool_vaults("pool_a", vault_a, vault_b);
+        reader.register_pool_vaults("pool_a", vault_a, vault_b);
+        assert_eq!(reader.get_pools_for_vault(vault_a), vec!["pool_a".to_string()]);
+        
+        // vault B 换成 vault C：反向索引跟着移动
+        reader.register_pool_vaults("pool_a", vault_a, vault_c);
+        assert!(reader.get_pools_for_vault(vault_b).is_empty());
+        assert_eq!(reader.get_pools_for_vault(vault_c), vec!["pool_a".to_string()]);
+        
+        let subscriptions = VaultSubscriptions::new();
+        subscriptions.add_pending(10001, vault_a);
+        subscriptions.add_pending(10002, vault_c);
+        subscriptions.add_pending(10003, vault_b);
+        subscriptions.cancel_pending(10003);
+        assert_eq!(subscriptions.confirm(10001, 777), Some(vault_a.to_string()));
+        assert_eq!(subscriptions.confirm(10003, 778), None);
+        assert_eq!(subscriptions.address(777), Some(vault_a.to_string()));
+        
+        // 注销池子：已确认的返回 subscription_id，等待确认的一并移除
+        let orphaned = reader.deregister_pool("pool_a");
+        assert_eq!(subscriptions.remove_addresses(&orphaned), vec![777]);
+        assert_eq!(subscriptions.pending_len(), 0);
+        assert_eq!(subscriptions.confirmed_len(), 0);
+    }
 }
-
-
-
@@ -0,0 +1,187 @@
+/*!
+ * 🪝 最小 webhook 客户端
+ *
+ * 只支持 `POST application/json` 和读取 JSON 的 `GET`：http 直连，https 走 native-tls。
+ * 告警、参考价格等调用方在后台任务里调用，失败只记录，不影响主流程。
+ */
+
+use anyhow::{anyhow, bail, Context, Result};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{timeout, Duration};
+use url::Url;
+
+/// 请求超时（连接 + 发送 + 读取响应）
+pub const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// GET 响应的大小上限（含响应头）
+pub const MAX_RESPONSE_BYTES: u64 = 1024 * 1024;
+
+/// POST JSON，返回 HTTP 状态码（非 2xx 视为错误）
+pub async fn post_json(url: &str, body: &str) -> Result<u16> {
+    timeout(WEBHOOK_TIMEOUT, post_json_inner(url, body))
+        .await
+        .map_err(|_| anyhow!("Webhook request to {} timed out", url))?
+}
+
+async fn post_json_inner(url: &str, body: &str) -> Result<u16> {
+    let parsed = Url::parse(url).context("Invalid webhook URL")?;
+    let host = parsed.host_str().ok_or_else(|| anyhow!("Missing host in webhook URL"))?;
+    let request = build_request(&parsed, host, body);
+
+    let response = exchange(&parsed, &request, false).await?;
+    let status = parse_status(&response)?;
+    if !(200..300).contains(&status) {
+        bail!("Webhook {} responded with HTTP {}", host, status);
+    }
+    Ok(status)
+}
+
+/// GET，返回响应体（非 2xx 视为错误）
+///
+/// 用 HTTP/1.0 请求，服务端不会返回 chunked 编码，读到连接关闭即为完整响应体。
+pub async fn get_json(url: &str) -> Result<String> {
+    timeout(WEBHOOK_TIMEOUT, get_json_inner(url))
+        .await
+        .map_err(|_| anyhow!("GET {} timed out", url))?
+}
+
+async fn get_json_inner(url: &str) -> Result<String> {
+    let parsed = Url::parse(url).context("Invalid URL")?;
+    let host = parsed.host_str().ok_or_else(|| anyhow!("Missing host in URL"))?;
+    let request = format!(
+        "GET {} HTTP/1.0\r\n\
+         Host: {}\r\n\
+         Accept: application/json\r\n\
+         Connection: close\r\n\r\n",
+        request_path(&parsed), host
+    );
+
+    let response = exchange(&parsed, &request, true).await?;
+    let status = parse_status(&response)?;
+    if !(200..300).contains(&status) {
+        bail!("GET {} responded with HTTP {}", host, status);
+    }
+    let (_, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| anyhow!("Malformed response from {}", host))?;
+    Ok(body.to_string())
+}
+
+/// 连接（https 走 TLS）、发送请求并读取响应；`read_to_end` 为 false 时只读第一块（状态行）
+async fn exchange(url: &Url, request: &str, read_to_end: bool) -> Result<String> {
+    let host = url.host_str().ok_or_else(|| anyhow!("Missing host in URL"))?;
+    let port = url.port_or_known_default().ok_or_else(|| anyhow!("Cannot determine port"))?;
+
+    let stream = TcpStream::connect((host, port))
+        .await
+        .with_context(|| format!("Failed to connect to {}:{}", host, port))?;
+
+    match url.scheme() {
+        "http" => send(stream, request, read_to_end).await,
+        "https" => {
+            let connector = tokio_native_tls::TlsConnector::from(
+                native_tls::TlsConnector::new().context("Failed to create TLS connector")?,
+            );
+            let tls = connector.connect(host, stream).await.context("TLS handshake failed")?;
+            send(tls, request, read_to_end).await
+        }
+        scheme => bail!("Unsupported scheme: {}", scheme),
+    }
+}
+
+fn request_path(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+fn build_request(url: &Url, host: &str, body: &str) -> String {
+    format!(
+        "POST {} HTTP/1.1\r\n\
+         Host: {}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        request_path(url), host, body.len(), body
+    )
+}
+
+async fn send<S>(mut stream: S, request: &str, read_to_end: bool) -> Result<String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.write_all(request.as_bytes()).await.context("Failed to send request")?;
+    stream.flush().await?;
+
+    if read_to_end {
+        let mut buffer = Vec::new();
+        (&mut stream).take(MAX_RESPONSE_BYTES).read_to_end(&mut buffer).await.context("Failed to read response")?;
+        return Ok(String::from_utf8_lossy(&buffer).into_owned());
+    }
+
+    // 只需要状态行
+    let mut buffer = vec![0u8; 1024];
+    let n = stream.read(&mut buffer).await.context("Failed to read webhook response")?;
+    Ok(String::from_utf8_lossy(&buffer[..n]).into_owned())
+}
+
+/// `HTTP/1.1 204 No Content` -> 204
+fn parse_status(response: &str) -> Result<u16> {
+    response
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| anyhow!("Malformed webhook response"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_post_json_sends_body_and_reads_status() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0u8; 4096];
+            let n = socket.read(&mut buffer).await.unwrap();
+            socket.write_all(b"HTTP/1.1 204 No Content\r\n\r\n").await.unwrap();
+            String::from_utf8_lossy(&buffer[..n]).into_owned()
+        });
+
+        let status = post_json(&format!("http://{}/hooks/spread?team=ops", addr), r#"{"pair":"SOL/USDC"}"#)
+            .await
+            .unwrap();
+        assert_eq!(status, 204);
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /hooks/spread?team=ops HTTP/1.1\r\n"));
+        assert!(request.contains("Content-Length: 19\r\n"));
+        assert!(request.ends_with(r#"{"pair":"SOL/USDC"}"#));
+    }
+
+    #[tokio::test]
+    async fn test_get_json_returns_body() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0u8; 4096];
+            let n = socket.read(&mut buffer).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n{\"price\":\"150.25\"}")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&buffer[..n]).into_owned()
+        });
+
+        let body = get_json(&format!("http://{}/v2/price?ids=sol", addr)).await.unwrap();
+        assert_eq!(body, r#"{"price":"150.25"}"#);
+        assert!(server.await.unwrap().starts_with("GET /v2/price?ids=sol HTTP/1.0\r\n"));
+    }
+}
@@ -1,59 +1,308 @@
 use anyhow::{Context, Result};
 use dashmap::DashMap;
-use futures_util::{SinkExt, StreamExt, future::join_all};
+use futures_util::{SinkExt, StreamExt};
 use serde_json::json;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::Instant;
 use tokio::net::TcpStream;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use tokio::time::{sleep, Duration};
 use tokio_tungstenite::{
-    tungstenite::protocol::Message, MaybeTlsStream, WebSocketStream,
+    tungstenite::{protocol::Message, Error as WsError}, MaybeTlsStream, WebSocketStream,
 };
 use tracing::{info, warn, error, debug};
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
 
-use crate::config::{PoolConfig, ProxyConfig};
+use crate::account_subscription::{self, AccountEncoding, Commitment, MalformedMessage};
+use crate::circuit_breaker::BreakerEvent;
+use crate::config::{priority_order, PoolConfig, ProxyConfig};
 use crate::coordinator::PriceChangeEvent; // 🔥 Coordinator事件
-use crate::dex_interface::DexPool;
+use crate::dex_interface::{ActivityStatus, DexError, DexPool};
+use crate::deserializers::spl_token;
+use crate::endpoint_pool::{rpc_url_for, EndpointPool};
 use crate::error_tracker::ErrorTracker;
+use crate::health::WsHeartbeat;
+use crate::latency_budget::IngestTiming;
 use crate::metrics::MetricsCollector;
-use crate::pool_factory::PoolFactory;
+use crate::pool_data_cache::PoolDataCache;
+use crate::pool_factory::{OwnerCheck, PoolFactory};
+use crate::pool_initializer::{fetch_accounts_batched, BatchedAccounts};
+use crate::pool_reload::{diff_pools, PoolDiff};
 use crate::pool_stats::PoolStatsCollector; // 🔥 池子统计收集器
+use crate::pool_update_log::PoolUpdateRecorder;
 use crate::price_cache::{PoolPrice, PriceCache};
+use crate::prometheus::CacheSize;
 use crate::proxy;
-use crate::vault_reader::VaultReader;
+use crate::reconnect_backoff::{BackoffPolicy, ReconnectBackoff};
+use crate::rpc_manager::{RpcHandle, RpcManager};
+use crate::sharding::ShardRing;
+use crate::supervisor::{spawn_supervised, Supervisor};
+use crate::vault_reader::{VaultReader, VaultSubscriptions};
 
 #[allow(dead_code)]
 type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
 
+/// 集中流动性池子（Raydium CLMM / PancakeSwap / Orca Whirlpool）：价格只能来自 sqrt_price，不能用 vault 余额比例代替
+fn is_concentrated_liquidity(dex_name: &str) -> bool {
+    dex_name.contains("CLMM") || dex_name.contains("PancakeSwap") || dex_name.contains("Whirlpool")
+        || dex_name.contains("Concentrated")
+}
+
 /// 订阅请求类型
 #[derive(Debug, Clone)]
 pub enum SubscriptionRequest {
-    VaultAccount { address: String, pool_name: String },
+    VaultAccount { address: String, pool_name: String, commitment: Commitment },  // 📡 跟随父池子的 commitment
+    PoolAccount { pool: PoolConfig },            // ♻️ 热重载新增的池子
+    Unsubscribe { subscription_ids: Vec<u64> },  // ♻️ 热重载删除的池子 / vault
+}
+
+/// 🔀 一条 WebSocket 连接（分片）的订阅状态
+///
+/// 服务器分配的 subscription_id 只在所属连接内有效，所以订阅登记按连接隔离；
+/// vault / bin array / tick array 订阅跟随父池子所在的连接。
+struct ConnectionShard {
+    index: usize,
+    shard_count: usize,
+    endpoint: AtomicUsize, // 🔀 本连接所用的端点（只在本连接断开时切换）
+    subscription_map: Mutex<HashMap<u64, PoolConfig>>,
+    pool_pending_map: Mutex<HashMap<u64, PoolConfig>>, // ♻️ request_id -> 热重载新增的池子（等待确认）
+    vault_subscriptions: VaultSubscriptions, // 🌐 vault 订阅登记（request_id / subscription_id -> vault地址）
+    subscription_tx: Mutex<Option<mpsc::UnboundedSender<SubscriptionRequest>>>, // 🌐 动态订阅channel（连接期间有效）
+    last_notification: Mutex<HashMap<u64, Instant>>, // 🔁 池子 subscription_id -> 最近一次通知（确认时开始计时）
+    unknown_notifications: Mutex<HashMap<u64, u32>>, // 🧹 未知 subscription_id -> 收到的通知数
+}
+
+impl ConnectionShard {
+    fn new(index: usize, shard_count: usize, endpoint: usize) -> Self {
+        Self {
+            index,
+            shard_count,
+            endpoint: AtomicUsize::new(endpoint),
+            subscription_map: Mutex::new(HashMap::new()),
+            pool_pending_map: Mutex::new(HashMap::new()),
+            vault_subscriptions: VaultSubscriptions::new(),
+            subscription_tx: Mutex::new(None),
+            last_notification: Mutex::new(HashMap::new()),
+            unknown_notifications: Mutex::new(HashMap::new()),
+        }
+    }
+    
+    /// 🔄 连接断开后旧的 subscription_id 全部失效
+    fn clear(&self) {
+        self.subscription_map.lock().unwrap().clear();
+        self.vault_subscriptions.clear();
+        self.pool_pending_map.lock().unwrap().clear();
+        self.last_notification.lock().unwrap().clear();
+        self.unknown_notifications.lock().unwrap().clear();
+    }
+    
+    /// 🔁 记录池子订阅收到通知（或刚确认）
+    fn touch(&self, subscription_id: u64) {
+        self.last_notification.lock().unwrap().insert(subscription_id, Instant::now());
+    }
+    
+    /// 🧹 记录一条未知 subscription_id 的通知，返回该 id 累计的通知数
+    fn record_unknown(&self, subscription_id: u64) -> u32 {
+        let mut unknown = self.unknown_notifications.lock().unwrap();
+        let count = unknown.entry(subscription_id).or_insert(0);
+        *count = count.saturating_add(1);
+        *count
+    }
+    
+    /// 🔁 超过 `silent_for` 没有通知的池子订阅
+    ///
+    /// 只有本连接在这段时间内有其他池子收到通知时才返回（整条连接都安静说明是连接问题，交给重连处理）
+    fn silent_subscriptions(&self, silent_for: Duration) -> Vec<(u64, PoolConfig)> {
+        let last_notification = self.last_notification.lock().unwrap();
+        let connection_active = last_notification.values().any(|at| at.elapsed() < silent_for);
+        if !connection_active {
+            return Vec::new();
+        }
+        self.subscription_map.lock().unwrap()
+            .iter()
+            .filter(|(id, _)| last_notification.get(id).is_none_or(|at| at.elapsed() >= silent_for))
+            .map(|(id, config)| (*id, config.clone()))
+            .collect()
+    }
+    
+    /// 🔁 移除池子订阅的登记（重新订阅前调用）
+    fn forget_subscription(&self, subscription_id: u64) {
+        self.subscription_map.lock().unwrap().remove(&subscription_id);
+        self.last_notification.lock().unwrap().remove(&subscription_id);
+    }
+    
+    fn endpoint(&self) -> usize {
+        self.endpoint.load(Ordering::SeqCst)
+    }
+    
+    /// 日志前缀（只有一条连接时为空）
+    fn label(&self) -> String {
+        if self.shard_count > 1 {
+            format!("[shard {}/{}] ", self.index + 1, self.shard_count)
+        } else {
+            String::new()
+        }
+    }
+    
+    /// 通过本连接的动态订阅channel发送请求；未连接时返回 false（重连时按注册表重新订阅）
+    fn send_request(&self, request: SubscriptionRequest) -> bool {
+        match self.subscription_tx.lock().unwrap().as_ref() {
+            Some(tx) => match tx.send(request) {
+                Ok(()) => true,
+                Err(e) => {
+                    error!("Failed to send subscription request: {}", e);
+                    false
+                }
+            },
+            None => false,
+        }
+    }
+    
+    /// ♻️ 请求退订本连接上的订阅（未连接时无需退订，重连只会订阅当前列表）
+    fn request_unsubscribe(&self, subscription_ids: Vec<u64>) {
+        if !subscription_ids.is_empty() {
+            self.send_request(SubscriptionRequest::Unsubscribe { subscription_ids });
+        }
+    }
+}
+
+/// 🔀 池子 -> 连接分片
+///
+/// 连接数 = ceil(池子数 / 单连接上限)。池子按地址的一致性哈希选首选连接，首选连接满了
+/// 顺延到下一条，同一份配置每次启动得到相同的分配；热重载新增的池子只影响自己。
+struct ShardPlan {
+    ring: ShardRing,
+    capacity: usize,
+    assignment: HashMap<String, usize>,
+    loads: Vec<usize>,
+}
+
+impl ShardPlan {
+    fn new(pools: &[PoolConfig], capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let shard_count = pools.len().div_ceil(capacity).max(1);
+        let mut plan = Self {
+            ring: ShardRing::new(shard_count, 64),
+            capacity,
+            assignment: HashMap::new(),
+            loads: vec![0; shard_count],
+        };
+        for pool in pools {
+            plan.assign(&pool.address);
+        }
+        plan
+    }
+    
+    fn shard_count(&self) -> usize {
+        self.loads.len()
+    }
+    
+    /// 分配池子（已分配的保持不变）；所有连接都满时仍放到首选连接
+    fn assign(&mut self, address: &str) -> usize {
+        if let Some(shard) = self.assignment.get(address) {
+            return *shard;
+        }
+        let preferred = self.ring.shard_for(address);
+        let count = self.shard_count();
+        let shard = (0..count)
+            .map(|offset| (preferred + offset) % count)
+            .find(|shard| self.loads[*shard] < self.capacity)
+            .unwrap_or(preferred);
+        self.loads[shard] += 1;
+        self.assignment.insert(address.to_string(), shard);
+        shard
+    }
+    
+    fn remove(&mut self, address: &str) {
+        if let Some(shard) = self.assignment.remove(address) {
+            self.loads[shard] -= 1;
+        }
+    }
+    
+    /// 池子所在的连接（未分配的归第一条连接）
+    fn shard_of(&self, address: &str) -> usize {
+        self.assignment.get(address).copied().unwrap_or(0)
+    }
+}
+
+/// 🔍 最近一次收到的池子账户数据（GET /pools/{address}/consistency、vault 一致性巡检）
+#[derive(Clone)]
+pub struct WsPoolAccounts {
+    pool_data_cache: Arc<Mutex<PoolDataCache>>,
+    active_pools: Arc<Mutex<Vec<PoolConfig>>>,
+}
+
+impl WsPoolAccounts {
+    /// 重新解析最近一次缓存的池子账户得到的储备（只缓存 vault 型池子的账户数据）
+    pub fn raw_reserves(&self, pool_address: &str) -> Option<(u64, u64)> {
+        let pool_type = self.active_pools.lock().unwrap()
+            .iter()
+            .find(|p| p.address == pool_address)
+            .map(|p| p.pool_type.clone())?;
+        let data = self.pool_data_cache.lock().unwrap().get(pool_address).cloned()?;
+        PoolFactory::create_pool(&pool_type, &data).ok().map(|pool| pool.get_reserves())
+    }
+}
+
+/// 📏 WebSocket 客户端内部缓存的大小（/metrics 读取，确认长时间运行后保持平稳）
+#[derive(Clone)]
+pub struct WsCacheSizes {
+    pool_data_cache: Arc<Mutex<PoolDataCache>>,
+    last_prices: Arc<DashMap<String, f64>>,
+}
+
+impl WsCacheSizes {
+    pub fn sizes(&self) -> Vec<CacheSize> {
+        vec![
+            self.pool_data_cache.lock().unwrap().size(),
+            // 条目数受配置中的池子数约束（热重载时清理），没有单独的上限
+            CacheSize {
+                cache: "last_prices",
+                entries: self.last_prices.len(),
+                capacity: None,
+                bytes: None,
+                evictions: 0,
+            },
+        ]
+    }
 }
 
 pub struct WebSocketClient {
-    url: String,
+    endpoints: Arc<EndpointPool>, // 🔀 多端点故障转移（WebSocket / RPC 共用活跃端点）
     metrics: Arc<MetricsCollector>,
     pool_stats: Arc<PoolStatsCollector>, // 🔥 池子活跃度统计收集器
     proxy_config: Option<ProxyConfig>,
     price_cache: Arc<PriceCache>,
     error_tracker: Arc<ErrorTracker>,
-    subscription_map: Arc<Mutex<HashMap<u64, PoolConfig>>>,
-    vault_pending_map: Arc<Mutex<HashMap<u64, String>>>, // 🌐 request_id -> vault地址（等待确认）
-    vault_subscription_map: Arc<Mutex<HashMap<u64, String>>>, // 🌐 subscription_id -> vault地址（已确认）
-    vault_reader: Arc<Mutex<VaultReader>>, // 🌐 Vault 读取器
-    pool_data_cache: Arc<Mutex<HashMap<String, Vec<u8>>>>, // 🌐 缓存池子数据用于提取 vault
+    active_pools: Arc<Mutex<Vec<PoolConfig>>>, // ♻️ 当前池子列表（热重载更新，重连时按它订阅）
+    shards: Arc<Mutex<Vec<Arc<ConnectionShard>>>>, // 🔀 每条连接的订阅登记
+    shard_plan: Arc<Mutex<ShardPlan>>, // 🔀 池子 -> 连接
+    max_subscriptions_per_connection: usize, // 🔀 单连接池子上限
+    unsubscribe_unknown_after: u32, // 🧹 未知 subscription_id 超过该通知数后退订
+    resubscribe_silent_after: Option<Duration>, // 🔁 池子订阅安静超过该时长后重新订阅（None = 不检查）
+    vault_reader: Arc<VaultReader>, // 🌐 Vault 读取器（内部并发，无需外层锁）
+    pool_data_cache: Arc<Mutex<PoolDataCache>>, // 🌐 缓存池子数据用于 vault 更新时重算（LRU 有上限）
     last_prices: Arc<DashMap<String, f64>>, // 🔥 Track last prices for change detection (使用DashMap避免锁争用)
     price_change_threshold: f64, // 🔥 Price change threshold for logging
-    vault_subscription_tx: Arc<Mutex<Option<mpsc::UnboundedSender<SubscriptionRequest>>>>, // 🌐 动态订阅channel
-    rpc_url: Option<String>, // 🚀 RPC URL for proactive vault detection
+    proactive_vault_fetch: bool, // 🚀 通过共享 RPC 主动查询vault
     coordinator_tx: Arc<Mutex<Option<mpsc::Sender<PriceChangeEvent>>>>, // 🔥 Coordinator事件发送器
+    owner_checks: Arc<DashMap<String, OwnerCheck>>, // 🔒 pool地址 -> owner校验结果
+    shutdown_tx: Option<broadcast::Sender<()>>, // 🛑 关闭信号（每个连接各自订阅）
+    shutting_down: Arc<AtomicBool>, // 🛑 关闭中：不再重连
+    unsubscribed: Arc<AtomicUsize>, // 🛑 关闭时成功退订的账户数
+    backoff_policy: BackoffPolicy, // 🔄 重连退避策略
+    rpc: RpcHandle, // 🛰️ 共享 RPC（vault 预取 / 流动性数组 / mint 查询，与其他调用方共用限速）
+    heartbeat: WsHeartbeat, // 🩺 连接状态 + 最近消息时间（/health）
+    update_recorder: Option<PoolUpdateRecorder>, // 🎞️ 写入 PriceCache 的更新同时记录到数据库（回放用）
+    supervisor: Supervisor, // 🛟 分片连接 panic 后单独重启
+    commitment: Commitment, // 📡 池子订阅默认 commitment（PoolConfig.commitment 覆盖）
+    encoding: AccountEncoding, // 📡 池子订阅默认编码（PoolConfig.encoding 覆盖）
+    priority_batch_size: usize, // 🚀 连接建立后立即订阅的高优先级池子数
+    subscribe_rate_per_sec: u32, // 🚀 其余池子的订阅速率（0 = 一次全部发送）
 }
 
 impl WebSocketClient {
@@ -64,26 +313,222 @@ impl WebSocketClient {
         price_cache: Arc<PriceCache>,
         error_tracker: Arc<ErrorTracker>,
         price_change_threshold: f64,
-        rpc_url: Option<String>, // 🚀 新参数：用于主动查询vault
+        proactive_vault_fetch: bool, // 🚀 是否主动查询vault（经 with_rpc 的共享句柄）
     ) -> Self {
+        let rpc = Arc::new(RpcManager::unlimited(&rpc_url_for(&url))).handle("vault_prefetch");
         Self {
-            url,
+            endpoints: Arc::new(EndpointPool::new(vec![url])),
             metrics,
             pool_stats: Arc::new(PoolStatsCollector::new(price_change_threshold)), // 🔥 初始化池子统计收集器
             proxy_config,
             price_cache,
             error_tracker,
-            subscription_map: Arc::new(Mutex::new(HashMap::new())),
-            vault_pending_map: Arc::new(Mutex::new(HashMap::new())), // 🌐 初始化vault等待映射
-            vault_subscription_map: Arc::new(Mutex::new(HashMap::new())), // 🌐 初始化vault订阅映射
-            vault_reader: Arc::new(Mutex::new(VaultReader::new())), // 🌐 初始化 VaultReader
-            pool_data_cache: Arc::new(Mutex::new(HashMap::new())), // 🌐 初始化池子数据缓存
+            active_pools: Arc::new(Mutex::new(Vec::new())),
+            shards: Arc::new(Mutex::new(vec![Arc::new(ConnectionShard::new(0, 1, 0))])), // 🔀 run() 时按池子数重新划分
+            shard_plan: Arc::new(Mutex::new(ShardPlan::new(&[], 100))),
+            max_subscriptions_per_connection: 100,
+            unsubscribe_unknown_after: 5,
+            resubscribe_silent_after: Some(Duration::from_secs(600)),
+            vault_reader: Arc::new(VaultReader::new()), // 🌐 初始化 VaultReader
+            pool_data_cache: Arc::new(Mutex::new(PoolDataCache::default())), // 🌐 初始化池子数据缓存
             last_prices: Arc::new(DashMap::new()), // 🔥 初始化价格追踪（使用DashMap）
             price_change_threshold, // 🔥 设置价格变化阈值
-            vault_subscription_tx: Arc::new(Mutex::new(None)), // 🌐 初始化为None，在连接时设置
-            rpc_url, // 🚀 设置RPC URL
+            proactive_vault_fetch,
             coordinator_tx: Arc::new(Mutex::new(None)), // 🔥 Coordinator发送器初始化为None
+            owner_checks: Arc::new(DashMap::new()), // 🔒 owner校验结果
+            shutdown_tx: None,
+            shutting_down: Arc::new(AtomicBool::new(false)),
+            unsubscribed: Arc::new(AtomicUsize::new(0)),
+            backoff_policy: BackoffPolicy::default(),
+            rpc,
+            heartbeat: WsHeartbeat::default(),
+            update_recorder: None,
+            supervisor: Supervisor::default(),
+            commitment: Commitment::default(),
+            encoding: AccountEncoding::default(),
+            priority_batch_size: 20,
+            subscribe_rate_per_sec: 0,
+        }
+    }
+    
+    /// 🎞️ 记录每次写入 PriceCache 的池子更新（database.record_pool_updates）
+    pub fn with_update_recorder(mut self, recorder: PoolUpdateRecorder) -> Self {
+        self.update_recorder = Some(recorder);
+        self
+    }
+    
+    /// 🛟 使用进程共享的任务监督器（分片重启计入 /health 和 /metrics）
+    pub fn with_supervisor(mut self, supervisor: Supervisor) -> Self {
+        self.supervisor = supervisor;
+        self
+    }
+    
+    /// 📡 池子订阅的默认 commitment / 编码（[websocket]，PoolConfig 按池子覆盖）
+    pub fn with_subscription_options(mut self, commitment: Commitment, encoding: AccountEncoding) -> Self {
+        self.commitment = commitment;
+        self.encoding = encoding;
+        self
+    }
+    
+    /// 🚀 订阅节奏：前 `priority_batch_size` 个（按优先级）立即订阅，其余按 `subscribe_rate_per_sec` 逐个发送（0 = 不限）
+    pub fn with_subscription_pacing(mut self, priority_batch_size: usize, subscribe_rate_per_sec: u32) -> Self {
+        self.priority_batch_size = priority_batch_size;
+        self.subscribe_rate_per_sec = subscribe_rate_per_sec;
+        self
+    }
+    
+    /// 📡 池子订阅使用的 commitment（vault / 流动性数组订阅跟随父池子）
+    fn commitment_for(&self, pool: &PoolConfig) -> Commitment {
+        pool.commitment.unwrap_or(self.commitment)
+    }
+    
+    /// 📡 池子 accountSubscribe 的参数
+    fn pool_subscribe_options(&self, pool: &PoolConfig) -> serde_json::Value {
+        account_subscription::subscribe_options(self.commitment_for(pool), pool.encoding.unwrap_or(self.encoding))
+    }
+    
+    /// 🔀 使用共享的多端点池（WebSocket 故障转移，/health 输出端点健康）
+    pub fn with_endpoints(mut self, endpoints: Arc<EndpointPool>) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+    
+    /// 🔄 设置重连退避策略
+    pub fn with_backoff(mut self, policy: BackoffPolicy) -> Self {
+        self.backoff_policy = policy;
+        self
+    }
+    
+    /// 🔀 单条连接最多订阅的池子数（超过时按池子地址分到多条连接）
+    pub fn with_max_subscriptions_per_connection(mut self, max: usize) -> Self {
+        self.max_subscriptions_per_connection = max.max(1);
+        self
+    }
+    
+    /// 🧹 订阅清理：未知 subscription_id 超过 `unsubscribe_unknown_after` 条通知后退订；
+    /// 池子订阅安静超过 `resubscribe_silent_after`（同连接其他池子仍在更新）时重新订阅
+    pub fn with_subscription_cleanup(mut self, unsubscribe_unknown_after: u32, resubscribe_silent_after: Option<Duration>) -> Self {
+        self.unsubscribe_unknown_after = unsubscribe_unknown_after;
+        self.resubscribe_silent_after = resubscribe_silent_after;
+        self
+    }
+    
+    /// 🌐 池子账户数据缓存的容量（超过时淘汰最久未更新的池子）
+    pub fn with_pool_data_cache_capacity(mut self, capacity: usize) -> Self {
+        self.pool_data_cache = Arc::new(Mutex::new(PoolDataCache::new(capacity)));
+        self
+    }
+    
+    /// 🛰️ 共享 RPC 句柄（vault 预取按批次消耗限速预算；默认是 WebSocket 地址对应的不限速端点）
+    pub fn with_rpc(mut self, rpc: RpcHandle) -> Self {
+        self.rpc = rpc;
+        self
+    }
+    
+    /// 🛑 接入关闭信号：收到后退订所有账户并关闭连接，不再重连
+    pub fn with_shutdown(mut self, shutdown_tx: broadcast::Sender<()>) -> Self {
+        self.shutdown_tx = Some(shutdown_tx);
+        self
+    }
+    
+    /// 🛑 关闭时成功退订的账户数
+    pub fn unsubscribed_count(&self) -> usize {
+        self.unsubscribed.load(Ordering::Relaxed)
+    }
+    
+    fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+    
+    /// 等待关闭信号（未接入时永不返回）
+    async fn wait_for_shutdown(shutdown_rx: &mut Option<broadcast::Receiver<()>>) {
+        match shutdown_rx.as_mut() {
+            Some(rx) => {
+                let _ = rx.recv().await;
+            }
+            None => std::future::pending::<()>().await,
+        }
+    }
+    
+    /// 🔒 共享 owner 校验结果（与RPC初始化阶段、API共用）
+    pub fn with_owner_checks(mut self, owner_checks: Arc<DashMap<String, OwnerCheck>>) -> Self {
+        self.owner_checks = owner_checks;
+        self
+    }
+    
+    /// 🔒 激活前校验池子账户 owner
+    ///
+    /// 已校验通过且 owner 未变化时直接放行；不匹配时拒绝激活、池子标记为 Degraded 并记录错误（仅首次记录），
+    /// 修正 pool_type 热重载后重新校验通过即恢复。
+    /// 之前校验通过的池子换了 owner（账户被关闭或迁移）时直接退役
+    async fn verify_pool_owner(&self, pool_config: &PoolConfig, owner: &str, data: &[u8]) -> bool {
+        let mut previously_verified = false;
+        if let Some(existing) = self.owner_checks.get(&pool_config.address) {
+            if existing.owner == owner && existing.pool_type == pool_config.pool_type {
+                // 启动时 RPC 初始化已拒绝的池子在这里补上 Degraded
+                if let Some(reason) = existing.degraded_reason() {
+                    self.pool_stats.degrade(&pool_config.name, reason);
+                }
+                return existing.verified;
+            }
+            previously_verified = existing.verified && existing.pool_type == pool_config.pool_type;
+        }
+        
+        let check = PoolFactory::check_owner(&pool_config.pool_type, owner, data);
+        let verified = check.verified;
+        
+        match check.reason() {
+            Some(reason) if previously_verified => {
+                self.retire_pool(pool_config, &format!("owner changed: {}", reason));
+            }
+            Some(reason) => {
+                warn!(
+                    pool = %pool_config.name,
+                    pool_type = %pool_config.pool_type,
+                    suggested_type = %check.suggested_type.as_deref().unwrap_or("unknown"),
+                    "Refusing pool activation: {}", reason
+                );
+                self.error_tracker
+                    .record_error("owner_mismatch", format!("{}: {}", pool_config.name, reason))
+                    .await;
+                if let Some(reason) = check.degraded_reason() {
+                    self.pool_stats.degrade(&pool_config.name, reason);
+                }
+                // 按旧 pool_type 缓存的价格不再可信
+                self.price_cache.remove_price(&pool_config.address);
+            }
+            None => {
+                if self.pool_stats.clear_degraded(&pool_config.name) {
+                    info!(pool = %pool_config.name, pool_type = %pool_config.pool_type, "🔒 Owner verified, degraded pool activated");
+                }
+            }
+        }
+        
+        self.owner_checks.insert(pool_config.address.clone(), check);
+        verified
+    }
+    
+    /// 🪦 池子账户已关闭 / 迁移：标记 Retired，移出路由，只记录这一条日志
+    ///
+    /// 订阅保留，热重载时恢复（见 apply_pool_list）
+    fn retire_pool(&self, pool_config: &PoolConfig, reason: &str) {
+        if !self.pool_stats.retire(&pool_config.name, reason) {
+            return;
         }
+        self.announce_retired(pool_config, reason);
+    }
+    
+    fn announce_retired(&self, pool_config: &PoolConfig, reason: &str) {
+        warn!(
+            pool = %pool_config.name,
+            address = %pool_config.address,
+            pool_type = %pool_config.pool_type,
+            "🪦 Pool retired, ignoring further updates until it is removed from config or reloaded: {}", reason
+        );
+        self.price_cache.remove_price(&pool_config.address);
+        self.last_prices.remove(&pool_config.name);
+        self.pool_data_cache.lock().unwrap().remove(&pool_config.address);
+        crate::orderbook_cache::remove(&pool_config.address);
     }
     
     /// Set the coordinator sender (used to send price change events)
@@ -94,18 +539,7 @@ impl WebSocketClient {
 
     /// Connect to the WebSocket server and start processing messages
     pub async fn run(&self, pools: Vec<PoolConfig>) -> Result<()> {
-        loop {
-            match self.connect_and_process(&pools).await {
-                Ok(_) => {
-                    println!("⚠️  WebSocket connection closed normally");
-                }
-                Err(e) => {
-                    eprintln!("❌ WebSocket error: {}. Reconnecting in 5 seconds...", e);
-                }
-            }
-
-            sleep(Duration::from_secs(5)).await;
-        }
+        self.run_with_failover(None, pools).await
     }
     
     /// Process messages from an already-connected WebSocket stream
@@ -116,142 +550,333 @@ impl WebSocketClient {
         pools: Vec<PoolConfig>,
     ) -> Result<()> {
         println!("📨 Starting message processing with pre-connected stream");
+        self.run_with_failover(Some(ws_stream), pools).await
+    }
+    
+    /// 🔀 按池子数划分连接，每条连接各自运行连接循环（第一条连接使用预先建立的连接）
+    async fn run_with_failover(
+        &self,
+        initial_stream: Option<proxy::WsStream>,
+        pools: Vec<PoolConfig>,
+    ) -> Result<()> {
+        let shard_count = self.configure_shards(pools);
+        if shard_count == 1 {
+            return self.run_connection(self.shard(0), initial_stream).await;
+        }
+        
+        info!(
+            "🔀 Sharding {} pools across {} WebSocket connections (max {} per connection)",
+            self.active_pools.lock().unwrap().len(), shard_count, self.max_subscriptions_per_connection
+        );
+        let mut initial_stream = initial_stream;
+        let connections: Vec<_> = (0..shard_count)
+            .map(|index| {
+                let client = self.clone_shared();
+                // 🛟 分片 panic 后单独重启（预先建立的连接只给第一次运行，重启后重新连接）
+                let mut stream = if index == 0 { initial_stream.take() } else { None };
+                spawn_supervised("websocket_shard", &self.supervisor, move || {
+                    let client = client.clone_shared();
+                    let stream = stream.take();
+                    async move {
+                        let shard = client.shard(index);
+                        Some(client.run_connection(shard, stream).await)
+                    }
+                })
+            })
+            .collect();
+        for (index, result) in futures_util::future::join_all(connections).await.into_iter().enumerate() {
+            match result.context("WebSocket shard supervisor failed")? {
+                Some(result) => result?,
+                None => anyhow::bail!("WebSocket shard {} exceeded its restart limit", index),
+            }
+        }
+        Ok(())
+    }
+    
+    /// 🔀 按池子列表重建连接分片和订阅登记，返回连接数
+    fn configure_shards(&self, pools: Vec<PoolConfig>) -> usize {
+        let plan = ShardPlan::new(&pools, self.max_subscriptions_per_connection);
+        let shard_count = plan.shard_count();
+        *self.shards.lock().unwrap() = (0..shard_count)
+            .map(|index| Arc::new(ConnectionShard::new(index, shard_count, self.endpoints.active_index())))
+            .collect();
+        *self.shard_plan.lock().unwrap() = plan;
+        *self.active_pools.lock().unwrap() = pools;
+        shard_count
+    }
+    
+    fn shard(&self, index: usize) -> Arc<ConnectionShard> {
+        Arc::clone(&self.shards.lock().unwrap()[index])
+    }
+    
+    fn all_shards(&self) -> Vec<Arc<ConnectionShard>> {
+        self.shards.lock().unwrap().clone()
+    }
+    
+    /// 🔀 池子所在的连接（vault 等动态订阅发到这里）
+    fn shard_for_pool(&self, pool_address: &str) -> Arc<ConnectionShard> {
+        let index = self.shard_plan.lock().unwrap().shard_of(pool_address);
+        let shards = self.shards.lock().unwrap();
+        Arc::clone(shards.get(index).unwrap_or(&shards[0]))
+    }
+    
+    /// 🔀 当前池子列表中分配到该连接的池子
+    fn shard_pools(&self, index: usize) -> Vec<PoolConfig> {
+        let plan = self.shard_plan.lock().unwrap();
+        self.active_pools.lock().unwrap()
+            .iter()
+            .filter(|pool| plan.shard_of(&pool.address) == index)
+            .cloned()
+            .collect()
+    }
+    
+    /// 连接循环：断开后记录端点失败、本连接切换到最健康的端点，按退避等待后重连（每条连接各自退避）
+    ///
+    /// 存活超过 `stable_after` 的连接断开不算端点故障，按原端点重连；
+    /// 其他连接不受影响，只在自己断开时才切换端点。
+    async fn run_connection(
+        &self,
+        shard: Arc<ConnectionShard>,
+        mut initial_stream: Option<proxy::WsStream>,
+    ) -> Result<()> {
+        let mut shutdown_rx = self.shutdown_tx.as_ref().map(|tx| tx.subscribe());
+        let mut backoff = ReconnectBackoff::new(self.backoff_policy.clone());
+        let label = shard.label();
         
         loop {
-            match self.process_stream(ws_stream, &pools).await {
+            if self.is_shutting_down() {
+                return Ok(());
+            }
+            
+            // ♻️ 每次连接按最新的池子列表订阅（热重载后重连不会订阅已删除的池子）
+            let pools = self.shard_pools(shard.index);
+            let endpoint = shard.endpoint();
+            let connected_at = Instant::now();
+            let result = match initial_stream.take() {
+                Some(ws_stream) => self.process_stream(&shard, ws_stream, &pools).await,
+                None => self.connect_and_process(&shard, &pools).await,
+            };
+            self.heartbeat.set_connected(false);
+            match result {
                 Ok(_) => {
-                    println!("⚠️  WebSocket connection closed normally");
-                    // Connection closed, try to reconnect
-                    break;
+                    if self.is_shutting_down() {
+                        return Ok(());
+                    }
+                    println!("⚠️  {}WebSocket connection closed normally", label);
                 }
                 Err(e) => {
-                    eprintln!("❌ WebSocket error: {}. Reconnecting in 5 seconds...", e);
-                    break;
+                    eprintln!("❌ {}WebSocket error: {}", label, e);
+                }
+            }
+            
+            // 🔄 存活足够久的连接重置退避
+            let connected_for = connected_at.elapsed();
+            backoff.record_disconnect(connected_for);
+            self.endpoints.record_latency(endpoint, self.metrics.get_stats(60).avg_latency_micros);
+            
+            let next = if connected_for >= backoff.policy().stable_after {
+                // 稳定运行后的断开（服务端例行关闭等）不算端点故障
+                endpoint
+            } else {
+                // 🔀 记录端点失败，同一端点连续失败过多时告警
+                if self.endpoints.record_failure(endpoint, backoff.policy().alert_after_failures) {
+                    let message = format!(
+                        "{} failed {} consecutive times",
+                        self.endpoints.url(endpoint),
+                        self.endpoints.consecutive_failures(endpoint)
+                    );
+                    error!("🚨 WebSocket endpoint unhealthy: {}", message);
+                    self.error_tracker.record_error("websocket_endpoint_down", message).await;
+                }
+                
+                let next = self.endpoints.fail_over();
+                if next != endpoint {
+                    warn!(
+                        "🔀 {}Failing over WebSocket endpoint [{}] -> [{}] {}",
+                        label, endpoint, next, self.endpoints.url(next)
+                    );
+                }
+                shard.endpoint.store(next, Ordering::SeqCst);
+                next
+            };
+            
+            let delay = backoff.next_delay();
+            self.metrics.record_reconnect_attempt();
+            eprintln!(
+                "🔄 {}Reconnecting to endpoint [{}/{}] in {:.1}s (consecutive failures: {})",
+                label, next + 1, self.endpoints.endpoint_count(),
+                delay.as_secs_f64(), self.endpoints.consecutive_failures(next)
+            );
+
+            tokio::select! {
+                _ = sleep(delay) => {}
+                _ = Self::wait_for_shutdown(&mut shutdown_rx) => {
+                    self.shutting_down.store(true, Ordering::SeqCst);
                 }
             }
         }
-        
-        // If we get here, connection was lost. Try to reconnect using the old method.
-        println!("🔄 Connection lost, switching to auto-reconnect mode...");
-        self.run(pools).await
     }
     
-    async fn connect_and_process(&self, pools: &[PoolConfig]) -> Result<()> {
-        println!("🔌 Connecting to WebSocket: {}", self.url);
+    async fn connect_and_process(&self, shard: &ConnectionShard, pools: &[PoolConfig]) -> Result<()> {
+        let endpoint = shard.endpoint();
+        let url = self.endpoints.url(endpoint).to_string();
+        println!(
+            "🔌 {}Connecting to WebSocket [{}/{}]: {} ({} pools)",
+            shard.label(), endpoint + 1, self.endpoints.endpoint_count(), url, pools.len()
+        );
         
         // Check if proxy is configured and enabled
         let ws_stream = if let Some(proxy_cfg) = &self.proxy_config {
             if proxy_cfg.enabled {
                 println!("🌐 Using proxy: {}:{}", proxy_cfg.host, proxy_cfg.port);
-                proxy::connect_via_proxy(&proxy_cfg.host, proxy_cfg.port, &self.url).await?
+                proxy::connect_via_proxy(&proxy_cfg.host, proxy_cfg.port, &url).await?
             } else {
                 println!("🌐 Proxy disabled, connecting directly");
-                proxy::connect_direct(&self.url).await?
+                proxy::connect_direct(&url).await?
             }
         } else {
             println!("🌐 No proxy configured, connecting directly");
-            proxy::connect_direct(&self.url).await?
+            proxy::connect_direct(&url).await?
         };
         
-        println!("✅ WebSocket connected successfully");
+        println!("✅ {}WebSocket connected successfully", shard.label());
         
         // Delegate to process_stream
-        self.process_stream(ws_stream, pools).await
+        self.process_stream(shard, ws_stream, pools).await
     }
     
     /// Process messages from a connected WebSocket stream
-    async fn process_stream(
+    ///
+    /// 每次（重新）连接都会调用：服务器会分配新的 subscription_id，
+    /// 所以先清空本连接的旧映射，订阅池子后立即重放已发现的 vault 订阅。
+    async fn process_stream<S>(
         &self,
-        ws_stream: proxy::WsStream,
+        shard: &ConnectionShard,
+        ws_stream: S,
         pools: &[PoolConfig],
-    ) -> Result<()> {
+    ) -> Result<()>
+    where
+        S: futures_util::Stream<Item = Result<Message, WsError>>
+            + futures_util::Sink<Message, Error = WsError>
+            + Unpin,
+    {
         let (mut write, mut read) = ws_stream.split();
+        self.heartbeat.set_connected(true);
+        let mut shutdown_rx = self.shutdown_tx.as_ref().map(|tx| tx.subscribe());
+        let endpoint = shard.endpoint();
+        
+        let label = shard.label();
+        
+        // 🔄 旧连接的 subscription_id 已失效
+        shard.clear();
         
         // 🌐 创建动态订阅channel
         let (vault_tx, mut vault_rx) = mpsc::unbounded_channel::<SubscriptionRequest>();
-        {
-            let mut tx_lock = self.vault_subscription_tx.lock().unwrap();
-            *tx_lock = Some(vault_tx);
-        }
+        *shard.subscription_tx.lock().unwrap() = Some(vault_tx);
         
         // 订阅ID计数器（池子使用1-N，vault使用10000+）
         let mut next_subscription_id = pools.len() as u64 + 10000;
         
-        // Subscribe to all pools
-        for (idx, pool) in pools.iter().enumerate() {
-            let subscribe_msg = json!({
-                "jsonrpc": "2.0",
-                "id": idx + 1,
-                "method": "accountSubscribe",
-                "params": [
-                    pool.address,
-                    {
-                        "encoding": "base64",
-                        "commitment": "confirmed"
-                    }
-                ]
-            });
-            
-            write
-                .send(Message::Text(subscribe_msg.to_string()))
-                .await
-                .context("Failed to send subscribe message")?;
-            
-            debug!("Subscribed to {} ({})", pool.name, pool.address);
+        // 🚀 按优先级订阅：高优先级批次立即发送，其余按速率在消息循环中逐个发送
+        // （request id 仍是池子在 pools 中的下标 + 1）
+        let connected_at = Instant::now();
+        let mut order: VecDeque<usize> = priority_order(pools).into();
+        let immediate = if self.subscribe_rate_per_sec == 0 { order.len() } else { self.priority_batch_size.min(order.len()) };
+        let top_pools: Vec<String> = order.iter()
+            .take(self.priority_batch_size)
+            .map(|&idx| pools[idx].address.clone())
+            .collect();
+        for idx in order.drain(..immediate).collect::<Vec<_>>() {
+            self.send_initial_subscription(&mut write, idx, &pools[idx]).await?;
+        }
+        let mut top_ready = top_pools.is_empty();
+        let trickle_period = Duration::from_secs(1) / self.subscribe_rate_per_sec.max(1);
+        let mut trickle = tokio::time::interval_at(tokio::time::Instant::now() + trickle_period, trickle_period);
+        if !order.is_empty() {
+            info!("🚀 {}Subscribed {} priority pools, trickling {} more at {}/s",
+                label, immediate, order.len(), self.subscribe_rate_per_sec);
         }
         
-        info!("Waiting for pool updates from {} pools...", pools.len());
+        // 🔄 重放已发现的 vault / DLMM bin array / CLMM tick array 订阅（注册表跨重连保留）
+        // 🔀 只重放父池子在本连接上的订阅
+        let known_subscriptions = [
+            ("vault accounts", self.vault_reader.vault_subscriptions()),
+            ("DLMM bin arrays", crate::dlmm_bin_cache::subscriptions()),
+            ("CLMM tick arrays", crate::clmm_tick_cache::subscriptions()),
+        ];
+        for (kind, known) in known_subscriptions {
+            let mut replayed = 0;
+            for (address, pool_address) in &known {
+                let Some(pool) = pools.iter().find(|p| &p.address == pool_address) else {
+                    continue;
+                };
+                next_subscription_id += 1;
+                self.send_vault_subscription(shard, &mut write, next_subscription_id, address, &pool.name, self.commitment_for(pool)).await;
+                replayed += 1;
+            }
+            if replayed > 0 {
+                info!("🔄 {}Re-subscribed {} known {}", label, replayed, kind);
+            }
+        }
+        
+        info!("{}Waiting for pool updates from {} pools...", label, pools.len());
         info!("🌐 Dynamic vault subscription enabled");
         
         // 🔥 关键修复：立即主动查询所有池子状态，触发vault订阅
         // 不等待WebSocket更新（Phoenix冷门池子可能几分钟都没交易）
-        if let Some(rpc_url) = &self.rpc_url {
-            let rpc_client = Arc::new(RpcClient::new_with_timeout(
-                rpc_url.clone(),
-                Duration::from_secs(5)
-            ));
-            
-            // 在后台异步执行，不阻塞WebSocket处理
-            tokio::spawn({
-                let self_clone = self.clone_for_proactive_fetch();
-                let pools_clone = pools.to_vec();
-                async move {
-                    // 等待1秒让WebSocket订阅完全建立
-                    sleep(Duration::from_millis(1000)).await;
-                    
-                    if let Err(e) = self_clone.proactively_trigger_vault_subscriptions(
-                        &pools_clone,
-                        rpc_client
-                    ).await {
-                        error!("Proactive vault subscription failed: {}", e);
-                    }
-                }
-            });
+        if self.proactive_vault_fetch {
+            self.spawn_proactive_vault_fetch(pools.to_vec());
         } else {
-            warn!("No RPC URL provided, vault pools may take longer to activate");
+            warn!("Proactive vault fetch disabled, vault pools may take longer to activate");
         }
         
+        // 🔁 定期检查长时间没有通知的池子订阅（服务器可能已悄悄丢弃）
+        let resubscribe_after = self.resubscribe_silent_after;
+        let mut silence_check = tokio::time::interval(
+            resubscribe_after.map_or(Duration::from_secs(60), |after| (after / 4).clamp(Duration::from_secs(1), Duration::from_secs(60)))
+        );
+        
         // 🌐 使用select!同时处理WebSocket消息和动态订阅请求
         loop {
             tokio::select! {
+                // 🛑 关闭：退订所有池子和vault账户，再关闭连接
+                _ = Self::wait_for_shutdown(&mut shutdown_rx) => {
+                    self.shutting_down.store(true, Ordering::SeqCst);
+                    let count = self.unsubscribe_all(shard, &mut write).await;
+                    self.unsubscribed.fetch_add(count, Ordering::Relaxed);
+                    info!("🛑 {}Unsubscribed {} accounts", label, count);
+                    if let Err(e) = write.close().await {
+                        debug!("WebSocket close error during shutdown: {}", e);
+                    }
+                    break;
+                }
+                
                 // 处理WebSocket消息
                 message = read.next() => {
                     match message {
                         Some(Ok(Message::Text(text))) => {
-                            if let Err(e) = self.handle_message(&text, pools).await {
+                            self.endpoints.record_message(endpoint);
+                            self.heartbeat.record_message();
+                            self.metrics.record_websocket_message(shard.index);
+                            if let Err(e) = self.handle_message(shard, &text, pools).await {
                                 eprintln!("⚠️  Error handling message: {}", e);
                             }
+                            if !top_ready && self.pools_fresh(shard, &top_pools, connected_at) {
+                                top_ready = true;
+                                info!("🚀 {}Top {} priority pools subscribed and fresh after {:.1}s",
+                                    label, top_pools.len(), connected_at.elapsed().as_secs_f64());
+                            }
                         }
                         Some(Ok(Message::Close(_))) => {
-                            println!("⚠️  Server closed the connection");
+                            println!("⚠️  {}Server closed the connection", label);
                             break;
                         }
                         Some(Err(e)) => {
-                            eprintln!("❌ WebSocket error: {}", e);
+                            eprintln!("❌ {}WebSocket error: {}", label, e);
                             break;
                         }
                         None => {
-                            println!("⚠️  WebSocket stream ended");
+                            println!("⚠️  {}WebSocket stream ended", label);
                             break;
                         }
                         _ => {}
@@ -261,70 +886,283 @@ impl WebSocketClient {
                 // 🌐 处理动态订阅请求
                 Some(req) = vault_rx.recv() => {
                     match req {
-                        SubscriptionRequest::VaultAccount { address, pool_name } => {
+                        SubscriptionRequest::VaultAccount { address, pool_name, commitment } => {
                             next_subscription_id += 1;
-                            let request_id = next_subscription_id;
-                            
-                            // 记录到pending map（等待服务器确认）
-                            {
-                                let mut pending = self.vault_pending_map.lock().unwrap();
-                                pending.insert(request_id, address.clone());
-                            }
-                            
-                            let subscribe_msg = json!({
-                                "jsonrpc": "2.0",
-                                "id": request_id,
-                                "method": "accountSubscribe",
-                                "params": [
-                                    address,
-                                    {
-                                        "encoding": "base64",
-                                        "commitment": "confirmed"
-                                    }
-                                ]
-                            });
-                            
-                            if let Err(e) = write.send(Message::Text(subscribe_msg.to_string())).await {
-                                error!("Failed to subscribe to vault {}: {}", address, e);
-                                // 订阅失败，从pending中移除
-                                let mut pending = self.vault_pending_map.lock().unwrap();
-                                pending.remove(&request_id);
-                            } else {
-                                info!("🌐 Subscribed to vault {} for pool {}", &address[0..8], pool_name);
+                            self.send_vault_subscription(shard, &mut write, next_subscription_id, &address, &pool_name, commitment).await;
+                        }
+                        SubscriptionRequest::PoolAccount { pool } => {
+                            next_subscription_id += 1;
+                            self.send_pool_subscription(shard, &mut write, next_subscription_id, pool).await;
+                        }
+                        SubscriptionRequest::Unsubscribe { subscription_ids } => {
+                            for subscription_id in subscription_ids {
+                                next_subscription_id += 1;
+                                Self::send_unsubscribe(&mut write, next_subscription_id, subscription_id).await;
                             }
                         }
                     }
                 }
+                
+                // 🚀 长尾池子按速率逐个订阅
+                _ = trickle.tick(), if !order.is_empty() => {
+                    if let Some(idx) = order.pop_front() {
+                        self.send_initial_subscription(&mut write, idx, &pools[idx]).await?;
+                    }
+                    if order.is_empty() {
+                        info!("🚀 {}All {} pool subscriptions sent after {:.1}s",
+                            label, pools.len(), connected_at.elapsed().as_secs_f64());
+                    }
+                }
+                
+                // 🔁 重新订阅安静过久的池子（先退订旧 id，确认后进入 subscription_map）
+                _ = silence_check.tick(), if resubscribe_after.is_some() => {
+                    let silent_for = resubscribe_after.unwrap_or_default();
+                    for (subscription_id, pool) in shard.silent_subscriptions(silent_for) {
+                        warn!(
+                            "🔁 {}No notifications for {} in {}s while other pools updated, resubscribing",
+                            label, pool.name, silent_for.as_secs()
+                        );
+                        shard.forget_subscription(subscription_id);
+                        self.pool_stats.record_resubscribe(&pool.name);
+                        next_subscription_id += 1;
+                        Self::send_unsubscribe(&mut write, next_subscription_id, subscription_id).await;
+                        next_subscription_id += 1;
+                        self.send_pool_subscription(shard, &mut write, next_subscription_id, pool).await;
+                    }
+                }
             }
         }
         
         // 清理channel
-        {
-            let mut tx_lock = self.vault_subscription_tx.lock().unwrap();
-            *tx_lock = None;
+        *shard.subscription_tx.lock().unwrap() = None;
+        
+        Ok(())
+    }
+    
+    /// 📊 订阅新登记的流动性数组（DLMM BinArray / CLMM TickArray），并通过 RPC 立即拉取一次
+    /// （订阅只在账户变化时推送）。`update` 为对应注册表的更新函数
+    fn subscribe_liquidity_arrays(
+        &self,
+        shard: &ConnectionShard,
+        addresses: Vec<String>,
+        pool_config: &PoolConfig,
+        kind: &'static str,
+        update: fn(&str, &[u8]) -> bool,
+    ) {
+        for address in &addresses {
+            shard.send_request(SubscriptionRequest::VaultAccount {
+                address: address.clone(),
+                pool_name: pool_config.name.clone(),
+                commitment: self.commitment_for(pool_config),
+            });
         }
         
+        let rpc = self.rpc.with_caller("liquidity_arrays");
+        let pool_name = pool_config.name.clone();
+        tokio::spawn(async move {
+            let keys: Vec<Pubkey> = addresses.iter()
+                .filter_map(|address| Pubkey::from_str(address).ok())
+                .collect();
+            let fetched = fetch_accounts_batched(&rpc, &keys).await;
+            let loaded = fetched.accounts.iter()
+                .filter(|(pubkey, account)| update(&pubkey.to_string(), &account.data))
+                .count();
+            info!("📊 [{}] Loaded {}/{} {}", pool_name, loaded, keys.len(), kind);
+        });
+    }
+    
+    /// 连接建立时的池子订阅（request id = 池子在本连接池子列表中的下标 + 1）
+    async fn send_initial_subscription<W>(&self, write: &mut W, idx: usize, pool: &PoolConfig) -> Result<()>
+    where
+        W: futures_util::Sink<Message, Error = WsError> + Unpin,
+    {
+        let subscribe_msg = json!({
+            "jsonrpc": "2.0",
+            "id": idx + 1,
+            "method": "accountSubscribe",
+            "params": [pool.address, self.pool_subscribe_options(pool)]
+        });
+        
+        write
+            .send(Message::Text(subscribe_msg.to_string()))
+            .await
+            .context("Failed to send subscribe message")?;
+        
+        debug!("Subscribed to {} ({}, {})", pool.name, pool.address, self.commitment_for(pool));
         Ok(())
     }
     
-    async fn handle_message(&self, text: &str, pools: &[PoolConfig]) -> Result<()> {
+    /// 🚀 池子都已确认订阅，且在 `since` 之后收到过数据
+    fn pools_fresh(&self, shard: &ConnectionShard, addresses: &[String], since: Instant) -> bool {
+        let fresh = addresses.iter()
+            .all(|address| self.price_cache.get_price(address).is_some_and(|p| p.last_update >= since));
+        fresh && {
+            let map = shard.subscription_map.lock().unwrap();
+            addresses.iter().all(|address| map.values().any(|pool| &pool.address == address))
+        }
+    }
+    
+    /// 🚀 后台通过共享 RPC 查询池子状态，触发 vault 订阅
+    fn spawn_proactive_vault_fetch(&self, pools: Vec<PoolConfig>) {
+        debug!("Proactive vault fetch via {}", self.rpc.manager().active_url());
+        
+        // 在后台异步执行，不阻塞WebSocket处理
+        let self_clone = self.clone_shared();
+        tokio::spawn(async move {
+            // 等待1秒让WebSocket订阅完全建立
+            sleep(Duration::from_millis(1000)).await;
+            
+            if let Err(e) = self_clone.proactively_trigger_vault_subscriptions(&pools).await {
+                error!("Proactive vault subscription failed: {}", e);
+            }
+        });
+    }
+    
+    /// ♻️ 发送热重载新增池子的订阅请求，记录到 pending map 等待服务器确认
+    async fn send_pool_subscription<W>(&self, shard: &ConnectionShard, write: &mut W, request_id: u64, pool: PoolConfig)
+    where
+        W: futures_util::Sink<Message> + Unpin,
+        W::Error: std::fmt::Display,
+    {
+        let subscribe_msg = json!({
+            "jsonrpc": "2.0",
+            "id": request_id,
+            "method": "accountSubscribe",
+            "params": [pool.address, self.pool_subscribe_options(&pool)]
+        });
+        
+        let name = pool.name.clone();
+        shard.pool_pending_map.lock().unwrap().insert(request_id, pool);
+        if let Err(e) = write.send(Message::Text(subscribe_msg.to_string())).await {
+            error!("Failed to subscribe to pool {}: {}", name, e);
+            shard.pool_pending_map.lock().unwrap().remove(&request_id);
+        } else {
+            info!("♻️  {}Subscribed to new pool {}", shard.label(), name);
+        }
+    }
+    
+    /// 🌐 发送 vault 账户订阅请求，记录到 pending map 等待服务器确认
+    async fn send_vault_subscription<W>(
+        &self,
+        shard: &ConnectionShard,
+        write: &mut W,
+        request_id: u64,
+        address: &str,
+        pool_name: &str,
+        commitment: Commitment,
+    ) -> bool
+    where
+        W: futures_util::Sink<Message> + Unpin,
+        W::Error: std::fmt::Display,
+    {
+        // 登记为等待服务器确认
+        shard.vault_subscriptions.add_pending(request_id, address);
+        
+        let subscribe_msg = json!({
+            "jsonrpc": "2.0",
+            "id": request_id,
+            "method": "accountSubscribe",
+            "params": [address, account_subscription::subscribe_options(commitment, AccountEncoding::Base64)]
+        });
+        
+        if let Err(e) = write.send(Message::Text(subscribe_msg.to_string())).await {
+            error!("Failed to subscribe to vault {}: {}", address, e);
+            // 订阅失败，撤销登记
+            shard.vault_subscriptions.cancel_pending(request_id);
+            false
+        } else {
+            info!("🌐 Subscribed to vault {} for pool {}", &address[..address.len().min(8)], pool_name);
+            true
+        }
+    }
+    
+    /// 发送 accountUnsubscribe（失败只记录警告，连接断开后订阅本身也会失效）
+    async fn send_unsubscribe<W>(write: &mut W, request_id: u64, subscription_id: u64)
+    where
+        W: futures_util::Sink<Message> + Unpin,
+        W::Error: std::fmt::Display,
+    {
+        let unsubscribe_msg = json!({
+            "jsonrpc": "2.0",
+            "id": request_id,
+            "method": "accountUnsubscribe",
+            "params": [subscription_id]
+        });
+        if let Err(e) = write.send(Message::Text(unsubscribe_msg.to_string())).await {
+            warn!("Failed to unsubscribe {}: {}", subscription_id, e);
+        }
+    }
+    
+    /// 🛑 对本连接所有已确认的订阅（池子 + vault）发送 accountUnsubscribe
+    ///
+    /// 返回成功发送的退订数
+    async fn unsubscribe_all<S>(&self, shard: &ConnectionShard, write: &mut S) -> usize
+    where
+        S: futures_util::Sink<Message> + Unpin,
+    {
+        let mut subscription_ids: Vec<u64> = shard.subscription_map.lock().unwrap().keys().copied().collect();
+        subscription_ids.extend(shard.vault_subscriptions.subscription_ids());
+        
+        // 退订请求ID从一个不会与订阅请求冲突的区间开始
+        let mut unsubscribed = 0;
+        for (idx, subscription_id) in subscription_ids.iter().enumerate() {
+            let unsubscribe_msg = json!({
+                "jsonrpc": "2.0",
+                "id": 1_000_000 + idx as u64,
+                "method": "accountUnsubscribe",
+                "params": [subscription_id]
+            });
+            
+            if write.send(Message::Text(unsubscribe_msg.to_string())).await.is_ok() {
+                unsubscribed += 1;
+            } else {
+                warn!("Failed to unsubscribe {}, connection already closed", subscription_id);
+                break;
+            }
+        }
+        
+        shard.subscription_map.lock().unwrap().clear();
+        shard.vault_subscriptions.clear_confirmed();
+        
+        unsubscribed
+    }
+    
+    async fn handle_message(&self, shard: &ConnectionShard, text: &str, pools: &[PoolConfig]) -> Result<()> {
         let start_time = Instant::now();
         
-        let msg: serde_json::Value = serde_json::from_str(text)
-            .context("Failed to parse JSON message")?;
+        // 🧩 内容错误只计数，不向上返回（读取循环只因传输错误重连）
+        let msg = match account_subscription::parse_message(text) {
+            Ok(msg) => msg,
+            Err(malformed) => {
+                self.record_malformed(shard, &malformed).await;
+                return Ok(());
+            }
+        };
         
         // Check if this is an account notification
         if msg.get("method").and_then(|m| m.as_str()) == Some("accountNotification") {
-            self.handle_account_notification(&msg, start_time).await?;
+            self.handle_account_notification(shard, &msg, start_time).await?;
+        } else if msg.get("result").is_some_and(|r| r.is_boolean()) {
+            // accountUnsubscribe 的确认
+            debug!("Unsubscribe acknowledged: id={:?}", msg.get("id"));
         } else if msg.get("result").is_some() {
             // This is a subscription response
-            let id = msg.get("id").and_then(|i| i.as_u64()).unwrap_or(0);
-            let subscription_id = msg.get("result").and_then(|r| r.as_u64()).unwrap_or(0);
+            let id = msg.get("id").and_then(account_subscription::subscription_id).unwrap_or(0);
+            let subscription_id = msg.get("result").and_then(account_subscription::subscription_id).unwrap_or(0);
             
             // Map subscription_id to pool config (id is 1-indexed, pools is 0-indexed)
             if id > 0 && (id as usize) <= pools.len() {
-                let pool_config = pools[(id - 1) as usize].clone();
-                self.subscription_map.lock().unwrap().insert(subscription_id, pool_config.clone());
+                // ♻️ 以热重载后的最新配置为准；订阅期间被删除的池子直接退订
+                let address = &pools[(id - 1) as usize].address;
+                let pool_config = match self.active_pool(address) {
+                    Some(config) => config,
+                    None => {
+                        shard.request_unsubscribe(vec![subscription_id]);
+                        return Ok(());
+                    }
+                };
+                shard.subscription_map.lock().unwrap().insert(subscription_id, pool_config.clone());
+                shard.touch(subscription_id);
                 
                 // 🔥 Record pool subscription stats
                 self.pool_stats.record_subscription(&pool_config.name, &pool_config.address);
@@ -332,17 +1170,17 @@ impl WebSocketClient {
                 debug!("✅ Pool subscription confirmed: id={}, subscription_id={}, pool={}", 
                        id, subscription_id, pool_config.name);
             } else if id >= 10000 {
-                // 🌐 这是vault账户订阅（ID >= 10000）
-                // 从pending map中获取vault地址，转移到subscription map
-                let vault_address = {
-                    let mut pending = self.vault_pending_map.lock().unwrap();
-                    pending.remove(&id)
-                };
-                
-                if let Some(address) = vault_address {
-                    self.vault_subscription_map.lock().unwrap().insert(subscription_id, address.clone());
+                // 🌐 这是vault账户订阅（ID >= 10000）：等待确认 -> 已确认
+                if let Some(address) = shard.vault_subscriptions.confirm(id, subscription_id) {
                     info!("✅ Vault subscription confirmed: request_id={}, subscription_id={}, vault={}", 
                            id, subscription_id, &address[0..8]);
+                } else if let Some(pool_config) = shard.pool_pending_map.lock().unwrap().remove(&id) {
+                    // ♻️ 热重载新增的池子
+                    self.pool_stats.record_subscription(&pool_config.name, &pool_config.address);
+                    info!("✅ Pool subscription confirmed: request_id={}, subscription_id={}, pool={}",
+                          id, subscription_id, pool_config.name);
+                    shard.subscription_map.lock().unwrap().insert(subscription_id, pool_config);
+                    shard.touch(subscription_id);
                 } else {
                     warn!("Vault subscription confirmed but not found in pending map: id={}", id);
                 }
@@ -356,36 +1194,22 @@ impl WebSocketClient {
     
     async fn handle_account_notification(
         &self,
+        shard: &ConnectionShard,
         msg: &serde_json::Value,
         start_time: Instant,
     ) -> Result<()> {
-        // Extract the base64-encoded account data
-        let data_array = msg
-            .pointer("/params/result/value/data")
-            .and_then(|d| d.as_array())
-            .context("Missing data field")?;
-        
-        let base64_data = data_array
-            .get(0)
-            .and_then(|d| d.as_str())
-            .context("Missing base64 data")?;
-        
-        // Get subscription ID to find the correct pool
-        let subscription_id = msg
-            .pointer("/params/subscription")
-            .and_then(|s| s.as_u64())
-            .context("Missing subscription ID")?;
-
-        // Decode base64 first (需要先解码来检查数据大小)
-        use base64::Engine;
-        let decoded = base64::engine::general_purpose::STANDARD
-            .decode(base64_data)
-            .context("Failed to decode base64")?;
-
-        let slot = msg
-            .pointer("/params/result/context/slot")
-            .and_then(|s| s.as_u64())
-            .unwrap_or(0);
+        // Decode first (需要先解码来检查数据大小；📡 base64+zstd 在这里解压)
+        // 🧩 畸形通知（缺字段 / 坏 base64 / 不支持的编码）只计数，连接继续读取
+        let notification = match account_subscription::parse_account_notification(msg) {
+            Ok(notification) => notification,
+            Err(malformed) => {
+                self.record_malformed(shard, &malformed).await;
+                return Ok(());
+            }
+        };
+        let subscription_id = notification.subscription_id;
+        let slot = notification.slot;
+        let decoded = notification.data;
 
         // ✅ 调试日志：验证slot提取
         if slot == 0 {
@@ -402,93 +1226,84 @@ impl WebSocketClient {
             );
         }
         
-        // 🌐 检查是否是 vault 账户更新（165 字节 = SPL Token Account）
-        if decoded.len() == 165 {
-            // 这是Token账户，从vault_subscription_map中查找vault地址
-            let vault_address = {
-                let vault_map = self.vault_subscription_map.lock().unwrap();
-                vault_map.get(&subscription_id).cloned()
-            };
-            
-            if let Some(address) = vault_address {
-                // 找到了vault地址，更新vault余额（带上正确的slot）
-                return self.handle_vault_update(&address, &decoded, slot).await;
-            } else {
-                // 不是我们订阅的vault，可能是其他Token账户
-                debug!("Received 165-byte account update (not a registered vault), subscription_id={}", subscription_id);
+        // 🌐 vault 账户更新：订阅ID对应已登记的 vault 时按任意长度处理
+        // （Token-2022 vault 带扩展时长度 > 165）
+        if let Some(address) = shard.vault_subscriptions.address(subscription_id) {
+            // 📊 DLMM bin array 复用 vault 订阅通道
+            if crate::dlmm_bin_cache::is_bin_array(&address) {
+                if !crate::dlmm_bin_cache::update_bin_array(&address, &decoded) {
+                    debug!("Ignoring unparsable bin array update: {}, len={}", address, decoded.len());
+                }
                 return Ok(());
             }
-        }
-        
-        // 🔧 处理其他小尺寸账户（82字节等）- 这些是Solana网络的其他账户更新
-        // 通常是: Program derived addresses, Metadata accounts, 或其他非池子账户
-        if decoded.len() < 200 && decoded.len() != 165 {
-            // 检查是否在我们的订阅映射中
-            let is_known = {
-                let map = self.subscription_map.lock().unwrap();
-                map.contains_key(&subscription_id)
-            } || {
-                let vault_map = self.vault_subscription_map.lock().unwrap();
-                vault_map.contains_key(&subscription_id)
-            };
-            
-            if !is_known {
-                // 不是我们订阅的账户，静默忽略（降低日志噪音）
-                debug!("Ignoring small account update (unknown subscription): id={}, len={}", subscription_id, decoded.len());
+            // 📈 CLMM tick array 同样复用 vault 订阅通道
+            if crate::clmm_tick_cache::is_tick_array(&address) {
+                if !crate::clmm_tick_cache::update_tick_array(&address, &decoded) {
+                    debug!("Ignoring unparsable tick array update: {}, len={}", address, decoded.len());
+                }
                 return Ok(());
             }
+            debug!("Received vault update: subscription_id={}, vault={}, len={}",
+                subscription_id, address, decoded.len());
+            return self.handle_vault_update(&address, &decoded, slot).await;
         }
         
-        // 🔥 修复：先检查是否是vault订阅
-        // 如果是vault，获取vault地址并处理
-        let vault_address_opt = {
-            let vault_map = self.vault_subscription_map.lock().unwrap();
-            vault_map.get(&subscription_id).cloned()
-        };
-        
-        if let Some(vault_address) = vault_address_opt {
-            // 这是一个vault订阅的更新
-            debug!("Received vault update: subscription_id={}, vault={}, len={}",
-                subscription_id, vault_address, decoded.len());
-            return self.handle_vault_update(&vault_address, &decoded, slot).await;
+        // 其他由 SPL Token / Token-2022 程序拥有的账户（或 165 字节的经典 token 账户）不是池子
+        let owner = notification.owner.as_deref();
+        let owned_by_token_program = owner.is_some_and(spl_token::is_token_program);
+        if owned_by_token_program || decoded.len() == spl_token::TOKEN_ACCOUNT_LEN {
+            debug!("Received token account update (not a registered vault), subscription_id={}, len={}",
+                subscription_id, decoded.len());
+            self.handle_unknown_subscription(shard, subscription_id, decoded.len());
+            return Ok(());
         }
         
         // 不是vault，查找pool配置
         let pool_config = {
-            let map = self.subscription_map.lock().unwrap();
+            let map = shard.subscription_map.lock().unwrap();
             map.get(&subscription_id).cloned()
         };
         
         let pool_config = match pool_config {
             Some(config) => config,
             None => {
-                warn!("Received update for unknown subscription ID: {}, data_len={}", subscription_id, decoded.len());
+                self.handle_unknown_subscription(shard, subscription_id, decoded.len());
                 return Ok(());
             }
         };
+        shard.touch(subscription_id);
         
         let pool_name = &pool_config.name;
         let pool_type_str = &pool_config.pool_type;
         let pool_address = &pool_config.address;
         
+        // 🪦 已退役的池子：不解析、不计入错误
+        if self.pool_stats.is_retired(pool_name) {
+            return Ok(());
+        }
+        
+        // 🔒 账户通知中包含 owner，激活前校验
+        if let Some(owner) = owner {
+            if !self.verify_pool_owner(&pool_config, owner, &decoded).await {
+                return Ok(());
+            }
+        }
+        
         // ========================================
         // New Trait-based Approach
         // ========================================
         
-        // Try to create pool using factory
-        let pool_result = if pool_type_str == "unknown" || pool_type_str.is_empty() {
-            // Auto-detect pool type
-            PoolFactory::create_pool_auto_detect(&decoded)
-        } else {
-            // Use specified pool type
-            PoolFactory::create_pool(pool_type_str, &decoded)
-        };
+        // Try to create pool using factory（pool_type 为 unknown 时按 owner 路由，没有 owner 才按大小探测）
+        let pool_result = PoolFactory::create_pool_for(pool_type_str, owner, &decoded);
         
         match pool_result {
             Ok(pool) => {
-                // Check if pool is active
-                if !pool.is_active() {
-                    // Silently skip inactive pools
+                self.pool_stats.record_decoded(pool_name, decoded.len());
+                
+                // 🚦 暂停 / 到期 / 无流动性的池子不更新缓存，已缓存的排除出路由（等待 vault 数据的继续订阅 vault）
+                let activity = pool.activity_status();
+                if !activity.is_trackable() {
+                    self.record_activity(&pool_config, activity);
                     return Ok(());
                 }
                 
@@ -499,17 +1314,14 @@ impl WebSocketClient {
                     let vault_a_str = vault_a.to_string();
                     let vault_b_str = vault_b.to_string();
                     
-                    let vault_already_registered = {
-                        let vault_reader = self.vault_reader.lock().unwrap();
-                        vault_reader.is_vault_account(&vault_a_str) && vault_reader.is_vault_account(&vault_b_str)
-                    };
+                    let vault_already_registered = self.vault_reader.is_vault_account(&vault_a_str)
+                        && self.vault_reader.is_vault_account(&vault_b_str);
+                    
+                    // 每次通知都刷新（被 LRU 淘汰的池子在这里重新缓存）
+                    self.pool_data_cache.lock().unwrap().insert(pool_address.clone(), decoded.clone());
                     
                     if !vault_already_registered {
                         // 首次处理，需要注册并订阅vault
-                        let mut pool_cache = self.pool_data_cache.lock().unwrap();
-                        pool_cache.insert(pool_address.clone(), decoded.clone());
-                        drop(pool_cache);
-                        
                         info!(
                             pool = %pool_name,
                             "Pool requires vault data, subscribing and waiting for vault updates..."
@@ -519,37 +1331,20 @@ impl WebSocketClient {
                         let vault_a_str = vault_a.to_string();
                         let vault_b_str = vault_b.to_string();
                         
-                        {
-                            let mut vault_reader = self.vault_reader.lock().unwrap();
-                            vault_reader.register_pool_vaults(
-                                pool_address,
-                                &vault_a_str,
-                                &vault_b_str
-                            );
-                        }
+                        self.vault_reader.register_pool_vaults(pool_address, &vault_a_str, &vault_b_str);
                         
                         println!("🌐 [{}] Detected vault addresses:", pool_name);
                         println!("   ├─ Vault A: {}", vault_a_str);
                         println!("   └─ Vault B: {}", vault_b_str);
                         
-                        // 🚀 发送动态订阅请求
-                        if let Some(tx) = self.vault_subscription_tx.lock().unwrap().as_ref() {
-                            // 订阅Vault A
-                            if let Err(e) = tx.send(SubscriptionRequest::VaultAccount {
-                                address: vault_a_str.clone(),
+                        // 🚀 发送动态订阅请求（走池子所在的连接）
+                        let sent = [vault_a_str.clone(), vault_b_str.clone()].into_iter()
+                            .all(|address| shard.send_request(SubscriptionRequest::VaultAccount {
+                                address,
                                 pool_name: pool_name.to_string(),
-                            }) {
-                                error!("Failed to send vault A subscription request: {}", e);
-                            }
-                            
-                            // 订阅Vault B
-                            if let Err(e) = tx.send(SubscriptionRequest::VaultAccount {
-                                address: vault_b_str.clone(),
-                                pool_name: pool_name.to_string(),
-                            }) {
-                                error!("Failed to send vault B subscription request: {}", e);
-                            }
-                            
+                                commitment: self.commitment_for(&pool_config),
+                            }));
+                        if sent {
                             println!("   ✅ Vault subscription requests sent!");
                         } else {
                             warn!("Vault subscription channel not available");
@@ -562,10 +1357,45 @@ impl WebSocketClient {
                     }
                 }
                 
+                // 📊 DLMM：登记活跃 bin 附近的 BinArray，新进入窗口的订阅并立即拉取一次
+                if PoolFactory::canonical_pool_type(pool_type_str) == Some("meteora_dlmm") {
+                    let new_bin_arrays = crate::dlmm_bin_cache::observe_lb_pair(pool_address, &decoded);
+                    if !new_bin_arrays.is_empty() {
+                        self.subscribe_liquidity_arrays(
+                            shard, new_bin_arrays, &pool_config, "DLMM bin arrays", crate::dlmm_bin_cache::update_bin_array);
+                    }
+                }
+                
+                // 📈 CLMM：当前 tick 两侧的 TickArray 窗口，tick 移到另一个数组时订阅新数组、退订移出的
+                if PoolFactory::canonical_pool_type(pool_type_str) == Some("clmm") {
+                    let window = crate::clmm_tick_cache::observe_pool(pool_address, &decoded);
+                    if !window.unsubscribe.is_empty() {
+                        shard.request_unsubscribe(shard.vault_subscriptions.remove_addresses(&window.unsubscribe));
+                    }
+                    if !window.subscribe.is_empty() {
+                        self.subscribe_liquidity_arrays(
+                            shard, window.subscribe, &pool_config, "CLMM tick arrays", crate::clmm_tick_cache::update_tick_array);
+                    }
+                }
+                
                 // Use unified update method
-                self.update_cache_from_pool(pool.as_ref(), &pool_config, pool_name, slot, start_time);
+                let commitment = self.commitment_for(&pool_config);
+                self.update_cache_from_pool(pool.as_ref(), &pool_config, pool_name, slot, Some(commitment), start_time);
+                // 📖 CLOB 池子保留完整订单簿，供路由器按档位报价
+                crate::orderbook_cache::register(&pool_config.address, pool);
+            }
+            Err(DexError::DexDisabled(dex)) => {
+                // 🔌 DEX 被禁用：跳过，不计入错误
+                self.pool_stats.record_skipped_disabled(&dex);
+                debug!(pool = %pool_name, dex = %dex, "Skipping update for disabled DEX");
             }
             Err(e) => {
+                // 🪦 账户长度连续与解析器不符：退役，不再计入错误
+                if let Some(reason) = self.pool_stats.record_decode_failure(pool_name, decoded.len()) {
+                    self.announce_retired(&pool_config, &reason);
+                    return Ok(());
+                }
+                
                 // Record error with deduplication
                 let error_key = format!("{}_{}", pool_type_str, "deserialize_failed");
                 let error_msg = format!("{}: {}, Expected vs Actual size issue", pool_name, e);
@@ -585,165 +1415,179 @@ impl WebSocketClient {
         Ok(())
     }
     
-    /// Clone necessary fields for proactive vault fetching in spawned task
-    fn clone_for_proactive_fetch(&self) -> Self {
+    /// 🧩 畸形消息：按类别计数并记入 ErrorTracker（不返回错误，不触发重连）
+    async fn record_malformed(&self, shard: &ConnectionShard, malformed: &MalformedMessage) {
+        self.metrics.record_malformed_message(malformed.kind);
+        debug!("{}Dropping {}", shard.label(), malformed);
+        self.error_tracker.record_error(malformed.kind.error_type(), format!("{}{}", shard.label(), malformed.detail)).await;
+    }
+    
+    /// 🧹 未知 subscription_id 的通知：每个 id 只告警一次，超过阈值后退订（不再为忽略的数据付费）
+    fn handle_unknown_subscription(&self, shard: &ConnectionShard, subscription_id: u64, data_len: usize) {
+        let count = shard.record_unknown(subscription_id);
+        if count == 1 {
+            warn!("{}Received update for unknown subscription ID: {}, data_len={}", shard.label(), subscription_id, data_len);
+        } else {
+            debug!("Ignoring update for unknown subscription ID: {} (#{}), data_len={}", subscription_id, count, data_len);
+        }
+        if count == self.unsubscribe_unknown_after.saturating_add(1) {
+            warn!(
+                "🧹 {}Unknown subscription ID {} produced {} notifications, unsubscribing",
+                shard.label(), subscription_id, count
+            );
+            shard.request_unsubscribe(vec![subscription_id]);
+        }
+    }
+    
+    /// Clone a handle sharing all state (proactive vault fetch / per-shard connection tasks)
+    fn clone_shared(&self) -> Self {
         Self {
-            url: self.url.clone(),
+            endpoints: self.endpoints.clone(),
             metrics: self.metrics.clone(),
             pool_stats: self.pool_stats.clone(), // 🔥 Clone pool stats collector
             proxy_config: self.proxy_config.clone(),
             price_cache: self.price_cache.clone(),
             error_tracker: self.error_tracker.clone(),
-            subscription_map: self.subscription_map.clone(),
-            vault_pending_map: self.vault_pending_map.clone(),
-            vault_subscription_map: self.vault_subscription_map.clone(),
+            active_pools: self.active_pools.clone(),
+            shards: self.shards.clone(),
+            shard_plan: self.shard_plan.clone(),
+            max_subscriptions_per_connection: self.max_subscriptions_per_connection,
+            unsubscribe_unknown_after: self.unsubscribe_unknown_after,
+            resubscribe_silent_after: self.resubscribe_silent_after,
             vault_reader: self.vault_reader.clone(),
             pool_data_cache: self.pool_data_cache.clone(),
             last_prices: self.last_prices.clone(),
             price_change_threshold: self.price_change_threshold,
-            vault_subscription_tx: self.vault_subscription_tx.clone(),
-            rpc_url: self.rpc_url.clone(),
+            proactive_vault_fetch: self.proactive_vault_fetch,
             coordinator_tx: self.coordinator_tx.clone(),
+            owner_checks: self.owner_checks.clone(),
+            shutdown_tx: self.shutdown_tx.clone(),
+            shutting_down: self.shutting_down.clone(),
+            unsubscribed: self.unsubscribed.clone(),
+            backoff_policy: self.backoff_policy.clone(),
+            rpc: self.rpc.clone(),
+            heartbeat: self.heartbeat.clone(),
+            update_recorder: self.update_recorder.clone(),
+            supervisor: self.supervisor.clone(),
+            commitment: self.commitment,
+            encoding: self.encoding,
+            priority_batch_size: self.priority_batch_size,
+            subscribe_rate_per_sec: self.subscribe_rate_per_sec,
         }
     }
     
+    /// 储备在池子账户里、但很少收到更新的池子（AlphaQ）：启动时直接用 RPC 查询结果写入缓存
+    fn primes_from_pool_account(pool_type: &str) -> bool {
+        matches!(PoolFactory::canonical_pool_type(pool_type), Some("alphaq"))
+    }
+
     /// 🚀 主动通过RPC查询池子并触发vault检测
     /// 解决Phoenix CLOB等冷门池子长时间无WebSocket更新的问题
-    /// 🔥 使用并行查询架构，避免串行阻塞
-    async fn proactively_trigger_vault_subscriptions(
-        &self,
-        pools: &[PoolConfig],
-        rpc_client: Arc<RpcClient>,
-    ) -> Result<()> {
+    /// 🪣 池子和 vault 各按 getMultipleAccounts 分批查询（每批 100 个），受共享 RPC 预算限速
+    async fn proactively_trigger_vault_subscriptions(&self, pools: &[PoolConfig]) -> Result<()> {
         info!("🚀 Proactively fetching pool states to trigger vault subscriptions...");
         
-        // 收集所有需要查询的池子（Phoenix、SolFi、Raydium CLMM、Orca Whirlpool）
-        let target_pools: Vec<_> = pools.iter()
+        // 收集所有需要查询的池子（Phoenix、SolFi、Raydium CLMM、Orca Whirlpool、PancakeSwap、AlphaQ，以及 💾 快照恢复的池子）
+        let mut target_pools: Vec<(&PoolConfig, Pubkey)> = pools.iter()
+            .filter(|pool| {
+                // 🔌 被禁用 DEX 的池子不查询
+                let enabled = PoolFactory::is_dex_enabled(&pool.pool_type);
+                if !enabled {
+                    let dex = PoolFactory::canonical_pool_type(&pool.pool_type).unwrap_or("unknown");
+                    self.pool_stats.record_skipped_disabled(dex);
+                }
+                enabled
+            })
             .filter(|pool| {
                 let pool_type_lower = pool.pool_type.to_lowercase();
-                pool_type_lower.contains("phoenix") 
+                self.price_cache.is_restored(&pool.address)
+                    || pool_type_lower.contains("phoenix") 
                     || pool_type_lower.contains("solfi")
                     || pool_type_lower.contains("clmm")
                     || pool_type_lower.contains("whirlpool")
+                    || matches!(PoolFactory::canonical_pool_type(&pool.pool_type), Some("pancakeswap" | "alphaq"))
+            })
+            .filter_map(|pool| match Pubkey::from_str(&pool.address) {
+                Ok(pubkey) => Some((pool, pubkey)),
+                Err(e) => {
+                    warn!("❌ Invalid pubkey for {}: {}", pool.name, e);
+                    None
+                }
             })
             .collect();
         
-        info!("📋 Found {} vault-dependent pools to query", target_pools.len());
+        // 🚀 与订阅顺序一致：priority 高的先查询；同优先级中 💾 快照恢复的池子优先
+        target_pools.sort_by_key(|(pool, _)| {
+            (std::cmp::Reverse(pool.priority.unwrap_or(0)), !self.price_cache.is_restored(&pool.address))
+        });
         
-        // 🚀 并行发起所有RPC查询
-        let futures: Vec<_> = target_pools.iter().map(|pool_config| {
-            let rpc_clone = rpc_client.clone();
-            let pool_name = pool_config.name.clone();
-            let pool_address = pool_config.address.clone();
-            let pool_type = pool_config.pool_type.clone();
-            let address_str = pool_config.address.clone();
-            
-            async move {
-                // 解析池子地址
-                let pubkey = match Pubkey::from_str(&address_str) {
-                    Ok(pk) => pk,
-                    Err(e) => {
-                        warn!("❌ Invalid pubkey for {}: {}", pool_name, e);
-                        return None;
-                    }
-                };
-                
-                // 🔥 使用spawn_blocking避免阻塞Tokio运行时
-                let account_result = tokio::task::spawn_blocking(move || {
-                    rpc_clone.get_account(&pubkey)
-                }).await;
-                
-                match account_result {
-                    Ok(Ok(account)) => {
-                        Some((pool_name, pool_address, pool_type, account.data))
-                    }
-                    Ok(Err(e)) => {
-                        warn!("❌ RPC error fetching {}: {}", pool_name, e);
-                        None
-                    }
-                    Err(e) => {
-                        error!("❌ Task error fetching {}: {}", pool_name, e);
-                        None
-                    }
-                }
-            }
-        }).collect();
+        info!("📋 Found {} vault-dependent pools to query", target_pools.len());
         
-        // 等待所有查询完成
-        let results = join_all(futures).await;
+        // 🪣 第一轮：批量查询池子账户
+        let pool_keys: Vec<Pubkey> = target_pools.iter().map(|(_, pubkey)| *pubkey).collect();
+        let pool_accounts = fetch_accounts_batched(&self.rpc, &pool_keys).await;
         
         // 统计并处理结果
         let mut fetched_count = 0;
         let mut vault_triggered_count = 0;
+        let mut vault_pools: Vec<(String, String, Pubkey, Pubkey)> = Vec::new();
         
-        for result in results.into_iter().flatten() {
-            let (pool_name, pool_address, pool_type, data) = result;
+        for (pool_config, pubkey) in &target_pools {
+            let account = match pool_accounts.accounts.get(pubkey) {
+                Some(account) => account,
+                None => {
+                    warn!("❌ Pool account not found: {}", pool_config.name);
+                    continue;
+                }
+            };
             fetched_count += 1;
+            let pool_name = &pool_config.name;
+            let pool_address = &pool_config.address;
+            
+            // 🔒 owner 校验失败的池子不触发vault订阅
+            if !self.verify_pool_owner(pool_config, &account.owner.to_string(), &account.data).await {
+                continue;
+            }
             
             // 解析池子数据，触发vault检测
-            match PoolFactory::create_pool(&pool_type, &data) {
+            let owner = account.owner.to_string();
+            match PoolFactory::create_pool_for(&pool_config.pool_type, Some(&owner), &account.data) {
                 Ok(pool) => {
                     if let Some((vault_a, vault_b)) = pool.get_vault_addresses() {
                         let vault_a_str = vault_a.to_string();
                         let vault_b_str = vault_b.to_string();
                         
                         // 检查vault是否已注册
-                        let vault_already_registered = {
-                            let vault_reader = self.vault_reader.lock().unwrap();
-                            vault_reader.is_vault_account(&vault_a_str) && 
-                            vault_reader.is_vault_account(&vault_b_str)
-                        };
+                        let vault_already_registered = self.vault_reader.is_vault_account(&vault_a_str)
+                            && self.vault_reader.is_vault_account(&vault_b_str);
                         
                         if !vault_already_registered {
                             // 注册vault
-                            {
-                                let mut vault_reader = self.vault_reader.lock().unwrap();
-                                vault_reader.register_pool_vaults(
-                                    &pool_address,
-                                    &vault_a_str,
-                                    &vault_b_str
-                                );
-                            }
+                            self.vault_reader.register_pool_vaults(pool_address, &vault_a_str, &vault_b_str);
                             
                             info!("🌐 Proactively detected vaults for {}: {}, {}", 
                                   pool_name, &vault_a_str[0..8], &vault_b_str[0..8]);
                             
-                            // 发送订阅请求
-                            if let Some(tx) = self.vault_subscription_tx.lock().unwrap().as_ref() {
-                                let _ = tx.send(SubscriptionRequest::VaultAccount {
-                                    address: vault_a_str.clone(),
-                                    pool_name: pool_name.clone(),
-                                });
-                                let _ = tx.send(SubscriptionRequest::VaultAccount {
-                                    address: vault_b_str.clone(),
+                            // 发送订阅请求（🔀 走池子所在的连接）
+                            let shard = self.shard_for_pool(pool_address);
+                            let sent = [vault_a_str.clone(), vault_b_str.clone()].into_iter()
+                                .all(|address| shard.send_request(SubscriptionRequest::VaultAccount {
+                                    address,
                                     pool_name: pool_name.clone(),
-                                });
-                                
+                                    commitment: self.commitment_for(pool_config),
+                                }));
+                            if sent {
                                 vault_triggered_count += 1;
                             }
                         } else {
                             info!("✓ Vaults already registered for {}, fetching initial balances...", pool_name);
                         }
                         
-                        // 🔥 关键修复：无论vault是否已注册，都查询初始余额
-                        // 这确保即使vault在RPC阶段已注册，也能获得初始数据
-                        // ✅ 修复：获取当前slot并传递给价格重新计算
-                        let current_slot = match rpc_client.get_slot() {
-                            Ok(slot) => slot,
-                            Err(e) => {
-                                warn!("Failed to get current slot: {}, using 0", e);
-                                0
-                            }
-                        };
-
-                        self.fetch_and_update_vault_balances(
-                            &rpc_client,
-                            &vault_a,
-                            &vault_b,
-                            &pool_address,
-                            &pool_name,
-                            current_slot,
-                        ).await;
+                        // 🔥 无论vault是否已注册，都查询初始余额（下面统一批量查询）
+                        vault_pools.push((pool_address.clone(), pool_name.clone(), vault_a, vault_b));
+                    } else if self.price_cache.is_restored(pool_address) || Self::primes_from_pool_account(&pool_config.pool_type) {
+                        // 💾 储备量在池子账户里：直接用查询结果替换快照数据（很少成交的池子不必等第一条通知）
+                        self.update_cache_from_pool(pool.as_ref(), pool_config, pool_name, pool_accounts.slot, None, Instant::now());
                     }
                 }
                 Err(e) => {
@@ -752,117 +1596,74 @@ impl WebSocketClient {
             }
         }
         
-        info!("✅ Proactive fetch completed: {} pools fetched, {} vault subscriptions triggered", 
-              fetched_count, vault_triggered_count);
+        // 🪣 第二轮：批量查询所有 vault 余额（响应里的 slot 用于价格重新计算）
+        let vault_keys: Vec<Pubkey> = vault_pools.iter()
+            .flat_map(|(_, _, vault_a, vault_b)| [*vault_a, *vault_b])
+            .collect();
+        let vault_accounts = if vault_keys.is_empty() {
+            BatchedAccounts::default()
+        } else {
+            fetch_accounts_batched(&self.rpc, &vault_keys).await
+        };
+        
+        for (pool_address, pool_name, vault_a, vault_b) in &vault_pools {
+            self.update_vault_balances(
+                &vault_accounts,
+                vault_a,
+                vault_b,
+                pool_address,
+                pool_name,
+            ).await;
+        }
+        
+        info!("✅ Proactive fetch completed: {} pools fetched, {} vault subscriptions triggered ({} RPC calls)", 
+              fetched_count, vault_triggered_count, pool_accounts.rpc_calls + vault_accounts.rpc_calls);
         
         Ok(())
     }
     
-    /// 🔥 新增：批量查询vault余额并更新
-    async fn fetch_and_update_vault_balances(
+    /// 🔥 用批量查询到的vault账户更新余额，并触发价格重新计算
+    async fn update_vault_balances(
         &self,
-        rpc_client: &Arc<RpcClient>,
+        vault_accounts: &BatchedAccounts,
         vault_a: &Pubkey,
         vault_b: &Pubkey,
         pool_address: &str,
         pool_name: &str,
-        slot: u64,  // ✅ 修复：接收 slot 参数
     ) {
-        // 并行查询两个vault
-        let rpc_a = rpc_client.clone();
-        let rpc_b = rpc_client.clone();
-        let vault_a_clone = *vault_a;
-        let vault_b_clone = *vault_b;
-
-        info!("🔍 Fetching vault balances for {} via RPC...", pool_name);
-
-        let (result_a, result_b) = tokio::join!(
-            tokio::task::spawn_blocking(move || rpc_a.get_account(&vault_a_clone)),
-            tokio::task::spawn_blocking(move || rpc_b.get_account(&vault_b_clone))
-        );
-
-        // 处理vault A
-        match result_a {
-            Ok(Ok(account_a)) => {
-                let vault_a_str = vault_a.to_string();
-
-                // 更新VaultReader（传递原始数据）
-                let amount_result = {
-                    let mut vault_reader = self.vault_reader.lock().unwrap();
-                    vault_reader.update_vault(&vault_a_str, &account_a.data)
-                };
-
-                match amount_result {
-                    Ok(amount) => {
-                        info!("💰 Fetched initial balance for vault A of {}: {}", pool_name, amount);
-                    }
-                    Err(e) => {
-                        warn!("❌ Failed to update vault A balance for {}: {}", pool_name, e);
-                    }
+        for (label, vault) in [("A", vault_a), ("B", vault_b)] {
+            let account = match vault_accounts.accounts.get(vault) {
+                Some(account) => account,
+                None => {
+                    warn!("❌ Vault {} account not found for {}", label, pool_name);
+                    continue;
                 }
-            }
-            Ok(Err(e)) => {
-                warn!("❌ RPC error fetching vault A for {}: {}", pool_name, e);
-            }
-            Err(e) => {
-                warn!("❌ Task error fetching vault A for {}: {}", pool_name, e);
-            }
-        }
-
-        // 处理vault B
-        match result_b {
-            Ok(Ok(account_b)) => {
-                let vault_b_str = vault_b.to_string();
-
-                // 更新VaultReader（传递原始数据）
-                let amount_result = {
-                    let mut vault_reader = self.vault_reader.lock().unwrap();
-                    vault_reader.update_vault(&vault_b_str, &account_b.data)
-                };
-
-                match amount_result {
-                    Ok(amount) => {
-                        info!("💰 Fetched initial balance for vault B of {}: {}", pool_name, amount);
-                    }
-                    Err(e) => {
-                        warn!("❌ Failed to update vault B balance for {}: {}", pool_name, e);
-                    }
+            };
+            
+            // 更新VaultReader（传递原始数据）
+            match self.vault_reader.update_vault(&vault.to_string(), &account.data, vault_accounts.slot) {
+                Ok(amount) => {
+                    info!("💰 Fetched initial balance for vault {} of {}: {}", label, pool_name, amount);
+                }
+                Err(e) => {
+                    warn!("❌ Failed to update vault {} balance for {}: {}", label, pool_name, e);
                 }
-            }
-            Ok(Err(e)) => {
-                warn!("❌ RPC error fetching vault B for {}: {}", pool_name, e);
-            }
-            Err(e) => {
-                warn!("❌ Task error fetching vault B for {}: {}", pool_name, e);
             }
         }
-
-        // 🔥 触发价格重新计算（带正确的slot）
-        // ✅ 修复：传递 slot 参数而不是使用硬编码的0
-        self.trigger_pool_price_recalculation(pool_address, pool_name, slot).await;
+        
+        // 🔥 触发价格重新计算（slot 取批量查询响应的 context.slot）
+        self.trigger_pool_price_recalculation(pool_address, pool_name, vault_accounts.slot).await;
     }
     
     /// 🔥 新增：触发池子价格重新计算
     async fn trigger_pool_price_recalculation(&self, pool_address: &str, pool_name: &str, slot: u64) {
-        // 获取池子配置和数据
-        let (pool_config, pool_data) = {
-            let subscription_map = self.subscription_map.lock().unwrap();
-            let cache = self.pool_data_cache.lock().unwrap();
-
-            let config = subscription_map.values()
-                .find(|p| p.address == pool_address)
-                .cloned();
-            let data = cache.get(pool_address).cloned();
-
-            (config, data)
-        };
-
-        if let (Some(config), Some(data)) = (pool_config, pool_data) {
+        if let Some((config, data)) = self.pool_config_and_data(pool_address) {
             // 解析池子并重新计算价格
             if let Ok(pool) = PoolFactory::create_pool(&config.pool_type, &data) {
                 let start_time = std::time::Instant::now();
                 // ✅ 修复：传递正确的slot而不是硬编码为0
-                self.update_cache_from_pool(pool.as_ref(), &config, pool_name, slot, start_time);
+                self.update_cache_from_pool(pool.as_ref(), &config, pool_name, slot, None, start_time);
+                crate::orderbook_cache::register(&config.address, pool);
                 info!("🔄 Recalculated price for {} after fetching vault balances (slot={})", pool_name, slot);
             }
         }
@@ -875,14 +1676,8 @@ impl WebSocketClient {
         data: &[u8],
         slot: u64,  // ✅ 修复：添加slot参数
     ) -> Result<()> {
-        // 检查是否是已注册的 vault
-        let is_vault = {
-            let vault_reader = self.vault_reader.lock().unwrap();
-            vault_reader.is_vault_account(vault_address)
-        };
-        
-        if !is_vault {
-            // 不是 vault 账户，忽略
+        // 检查是否是已注册的 vault（不是则忽略）
+        if !self.vault_reader.is_vault_account(vault_address) {
             return Ok(());
         }
         
@@ -894,45 +1689,15 @@ impl WebSocketClient {
             "Received vault update"
         );
         
-        // 🔥 关键修复：分离锁的作用域，避免死锁
-        // 在同一个作用域内获取所有需要的数据，然后立即释放锁
-        let (amount_result, pool_addresses) = {
-            let mut vault_reader = self.vault_reader.lock().unwrap();
-            // 更新vault余额
-            let amount = vault_reader.update_vault(vault_address, data);
-            // 获取使用此vault的池子列表
-            let pools = if amount.is_ok() {
-                vault_reader.get_pools_for_vault(vault_address)
-            } else {
-                Vec::new()
-            };
-            (amount, pools)
-        }; // MutexGuard在这里被drop，锁已释放
-        
-        // 处理结果（此时已不持有任何锁）
-        match amount_result {
+        match self.vault_reader.update_vault(vault_address, data, slot) {
             Ok(amount) => {
                 debug!(vault = %vault_address, amount = %amount, "Vault balance updated");
                 
                 // 🚨 Critical fix: Trigger price recalculation for related pools
-                // 一次性获取所有需要的数据（避免嵌套锁）
-                let configs_and_data: Vec<_> = {
-                        let subscription_map = self.subscription_map.lock().unwrap();
-                    let cache = self.pool_data_cache.lock().unwrap();
-                    
-                    pool_addresses.into_iter()
-                        .filter_map(|pool_addr| {
-                            let config = subscription_map.values()
-                            .find(|p| p.address == pool_addr)
-                                .cloned()?;
-                            let data = cache.get(&pool_addr).cloned()?;
-                            Some((config, data))
-                        })
-                        .collect()
-                        };
-                        
-                // 安全处理（不持有任何锁）
-                for (config, data) in configs_and_data {
+                for pool_address in self.vault_reader.get_pools_for_vault(vault_address) {
+                    let Some((config, data)) = self.pool_config_and_data(&pool_address) else {
+                        continue;
+                    };
                     info!(pool = %config.name, "Recalculating price after vault update (slot={})", slot);
 
                     // 🔥 Record vault update stats
@@ -941,7 +1706,10 @@ impl WebSocketClient {
                     if let Ok(pool) = PoolFactory::create_pool(&config.pool_type, &data) {
                         let start_time = Instant::now();
                         // ✅ 修复：传递正确的slot
-                        self.update_cache_from_pool(pool.as_ref(), &config, &config.name, slot, start_time);
+                        // 📡 vault 订阅跟随父池子的 commitment
+                        let commitment = self.commitment_for(&config);
+                        self.update_cache_from_pool(pool.as_ref(), &config, &config.name, slot, Some(commitment), start_time);
+                        crate::orderbook_cache::register(&config.address, pool);
                     }
                 }
             }
@@ -953,6 +1721,103 @@ impl WebSocketClient {
         Ok(())
     }
     
+    /// 已订阅池子的配置 + 最近一次账户数据（两者都有才返回）
+    fn pool_config_and_data(&self, pool_address: &str) -> Option<(PoolConfig, Vec<u8>)> {
+        // 🔀 共用 vault 的池子可能在另一条连接上
+        let config = self.shard_for_pool(pool_address).subscription_map.lock().unwrap()
+            .values()
+            .find(|p| p.address == pool_address)
+            .cloned()?;
+        let data = self.pool_data_cache.lock().unwrap().get(pool_address).cloned()?;
+        Some((config, data))
+    }
+    
+    /// 🚦 记录池子的活跃状态（PriceCache 据此排除非 Active 的池子），状态变化时打日志
+    fn record_activity(&self, pool_config: &PoolConfig, status: ActivityStatus) {
+        let previous = self.price_cache.activity_status(&pool_config.address);
+        if !self.price_cache.set_activity_status(&pool_config.address, status.clone()) {
+            return;
+        }
+        match previous {
+            // 首次解析即 Active 是常态，不打日志
+            None if status.is_active() => {}
+            _ if status.is_active() => info!(pool = %pool_config.name, "🚦 Pool is active again"),
+            _ => info!(
+                pool = %pool_config.name,
+                status = %status,
+                previous = %previous.map_or_else(|| "none".to_string(), |p| p.to_string()),
+                "🚦 Pool is not active, excluded from routing"
+            ),
+        }
+    }
+    
+    /// 登记代币符号 → mint，mint 元数据未缓存时在后台拉取
+    fn register_token_mint(&self, token: &str, mint: Pubkey) {
+        if mint == Pubkey::default() {
+            return;
+        }
+        let mint_cache = match crate::mint_decimals_cache::get_global_mint_cache() {
+            Some(cache) => cache,
+            None => return,
+        };
+        if !mint_cache.register_token_mint(token, mint) {
+            return;
+        }
+        Self::fetch_mint_in_background(mint_cache, mint);
+    }
+
+    /// 📐 decimals 仍是猜测值的池子：后台拉取两侧 mint，下一次更新即用真实 decimals
+    fn resolve_guessed_decimals(&self, pool: &dyn DexPool, pool_name: &str) {
+        if !pool.decimals_guessed() {
+            return;
+        }
+        let (Some(mint_cache), Some((base_mint, quote_mint))) =
+            (crate::mint_decimals_cache::get_global_mint_cache(), pool.get_mints())
+        else {
+            return;
+        };
+        for mint in [base_mint, quote_mint] {
+            if mint != Pubkey::default() && mint_cache.cached_info(&mint).is_none() {
+                debug!(pool = %pool_name, mint = %mint, "📐 Decimals guessed, fetching mint");
+                Self::fetch_mint_in_background(mint_cache.clone(), mint);
+            }
+        }
+    }
+
+    /// 💸 后台拉取池子的费率配置账户（已缓存 / 拉取中 / 退避期内不发起请求）
+    fn prefetch_fee_config(&self, fee_config: Pubkey) {
+        use crate::deserializers::raydium_clmm::{begin_amm_config_fetch, fetch_amm_config_fee};
+        if !begin_amm_config_fetch(&fee_config, Instant::now()) {
+            return;
+        }
+        let rpc = self.rpc.with_caller("amm_config");
+        tokio::spawn(async move {
+            if let Some(rate) = fetch_amm_config_fee(&rpc, fee_config).await {
+                debug!("💸 AmmConfig {} trade fee rate {}", fee_config, rate);
+            }
+        });
+    }
+
+    /// 后台拉取 mint 元数据（同一 mint 同时只有一个请求）
+    fn fetch_mint_in_background(mint_cache: Arc<crate::mint_decimals_cache::MintInfoCache>, mint: Pubkey) {
+        if !mint_cache.begin_fetch(&mint) {
+            return;
+        }
+        // RPC 限速在 mint 缓存的共享句柄内完成
+        tokio::spawn(async move {
+            let fetch_cache = mint_cache.clone();
+            match tokio::task::spawn_blocking(move || fetch_cache.get_or_fetch_info(&mint)).await {
+                Ok(Ok(info)) if info.transfer_fee.is_some() => {
+                    info!("🪙 Mint {} has a Token-2022 transfer fee, applying it to routed hops", mint);
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => debug!("Failed to fetch mint info {}: {}", mint, e),
+                Err(e) => debug!("Mint info task join error: {}", e),
+            }
+            mint_cache.finish_fetch(&mint);
+        });
+    }
+
     /// Unified method to update cache from any DexPool implementation
     /// 
     /// This eliminates code duplication across different DEX types
@@ -962,28 +1827,28 @@ impl WebSocketClient {
         pool_config: &PoolConfig,
         pool_name: &str,
         slot: u64,
+        commitment: Option<Commitment>,  // 📡 订阅驱动的更新；RPC 拉取为 None
         start_time: Instant,
     ) {
         let latency = start_time.elapsed();
         let latency_micros = latency.as_micros() as u64;
+        let cache_update_started = Instant::now();  // ⏱️ 延迟预算：解析完成 → 事件发出
         
-        // 🌐 获取储备量（优先从 VaultReader 读取）
-        let (base_reserve, quote_reserve) = {
-            let vault_reader = self.vault_reader.lock().unwrap();
-            if let Some(reserves) = vault_reader.get_pool_reserves(&pool_config.address) {
-                // 从 vault 读取实际储备量
-                reserves
-            } else {
-                // 从池子账户直接读取
-                pool.get_reserves()
-            }
-        };
+        // 🌐 获取储备量（优先从 VaultReader 读取实际储备量，否则从池子账户直接读取）
+        let (base_reserve, quote_reserve) = self.vault_reader
+            .get_pool_reserves(&pool_config.address)
+            .unwrap_or_else(|| pool.get_reserves());
+        // 🚦 vault 型池子收到 vault 余额后才是 Active；非 Active 的池子照常写入缓存，但不进路由
+        let vault_reserves = self.vault_reader.get_received_pool_reserves(&pool_config.address);
+        self.record_activity(pool_config, pool.activity_status().with_vault_reserves(vault_reserves));
         
         // 优先使用 DexPool 自带的价格计算（Phoenix等CLOB依赖该值）
         let mut price = pool.calculate_price();
+        let dex_name = pool.dex_name();
+        let is_clmm = is_concentrated_liquidity(dex_name);
 
-        if price == 0.0 {
-            // Fallback: 使用储备计算（适用于AMM/CLMM）
+        if price == 0.0 && !is_clmm {
+            // Fallback: 使用储备计算（仅适用于AMM；集中流动性池子的 vault 比例不是价格）
             if base_reserve > 0 && quote_reserve > 0 {
                 let (base_decimals, quote_decimals) = pool.get_decimals();
                 let base_f64 = base_reserve as f64 / 10f64.powi(base_decimals as i32);
@@ -996,13 +1861,11 @@ impl WebSocketClient {
         }
         
         let (base_decimals, quote_decimals) = pool.get_decimals();
-        let dex_name = pool.dex_name();
         
         // 🚨 Critical fix: Handle zero price for vault-based pools
         if price == 0.0 {
             // 检查是否是vault-based池子（SolFi V2, GoonFi等）或CLMM池子
             let is_vault_based = pool.get_vault_addresses().is_some();
-            let is_clmm = dex_name.contains("CLMM") || dex_name.contains("Concentrated");
             
             if is_vault_based || is_clmm {
                 // Vault池子或CLMM池子允许以price=0激活，等待后续数据
@@ -1026,6 +1889,7 @@ impl WebSocketClient {
         
         // 🔥 Record pool stats - price update
         self.pool_stats.record_price_update(pool_name, price);
+        self.pool_stats.record_head_lag(pool_name, slot);  // ⛓️ 相对链头的 slot 延迟
         
         // Update price cache
         let pool_price = PoolPrice {
@@ -1039,36 +1903,48 @@ impl WebSocketClient {
             price,
             last_update: Instant::now(),
             slot,  // 🎯 记录slot用于数据一致性
+            liquidity_usd: None,
+            commitment,
         };
 
-        self.price_cache.update_price(pool_price);
+        // 💸 链上费率（Whirlpool / Raydium CLMM / PancakeSwap）优先于按DEX名称的默认值
+        //    费率在单独配置账户里的池子：后台异步预取，拿到之前用 DEX 默认费率
+        if let Some(fee_config) = pool.fee_config_account() {
+            self.prefetch_fee_config(fee_config);
+        }
+        if let Some(fee_rate) = pool.get_fee_rate() {
+            crate::fee_registry::global().set_onchain_fee_rate(&pool_config.address, fee_rate);
+        }
+
+        // 🪙 登记交易对代币的 mint（路由器按代币符号查 Token-2022 转账手续费）
+        if let (Some((base_mint, quote_mint)), Some((base_token, quote_token))) =
+            (pool.get_mints(), pool_name.split_once('/'))
+        {
+            self.register_token_mint(base_token, base_mint);
+            self.register_token_mint(quote_token, quote_mint);
+        }
+        self.resolve_guessed_decimals(pool, pool_name);
+
+        if let Some(recorder) = &self.update_recorder {
+            recorder.record(&pool_price);
+        }
+        match self.price_cache.update_price(pool_price) {
+            Some(BreakerEvent::Tripped(reason)) => self.pool_stats.record_quarantine(pool_name, reason),
+            Some(BreakerEvent::Cleared) => self.pool_stats.record_quarantine_cleared(pool_name),
+            None => {}
+        }
+        // 🧯 被隔离的池子不触发扫描（快照里也没有它）
+        let quarantined = self.price_cache.is_quarantined(&pool_config.address);
 
         // 🔥 Send price change event to Coordinator
         // Calculate price change percentage
-        let price_change_percent = if let Some(entry) = self.last_prices.get(pool_name) {
-            let last_price = *entry.value();
-
-            if last_price == 0.0 || price == 0.0 {
-                // Handle zero price case
-                if last_price != price {
-                    1.0 // 100% change (or -100%) for logging/signaling
-                } else {
-                    0.0
-                }
-            } else {
-                let change = ((price - last_price) / last_price * 100.0).abs();
-                if change.is_finite() {
-                    change / 100.0 // Convert to decimal (e.g., 0.15% -> 0.0015)
-                } else {
-                    0.0
-                }
-            }
-        } else {
-            0.01 // First update - treat as 1% change to trigger Coordinator
-        };
+        let price_change_percent = PriceChangeEvent::change_fraction(
+            self.last_prices.get(pool_name).map(|entry| *entry.value()),
+            price,
+        );
 
         // Send to Coordinator if sender is registered
-        if let Some(tx) = self.coordinator_tx.lock().unwrap().as_ref() {
+        if let Some(tx) = self.coordinator_tx.lock().unwrap().as_ref().filter(|_| !quarantined) {
             let event = PriceChangeEvent {
                 pool_id: pool_config.address.clone(),
                 pool_name: pool_name.to_string(),
@@ -1077,6 +1953,11 @@ impl WebSocketClient {
                 old_price: if price_change_percent > 0.0 { Some(self.last_prices.get(pool_name).map_or(0.0, |v| *v.value())) } else { None },
                 new_price: price,
                 timestamp: Instant::now(),
+                ingest: Some(IngestTiming {
+                    received_at: start_time,
+                    deserialize: latency,
+                    cache_update: cache_update_started.elapsed(),
+                }),
             };
 
             // Use try_send to avoid blocking
@@ -1143,5 +2024,883 @@ impl WebSocketClient {
     pub fn pool_stats(&self) -> Arc<PoolStatsCollector> {
         Arc::clone(&self.pool_stats)
     }
+    
+    /// 🔁 vault 登记表（路由器 / 验证器识别共用 vault 的包装池）
+    pub fn vault_reader(&self) -> Arc<VaultReader> {
+        Arc::clone(&self.vault_reader)
+    }
+    
+    /// 📏 内部缓存的大小句柄（/metrics）
+    pub fn cache_sizes(&self) -> WsCacheSizes {
+        WsCacheSizes {
+            pool_data_cache: self.pool_data_cache.clone(),
+            last_prices: self.last_prices.clone(),
+        }
+    }
+    
+    /// 🔍 最近一次的池子账户数据（一致性检查）
+    pub fn pool_accounts(&self) -> WsPoolAccounts {
+        WsPoolAccounts {
+            pool_data_cache: self.pool_data_cache.clone(),
+            active_pools: self.active_pools.clone(),
+        }
+    }
+    
+    /// 🩺 WebSocket 心跳（/health 读取）
+    pub fn heartbeat(&self) -> WsHeartbeat {
+        self.heartbeat.clone()
+    }
+    
+    /// ♻️ 当前池子列表中的配置（按地址）
+    fn active_pool(&self, address: &str) -> Option<PoolConfig> {
+        self.active_pools.lock().unwrap()
+            .iter()
+            .find(|p| p.address == address)
+            .cloned()
+    }
+    
+    /// ♻️ 热重载：应用新的池子列表，返回差异
+    ///
+    /// - 新增池子：分配连接后发送 accountSubscribe（确认后进入该连接的 subscription_map）
+    /// - 删除池子：退订池子及不再被其他池子使用的 vault，移出 PriceCache / 统计 / VaultReader
+    /// - 修改池子：更新 subscription_map 中的元数据，不重新订阅
+    /// - 🪦 仍在列表中的退役池子：恢复为 Active
+    pub fn apply_pool_list(&self, new_pools: Vec<PoolConfig>) -> PoolDiff {
+        let mut diff = {
+            let mut active = self.active_pools.lock().unwrap();
+            let diff = diff_pools(&active, &new_pools);
+            *active = new_pools;
+            diff
+        };
+        let fees = crate::fee_registry::global();
+        
+        // 修改：重新登记元数据
+        for (old, new) in &diff.updated {
+            let shard = self.shard_for_pool(&new.address);
+            for config in shard.subscription_map.lock().unwrap().values_mut() {
+                if config.address == new.address {
+                    *config = new.clone();
+                }
+            }
+            for config in shard.pool_pending_map.lock().unwrap().values_mut() {
+                if config.address == new.address {
+                    *config = new.clone();
+                }
+            }
+            if old.name != new.name {
+                self.price_cache.rename_pair(&new.address, &new.name);
+                self.pool_stats.rename(&old.name, &new.name);
+                self.last_prices.remove(&old.name);
+            }
+            if old.pool_type != new.pool_type {
+                // pool_type 变化后下一次通知重新解析并校验 owner
+                self.owner_checks.remove(&new.address);
+                crate::orderbook_cache::remove(&new.address);
+            }
+            fees.reload_pool(new);
+            self.price_cache.staleness().reload_pool(new);
+            self.price_cache.circuit_breaker().reload_pool(new);
+            crate::pool_mints::global().reload_pool(new);
+            info!("♻️  Pool metadata updated: {} ({})", new.name, new.address);
+        }
+        
+        // 删除：退订并清理所有状态（🔀 每条连接各自退订自己的 subscription_id）
+        let shards = self.all_shards();
+        let mut unsubscribe_ids: Vec<Vec<u64>> = vec![Vec::new(); shards.len()];
+        let mut orphaned_accounts = Vec::new();
+        for pool in &diff.removed {
+            let shard = self.shard_for_pool(&pool.address);
+            {
+                let mut map = shard.subscription_map.lock().unwrap();
+                let ids: Vec<u64> = map.iter()
+                    .filter(|(_, config)| config.address == pool.address)
+                    .map(|(id, _)| *id)
+                    .collect();
+                for id in ids {
+                    map.remove(&id);
+                    unsubscribe_ids[shard.index].push(id);
+                }
+            }
+            shard.pool_pending_map.lock().unwrap().retain(|_, config| config.address != pool.address);
+            self.shard_plan.lock().unwrap().remove(&pool.address);
+            
+            // 共用的 vault 可能由另一条连接上的池子先订阅，下面在所有连接上查找
+            let orphaned_vaults = self.vault_reader.deregister_pool(&pool.address);
+            info!(
+                "♻️  Pool removed: {} ({}), {} orphaned vaults dropped",
+                pool.name, pool.address, orphaned_vaults.len()
+            );
+            orphaned_accounts.extend(orphaned_vaults);
+            
+            // 📊 DLMM bin array / 📈 CLMM tick array 同样走 vault 订阅通道
+            orphaned_accounts.extend(crate::dlmm_bin_cache::remove_pool(&pool.address));
+            orphaned_accounts.extend(crate::clmm_tick_cache::remove_pool(&pool.address));
+            
+            self.price_cache.remove_price(&pool.address);
+            self.pool_stats.remove(&pool.name);
+            self.last_prices.remove(&pool.name);
+            self.pool_data_cache.lock().unwrap().remove(&pool.address);
+            self.owner_checks.remove(&pool.address);
+            crate::orderbook_cache::remove(&pool.address);
+            fees.clear_pool(&pool.address);
+            self.price_cache.staleness().clear_pool(&pool.address);
+            self.price_cache.circuit_breaker().clear_pool(&pool.address);
+            crate::pool_mints::global().clear_pool(&pool.address);
+        }
+        for (shard, mut ids) in shards.iter().zip(unsubscribe_ids) {
+            if !orphaned_accounts.is_empty() {
+                ids.extend(shard.vault_subscriptions.remove_addresses(&orphaned_accounts));
+            }
+            shard.request_unsubscribe(ids);
+        }
+        
+        // 新增：分配连接后通过该连接的动态订阅channel订阅（未连接时下次连接自动订阅）
+        for pool in &diff.added {
+            fees.reload_pool(pool);
+            self.price_cache.staleness().reload_pool(pool);
+            self.price_cache.circuit_breaker().reload_pool(pool);
+            crate::pool_mints::global().reload_pool(pool);
+            
+            let (index, load) = {
+                let mut plan = self.shard_plan.lock().unwrap();
+                let index = plan.assign(&pool.address);
+                (index, plan.loads[index])
+            };
+            if load > self.max_subscriptions_per_connection {
+                warn!(
+                    "🔀 {}Connection now has {} pools (max_subscriptions_per_connection = {}), restart to rebalance",
+                    self.shard(index).label(), load, self.max_subscriptions_per_connection
+                );
+            }
+            self.shard_for_pool(&pool.address).send_request(SubscriptionRequest::PoolAccount { pool: pool.clone() });
+        }
+        if !diff.added.is_empty() {
+            if self.proactive_vault_fetch {
+                self.spawn_proactive_vault_fetch(diff.added.clone());
+            }
+        }
+        
+        // 不在新配置中的池子不再保留上次价格（改名 / 删除之外的残留条目）
+        {
+            let active = self.active_pools.lock().unwrap();
+            let names: std::collections::HashSet<&str> = active.iter().map(|p| p.name.as_str()).collect();
+            self.last_prices.retain(|name, _| names.contains(name.as_str()));
+        }
+        
+        // 🪦 仍在配置中的退役池子恢复：下一次通知重新校验 owner 并解析
+        for pool in self.active_pools.lock().unwrap().iter() {
+            if self.pool_stats.revive(&pool.name) {
+                self.owner_checks.remove(&pool.address);
+                info!("🪦 Retired pool revived by reload: {} ({})", pool.name, pool.address);
+                diff.revived.push(pool.clone());
+            }
+        }
+        
+        diff
+    }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pool_stats::{DegradedReason, PoolLifecycle};
+    use std::collections::VecDeque;
+    use std::pin::Pin;
+    use std::task::{Context as TaskContext, Poll};
+    
+    /// 内存中的 WebSocket：按顺序吐出预设消息，记录所有发出的文本帧
+    struct MockStream {
+        incoming: VecDeque<Message>,
+        sent: Arc<Mutex<Vec<String>>>,
+    }
+    
+    impl futures_util::Stream for MockStream {
+        type Item = Result<Message, WsError>;
+        
+        fn poll_next(mut self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+            Poll::Ready(self.incoming.pop_front().map(Ok))
+        }
+    }
+    
+    impl futures_util::Sink<Message> for MockStream {
+        type Error = WsError;
+        
+        fn poll_ready(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        
+        fn start_send(self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+            if let Message::Text(text) = item {
+                self.sent.lock().unwrap().push(text);
+            }
+            Ok(())
+        }
+        
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+    
+    fn subscribed_addresses(sent: &[String]) -> Vec<String> {
+        sent.iter()
+            .filter_map(|text| serde_json::from_str::<serde_json::Value>(text).ok())
+            .filter(|msg| msg["method"] == "accountSubscribe")
+            .filter_map(|msg| msg["params"][0].as_str().map(String::from))
+            .collect()
+    }
+    
+    #[tokio::test]
+    async fn test_reconnect_replays_vault_subscriptions() {
+        let pool_address = "7XawhbbxtsRcQA8KTkHT9f9nc6d69UwqCDh6U5EEbEmX";
+        let vault_a = "5Gdp2nbH9BdG6RfwX5uQo3FX9QaNGsj7CgU3i6TqoDJu";
+        let vault_b = "9oZ5dQPoWSoAjG5jfqwpGMQvNDoGHSJkM5bH5a2XbK4y";
+        let pools = vec![PoolConfig {
+            address: pool_address.to_string(),
+            name: "SOL/USDC (SolFi V2)".to_string(),
+            pair: "SOL/USDC".to_string(),
+            pool_type: "solfi_v2".to_string(),
+            fee_bps: None,
+            max_age_ms: None,
+            base_mint: None,
+            quote_mint: None,
+            max_price_jump_percent: None,
+            commitment: None,
+            encoding: None,
+            priority: None,
+        }];
+        
+        let client = WebSocketClient::new(
+            "wss://example.invalid".to_string(),
+            Arc::new(MetricsCollector::new(100)),
+            None,
+            Arc::new(PriceCache::new()),
+            Arc::new(ErrorTracker::new()),
+            0.1,
+            false,
+        );
+        client.vault_reader.register_pool_vaults(pool_address, vault_a, vault_b);
+        *client.active_pools.lock().unwrap() = pools.clone();
+        
+        // 第一次连接：池子 + 两个 vault 订阅都被确认
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let confirmations = [(1, 501), (10002, 777), (10003, 778)]
+            .iter()
+            .map(|(id, sub)| Message::Text(json!({"jsonrpc": "2.0", "id": id, "result": sub}).to_string()))
+            .collect();
+        client.process_stream(&client.shard(0), MockStream { incoming: confirmations, sent: sent.clone() }, &pools).await.unwrap();
+        
+        assert_eq!(subscribed_addresses(&sent.lock().unwrap()), vec![pool_address, vault_a, vault_b]);
+        assert_eq!(client.shard(0).vault_subscriptions.confirmed_len(), 2);
+        
+        // 断线重连：vault 订阅立即重发，旧 subscription_id 映射被清空
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        client.process_stream(&client.shard(0), MockStream { incoming: VecDeque::new(), sent: sent.clone() }, &pools).await.unwrap();
+        
+        assert_eq!(subscribed_addresses(&sent.lock().unwrap()), vec![pool_address, vault_a, vault_b]);
+        assert!(client.shard(0).subscription_map.lock().unwrap().is_empty());
+        assert_eq!(client.shard(0).vault_subscriptions.confirmed_len(), 0);
+        assert_eq!(client.shard(0).vault_subscriptions.pending_len(), 2);
+    }
+    
+    #[tokio::test]
+    async fn test_apply_pool_list_diffs_subscriptions() {
+        let pool = |address: &str, name: &str| PoolConfig {
+            address: address.to_string(),
+            name: name.to_string(),
+            pair: "SOL/USDC".to_string(),
+            pool_type: "solfi_v2".to_string(),
+            fee_bps: None,
+            max_age_ms: None,
+            base_mint: None,
+            quote_mint: None,
+            max_price_jump_percent: None,
+            commitment: None,
+            encoding: None,
+            priority: None,
+        };
+        let removed = pool("7XawhbbxtsRcQA8KTkHT9f9nc6d69UwqCDh6U5EEbEmX", "SOL/USDC (SolFi V2)");
+        let kept = pool("58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2", "SOL/USDC (Raydium)");
+        let added = pool("Czfq3xZZDmsdGdUyrNLtRhGc47cXcZtLG4crryfu44zE", "SOL/USDC (Orca)");
+        let vault_a = "5Gdp2nbH9BdG6RfwX5uQo3FX9QaNGsj7CgU3i6TqoDJu";
+        let vault_b = "9oZ5dQPoWSoAjG5jfqwpGMQvNDoGHSJkM5bH5a2XbK4y";
+        
+        let client = WebSocketClient::new(
+            "wss://example.invalid".to_string(),
+            Arc::new(MetricsCollector::new(100)),
+            None,
+            Arc::new(PriceCache::new()),
+            Arc::new(ErrorTracker::new()),
+            0.1,
+            false,
+        );
+        *client.active_pools.lock().unwrap() = vec![removed.clone(), kept.clone()];
+        client.vault_reader.register_pool_vaults(&removed.address, vault_a, vault_b);
+        client.shard(0).subscription_map.lock().unwrap().insert(501, removed.clone());
+        client.shard(0).subscription_map.lock().unwrap().insert(502, kept.clone());
+        client.shard(0).vault_subscriptions.add_pending(10002, vault_a);
+        client.shard(0).vault_subscriptions.add_pending(10003, vault_b);
+        client.shard(0).vault_subscriptions.confirm(10002, 777);
+        client.shard(0).vault_subscriptions.confirm(10003, 778);
+        client.price_cache.update_price(PoolPrice {
+            dex_name: "SolFi V2".to_string(),
+            base_reserve: 1_000,
+            quote_reserve: 150_000,
+            ..PoolPrice::for_test(&removed.address, &removed.name, 150.0)
+        });
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        *client.shard(0).subscription_tx.lock().unwrap() = Some(tx);
+        
+        let renamed = PoolConfig { name: "SOL/USDC (Raydium V4)".to_string(), ..kept.clone() };
+        let diff = client.apply_pool_list(vec![renamed.clone(), added.clone()]);
+        assert_eq!(diff.added, vec![added.clone()]);
+        assert_eq!(diff.removed, vec![removed.clone()]);
+        assert_eq!(diff.updated, vec![(kept, renamed.clone())]);
+        
+        // 删除的池子及其 vault 退订并移出缓存；修改的池子只更新元数据
+        match rx.try_recv().unwrap() {
+            SubscriptionRequest::Unsubscribe { mut subscription_ids } => {
+                subscription_ids.sort();
+                assert_eq!(subscription_ids, vec![501, 777, 778]);
+            }
+            other => panic!("unexpected request: {:?}", other),
+        }
+        match rx.try_recv().unwrap() {
+            SubscriptionRequest::PoolAccount { pool } => assert_eq!(pool, added),
+            other => panic!("unexpected request: {:?}", other),
+        }
+        assert!(client.price_cache.get_price(&removed.address).is_none());
+        assert!(!client.vault_reader.has_pool_vaults(&removed.address));
+        assert_eq!(client.shard(0).vault_subscriptions.confirmed_len(), 0);
+        assert_eq!(client.shard(0).subscription_map.lock().unwrap()[&502], renamed);
+        
+        // 新增池子的订阅确认（request_id 来自动态订阅区间）
+        client.shard(0).pool_pending_map.lock().unwrap().insert(10007, added.clone());
+        let confirmation = json!({"jsonrpc": "2.0", "id": 10007, "result": 903}).to_string();
+        client.handle_message(&client.shard(0), &confirmation, &[renamed]).await.unwrap();
+        assert_eq!(client.shard(0).subscription_map.lock().unwrap()[&903], added);
+        assert!(client.shard(0).pool_pending_map.lock().unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_owner_mismatch_degrades_until_reload_corrects_pool_type() {
+        let whirlpool_program = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+        let fixture: serde_json::Value =
+            serde_json::from_str(include_str!("../fixtures/whirlpool.json")).unwrap();
+        let misconfigured = PoolConfig {
+            address: "Czfq3xZZDmsdGdUyrNLtRhGc47cXcZtLG4crryfu44zE".to_string(),
+            name: "SOL/USDC (Whirlpool)".to_string(),
+            pair: "SOL/USDC".to_string(),
+            pool_type: "raydium_v4".to_string(),
+            fee_bps: None,
+            max_age_ms: None,
+            base_mint: None,
+            quote_mint: None,
+            max_price_jump_percent: None,
+            commitment: None,
+            encoding: None,
+            priority: None,
+        };
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "accountNotification",
+            "params": {
+                "result": {
+                    "context": {"slot": 318504221},
+                    "value": {
+                        "data": [fixture["data"], "base64"],
+                        "executable": false,
+                        "lamports": 6124800,
+                        "owner": whirlpool_program,
+                        "rentEpoch": 18446744073709551615u64,
+                        "space": 653
+                    }
+                },
+                "subscription": 1
+            }
+        })
+        .to_string();
+        
+        // 其他测试可能已初始化全局 mint 缓存：预先写入 SOL / USDC 精度，避免 Whirlpool 定价走 RPC
+        if let Some(cache) = crate::mint_decimals_cache::get_global_mint_cache() {
+            use base64::Engine;
+            let data = base64::engine::general_purpose::STANDARD.decode(fixture["data"].as_str().unwrap()).unwrap();
+            let (mint_a, mint_b) = crate::deserializers::WhirlpoolState::from_account_data(&data).unwrap().get_mints().unwrap();
+            for (mint, decimals) in [(mint_a, 9), (mint_b, 6)] {
+                cache.insert_info(mint, crate::mint_decimals_cache::MintInfo {
+                    decimals,
+                    program: crate::deserializers::spl_token::TokenProgram::SplToken,
+                    transfer_fee: None,
+                });
+            }
+        }
+        
+        let client = WebSocketClient::new(
+            "wss://example.invalid".to_string(),
+            Arc::new(MetricsCollector::new(100)),
+            None,
+            Arc::new(PriceCache::new()),
+            Arc::new(ErrorTracker::new()),
+            0.1,
+            false,
+        );
+        *client.active_pools.lock().unwrap() = vec![misconfigured.clone()];
+        client.shard(0).subscription_map.lock().unwrap().insert(1, misconfigured.clone());
+        
+        // Whirlpool 账户配置成 raydium_v4：拒绝激活，标记 Degraded，建议正确的类型
+        client.handle_message(&client.shard(0), &notification, &[misconfigured.clone()]).await.unwrap();
+        let check = client.owner_checks.get(&misconfigured.address).unwrap().clone();
+        assert!(!check.verified);
+        assert_eq!(check.suggested_type.as_deref(), Some("whirlpool"));
+        match client.pool_stats.lifecycle(&misconfigured.name) {
+            PoolLifecycle::Degraded { reason: DegradedReason::OwnerMismatch { expected, actual }, .. } => {
+                assert_eq!(expected, "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+                assert_eq!(actual, whirlpool_program);
+            }
+            other => panic!("expected Degraded, got {:?}", other),
+        }
+        assert!(client.price_cache.get_price(&misconfigured.address).is_none());
+        
+        // 热重载修正 pool_type：下一次通知重新校验，池子激活
+        let corrected = PoolConfig { pool_type: "whirlpool".to_string(), ..misconfigured.clone() };
+        let diff = client.apply_pool_list(vec![corrected.clone()]);
+        assert_eq!(diff.updated, vec![(misconfigured, corrected.clone())]);
+        client.handle_message(&client.shard(0), &notification, &[corrected.clone()]).await.unwrap();
+        assert!(client.owner_checks.get(&corrected.address).unwrap().verified);
+        assert_eq!(client.pool_stats.lifecycle(&corrected.name), PoolLifecycle::Active);
+        assert!(client.pool_stats.degraded_pools().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_pools_sharded_across_connections() {
+        let pool = |i: usize| PoolConfig {
+            address: Pubkey::new_unique().to_string(),
+            name: format!("SOL/USDC (SolFi V2) #{}", i),
+            pair: "SOL/USDC".to_string(),
+            pool_type: "solfi_v2".to_string(),
+            fee_bps: None,
+            max_age_ms: None,
+            base_mint: None,
+            quote_mint: None,
+            max_price_jump_percent: None,
+            commitment: None,
+            encoding: None,
+            priority: None,
+        };
+        let pools: Vec<PoolConfig> = (0..400).map(pool).collect();
+        
+        let client = WebSocketClient::new(
+            "wss://example.invalid".to_string(),
+            Arc::new(MetricsCollector::new(100)),
+            None,
+            Arc::new(PriceCache::new()),
+            Arc::new(ErrorTracker::new()),
+            0.1,
+            false,
+        )
+        .with_max_subscriptions_per_connection(100);
+        
+        // 400 个池子 / 每连接 100 个 → 4 条连接，同一份配置的分配每次相同
+        assert_eq!(client.configure_shards(pools.clone()), 4);
+        let shard_pools: Vec<Vec<PoolConfig>> = (0..4).map(|index| client.shard_pools(index)).collect();
+        assert!(shard_pools.iter().all(|assigned| assigned.len() == 100));
+        let replanned = ShardPlan::new(&pools, 100);
+        {
+            let plan = client.shard_plan.lock().unwrap();
+            assert!(pools.iter().all(|p| replanned.shard_of(&p.address) == plan.shard_of(&p.address)));
+        }
+        
+        // 每条连接只订阅自己的池子，subscription_id 登记在所属连接上
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let confirmation = Message::Text(json!({"jsonrpc": "2.0", "id": 1, "result": 501}).to_string());
+        let stream = MockStream { incoming: VecDeque::from([confirmation]), sent: sent.clone() };
+        client.process_stream(&client.shard(2), stream, &shard_pools[2]).await.unwrap();
+        let expected: Vec<String> = shard_pools[2].iter().map(|p| p.address.clone()).collect();
+        assert_eq!(subscribed_addresses(&sent.lock().unwrap()), expected);
+        assert_eq!(client.shard(2).subscription_map.lock().unwrap()[&501], shard_pools[2][0]);
+        assert!(client.shard(0).subscription_map.lock().unwrap().is_empty());
+        
+        // 热重载新增的池子只发到分配到的连接
+        let receivers: Vec<_> = (0..4)
+            .map(|index| {
+                let (tx, rx) = mpsc::unbounded_channel();
+                *client.shard(index).subscription_tx.lock().unwrap() = Some(tx);
+                rx
+            })
+            .collect();
+        let added = pool(400);
+        let mut reloaded = pools.clone();
+        reloaded.push(added.clone());
+        client.apply_pool_list(reloaded);
+        let owner = client.shard_plan.lock().unwrap().shard_of(&added.address);
+        for (index, mut rx) in receivers.into_iter().enumerate() {
+            match rx.try_recv() {
+                Ok(SubscriptionRequest::PoolAccount { pool }) if index == owner => assert_eq!(pool, added),
+                Err(_) if index != owner => {}
+                other => panic!("unexpected request on shard {}: {:?}", index, other),
+            }
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_unknown_subscription_unsubscribed_after_threshold() {
+        use base64::Engine;
+        let client = WebSocketClient::new(
+            "wss://example.invalid".to_string(),
+            Arc::new(MetricsCollector::new(100)),
+            None,
+            Arc::new(PriceCache::new()),
+            Arc::new(ErrorTracker::new()),
+            0.1,
+            false,
+        )
+        .with_subscription_cleanup(5, None);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        *client.shard(0).subscription_tx.lock().unwrap() = Some(tx);
+        
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "accountNotification",
+            "params": {
+                "subscription": 4242,
+                "result": {
+                    "context": {"slot": 7},
+                    "value": {"data": [base64::engine::general_purpose::STANDARD.encode([0u8; 300]), "base64"]}
+                }
+            }
+        })
+        .to_string();
+        
+        // 前 5 条只忽略，第 6 条触发退订，之后不再重复退订
+        for _ in 0..5 {
+            client.handle_message(&client.shard(0), &notification, &[]).await.unwrap();
+        }
+        assert!(rx.try_recv().is_err());
+        for _ in 0..3 {
+            client.handle_message(&client.shard(0), &notification, &[]).await.unwrap();
+        }
+        match rx.try_recv().unwrap() {
+            SubscriptionRequest::Unsubscribe { subscription_ids } => assert_eq!(subscription_ids, vec![4242]),
+            other => panic!("unexpected request: {:?}", other),
+        }
+        assert!(rx.try_recv().is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_per_pool_commitment_and_zstd_notification() {
+        let pool = |address: &str, commitment: Option<Commitment>, encoding: Option<AccountEncoding>| PoolConfig {
+            address: address.to_string(),
+            name: "USDC/USDT (SolFi V2)".to_string(),
+            pair: "USDC/USDT".to_string(),
+            pool_type: "solfi_v2".to_string(),
+            fee_bps: None,
+            max_age_ms: None,
+            base_mint: None,
+            quote_mint: None,
+            max_price_jump_percent: None,
+            commitment,
+            encoding,
+            priority: None,
+        };
+        let hot = pool("7XawhbbxtsRcQA8KTkHT9f9nc6d69UwqCDh6U5EEbEmX", Some(Commitment::Processed), Some(AccountEncoding::Base64Zstd));
+        let normal = pool("58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2", None, None);
+        
+        let client = WebSocketClient::new(
+            "wss://example.invalid".to_string(),
+            Arc::new(MetricsCollector::new(100)),
+            None,
+            Arc::new(PriceCache::new()),
+            Arc::new(ErrorTracker::new()),
+            0.1,
+            false,
+        )
+        .with_subscription_options(Commitment::Finalized, AccountEncoding::Base64);
+        
+        // 池子配置覆盖全局默认
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let pools = vec![hot.clone(), normal.clone()];
+        client.process_stream(&client.shard(0), MockStream { incoming: VecDeque::new(), sent: sent.clone() }, &pools).await.unwrap();
+        let options: Vec<serde_json::Value> = sent.lock().unwrap().iter()
+            .filter_map(|text| serde_json::from_str::<serde_json::Value>(text).ok())
+            .filter(|msg| msg["method"] == "accountSubscribe")
+            .map(|msg| msg["params"][1].clone())
+            .collect();
+        assert_eq!(options, vec![
+            json!({"encoding": "base64+zstd", "commitment": "processed"}),
+            json!({"encoding": "base64", "commitment": "finalized"}),
+        ]);
+        
+        // base64+zstd 通知解压后与原始账户数据一致，vault 订阅跟随池子的 commitment
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        *client.shard(0).subscription_tx.lock().unwrap() = Some(tx);
+        client.shard(0).subscription_map.lock().unwrap().insert(1, hot.clone());
+        let notification = include_str!("../fixtures/notifications/solfi_v2_zstd.json");
+        client.handle_message(&client.shard(0), notification, &pools).await.unwrap();
+        
+        let raw = std::fs::read("analysis-results/solfi_v2-USDC-USDT-(SolFi-V2).bin").unwrap();
+        assert_eq!(client.pool_data_cache.lock().unwrap().get(&hot.address), Some(&raw));
+        match rx.try_recv().unwrap() {
+            SubscriptionRequest::VaultAccount { commitment, .. } => assert_eq!(commitment, Commitment::Processed),
+            other => panic!("unexpected request: {:?}", other),
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_priority_pools_subscribed_first() {
+        let pool = |i: usize, priority: Option<u32>| PoolConfig {
+            address: Pubkey::new_unique().to_string(),
+            name: format!("SOL/USDC #{}", i),
+            pair: "SOL/USDC".to_string(),
+            pool_type: "solfi_v2".to_string(),
+            fee_bps: None,
+            max_age_ms: None,
+            base_mint: None,
+            quote_mint: None,
+            max_price_jump_percent: None,
+            commitment: None,
+            encoding: None,
+            priority,
+        };
+        let pools = vec![pool(0, None), pool(1, Some(10)), pool(2, None), pool(3, Some(50)), pool(4, Some(10))];
+        let sent_ids = |sent: &[String]| -> Vec<u64> {
+            sent.iter()
+                .filter_map(|text| serde_json::from_str::<serde_json::Value>(text).ok())
+                .filter(|msg| msg["method"] == "accountSubscribe")
+                .filter_map(|msg| msg["id"].as_u64())
+                .collect()
+        };
+        let client = |rate: u32| WebSocketClient::new(
+            "wss://example.invalid".to_string(),
+            Arc::new(MetricsCollector::new(100)),
+            None,
+            Arc::new(PriceCache::new()),
+            Arc::new(ErrorTracker::new()),
+            0.1,
+            false,
+        )
+        .with_subscription_pacing(2, rate);
+        
+        // 不限速：全部按优先级发送，request id 仍对应池子在列表中的位置
+        let unpaced = client(0);
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        unpaced.process_stream(&unpaced.shard(0), MockStream { incoming: VecDeque::new(), sent: sent.clone() }, &pools).await.unwrap();
+        assert_eq!(sent_ids(&sent.lock().unwrap()), vec![4, 2, 5, 1, 3]);
+        
+        // 限速：连接建立时只立即发送高优先级批次，其余在消息循环中逐个发送
+        let paced = client(10);
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        paced.process_stream(&paced.shard(0), MockStream { incoming: VecDeque::new(), sent: sent.clone() }, &pools).await.unwrap();
+        assert_eq!(sent_ids(&sent.lock().unwrap()), vec![4, 2]);
+    }
+    
+    #[test]
+    fn test_silent_subscriptions_need_active_connection() {
+        let pool = |address: &str| PoolConfig {
+            address: address.to_string(),
+            name: address.to_string(),
+            pair: "SOL/USDC".to_string(),
+            pool_type: "solfi_v2".to_string(),
+            fee_bps: None,
+            max_age_ms: None,
+            base_mint: None,
+            quote_mint: None,
+            max_price_jump_percent: None,
+            commitment: None,
+            encoding: None,
+            priority: None,
+        };
+        let shard = ConnectionShard::new(0, 1, 0);
+        shard.subscription_map.lock().unwrap().insert(1, pool("quiet"));
+        shard.subscription_map.lock().unwrap().insert(2, pool("busy"));
+        let long_ago = Instant::now() - Duration::from_secs(120);
+        shard.last_notification.lock().unwrap().insert(1, long_ago);
+        shard.last_notification.lock().unwrap().insert(2, long_ago);
+        
+        // 整条连接都安静：交给重连处理，不逐个重新订阅
+        assert!(shard.silent_subscriptions(Duration::from_secs(60)).is_empty());
+        
+        // 其他池子仍在更新：安静的池子需要重新订阅
+        shard.touch(2);
+        let silent = shard.silent_subscriptions(Duration::from_secs(60));
+        assert_eq!(silent.len(), 1);
+        assert_eq!(silent[0].0, 1);
+        
+        shard.forget_subscription(1);
+        assert!(shard.silent_subscriptions(Duration::from_secs(60)).is_empty());
+    }
+    
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_vault_updates_do_not_deadlock() {
+        let data = std::fs::read("analysis-results/solfi_v2-USDC-USDT-(SolFi-V2).bin").unwrap();
+        let (vault_a, vault_b) = PoolFactory::create_pool("solfi_v2", &data).unwrap().get_vault_addresses().unwrap();
+        let (vault_a, vault_b) = (vault_a.to_string(), vault_b.to_string());
+        
+        let client = Arc::new(WebSocketClient::new(
+            "wss://example.invalid".to_string(),
+            Arc::new(MetricsCollector::new(100)),
+            None,
+            Arc::new(PriceCache::new()),
+            Arc::new(ErrorTracker::new()),
+            0.1,
+            false,
+        ));
+        
+        // 20 个池子共用同一对 vault：每次 vault 更新都要重算所有池子
+        let pools: Vec<PoolConfig> = (0..20)
+            .map(|i| PoolConfig {
+                address: Pubkey::new_unique().to_string(),
+                name: format!("USDC/USDT (SolFi V2) #{}", i),
+                pair: "USDC/USDT".to_string(),
+                pool_type: "solfi_v2".to_string(),
+                fee_bps: None,
+                max_age_ms: None,
+                base_mint: None,
+                quote_mint: None,
+                max_price_jump_percent: None,
+                commitment: None,
+                encoding: None,
+                priority: None,
+            })
+            .collect();
+        for (idx, pool) in pools.iter().enumerate() {
+            client.shard(0).subscription_map.lock().unwrap().insert(idx as u64 + 1, pool.clone());
+            client.pool_data_cache.lock().unwrap().insert(pool.address.clone(), data.clone());
+            client.vault_reader.register_pool_vaults(&pool.address, &vault_a, &vault_b);
+        }
+        
+        let token_account = |amount: u64| {
+            let mut account = vec![0u8; 165];
+            account[64..72].copy_from_slice(&amount.to_le_bytes());
+            account[108] = 1;
+            account
+        };
+        
+        let started = Instant::now();
+        let tasks: Vec<_> = (0..16u64)
+            .map(|task| {
+                let client = client.clone();
+                let pools = pools.clone();
+                let (vault_a, vault_b) = (vault_a.clone(), vault_b.clone());
+                tokio::spawn(async move {
+                    for i in 0..200u64 {
+                        let vault = if (task + i) % 2 == 0 { &vault_a } else { &vault_b };
+                        client.handle_vault_update(vault, &token_account(1_000_000 + i), i).await.unwrap();
+                        
+                        let pool = &pools[((task + i) % pools.len() as u64) as usize];
+                        client.trigger_pool_price_recalculation(&pool.address, &pool.name, i).await;
+                        
+                        // 临时池子登记 / 注销与读取交错（共用 vault 不会被移除）
+                        if i % 25 == 0 {
+                            let temp = format!("temp-{}-{}", task, i);
+                            client.vault_reader.register_pool_vaults(&temp, &vault_a, &vault_b);
+                            assert!(client.vault_reader.deregister_pool(&temp).is_empty());
+                        }
+                    }
+                })
+            })
+            .collect();
+        
+        let results = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            futures_util::future::join_all(tasks),
+        )
+        .await
+        .expect("concurrent vault updates deadlocked");
+        for result in results {
+            result.unwrap();
+        }
+        println!("16 tasks x 200 vault updates finished in {:?}", started.elapsed());
+        
+        assert_eq!(client.vault_reader.get_pools_for_vault(&vault_a).len(), pools.len());
+        assert_eq!(client.vault_reader.get_stats().total_vaults, 2);
+        for pool in &pools {
+            assert!(client.price_cache.get_price(&pool.address).is_some(), "{} not recalculated", pool.name);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_internal_caches_stay_bounded_over_10k_pools() {
+        use base64::Engine;
+        let data = std::fs::read("analysis-results/solfi_v2-USDC-USDT-(SolFi-V2).bin").unwrap();
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&data);
+        
+        let error_tracker = Arc::new(ErrorTracker::from_config(&crate::config::ErrorTrackingConfig {
+            max_error_keys: 64,
+            ..Default::default()
+        }));
+        let client = WebSocketClient::new(
+            "wss://example.invalid".to_string(),
+            Arc::new(MetricsCollector::new(100)),
+            None,
+            Arc::new(PriceCache::new()),
+            error_tracker.clone(),
+            0.1,
+            false,
+        )
+        .with_pool_data_cache_capacity(256);
+        
+        let pools: Vec<PoolConfig> = (0..10_000)
+            .map(|i| PoolConfig {
+                address: Pubkey::new_unique().to_string(),
+                name: format!("USDC/USDT (SolFi V2) #{}", i),
+                pair: "USDC/USDT".to_string(),
+                pool_type: "solfi_v2".to_string(),
+                fee_bps: None,
+                max_age_ms: None,
+                base_mint: None,
+                quote_mint: None,
+                max_price_jump_percent: None,
+                commitment: None,
+                encoding: None,
+                priority: None,
+            })
+            .collect();
+        *client.active_pools.lock().unwrap() = pools.clone();
+        
+        // 每个池子走一遍完整的通知处理路径，同时制造同样多的不同 error_key
+        for (idx, pool) in pools.iter().enumerate() {
+            let subscription = idx as u64 + 1;
+            client.shard(0).subscription_map.lock().unwrap().insert(subscription, pool.clone());
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": "accountNotification",
+                "params": {
+                    "subscription": subscription,
+                    "result": {
+                        "context": {"slot": 1_000 + idx as u64},
+                        "value": {"data": [encoded, "base64"]}
+                    }
+                }
+            })
+            .to_string();
+            client.handle_message(&client.shard(0), &notification, &[]).await.unwrap();
+            error_tracker.record_error(&format!("synthetic_{}", idx), format!("{}: synthetic", pool.name)).await;
+            
+            let sizes = client.cache_sizes().sizes();
+            assert!(sizes[0].entries <= 256, "pool_data_cache grew to {}", sizes[0].entries);
+        }
+        
+        let sizes = client.cache_sizes().sizes();
+        assert_eq!(sizes[0].cache, "pool_data");
+        assert_eq!(sizes[0].entries, 256);
+        assert_eq!(sizes[0].evictions, 10_000 - 256);
+        assert_eq!(sizes[0].bytes, Some(256 * data.len()));
+        let errors = error_tracker.cache_size().await;
+        assert_eq!(errors.entries, 64);
+        assert!(errors.evictions >= 10_000 - 64);
+        
+        // 被淘汰的池子在下一次通知时重新缓存
+        assert!(client.pool_data_cache.lock().unwrap().get(&pools[0].address).is_none());
+        
+        // 热重载缩小池子列表：last_prices 只保留配置中的池子
+        client.apply_pool_list(pools[..100].to_vec());
+        let sizes = client.cache_sizes().sizes();
+        assert!(sizes[0].entries <= 100);
+        assert_eq!(sizes[1].cache, "last_prices");
+        assert!(sizes[1].entries <= 100);
+    }
+}
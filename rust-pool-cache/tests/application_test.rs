@@ -0,0 +1,141 @@
+/*!
+ * Application 集成测试 - 用本地 mock WebSocket 源启动完整装配，再通过 CancellationToken 有序关闭
+ */
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tokio::time::{sleep, timeout};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::CancellationToken;
+
+use solana_pool_cache::app::{DatabaseReport, InitializationReport, ShutdownTrigger};
+use solana_pool_cache::config::Config;
+use solana_pool_cache::{Application, ApplicationOptions};
+
+const POOL_NAME: &str = "SOL/USDC (Raydium V4)";
+
+/// mock 源收到的订阅 / 退订请求数
+#[derive(Default)]
+struct MockFeed {
+    subscribes: AtomicUsize,
+    unsubscribes: AtomicUsize,
+}
+
+/// 最小 Solana WebSocket RPC：确认 accountSubscribe（subscription id = 请求 id + 100），记录 accountUnsubscribe
+async fn spawn_mock_feed() -> (String, Arc<MockFeed>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let feed = Arc::new(MockFeed::default());
+
+    let counters = feed.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let counters = counters.clone();
+            tokio::spawn(async move {
+                // 主动 vault 查询会把 HTTP RPC 请求发到同一端口，握手失败直接丢弃
+                let Ok(mut ws) = tokio_tungstenite::accept_async(stream).await else {
+                    return;
+                };
+                while let Some(Ok(Message::Text(text))) = ws.next().await {
+                    let request: Value = serde_json::from_str(&text).unwrap();
+                    let id = request["id"].as_u64().unwrap();
+                    let result = match request["method"].as_str() {
+                        Some("accountSubscribe") => {
+                            counters.subscribes.fetch_add(1, Ordering::SeqCst);
+                            json!(id + 100)
+                        }
+                        Some("accountUnsubscribe") => {
+                            counters.unsubscribes.fetch_add(1, Ordering::SeqCst);
+                            json!(true)
+                        }
+                        _ => continue,
+                    };
+                    let response = json!({ "jsonrpc": "2.0", "result": result, "id": id });
+                    if ws.send(Message::Text(response.to_string())).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    (url, feed)
+}
+
+/// 单池配置：不做 RPC 初始化，mint 缓存指向本地关闭的端口（epoch 查询立即失败）
+fn test_config(ws_url: &str) -> Config {
+    toml::from_str(&format!(
+        r#"
+[websocket]
+url = "{}"
+
+[initialization]
+enabled = false
+rpc_urls = ["http://127.0.0.1:9"]
+
+[[pools]]
+name = "{}"
+address = "58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2"
+pool_type = "amm_v4"
+pair = "SOL/USDC"
+"#,
+        ws_url, POOL_NAME
+    ))
+    .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_application_boots_and_shuts_down_cleanly() {
+    let (url, feed) = spawn_mock_feed().await;
+    let options = ApplicationOptions {
+        config_path: None,
+        api_port: 0,
+    };
+    let app = Application::build_with_options(test_config(&url), options).await.unwrap();
+
+    let startup = app.startup_report();
+    assert_eq!(startup.monitored_pools, 1);
+    assert_eq!(startup.initialization, InitializationReport::Disabled);
+    assert_eq!(startup.database, DatabaseReport::NotConfigured);
+    assert_eq!(startup.websocket_url, url);
+    assert!(startup.to_string().contains("Monitoring 1 pools"));
+
+    let pool_stats = app.pool_stats();
+    let shutdown = CancellationToken::new();
+    let running = tokio::spawn(app.run(shutdown.clone()));
+
+    // 等客户端处理完订阅确认（之后退订才会包含这个池子）
+    timeout(Duration::from_secs(5), async {
+        while pool_stats.get_pool_stats(POOL_NAME).is_none() {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("pool subscription was not confirmed");
+    assert_eq!(feed.subscribes.load(Ordering::SeqCst), 1);
+
+    shutdown.cancel();
+    let report = timeout(Duration::from_secs(10), running)
+        .await
+        .expect("shutdown exceeded its deadline")
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(report.trigger, ShutdownTrigger::Requested);
+    assert_eq!(report.unsubscribed_accounts, 1);
+    assert_eq!(report.flushed_records, 0);
+
+    // 退订请求确实到达 mock 源
+    timeout(Duration::from_secs(2), async {
+        while feed.unsubscribes.load(Ordering::SeqCst) == 0 {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("unsubscribe never reached the feed");
+}
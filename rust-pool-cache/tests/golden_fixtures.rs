@@ -0,0 +1,207 @@
+/// 反序列化器 golden 测试：fixtures/ 下每个 pool_type 一份真实主网账户
+///
+/// 每份 fixture 经 PoolFactory::create_pool 解析后检查：
+/// - 账户大小与该类型的结构大小一致
+/// - decimals 非零
+/// - 储备在池子账户内的类型：储备在合理范围内（vault / 流动性推导的类型跳过）
+/// - calculate_price() 有限且非负
+/// - 有 vault 的类型：get_vault_addresses() 返回两个不同的非默认 pubkey
+///
+/// 新增 fixture：cargo run --bin capture_fixture -- <address> <pool_type>
+/// 设置 REQUIRE_ALL_FIXTURES=1 时，缺少 fixture 的 pool_type 也算失败。
+use std::path::Path;
+
+use solana_pool_cache::pool_factory::{PoolFactory, KNOWN_POOL_TYPES};
+use solana_pool_cache::pool_fixture::{load_all, PoolFixture};
+use solana_sdk::pubkey::Pubkey;
+
+/// 储备来源
+#[derive(Clone, Copy, PartialEq)]
+enum Reserves {
+    /// 储备直接存在池子账户里
+    InAccount,
+    /// 储备在外部 vault 账户，或由流动性 / bin 推导
+    External,
+}
+
+struct TypeSpec {
+    pool_type: &'static str,
+    /// 期望的账户大小（空 = 变长，不检查）
+    sizes: &'static [usize],
+    reserves: Reserves,
+    /// 是否应返回 vault 地址
+    vaults: bool,
+}
+
+const SPECS: &[TypeSpec] = &[
+    TypeSpec { pool_type: "amm_v4", sizes: &[752, 388], reserves: Reserves::External, vaults: false },
+    TypeSpec { pool_type: "clmm", sizes: &[1544], reserves: Reserves::External, vaults: true },
+    TypeSpec { pool_type: "lifinity_v2", sizes: &[911], reserves: Reserves::External, vaults: true },
+    TypeSpec { pool_type: "meteora_dlmm", sizes: &[904], reserves: Reserves::External, vaults: false },
+    TypeSpec { pool_type: "alphaq", sizes: &[672], reserves: Reserves::InAccount, vaults: false },
+    TypeSpec { pool_type: "solfi_v2", sizes: &[1728], reserves: Reserves::External, vaults: true },
+    TypeSpec { pool_type: "humidifi", sizes: &[1728], reserves: Reserves::External, vaults: true },
+    TypeSpec { pool_type: "goonfi", sizes: &[856], reserves: Reserves::External, vaults: true },
+    TypeSpec { pool_type: "tesserav", sizes: &[1264], reserves: Reserves::InAccount, vaults: false },
+    TypeSpec { pool_type: "stabble", sizes: &[438], reserves: Reserves::InAccount, vaults: false },
+    TypeSpec { pool_type: "aquifer", sizes: &[], reserves: Reserves::External, vaults: false },
+    TypeSpec { pool_type: "whirlpool", sizes: &[653], reserves: Reserves::External, vaults: true },
+    TypeSpec { pool_type: "pancakeswap", sizes: &[1544], reserves: Reserves::External, vaults: true },
+    TypeSpec { pool_type: "phoenix", sizes: &[], reserves: Reserves::External, vaults: false },
+    TypeSpec { pool_type: "openbook_v2", sizes: &[], reserves: Reserves::External, vaults: false },
+];
+
+/// 储备上限（原始单位）：超过说明读到了错位的字段
+const MAX_PLAUSIBLE_RESERVE: u64 = 1_000_000_000_000_000_000;
+
+fn spec(pool_type: &str) -> Option<&'static TypeSpec> {
+    SPECS.iter().find(|s| s.pool_type == pool_type)
+}
+
+/// 检查一份 fixture，返回所有不满足的不变量
+fn check_fixture(fixture: &PoolFixture) -> Vec<String> {
+    let mut failures = Vec::new();
+    let spec = match spec(&fixture.pool_type) {
+        Some(spec) => spec,
+        None => return vec![format!("no spec for pool_type '{}'", fixture.pool_type)],
+    };
+    let data = match fixture.decode_data() {
+        Ok(data) => data,
+        Err(e) => return vec![format!("{:#}", e)],
+    };
+
+    if !spec.sizes.is_empty() && !spec.sizes.contains(&data.len()) {
+        failures.push(format!("size {} bytes, expected one of {:?}", data.len(), spec.sizes));
+    }
+    if let Some(owner) = &fixture.owner {
+        if let Err(e) = PoolFactory::verify_owner(spec.pool_type, owner) {
+            failures.push(e.to_string());
+        }
+    }
+
+    let pool = match PoolFactory::create_pool(spec.pool_type, &data) {
+        Ok(pool) => pool,
+        Err(e) => {
+            failures.push(format!("create_pool failed: {}", e));
+            return failures;
+        }
+    };
+
+    let (base_decimals, quote_decimals) = pool.get_decimals();
+    if base_decimals == 0 || quote_decimals == 0 {
+        failures.push(format!("zero decimals ({}, {})", base_decimals, quote_decimals));
+    }
+
+    let (reserve_a, reserve_b) = pool.get_reserves();
+    if spec.reserves == Reserves::InAccount && fixture.known_issue("reserves").is_none() {
+        for reserve in [reserve_a, reserve_b] {
+            if reserve == 0 || reserve > MAX_PLAUSIBLE_RESERVE {
+                failures.push(format!("implausible reserves ({}, {})", reserve_a, reserve_b));
+                break;
+            }
+        }
+    }
+
+    let price = pool.calculate_price();
+    if !price.is_finite() || price < 0.0 {
+        failures.push(format!("price {} is not finite and non-negative", price));
+    }
+
+    if spec.vaults && fixture.known_issue("vaults").is_none() {
+        match pool.get_vault_addresses() {
+            Some((vault_a, vault_b)) => {
+                if vault_a == Pubkey::default() || vault_b == Pubkey::default() || vault_a == vault_b {
+                    failures.push(format!("invalid vaults ({}, {})", vault_a, vault_b));
+                }
+            }
+            None => failures.push("get_vault_addresses() returned None".to_string()),
+        }
+    }
+
+    failures
+}
+
+#[test]
+fn test_golden_fixtures() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    let fixtures = load_all(&dir).expect("failed to load fixtures");
+    assert!(!fixtures.is_empty(), "no fixtures in {}", dir.display());
+
+    let mut failures = Vec::new();
+    for fixture in &fixtures {
+        assert_eq!(
+            PoolFactory::canonical_pool_type(&fixture.pool_type),
+            Some(fixture.pool_type.as_str()),
+            "fixture pool_type must be a canonical name"
+        );
+        for (check, reason) in &fixture.known_issues {
+            println!("⚠️  {}: skipping '{}' check (known issue: {})", fixture.pool_type, check, reason);
+        }
+        let errors = check_fixture(fixture);
+        if errors.is_empty() {
+            println!("✅ {} ({})", fixture.pool_type, fixture.address.as_deref().unwrap_or("unknown address"));
+        }
+        failures.extend(errors.into_iter().map(|e| format!("{}: {}", fixture.pool_type, e)));
+    }
+    assert!(failures.is_empty(), "golden fixture failures:\n{}", failures.join("\n"));
+
+    let missing: Vec<&str> = KNOWN_POOL_TYPES.iter()
+        .copied()
+        .filter(|t| !fixtures.iter().any(|f| f.pool_type == *t))
+        .collect();
+    if !missing.is_empty() {
+        println!("⚠️  no fixture for: {}", missing.join(", "));
+        assert!(
+            std::env::var("REQUIRE_ALL_FIXTURES").is_err(),
+            "REQUIRE_ALL_FIXTURES set, missing fixtures: {}",
+            missing.join(", ")
+        );
+    }
+}
+
+#[test]
+fn test_every_known_pool_type_has_a_spec() {
+    for pool_type in KNOWN_POOL_TYPES {
+        assert!(spec(pool_type).is_some(), "missing TypeSpec for {}", pool_type);
+    }
+}
+
+#[test]
+fn test_checks_reject_corrupted_account() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    let fixture = PoolFixture::load(&dir.join("lifinity_v2.json")).unwrap();
+    let mut data = fixture.decode_data().unwrap();
+
+    // 清零 vault A（偏移 158）→ vault 检查失败
+    data[158..190].fill(0);
+    let mut corrupted = PoolFixture::new("lifinity_v2", &data);
+    corrupted.owner = fixture.owner.clone();
+    let failures = check_fixture(&corrupted);
+    assert!(failures.iter().any(|f| f.contains("invalid vaults")), "{:?}", failures);
+
+    // 截断 → 大小检查与解析都失败
+    let truncated = PoolFixture::new("lifinity_v2", &data[..900]);
+    let failures = check_fixture(&truncated);
+    assert!(failures.iter().any(|f| f.starts_with("size 900")), "{:?}", failures);
+}
+
+#[test]
+fn test_lifinity_v2_vaults_match_known_pool() {
+    // SOL/USDC Lifinity V2 池子 DrRd8gYMJu9XGxLhwTCPdHNLXCKHsxJtMpbn62YqmwQe 的 vault（Lifinity UI 列出的池子账户）
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    let fixture = PoolFixture::load(&dir.join("lifinity_v2.json")).unwrap();
+    assert_eq!(fixture.address.as_deref(), Some("DrRd8gYMJu9XGxLhwTCPdHNLXCKHsxJtMpbn62YqmwQe"));
+    let pool = PoolFactory::create_pool("lifinity_v2", &fixture.decode_data().unwrap()).unwrap();
+
+    let (vault_a, vault_b) = pool.get_vault_addresses().unwrap();
+    assert_eq!(vault_a.to_string(), "EVGW4q1iFjDmtxtHr3NoPi5iVKAxwEjohsusMrinDxr6");
+    assert_eq!(vault_b.to_string(), "53EkU98Vbv2TQPwGG6t2asCynzFjCX5AnvaabbXafaed");
+    let (mint_a, mint_b) = pool.get_mints().unwrap();
+    assert_eq!(mint_a.to_string(), "So11111111111111111111111111111111111111112");
+    assert_eq!(mint_b.to_string(), "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+
+    // 储备量走 vault，价格取 oracle 调整后的 last_price
+    assert_eq!(pool.get_reserves(), (0, 0));
+    let price = pool.calculate_price();
+    assert!((100.0..400.0).contains(&price), "oracle price {} out of range", price);
+}
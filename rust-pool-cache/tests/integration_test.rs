@@ -30,6 +30,7 @@ mod integration_tests {
             enable_split_optimization: true,
             max_splits: 5,
             min_split_amount: 100.0,
+            ..Default::default()
         };
         
         let router = AdvancedRouter::new(cache, config);
@@ -97,6 +98,8 @@ mod integration_tests {
                 quote_decimals: 6,
                 last_update: Instant::now(),
                 slot: 1000,
+                liquidity_usd: None,
+                commitment: None,
             },
             PoolPrice {
                 pool_id: "orca_sol_usdc".to_string(),
@@ -109,6 +112,8 @@ mod integration_tests {
                 quote_decimals: 6,
                 last_update: Instant::now(),
                 slot: 1000,
+                liquidity_usd: None,
+                commitment: None,
             },
             PoolPrice {
                 pool_id: "solfi_usdc_usdt".to_string(),
@@ -121,6 +126,8 @@ mod integration_tests {
                 quote_decimals: 6,
                 last_update: Instant::now(),
                 slot: 1000,
+                liquidity_usd: None,
+                commitment: None,
             },
             PoolPrice {
                 pool_id: "raydium_sol_usdt".to_string(),
@@ -133,6 +140,8 @@ mod integration_tests {
                 quote_decimals: 6,
                 last_update: Instant::now(),
                 slot: 1000,
+                liquidity_usd: None,
+                commitment: None,
             },
         ]
     }
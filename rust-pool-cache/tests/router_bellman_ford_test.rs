@@ -6,6 +6,7 @@
 mod bellman_ford_tests {
     use solana_pool_cache::price_cache::{PoolPrice, PriceCache};
     use solana_pool_cache::router_bellman_ford::BellmanFordScanner;
+    use solana_pool_cache::execution_cost::ExecutionCostModel;
     use std::time::Instant;
     
     #[test]
@@ -19,7 +20,7 @@ mod bellman_ford_tests {
             create_test_pool("pool3", "USDC/USDT", "SolFi V2", 1.001, 10000000, 10010000),
         ];
         
-        let scanner = BellmanFordScanner::new(6, 0.1);
+        let scanner = BellmanFordScanner::new(6, 0.1, ExecutionCostModel::shared_default());
         let cycles = scanner.find_all_cycles(&pools, 1000.0);
         
         // 应该找到至少一个循环
@@ -39,7 +40,7 @@ mod bellman_ford_tests {
             create_test_pool("pool2", "SOL/USDC", "Lifinity V2", 151.0, 800000, 120800000),
         ];
         
-        let scanner = BellmanFordScanner::new(6, 0.3);
+        let scanner = BellmanFordScanner::new(6, 0.3, ExecutionCostModel::shared_default());
         let cycles = scanner.find_all_cycles(&pools, 1000.0);
         
         for cycle in &cycles {
@@ -52,7 +53,7 @@ mod bellman_ford_tests {
     #[test]
     fn test_max_hops_limit() {
         // 测试最大跳数限制
-        let scanner = BellmanFordScanner::new(4, 0.1);
+        let scanner = BellmanFordScanner::new(4, 0.1, ExecutionCostModel::shared_default());
         
         // TODO: 创建更复杂的图来测试跳数限制
     }
@@ -77,6 +78,8 @@ mod bellman_ford_tests {
             quote_decimals: 6,
             last_update: Instant::now(),
             slot: 1000,
+            liquidity_usd: None,
+            commitment: None,
         }
     }
 }
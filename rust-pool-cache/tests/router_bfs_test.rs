@@ -5,6 +5,7 @@
  */
 
 use solana_pool_cache::router_bfs::BfsScanner;
+use solana_pool_cache::execution_cost::ExecutionCostModel;
 use solana_pool_cache::price_cache::{PoolPrice, PriceCache};
 use std::time::Instant;
 use std::sync::Arc;
@@ -28,6 +29,8 @@ fn create_test_pool(
         price,
         last_update: Instant::now(),
         slot: 1000,
+        liquidity_usd: None,
+        commitment: None,
     }
 }
 
@@ -39,7 +42,7 @@ fn test_bfs_simple_2hop() {
         create_test_pool("pool2", "Orca", "SOL/USDC", 186.0, 1000_000_000_000, 186_000_000_000),
     ];
     
-    let scanner = BfsScanner::new(3, 0.1);
+    let scanner = BfsScanner::new(3, 0.1, ExecutionCostModel::shared_default());
     let paths = scanner.find_all_opportunities(&pools, 1000.0);
     
     // 应该找到至少1条路径：USDC → SOL (pool1) → USDC (pool2)
@@ -59,7 +62,7 @@ fn test_bfs_triangle_3hop() {
         create_test_pool("pool3", "Meteora", "USDT/SOL", 0.00541, 1_000_000_000, 5_410_000_000_000),
     ];
     
-    let scanner = BfsScanner::new(4, 0.05);
+    let scanner = BfsScanner::new(4, 0.05, ExecutionCostModel::shared_default());
     let paths = scanner.find_all_opportunities(&pools, 1.0);  // 1 SOL
     
     // 应该找到三角套利路径
@@ -80,7 +83,7 @@ fn test_bfs_early_pruning() {
         create_test_pool("pool3", "Meteora", "USDC/USDT", 1.0, 1_000_000_000, 1_000_000_000),
     ];
     
-    let scanner = BfsScanner::new(4, 0.1);
+    let scanner = BfsScanner::new(4, 0.1, ExecutionCostModel::shared_default());
     let paths = scanner.find_all_opportunities(&pools, 1000.0);
     
     // pool2价格更低，不应该产生有效套利
@@ -98,7 +101,7 @@ fn test_bfs_no_infinite_loop() {
         create_test_pool("pool3", "Meteora", "SOL/USDC", 184.0, 1000_000_000_000, 184_000_000_000),
     ];
     
-    let scanner = BfsScanner::new(3, 0.1);
+    let scanner = BfsScanner::new(3, 0.1, ExecutionCostModel::shared_default());
     
     // 应该在合理时间内完成（不死循环）
     let start = std::time::Instant::now();
@@ -117,7 +120,7 @@ fn test_bfs_path_deduplication() {
         create_test_pool("pool3", "Meteora", "SOL/USDC", 185.5, 1000_000_000_000, 185_500_000_000),
     ];
     
-    let scanner = BfsScanner::new(3, 0.1);
+    let scanner = BfsScanner::new(3, 0.1, ExecutionCostModel::shared_default());
     let paths = scanner.find_all_opportunities(&pools, 1000.0);
     
     // 验证没有完全重复的路径
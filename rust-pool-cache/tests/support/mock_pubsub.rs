@@ -0,0 +1,206 @@
+/*!
+ * Mock Solana pubsub 服务器（只实现 WebSocketClient 用到的部分）
+ *
+ * - accountSubscribe：分配递增的 subscription id（从 `FIRST_SUBSCRIPTION_ID` 开始，
+ *   与 request id 不重合，测试能区分两者）并记录 账户 -> subscription id
+ * - accountUnsubscribe：记录并回复 true
+ * - 测试按需推送 accountNotification、任意原始帧（畸形 JSON / base64），或直接断开当前连接
+ *
+ * 同一时间只服务最新的一条连接：客户端重连后，推送都发到新连接上。
+ */
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+use super::eventually;
+
+/// 第一个分配出去的 subscription id
+pub const FIRST_SUBSCRIPTION_ID: u64 = 1000;
+
+/// 服务器确认过的一次 accountSubscribe
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subscription {
+    /// 第几条连接（从 1 开始）
+    pub connection: usize,
+    pub request_id: u64,
+    pub account: String,
+    pub subscription_id: u64,
+}
+
+/// 推给当前连接的指令
+enum Push {
+    Frame(String),
+    /// 不发 Close 帧直接丢弃连接（模拟网络中断）
+    Disconnect,
+}
+
+struct State {
+    next_subscription_id: AtomicU64,
+    connections: AtomicUsize,
+    subscriptions: Mutex<Vec<Subscription>>,
+    unsubscribes: Mutex<Vec<u64>>,
+    current: Mutex<Option<mpsc::UnboundedSender<Push>>>,
+}
+
+pub struct MockPubsub {
+    url: String,
+    state: Arc<State>,
+}
+
+impl MockPubsub {
+    /// 在随机端口上启动
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let state = Arc::new(State {
+            next_subscription_id: AtomicU64::new(FIRST_SUBSCRIPTION_ID),
+            connections: AtomicUsize::new(0),
+            subscriptions: Mutex::new(Vec::new()),
+            unsubscribes: Mutex::new(Vec::new()),
+            current: Mutex::new(None),
+        });
+
+        let server = state.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                // 非 WebSocket 请求（如 HTTP RPC）握手失败直接丢弃
+                let Ok(ws) = tokio_tungstenite::accept_async(stream).await else {
+                    continue;
+                };
+                let connection = server.connections.fetch_add(1, Ordering::SeqCst) + 1;
+                let (tx, rx) = mpsc::unbounded_channel();
+                *server.current.lock().unwrap() = Some(tx);
+                tokio::spawn(serve(server.clone(), connection, ws, rx));
+            }
+        });
+
+        Self { url, state }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// 已接受的 WebSocket 连接数（重连后递增）
+    pub fn connections(&self) -> usize {
+        self.state.connections.load(Ordering::SeqCst)
+    }
+
+    /// 所有连接上确认过的订阅（按确认顺序）
+    pub fn subscriptions(&self) -> Vec<Subscription> {
+        self.state.subscriptions.lock().unwrap().clone()
+    }
+
+    /// 收到的 accountUnsubscribe 参数
+    pub fn unsubscribes(&self) -> Vec<u64> {
+        self.state.unsubscribes.lock().unwrap().clone()
+    }
+
+    /// 等待 `account` 在第 `connection` 条连接上被订阅
+    pub async fn wait_for_subscription(&self, account: &str, connection: usize) -> Subscription {
+        let find = || {
+            self.subscriptions()
+                .into_iter()
+                .find(|s| s.account == account && s.connection == connection)
+        };
+        eventually(&format!("subscription to {} on connection {}", account, connection), || find().is_some()).await;
+        find().unwrap()
+    }
+
+    /// 推送 accountNotification（`data` 为 base64 账户数据）
+    pub fn push_account(&self, subscription_id: u64, slot: u64, data: &str) {
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "accountNotification",
+            "params": {
+                "subscription": subscription_id,
+                "result": {
+                    "context": { "slot": slot },
+                    "value": { "data": [data, "base64"], "lamports": 1, "executable": false }
+                }
+            }
+        });
+        self.push_raw(&notification.to_string());
+    }
+
+    /// 原样推送一个文本帧（用于畸形消息）
+    pub fn push_raw(&self, text: &str) {
+        self.send(Push::Frame(text.to_string()));
+    }
+
+    /// 断开当前连接（不发 Close 帧）
+    pub fn disconnect(&self) {
+        self.send(Push::Disconnect);
+    }
+
+    fn send(&self, push: Push) {
+        let current = self.state.current.lock().unwrap();
+        let sent = current.as_ref().is_some_and(|tx| tx.send(push).is_ok());
+        assert!(sent, "no client connected to the mock pubsub server");
+    }
+}
+
+/// 一条连接：回复订阅 / 退订请求，转发测试推送
+async fn serve(
+    state: Arc<State>,
+    connection: usize,
+    mut ws: tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
+    mut pushes: mpsc::UnboundedReceiver<Push>,
+) {
+    loop {
+        tokio::select! {
+            message = ws.next() => {
+                let Some(Ok(message)) = message else {
+                    return;
+                };
+                let Message::Text(text) = message else {
+                    continue;
+                };
+                let Some(response) = respond(&state, connection, &text) else {
+                    continue;
+                };
+                if ws.send(Message::Text(response.to_string())).await.is_err() {
+                    return;
+                }
+            }
+            push = pushes.recv() => match push {
+                Some(Push::Frame(text)) => {
+                    if ws.send(Message::Text(text)).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Push::Disconnect) | None => return,
+            }
+        }
+    }
+}
+
+/// JSON-RPC 请求的回复（不认识的请求返回 None）
+fn respond(state: &State, connection: usize, text: &str) -> Option<Value> {
+    let request: Value = serde_json::from_str(text).ok()?;
+    let id = request["id"].as_u64()?;
+    let result = match request["method"].as_str()? {
+        "accountSubscribe" => {
+            let subscription_id = state.next_subscription_id.fetch_add(1, Ordering::SeqCst);
+            state.subscriptions.lock().unwrap().push(Subscription {
+                connection,
+                request_id: id,
+                account: request["params"][0].as_str()?.to_string(),
+                subscription_id,
+            });
+            json!(subscription_id)
+        }
+        "accountUnsubscribe" => {
+            state.unsubscribes.lock().unwrap().push(request["params"][0].as_u64()?);
+            json!(true)
+        }
+        _ => return None,
+    };
+    Some(json!({ "jsonrpc": "2.0", "result": result, "id": id }))
+}
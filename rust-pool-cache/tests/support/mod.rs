@@ -0,0 +1,60 @@
+/*!
+ * 集成测试共用工具：mock Solana pubsub 服务器 + fixture 辅助函数
+ *
+ * 每个测试文件各自 `mod support;`，用不到的辅助函数不算 dead code。
+ */
+#![allow(dead_code)]
+
+pub mod mock_pubsub;
+
+use std::path::Path;
+use std::time::Duration;
+
+use base64::Engine;
+use solana_pool_cache::config::PoolConfig;
+use solana_pool_cache::pool_fixture::{fixture_path, PoolFixture};
+use tokio::time::{sleep, timeout};
+
+/// 读取 `fixtures/<pool_type>.json`
+pub fn fixture(pool_type: &str) -> PoolFixture {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    PoolFixture::load(&fixture_path(&dir, pool_type)).unwrap()
+}
+
+/// fixture 对应的池子配置（`address` 为空时使用 fixture 记录的地址）
+pub fn pool_config(fixture: &PoolFixture, address: Option<&str>) -> PoolConfig {
+    let name = fixture.name.clone().unwrap_or_else(|| fixture.pool_type.clone());
+    PoolConfig {
+        address: address.map(str::to_string).or_else(|| fixture.address.clone()).unwrap(),
+        pair: name.split(' ').next().unwrap_or_default().to_string(),
+        name,
+        pool_type: fixture.pool_type.clone(),
+        fee_bps: None,
+        max_age_ms: None,
+        base_mint: None,
+        quote_mint: None,
+        max_price_jump_percent: None,
+        commitment: None,
+        encoding: None,
+        priority: None,
+    }
+}
+
+/// 165 字节的 SPL Token 账户（base64），只填 amount 和 state = Initialized
+pub fn token_account(amount: u64) -> String {
+    let mut account = vec![0u8; 165];
+    account[64..72].copy_from_slice(&amount.to_le_bytes());
+    account[108] = 1;
+    base64::engine::general_purpose::STANDARD.encode(account)
+}
+
+/// 每 10ms 检查一次，5 秒内不成立则 panic
+pub async fn eventually(what: &str, mut condition: impl FnMut() -> bool) {
+    timeout(Duration::from_secs(5), async {
+        while !condition() {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("timed out waiting for {}", what));
+}
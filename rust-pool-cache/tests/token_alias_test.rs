@@ -0,0 +1,102 @@
+/*!
+ * 代币别名集成测试：wSOL 池子和 SOL 池子只有归一成同一节点后才能连成循环
+ *
+ * 别名表显式传给每个扫描器（`with_token_aliases`），开 / 关别名的场景互不影响。
+ */
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use solana_pool_cache::price_cache::{PoolPrice, PriceCache};
+use solana_pool_cache::router_bellman_ford::BellmanFordScanner;
+use solana_pool_cache::router_bfs::BfsScanner;
+use solana_pool_cache::execution_cost::ExecutionCostModel;
+use solana_pool_cache::router_direct::DirectArbTable;
+use solana_pool_cache::token_alias::TokenAliases;
+
+/// 深度 1,000,000 base 代币，储备量与价格一致（100 USDC 的价格冲击可以忽略）
+fn pool(pool_id: &str, pair: &str, price: f64, base_decimals: u8, quote_decimals: u8) -> PoolPrice {
+    let depth = 1_000_000.0;
+    PoolPrice {
+        pool_id: pool_id.to_string(),
+        dex_name: "Raydium AMM V4".to_string(),
+        pair: pair.to_string(),
+        base_reserve: (depth * 10f64.powi(base_decimals as i32)) as u64,
+        quote_reserve: (depth * price * 10f64.powi(quote_decimals as i32)) as u64,
+        base_decimals,
+        quote_decimals,
+        price,
+        last_update: Instant::now(),
+        slot: 1000,
+        liquidity_usd: None,
+        commitment: None,
+    }
+}
+
+/// USDC → SOL（150）→ USDT（wSOL 池子，152）→ USDC（1.0）：毛利约 1.3%，三跳手续费 0.75%
+fn bridged_cycle() -> Vec<PoolPrice> {
+    vec![
+        pool("sol-usdc", "SOL/USDC", 150.0, 9, 6),
+        pool("wsol-usdt", "wSOL/USDT", 152.0, 9, 6),
+        pool("usdt-usdc", "USDT/USDC", 1.0, 6, 6),
+    ]
+}
+
+#[test]
+fn test_wsol_sol_cycle_missed_without_aliasing() {
+    // 关闭别名：SOL 和 wSOL 是两个节点，三个池子连成一条链而不是环
+    let pools = bridged_cycle();
+    let disabled = Arc::new(TokenAliases::disabled());
+    let bfs = BfsScanner::new(3, 0.1, ExecutionCostModel::shared_default()).with_token_aliases(disabled.clone());
+    let bellman_ford = BellmanFordScanner::new(4, 0.1, ExecutionCostModel::shared_default()).with_token_aliases(disabled.clone());
+    assert!(bfs.find_all_opportunities(&pools, 100.0).is_empty());
+    assert!(bellman_ford.find_all_cycles(&pools, 100.0).is_empty());
+
+    let cache = Arc::new(PriceCache::new());
+    let table = DirectArbTable::new(cache.clone(), 0.3).with_token_aliases(disabled);
+    cache.update_price(pool("sol-usdc", "SOL/USDC", 150.0, 9, 6));
+    cache.update_price(pool("wsol-usdc", "wSOL/USDC", 152.0, 9, 6));
+    assert!(table.on_pool_event("sol-usdc").is_none());
+    assert!(table.on_pool_event("wsol-usdc").is_none());
+}
+
+#[test]
+fn test_wsol_sol_cycle_only_found_with_aliasing() {
+    // 默认别名（wSOL → SOL）
+    let pools = bridged_cycle();
+    let aliases = Arc::new(TokenAliases::new());
+    let bfs = BfsScanner::new(3, 0.1, ExecutionCostModel::shared_default()).with_token_aliases(aliases.clone());
+    let bellman_ford = BellmanFordScanner::new(4, 0.1, ExecutionCostModel::shared_default()).with_token_aliases(aliases.clone());
+    let bfs_paths = bfs.find_all_opportunities(&pools, 100.0);
+    let bf_paths = bellman_ford.find_all_cycles(&pools, 100.0);
+    assert!(!bfs_paths.is_empty(), "BFS should find the SOL/wSOL cycle");
+    assert!(!bf_paths.is_empty(), "Bellman-Ford should find the SOL/wSOL cycle");
+
+    for path in bfs_paths.iter().chain(&bf_paths) {
+        assert!(path.roi_percent > 0.1);
+        assert!(path.start_token != "wSOL" && path.end_token != "wSOL");
+        for step in &path.steps {
+            // 路径上只出现规范符号，原始代币保留在 raw_* 字段
+            assert!(step.input_token != "wSOL" && step.output_token != "wSOL");
+            if step.pool_id == "wsol-usdt" {
+                assert_eq!(step.input_token, "SOL");
+                assert_eq!(step.raw_input_token.as_deref(), Some("wSOL"));
+                assert_eq!(step.raw_output_token, None);
+            } else {
+                assert_eq!((step.raw_input_token.as_deref(), step.raw_output_token.as_deref()), (None, None));
+            }
+        }
+    }
+
+    // 直接套利快速通道：wSOL/USDC 与 SOL/USDC 落在同一个报价簿
+    let cache = Arc::new(PriceCache::new());
+    let table = DirectArbTable::new(cache.clone(), 0.3).with_token_aliases(aliases);
+    cache.update_price(pool("sol-usdc", "SOL/USDC", 150.0, 9, 6));
+    cache.update_price(pool("wsol-usdc", "wSOL/USDC", 152.0, 9, 6));
+    assert!(table.on_pool_event("sol-usdc").is_none());
+    let opportunity = table.on_pool_event("wsol-usdc").expect("SOL/wSOL spread above threshold");
+    assert_eq!(opportunity.pair, "SOL/USDC");
+    let path = opportunity.to_path(&cache, 100.0).unwrap();
+    assert_eq!(path.start_token, "USDC");
+    assert_eq!(path.steps[1].raw_input_token.as_deref(), Some("wSOL"));
+}
@@ -0,0 +1,341 @@
+/*!
+ * WebSocketClient 集成测试 - 对着 mock pubsub 服务器跑完整的订阅 / 通知 / 重连流程
+ */
+
+mod support;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use solana_pool_cache::account_subscription::MalformedKind;
+use solana_pool_cache::config::PoolConfig;
+use solana_pool_cache::error_tracker::ErrorTracker;
+use solana_pool_cache::metrics::MetricsCollector;
+use solana_pool_cache::pool_factory::PoolFactory;
+use solana_pool_cache::price_cache::{PoolPrice, PriceCache};
+use solana_pool_cache::reconnect_backoff::BackoffPolicy;
+use solana_pool_cache::router::Router;
+use solana_pool_cache::websocket::WebSocketClient;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{timeout, Instant};
+
+use support::mock_pubsub::{MockPubsub, FIRST_SUBSCRIPTION_ID};
+use support::{eventually, fixture, pool_config, token_account};
+
+/// 连接 mock 服务器的客户端（快速重连，不主动查询 vault）
+fn client(server: &MockPubsub, price_cache: Arc<PriceCache>) -> Arc<WebSocketClient> {
+    let client = WebSocketClient::new(
+        server.url().to_string(),
+        Arc::new(MetricsCollector::new(100)),
+        None,
+        price_cache,
+        Arc::new(ErrorTracker::new()),
+        0.1,
+        false,
+    )
+    .with_backoff(BackoffPolicy {
+        initial: Duration::from_millis(20),
+        max: Duration::from_millis(100),
+        ..Default::default()
+    });
+    Arc::new(client)
+}
+
+fn run(client: &Arc<WebSocketClient>, pools: Vec<PoolConfig>) -> JoinHandle<()> {
+    let client = client.clone();
+    tokio::spawn(async move {
+        let _ = client.run(pools).await;
+    })
+}
+
+#[tokio::test]
+async fn test_pool_subscription_confirmation_maps_to_pool() {
+    let server = MockPubsub::start().await;
+    let solfi = fixture("solfi_v2");
+    let first = pool_config(&solfi, None);
+    let mut second = pool_config(&solfi, Some(&Pubkey::new_unique().to_string()));
+    second.name = "USDC/USDT (SolFi V2) #2".to_string();
+
+    let price_cache = Arc::new(PriceCache::new());
+    let client = client(&server, price_cache.clone());
+    let running = run(&client, vec![first.clone(), second.clone()]);
+
+    // request id = 池子下标 + 1，subscription id 由服务器分配
+    let first_sub = server.wait_for_subscription(&first.address, 1).await;
+    let second_sub = server.wait_for_subscription(&second.address, 1).await;
+    assert_eq!((first_sub.request_id, second_sub.request_id), (1, 2));
+    assert!(first_sub.subscription_id >= FIRST_SUBSCRIPTION_ID);
+    assert_ne!(first_sub.subscription_id, second_sub.subscription_id);
+
+    let pool_stats = client.pool_stats();
+    eventually("both subscriptions confirmed", || {
+        pool_stats.get_pool_stats(&first.name).is_some() && pool_stats.get_pool_stats(&second.name).is_some()
+    })
+    .await;
+
+    // 第二个池子的 subscription id 上的通知只更新第二个池子
+    server.push_account(second_sub.subscription_id, 100, &solfi.data);
+    eventually("second pool cached", || price_cache.get_price(&second.address).is_some()).await;
+    assert_eq!(price_cache.get_price(&second.address).unwrap().slot, 100);
+    assert!(price_cache.get_price(&first.address).is_none());
+
+    running.abort();
+}
+
+#[tokio::test]
+async fn test_vault_subscription_round_trip() {
+    let server = MockPubsub::start().await;
+    let solfi = fixture("solfi_v2");
+    let pool = pool_config(&solfi, None);
+    let (vault_a, vault_b) = PoolFactory::create_pool("solfi_v2", &solfi.decode_data().unwrap())
+        .unwrap()
+        .get_vault_addresses()
+        .unwrap();
+    let (vault_a, vault_b) = (vault_a.to_string(), vault_b.to_string());
+
+    let price_cache = Arc::new(PriceCache::new());
+    let client = client(&server, price_cache.clone());
+    let running = run(&client, vec![pool.clone()]);
+
+    // 池子通知解析出 vault 地址后，在同一连接上动态订阅两个 vault（request id >= 10000）
+    let pool_sub = server.wait_for_subscription(&pool.address, 1).await;
+    server.push_account(pool_sub.subscription_id, 100, &solfi.data);
+    let vault_a_sub = server.wait_for_subscription(&vault_a, 1).await;
+    let vault_b_sub = server.wait_for_subscription(&vault_b, 1).await;
+    assert!(vault_a_sub.request_id >= 10000 && vault_b_sub.request_id >= 10000);
+    assert_eq!(client.vault_reader().vault_subscriptions().len(), 2);
+
+    // vault 余额通知按确认后的 subscription id 找到 vault，重算池子储备
+    server.push_account(vault_a_sub.subscription_id, 101, &token_account(5_000_000));
+    server.push_account(vault_b_sub.subscription_id, 102, &token_account(7_000_000));
+    let vault_reader = client.vault_reader();
+    eventually("vault balances received", || {
+        vault_reader.get_received_pool_reserves(&pool.address) == Some((5_000_000, 7_000_000))
+    })
+    .await;
+    eventually("pool recalculated from vaults", || {
+        price_cache.get_price(&pool.address).is_some_and(|p| p.slot == 102)
+    })
+    .await;
+    let price = price_cache.get_price(&pool.address).unwrap();
+    assert_eq!((price.base_reserve, price.quote_reserve), (5_000_000, 7_000_000));
+
+    running.abort();
+}
+
+#[tokio::test]
+async fn test_reconnect_resubscribes_pools_and_vaults() {
+    let server = MockPubsub::start().await;
+    let solfi = fixture("solfi_v2");
+    let pool = pool_config(&solfi, None);
+
+    let price_cache = Arc::new(PriceCache::new());
+    let client = client(&server, price_cache.clone());
+    let running = run(&client, vec![pool.clone()]);
+
+    let before = server.wait_for_subscription(&pool.address, 1).await;
+    server.push_account(before.subscription_id, 100, &solfi.data);
+    eventually("vault subscriptions", || server.subscriptions().len() == 3).await;
+
+    // 连接中断：客户端重连，重新订阅池子并重放已发现的 vault 订阅
+    server.disconnect();
+    let after = server.wait_for_subscription(&pool.address, 2).await;
+    assert_ne!(after.subscription_id, before.subscription_id);
+    eventually("vault subscriptions replayed", || {
+        server.subscriptions().iter().filter(|s| s.connection == 2).count() == 3
+    })
+    .await;
+    let vaults = |connection: usize| -> Vec<String> {
+        let mut vaults: Vec<String> = server.subscriptions().into_iter()
+            .filter(|s| s.connection == connection && s.account != pool.address)
+            .map(|s| s.account)
+            .collect();
+        vaults.sort();
+        vaults
+    };
+    assert_eq!(vaults(1), vaults(2));
+
+    // 新连接的 subscription id 生效
+    server.push_account(after.subscription_id, 200, &solfi.data);
+    eventually("update after reconnect", || {
+        price_cache.get_price(&pool.address).is_some_and(|p| p.slot == 200)
+    })
+    .await;
+    assert_eq!(server.connections(), 2);
+
+    running.abort();
+}
+
+#[tokio::test]
+async fn test_notification_reaches_coordinator_channel() {
+    let server = MockPubsub::start().await;
+    let solfi = fixture("solfi_v2");
+    let pool = pool_config(&solfi, None);
+
+    let client = client(&server, Arc::new(PriceCache::new()));
+    let (tx, mut rx) = mpsc::channel(16);
+    client.set_coordinator_sender(tx);
+    let running = run(&client, vec![pool.clone()]);
+    let sub = server.wait_for_subscription(&pool.address, 1).await;
+
+    // 畸形帧只记录错误，不影响连接
+    server.push_raw("not json");
+    server.push_account(sub.subscription_id, 99, "%%% not base64 %%%");
+    server.push_account(FIRST_SUBSCRIPTION_ID + 999, 99, &solfi.data);
+
+    server.push_account(sub.subscription_id, 100, &solfi.data);
+    let event = timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("no PriceChangeEvent")
+        .unwrap();
+    assert_eq!(event.pool_id, pool.address);
+    assert_eq!(event.pool_name, pool.name);
+    assert!(event.ingest.is_some());
+    assert_eq!(server.connections(), 1);
+
+    running.abort();
+}
+
+#[tokio::test]
+async fn test_broken_frames_are_counted_without_reconnecting() {
+    let server = MockPubsub::start().await;
+    let solfi = fixture("solfi_v2");
+    let pool = pool_config(&solfi, None);
+
+    let metrics = Arc::new(MetricsCollector::new(100));
+    let price_cache = Arc::new(PriceCache::new());
+    let client = Arc::new(WebSocketClient::new(
+        server.url().to_string(),
+        metrics.clone(),
+        None,
+        price_cache.clone(),
+        Arc::new(ErrorTracker::new()),
+        0.1,
+        false,
+    ));
+    let running = run(&client, vec![pool.clone()]);
+    let sub = server.wait_for_subscription(&pool.address, 1).await;
+
+    let frame = |subscription: serde_json::Value, data: serde_json::Value| {
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "accountNotification",
+            "params": {
+                "subscription": subscription,
+                "result": { "context": { "slot": 50 }, "value": { "data": data } }
+            }
+        })
+        .to_string()
+    };
+    let id = serde_json::json!(sub.subscription_id);
+    let truncated = &solfi.data[..solfi.data.len() - 3];
+    let corpus = [
+        "{\"jsonrpc\": \"2.0\", \"method\": \"accountNotif".to_string(),
+        frame(id.clone(), serde_json::json!([])),
+        frame(id.clone(), serde_json::json!([truncated, "base64"])),
+        frame(id.clone(), serde_json::json!([solfi.data, "base64+zstd"])),
+        frame(id.clone(), serde_json::json!([solfi.data, "jsonParsed"])),
+        frame(serde_json::json!("not-a-number"), serde_json::json!([solfi.data, "base64"])),
+    ];
+    for text in &corpus {
+        server.push_raw(text);
+    }
+
+    // 折行的 base64 + 字符串形式的 subscription id 仍然是有效通知
+    let wrapped: String = solfi.data.as_bytes().chunks(76)
+        .map(|line| std::str::from_utf8(line).unwrap())
+        .collect::<Vec<_>>()
+        .join("\n");
+    server.push_raw(&frame(serde_json::json!(sub.subscription_id.to_string()), serde_json::json!([wrapped, "base64"])));
+    eventually("wrapped notification cached", || {
+        price_cache.get_price(&pool.address).is_some_and(|p| p.slot == 50)
+    })
+    .await;
+    eventually("all broken frames counted", || {
+        MalformedKind::ALL.iter().map(|kind| metrics.malformed_messages(*kind)).sum::<u64>() == 6
+    })
+    .await;
+    assert_eq!(metrics.malformed_messages(MalformedKind::BadJson), 1);
+    assert_eq!(metrics.malformed_messages(MalformedKind::MissingField), 2);
+    assert_eq!(metrics.malformed_messages(MalformedKind::BadBase64), 2);
+    assert_eq!(metrics.malformed_messages(MalformedKind::UnexpectedEncoding), 1);
+
+    // 内容错误不会触发重连
+    server.push_account(sub.subscription_id, 51, &solfi.data);
+    eventually("valid notification after broken frames", || {
+        price_cache.get_price(&pool.address).is_some_and(|p| p.slot == 51)
+    })
+    .await;
+    assert_eq!(server.connections(), 1);
+
+    running.abort();
+}
+
+#[tokio::test]
+async fn test_pancakeswap_vault_reserves_feed_router() {
+    let server = MockPubsub::start().await;
+    let pancake = fixture("pancakeswap");
+    let mut pool = pool_config(&pancake, None);
+    // 路由按 pair 名分组（未配置 mint 时），两边使用同一个交易对名
+    pool.name = "SOL/USDT".to_string();
+    let (vault_a, vault_b) = PoolFactory::create_pool("pancakeswap", &pancake.decode_data().unwrap())
+        .unwrap()
+        .get_vault_addresses()
+        .unwrap();
+    let (vault_a, vault_b) = (vault_a.to_string(), vault_b.to_string());
+
+    let price_cache = Arc::new(PriceCache::new());
+    let client = client(&server, price_cache.clone());
+    let running = run(&client, vec![pool.clone()]);
+
+    // 池子通知解析出 vault（Raydium CLMM 布局）后订阅两个 vault
+    let pool_sub = server.wait_for_subscription(&pool.address, 1).await;
+    server.push_account(pool_sub.subscription_id, 100, &pancake.data);
+    let vault_a_sub = server.wait_for_subscription(&vault_a, 1).await;
+    let vault_b_sub = server.wait_for_subscription(&vault_b, 1).await;
+
+    // 5,000 SOL / 994,500 USDT：与 sqrt_price 给出的 ~198.9 一致
+    server.push_account(vault_a_sub.subscription_id, 101, &token_account(5_000_000_000_000));
+    server.push_account(vault_b_sub.subscription_id, 102, &token_account(994_500_000_000));
+    eventually("reserves from vaults", || {
+        price_cache.get_price(&pool.address)
+            .is_some_and(|p| (p.base_reserve, p.quote_reserve) == (5_000_000_000_000, 994_500_000_000))
+    })
+    .await;
+    let price = price_cache.get_price(&pool.address).unwrap();
+    assert_eq!(price.dex_name, "PancakeSwap");
+    assert!((price.price - 198.9).abs() < 0.1, "price from sqrt_price, got {}", price.price);
+
+    // vault 余额变化持续反映到储备
+    server.push_account(vault_b_sub.subscription_id, 103, &token_account(990_000_000_000));
+    eventually("reserves follow vault updates", || {
+        price_cache.get_price(&pool.address).is_some_and(|p| p.quote_reserve == 990_000_000_000 && p.slot == 103)
+    })
+    .await;
+
+    // 同一交易对上价格更高的池子：PancakeSwap 成为买入腿
+    price_cache.update_price(PoolPrice {
+        pool_id: "other-sol-usdt".to_string(),
+        dex_name: "Raydium AMM V4".to_string(),
+        pair: "SOL/USDT".to_string(),
+        base_reserve: 5_000_000_000_000,
+        quote_reserve: 1_025_000_000_000,
+        base_decimals: 9,
+        quote_decimals: 6,
+        price: 205.0,
+        last_update: Instant::now().into_std(),
+        slot: 103,
+        liquidity_usd: None,
+        commitment: None,
+    });
+    let paths = Router::new(price_cache.clone()).find_all_opportunities(1_000.0);
+    assert!(
+        paths.iter().any(|path| path.steps.iter().any(|step| step.pool_id == pool.address)),
+        "PancakeSwap pool should participate in a router path: {:?}",
+        paths.iter().map(|p| p.signature()).collect::<Vec<_>>()
+    );
+
+    running.abort();
+}